@@ -0,0 +1,197 @@
+//! Tuple representation benchmarks: construction, cloning and join-row
+//! building with inline (SmallVec) storage, plus end-to-end join and
+//! aggregation queries.
+//!
+//! A counting allocator reports heap allocations per operation before the
+//! timed runs, comparing `Tuple` against the previous `Vec<Value>` storage.
+//! Tuples up to `TUPLE_INLINE_ARITY` columns allocate nothing for their
+//! value buffer; the `Vec` baseline allocates once per tuple.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use inputlayer::value::{Tuple, Value, TUPLE_INLINE_ARITY};
+use inputlayer::{protocol::handler::Handler, Config};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::runtime::Runtime;
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const ITERATIONS: usize = 10_000;
+
+/// Average heap allocations per call of `f`
+fn allocations_per_op(mut f: impl FnMut()) -> f64 {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    (ALLOCATIONS.load(Ordering::Relaxed) - before) as f64 / ITERATIONS as f64
+}
+
+fn row(arity: usize) -> Vec<Value> {
+    (0..arity as i64).map(Value::Int64).collect()
+}
+
+fn report_allocations() {
+    for arity in [2, TUPLE_INLINE_ARITY, TUPLE_INLINE_ARITY + 2] {
+        let values = row(arity);
+        let vec_allocs = allocations_per_op(|| {
+            black_box(values.clone());
+        });
+        let tuple_allocs = allocations_per_op(|| {
+            black_box(Tuple::new(values.clone()));
+        });
+        let tuple = Tuple::new(values.clone());
+        let clone_allocs = allocations_per_op(|| {
+            black_box(tuple.clone());
+        });
+        println!(
+            "arity {arity}: Vec<Value> {vec_allocs:.2} allocs/op, \
+             Tuple::new {tuple_allocs:.2}, Tuple::clone {clone_allocs:.2}"
+        );
+    }
+
+    let left = Tuple::new(row(2));
+    let right = Tuple::new(row(2));
+    let old_join = allocations_per_op(|| {
+        black_box(left.concat(&right.excluding_indices(&[0])));
+    });
+    let new_join = allocations_per_op(|| {
+        black_box(left.concat_excluding(&right, &[0]));
+    });
+    println!(
+        "join row (2 x 2 on 1 key): concat+excluding {old_join:.2}, concat_excluding {new_join:.2}"
+    );
+}
+
+fn bench_tuple_ops(c: &mut Criterion) {
+    report_allocations();
+
+    let mut group = c.benchmark_group("tuple_ops");
+    for arity in [2, TUPLE_INLINE_ARITY, 8] {
+        let values = row(arity);
+        let tuple = Tuple::new(values.clone());
+
+        group.bench_with_input(BenchmarkId::new("vec_clone", arity), &arity, |b, _| {
+            b.iter(|| black_box(values.clone()));
+        });
+        group.bench_with_input(BenchmarkId::new("new", arity), &arity, |b, _| {
+            b.iter(|| black_box(Tuple::new(values.clone())));
+        });
+        group.bench_with_input(BenchmarkId::new("clone", arity), &arity, |b, _| {
+            b.iter(|| black_box(tuple.clone()));
+        });
+        group.bench_with_input(BenchmarkId::new("project", arity), &arity, |b, _| {
+            b.iter(|| black_box(tuple.project(&[1, 0])));
+        });
+        group.bench_with_input(BenchmarkId::new("join_row", arity), &arity, |b, _| {
+            b.iter(|| black_box(tuple.concat_excluding(&tuple, &[0])));
+        });
+        group.bench_with_input(
+            BenchmarkId::new("join_row_unfused", arity),
+            &arity,
+            |b, _| b.iter(|| black_box(tuple.concat(&tuple.excluding_indices(&[0])))),
+        );
+    }
+    group.finish();
+}
+
+fn make_bench_handler() -> (Handler, TempDir) {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let mut config = Config::default();
+    config.storage.data_dir = tmp.path().to_path_buf();
+    config.storage.performance.query_timeout_ms = 0;
+    let handler = Handler::from_config(config).expect("handler");
+    (handler, tmp)
+}
+
+fn bench_join_query(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+
+    let mut group = c.benchmark_group("tuple_join_query");
+    for size in [1_000u32, 10_000] {
+        let (handler, _tmp) = make_bench_handler();
+
+        rt.block_on(async {
+            let orders: Vec<String> = (1..=size)
+                .map(|i| format!("({}, {}, {})", i, i % 100, i * 3))
+                .collect();
+            let customers: Vec<String> = (0..100).map(|i| format!("({i}, \"c{i}\")")).collect();
+            handler
+                .query_program(None, format!("+orders[{}]", orders.join(", ")))
+                .await
+                .unwrap();
+            handler
+                .query_program(None, format!("+customer[{}]", customers.join(", ")))
+                .await
+                .unwrap();
+        });
+
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            b.iter(|| {
+                rt.block_on(
+                    handler
+                        .query_program(None, "?orders(Id, C, Amt), customer(C, Name)".to_string()),
+                )
+            });
+        });
+    }
+    group.finish();
+}
+
+fn bench_aggregation_query(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+
+    let mut group = c.benchmark_group("tuple_agg_query");
+    for size in [1_000u32, 10_000] {
+        let (handler, _tmp) = make_bench_handler();
+
+        rt.block_on(async {
+            let tuples: Vec<String> = (1..=size)
+                .map(|i| format!("({}, {}, {})", i % 10, i % 7, i))
+                .collect();
+            handler
+                .query_program(None, format!("+events[{}]", tuples.join(", ")))
+                .await
+                .unwrap();
+            handler
+                .query_program(
+                    None,
+                    "+events_sum(G, K, sum<V>) <- events(G, K, V)".to_string(),
+                )
+                .await
+                .unwrap();
+        });
+
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            b.iter(|| rt.block_on(handler.query_program(None, "?events_sum(G, K, S)".to_string())));
+        });
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default()
+        .measurement_time(Duration::from_secs(10))
+        .warm_up_time(Duration::from_secs(3));
+    targets = bench_tuple_ops, bench_join_query, bench_aggregation_query
+}
+criterion_main!(benches);
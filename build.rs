@@ -0,0 +1,13 @@
+//! Build script: generates the gRPC service and client from
+//! `proto/inputlayer.proto` when the `grpc` feature is enabled.
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/inputlayer.proto");
+        if let Err(e) = tonic_build::compile_protos("proto/inputlayer.proto") {
+            panic!("failed to compile proto/inputlayer.proto: {e}");
+        }
+    }
+}
@@ -250,6 +250,28 @@ fn print_ir_structure(ir: &IRNode, indent: usize) {
             println!("{}  right:", prefix);
             print_ir_structure(right, indent + 4);
         }
+        IRNode::Semijoin {
+            left,
+            right,
+            left_keys,
+            right_keys,
+            output_schema,
+        } => {
+            println!("{}Semijoin", prefix);
+            println!("{}  left_keys: {:?}", prefix, left_keys);
+            println!("{}  right_keys: {:?}", prefix, right_keys);
+            println!("{}  output: {:?}", prefix, output_schema);
+            println!("{}  left:", prefix);
+            print_ir_structure(left, indent + 4);
+            println!("{}  right:", prefix);
+            print_ir_structure(right, indent + 4);
+        }
+        IRNode::Sort { input, order_by } => {
+            println!("{}Sort", prefix);
+            println!("{}  order_by: {:?}", prefix, order_by);
+            println!("{}  input:", prefix);
+            print_ir_structure(input, indent + 4);
+        }
         IRNode::Compute { input, expressions } => {
             println!("{}Compute", prefix);
             println!(
@@ -137,11 +137,13 @@ InputLayer is a knowledge graph engine with recursive IQL, incremental maintenan
 - `.rel` - list all relations with schemas and row counts
 - `.rel <name>` - describe a relation (schema + sample data)
 - `.rel drop <name>` - drop a relation
+- `.rel alter <name> add <col>: <type> [default <v>]` / `drop <col>` / `widen <col> to <type>` - migrate a relation's schema
 - `.rule` / `.rule list` - list all persistent rules
 - `.rule def <name>` - show rule definition (clauses)
 - `.rule drop <name>` - delete a rule
+- `.rule refresh <name> eager|lazy|every <duration>|none` - cache a rule's result and set when it is recomputed
 - `.rule edit <name> <n> <clause>` - edit clause #n
-- `.kg` / `.kg list` / `.kg create <n>` / `.kg use <n>` / `.kg drop <n>` - knowledge graph management
+- `.kg` / `.kg list` / `.kg create <n>` / `.kg use <n>` / `.kg drop <n> [cascade]` / `.kg copy <src> <dst>` / `.kg rename <old> <new>` - knowledge graph management
 - `.session` / `.session clear` - session rule management
 - `.index list` / `.index create <name> on <rel>(<col>) [metric cosine]` - HNSW vector index management
 - `.index stats <name>` / `.index rebuild <name>` / `.index drop <name>`
@@ -110,6 +110,62 @@ pub enum BuiltinFunc {
     IntervalDuration,
     /// Check if point is in interval: `point_in_interval(ts`, start, end) -> Bool
     PointInInterval,
+    /// Date literal or conversion: `date("2024-01-15")`, `date(ts)` -> Date
+    Date,
+    /// Duration literal or conversion: `duration("1d12h")`, `duration(ms)` -> Duration
+    Duration,
+
+    // UUID functions
+    /// UUID literal: `uuid("67e55044-10b1-426f-9247-bb680e5fe0c8")` -> Uuid
+    Uuid,
+    /// Generate a time-ordered UUID: `uuid_v7()` -> Uuid
+    UuidV7,
+
+    // Null handling functions
+    /// Test for NULL: `is_null(x)` -> Bool
+    IsNull,
+    /// First non-NULL argument: `coalesce(x, default)`
+    Coalesce,
+
+    // List and map functions
+    /// Build a list: `list(a, b, ...)` -> List
+    List,
+    /// Build a map from key-value pairs: `map("k1", v1, "k2", v2, ...)` -> Map
+    Map,
+    /// Element at a 0-based index: `list_get(l, i)` -> element, or Null if out of range
+    ListGet,
+    /// Number of elements: `list_len(l)` -> Int64
+    ListLen,
+    /// Value at a key: `map_get(m, "k")` -> value, or Null if absent
+    MapGet,
+    /// Generator: `X = list_elem(l)` binds X to each element, one row per element
+    ListElem,
+    /// Generator: `K = map_key(m)` binds K to each key, one row per key
+    MapKey,
+
+    // JSON functions
+    /// Parse JSON text: `json_parse(s)` -> Json, or Null if malformed
+    JsonParse,
+    /// Value at a path: `json_extract(j, "$.a.b[0]")` -> scalar or Json, Null if absent
+    JsonExtract,
+    /// JSON type name: `json_type(j)` -> "object", "array", "string", "number", "boolean", "null"
+    JsonType,
+    /// Generator: `E = json_array_elements(j)` binds E to each array element
+    JsonArrayElements,
+
+    // Bytes functions
+    /// Decode hex: `hex("deadbeef")` -> Bytes, or Null if malformed
+    Hex,
+    /// Decode base64: `base64("3q2+7w==")` -> Bytes, or Null if malformed
+    Base64,
+    /// Encode as lowercase hex: `to_hex(b)` -> String
+    ToHex,
+    /// Encode as base64: `to_base64(b)` -> String
+    ToBase64,
+    /// Number of bytes: `bytes_len(b)` -> Int64
+    BytesLen,
+    /// Sub-range: `bytes_slice(b, start, len)` -> Bytes, clamped to the end
+    BytesSlice,
 
     // Int8 quantization functions
     /// Linear quantization: `quantize_linear(v)` -> `VectorInt8`
@@ -222,6 +278,33 @@ impl BuiltinFunc {
             "interval_contains" => Some(BuiltinFunc::IntervalContains),
             "interval_duration" => Some(BuiltinFunc::IntervalDuration),
             "point_in_interval" => Some(BuiltinFunc::PointInInterval),
+            "date" => Some(BuiltinFunc::Date),
+            "duration" => Some(BuiltinFunc::Duration),
+            "uuid" => Some(BuiltinFunc::Uuid),
+            "uuid_v7" => Some(BuiltinFunc::UuidV7),
+            // Null handling functions
+            "is_null" => Some(BuiltinFunc::IsNull),
+            "coalesce" => Some(BuiltinFunc::Coalesce),
+            // List and map functions
+            "list" => Some(BuiltinFunc::List),
+            "map" => Some(BuiltinFunc::Map),
+            "list_get" => Some(BuiltinFunc::ListGet),
+            "list_len" => Some(BuiltinFunc::ListLen),
+            "map_get" => Some(BuiltinFunc::MapGet),
+            "list_elem" => Some(BuiltinFunc::ListElem),
+            "map_key" => Some(BuiltinFunc::MapKey),
+            // JSON functions
+            "json_parse" => Some(BuiltinFunc::JsonParse),
+            "json_extract" => Some(BuiltinFunc::JsonExtract),
+            "json_type" => Some(BuiltinFunc::JsonType),
+            "json_array_elements" => Some(BuiltinFunc::JsonArrayElements),
+            // Bytes functions
+            "hex" => Some(BuiltinFunc::Hex),
+            "base64" => Some(BuiltinFunc::Base64),
+            "to_hex" => Some(BuiltinFunc::ToHex),
+            "to_base64" => Some(BuiltinFunc::ToBase64),
+            "bytes_len" => Some(BuiltinFunc::BytesLen),
+            "bytes_slice" => Some(BuiltinFunc::BytesSlice),
             // Quantization functions
             "quantize_linear" => Some(BuiltinFunc::QuantizeLinear),
             "quantize_symmetric" => Some(BuiltinFunc::QuantizeSymmetric),
@@ -279,6 +362,23 @@ impl BuiltinFunc {
             BuiltinFunc::VecNormalize | BuiltinFunc::VecDim => 1,
             // Temporal functions
             BuiltinFunc::TimeNow => 0,
+            BuiltinFunc::Date | BuiltinFunc::Duration => 1,
+            BuiltinFunc::Uuid => 1,
+            BuiltinFunc::UuidV7 => 0,
+            BuiltinFunc::IsNull => 1,
+            BuiltinFunc::Coalesce => 2,
+            // Variadic: `check_arity` accepts any count (pairs for `map`)
+            BuiltinFunc::List | BuiltinFunc::Map => 0,
+            BuiltinFunc::ListLen | BuiltinFunc::ListElem | BuiltinFunc::MapKey => 1,
+            BuiltinFunc::ListGet | BuiltinFunc::MapGet => 2,
+            BuiltinFunc::JsonParse | BuiltinFunc::JsonType | BuiltinFunc::JsonArrayElements => 1,
+            BuiltinFunc::JsonExtract => 2,
+            BuiltinFunc::Hex
+            | BuiltinFunc::Base64
+            | BuiltinFunc::ToHex
+            | BuiltinFunc::ToBase64
+            | BuiltinFunc::BytesLen => 1,
+            BuiltinFunc::BytesSlice => 3,
             BuiltinFunc::TimeDiff
             | BuiltinFunc::TimeAdd
             | BuiltinFunc::TimeSub
@@ -327,6 +427,33 @@ impl BuiltinFunc {
         }
     }
 
+    /// Check that `count` arguments are valid for this function
+    pub fn check_arity(&self, count: usize) -> Result<(), String> {
+        match self {
+            BuiltinFunc::List => Ok(()),
+            BuiltinFunc::Map if count.is_multiple_of(2) => Ok(()),
+            BuiltinFunc::Map => Err(format!(
+                "Function 'map' takes key-value pairs, but {count} argument(s) provided"
+            )),
+            _ if count == self.arity() => Ok(()),
+            _ => Err(format!(
+                "Function '{}' requires {} argument(s), but {} provided",
+                self.as_str(),
+                self.arity(),
+                count
+            )),
+        }
+    }
+
+    /// Whether the function is a generator, which binds its variable once
+    /// per element and so produces any number of rows
+    pub fn is_generator(&self) -> bool {
+        matches!(
+            self,
+            BuiltinFunc::ListElem | BuiltinFunc::MapKey | BuiltinFunc::JsonArrayElements
+        )
+    }
+
     /// Get the string representation of the function name
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -354,6 +481,33 @@ impl BuiltinFunc {
             BuiltinFunc::IntervalContains => "interval_contains",
             BuiltinFunc::IntervalDuration => "interval_duration",
             BuiltinFunc::PointInInterval => "point_in_interval",
+            BuiltinFunc::Date => "date",
+            BuiltinFunc::Duration => "duration",
+            BuiltinFunc::Uuid => "uuid",
+            BuiltinFunc::UuidV7 => "uuid_v7",
+            // Null handling functions
+            BuiltinFunc::IsNull => "is_null",
+            BuiltinFunc::Coalesce => "coalesce",
+            // List and map functions
+            BuiltinFunc::List => "list",
+            BuiltinFunc::Map => "map",
+            BuiltinFunc::ListGet => "list_get",
+            BuiltinFunc::ListLen => "list_len",
+            BuiltinFunc::MapGet => "map_get",
+            BuiltinFunc::ListElem => "list_elem",
+            BuiltinFunc::MapKey => "map_key",
+            // JSON functions
+            BuiltinFunc::JsonParse => "json_parse",
+            BuiltinFunc::JsonExtract => "json_extract",
+            BuiltinFunc::JsonType => "json_type",
+            BuiltinFunc::JsonArrayElements => "json_array_elements",
+            // Bytes functions
+            BuiltinFunc::Hex => "hex",
+            BuiltinFunc::Base64 => "base64",
+            BuiltinFunc::ToHex => "to_hex",
+            BuiltinFunc::ToBase64 => "to_base64",
+            BuiltinFunc::BytesLen => "bytes_len",
+            BuiltinFunc::BytesSlice => "bytes_slice",
             // Quantization functions
             BuiltinFunc::QuantizeLinear => "quantize_linear",
             BuiltinFunc::QuantizeSymmetric => "quantize_symmetric",
@@ -399,7 +553,7 @@ impl BuiltinFunc {
 }
 
 /// Arithmetic operators for expressions
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ArithOp {
     /// Addition (+)
     Add,
@@ -441,7 +595,7 @@ impl ArithOp {
 /// Arithmetic expression tree
 ///
 /// Represents arithmetic expressions like `d + 1` or `x * y + z`.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ArithExpr {
     /// A variable reference
     Variable(String),
@@ -501,6 +655,8 @@ impl ArithExpr {
     }
 
     /// Try to evaluate as a constant if all values are known
+    ///
+    /// Returns `None` on division by zero or integer overflow.
     pub fn try_eval_constant(&self) -> Option<i64> {
         match self {
             ArithExpr::Constant(v) => Some(*v),
@@ -510,25 +666,46 @@ impl ArithExpr {
                 let l = left.try_eval_constant()?;
                 let r = right.try_eval_constant()?;
                 Some(match op {
-                    ArithOp::Add => l + r,
-                    ArithOp::Sub => l - r,
-                    ArithOp::Mul => l * r,
+                    ArithOp::Add => l.checked_add(r)?,
+                    ArithOp::Sub => l.checked_sub(r)?,
+                    ArithOp::Mul => l.checked_mul(r)?,
                     ArithOp::Div => {
                         if r == 0 {
                             return None;
                         }
-                        l / r
+                        l.checked_div(r)?
                     }
                     ArithOp::Mod => {
                         if r == 0 {
                             return None;
                         }
-                        l % r
+                        l.checked_rem(r)?
                     }
                 })
             }
         }
     }
+
+    /// Replace every constant integer subexpression with its value
+    ///
+    /// `X + (2 * 3)` becomes `X + 6`. Subexpressions that cannot be
+    /// evaluated (variables, floats, division by zero, overflow) are kept.
+    pub fn fold_constants(self) -> Self {
+        match self {
+            ArithExpr::Binary { op, left, right } => {
+                let folded = ArithExpr::Binary {
+                    op,
+                    left: Box::new(left.fold_constants()),
+                    right: Box::new(right.fold_constants()),
+                };
+                match folded.try_eval_constant() {
+                    Some(v) => ArithExpr::Constant(v),
+                    None => folded,
+                }
+            }
+            other => other,
+        }
+    }
 }
 
 impl AggregateFunc {
@@ -943,7 +1120,7 @@ impl Atom {
 }
 
 /// Comparison operators for filter predicates in rule bodies
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ComparisonOp {
     Equal,          // =
     NotEqual,       // !=
@@ -1722,6 +1899,16 @@ mod tests {
         assert_eq!(BuiltinFunc::Substr.arity(), 3);
     }
 
+    #[test]
+    fn test_builtin_func_check_arity() {
+        assert!(BuiltinFunc::Pow.check_arity(2).is_ok());
+        assert!(BuiltinFunc::Pow.check_arity(1).is_err());
+        assert!(BuiltinFunc::List.check_arity(0).is_ok());
+        assert!(BuiltinFunc::List.check_arity(5).is_ok());
+        assert!(BuiltinFunc::Map.check_arity(4).is_ok());
+        assert!(BuiltinFunc::Map.check_arity(3).is_err());
+    }
+
     #[test]
     fn test_builtin_func_as_str_roundtrip() {
         let funcs = [
@@ -1733,6 +1920,29 @@ mod tests {
             BuiltinFunc::Len,
             BuiltinFunc::TimeNow,
             BuiltinFunc::Floor,
+            BuiltinFunc::Date,
+            BuiltinFunc::Duration,
+            BuiltinFunc::Uuid,
+            BuiltinFunc::UuidV7,
+            BuiltinFunc::IsNull,
+            BuiltinFunc::Coalesce,
+            BuiltinFunc::List,
+            BuiltinFunc::Map,
+            BuiltinFunc::ListGet,
+            BuiltinFunc::ListLen,
+            BuiltinFunc::MapGet,
+            BuiltinFunc::ListElem,
+            BuiltinFunc::MapKey,
+            BuiltinFunc::JsonParse,
+            BuiltinFunc::JsonExtract,
+            BuiltinFunc::JsonType,
+            BuiltinFunc::JsonArrayElements,
+            BuiltinFunc::Hex,
+            BuiltinFunc::Base64,
+            BuiltinFunc::ToHex,
+            BuiltinFunc::ToBase64,
+            BuiltinFunc::BytesLen,
+            BuiltinFunc::BytesSlice,
         ];
         for func in &funcs {
             let name = func.as_str();
@@ -1850,6 +2060,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_arith_expr_try_eval_constant_overflow() {
+        let expr = ArithExpr::Binary {
+            op: ArithOp::Mul,
+            left: Box::new(ArithExpr::Constant(i64::MAX)),
+            right: Box::new(ArithExpr::Constant(2)),
+        };
+        assert_eq!(expr.try_eval_constant(), None);
+    }
+
+    #[test]
+    fn test_arith_expr_fold_constants() {
+        // X + (2 * 3) -> X + 6
+        let expr = ArithExpr::Binary {
+            op: ArithOp::Add,
+            left: Box::new(ArithExpr::Variable("x".to_string())),
+            right: Box::new(ArithExpr::Binary {
+                op: ArithOp::Mul,
+                left: Box::new(ArithExpr::Constant(2)),
+                right: Box::new(ArithExpr::Constant(3)),
+            }),
+        };
+        assert_eq!(
+            expr.fold_constants(),
+            ArithExpr::Binary {
+                op: ArithOp::Add,
+                left: Box::new(ArithExpr::Variable("x".to_string())),
+                right: Box::new(ArithExpr::Constant(6)),
+            }
+        );
+
+        // Division by zero is left for the runtime
+        let div = ArithExpr::Binary {
+            op: ArithOp::Div,
+            left: Box::new(ArithExpr::Constant(1)),
+            right: Box::new(ArithExpr::Constant(0)),
+        };
+        assert_eq!(div.clone().fold_constants(), div);
+    }
+
     // --- Term ---
 
     #[test]
@@ -0,0 +1,209 @@
+//! Statement Audit Log
+//!
+//! With `[audit] enabled`, every program a client runs is recorded: who ran
+//! it, when, against which knowledge graph, the statement text, the rows it
+//! returned, how long it took and whether it succeeded. Passwords in
+//! `.user create` and `.user password` are redacted before recording.
+//!
+//! Records are appended as JSON Lines to `audit.jsonl` in the audit
+//! directory. Once the file reaches `max_file_bytes` it is renamed to
+//! `audit.1.jsonl`, older files shift up (`audit.2.jsonl`, ...), and files
+//! past `max_files` are deleted. A rotated file can be loaded into a
+//! knowledge graph with `copy` for postmortems.
+//!
+//! With `relation` (the default), each record is also appended to the
+//! `statements` relation of the `_audit` knowledge graph. Only admins can
+//! use `_audit`, and it is read-only, so the trail can be queried with
+//! Datalog but not rewritten through the server:
+//!
+//! ```text
+//! .kg use _audit
+//! ?statements(Time, User, Session, Kg, Statement, Rows, Ms, "error", Error)
+//! ```
+
+use std::io;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::config::AuditConfig;
+use crate::jsonl_log::JsonlLog;
+use crate::schema::{ColumnSchema, RelationSchema, SchemaType};
+use crate::value::{Tuple, Value};
+
+/// Knowledge graph holding the `statements` relation
+pub const AUDIT_KG: &str = "_audit";
+
+/// Relation of `AUDIT_KG` audit records are appended to
+pub const AUDIT_RELATION: &str = "statements";
+
+/// One executed program
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditRecord {
+    /// Start of execution, in milliseconds since the epoch
+    pub time: i64,
+    /// Authenticated user; `None` when authentication is off
+    pub user: Option<String>,
+    pub session: Option<String>,
+    pub knowledge_graph: String,
+    /// Program text, with passwords redacted
+    pub statement: String,
+    /// Rows in the result
+    pub rows: usize,
+    pub duration_ms: u64,
+    /// `ok` or `error`
+    pub outcome: &'static str,
+    pub error: Option<String>,
+}
+
+impl AuditRecord {
+    /// The record as a tuple of [`relation_schema`]
+    pub fn to_tuple(&self) -> Tuple {
+        Tuple::new(vec![
+            Value::Timestamp(self.time),
+            Value::string(self.user.as_deref().unwrap_or("")),
+            Value::string(self.session.as_deref().unwrap_or("")),
+            Value::string(&self.knowledge_graph),
+            Value::string(&self.statement),
+            Value::Int64(self.rows as i64),
+            Value::Int64(self.duration_ms as i64),
+            Value::string(self.outcome),
+            Value::string(self.error.as_deref().unwrap_or("")),
+        ])
+    }
+}
+
+/// Schema of the `statements` relation
+pub fn relation_schema() -> RelationSchema {
+    [
+        ("time", SchemaType::Timestamp),
+        ("user", SchemaType::String),
+        ("session", SchemaType::String),
+        ("knowledge_graph", SchemaType::String),
+        ("statement", SchemaType::String),
+        ("rows", SchemaType::Int),
+        ("duration_ms", SchemaType::Int),
+        ("outcome", SchemaType::String),
+        ("error", SchemaType::String),
+    ]
+    .into_iter()
+    .fold(RelationSchema::new(AUDIT_RELATION), |schema, (name, ty)| {
+        schema.with_column(ColumnSchema::new(name, ty))
+    })
+}
+
+/// `program` with the passwords of `.user create` and `.user password`
+/// replaced, whether or not the command is well formed
+pub fn redact(program: &str) -> String {
+    program
+        .lines()
+        .map(|line| {
+            let mut parts: Vec<&str> = line.split_whitespace().collect();
+            let has_password = parts.len() > 3
+                && parts[0] == ".user"
+                && ["create", "password"]
+                    .iter()
+                    .any(|cmd| parts[1].eq_ignore_ascii_case(cmd));
+            if has_password {
+                parts[3] = "[REDACTED]";
+                parts.join(" ")
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Append-only audit files with size-based rotation
+pub struct AuditFile(JsonlLog);
+
+impl AuditFile {
+    /// Open (or create) the active audit file in `config.dir`, defaulting
+    /// to `audit` under `data_dir`
+    pub fn open(config: &AuditConfig, data_dir: &Path) -> io::Result<Self> {
+        let dir = config.dir.clone().unwrap_or_else(|| data_dir.join("audit"));
+        JsonlLog::open(dir, "audit", config.max_file_bytes, config.max_files).map(Self)
+    }
+
+    /// Directory holding the audit files
+    pub fn dir(&self) -> &Path {
+        self.0.dir()
+    }
+
+    /// Append `record` as one JSON line, rotating first if it would take
+    /// the active file past `max_file_bytes`
+    pub fn append(&self, record: &AuditRecord) -> io::Result<()> {
+        self.0.append(record)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn record(statement: &str) -> AuditRecord {
+        AuditRecord {
+            time: 1_700_000_000_000,
+            user: Some("alice".to_string()),
+            session: None,
+            knowledge_graph: "default".to_string(),
+            statement: statement.to_string(),
+            rows: 2,
+            duration_ms: 3,
+            outcome: "ok",
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_redact_passwords() {
+        let program = "+edge[(1, 2)]\n.user create bob hunter2 editor\n.user password bob s3cret";
+        assert_eq!(
+            redact(program),
+            "+edge[(1, 2)]\n.user create bob [REDACTED] editor\n.user password bob [REDACTED]"
+        );
+        assert_eq!(redact(".user list"), ".user list");
+    }
+
+    #[test]
+    fn test_record_matches_schema() {
+        let tuple = record("?edge(X, Y)").to_tuple();
+        assert_eq!(tuple.arity(), relation_schema().arity());
+    }
+
+    #[test]
+    fn test_append_rotates_and_prunes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let line_len = serde_json::to_vec(&record("?a(X)")).unwrap().len() as u64 + 1;
+        let config = AuditConfig {
+            enabled: true,
+            dir: Some(tmp.path().to_path_buf()),
+            max_file_bytes: line_len * 2,
+            max_files: 2,
+            relation: false,
+        };
+        let audit = AuditFile::open(&config, tmp.path()).unwrap();
+        for _ in 0..7 {
+            audit.append(&record("?a(X)")).unwrap();
+        }
+
+        let lines = |name: &str| {
+            fs::read_to_string(tmp.path().join(name))
+                .unwrap()
+                .lines()
+                .count()
+        };
+        assert_eq!(lines("audit.jsonl"), 1);
+        assert_eq!(lines("audit.1.jsonl"), 2);
+        assert_eq!(lines("audit.2.jsonl"), 2);
+        assert!(!tmp.path().join("audit.3.jsonl").exists());
+
+        let first = fs::read_to_string(tmp.path().join("audit.jsonl")).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(first.trim()).unwrap();
+        assert_eq!(parsed["user"], "alice");
+        assert_eq!(parsed["outcome"], "ok");
+    }
+}
@@ -3,7 +3,8 @@
 //! Provides role-based authorization for all IQL operations,
 //! password hashing (argon2id), and API key management (SHA-256).
 
-use crate::statement::{MetaCommand, Statement};
+use crate::ast::BodyPredicate;
+use crate::statement::{DeletePattern, GrantObject, MetaCommand, ShowTarget, Statement};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::path::Path;
@@ -51,6 +52,9 @@ impl FromStr for Role {
 pub struct AuthIdentity {
     pub username: String,
     pub role: Role,
+    /// Label of the API key used to authenticate (None for password logins).
+    /// Recorded in audit logs and used to key per-key rate limits.
+    pub api_key: Option<String>,
 }
 
 // ── Password Hashing (argon2id) ─────────────────────────────────────────────
@@ -91,6 +95,16 @@ pub fn hash_api_key(key: &str) -> String {
     format!("{:x}", hasher.finalize())
 }
 
+/// Compare two byte strings in time that depends only on their lengths, not
+/// on where they differ. Used to check API key hashes.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    std::hint::black_box(diff) == 0
+}
+
 /// Generate a random API key (32 bytes → 64 hex characters).
 pub fn generate_api_key() -> String {
     use rand::Rng;
@@ -178,6 +192,75 @@ impl FromStr for KgRole {
     }
 }
 
+impl KgRole {
+    /// Privilege this role gives on every relation and view of the KG
+    pub fn privilege(self) -> Privilege {
+        match self {
+            KgRole::Owner => Privilege::Admin,
+            KgRole::Editor => Privilege::Write,
+            KgRole::Viewer => Privilege::Read,
+        }
+    }
+}
+
+/// Privilege on a knowledge graph, relation or view. Each level includes
+/// the ones below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Privilege {
+    /// Query
+    Read,
+    /// Insert, delete and update facts
+    Write,
+    /// Change schema or rules, drop, and grant to others
+    Admin,
+}
+
+impl Privilege {
+    /// KG role holding this privilege on the whole knowledge graph
+    pub fn kg_role(self) -> KgRole {
+        match self {
+            Privilege::Admin => KgRole::Owner,
+            Privilege::Write => KgRole::Editor,
+            Privilege::Read => KgRole::Viewer,
+        }
+    }
+
+    /// The next lower privilege, left after revoking this one
+    pub fn below(self) -> Option<Privilege> {
+        match self {
+            Privilege::Admin => Some(Privilege::Write),
+            Privilege::Write => Some(Privilege::Read),
+            Privilege::Read => None,
+        }
+    }
+}
+
+impl fmt::Display for Privilege {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Privilege::Read => write!(f, "read"),
+            Privilege::Write => write!(f, "write"),
+            Privilege::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+impl FromStr for Privilege {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "read" => Ok(Privilege::Read),
+            "write" => Ok(Privilege::Write),
+            "admin" => Ok(Privilege::Admin),
+            _ => Err(format!(
+                "Unknown privilege '{s}'. Valid privileges: read, write, admin"
+            )),
+        }
+    }
+}
+
 /// Check whether a KG role permits a given statement on that KG.
 /// Called AFTER the global `authorize_statement()` check passes.
 pub fn authorize_kg_operation(kg_role: &KgRole, stmt: &Statement) -> Result<(), String> {
@@ -200,13 +283,35 @@ fn authorize_kg_editor(stmt: &Statement) -> Result<(), String> {
         | Statement::Fact(_)
         | Statement::SchemaDecl(_)
         | Statement::TypeDecl(_)
-        | Statement::DeleteRelationOrRule(_) => Ok(()),
+        | Statement::DeleteRelationOrRule(_)
+        | Statement::Analyze(_)
+        | Statement::Show(_)
+        | Statement::Describe(_)
+        | Statement::Transaction(_)
+        | Statement::Prepare(_)
+        | Statement::Execute(_)
+        | Statement::Deallocate(_) => Ok(()),
+
+        // Reads and writes files on the server (admin only, should not reach per-KG check)
+        Statement::Copy(_) => {
+            Err("Permission denied: only admins can copy to or from server files".to_string())
+        }
+
+        Statement::Grant(_) | Statement::Revoke(_) => {
+            Err("Permission denied: only KG owners can manage grants".to_string())
+        }
 
         Statement::Meta(cmd) => match cmd {
             // KG editors cannot drop KGs or manage ACLs (Owner only)
-            MetaCommand::KgDrop(_) => {
+            MetaCommand::KgDrop(_) | MetaCommand::KgDropCascade(_) => {
                 Err("Permission denied: only KG owners can drop this knowledge graph".to_string())
             }
+            MetaCommand::KgRename { .. } => {
+                Err("Permission denied: only KG owners can rename this knowledge graph".to_string())
+            }
+            MetaCommand::KgRestore(_) => Err(
+                "Permission denied: only KG owners can restore this knowledge graph".to_string(),
+            ),
             MetaCommand::KgAclGrant { .. } | MetaCommand::KgAclRevoke { .. } => {
                 Err("Permission denied: only KG owners can manage ACLs".to_string())
             }
@@ -214,11 +319,13 @@ fn authorize_kg_editor(stmt: &Statement) -> Result<(), String> {
             MetaCommand::KgShow
             | MetaCommand::KgList
             | MetaCommand::KgUse(_)
-            | MetaCommand::KgCreate(_) => Ok(()),
+            | MetaCommand::KgCreate(_)
+            | MetaCommand::KgCopy { .. } => Ok(()),
             // Relation/rule management
             MetaCommand::RelList
             | MetaCommand::RelDescribe(_)
             | MetaCommand::RelDrop(_)
+            | MetaCommand::RelAlter { .. }
             | MetaCommand::RuleList
             | MetaCommand::RuleQuery(_)
             | MetaCommand::RuleShowDef(_)
@@ -226,7 +333,9 @@ fn authorize_kg_editor(stmt: &Statement) -> Result<(), String> {
             | MetaCommand::RuleDropPrefix(_)
             | MetaCommand::RuleEdit { .. }
             | MetaCommand::RuleClear(_)
-            | MetaCommand::RuleRemove { .. } => Ok(()),
+            | MetaCommand::RuleRemove { .. }
+            | MetaCommand::RuleRefresh(_)
+            | MetaCommand::RuleSetRefresh { .. } => Ok(()),
             // Index management
             MetaCommand::IndexList
             | MetaCommand::IndexCreate(_)
@@ -241,7 +350,11 @@ fn authorize_kg_editor(stmt: &Statement) -> Result<(), String> {
             MetaCommand::SessionList
             | MetaCommand::SessionClear
             | MetaCommand::SessionDrop(_)
-            | MetaCommand::SessionDropName(_) => Ok(()),
+            | MetaCommand::SessionDropName(_)
+            | MetaCommand::SessionSettings
+            | MetaCommand::SessionSet { .. }
+            | MetaCommand::SessionSetPriority(_)
+            | MetaCommand::SessionReset(_) => Ok(()),
             // Read-only system commands
             MetaCommand::Debug(_)
             | MetaCommand::Why(_)
@@ -257,6 +370,13 @@ fn authorize_kg_editor(stmt: &Statement) -> Result<(), String> {
             | MetaCommand::AgentExamples => Ok(()),
             // System administration (admin only, should not reach per-KG check)
             MetaCommand::Compact
+            | MetaCommand::EncryptionRotate
+            | MetaCommand::LogShow
+            | MetaCommand::LogLevel(_)
+            | MetaCommand::ReplicationStatus
+            | MetaCommand::ReplicationPromote
+            | MetaCommand::ClusterStatus
+            | MetaCommand::ShardStatus
             | MetaCommand::UserList
             | MetaCommand::UserCreate { .. }
             | MetaCommand::UserDrop(_)
@@ -273,7 +393,14 @@ fn authorize_kg_editor(stmt: &Statement) -> Result<(), String> {
 
 fn authorize_kg_viewer(stmt: &Statement) -> Result<(), String> {
     match stmt {
-        Statement::Query(_) | Statement::SessionRule(_) => Ok(()),
+        Statement::Query(_)
+        | Statement::SessionRule(_)
+        | Statement::Show(_)
+        | Statement::Describe(_)
+        | Statement::Transaction(_)
+        | Statement::Prepare(_)
+        | Statement::Execute(_)
+        | Statement::Deallocate(_) => Ok(()),
 
         Statement::Insert(_)
         | Statement::Delete(_)
@@ -282,7 +409,11 @@ fn authorize_kg_viewer(stmt: &Statement) -> Result<(), String> {
         | Statement::Fact(_)
         | Statement::SchemaDecl(_)
         | Statement::TypeDecl(_)
-        | Statement::DeleteRelationOrRule(_) => {
+        | Statement::DeleteRelationOrRule(_)
+        | Statement::Analyze(_)
+        | Statement::Copy(_)
+        | Statement::Grant(_)
+        | Statement::Revoke(_) => {
             Err("Permission denied: you have viewer access to this knowledge graph".to_string())
         }
 
@@ -310,7 +441,11 @@ fn authorize_kg_viewer(stmt: &Statement) -> Result<(), String> {
             MetaCommand::SessionList
             | MetaCommand::SessionClear
             | MetaCommand::SessionDrop(_)
-            | MetaCommand::SessionDropName(_) => Ok(()),
+            | MetaCommand::SessionDropName(_)
+            | MetaCommand::SessionSettings
+            | MetaCommand::SessionSet { .. }
+            | MetaCommand::SessionSetPriority(_)
+            | MetaCommand::SessionReset(_) => Ok(()),
             // Agent commands (read-only interaction)
             MetaCommand::AgentMessage(_)
             | MetaCommand::AgentStart(_)
@@ -325,7 +460,7 @@ fn authorize_kg_viewer(stmt: &Statement) -> Result<(), String> {
 
 // ── Authorization ───────────────────────────────────────────────────────────
 //
-// Two-layer authorization model:
+// Layered authorization model:
 //
 //   Layer 1 - Global role (`authorize_statement`):
 //     Gates system-level operations only: user management, API keys, compaction,
@@ -338,8 +473,16 @@ fn authorize_kg_viewer(stmt: &Statement) -> Result<(), String> {
 //     Editor, Viewer) determines what the user can do within that KG.
 //     This is the authority for data access - not the global role.
 //
+//   Layer 3 - Object grants (`authorize_object_access`):
+//     Consulted when the KG role alone (or no KG role) does not allow a
+//     statement. `grant read|write|admin on relation|view ...` gives a user
+//     a privilege on one object; the statement passes if every relation and
+//     view it touches is covered by that grant or the KG role.
+//
 // This separation means a global Viewer who is a KG Owner can fully manage
 // their KG, and a global Editor who is a KG Viewer can only read that KG.
+// An analyst with `read` on a view can query it without any access to the
+// relations it derives from.
 
 /// Check whether a global role is authorized to execute a given statement.
 /// This only gates system-level operations. Data/KG-scoped operations are
@@ -355,6 +498,11 @@ pub fn authorize_statement(role: &Role, stmt: &Statement) -> Result<(), String>
 /// Only blocks system-level operations. Data operations are deferred to per-KG auth.
 fn authorize_non_admin(role: &Role, stmt: &Statement) -> Result<(), String> {
     match stmt {
+        // Lists the programs of every user
+        Statement::Show(ShowTarget::Queries) => {
+            Err("Permission denied: only admins can list queries".to_string())
+        }
+
         // All data operations are deferred to per-KG authorization.
         // The per-KG role (Owner/Editor/Viewer) determines access.
         Statement::Query(_)
@@ -366,7 +514,22 @@ fn authorize_non_admin(role: &Role, stmt: &Statement) -> Result<(), String> {
         | Statement::Fact(_)
         | Statement::SchemaDecl(_)
         | Statement::TypeDecl(_)
-        | Statement::DeleteRelationOrRule(_) => Ok(()),
+        | Statement::DeleteRelationOrRule(_)
+        | Statement::Analyze(_)
+        | Statement::Show(_)
+        | Statement::Describe(_)
+        | Statement::Transaction(_)
+        | Statement::Prepare(_)
+        | Statement::Execute(_)
+        | Statement::Deallocate(_) => Ok(()),
+
+        // Reads and writes files on the server
+        Statement::Copy(_) => {
+            Err("Permission denied: only admins can copy to or from server files".to_string())
+        }
+
+        // Grants - deferred to per-KG auth (Owner) and object grants (admin)
+        Statement::Grant(_) | Statement::Revoke(_) => Ok(()),
 
         Statement::Meta(cmd) => authorize_non_admin_meta(role, cmd),
     }
@@ -377,14 +540,17 @@ fn authorize_non_admin_meta(role: &Role, cmd: &MetaCommand) -> Result<(), String
     match cmd {
         // KG lifecycle: editors can create, viewers cannot.
         // Drop is deferred to per-KG auth (requires Owner).
-        MetaCommand::KgCreate(_) => {
+        MetaCommand::KgCreate(_) | MetaCommand::KgCopy { .. } => {
             if *role == Role::Viewer {
                 Err("Permission denied: viewers cannot create knowledge graphs".to_string())
             } else {
                 Ok(())
             }
         }
-        MetaCommand::KgDrop(_) => Ok(()), // per-KG Owner check enforces this
+        MetaCommand::KgDrop(_)
+        | MetaCommand::KgDropCascade(_)
+        | MetaCommand::KgRename { .. }
+        | MetaCommand::KgRestore(_) => Ok(()), // per-KG Owner check enforces this
 
         // KG navigation - all roles
         MetaCommand::KgShow | MetaCommand::KgList | MetaCommand::KgUse(_) => Ok(()),
@@ -393,6 +559,7 @@ fn authorize_non_admin_meta(role: &Role, cmd: &MetaCommand) -> Result<(), String
         MetaCommand::RelList
         | MetaCommand::RelDescribe(_)
         | MetaCommand::RelDrop(_)
+        | MetaCommand::RelAlter { .. }
         | MetaCommand::RuleList
         | MetaCommand::RuleQuery(_)
         | MetaCommand::RuleShowDef(_)
@@ -400,7 +567,9 @@ fn authorize_non_admin_meta(role: &Role, cmd: &MetaCommand) -> Result<(), String
         | MetaCommand::RuleDropPrefix(_)
         | MetaCommand::RuleEdit { .. }
         | MetaCommand::RuleClear(_)
-        | MetaCommand::RuleRemove { .. } => Ok(()),
+        | MetaCommand::RuleRemove { .. }
+        | MetaCommand::RuleRefresh(_)
+        | MetaCommand::RuleSetRefresh { .. } => Ok(()),
 
         // Index management - deferred to per-KG auth
         MetaCommand::IndexList
@@ -421,7 +590,11 @@ fn authorize_non_admin_meta(role: &Role, cmd: &MetaCommand) -> Result<(), String
         MetaCommand::SessionList
         | MetaCommand::SessionClear
         | MetaCommand::SessionDrop(_)
-        | MetaCommand::SessionDropName(_) => Ok(()),
+        | MetaCommand::SessionDropName(_)
+        | MetaCommand::SessionSettings
+        | MetaCommand::SessionSet { .. }
+        | MetaCommand::SessionSetPriority(_)
+        | MetaCommand::SessionReset(_) => Ok(()),
 
         // Read-only system commands - all roles
         MetaCommand::Debug(_)
@@ -440,6 +613,21 @@ fn authorize_non_admin_meta(role: &Role, cmd: &MetaCommand) -> Result<(), String
 
         // System administration - admin only
         MetaCommand::Compact => Err("Permission denied: only admins can compact".to_string()),
+        MetaCommand::EncryptionRotate => {
+            Err("Permission denied: only admins can rotate encryption keys".to_string())
+        }
+        MetaCommand::LogShow | MetaCommand::LogLevel(_) => {
+            Err("Permission denied: only admins can change log verbosity".to_string())
+        }
+        MetaCommand::ReplicationStatus | MetaCommand::ReplicationPromote => {
+            Err("Permission denied: only admins can manage replication".to_string())
+        }
+        MetaCommand::ClusterStatus => {
+            Err("Permission denied: only admins can inspect the cluster".to_string())
+        }
+        MetaCommand::ShardStatus => {
+            Err("Permission denied: only admins can inspect the shards".to_string())
+        }
         MetaCommand::UserList
         | MetaCommand::UserCreate { .. }
         | MetaCommand::UserDrop(_)
@@ -453,6 +641,169 @@ fn authorize_non_admin_meta(role: &Role, cmd: &MetaCommand) -> Result<(), String
     }
 }
 
+// ── Object Grants ───────────────────────────────────────────────────────────
+
+/// A relation or view a statement touches, with the privilege it needs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectUse {
+    pub name: String,
+    pub privilege: Privilege,
+    /// The name may also be a session rule or session fact rather than a
+    /// stored relation or view (reads, and session facts)
+    pub session_ok: bool,
+}
+
+/// Whether a name is a stored relation or view, and the user's privilege on it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectAccess {
+    /// Not a relation or view of the knowledge graph
+    Missing,
+    /// A relation or view; the user's privilege from grants or KG role
+    Stored(Option<Privilege>),
+}
+
+fn object_use(name: &str, privilege: Privilege, session_ok: bool) -> ObjectUse {
+    ObjectUse {
+        name: name.trim_start_matches('~').to_string(),
+        privilege,
+        session_ok,
+    }
+}
+
+fn body_reads(body: &[BodyPredicate], uses: &mut Vec<ObjectUse>) {
+    for atom in body.iter().filter_map(BodyPredicate::atom) {
+        uses.push(object_use(&atom.relation, Privilege::Read, true));
+    }
+}
+
+/// Relations and views `stmt` touches and the privilege each needs.
+/// `None` for statements that act on the knowledge graph as a whole,
+/// which only a KG role can allow.
+pub fn statement_objects(stmt: &Statement) -> Option<Vec<ObjectUse>> {
+    let mut uses = Vec::new();
+    match stmt {
+        Statement::Query(query) => {
+            uses.push(object_use(&query.goal.relation, Privilege::Read, true));
+            body_reads(&query.body, &mut uses);
+        }
+        Statement::SessionRule(rule) => body_reads(&rule.body, &mut uses),
+        Statement::Fact(rule) => uses.push(object_use(&rule.head.relation, Privilege::Write, true)),
+        Statement::Insert(op) => uses.push(object_use(&op.relation, Privilege::Write, false)),
+        Statement::Delete(op) => {
+            uses.push(object_use(&op.relation, Privilege::Write, false));
+            if let DeletePattern::Conditional { body, .. } = &op.pattern {
+                body_reads(body, &mut uses);
+            }
+        }
+        Statement::Update(op) => {
+            for target in &op.deletes {
+                uses.push(object_use(&target.relation, Privilege::Write, false));
+            }
+            for target in &op.inserts {
+                uses.push(object_use(&target.relation, Privilege::Write, false));
+            }
+            body_reads(&op.body, &mut uses);
+        }
+        Statement::PersistentRule(rule) => {
+            uses.push(object_use(&rule.head.relation, Privilege::Admin, false));
+            body_reads(&rule.body, &mut uses);
+        }
+        Statement::SchemaDecl(decl) => {
+            let privilege = if decl.persistent {
+                Privilege::Admin
+            } else {
+                Privilege::Write
+            };
+            uses.push(object_use(&decl.name, privilege, !decl.persistent));
+        }
+        Statement::DeleteRelationOrRule(name) => {
+            uses.push(object_use(name, Privilege::Admin, false));
+        }
+        Statement::Analyze(Some(name)) => uses.push(object_use(name, Privilege::Write, false)),
+        Statement::Describe(name) => uses.push(object_use(name, Privilege::Read, false)),
+        Statement::Grant(grant) | Statement::Revoke(grant) => match &grant.object {
+            GrantObject::Relation(name) | GrantObject::View(name) => {
+                uses.push(object_use(name, Privilege::Admin, false));
+            }
+            GrantObject::Database(_) => return None,
+        },
+        // An executed statement is checked once bound, as itself
+        Statement::Transaction(_)
+        | Statement::Prepare(_)
+        | Statement::Execute(_)
+        | Statement::Deallocate(_) => {}
+        Statement::Analyze(None)
+        | Statement::Show(_)
+        | Statement::TypeDecl(_)
+        | Statement::Copy(_) => return None,
+        Statement::Meta(cmd) => match cmd {
+            MetaCommand::RelDescribe(name)
+            | MetaCommand::RuleQuery(name)
+            | MetaCommand::RuleShowDef(name) => {
+                uses.push(object_use(name, Privilege::Read, false));
+            }
+            MetaCommand::RuleRefresh(name) => {
+                uses.push(object_use(name, Privilege::Write, false));
+            }
+            MetaCommand::RelDrop(name)
+            | MetaCommand::RelAlter { relation: name, .. }
+            | MetaCommand::RuleDrop(name)
+            | MetaCommand::RuleEdit { name, .. }
+            | MetaCommand::RuleClear(name)
+            | MetaCommand::RuleRemove { name, .. }
+            | MetaCommand::RuleSetRefresh { name, .. } => {
+                uses.push(object_use(name, Privilege::Admin, false));
+            }
+            // Navigation and per-connection state
+            MetaCommand::KgUse(_)
+            | MetaCommand::SessionList
+            | MetaCommand::SessionClear
+            | MetaCommand::SessionDrop(_)
+            | MetaCommand::SessionDropName(_)
+            | MetaCommand::SessionSettings
+            | MetaCommand::SessionSet { .. }
+            | MetaCommand::SessionSetPriority(_)
+            | MetaCommand::SessionReset(_)
+            | MetaCommand::Status
+            | MetaCommand::Help
+            | MetaCommand::Quit => {}
+            _ => return None,
+        },
+    }
+    Some(uses)
+}
+
+/// Check `stmt` against the user's privileges on the relations and views
+/// it touches. `access` looks a name up in the knowledge graph.
+pub fn authorize_object_access(
+    stmt: &Statement,
+    access: impl Fn(&str) -> ObjectAccess,
+) -> Result<(), String> {
+    let Some(uses) = statement_objects(stmt) else {
+        return Err("Access denied".to_string());
+    };
+    for used in uses {
+        match access(&used.name) {
+            ObjectAccess::Missing if used.session_ok => {}
+            ObjectAccess::Missing => {
+                return Err(format!(
+                    "Permission denied: '{}' does not exist, and creating it needs access to the knowledge graph",
+                    used.name
+                ));
+            }
+            ObjectAccess::Stored(held) => {
+                if held.is_none_or(|held| held < used.privilege) {
+                    return Err(format!(
+                        "Permission denied: needs {} on '{}'",
+                        used.privilege, used.name
+                    ));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
@@ -516,6 +867,21 @@ mod tests {
         assert_ne!(h1, h2);
     }
 
+    #[test]
+    fn test_constant_time_eq() {
+        let h = hash_api_key("key-a");
+        assert!(constant_time_eq(
+            h.as_bytes(),
+            hash_api_key("key-a").as_bytes()
+        ));
+        assert!(!constant_time_eq(
+            h.as_bytes(),
+            hash_api_key("key-b").as_bytes()
+        ));
+        assert!(!constant_time_eq(b"abc", b"abcd"));
+        assert!(constant_time_eq(b"", b""));
+    }
+
     #[test]
     fn test_generate_api_key_length() {
         let key = generate_api_key();
@@ -539,6 +905,8 @@ mod tests {
             ".kg create test",
             ".kg drop test",
             ".compact",
+            ".encryption rotate",
+            ".log level debug",
             ".user list",
             ".apikey list",
         ];
@@ -573,9 +941,12 @@ mod tests {
         // System operations remain admin-only
         let denied = vec![
             ".compact",
+            ".encryption rotate",
+            ".log reset",
             ".user list",
             ".user create bob pass editor",
             ".apikey create mykey",
+            "copy edge to 'edges.csv'",
         ];
         for s in denied {
             let stmt = parse_statement(s).unwrap();
@@ -611,7 +982,13 @@ mod tests {
         }
 
         // Only KG creation and system ops are blocked at global level
-        let denied = vec![".kg create test", ".compact", ".user list", ".apikey list"];
+        let denied = vec![
+            ".kg create test",
+            ".compact",
+            ".user list",
+            ".apikey list",
+            "show queries",
+        ];
         for s in denied {
             let stmt = parse_statement(s).unwrap();
             assert!(
@@ -758,6 +1135,91 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[test]
+    fn test_privilege_order_and_kg_roles() {
+        assert!(Privilege::Read < Privilege::Write && Privilege::Write < Privilege::Admin);
+        assert_eq!(Privilege::from_str("WRITE").unwrap(), Privilege::Write);
+        assert!(Privilege::from_str("drop").is_err());
+        for role in [KgRole::Owner, KgRole::Editor, KgRole::Viewer] {
+            assert_eq!(role.privilege().kg_role(), role);
+        }
+        assert_eq!(Privilege::Admin.below(), Some(Privilege::Write));
+        assert_eq!(Privilege::Read.below(), None);
+    }
+
+    #[test]
+    fn test_object_access() {
+        use crate::statement::parse_statement;
+        // Analyst: read on the view `report`, write on `notes`, nothing on `salary`
+        let access = |name: &str| match name {
+            "report" => ObjectAccess::Stored(Some(Privilege::Read)),
+            "notes" => ObjectAccess::Stored(Some(Privilege::Write)),
+            "salary" => ObjectAccess::Stored(None),
+            _ => ObjectAccess::Missing,
+        };
+        let allowed = [
+            "?report(X, Y)",
+            "?report(X, Y), notes(X, _)",
+            "+notes(1, \"ok\")",
+            "-notes(X, Y) <- report(X, Y)",
+            "mine(X) <- report(X, _)",
+            "?mine(X)",
+            "describe report",
+            ".rule def report",
+            ".kg use hr",
+            "begin",
+        ];
+        for s in allowed {
+            let stmt = parse_statement(s).unwrap();
+            assert!(
+                authorize_object_access(&stmt, access).is_ok(),
+                "should be allowed: {s}"
+            );
+        }
+        let denied = [
+            "?salary(X, Y)",
+            "?report(X, Y), !salary(X, _)",
+            "+report(1, 2)",
+            "-report",
+            ".rule drop report",
+            "+notes(X, Y) <- salary(X, Y)",
+            "+fresh(1)",
+            "grant read on relation notes to bob",
+            "show relations",
+            ".kg drop hr",
+        ];
+        for s in denied {
+            let stmt = parse_statement(s).unwrap();
+            assert!(
+                authorize_object_access(&stmt, access).is_err(),
+                "should be denied: {s}"
+            );
+        }
+
+        // Admin on an object allows managing it and granting it on
+        let owner = |_: &str| ObjectAccess::Stored(Some(Privilege::Admin));
+        for s in [
+            "grant read on view report to bob",
+            "-report",
+            ".rel alter report add note: string",
+        ] {
+            let stmt = parse_statement(s).unwrap();
+            assert!(authorize_object_access(&stmt, owner).is_ok(), "{s}");
+        }
+        let stmt = parse_statement("grant read on database hr to bob").unwrap();
+        assert!(authorize_object_access(&stmt, owner).is_err());
+    }
+
+    #[test]
+    fn test_kg_roles_cannot_grant_below_owner() {
+        use crate::statement::parse_statement;
+        let stmt = parse_statement("grant read on relation edge to bob").unwrap();
+        assert!(authorize_kg_operation(&KgRole::Owner, &stmt).is_ok());
+        assert!(authorize_kg_operation(&KgRole::Editor, &stmt).is_err());
+        assert!(authorize_kg_operation(&KgRole::Viewer, &stmt).is_err());
+        assert!(authorize_statement(&Role::Viewer, &stmt).is_ok());
+    }
+
     #[test]
     fn test_persisted_credentials_load_invalid_toml() {
         let dir = tempfile::tempdir().unwrap();
@@ -29,6 +29,7 @@ use std::fs;
 use std::future::Future;
 use std::path::PathBuf;
 use std::pin::Pin;
+use std::sync::Arc;
 use tokio_tungstenite::tungstenite;
 
 // ── Health check DTOs (HTTP, one-time on startup) ────────────────
@@ -224,10 +225,19 @@ struct WsClient {
 impl WsClient {
     /// Connect to the WebSocket endpoint, authenticate with an API key,
     /// and spawn a background reader task.
-    async fn connect(ws_url: &str, api_key: &str) -> Result<(Self, WsResponse), String> {
+    async fn connect(
+        ws_url: &str,
+        api_key: &str,
+        tls: Option<Arc<rustls::ClientConfig>>,
+    ) -> Result<(Self, WsResponse), String> {
         let (ws_stream, _) = tokio::time::timeout(
             std::time::Duration::from_secs(10),
-            tokio_tungstenite::connect_async(ws_url),
+            tokio_tungstenite::connect_async_tls_with_config(
+                ws_url,
+                None,
+                false,
+                tls.map(tokio_tungstenite::Connector::Rustls),
+            ),
         )
         .await
         .map_err(|_| "WebSocket connection timeout (10s)".to_string())?
@@ -494,6 +504,9 @@ struct Args {
     display_limit: Option<usize>,
     api_key: Option<String>,
     timeout_secs: u64,
+    tls_ca: Option<PathBuf>,
+    tls_cert: Option<PathBuf>,
+    tls_key: Option<PathBuf>,
 }
 
 fn parse_args() -> Args {
@@ -505,6 +518,9 @@ fn parse_args() -> Args {
         display_limit: None,
         api_key: None,
         timeout_secs: 120,
+        tls_ca: None,
+        tls_cert: None,
+        tls_key: None,
     };
 
     let mut i = 1;
@@ -565,6 +581,20 @@ fn parse_args() -> Args {
                     std::process::exit(1);
                 }
             }
+            flag @ ("--tls-ca" | "--tls-cert" | "--tls-key") => {
+                if i + 1 < args.len() {
+                    let path = Some(PathBuf::from(&args[i + 1]));
+                    match flag {
+                        "--tls-ca" => result.tls_ca = path,
+                        "--tls-cert" => result.tls_cert = path,
+                        _ => result.tls_key = path,
+                    }
+                    i += 2;
+                } else {
+                    eprintln!("Error: {flag} requires a file path");
+                    std::process::exit(1);
+                }
+            }
             "--help" | "-h" => {
                 print_usage();
                 std::process::exit(0);
@@ -581,6 +611,11 @@ fn parse_args() -> Args {
         }
     }
 
+    if result.tls_cert.is_some() != result.tls_key.is_some() {
+        eprintln!("Error: --tls-cert and --tls-key must be given together");
+        std::process::exit(1);
+    }
+
     // Fall back to INPUTLAYER_API_KEY env var if --api-key not provided
     if result.api_key.is_none() {
         result.api_key = env::var("INPUTLAYER_API_KEY").ok();
@@ -604,6 +639,9 @@ fn print_usage() {
         "  -l, --limit <N>       Max rows to display (0 = unlimited, default: 50 REPL, 0 script)"
     );
     println!("  -t, --timeout <SECS>  Response timeout in seconds (default: 120)");
+    println!("      --tls-ca <FILE>   PEM CA bundle to trust for https:// servers");
+    println!("      --tls-cert <FILE> PEM client certificate (mutual TLS)");
+    println!("      --tls-key <FILE>  PEM private key for --tls-cert");
     println!("  -h, --help            Show this help message");
     println!();
     println!("ENVIRONMENT:");
@@ -612,6 +650,7 @@ fn print_usage() {
     println!("EXAMPLES:");
     println!("  inputlayer-client -k <api-key>                    # Connect with API key");
     println!("  inputlayer-client --server http://10.0.0.5:8080   # Connect to remote server");
+    println!("  inputlayer-client --server https://db:8080 --tls-ca ca.pem  # Connect over TLS");
     println!("  inputlayer-client script.iql                      # Execute script");
 }
 
@@ -635,10 +674,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // HTTP health check (one-time, fast feedback if server is down)
     let http_base = args.server.trim_end_matches('/');
     let health_url = format!("{http_base}/health");
-    let http_client = Client::builder()
-        .timeout(std::time::Duration::from_secs(5))
-        .build()
-        .unwrap_or_else(|_| Client::new());
+    // https:// servers: one TLS configuration for the health check and WebSocket
+    let tls = if http_base.starts_with("https://") {
+        let client_cert = args.tls_cert.as_deref().zip(args.tls_key.as_deref());
+        let config = inputlayer::protocol::tls::client_config(args.tls_ca.as_deref(), client_cert)?;
+        Some(Arc::new(config))
+    } else {
+        None
+    };
+    let mut http_client = Client::builder().timeout(std::time::Duration::from_secs(5));
+    if let Some(tls) = &tls {
+        http_client = http_client.use_preconfigured_tls(rustls::ClientConfig::clone(tls));
+    }
+    let http_client = http_client.build()?;
 
     // Retry health check up to 3 times (server may be slow to respond under load)
     let mut health: Option<ApiResponse<HealthResponse>> = None;
@@ -678,7 +726,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let ws_url = http_to_ws_url(http_base);
     let mut ws_result = None;
     for attempt in 0..3 {
-        match WsClient::connect(&ws_url, &api_key).await {
+        match WsClient::connect(&ws_url, &api_key, tls.clone()).await {
             Ok(pair) => {
                 ws_result = Some(pair);
                 break;
@@ -879,6 +927,18 @@ fn execute_script<'a>(
 // ── REPL ────────────────────────────────────────────────────────
 
 async fn run_repl(state: &mut ReplState) -> Result<(), Box<dyn std::error::Error>> {
+    // Someone is waiting on every REPL query: have the server run them
+    // ahead of batch work. Servers without priorities answer with an error,
+    // which is fine to ignore.
+    if state
+        .ws
+        .send_execute(".session set priority interactive")
+        .await
+        .is_ok()
+    {
+        let _ = state.ws.recv_response().await;
+    }
+
     let history_path = get_history_path();
     let initial_prompt = state.prompt();
 
@@ -1500,7 +1560,13 @@ fn print_help() {
     println!("  .kg list             List all knowledge graphs");
     println!("  .kg create <name>    Create knowledge graph");
     println!("  .kg use <name>       Switch to knowledge graph");
-    println!("  .kg drop <name>      Drop knowledge graph");
+    println!(
+        "  .kg drop <name>      Drop knowledge graph (add 'cascade' to drop rules reading it)"
+    );
+    println!("  .kg copy <src> <dst> Copy knowledge graph with its data and rules");
+    println!("  .kg rename <old> <new> Rename knowledge graph");
+    println!("  .kg restore lsn <n>  Restore current knowledge graph to an LSN");
+    println!("  .kg restore time <t> Restore current knowledge graph to a timestamp");
     println!("  .rel                 List relations");
     println!("  .rel <name>          Describe relation");
     println!("  .rule                List rules");
@@ -1508,6 +1574,7 @@ fn print_help() {
     println!("  .rule drop <name>    Drop all clauses of a rule");
     println!("  .rule drop prefix <p> Drop all rules matching prefix");
     println!("  .rule remove <name> <n>  Remove clause n from rule (1-based)");
+    println!("  .rule refresh <name> [eager|lazy|every <d>|none]  Cache a rule / refresh it now");
     println!("  .session             List session rules");
     println!("  .session clear       Clear all session rules");
     println!("  .session drop <n|name>  Drop session rule by index or relation name");
@@ -12,6 +12,9 @@ fn main() {
         enable_subplan_sharing: false,
         enable_boolean_specialization: false,
         enable_magic_sets: false,
+        enable_semijoin_reduction: false,
+        enable_equality_saturation: false,
+        execution_backend: None,
     };
     let mut engine = IQLEngine::with_config(config);
     engine.add_fact("edge", edges.clone());
@@ -29,6 +32,9 @@ fn main() {
         enable_subplan_sharing: false,
         enable_boolean_specialization: false,
         enable_magic_sets: false,
+        enable_semijoin_reduction: false,
+        enable_equality_saturation: false,
+        execution_backend: None,
     };
     let mut engine2 = IQLEngine::with_config(config);
     engine2.add_fact("edge", edges.clone());
@@ -24,9 +24,16 @@
 //! - WebSocket API at `/ws`
 //! - AsyncAPI docs at `/api/ws-docs`
 //! - GUI dashboard at `/` (if GUI is enabled)
+//!
+//! With `[timely]` naming this process a worker (`process > 0`), it only
+//! runs the dataflows its coordinator sends it.
+//!
+//! With the `grpc` feature and `[grpc] enabled = true`, a gRPC server
+//! runs alongside it (see `proto/inputlayer.proto`). Likewise the `flight`
+//! feature and `[flight] enabled = true` add an Arrow Flight server.
 
 use clap::Parser;
-use inputlayer::config::LoggingConfig;
+use inputlayer::config::{LoggingConfig, TracingConfig};
 use inputlayer::protocol::rest;
 use inputlayer::protocol::Handler;
 use inputlayer::Config;
@@ -89,7 +96,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     };
 
     // Initialize tracing using config as fallback when env vars are not set
-    init_tracing(&config.logging);
+    init_tracing(&config.logging, &config.tracing);
 
     // Install a panic hook that logs panics via tracing and prints to stderr.
     // Without this, panics on worker threads may lose diagnostic data because
@@ -119,7 +126,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         config.storage.data_dir = data_dir;
     }
 
+    if config.timely.is_worker() {
+        return run_timely_worker(config).await;
+    }
+
     let http_config = config.http.clone();
+    let grpc_config = config.grpc.clone();
+    let flight_config = config.flight.clone();
 
     // Warn about durability settings before config is moved
     if !config.storage.persist.enabled {
@@ -157,13 +170,163 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     }
     println!();
 
+    if grpc_config.enabled {
+        start_grpc(Arc::clone(&handler), grpc_config);
+    }
+    if flight_config.enabled {
+        start_flight(Arc::clone(&handler), flight_config);
+    }
+
     // Start HTTP server
-    rest::start_http_server(handler, &http_config).await?;
+    let served = rest::start_http_server(handler, &http_config).await;
+    shutdown_otel();
+    served?;
+
+    Ok(())
+}
+
+/// Run only the coordinator's dataflows, as a worker process of a timely
+/// cluster; no HTTP server or storage is started
+async fn run_timely_worker(
+    mut config: Config,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    config.validate().map_err(|e| {
+        eprintln!("ERROR: Invalid configuration: {e}");
+        Box::<dyn std::error::Error + Send + Sync>::from(e.clone())
+    })?;
+    let timely = config.timely;
+    println!(
+        "Timely worker process {} of {}",
+        timely.process,
+        timely.processes.len()
+    );
+    println!("Address: {}", timely.processes[timely.process]);
+    println!();
 
+    tokio::task::spawn_blocking(move || inputlayer::execution::serve_cluster_worker(&timely))
+        .await??;
     Ok(())
 }
 
-fn init_tracing(logging_config: &LoggingConfig) {
+/// Run the gRPC server in the background; it stops with the process
+#[cfg(feature = "grpc")]
+fn start_grpc(handler: Arc<Handler>, config: inputlayer::config::GrpcConfig) {
+    tokio::spawn(async move {
+        if let Err(e) = inputlayer::protocol::grpc::start_grpc_server(handler, &config).await {
+            tracing::error!(error = %e, "grpc_server_failed");
+            eprintln!("ERROR: gRPC server failed: {e}");
+        }
+    });
+}
+
+#[cfg(not(feature = "grpc"))]
+fn start_grpc(_handler: Arc<Handler>, _config: inputlayer::config::GrpcConfig) {
+    eprintln!(
+        "WARNING: [grpc] is enabled but this build does not include the grpc feature. \
+         Rebuild with --features grpc."
+    );
+}
+
+/// Run the Arrow Flight server in the background; it stops with the process
+#[cfg(feature = "flight")]
+fn start_flight(handler: Arc<Handler>, config: inputlayer::config::FlightConfig) {
+    tokio::spawn(async move {
+        if let Err(e) = inputlayer::protocol::flight::start_flight_server(handler, &config).await {
+            tracing::error!(error = %e, "flight_server_failed");
+            eprintln!("ERROR: Arrow Flight server failed: {e}");
+        }
+    });
+}
+
+#[cfg(not(feature = "flight"))]
+fn start_flight(_handler: Arc<Handler>, _config: inputlayer::config::FlightConfig) {
+    eprintln!(
+        "WARNING: [flight] is enabled but this build does not include the flight feature. \
+         Rebuild with --features flight."
+    );
+}
+
+/// Span exporter handle (`otel` feature)
+#[cfg(feature = "otel")]
+type OtelTracer = inputlayer::telemetry::Tracer;
+#[cfg(not(feature = "otel"))]
+type OtelTracer = ();
+
+/// Start the OTLP exporter if `[tracing]` enables it
+#[cfg(feature = "otel")]
+fn init_otel(config: &TracingConfig) -> Option<OtelTracer> {
+    if !config.enabled {
+        return None;
+    }
+    match inputlayer::telemetry::init_tracer(config) {
+        Ok(tracer) => Some(tracer),
+        Err(e) => {
+            eprintln!("WARNING: OpenTelemetry export disabled: {e}");
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "otel"))]
+fn init_otel(config: &TracingConfig) -> Option<OtelTracer> {
+    if config.enabled {
+        eprintln!(
+            "WARNING: [tracing] is enabled but this build does not include the otel feature. \
+             Rebuild with --features otel."
+        );
+    }
+    None
+}
+
+/// Box `subscriber` for installation, adding the OTLP export layer
+#[cfg(feature = "otel")]
+fn with_otel<S>(
+    subscriber: S,
+    tracer: Option<&OtelTracer>,
+) -> Box<dyn tracing::Subscriber + Send + Sync>
+where
+    S: tracing::Subscriber
+        + for<'span> tracing_subscriber::registry::LookupSpan<'span>
+        + Send
+        + Sync
+        + 'static,
+{
+    use tracing_subscriber::layer::SubscriberExt;
+    match tracer {
+        Some(tracer) => Box::new(subscriber.with(inputlayer::telemetry::layer(tracer.clone()))),
+        None => Box::new(subscriber),
+    }
+}
+
+#[cfg(not(feature = "otel"))]
+fn with_otel<S>(
+    subscriber: S,
+    _tracer: Option<&OtelTracer>,
+) -> Box<dyn tracing::Subscriber + Send + Sync>
+where
+    S: tracing::Subscriber + Send + Sync + 'static,
+{
+    Box::new(subscriber)
+}
+
+/// Export buffered spans before the process exits
+fn shutdown_otel() {
+    #[cfg(feature = "otel")]
+    inputlayer::telemetry::shutdown();
+}
+
+/// Let `.log level` replace the filter installed through `handle`
+fn register_log_filter<S: 'static>(
+    directives: &str,
+    handle: tracing_subscriber::reload::Handle<tracing_subscriber::EnvFilter, S>,
+) {
+    inputlayer::logging::register(inputlayer::logging::LogFilter::new(
+        directives.to_string(),
+        Box::new(move |filter| handle.reload(filter).map_err(|e| e.to_string())),
+    ));
+}
+
+fn init_tracing(logging_config: &LoggingConfig, tracing_config: &TracingConfig) {
     // IL_TRACE_FILE controls where logs go:
     //   - Not set: logs to stderr (production default)
     //   - Set to a path: logs to that file
@@ -178,13 +341,21 @@ fn init_tracing(logging_config: &LoggingConfig) {
         .ok()
         .map_or_else(|| logging_config.format == "json", |v| v != "0");
 
-    // Use IL_TRACE_LEVEL env var if set, otherwise fall back to config.logging.level
+    // Use IL_TRACE_LEVEL env var if set, otherwise fall back to config.logging
+    // (level plus per-module targets)
     let level = env::var("IL_TRACE_LEVEL")
         .ok()
-        .unwrap_or_else(|| logging_config.level.clone());
+        .unwrap_or_else(|| inputlayer::logging::filter_directives(logging_config));
+
+    let (level, filter) = match tracing_subscriber::EnvFilter::try_new(&level) {
+        Ok(filter) => (level, filter),
+        Err(_) => (
+            "info".to_string(),
+            tracing_subscriber::EnvFilter::new("info"),
+        ),
+    };
 
-    let filter = tracing_subscriber::EnvFilter::try_new(&level)
-        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info"));
+    let tracer = init_otel(tracing_config);
 
     let use_file = if let Ok(log_path) = env::var("IL_TRACE_FILE") {
         // Try to open the log file
@@ -207,10 +378,14 @@ fn init_tracing(logging_config: &LoggingConfig) {
                         .with_timer(tracing_subscriber::fmt::time::SystemTime)
                 };
 
-                let subscriber: Box<dyn tracing::Subscriber + Send + Sync> = if json {
-                    Box::new(base().json().finish())
+                let subscriber = if json {
+                    let builder = base().json().with_filter_reloading();
+                    register_log_filter(&level, builder.reload_handle());
+                    with_otel(builder.finish(), tracer.as_ref())
                 } else {
-                    Box::new(base().compact().finish())
+                    let builder = base().compact().with_filter_reloading();
+                    register_log_filter(&level, builder.reload_handle());
+                    with_otel(builder.finish(), tracer.as_ref())
                 };
 
                 let _ = tracing::subscriber::set_global_default(subscriber);
@@ -239,10 +414,14 @@ fn init_tracing(logging_config: &LoggingConfig) {
                 .with_timer(tracing_subscriber::fmt::time::SystemTime)
         };
 
-        let subscriber: Box<dyn tracing::Subscriber + Send + Sync> = if json {
-            Box::new(base().json().finish())
+        let subscriber = if json {
+            let builder = base().json().with_filter_reloading();
+            register_log_filter(&level, builder.reload_handle());
+            with_otel(builder.finish(), tracer.as_ref())
         } else {
-            Box::new(base().compact().finish())
+            let builder = base().compact().with_filter_reloading();
+            register_log_filter(&level, builder.reload_handle());
+            with_otel(builder.finish(), tracer.as_ref())
         };
 
         let _ = tracing::subscriber::set_global_default(subscriber);
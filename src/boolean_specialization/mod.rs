@@ -12,10 +12,11 @@
 //! ```
 
 use crate::ir::{IRNode, Predicate};
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 
 /// Semiring type for query execution
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
 pub enum SemiringType {
     /// Set semantics (presence only)
     #[default]
@@ -227,6 +228,11 @@ impl BooleanSpecializer {
                 predicate,
             },
 
+            IRNode::Sort { input, order_by } => IRNode::Sort {
+                input: Box::new(self.transform_for_semiring(*input, annotation)),
+                order_by,
+            },
+
             IRNode::Join {
                 left,
                 right,
@@ -314,6 +320,20 @@ impl BooleanSpecializer {
                 output_schema,
             },
 
+            IRNode::Semijoin {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                output_schema,
+            } => IRNode::Semijoin {
+                left: Box::new(self.transform_for_semiring(*left, annotation)),
+                right: Box::new(self.transform_for_semiring(*right, annotation)),
+                left_keys,
+                right_keys,
+                output_schema,
+            },
+
             // Scans don't need transformation
             IRNode::Scan { .. } => ir,
 
@@ -553,6 +573,27 @@ impl BooleanSpecializer {
                 }
             }
 
+            IRNode::Semijoin { left, right, .. } => {
+                // Semijoin only filters left; the right side contributes a key set
+                let left_ann = self.analyze_node(left);
+                let right_ann = self.analyze_node(right);
+                SemiringAnnotation {
+                    semiring: left_ann.semiring,
+                    needs_duplicates: left_ann.needs_duplicates,
+                    is_recursive: left_ann.is_recursive || right_ann.is_recursive,
+                    reason: format!("semijoin inherits from left: {:?}", left_ann.semiring),
+                }
+            }
+
+            IRNode::Sort { input, .. } => {
+                // Ordering does not change multiplicities
+                let child = self.analyze_node(input);
+                SemiringAnnotation {
+                    reason: format!("sort inherits from child: {:?}", child.semiring),
+                    ..child
+                }
+            }
+
             IRNode::Compute { input, .. } => {
                 // Compute preserves the semiring of its input
                 let child = self.analyze_node(input);
@@ -700,14 +741,16 @@ impl BooleanSpecializer {
                 self.count_nodes_recursive(left, stats);
                 self.count_nodes_recursive(right, stats);
             }
-            IRNode::Distinct { input } => self.count_nodes_recursive(input, stats),
+            IRNode::Distinct { input } | IRNode::Sort { input, .. } => {
+                self.count_nodes_recursive(input, stats);
+            }
             IRNode::Union { inputs } => {
                 for input in inputs {
                     self.count_nodes_recursive(input, stats);
                 }
             }
             IRNode::Aggregate { input, .. } => self.count_nodes_recursive(input, stats),
-            IRNode::Antijoin { left, right, .. } => {
+            IRNode::Antijoin { left, right, .. } | IRNode::Semijoin { left, right, .. } => {
                 self.count_nodes_recursive(left, stats);
                 self.count_nodes_recursive(right, stats);
             }
@@ -774,7 +817,10 @@ impl BooleanSpecializer {
                 let right_sem = self.analyze_ir_pattern(right);
                 left_sem.meet(&right_sem)
             }
-            IRNode::Compute { input, .. } => self.analyze_ir_pattern(input),
+            IRNode::Semijoin { left, .. } => self.analyze_ir_pattern(left),
+            IRNode::Compute { input, .. } | IRNode::Sort { input, .. } => {
+                self.analyze_ir_pattern(input)
+            }
             IRNode::HnswScan { .. } => SemiringType::Boolean, // Terminal node like Scan
             IRNode::FlatMap { input, .. } => self.analyze_ir_pattern(input),
             IRNode::JoinFlatMap { left, right, .. } => {
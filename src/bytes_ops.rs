@@ -0,0 +1,87 @@
+//! Binary blob operations behind `Value::Bytes` and the byte builtins.
+//!
+//! Hex and base64 codecs for literals and display, plus slicing.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+/// Lowercase hex encoding
+pub fn encode_hex(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut out, b| {
+            let _ = write!(out, "{b:02x}");
+            out
+        })
+}
+
+/// Decode hex text (either case, optional `0x` prefix); `None` if malformed
+pub fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim();
+    let digits = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text);
+    if !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(digits.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Standard (padded) base64 encoding
+pub fn encode_base64(bytes: &[u8]) -> String {
+    STANDARD.encode(bytes)
+}
+
+/// Decode standard base64 text; `None` if malformed
+pub fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    STANDARD.decode(text.trim()).ok()
+}
+
+/// `len` bytes starting at `start`, clamped to the end of the input.
+/// Negative arguments yield `None`.
+pub fn slice(bytes: &[u8], start: i64, len: i64) -> Option<&[u8]> {
+    let start = usize::try_from(start).ok()?.min(bytes.len());
+    let len = usize::try_from(len).ok()?;
+    let end = start.saturating_add(len).min(bytes.len());
+    Some(&bytes[start..end])
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_roundtrip() {
+        let bytes = [0x00, 0xde, 0xad, 0xbe, 0xef];
+        assert_eq!(encode_hex(&bytes), "00deadbeef");
+        assert_eq!(decode_hex("00DEADbeef").unwrap(), bytes);
+        assert_eq!(decode_hex("0x00deadbeef").unwrap(), bytes);
+        assert_eq!(decode_hex("").unwrap(), Vec::<u8>::new());
+        assert_eq!(decode_hex("abc"), None);
+        assert_eq!(decode_hex("zz"), None);
+        // Multi-byte characters must not panic
+        assert_eq!(decode_hex("é"), None);
+    }
+
+    #[test]
+    fn test_base64_roundtrip() {
+        assert_eq!(encode_base64(b"hello"), "aGVsbG8=");
+        assert_eq!(decode_base64("aGVsbG8=").unwrap(), b"hello");
+        assert_eq!(decode_base64("not base64!"), None);
+    }
+
+    #[test]
+    fn test_slice_clamps() {
+        let bytes = [1, 2, 3, 4];
+        assert_eq!(slice(&bytes, 1, 2), Some(&bytes[1..3]));
+        assert_eq!(slice(&bytes, 2, 10), Some(&bytes[2..]));
+        assert_eq!(slice(&bytes, 9, 1), Some(&bytes[4..]));
+        assert_eq!(slice(&bytes, -1, 1), None);
+    }
+}
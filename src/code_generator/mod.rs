@@ -12,34 +12,62 @@
 //!
 //! - Arbitrary arity tuples with multiple data types
 //! - Complex joins with multi-column keys
+//! - Hash joins against small static inputs, merge joins over arrangements
 //! - Generic projections (any column reordering or selection)
 //! - Recursive evaluation via `.iterative()` scopes with `Variable`
+//! - Mutual recursion: one `Variable` per relation of an SCC in a shared scope
+//! - Stratum dataflows: the non-recursive rules of a stratum share input
+//!   streams and join arrangements
 //! - Semi-naive evaluation for efficient fixpoint computation
+//! - Min-plus (tropical) evaluation of shortest-path recursion, distances in the diff
+//! - Multi-worker execution: DD exchanges records by key between timely workers,
+//!   in one process or across the processes of a cluster (`execution::ClusterBackend`)
 
 use crate::boolean_specialization::SemiringType;
-use crate::ir::{AggregateFunction, ArithOp, BuiltinFunction, IRExpression, IRNode, Predicate};
-use crate::semiring_types::{BooleanDiff, DiffType};
+use crate::execution::{CancelHandle, MemoryTracker, QueryProgress, QueryTimeout};
+use crate::ir::{
+    AggregateFunction, ArithOp, BuiltinFunction, IRExpression, IRNode, Predicate, SortDirection,
+};
+use crate::join_planning::{
+    select_join_algorithm, JoinAlgorithm, JoinAlgorithmConfig, JoinInputInfo, JoinSide,
+};
+use crate::semiring_types::{BooleanDiff, DiffType, MinDiff};
 use differential_dataflow::collection::vec::Collection;
 use differential_dataflow::lattice::Lattice;
+use differential_dataflow::operators::arrange::{Arranged, TraceAgent};
 use differential_dataflow::operators::iterate::Variable;
+use differential_dataflow::trace::implementations::ValSpine;
 use parking_lot::Mutex;
+use std::any::Any;
+use std::cell::Cell;
 use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::panic::{catch_unwind, AssertUnwindSafe};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
-use timely::dataflow::operators::vec::{Map, ToStream};
-use timely::dataflow::operators::{Inspect, Probe};
+use timely::communication::allocator::GenericBuilder;
+use timely::communication::Allocate;
+use timely::dataflow::operators::vec::{Filter, Map, ToStream};
+use timely::dataflow::operators::{Exchange, Inspect, Probe};
+use timely::dataflow::scopes::ScopeParent;
 use timely::dataflow::ProbeHandle;
 use timely::dataflow::Scope;
+use timely::execute::execute_from;
 use timely::order::Product;
-use tracing::info;
+use timely::worker::Worker;
+use timely::{CommunicationConfig, WorkerConfig};
+use tracing::{debug, info, trace};
 
 use crate::temporal_ops;
 use crate::value::{Tuple, Value};
 use crate::vector_ops;
 
+mod prefilter;
+
+use prefilter::SideFilter;
+pub use prefilter::{JoinPrefilter, PrefilterMetrics};
+
 // Thread-local cancellation flag for cooperative query timeout.
 // Set by Handler before DD computation, checked in spin loops.
 thread_local! {
@@ -54,6 +82,83 @@ pub fn set_query_cancel_flag(flag: Option<Arc<AtomicBool>>) {
     });
 }
 
+/// Called by the engine between strata with a check for interruption. It may
+/// block while the query's execution slot is lent to a higher-priority query.
+pub type StratumYield = Arc<dyn Fn(&dyn Fn() -> bool) + Send + Sync>;
+
+// Thread-local stratum yield hook, set by Handler only around lock-free
+// snapshot execution: a query that waits here must hold no storage lock.
+thread_local! {
+    static STRATUM_YIELD: RefCell<Option<StratumYield>> = const { RefCell::new(None) };
+}
+
+/// Set the stratum yield hook for the current thread.
+pub fn set_stratum_yield(hook: Option<StratumYield>) {
+    STRATUM_YIELD.with(|cell| {
+        *cell.borrow_mut() = hook;
+    });
+}
+
+/// Run the current thread's stratum yield hook, if any.
+pub(crate) fn yield_between_strata(interrupted: &dyn Fn() -> bool) {
+    let hook = STRATUM_YIELD.with(|cell| cell.borrow().clone());
+    if let Some(hook) = hook {
+        hook(interrupted);
+    }
+}
+
+/// Handle sharing the current thread's cancel flag, so work moved to other
+/// threads observes the caller's cancellation.
+pub(crate) fn current_cancel_handle() -> Option<CancelHandle> {
+    QUERY_CANCEL
+        .with(|cell| cell.borrow().clone())
+        .map(CancelHandle::from_flag)
+}
+
+/// The current thread's cancel flag and wall-clock budget, for operator
+/// closures that must observe interruption without a thread-local lookup per
+/// record.
+fn query_interrupt_token() -> InterruptToken {
+    InterruptToken {
+        cancel: QUERY_CANCEL.with(|cell| cell.borrow().clone()),
+        timeout: QUERY_CLOCK.with(|cell| cell.borrow().as_ref().map(|c| c.timeout.clone())),
+        records: Cell::new(0),
+    }
+}
+
+/// Records an operator processes between reads of the wall clock
+const CLOCK_CHECK_INTERVAL: u32 = 1024;
+
+/// Interruption state captured by an operator closure when its dataflow is
+/// built. A single worker step can spend a long time inside one operator (a
+/// large fixpoint round), so operators check the clock themselves.
+struct InterruptToken {
+    cancel: Option<Arc<AtomicBool>>,
+    timeout: Option<QueryTimeout>,
+    records: Cell<u32>,
+}
+
+impl InterruptToken {
+    fn is_interrupted(&self) -> bool {
+        if self
+            .cancel
+            .as_ref()
+            .is_some_and(|f| f.load(Ordering::Relaxed))
+        {
+            return true;
+        }
+        let Some(timeout) = &self.timeout else {
+            return false;
+        };
+        if timeout.is_cancelled() {
+            return true;
+        }
+        let records = self.records.get().wrapping_add(1);
+        self.records.set(records);
+        records.is_multiple_of(CLOCK_CHECK_INTERVAL) && timeout.check().is_err()
+    }
+}
+
 /// Check if the current query has been cancelled.
 fn is_query_cancelled() -> bool {
     QUERY_CANCEL.with(|cell| {
@@ -73,6 +178,117 @@ fn signal_query_cancel() {
     });
 }
 
+// Memory tracker of the running query, installed on each worker thread while
+// its dataflow is built and stepped, so operators can charge what they
+// materialize without threading the tracker through every generator.
+thread_local! {
+    static QUERY_MEMORY: RefCell<Option<MemoryTracker>> = const { RefCell::new(None) };
+}
+
+// Wall-clock budget of the running query and the worker steps taken so far,
+// installed alongside the memory tracker.
+thread_local! {
+    static QUERY_CLOCK: RefCell<Option<StepClock>> = const { RefCell::new(None) };
+}
+
+// Bloom-filter join pre-filtering settings of the running query.
+thread_local! {
+    static QUERY_PREFILTER: RefCell<Option<JoinPrefilter>> = const { RefCell::new(None) };
+}
+
+// Physical join selection thresholds of the running query. Joins built
+// outside a query scope always merge.
+thread_local! {
+    static QUERY_JOIN_ALGORITHMS: Cell<JoinAlgorithmConfig> = const {
+        Cell::new(JoinAlgorithmConfig {
+            max_hash_build_rows: 0,
+        })
+    };
+}
+
+/// A query's timeout together with its shared dataflow step counter
+#[derive(Clone)]
+struct StepClock {
+    timeout: QueryTimeout,
+    steps: Arc<AtomicU64>,
+}
+
+/// Per-execution state handed to every worker thread of a query
+#[derive(Clone)]
+struct QueryContext {
+    cancel: Option<Arc<AtomicBool>>,
+    memory: Option<MemoryTracker>,
+    clock: Option<StepClock>,
+    prefilter: Option<JoinPrefilter>,
+    join_algorithms: JoinAlgorithmConfig,
+}
+
+/// Installs a query's cancel flag, memory tracker and wall-clock budget on
+/// the current thread until dropped. Worker threads (timely workers, rayon
+/// jobs) do not inherit the caller's thread-locals, so every worker closure
+/// installs one.
+struct QueryScope {
+    previous: QueryContext,
+}
+
+impl QueryScope {
+    fn install(context: QueryContext) -> Self {
+        QueryScope {
+            previous: QueryContext {
+                cancel: QUERY_CANCEL.with(|cell| cell.replace(context.cancel)),
+                memory: QUERY_MEMORY.with(|cell| cell.replace(context.memory)),
+                clock: QUERY_CLOCK.with(|cell| cell.replace(context.clock)),
+                prefilter: QUERY_PREFILTER.with(|cell| cell.replace(context.prefilter)),
+                join_algorithms: QUERY_JOIN_ALGORITHMS
+                    .with(|cell| cell.replace(context.join_algorithms)),
+            },
+        }
+    }
+}
+
+impl Drop for QueryScope {
+    fn drop(&mut self) {
+        let cancel = self.previous.cancel.take();
+        let memory = self.previous.memory.take();
+        let clock = self.previous.clock.take();
+        let prefilter = self.previous.prefilter.take();
+        QUERY_CANCEL.with(|cell| *cell.borrow_mut() = cancel);
+        QUERY_MEMORY.with(|cell| *cell.borrow_mut() = memory);
+        QUERY_CLOCK.with(|cell| *cell.borrow_mut() = clock);
+        QUERY_PREFILTER.with(|cell| *cell.borrow_mut() = prefilter);
+        QUERY_JOIN_ALGORITHMS.with(|cell| cell.set(self.previous.join_algorithms));
+    }
+}
+
+/// Drop every dataflow installed on `worker`.
+///
+/// Leaving the stepping loop alone does not stop a computation: timely keeps
+/// stepping installed dataflows until they complete before a worker returns.
+fn drop_dataflows<A: Allocate>(worker: &mut Worker<A>) {
+    for id in worker.installed_dataflows() {
+        worker.drop_dataflow(id);
+    }
+}
+
+/// Check if the current query must stop: cancelled, over its memory budget,
+/// or out of time. Called once per worker step, which it counts towards the
+/// query's progress.
+fn is_query_interrupted() -> bool {
+    let timed_out = QUERY_CLOCK.with(|cell| {
+        cell.borrow().as_ref().is_some_and(|clock| {
+            clock.steps.fetch_add(1, Ordering::Relaxed);
+            clock.timeout.check().is_err()
+        })
+    });
+    timed_out
+        || is_query_cancelled()
+        || QUERY_MEMORY.with(|cell| {
+            cell.borrow()
+                .as_ref()
+                .is_some_and(MemoryTracker::is_exceeded)
+        })
+}
+
 /// Extract a human-readable message from a panic payload.
 fn format_panic_payload(payload: Box<dyn std::any::Any + Send>) -> String {
     if let Some(s) = payload.downcast_ref::<&str>() {
@@ -126,6 +342,58 @@ impl ExecutionConfig {
     }
 }
 
+/// Timely communication of one multi-worker execution: allocator builders
+/// for this process's workers, and a guard that keeps the network threads
+/// connecting it to other processes alive until dropped
+pub(crate) struct TimelyNetwork {
+    pub(crate) builders: Vec<GenericBuilder>,
+    pub(crate) guard: Box<dyn Any + Send>,
+}
+
+impl TimelyNetwork {
+    /// `num_workers` workers exchanging data within this process
+    fn process(num_workers: usize) -> Result<Self, String> {
+        let (builders, guard) = CommunicationConfig::Process(num_workers).try_build()?;
+        Ok(TimelyNetwork { builders, guard })
+    }
+}
+
+/// Monotonic aggregate evaluated inside a recursive fixpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LoopAggregate {
+    Min,
+    Max,
+    /// Number of distinct values per group
+    Count,
+}
+
+/// Aggregate head of a recursive relation: the first `group_len` columns
+/// form the group, the next one is aggregated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LoopAggregation {
+    function: LoopAggregate,
+    group_len: usize,
+}
+
+/// Recursive rule `rel(group..., min<D>) <- ...` whose distance `D` is the
+/// relation's own distance plus other terms, evaluated in the min-plus semiring
+#[derive(Debug, Clone)]
+struct MinPlusRule {
+    /// Rule body, before the min aggregate
+    body: IRNode,
+    /// Body columns forming the head's group
+    group_by: Vec<usize>,
+    /// Body column holding the derived distance
+    distance_col: usize,
+}
+
+/// A relation arranged by join key: key columns -> full tuple
+type KeyedArrangement<G, R> =
+    Arranged<G, TraceAgent<ValSpine<Tuple, Tuple, <G as ScopeParent>::Timestamp, R>>>;
+
+/// Join arrangements of one dataflow, by relation and key columns
+type SharedArrangements<G, R> = RefCell<HashMap<(String, Vec<usize>), KeyedArrangement<G, R>>>;
+
 /// Executes IR trees using Differential Dataflow.
 pub struct CodeGenerator {
     /// Input data for base relations (Arc-wrapped for cheap cloning into DD closures).
@@ -134,11 +402,26 @@ pub struct CodeGenerator {
     /// Semiring annotations (debug tracing only).
     #[allow(dead_code)]
     semiring_annotations: Vec<crate::boolean_specialization::SemiringAnnotation>,
-    /// Diff type dispatch: Boolean -> BooleanDiff(i8), Counting/Min/Max -> isize.
+    /// Diff type dispatch: Boolean -> BooleanDiff(i8), Counting/Min/Max -> isize,
+    /// except shortest-path recursion under Min, which uses MinDiff.
     semiring_type: SemiringType,
     /// Maximum number of result rows (0 = unlimited).
     /// Prevents OOM from queries returning unbounded result sets.
     max_result_rows: usize,
+    /// Memory accounting of the running query (`None` = untracked).
+    memory_tracker: Option<MemoryTracker>,
+    /// Cancellation token of the running query. Falls back to the calling
+    /// thread's cancel flag when unset.
+    cancel: Option<CancelHandle>,
+    /// Wall-clock budget of the running query (`None` = unlimited).
+    timeout: Option<QueryTimeout>,
+    /// Worker steps taken by this generator's dataflows, reported when the
+    /// query times out.
+    steps: Arc<AtomicU64>,
+    /// Bloom-filter pre-filtering of lopsided joins (`None` = disabled).
+    join_prefilter: Option<JoinPrefilter>,
+    /// Thresholds for choosing hash or merge joins.
+    join_algorithms: JoinAlgorithmConfig,
 }
 
 impl CodeGenerator {
@@ -149,6 +432,12 @@ impl CodeGenerator {
             semiring_annotations: Vec::new(),
             semiring_type: SemiringType::Counting, // safe default
             max_result_rows: 0,                    // unlimited
+            memory_tracker: None,
+            cancel: None,
+            timeout: None,
+            steps: Arc::new(AtomicU64::new(0)),
+            join_prefilter: None,
+            join_algorithms: JoinAlgorithmConfig::default(),
         }
     }
 
@@ -157,8 +446,119 @@ impl CodeGenerator {
         self.max_result_rows = max;
     }
 
+    /// Charge what this generator's dataflows materialize to `tracker`, for
+    /// as long as each dataflow runs; execution fails once its budget is
+    /// exceeded.
+    pub fn set_memory_tracker(&mut self, tracker: Option<MemoryTracker>) {
+        self.memory_tracker = tracker;
+    }
+
+    /// Stop execution once `handle` is cancelled, from any thread.
+    pub fn set_cancel_handle(&mut self, handle: Option<CancelHandle>) {
+        self.cancel = handle;
+    }
+
+    /// Cancel flag handed to worker threads: the explicit handle, else the
+    /// calling thread's flag
+    fn query_cancel_flag(&self) -> Option<Arc<AtomicBool>> {
+        match &self.cancel {
+            Some(handle) => Some(handle.flag()),
+            None => QUERY_CANCEL.with(|cell| cell.borrow().clone()),
+        }
+    }
+
+    /// Stop execution once `timeout` expires. The deadline is checked every
+    /// worker step and inside recursive fixpoints.
+    pub fn set_query_timeout(&mut self, timeout: Option<QueryTimeout>) {
+        self.timeout = timeout;
+    }
+
+    /// Filter the large side of lopsided joins and antijoins with a bloom
+    /// filter over the small side's keys before arranging it.
+    pub fn set_join_prefilter(&mut self, prefilter: Option<JoinPrefilter>) {
+        self.join_prefilter = prefilter;
+    }
+
+    /// Choose between hash and merge joins with `config`.
+    pub fn set_join_algorithms(&mut self, config: JoinAlgorithmConfig) {
+        self.join_algorithms = config;
+    }
+
+    /// State every worker thread of one execution installs. The execution's
+    /// dataflow charges a child of the memory tracker, so its arrangements
+    /// are credited back once the workers drop it.
+    fn query_context(&self) -> QueryContext {
+        QueryContext {
+            cancel: self.query_cancel_flag(),
+            memory: self.memory_tracker.as_ref().map(MemoryTracker::child),
+            clock: self.timeout.clone().map(|timeout| StepClock {
+                timeout,
+                steps: Arc::clone(&self.steps),
+            }),
+            prefilter: self.join_prefilter.clone(),
+            join_algorithms: self.join_algorithms,
+        }
+    }
+
+    /// `Err` with the progress made so far once the query's time is up
+    fn check_timeout(&self, rows: usize) -> Result<(), String> {
+        match &self.timeout {
+            Some(timeout) => timeout.check().map_err(|e| {
+                e.with_progress(QueryProgress {
+                    steps: self.steps.load(Ordering::Relaxed),
+                    rows,
+                })
+                .to_string()
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether the running query has been cancelled
+    fn is_cancelled(&self) -> bool {
+        self.query_cancel_flag()
+            .is_some_and(|f| f.load(Ordering::Relaxed))
+    }
+
+    /// `Err` once the query has exceeded its memory budget
+    fn check_memory_budget(&self) -> Result<(), String> {
+        match &self.memory_tracker {
+            Some(tracker) => tracker.check().map_err(|e| e.to_string()),
+            None => Ok(()),
+        }
+    }
+
+    /// Charge every update of `collection` to the running query's memory
+    /// budget. Applied to the inputs of arrangements, which keep their
+    /// updates for the lifetime of the dataflow.
+    fn track_memory<G, R>(collection: Collection<G, Tuple, R>) -> Collection<G, Tuple, R>
+    where
+        G: Scope,
+        R: DiffType,
+    {
+        match QUERY_MEMORY.with(|cell| cell.borrow().clone()) {
+            Some(tracker) => collection.inspect(move |(tuple, _time, _diff)| {
+                tracker.charge(tuple.estimated_bytes());
+            }),
+            None => collection,
+        }
+    }
+
+    /// Stop feeding `collection` back into its fixpoint once the query is
+    /// interrupted, so the iteration drains within the current round instead
+    /// of running to completion.
+    fn interruptible<G, R>(collection: Collection<G, Tuple, R>) -> Collection<G, Tuple, R>
+    where
+        G: Scope,
+        R: DiffType,
+    {
+        let interrupt = query_interrupt_token();
+        collection.filter(move |_| !interrupt.is_interrupted())
+    }
+
     /// Set the semiring type for diff-type dispatch.
-    /// Boolean -> BooleanDiff(i8), anything else -> isize.
+    /// Boolean -> BooleanDiff(i8), anything else -> isize (Min also enables
+    /// min-plus evaluation of shortest-path recursion).
     pub fn set_semiring_type(&mut self, st: SemiringType) {
         self.semiring_type = st;
     }
@@ -168,13 +568,8 @@ impl CodeGenerator {
         &mut self,
         annotations: Vec<crate::boolean_specialization::SemiringAnnotation>,
     ) {
-        if std::env::var("IL_DEBUG").is_ok() && !annotations.is_empty() {
-            for (i, ann) in annotations.iter().enumerate() {
-                eprintln!(
-                    "DEBUG CodeGen semiring[{}]: {:?} ({})",
-                    i, ann.semiring, ann.reason
-                );
-            }
+        for (rule, ann) in annotations.iter().enumerate() {
+            debug!(rule, semiring = ?ann.semiring, reason = %ann.reason, "codegen_semiring");
         }
         self.semiring_annotations = annotations;
     }
@@ -200,21 +595,52 @@ impl CodeGenerator {
     /// fixpoint iteration. This method always executes a single pass.
     /// Dispatches to `BooleanDiff` or `isize` based on the semiring type.
     pub fn execute(&self, ir: &IRNode) -> Result<Vec<Tuple>, String> {
-        if std::env::var("IL_DEBUG").is_ok() {
-            eprintln!(
-                "DEBUG CodeGen::execute: semiring={:?}, diff_type={}",
-                self.semiring_type,
-                if self.semiring_type == SemiringType::Boolean {
-                    "BooleanDiff(i8)"
-                } else {
-                    "isize"
-                }
-            );
+        debug!(
+            semiring = ?self.semiring_type,
+            diff_type = if self.semiring_type == SemiringType::Boolean {
+                "BooleanDiff(i8)"
+            } else {
+                "isize"
+            },
+            "codegen_execute"
+        );
+        let (plan, order_by) = Self::split_sort(ir);
+        let mut results = match self.semiring_type {
+            SemiringType::Boolean => self.execute_single_pass_typed::<BooleanDiff>(plan),
+            _ => self.execute_single_pass_typed::<isize>(plan),
+        }?;
+        Self::sort_tuples(&mut results, order_by);
+        Ok(results)
+    }
+
+    /// Split a plan into the part evaluated by the dataflow and the order
+    /// its results are returned in (empty unless the plan is rooted at `Sort`)
+    fn split_sort(ir: &IRNode) -> (&IRNode, &[(usize, SortDirection)]) {
+        match ir {
+            IRNode::Sort { input, order_by } => (input, order_by),
+            other => (other, &[]),
         }
-        match self.semiring_type {
-            SemiringType::Boolean => self.execute_single_pass_typed::<BooleanDiff>(ir),
-            _ => self.execute_single_pass_typed::<isize>(ir),
+    }
+
+    /// Order `tuples` by the `order_by` columns, most significant first.
+    /// Ties are broken by the whole tuple, so the order is deterministic.
+    pub(crate) fn sort_tuples(tuples: &mut [Tuple], order_by: &[(usize, SortDirection)]) {
+        if order_by.is_empty() {
+            return;
         }
+        tuples.sort_by(|a, b| {
+            order_by
+                .iter()
+                .map(|&(col, direction)| {
+                    let ordering = a.get(col).cmp(&b.get(col));
+                    match direction {
+                        SortDirection::Asc => ordering,
+                        SortDirection::Desc => ordering.reverse(),
+                    }
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| a.cmp(b))
+        });
     }
 
     /// Alias for execute (for backward compatibility during migration)
@@ -235,8 +661,10 @@ impl CodeGenerator {
 
         // Execute DD computation with panic safety - DD bugs (e.g. merge_batcher
         // out-of-bounds) should produce an error, not crash the server.
+        let query = self.query_context();
         catch_unwind(AssertUnwindSafe(|| {
             timely::execute_directly(move |worker| {
+                let _query = QueryScope::install(query.clone());
                 let probe = ProbeHandle::new();
                 let mut steps: u64 = 0;
                 let start = Instant::now();
@@ -252,7 +680,7 @@ impl CodeGenerator {
                     );
 
                     // distinct_core::<R> gives set semantics while preserving diff type R
-                    collection
+                    Self::track_memory(collection)
                         .distinct_core::<R>()
                         .inner
                         .inspect(move |(data, _time, _diff)| {
@@ -269,7 +697,7 @@ impl CodeGenerator {
 
                 // Wait for computation to complete
                 while !probe.done() {
-                    if is_query_cancelled() {
+                    if is_query_interrupted() {
                         break;
                     }
                     worker.step();
@@ -284,6 +712,9 @@ impl CodeGenerator {
                         last_log = Instant::now();
                     }
                 }
+                if is_query_interrupted() {
+                    drop_dataflows(worker);
+                }
             });
         }))
         .map_err(|e| {
@@ -293,7 +724,10 @@ impl CodeGenerator {
             )
         })?;
 
-        if is_query_cancelled() {
+        self.check_memory_budget()?;
+        self.check_timeout(results.lock().len())?;
+
+        if self.is_cancelled() {
             // If we hit the result limit, the cancel was self-triggered - return results
             let collected = results.lock().len();
             if result_limit == 0 || collected < result_limit {
@@ -333,11 +767,7 @@ impl CodeGenerator {
         let (base_inputs, recursive_inputs) = if let Some((_, base_idx, rec_idx)) =
             Self::detect_recursive_union_for_relation(inputs, Some(recursive_rel))
         {
-            if std::env::var("IL_DEBUG").is_ok() {
-                eprintln!(
-                    "DEBUG: recursive fixpoint: base_indices={base_idx:?}, recursive_indices={rec_idx:?}"
-                );
-            }
+            debug!(base_indices = ?base_idx, recursive_indices = ?rec_idx, "recursive_fixpoint_split");
             let base: Vec<IRNode> = base_idx.iter().map(|&i| inputs[i].clone()).collect();
             let rec: Vec<IRNode> = rec_idx.iter().map(|&i| inputs[i].clone()).collect();
             (base, rec)
@@ -345,11 +775,10 @@ impl CodeGenerator {
             // ALL inputs reference the recursive relation (e.g. edge(X,Y) <- edge(X,Y)
             // plus edge(X,Y) <- edge(X,Z), edge(Z,Y)). Use existing base facts as the
             // implicit base case via a Scan node, and treat all inputs as recursive.
-            if std::env::var("IL_DEBUG").is_ok() {
-                eprintln!(
-                    "DEBUG: all inputs reference '{recursive_rel}' - using base facts as implicit base case"
-                );
-            }
+            debug!(
+                relation = recursive_rel,
+                "recursive_fixpoint_implicit_base: all inputs reference the relation"
+            );
             let base = vec![IRNode::Scan {
                 relation: recursive_rel.to_string(),
                 schema: Vec::new(),
@@ -363,11 +792,7 @@ impl CodeGenerator {
         if let Some(edge_relation) =
             Self::detect_transitive_closure_pattern(&base_inputs, &recursive_inputs, recursive_rel)
         {
-            if std::env::var("IL_DEBUG").is_ok() {
-                eprintln!(
-                    "DEBUG: detected transitive closure pattern with edge relation '{edge_relation}'"
-                );
-            }
+            debug!(edge = %edge_relation, "transitive_closure_detected");
             return self.execute_transitive_closure_optimized(&edge_relation, recursive_rel);
         }
 
@@ -375,14 +800,12 @@ impl CodeGenerator {
         if let Some((edge_rel, seeds, bound_col)) =
             self.detect_bound_tc_pattern(&base_inputs, &recursive_inputs, recursive_rel)
         {
-            if std::env::var("IL_DEBUG").is_ok() {
-                eprintln!(
-                    "DEBUG: detected bound TC pattern: edge='{}', seeds={}, bound_col={}",
-                    edge_rel,
-                    seeds.len(),
-                    bound_col
-                );
-            }
+            debug!(
+                edge = %edge_rel,
+                seeds = seeds.len(),
+                bound_col,
+                "bound_transitive_closure_detected"
+            );
             return self.execute_bound_transitive_closure_optimized(
                 &edge_rel,
                 recursive_rel,
@@ -391,6 +814,19 @@ impl CodeGenerator {
             );
         }
 
+        // Shortest-path shaped relations carry their distance in a MinDiff
+        if self.semiring_type == SemiringType::Min {
+            if let Some(rules) = Self::detect_min_plus_pattern(&recursive_inputs, recursive_rel) {
+                if let Some(results) = self.execute_min_plus(&base_inputs, rules, recursive_rel)? {
+                    return Ok(results);
+                }
+                debug!(
+                    relation = recursive_rel,
+                    "min_plus_fallback: non-integer distance"
+                );
+            }
+        }
+
         // For complex patterns, use the general DD iterative approach.
         // For Min/Max semiring, we still use isize as the DD diff type but apply
         // early min/max aggregation inside the fixpoint loop to prune non-optimal
@@ -717,8 +1153,10 @@ impl CodeGenerator {
         let edge_data = edges.clone();
 
         // Execute DD computation with TRUE recursion using .iterative()
+        let query = self.query_context();
         catch_unwind(AssertUnwindSafe(|| {
             timely::execute_directly(move |worker| {
+                let _query = QueryScope::install(query.clone());
                 let probe = ProbeHandle::new();
 
                 worker.dataflow::<(), _, _>(|scope| {
@@ -768,10 +1206,11 @@ impl CodeGenerator {
                         });
 
                         // Combine base case and recursive case
-                        let next = base_case.concat(recursive).distinct_core::<R>();
+                        let next =
+                            Self::track_memory(base_case.concat(recursive)).distinct_core::<R>();
 
                         // Set variable for next iteration
-                        variable.set(next.clone());
+                        variable.set(Self::interruptible(next.clone()));
 
                         // Leave scope with final result
                         next.leave()
@@ -794,12 +1233,15 @@ impl CodeGenerator {
 
                 // Wait for computation to complete
                 while !probe.done() {
-                    if is_query_cancelled() {
+                    if is_query_interrupted() {
                         break;
                     }
                     worker.step();
                     std::thread::yield_now();
                 }
+                if is_query_interrupted() {
+                    drop_dataflows(worker);
+                }
             });
         }))
         .map_err(|e| {
@@ -809,7 +1251,10 @@ impl CodeGenerator {
             )
         })?;
 
-        if is_query_cancelled() {
+        self.check_memory_budget()?;
+        self.check_timeout(results.lock().len())?;
+
+        if self.is_cancelled() {
             // If we hit the result limit, the cancel was self-triggered - return results
             let collected = results.lock().len();
             if result_limit == 0 || collected < result_limit {
@@ -892,8 +1337,10 @@ impl CodeGenerator {
         let all_edge_data = all_edges;
         let seed_edge_data = seed_edges;
 
+        let query = self.query_context();
         catch_unwind(AssertUnwindSafe(|| {
             timely::execute_directly(move |worker| {
+                let _query = QueryScope::install(query.clone());
                 let probe = ProbeHandle::new();
 
                 worker.dataflow::<(), _, _>(|scope| {
@@ -941,9 +1388,10 @@ impl CodeGenerator {
                             let y = tuple.get(1).cloned().unwrap_or(Value::Null);
                             Tuple::new(vec![x, y])
                         });
-                        let next = base_case.concat(recursive).distinct_core::<R>();
+                        let next =
+                            Self::track_memory(base_case.concat(recursive)).distinct_core::<R>();
 
-                        variable.set(next.clone());
+                        variable.set(Self::interruptible(next.clone()));
                         next.leave()
                     });
 
@@ -962,12 +1410,15 @@ impl CodeGenerator {
                 });
 
                 while !probe.done() {
-                    if is_query_cancelled() {
+                    if is_query_interrupted() {
                         break;
                     }
                     worker.step();
                     std::thread::yield_now();
                 }
+                if is_query_interrupted() {
+                    drop_dataflows(worker);
+                }
             });
         }))
         .map_err(|e| {
@@ -977,7 +1428,10 @@ impl CodeGenerator {
             )
         })?;
 
-        if is_query_cancelled() {
+        self.check_memory_budget()?;
+        self.check_timeout(results.lock().len())?;
+
+        if self.is_cancelled() {
             let collected = results.lock().len();
             if result_limit == 0 || collected < result_limit {
                 return Err("Query cancelled due to timeout".to_string());
@@ -991,117 +1445,564 @@ impl CodeGenerator {
         Ok(final_results)
     }
 
-    /// General recursive execution using DD's `.iterative()` scope
+    /// Rewrite a recursive relation's rules for aggregation inside the fixpoint.
     ///
-    /// Uses DD's native semi-naive evaluation via `Variable` for proper
-    /// incremental fixpoint computation. This handles arbitrary recursive patterns
-    /// (not just transitive closure) by routing the recursive relation through a
-    /// live collection in the iterative scope.
-    /// Extract min/max aggregation info from a single recursive input IR node.
-    /// Returns (group_by_indices, agg_col_index, is_min) if the top-level node
-    /// is an Aggregate with a single Min or Max function.
-    fn extract_minmax_aggregation(ir: &IRNode) -> Option<(Vec<usize>, usize, bool)> {
-        match ir {
-            IRNode::Aggregate {
+    /// A rule with an aggregate head (`dist(Y, min<D>) <- ...`) becomes a rule
+    /// emitting its `(group..., value)` contributions; the loop then keeps one
+    /// aggregated row per group (see `reduce_loop_aggregate`). Only monotonic
+    /// aggregates are accepted: `min` and `max` (a group's value only moves
+    /// one way as contributions arrive) and `count` (of distinct values, which
+    /// only grows). `sum`, `avg` and the ranking aggregates are rejected, since
+    /// their fixpoint is not well defined.
+    ///
+    /// Returns `None` as the aggregation when no rule aggregates.
+    fn plan_loop_aggregation(
+        relation: &str,
+        rules: &[IRNode],
+    ) -> Result<(Option<LoopAggregation>, Vec<IRNode>), String> {
+        let mut aggregation: Option<LoopAggregation> = None;
+        let mut plain_rules = 0;
+        let mut rewritten = Vec::with_capacity(rules.len());
+
+        for rule in rules {
+            let IRNode::Aggregate {
+                input,
                 group_by,
                 aggregations,
-                ..
-            } => {
-                if aggregations.len() != 1 {
-                    return None;
-                }
-                match &aggregations[0] {
-                    (AggregateFunction::Min, col) => Some((group_by.clone(), *col, true)),
-                    (AggregateFunction::Max, col) => Some((group_by.clone(), *col, false)),
-                    _ => None,
+                output_schema,
+            } = rule
+            else {
+                plain_rules += 1;
+                rewritten.push(rule.clone());
+                continue;
+            };
+
+            let [(function, col)] = aggregations.as_slice() else {
+                return Err(format!(
+                    "Recursive rule '{relation}' can aggregate only one column"
+                ));
+            };
+            let function = match function {
+                AggregateFunction::Min => LoopAggregate::Min,
+                AggregateFunction::Max => LoopAggregate::Max,
+                AggregateFunction::Count | AggregateFunction::CountDistinct => LoopAggregate::Count,
+                other => {
+                    return Err(format!(
+                        "Aggregate {other:?} in recursive rule '{relation}' is not monotonic; \
+                         only min, max and count can be used in recursion"
+                    ));
                 }
+            };
+            let this = LoopAggregation {
+                function,
+                group_len: group_by.len(),
+            };
+            if aggregation.is_some_and(|existing| existing != this) {
+                return Err(format!(
+                    "Rules for recursive relation '{relation}' use different aggregates"
+                ));
             }
-            _ => None,
+            aggregation = Some(this);
+
+            let mut projection = group_by.clone();
+            projection.push(*col);
+            rewritten.push(IRNode::Map {
+                input: input.clone(),
+                projection,
+                output_schema: output_schema.clone(),
+            });
         }
-    }
 
-    /// Strip the top-level Aggregate node from an IR, returning the inner input.
-    /// Used when we want to apply aggregation at a different point (e.g., in the
-    /// fixpoint loop instead of inside the recursive body).
-    fn strip_top_aggregate(ir: &IRNode) -> &IRNode {
-        match ir {
-            IRNode::Aggregate { input, .. } => input,
-            _ => ir,
+        // A rule without an aggregate contributes candidate values to min/max,
+        // but has no meaningful reading as a contribution to a count
+        if aggregation.is_some_and(|agg| agg.function == LoopAggregate::Count) && plain_rules > 0 {
+            return Err(format!(
+                "count<> in recursive relation '{relation}' must appear in every rule"
+            ));
         }
+
+        Ok((aggregation, rewritten))
     }
 
-    fn execute_recursive_dd_iterative_typed<R: DiffType>(
-        &self,
-        base_inputs: &[IRNode],
+    /// Keep one row per group of `(group..., value)` contributions: the
+    /// minimum or maximum value, or the number of distinct values.
+    fn reduce_loop_aggregate<G, R: DiffType>(
+        contributions: Collection<G, Tuple, R>,
+        aggregation: LoopAggregation,
+    ) -> Collection<G, Tuple, R>
+    where
+        G: Scope,
+        G::Timestamp: Lattice + Ord,
+    {
+        let LoopAggregation {
+            function,
+            group_len,
+        } = aggregation;
+        contributions
+            .map(move |tuple| {
+                let key = Tuple::new(tuple.values()[..group_len].to_vec());
+                (key, tuple)
+            })
+            .reduce(move |key, input, output| {
+                let values = input.iter().filter_map(|(tuple, _)| tuple.get(group_len));
+                let value = match function {
+                    LoopAggregate::Min => values.min().cloned(),
+                    LoopAggregate::Max => values.max().cloned(),
+                    LoopAggregate::Count => Some(Value::Int64(input.len() as i64)),
+                };
+                if let Some(value) = value {
+                    let mut row = key.values().to_vec();
+                    row.push(value);
+                    output.push((Tuple::new(row), R::one()));
+                }
+            })
+            .map(|(_key, tuple)| tuple)
+    }
+
+    /// Detect a shortest-path shaped recursive relation for min-plus evaluation.
+    ///
+    /// Every recursive rule must aggregate with a single `min<>` whose column
+    /// is the relation's own (last) distance column plus or minus other terms,
+    /// with that distance used nowhere else: not in join keys, filters or other
+    /// expressions. Under that condition the distance can travel in the diff
+    /// (`MinDiff`, where joins add and consolidation takes the minimum) instead
+    /// of in the tuple. Bodies are limited to scans, projections, filters,
+    /// joins and computed columns, which behave correctly over `MinDiff`.
+    fn detect_min_plus_pattern(
         recursive_inputs: &[IRNode],
         recursive_rel: &str,
-    ) -> Result<Vec<Tuple>, String> {
-        // Check if we can use aggregation-in-loop optimization for min/max.
-        // If ALL recursive inputs have a top-level min or max aggregate with the
-        // same group_by and agg_col, we strip the aggregate from the recursive body
-        // and apply it to the combined (base + recursive) result in the loop.
-        // This prunes non-optimal paths early, reducing intermediate data.
-        let agg_in_loop = if recursive_inputs.len() == 1 {
-            Self::extract_minmax_aggregation(&recursive_inputs[0])
-        } else {
-            // For multiple recursive inputs, check if ALL have the same aggregation
-            let first = Self::extract_minmax_aggregation(&recursive_inputs[0]);
-            if let Some(ref first_agg) = first {
-                let all_same = recursive_inputs[1..].iter().all(|ri| {
-                    Self::extract_minmax_aggregation(ri).is_some_and(|a| a == *first_agg)
-                });
-                if all_same {
-                    first
-                } else {
-                    None
+    ) -> Option<Vec<MinPlusRule>> {
+        if recursive_inputs.is_empty() {
+            return None;
+        }
+        let mut group_len = None;
+        recursive_inputs
+            .iter()
+            .map(|rule| {
+                let IRNode::Aggregate {
+                    input,
+                    group_by,
+                    aggregations,
+                    output_schema,
+                } = rule
+                else {
+                    return None;
+                };
+                let [(AggregateFunction::Min, distance_col)] = aggregations.as_slice() else {
+                    return None;
+                };
+                if output_schema.len() != group_by.len() + 1
+                    || *group_len.get_or_insert(group_by.len()) != group_by.len()
+                {
+                    return None;
                 }
-            } else {
-                None
-            }
-        };
 
-        // Build the recursive IR, optionally stripping the aggregate
-        let effective_recursive_inputs: Vec<IRNode> = if agg_in_loop.is_some() {
-            recursive_inputs
+                let offsets = Self::min_plus_offsets(input, recursive_rel, group_by.len())?;
+                let carries_distance = offsets.get(*distance_col).copied().unwrap_or(false);
+                let group_is_plain = group_by
+                    .iter()
+                    .all(|&col| !offsets.get(col).copied().unwrap_or(true));
+                (carries_distance && group_is_plain).then(|| MinPlusRule {
+                    body: (**input).clone(),
+                    group_by: group_by.clone(),
+                    distance_col: *distance_col,
+                })
+            })
+            .collect()
+    }
+
+    /// For each output column of `ir`, whether it holds the recursive
+    /// relation's distance plus an offset. `None` when the distance is used
+    /// in a way min-plus evaluation cannot express.
+    fn min_plus_offsets(
+        ir: &IRNode,
+        recursive_rel: &str,
+        distance_col: usize,
+    ) -> Option<Vec<bool>> {
+        let project = |offsets: &[bool], projection: &[usize]| -> Option<Vec<bool>> {
+            projection
                 .iter()
-                .map(|ri| Self::strip_top_aggregate(ri).clone())
+                .map(|&i| offsets.get(i).copied())
                 .collect()
-        } else {
-            recursive_inputs.to_vec()
         };
-
-        let base_ir = if base_inputs.len() == 1 {
-            base_inputs[0].clone()
-        } else {
-            IRNode::Union {
-                inputs: base_inputs.to_vec(),
-            }
+        let reads_offset = |offsets: &[bool], predicate: &Predicate| {
+            predicate
+                .referenced_columns()
+                .iter()
+                .any(|&c| offsets.get(c).copied().unwrap_or(true))
         };
-        let recursive_ir = if effective_recursive_inputs.len() == 1 {
-            effective_recursive_inputs[0].clone()
-        } else {
-            IRNode::Union {
-                inputs: effective_recursive_inputs,
+        let join = |left: &IRNode, right: &IRNode, left_keys: &[usize], right_keys: &[usize]| {
+            let left = Self::min_plus_offsets(left, recursive_rel, distance_col)?;
+            let right = Self::min_plus_offsets(right, recursive_rel, distance_col)?;
+            // Two distances in one tuple would both land in the diff
+            let keyed_offset = left_keys
+                .iter()
+                .any(|&k| left.get(k).copied().unwrap_or(true))
+                || right_keys
+                    .iter()
+                    .any(|&k| right.get(k).copied().unwrap_or(true));
+            if keyed_offset || (left.contains(&true) && right.contains(&true)) {
+                return None;
             }
+            Some((left, right))
         };
 
-        let results = Arc::new(Mutex::new(Vec::new()));
-        let results_clone = Arc::clone(&results);
-        let input_data = self.input_tuples.clone();
-        let rec_rel = recursive_rel.to_string();
-        let result_limit = self.max_result_rows;
-
-        if std::env::var("IL_DEBUG").is_ok() {
-            if let Some(ref agg) = agg_in_loop {
-                eprintln!(
-                    "DEBUG: recursive min/max aggregation-in-loop: group_by={:?}, agg_col={}, is_min={}",
-                    agg.0, agg.1, agg.2
-                );
-            }
-        }
-
-        catch_unwind(AssertUnwindSafe(|| {
-            timely::execute_directly(move |worker| {
+        match ir {
+            IRNode::Scan { relation, schema } if relation == recursive_rel => (distance_col
+                < schema.len())
+            .then(|| (0..schema.len()).map(|i| i == distance_col).collect()),
+            IRNode::Scan { schema, .. } => Some(vec![false; schema.len()]),
+            IRNode::Map {
+                input, projection, ..
+            } => project(
+                &Self::min_plus_offsets(input, recursive_rel, distance_col)?,
+                projection,
+            ),
+            IRNode::Filter { input, predicate } => {
+                let offsets = Self::min_plus_offsets(input, recursive_rel, distance_col)?;
+                (!reads_offset(&offsets, predicate)).then_some(offsets)
+            }
+            IRNode::FlatMap {
+                input,
+                projection,
+                filter_predicate,
+                ..
+            } => {
+                let offsets = project(
+                    &Self::min_plus_offsets(input, recursive_rel, distance_col)?,
+                    projection,
+                )?;
+                match filter_predicate {
+                    Some(predicate) if reads_offset(&offsets, predicate) => None,
+                    _ => Some(offsets),
+                }
+            }
+            IRNode::Join {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                ..
+            } => {
+                let (mut offsets, right) = join(left, right, left_keys, right_keys)?;
+                if left_keys.is_empty() {
+                    offsets.extend(right);
+                } else {
+                    offsets.extend(
+                        right
+                            .iter()
+                            .enumerate()
+                            .filter(|(i, _)| !right_keys.contains(i))
+                            .map(|(_, &offset)| offset),
+                    );
+                }
+                Some(offsets)
+            }
+            IRNode::JoinFlatMap {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                projection,
+                filter_predicate,
+                ..
+            } => {
+                let (mut offsets, right) = join(left, right, left_keys, right_keys)?;
+                offsets.extend(right);
+                let offsets = project(&offsets, projection)?;
+                match filter_predicate {
+                    Some(predicate) if reads_offset(&offsets, predicate) => None,
+                    _ => Some(offsets),
+                }
+            }
+            IRNode::Compute { input, expressions } => {
+                let mut offsets = Self::min_plus_offsets(input, recursive_rel, distance_col)?;
+                let computed = expressions
+                    .iter()
+                    .map(|(_, expr)| Self::min_plus_expression_offset(expr, &offsets))
+                    .collect::<Option<Vec<bool>>>()?;
+                offsets.extend(computed);
+                Some(offsets)
+            }
+            // Distinct, aggregates and negation rely on counting diffs, so
+            // they cannot run over MinDiff collections
+            _ => None,
+        }
+    }
+
+    /// Whether a computed expression holds the distance plus an offset:
+    /// `distance + x`, `x + distance` or `distance - x`. `None` when the
+    /// distance is used any other way.
+    fn min_plus_expression_offset(expr: &IRExpression, offsets: &[bool]) -> Option<bool> {
+        match expr {
+            IRExpression::Column(col) => offsets.get(*col).copied(),
+            IRExpression::FunctionCall(_, args) => {
+                for arg in args {
+                    if Self::min_plus_expression_offset(arg, offsets)? {
+                        return None;
+                    }
+                }
+                Some(false)
+            }
+            IRExpression::Arithmetic { op, left, right } => {
+                let left = Self::min_plus_expression_offset(left, offsets)?;
+                let right = Self::min_plus_expression_offset(right, offsets)?;
+                match (op, left, right) {
+                    (_, false, false) => Some(false),
+                    (ArithOp::Add | ArithOp::Sub, true, false) | (ArithOp::Add, false, true) => {
+                        Some(true)
+                    }
+                    _ => None,
+                }
+            }
+            IRExpression::IntConstant(_)
+            | IRExpression::FloatConstant(_)
+            | IRExpression::StringConstant(_)
+            | IRExpression::BoolConstant(_)
+            | IRExpression::VectorLiteral(_) => Some(false),
+        }
+    }
+
+    /// Shortest paths in the min-plus (tropical) semiring.
+    ///
+    /// Tuples hold only the group; the distance travels as a `MinDiff`, so a
+    /// join adds distances and consolidation keeps the shortest. Inside the
+    /// loop the relation is read with a zero distance column, which makes the
+    /// body compute the offset it adds; that offset is then folded into the
+    /// diff. Only improvements are fed back, so each group's distance is
+    /// propagated once per improvement rather than once per path.
+    ///
+    /// Returns `Ok(None)` when a distance turns out not to be an integer, in
+    /// which case the caller falls back to tuple-based evaluation.
+    fn execute_min_plus(
+        &self,
+        base_inputs: &[IRNode],
+        rules: Vec<MinPlusRule>,
+        recursive_rel: &str,
+    ) -> Result<Option<Vec<Tuple>>, String> {
+        let base_ir = if base_inputs.len() == 1 {
+            base_inputs[0].clone()
+        } else {
+            IRNode::Union {
+                inputs: base_inputs.to_vec(),
+            }
+        };
+        let group_len = rules.first().map_or(0, |rule| rule.group_by.len());
+
+        let mut base = Vec::new();
+        for tuple in self.execute_single_pass_typed::<isize>(&base_ir)? {
+            let Some(distance) = tuple.get(group_len).and_then(Value::as_i64) else {
+                return Ok(None);
+            };
+            base.push((Tuple::new(tuple.values()[..group_len].to_vec()), distance));
+        }
+
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let results_clone = Arc::clone(&results);
+        let non_integer = Arc::new(AtomicBool::new(false));
+        let non_integer_clone = Arc::clone(&non_integer);
+        let input_data = self.input_tuples.clone();
+        let rec_rel = recursive_rel.to_string();
+        let result_limit = self.max_result_rows;
+
+        debug!(
+            relation = recursive_rel,
+            recursive_rules = rules.len(),
+            "min_plus_evaluation"
+        );
+
+        let query = self.query_context();
+        catch_unwind(AssertUnwindSafe(|| {
+            timely::execute_directly(move |worker| {
+                let _query = QueryScope::install(query.clone());
+                let probe = ProbeHandle::new();
+
+                worker.dataflow::<(), _, _>(|scope| {
+                    let base: Collection<_, Tuple, MinDiff> = Collection::new(
+                        base.to_stream(scope)
+                            .map(|(group, distance)| (group, (), MinDiff(distance))),
+                    );
+
+                    let result = scope.iterative::<Iter, _, _>(|inner| {
+                        // `MinDiff` has no real inverse, so the variable is never
+                        // fed a retraction: it carries only strict improvements,
+                        // and min-consolidation of a group's updates supersedes
+                        // every distance it has already improved on
+                        let (variable, distances): (_, Collection<_, Tuple, MinDiff>) =
+                            Variable::new(inner, Product::new((), 1));
+
+                        let bodies: Vec<&IRNode> = rules.iter().map(|rule| &rule.body).collect();
+                        let mut live = Self::scoped_inputs::<_, MinDiff>(
+                            inner,
+                            &bodies,
+                            &[rec_rel.as_str()],
+                            &input_data,
+                        );
+                        live.insert(
+                            rec_rel.clone(),
+                            distances.map(|group| {
+                                let mut values = group.values().to_vec();
+                                values.push(Value::Int64(0));
+                                Tuple::new(values)
+                            }),
+                        );
+
+                        let mut candidates = base.enter(inner);
+                        for rule in &rules {
+                            let derived = Self::generate_collection_tuples::<_, MinDiff>(
+                                inner,
+                                &rule.body,
+                                &input_data,
+                                Some(&live),
+                            );
+                            let group_by = rule.group_by.clone();
+                            let distance_col = rule.distance_col;
+                            let non_integer = Arc::clone(&non_integer_clone);
+                            candidates = candidates.concat(Collection::new(
+                                derived.inner.flat_map(move |(tuple, time, diff)| {
+                                    let Some(offset) =
+                                        tuple.get(distance_col).and_then(Value::as_i64)
+                                    else {
+                                        non_integer.store(true, Ordering::Relaxed);
+                                        return None;
+                                    };
+                                    let group = tuple.from_indices(&group_by);
+                                    Some((group, time, MinDiff(diff.0.saturating_add(offset))))
+                                }),
+                            ));
+                        }
+
+                        // Feed back only candidates that improve on the best
+                        // distance seen so far for their group
+                        let mut best: HashMap<Tuple, i64> = HashMap::new();
+                        let improved = Collection::new(candidates.inner.filter(
+                            move |(group, _time, diff): &(Tuple, _, MinDiff)| {
+                                match best.get_mut(group) {
+                                    Some(known) if *known <= diff.0 => false,
+                                    Some(known) => {
+                                        *known = diff.0;
+                                        true
+                                    }
+                                    None => {
+                                        best.insert(group.clone(), diff.0);
+                                        true
+                                    }
+                                }
+                            },
+                        ));
+
+                        variable.set(Self::interruptible(improved.clone()));
+                        improved.leave()
+                    });
+
+                    result
+                        .consolidate()
+                        .inner
+                        .inspect(move |(group, _time, diff)| {
+                            let mut guard = results_clone.lock();
+                            if result_limit == 0 || guard.len() < result_limit {
+                                let mut values = group.values().to_vec();
+                                values.push(Value::Int64(diff.0));
+                                guard.push(Tuple::new(values));
+                                if result_limit > 0 && guard.len() >= result_limit {
+                                    signal_query_cancel();
+                                }
+                            }
+                        })
+                        .probe_with(&probe);
+                });
+
+                while !probe.done() {
+                    if is_query_interrupted() {
+                        break;
+                    }
+                    worker.step();
+                    std::thread::yield_now();
+                }
+                if is_query_interrupted() {
+                    drop_dataflows(worker);
+                }
+            });
+        }))
+        .map_err(|e| {
+            format!(
+                "Internal error in query execution: {}",
+                format_panic_payload(e)
+            )
+        })?;
+
+        self.check_memory_budget()?;
+        self.check_timeout(results.lock().len())?;
+
+        if self.is_cancelled() {
+            let collected = results.lock().len();
+            if result_limit == 0 || collected < result_limit {
+                return Err("Query cancelled due to timeout".to_string());
+            }
+        }
+        if non_integer.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+
+        let final_results = Arc::try_unwrap(results)
+            .map_err(|_| "Failed to extract results")?
+            .into_inner();
+
+        Ok(Some(final_results))
+    }
+
+    /// General recursive execution using DD's `.iterative()` scope
+    ///
+    /// Uses DD's native semi-naive evaluation via `Variable` for proper
+    /// incremental fixpoint computation. This handles arbitrary recursive patterns
+    /// (not just transitive closure) by routing the recursive relation through a
+    /// live collection in the iterative scope: any join shape, extra atoms,
+    /// filters and computed columns in the recursive body are generated once
+    /// inside the scope rather than re-run per iteration.
+    fn execute_recursive_dd_iterative_typed<R: DiffType>(
+        &self,
+        base_inputs: &[IRNode],
+        recursive_inputs: &[IRNode],
+        recursive_rel: &str,
+    ) -> Result<Vec<Tuple>, String> {
+        // Aggregate heads are evaluated inside the loop: rules emit
+        // contributions and each iteration keeps one row per group, which
+        // also prunes non-optimal paths early.
+        let mut rules = base_inputs.to_vec();
+        rules.extend_from_slice(recursive_inputs);
+        let (aggregation, mut rules) = Self::plan_loop_aggregation(recursive_rel, &rules)?;
+        let effective_recursive_inputs = rules.split_off(base_inputs.len());
+        let base_inputs = rules;
+
+        let base_ir = if base_inputs.len() == 1 {
+            base_inputs[0].clone()
+        } else {
+            IRNode::Union {
+                inputs: base_inputs.clone(),
+            }
+        };
+        let recursive_ir = if effective_recursive_inputs.len() == 1 {
+            effective_recursive_inputs[0].clone()
+        } else {
+            IRNode::Union {
+                inputs: effective_recursive_inputs,
+            }
+        };
+
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let results_clone = Arc::clone(&results);
+        let input_data = self.input_tuples.clone();
+        let rec_rel = recursive_rel.to_string();
+        let result_limit = self.max_result_rows;
+
+        if let Some(agg) = aggregation {
+            debug!(relation = recursive_rel, aggregation = ?agg, "recursive_aggregation_in_loop");
+        }
+
+        let query = self.query_context();
+        catch_unwind(AssertUnwindSafe(|| {
+            timely::execute_directly(move |worker| {
+                let _query = QueryScope::install(query.clone());
                 let probe = ProbeHandle::new();
 
                 worker.dataflow::<(), _, _>(|scope| {
@@ -1119,19 +2020,15 @@ impl CodeGenerator {
                         let (variable, var_collection) = Variable::new(inner, Product::new((), 1));
 
                         // Build live collections map:
-                        // - All base relations entered into the iterative scope
+                        // - Base relations read by the recursive body, streamed
+                        //   into the iterative scope once each
                         // - The recursive relation backed by the Variable
-                        let mut live: HashMap<String, Collection<_, Tuple, R>> = HashMap::new();
-                        for (name, tuples) in input_data.iter() {
-                            let coll: Collection<_, Tuple, R> = Collection::new(
-                                tuples
-                                    .clone()
-                                    .to_stream(inner)
-                                    .map(|x| (x, Product::default(), R::one())),
-                            );
-                            live.insert(name.clone(), coll);
-                        }
-                        // Override the recursive relation with the Variable's collection
+                        let mut live = Self::scoped_inputs::<_, R>(
+                            inner,
+                            &[&recursive_ir],
+                            &[rec_rel.as_str()],
+                            &input_data,
+                        );
                         live.insert(rec_rel.clone(), var_collection);
 
                         // Generate recursive body using live collections
@@ -1151,52 +2048,28 @@ impl CodeGenerator {
                         // Combine base + recursive results
                         let combined = base_in_scope.concat(recursive_result);
 
-                        // Apply deduplication strategy based on aggregation mode
-                        let next = if let Some((ref group_by, agg_col, is_min)) = agg_in_loop {
-                            // Min/Max aggregation-in-loop: instead of distinct(), apply
-                            // reduce() with min/max logic. This prunes non-optimal paths
-                            // at each iteration, reducing intermediate data volume.
-                            let group_by = group_by.clone();
-                            combined
-                                .map(move |tuple| {
-                                    let key: Vec<Value> = group_by
-                                        .iter()
-                                        .map(|&i| tuple.get(i).cloned().unwrap_or(Value::Null))
-                                        .collect();
-                                    (Tuple::new(key), tuple)
-                                })
-                                .reduce(move |_key, input, output| {
-                                    // Find the tuple with min/max value at agg_col
-                                    let best = if is_min {
-                                        input.iter().min_by(|(a, _), (b, _)| {
-                                            let va = a.get(agg_col).cloned().unwrap_or(Value::Null);
-                                            let vb = b.get(agg_col).cloned().unwrap_or(Value::Null);
-                                            va.cmp(&vb)
-                                        })
-                                    } else {
-                                        input.iter().max_by(|(a, _), (b, _)| {
-                                            let va = a.get(agg_col).cloned().unwrap_or(Value::Null);
-                                            let vb = b.get(agg_col).cloned().unwrap_or(Value::Null);
-                                            va.cmp(&vb)
-                                        })
-                                    };
-                                    if let Some((tuple, _count)) = best {
-                                        output.push(((*tuple).clone(), R::one()));
-                                    }
-                                })
-                                .map(|(_key, tuple)| tuple)
-                        } else {
-                            // Standard deduplication with distinct
-                            combined.distinct_core::<R>()
+                        // Aggregate heads keep one row per group; others
+                        // need set semantics
+                        let next = match aggregation {
+                            Some(agg) => Self::reduce_loop_aggregate(combined, agg),
+                            None => Self::track_memory(combined).distinct_core::<R>(),
                         };
 
                         // Set variable for next iteration
-                        variable.set(next.clone());
+                        variable.set(Self::interruptible(next.clone()));
 
                         // Leave scope with final result
                         next.leave()
                     });
 
+                    // A group's aggregate may be replaced across iterations;
+                    // consolidate so superseded rows cancel out
+                    let result = if aggregation.is_some() {
+                        result.consolidate()
+                    } else {
+                        result
+                    };
+
                     // Capture results
                     result
                         .inner
@@ -1214,12 +2087,15 @@ impl CodeGenerator {
 
                 // Wait for computation to complete
                 while !probe.done() {
-                    if is_query_cancelled() {
+                    if is_query_interrupted() {
                         break;
                     }
                     worker.step();
                     std::thread::yield_now();
                 }
+                if is_query_interrupted() {
+                    drop_dataflows(worker);
+                }
             });
         }))
         .map_err(|e| {
@@ -1229,7 +2105,10 @@ impl CodeGenerator {
             )
         })?;
 
-        if is_query_cancelled() {
+        self.check_memory_budget()?;
+        self.check_timeout(results.lock().len())?;
+
+        if self.is_cancelled() {
             // If we hit the result limit, the cancel was self-triggered - return results
             let collected = results.lock().len();
             if result_limit == 0 || collected < result_limit {
@@ -1253,84 +2132,480 @@ impl CodeGenerator {
         ir: &IRNode,
         recursive_rel: &str,
     ) -> Result<Vec<Tuple>, String> {
-        self.execute_recursive_fixpoint_tuples(ir, recursive_rel)
+        let (plan, order_by) = Self::split_sort(ir);
+        let mut results = self.execute_recursive_fixpoint_tuples(plan, recursive_rel)?;
+        Self::sort_tuples(&mut results, order_by);
+        Ok(results)
+    }
+
+    /// Execute a group of mutually recursive relations to a joint fixpoint.
+    ///
+    /// `relations` pairs each relation of a recursive SCC with its (unoptimized)
+    /// IR. All relations are evaluated together in one `.iterative()` scope
+    /// with one `Variable` per relation, so a derivation in one relation is
+    /// visible to the others in the next round (semi-naive evaluation across
+    /// the whole SCC). Returns the contents of every relation in the group.
+    pub fn execute_mutual_recursive(
+        &self,
+        relations: &[(String, IRNode)],
+    ) -> Result<HashMap<String, Vec<Tuple>>, String> {
+        match self.semiring_type {
+            SemiringType::Boolean => self.execute_mutual_recursive_typed::<BooleanDiff>(relations),
+            _ => self.execute_mutual_recursive_typed::<isize>(relations),
+        }
+    }
+
+    fn execute_mutual_recursive_typed<R: DiffType>(
+        &self,
+        relations: &[(String, IRNode)],
+    ) -> Result<HashMap<String, Vec<Tuple>>, String> {
+        let group: Vec<&str> = relations.iter().map(|(name, _)| name.as_str()).collect();
+
+        // Split each relation's rules into base cases (reading no relation of
+        // the group) and recursive cases
+        let as_union = |inputs: Vec<IRNode>| match inputs.len() {
+            0 => None,
+            1 => inputs.into_iter().next(),
+            _ => Some(IRNode::Union { inputs }),
+        };
+        type Plan = (
+            String,
+            Option<IRNode>,
+            Option<IRNode>,
+            Option<LoopAggregation>,
+        );
+        let plans: Vec<Plan> = relations
+            .iter()
+            .map(|(name, ir)| {
+                let inputs = match ir {
+                    IRNode::Union { inputs } => inputs.clone(),
+                    other => vec![other.clone()],
+                };
+                let (aggregation, inputs) = Self::plan_loop_aggregation(name, &inputs)?;
+                let (recursive, base): (Vec<IRNode>, Vec<IRNode>) =
+                    inputs.into_iter().partition(|input| {
+                        group
+                            .iter()
+                            .any(|rel| Self::references_relation(input, rel))
+                    });
+                Ok((
+                    name.clone(),
+                    as_union(base),
+                    as_union(recursive),
+                    aggregation,
+                ))
+            })
+            .collect::<Result<_, String>>()?;
+
+        debug!(relations = ?group, "mutual_recursion");
+
+        let results: Arc<Mutex<HashMap<String, Vec<Tuple>>>> = Arc::new(Mutex::new(
+            group
+                .iter()
+                .map(|name| ((*name).to_string(), Vec::new()))
+                .collect(),
+        ));
+        let results_clone = Arc::clone(&results);
+        let input_data = self.input_tuples.clone();
+        let result_limit = self.max_result_rows;
+
+        let query = self.query_context();
+        catch_unwind(AssertUnwindSafe(|| {
+            timely::execute_directly(move |worker| {
+                let _query = QueryScope::install(query.clone());
+                let probe = ProbeHandle::new();
+
+                worker.dataflow::<(), _, _>(|scope| {
+                    // Base cases only read static input data
+                    let base_collections: Vec<Option<Collection<_, Tuple, R>>> = plans
+                        .iter()
+                        .map(|(_, base, _, _)| {
+                            base.as_ref().map(|ir| {
+                                Self::generate_collection_tuples::<_, R>(
+                                    scope,
+                                    ir,
+                                    &input_data,
+                                    None,
+                                )
+                            })
+                        })
+                        .collect();
+
+                    let outputs = scope.iterative::<Iter, _, _>(|inner| {
+                        // Base relations read by the recursive bodies, plus
+                        // one Variable per relation of the group
+                        let bodies: Vec<&IRNode> = plans
+                            .iter()
+                            .filter_map(|(_, _, recursive, _)| recursive.as_ref())
+                            .collect();
+                        let bound: Vec<&str> =
+                            plans.iter().map(|(name, _, _, _)| name.as_str()).collect();
+                        let mut live =
+                            Self::scoped_inputs::<_, R>(inner, &bodies, &bound, &input_data);
+                        let mut variables = Vec::with_capacity(plans.len());
+                        for (name, _, _, _) in &plans {
+                            let (variable, collection) = Variable::new(inner, Product::new((), 1));
+                            live.insert(name.clone(), collection);
+                            variables.push(variable);
+                        }
+
+                        let mut outputs = Vec::with_capacity(plans.len());
+                        for (((_, _, recursive, aggregation), base), variable) in
+                            plans.iter().zip(base_collections).zip(variables)
+                        {
+                            let mut combined: Collection<_, Tuple, R> = match base {
+                                Some(base) => base.enter(inner),
+                                None => Collection::new(
+                                    Vec::<Tuple>::new()
+                                        .to_stream(inner)
+                                        .map(|x| (x, Product::default(), R::one())),
+                                ),
+                            };
+                            if let Some(recursive) = recursive {
+                                combined =
+                                    combined.concat(Self::generate_collection_tuples::<_, R>(
+                                        inner,
+                                        recursive,
+                                        &input_data,
+                                        Some(&live),
+                                    ));
+                            }
+                            let next = match aggregation {
+                                Some(agg) => Self::reduce_loop_aggregate(combined, *agg),
+                                None => Self::track_memory(combined).distinct_core::<R>(),
+                            };
+                            variable.set(Self::interruptible(next.clone()));
+                            let output = next.leave();
+                            outputs.push(if aggregation.is_some() {
+                                output.consolidate()
+                            } else {
+                                output
+                            });
+                        }
+                        outputs
+                    });
+
+                    for ((name, _, _, _), output) in plans.iter().zip(outputs) {
+                        let results = Arc::clone(&results_clone);
+                        let name = name.clone();
+                        output
+                            .inner
+                            .inspect(move |(data, _time, _diff)| {
+                                let mut guard = results.lock();
+                                let tuples = guard.entry(name.clone()).or_default();
+                                if result_limit == 0 || tuples.len() < result_limit {
+                                    tuples.push(data.clone());
+                                    if result_limit > 0 && tuples.len() >= result_limit {
+                                        signal_query_cancel();
+                                    }
+                                }
+                            })
+                            .probe_with(&probe);
+                    }
+                });
+
+                // Wait for computation to complete
+                while !probe.done() {
+                    if is_query_interrupted() {
+                        break;
+                    }
+                    worker.step();
+                    std::thread::yield_now();
+                }
+                if is_query_interrupted() {
+                    drop_dataflows(worker);
+                }
+            });
+        }))
+        .map_err(|e| {
+            format!(
+                "Internal error in query execution: {}",
+                format_panic_payload(e)
+            )
+        })?;
+
+        self.check_memory_budget()?;
+        self.check_timeout(results.lock().values().map(Vec::len).sum())?;
+
+        if self.is_cancelled() {
+            // If we hit the result limit, the cancel was self-triggered - return results
+            let limit_hit = result_limit > 0
+                && results
+                    .lock()
+                    .values()
+                    .any(|tuples| tuples.len() >= result_limit);
+            if !limit_hit {
+                return Err("Query cancelled due to timeout".to_string());
+            }
+        }
+
+        let final_results = Arc::try_unwrap(results)
+            .map_err(|_| "Failed to extract results")?
+            .into_inner();
+
+        Ok(final_results)
     }
 
-    /// Execute with Rayon-based parallelism. Falls back to single-worker for joins
-    /// (data must be co-located). Scan/filter/map queries partition data across workers.
+    /// Execute the non-recursive plans of one stratum in a single dataflow.
+    ///
+    /// `plans` pairs each relation with the plan that derives it, in
+    /// dependency order: a plan may read the relations of earlier plans.
+    /// Base relations are streamed in once, each derived relation feeds the
+    /// plans after it directly, and a relation joined on the same key
+    /// columns by several plans is arranged once, with every such join
+    /// reading the shared arrangement. Returns the contents of every relation.
+    pub fn execute_stratum(
+        &self,
+        plans: &[(String, IRNode)],
+    ) -> Result<HashMap<String, Vec<Tuple>>, String> {
+        match self.semiring_type {
+            SemiringType::Boolean => self.execute_stratum_typed::<BooleanDiff>(plans),
+            _ => self.execute_stratum_typed::<isize>(plans),
+        }
+    }
+
+    fn execute_stratum_typed<R: DiffType>(
+        &self,
+        plans: &[(String, IRNode)],
+    ) -> Result<HashMap<String, Vec<Tuple>>, String> {
+        let results: Arc<Mutex<HashMap<String, Vec<Tuple>>>> = Arc::new(Mutex::new(
+            plans
+                .iter()
+                .map(|(name, _)| (name.clone(), Vec::new()))
+                .collect(),
+        ));
+        let results_clone = Arc::clone(&results);
+        let input_data = self.input_tuples.clone();
+        let dataflow_plans = plans.to_vec();
+        let result_limit = self.max_result_rows;
+
+        let query = self.query_context();
+        catch_unwind(AssertUnwindSafe(|| {
+            timely::execute_directly(move |worker| {
+                let _query = QueryScope::install(query.clone());
+                let probe = ProbeHandle::new();
+
+                worker.dataflow::<(), _, _>(|scope| {
+                    let bodies: Vec<&IRNode> = dataflow_plans.iter().map(|(_, ir)| ir).collect();
+                    let heads: Vec<&str> = dataflow_plans
+                        .iter()
+                        .map(|(name, _)| name.as_str())
+                        .collect();
+                    let mut live = Self::scoped_inputs::<_, R>(scope, &bodies, &heads, &input_data);
+                    let arrangements: SharedArrangements<_, R> = RefCell::new(HashMap::new());
+
+                    for (name, ir) in &dataflow_plans {
+                        let body = Self::generate_collection_shared::<_, R>(
+                            scope,
+                            ir,
+                            &input_data,
+                            Some(&live),
+                            Some(&arrangements),
+                        );
+                        let output = Self::track_memory(body).distinct_core::<R>();
+                        live.insert(name.clone(), output.clone());
+
+                        // The limit caps what is returned, not what later
+                        // plans of the stratum read
+                        let results = Arc::clone(&results_clone);
+                        let name = name.clone();
+                        output
+                            .inner
+                            .inspect(move |(data, _time, _diff)| {
+                                let mut guard = results.lock();
+                                let tuples = guard.entry(name.clone()).or_default();
+                                if result_limit == 0 || tuples.len() < result_limit {
+                                    tuples.push(data.clone());
+                                }
+                            })
+                            .probe_with(&probe);
+                    }
+                });
+
+                // Wait for computation to complete
+                while !probe.done() {
+                    if is_query_interrupted() {
+                        break;
+                    }
+                    worker.step();
+                    std::thread::yield_now();
+                }
+                if is_query_interrupted() {
+                    drop_dataflows(worker);
+                }
+            });
+        }))
+        .map_err(|e| {
+            format!(
+                "Internal error in query execution: {}",
+                format_panic_payload(e)
+            )
+        })?;
+
+        self.check_memory_budget()?;
+        self.check_timeout(results.lock().values().map(Vec::len).sum())?;
+
+        if self.is_cancelled() {
+            return Err("Query cancelled due to timeout".to_string());
+        }
+
+        let mut final_results = Arc::try_unwrap(results)
+            .map_err(|_| "Failed to extract results")?
+            .into_inner();
+        for (name, ir) in plans {
+            if let Some(tuples) = final_results.get_mut(name) {
+                Self::sort_tuples(tuples, Self::split_sort(ir).1);
+            }
+        }
+
+        Ok(final_results)
+    }
+
+    /// Execute on `config.num_workers` timely workers.
+    ///
+    /// Every worker builds the same dataflow and streams a disjoint share of
+    /// each base relation (see `worker_share`). Joins, semijoins, aggregates
+    /// and `distinct` arrange their inputs by key, and DD exchanges records
+    /// between workers by key hash before arranging, so each key is processed
+    /// by exactly one worker and the union of the workers' outputs equals the
+    /// single-worker result.
     pub fn execute_with_config(
         &self,
         ir: &IRNode,
         config: ExecutionConfig,
     ) -> Result<Vec<Tuple>, String> {
-        use rayon::prelude::*;
-        use std::collections::HashSet;
-
-        if config.num_workers == 1 {
-            // Fall back to direct execution for single worker
+        if config.num_workers <= 1 {
             return self.generate_and_execute_tuples(ir);
         }
+        let network = TimelyNetwork::process(config.num_workers)?;
+        self.execute_on_network(ir, network, false)
+    }
 
-        // Check if the IR contains joins - if so, use single-worker for correctness
-        // Joins require coordinated data exchange which our simple partitioning doesn't handle
-        if Self::contains_join(ir) {
-            return self.generate_and_execute_tuples(ir);
-        }
+    /// Execute on the workers of every process connected by `network`.
+    ///
+    /// Every process of the cluster must call this with the same plan and
+    /// inputs, so that all workers build the same dataflow. Results are
+    /// gathered on the first worker, so only the process hosting it (the
+    /// coordinator) gets them; the others return an empty result.
+    pub(crate) fn execute_distributed(
+        &self,
+        ir: &IRNode,
+        network: TimelyNetwork,
+    ) -> Result<Vec<Tuple>, String> {
+        self.execute_on_network(ir, network, true)
+    }
 
-        // For queries without joins, we can partition and process in parallel
-        let num_workers = config.num_workers;
+    /// Run a single-pass plan on the workers of `network`, reporting result
+    /// tuples on every worker, or only on the first one when `gather` is set.
+    fn execute_on_network(
+        &self,
+        ir: &IRNode,
+        network: TimelyNetwork,
+        gather: bool,
+    ) -> Result<Vec<Tuple>, String> {
+        let (plan, order_by) = Self::split_sort(ir);
+        let mut results = match self.semiring_type {
+            SemiringType::Boolean => {
+                self.execute_multi_worker_typed::<BooleanDiff>(plan, network, gather)
+            }
+            _ => self.execute_multi_worker_typed::<isize>(plan, network, gather),
+        }?;
+        Self::sort_tuples(&mut results, order_by);
+        Ok(results)
+    }
 
-        // Partition input data across workers
-        let partitioned_inputs: Vec<HashMap<String, Vec<Tuple>>> = (0..num_workers)
-            .map(|worker_idx| {
-                Self::partition_data_for_worker(&self.input_tuples, worker_idx, num_workers)
-            })
-            .collect();
+    /// Multi-worker counterpart of `execute_single_pass_typed`.
+    fn execute_multi_worker_typed<R: DiffType>(
+        &self,
+        ir: &IRNode,
+        network: TimelyNetwork,
+        gather: bool,
+    ) -> Result<Vec<Tuple>, String> {
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let results_clone = Arc::clone(&results);
+
+        let input_data = Arc::clone(&self.input_tuples);
+        let ir_clone = ir.clone();
+        let result_limit = self.max_result_rows;
+
+        // The cancel flag is thread-local; hand it to every worker thread so
+        // timeouts and the result limit stop all workers.
+        let query = self.query_context();
+        let TimelyNetwork { builders, guard } = network;
+        let guards = catch_unwind(AssertUnwindSafe(|| {
+            execute_from(builders, guard, WorkerConfig::default(), move |worker| {
+                let _query = QueryScope::install(query.clone());
+                let probe = ProbeHandle::new();
+                let results = Arc::clone(&results_clone);
+
+                worker.dataflow::<(), _, _>(|scope| {
+                    let collection = Self::generate_collection_tuples::<_, R>(
+                        scope,
+                        &ir_clone,
+                        &input_data,
+                        None,
+                    );
+
+                    // distinct_core exchanges by tuple, so each output tuple
+                    // is reported by exactly one worker.
+                    let distinct = Self::track_memory(collection).distinct_core::<R>().inner;
+                    let reported = if gather {
+                        distinct.exchange(|_| 0)
+                    } else {
+                        distinct
+                    };
+                    reported
+                        .inspect(move |(data, _time, _diff)| {
+                            let mut guard = results.lock();
+                            if result_limit == 0 || guard.len() < result_limit {
+                                guard.push(data.clone());
+                                // Other processes cannot see this worker's
+                                // cancel flag, and a process leaving early
+                                // would stall them, so gathered results are
+                                // only truncated
+                                if result_limit > 0 && guard.len() >= result_limit && !gather {
+                                    signal_query_cancel();
+                                }
+                            }
+                        })
+                        .probe_with(&probe);
+                });
 
-        let ir_clone = ir.clone();
-        let semiring_type = self.semiring_type;
-
-        // Execute in parallel using Rayon
-        let all_results: Vec<Vec<Tuple>> = partitioned_inputs
-            .into_par_iter()
-            .map(|partition| {
-                // Create a temporary code generator with this partition
-                let mut temp_codegen = CodeGenerator::new();
-                temp_codegen.set_semiring_type(semiring_type);
-                for (relation, tuples) in partition {
-                    temp_codegen.add_input_tuples(relation, tuples);
+                while !probe.done() {
+                    if is_query_interrupted() {
+                        break;
+                    }
+                    worker.step();
+                    std::thread::yield_now();
+                }
+                if is_query_interrupted() {
+                    drop_dataflows(worker);
                 }
-                temp_codegen
-                    .generate_and_execute_tuples(&ir_clone)
-                    .unwrap_or_default()
             })
-            .collect();
+        }))
+        .map_err(|e| {
+            format!(
+                "Internal error in query execution: {}",
+                format_panic_payload(e)
+            )
+        })??;
 
-        // Merge and deduplicate results
-        let mut combined: HashSet<Tuple> = HashSet::new();
-        for results in all_results {
-            combined.extend(results);
+        for outcome in guards.join() {
+            outcome.map_err(|e| format!("Internal error in query execution: {e}"))?;
         }
 
-        Ok(combined.into_iter().collect())
-    }
+        self.check_memory_budget()?;
+        self.check_timeout(results.lock().len())?;
 
-    /// Check if IR tree contains any join operations
-    fn contains_join(ir: &IRNode) -> bool {
-        match ir {
-            IRNode::Scan { .. } => false,
-            IRNode::HnswScan { .. } => false,
-            IRNode::Map { input, .. } => Self::contains_join(input),
-            IRNode::Filter { input, .. } => Self::contains_join(input),
-            IRNode::Join { .. } => true,
-            IRNode::Distinct { input } => Self::contains_join(input),
-            IRNode::Union { inputs } => inputs.iter().any(Self::contains_join),
-            IRNode::Aggregate { input, .. } => Self::contains_join(input),
-            IRNode::Antijoin { .. } => true, // Antijoin is also a join-like operation
-            IRNode::Compute { input, .. } => Self::contains_join(input),
-            IRNode::FlatMap { input, .. } => Self::contains_join(input),
-            IRNode::JoinFlatMap { .. } => true,
+        if self.is_cancelled() {
+            let collected = results.lock().len();
+            if result_limit == 0 || collected < result_limit {
+                return Err("Query cancelled due to timeout".to_string());
+            }
         }
+
+        let final_results = std::mem::take(&mut *results.lock());
+        Ok(final_results)
     }
 
     /// Execute with the number of workers equal to CPU cores
@@ -1338,41 +2613,44 @@ impl CodeGenerator {
         self.execute_with_config(ir, ExecutionConfig::all_cores())
     }
 
-    /// Partition input data for a specific worker
+    /// The share of `tuples` streamed by worker `worker_index` of `num_workers`.
     ///
-    /// Each worker gets tuples where `hash(tuple) % num_workers == worker_index`
-    fn partition_data_for_worker(
-        input_data: &HashMap<String, Vec<Tuple>>,
-        worker_index: usize,
-        num_workers: usize,
-    ) -> HashMap<String, Vec<Tuple>> {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-
-        input_data
+    /// Shares are disjoint and together cover every tuple, so a relation is
+    /// introduced into a multi-worker dataflow exactly once.
+    fn worker_share(tuples: &[Tuple], worker_index: usize, num_workers: usize) -> Vec<Tuple> {
+        if num_workers <= 1 {
+            return tuples.to_vec();
+        }
+        tuples
             .iter()
-            .map(|(relation, tuples)| {
-                let partitioned: Vec<Tuple> = tuples
-                    .iter()
-                    .filter(|tuple| {
-                        let mut hasher = DefaultHasher::new();
-                        tuple.hash(&mut hasher);
-                        let hash = hasher.finish() as usize;
-                        hash % num_workers == worker_index
-                    })
-                    .cloned()
-                    .collect();
-                (relation.clone(), partitioned)
-            })
+            .skip(worker_index)
+            .step_by(num_workers)
+            .cloned()
             .collect()
     }
 
     /// Generate DD collection from IR (production: Tuple)
-    fn generate_collection_tuples<G, R: DiffType>(
+    pub(crate) fn generate_collection_tuples<G, R: DiffType>(
+        scope: &mut G,
+        ir: &IRNode,
+        input_data: &HashMap<String, Vec<Tuple>>,
+        live: Option<&HashMap<String, Collection<G, Tuple, R>>>,
+    ) -> Collection<G, Tuple, R>
+    where
+        G: Scope,
+        G::Timestamp: Lattice + Ord + Default,
+    {
+        Self::generate_collection_shared::<G, R>(scope, ir, input_data, live, None)
+    }
+
+    /// Generate DD collection from IR, reusing the join arrangements in
+    /// `arrangements` (see `execute_stratum`)
+    fn generate_collection_shared<G, R: DiffType>(
         scope: &mut G,
         ir: &IRNode,
         input_data: &HashMap<String, Vec<Tuple>>,
         live: Option<&HashMap<String, Collection<G, Tuple, R>>>,
+        arrangements: Option<&SharedArrangements<G, R>>,
     ) -> Collection<G, Tuple, R>
     where
         G: Scope,
@@ -1385,11 +2663,23 @@ impl CodeGenerator {
 
             IRNode::Map {
                 input, projection, ..
-            } => Self::generate_map_tuples::<G, R>(scope, input, projection, input_data, live),
+            } => Self::generate_map_tuples::<G, R>(
+                scope,
+                input,
+                projection,
+                input_data,
+                live,
+                arrangements,
+            ),
 
-            IRNode::Filter { input, predicate } => {
-                Self::generate_filter_tuples::<G, R>(scope, input, predicate, input_data, live)
-            }
+            IRNode::Filter { input, predicate } => Self::generate_filter_tuples::<G, R>(
+                scope,
+                input,
+                predicate,
+                input_data,
+                live,
+                arrangements,
+            ),
 
             IRNode::Join {
                 left,
@@ -1398,10 +2688,14 @@ impl CodeGenerator {
                 right_keys,
                 output_schema,
             } => {
-                if std::env::var("IL_DEBUG").is_ok() {
-                    eprintln!("DEBUG IRNode::Join: left schema={:?} right schema={:?} left_keys={:?} right_keys={:?} output_schema={:?}",
-                             left.output_schema(), right.output_schema(), left_keys, right_keys, output_schema);
-                }
+                trace!(
+                    left_schema = ?left.output_schema(),
+                    right_schema = ?right.output_schema(),
+                    ?left_keys,
+                    ?right_keys,
+                    ?output_schema,
+                    "codegen_join"
+                );
                 Self::generate_join_tuples::<G, R>(
                     scope,
                     left,
@@ -1411,15 +2705,25 @@ impl CodeGenerator {
                     output_schema,
                     input_data,
                     live,
+                    arrangements,
                 )
             }
 
             IRNode::Distinct { input } => {
-                Self::generate_distinct_tuples::<G, R>(scope, input, input_data, live)
+                Self::generate_distinct_tuples::<G, R>(scope, input, input_data, live, arrangements)
             }
 
+            // Collections are unordered; results are sorted once collected
+            IRNode::Sort { input, .. } => Self::generate_collection_shared::<G, R>(
+                scope,
+                input,
+                input_data,
+                live,
+                arrangements,
+            ),
+
             IRNode::Union { inputs } => {
-                Self::generate_union_tuples::<G, R>(scope, inputs, input_data, live)
+                Self::generate_union_tuples::<G, R>(scope, inputs, input_data, live, arrangements)
             }
 
             IRNode::Aggregate {
@@ -1434,6 +2738,7 @@ impl CodeGenerator {
                 aggregations,
                 input_data,
                 live,
+                arrangements,
             ),
 
             IRNode::Antijoin {
@@ -1443,12 +2748,41 @@ impl CodeGenerator {
                 right_keys,
                 ..
             } => Self::generate_antijoin_tuples::<G, R>(
-                scope, left, right, left_keys, right_keys, input_data, live,
+                scope,
+                left,
+                right,
+                left_keys,
+                right_keys,
+                input_data,
+                live,
+                arrangements,
             ),
 
-            IRNode::Compute { input, expressions } => {
-                Self::generate_compute_tuples::<G, R>(scope, input, expressions, input_data, live)
-            }
+            IRNode::Semijoin {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                ..
+            } => Self::generate_semijoin_tuples::<G, R>(
+                scope,
+                left,
+                right,
+                left_keys,
+                right_keys,
+                input_data,
+                live,
+                arrangements,
+            ),
+
+            IRNode::Compute { input, expressions } => Self::generate_compute_tuples::<G, R>(
+                scope,
+                input,
+                expressions,
+                input_data,
+                live,
+                arrangements,
+            ),
 
             IRNode::HnswScan { .. } => {
                 // HNSW queries are resolved by the IndexManager before reaching
@@ -1471,8 +2805,13 @@ impl CodeGenerator {
             } => {
                 // Fused Map+Filter: uses flat_map() to apply projection + optional filter
                 // in a single DD operator, eliminating intermediate collection
-                let input_coll =
-                    Self::generate_collection_tuples::<G, R>(scope, input, input_data, live);
+                let input_coll = Self::generate_collection_shared::<G, R>(
+                    scope,
+                    input,
+                    input_data,
+                    live,
+                    arrangements,
+                );
                 let projection = projection.clone();
                 let pred_fn = filter_predicate
                     .as_ref()
@@ -1502,63 +2841,458 @@ impl CodeGenerator {
                 filter_predicate,
                 ..
             } => {
-                // Fused Join+Map+Filter using DD's join_map/join_core to avoid
+                let projection = projection.clone();
+                let pred_fn = filter_predicate
+                    .as_ref()
+                    .map(|p| Self::predicate_to_tuple_fn(p));
+                let null_keys = left_keys.clone();
+                let emit = move |left_tuple: &Tuple, right_tuple: &Tuple| {
+                    if Self::has_null_key(left_tuple, &null_keys) {
+                        return None;
+                    }
+                    let projected = left_tuple.concat_project(right_tuple, &projection);
+                    match &pred_fn {
+                        Some(f) if !f(&projected) => None,
+                        _ => Some(projected),
+                    }
+                };
+                let sides = [left.as_ref(), right.as_ref()];
+                let keys = [left_keys.as_slice(), right_keys.as_slice()];
+                if let JoinAlgorithm::Hash { build } =
+                    Self::plan_join(sides, keys, input_data, live, arrangements)
+                {
+                    if let Some(table) = Self::hash_table(sides, keys, build, input_data, live) {
+                        return Self::hash_join(
+                            scope,
+                            sides,
+                            keys,
+                            build,
+                            table,
+                            input_data,
+                            live,
+                            arrangements,
+                            emit,
+                        );
+                    }
+                }
+
+                // Fused Join+Map+Filter using DD's join_core to avoid
                 // materializing an intermediate (key, (left, right)) collection.
-                let left_coll =
-                    Self::generate_collection_tuples::<G, R>(scope, left, input_data, live);
-                let right_coll =
-                    Self::generate_collection_tuples::<G, R>(scope, right, input_data, live);
+                let (left_filter, right_filter) = Self::join_prefilters(
+                    left, right, left_keys, right_keys, input_data, live, false,
+                );
+                let left_arranged = Self::arrange_join_side::<G, R>(
+                    scope,
+                    left,
+                    left_keys,
+                    left_filter,
+                    input_data,
+                    live,
+                    arrangements,
+                );
+                let right_arranged = Self::arrange_join_side::<G, R>(
+                    scope,
+                    right,
+                    right_keys,
+                    right_filter,
+                    input_data,
+                    live,
+                    arrangements,
+                );
 
-                let left_key_indices = left_keys.clone();
-                let right_key_indices = right_keys.clone();
-                let projection = projection.clone();
+                let interrupt = query_interrupt_token();
+                left_arranged.join_core(right_arranged, move |_key, left_tuple, right_tuple| {
+                    // An interrupted query stops producing join output, so a
+                    // runaway fixpoint drains instead of growing
+                    if interrupt.is_interrupted() {
+                        return None;
+                    }
+                    emit(left_tuple, right_tuple)
+                })
+            }
+        }
+    }
 
-                // Key left by join columns
-                let left_keyed = left_coll.map(move |tuple| {
-                    let key = Tuple::new(
-                        left_key_indices
-                            .iter()
-                            .map(|&i| tuple.values()[i].clone())
-                            .collect(),
-                    );
-                    (key, tuple)
-                });
+    /// Physical algorithm of a join of `[left, right]` on
+    /// `[left_keys, right_keys]`, chosen by the join planner from what is
+    /// known while the dataflow is built: the row counts of sides read from
+    /// the inputs, and whether a side's arrangement on the join key already
+    /// exists in the shared cache.
+    fn plan_join<G, R: DiffType>(
+        sides: [&IRNode; 2],
+        keys: [&[usize]; 2],
+        input_data: &HashMap<String, Vec<Tuple>>,
+        live: Option<&HashMap<String, Collection<G, Tuple, R>>>,
+        arrangements: Option<&SharedArrangements<G, R>>,
+    ) -> JoinAlgorithm
+    where
+        G: Scope,
+        G::Timestamp: Lattice + Ord + Default,
+    {
+        let info = |side: &IRNode, keys: &[usize]| JoinInputInfo {
+            static_rows: Self::estimated_rows(side, input_data, live),
+            arranged_by_key: match (side, arrangements) {
+                (IRNode::Scan { relation, .. }, Some(cache)) => cache
+                    .borrow()
+                    .contains_key(&(relation.clone(), keys.to_vec())),
+                _ => false,
+            },
+        };
+        select_join_algorithm(
+            &info(sides[0], keys[0]),
+            &info(sides[1], keys[1]),
+            &QUERY_JOIN_ALGORITHMS.with(Cell::get),
+        )
+    }
 
-                // Key right by join columns
-                let right_keyed = right_coll.map(move |tuple| {
-                    let key = Tuple::new(
-                        right_key_indices
-                            .iter()
-                            .map(|&i| tuple.values()[i].clone())
-                            .collect(),
-                    );
-                    (key, tuple)
-                });
+    /// Hash table of a join's `build` side, keyed by its join columns and
+    /// computed from the inputs. Charged to the query's memory budget.
+    /// `None` when the side cannot be computed without the dataflow.
+    fn hash_table<G, R: DiffType>(
+        sides: [&IRNode; 2],
+        keys: [&[usize]; 2],
+        build: JoinSide,
+        input_data: &HashMap<String, Vec<Tuple>>,
+        live: Option<&HashMap<String, Collection<G, Tuple, R>>>,
+    ) -> Option<HashMap<Tuple, Vec<Tuple>>>
+    where
+        G: Scope,
+        G::Timestamp: Lattice + Ord + Default,
+    {
+        let build = match build {
+            JoinSide::Left => 0,
+            JoinSide::Right => 1,
+        };
+        let tuples = Self::static_side_tuples(sides[build], input_data, live)?;
+
+        let mut table: HashMap<Tuple, Vec<Tuple>> = HashMap::new();
+        let mut bytes = 0;
+        for tuple in tuples {
+            bytes += tuple.estimated_bytes();
+            table
+                .entry(Self::join_key(&tuple, keys[build]))
+                .or_default()
+                .push(tuple);
+        }
+        if let Some(tracker) = QUERY_MEMORY.with(|cell| cell.borrow().clone()) {
+            tracker.charge(bytes);
+        }
+        Some(table)
+    }
+
+    /// Hash join: the other side of `build` streams through `table` without
+    /// being arranged. Every worker holds the whole table and probes with
+    /// its own share of the streamed side. `emit` maps each matching
+    /// (left, right) pair to an output row.
+    fn hash_join<G, R: DiffType, F>(
+        scope: &mut G,
+        sides: [&IRNode; 2],
+        keys: [&[usize]; 2],
+        build: JoinSide,
+        table: HashMap<Tuple, Vec<Tuple>>,
+        input_data: &HashMap<String, Vec<Tuple>>,
+        live: Option<&HashMap<String, Collection<G, Tuple, R>>>,
+        arrangements: Option<&SharedArrangements<G, R>>,
+        emit: F,
+    ) -> Collection<G, Tuple, R>
+    where
+        G: Scope,
+        G::Timestamp: Lattice + Ord + Default,
+        F: Fn(&Tuple, &Tuple) -> Option<Tuple> + 'static,
+    {
+        let probe = match build {
+            JoinSide::Left => 1,
+            JoinSide::Right => 0,
+        };
+        let probe_keys = keys[probe].to_vec();
+        let interrupt = query_interrupt_token();
+        Self::generate_collection_shared::<G, R>(
+            scope,
+            sides[probe],
+            input_data,
+            live,
+            arrangements,
+        )
+        .flat_map(move |tuple| {
+            let mut joined = Vec::new();
+            if interrupt.is_interrupted() {
+                return joined;
+            }
+            let Some(matches) = table.get(&Self::join_key(&tuple, &probe_keys)) else {
+                return joined;
+            };
+            for other in matches {
+                let row = match build {
+                    JoinSide::Left => emit(other, &tuple),
+                    JoinSide::Right => emit(&tuple, other),
+                };
+                joined.extend(row);
+            }
+            joined
+        })
+    }
+
+    /// Arrange one side of a join by its key columns.
+    ///
+    /// With a shared cache, a side that is a bare scan reuses the arrangement
+    /// of that relation on the same columns, so a relation joined on the
+    /// same key by several rules of a stratum is arranged only once. A side
+    /// with a pre-filter drops tuples whose key cannot match before it is
+    /// arranged, and is never shared.
+    fn arrange_join_side<G, R: DiffType>(
+        scope: &mut G,
+        side: &IRNode,
+        keys: &[usize],
+        filter: Option<SideFilter>,
+        input_data: &HashMap<String, Vec<Tuple>>,
+        live: Option<&HashMap<String, Collection<G, Tuple, R>>>,
+        arrangements: Option<&SharedArrangements<G, R>>,
+    ) -> KeyedArrangement<G, R>
+    where
+        G: Scope,
+        G::Timestamp: Lattice + Ord + Default,
+    {
+        let cache_key = match (side, arrangements, &filter) {
+            (IRNode::Scan { relation, .. }, Some(_), None) => {
+                Some((relation.clone(), keys.to_vec()))
+            }
+            _ => None,
+        };
+        if let (Some(key), Some(cache)) = (&cache_key, arrangements) {
+            if let Some(arranged) = cache.borrow().get(key) {
+                return arranged.clone();
+            }
+        }
+
+        let keys = keys.to_vec();
+        let side_coll = match filter {
+            Some(SideFilter::Inputs(restricted)) => {
+                Self::generate_collection_shared::<G, R>(scope, side, &restricted, live, None)
+            }
+            Some(SideFilter::Bloom(filter)) => {
+                let filter_keys = keys.clone();
+                Self::generate_collection_shared::<G, R>(
+                    scope,
+                    side,
+                    input_data,
+                    live,
+                    arrangements,
+                )
+                .filter(move |tuple| filter.might_match(&tuple.from_indices(&filter_keys)))
+            }
+            None => Self::generate_collection_shared::<G, R>(
+                scope,
+                side,
+                input_data,
+                live,
+                arrangements,
+            ),
+        };
+        let arranged = Self::track_memory(side_coll)
+            .map(move |tuple| (Self::join_key(&tuple, &keys), tuple))
+            .arrange_by_key();
+        if let (Some(key), Some(cache)) = (cache_key, arrangements) {
+            cache.borrow_mut().insert(key, arranged.clone());
+        }
+        arranged
+    }
+
+    /// Pre-filters for the (left, right) sides of a join.
+    ///
+    /// Only sides known while the dataflow is built are used to filter the
+    /// other side; sides that read live (derived or recursive) relations are
+    /// never evaluated, so filters are only built from complete data.
+    ///
+    /// A side that is a scan behind filters and projections is restricted to
+    /// the exact keys of the other side when those are few enough (a runtime
+    /// semijoin filter). Otherwise a side estimated to be much larger than
+    /// the other gets a bloom filter over the other side's keys. With
+    /// `negated`, the left side is the kept side of an antijoin and is only
+    /// ever bloom-filtered.
+    fn join_prefilters<G, R: DiffType>(
+        left: &IRNode,
+        right: &IRNode,
+        left_keys: &[usize],
+        right_keys: &[usize],
+        input_data: &HashMap<String, Vec<Tuple>>,
+        live: Option<&HashMap<String, Collection<G, Tuple, R>>>,
+        negated: bool,
+    ) -> (Option<SideFilter>, Option<SideFilter>)
+    where
+        G: Scope,
+        G::Timestamp: Lattice + Ord + Default,
+    {
+        let Some(prefilter) = QUERY_PREFILTER.with(|cell| cell.borrow().clone()) else {
+            return (None, None);
+        };
+        if left_keys.is_empty() || right_keys.is_empty() {
+            return (None, None);
+        }
+
+        let filter_for = |probe: &IRNode,
+                          probe_keys: &[usize],
+                          build: &IRNode,
+                          build_keys: &[usize],
+                          runtime: bool| {
+            let probe_rows = Self::estimated_rows(probe, input_data, live)?;
+            let build_rows = Self::estimated_rows(build, input_data, live)?;
+            let bloom = prefilter.applies(build_rows, probe_rows);
+            let scan = if runtime && probe_rows >= prefilter.min_probe_rows {
+                Self::scan_key_columns(probe, probe_keys)
+            } else {
+                None
+            };
+            if !bloom && scan.is_none() {
+                return None;
+            }
+
+            let tuples = Self::static_side_tuples(build, input_data, live)?;
+            if let Some((relation, scan_keys)) = scan {
+                let keys: HashSet<Tuple> = tuples
+                    .iter()
+                    .map(|tuple| tuple.from_indices(build_keys))
+                    .collect();
+                if prefilter.applies_runtime(keys.len(), probe_rows) {
+                    let scanned = input_data.get(relation).map_or(&[][..], Vec::as_slice);
+                    let kept: Vec<Tuple> = scanned
+                        .iter()
+                        .filter(|tuple| keys.contains(&tuple.from_indices(&scan_keys)))
+                        .cloned()
+                        .collect();
+                    prefilter.record_runtime_filter(scanned.len(), kept.len());
+                    let mut restricted = HashMap::with_capacity(1);
+                    restricted.insert(relation.to_string(), kept);
+                    return Some(SideFilter::Inputs(restricted));
+                }
+            }
+            if !bloom {
+                return None;
+            }
+            let expected = tuples.len();
+            Some(SideFilter::Bloom(prefilter.build(
+                tuples.iter().map(|tuple| tuple.from_indices(build_keys)),
+                expected,
+            )))
+        };
+        (
+            filter_for(left, left_keys, right, right_keys, !negated),
+            filter_for(right, right_keys, left, left_keys, true),
+        )
+    }
+
+    /// The relation scanned by `node` and the scan columns its `keys` come
+    /// from, when `node` is a chain of filters and projections over a
+    /// single scan. Restricting that scan to matching keys then restricts
+    /// `node` the same way.
+    fn scan_key_columns<'a>(node: &'a IRNode, keys: &[usize]) -> Option<(&'a str, Vec<usize>)> {
+        match node {
+            IRNode::Scan { relation, .. } => Some((relation.as_str(), keys.to_vec())),
+            IRNode::Filter { input, .. } | IRNode::Distinct { input } => {
+                Self::scan_key_columns(input, keys)
+            }
+            IRNode::Map {
+                input, projection, ..
+            }
+            | IRNode::FlatMap {
+                input, projection, ..
+            } => {
+                let input_keys = keys
+                    .iter()
+                    .map(|&key| projection.get(key).copied())
+                    .collect::<Option<Vec<_>>>()?;
+                Self::scan_key_columns(input, &input_keys)
+            }
+            _ => None,
+        }
+    }
+
+    /// Upper bound on the rows of `node` from input cardinalities, or `None`
+    /// when it reads a live relation or its size cannot be bounded.
+    fn estimated_rows<G, R: DiffType>(
+        node: &IRNode,
+        input_data: &HashMap<String, Vec<Tuple>>,
+        live: Option<&HashMap<String, Collection<G, Tuple, R>>>,
+    ) -> Option<usize>
+    where
+        G: Scope,
+        G::Timestamp: Lattice + Ord + Default,
+    {
+        match node {
+            IRNode::Scan { relation, .. } => {
+                if live.is_some_and(|live| live.contains_key(relation)) {
+                    return None;
+                }
+                Some(input_data.get(relation).map_or(0, Vec::len))
+            }
+            IRNode::Filter { input, .. }
+            | IRNode::Map { input, .. }
+            | IRNode::FlatMap { input, .. }
+            | IRNode::Distinct { input }
+            | IRNode::Compute { input, .. }
+            | IRNode::Sort { input, .. } => Self::estimated_rows(input, input_data, live),
+            _ => None,
+        }
+    }
 
-                if filter_predicate.is_none() {
-                    // No filter: use join_map to fuse join + projection in one operator
-                    left_keyed.join_map(right_keyed, move |_key, left_tuple, right_tuple| {
-                        let combined = left_tuple.concat(right_tuple);
-                        combined.project(&projection)
-                    })
-                } else {
-                    // With filter: use arrange_by_key + join_core which supports
-                    // returning Option (skipping non-matching tuples)
-                    let pred_fn = filter_predicate
-                        .as_ref()
-                        .map(|p| Self::predicate_to_tuple_fn(p));
-                    let left_arranged = left_keyed.arrange_by_key();
-                    let right_arranged = right_keyed.arrange_by_key();
-                    left_arranged.join_core(right_arranged, move |_key, left_tuple, right_tuple| {
-                        let combined = left_tuple.concat(right_tuple);
-                        let projected = combined.project(&projection);
-                        match &pred_fn {
-                            Some(f) if !f(&projected) => None,
-                            _ => Some(projected),
-                        }
-                    })
+    /// Rows of `node` computed directly from the inputs, for the small side
+    /// of a pre-filtered join. `None` for plans that need the dataflow.
+    fn static_side_tuples<G, R: DiffType>(
+        node: &IRNode,
+        input_data: &HashMap<String, Vec<Tuple>>,
+        live: Option<&HashMap<String, Collection<G, Tuple, R>>>,
+    ) -> Option<Vec<Tuple>>
+    where
+        G: Scope,
+        G::Timestamp: Lattice + Ord + Default,
+    {
+        match node {
+            IRNode::Scan { relation, .. } => {
+                if live.is_some_and(|live| live.contains_key(relation)) {
+                    return None;
                 }
+                Some(input_data.get(relation).cloned().unwrap_or_default())
             }
+            IRNode::Filter { input, predicate } => {
+                let keep = Self::predicate_to_tuple_fn(predicate);
+                // A selective filter over a scan only clones the rows it keeps
+                if let IRNode::Scan { relation, .. } = input.as_ref() {
+                    if !live.is_some_and(|live| live.contains_key(relation)) {
+                        return Some(
+                            input_data
+                                .get(relation)
+                                .map(|tuples| {
+                                    tuples.iter().filter(|tuple| keep(tuple)).cloned().collect()
+                                })
+                                .unwrap_or_default(),
+                        );
+                    }
+                }
+                let mut tuples = Self::static_side_tuples(input, input_data, live)?;
+                tuples.retain(|tuple| keep(tuple));
+                Some(tuples)
+            }
+            IRNode::Map {
+                input, projection, ..
+            } => Some(
+                Self::static_side_tuples(input, input_data, live)?
+                    .iter()
+                    .map(|tuple| tuple.project(projection))
+                    .collect(),
+            ),
+            // Duplicates do not change a bloom filter
+            IRNode::Distinct { input } => Self::static_side_tuples(input, input_data, live),
+            _ => None,
+        }
+    }
+
+    /// Join key of `tuple`. Cartesian products (no key columns) key every
+    /// tuple by the same sentinel, since empty tuples as keys cause issues
+    /// in Differential Dataflow.
+    fn join_key(tuple: &Tuple, keys: &[usize]) -> Tuple {
+        if keys.is_empty() {
+            Tuple::new(vec![Value::Int64(0)])
+        } else {
+            tuple.from_indices(keys)
         }
     }
 
@@ -1580,20 +3314,16 @@ impl CodeGenerator {
         // Check live collections first (for recursive relations in iterative scopes)
         if let Some(live_map) = live {
             if let Some(collection) = live_map.get(relation) {
-                if std::env::var("IL_DEBUG").is_ok() {
-                    eprintln!("DEBUG Scan '{relation}': using live collection");
-                }
+                trace!(relation, "codegen_scan_live_collection");
                 return collection.clone();
             }
         }
 
-        let data = input_data.get(relation).cloned().unwrap_or_default();
-        if std::env::var("IL_DEBUG").is_ok() {
-            eprintln!("DEBUG Scan '{}': {} tuples", relation, data.len());
-            for t in &data {
-                eprintln!("DEBUG Scan '{}': {:?}", relation, t.values());
-            }
-        }
+        let data = input_data
+            .get(relation)
+            .map(|tuples| Self::worker_share(tuples, scope.index(), scope.peers()))
+            .unwrap_or_default();
+        trace!(relation, tuples = data.len(), "codegen_scan");
         Collection::new(
             data.to_stream(scope)
                 .map(|x| (x, Default::default(), R::one())),
@@ -1607,12 +3337,14 @@ impl CodeGenerator {
         projection: &[usize],
         input_data: &HashMap<String, Vec<Tuple>>,
         live: Option<&HashMap<String, Collection<G, Tuple, R>>>,
+        arrangements: Option<&SharedArrangements<G, R>>,
     ) -> Collection<G, Tuple, R>
     where
         G: Scope,
         G::Timestamp: Lattice + Ord + Default,
     {
-        let input_coll = Self::generate_collection_tuples::<G, R>(scope, input, input_data, live);
+        let input_coll =
+            Self::generate_collection_shared::<G, R>(scope, input, input_data, live, arrangements);
         let projection = projection.to_vec();
 
         input_coll.map(move |tuple| tuple.project(&projection))
@@ -1625,18 +3357,36 @@ impl CodeGenerator {
         predicate: &Predicate,
         input_data: &HashMap<String, Vec<Tuple>>,
         live: Option<&HashMap<String, Collection<G, Tuple, R>>>,
+        arrangements: Option<&SharedArrangements<G, R>>,
     ) -> Collection<G, Tuple, R>
     where
         G: Scope,
         G::Timestamp: Lattice + Ord + Default,
     {
-        let input_coll = Self::generate_collection_tuples::<G, R>(scope, input, input_data, live);
+        let input_coll =
+            Self::generate_collection_shared::<G, R>(scope, input, input_data, live, arrangements);
         let pred_fn = Self::predicate_to_tuple_fn(predicate);
         input_coll.filter(move |tuple| pred_fn(tuple))
     }
 
+    /// The value at `col`, or `None` if the column is missing or NULL.
+    fn non_null(tuple: &Tuple, col: usize) -> Option<&Value> {
+        tuple.get(col).filter(|v| !v.is_null())
+    }
+
+    /// Whether any of the `keys` columns of `tuple` is missing or NULL.
+    /// Such a tuple can never match in a join.
+    fn has_null_key(tuple: &Tuple, keys: &[usize]) -> bool {
+        keys.iter().any(|&k| Self::non_null(tuple, k).is_none())
+    }
+
     /// Convert predicate to filter function (production: Tuple)
-    fn predicate_to_tuple_fn(
+    ///
+    /// Predicates follow SQL three-valued logic: a comparison with a NULL
+    /// (or missing) operand is UNKNOWN, and only TRUE rows pass the filter.
+    /// Since predicates have no negation, UNKNOWN can be folded to `false`
+    /// and `And`/`Or` keep their usual meaning.
+    pub(crate) fn predicate_to_tuple_fn(
         predicate: &Predicate,
     ) -> Box<dyn Fn(&Tuple) -> bool + Send + Sync + 'static> {
         match predicate.clone() {
@@ -1655,16 +3405,18 @@ impl CodeGenerator {
                 false
             }),
             Predicate::ColumnNeConst(col, val) => Box::new(move |tuple: &Tuple| {
-                if let Some(v) = tuple.get(col) {
-                    // Try integer first
-                    if let Some(i) = v.as_i64() {
-                        return i != val;
-                    }
-                    // Fall back to float comparison for Float64 values
-                    if let Some(f) = v.as_f64() {
-                        return (f - (val as f64)).abs() >= FLOAT_EQ_TOLERANCE;
-                    }
+                let Some(v) = Self::non_null(tuple, col) else {
+                    return false;
+                };
+                // Try integer first
+                if let Some(i) = v.as_i64() {
+                    return i != val;
                 }
+                // Fall back to float comparison for Float64 values
+                if let Some(f) = v.as_f64() {
+                    return (f - (val as f64)).abs() >= FLOAT_EQ_TOLERANCE;
+                }
+                // A non-null value of another type never equals the constant
                 true
             }),
             Predicate::ColumnGtConst(col, val) => Box::new(move |tuple: &Tuple| {
@@ -1727,10 +3479,7 @@ impl CodeGenerator {
                     .is_some_and(|s| s == val)
             }),
             Predicate::ColumnNeStr(col, val) => Box::new(move |tuple: &Tuple| {
-                tuple
-                    .get(col)
-                    .and_then(|v| v.as_str())
-                    .is_none_or(|s| s != val)
+                Self::non_null(tuple, col).is_some_and(|v| v.as_str().is_none_or(|s| s != val))
             }),
             Predicate::ColumnLtStr(col, val) => Box::new(move |tuple: &Tuple| {
                 tuple
@@ -1764,10 +3513,10 @@ impl CodeGenerator {
                     .is_some_and(|f| (f - val).abs() < FLOAT_EQ_TOLERANCE)
             }),
             Predicate::ColumnNeFloat(col, val) => Box::new(move |tuple: &Tuple| {
-                tuple
-                    .get(col)
-                    .and_then(super::value::Value::as_f64)
-                    .is_none_or(|f| (f - val).abs() >= FLOAT_EQ_TOLERANCE)
+                Self::non_null(tuple, col).is_some_and(|v| {
+                    v.as_f64()
+                        .is_none_or(|f| (f - val).abs() >= FLOAT_EQ_TOLERANCE)
+                })
             }),
             Predicate::ColumnGtFloat(col, val) => Box::new(move |tuple: &Tuple| {
                 tuple
@@ -1801,21 +3550,20 @@ impl CodeGenerator {
                     .is_some_and(|b| b == val)
             }),
             Predicate::ColumnNeBool(col, val) => Box::new(move |tuple: &Tuple| {
-                tuple
-                    .get(col)
-                    .and_then(super::value::Value::as_bool)
-                    .is_none_or(|b| b != val)
+                Self::non_null(tuple, col).is_some_and(|v| v.as_bool().is_none_or(|b| b != val))
             }),
             // Column comparisons
             Predicate::ColumnsEq(left, right) => Box::new(move |tuple: &Tuple| {
-                let lv = tuple.get(left);
-                let rv = tuple.get(right);
-                lv == rv
+                match (Self::non_null(tuple, left), Self::non_null(tuple, right)) {
+                    (Some(lv), Some(rv)) => lv == rv,
+                    _ => false,
+                }
             }),
             Predicate::ColumnsNe(left, right) => Box::new(move |tuple: &Tuple| {
-                let lv = tuple.get(left);
-                let rv = tuple.get(right);
-                lv != rv
+                match (Self::non_null(tuple, left), Self::non_null(tuple, right)) {
+                    (Some(lv), Some(rv)) => lv != rv,
+                    _ => false,
+                }
             }),
             // Column-to-column ordering comparisons
             Predicate::ColumnsLt(left, right) => Box::new(move |tuple: &Tuple| {
@@ -2004,59 +3752,72 @@ impl CodeGenerator {
         _output_schema: &[String],
         input_data: &HashMap<String, Vec<Tuple>>,
         live: Option<&HashMap<String, Collection<G, Tuple, R>>>,
+        arrangements: Option<&SharedArrangements<G, R>>,
     ) -> Collection<G, Tuple, R>
     where
         G: Scope,
         G::Timestamp: Lattice + Ord + Default,
     {
-        let left_coll = Self::generate_collection_tuples::<G, R>(scope, left, input_data, live);
-        let right_coll = Self::generate_collection_tuples::<G, R>(scope, right, input_data, live);
-
-        // CARTESIAN PRODUCT FIX: When both key arrays are empty, we need a
-        // Cartesian product (cross join). Using empty tuples as keys causes
-        // issues in Differential Dataflow, so we use a sentinel value instead.
-        let is_cartesian = left_keys.is_empty() && right_keys.is_empty();
-
-        if is_cartesian {
-            // All tuples keyed by the same constant = full Cartesian product
-            let sentinel = Tuple::new(vec![Value::Int64(0)]);
-
-            let left_keyed = left_coll.map(move |tuple| (sentinel.clone(), tuple));
-
-            let sentinel2 = Tuple::new(vec![Value::Int64(0)]);
-            let right_keyed = right_coll.map(move |tuple| (sentinel2.clone(), tuple));
-
-            // For Cartesian product, concatenate ALL columns from both sides
-            left_keyed
-                .join(right_keyed)
-                .map(|(_key, (left_tuple, right_tuple))| left_tuple.concat(&right_tuple))
-        } else {
-            // Normal join with actual keys
-            let left_keys = left_keys.to_vec();
-            let right_keys = right_keys.to_vec();
-            let right_keys_clone = right_keys.clone();
-
-            // Map to (key, full_tuple) format - keep full tuples for correct reconstruction
-            let left_keyed = left_coll.map(move |tuple| {
-                let key = tuple.from_indices(&left_keys);
-                (key, tuple)
-            });
-
-            let right_keyed = right_coll.map(move |tuple| {
-                let key = tuple.from_indices(&right_keys_clone);
-                (key, tuple)
-            });
-
-            // Join and reconstruct: all of left + non-key columns of right
-            let right_keys_for_map = right_keys.clone();
-            left_keyed
-                .join(right_keyed)
-                .map(move |(_key, (left_tuple, right_tuple))| {
-                    // Output schema: all columns from left, then non-key columns from right
-                    let right_non_keys = right_tuple.excluding_indices(&right_keys_for_map);
-                    left_tuple.concat(&right_non_keys)
-                })
+        // Output: all of left + non-key columns of right. A Cartesian product
+        // (no keys on either side) concatenates all columns of both sides.
+        // As in SQL, NULL never equals NULL, so a pair joined on a NULL key
+        // is dropped.
+        let output_keys = right_keys.to_vec();
+        let null_keys = left_keys.to_vec();
+        let emit = move |left_tuple: &Tuple, right_tuple: &Tuple| {
+            if Self::has_null_key(left_tuple, &null_keys) {
+                return None;
+            }
+            Some(left_tuple.concat_excluding(right_tuple, &output_keys))
+        };
+        let sides = [left, right];
+        let keys = [left_keys, right_keys];
+        if let JoinAlgorithm::Hash { build } =
+            Self::plan_join(sides, keys, input_data, live, arrangements)
+        {
+            if let Some(table) = Self::hash_table(sides, keys, build, input_data, live) {
+                return Self::hash_join(
+                    scope,
+                    sides,
+                    keys,
+                    build,
+                    table,
+                    input_data,
+                    live,
+                    arrangements,
+                    emit,
+                );
+            }
         }
+
+        let (left_filter, right_filter) =
+            Self::join_prefilters(left, right, left_keys, right_keys, input_data, live, false);
+        let left_arranged = Self::arrange_join_side::<G, R>(
+            scope,
+            left,
+            left_keys,
+            left_filter,
+            input_data,
+            live,
+            arrangements,
+        );
+        let right_arranged = Self::arrange_join_side::<G, R>(
+            scope,
+            right,
+            right_keys,
+            right_filter,
+            input_data,
+            live,
+            arrangements,
+        );
+
+        let interrupt = query_interrupt_token();
+        left_arranged.join_core(right_arranged, move |_key, left_tuple, right_tuple| {
+            if interrupt.is_interrupted() {
+                return None;
+            }
+            emit(left_tuple, right_tuple)
+        })
     }
 
     /// Generate antijoin node (negation): Left - (Left JOIN Right)
@@ -2066,13 +3827,12 @@ impl CodeGenerator {
     ///
     /// ## DD Implementation
     ///
-    /// For stratified negation, the right side is always fully computed before
-    /// the antijoin. We collect all right keys into a set, then filter the left
-    /// collection to exclude tuples whose keys are in the right set.
-    ///
-    /// The left side is generated as a proper DD collection with `live` support
-    /// for derived/recursive relations. The right side is eagerly collected from
-    /// `input_data` and `live` collections.
+    /// Both sides are DD collections of the same dataflow. The right side is
+    /// projected to its key columns and made distinct, and the keyed left side
+    /// is restricted with DD's `antijoin` (left minus its semijoin with the
+    /// keys). Negation therefore shares the dataflow's arrangements, reads
+    /// derived relations through `live`, and is maintained under updates in
+    /// incremental views.
     ///
     /// ## Example
     /// ```text
@@ -2090,147 +3850,133 @@ impl CodeGenerator {
         right_keys: &[usize],
         input_data: &HashMap<String, Vec<Tuple>>,
         live: Option<&HashMap<String, Collection<G, Tuple, R>>>,
+        arrangements: Option<&SharedArrangements<G, R>>,
     ) -> Collection<G, Tuple, R>
     where
         G: Scope,
         G::Timestamp: Lattice + Ord + Default,
     {
-        use std::collections::HashSet;
-
-        // For stratified negation, collect right keys eagerly.
-        // This is correct because the right side is already fully computed.
-        let right_keys_set: HashSet<Tuple> = {
-            let mut set = HashSet::new();
-            Self::collect_tuples_from_ir(right, input_data, live, right_keys, &mut set);
-            set
+        // The left side is never restricted to the right's keys: its
+        // unmatched tuples are the antijoin's output
+        let (left_filter, right_filter) =
+            Self::join_prefilters(left, right, left_keys, right_keys, input_data, live, true);
+        let left_coll =
+            Self::generate_collection_shared::<G, R>(scope, left, input_data, live, arrangements);
+
+        let left_keys = left_keys.to_vec();
+        let right_keys = right_keys.to_vec();
+
+        // Right keys absent from the left cannot remove anything
+        let right_coll = match right_filter {
+            Some(SideFilter::Inputs(restricted)) => {
+                Self::generate_collection_shared::<G, R>(scope, right, &restricted, live, None)
+            }
+            Some(SideFilter::Bloom(filter)) => {
+                let filter_keys = right_keys.clone();
+                Self::generate_collection_shared::<G, R>(
+                    scope,
+                    right,
+                    input_data,
+                    live,
+                    arrangements,
+                )
+                .filter(move |tuple| filter.might_match(&tuple.from_indices(&filter_keys)))
+            }
+            None => Self::generate_collection_shared::<G, R>(
+                scope,
+                right,
+                input_data,
+                live,
+                arrangements,
+            ),
         };
 
-        let left_coll = Self::generate_collection_tuples::<G, R>(scope, left, input_data, live);
-        let left_keys_vec = left_keys.to_vec();
+        // Distinct keys give each matching key multiplicity one, so a left
+        // tuple is removed exactly once however many right tuples match it.
+        // A NULL key matches nothing: right tuples with one remove nothing,
+        // and left tuples with one are always kept (SQL `NOT EXISTS`).
+        let null_keys = right_keys.clone();
+        let key_set = Self::track_memory(
+            right_coll
+                .filter(move |tuple| !Self::has_null_key(tuple, &null_keys))
+                .map(move |tuple| tuple.from_indices(&right_keys)),
+        )
+        .distinct_core::<R>();
 
-        // Filter left to only keep tuples whose key is NOT in right set
-        left_coll.filter(move |tuple| {
-            let key = tuple.from_indices(&left_keys_vec);
-            !right_keys_set.contains(&key)
-        })
-    }
+        let Some(SideFilter::Bloom(filter)) = left_filter else {
+            return Self::track_memory(left_coll)
+                .map(move |tuple| (tuple.from_indices(&left_keys), tuple))
+                .antijoin(key_set)
+                .map(|(_key, tuple)| tuple);
+        };
 
-    /// Helper function to recursively collect tuples from an IR node into a `HashSet`.
+        // Left tuples whose key the filter rules out have no match, so they
+        // bypass the antijoin's arrangement
+        let filter_keys = left_keys.clone();
+        let tagged = left_coll.map(move |tuple| {
+            let candidate = filter.might_match(&tuple.from_indices(&filter_keys));
+            (candidate, tuple)
+        });
+        let unmatched = tagged
+            .clone()
+            .filter(|(candidate, _)| !candidate)
+            .map(|(_, tuple)| tuple);
+        let candidates = tagged
+            .filter(|(candidate, _)| *candidate)
+            .map(|(_, tuple)| tuple);
+        Self::track_memory(candidates)
+            .map(move |tuple| (tuple.from_indices(&left_keys), tuple))
+            .antijoin(key_set)
+            .map(|(_key, tuple)| tuple)
+            .concat(unmatched)
+    }
+
+    /// Generate semijoin node: Left tuples with at least one match in Right
     ///
-    /// Checks both `input_data` (static base relation data) and `live` collections
-    /// (derived relations from recursive/session scopes).
-    fn collect_tuples_from_ir<G, R: DiffType>(
-        node: &IRNode,
-        input_data: &HashMap<String, Vec<Tuple>>,
-        live: Option<&HashMap<String, Collection<G, Tuple, R>>>,
-        key_indices: &[usize],
-        result: &mut std::collections::HashSet<Tuple>,
-    ) where
-        G: Scope,
-        G::Timestamp: Lattice + Ord + Default,
-    {
-        match node {
-            IRNode::Scan { relation, .. } => {
-                if let Some(tuples) = input_data.get(relation) {
-                    for tuple in tuples {
-                        let key = tuple.from_indices(key_indices);
-                        result.insert(key);
-                    }
-                }
-                // Also check live collections for derived relations
-                if let Some(live_map) = live {
-                    if live_map.contains_key(relation) && !input_data.contains_key(relation) {
-                        // The relation exists only as a live DD collection.
-                        // For stratified negation, this should not happen (the right side
-                        // of negation must be in a lower stratum and already materialized).
-                        // Log a debug warning if it does occur.
-                        if std::env::var("IL_DEBUG").is_ok() {
-                            eprintln!(
-                                "DEBUG antijoin: relation '{relation}' is live-only (not in input_data), \
-                                 negation may be incomplete"
-                            );
-                        }
-                    }
-                }
-            }
-            IRNode::Distinct { input } => {
-                Self::collect_tuples_from_ir(input, input_data, live, key_indices, result);
-            }
-            IRNode::Union { inputs } => {
-                for input in inputs {
-                    Self::collect_tuples_from_ir(input, input_data, live, key_indices, result);
-                }
-            }
-            // For complex nodes like Join, execute a sub-dataflow to materialize tuples
-            _ => {
-                let tuples = Self::execute_subquery_for_antijoin(node, input_data, live);
-                for tuple in tuples {
-                    let key = tuple.from_indices(key_indices);
-                    result.insert(key);
-                }
-            }
-        }
-    }
-
-    /// Execute a subquery to collect tuples for antijoin.
+    /// ## DD Implementation
     ///
-    /// For complex right-side IR nodes (joins, aggregates), we execute the
-    /// sub-dataflow to materialize all tuples. The `live` parameter is passed
-    /// through so that derived relations are available during execution.
-    fn execute_subquery_for_antijoin<G, R: DiffType>(
-        node: &IRNode,
+    /// The right side is projected to its key columns and made distinct, so
+    /// each key contributes multiplicity one regardless of how many right
+    /// tuples share it. The left side is keyed by its join columns and
+    /// restricted with DD's `semijoin`, which keeps the full left tuple.
+    /// Both sides stay live DD collections, so semijoins are safe inside
+    /// recursive scopes.
+    fn generate_semijoin_tuples<G, R: DiffType>(
+        scope: &mut G,
+        left: &IRNode,
+        right: &IRNode,
+        left_keys: &[usize],
+        right_keys: &[usize],
         input_data: &HashMap<String, Vec<Tuple>>,
-        _live: Option<&HashMap<String, Collection<G, Tuple, R>>>,
-    ) -> Vec<Tuple>
+        live: Option<&HashMap<String, Collection<G, Tuple, R>>>,
+        arrangements: Option<&SharedArrangements<G, R>>,
+    ) -> Collection<G, Tuple, R>
     where
         G: Scope,
         G::Timestamp: Lattice + Ord + Default,
     {
-        // Execute in a fresh timely context. Note: live collections from the
-        // outer scope cannot cross into this new context, so we pass None.
-        // For stratified negation, this is correct because the right side
-        // must already be fully materialized in input_data.
-        let results = Arc::new(Mutex::new(Vec::new()));
-        let results_clone = Arc::clone(&results);
-        let node_clone = node.clone();
-        let input_data_clone = input_data.clone();
-
-        let dd_result = catch_unwind(AssertUnwindSafe(|| {
-            timely::execute_directly(move |worker| {
-                worker.dataflow::<(), _, _>(|scope| {
-                    let coll = Self::generate_collection_tuples::<_, isize>(
-                        scope,
-                        &node_clone,
-                        &input_data_clone,
-                        None,
-                    );
-                    let results_ref = Arc::clone(&results_clone);
-                    coll.inner.inspect(move |(tuple, _time, diff)| {
-                        if *diff > 0 {
-                            results_ref.lock().push(tuple.clone());
-                        }
-                    });
-                });
-                // Step until complete
-                while worker.step() {
-                    std::thread::yield_now();
-                }
-            });
-        }));
-
-        if let Err(e) = dd_result {
-            tracing::error!(
-                error = %format_panic_payload(e),
-                "Antijoin subquery panicked"
-            );
-            return Vec::new();
-        }
-
-        // Safely extract results from Arc<Mutex<Vec<Tuple>>>
-        match Arc::try_unwrap(results) {
-            Ok(mutex) => mutex.into_inner(),
-            Err(arc) => arc.lock().clone(),
-        }
+        let left_coll =
+            Self::generate_collection_shared::<G, R>(scope, left, input_data, live, arrangements);
+        let right_coll =
+            Self::generate_collection_shared::<G, R>(scope, right, input_data, live, arrangements);
+
+        let left_keys = left_keys.to_vec();
+        let right_keys = right_keys.to_vec();
+
+        // A NULL key matches nothing, so right tuples with one are dropped
+        let null_keys = right_keys.clone();
+        let key_set = right_coll
+            .filter(move |tuple| !Self::has_null_key(tuple, &null_keys))
+            .map(move |tuple| tuple.from_indices(&right_keys))
+            .distinct_core::<R>();
+
+        Self::track_memory(left_coll)
+            .map(move |tuple| {
+                let key = tuple.from_indices(&left_keys);
+                (key, tuple)
+            })
+            .semijoin(key_set)
+            .map(|(_key, tuple)| tuple)
     }
 
     /// Generate distinct node (production)
@@ -2239,13 +3985,15 @@ impl CodeGenerator {
         input: &IRNode,
         input_data: &HashMap<String, Vec<Tuple>>,
         live: Option<&HashMap<String, Collection<G, Tuple, R>>>,
+        arrangements: Option<&SharedArrangements<G, R>>,
     ) -> Collection<G, Tuple, R>
     where
         G: Scope,
         G::Timestamp: Lattice + Ord + Default,
     {
-        let input_coll = Self::generate_collection_tuples::<G, R>(scope, input, input_data, live);
-        input_coll.distinct_core::<R>()
+        let input_coll =
+            Self::generate_collection_shared::<G, R>(scope, input, input_data, live, arrangements);
+        Self::track_memory(input_coll).distinct_core::<R>()
     }
 
     /// Generate union node (production)
@@ -2258,6 +4006,7 @@ impl CodeGenerator {
         inputs: &[IRNode],
         input_data: &HashMap<String, Vec<Tuple>>,
         live: Option<&HashMap<String, Collection<G, Tuple, R>>>,
+        arrangements: Option<&SharedArrangements<G, R>>,
     ) -> Collection<G, Tuple, R>
     where
         G: Scope,
@@ -2271,11 +4020,22 @@ impl CodeGenerator {
             );
         }
 
-        let mut result =
-            Self::generate_collection_tuples::<G, R>(scope, &inputs[0], input_data, live);
+        let mut result = Self::generate_collection_shared::<G, R>(
+            scope,
+            &inputs[0],
+            input_data,
+            live,
+            arrangements,
+        );
 
         for input in &inputs[1..] {
-            let coll = Self::generate_collection_tuples::<G, R>(scope, input, input_data, live);
+            let coll = Self::generate_collection_shared::<G, R>(
+                scope,
+                input,
+                input_data,
+                live,
+                arrangements,
+            );
             result = result.concat(coll);
         }
 
@@ -2290,11 +4050,13 @@ impl CodeGenerator {
             IRNode::Map { input, .. }
             | IRNode::Filter { input, .. }
             | IRNode::Distinct { input }
+            | IRNode::Sort { input, .. }
             | IRNode::Aggregate { input, .. }
             | IRNode::Compute { input, .. }
             | IRNode::FlatMap { input, .. } => Self::references_relation(input, relation),
             IRNode::Join { left, right, .. }
             | IRNode::Antijoin { left, right, .. }
+            | IRNode::Semijoin { left, right, .. }
             | IRNode::JoinFlatMap { left, right, .. } => {
                 Self::references_relation(left, relation)
                     || Self::references_relation(right, relation)
@@ -2313,6 +4075,74 @@ impl CodeGenerator {
         Self::detect_recursive_union_for_relation(inputs, None)
     }
 
+    /// Collect the names of all relations (and HNSW indexes) an IR reads
+    pub(crate) fn collect_scans(ir: &IRNode, scans: &mut Vec<String>) {
+        match ir {
+            IRNode::Scan { relation, .. } => {
+                scans.push(relation.clone());
+            }
+            IRNode::HnswScan { index_name, .. } => {
+                scans.push(index_name.clone());
+            }
+            IRNode::Map { input, .. }
+            | IRNode::Filter { input, .. }
+            | IRNode::Distinct { input }
+            | IRNode::Sort { input, .. }
+            | IRNode::Aggregate { input, .. }
+            | IRNode::Compute { input, .. }
+            | IRNode::FlatMap { input, .. } => {
+                Self::collect_scans(input, scans);
+            }
+            IRNode::Join { left, right, .. }
+            | IRNode::Antijoin { left, right, .. }
+            | IRNode::Semijoin { left, right, .. }
+            | IRNode::JoinFlatMap { left, right, .. } => {
+                Self::collect_scans(left, scans);
+                Self::collect_scans(right, scans);
+            }
+            IRNode::Union { inputs } => {
+                for inp in inputs {
+                    Self::collect_scans(inp, scans);
+                }
+            }
+        }
+    }
+
+    /// Stream the base relations read by `bodies` into `scope` once each, so
+    /// every scan of a relation inside a recursive scope shares one
+    /// collection. Relations in `bound` (the scope's `Variable`s) are skipped.
+    fn scoped_inputs<G, R: DiffType>(
+        scope: &mut G,
+        bodies: &[&IRNode],
+        bound: &[&str],
+        input_data: &HashMap<String, Vec<Tuple>>,
+    ) -> HashMap<String, Collection<G, Tuple, R>>
+    where
+        G: Scope,
+        G::Timestamp: Lattice + Ord + Default,
+    {
+        let mut scans = Vec::new();
+        for body in bodies {
+            Self::collect_scans(body, &mut scans);
+        }
+
+        let mut live = HashMap::new();
+        for relation in scans {
+            if bound.contains(&relation.as_str()) || live.contains_key(&relation) {
+                continue;
+            }
+            if let Some(tuples) = input_data.get(&relation) {
+                let coll: Collection<G, Tuple, R> = Collection::new(
+                    Self::worker_share(tuples, scope.index(), scope.peers())
+                        .to_stream(scope)
+                        .map(|x| (x, Default::default(), R::one())),
+                );
+                live.insert(relation, coll);
+            }
+        }
+        live
+    }
+
     /// Detect recursion for a specific relation in a Union node
     ///
     /// If `expected_relation` is Some, only that relation is considered for recursion.
@@ -2326,55 +4156,20 @@ impl CodeGenerator {
         // Find all relations that are scanned by each input
         let mut scan_relations: HashMap<String, Vec<usize>> = HashMap::new();
 
-        fn collect_scans(ir: &IRNode, scans: &mut Vec<String>) {
-            match ir {
-                IRNode::Scan { relation, .. } => {
-                    scans.push(relation.clone());
-                }
-                IRNode::HnswScan { index_name, .. } => {
-                    scans.push(index_name.clone());
-                }
-                IRNode::Map { input, .. }
-                | IRNode::Filter { input, .. }
-                | IRNode::Distinct { input }
-                | IRNode::Aggregate { input, .. }
-                | IRNode::Compute { input, .. }
-                | IRNode::FlatMap { input, .. } => {
-                    collect_scans(input, scans);
-                }
-                IRNode::Join { left, right, .. }
-                | IRNode::Antijoin { left, right, .. }
-                | IRNode::JoinFlatMap { left, right, .. } => {
-                    collect_scans(left, scans);
-                    collect_scans(right, scans);
-                }
-                IRNode::Union { inputs } => {
-                    for inp in inputs {
-                        collect_scans(inp, scans);
-                    }
-                }
-            }
-        }
-
         for (i, input) in inputs.iter().enumerate() {
             let mut scans = Vec::new();
-            collect_scans(input, &mut scans);
+            Self::collect_scans(input, &mut scans);
             for rel in scans {
                 scan_relations.entry(rel).or_default().push(i);
             }
         }
 
-        // DEBUG: Log what we found
-        if std::env::var("IL_DEBUG").is_ok() {
-            eprintln!(
-                "DEBUG detect_recursive_union: {} inputs, scan_relations = {:?}",
-                inputs.len(),
-                scan_relations
-            );
-            if let Some(expected) = expected_relation {
-                eprintln!("DEBUG: expected_relation = {expected}");
-            }
-        }
+        trace!(
+            inputs = inputs.len(),
+            ?scan_relations,
+            ?expected_relation,
+            "detect_recursive_union"
+        );
 
         // If we have an expected relation, only check that one
         if let Some(expected) = expected_relation {
@@ -2418,17 +4213,19 @@ impl CodeGenerator {
         aggregations: &[(AggregateFunction, usize)],
         input_data: &HashMap<String, Vec<Tuple>>,
         live: Option<&HashMap<String, Collection<G, Tuple, R>>>,
+        arrangements: Option<&SharedArrangements<G, R>>,
     ) -> Collection<G, Tuple, R>
     where
         G: Scope,
         G::Timestamp: Lattice + Ord + Default,
     {
-        let input_coll = Self::generate_collection_tuples::<G, R>(scope, input, input_data, live);
+        let input_coll =
+            Self::generate_collection_shared::<G, R>(scope, input, input_data, live, arrangements);
         let group_by = group_by.to_vec();
         let aggregations = aggregations.to_vec();
 
         // Map to (group_key, value_tuple) pairs
-        let keyed = input_coll.map(move |tuple| {
+        let keyed = Self::track_memory(input_coll).map(move |tuple| {
             // Extract group-by columns as key
             let key = tuple.project(&group_by);
             // Keep entire tuple as value for aggregation
@@ -2727,51 +4524,109 @@ impl CodeGenerator {
         expressions: &[(String, IRExpression)],
         input_data: &HashMap<String, Vec<Tuple>>,
         live: Option<&HashMap<String, Collection<G, Tuple, R>>>,
+        arrangements: Option<&SharedArrangements<G, R>>,
     ) -> Collection<G, Tuple, R>
     where
         G: Scope,
         G::Timestamp: Lattice + Ord + Default,
     {
-        let input_coll = Self::generate_collection_tuples::<G, R>(scope, input, input_data, live);
+        let input_coll =
+            Self::generate_collection_shared::<G, R>(scope, input, input_data, live, arrangements);
         let expressions = expressions.to_vec();
 
+        // Generators fan each row out into one row per element
+        if expressions.iter().any(|(_, expr)| Self::is_generator(expr)) {
+            return input_coll.flat_map(move |tuple| Self::compute_rows(tuple, &expressions));
+        }
+
         input_coll.map(move |tuple| {
             // Evaluate each expression and append to tuple
             // Use a growing tuple so chained computed columns work:
             // e.g., Q = quantize(V), D = dequantize(Q) - D needs to see Q
             let mut current_tuple = tuple.clone();
 
-            if std::env::var("IL_DEBUG").is_ok() {
-                eprintln!("DEBUG Compute: input tuple = {:?}", current_tuple.values());
-            }
-
             for (name, expr) in &expressions {
                 let value = Self::evaluate_expression(expr, &current_tuple);
-                if std::env::var("IL_DEBUG").is_ok() {
-                    eprintln!("DEBUG Compute: expr='{name}' => {value:?}");
-                }
+                trace!(expr = %name, ?value, "codegen_compute");
                 // Extend the current tuple with the computed value
                 // so subsequent expressions can reference it
-                let mut values: Vec<Value> = current_tuple.values().to_vec();
-                values.push(value);
-                current_tuple = Tuple::new(values);
-            }
-
-            if std::env::var("IL_DEBUG").is_ok() {
-                eprintln!("DEBUG Compute: output = {:?}", current_tuple.values());
+                current_tuple.push(value);
             }
 
             current_tuple
         })
     }
 
-    /// Evaluate an IR expression against a tuple
-    fn evaluate_expression(expr: &IRExpression, tuple: &Tuple) -> Value {
+    /// Evaluate an IR expression against a tuple
+    /// Whether `expr` is a generator call (`list_elem`, `map_key`, `json_array_elements`)
+    fn is_generator(expr: &IRExpression) -> bool {
+        matches!(
+            expr,
+            IRExpression::FunctionCall(
+                BuiltinFunction::ListElem
+                    | BuiltinFunction::MapKey
+                    | BuiltinFunction::JsonArrayElements,
+                _
+            )
+        )
+    }
+
+    /// The values a generator binds its variable to, one per output row.
+    /// A NULL or non-collection argument produces no rows.
+    fn generator_values(expr: &IRExpression, tuple: &Tuple) -> Option<Vec<Value>> {
+        let IRExpression::FunctionCall(func, args) = expr else {
+            return None;
+        };
+        let collection = || {
+            args.first()
+                .map_or(Value::Null, |arg| Self::evaluate_expression(arg, tuple))
+        };
+        match func {
+            BuiltinFunction::ListElem => Some(match collection() {
+                Value::List(items) => items.as_ref().clone(),
+                _ => Vec::new(),
+            }),
+            BuiltinFunction::MapKey => Some(match collection() {
+                Value::Map(entries) => entries.keys().map(|k| Value::string(k)).collect(),
+                _ => Vec::new(),
+            }),
+            BuiltinFunction::JsonArrayElements => Some(
+                crate::json_ops::parse(&collection())
+                    .map(|doc| crate::json_ops::array_elements(&doc))
+                    .unwrap_or_default(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Append computed columns to `tuple`, expanding generators: each
+    /// generator multiplies the rows by the number of elements it yields.
+    /// Later expressions see the columns computed before them.
+    pub(crate) fn compute_rows(tuple: Tuple, expressions: &[(String, IRExpression)]) -> Vec<Tuple> {
+        let mut rows = vec![tuple];
+        for (_, expr) in expressions {
+            rows = rows
+                .into_iter()
+                .flat_map(|row| {
+                    let values = Self::generator_values(expr, &row)
+                        .unwrap_or_else(|| vec![Self::evaluate_expression(expr, &row)]);
+                    values.into_iter().map(move |value| {
+                        let mut extended = row.clone();
+                        extended.push(value);
+                        extended
+                    })
+                })
+                .collect();
+        }
+        rows
+    }
+
+    pub(crate) fn evaluate_expression(expr: &IRExpression, tuple: &Tuple) -> Value {
         match expr {
             IRExpression::Column(idx) => tuple.get(*idx).cloned().unwrap_or(Value::Null),
             IRExpression::IntConstant(val) => Value::Int64(*val),
             IRExpression::FloatConstant(val) => Value::Float64(*val),
-            IRExpression::StringConstant(s) => Value::String(s.clone().into()),
+            IRExpression::StringConstant(s) => Value::interned(s),
             IRExpression::BoolConstant(b) => Value::Bool(*b),
             IRExpression::VectorLiteral(vals) => Value::vector(vals.clone()),
             IRExpression::FunctionCall(func, args) => Self::evaluate_function(func, args, tuple),
@@ -3276,6 +5131,145 @@ impl CodeGenerator {
                 }
                 Value::Null
             }
+            BuiltinFunction::Date => match arg_values.first() {
+                Some(Value::String(s)) => {
+                    temporal_ops::parse_date(s).map_or(Value::Null, Value::Date)
+                }
+                Some(Value::Date(d)) => Value::Date(*d),
+                // Timestamps (or raw millisecond integers) truncate to their UTC day
+                Some(v) => v.as_timestamp().map_or(Value::Null, |ts| {
+                    Value::Date(temporal_ops::timestamp_to_date(ts))
+                }),
+                None => Value::Null,
+            },
+            BuiltinFunction::Duration => match arg_values.first() {
+                Some(Value::String(s)) => {
+                    temporal_ops::parse_duration(s).map_or(Value::Null, Value::Duration)
+                }
+                // Integers are taken as milliseconds
+                Some(v @ (Value::Duration(_) | Value::Int32(_) | Value::Int64(_))) => {
+                    v.as_i64().map_or(Value::Null, Value::Duration)
+                }
+                _ => Value::Null,
+            },
+
+            // UUID functions
+            BuiltinFunction::Uuid => match arg_values.first() {
+                Some(Value::String(s)) => Value::uuid(s).unwrap_or(Value::Null),
+                Some(v @ Value::Uuid(_)) => v.clone(),
+                _ => Value::Null,
+            },
+            BuiltinFunction::UuidV7 => Value::Uuid(uuid::Uuid::now_v7()),
+
+            // Null handling functions
+            BuiltinFunction::IsNull => {
+                Value::Bool(arg_values.first().is_none_or(super::value::Value::is_null))
+            }
+            BuiltinFunction::Coalesce => arg_values
+                .iter()
+                .find(|v| !v.is_null())
+                .cloned()
+                .unwrap_or(Value::Null),
+
+            // List and map functions
+            BuiltinFunction::List => Value::list(arg_values),
+            BuiltinFunction::Map => {
+                // Pairs with a non-string key are dropped
+                let entries = arg_values
+                    .chunks_exact(2)
+                    .filter_map(|pair| Some((pair[0].as_str()?, pair[1].clone())));
+                Value::map(entries)
+            }
+            BuiltinFunction::ListGet => {
+                let item = match (arg_values.first(), arg_values.get(1)) {
+                    (Some(Value::List(items)), Some(index)) => index
+                        .as_i64()
+                        .and_then(|i| usize::try_from(i).ok())
+                        .and_then(|i| items.get(i)),
+                    _ => None,
+                };
+                item.cloned().unwrap_or(Value::Null)
+            }
+            BuiltinFunction::ListLen => match arg_values.first() {
+                Some(Value::List(items)) => Value::Int64(items.len() as i64),
+                _ => Value::Null,
+            },
+            BuiltinFunction::MapGet => {
+                let value = match (arg_values.first(), arg_values.get(1)) {
+                    (Some(Value::Map(entries)), Some(key)) => {
+                        key.as_str().and_then(|k| entries.get(k))
+                    }
+                    _ => None,
+                };
+                value.cloned().unwrap_or(Value::Null)
+            }
+            // Generators are expanded by `compute_rows`; nested inside
+            // another expression they have no single value
+            BuiltinFunction::ListElem
+            | BuiltinFunction::MapKey
+            | BuiltinFunction::JsonArrayElements => Value::Null,
+
+            // JSON functions accept JSON values or JSON text in strings
+            BuiltinFunction::JsonParse => match arg_values.first() {
+                Some(Value::String(s)) => Value::json(s).unwrap_or(Value::Null),
+                Some(v @ Value::Json(_)) => v.clone(),
+                _ => Value::Null,
+            },
+            BuiltinFunction::JsonExtract => {
+                let doc = arg_values.first().and_then(crate::json_ops::parse);
+                let path = arg_values
+                    .get(1)
+                    .and_then(Value::as_str)
+                    .and_then(|p| crate::json_ops::parse_path(p).ok());
+                match (doc, path) {
+                    (Some(doc), Some(path)) => crate::json_ops::extract(&doc, &path)
+                        .map_or(Value::Null, crate::json_ops::to_value),
+                    _ => Value::Null,
+                }
+            }
+            // Bytes functions
+            BuiltinFunction::Hex => match arg_values.first() {
+                Some(Value::String(s)) => Value::from_hex(s).unwrap_or(Value::Null),
+                Some(v @ Value::Bytes(_)) => v.clone(),
+                _ => Value::Null,
+            },
+            BuiltinFunction::Base64 => match arg_values.first() {
+                Some(Value::String(s)) => Value::from_base64(s).unwrap_or(Value::Null),
+                Some(v @ Value::Bytes(_)) => v.clone(),
+                _ => Value::Null,
+            },
+            BuiltinFunction::ToHex => arg_values
+                .first()
+                .and_then(Value::as_bytes)
+                .map_or(Value::Null, |b| {
+                    Value::string(&crate::bytes_ops::encode_hex(b))
+                }),
+            BuiltinFunction::ToBase64 => arg_values
+                .first()
+                .and_then(Value::as_bytes)
+                .map_or(Value::Null, |b| {
+                    Value::string(&crate::bytes_ops::encode_base64(b))
+                }),
+            BuiltinFunction::BytesLen => arg_values
+                .first()
+                .and_then(Value::as_bytes)
+                .map_or(Value::Null, |b| Value::Int64(b.len() as i64)),
+            BuiltinFunction::BytesSlice => {
+                let bytes = arg_values.first().and_then(Value::as_bytes);
+                let start = arg_values.get(1).and_then(Value::as_i64);
+                let len = arg_values.get(2).and_then(Value::as_i64);
+                match (bytes, start, len) {
+                    (Some(b), Some(start), Some(len)) => crate::bytes_ops::slice(b, start, len)
+                        .map_or(Value::Null, |s| Value::bytes(s.to_vec())),
+                    _ => Value::Null,
+                }
+            }
+            BuiltinFunction::JsonType => arg_values
+                .first()
+                .and_then(crate::json_ops::parse)
+                .map_or(Value::Null, |doc| {
+                    Value::string(crate::json_ops::type_name(&doc))
+                }),
 
             // Math utility functions
             BuiltinFunction::AbsInt64 => {
@@ -3506,8 +5500,65 @@ impl CodeGenerator {
         }
     }
 
+    /// Evaluate arithmetic on dates, timestamps and durations.
+    ///
+    /// Returns `None` when neither operand is temporal so the caller falls
+    /// back to numeric arithmetic; unsupported temporal combinations and
+    /// overflow produce `Some(Value::Null)`.
+    fn evaluate_temporal_arithmetic(op: ArithOp, left: &Value, right: &Value) -> Option<Value> {
+        let int = |v: &Value| match v {
+            Value::Int32(n) => Some(i64::from(*n)),
+            Value::Int64(n) => Some(*n),
+            _ => None,
+        };
+        let result = match (op, left, right) {
+            (ArithOp::Add, Value::Date(d), Value::Duration(ms))
+            | (ArithOp::Add, Value::Duration(ms), Value::Date(d)) => {
+                Some(Value::Date(temporal_ops::date_add(*d, *ms)))
+            }
+            (ArithOp::Sub, Value::Date(d), Value::Duration(ms)) => ms
+                .checked_neg()
+                .map(|neg| Value::Date(temporal_ops::date_add(*d, neg))),
+            (ArithOp::Sub, Value::Date(a), Value::Date(b)) => Some(Value::Duration(
+                (i64::from(*a) - i64::from(*b)) * temporal_ops::MS_PER_DAY,
+            )),
+            (ArithOp::Add, Value::Timestamp(ts), Value::Duration(ms))
+            | (ArithOp::Add, Value::Duration(ms), Value::Timestamp(ts)) => {
+                ts.checked_add(*ms).map(Value::Timestamp)
+            }
+            (ArithOp::Sub, Value::Timestamp(ts), Value::Duration(ms)) => {
+                ts.checked_sub(*ms).map(Value::Timestamp)
+            }
+            (ArithOp::Sub, Value::Timestamp(a), Value::Timestamp(b)) => {
+                a.checked_sub(*b).map(Value::Duration)
+            }
+            (ArithOp::Add, Value::Duration(a), Value::Duration(b)) => {
+                a.checked_add(*b).map(Value::Duration)
+            }
+            (ArithOp::Sub, Value::Duration(a), Value::Duration(b)) => {
+                a.checked_sub(*b).map(Value::Duration)
+            }
+            (ArithOp::Mul, Value::Duration(ms), n) | (ArithOp::Mul, n, Value::Duration(ms))
+                if int(n).is_some() =>
+            {
+                int(n).and_then(|n| ms.checked_mul(n)).map(Value::Duration)
+            }
+            (ArithOp::Div, Value::Duration(ms), n) if int(n).is_some() => {
+                int(n).and_then(|n| ms.checked_div(n)).map(Value::Duration)
+            }
+            (_, Value::Date(_) | Value::Duration(_), _)
+            | (_, _, Value::Date(_) | Value::Duration(_)) => None,
+            // Plain timestamp arithmetic keeps its historical numeric behaviour
+            _ => return None,
+        };
+        Some(result.unwrap_or(Value::Null))
+    }
+
     /// Evaluate arithmetic operation
     fn evaluate_arithmetic(op: ArithOp, left: &Value, right: &Value) -> Value {
+        if let Some(result) = Self::evaluate_temporal_arithmetic(op, left, right) {
+            return result;
+        }
         let l = left.to_f64();
         let r = right.to_f64();
 
@@ -3591,6 +5642,7 @@ impl CodeGenerator {
 
         // Iterate until fixpoint
         while changed {
+            self.check_timeout(tc.len())?;
             changed = false;
             let current: Vec<(i64, i64)> = tc.iter().copied().collect();
 
@@ -3733,8 +5785,10 @@ impl CodeGenerator {
         let edge_data = edges.clone();
 
         // Execute DD computation with TRUE recursion
+        let query = self.query_context();
         catch_unwind(AssertUnwindSafe(|| {
             timely::execute_directly(move |worker| {
+                let _query = QueryScope::install(query.clone());
                 let probe = ProbeHandle::new();
 
                 worker.dataflow::<(), _, _>(|scope| {
@@ -3777,7 +5831,7 @@ impl CodeGenerator {
                         let next = edges_in_scope.concat(recursive).distinct();
 
                         // Set variable for next iteration
-                        variable.set(next.clone());
+                        variable.set(Self::interruptible(next.clone()));
 
                         // Leave scope with final result
                         next.leave()
@@ -3800,12 +5854,15 @@ impl CodeGenerator {
 
                 // Wait for computation to complete
                 while !probe.done() {
-                    if is_query_cancelled() {
+                    if is_query_interrupted() {
                         break;
                     }
                     worker.step();
                     std::thread::yield_now();
                 }
+                if is_query_interrupted() {
+                    drop_dataflows(worker);
+                }
             });
         }))
         .map_err(|e| {
@@ -3815,7 +5872,10 @@ impl CodeGenerator {
             )
         })?;
 
-        if is_query_cancelled() {
+        self.check_memory_budget()?;
+        self.check_timeout(results.lock().len())?;
+
+        if self.is_cancelled() {
             // If we hit the result limit, the cancel was self-triggered - return results
             let collected = results.lock().len();
             if result_limit == 0 || collected < result_limit {
@@ -3870,8 +5930,10 @@ impl CodeGenerator {
         let result_limit = self.max_result_rows;
 
         // Execute DD computation with TRUE recursion
+        let query = self.query_context();
         catch_unwind(AssertUnwindSafe(|| {
             timely::execute_directly(move |worker| {
+                let _query = QueryScope::install(query.clone());
                 let probe = ProbeHandle::new();
 
                 worker.dataflow::<(), _, _>(|scope| {
@@ -3916,7 +5978,7 @@ impl CodeGenerator {
                         let next = sources_in_scope.concat(recursive).distinct();
 
                         // Set variable for next iteration
-                        variable.set(next.clone());
+                        variable.set(Self::interruptible(next.clone()));
 
                         // Leave scope with final result
                         next.leave()
@@ -3939,12 +6001,15 @@ impl CodeGenerator {
 
                 // Wait for computation to complete
                 while !probe.done() {
-                    if is_query_cancelled() {
+                    if is_query_interrupted() {
                         break;
                     }
                     worker.step();
                     std::thread::yield_now();
                 }
+                if is_query_interrupted() {
+                    drop_dataflows(worker);
+                }
             });
         }))
         .map_err(|e| {
@@ -3954,7 +6019,10 @@ impl CodeGenerator {
             )
         })?;
 
-        if is_query_cancelled() {
+        self.check_memory_budget()?;
+        self.check_timeout(results.lock().len())?;
+
+        if self.is_cancelled() {
             // If we hit the result limit, the cancel was self-triggered - return results
             let collected = results.lock().len();
             if result_limit == 0 || collected < result_limit {
@@ -4654,6 +6722,343 @@ mod tests {
         assert!(results.contains(&4i64));
     }
 
+    fn pair_scan(relation: &str, x: &str, y: &str) -> IRNode {
+        IRNode::Scan {
+            relation: relation.to_string(),
+            schema: vec![x.to_string(), y.to_string()],
+        }
+    }
+
+    /// Run `ir` over a small `hub` and a large `edge` relation, with and
+    /// without bloom pre-filtering; returns both results and the metrics.
+    fn run_with_prefilter(
+        ir: &IRNode,
+        prefilter: JoinPrefilter,
+    ) -> (Vec<Tuple>, Vec<Tuple>, PrefilterMetrics) {
+        let hub = edges(&[(3, 30), (7, 70), (11, 110)]);
+        let big: Vec<(i64, i64)> = (0..2000).map(|i| (i % 500, i)).collect();
+
+        let run = |prefilter: Option<JoinPrefilter>| {
+            let mut codegen = CodeGenerator::new();
+            codegen.add_input("hub".to_string(), hub.clone());
+            codegen.add_input("edge".to_string(), edges(&big));
+            codegen.set_join_prefilter(prefilter);
+            // Hash joins never arrange, so there is nothing to pre-filter
+            codegen.set_join_algorithms(JoinAlgorithmConfig {
+                max_hash_build_rows: 0,
+            });
+            let mut results = codegen.execute(ir).unwrap();
+            results.sort();
+            results
+        };
+
+        let filtered = run(Some(prefilter.clone()));
+        (filtered, run(None), prefilter.metrics())
+    }
+
+    #[test]
+    fn test_join_prefilter_keeps_matches() {
+        let ir = IRNode::Join {
+            left: Box::new(pair_scan("hub", "x", "h")),
+            right: Box::new(pair_scan("edge", "x", "y")),
+            left_keys: vec![0],
+            right_keys: vec![0],
+            output_schema: vec!["x".to_string(), "h".to_string(), "y".to_string()],
+        };
+        // Without runtime filters the scan of `edge` is bloom-filtered
+        let prefilter = JoinPrefilter::new(100, 10, 0.01).with_runtime_filter_keys(0);
+        let (filtered, unfiltered, metrics) = run_with_prefilter(&ir, prefilter);
+
+        assert_eq!(filtered.len(), 12);
+        assert_eq!(filtered, unfiltered);
+        assert_eq!(metrics.filters_built, 1);
+        assert_eq!(metrics.probed, 2000);
+        assert!(metrics.rejected() > 1000, "{metrics:?}");
+    }
+
+    #[test]
+    fn test_antijoin_prefilter_keeps_unmatched() {
+        let ir = IRNode::Antijoin {
+            left: Box::new(pair_scan("edge", "x", "y")),
+            right: Box::new(pair_scan("hub", "x", "h")),
+            left_keys: vec![0],
+            right_keys: vec![0],
+            output_schema: vec!["x".to_string(), "y".to_string()],
+        };
+        let (filtered, unfiltered, metrics) =
+            run_with_prefilter(&ir, JoinPrefilter::new(100, 10, 0.01));
+
+        assert_eq!(filtered.len(), 2000 - 12);
+        assert_eq!(filtered, unfiltered);
+        assert_eq!(metrics.probed, 2000);
+        assert_eq!(metrics.runtime_filters, 0);
+        assert!(metrics.hit_rate() < 0.5, "{metrics:?}");
+    }
+
+    #[test]
+    fn test_runtime_filter_restricts_probe_scan() {
+        // edge(x, y), y < 10 has keys 0..10; only 40 of the 2000 rows of the
+        // other scan share them
+        let ir = IRNode::Join {
+            left: Box::new(IRNode::Filter {
+                input: Box::new(pair_scan("edge", "x", "y")),
+                predicate: Predicate::ColumnLtConst(1, 10),
+            }),
+            right: Box::new(pair_scan("edge", "x", "z")),
+            left_keys: vec![0],
+            right_keys: vec![0],
+            output_schema: vec!["x".to_string(), "y".to_string(), "z".to_string()],
+        };
+        let (filtered, unfiltered, metrics) =
+            run_with_prefilter(&ir, JoinPrefilter::new(100, 10, 0.01));
+
+        assert_eq!(filtered.len(), 40);
+        assert_eq!(filtered, unfiltered);
+        assert_eq!(metrics.runtime_filters, 1);
+        assert_eq!(metrics.scan_rows_skipped, 2000 - 40);
+        assert_eq!(metrics.filters_built, 0);
+    }
+
+    /// Run `ir` over `edge`, as a hash join when `hash` is set and as a merge
+    /// join otherwise
+    fn run_with_join_algorithm(ir: &IRNode, recursive: Option<&str>, hash: bool) -> Vec<Tuple> {
+        let chain: Vec<(i64, i64)> = (0..50).map(|i| (i, i + 1)).collect();
+        let mut codegen = CodeGenerator::new();
+        codegen.add_input("edge".to_string(), edges(&chain));
+        codegen.set_join_algorithms(JoinAlgorithmConfig {
+            max_hash_build_rows: if hash { 1000 } else { 0 },
+        });
+        let mut results = match recursive {
+            Some(relation) => codegen.execute_recursive(ir, relation).unwrap(),
+            None => codegen.execute(ir).unwrap(),
+        };
+        results.sort();
+        results
+    }
+
+    #[test]
+    fn test_hash_join_matches_merge_join() {
+        let join = IRNode::Join {
+            left: Box::new(pair_scan("edge", "x", "y")),
+            right: Box::new(pair_scan("edge", "y", "z")),
+            left_keys: vec![1],
+            right_keys: vec![0],
+            output_schema: vec!["x".to_string(), "y".to_string(), "z".to_string()],
+        };
+        let hashed = run_with_join_algorithm(&join, None, true);
+        assert_eq!(hashed.len(), 49);
+        assert_eq!(hashed, run_with_join_algorithm(&join, None, false));
+
+        let fused = IRNode::JoinFlatMap {
+            left: Box::new(pair_scan("edge", "x", "y")),
+            right: Box::new(pair_scan("edge", "y", "z")),
+            left_keys: vec![1],
+            right_keys: vec![0],
+            projection: vec![0, 3],
+            filter_predicate: Some(Predicate::ColumnGtConst(0, 10)),
+            output_schema: vec!["x".to_string(), "z".to_string()],
+        };
+        let hashed = run_with_join_algorithm(&fused, None, true);
+        assert_eq!(hashed.len(), 38);
+        assert_eq!(hashed, run_with_join_algorithm(&fused, None, false));
+    }
+
+    /// A static build side is hashed once and probed by every round of a
+    /// fixpoint.
+    #[test]
+    fn test_hash_join_in_recursion() {
+        // reach(X, Y) <- edge(X, Y)
+        // reach(X, Z) <- reach(X, Y), edge(Y, Z)
+        let ir = IRNode::Union {
+            inputs: vec![
+                pair_scan("edge", "x", "y"),
+                IRNode::Map {
+                    input: Box::new(IRNode::Join {
+                        left: Box::new(pair_scan("reach", "x", "y")),
+                        right: Box::new(pair_scan("edge", "y", "z")),
+                        left_keys: vec![1],
+                        right_keys: vec![0],
+                        output_schema: vec!["x".to_string(), "y".to_string(), "z".to_string()],
+                    }),
+                    projection: vec![0, 2],
+                    output_schema: vec!["x".to_string(), "z".to_string()],
+                },
+            ],
+        };
+        let hashed = run_with_join_algorithm(&ir, Some("reach"), true);
+        assert_eq!(hashed.len(), 50 * 51 / 2);
+        assert_eq!(hashed, run_with_join_algorithm(&ir, Some("reach"), false));
+    }
+
+    #[test]
+    fn test_recursion_with_non_tc_join_keys() {
+        // subordinate(M, E) <- reports(E, M)
+        // subordinate(M, E) <- reports(E, Mid), subordinate(M, Mid)
+        let ir = IRNode::Union {
+            inputs: vec![
+                IRNode::Map {
+                    input: Box::new(pair_scan("reports", "e", "m")),
+                    projection: vec![1, 0],
+                    output_schema: vec!["m".to_string(), "e".to_string()],
+                },
+                IRNode::Map {
+                    input: Box::new(IRNode::Join {
+                        left: Box::new(pair_scan("reports", "e", "mid")),
+                        right: Box::new(pair_scan("subordinate", "m", "mid")),
+                        left_keys: vec![1],
+                        right_keys: vec![1],
+                        output_schema: vec!["e".to_string(), "mid".to_string(), "m".to_string()],
+                    }),
+                    projection: vec![2, 0],
+                    output_schema: vec!["m".to_string(), "e".to_string()],
+                },
+            ],
+        };
+
+        let mut codegen = CodeGenerator::new();
+        codegen.add_input("reports".to_string(), edges(&[(2, 1), (3, 2), (4, 3)]));
+        let mut results = codegen.execute_recursive(&ir, "subordinate").unwrap();
+        results.sort();
+        assert_eq!(
+            results,
+            edges(&[(1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)])
+        );
+    }
+
+    #[test]
+    fn test_recursion_nonlinear_with_constraint() {
+        // reach(X, Y) <- edge(X, Y)
+        // reach(X, Z) <- reach(X, Y), reach(Y, Z), Z < 5
+        let ir = IRNode::Union {
+            inputs: vec![
+                pair_scan("edge", "x", "y"),
+                IRNode::Filter {
+                    input: Box::new(IRNode::Map {
+                        input: Box::new(IRNode::Join {
+                            left: Box::new(pair_scan("reach", "x", "y")),
+                            right: Box::new(pair_scan("reach", "y", "z")),
+                            left_keys: vec![1],
+                            right_keys: vec![0],
+                            output_schema: vec!["x".to_string(), "y".to_string(), "z".to_string()],
+                        }),
+                        projection: vec![0, 2],
+                        output_schema: vec!["x".to_string(), "z".to_string()],
+                    }),
+                    predicate: Predicate::ColumnLtConst(1, 5),
+                },
+            ],
+        };
+
+        let mut codegen = CodeGenerator::new();
+        codegen.add_input(
+            "edge".to_string(),
+            edges(&[(1, 2), (2, 3), (3, 4), (4, 5), (5, 6)]),
+        );
+        let mut results = codegen.execute_recursive(&ir, "reach").unwrap();
+        results.sort();
+        assert_eq!(
+            results,
+            edges(&[
+                (1, 2),
+                (1, 3),
+                (1, 4),
+                (2, 3),
+                (2, 4),
+                (3, 4),
+                (4, 5),
+                (5, 6)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_mutual_recursion_odd_even_paths() {
+        // odd(X, Y) <- edge(X, Y)
+        // odd(X, Z) <- even(X, Y), edge(Y, Z)
+        // even(X, Z) <- odd(X, Y), edge(Y, Z)
+        let scan = |relation: &str| IRNode::Scan {
+            relation: relation.to_string(),
+            schema: vec!["x".to_string(), "y".to_string()],
+        };
+        let step = |relation: &str| IRNode::Map {
+            input: Box::new(IRNode::Join {
+                left: Box::new(scan(relation)),
+                right: Box::new(scan("edge")),
+                left_keys: vec![1],
+                right_keys: vec![0],
+                output_schema: vec!["x".to_string(), "y".to_string(), "z".to_string()],
+            }),
+            projection: vec![0, 2],
+            output_schema: vec!["x".to_string(), "z".to_string()],
+        };
+
+        let mut codegen = CodeGenerator::new();
+        codegen.add_input("edge".to_string(), edges(&[(1, 2), (2, 3), (3, 4), (4, 5)]));
+        let relations = vec![
+            (
+                "odd".to_string(),
+                IRNode::Union {
+                    inputs: vec![scan("edge"), step("even")],
+                },
+            ),
+            ("even".to_string(), step("odd")),
+        ];
+
+        let results = codegen.execute_mutual_recursive(&relations).unwrap();
+        let mut odd = results["odd"].clone();
+        odd.sort();
+        assert_eq!(
+            odd,
+            edges(&[(1, 2), (1, 4), (2, 3), (2, 5), (3, 4), (4, 5)])
+        );
+        let mut even = results["even"].clone();
+        even.sort();
+        assert_eq!(even, edges(&[(1, 3), (1, 5), (2, 4), (3, 5)]));
+    }
+
+    #[test]
+    fn test_execute_stratum_shares_inputs_between_plans() {
+        // hop(X, Z) <- edge(X, Y), edge(Y, Z)
+        // hop3(X, W) <- hop(X, Z), edge(Z, W)
+        // back(X, Y) <- edge(X, Y), edge(Y, X)
+        let hop_join = |left: IRNode| IRNode::Map {
+            input: Box::new(IRNode::Join {
+                left: Box::new(left),
+                right: Box::new(pair_scan("edge", "y", "z")),
+                left_keys: vec![1],
+                right_keys: vec![0],
+                output_schema: vec!["x".to_string(), "y".to_string(), "z".to_string()],
+            }),
+            projection: vec![0, 2],
+            output_schema: vec!["x".to_string(), "z".to_string()],
+        };
+        let back = IRNode::Join {
+            left: Box::new(pair_scan("edge", "x", "y")),
+            right: Box::new(pair_scan("edge", "y", "x")),
+            left_keys: vec![0, 1],
+            right_keys: vec![1, 0],
+            output_schema: vec!["x".to_string(), "y".to_string()],
+        };
+        let plans = vec![
+            ("hop".to_string(), hop_join(pair_scan("edge", "x", "y"))),
+            ("hop3".to_string(), hop_join(pair_scan("hop", "x", "y"))),
+            ("back".to_string(), back),
+        ];
+
+        let mut codegen = CodeGenerator::new();
+        codegen.add_input("edge".to_string(), edges(&[(1, 2), (2, 3), (3, 4), (4, 3)]));
+        let results = codegen.execute_stratum(&plans).unwrap();
+
+        let sorted = |name: &str| {
+            let mut tuples = results[name].clone();
+            tuples.sort();
+            tuples
+        };
+        assert_eq!(sorted("hop"), edges(&[(1, 3), (2, 4), (3, 3), (4, 4)]));
+        assert_eq!(sorted("hop3"), edges(&[(1, 4), (2, 3), (3, 4), (4, 3)]));
+        assert_eq!(sorted("back"), edges(&[(3, 4), (4, 3)]));
+    }
+
     // True DD Recursion Tests (Using Variable + .iterative())
     #[test]
     fn test_transitive_closure_dd_linear() {
@@ -5366,7 +7771,7 @@ mod tests {
 
     #[test]
     fn test_antijoin_right_side_join() {
-        // Tests execute_subquery_for_antijoin: right side is a Join (complex node)
+        // Antijoin whose right side is a Join (complex node)
         // Pattern: allowed(X) <- user(X), !(banned_group(X, G), active_ban(G))
         // This means: users not in any actively-banned group
         let mut codegen = CodeGenerator::new();
@@ -5447,7 +7852,7 @@ mod tests {
 
     #[test]
     fn test_antijoin_right_side_filter() {
-        // Tests collect_tuples_from_ir with Filter node on right side
+        // Antijoin with a Filter node on the right side
         // Pattern: available(X) <- item(X), !reserved(X, Y) where Y > 100
         let mut codegen = CodeGenerator::new();
 
@@ -5510,7 +7915,7 @@ mod tests {
 
     #[test]
     fn test_antijoin_right_side_distinct() {
-        // Tests collect_tuples_from_ir with Distinct node on right side
+        // Antijoin with a Distinct node on the right side
         // Right side has duplicate keys that should be deduplicated
         let mut codegen = CodeGenerator::new();
 
@@ -5568,7 +7973,7 @@ mod tests {
 
     #[test]
     fn test_antijoin_right_side_union() {
-        // Tests collect_tuples_from_ir with Union node on right side
+        // Antijoin with a Union node on the right side
         // Exclude items from multiple sources
         let mut codegen = CodeGenerator::new();
 
@@ -5630,6 +8035,59 @@ mod tests {
         assert!(ids.contains(&5));
     }
 
+    // Semijoin Tests
+    #[test]
+    fn test_semijoin_keeps_matching_left_tuples() {
+        // edge(x, y) semijoin hub(y): keep edges pointing at a hub.
+        // Duplicate hub keys must not duplicate the left tuples.
+        let mut codegen = CodeGenerator::new();
+
+        codegen.add_input_tuples(
+            "edge".to_string(),
+            vec![
+                Tuple::new(vec![Value::Int32(1), Value::Int32(10)]),
+                Tuple::new(vec![Value::Int32(2), Value::Int32(20)]),
+                Tuple::new(vec![Value::Int32(3), Value::Int32(10)]),
+                Tuple::new(vec![Value::Int32(4), Value::Int32(30)]),
+            ],
+        );
+
+        codegen.add_input_tuples(
+            "hub".to_string(),
+            vec![
+                Tuple::new(vec![Value::Int32(10), Value::Int32(0)]),
+                Tuple::new(vec![Value::Int32(10), Value::Int32(1)]),
+                Tuple::new(vec![Value::Int32(30), Value::Int32(0)]),
+            ],
+        );
+
+        let ir = IRNode::Semijoin {
+            left: Box::new(IRNode::Scan {
+                relation: "edge".to_string(),
+                schema: vec!["x".to_string(), "y".to_string()],
+            }),
+            right: Box::new(IRNode::Scan {
+                relation: "hub".to_string(),
+                schema: vec!["y".to_string(), "w".to_string()],
+            }),
+            left_keys: vec![1],
+            right_keys: vec![0],
+            output_schema: vec!["x".to_string(), "y".to_string()],
+        };
+
+        let results = codegen.generate_and_execute_tuples(&ir).unwrap();
+        assert_eq!(results.len(), 3, "Expected 3 edges into hubs");
+
+        let sources: Vec<i32> = results
+            .iter()
+            .filter_map(|t| t.get(0).and_then(Value::as_i32))
+            .collect();
+        assert!(sources.contains(&1));
+        assert!(sources.contains(&3));
+        assert!(sources.contains(&4));
+        assert!(!sources.contains(&2));
+    }
+
     // Multi-Worker Execution Tests
     #[test]
     fn test_multi_worker_simple_scan() {
@@ -5755,8 +8213,84 @@ mod tests {
         let config = ExecutionConfig::with_workers(2);
         let results = codegen.execute_with_config(&ir, config).unwrap();
 
-        // Should have 2 results: keys 2 and 3 match
-        assert_eq!(results.len(), 2, "Expected 2 join results");
+        // Should have 2 results: keys 2 and 3 match
+        assert_eq!(results.len(), 2, "Expected 2 join results");
+    }
+
+    #[test]
+    fn test_multi_worker_join_matches_single_worker() {
+        let mut codegen = CodeGenerator::new();
+        codegen.add_input_tuples(
+            "edge".to_string(),
+            (0..200)
+                .map(|i| Tuple::new(vec![Value::Int32(i % 50), Value::Int32(i)]))
+                .collect(),
+        );
+        codegen.add_input_tuples(
+            "label".to_string(),
+            (0..50)
+                .map(|i| Tuple::new(vec![Value::Int32(i), Value::Int32(i * 10)]))
+                .collect(),
+        );
+
+        let ir = IRNode::Join {
+            left: Box::new(IRNode::Scan {
+                relation: "edge".to_string(),
+                schema: vec!["x".to_string(), "y".to_string()],
+            }),
+            right: Box::new(IRNode::Scan {
+                relation: "label".to_string(),
+                schema: vec!["x".to_string(), "l".to_string()],
+            }),
+            left_keys: vec![0],
+            right_keys: vec![0],
+            output_schema: vec!["x".to_string(), "y".to_string(), "l".to_string()],
+        };
+
+        let mut single = codegen.execute(&ir).unwrap();
+        let mut multi = codegen
+            .execute_with_config(&ir, ExecutionConfig::with_workers(4))
+            .unwrap();
+        single.sort();
+        multi.sort();
+
+        // Every edge matches exactly one label, even when the two sides of a
+        // key start out on different workers
+        assert_eq!(multi.len(), 200);
+        assert_eq!(single, multi);
+    }
+
+    #[test]
+    fn test_multi_worker_aggregate_groups_across_workers() {
+        let mut codegen = CodeGenerator::new();
+        codegen.add_input_tuples(
+            "sales".to_string(),
+            (0..40)
+                .map(|i| Tuple::new(vec![Value::Int32(i % 4), Value::Int32(i)]))
+                .collect(),
+        );
+
+        let ir = IRNode::Aggregate {
+            input: Box::new(IRNode::Scan {
+                relation: "sales".to_string(),
+                schema: vec!["region".to_string(), "amount".to_string()],
+            }),
+            group_by: vec![0],
+            aggregations: vec![(AggregateFunction::Count, 1)],
+            output_schema: vec!["region".to_string(), "count".to_string()],
+        };
+
+        let mut single = codegen.execute(&ir).unwrap();
+        let mut multi = codegen
+            .execute_with_config(&ir, ExecutionConfig::with_workers(3))
+            .unwrap();
+        single.sort();
+        multi.sort();
+
+        // One row per group: a group split across workers must not produce
+        // partial counts
+        assert_eq!(multi.len(), 4);
+        assert_eq!(single, multi);
     }
 
     #[test]
@@ -8243,68 +10777,616 @@ mod tests {
         }
     }
 
-    // === strip_top_aggregate tests ===
+    #[test]
+    fn test_evaluate_arithmetic_dates_and_durations() {
+        let day = Value::Date(19_782); // 2024-02-29
+        let week = Value::Duration(7 * temporal_ops::MS_PER_DAY);
+        assert_eq!(
+            CodeGenerator::evaluate_arithmetic(ArithOp::Add, &day, &week),
+            Value::Date(19_789)
+        );
+        assert_eq!(
+            CodeGenerator::evaluate_arithmetic(ArithOp::Sub, &day, &week),
+            Value::Date(19_775)
+        );
+        assert_eq!(
+            CodeGenerator::evaluate_arithmetic(ArithOp::Sub, &Value::Date(19_789), &day),
+            week
+        );
+        assert_eq!(
+            CodeGenerator::evaluate_arithmetic(
+                ArithOp::Sub,
+                &Value::Timestamp(5_000),
+                &Value::Timestamp(2_000)
+            ),
+            Value::Duration(3_000)
+        );
+        assert_eq!(
+            CodeGenerator::evaluate_arithmetic(
+                ArithOp::Add,
+                &Value::Timestamp(1_000),
+                &Value::Duration(500)
+            ),
+            Value::Timestamp(1_500)
+        );
+        assert_eq!(
+            CodeGenerator::evaluate_arithmetic(
+                ArithOp::Mul,
+                &Value::Int64(3),
+                &Value::Duration(500)
+            ),
+            Value::Duration(1_500)
+        );
+        assert_eq!(
+            CodeGenerator::evaluate_arithmetic(
+                ArithOp::Div,
+                &Value::Duration(500),
+                &Value::Int64(0)
+            ),
+            Value::Null
+        );
+        // Adding two dates is meaningless
+        assert_eq!(
+            CodeGenerator::evaluate_arithmetic(ArithOp::Add, &day, &day),
+            Value::Null
+        );
+    }
+
+    #[test]
+    fn test_evaluate_date_and_duration_literals() {
+        let tuple = Tuple::empty();
+        let lit = |s: &str| vec![IRExpression::StringConstant(s.to_string())];
+        assert_eq!(
+            CodeGenerator::evaluate_function(&BuiltinFunction::Date, &lit("2024-02-29"), &tuple),
+            Value::Date(19_782)
+        );
+        assert_eq!(
+            CodeGenerator::evaluate_function(&BuiltinFunction::Date, &lit("not a date"), &tuple),
+            Value::Null
+        );
+        assert_eq!(
+            CodeGenerator::evaluate_function(&BuiltinFunction::Duration, &lit("1h30m"), &tuple),
+            Value::Duration(5_400_000)
+        );
+        assert_eq!(
+            CodeGenerator::evaluate_function(
+                &BuiltinFunction::Date,
+                &[IRExpression::IntConstant(temporal_ops::MS_PER_DAY + 1)],
+                &tuple
+            ),
+            Value::Date(1)
+        );
+    }
+
+    #[test]
+    fn test_evaluate_uuid_functions() {
+        let tuple = Tuple::empty();
+        let text = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+        assert_eq!(
+            CodeGenerator::evaluate_function(
+                &BuiltinFunction::Uuid,
+                &[IRExpression::StringConstant(text.to_string())],
+                &tuple
+            ),
+            Value::uuid(text).unwrap()
+        );
+        let first = CodeGenerator::evaluate_function(&BuiltinFunction::UuidV7, &[], &tuple);
+        let second = CodeGenerator::evaluate_function(&BuiltinFunction::UuidV7, &[], &tuple);
+        assert!(matches!(first, Value::Uuid(u) if u.get_version_num() == 7));
+        // v7 UUIDs from one process are monotonic
+        assert!(first < second);
+    }
+
+    #[test]
+    fn test_evaluate_null_functions() {
+        let tuple = Tuple::new(vec![Value::Null, Value::Int64(7)]);
+        let col = IRExpression::Column;
+        assert_eq!(
+            CodeGenerator::evaluate_function(&BuiltinFunction::IsNull, &[col(0)], &tuple),
+            Value::Bool(true)
+        );
+        assert_eq!(
+            CodeGenerator::evaluate_function(&BuiltinFunction::IsNull, &[col(1)], &tuple),
+            Value::Bool(false)
+        );
+        assert_eq!(
+            CodeGenerator::evaluate_function(&BuiltinFunction::Coalesce, &[col(0), col(1)], &tuple),
+            Value::Int64(7)
+        );
+        assert_eq!(
+            CodeGenerator::evaluate_function(&BuiltinFunction::Coalesce, &[col(1), col(0)], &tuple),
+            Value::Int64(7)
+        );
+        assert_eq!(
+            CodeGenerator::evaluate_function(&BuiltinFunction::Coalesce, &[col(0), col(0)], &tuple),
+            Value::Null
+        );
+    }
+
+    #[test]
+    fn test_evaluate_list_and_map_functions() {
+        let tuple = Tuple::new(vec![
+            Value::list(vec![Value::Int64(10), Value::Int64(20)]),
+            Value::map([("k", Value::string("v"))]),
+        ]);
+        let col = IRExpression::Column;
+        let eval =
+            |func, args: &[IRExpression]| CodeGenerator::evaluate_function(func, args, &tuple);
+        assert_eq!(eval(&BuiltinFunction::ListLen, &[col(0)]), Value::Int64(2));
+        assert_eq!(
+            eval(
+                &BuiltinFunction::ListGet,
+                &[col(0), IRExpression::IntConstant(1)]
+            ),
+            Value::Int64(20)
+        );
+        assert_eq!(
+            eval(
+                &BuiltinFunction::ListGet,
+                &[col(0), IRExpression::IntConstant(5)]
+            ),
+            Value::Null
+        );
+        assert_eq!(
+            eval(
+                &BuiltinFunction::MapGet,
+                &[col(1), IRExpression::StringConstant("k".into())]
+            ),
+            Value::string("v")
+        );
+        assert_eq!(
+            eval(
+                &BuiltinFunction::MapGet,
+                &[col(1), IRExpression::StringConstant("x".into())]
+            ),
+            Value::Null
+        );
+        assert_eq!(
+            eval(
+                &BuiltinFunction::List,
+                &[IRExpression::IntConstant(1), IRExpression::IntConstant(2)]
+            ),
+            Value::list(vec![Value::Int64(1), Value::Int64(2)])
+        );
+        assert_eq!(
+            eval(
+                &BuiltinFunction::Map,
+                &[
+                    IRExpression::StringConstant("a".into()),
+                    IRExpression::IntConstant(1)
+                ]
+            ),
+            Value::map([("a", Value::Int64(1))])
+        );
+    }
+
+    #[test]
+    fn test_evaluate_json_functions() {
+        let payload = r#"{"user": {"id": 7, "tags": ["a", "b"]}, "ok": true}"#;
+        let tuple = Tuple::new(vec![Value::json(payload).unwrap(), Value::string(payload)]);
+        let col = IRExpression::Column;
+        let path = |p: &str| IRExpression::StringConstant(p.to_string());
+        let eval =
+            |func, args: &[IRExpression]| CodeGenerator::evaluate_function(func, args, &tuple);
+
+        assert_eq!(
+            eval(&BuiltinFunction::JsonParse, &[col(1)]),
+            tuple.values()[0]
+        );
+        assert_eq!(
+            eval(&BuiltinFunction::JsonParse, &[path("{broken")]),
+            Value::Null
+        );
+        assert_eq!(
+            eval(&BuiltinFunction::JsonExtract, &[col(0), path("$.user.id")]),
+            Value::Int64(7)
+        );
+        // Raw JSON text in a string column is parsed on the fly
+        assert_eq!(
+            eval(
+                &BuiltinFunction::JsonExtract,
+                &[col(1), path("$.user.tags[1]")]
+            ),
+            Value::string("b")
+        );
+        assert_eq!(
+            eval(
+                &BuiltinFunction::JsonExtract,
+                &[col(0), path("$.user.tags")]
+            ),
+            Value::json(r#"["a","b"]"#).unwrap()
+        );
+        assert_eq!(
+            eval(&BuiltinFunction::JsonExtract, &[col(0), path("$.missing")]),
+            Value::Null
+        );
+        assert_eq!(
+            eval(&BuiltinFunction::JsonType, &[col(0)]),
+            Value::string("object")
+        );
+
+        let elements = (
+            "T".to_string(),
+            IRExpression::FunctionCall(
+                BuiltinFunction::JsonArrayElements,
+                vec![IRExpression::FunctionCall(
+                    BuiltinFunction::JsonExtract,
+                    vec![col(0), path("$.user.tags")],
+                )],
+            ),
+        );
+        let rows = CodeGenerator::compute_rows(tuple.clone(), &[elements]);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].get(2), Some(&Value::string("b")));
+    }
+
+    #[test]
+    fn test_evaluate_bytes_functions() {
+        let tuple = Tuple::new(vec![Value::bytes(vec![0xde, 0xad, 0xbe, 0xef])]);
+        let col = IRExpression::Column;
+        let int = IRExpression::IntConstant;
+        let text = |s: &str| IRExpression::StringConstant(s.to_string());
+        let eval =
+            |func, args: &[IRExpression]| CodeGenerator::evaluate_function(func, args, &tuple);
+
+        assert_eq!(
+            eval(&BuiltinFunction::Hex, &[text("deadbeef")]),
+            tuple.values()[0]
+        );
+        assert_eq!(
+            eval(&BuiltinFunction::Base64, &[text("3q2+7w==")]),
+            tuple.values()[0]
+        );
+        assert_eq!(eval(&BuiltinFunction::Hex, &[text("nope")]), Value::Null);
+        assert_eq!(
+            eval(&BuiltinFunction::ToHex, &[col(0)]),
+            Value::string("deadbeef")
+        );
+        assert_eq!(
+            eval(&BuiltinFunction::ToBase64, &[col(0)]),
+            Value::string("3q2+7w==")
+        );
+        assert_eq!(eval(&BuiltinFunction::BytesLen, &[col(0)]), Value::Int64(4));
+        assert_eq!(
+            eval(&BuiltinFunction::BytesSlice, &[col(0), int(1), int(2)]),
+            Value::bytes(vec![0xad, 0xbe])
+        );
+        assert_eq!(
+            eval(&BuiltinFunction::BytesSlice, &[col(0), int(3), int(10)]),
+            Value::bytes(vec![0xef])
+        );
+        assert_eq!(
+            eval(&BuiltinFunction::BytesLen, &[text("abc")]),
+            Value::Null
+        );
+    }
+
+    #[test]
+    fn test_compute_rows_flattens_generators() {
+        let tuple = Tuple::new(vec![
+            Value::list(vec![Value::Int64(1), Value::Int64(2)]),
+            Value::map([("a", Value::Null), ("b", Value::Null)]),
+        ]);
+        let exprs = vec![
+            (
+                "X".to_string(),
+                IRExpression::FunctionCall(
+                    BuiltinFunction::ListElem,
+                    vec![IRExpression::Column(0)],
+                ),
+            ),
+            (
+                "K".to_string(),
+                IRExpression::FunctionCall(BuiltinFunction::MapKey, vec![IRExpression::Column(1)]),
+            ),
+        ];
+        let rows = CodeGenerator::compute_rows(tuple.clone(), &exprs);
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0].get(2), Some(&Value::Int64(1)));
+        assert_eq!(rows[0].get(3), Some(&Value::string("a")));
+        assert_eq!(rows[3].get(2), Some(&Value::Int64(2)));
+        assert_eq!(rows[3].get(3), Some(&Value::string("b")));
+
+        // An empty or non-collection argument yields no rows
+        let empty = Tuple::new(vec![Value::Null, Value::Null]);
+        assert!(CodeGenerator::compute_rows(empty, &exprs).is_empty());
+    }
+
+    #[test]
+    fn test_predicates_treat_null_as_unknown() {
+        let null_row = Tuple::new(vec![Value::Null, Value::Int64(1)]);
+        let predicates = [
+            Predicate::ColumnEqConst(0, 1),
+            Predicate::ColumnNeConst(0, 1),
+            Predicate::ColumnNeStr(0, "a".to_string()),
+            Predicate::ColumnNeFloat(0, 1.0),
+            Predicate::ColumnNeBool(0, true),
+            Predicate::ColumnsEq(0, 0),
+            Predicate::ColumnsNe(0, 1),
+            // Missing columns behave like NULL
+            Predicate::ColumnNeConst(5, 1),
+        ];
+        for predicate in &predicates {
+            let f = CodeGenerator::predicate_to_tuple_fn(predicate);
+            assert!(!f(&null_row), "{predicate:?} should be UNKNOWN on NULL");
+        }
+        // UNKNOWN OR TRUE is TRUE; UNKNOWN AND TRUE is UNKNOWN
+        let known = Predicate::ColumnEqConst(1, 1);
+        let unknown = Predicate::ColumnNeConst(0, 1);
+        let or = Predicate::Or(Box::new(unknown.clone()), Box::new(known.clone()));
+        let and = Predicate::And(Box::new(unknown), Box::new(known));
+        assert!(CodeGenerator::predicate_to_tuple_fn(&or)(&null_row));
+        assert!(!CodeGenerator::predicate_to_tuple_fn(&and)(&null_row));
+        // A non-null value of another type is still not equal
+        let text_row = Tuple::new(vec![Value::string("x")]);
+        assert!(CodeGenerator::predicate_to_tuple_fn(
+            &Predicate::ColumnNeConst(0, 1)
+        )(&text_row));
+    }
+
+    #[test]
+    fn test_null_keys_never_match_in_joins() {
+        let mut codegen = CodeGenerator::new();
+        codegen.add_input_tuples(
+            "r".to_string(),
+            vec![
+                Tuple::new(vec![Value::Int32(1), Value::Null]),
+                Tuple::new(vec![Value::Int32(2), Value::Int32(10)]),
+            ],
+        );
+        codegen.add_input_tuples(
+            "s".to_string(),
+            vec![
+                Tuple::new(vec![Value::Null, Value::Int32(100)]),
+                Tuple::new(vec![Value::Int32(10), Value::Int32(200)]),
+            ],
+        );
+        let scan = |relation: &str| {
+            Box::new(IRNode::Scan {
+                relation: relation.to_string(),
+                schema: vec!["a".to_string(), "b".to_string()],
+            })
+        };
+
+        let join = IRNode::Join {
+            left: scan("r"),
+            right: scan("s"),
+            left_keys: vec![1],
+            right_keys: vec![0],
+            output_schema: vec!["x".to_string(), "y".to_string(), "z".to_string()],
+        };
+        let results = codegen.generate_and_execute_tuples(&join).unwrap();
+        assert_eq!(
+            results,
+            vec![Tuple::new(vec![
+                Value::Int32(2),
+                Value::Int32(10),
+                Value::Int32(200)
+            ])]
+        );
+
+        // NOT EXISTS semantics: the NULL-keyed left row has no match, so it stays
+        let antijoin = IRNode::Antijoin {
+            left: scan("r"),
+            right: scan("s"),
+            left_keys: vec![1],
+            right_keys: vec![0],
+            output_schema: vec!["x".to_string(), "y".to_string()],
+        };
+        let results = codegen.generate_and_execute_tuples(&antijoin).unwrap();
+        assert_eq!(
+            results,
+            vec![Tuple::new(vec![Value::Int32(1), Value::Null])]
+        );
+    }
+
+    // === loop aggregation tests ===
+
+    fn aggregate_rule(function: AggregateFunction) -> IRNode {
+        IRNode::Aggregate {
+            input: Box::new(IRNode::Scan {
+                relation: "r".to_string(),
+                schema: vec!["x".to_string(), "y".to_string(), "d".to_string()],
+            }),
+            group_by: vec![1],
+            aggregations: vec![(function, 2)],
+            output_schema: vec!["y".to_string(), "min_d".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_plan_loop_aggregation_rewrites_aggregate_to_contributions() {
+        let base = IRNode::Scan {
+            relation: "start".to_string(),
+            schema: vec!["y".to_string(), "d".to_string()],
+        };
+        let rules = vec![base, aggregate_rule(AggregateFunction::Min)];
+        let (aggregation, rewritten) =
+            CodeGenerator::plan_loop_aggregation("dist", &rules).unwrap();
+
+        assert_eq!(
+            aggregation,
+            Some(LoopAggregation {
+                function: LoopAggregate::Min,
+                group_len: 1,
+            })
+        );
+        assert!(matches!(rewritten[0], IRNode::Scan { .. }));
+        match &rewritten[1] {
+            IRNode::Map { projection, .. } => assert_eq!(projection, &vec![1, 2]),
+            other => panic!("Expected Map, got {other:?}"),
+        }
+    }
 
     #[test]
-    fn test_strip_top_aggregate_with_aggregate() {
-        let inner = IRNode::Scan {
+    fn test_plan_loop_aggregation_without_aggregate() {
+        let rules = vec![IRNode::Scan {
             relation: "r".to_string(),
             schema: vec!["x".to_string()],
+        }];
+        let (aggregation, rewritten) = CodeGenerator::plan_loop_aggregation("r", &rules).unwrap();
+        assert_eq!(aggregation, None);
+        assert_eq!(rewritten.len(), 1);
+    }
+
+    #[test]
+    fn test_plan_loop_aggregation_rejects_non_monotonic() {
+        let rules = vec![aggregate_rule(AggregateFunction::Sum)];
+        let err = CodeGenerator::plan_loop_aggregation("dist", &rules).unwrap_err();
+        assert!(err.contains("not monotonic"), "{err}");
+    }
+
+    #[test]
+    fn test_plan_loop_aggregation_rejects_mixed_aggregates() {
+        let rules = vec![
+            aggregate_rule(AggregateFunction::Min),
+            aggregate_rule(AggregateFunction::Max),
+        ];
+        let err = CodeGenerator::plan_loop_aggregation("dist", &rules).unwrap_err();
+        assert!(err.contains("different aggregates"), "{err}");
+    }
+
+    // === min-plus tests ===
+
+    /// dist(Z, min<D>) <- dist(Y, D1), wedge(Y, Z, W), D = D1 + W
+    fn shortest_path_rule(distance: IRExpression) -> IRNode {
+        IRNode::Aggregate {
+            input: Box::new(IRNode::Compute {
+                input: Box::new(IRNode::Join {
+                    left: Box::new(pair_scan("dist", "y", "d1")),
+                    right: Box::new(IRNode::Scan {
+                        relation: "wedge".to_string(),
+                        schema: vec!["y".to_string(), "z".to_string(), "w".to_string()],
+                    }),
+                    left_keys: vec![0],
+                    right_keys: vec![0],
+                    output_schema: vec![
+                        "y".to_string(),
+                        "d1".to_string(),
+                        "z".to_string(),
+                        "w".to_string(),
+                    ],
+                }),
+                expressions: vec![("d".to_string(), distance)],
+            }),
+            group_by: vec![2],
+            aggregations: vec![(AggregateFunction::Min, 4)],
+            output_schema: vec!["z".to_string(), "min_d".to_string()],
+        }
+    }
+
+    fn add_columns(left: usize, right: usize) -> IRExpression {
+        IRExpression::Arithmetic {
+            op: ArithOp::Add,
+            left: Box::new(IRExpression::Column(left)),
+            right: Box::new(IRExpression::Column(right)),
+        }
+    }
+
+    #[test]
+    fn test_detect_min_plus_pattern() {
+        let rules = CodeGenerator::detect_min_plus_pattern(
+            &[shortest_path_rule(add_columns(1, 3))],
+            "dist",
+        )
+        .unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].group_by, vec![2]);
+        assert_eq!(rules[0].distance_col, 4);
+    }
+
+    #[test]
+    fn test_detect_min_plus_pattern_rejects_non_additive_distance() {
+        // D = D1 * W is not expressible as an offset
+        let product = IRExpression::Arithmetic {
+            op: ArithOp::Mul,
+            left: Box::new(IRExpression::Column(1)),
+            right: Box::new(IRExpression::Column(3)),
         };
-        let ir = IRNode::Aggregate {
-            input: Box::new(inner),
+        assert!(
+            CodeGenerator::detect_min_plus_pattern(&[shortest_path_rule(product)], "dist")
+                .is_none()
+        );
+
+        // The distance column itself is aggregated, but D1 is filtered on
+        let filtered = IRNode::Aggregate {
+            input: Box::new(IRNode::Filter {
+                input: Box::new(pair_scan("dist", "y", "d1")),
+                predicate: Predicate::ColumnLtConst(1, 100),
+            }),
             group_by: vec![0],
-            aggregations: vec![(AggregateFunction::Count, 1)],
-            output_schema: vec!["x".to_string(), "cnt".to_string()],
+            aggregations: vec![(AggregateFunction::Min, 1)],
+            output_schema: vec!["y".to_string(), "min_d1".to_string()],
         };
-        let stripped = CodeGenerator::strip_top_aggregate(&ir);
-        assert!(matches!(stripped, IRNode::Scan { .. }));
+        assert!(CodeGenerator::detect_min_plus_pattern(&[filtered], "dist").is_none());
     }
 
     #[test]
-    fn test_strip_top_aggregate_without_aggregate() {
-        let ir = IRNode::Scan {
-            relation: "r".to_string(),
-            schema: vec!["x".to_string()],
+    fn test_min_plus_shortest_paths_match_tuple_evaluation() {
+        //   1 --5--> 2 --3--> 3 --2--> 4
+        //   1 ------10------> 3 --1--> 1
+        let weighted =
+            |x, y, w| Tuple::new(vec![Value::Int64(x), Value::Int64(y), Value::Int64(w)]);
+        let ir = IRNode::Union {
+            inputs: vec![
+                pair_scan("source", "y", "d"),
+                shortest_path_rule(add_columns(1, 3)),
+            ],
+        };
+
+        let run = |semiring| {
+            let mut codegen = CodeGenerator::new();
+            codegen.set_semiring_type(semiring);
+            codegen.add_input("source".to_string(), edges(&[(1, 0)]));
+            codegen.add_input(
+                "wedge".to_string(),
+                vec![
+                    weighted(1, 2, 5),
+                    weighted(2, 3, 3),
+                    weighted(1, 3, 10),
+                    weighted(3, 4, 2),
+                    weighted(3, 1, 1),
+                ],
+            );
+            let mut results = codegen.execute_recursive(&ir, "dist").unwrap();
+            results.sort();
+            results
         };
-        let stripped = CodeGenerator::strip_top_aggregate(&ir);
-        assert!(matches!(stripped, IRNode::Scan { .. }));
+
+        let expected = edges(&[(1, 0), (2, 5), (3, 8), (4, 10)]);
+        assert_eq!(run(SemiringType::Min), expected);
+        assert_eq!(run(SemiringType::Counting), expected);
     }
 
-    // === partition_data_for_worker tests ===
+    // === worker_share tests ===
 
     #[test]
-    fn test_partition_data_single_worker() {
-        let mut data = HashMap::new();
-        data.insert(
-            "rel".to_string(),
-            vec![
-                Tuple::new(vec![Value::Int32(1)]),
-                Tuple::new(vec![Value::Int32(2)]),
-                Tuple::new(vec![Value::Int32(3)]),
-            ],
-        );
-        let partitioned = CodeGenerator::partition_data_for_worker(&data, 0, 1);
+    fn test_worker_share_single_worker() {
+        let tuples = vec![
+            Tuple::new(vec![Value::Int32(1)]),
+            Tuple::new(vec![Value::Int32(2)]),
+            Tuple::new(vec![Value::Int32(3)]),
+        ];
         // Single worker gets all data
-        assert_eq!(partitioned["rel"].len(), 3);
+        assert_eq!(CodeGenerator::worker_share(&tuples, 0, 1).len(), 3);
     }
 
     #[test]
-    fn test_partition_data_covers_all_tuples() {
-        let mut data = HashMap::new();
+    fn test_worker_share_covers_all_tuples() {
         let tuples: Vec<Tuple> = (0..100)
             .map(|i| Tuple::new(vec![Value::Int32(i)]))
             .collect();
-        data.insert("rel".to_string(), tuples);
 
         let num_workers = 4;
-        let mut total = 0;
+        let mut seen = HashSet::new();
         for w in 0..num_workers {
-            let partitioned = CodeGenerator::partition_data_for_worker(&data, w, num_workers);
-            total += partitioned["rel"].len();
+            for tuple in CodeGenerator::worker_share(&tuples, w, num_workers) {
+                // Shares are disjoint
+                assert!(seen.insert(tuple));
+            }
         }
         // All tuples must be accounted for across workers
-        assert_eq!(total, 100);
+        assert_eq!(seen.len(), 100);
     }
 
     // === detect_transitive_closure_pattern tests ===
@@ -8422,6 +11504,84 @@ mod tests {
         set_query_cancel_flag(None);
     }
 
+    /// Cancelling a handle from another thread stops a long-running fixpoint
+    /// instead of letting the dataflow step to completion.
+    #[test]
+    fn test_cancel_handle_interrupts_recursive_query() {
+        // reach(X, Y) <- edge(X, Y)
+        // reach(X, Z) <- reach(X, Y), edge(Y, Z)
+        let ir = IRNode::Union {
+            inputs: vec![
+                pair_scan("edge", "x", "y"),
+                IRNode::Map {
+                    input: Box::new(IRNode::Join {
+                        left: Box::new(pair_scan("reach", "x", "y")),
+                        right: Box::new(pair_scan("edge", "y", "z")),
+                        left_keys: vec![1],
+                        right_keys: vec![0],
+                        output_schema: vec!["x".to_string(), "y".to_string(), "z".to_string()],
+                    }),
+                    projection: vec![0, 2],
+                    output_schema: vec!["x".to_string(), "z".to_string()],
+                },
+            ],
+        };
+        let chain: Vec<(i64, i64)> = (0..3000).map(|i| (i, i + 1)).collect();
+
+        let handle = CancelHandle::new();
+        let mut codegen = CodeGenerator::new();
+        codegen.add_input("edge".to_string(), edges(&chain));
+        codegen.set_cancel_handle(Some(handle.clone()));
+
+        let canceller = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            handle.cancel();
+        });
+        let start = Instant::now();
+        let err = codegen.execute_recursive(&ir, "reach").unwrap_err();
+        canceller.join().unwrap();
+
+        assert!(err.contains("cancelled"), "{err}");
+        assert!(start.elapsed().as_secs() < 30);
+        // The explicit handle is scoped to the execution
+        assert!(!is_query_cancelled());
+    }
+
+    /// A query that outlives its wall-clock budget stops inside the fixpoint
+    /// and reports how far it got.
+    #[test]
+    fn test_query_timeout_reports_progress() {
+        let ir = IRNode::Union {
+            inputs: vec![
+                pair_scan("edge", "x", "y"),
+                IRNode::Map {
+                    input: Box::new(IRNode::Join {
+                        left: Box::new(pair_scan("reach", "x", "y")),
+                        right: Box::new(pair_scan("edge", "y", "z")),
+                        left_keys: vec![1],
+                        right_keys: vec![0],
+                        output_schema: vec!["x".to_string(), "y".to_string(), "z".to_string()],
+                    }),
+                    projection: vec![0, 2],
+                    output_schema: vec!["x".to_string(), "z".to_string()],
+                },
+            ],
+        };
+        let chain: Vec<(i64, i64)> = (0..3000).map(|i| (i, i + 1)).collect();
+
+        let mut codegen = CodeGenerator::new();
+        codegen.add_input("edge".to_string(), edges(&chain));
+        codegen.set_query_timeout(Some(QueryTimeout::new(Some(
+            std::time::Duration::from_millis(50),
+        ))));
+
+        let start = Instant::now();
+        let err = codegen.execute_recursive(&ir, "reach").unwrap_err();
+        assert!(err.contains("exceeded timeout"), "{err}");
+        assert!(err.contains("dataflow steps"), "{err}");
+        assert!(start.elapsed().as_secs() < 30);
+    }
+
     // === Regression tests for result set size limit ===
 
     /// Verify max_result_rows=0 means unlimited (default behavior)
@@ -0,0 +1,235 @@
+//! Join Pre-Filtering
+//!
+//! When one side of a join or antijoin can be evaluated straight from the
+//! inputs while the dataflow is built, its join keys are used to shrink the
+//! other side before it enters the dataflow:
+//!
+//! - **Runtime semijoin filters**: when the build side has few distinct keys
+//!   (typically a relation behind a selective constant filter) and the other
+//!   side is a scan behind filters and projections, the scan only emits
+//!   tuples whose key was observed on the build side.
+//! - **Bloom filters**: otherwise, when one side is much larger, it is
+//!   filtered by a bloom filter over the small side's keys before it is
+//!   arranged.
+//!
+//! Neither filter has false negatives: joins keep every match, and antijoins
+//! pass the tuples a bloom filter rejects straight to the output.
+//!
+//! Whether a side is filtered is decided from input cardinalities; see
+//! `JoinPrefilter::applies` and `JoinPrefilter::applies_runtime`.
+
+use crate::bloom_filter::BloomFilter;
+use crate::value::Tuple;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Settings and shared metrics of join pre-filtering
+///
+/// Cloning shares the metrics, so every dataflow of an engine reports into
+/// the same counters.
+#[derive(Debug, Clone)]
+pub struct JoinPrefilter {
+    /// Minimum estimated rows of the filtered (large) side
+    pub min_probe_rows: usize,
+    /// Minimum ratio of the large side's rows to the small side's rows
+    pub min_size_ratio: usize,
+    /// Target false positive rate of the bloom filters
+    pub false_positive_rate: f64,
+    /// Most distinct build-side keys a runtime semijoin filter may hold
+    /// (0 = runtime filters disabled)
+    pub max_runtime_filter_keys: usize,
+    counters: Arc<PrefilterCounters>,
+}
+
+impl Default for JoinPrefilter {
+    fn default() -> Self {
+        JoinPrefilter::new(10_000, 10, 0.01)
+    }
+}
+
+impl JoinPrefilter {
+    /// Pre-filtering with the given thresholds
+    pub fn new(min_probe_rows: usize, min_size_ratio: usize, false_positive_rate: f64) -> Self {
+        JoinPrefilter {
+            min_probe_rows,
+            min_size_ratio,
+            false_positive_rate,
+            max_runtime_filter_keys: 100_000,
+            counters: Arc::new(PrefilterCounters::default()),
+        }
+    }
+
+    /// Limit runtime semijoin filters to `max_keys` distinct keys
+    /// (0 disables them)
+    #[must_use]
+    pub fn with_runtime_filter_keys(mut self, max_keys: usize) -> Self {
+        self.max_runtime_filter_keys = max_keys;
+        self
+    }
+
+    /// Whether a side of `probe_rows` rows is worth filtering by the keys of
+    /// a side of `build_rows` rows
+    pub fn applies(&self, build_rows: usize, probe_rows: usize) -> bool {
+        probe_rows >= self.min_probe_rows
+            && probe_rows >= build_rows.saturating_mul(self.min_size_ratio.max(1))
+    }
+
+    /// Whether a scan of `probe_rows` rows is worth restricting to a build
+    /// side with `build_keys` distinct keys
+    pub fn applies_runtime(&self, build_keys: usize, probe_rows: usize) -> bool {
+        self.max_runtime_filter_keys > 0
+            && build_keys <= self.max_runtime_filter_keys
+            && probe_rows >= self.min_probe_rows
+            && probe_rows >= build_keys.saturating_mul(self.min_size_ratio.max(1))
+    }
+
+    /// Record a runtime filter that kept `kept` of a scan's `scanned` rows
+    pub(crate) fn record_runtime_filter(&self, scanned: usize, kept: usize) {
+        self.counters
+            .runtime_filters
+            .fetch_add(1, Ordering::Relaxed);
+        self.counters
+            .scan_rows_skipped
+            .fetch_add(scanned.saturating_sub(kept) as u64, Ordering::Relaxed);
+    }
+
+    /// Bloom filter over `keys`, reporting into this prefilter's metrics
+    pub(crate) fn build<I>(&self, keys: I, expected: usize) -> KeyFilter
+    where
+        I: IntoIterator<Item = Tuple>,
+    {
+        let mut bloom = BloomFilter::new(expected.max(1), self.false_positive_rate);
+        for key in keys {
+            bloom.insert(&key);
+        }
+        self.counters.filters_built.fetch_add(1, Ordering::Relaxed);
+        KeyFilter {
+            bloom,
+            counters: Arc::clone(&self.counters),
+        }
+    }
+
+    /// Metrics accumulated so far
+    pub fn metrics(&self) -> PrefilterMetrics {
+        PrefilterMetrics {
+            filters_built: self.counters.filters_built.load(Ordering::Relaxed),
+            probed: self.counters.probed.load(Ordering::Relaxed),
+            passed: self.counters.passed.load(Ordering::Relaxed),
+            runtime_filters: self.counters.runtime_filters.load(Ordering::Relaxed),
+            scan_rows_skipped: self.counters.scan_rows_skipped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct PrefilterCounters {
+    filters_built: AtomicU64,
+    probed: AtomicU64,
+    passed: AtomicU64,
+    runtime_filters: AtomicU64,
+    scan_rows_skipped: AtomicU64,
+}
+
+/// Snapshot of pre-filtering metrics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrefilterMetrics {
+    /// Bloom filters built
+    pub filters_built: u64,
+    /// Tuples tested against a filter
+    pub probed: u64,
+    /// Tested tuples the filter reported as possible matches
+    pub passed: u64,
+    /// Runtime semijoin filters pushed into scans
+    pub runtime_filters: u64,
+    /// Scanned rows a runtime filter kept out of the dataflow
+    pub scan_rows_skipped: u64,
+}
+
+impl PrefilterMetrics {
+    /// Tested tuples the filter ruled out
+    pub fn rejected(&self) -> u64 {
+        self.probed.saturating_sub(self.passed)
+    }
+
+    /// Fraction of tested tuples that passed the filter (1.0 when nothing
+    /// was tested)
+    pub fn hit_rate(&self) -> f64 {
+        if self.probed == 0 {
+            return 1.0;
+        }
+        self.passed as f64 / self.probed as f64
+    }
+}
+
+/// Pre-filter chosen for one side of a join
+pub(crate) enum SideFilter {
+    /// Drop tuples whose key the bloom filter rules out
+    Bloom(KeyFilter),
+    /// Generate the side from these inputs: its scanned relation restricted
+    /// to the keys observed on the other side
+    Inputs(HashMap<String, Vec<Tuple>>),
+}
+
+/// Bloom filter over one join side's keys, used by operator closures
+pub(crate) struct KeyFilter {
+    bloom: BloomFilter,
+    counters: Arc<PrefilterCounters>,
+}
+
+impl KeyFilter {
+    /// Whether `key` may have a match on the filtered side
+    pub(crate) fn might_match(&self, key: &Tuple) -> bool {
+        let hit = self.bloom.might_contain(key);
+        self.counters.probed.fetch_add(1, Ordering::Relaxed);
+        if hit {
+            self.counters.passed.fetch_add(1, Ordering::Relaxed);
+        }
+        hit
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_applies_to_large_probe_sides_only() {
+        let prefilter = JoinPrefilter::new(1000, 10, 0.01);
+        assert!(prefilter.applies(10, 1000));
+        assert!(!prefilter.applies(10, 999));
+        assert!(!prefilter.applies(200, 1500));
+    }
+
+    #[test]
+    fn test_runtime_filters_need_few_keys() {
+        let prefilter = JoinPrefilter::new(100, 2, 0.01).with_runtime_filter_keys(50);
+        assert!(prefilter.applies_runtime(50, 100));
+        assert!(!prefilter.applies_runtime(51, 1000));
+        assert!(!prefilter.applies_runtime(5, 99));
+        assert!(!prefilter.applies_runtime(40, 79));
+        assert!(!prefilter
+            .with_runtime_filter_keys(0)
+            .applies_runtime(0, 1000));
+    }
+
+    #[test]
+    fn test_key_filter_records_metrics() {
+        let prefilter = JoinPrefilter::new(0, 1, 0.001);
+        let filter = prefilter.build((0..10).map(|i| Tuple::from_pair(i, i)), 10);
+        for i in 0..10 {
+            assert!(filter.might_match(&Tuple::from_pair(i, i)));
+        }
+        for i in 1000..2000 {
+            filter.might_match(&Tuple::from_pair(i, i));
+        }
+
+        let metrics = prefilter.metrics();
+        assert_eq!(metrics.filters_built, 1);
+        assert_eq!(metrics.probed, 1010);
+        assert!(metrics.passed >= 10);
+        assert!(metrics.rejected() > 900);
+        assert!(metrics.hit_rate() < 0.2);
+    }
+}
@@ -41,6 +41,28 @@ pub struct Config {
     pub logging: LoggingConfig,
     #[serde(default)]
     pub http: HttpConfig,
+    #[serde(default)]
+    pub grpc: GrpcConfig,
+    #[serde(default)]
+    pub flight: FlightConfig,
+    #[serde(default)]
+    pub tracing: TracingConfig,
+    #[serde(default)]
+    pub audit: AuditConfig,
+    #[serde(default)]
+    pub replication: ReplicationConfig,
+    #[serde(default)]
+    pub cluster: ClusterConfig,
+    #[serde(default)]
+    pub sharding: ShardingConfig,
+    #[serde(default)]
+    pub timely: TimelyConfig,
+    #[serde(default)]
+    pub cdc: CdcConfig,
+    #[serde(default)]
+    pub kafka: KafkaConfig,
+    #[serde(default)]
+    pub admission: AdmissionConfig,
 }
 
 /// Storage engine configuration
@@ -72,6 +94,45 @@ pub struct StorageConfig {
     /// Maximum number of knowledge graphs allowed (0 = unlimited)
     #[serde(default = "default_max_knowledge_graphs")]
     pub max_knowledge_graphs: usize,
+
+    /// Schema validation on writes (check constraint handling)
+    #[serde(default)]
+    pub validation: crate::schema::ValidationPolicy,
+
+    /// Implicit type coercion on insert and in joins
+    #[serde(default)]
+    pub coercion: crate::value::coercion::CoercionMode,
+
+    /// Seconds between sweeps deleting tuples expired by retention
+    /// policies (0 = disabled)
+    #[serde(default = "default_retention_sweep_interval_secs")]
+    pub retention_sweep_interval_secs: u64,
+
+    /// Seconds between sweeps refreshing views with a periodic refresh
+    /// policy (0 = disabled)
+    #[serde(default = "default_view_refresh_sweep_interval_secs")]
+    pub view_refresh_sweep_interval_secs: u64,
+
+    /// Which relations are kept in memory
+    #[serde(default)]
+    pub residency: ResidencyConfig,
+}
+
+/// Relation residency: which relations of a knowledge graph are held in
+/// memory
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResidencyConfig {
+    /// Load relations from disk on first use instead of when the
+    /// knowledge graph is loaded
+    #[serde(default)]
+    pub lazy_load: bool,
+
+    /// Estimated bytes of relation data each knowledge graph may hold in
+    /// memory; least recently used relations beyond it are evicted and
+    /// reloaded on their next use (0 = unlimited)
+    #[serde(default)]
+    pub max_resident_bytes: usize,
 }
 
 /// Persistence configuration (legacy)
@@ -118,6 +179,31 @@ pub struct PersistLayerConfig {
     #[serde(default = "default_max_wal_size_bytes")]
     pub max_wal_size_bytes: u64,
 
+    /// WAL segment size in bytes. The active WAL file is sealed and a new one
+    /// started once it reaches this size, so flushing a shard only rewrites
+    /// the segments holding its entries. 0 = single file.
+    #[serde(default = "default_wal_segment_size_bytes")]
+    pub wal_segment_size_bytes: u64,
+
+    /// How often buffered WAL writes are synced to disk in `batched` mode,
+    /// in milliseconds. Bounds the writes lost on power failure.
+    #[serde(default = "default_wal_sync_interval_ms")]
+    pub wal_sync_interval_ms: u64,
+
+    /// What to do with batch files that fail verification after an
+    /// unclean shutdown (quarantine or fail)
+    #[serde(default)]
+    pub on_corruption: CorruptionPolicy,
+
+    /// Keep sealed WAL segments in `wal/archive/` for point-in-time
+    /// recovery (`.kg restore`)
+    #[serde(default)]
+    pub wal_archive: bool,
+
+    /// Delete archived WAL segments older than this many seconds (0 = keep forever)
+    #[serde(default = "default_wal_archive_retention_secs")]
+    pub wal_archive_retention_secs: u64,
+
     /// Auto-compaction: maximum number of batch files per shard before triggering
     /// background compaction. 0 = disabled (manual `.compact` only).
     #[serde(default = "default_auto_compact_threshold")]
@@ -126,6 +212,75 @@ pub struct PersistLayerConfig {
     /// Auto-compaction check interval in seconds. 0 = disabled.
     #[serde(default = "default_auto_compact_interval_secs")]
     pub auto_compact_interval_secs: u64,
+
+    /// Mirror batch files, shard metadata and WAL segments to an object
+    /// store, using the local persist directory as a cache
+    #[serde(default)]
+    pub object_store: Option<ObjectStoreConfig>,
+
+    /// Encrypt batch files, WAL records and shard metadata at rest
+    #[serde(default)]
+    pub encryption: EncryptionConfig,
+}
+
+/// Encryption at rest of persist files (AES-256-GCM)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncryptionConfig {
+    /// Encrypt files written from now on; existing plain files stay
+    /// readable until a key rotation rewrites them
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Base64 encoded 32-byte key new files are encrypted with. Falls back
+    /// to `IL_ENCRYPTION_KEY`.
+    #[serde(default)]
+    pub key: Option<String>,
+
+    /// Keys used before a rotation, still accepted for reading files they
+    /// encrypted. Falls back to `IL_ENCRYPTION_PREVIOUS_KEYS`, comma
+    /// separated.
+    #[serde(default)]
+    pub previous_keys: Vec<String>,
+
+    /// Reject plain files instead of reading them. Turned on for good by
+    /// the first key rotation, which leaves no plain file behind.
+    #[serde(default)]
+    pub reject_plaintext: bool,
+}
+
+/// Object store (S3, GCS or compatible) holding persist files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ObjectStoreConfig {
+    /// Store location: `s3://bucket/prefix`, `gs://bucket/prefix` or
+    /// `file:///path`
+    pub url: String,
+
+    /// Region (S3). Falls back to `AWS_REGION`.
+    #[serde(default)]
+    pub region: Option<String>,
+
+    /// Endpoint of an S3-compatible store such as MinIO or R2
+    #[serde(default)]
+    pub endpoint: Option<String>,
+
+    /// Access key ID (S3). Falls back to `AWS_ACCESS_KEY_ID`.
+    #[serde(default)]
+    pub access_key_id: Option<String>,
+
+    /// Secret access key (S3). Falls back to `AWS_SECRET_ACCESS_KEY`.
+    #[serde(default)]
+    pub secret_access_key: Option<String>,
+
+    /// Service account key file (GCS). Falls back to
+    /// `GOOGLE_SERVICE_ACCOUNT`.
+    #[serde(default)]
+    pub service_account_path: Option<PathBuf>,
+
+    /// Allow plain HTTP endpoints
+    #[serde(default)]
+    pub allow_http: bool,
 }
 
 fn default_buffer_size() -> usize {
@@ -136,6 +291,18 @@ fn default_max_wal_size_bytes() -> u64 {
     67_108_864 // 64 MB
 }
 
+fn default_wal_segment_size_bytes() -> u64 {
+    16_777_216 // 16 MB
+}
+
+fn default_wal_sync_interval_ms() -> u64 {
+    1000
+}
+
+fn default_wal_archive_retention_secs() -> u64 {
+    604_800 // 7 days
+}
+
 fn default_auto_compact_threshold() -> usize {
     10 // Compact when a shard has 10+ batch files
 }
@@ -152,8 +319,15 @@ impl Default for PersistLayerConfig {
             durability_mode: DurabilityMode::Immediate,
             compaction_window: 0,
             max_wal_size_bytes: default_max_wal_size_bytes(),
+            wal_segment_size_bytes: default_wal_segment_size_bytes(),
+            wal_sync_interval_ms: default_wal_sync_interval_ms(),
+            on_corruption: CorruptionPolicy::Quarantine,
+            wal_archive: false,
+            wal_archive_retention_secs: default_wal_archive_retention_secs(),
             auto_compact_threshold: default_auto_compact_threshold(),
             auto_compact_interval_secs: default_auto_compact_interval_secs(),
+            object_store: None,
+            encryption: EncryptionConfig::default(),
         }
     }
 }
@@ -182,6 +356,18 @@ pub enum CompressionType {
     None,
 }
 
+/// Handling of batch files that fail verification on startup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CorruptionPolicy {
+    /// Move the file to `quarantine/`, log it and start without its data
+    #[default]
+    Quarantine,
+
+    /// Refuse to start until the data directory is repaired
+    Fail,
+}
+
 /// Write durability mode - controls when writes are considered durable
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
@@ -247,12 +433,24 @@ pub struct PerformanceConfig {
     #[serde(default = "default_slow_query_log_ms")]
     pub slow_query_log_ms: u64,
 
+    /// Also record slow queries, with their optimized IR, input cardinalities
+    /// and per-rule timings, in the `slow_queries` relation of the `_audit`
+    /// knowledge graph.
+    #[serde(default = "default_true")]
+    pub slow_query_relation: bool,
+
     /// Maximum query cost score. Queries exceeding this are rejected before
     /// execution. Cost is estimated from the IR tree (joins, aggregations,
     /// negation, recursion). 0 = no limit.
     #[serde(default)]
     pub max_query_cost: u64,
 
+    /// Maximum bytes a single query may hold at once in dataflow
+    /// arrangements and derived relations. Queries exceeding it are aborted
+    /// with a resource error. 0 = no limit.
+    #[serde(default)]
+    pub max_query_memory_bytes: usize,
+
     /// Timing profiling mode for query execution.
     /// "off" = no overhead, "summary" = stage totals (default), "detailed" = per-rule breakdown.
     #[serde(default)]
@@ -293,6 +491,11 @@ pub struct LoggingConfig {
     /// Log format (text, json)
     #[serde(default = "default_log_format")]
     pub format: String,
+
+    /// Level per module target, overriding `level` for that module and
+    /// its children (e.g. `"inputlayer::code_generator" = "debug"`)
+    #[serde(default)]
+    pub targets: std::collections::BTreeMap<String, String>,
 }
 
 /// HTTP server configuration for WebSocket API and GUI
@@ -319,31 +522,606 @@ pub struct HttpConfig {
     #[serde(default)]
     pub cors_allow_all: bool,
 
-    /// GUI static file serving configuration
+    /// GUI static file serving configuration
+    #[serde(default)]
+    pub gui: GuiConfig,
+
+    /// Authentication configuration
+    #[serde(default)]
+    pub auth: AuthConfig,
+
+    /// WebSocket idle timeout in milliseconds. 0 = disabled.
+    #[serde(default = "default_ws_idle_timeout_ms")]
+    pub ws_idle_timeout_ms: u64,
+
+    /// Seconds to wait on shutdown for running programs to finish before
+    /// flushing storage. New programs are refused meanwhile.
+    #[serde(default = "default_drain_timeout_secs")]
+    pub drain_timeout_secs: u64,
+
+    /// Graceful shutdown timeout in seconds. If the storage lock cannot be acquired
+    /// within this time during shutdown, WAL flush is skipped (safe - replayed on restart).
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+
+    /// Stats endpoint computation timeout in seconds. Large deployments with many
+    /// KGs/relations may need a higher value.
+    #[serde(default = "default_stats_timeout_secs")]
+    pub stats_timeout_secs: u64,
+
+    /// Most changes a WebSocket view subscription may have pending, and
+    /// kept for resume. A subscriber that falls further behind is dropped.
+    #[serde(default = "default_ws_subscription_max_rows")]
+    pub ws_subscription_max_rows: usize,
+
+    /// How long a view subscription outlives its connection, waiting to be
+    /// resumed, in seconds. 0 = dropped on disconnect.
+    #[serde(default = "default_ws_subscription_resume_secs")]
+    pub ws_subscription_resume_secs: u64,
+
+    /// How long a result cursor is kept without a fetch, in seconds.
+    /// 0 = no cursors (results that fit in one page are still returned).
+    #[serde(default = "default_cursor_idle_secs")]
+    pub cursor_idle_secs: u64,
+
+    /// Open result cursors per client. 0 = unlimited.
+    #[serde(default = "default_max_cursors_per_client")]
+    pub max_cursors_per_client: usize,
+
+    /// Most rows in one cursor page
+    #[serde(default = "default_cursor_max_page_rows")]
+    pub cursor_max_page_rows: usize,
+
+    /// Rate limiting configuration
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+
+    /// TLS for the HTTP, WebSocket and gRPC listeners
+    #[serde(default)]
+    pub tls: TlsConfig,
+
+    /// WebSocket frame compression
+    #[serde(default)]
+    pub compression: CompressionConfig,
+}
+
+/// WebSocket frame compression. Clients offer codecs when they
+/// authenticate; large messages on connections that agreed on one are sent
+/// compressed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CompressionConfig {
+    /// Accepted codecs, most preferred first ("zstd", "lz4").
+    /// Empty = compression disabled.
+    #[serde(default = "default_compression_codecs")]
+    pub codecs: Vec<crate::protocol::compression::Codec>,
+
+    /// Messages smaller than this (in bytes) are sent uncompressed
+    #[serde(default = "default_compression_min_bytes")]
+    pub min_bytes: usize,
+
+    /// zstd compression level (1-22)
+    #[serde(default = "default_zstd_level")]
+    pub zstd_level: i32,
+}
+
+/// TLS configuration. When enabled, the HTTP server (and the gRPC server,
+/// if running) only accept TLS connections.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    /// Serve over TLS
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// PEM certificate chain, leaf first
+    #[serde(default)]
+    pub cert_path: Option<PathBuf>,
+
+    /// PEM private key (PKCS#8, PKCS#1 or SEC1)
+    #[serde(default)]
+    pub key_path: Option<PathBuf>,
+
+    /// PEM CA bundle for verifying client certificates (mutual TLS).
+    /// Unset = client certificates are not requested.
+    #[serde(default)]
+    pub client_ca_path: Option<PathBuf>,
+
+    /// Reject clients without a certificate signed by `client_ca_path`.
+    /// When false, a certificate is verified if presented but not required.
+    #[serde(default = "default_true")]
+    pub require_client_cert: bool,
+
+    /// Certificates chosen by the server name the client asks for (SNI).
+    /// Clients that send no name, or an unlisted one, get `cert_path`.
+    #[serde(default)]
+    pub sni: Vec<SniCertConfig>,
+}
+
+/// A certificate served for one host name
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SniCertConfig {
+    /// Host name, e.g. "eu.db.example.com"
+    pub server_name: String,
+    /// PEM certificate chain, leaf first
+    pub cert_path: PathBuf,
+    /// PEM private key
+    pub key_path: PathBuf,
+}
+
+/// gRPC server configuration (`grpc` feature)
+///
+/// The service is defined in `proto/inputlayer.proto`. Calls authenticate
+/// with the same API keys as the HTTP API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GrpcConfig {
+    /// Enable the gRPC server alongside the HTTP server
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// gRPC server bind address
+    #[serde(default = "default_http_host")]
+    pub host: String,
+
+    /// gRPC server port
+    #[serde(default = "default_grpc_port")]
+    pub port: u16,
+}
+
+/// Arrow Flight server configuration (`flight` feature)
+///
+/// `DoGet` tickets carry a query; its result streams back as Arrow record
+/// batches. Calls authenticate with the same API keys as the HTTP API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FlightConfig {
+    /// Enable the Flight server alongside the HTTP server
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Flight server bind address
+    #[serde(default = "default_http_host")]
+    pub host: String,
+
+    /// Flight server port
+    #[serde(default = "default_flight_port")]
+    pub port: u16,
+}
+
+/// OpenTelemetry trace export (`otel` feature)
+///
+/// Spans for each query (parse, optimize, execute per rule, persist) are
+/// batched and sent to an OTLP/gRPC collector such as Jaeger or Tempo.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TracingConfig {
+    /// Export spans over OTLP
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// OTLP/gRPC collector endpoint
+    #[serde(default = "default_otlp_endpoint")]
+    pub otlp_endpoint: String,
+
+    /// `service.name` reported with every span
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+
+    /// Fraction of traces exported (0.0 - 1.0). Traces started by a caller
+    /// that propagates a sampling decision follow that decision.
+    #[serde(default = "default_sample_ratio")]
+    pub sample_ratio: f64,
+}
+
+/// Statement audit log (see [`crate::audit`])
+///
+/// Records who ran each program, when, against which knowledge graph, with
+/// its text, result rows, duration and outcome.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuditConfig {
+    /// Record every program clients run
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Directory of the JSON Lines audit files (default: `audit` under
+    /// `storage.data_dir`)
+    #[serde(default)]
+    pub dir: Option<PathBuf>,
+
+    /// Size in bytes at which the active audit file is rotated. 0 = never.
+    #[serde(default = "default_audit_max_file_bytes")]
+    pub max_file_bytes: u64,
+
+    /// Rotated audit files kept; older ones are deleted
+    #[serde(default = "default_audit_max_files")]
+    pub max_files: usize,
+
+    /// Also append each record to the `statements` relation of the
+    /// read-only `_audit` knowledge graph, so admins can query it
+    #[serde(default = "default_true")]
+    pub relation: bool,
+}
+
+/// Role of this server in leader-follower replication
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplicationRole {
+    /// No replication; changes are not logged for followers
+    #[default]
+    None,
+
+    /// Log every committed change and stream it to followers
+    Leader,
+
+    /// Apply the changes streamed by `leader_url` and serve reads only,
+    /// until promoted with `.replication promote`
+    Follower,
+}
+
+/// Leader-follower replication (see [`crate::protocol::replication`])
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReplicationConfig {
+    #[serde(default)]
+    pub role: ReplicationRole,
+
+    /// HTTP address of the leader, e.g. `http://leader:8080` (followers)
+    #[serde(default)]
+    pub leader_url: Option<String>,
+
+    /// Admin API key the follower presents to the leader
+    #[serde(default)]
+    pub api_key: Option<String>,
+
+    /// Bytes of recent changes kept for followers that reconnect. A
+    /// follower further behind is sent a full snapshot instead.
+    #[serde(default = "default_replication_log_bytes")]
+    pub log_max_bytes: usize,
+
+    /// Delay before a follower reconnects after losing the leader
+    #[serde(default = "default_replication_reconnect_ms")]
+    pub reconnect_ms: u64,
+}
+
+/// A server of the cluster
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClusterMember {
+    /// Unique, stable ID of the server
+    pub id: u64,
+    /// HTTP address the other members reach it at, e.g. `http://node1:8080`
+    pub url: String,
+}
+
+/// Raft-elected writer and metadata ordering (see
+/// [`crate::protocol::cluster`]). Disabled while `members` is empty.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClusterConfig {
+    /// This server's ID among `members`
+    #[serde(default)]
+    pub node_id: u64,
+
+    /// Every server of the cluster, this one included
+    #[serde(default)]
+    pub members: Vec<ClusterMember>,
+
+    /// Secret shared by the members, used to authenticate to each other
+    #[serde(default)]
+    pub secret: Option<String>,
+
+    /// Time without hearing from a leader before a member stands for
+    /// election; randomized up to twice this
+    #[serde(default = "default_cluster_election_timeout_ms")]
+    pub election_timeout_ms: u64,
+
+    /// Interval of the leader's heartbeats
+    #[serde(default = "default_cluster_heartbeat_ms")]
+    pub heartbeat_ms: u64,
+
+    /// Time a schema change waits for a majority to agree on its order
+    /// before it is rejected
+    #[serde(default = "default_cluster_ddl_timeout_ms")]
+    pub ddl_timeout_ms: u64,
+}
+
+impl ClusterConfig {
+    /// Whether this server is part of a cluster
+    pub fn is_enabled(&self) -> bool {
+        !self.members.is_empty()
+    }
+}
+
+/// An engine node holding a slice of the sharded relations
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShardNode {
+    /// Unique, stable name of the shard
+    pub id: String,
+    /// HTTP address of the shard, e.g. `http://shard1:8080`
+    pub url: String,
+}
+
+/// Routing of sharded relations to engine nodes (see
+/// [`crate::protocol::shard`]). Disabled while `shards` is empty.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShardingConfig {
+    /// Shards in placement order. Hash placement depends on the order and
+    /// count, so they must not change once relations are sharded.
+    #[serde(default)]
+    pub shards: Vec<ShardNode>,
+
+    /// Admin API key the router presents to the shards
+    #[serde(default)]
+    pub api_key: Option<String>,
+
+    /// Time a shard has to answer one request
+    #[serde(default = "default_sharding_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl ShardingConfig {
+    /// Whether this server routes sharded relations
+    pub fn is_enabled(&self) -> bool {
+        !self.shards.is_empty()
+    }
+}
+
+/// Processes that run query dataflows together (see
+/// [`crate::execution::ClusterBackend`]). Disabled while `processes` is empty.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TimelyConfig {
+    /// `host:port` every process listens on for the others, the coordinator
+    /// first. All processes must list the same addresses in the same order.
+    #[serde(default)]
+    pub processes: Vec<String>,
+
+    /// Index of this process in `processes`. Process 0 is the server that
+    /// takes queries; the others only run dataflows.
+    #[serde(default)]
+    pub process: usize,
+
+    /// Worker threads per process, the same on every process
+    #[serde(default = "default_timely_workers")]
+    pub workers: usize,
+
+    /// Secret the processes authenticate to each other with
+    #[serde(default)]
+    pub secret: Option<String>,
+
+    /// Plans whose inputs hold fewer rows run on the coordinator alone
+    #[serde(default = "default_timely_min_rows")]
+    pub min_rows: usize,
+
+    /// Time allowed for the processes to connect for one plan
+    #[serde(default = "default_timely_connect_timeout_ms")]
+    pub connect_timeout_ms: u64,
+}
+
+impl TimelyConfig {
+    /// Whether dataflows run across several processes
+    pub fn is_enabled(&self) -> bool {
+        !self.processes.is_empty()
+    }
+
+    /// Whether this process runs dataflows for a coordinator instead of
+    /// serving queries
+    pub fn is_worker(&self) -> bool {
+        self.is_enabled() && self.process > 0
+    }
+}
+
+/// Change data capture (see [`crate::storage_engine::ChangeFeed`])
+///
+/// Publishes every committed insert and delete to `changes.jsonl`, to
+/// subscribers of `GET /v1/changes`, and optionally to a Kafka topic.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CdcConfig {
+    /// Publish committed changes
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Knowledge graphs whose changes are published (default: all but the
+    /// system knowledge graphs, which are never published)
+    #[serde(default)]
+    pub knowledge_graphs: Vec<String>,
+
+    /// Append changes to rotated JSON Lines files
+    #[serde(default = "default_true")]
+    pub file: bool,
+
+    /// Directory of the change files (default: `cdc` under
+    /// `storage.data_dir`)
+    #[serde(default)]
+    pub dir: Option<PathBuf>,
+
+    /// Size in bytes at which the active change file is rotated. 0 = never.
+    #[serde(default = "default_cdc_max_file_bytes")]
+    pub max_file_bytes: u64,
+
+    /// Rotated change files kept; older ones are deleted
+    #[serde(default = "default_cdc_max_files")]
+    pub max_files: usize,
+
+    /// Transactions buffered for each `GET /v1/changes` subscriber. One
+    /// that falls further behind is disconnected.
+    #[serde(default = "default_cdc_subscriber_buffer")]
+    pub subscriber_buffer: usize,
+
+    /// Kafka topic to produce changes to (needs the `kafka` feature)
+    #[serde(default)]
+    pub kafka: Option<CdcKafkaConfig>,
+}
+
+/// Kafka sink of change data capture
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CdcKafkaConfig {
+    /// Comma-separated `host:port` bootstrap brokers
+    pub brokers: String,
+
+    /// Topic the changes are produced to, keyed by knowledge graph
+    pub topic: String,
+
+    /// Extra librdkafka producer properties (`"compression.type" = "lz4"`)
+    #[serde(default)]
+    pub properties: std::collections::BTreeMap<String, String>,
+}
+
+impl CdcConfig {
+    /// Whether changes to `kg` are published
+    pub fn publishes(&self, kg: &str) -> bool {
+        self.enabled
+            && kg != crate::auth::INTERNAL_KG
+            && kg != crate::audit::AUDIT_KG
+            && (self.knowledge_graphs.is_empty() || self.knowledge_graphs.iter().any(|k| k == kg))
+    }
+}
+
+/// Kafka topics consumed into relations (see [`crate::protocol::kafka`],
+/// `kafka` feature). Disabled while `topics` is empty.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KafkaConfig {
+    /// Comma-separated `host:port` bootstrap brokers
+    #[serde(default)]
+    pub brokers: String,
+
+    /// Consumer group reported to the brokers. Offsets are checkpointed by
+    /// the server, not committed to the group.
+    #[serde(default = "default_kafka_group_id")]
+    pub group_id: String,
+
+    /// Topics and the relations they are inserted into
+    #[serde(default)]
+    pub topics: Vec<KafkaTopicConfig>,
+
+    /// Messages inserted per batch, at most
+    #[serde(default = "default_kafka_batch_size")]
+    pub batch_size: usize,
+
+    /// Time a batch waits to fill before it is inserted anyway
+    #[serde(default = "default_kafka_batch_wait_ms")]
+    pub batch_wait_ms: u64,
+
+    /// Extra librdkafka consumer properties (`"security.protocol" = "ssl"`)
+    #[serde(default)]
+    pub properties: std::collections::BTreeMap<String, String>,
+}
+
+/// Encoding of Kafka message payloads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum KafkaFormat {
+    /// One JSON object per message
+    #[default]
+    Json,
+    /// One Avro record per message, in the topic's writer schema
+    Avro,
+}
+
+/// Where to start reading a partition that has no checkpoint yet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum KafkaStart {
+    /// The oldest message the topic still holds
+    #[default]
+    Earliest,
+    /// Only messages produced from now on
+    Latest,
+}
+
+/// A Kafka topic consumed into a relation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KafkaTopicConfig {
+    pub topic: String,
+
+    /// Knowledge graph of the relation (default:
+    /// `storage.default_knowledge_graph`)
+    #[serde(default)]
+    pub knowledge_graph: Option<String>,
+
+    pub relation: String,
+
+    #[serde(default)]
+    pub format: KafkaFormat,
+
+    /// Field read into each column, in column order; dots name nested
+    /// fields (`customer.id`). Default: the names of the relation's
+    /// declared columns.
+    #[serde(default)]
+    pub columns: Vec<String>,
+
+    /// Writer schema of Avro payloads, as JSON
+    #[serde(default)]
+    pub avro_schema: Option<String>,
+
+    /// Avro payloads start with the 5-byte Confluent schema registry
+    /// header (magic byte and schema ID)
+    #[serde(default)]
+    pub confluent_header: bool,
+
     #[serde(default)]
-    pub gui: GuiConfig,
+    pub start: KafkaStart,
+}
 
-    /// Authentication configuration
+impl KafkaConfig {
+    /// Whether any topic is consumed
+    pub fn is_enabled(&self) -> bool {
+        !self.topics.is_empty()
+    }
+}
+
+/// Admission control in front of the query engine (see
+/// [`crate::protocol::admission`])
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AdmissionConfig {
+    /// Queries running at once (0 = the CPU cores left after reserving
+    /// a quarter, at least 2, for network I/O)
     #[serde(default)]
-    pub auth: AuthConfig,
+    pub max_concurrent_queries: usize,
 
-    /// WebSocket idle timeout in milliseconds. 0 = disabled.
-    #[serde(default = "default_ws_idle_timeout_ms")]
-    pub ws_idle_timeout_ms: u64,
+    /// Queries waiting for a free slot; more are refused (0 = unlimited)
+    #[serde(default = "default_admission_max_queued")]
+    pub max_queued_queries: usize,
 
-    /// Graceful shutdown timeout in seconds. If the storage lock cannot be acquired
-    /// within this time during shutdown, WAL flush is skipped (safe - replayed on restart).
-    #[serde(default = "default_shutdown_timeout_secs")]
-    pub shutdown_timeout_secs: u64,
+    /// Queries waiting per knowledge graph; more are refused (0 = unlimited)
+    #[serde(default)]
+    pub max_queued_per_knowledge_graph: usize,
 
-    /// Stats endpoint computation timeout in seconds. Large deployments with many
-    /// KGs/relations may need a higher value.
-    #[serde(default = "default_stats_timeout_secs")]
-    pub stats_timeout_secs: u64,
+    /// Time a query may wait for a slot before it is refused (0 = no limit)
+    #[serde(default = "default_admission_queue_timeout_ms")]
+    pub queue_timeout_ms: u64,
 
-    /// Rate limiting configuration
+    /// Finished queries kept for `show queries`
+    #[serde(default = "default_admission_finished_history")]
+    pub finished_history: usize,
+
+    /// Priority of queries whose session and API key set none
+    /// ("interactive", "normal" or "batch")
     #[serde(default)]
-    pub rate_limit: RateLimitConfig,
+    pub default_priority: crate::protocol::admission::Priority,
+}
+
+impl AdmissionConfig {
+    /// Queries allowed to run at once
+    pub fn concurrency(&self) -> usize {
+        if self.max_concurrent_queries > 0 {
+            return self.max_concurrent_queries;
+        }
+        let ncpu = std::thread::available_parallelism().map_or(4, std::num::NonZero::get);
+        // Reserve ~25% of cores (min 2) for Tokio async I/O, health checks, WebSocket handling
+        let io_reserve = (ncpu / 4).max(2).min(ncpu - 1);
+        ncpu - io_reserve
+    }
 }
 
 /// GUI static file serving configuration
@@ -378,6 +1156,32 @@ pub struct AuthConfig {
     /// even when the data directory is wiped.
     #[serde(default)]
     pub credentials_file: Option<PathBuf>,
+
+    /// Allow username/password login on WebSocket connections. Set to false
+    /// to require an API key.
+    #[serde(default = "default_true")]
+    pub password_login: bool,
+
+    /// API keys defined here rather than with `.apikey create`. Only the
+    /// SHA-256 of each key is configured.
+    #[serde(default)]
+    pub api_keys: Vec<ApiKeyConfig>,
+}
+
+/// An API key from the configuration file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiKeyConfig {
+    /// Name shown in audit logs
+    pub label: String,
+    /// Hex SHA-256 of the key (`printf %s "$KEY" | sha256sum`)
+    pub key_sha256: String,
+    /// User the key authenticates as; its role and knowledge graph access apply
+    pub username: String,
+    /// Priority of the key's queries, and the highest a session using the
+    /// key may choose (unset = `[admission] default_priority`, no ceiling)
+    #[serde(default)]
+    pub priority: Option<crate::protocol::admission::Priority>,
 }
 
 /// Rate limiting configuration
@@ -407,6 +1211,23 @@ pub struct RateLimitConfig {
     /// Maximum HTTP requests per second per IP address (0 = unlimited) (#27)
     #[serde(default = "default_per_ip_max_rps")]
     pub per_ip_max_rps: u32,
+
+    /// Maximum HTTP requests per second per API key (0 = unlimited)
+    #[serde(default)]
+    pub per_key_max_rps: u32,
+
+    /// Maximum programs running at once per client: an API key, or a
+    /// WebSocket connection without one (0 = unlimited)
+    #[serde(default = "default_max_concurrent_queries_per_client")]
+    pub max_concurrent_queries_per_client: usize,
+
+    /// Maximum statements per second per client (0 = unlimited)
+    #[serde(default)]
+    pub max_statements_per_sec: u32,
+
+    /// Maximum size of one result, in bytes (0 = unlimited)
+    #[serde(default = "default_max_result_bytes")]
+    pub max_result_bytes: usize,
 }
 
 // Default value functions
@@ -440,9 +1261,33 @@ fn default_slow_query_log_ms() -> u64 {
 fn default_max_knowledge_graphs() -> usize {
     1000
 }
+fn default_retention_sweep_interval_secs() -> u64 {
+    60
+}
+fn default_view_refresh_sweep_interval_secs() -> u64 {
+    10
+}
 fn default_ws_idle_timeout_ms() -> u64 {
     300_000 // 5 minutes
 }
+fn default_ws_subscription_max_rows() -> usize {
+    100_000
+}
+fn default_ws_subscription_resume_secs() -> u64 {
+    60
+}
+fn default_cursor_idle_secs() -> u64 {
+    300
+}
+fn default_max_cursors_per_client() -> usize {
+    16
+}
+fn default_cursor_max_page_rows() -> usize {
+    10_000
+}
+fn default_drain_timeout_secs() -> u64 {
+    30
+}
 fn default_shutdown_timeout_secs() -> u64 {
     30
 }
@@ -461,6 +1306,91 @@ fn default_http_host() -> String {
 fn default_http_port() -> u16 {
     8080
 }
+fn default_grpc_port() -> u16 {
+    50051
+}
+fn default_flight_port() -> u16 {
+    50052
+}
+fn default_otlp_endpoint() -> String {
+    "http://localhost:4317".to_string()
+}
+fn default_service_name() -> String {
+    "inputlayer".to_string()
+}
+fn default_sample_ratio() -> f64 {
+    1.0
+}
+fn default_audit_max_file_bytes() -> u64 {
+    67_108_864 // 64 MB
+}
+fn default_audit_max_files() -> usize {
+    10
+}
+fn default_cdc_max_file_bytes() -> u64 {
+    67_108_864 // 64 MB
+}
+fn default_cdc_max_files() -> usize {
+    10
+}
+fn default_cdc_subscriber_buffer() -> usize {
+    1024
+}
+fn default_kafka_group_id() -> String {
+    "inputlayer".to_string()
+}
+fn default_kafka_batch_size() -> usize {
+    1000
+}
+fn default_kafka_batch_wait_ms() -> u64 {
+    100
+}
+fn default_admission_max_queued() -> usize {
+    1024
+}
+fn default_admission_queue_timeout_ms() -> u64 {
+    30_000
+}
+fn default_admission_finished_history() -> usize {
+    100
+}
+fn default_replication_log_bytes() -> usize {
+    268_435_456 // 256 MB
+}
+fn default_replication_reconnect_ms() -> u64 {
+    1000
+}
+fn default_cluster_election_timeout_ms() -> u64 {
+    1500
+}
+fn default_cluster_heartbeat_ms() -> u64 {
+    250
+}
+fn default_cluster_ddl_timeout_ms() -> u64 {
+    5000
+}
+fn default_sharding_timeout_ms() -> u64 {
+    30_000
+}
+fn default_timely_workers() -> usize {
+    1
+}
+fn default_timely_min_rows() -> usize {
+    100_000
+}
+fn default_timely_connect_timeout_ms() -> u64 {
+    5000
+}
+fn default_compression_codecs() -> Vec<crate::protocol::compression::Codec> {
+    use crate::protocol::compression::Codec;
+    vec![Codec::Zstd, Codec::Lz4]
+}
+fn default_compression_min_bytes() -> usize {
+    1024
+}
+fn default_zstd_level() -> i32 {
+    3
+}
 fn default_gui_static_dir() -> String {
     "./gui/dist".to_string()
 }
@@ -485,6 +1415,12 @@ fn default_notification_buffer_size() -> usize {
 fn default_per_ip_max_rps() -> u32 {
     100
 }
+fn default_max_concurrent_queries_per_client() -> usize {
+    16
+}
+fn default_max_result_bytes() -> usize {
+    256 * 1024 * 1024 // 256 MB
+}
 
 impl Default for RateLimitConfig {
     fn default() -> Self {
@@ -495,6 +1431,10 @@ impl Default for RateLimitConfig {
             ws_max_lifetime_secs: default_ws_max_lifetime_secs(),
             notification_buffer_size: default_notification_buffer_size(),
             per_ip_max_rps: default_per_ip_max_rps(),
+            per_key_max_rps: 0,
+            max_concurrent_queries_per_client: default_max_concurrent_queries_per_client(),
+            max_statements_per_sec: 0,
+            max_result_bytes: default_max_result_bytes(),
         }
     }
 }
@@ -556,6 +1496,182 @@ impl Config {
             );
         }
 
+        // Configured API keys: compared against the hash of presented keys
+        let mut labels = std::collections::HashSet::new();
+        for key in &mut self.http.auth.api_keys {
+            key.key_sha256 = key.key_sha256.trim().to_ascii_lowercase();
+            if key.key_sha256.len() != 64 || !key.key_sha256.bytes().all(|b| b.is_ascii_hexdigit())
+            {
+                return Err(format!(
+                    "http.auth.api_keys: key_sha256 of '{}' must be 64 hex characters",
+                    key.label
+                ));
+            }
+            if key.label.is_empty() || !labels.insert(key.label.clone()) {
+                return Err(format!(
+                    "http.auth.api_keys: labels must be non-empty and unique ('{}')",
+                    key.label
+                ));
+            }
+        }
+
+        let tls = &mut self.http.tls;
+        if tls.enabled {
+            if tls.cert_path.is_none() || tls.key_path.is_none() {
+                return Err("http.tls: cert_path and key_path are required".to_string());
+            }
+            let mut names = std::collections::HashSet::new();
+            for entry in &mut tls.sni {
+                entry.server_name = entry.server_name.trim().to_ascii_lowercase();
+                if entry.server_name.is_empty() || !names.insert(entry.server_name.clone()) {
+                    return Err(format!(
+                        "http.tls.sni: server names must be non-empty and unique ('{}')",
+                        entry.server_name
+                    ));
+                }
+            }
+        }
+
+        let compression = &self.http.compression;
+        if !(1..=22).contains(&compression.zstd_level) {
+            return Err(format!(
+                "http.compression: zstd_level must be between 1 and 22 (got {})",
+                compression.zstd_level
+            ));
+        }
+
+        for (target, level) in &self.logging.targets {
+            if level
+                .parse::<tracing_subscriber::filter::LevelFilter>()
+                .is_err()
+            {
+                return Err(format!(
+                    "logging.targets: invalid level '{level}' for '{target}'"
+                ));
+            }
+        }
+
+        let ratio = self.tracing.sample_ratio;
+        if !(0.0..=1.0).contains(&ratio) {
+            return Err(format!(
+                "tracing: sample_ratio must be between 0.0 and 1.0 (got {ratio})"
+            ));
+        }
+
+        if self.replication.role == ReplicationRole::Follower {
+            if self.replication.leader_url.is_none() {
+                return Err("replication: a follower needs leader_url".to_string());
+            }
+            if self.replication.api_key.is_none() {
+                return Err("replication: a follower needs the leader's api_key".to_string());
+            }
+        }
+
+        if self.cluster.is_enabled() {
+            let cluster = &self.cluster;
+            if self.replication.role != ReplicationRole::None {
+                return Err(
+                    "cluster: leave replication.role unset, the cluster elects the leader"
+                        .to_string(),
+                );
+            }
+            if !cluster.members.iter().any(|m| m.id == cluster.node_id) {
+                return Err(format!(
+                    "cluster: node_id {} is not one of the members",
+                    cluster.node_id
+                ));
+            }
+            let mut ids: Vec<u64> = cluster.members.iter().map(|m| m.id).collect();
+            ids.sort_unstable();
+            ids.dedup();
+            if ids.len() != cluster.members.len() {
+                return Err("cluster: member ids must be unique".to_string());
+            }
+            if cluster.secret.as_deref().is_none_or(str::is_empty) {
+                return Err("cluster: members need a shared secret".to_string());
+            }
+            if cluster.heartbeat_ms == 0 || cluster.heartbeat_ms >= cluster.election_timeout_ms {
+                return Err(format!(
+                    "cluster: heartbeat_ms ({}) must be positive and below election_timeout_ms ({})",
+                    cluster.heartbeat_ms, cluster.election_timeout_ms
+                ));
+            }
+        }
+
+        if self.sharding.is_enabled() {
+            let sharding = &self.sharding;
+            let mut ids: Vec<&str> = sharding.shards.iter().map(|s| s.id.as_str()).collect();
+            ids.sort_unstable();
+            ids.dedup();
+            if ids.len() != sharding.shards.len() {
+                return Err("sharding: shard ids must be unique".to_string());
+            }
+            if sharding.api_key.as_deref().is_none_or(str::is_empty) {
+                return Err("sharding: the router needs the shards' admin api_key".to_string());
+            }
+            if sharding.timeout_ms == 0 {
+                return Err("sharding: timeout_ms must be positive".to_string());
+            }
+        }
+
+        if self.timely.is_enabled() {
+            let timely = &self.timely;
+            if timely.processes.len() < 2 {
+                return Err("timely: list at least two processes".to_string());
+            }
+            if timely.process >= timely.processes.len() {
+                return Err(format!(
+                    "timely: process {} is not among the {} processes",
+                    timely.process,
+                    timely.processes.len()
+                ));
+            }
+            if timely.workers == 0 {
+                return Err("timely: workers must be positive".to_string());
+            }
+            if timely.secret.as_deref().is_none_or(str::is_empty) {
+                return Err("timely: processes need a shared secret".to_string());
+            }
+            if timely.connect_timeout_ms == 0 {
+                return Err("timely: connect_timeout_ms must be positive".to_string());
+            }
+        }
+
+        if self.cdc.enabled {
+            if self.cdc.subscriber_buffer == 0 {
+                return Err("cdc: subscriber_buffer must be positive".to_string());
+            }
+            if let Some(kafka) = &self.cdc.kafka {
+                if kafka.brokers.trim().is_empty() || kafka.topic.trim().is_empty() {
+                    return Err("cdc.kafka: brokers and topic are required".to_string());
+                }
+            }
+        }
+
+        if self.kafka.is_enabled() {
+            let kafka = &self.kafka;
+            if kafka.brokers.trim().is_empty() {
+                return Err("kafka: brokers are required to consume topics".to_string());
+            }
+            if kafka.batch_size == 0 {
+                return Err("kafka: batch_size must be positive".to_string());
+            }
+            let mut topics: Vec<&str> = kafka.topics.iter().map(|t| t.topic.as_str()).collect();
+            topics.sort_unstable();
+            topics.dedup();
+            if topics.len() != kafka.topics.len() {
+                return Err("kafka: each topic can be listed once".to_string());
+            }
+            for topic in &kafka.topics {
+                if topic.format == KafkaFormat::Avro && topic.avro_schema.is_none() {
+                    return Err(format!(
+                        "kafka: topic '{}' needs avro_schema to read Avro payloads",
+                        topic.topic
+                    ));
+                }
+            }
+        }
+
         // Warn about extremely high WS connection limits
         if self.http.rate_limit.max_ws_connections > 100_000 {
             tracing::warn!(
@@ -632,10 +1748,17 @@ impl Config {
                     max_string_value_bytes: 65_536,
                     max_result_rows: 100_000,
                     slow_query_log_ms: 5000,
+                    slow_query_relation: true,
                     max_query_cost: 0,
+                    max_query_memory_bytes: 0,
                     timing_mode: crate::execution::TimingMode::default(),
                 },
                 max_knowledge_graphs: 1000,
+                validation: crate::schema::ValidationPolicy::default(),
+                coercion: crate::value::coercion::CoercionMode::default(),
+                retention_sweep_interval_secs: default_retention_sweep_interval_secs(),
+                view_refresh_sweep_interval_secs: default_view_refresh_sweep_interval_secs(),
+                residency: ResidencyConfig::default(),
             },
             optimization: OptimizationConfig {
                 enable_join_planning: true,
@@ -644,11 +1767,19 @@ impl Config {
                 enable_boolean_specialization: true,
                 enable_magic_sets: true,
             },
-            logging: LoggingConfig {
-                level: "info".to_string(),
-                format: "text".to_string(),
-            },
+            logging: LoggingConfig::default(),
             http: HttpConfig::default(),
+            grpc: GrpcConfig::default(),
+            flight: FlightConfig::default(),
+            tracing: TracingConfig::default(),
+            audit: AuditConfig::default(),
+            replication: ReplicationConfig::default(),
+            cluster: ClusterConfig::default(),
+            sharding: ShardingConfig::default(),
+            timely: TimelyConfig::default(),
+            cdc: CdcConfig::default(),
+            kafka: KafkaConfig::default(),
+            admission: AdmissionConfig::default(),
         }
     }
 }
@@ -672,7 +1803,9 @@ impl Default for PerformanceConfig {
             max_string_value_bytes: default_max_string_value_bytes(),
             max_result_rows: 100_000, // match Config::default()
             slow_query_log_ms: default_slow_query_log_ms(),
-            max_query_cost: 0, // 0 = unlimited
+            slow_query_relation: true,
+            max_query_cost: 0,         // 0 = unlimited
+            max_query_memory_bytes: 0, // 0 = unlimited
             timing_mode: crate::execution::TimingMode::default(),
         }
     }
@@ -683,6 +1816,7 @@ impl Default for LoggingConfig {
         LoggingConfig {
             level: default_log_level(),
             format: default_log_format(),
+            targets: std::collections::BTreeMap::new(),
         }
     }
 }
@@ -698,9 +1832,172 @@ impl Default for HttpConfig {
             gui: GuiConfig::default(),
             auth: AuthConfig::default(),
             ws_idle_timeout_ms: default_ws_idle_timeout_ms(),
+            drain_timeout_secs: default_drain_timeout_secs(),
             shutdown_timeout_secs: default_shutdown_timeout_secs(),
             stats_timeout_secs: default_stats_timeout_secs(),
+            ws_subscription_max_rows: default_ws_subscription_max_rows(),
+            ws_subscription_resume_secs: default_ws_subscription_resume_secs(),
+            cursor_idle_secs: default_cursor_idle_secs(),
+            max_cursors_per_client: default_max_cursors_per_client(),
+            cursor_max_page_rows: default_cursor_max_page_rows(),
             rate_limit: RateLimitConfig::default(),
+            tls: TlsConfig::default(),
+            compression: CompressionConfig::default(),
+        }
+    }
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        TlsConfig {
+            enabled: false,
+            cert_path: None,
+            key_path: None,
+            client_ca_path: None,
+            require_client_cert: true,
+            sni: Vec::new(),
+        }
+    }
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        CompressionConfig {
+            codecs: default_compression_codecs(),
+            min_bytes: default_compression_min_bytes(),
+            zstd_level: default_zstd_level(),
+        }
+    }
+}
+
+impl Default for GrpcConfig {
+    fn default() -> Self {
+        GrpcConfig {
+            enabled: false,
+            host: default_http_host(),
+            port: default_grpc_port(),
+        }
+    }
+}
+
+impl Default for FlightConfig {
+    fn default() -> Self {
+        FlightConfig {
+            enabled: false,
+            host: default_http_host(),
+            port: default_flight_port(),
+        }
+    }
+}
+
+impl Default for TracingConfig {
+    fn default() -> Self {
+        TracingConfig {
+            enabled: false,
+            otlp_endpoint: default_otlp_endpoint(),
+            service_name: default_service_name(),
+            sample_ratio: default_sample_ratio(),
+        }
+    }
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        AuditConfig {
+            enabled: false,
+            dir: None,
+            max_file_bytes: default_audit_max_file_bytes(),
+            max_files: default_audit_max_files(),
+            relation: true,
+        }
+    }
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        ClusterConfig {
+            node_id: 0,
+            members: Vec::new(),
+            secret: None,
+            election_timeout_ms: default_cluster_election_timeout_ms(),
+            heartbeat_ms: default_cluster_heartbeat_ms(),
+            ddl_timeout_ms: default_cluster_ddl_timeout_ms(),
+        }
+    }
+}
+
+impl Default for ShardingConfig {
+    fn default() -> Self {
+        ShardingConfig {
+            shards: Vec::new(),
+            api_key: None,
+            timeout_ms: default_sharding_timeout_ms(),
+        }
+    }
+}
+
+impl Default for TimelyConfig {
+    fn default() -> Self {
+        TimelyConfig {
+            processes: Vec::new(),
+            process: 0,
+            workers: default_timely_workers(),
+            secret: None,
+            min_rows: default_timely_min_rows(),
+            connect_timeout_ms: default_timely_connect_timeout_ms(),
+        }
+    }
+}
+
+impl Default for CdcConfig {
+    fn default() -> Self {
+        CdcConfig {
+            enabled: false,
+            knowledge_graphs: Vec::new(),
+            file: true,
+            dir: None,
+            max_file_bytes: default_cdc_max_file_bytes(),
+            max_files: default_cdc_max_files(),
+            subscriber_buffer: default_cdc_subscriber_buffer(),
+            kafka: None,
+        }
+    }
+}
+
+impl Default for KafkaConfig {
+    fn default() -> Self {
+        KafkaConfig {
+            brokers: String::new(),
+            group_id: default_kafka_group_id(),
+            topics: Vec::new(),
+            batch_size: default_kafka_batch_size(),
+            batch_wait_ms: default_kafka_batch_wait_ms(),
+            properties: std::collections::BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for AdmissionConfig {
+    fn default() -> Self {
+        AdmissionConfig {
+            max_concurrent_queries: 0,
+            max_queued_queries: default_admission_max_queued(),
+            max_queued_per_knowledge_graph: 0,
+            queue_timeout_ms: default_admission_queue_timeout_ms(),
+            finished_history: default_admission_finished_history(),
+            default_priority: crate::protocol::admission::Priority::default(),
+        }
+    }
+}
+
+impl Default for ReplicationConfig {
+    fn default() -> Self {
+        ReplicationConfig {
+            role: ReplicationRole::None,
+            leader_url: None,
+            api_key: None,
+            log_max_bytes: default_replication_log_bytes(),
+            reconnect_ms: default_replication_reconnect_ms(),
         }
     }
 }
@@ -720,6 +2017,8 @@ impl Default for AuthConfig {
             bootstrap_admin_password: None,
             session_timeout_secs: default_session_timeout(),
             credentials_file: None,
+            password_login: true,
+            api_keys: Vec::new(),
         }
     }
 }
@@ -908,6 +2207,7 @@ mod tests {
             bootstrap_admin_password: Some("secret123".to_string()),
             session_timeout_secs: 3600,
             credentials_file: None,
+            ..AuthConfig::default()
         };
         assert_eq!(auth.bootstrap_admin_password.as_deref(), Some("secret123"));
     }
@@ -974,6 +2274,268 @@ mod tests {
         assert_eq!(config.storage.persist.buffer_size, 1000);
     }
 
+    #[test]
+    fn test_validate_configured_api_keys() {
+        let mut config = Config::default();
+        config.http.auth.api_keys.push(ApiKeyConfig {
+            label: "ci".to_string(),
+            key_sha256: format!(" {} ", "AB".repeat(32)),
+            username: "admin".to_string(),
+            priority: None,
+        });
+        config.validate().unwrap();
+        assert_eq!(config.http.auth.api_keys[0].key_sha256, "ab".repeat(32));
+
+        let mut duplicate = config.clone();
+        duplicate
+            .http
+            .auth
+            .api_keys
+            .push(duplicate.http.auth.api_keys[0].clone());
+        assert!(duplicate.validate().is_err());
+
+        config.http.auth.api_keys[0].key_sha256 = "not-a-hash".to_string();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_tls() {
+        let mut config = Config::default();
+        config.http.tls.enabled = true;
+        assert!(config.validate().is_err());
+
+        config.http.tls.cert_path = Some(PathBuf::from("server.pem"));
+        config.http.tls.key_path = Some(PathBuf::from("server.key"));
+        config.http.tls.sni.push(SniCertConfig {
+            server_name: " EU.example.com".to_string(),
+            cert_path: PathBuf::from("eu.pem"),
+            key_path: PathBuf::from("eu.key"),
+        });
+        config.validate().unwrap();
+        assert_eq!(config.http.tls.sni[0].server_name, "eu.example.com");
+        assert!(config.http.tls.require_client_cert);
+
+        let entry = config.http.tls.sni[0].clone();
+        config.http.tls.sni.push(entry);
+        assert!(config.validate().is_err());
+    }
+
+    /// `toml` merged over the default configuration, as a config file
+    /// that sets only some sections is loaded
+    fn parse_over_defaults(toml: &str) -> Result<Config, figment::Error> {
+        Figment::from(figment::providers::Serialized::defaults(Config::default()))
+            .merge(Toml::string(toml))
+            .extract()
+    }
+
+    #[test]
+    fn test_compression_config() {
+        use crate::protocol::compression::Codec;
+
+        let mut config =
+            parse_over_defaults("[http.compression]\ncodecs = [\"lz4\"]\nmin_bytes = 4096\n")
+                .unwrap();
+        assert_eq!(config.http.compression.codecs, vec![Codec::Lz4]);
+        assert_eq!(config.http.compression.min_bytes, 4096);
+        config.validate().unwrap();
+
+        assert!(parse_over_defaults("[http.compression]\ncodecs = [\"brotli\"]\n").is_err());
+        config.http.compression.zstd_level = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_logging_targets() {
+        let mut config = parse_over_defaults(
+            "[logging]\nlevel = \"warn\"\n[logging.targets]\n\"inputlayer::storage\" = \"debug\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.logging.targets["inputlayer::storage"], "debug");
+        config.validate().unwrap();
+
+        config
+            .logging
+            .targets
+            .insert("inputlayer::parser".to_string(), "loud".to_string());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_tracing_config() {
+        let mut config = parse_over_defaults(
+            "[tracing]\nenabled = true\notlp_endpoint = \"http://collector:4317\"\nsample_ratio = 0.25\n",
+        )
+        .unwrap();
+        assert!(config.tracing.enabled);
+        assert_eq!(config.tracing.otlp_endpoint, "http://collector:4317");
+        assert_eq!(config.tracing.service_name, "inputlayer");
+        config.validate().unwrap();
+
+        config.tracing.sample_ratio = 1.5;
+        assert!(config.validate().is_err());
+        assert!(!Config::default().tracing.enabled);
+    }
+
+    #[test]
+    fn test_audit_config() {
+        let config =
+            parse_over_defaults("[audit]\nenabled = true\nmax_files = 3\nrelation = false\n")
+                .unwrap();
+        assert!(config.audit.enabled);
+        assert_eq!(config.audit.max_files, 3);
+        assert_eq!(config.audit.max_file_bytes, 67_108_864);
+        assert!(!config.audit.relation);
+        assert!(config.audit.dir.is_none());
+        assert!(!Config::default().audit.enabled);
+    }
+
+    #[test]
+    fn test_replication_config() {
+        let mut config = parse_over_defaults(
+            "[replication]\nrole = \"follower\"\nleader_url = \"http://leader:8080\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.replication.role, ReplicationRole::Follower);
+        assert_eq!(config.replication.log_max_bytes, 268_435_456);
+        assert!(config.validate().is_err());
+
+        config.replication.api_key = Some("il_key".to_string());
+        config.validate().unwrap();
+        assert_eq!(Config::default().replication.role, ReplicationRole::None);
+    }
+
+    #[test]
+    fn test_cluster_config() {
+        let mut config = parse_over_defaults(
+            "[cluster]\nnode_id = 2\nsecret = \"s3cret\"\n\
+             members = [{ id = 1, url = \"http://a:8080\" }, { id = 2, url = \"http://b:8080\" }]\n",
+        )
+        .unwrap();
+        assert!(config.cluster.is_enabled());
+        assert_eq!(config.cluster.election_timeout_ms, 1500);
+        config.validate().unwrap();
+
+        config.cluster.node_id = 3;
+        assert!(config.validate().is_err());
+        config.cluster.node_id = 2;
+        config.replication.role = ReplicationRole::Leader;
+        assert!(config.validate().is_err());
+        config.replication.role = ReplicationRole::None;
+        config.cluster.heartbeat_ms = config.cluster.election_timeout_ms;
+        assert!(config.validate().is_err());
+        assert!(!Config::default().cluster.is_enabled());
+    }
+
+    #[test]
+    fn test_sharding_config() {
+        let mut config = parse_over_defaults(
+            "[sharding]\n\
+             shards = [{ id = \"a\", url = \"http://a:8080\" }, { id = \"b\", url = \"http://b:8080\" }]\n",
+        )
+        .unwrap();
+        assert!(config.sharding.is_enabled());
+        assert_eq!(config.sharding.timeout_ms, 30_000);
+        assert!(config.validate().is_err());
+
+        config.sharding.api_key = Some("il_key".to_string());
+        config.validate().unwrap();
+        config.sharding.shards[1].id = "a".to_string();
+        assert!(config.validate().is_err());
+        assert!(!Config::default().sharding.is_enabled());
+    }
+
+    #[test]
+    fn test_timely_config() {
+        let mut config = parse_over_defaults(
+            "[timely]\n\
+             processes = [\"node1:2101\", \"node2:2101\"]\n\
+             process = 1\n",
+        )
+        .unwrap();
+        assert!(config.timely.is_enabled());
+        assert!(config.timely.is_worker());
+        assert_eq!(config.timely.workers, 1);
+        assert!(config.validate().is_err());
+
+        config.timely.secret = Some("s".to_string());
+        config.validate().unwrap();
+        config.timely.process = 2;
+        assert!(config.validate().is_err());
+        config.timely.process = 0;
+        assert!(!config.timely.is_worker());
+        config.timely.processes.truncate(1);
+        assert!(config.validate().is_err());
+        assert!(!Config::default().timely.is_enabled());
+    }
+
+    #[test]
+    fn test_kafka_config() {
+        let mut config = parse_over_defaults(
+            "[kafka]\n\
+             brokers = \"kafka:9092\"\n\
+             [[kafka.topics]]\n\
+             topic = \"orders\"\n\
+             relation = \"order\"\n\
+             format = \"avro\"\n\
+             start = \"latest\"\n",
+        )
+        .unwrap();
+        assert!(config.kafka.is_enabled());
+        assert_eq!(config.kafka.batch_size, 1000);
+        assert_eq!(config.kafka.topics[0].start, KafkaStart::Latest);
+        assert!(config.validate().is_err(), "Avro needs a schema");
+
+        config.kafka.topics[0].format = KafkaFormat::Json;
+        config.validate().unwrap();
+        config.kafka.topics.push(config.kafka.topics[0].clone());
+        assert!(config.validate().is_err(), "duplicate topic");
+        assert!(!Config::default().kafka.is_enabled());
+    }
+
+    #[test]
+    fn test_cdc_config() {
+        let mut config = parse_over_defaults(
+            "[cdc]\n\
+             enabled = true\n\
+             knowledge_graphs = [\"orders\"]\n\
+             [cdc.kafka]\n\
+             brokers = \"kafka:9092\"\n\
+             topic = \"\"\n",
+        )
+        .unwrap();
+        assert!(config.cdc.file);
+        assert_eq!(config.cdc.max_files, 10);
+        assert!(config.cdc.publishes("orders"));
+        assert!(!config.cdc.publishes("default"));
+        assert!(config.validate().is_err());
+
+        config.cdc.kafka = None;
+        config.validate().unwrap();
+        config.cdc.knowledge_graphs.clear();
+        assert!(config.cdc.publishes("default"));
+        assert!(!config.cdc.publishes(crate::auth::INTERNAL_KG));
+        assert!(!Config::default().cdc.publishes("default"));
+    }
+
+    #[test]
+    fn test_admission_config() {
+        let config = parse_over_defaults(
+            "[admission]\n\
+             max_concurrent_queries = 3\n\
+             max_queued_per_knowledge_graph = 10\n\
+             default_priority = \"batch\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.admission.concurrency(), 3);
+        assert_eq!(
+            config.admission.default_priority,
+            crate::protocol::admission::Priority::Batch
+        );
+        assert_eq!(config.admission.max_queued_queries, 1024);
+        assert_eq!(config.admission.max_queued_per_knowledge_graph, 10);
+        assert!(Config::default().admission.concurrency() >= 1);
+    }
+
     #[test]
     fn test_validate_accepts_normal_values() {
         let mut config = Config::default();
@@ -996,16 +2558,35 @@ mod tests {
         assert_eq!(persist.max_wal_size_bytes, 67_108_864); // 64 MB
     }
 
+    #[test]
+    fn test_default_wal_segment_and_sync_interval() {
+        let persist = PersistLayerConfig::default();
+        assert_eq!(persist.wal_segment_size_bytes, 16_777_216); // 16 MB
+        assert_eq!(persist.wal_sync_interval_ms, 1000);
+    }
+
+    #[test]
+    fn test_corruption_policy_parse() {
+        let persist: PersistLayerConfig = toml::from_str(r#"on_corruption = "fail""#).unwrap();
+        assert_eq!(persist.on_corruption, CorruptionPolicy::Fail);
+        assert_eq!(
+            PersistLayerConfig::default().on_corruption,
+            CorruptionPolicy::Quarantine
+        );
+    }
+
     #[test]
     fn test_default_slow_query_log_ms() {
         let perf = PerformanceConfig::default();
         assert_eq!(perf.slow_query_log_ms, 5000);
+        assert!(perf.slow_query_relation);
     }
 
     #[test]
     fn test_default_shutdown_timeout_secs() {
         let http = HttpConfig::default();
         assert_eq!(http.shutdown_timeout_secs, 30);
+        assert_eq!(http.drain_timeout_secs, 30);
     }
 
     #[test]
@@ -1039,7 +2620,9 @@ mod tests {
         let mut config = Config::default();
         config.storage.persist.max_wal_size_bytes = 123_456;
         config.storage.performance.slow_query_log_ms = 2000;
+        config.storage.performance.slow_query_relation = false;
         config.http.shutdown_timeout_secs = 60;
+        config.http.drain_timeout_secs = 5;
         config.http.stats_timeout_secs = 10;
         config.http.rate_limit.notification_buffer_size = 8192;
 
@@ -1048,7 +2631,9 @@ mod tests {
 
         assert_eq!(parsed.storage.persist.max_wal_size_bytes, 123_456);
         assert_eq!(parsed.storage.performance.slow_query_log_ms, 2000);
+        assert!(!parsed.storage.performance.slow_query_relation);
         assert_eq!(parsed.http.shutdown_timeout_secs, 60);
+        assert_eq!(parsed.http.drain_timeout_secs, 5);
         assert_eq!(parsed.http.stats_timeout_secs, 10);
         assert_eq!(parsed.http.rate_limit.notification_buffer_size, 8192);
     }
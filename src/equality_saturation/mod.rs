@@ -0,0 +1,1397 @@
+//! # Equality Saturation Optimizer
+//!
+//! Optional alternative to running independent rewrite passes in a fixed
+//! order. The plan is loaded into an e-graph, where each e-class holds every
+//! equivalent form of a subplan found so far. Rewrites only ever *add*
+//! forms, so one decision (e.g. a join reordering) never hides an
+//! opportunity for another (e.g. pushing a filter into the new join input).
+//! When no rewrite adds anything new, or the iteration/size limits are hit,
+//! the cheapest plan is extracted using cardinality estimates.
+//!
+//! Join sizes are estimated as `|L| * |R| / max(ndv(L.k), ndv(R.k))` per key
+//! when the distinct counts of the key columns are known (from `analyze`),
+//! and as the size of the larger input otherwise.
+//!
+//! ## Rewrites
+//!
+//! - Join commutativity and associativity (original column order restored by a `Map`)
+//! - Filter pushdown through joins, antijoins, semijoins, maps, unions and distinct
+//! - Filter split/merge: `Filter(Filter(x, p1), p2)` <-> `Filter(x, p1 AND p2)`
+//! - Map fusion and identity-map elimination
+//! - Union flattening and empty-branch elimination
+//!
+//! ## Example
+//!
+//! ```text
+//! Filter(Join(Join(big, mid), small), big.x = 1)
+//!   -> Map(Join(Filter(big, x = 1), Join(mid, small)), perm)
+//! ```
+
+use crate::ir::{IRNode, Predicate};
+use std::collections::{HashMap, HashSet};
+
+/// Row estimate for relations without a known cardinality
+const DEFAULT_ROWS: f64 = 1000.0;
+
+/// Assumed fraction of rows that pass a filter
+const FILTER_SELECTIVITY: f64 = 0.5;
+
+/// Cost per input row of a projection relative to other operators
+const MAP_COST_FACTOR: f64 = 0.1;
+
+/// E-class identifier
+type Id = usize;
+
+/// Column provenance used when reordering joins: (join input, column)
+type Label = (usize, usize);
+
+/// Equality saturation optimizer
+pub struct EqualitySaturation {
+    /// Maximum number of rewrite rounds
+    max_iterations: usize,
+    /// Stop exploring once the e-graph holds this many e-classes
+    max_classes: usize,
+}
+
+impl EqualitySaturation {
+    /// Create an optimizer with default limits
+    pub fn new() -> Self {
+        Self::with_limits(8, 2_000)
+    }
+
+    /// Create an optimizer with custom exploration limits
+    pub fn with_limits(max_iterations: usize, max_classes: usize) -> Self {
+        EqualitySaturation {
+            max_iterations,
+            max_classes,
+        }
+    }
+
+    /// Explore equivalent plans and return the cheapest one.
+    ///
+    /// `cardinalities` maps base relation names to row counts; unknown
+    /// relations are assumed to hold `DEFAULT_ROWS` rows.
+    pub fn optimize(&self, ir: IRNode, cardinalities: &HashMap<String, usize>) -> IRNode {
+        self.optimize_with_stats(ir, cardinalities, &HashMap::new())
+    }
+
+    /// Like `optimize`, using per-column distinct counts of base relations
+    /// (relation name to one count per column) to estimate join sizes.
+    pub fn optimize_with_stats(
+        &self,
+        ir: IRNode,
+        cardinalities: &HashMap<String, usize>,
+        distinct_counts: &HashMap<String, Vec<usize>>,
+    ) -> IRNode {
+        let mut egraph = EGraph::new(cardinalities, distinct_counts);
+        let root = egraph.add_ir(ir.clone());
+
+        for _ in 0..self.max_iterations {
+            let changed = egraph.apply_rewrites();
+            egraph.rebuild();
+            if !changed || egraph.parents.len() > self.max_classes {
+                break;
+            }
+        }
+
+        egraph.extract(root).unwrap_or(ir)
+    }
+}
+
+impl Default for EqualitySaturation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An operator whose child slots refer to e-classes
+#[derive(Debug, Clone)]
+struct ENode {
+    /// The operator, with placeholder children
+    op: IRNode,
+    /// Hash-consing key of `op`
+    op_key: String,
+    children: Vec<Id>,
+}
+
+/// A set of equivalent e-nodes
+struct EClass {
+    nodes: Vec<ENode>,
+    schema: Vec<String>,
+    rows: f64,
+    /// Estimated distinct values per output column, where known
+    distinct: Vec<Option<f64>>,
+}
+
+struct EGraph<'a> {
+    /// Union-find over e-class ids
+    parents: Vec<Id>,
+    /// Canonical id -> class
+    classes: HashMap<Id, EClass>,
+    /// Hash-cons: (operator key, canonical children) -> class
+    memo: HashMap<(String, Vec<Id>), Id>,
+    cardinalities: &'a HashMap<String, usize>,
+    distinct_counts: &'a HashMap<String, Vec<usize>>,
+}
+
+impl<'a> EGraph<'a> {
+    fn new(
+        cardinalities: &'a HashMap<String, usize>,
+        distinct_counts: &'a HashMap<String, Vec<usize>>,
+    ) -> Self {
+        EGraph {
+            parents: Vec::new(),
+            classes: HashMap::new(),
+            memo: HashMap::new(),
+            cardinalities,
+            distinct_counts,
+        }
+    }
+
+    fn find(&self, mut id: Id) -> Id {
+        while self.parents[id] != id {
+            id = self.parents[id];
+        }
+        id
+    }
+
+    fn class(&self, id: Id) -> &EClass {
+        &self.classes[&self.find(id)]
+    }
+
+    fn schema(&self, id: Id) -> &[String] {
+        &self.class(id).schema
+    }
+
+    fn arity(&self, id: Id) -> usize {
+        self.class(id).schema.len()
+    }
+
+    fn rows(&self, id: Id) -> f64 {
+        self.class(id).rows
+    }
+
+    fn distinct(&self, id: Id, col: usize) -> Option<f64> {
+        self.class(id).distinct.get(col).copied().flatten()
+    }
+
+    fn nodes(&self, id: Id) -> Vec<ENode> {
+        self.class(id).nodes.clone()
+    }
+
+    fn is_empty_class(&self, id: Id) -> bool {
+        self.class(id)
+            .nodes
+            .iter()
+            .any(|n| matches!(n.op, IRNode::Union { .. }) && n.children.is_empty())
+    }
+
+    /// Add an operator over existing classes, returning its class
+    fn add(&mut self, op: IRNode, children: Vec<Id>) -> Id {
+        let children: Vec<Id> = children.into_iter().map(|c| self.find(c)).collect();
+        let op_key = format!("{op:?}");
+        let key = (op_key.clone(), children.clone());
+        if let Some(&id) = self.memo.get(&key) {
+            return self.find(id);
+        }
+
+        let schema = self.node_schema(&op, &children);
+        let rows = self.node_rows(&op, &children);
+        let distinct = self.node_distinct(&op, &children, schema.len(), rows);
+        let id = self.parents.len();
+        self.parents.push(id);
+        self.classes.insert(
+            id,
+            EClass {
+                nodes: vec![ENode {
+                    op,
+                    op_key,
+                    children,
+                }],
+                schema,
+                rows,
+                distinct,
+            },
+        );
+        self.memo.insert(key, id);
+        id
+    }
+
+    fn add_ir(&mut self, ir: IRNode) -> Id {
+        let (op, children) = split(ir);
+        let children = children.into_iter().map(|c| self.add_ir(c)).collect();
+        self.add(op, children)
+    }
+
+    fn empty(&mut self) -> Id {
+        self.add(IRNode::Union { inputs: Vec::new() }, Vec::new())
+    }
+
+    fn filter(&mut self, input: Id, predicate: Predicate) -> Id {
+        let op = IRNode::Filter {
+            input: Box::new(placeholder()),
+            predicate,
+        };
+        self.add(op, vec![input])
+    }
+
+    fn map(&mut self, input: Id, projection: Vec<usize>, output_schema: Vec<String>) -> Id {
+        let op = IRNode::Map {
+            input: Box::new(placeholder()),
+            projection,
+            output_schema,
+        };
+        self.add(op, vec![input])
+    }
+
+    fn join(
+        &mut self,
+        left: Id,
+        right: Id,
+        left_keys: Vec<usize>,
+        right_keys: Vec<usize>,
+        output_schema: Vec<String>,
+    ) -> Id {
+        let op = IRNode::Join {
+            left: Box::new(placeholder()),
+            right: Box::new(placeholder()),
+            left_keys,
+            right_keys,
+            output_schema,
+        };
+        self.add(op, vec![left, right])
+    }
+
+    /// Merge two classes. Returns false if they were already equal.
+    fn union(&mut self, a: Id, b: Id) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        // Keep the older class as root so its schema stays authoritative
+        let (root, other) = if a < b { (a, b) } else { (b, a) };
+        self.parents[other] = root;
+        if let Some(merged) = self.classes.remove(&other) {
+            if let Some(class) = self.classes.get_mut(&root) {
+                class.nodes.extend(merged.nodes);
+                class.rows = class.rows.min(merged.rows);
+                if class.distinct.is_empty() {
+                    class.distinct = merged.distinct;
+                }
+                // The empty relation has no schema of its own
+                if class.schema.is_empty() {
+                    class.schema = merged.schema;
+                }
+            }
+        }
+        true
+    }
+
+    /// Restore the congruence invariant after unions: canonicalize children,
+    /// drop duplicate nodes, and merge classes that now share a node.
+    fn rebuild(&mut self) {
+        loop {
+            let mut memo: HashMap<(String, Vec<Id>), Id> = HashMap::new();
+            let mut pending = Vec::new();
+            let ids: Vec<Id> = self.classes.keys().copied().collect();
+
+            for id in ids {
+                let mut seen = HashSet::new();
+                let mut nodes = Vec::new();
+                for node in &self.classes[&id].nodes {
+                    let children: Vec<Id> = node.children.iter().map(|&c| self.find(c)).collect();
+                    let key = (node.op_key.clone(), children.clone());
+                    if !seen.insert(key.clone()) {
+                        continue;
+                    }
+                    match memo.get(&key) {
+                        Some(&other) if other != id => pending.push((id, other)),
+                        _ => {
+                            memo.insert(key, id);
+                        }
+                    }
+                    nodes.push(ENode {
+                        op: node.op.clone(),
+                        op_key: node.op_key.clone(),
+                        children,
+                    });
+                }
+                if let Some(class) = self.classes.get_mut(&id) {
+                    class.nodes = nodes;
+                }
+            }
+
+            if pending.is_empty() {
+                self.memo = memo;
+                return;
+            }
+            for (a, b) in pending {
+                self.union(a, b);
+            }
+        }
+    }
+
+    /// Run every rewrite once over a snapshot of the e-graph.
+    /// Returns true if anything was added or merged.
+    fn apply_rewrites(&mut self) -> bool {
+        let classes_before = self.parents.len();
+        let snapshot: Vec<(Id, ENode)> = self
+            .classes
+            .iter()
+            .flat_map(|(&id, class)| class.nodes.iter().map(move |n| (id, n.clone())))
+            .collect();
+
+        let mut merged = false;
+        for (id, node) in snapshot {
+            for equivalent in self.rewrite(&node) {
+                merged |= self.union(id, equivalent);
+            }
+        }
+        merged || self.parents.len() > classes_before
+    }
+
+    /// Classes equivalent to `node`, derived by the rewrite rules
+    fn rewrite(&mut self, node: &ENode) -> Vec<Id> {
+        let mut out = Vec::new();
+        match &node.op {
+            IRNode::Filter { predicate, .. } => {
+                self.rewrite_filter(predicate, node.children[0], &mut out);
+            }
+            IRNode::Map {
+                projection,
+                output_schema,
+                ..
+            } => self.rewrite_map(projection, output_schema, node.children[0], &mut out),
+            IRNode::Distinct { .. } if self.is_empty_class(node.children[0]) => {
+                out.push(self.empty());
+            }
+            IRNode::Union { .. } => self.rewrite_union(&node.children, &mut out),
+            IRNode::Join { .. } => {
+                if node.children.iter().any(|&c| self.is_empty_class(c)) {
+                    out.push(self.empty());
+                    return out;
+                }
+                self.commute_join(node, &mut out);
+                self.associate_left(node, &mut out);
+                self.associate_right(node, &mut out);
+            }
+            IRNode::Semijoin { .. } if node.children.iter().any(|&c| self.is_empty_class(c)) => {
+                out.push(self.empty());
+            }
+            IRNode::Antijoin { .. } => {
+                if self.is_empty_class(node.children[0]) {
+                    out.push(self.empty());
+                } else if self.is_empty_class(node.children[1]) {
+                    out.push(node.children[0]);
+                }
+            }
+            _ => {}
+        }
+        out
+    }
+
+    fn rewrite_filter(&mut self, predicate: &Predicate, input: Id, out: &mut Vec<Id>) {
+        if predicate.is_always_true() {
+            out.push(input);
+            return;
+        }
+        if predicate.is_always_false() || self.is_empty_class(input) {
+            out.push(self.empty());
+            return;
+        }
+
+        // Split conjunctions so each conjunct can move on its own
+        if let Predicate::And(p1, p2) = predicate {
+            let inner = self.filter(input, (**p1).clone());
+            out.push(self.filter(inner, (**p2).clone()));
+            let inner = self.filter(input, (**p2).clone());
+            out.push(self.filter(inner, (**p1).clone()));
+        }
+
+        for child in self.nodes(input) {
+            match &child.op {
+                IRNode::Filter {
+                    predicate: inner, ..
+                } => {
+                    let merged =
+                        Predicate::And(Box::new(inner.clone()), Box::new(predicate.clone()));
+                    out.push(self.filter(child.children[0], merged));
+                }
+                IRNode::Join { right_keys, .. } => {
+                    if let Some(id) = self.push_filter_into_join(predicate, &child, right_keys) {
+                        out.push(id);
+                    }
+                }
+                // Output columns are exactly the left input's columns
+                IRNode::Antijoin { .. } | IRNode::Semijoin { .. } => {
+                    let left = self.filter(child.children[0], predicate.clone());
+                    out.push(self.add(child.op.clone(), vec![left, child.children[1]]));
+                }
+                IRNode::Map { projection, .. } => {
+                    if let Some(p) = predicate.remap_columns(&|c| projection.get(c).copied()) {
+                        let filtered = self.filter(child.children[0], p);
+                        out.push(self.add(child.op.clone(), vec![filtered]));
+                    }
+                }
+                IRNode::Union { .. } if !child.children.is_empty() => {
+                    let inputs = child
+                        .children
+                        .iter()
+                        .map(|&c| self.filter(c, predicate.clone()))
+                        .collect();
+                    out.push(self.add(child.op.clone(), inputs));
+                }
+                IRNode::Distinct { .. } => {
+                    let filtered = self.filter(child.children[0], predicate.clone());
+                    out.push(self.add(child.op.clone(), vec![filtered]));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// `Filter(Join(A, B), p)` -> `Join(Filter(A, p), B)` or `Join(A, Filter(B, p'))`
+    fn push_filter_into_join(
+        &mut self,
+        predicate: &Predicate,
+        join: &ENode,
+        right_keys: &[usize],
+    ) -> Option<Id> {
+        let (left, right) = (join.children[0], join.children[1]);
+        let left_arity = self.arity(left);
+        let columns = predicate.referenced_columns();
+
+        if columns.iter().all(|&c| c < left_arity) {
+            let filtered = self.filter(left, predicate.clone());
+            return Some(self.add(join.op.clone(), vec![filtered, right]));
+        }
+        if columns.iter().all(|&c| c >= left_arity) {
+            // Join output carries the right input's non-key columns, in order
+            let right_columns: Vec<usize> = (0..self.arity(right))
+                .filter(|c| !right_keys.contains(c))
+                .collect();
+            let p = predicate.remap_columns(&|c| right_columns.get(c - left_arity).copied())?;
+            let filtered = self.filter(right, p);
+            return Some(self.add(join.op.clone(), vec![left, filtered]));
+        }
+        None
+    }
+
+    fn rewrite_map(
+        &mut self,
+        projection: &[usize],
+        output_schema: &[String],
+        input: Id,
+        out: &mut Vec<Id>,
+    ) {
+        if self.is_empty_class(input) {
+            out.push(self.empty());
+            return;
+        }
+        let is_identity = projection.len() == self.arity(input)
+            && projection.iter().enumerate().all(|(i, &c)| i == c);
+        if is_identity {
+            out.push(input);
+        }
+
+        for child in self.nodes(input) {
+            if let IRNode::Map {
+                projection: inner, ..
+            } = &child.op
+            {
+                let fused: Option<Vec<usize>> =
+                    projection.iter().map(|&c| inner.get(c).copied()).collect();
+                if let Some(fused) = fused {
+                    out.push(self.map(child.children[0], fused, output_schema.to_vec()));
+                }
+            }
+        }
+    }
+
+    fn rewrite_union(&mut self, inputs: &[Id], out: &mut Vec<Id>) {
+        if inputs.is_empty() {
+            return;
+        }
+
+        let mut flat = Vec::new();
+        let mut changed = false;
+        for &input in inputs {
+            if self.is_empty_class(input) {
+                changed = true;
+                continue;
+            }
+            let nested = self
+                .class(input)
+                .nodes
+                .iter()
+                .find(|n| matches!(n.op, IRNode::Union { .. }) && n.children.len() > 1)
+                .map(|n| n.children.clone());
+            match nested {
+                Some(children) => {
+                    flat.extend(children);
+                    changed = true;
+                }
+                None => flat.push(input),
+            }
+        }
+
+        match flat.len() {
+            0 => out.push(self.empty()),
+            1 => out.push(flat[0]),
+            _ if changed => out.push(self.add(IRNode::Union { inputs: Vec::new() }, flat)),
+            _ => {}
+        }
+    }
+
+    /// `Join(A, B)` -> `Map(Join(B, A), perm)`
+    fn commute_join(&mut self, node: &ENode, out: &mut Vec<Id>) {
+        let IRNode::Join {
+            left_keys,
+            right_keys,
+            output_schema,
+            ..
+        } = &node.op
+        else {
+            return;
+        };
+        let (a, b) = (node.children[0], node.children[1]);
+        let (la, lb) = (self.labels(0, a), self.labels(1, b));
+        let mut eq = LabelEq::default();
+        for (&l, &r) in left_keys.iter().zip(right_keys) {
+            eq.union((0, l), (1, r));
+        }
+
+        let original = join_output(&la, &lb, right_keys);
+        let swapped = join_output(&lb, &la, left_keys);
+        let Some(perm) = eq.positions(&original, &swapped) else {
+            return;
+        };
+        let schema = self.label_names(&swapped, &[a, b]);
+        let joined = self.join(b, a, right_keys.clone(), left_keys.clone(), schema);
+        out.push(self.map(joined, perm, output_schema.clone()));
+    }
+
+    /// `Join(Join(A, B), C)` -> `Map(Join(A, Join(B, C)), perm)`
+    ///
+    /// Applies when the outer join keys resolve to columns of `B`.
+    fn associate_left(&mut self, node: &ENode, out: &mut Vec<Id>) {
+        let IRNode::Join {
+            left_keys: outer_lk,
+            right_keys: outer_rk,
+            output_schema,
+            ..
+        } = &node.op
+        else {
+            return;
+        };
+        let (ab_id, c) = (node.children[0], node.children[1]);
+
+        for inner in self.nodes(ab_id) {
+            let IRNode::Join {
+                left_keys: inner_lk,
+                right_keys: inner_rk,
+                ..
+            } = &inner.op
+            else {
+                continue;
+            };
+            let (a, b) = (inner.children[0], inner.children[1]);
+            let (la, lb, lc) = (self.labels(0, a), self.labels(1, b), self.labels(2, c));
+
+            let mut eq = LabelEq::default();
+            for (&l, &r) in inner_lk.iter().zip(inner_rk) {
+                eq.union((0, l), (1, r));
+            }
+            let ab = join_output(&la, &lb, inner_rk);
+            if outer_lk.iter().any(|&k| k >= ab.len()) {
+                continue;
+            }
+            for (&l, &r) in outer_lk.iter().zip(outer_rk) {
+                eq.union(ab[l], (2, r));
+            }
+            let original = join_output(&ab, &lc, outer_rk);
+
+            // B ⋈ C on the outer keys, which must all be available in B
+            let bc_lk: Option<Vec<usize>> =
+                outer_lk.iter().map(|&k| eq.position(&lb, ab[k])).collect();
+            let Some(bc_lk) = bc_lk else {
+                continue;
+            };
+            let bc = join_output(&lb, &lc, outer_rk);
+
+            // A ⋈ (B ⋈ C) on the inner keys; B's columns lead the B ⋈ C output
+            let new_output = join_output(&la, &bc, inner_rk);
+            let Some(perm) = eq.positions(&original, &new_output) else {
+                continue;
+            };
+
+            let inputs = [a, b, c];
+            let bc_schema = self.label_names(&bc, &inputs);
+            let bc_id = self.join(b, c, bc_lk, outer_rk.clone(), bc_schema);
+            let schema = self.label_names(&new_output, &inputs);
+            let joined = self.join(a, bc_id, inner_lk.clone(), inner_rk.clone(), schema);
+            out.push(self.map(joined, perm, output_schema.clone()));
+        }
+    }
+
+    /// `Join(A, Join(B, C))` -> `Map(Join(Join(A, B), C), perm)`
+    ///
+    /// Applies when the outer join keys resolve to columns of `B`.
+    fn associate_right(&mut self, node: &ENode, out: &mut Vec<Id>) {
+        let IRNode::Join {
+            left_keys: outer_lk,
+            right_keys: outer_rk,
+            output_schema,
+            ..
+        } = &node.op
+        else {
+            return;
+        };
+        let (a, bc_id) = (node.children[0], node.children[1]);
+
+        for inner in self.nodes(bc_id) {
+            let IRNode::Join {
+                left_keys: inner_lk,
+                right_keys: inner_rk,
+                ..
+            } = &inner.op
+            else {
+                continue;
+            };
+            let (b, c) = (inner.children[0], inner.children[1]);
+            let (la, lb, lc) = (self.labels(0, a), self.labels(1, b), self.labels(2, c));
+
+            let mut eq = LabelEq::default();
+            for (&l, &r) in inner_lk.iter().zip(inner_rk) {
+                eq.union((1, l), (2, r));
+            }
+            let bc = join_output(&lb, &lc, inner_rk);
+            if outer_rk.iter().any(|&k| k >= bc.len()) {
+                continue;
+            }
+            for (&l, &r) in outer_lk.iter().zip(outer_rk) {
+                eq.union((0, l), bc[r]);
+            }
+            let original = join_output(&la, &bc, outer_rk);
+
+            // A ⋈ B on the outer keys, which must all be available in B
+            let ab_rk: Option<Vec<usize>> =
+                outer_rk.iter().map(|&k| eq.position(&lb, bc[k])).collect();
+            let Some(ab_rk) = ab_rk else {
+                continue;
+            };
+            let ab = join_output(&la, &lb, &ab_rk);
+
+            // (A ⋈ B) ⋈ C on the inner keys; a B key dropped by the first
+            // join is replaced by the equal A column
+            let ab_lk: Option<Vec<usize>> =
+                inner_lk.iter().map(|&k| eq.position(&ab, (1, k))).collect();
+            let Some(ab_lk) = ab_lk else {
+                continue;
+            };
+            let new_output = join_output(&ab, &lc, inner_rk);
+            let Some(perm) = eq.positions(&original, &new_output) else {
+                continue;
+            };
+
+            let inputs = [a, b, c];
+            let ab_schema = self.label_names(&ab, &inputs);
+            let ab_id = self.join(a, b, outer_lk.clone(), ab_rk, ab_schema);
+            let schema = self.label_names(&new_output, &inputs);
+            let joined = self.join(ab_id, c, ab_lk, inner_rk.clone(), schema);
+            out.push(self.map(joined, perm, output_schema.clone()));
+        }
+    }
+
+    fn labels(&self, input: usize, id: Id) -> Vec<Label> {
+        (0..self.arity(id)).map(|c| (input, c)).collect()
+    }
+
+    fn label_names(&self, labels: &[Label], inputs: &[Id]) -> Vec<String> {
+        labels
+            .iter()
+            .map(|&(input, col)| {
+                self.schema(inputs[input])
+                    .get(col)
+                    .cloned()
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    fn node_schema(&self, op: &IRNode, children: &[Id]) -> Vec<String> {
+        let stubs = children
+            .iter()
+            .map(|&c| IRNode::Scan {
+                relation: String::new(),
+                schema: self.schema(c).to_vec(),
+            })
+            .collect();
+        assemble(op.clone(), stubs).output_schema()
+    }
+
+    fn node_rows(&self, op: &IRNode, children: &[Id]) -> f64 {
+        let child = |i: usize| children.get(i).map_or(0.0, |&c| self.rows(c));
+        match op {
+            IRNode::Scan { relation, .. } => self
+                .cardinalities
+                .get(relation)
+                .map_or(DEFAULT_ROWS, |&n| n as f64),
+            IRNode::Filter { .. }
+            | IRNode::FlatMap {
+                filter_predicate: Some(_),
+                ..
+            } => child(0) * FILTER_SELECTIVITY,
+            IRNode::Union { .. } => children.iter().map(|&c| self.rows(c)).sum(),
+            IRNode::Join {
+                left_keys,
+                right_keys,
+                ..
+            }
+            | IRNode::JoinFlatMap {
+                left_keys,
+                right_keys,
+                ..
+            } => {
+                if left_keys.is_empty() {
+                    return child(0) * child(1);
+                }
+                let (Some(&left), Some(&right)) = (children.first(), children.get(1)) else {
+                    return child(0).max(child(1));
+                };
+                let divisor: Option<f64> = left_keys
+                    .iter()
+                    .zip(right_keys)
+                    .map(|(&lk, &rk)| {
+                        let ndv = self.distinct(left, lk)?.max(self.distinct(right, rk)?);
+                        Some(ndv.max(1.0))
+                    })
+                    .product();
+                match divisor {
+                    Some(divisor) => child(0) * child(1) / divisor,
+                    None => child(0).max(child(1)),
+                }
+            }
+            IRNode::Aggregate { group_by, .. } if group_by.is_empty() => 1.0,
+            IRNode::HnswScan { k, .. } => *k as f64,
+            _ => child(0),
+        }
+    }
+
+    /// Distinct value estimates of an operator's output columns, capped at
+    /// its row estimate. Only known for columns passed through unchanged
+    /// from an analyzed base relation.
+    fn node_distinct(
+        &self,
+        op: &IRNode,
+        children: &[Id],
+        arity: usize,
+        rows: f64,
+    ) -> Vec<Option<f64>> {
+        let column = |child: usize, col: usize| {
+            children
+                .get(child)
+                .and_then(|&c| self.distinct(c, col))
+                .map(|ndv| ndv.min(rows))
+        };
+        match op {
+            IRNode::Scan { relation, .. } => match self.distinct_counts.get(relation) {
+                Some(counts) => (0..arity)
+                    .map(|i| counts.get(i).map(|&n| (n as f64).min(rows)))
+                    .collect(),
+                None => vec![None; arity],
+            },
+            IRNode::Filter { .. }
+            | IRNode::Distinct { .. }
+            | IRNode::Sort { .. }
+            | IRNode::Antijoin { .. }
+            | IRNode::Semijoin { .. } => (0..arity).map(|i| column(0, i)).collect(),
+            IRNode::Map { projection, .. } => projection.iter().map(|&i| column(0, i)).collect(),
+            IRNode::Join { right_keys, .. } => {
+                let left_arity = children.first().map_or(0, |&c| self.arity(c));
+                let right_arity = children.get(1).map_or(0, |&c| self.arity(c));
+                (0..left_arity)
+                    .map(|i| column(0, i))
+                    .chain(
+                        (0..right_arity)
+                            .filter(|i| !right_keys.contains(i))
+                            .map(|i| column(1, i)),
+                    )
+                    .collect()
+            }
+            _ => vec![None; arity],
+        }
+    }
+
+    /// Estimated work done by one operator (excluding its inputs' own cost)
+    fn node_cost(&self, node: &ENode, rows: f64) -> f64 {
+        let input_rows: f64 = node.children.iter().map(|&c| self.rows(c)).sum();
+        let work = match node.op {
+            IRNode::Scan { .. } | IRNode::HnswScan { .. } => rows,
+            IRNode::Map { .. } => input_rows * MAP_COST_FACTOR,
+            IRNode::Join { .. } | IRNode::JoinFlatMap { .. } => input_rows + rows,
+            _ => input_rows,
+        };
+        // Every operator has a fixed overhead, so cyclic choices never win
+        1.0 + work
+    }
+
+    /// Pick the cheapest node of every class and build the plan for `root`
+    fn extract(&self, root: Id) -> Option<IRNode> {
+        let mut best: HashMap<Id, (f64, ENode)> = HashMap::new();
+        loop {
+            let mut changed = false;
+            for (&id, class) in &self.classes {
+                for node in &class.nodes {
+                    let child_cost: Option<f64> = node
+                        .children
+                        .iter()
+                        .map(|&c| best.get(&self.find(c)).map(|(cost, _)| *cost))
+                        .sum();
+                    let Some(child_cost) = child_cost else {
+                        continue;
+                    };
+                    let cost = self.node_cost(node, class.rows) + child_cost;
+                    match best.get(&id) {
+                        Some((current, _)) if *current <= cost => {}
+                        _ => {
+                            best.insert(id, (cost, node.clone()));
+                            changed = true;
+                        }
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        self.build(root, &best, &mut HashSet::new())
+    }
+
+    fn build(
+        &self,
+        id: Id,
+        best: &HashMap<Id, (f64, ENode)>,
+        visiting: &mut HashSet<Id>,
+    ) -> Option<IRNode> {
+        let id = self.find(id);
+        if !visiting.insert(id) {
+            return None;
+        }
+        let (_, node) = best.get(&id)?;
+        let children = node
+            .children
+            .iter()
+            .map(|&c| self.build(c, best, visiting))
+            .collect::<Option<Vec<_>>>()?;
+        visiting.remove(&id);
+        Some(assemble(node.op.clone(), children))
+    }
+}
+
+/// Equivalence of columns that a join forces to hold equal values
+#[derive(Default)]
+struct LabelEq {
+    parent: HashMap<Label, Label>,
+}
+
+impl LabelEq {
+    fn find(&self, mut label: Label) -> Label {
+        while let Some(&next) = self.parent.get(&label) {
+            label = next;
+        }
+        label
+    }
+
+    fn union(&mut self, a: Label, b: Label) {
+        let (ra, rb) = (self.find(a), self.find(b));
+        if ra != rb {
+            self.parent.insert(rb, ra);
+        }
+    }
+
+    /// Position of `target` in `labels`, preferring the exact column over
+    /// an equal one
+    fn position(&self, labels: &[Label], target: Label) -> Option<usize> {
+        labels.iter().position(|&l| l == target).or_else(|| {
+            let root = self.find(target);
+            labels.iter().position(|&l| self.find(l) == root)
+        })
+    }
+
+    /// Projection that reads every `targets` column out of `labels`
+    fn positions(&self, targets: &[Label], labels: &[Label]) -> Option<Vec<usize>> {
+        targets.iter().map(|&t| self.position(labels, t)).collect()
+    }
+}
+
+/// Columns produced by a join: all of left, then right's non-key columns
+fn join_output(left: &[Label], right: &[Label], right_keys: &[usize]) -> Vec<Label> {
+    left.iter()
+        .copied()
+        .chain(
+            right
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !right_keys.contains(i))
+                .map(|(_, &l)| l),
+        )
+        .collect()
+}
+
+/// Stand-in for a child slot inside an e-node's operator
+fn placeholder() -> IRNode {
+    IRNode::Union { inputs: Vec::new() }
+}
+
+/// Split a node into its operator (children replaced by placeholders) and
+/// its children
+fn split(ir: IRNode) -> (IRNode, Vec<IRNode>) {
+    let hole = || Box::new(placeholder());
+    match ir {
+        IRNode::Map {
+            input,
+            projection,
+            output_schema,
+        } => (
+            IRNode::Map {
+                input: hole(),
+                projection,
+                output_schema,
+            },
+            vec![*input],
+        ),
+        IRNode::Filter { input, predicate } => (
+            IRNode::Filter {
+                input: hole(),
+                predicate,
+            },
+            vec![*input],
+        ),
+        IRNode::Join {
+            left,
+            right,
+            left_keys,
+            right_keys,
+            output_schema,
+        } => (
+            IRNode::Join {
+                left: hole(),
+                right: hole(),
+                left_keys,
+                right_keys,
+                output_schema,
+            },
+            vec![*left, *right],
+        ),
+        IRNode::Distinct { input } => (IRNode::Distinct { input: hole() }, vec![*input]),
+        IRNode::Sort { input, order_by } => (
+            IRNode::Sort {
+                input: hole(),
+                order_by,
+            },
+            vec![*input],
+        ),
+        IRNode::Union { inputs } => (IRNode::Union { inputs: Vec::new() }, inputs),
+        IRNode::Aggregate {
+            input,
+            group_by,
+            aggregations,
+            output_schema,
+        } => (
+            IRNode::Aggregate {
+                input: hole(),
+                group_by,
+                aggregations,
+                output_schema,
+            },
+            vec![*input],
+        ),
+        IRNode::Antijoin {
+            left,
+            right,
+            left_keys,
+            right_keys,
+            output_schema,
+        } => (
+            IRNode::Antijoin {
+                left: hole(),
+                right: hole(),
+                left_keys,
+                right_keys,
+                output_schema,
+            },
+            vec![*left, *right],
+        ),
+        IRNode::Semijoin {
+            left,
+            right,
+            left_keys,
+            right_keys,
+            output_schema,
+        } => (
+            IRNode::Semijoin {
+                left: hole(),
+                right: hole(),
+                left_keys,
+                right_keys,
+                output_schema,
+            },
+            vec![*left, *right],
+        ),
+        IRNode::Compute { input, expressions } => (
+            IRNode::Compute {
+                input: hole(),
+                expressions,
+            },
+            vec![*input],
+        ),
+        IRNode::FlatMap {
+            input,
+            projection,
+            filter_predicate,
+            output_schema,
+        } => (
+            IRNode::FlatMap {
+                input: hole(),
+                projection,
+                filter_predicate,
+                output_schema,
+            },
+            vec![*input],
+        ),
+        IRNode::JoinFlatMap {
+            left,
+            right,
+            left_keys,
+            right_keys,
+            projection,
+            filter_predicate,
+            output_schema,
+        } => (
+            IRNode::JoinFlatMap {
+                left: hole(),
+                right: hole(),
+                left_keys,
+                right_keys,
+                projection,
+                filter_predicate,
+                output_schema,
+            },
+            vec![*left, *right],
+        ),
+        leaf @ (IRNode::Scan { .. } | IRNode::HnswScan { .. }) => (leaf, Vec::new()),
+    }
+}
+
+/// Inverse of `split`: put `children` back into the operator's child slots
+fn assemble(op: IRNode, children: Vec<IRNode>) -> IRNode {
+    if let IRNode::Union { .. } = op {
+        return IRNode::Union { inputs: children };
+    }
+    let mut children = children.into_iter();
+    let mut next = || Box::new(children.next().unwrap_or_else(placeholder));
+    match op {
+        IRNode::Map {
+            projection,
+            output_schema,
+            ..
+        } => IRNode::Map {
+            input: next(),
+            projection,
+            output_schema,
+        },
+        IRNode::Filter { predicate, .. } => IRNode::Filter {
+            input: next(),
+            predicate,
+        },
+        IRNode::Join {
+            left_keys,
+            right_keys,
+            output_schema,
+            ..
+        } => IRNode::Join {
+            left: next(),
+            right: next(),
+            left_keys,
+            right_keys,
+            output_schema,
+        },
+        IRNode::Distinct { .. } => IRNode::Distinct { input: next() },
+        IRNode::Sort { order_by, .. } => IRNode::Sort {
+            input: next(),
+            order_by,
+        },
+        IRNode::Aggregate {
+            group_by,
+            aggregations,
+            output_schema,
+            ..
+        } => IRNode::Aggregate {
+            input: next(),
+            group_by,
+            aggregations,
+            output_schema,
+        },
+        IRNode::Antijoin {
+            left_keys,
+            right_keys,
+            output_schema,
+            ..
+        } => IRNode::Antijoin {
+            left: next(),
+            right: next(),
+            left_keys,
+            right_keys,
+            output_schema,
+        },
+        IRNode::Semijoin {
+            left_keys,
+            right_keys,
+            output_schema,
+            ..
+        } => IRNode::Semijoin {
+            left: next(),
+            right: next(),
+            left_keys,
+            right_keys,
+            output_schema,
+        },
+        IRNode::Compute { expressions, .. } => IRNode::Compute {
+            input: next(),
+            expressions,
+        },
+        IRNode::FlatMap {
+            projection,
+            filter_predicate,
+            output_schema,
+            ..
+        } => IRNode::FlatMap {
+            input: next(),
+            projection,
+            filter_predicate,
+            output_schema,
+        },
+        IRNode::JoinFlatMap {
+            left_keys,
+            right_keys,
+            projection,
+            filter_predicate,
+            output_schema,
+            ..
+        } => IRNode::JoinFlatMap {
+            left: next(),
+            right: next(),
+            left_keys,
+            right_keys,
+            projection,
+            filter_predicate,
+            output_schema,
+        },
+        leaf => leaf,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn scan(relation: &str, schema: &[&str]) -> IRNode {
+        IRNode::Scan {
+            relation: relation.to_string(),
+            schema: schema.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    fn join(left: IRNode, right: IRNode, lk: Vec<usize>, rk: Vec<usize>) -> IRNode {
+        let left_schema = left.output_schema();
+        let right_schema = right.output_schema();
+        let output_schema = left_schema
+            .into_iter()
+            .chain(
+                right_schema
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(i, _)| !rk.contains(i))
+                    .map(|(_, name)| name),
+            )
+            .collect();
+        IRNode::Join {
+            left: Box::new(left),
+            right: Box::new(right),
+            left_keys: lk,
+            right_keys: rk,
+            output_schema,
+        }
+    }
+
+    fn cards(entries: &[(&str, usize)]) -> HashMap<String, usize> {
+        entries
+            .iter()
+            .map(|(n, c)| ((*n).to_string(), *c))
+            .collect()
+    }
+
+    /// Collect (relation, filtered?) for scans in plan order
+    fn filtered_scans(ir: &IRNode, under_filter: bool, out: &mut Vec<(String, bool)>) {
+        match ir {
+            IRNode::Scan { relation, .. } => out.push((relation.clone(), under_filter)),
+            IRNode::Filter { input, .. } => filtered_scans(input, true, out),
+            IRNode::Map { input, .. } | IRNode::Distinct { input } => {
+                filtered_scans(input, under_filter, out);
+            }
+            IRNode::Join { left, right, .. } => {
+                filtered_scans(left, under_filter, out);
+                filtered_scans(right, under_filter, out);
+            }
+            IRNode::Union { inputs } => {
+                for input in inputs {
+                    filtered_scans(input, under_filter, out);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_plan_without_rewrites_is_unchanged() {
+        let ir = scan("edge", &["x", "y"]);
+        let optimized = EqualitySaturation::new().optimize(ir.clone(), &HashMap::new());
+        assert_eq!(optimized, ir);
+    }
+
+    #[test]
+    fn test_filter_pushed_into_join_input() {
+        let ir = IRNode::Filter {
+            input: Box::new(join(
+                scan("a", &["x", "y"]),
+                scan("b", &["y", "z"]),
+                vec![1],
+                vec![0],
+            )),
+            predicate: Predicate::ColumnEqConst(2, 5),
+        };
+        let optimized = EqualitySaturation::new().optimize(ir, &HashMap::new());
+
+        let mut scans = Vec::new();
+        filtered_scans(&optimized, false, &mut scans);
+        assert!(scans.contains(&("b".to_string(), true)));
+        assert!(scans.contains(&("a".to_string(), false)));
+        assert_eq!(optimized.output_schema(), vec!["x", "y", "z"]);
+    }
+
+    #[test]
+    fn test_join_reordered_to_avoid_large_intermediate() {
+        // (big ⋈ mid) ⋈ small where small joins mid: joining mid with small
+        // first keeps the intermediate result small
+        let ir = join(
+            join(
+                scan("big", &["a", "b"]),
+                scan("mid", &["b", "c"]),
+                vec![1],
+                vec![0],
+            ),
+            scan("small", &["c", "d"]),
+            vec![2],
+            vec![0],
+        );
+        let cardinalities = cards(&[("big", 1_000_000), ("mid", 100), ("small", 10)]);
+        let optimized = EqualitySaturation::new().optimize(ir.clone(), &cardinalities);
+
+        assert_ne!(optimized, ir);
+        // Column order is preserved
+        assert_eq!(optimized.output_schema(), vec!["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn test_false_union_branch_eliminated() {
+        let ir = IRNode::Union {
+            inputs: vec![
+                IRNode::Filter {
+                    input: Box::new(scan("a", &["x"])),
+                    predicate: Predicate::False,
+                },
+                scan("b", &["x"]),
+            ],
+        };
+        let optimized = EqualitySaturation::new().optimize(ir, &HashMap::new());
+        assert_eq!(optimized, scan("b", &["x"]));
+    }
+
+    #[test]
+    fn test_identity_maps_removed() {
+        let ir = IRNode::Map {
+            input: Box::new(IRNode::Map {
+                input: Box::new(scan("a", &["x", "y"])),
+                projection: vec![1, 0],
+                output_schema: vec!["y".to_string(), "x".to_string()],
+            }),
+            projection: vec![1, 0],
+            output_schema: vec!["x".to_string(), "y".to_string()],
+        };
+        let optimized = EqualitySaturation::new().optimize(ir, &HashMap::new());
+        assert_eq!(optimized, scan("a", &["x", "y"]));
+    }
+
+    #[test]
+    fn test_commute_join_permutation() {
+        let a = vec![(0, 0), (0, 1)];
+        let b = vec![(1, 0), (1, 1)];
+        let mut eq = LabelEq::default();
+        eq.union((0, 1), (1, 0));
+
+        let original = join_output(&a, &b, &[0]);
+        let swapped = join_output(&b, &a, &[1]);
+        assert_eq!(original, vec![(0, 0), (0, 1), (1, 1)]);
+        assert_eq!(swapped, vec![(1, 0), (1, 1), (0, 0)]);
+        // a.1 was dropped from the swapped output; b.0 holds the same value
+        assert_eq!(eq.positions(&original, &swapped), Some(vec![2, 0, 1]));
+    }
+
+    #[test]
+    fn test_split_assemble_round_trip() {
+        let ir = join(
+            IRNode::Filter {
+                input: Box::new(scan("a", &["x", "y"])),
+                predicate: Predicate::ColumnGtConst(0, 1),
+            },
+            scan("b", &["y"]),
+            vec![1],
+            vec![0],
+        );
+        let (op, children) = split(ir.clone());
+        assert_eq!(children.len(), 2);
+        assert_eq!(assemble(op, children), ir);
+    }
+
+    #[test]
+    fn test_join_rows_use_distinct_counts() {
+        let ir = join(
+            scan("a", &["x", "k"]),
+            scan("b", &["k", "z"]),
+            vec![1],
+            vec![0],
+        );
+        let cardinalities = cards(&[("a", 1_000), ("b", 1_000)]);
+        let join_rows = |distinct: &HashMap<String, Vec<usize>>| {
+            let mut egraph = EGraph::new(&cardinalities, distinct);
+            let root = egraph.add_ir(ir.clone());
+            egraph.rows(root)
+        };
+
+        // Without statistics the larger input bounds the estimate
+        assert!((join_rows(&HashMap::new()) - 1_000.0).abs() < 1e-9);
+
+        // Few distinct keys: every key matches many tuples on each side
+        let few = HashMap::from([
+            ("a".to_string(), vec![1_000, 10]),
+            ("b".to_string(), vec![10, 1_000]),
+        ]);
+        assert!((join_rows(&few) - 100_000.0).abs() < 1e-9);
+
+        // Distinct counts of unanalyzed relations are unknown
+        let partial = HashMap::from([("a".to_string(), vec![1_000, 10])]);
+        assert!((join_rows(&partial) - 1_000.0).abs() < 1e-9);
+    }
+}
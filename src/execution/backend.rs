@@ -0,0 +1,336 @@
+//! Pluggable Execution Backends
+//!
+//! The engine hands optimized IR to an `ExecutionBackend` instead of
+//! constructing a `CodeGenerator` directly, so alternative executors (a
+//! single-threaded interpreter, a distributed runner) can be swapped in via
+//! `OptimizationConfig::execution_backend` without touching the engine's
+//! execution loop.
+//!
+//! `DifferentialBackend`, which runs plans on Differential Dataflow through
+//! the `CodeGenerator`, is used when no backend is configured.
+
+use super::memory::MemoryTracker;
+use super::timeout::{CancelHandle, QueryTimeout};
+use crate::boolean_specialization::{SemiringAnnotation, SemiringType};
+use crate::code_generator::{CodeGenerator, ExecutionConfig, JoinPrefilter};
+use crate::ir::IRNode;
+use crate::value::Tuple;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Upper bound on rounds of the naive mutual-recursion fallback
+const MAX_NAIVE_ITERATIONS: usize = 1000;
+
+/// Relations visible to a plan, keyed by relation name
+pub type BackendInputs = Arc<HashMap<String, Vec<Tuple>>>;
+
+/// Per-execution settings passed to a backend
+#[derive(Debug, Clone)]
+pub struct BackendOptions {
+    /// Semiring selected by boolean specialization
+    pub semiring: SemiringType,
+    /// Maximum number of result rows (0 = unlimited)
+    pub max_result_rows: usize,
+    /// Number of worker threads (1 = single-threaded)
+    pub num_workers: usize,
+    /// Semiring annotations, for debug tracing
+    pub semiring_annotations: Vec<SemiringAnnotation>,
+    /// Memory budget of the query the plan belongs to (`None` = unlimited)
+    pub memory_tracker: Option<MemoryTracker>,
+    /// Cancellation token of the query the plan belongs to
+    pub cancel: Option<CancelHandle>,
+    /// Wall-clock budget of the query the plan belongs to (`None` = unlimited)
+    pub timeout: Option<QueryTimeout>,
+    /// Pre-filtering of joins by the keys of their other side (`None` = disabled)
+    pub join_prefilter: Option<JoinPrefilter>,
+}
+
+impl BackendOptions {
+    /// Whether the query this plan belongs to has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(CancelHandle::is_cancelled)
+    }
+
+    /// `Err` once the query has been cancelled or run out of time
+    pub(super) fn check_interrupted(&self) -> Result<(), String> {
+        if let Some(timeout) = &self.timeout {
+            timeout.check().map_err(|e| e.to_string())?;
+        }
+        if self.is_cancelled() {
+            return Err("Query cancelled".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Default for BackendOptions {
+    fn default() -> Self {
+        BackendOptions {
+            semiring: SemiringType::Counting,
+            max_result_rows: 0,
+            num_workers: 1,
+            semiring_annotations: Vec::new(),
+            memory_tracker: None,
+            cancel: None,
+            timeout: None,
+            join_prefilter: None,
+        }
+    }
+}
+
+/// Executes optimized IR plans against in-memory relations.
+pub trait ExecutionBackend: Send + Sync + std::fmt::Debug {
+    /// Short backend name, for logs and traces
+    fn name(&self) -> &'static str;
+
+    /// Execute a non-recursive plan
+    fn execute(
+        &self,
+        ir: &IRNode,
+        inputs: BackendInputs,
+        options: &BackendOptions,
+    ) -> Result<Vec<Tuple>, String>;
+
+    /// Execute a plan that reads `recursive_relation` (its own head) to fixpoint
+    fn execute_recursive(
+        &self,
+        ir: &IRNode,
+        recursive_relation: &str,
+        inputs: BackendInputs,
+        options: &BackendOptions,
+    ) -> Result<Vec<Tuple>, String>;
+
+    /// Execute the plans of mutually recursive relations to a joint fixpoint.
+    ///
+    /// `relations` pairs each relation of the recursive group with the plan
+    /// that derives it; every plan may read every relation of the group.
+    /// Returns the contents of each relation.
+    ///
+    /// The default re-runs all plans with `execute` until no relation
+    /// changes (naive evaluation). Backends with native iteration should
+    /// override it.
+    fn execute_mutual_recursive(
+        &self,
+        relations: &[(String, IRNode)],
+        inputs: BackendInputs,
+        options: &BackendOptions,
+    ) -> Result<HashMap<String, Vec<Tuple>>, String> {
+        let mut current = (*inputs).clone();
+        for (name, _) in relations {
+            current.entry(name.clone()).or_default();
+        }
+
+        for _ in 0..MAX_NAIVE_ITERATIONS {
+            options.check_interrupted()?;
+            let mut changed = false;
+            for (name, ir) in relations {
+                let tuples = self.execute(ir, Arc::new(current.clone()), options)?;
+                let previous: HashSet<&Tuple> = current[name].iter().collect();
+                let next: HashSet<&Tuple> = tuples.iter().collect();
+                if previous != next {
+                    changed = true;
+                    current.insert(name.clone(), tuples);
+                }
+            }
+            if !changed {
+                return Ok(relations
+                    .iter()
+                    .map(|(name, _)| (name.clone(), current.remove(name).unwrap_or_default()))
+                    .collect());
+            }
+        }
+
+        let names: Vec<&str> = relations.iter().map(|(name, _)| name.as_str()).collect();
+        Err(format!(
+            "Mutually recursive relations ({}) did not reach a fixpoint after {MAX_NAIVE_ITERATIONS} iterations",
+            names.join(", ")
+        ))
+    }
+
+    /// Execute the non-recursive plans of one stratum.
+    ///
+    /// `plans` pairs each relation with the plan that derives it, in
+    /// dependency order; a plan may read the relations of earlier plans.
+    /// Returns the contents of each relation.
+    ///
+    /// The default runs the plans one at a time with `execute`, feeding
+    /// each result to the plans after it. Backends that can evaluate several
+    /// plans in one computation should override it.
+    fn execute_stratum(
+        &self,
+        plans: &[(String, IRNode)],
+        inputs: BackendInputs,
+        options: &BackendOptions,
+    ) -> Result<HashMap<String, Vec<Tuple>>, String> {
+        execute_plans_in_order(self, plans, inputs, options)
+    }
+}
+
+/// Run `plans` one at a time, making each result visible to later plans
+fn execute_plans_in_order<B: ExecutionBackend + ?Sized>(
+    backend: &B,
+    plans: &[(String, IRNode)],
+    mut inputs: BackendInputs,
+    options: &BackendOptions,
+) -> Result<HashMap<String, Vec<Tuple>>, String> {
+    let mut results = HashMap::with_capacity(plans.len());
+    for (name, ir) in plans {
+        options.check_interrupted()?;
+        let tuples = backend.execute(ir, Arc::clone(&inputs), options)?;
+        Arc::make_mut(&mut inputs).insert(name.clone(), tuples.clone());
+        results.insert(name.clone(), tuples);
+    }
+    Ok(results)
+}
+
+/// Default backend: Differential Dataflow via `CodeGenerator`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DifferentialBackend;
+
+impl DifferentialBackend {
+    pub(super) fn codegen(inputs: BackendInputs, options: &BackendOptions) -> CodeGenerator {
+        let mut codegen = CodeGenerator::new();
+        codegen.set_max_result_rows(options.max_result_rows);
+        codegen.set_semiring_type(options.semiring);
+        if !options.semiring_annotations.is_empty() {
+            codegen.set_semiring_annotations(options.semiring_annotations.clone());
+        }
+        codegen.set_memory_tracker(options.memory_tracker.clone());
+        codegen.set_cancel_handle(options.cancel.clone());
+        codegen.set_query_timeout(options.timeout.clone());
+        codegen.set_join_prefilter(options.join_prefilter.clone());
+        codegen.set_shared_input(inputs);
+        codegen
+    }
+}
+
+impl ExecutionBackend for DifferentialBackend {
+    fn name(&self) -> &'static str {
+        "differential"
+    }
+
+    fn execute(
+        &self,
+        ir: &IRNode,
+        inputs: BackendInputs,
+        options: &BackendOptions,
+    ) -> Result<Vec<Tuple>, String> {
+        let codegen = Self::codegen(inputs, options);
+        if options.num_workers > 1 {
+            codegen.execute_with_config(ir, ExecutionConfig::with_workers(options.num_workers))
+        } else {
+            codegen.execute(ir)
+        }
+    }
+
+    fn execute_recursive(
+        &self,
+        ir: &IRNode,
+        recursive_relation: &str,
+        inputs: BackendInputs,
+        options: &BackendOptions,
+    ) -> Result<Vec<Tuple>, String> {
+        Self::codegen(inputs, options).execute_recursive(ir, recursive_relation)
+    }
+
+    fn execute_mutual_recursive(
+        &self,
+        relations: &[(String, IRNode)],
+        inputs: BackendInputs,
+        options: &BackendOptions,
+    ) -> Result<HashMap<String, Vec<Tuple>>, String> {
+        Self::codegen(inputs, options).execute_mutual_recursive(relations)
+    }
+
+    fn execute_stratum(
+        &self,
+        plans: &[(String, IRNode)],
+        inputs: BackendInputs,
+        options: &BackendOptions,
+    ) -> Result<HashMap<String, Vec<Tuple>>, String> {
+        // One shared dataflow is single-worker; multi-worker execution
+        // runs the plans one at a time
+        if options.num_workers > 1 {
+            return execute_plans_in_order(self, plans, inputs, options);
+        }
+        Self::codegen(inputs, options).execute_stratum(plans)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_differential_backend_executes_scan() {
+        let mut inputs = HashMap::new();
+        inputs.insert(
+            "edge".to_string(),
+            vec![Tuple::from_pair(1, 2), Tuple::from_pair(2, 3)],
+        );
+        let ir = IRNode::Scan {
+            relation: "edge".to_string(),
+            schema: vec!["x".to_string(), "y".to_string()],
+        };
+
+        let backend = DifferentialBackend;
+        let mut results = backend
+            .execute(&ir, Arc::new(inputs), &BackendOptions::default())
+            .unwrap();
+        results.sort();
+        assert_eq!(
+            results,
+            vec![Tuple::from_pair(1, 2), Tuple::from_pair(2, 3)]
+        );
+        assert_eq!(backend.name(), "differential");
+    }
+
+    #[test]
+    fn test_default_execute_stratum_feeds_later_plans() {
+        /// Backend that only implements single-plan execution
+        #[derive(Debug)]
+        struct PlanAtATime;
+
+        impl ExecutionBackend for PlanAtATime {
+            fn name(&self) -> &'static str {
+                "plan-at-a-time"
+            }
+
+            fn execute(
+                &self,
+                ir: &IRNode,
+                inputs: BackendInputs,
+                options: &BackendOptions,
+            ) -> Result<Vec<Tuple>, String> {
+                DifferentialBackend.execute(ir, inputs, options)
+            }
+
+            fn execute_recursive(
+                &self,
+                _ir: &IRNode,
+                _recursive_relation: &str,
+                _inputs: BackendInputs,
+                _options: &BackendOptions,
+            ) -> Result<Vec<Tuple>, String> {
+                Err("recursion is not supported".to_string())
+            }
+        }
+
+        let scan = |relation: &str| IRNode::Scan {
+            relation: relation.to_string(),
+            schema: vec!["x".to_string(), "y".to_string()],
+        };
+        let plans = vec![
+            ("copy".to_string(), scan("edge")),
+            ("copy2".to_string(), scan("copy")),
+        ];
+        let mut inputs = HashMap::new();
+        inputs.insert("edge".to_string(), vec![Tuple::from_pair(1, 2)]);
+
+        let results = PlanAtATime
+            .execute_stratum(&plans, Arc::new(inputs), &BackendOptions::default())
+            .unwrap();
+        assert_eq!(results["copy2"], vec![Tuple::from_pair(1, 2)]);
+    }
+}
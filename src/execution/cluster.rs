@@ -0,0 +1,706 @@
+//! Multi-Process Execution
+//!
+//! `ClusterBackend` runs plans on the timely workers of several processes
+//! instead of the threads of one. The server taking queries is process 0,
+//! the coordinator; every other process listed in `[timely]` runs
+//! `inputlayer-server` as a worker (see `serve_cluster_worker`) and only
+//! executes dataflows.
+//!
+//! For each plan the coordinator connects to every worker process and sends
+//! it the plan, the relations the plan scans and the query's settings. The
+//! worker processes connect to each other, so that every pair of processes
+//! shares one socket, and report back once ready. All processes then build
+//! the same dataflow over those sockets: each timely worker streams a
+//! disjoint share of every relation, DD exchanges records by key between
+//! the workers of all processes, and the results are gathered on the
+//! coordinator's first worker.
+//!
+//! Recursive plans, and plans whose inputs hold fewer than `min_rows` rows,
+//! run on the coordinator alone as with `DifferentialBackend`. So do plans
+//! the worker processes cannot be reached for. A worker process stops a
+//! plan when the query's deadline passes or the coordinator cancels it.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::ops::DerefMut;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use parking_lot::{Condvar, Mutex};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use timely::communication::allocator::zero_copy::bytes_slab::BytesRefill;
+use timely::communication::allocator::zero_copy::initialize::initialize_networking_from_sockets;
+use timely::communication::allocator::{GenericBuilder, Process};
+use tracing::{debug, info, warn};
+
+use super::backend::{BackendInputs, BackendOptions, DifferentialBackend, ExecutionBackend};
+use super::timeout::{CancelHandle, QueryTimeout};
+use crate::auth::constant_time_eq;
+use crate::boolean_specialization::SemiringType;
+use crate::code_generator::{CodeGenerator, JoinPrefilter, TimelyNetwork};
+use crate::config::TimelyConfig;
+use crate::ir::{IRNode, SerializedPlan};
+use crate::value::Tuple;
+
+/// Largest handshake frame read before its sender is authenticated
+const MAX_HELLO_BYTES: u64 = 64 * 1024;
+
+/// How often the coordinator checks whether a running plan was cancelled
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Backend installed by `install_cluster`
+static INSTALLED: OnceLock<ClusterBackend> = OnceLock::new();
+
+/// Run plans across the processes of `config` from now on, unless
+/// multi-process execution is disabled or this process is one of the
+/// workers. Only the first call has an effect.
+pub fn install_cluster(config: &TimelyConfig) {
+    if !config.is_enabled() || config.is_worker() {
+        return;
+    }
+    if INSTALLED.set(ClusterBackend::new(config.clone())).is_ok() {
+        info!(
+            processes = config.processes.len(),
+            workers = config.workers,
+            "timely_cluster_installed"
+        );
+    }
+}
+
+/// The backend installed by `install_cluster`, if any
+pub fn installed_cluster() -> Option<&'static ClusterBackend> {
+    INSTALLED.get()
+}
+
+/// Run the coordinator's plans as worker process `config.process`. Returns
+/// only if the listening socket cannot be opened.
+pub fn serve_cluster_worker(config: &TimelyConfig) -> io::Result<()> {
+    let address = config.processes.get(config.process).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "process {} is not among the timely processes",
+                config.process
+            ),
+        )
+    })?;
+    let listener = TcpListener::bind(address.as_str())?;
+    info!(
+        process = config.process,
+        address = %address,
+        workers = config.workers,
+        "timely_worker_listening"
+    );
+    Arc::new(WorkerProcess::new(config.clone())).serve(&listener);
+    Ok(())
+}
+
+/// First frame on every connection between processes
+#[derive(Debug, Serialize, Deserialize)]
+struct Hello {
+    secret: String,
+    job: u64,
+    kind: HelloKind,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum HelloKind {
+    /// The coordinator starts a job; its `JobSpec` follows
+    Job,
+    /// A worker process joins the job's sockets
+    Peer { process: usize },
+    /// The coordinator cancels the job
+    Cancel,
+}
+
+/// Everything a worker process needs to build the coordinator's dataflow
+#[derive(Debug, Serialize, Deserialize)]
+struct JobSpec<'a> {
+    /// Number of processes and workers per process the coordinator expects
+    processes: usize,
+    workers: usize,
+    /// The plan, as `SerializedPlan` bytes
+    plan: Vec<u8>,
+    /// Every relation the plan scans
+    inputs: Cow<'a, HashMap<String, Vec<Tuple>>>,
+    semiring: SemiringType,
+    /// Time left of the query's budget when the job was sent
+    timeout_ms: Option<u64>,
+    prefilter: Option<PrefilterSpec>,
+}
+
+/// Thresholds of a `JoinPrefilter`, which decide the shape of the dataflow
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct PrefilterSpec {
+    min_probe_rows: usize,
+    min_size_ratio: usize,
+    false_positive_rate: f64,
+    max_runtime_filter_keys: usize,
+}
+
+impl PrefilterSpec {
+    fn of(prefilter: &JoinPrefilter) -> Self {
+        PrefilterSpec {
+            min_probe_rows: prefilter.min_probe_rows,
+            min_size_ratio: prefilter.min_size_ratio,
+            false_positive_rate: prefilter.false_positive_rate,
+            max_runtime_filter_keys: prefilter.max_runtime_filter_keys,
+        }
+    }
+
+    fn into_prefilter(self) -> JoinPrefilter {
+        JoinPrefilter::new(
+            self.min_probe_rows,
+            self.min_size_ratio,
+            self.false_positive_rate,
+        )
+        .with_runtime_filter_keys(self.max_runtime_filter_keys)
+    }
+}
+
+/// Runs plans on the workers of every process of `[timely]`, as process 0
+#[derive(Debug)]
+pub struct ClusterBackend {
+    config: TimelyConfig,
+    next_job: AtomicU64,
+}
+
+impl ClusterBackend {
+    /// Coordinate the processes of `config`
+    pub fn new(config: TimelyConfig) -> Self {
+        ClusterBackend {
+            config,
+            // Job IDs only need to differ across coordinator restarts
+            next_job: AtomicU64::new(rand::random()),
+        }
+    }
+
+    fn connect_timeout(&self) -> Duration {
+        Duration::from_millis(self.config.connect_timeout_ms)
+    }
+
+    fn hello(&self, job: u64, kind: HelloKind) -> Hello {
+        Hello {
+            secret: self.config.secret.clone().unwrap_or_default(),
+            job,
+            kind,
+        }
+    }
+
+    /// Send `job` to every worker process and wait until they are connected
+    /// to each other. Returns this process's socket to each process.
+    fn start_job(&self, job: u64, payload: &[u8]) -> Result<Vec<Option<TcpStream>>, String> {
+        let timeout = self.connect_timeout();
+        let mut sockets: Vec<Option<TcpStream>> = vec![None];
+        for (process, address) in self.config.processes.iter().enumerate().skip(1) {
+            let stream = connect(address, timeout)
+                .and_then(|mut stream| {
+                    write_frame(&mut stream, &self.hello(job, HelloKind::Job))?;
+                    write_bytes(&mut stream, payload)?;
+                    Ok(stream)
+                })
+                .map_err(|e| format!("Cannot reach timely process {process} at {address}: {e}"))?;
+            // Peers wait up to the timeout for each other before answering
+            stream
+                .set_read_timeout(Some(timeout * 2))
+                .map_err(|e| e.to_string())?;
+            sockets.push(Some(stream));
+        }
+        for (process, socket) in sockets.iter_mut().enumerate() {
+            let Some(stream) = socket else { continue };
+            let ready: Result<(), String> = read_frame(stream, MAX_HELLO_BYTES)
+                .map_err(|e| format!("Timely process {process} did not start the plan: {e}"))?;
+            ready.map_err(|e| format!("Timely process {process} rejected the plan: {e}"))?;
+        }
+        Ok(sockets)
+    }
+}
+
+impl ExecutionBackend for ClusterBackend {
+    fn name(&self) -> &'static str {
+        "timely-cluster"
+    }
+
+    fn execute(
+        &self,
+        ir: &IRNode,
+        inputs: BackendInputs,
+        options: &BackendOptions,
+    ) -> Result<Vec<Tuple>, String> {
+        let mut relations = Vec::new();
+        CodeGenerator::collect_scans(ir, &mut relations);
+        relations.sort_unstable();
+        relations.dedup();
+        let rows: usize = relations
+            .iter()
+            .filter_map(|relation| inputs.get(relation))
+            .map(Vec::len)
+            .sum();
+        if rows < self.config.min_rows {
+            return DifferentialBackend.execute(ir, inputs, options);
+        }
+        options.check_interrupted()?;
+
+        let scanned: HashMap<String, Vec<Tuple>> = relations
+            .into_iter()
+            .filter_map(|relation| {
+                let tuples = inputs.get(&relation)?.clone();
+                Some((relation, tuples))
+            })
+            .collect();
+        let job = self.next_job.fetch_add(1, Ordering::Relaxed);
+        let spec = JobSpec {
+            processes: self.config.processes.len(),
+            workers: self.config.workers,
+            plan: SerializedPlan::new(ir.clone()).to_bytes()?,
+            inputs: Cow::Borrowed(&scanned),
+            semiring: options.semiring,
+            timeout_ms: options
+                .timeout
+                .as_ref()
+                .and_then(QueryTimeout::remaining)
+                .map(|left| u64::try_from(left.as_millis()).unwrap_or(u64::MAX)),
+            prefilter: options.join_prefilter.as_ref().map(PrefilterSpec::of),
+        };
+        let payload =
+            bincode::serialize(&spec).map_err(|e| format!("Failed to encode the plan: {e}"))?;
+
+        let started = Instant::now();
+        let sockets = match self.start_job(job, &payload) {
+            Ok(sockets) => sockets,
+            Err(e) => {
+                warn!(job, error = %e, "timely_cluster_unavailable");
+                return DifferentialBackend.execute(ir, inputs, options);
+            }
+        };
+        let network = timely_network(sockets, 0, self.config.workers)?;
+        let _forwarder = CancelForwarder::start(self, job, options.cancel.clone());
+        let results = DifferentialBackend::codegen(Arc::new(scanned), options)
+            .execute_distributed(ir, network);
+        info!(
+            job,
+            rows,
+            payload_bytes = payload.len(),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            ok = results.is_ok(),
+            "timely_job_finished"
+        );
+        results
+    }
+
+    fn execute_recursive(
+        &self,
+        ir: &IRNode,
+        recursive_relation: &str,
+        inputs: BackendInputs,
+        options: &BackendOptions,
+    ) -> Result<Vec<Tuple>, String> {
+        DifferentialBackend.execute_recursive(ir, recursive_relation, inputs, options)
+    }
+
+    fn execute_mutual_recursive(
+        &self,
+        relations: &[(String, IRNode)],
+        inputs: BackendInputs,
+        options: &BackendOptions,
+    ) -> Result<HashMap<String, Vec<Tuple>>, String> {
+        DifferentialBackend.execute_mutual_recursive(relations, inputs, options)
+    }
+}
+
+/// Tells the worker processes to stop a job once its query is cancelled.
+/// Stops watching when dropped.
+struct CancelForwarder {
+    finished: Arc<AtomicBool>,
+    watcher: Option<JoinHandle<()>>,
+}
+
+impl CancelForwarder {
+    fn start(backend: &ClusterBackend, job: u64, cancel: Option<CancelHandle>) -> Self {
+        let finished = Arc::new(AtomicBool::new(false));
+        let watcher = cancel.map(|cancel| {
+            let finished = Arc::clone(&finished);
+            let addresses: Vec<String> = backend.config.processes[1..].to_vec();
+            let timeout = backend.connect_timeout();
+            let hello = backend.hello(job, HelloKind::Cancel);
+            thread::spawn(move || {
+                while !finished.load(Ordering::Relaxed) {
+                    if cancel.is_cancelled() {
+                        for address in &addresses {
+                            let sent = connect(address, timeout)
+                                .and_then(|mut stream| write_frame(&mut stream, &hello));
+                            if let Err(e) = sent {
+                                warn!(job, address = %address, error = %e, "timely_cancel_failed");
+                            }
+                        }
+                        return;
+                    }
+                    thread::sleep(CANCEL_POLL_INTERVAL);
+                }
+            })
+        });
+        CancelForwarder { finished, watcher }
+    }
+}
+
+impl Drop for CancelForwarder {
+    fn drop(&mut self) {
+        self.finished.store(true, Ordering::Relaxed);
+        if let Some(watcher) = self.watcher.take() {
+            let _ = watcher.join();
+        }
+    }
+}
+
+/// A worker process: runs the jobs the coordinator sends it
+struct WorkerProcess {
+    config: TimelyConfig,
+    /// Sockets from higher-numbered worker processes, by job
+    peers: Mutex<HashMap<u64, Vec<(usize, TcpStream)>>>,
+    peer_arrived: Condvar,
+    /// Cancel handles of the running jobs
+    running: Mutex<HashMap<u64, CancelHandle>>,
+}
+
+impl WorkerProcess {
+    fn new(config: TimelyConfig) -> Self {
+        WorkerProcess {
+            config,
+            peers: Mutex::new(HashMap::new()),
+            peer_arrived: Condvar::new(),
+            running: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn connect_timeout(&self) -> Duration {
+        Duration::from_millis(self.config.connect_timeout_ms)
+    }
+
+    fn serve(self: Arc<Self>, listener: &TcpListener) {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let worker = Arc::clone(&self);
+                    thread::spawn(move || worker.handle(stream));
+                }
+                Err(e) => warn!(error = %e, "timely_accept_failed"),
+            }
+        }
+    }
+
+    fn handle(&self, mut stream: TcpStream) {
+        let hello = stream
+            .set_read_timeout(Some(self.connect_timeout()))
+            .and_then(|()| read_frame::<Hello>(&mut stream, MAX_HELLO_BYTES));
+        let hello = match hello {
+            Ok(hello) => hello,
+            Err(e) => {
+                debug!(error = %e, "timely_hello_unreadable");
+                return;
+            }
+        };
+        let secret = self.config.secret.as_deref().unwrap_or_default();
+        if !constant_time_eq(secret.as_bytes(), hello.secret.as_bytes()) {
+            warn!(peer = ?stream.peer_addr().ok(), "timely_hello_rejected");
+            return;
+        }
+        match hello.kind {
+            HelloKind::Job => self.run_job(stream, hello.job),
+            HelloKind::Peer { process } => {
+                self.peers
+                    .lock()
+                    .entry(hello.job)
+                    .or_default()
+                    .push((process, stream));
+                self.peer_arrived.notify_all();
+            }
+            HelloKind::Cancel => {
+                if let Some(cancel) = self.running.lock().get(&hello.job) {
+                    cancel.cancel();
+                }
+            }
+        }
+    }
+
+    fn run_job(&self, mut coordinator: TcpStream, job: u64) {
+        let prepared = read_frame::<JobSpec<'static>>(&mut coordinator, u64::MAX)
+            .map_err(|e| format!("Cannot read the job: {e}"))
+            .and_then(|spec| {
+                let plan = self.check_job(&spec)?;
+                let sockets = self.connect_peers(job)?;
+                Ok((spec, plan, sockets))
+            });
+        let (spec, plan, mut sockets) = match prepared {
+            Ok(prepared) => prepared,
+            Err(e) => {
+                warn!(job, error = %e, "timely_job_rejected");
+                let _ = write_frame(&mut coordinator, &Err::<(), String>(e));
+                return;
+            }
+        };
+        if write_frame(&mut coordinator, &Ok::<(), String>(())).is_err() {
+            return;
+        }
+        sockets[0] = Some(coordinator);
+
+        let cancel = CancelHandle::new();
+        self.running.lock().insert(job, cancel.clone());
+        let started = Instant::now();
+        let outcome = timely_network(sockets, self.config.process, self.config.workers)
+            .and_then(|network| job_codegen(spec, cancel).execute_distributed(&plan, network));
+        self.running.lock().remove(&job);
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        match outcome {
+            Ok(_) => debug!(job, elapsed_ms, "timely_job_finished"),
+            Err(e) => warn!(job, elapsed_ms, error = %e, "timely_job_failed"),
+        }
+    }
+
+    /// The plan of `spec`, if this process is configured like the coordinator
+    fn check_job(&self, spec: &JobSpec<'_>) -> Result<IRNode, String> {
+        if spec.processes != self.config.processes.len() || spec.workers != self.config.workers {
+            return Err(format!(
+                "Coordinator expects {} processes of {} workers, this process is configured for {} of {}",
+                spec.processes,
+                spec.workers,
+                self.config.processes.len(),
+                self.config.workers
+            ));
+        }
+        Ok(SerializedPlan::from_bytes(&spec.plan)?.into_ir())
+    }
+
+    /// Connect to the lower-numbered worker processes and wait for the
+    /// higher-numbered ones, so that every pair of processes shares a socket.
+    /// The coordinator's slot is left empty.
+    fn connect_peers(&self, job: u64) -> Result<Vec<Option<TcpStream>>, String> {
+        let me = self.config.process;
+        let count = self.config.processes.len();
+        let timeout = self.connect_timeout();
+        let mut sockets: Vec<Option<TcpStream>> = (0..count).map(|_| None).collect();
+        for (process, address) in self.config.processes.iter().enumerate().take(me).skip(1) {
+            let hello = Hello {
+                secret: self.config.secret.clone().unwrap_or_default(),
+                job,
+                kind: HelloKind::Peer { process: me },
+            };
+            let stream = connect(address, timeout)
+                .and_then(|mut stream| write_frame(&mut stream, &hello).map(|()| stream))
+                .map_err(|e| format!("Cannot reach timely process {process} at {address}: {e}"))?;
+            sockets[process] = Some(stream);
+        }
+
+        let expected = count - me - 1;
+        let deadline = Instant::now() + timeout;
+        let mut peers = self.peers.lock();
+        loop {
+            let arrived = peers.get(&job).map_or(0, Vec::len);
+            if arrived >= expected {
+                break;
+            }
+            if self
+                .peer_arrived
+                .wait_until(&mut peers, deadline)
+                .timed_out()
+            {
+                peers.remove(&job);
+                return Err(format!(
+                    "Timed out waiting for {} other timely processes",
+                    expected - arrived
+                ));
+            }
+        }
+        for (process, stream) in peers.remove(&job).unwrap_or_default() {
+            if process <= me || process >= count {
+                return Err(format!(
+                    "Unexpected timely process {process} joined the job"
+                ));
+            }
+            sockets[process] = Some(stream);
+        }
+        if sockets.iter().skip(me + 1).any(Option::is_none) {
+            return Err("A timely process joined the job twice".to_string());
+        }
+        Ok(sockets)
+    }
+}
+
+/// Code generator for a job received from the coordinator
+fn job_codegen(spec: JobSpec<'_>, cancel: CancelHandle) -> CodeGenerator {
+    let mut codegen = CodeGenerator::new();
+    codegen.set_semiring_type(spec.semiring);
+    codegen.set_cancel_handle(Some(cancel));
+    codegen.set_query_timeout(
+        spec.timeout_ms
+            .map(|ms| QueryTimeout::new(Some(Duration::from_millis(ms)))),
+    );
+    codegen.set_join_prefilter(spec.prefilter.map(PrefilterSpec::into_prefilter));
+    codegen.set_shared_input(Arc::new(spec.inputs.into_owned()));
+    codegen
+}
+
+/// Start timely communication over `sockets`, which hold one connected
+/// socket per other process and `None` at index `process`
+fn timely_network(
+    sockets: Vec<Option<TcpStream>>,
+    process: usize,
+    workers: usize,
+) -> Result<TimelyNetwork, String> {
+    for socket in sockets.iter().flatten() {
+        socket
+            .set_read_timeout(None)
+            .map_err(|e| format!("Cannot prepare timely socket: {e}"))?;
+    }
+    let refill = BytesRefill {
+        logic: Arc::new(|size| Box::new(vec![0_u8; size]) as Box<dyn DerefMut<Target = [u8]>>),
+        limit: None,
+    };
+    let (builders, guard) = initialize_networking_from_sockets::<_, Process>(
+        sockets,
+        process,
+        workers,
+        refill,
+        Arc::new(|_| None),
+    )
+    .map_err(|e| format!("Cannot start timely networking: {e}"))?;
+    Ok(TimelyNetwork {
+        builders: builders.into_iter().map(GenericBuilder::ZeroCopy).collect(),
+        guard: Box::new(guard),
+    })
+}
+
+/// Connect to `address`, trying each address it resolves to
+fn connect(address: &str, timeout: Duration) -> io::Result<TcpStream> {
+    let mut last_error = None;
+    for resolved in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&resolved, timeout) {
+            Ok(stream) => {
+                stream.set_nodelay(true)?;
+                return Ok(stream);
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error
+        .unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "address did not resolve")))
+}
+
+fn write_frame<T: Serialize>(stream: &mut TcpStream, message: &T) -> io::Result<()> {
+    let bytes =
+        bincode::serialize(message).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    write_bytes(stream, &bytes)
+}
+
+/// Write `bytes` as one length-prefixed frame
+fn write_bytes(stream: &mut TcpStream, bytes: &[u8]) -> io::Result<()> {
+    stream.write_all(&(bytes.len() as u64).to_be_bytes())?;
+    stream.write_all(bytes)?;
+    stream.flush()
+}
+
+/// Read one length-prefixed frame of at most `limit` bytes
+fn read_frame<T: DeserializeOwned>(stream: &mut TcpStream, limit: u64) -> io::Result<T> {
+    let mut length = [0_u8; 8];
+    stream.read_exact(&mut length)?;
+    let length = u64::from_be_bytes(length);
+    if length > limit {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame of {length} bytes exceeds {limit}"),
+        ));
+    }
+    let length =
+        usize::try_from(length).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let mut bytes = vec![0_u8; length];
+    stream.read_exact(&mut bytes)?;
+    bincode::deserialize(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn scan(relation: &str) -> IRNode {
+        IRNode::Scan {
+            relation: relation.to_string(),
+            schema: vec!["x".to_string(), "y".to_string()],
+        }
+    }
+
+    /// Coordinator config plus a worker process listening on a free port
+    fn start_worker(secret: &str) -> TimelyConfig {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let worker_address = listener.local_addr().unwrap().to_string();
+        let config = TimelyConfig {
+            // The coordinator never listens
+            processes: vec!["127.0.0.1:1".to_string(), worker_address],
+            process: 0,
+            workers: 2,
+            secret: Some("secret".to_string()),
+            min_rows: 0,
+            connect_timeout_ms: 5000,
+        };
+        let worker_config = TimelyConfig {
+            process: 1,
+            secret: Some(secret.to_string()),
+            ..config.clone()
+        };
+        thread::spawn(move || Arc::new(WorkerProcess::new(worker_config)).serve(&listener));
+        config
+    }
+
+    #[test]
+    fn test_cluster_join_matches_single_process() {
+        let config = start_worker("secret");
+        let edges: Vec<Tuple> = (0..200).map(|i| Tuple::from_pair(i, i + 1)).collect();
+        let mut inputs = HashMap::new();
+        inputs.insert("edge".to_string(), edges);
+        let inputs = Arc::new(inputs);
+        let ir = IRNode::Join {
+            left: Box::new(scan("edge")),
+            right: Box::new(scan("edge")),
+            left_keys: vec![1],
+            right_keys: vec![0],
+            output_schema: vec!["x".to_string(), "y".to_string(), "z".to_string()],
+        };
+        let options = BackendOptions::default();
+
+        let mut expected = DifferentialBackend
+            .execute(&ir, Arc::clone(&inputs), &options)
+            .unwrap();
+        let mut results = ClusterBackend::new(config)
+            .execute(&ir, inputs, &options)
+            .unwrap();
+        expected.sort();
+        results.sort();
+        assert_eq!(results.len(), 199);
+        assert_eq!(results, expected);
+    }
+
+    #[test]
+    fn test_cluster_runs_locally_when_workers_refuse() {
+        let config = start_worker("other");
+        let mut inputs = HashMap::new();
+        inputs.insert("edge".to_string(), vec![Tuple::from_pair(1, 2)]);
+        let results = ClusterBackend::new(config)
+            .execute(&scan("edge"), Arc::new(inputs), &BackendOptions::default())
+            .unwrap();
+        assert_eq!(results, vec![Tuple::from_pair(1, 2)]);
+    }
+
+    #[test]
+    fn test_prefilter_spec_round_trip() {
+        let prefilter = JoinPrefilter::new(5, 3, 0.05).with_runtime_filter_keys(7);
+        let restored = PrefilterSpec::of(&prefilter).into_prefilter();
+        assert_eq!(restored.min_probe_rows, 5);
+        assert_eq!(restored.min_size_ratio, 3);
+        assert_eq!(restored.max_runtime_filter_keys, 7);
+        assert!((restored.false_positive_rate - 0.05).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,265 @@
+//! Per-Query Memory Budgets
+//!
+//! Differential Dataflow keeps every update that enters an arrangement
+//! (joins, distinct, aggregation) until the dataflow is dropped, and the
+//! code generator buffers result rows until the computation completes. A
+//! single pathological query can therefore exhaust the server's memory.
+//!
+//! ## Design
+//!
+//! - `ResourceLimits` holds the configured per-query budget
+//! - `MemoryTracker` is shared by all dataflows of one query; operators
+//!   charge the estimated size of each update they materialize, and the
+//!   engine charges the derived relations it holds between dataflows
+//! - Each dataflow charges through its own child tracker. Dropping the
+//!   dataflow frees its arrangements, so the last clone of the child credits
+//!   back everything charged through it
+//! - The tracker keeps the live total and its peak. Once the live total
+//!   exceeds the budget the tracker latches, the worker stepping loop stops,
+//!   and the query fails with `ResourceError`
+//!
+//! Within a dataflow, charges are an upper bound: retractions and
+//! compaction are not credited back until the dataflow is dropped.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Resource limit error
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ResourceError {
+    /// The query materialized more data than its memory budget allows
+    #[error("Query exceeded memory budget of {budget} bytes (peak {used} bytes)")]
+    MemoryBudgetExceeded {
+        /// Configured budget in bytes
+        budget: usize,
+        /// Peak bytes held when the budget was exceeded
+        used: usize,
+    },
+}
+
+/// Per-query resource limits
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// Maximum bytes a query may hold (0 = unlimited)
+    pub max_query_memory_bytes: usize,
+}
+
+impl ResourceLimits {
+    /// Limits with the given per-query memory budget (0 = unlimited)
+    pub fn with_memory_budget(max_query_memory_bytes: usize) -> Self {
+        ResourceLimits {
+            max_query_memory_bytes,
+        }
+    }
+
+    /// Tracker measuring a query under these limits. With unlimited memory
+    /// it only measures.
+    pub fn memory_tracker(&self) -> MemoryTracker {
+        MemoryTracker::new(self.max_query_memory_bytes)
+    }
+}
+
+/// Counters shared by every tracker of one query
+#[derive(Debug)]
+struct Counters {
+    used: AtomicUsize,
+    peak: AtomicUsize,
+    exceeded: AtomicBool,
+    budget: usize,
+}
+
+impl Counters {
+    fn release(&self, bytes: usize) {
+        // Never below zero if more is released than was charged
+        let _ = self
+            .used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                Some(used.saturating_sub(bytes))
+            });
+    }
+}
+
+/// Bytes charged through a child tracker, credited back when its last
+/// clone is dropped
+#[derive(Debug)]
+struct Held {
+    counters: Arc<Counters>,
+    bytes: AtomicUsize,
+}
+
+impl Drop for Held {
+    fn drop(&mut self) {
+        self.counters.release(*self.bytes.get_mut());
+    }
+}
+
+/// Shared accounting of the memory held by one query
+///
+/// Cloning shares the counters, so every worker and operator of the query
+/// charges the same budget.
+#[derive(Debug, Clone)]
+pub struct MemoryTracker {
+    counters: Arc<Counters>,
+    /// Set on the tracker of one dataflow
+    held: Option<Arc<Held>>,
+}
+
+impl MemoryTracker {
+    /// Create a tracker for a budget of `budget` bytes (0 = unlimited)
+    pub fn new(budget: usize) -> Self {
+        MemoryTracker {
+            counters: Arc::new(Counters {
+                used: AtomicUsize::new(0),
+                peak: AtomicUsize::new(0),
+                exceeded: AtomicBool::new(false),
+                budget,
+            }),
+            held: None,
+        }
+    }
+
+    /// A tracker charging the same budget whose charges are credited back
+    /// once it and all its clones are dropped, for state that is freed
+    /// together, like the arrangements of one dataflow
+    pub fn child(&self) -> MemoryTracker {
+        MemoryTracker {
+            counters: Arc::clone(&self.counters),
+            held: Some(Arc::new(Held {
+                counters: Arc::clone(&self.counters),
+                bytes: AtomicUsize::new(0),
+            })),
+        }
+    }
+
+    /// Charge `bytes` against the budget.
+    ///
+    /// Returns `false` once the budget has been exceeded.
+    pub fn charge(&self, bytes: usize) -> bool {
+        let counters = &self.counters;
+        if let Some(held) = &self.held {
+            held.bytes.fetch_add(bytes, Ordering::Relaxed);
+        }
+        let used = counters
+            .used
+            .fetch_add(bytes, Ordering::Relaxed)
+            .saturating_add(bytes);
+        counters.peak.fetch_max(used, Ordering::Relaxed);
+        if counters.budget > 0 && used > counters.budget {
+            counters.exceeded.store(true, Ordering::Relaxed);
+            return false;
+        }
+        !self.is_exceeded()
+    }
+
+    /// Credit back `bytes` charged earlier, once they are freed
+    pub fn release(&self, bytes: usize) {
+        if let Some(held) = &self.held {
+            let _ = held
+                .bytes
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |held| {
+                    Some(held.saturating_sub(bytes))
+                });
+        }
+        self.counters.release(bytes);
+    }
+
+    /// Whether the budget has been exceeded
+    pub fn is_exceeded(&self) -> bool {
+        self.counters.exceeded.load(Ordering::Relaxed)
+    }
+
+    /// Bytes held now
+    pub fn used(&self) -> usize {
+        self.counters.used.load(Ordering::Relaxed)
+    }
+
+    /// Most bytes held at once so far
+    pub fn peak(&self) -> usize {
+        self.counters.peak.load(Ordering::Relaxed)
+    }
+
+    /// Configured budget in bytes (0 = unlimited)
+    pub fn budget(&self) -> usize {
+        self.counters.budget
+    }
+
+    /// `Err` once the budget has been exceeded
+    pub fn check(&self) -> Result<(), ResourceError> {
+        if self.is_exceeded() {
+            return Err(ResourceError::MemoryBudgetExceeded {
+                budget: self.budget(),
+                used: self.peak(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unlimited_tracker_only_measures() {
+        let tracker = ResourceLimits::default().memory_tracker();
+        assert!(tracker.charge(usize::MAX / 2));
+        assert!(tracker.check().is_ok());
+        assert_eq!(tracker.peak(), usize::MAX / 2);
+        assert_eq!(
+            ResourceLimits::with_memory_budget(1024)
+                .memory_tracker()
+                .budget(),
+            1024
+        );
+    }
+
+    #[test]
+    fn test_charge_within_budget() {
+        let tracker = MemoryTracker::new(100);
+        assert!(tracker.charge(60));
+        assert!(tracker.charge(40));
+        assert_eq!(tracker.used(), 100);
+        assert!(tracker.check().is_ok());
+    }
+
+    #[test]
+    fn test_exceeding_budget_latches() {
+        let tracker = MemoryTracker::new(100);
+        let shared = tracker.clone();
+        assert!(tracker.charge(80));
+        assert!(!shared.charge(40));
+        assert!(tracker.is_exceeded());
+        assert_eq!(
+            tracker.check().unwrap_err(),
+            ResourceError::MemoryBudgetExceeded {
+                budget: 100,
+                used: 120
+            }
+        );
+    }
+
+    #[test]
+    fn test_child_credits_back_when_dropped() {
+        let tracker = MemoryTracker::new(100);
+        assert!(tracker.charge(30));
+        let dataflow = tracker.child();
+        let worker = dataflow.clone();
+        assert!(dataflow.charge(40));
+        assert!(worker.charge(20));
+        assert_eq!(tracker.used(), 90);
+
+        drop(dataflow);
+        assert_eq!(tracker.used(), 90, "a clone is still alive");
+        drop(worker);
+        assert_eq!(tracker.used(), 30);
+        assert_eq!(tracker.peak(), 90);
+
+        // Freed memory is available to the next dataflow
+        let next = tracker.child();
+        assert!(next.charge(60));
+        tracker.release(30);
+        assert_eq!(tracker.used(), 60);
+        assert!(tracker.check().is_ok());
+    }
+}
@@ -2,11 +2,26 @@
 //!
 //! Provides production-grade query execution with:
 //! - Timeout enforcement via cooperative cancellation
+//! - Per-query memory budgets
+//! - Cross-query caching of materialized subplans
+//! - Plan capture for the slow query log
+//! - Pluggable execution backends
+//! - Dataflows spread over the worker processes of a timely cluster
 
+mod backend;
+mod cluster;
+mod memory;
+pub mod plan_capture;
+mod subplan_cache;
 mod timeout;
 pub mod timing;
 
-pub use timeout::{CancelHandle, QueryTimeout, TimeoutError};
+pub use backend::{BackendInputs, BackendOptions, DifferentialBackend, ExecutionBackend};
+pub use cluster::{install_cluster, installed_cluster, serve_cluster_worker, ClusterBackend};
+pub use memory::{MemoryTracker, ResourceError, ResourceLimits};
+pub use plan_capture::{PlanCapture, PlanStep, ProgramPlan};
+pub use subplan_cache::{SubplanCache, SubplanCacheEntryStats, SubplanCacheStats};
+pub use timeout::{CancelHandle, QueryProgress, QueryTimeout, TimeoutError};
 pub use timing::{
     IrBuilderTiming, OptimizerTiming, RuleTiming, TimingBreakdown, TimingCollector,
     TimingHistograms, TimingMode,
@@ -19,6 +34,10 @@ pub enum ExecutionError {
     #[error("Query timeout: {0}")]
     Timeout(#[from] TimeoutError),
 
+    /// Query exceeded a resource limit
+    #[error("Resource limit: {0}")]
+    Resource(#[from] ResourceError),
+
     /// Query execution error
     #[error("Query error: {0}")]
     QueryError(String),
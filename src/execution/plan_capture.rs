@@ -0,0 +1,146 @@
+//! Plan Capture
+//!
+//! While a [`PlanCapture`] is installed on a thread, the engine records the
+//! plan of every program it runs there: the optimized IR of each rule, the
+//! rows of the stored relations the program reads, and the execution time
+//! and output rows of each rule (or recursive component). The handler
+//! installs one around query execution when the slow query log is on and
+//! keeps the plan only if the query turns out slow (see
+//! [`crate::slow_query_log`]).
+
+use std::cell::RefCell;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+// Thread-local capture, set by Handler around DD computation like the
+// query cancellation flag.
+thread_local! {
+    static CAPTURE: RefCell<Option<PlanCapture>> = const { RefCell::new(None) };
+}
+
+/// One rule, or group of rules evaluated together, as executed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanStep {
+    /// Rule head, or the heads of the group, comma-separated
+    pub rules: String,
+    pub recursive: bool,
+    /// Execution time (us); zero with `timing_mode = "off"`
+    pub execution_us: u64,
+    /// Rows produced
+    pub rows: usize,
+}
+
+/// The plan of one program run by the engine
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProgramPlan {
+    /// Optimized IR of each rule, by rule head
+    pub rules: Vec<(String, String)>,
+    /// Rows of each stored relation the program reads
+    pub inputs: Vec<(String, usize)>,
+    /// Executed rules, in execution order
+    pub steps: Vec<PlanStep>,
+}
+
+/// Plans recorded on the threads a capture is installed on
+#[derive(Debug, Clone, Default)]
+pub struct PlanCapture(Arc<Mutex<Vec<ProgramPlan>>>);
+
+impl PlanCapture {
+    /// Record plans on the current thread until the returned guard is
+    /// dropped
+    pub fn install(&self) -> PlanCaptureScope {
+        CAPTURE.with(|cell| *cell.borrow_mut() = Some(self.clone()));
+        PlanCaptureScope(())
+    }
+
+    /// The programs recorded so far. A program cut short (timed out or
+    /// failed) has the steps it finished.
+    pub fn plans(&self) -> Vec<ProgramPlan> {
+        self.0.lock().clone()
+    }
+}
+
+/// Stops recording on the current thread when dropped
+pub struct PlanCaptureScope(());
+
+impl Drop for PlanCaptureScope {
+    fn drop(&mut self) {
+        CAPTURE.with(|cell| *cell.borrow_mut() = None);
+    }
+}
+
+fn with_capture(f: impl FnOnce(&mut Vec<ProgramPlan>)) {
+    CAPTURE.with(|cell| {
+        if let Some(capture) = cell.borrow().as_ref() {
+            f(&mut capture.0.lock());
+        }
+    });
+}
+
+/// Start recording a program. `plan` is only built while capturing.
+pub(crate) fn begin_program(plan: impl FnOnce() -> ProgramPlan) {
+    with_capture(|plans| plans.push(plan()));
+}
+
+/// Record an executed rule of the current program
+pub(crate) fn record_step(rules: &str, execution_us: u64, recursive: bool) {
+    with_capture(|plans| {
+        if let Some(program) = plans.last_mut() {
+            program.steps.push(PlanStep {
+                rules: rules.to_string(),
+                recursive,
+                execution_us,
+                rows: 0,
+            });
+        }
+    });
+}
+
+/// Fill in the rows each step of the current program produced, given the
+/// rows of each rule head
+pub(crate) fn finish_program(rows: impl Fn(&str) -> usize) {
+    with_capture(|plans| {
+        if let Some(program) = plans.last_mut() {
+            for step in &mut program.steps {
+                step.rows = step.rules.split(", ").map(&rows).sum();
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::IQLEngine;
+
+    #[test]
+    fn test_capture_records_plan_inputs_and_steps() {
+        let mut engine = IQLEngine::new();
+        engine.add_fact("edge", vec![(1, 2), (2, 3), (3, 4)]);
+        let program = "path(X, Y) <- edge(X, Y)\npath(X, Z) <- path(X, Y), edge(Y, Z)";
+
+        // Nothing is recorded without a capture installed
+        let capture = PlanCapture::default();
+        engine.execute(program).unwrap();
+        assert!(capture.plans().is_empty());
+
+        let scope = capture.install();
+        engine.execute(program).unwrap();
+        drop(scope);
+        engine.execute(program).unwrap();
+
+        let plans = capture.plans();
+        assert_eq!(plans.len(), 1);
+        let plan = &plans[0];
+        assert!(plan
+            .rules
+            .iter()
+            .any(|(head, ir)| head == "path" && ir.contains("Scan(edge)")));
+        assert_eq!(plan.inputs, vec![("edge".to_string(), 3)]);
+        let step = plan.steps.iter().find(|s| s.rules == "path").unwrap();
+        assert!(step.recursive);
+        assert_eq!(step.rows, 6);
+    }
+}
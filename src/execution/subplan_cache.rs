@@ -0,0 +1,535 @@
+//! Cross-Query Subplan Cache
+//!
+//! Keeps the materialized results of subplans across successive queries on
+//! the same engine, so repeated interactive queries over the same joins skip
+//! re-execution.
+//!
+//! ## Design
+//!
+//! - Entries are keyed by a structural hash of the IR (variable names are
+//!   ignored, so `edge(X, Y)` and `edge(A, B)` share an entry) combined with
+//!   the execution settings that affect the result.
+//! - Each entry records the base relations it reads. Changing a relation
+//!   invalidates every entry that depends on it.
+//! - A knowledge graph shares one cache between the engines of all its
+//!   snapshots. Their keys also hold the version of each relation read
+//!   (see `IQLEngine::set_relation_versions`), so a query only ever reuses
+//!   results computed from the data of its own snapshot.
+//! - Capacity is bounded by entry count and total cached tuples. Eviction
+//!   weighs what an entry saves against the room it takes (GreedyDual-Size):
+//!   each entry's priority is its recorded execution time per cached tuple,
+//!   on top of a floor that rises to the priority of every evicted entry, so
+//!   entries nobody hits age out. The lowest priority is evicted first,
+//!   the least recently used among equals.
+//! - A result is not admitted if making room for it would evict an entry of
+//!   higher priority, so a cheap huge result cannot displace many expensive
+//!   small ones.
+//! - A cache with version keys stays valid across restarts and can be saved
+//!   with `to_bytes` and reloaded with `load_bytes`. The bytes are only
+//!   accepted by the build that wrote them, as key hashes are not stable
+//!   between builds.
+//!
+//! The cache is internally synchronized and can be shared between engines
+//! via `Arc`.
+
+use crate::metrics::metrics;
+use crate::value::Tuple;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Default maximum number of cached subplans
+const DEFAULT_MAX_ENTRIES: usize = 64;
+
+/// Default maximum number of tuples held across all entries
+const DEFAULT_MAX_TUPLES: usize = 1_000_000;
+
+/// Subplan cache statistics
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SubplanCacheStats {
+    /// Lookups answered from the cache
+    pub hits: u64,
+    /// Lookups that found no entry
+    pub misses: u64,
+    /// Entries dropped because a relation they read changed
+    pub invalidations: u64,
+    /// Entries dropped to make room for new ones
+    pub evictions: u64,
+    /// Results not admitted because they were worth less than the entries
+    /// they would have displaced
+    pub rejections: u64,
+    /// Number of entries currently cached
+    pub entries: usize,
+    /// Number of tuples currently cached
+    pub cached_tuples: usize,
+    /// Cost, size and priority of every cached entry, lowest priority
+    /// (next to be evicted) first
+    pub entry_stats: Vec<SubplanCacheEntryStats>,
+}
+
+/// One cached subplan, as reported in [`SubplanCacheStats::entry_stats`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubplanCacheEntryStats {
+    /// Cache key
+    pub key: u64,
+    /// Base relations the subplan reads
+    pub dependencies: Vec<String>,
+    /// Number of cached tuples
+    pub tuples: usize,
+    /// Execution time of the subplan when it was cached (us)
+    pub cost_us: u64,
+    /// Lookups answered from this entry
+    pub hits: u64,
+    /// Eviction priority; the lowest is evicted first
+    pub priority: f64,
+}
+
+/// A cached subplan result
+struct CacheEntry {
+    /// Materialized tuples
+    tuples: Vec<Tuple>,
+    /// Base relations the subplan reads
+    dependencies: Vec<String>,
+    /// Execution time of the subplan (us)
+    cost_us: u64,
+    hits: u64,
+    /// Floor at the last hit or insert plus cost per tuple
+    priority: f64,
+    /// Logical timestamp of the last hit or insert (breaks priority ties)
+    last_used: u64,
+}
+
+/// Saved form of a cache (see `SubplanCache::to_bytes`)
+#[derive(Serialize, Deserialize)]
+struct SavedCache {
+    /// Version of the build that wrote it
+    version: String,
+    /// Entries, lowest priority first
+    entries: Vec<SavedEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedEntry {
+    key: u64,
+    tuples: Vec<Tuple>,
+    dependencies: Vec<String>,
+    cost_us: u64,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<u64, CacheEntry>,
+    clock: u64,
+    /// Priority of the last evicted entry
+    floor: f64,
+    cached_tuples: usize,
+    counters: Counters,
+}
+
+/// Running totals reported in `SubplanCacheStats`
+#[derive(Debug, Clone, Copy, Default)]
+struct Counters {
+    hits: u64,
+    misses: u64,
+    invalidations: u64,
+    evictions: u64,
+    rejections: u64,
+}
+
+/// Recomputation time saved per cached tuple
+fn density(cost_us: u64, tuples: usize) -> f64 {
+    cost_us as f64 / tuples.max(1) as f64
+}
+
+/// Materialized subplan results shared across queries.
+pub struct SubplanCache {
+    state: Mutex<CacheState>,
+    max_entries: usize,
+    max_tuples: usize,
+}
+
+impl SubplanCache {
+    /// Create a cache with default capacity limits
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_MAX_ENTRIES, DEFAULT_MAX_TUPLES)
+    }
+
+    /// Create a cache holding at most `max_entries` subplans and
+    /// `max_tuples` tuples in total
+    pub fn with_capacity(max_entries: usize, max_tuples: usize) -> Self {
+        SubplanCache {
+            state: Mutex::new(CacheState::default()),
+            max_entries,
+            max_tuples,
+        }
+    }
+
+    /// Look up a cached result
+    pub fn get(&self, key: u64) -> Option<Vec<Tuple>> {
+        let mut state = self.state.lock();
+        state.clock += 1;
+        let now = state.clock;
+        let floor = state.floor;
+        let hit = state.entries.get_mut(&key).map(|entry| {
+            entry.last_used = now;
+            entry.hits += 1;
+            entry.priority = floor + density(entry.cost_us, entry.tuples.len());
+            entry.tuples.clone()
+        });
+        if hit.is_some() {
+            state.counters.hits += 1;
+            metrics().subplan_cache_hits.inc();
+        } else {
+            state.counters.misses += 1;
+            metrics().subplan_cache_misses.inc();
+        }
+        hit
+    }
+
+    /// Store a result that depends on the given base relations and took
+    /// `cost_us` to compute.
+    ///
+    /// Results larger than the whole tuple budget, or worth less than the
+    /// entries that would have to be evicted for them, are not admitted.
+    pub fn insert(&self, key: u64, tuples: Vec<Tuple>, dependencies: Vec<String>, cost_us: u64) {
+        if self.max_entries == 0 || tuples.len() > self.max_tuples {
+            return;
+        }
+
+        let mut state = self.state.lock();
+        if let Some(old) = state.entries.remove(&key) {
+            state.cached_tuples -= old.tuples.len();
+        }
+
+        let priority = state.floor + density(cost_us, tuples.len());
+        let Some(victims) = self.victims(&state, tuples.len(), priority) else {
+            state.counters.rejections += 1;
+            return;
+        };
+        for victim in victims {
+            if let Some(entry) = state.entries.remove(&victim) {
+                state.cached_tuples -= entry.tuples.len();
+                state.floor = state.floor.max(entry.priority);
+                state.counters.evictions += 1;
+            }
+        }
+
+        state.clock += 1;
+        let last_used = state.clock;
+        state.cached_tuples += tuples.len();
+        state.entries.insert(
+            key,
+            CacheEntry {
+                tuples,
+                dependencies,
+                cost_us,
+                hits: 0,
+                priority,
+                last_used,
+            },
+        );
+    }
+
+    /// Entries to evict, lowest priority first, to make room for `tuples`
+    /// more tuples, or `None` if one of them outranks the newcomer's
+    /// `priority`
+    fn victims(&self, state: &CacheState, tuples: usize, priority: f64) -> Option<Vec<u64>> {
+        let mut candidates: Vec<(&u64, &CacheEntry)> = state.entries.iter().collect();
+        candidates.sort_by(|(_, a), (_, b)| {
+            a.priority
+                .total_cmp(&b.priority)
+                .then(a.last_used.cmp(&b.last_used))
+        });
+
+        let mut entries = state.entries.len();
+        let mut cached = state.cached_tuples;
+        let mut victims = Vec::new();
+        for (key, entry) in candidates {
+            if entries < self.max_entries && cached + tuples <= self.max_tuples {
+                break;
+            }
+            if entry.priority > priority {
+                return None;
+            }
+            victims.push(*key);
+            entries -= 1;
+            cached -= entry.tuples.len();
+        }
+        Some(victims)
+    }
+
+    /// Drop every entry that reads `relation`
+    pub fn invalidate_relation(&self, relation: &str) {
+        let mut state = self.state.lock();
+        let stale: Vec<u64> = state
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.dependencies.iter().any(|d| d == relation))
+            .map(|(key, _)| *key)
+            .collect();
+        for key in stale {
+            if let Some(entry) = state.entries.remove(&key) {
+                state.cached_tuples -= entry.tuples.len();
+                state.counters.invalidations += 1;
+                metrics().subplan_cache_invalidations.inc();
+            }
+        }
+    }
+
+    /// Drop all entries.
+    ///
+    /// A reset is not an invalidation: nothing is added to the
+    /// invalidation counters.
+    pub fn clear(&self) {
+        let mut state = self.state.lock();
+        state.entries.clear();
+        state.cached_tuples = 0;
+    }
+
+    /// Number of cached entries
+    pub fn len(&self) -> usize {
+        self.state.lock().entries.len()
+    }
+
+    /// Whether the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.state.lock().entries.is_empty()
+    }
+
+    /// Get cache statistics
+    pub fn stats(&self) -> SubplanCacheStats {
+        let state = self.state.lock();
+        let mut entry_stats: Vec<SubplanCacheEntryStats> = state
+            .entries
+            .iter()
+            .map(|(key, entry)| SubplanCacheEntryStats {
+                key: *key,
+                dependencies: entry.dependencies.clone(),
+                tuples: entry.tuples.len(),
+                cost_us: entry.cost_us,
+                hits: entry.hits,
+                priority: entry.priority,
+            })
+            .collect();
+        entry_stats.sort_by(|a, b| a.priority.total_cmp(&b.priority));
+        let counters = state.counters;
+        SubplanCacheStats {
+            hits: counters.hits,
+            misses: counters.misses,
+            invalidations: counters.invalidations,
+            evictions: counters.evictions,
+            rejections: counters.rejections,
+            entries: state.entries.len(),
+            cached_tuples: state.cached_tuples,
+            entry_stats,
+        }
+    }
+
+    /// Serialize the cached entries
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        let state = self.state.lock();
+        let mut entries: Vec<(&u64, &CacheEntry)> = state.entries.iter().collect();
+        entries.sort_by(|(_, a), (_, b)| {
+            a.priority
+                .total_cmp(&b.priority)
+                .then(a.last_used.cmp(&b.last_used))
+        });
+        let saved = SavedCache {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            entries: entries
+                .into_iter()
+                .map(|(key, entry)| SavedEntry {
+                    key: *key,
+                    tuples: entry.tuples.clone(),
+                    dependencies: entry.dependencies.clone(),
+                    cost_us: entry.cost_us,
+                })
+                .collect(),
+        };
+        // JSON, as `Value` only deserializes from self-describing formats
+        serde_json::to_vec(&saved).map_err(|e| format!("Failed to serialize subplan cache: {e}"))
+    }
+
+    /// Add the entries of a cache serialized by `to_bytes`, subject to this
+    /// cache's capacity. Returns the number of entries read, which is zero
+    /// when they were written by another build.
+    pub fn load_bytes(&self, bytes: &[u8]) -> Result<usize, String> {
+        let saved: SavedCache = serde_json::from_slice(bytes)
+            .map_err(|e| format!("Invalid subplan cache bytes: {e}"))?;
+        if saved.version != env!("CARGO_PKG_VERSION") {
+            return Ok(0);
+        }
+        let count = saved.entries.len();
+        for entry in saved.entries {
+            self.insert(entry.key, entry.tuples, entry.dependencies, entry.cost_us);
+        }
+        Ok(count)
+    }
+}
+
+impl Default for SubplanCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn tuples(n: i32) -> Vec<Tuple> {
+        (0..n).map(|i| Tuple::from_pair(i, 0)).collect()
+    }
+
+    #[test]
+    fn test_hit_and_miss() {
+        let cache = SubplanCache::new();
+        assert!(cache.get(1).is_none());
+
+        cache.insert(1, tuples(3), vec!["edge".to_string()], 0);
+        assert_eq!(cache.get(1).unwrap().len(), 3);
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.entries, 1);
+        assert_eq!(stats.cached_tuples, 3);
+    }
+
+    #[test]
+    fn test_invalidate_relation_drops_dependents_only() {
+        let cache = SubplanCache::new();
+        cache.insert(1, tuples(2), vec!["edge".to_string()], 0);
+        cache.insert(
+            2,
+            tuples(2),
+            vec!["edge".to_string(), "node".to_string()],
+            0,
+        );
+        cache.insert(3, tuples(2), vec!["node".to_string()], 0);
+
+        cache.invalidate_relation("edge");
+
+        assert!(cache.get(1).is_none());
+        assert!(cache.get(2).is_none());
+        assert!(cache.get(3).is_some());
+        assert_eq!(cache.stats().invalidations, 2);
+        assert_eq!(cache.stats().cached_tuples, 2);
+    }
+
+    #[test]
+    fn test_lru_eviction_by_entry_count() {
+        let cache = SubplanCache::with_capacity(2, 1000);
+        cache.insert(1, tuples(1), vec![], 0);
+        cache.insert(2, tuples(1), vec![], 0);
+        // Touch 1 so 2 becomes least recently used
+        assert!(cache.get(1).is_some());
+        cache.insert(3, tuples(1), vec![], 0);
+
+        assert!(cache.get(1).is_some());
+        assert!(cache.get(2).is_none());
+        assert!(cache.get(3).is_some());
+        assert_eq!(cache.stats().evictions, 1);
+    }
+
+    #[test]
+    fn test_eviction_by_tuple_budget() {
+        let cache = SubplanCache::with_capacity(10, 5);
+        cache.insert(1, tuples(3), vec![], 0);
+        cache.insert(2, tuples(3), vec![], 0);
+
+        assert!(cache.get(1).is_none());
+        assert!(cache.get(2).is_some());
+        assert_eq!(cache.stats().cached_tuples, 3);
+    }
+
+    #[test]
+    fn test_oversized_result_not_admitted() {
+        let cache = SubplanCache::with_capacity(10, 5);
+        cache.insert(1, tuples(6), vec![], 0);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_reinsert_replaces_entry() {
+        let cache = SubplanCache::new();
+        cache.insert(1, tuples(2), vec![], 0);
+        cache.insert(1, tuples(4), vec![], 0);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.stats().cached_tuples, 4);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let cache = SubplanCache::with_capacity(2, 1000);
+        cache.insert(1, tuples(2), vec!["edge".to_string()], 2_000);
+        cache.insert(2, tuples(3), vec![], 30);
+
+        let restored = SubplanCache::with_capacity(2, 1000);
+        assert_eq!(restored.load_bytes(&cache.to_bytes().unwrap()).unwrap(), 2);
+        assert_eq!(restored.get(1).unwrap().len(), 2);
+        assert_eq!(restored.get(2).unwrap().len(), 3);
+
+        // Costs survive: the cheap entry is still evicted first
+        let restored = SubplanCache::with_capacity(2, 1000);
+        restored.load_bytes(&cache.to_bytes().unwrap()).unwrap();
+        restored.insert(3, tuples(1), vec![], 100);
+        assert!(restored.get(1).is_some());
+        assert!(restored.get(2).is_none());
+
+        // Dependencies survive for invalidation
+        restored.invalidate_relation("edge");
+        assert!(restored.get(1).is_none());
+
+        assert!(restored.load_bytes(b"garbage").is_err());
+    }
+
+    #[test]
+    fn test_clear() {
+        let cache = SubplanCache::new();
+        cache.insert(1, tuples(2), vec![], 0);
+        cache.insert(2, tuples(2), vec![], 0);
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.stats().cached_tuples, 0);
+        assert_eq!(cache.stats().invalidations, 0);
+    }
+
+    #[test]
+    fn test_cheap_large_result_does_not_displace_expensive_small_ones() {
+        let cache = SubplanCache::with_capacity(10, 10);
+        for key in 0..5 {
+            cache.insert(key, tuples(1), vec![], 5_000);
+        }
+        // Costs 100us per tuple against 5000us for each cached tuple
+        cache.insert(9, tuples(8), vec![], 800);
+        assert!(cache.get(9).is_none());
+        assert_eq!(cache.len(), 5);
+        assert_eq!(cache.stats().rejections, 1);
+
+        // An expensive result displaces the cheapest entries
+        cache.insert(10, tuples(2), vec![], 5_000);
+        cache.insert(11, tuples(8), vec![], 80_000);
+        assert!(cache.get(11).is_some());
+        assert!(cache.get(10).is_none());
+        assert_eq!(cache.stats().cached_tuples, 10);
+    }
+
+    #[test]
+    fn test_entry_stats_report_cost_and_size() {
+        let cache = SubplanCache::new();
+        cache.insert(1, tuples(4), vec!["edge".to_string()], 2_000);
+        cache.insert(2, tuples(1), vec![], 2_000);
+        assert!(cache.get(1).is_some());
+
+        let entries = cache.stats().entry_stats;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].key, 1, "lowest priority first");
+        assert_eq!(entries[0].tuples, 4);
+        assert_eq!(entries[0].cost_us, 2_000);
+        assert_eq!(entries[0].hits, 1);
+        assert_eq!(entries[0].dependencies, vec!["edge".to_string()]);
+        assert!(entries[0].priority < entries[1].priority);
+    }
+}
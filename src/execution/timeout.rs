@@ -17,19 +17,53 @@ use std::time::{Duration, Instant};
 
 /// Timeout error
 #[derive(Debug, Clone, thiserror::Error)]
-#[error("Query exceeded timeout of {timeout:?} (ran for {elapsed:?})")]
+#[error("Query exceeded timeout of {timeout:?} (ran for {elapsed:?}{progress})")]
 pub struct TimeoutError {
     /// The timeout duration that was exceeded
     pub timeout: Duration,
     /// How long the query actually ran
     pub elapsed: Duration,
+    /// Work done before the query was stopped
+    pub progress: QueryProgress,
+}
+
+impl TimeoutError {
+    /// Attach the progress the query made before it timed out
+    #[must_use]
+    pub fn with_progress(mut self, progress: QueryProgress) -> Self {
+        self.progress = progress;
+        self
+    }
+}
+
+/// Partial progress of a query that was stopped early
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueryProgress {
+    /// Dataflow worker steps taken
+    pub steps: u64,
+    /// Result rows produced before the query was stopped
+    pub rows: usize,
+}
+
+impl std::fmt::Display for QueryProgress {
+    /// Rendered as a suffix of the timeout message; empty when nothing ran
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if *self == QueryProgress::default() {
+            return Ok(());
+        }
+        write!(
+            f,
+            "; {} dataflow steps, {} rows produced",
+            self.steps, self.rows
+        )
+    }
 }
 
 /// Query timeout controller
 ///
 /// Provides cooperative cancellation for long-running queries.
 /// The controller can be shared across threads and checked periodically.
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct QueryTimeout {
     /// Cancellation flag (shared across threads)
     cancelled: Arc<AtomicBool>,
@@ -67,6 +101,7 @@ impl QueryTimeout {
             return Err(TimeoutError {
                 timeout: self.timeout_duration.unwrap_or(Duration::ZERO),
                 elapsed: self.start_time.elapsed(),
+                progress: QueryProgress::default(),
             });
         }
 
@@ -75,7 +110,11 @@ impl QueryTimeout {
             let elapsed = self.start_time.elapsed();
             if elapsed > timeout {
                 self.cancelled.store(true, Ordering::Relaxed);
-                return Err(TimeoutError { timeout, elapsed });
+                return Err(TimeoutError {
+                    timeout,
+                    elapsed,
+                    progress: QueryProgress::default(),
+                });
             }
         }
 
@@ -131,12 +170,35 @@ impl Default for QueryTimeout {
 }
 
 /// Handle for cancelling a query from another thread
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct CancelHandle {
     cancelled: Arc<AtomicBool>,
 }
 
+impl Default for CancelHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl CancelHandle {
+    /// Create a handle that is not tied to a timeout controller
+    pub fn new() -> Self {
+        CancelHandle {
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Handle sharing an existing cancellation flag
+    pub(crate) fn from_flag(cancelled: Arc<AtomicBool>) -> Self {
+        CancelHandle { cancelled }
+    }
+
+    /// The shared cancellation flag
+    pub(crate) fn flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.cancelled)
+    }
+
     /// Cancel the associated query
     pub fn cancel(&self) {
         self.cancelled.store(true, Ordering::Relaxed);
@@ -251,6 +313,15 @@ mod tests {
         assert!(e2 > e1);
     }
 
+    #[test]
+    fn test_standalone_cancel_handle() {
+        let handle = CancelHandle::default();
+        let shared = CancelHandle::from_flag(handle.flag());
+        assert!(!shared.is_cancelled());
+        handle.cancel();
+        assert!(shared.is_cancelled());
+    }
+
     #[test]
     fn test_cancel_handle_clone() {
         let timeout = QueryTimeout::new(Some(Duration::from_secs(10)));
@@ -280,12 +351,29 @@ mod tests {
         let err = TimeoutError {
             timeout: Duration::from_secs(5),
             elapsed: Duration::from_secs(6),
+            progress: QueryProgress::default(),
         };
         let msg = err.to_string();
         assert!(msg.contains("5"));
         assert!(msg.contains("6"));
     }
 
+    #[test]
+    fn test_timeout_error_reports_progress() {
+        let err = TimeoutError {
+            timeout: Duration::from_secs(1),
+            elapsed: Duration::from_secs(2),
+            progress: QueryProgress::default(),
+        }
+        .with_progress(QueryProgress {
+            steps: 1200,
+            rows: 35,
+        });
+        let msg = err.to_string();
+        assert!(msg.contains("1200 dataflow steps"), "{msg}");
+        assert!(msg.contains("35 rows produced"), "{msg}");
+    }
+
     #[test]
     fn test_remaining_after_timeout() {
         let timeout = QueryTimeout::new(Some(Duration::from_millis(50)));
@@ -38,6 +38,10 @@ pub struct TimingBreakdown {
     pub optimize_us: u64,
     /// Shared views (CSE) execution time (us)
     pub shared_views_us: u64,
+    /// Most bytes the query held at once in dataflow arrangements and
+    /// derived relations
+    #[serde(default)]
+    pub peak_memory_bytes: u64,
     /// Per-rule execution timings (only in Detailed mode)
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub rules: Vec<RuleTiming>,
@@ -144,7 +148,8 @@ impl TimingCollector {
         (result, elapsed_us)
     }
 
-    /// Record a rule execution timing (only in Detailed mode).
+    /// Record a rule execution timing (only in Detailed mode). The step is
+    /// also recorded by an installed plan capture, whatever the mode.
     pub fn record_rule(
         &mut self,
         head: String,
@@ -152,6 +157,7 @@ impl TimingCollector {
         is_recursive: bool,
         workers: usize,
     ) {
+        super::plan_capture::record_step(&head, execution_us, is_recursive);
         if self.mode == TimingMode::Detailed {
             self.breakdown.rules.push(RuleTiming {
                 rule_head: head,
@@ -177,73 +183,10 @@ impl TimingCollector {
 }
 
 // ---------------------------------------------------------------------------
-// Prometheus histogram buckets for query timing stages
+// Prometheus histograms for query timing stages
 // ---------------------------------------------------------------------------
 
-use std::sync::atomic::{AtomicU64, Ordering};
-
-/// Fixed bucket boundaries in seconds for Prometheus histograms.
-const BUCKET_BOUNDS: [f64; 8] = [0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
-
-/// Number of buckets including the implicit +Inf bucket.
-const NUM_BUCKETS: usize = BUCKET_BOUNDS.len() + 1; // 9
-
-/// A single Prometheus-style histogram with fixed buckets.
-///
-/// Each bucket counter tracks cumulative observations <= that boundary.
-/// The last bucket is +Inf (all observations).
-struct Histogram {
-    /// Cumulative bucket counters. Index `i` counts observations <= BUCKET_BOUNDS[i].
-    /// The last index counts all observations (+Inf).
-    buckets: [AtomicU64; NUM_BUCKETS],
-    /// Sum of all observed values (stored as integer microseconds).
-    sum_us: AtomicU64,
-    /// Total number of observations.
-    count: AtomicU64,
-}
-
-impl Histogram {
-    fn new() -> Self {
-        Self {
-            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
-            sum_us: AtomicU64::new(0),
-            count: AtomicU64::new(0),
-        }
-    }
-
-    /// Record a duration in microseconds.
-    fn record_us(&self, us: u64) {
-        let secs = us as f64 / 1_000_000.0;
-        // Increment all cumulative buckets where the value fits.
-        for (i, &bound) in BUCKET_BOUNDS.iter().enumerate() {
-            if secs <= bound {
-                self.buckets[i].fetch_add(1, Ordering::Relaxed);
-            }
-        }
-        // +Inf bucket always gets incremented.
-        self.buckets[NUM_BUCKETS - 1].fetch_add(1, Ordering::Relaxed);
-        self.sum_us.fetch_add(us, Ordering::Relaxed);
-        self.count.fetch_add(1, Ordering::Relaxed);
-    }
-
-    /// Format this histogram as Prometheus text exposition lines.
-    /// `name` is the full metric name (e.g. `inputlayer_query_parse_seconds`).
-    fn format_prometheus(&self, name: &str, help: &str, out: &mut String) {
-        use std::fmt::Write;
-        let _ = writeln!(out, "# HELP {name} {help}");
-        let _ = writeln!(out, "# TYPE {name} histogram");
-        for (i, &bound) in BUCKET_BOUNDS.iter().enumerate() {
-            let cumulative = self.buckets[i].load(Ordering::Relaxed);
-            let _ = writeln!(out, "{name}_bucket{{le=\"{bound}\"}} {cumulative}");
-        }
-        let inf_count = self.buckets[NUM_BUCKETS - 1].load(Ordering::Relaxed);
-        let _ = writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {inf_count}");
-        let sum_secs = self.sum_us.load(Ordering::Relaxed) as f64 / 1_000_000.0;
-        let count = self.count.load(Ordering::Relaxed);
-        let _ = writeln!(out, "{name}_sum {sum_secs}");
-        let _ = writeln!(out, "{name}_count {count}");
-    }
-}
+use crate::metrics::{Histogram, LATENCY_BUCKETS};
 
 /// Accumulated timing histograms for Prometheus export.
 ///
@@ -260,10 +203,10 @@ impl TimingHistograms {
     /// Create a new set of empty histograms.
     pub fn new() -> Self {
         Self {
-            parse: Histogram::new(),
-            optimize: Histogram::new(),
-            execute: Histogram::new(),
-            total: Histogram::new(),
+            parse: Histogram::new(&LATENCY_BUCKETS),
+            optimize: Histogram::new(&LATENCY_BUCKETS),
+            execute: Histogram::new(&LATENCY_BUCKETS),
+            total: Histogram::new(&LATENCY_BUCKETS),
         }
     }
 
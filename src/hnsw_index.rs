@@ -9,6 +9,12 @@
 //! - Cosine: Normalized dot product (1 - similarity)
 //! - Euclidean (L2): Standard Euclidean distance
 //! - Dot Product: Inner product (negated for distance)
+//!
+//! ## Maintenance
+//!
+//! New vectors are linked into the existing graph as they are inserted.
+//! Deletes leave tombstones that searches skip; the graph is rebuilt when
+//! a vector is replaced or tombstones pass 30% of the entries.
 
 use crate::index_manager::{DistanceMetric, HnswConfig, Index, TupleId};
 use hnsw_rs::hnsw::Hnsw;
@@ -44,6 +50,9 @@ struct HnswInnerOwned {
     hnsw: Box<Hnsw<'static, f32, DistL2>>,
     /// Stored vectors that the HNSW references
     _storage: Arc<Vec<Vec<f32>>>,
+    /// Vectors linked into the graph after it was built, kept alive for it
+    /// like `_storage`
+    appended: Vec<Arc<Vec<f32>>>,
     /// Mapping from HNSW internal index to tuple_id
     index_to_tuple_id: Vec<TupleId>,
 }
@@ -137,6 +146,7 @@ impl HnswIndex {
         *self.inner.write() = Some(HnswInnerOwned {
             hnsw: Box::new(hnsw),
             _storage: storage,
+            appended: Vec::new(),
             index_to_tuple_id,
         });
 
@@ -219,15 +229,25 @@ impl Index for HnswIndex {
 
         // For Manhattan, request more candidates since L2 ordering != L1 ordering.
         // Reranking from a larger candidate set improves recall.
-        let search_k = if is_manhattan { k * 4 } else { k };
-        let raw_results = inner.hnsw.search(&prepared_query, search_k, ef_search);
+        // Deleted entries stay in the graph until compaction, so ask for
+        // enough extra candidates to cover them.
+        let tombstones = self.tombstones.read();
+        let search_k = if is_manhattan { k * 4 } else { k } + tombstones.len();
+        let raw_results = inner
+            .hnsw
+            .search(&prepared_query, search_k, ef_search.max(search_k));
+        let raw_results = raw_results.into_iter().filter(|neighbour| {
+            inner
+                .index_to_tuple_id
+                .get(neighbour.d_id)
+                .is_some_and(|id| !tombstones.contains(id))
+        });
 
         // Map internal indices to tuple IDs using the stored mapping
         let mut results: Vec<(TupleId, f64)> = if is_manhattan {
             // Recompute actual L1 distance from stored vectors
             let vectors = self.vectors.read();
             raw_results
-                .into_iter()
                 .filter_map(|neighbour| {
                     let internal_idx = neighbour.d_id;
                     if internal_idx < inner.index_to_tuple_id.len() {
@@ -248,7 +268,6 @@ impl Index for HnswIndex {
                 .collect()
         } else {
             raw_results
-                .into_iter()
                 .filter_map(|neighbour| {
                     let internal_idx = neighbour.d_id;
                     if internal_idx < inner.index_to_tuple_id.len() {
@@ -303,25 +322,43 @@ impl Index for HnswIndex {
         }
 
         // Check for duplicate ID and update in place if found
+        let prepared = self.prepare_vector(vector);
         {
             let mut vectors = self.vectors.write();
             if let Some(pos) = vectors
                 .iter()
                 .position(|(existing_id, _)| *existing_id == id)
             {
-                let prepared = self.prepare_vector(vector);
                 vectors[pos] = (id, prepared);
-            } else {
-                let prepared = self.prepare_vector(vector);
-                vectors.push((id, prepared));
+                drop(vectors);
+                // The graph still holds the old vector: rebuild without it
+                self.tombstones.write().remove(&id);
+                return self.rebuild_hnsw();
             }
+            vectors.push((id, prepared.clone()));
         }
 
-        // Rebuild HNSW structure
-        // Note: For better performance, we could batch inserts and rebuild less frequently
-        self.rebuild_hnsw()?;
-
-        Ok(())
+        // New IDs are linked into the existing graph; only the first
+        // insert has to build it
+        let mut inner = self.inner.write();
+        match inner.as_mut() {
+            Some(inner) => {
+                let internal_idx = inner.index_to_tuple_id.len();
+                let stored = Arc::new(prepared);
+                // SAFETY: as in rebuild_hnsw(): the Arc is kept in `appended`,
+                // which is dropped after the graph holding this reference
+                let stored_ref: &'static Vec<f32> =
+                    unsafe { &*Arc::as_ptr(&stored).cast::<Vec<f32>>() };
+                inner.hnsw.insert((stored_ref, internal_idx));
+                inner.appended.push(stored);
+                inner.index_to_tuple_id.push(id);
+                Ok(())
+            }
+            None => {
+                drop(inner);
+                self.rebuild_hnsw()
+            }
+        }
     }
 
     fn insert_batch(&mut self, entries: &[(TupleId, Vec<f32>)]) -> Result<(), String> {
@@ -656,6 +693,31 @@ mod tests {
         assert!(results.iter().all(|(id, _)| *id != 0));
     }
 
+    #[test]
+    fn test_hnsw_incremental_insert_and_delete() {
+        let mut index = HnswIndex::new(make_config(DistanceMetric::Euclidean));
+        for i in 0..20 {
+            index.insert(i, &[i as f32, 0.0]).unwrap();
+        }
+        // Later inserts are linked into the graph built by the first one
+        index.insert(100, &[5.4, 0.0]).unwrap();
+        let results = index.search(&[5.5, 0.0], 1, None);
+        assert_eq!(results[0].0, 100);
+
+        // Deleted entries are not returned, even before compaction
+        index.delete(100);
+        assert_eq!(index.tombstone_count(), 1);
+        let results = index.search(&[5.5, 0.0], 2, None);
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|(id, _)| *id != 100));
+
+        // Re-inserting a deleted ID makes it visible again
+        index.insert(100, &[5.6, 0.0]).unwrap();
+        assert_eq!(index.tombstone_count(), 0);
+        let results = index.search(&[5.5, 0.0], 1, None);
+        assert_eq!(results[0].0, 100);
+    }
+
     #[test]
     fn test_hnsw_rebuild() {
         let mut index = HnswIndex::new(make_config(DistanceMetric::Euclidean));
@@ -11,25 +11,56 @@
 //! Main thread --command_tx--► Worker thread (timely::execute_directly)
 //!                              ├─ InputSessions (one per base relation)
 //!                              ├─ Arrangements (queryable via cursor)
+//!                              ├─ Views (persistent dataflows over arrangements)
 //!                              ├─ DerivedRelationsManager (rule tracking)
 //!                              ├─ IndexManager (HNSW indexes)
 //!                              └─ Command loop (blocking recv + batch)
 //! ```
 //!
+//! ## Incremental Views
+//!
+//! `install_view` compiles an IR plan into a dataflow that stays resident
+//! on the worker. The dataflow reads the arrangements of base relations (and
+//! of other views), so inserts and deletes flow through it as differences
+//! when time advances instead of triggering a recomputation.
+//!
+//! `subscribe` streams a view's changes as `Delta`s: its current contents
+//! first, then every insertion and retraction as base data changes. While a
+//! subscription is open, writes advance time immediately so changes are
+//! pushed without waiting for a read.
+//!
 //! ## Thread Safety
 //!
 //! InputSessions and TraceAgents are NOT Send/Sync (Rc-based internally).
 //! All DD state lives on the worker thread. The main thread communicates
 //! exclusively through the command channel.
 
+use crate::code_generator::CodeGenerator;
 use crate::derived_relations::{CompiledRule, DerivedRelationsManager};
 use crate::index_manager::{Index, IndexManager, IndexStats, RegisteredIndex, TupleId};
+use crate::ir::IRNode;
 use crate::value::Tuple;
 use crossbeam_channel as channel;
 use parking_lot::Mutex;
 use std::collections::{HashMap, HashSet};
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+/// A change to a view: `diff` copies of `data` were added (positive) or
+/// retracted (negative) at logical time `time`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delta<T> {
+    pub data: T,
+    pub time: u64,
+    pub diff: isize,
+}
+
+/// Subscribers of one view: the time from which each receives updates
+/// (earlier updates are covered by its snapshot), and its sink.
+type ViewSinks = std::rc::Rc<std::cell::RefCell<Vec<(u64, mpsc::UnboundedSender<Delta<Tuple>>)>>>;
 
 /// Commands sent from the main thread to the worker thread.
 enum EngineCommand {
@@ -81,6 +112,26 @@ enum EngineCommand {
         response: channel::Sender<(usize, usize, usize)>,
     },
 
+    // === Incremental Views ===
+    InstallView {
+        name: String,
+        plan: IRNode,
+        response: channel::Sender<Result<(), String>>,
+    },
+    DropView {
+        name: String,
+        response: channel::Sender<bool>,
+    },
+    ReadView {
+        name: String,
+        response: channel::Sender<Option<Vec<Tuple>>>,
+    },
+    Subscribe {
+        view: String,
+        sink: mpsc::UnboundedSender<Delta<Tuple>>,
+        response: channel::Sender<Result<(), String>>,
+    },
+
     // === Index Management ===
     RegisterIndex {
         index: RegisteredIndex,
@@ -123,6 +174,9 @@ pub struct IncrementalEngine {
     current_time: Arc<AtomicU64>,
     max_write_time: Arc<AtomicU64>,
     known_relations: Mutex<HashSet<String>>,
+    views: Arc<Mutex<HashSet<String>>>,
+    subscriptions: Arc<AtomicUsize>,
+    next_subscription: AtomicU64,
     derived_relations: Arc<Mutex<DerivedRelationsManager>>,
     index_manager: Arc<Mutex<IndexManager>>,
 }
@@ -154,6 +208,9 @@ impl IncrementalEngine {
             current_time,
             max_write_time,
             known_relations,
+            views: Arc::new(Mutex::new(HashSet::new())),
+            subscriptions: Arc::new(AtomicUsize::new(0)),
+            next_subscription: AtomicU64::new(0),
             derived_relations,
             index_manager,
         })
@@ -162,7 +219,8 @@ impl IncrementalEngine {
     /// The worker thread's main loop.
     ///
     /// Creates a timely computation with u64 timestamps, InputSessions for
-    /// each relation, and arrangements. Processes commands via blocking recv.
+    /// each relation, and arrangements. Processes commands via blocking recv,
+    /// stepping the computation in between while it lags behind the inputs.
     fn worker_loop(
         relations: Vec<String>,
         command_rx: channel::Receiver<EngineCommand>,
@@ -172,11 +230,14 @@ impl IncrementalEngine {
         use differential_dataflow::input::Input;
         use differential_dataflow::trace::cursor::Cursor;
         use differential_dataflow::trace::TraceReader;
-        use timely::dataflow::operators::Probe;
+        use timely::dataflow::operators::{Inspect, Probe};
         use timely::dataflow::ProbeHandle;
+        use timely::progress::Antichain;
 
         timely::execute_directly(move |worker| {
             let probe = ProbeHandle::<u64>::new();
+            // Time the input sessions have been advanced to
+            let mut input_time: u64 = 0;
 
             let mut input_sessions: HashMap<
                 String,
@@ -190,6 +251,26 @@ impl IncrementalEngine {
             >;
             type KeyTraceAgent = differential_dataflow::operators::arrange::TraceAgent<KeyTrace>;
             let mut traces: HashMap<String, KeyTraceAgent> = HashMap::new();
+            let mut view_traces: HashMap<String, KeyTraceAgent> = HashMap::new();
+            let mut view_sinks: HashMap<String, ViewSinks> = HashMap::new();
+
+            // Tuples with a positive accumulated count
+            fn read_trace(trace: &mut KeyTraceAgent) -> Vec<Tuple> {
+                let mut result = Vec::new();
+                let (mut cursor, storage) = trace.cursor();
+                while cursor.key_valid(&storage) {
+                    let key = cursor.key(&storage).clone();
+                    let mut total_diff: isize = 0;
+                    cursor.map_times(&storage, |_time, diff| {
+                        total_diff += *diff;
+                    });
+                    if total_diff > 0 {
+                        result.push(key);
+                    }
+                    cursor.step_key(&storage);
+                }
+                result
+            }
 
             worker.dataflow::<u64, _, _>(|scope| {
                 for relation in &relations {
@@ -201,11 +282,22 @@ impl IncrementalEngine {
                 }
             });
 
-            // Command processing loop: blocking recv + batch drain
+            // Command processing loop: blocking recv + batch drain. While the
+            // computation lags behind the inputs, keep stepping it so views
+            // and subscribers see updates without waiting for a read.
             loop {
-                let first_cmd = match command_rx.recv() {
-                    Ok(cmd) => cmd,
-                    Err(_) => return, // channel disconnected
+                let first_cmd = if probe.less_than(&input_time) {
+                    worker.step();
+                    match command_rx.try_recv() {
+                        Ok(cmd) => cmd,
+                        Err(channel::TryRecvError::Empty) => continue,
+                        Err(channel::TryRecvError::Disconnected) => return,
+                    }
+                } else {
+                    match command_rx.recv() {
+                        Ok(cmd) => cmd,
+                        Err(_) => return, // channel disconnected
+                    }
                 };
 
                 let mut commands = vec![first_cmd];
@@ -224,6 +316,7 @@ impl IncrementalEngine {
                         }
 
                         EngineCommand::AdvanceTime(time) => {
+                            input_time = input_time.max(time);
                             for session in input_sessions.values_mut() {
                                 session.advance_to(time);
                                 session.flush();
@@ -243,22 +336,8 @@ impl IncrementalEngine {
                         }
 
                         EngineCommand::ReadRelation { relation, response } => {
-                            let mut result = Vec::new();
-                            if let Some(trace) = traces.get_mut(&relation) {
-                                let (mut cursor, storage) = trace.cursor();
-                                while cursor.key_valid(&storage) {
-                                    let key = cursor.key(&storage).clone();
-                                    let mut total_diff: isize = 0;
-                                    cursor.map_times(&storage, |_time, diff| {
-                                        total_diff += *diff;
-                                    });
-                                    if total_diff > 0 {
-                                        result.push(key);
-                                    }
-                                    cursor.step_key(&storage);
-                                }
-                            }
-                            let _ = response.send(result);
+                            let result = traces.get_mut(&relation).map(read_trace);
+                            let _ = response.send(result.unwrap_or_default());
                         }
 
                         EngineCommand::AddRelation { name, response } => {
@@ -279,6 +358,8 @@ impl IncrementalEngine {
                             // Drop sessions without stepping to avoid merge batcher issues
                             input_sessions.clear();
                             traces.clear();
+                            view_traces.clear();
+                            view_sinks.clear();
                             let _ = response.send(());
                             return;
                         }
@@ -328,6 +409,130 @@ impl IncrementalEngine {
                             ));
                         }
 
+                        // === Incremental Views ===
+                        EngineCommand::InstallView {
+                            name,
+                            plan,
+                            response,
+                        } => {
+                            let mut scans = Vec::new();
+                            CodeGenerator::collect_scans(&plan, &mut scans);
+                            let missing = scans
+                                .iter()
+                                .find(|relation| {
+                                    !traces.contains_key(*relation)
+                                        && !view_traces.contains_key(*relation)
+                                })
+                                .cloned();
+
+                            let result = if traces.contains_key(&name)
+                                || view_traces.contains_key(&name)
+                            {
+                                Err(format!("Relation '{name}' already exists"))
+                            } else if let Some(relation) = missing {
+                                Err(format!("View '{name}' reads unknown relation '{relation}'"))
+                            } else {
+                                worker.dataflow::<u64, _, _>(|scope| {
+                                    // Import the arrangements the plan reads, so
+                                    // the view sees their full history and all
+                                    // later updates
+                                    let mut live = HashMap::new();
+                                    for relation in scans {
+                                        if live.contains_key(&relation) {
+                                            continue;
+                                        }
+                                        let trace = view_traces
+                                            .get_mut(&relation)
+                                            .or(traces.get_mut(&relation));
+                                        if let Some(trace) = trace {
+                                            let collection = trace.import(scope).as_collection(
+                                                |tuple: &Tuple, (): &()| tuple.clone(),
+                                            );
+                                            live.insert(relation, collection);
+                                        }
+                                    }
+
+                                    let view =
+                                        CodeGenerator::generate_collection_tuples::<_, isize>(
+                                            scope,
+                                            &plan,
+                                            &HashMap::new(),
+                                            Some(&live),
+                                        )
+                                        .distinct_core::<isize>();
+                                    let arranged = view.arrange_by_self();
+
+                                    // Forward changes to subscribers. Reading from
+                                    // the arrangement means a change is either in
+                                    // the trace a snapshot reads, or at a time at
+                                    // or after the trace's upper bound.
+                                    let sinks = ViewSinks::default();
+                                    let forward = std::rc::Rc::clone(&sinks);
+                                    let trace = arranged.trace.clone();
+                                    arranged
+                                        .as_collection(|tuple: &Tuple, (): &()| tuple.clone())
+                                        .inner
+                                        .inspect(move |(data, time, diff)| {
+                                            forward.borrow_mut().retain(|(since, sink)| {
+                                                *time < *since
+                                                    || sink
+                                                        .send(Delta {
+                                                            data: data.clone(),
+                                                            time: *time,
+                                                            diff: *diff,
+                                                        })
+                                                        .is_ok()
+                                            });
+                                        })
+                                        .probe_with(&probe);
+
+                                    view_traces.insert(name.clone(), trace);
+                                    view_sinks.insert(name.clone(), sinks);
+                                });
+                                Ok(())
+                            };
+                            let _ = response.send(result);
+                        }
+
+                        EngineCommand::DropView { name, response } => {
+                            // Dropping the sinks ends the view's subscriptions
+                            view_sinks.remove(&name);
+                            let _ = response.send(view_traces.remove(&name).is_some());
+                        }
+
+                        EngineCommand::ReadView { name, response } => {
+                            let result = view_traces.get_mut(&name).map(read_trace);
+                            let _ = response.send(result);
+                        }
+
+                        EngineCommand::Subscribe {
+                            view,
+                            sink,
+                            response,
+                        } => {
+                            let result = match (view_traces.get_mut(&view), view_sinks.get(&view)) {
+                                (Some(trace), Some(sinks)) => {
+                                    // Snapshot everything the trace holds, then
+                                    // forward changes from its upper bound on
+                                    let mut upper = Antichain::new();
+                                    trace.read_upper(&mut upper);
+                                    let since =
+                                        upper.elements().first().copied().unwrap_or(u64::MAX);
+                                    for data in read_trace(trace) {
+                                        let _ = sink.send(Delta {
+                                            data,
+                                            time: since,
+                                            diff: 1,
+                                        });
+                                    }
+                                    sinks.borrow_mut().push((since, sink));
+                                    Ok(())
+                                }
+                                _ => Err(format!("View '{view}' not found")),
+                            };
+                            let _ = response.send(result);
+                        }
+
                         // === Index Management ===
                         EngineCommand::RegisterIndex { index, response } => {
                             let mut mgr = index_manager.lock();
@@ -369,18 +574,7 @@ impl IncrementalEngine {
                         } => {
                             let mut mgr = index_manager.lock();
                             let result = if let Some(mat) = mgr.get_materialized_mut(&name) {
-                                for id in deletes {
-                                    mat.index.delete(id);
-                                }
-                                let mut insert_result = Ok(());
-                                for (id, vector) in inserts {
-                                    if let Err(e) = mat.index.insert(id, &vector) {
-                                        insert_result = Err(e);
-                                        break;
-                                    }
-                                }
-                                mat.tuple_count = mat.index.len();
-                                insert_result
+                                mat.apply(&inserts, &deletes)
                             } else {
                                 Err(format!("Index '{name}' not found or invalid"))
                             };
@@ -410,7 +604,8 @@ impl IncrementalEngine {
                 relation: relation.to_string(),
                 updates,
             })
-            .map_err(|_| "Worker disconnected".to_string())
+            .map_err(|_| "Worker disconnected".to_string())?;
+        self.publish_to_subscribers()
     }
 
     /// Delete tuples from a base relation at the given logical time.
@@ -423,7 +618,21 @@ impl IncrementalEngine {
                 relation: relation.to_string(),
                 updates,
             })
-            .map_err(|_| "Worker disconnected".to_string())
+            .map_err(|_| "Worker disconnected".to_string())?;
+        self.publish_to_subscribers()
+    }
+
+    /// With subscriptions open, advance time past all writes so their
+    /// changes are pushed right away rather than at the next consistent read.
+    fn publish_to_subscribers(&self) -> Result<(), String> {
+        if self.subscriptions.load(Ordering::SeqCst) == 0 {
+            return Ok(());
+        }
+        let target = self.max_write_time.load(Ordering::SeqCst) + 1;
+        if target > self.current_time() {
+            self.advance_time(target)?;
+        }
+        Ok(())
     }
 
     /// Advance the logical time and flush all InputSessions.
@@ -582,6 +791,188 @@ impl IncrementalEngine {
         Arc::clone(&self.derived_relations)
     }
 
+    // === Incremental Views API ===
+
+    /// Install a view maintained by a persistent dataflow.
+    ///
+    /// `plan` may read base relations and previously installed views. Base
+    /// relations it reads are created if missing. The view reflects every
+    /// update through a time once `wait_until_caught_up` for that time
+    /// returns.
+    pub fn install_view(&self, name: &str, plan: IRNode) -> Result<(), String> {
+        check_view_plan(&plan)?;
+        let mut scans = Vec::new();
+        CodeGenerator::collect_scans(&plan, &mut scans);
+        for relation in &scans {
+            if !self.has_view(relation) {
+                self.ensure_relation(relation)?;
+            }
+        }
+
+        let (tx, rx) = channel::bounded(1);
+        self.command_tx
+            .send(EngineCommand::InstallView {
+                name: name.to_string(),
+                plan,
+                response: tx,
+            })
+            .map_err(|_| "Worker disconnected".to_string())?;
+        rx.recv()
+            .map_err(|_| "Worker disconnected while installing view".to_string())??;
+        self.views.lock().insert(name.to_string());
+        Ok(())
+    }
+
+    /// Install a set of views (e.g. from `IQLEngine::compile_views`), each
+    /// after the views it reads.
+    pub fn install_views(&self, views: Vec<(String, IRNode)>) -> Result<(), String> {
+        let names: HashSet<String> = views.iter().map(|(name, _)| name.clone()).collect();
+        let mut pending = views;
+        while !pending.is_empty() {
+            let (ready, blocked): (Vec<_>, Vec<_>) = pending.into_iter().partition(|(_, plan)| {
+                let mut scans = Vec::new();
+                CodeGenerator::collect_scans(plan, &mut scans);
+                scans
+                    .iter()
+                    .all(|relation| !names.contains(relation) || self.has_view(relation))
+            });
+            if ready.is_empty() {
+                let blocked: Vec<&str> = blocked.iter().map(|(name, _)| name.as_str()).collect();
+                return Err(format!(
+                    "Views depend on each other cyclically: {}",
+                    blocked.join(", ")
+                ));
+            }
+            for (name, plan) in ready {
+                self.install_view(&name, plan)?;
+            }
+            pending = blocked;
+        }
+        Ok(())
+    }
+
+    /// Read the current contents of a view, or `None` if it is not installed.
+    pub fn read_view(&self, name: &str) -> Result<Option<Vec<Tuple>>, String> {
+        let (tx, rx) = channel::bounded(1);
+        self.command_tx
+            .send(EngineCommand::ReadView {
+                name: name.to_string(),
+                response: tx,
+            })
+            .map_err(|_| "Worker disconnected".to_string())?;
+        rx.recv()
+            .map_err(|_| "Worker disconnected while reading view".to_string())
+    }
+
+    /// Read a view after advancing time past all writes, like
+    /// `read_relation_consistent`.
+    pub fn read_view_consistent(&self, name: &str) -> Result<Option<Vec<Tuple>>, String> {
+        let target = self.max_write_time.load(Ordering::SeqCst) + 1;
+        self.advance_time(target)?;
+        self.wait_until_caught_up(target)?;
+        self.read_view(name)
+    }
+
+    /// Stop serving a view. Returns whether it was installed.
+    ///
+    /// Views installed on top of it keep their own copy of its arrangement.
+    pub fn drop_view(&self, name: &str) -> Result<bool, String> {
+        let (tx, rx) = channel::bounded(1);
+        self.command_tx
+            .send(EngineCommand::DropView {
+                name: name.to_string(),
+                response: tx,
+            })
+            .map_err(|_| "Worker disconnected".to_string())?;
+        let dropped = rx
+            .recv()
+            .map_err(|_| "Worker disconnected while dropping view".to_string())?;
+        self.views.lock().remove(name);
+        Ok(dropped)
+    }
+
+    /// Check if a view is installed.
+    pub fn has_view(&self, name: &str) -> bool {
+        self.views.lock().contains(name)
+    }
+
+    /// Subscribe to the changes of an installed view.
+    ///
+    /// The stream yields the view's current contents as insertions, then
+    /// each insertion and retraction as base relations change. It ends when
+    /// the view is dropped or the engine shuts down.
+    pub fn subscribe(&self, view: &str) -> Result<ViewSubscription, String> {
+        self.subscribe_owning(view, Vec::new())
+    }
+
+    /// Install the views of a compiled query and subscribe to the last one
+    /// (the query's own head, as ordered by `IQLEngine::compile_views`).
+    ///
+    /// The views are installed under private names, so the same query can
+    /// be subscribed to many times, and are dropped with the subscription.
+    pub fn subscribe_query(
+        &self,
+        views: Vec<(String, IRNode)>,
+    ) -> Result<ViewSubscription, String> {
+        let query = views
+            .last()
+            .map(|(name, _)| name.clone())
+            .ok_or("Query defines no views")?;
+
+        let id = self.next_subscription.fetch_add(1, Ordering::SeqCst);
+        let renames: HashMap<String, String> = views
+            .iter()
+            .map(|(name, _)| (name.clone(), format!("__sub{id}_{name}")))
+            .collect();
+        let views: Vec<(String, IRNode)> = views
+            .into_iter()
+            .map(|(name, mut plan)| {
+                rename_scans(&mut plan, &renames);
+                (renames[&name].clone(), plan)
+            })
+            .collect();
+        let owned: Vec<String> = views.iter().map(|(name, _)| name.clone()).collect();
+
+        if let Err(e) = self.install_views(views) {
+            for name in owned.iter().rev() {
+                let _ = self.drop_view(name);
+            }
+            return Err(e);
+        }
+        self.subscribe_owning(&renames[&query], owned)
+    }
+
+    fn subscribe_owning(
+        &self,
+        view: &str,
+        owned_views: Vec<String>,
+    ) -> Result<ViewSubscription, String> {
+        let (sink, receiver) = mpsc::unbounded_channel();
+        let (tx, rx) = channel::bounded(1);
+        self.command_tx
+            .send(EngineCommand::Subscribe {
+                view: view.to_string(),
+                sink,
+                response: tx,
+            })
+            .map_err(|_| "Worker disconnected".to_string())?;
+        rx.recv()
+            .map_err(|_| "Worker disconnected while subscribing".to_string())??;
+
+        self.subscriptions.fetch_add(1, Ordering::SeqCst);
+        let subscription = ViewSubscription {
+            view: view.to_string(),
+            receiver,
+            command_tx: self.command_tx.clone(),
+            views: Arc::clone(&self.views),
+            subscriptions: Arc::clone(&self.subscriptions),
+            owned_views,
+        };
+        // Push writes that are still waiting for a time advance
+        self.publish_to_subscribers()?;
+        Ok(subscription)
+    }
+
     // === Index Management API ===
 
     /// Register a new index (metadata only, does not build).
@@ -676,6 +1067,25 @@ impl IncrementalEngine {
             .map_err(|_| "Worker disconnected while notifying indexes".to_string())
     }
 
+    /// Build a registered index from the current tuples of its relation.
+    /// Returns the number of vectors indexed.
+    pub fn build_index(&self, name: &str, tuples: &[Tuple]) -> Result<usize, String> {
+        self.index_manager.lock().build_index(name, tuples)
+    }
+
+    /// Apply inserted and deleted base tuples to the indexes on a relation.
+    /// Returns the names of indexes that had to be invalidated.
+    pub fn update_indexes(
+        &self,
+        relation: &str,
+        inserted: &[Tuple],
+        deleted: &[Tuple],
+    ) -> Vec<String> {
+        self.index_manager
+            .lock()
+            .apply_base_changes(relation, inserted, deleted)
+    }
+
     /// Check if an index exists.
     pub fn has_index(&self, name: &str) -> bool {
         self.index_manager.lock().has_index(name)
@@ -702,6 +1112,108 @@ impl IncrementalEngine {
     }
 }
 
+/// A stream of changes to a view, created by `IncrementalEngine::subscribe`.
+pub struct ViewSubscription {
+    view: String,
+    receiver: mpsc::UnboundedReceiver<Delta<Tuple>>,
+    command_tx: channel::Sender<EngineCommand>,
+    views: Arc<Mutex<HashSet<String>>>,
+    subscriptions: Arc<AtomicUsize>,
+    /// Views installed for this subscription, dropped with it
+    owned_views: Vec<String>,
+}
+
+impl ViewSubscription {
+    /// Name of the subscribed view.
+    pub fn view(&self) -> &str {
+        &self.view
+    }
+
+    /// Take the next change if one is ready, without waiting.
+    pub fn try_next(&mut self) -> Option<Delta<Tuple>> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl futures_util::Stream for ViewSubscription {
+    type Item = Delta<Tuple>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().receiver.poll_recv(cx)
+    }
+}
+
+impl Drop for ViewSubscription {
+    fn drop(&mut self) {
+        self.subscriptions.fetch_sub(1, Ordering::SeqCst);
+        for name in self.owned_views.iter().rev() {
+            self.views.lock().remove(name);
+            // Nobody waits for the reply; don't block if the worker is gone
+            let (tx, _rx) = channel::bounded(1);
+            let _ = self.command_tx.try_send(EngineCommand::DropView {
+                name: name.clone(),
+                response: tx,
+            });
+        }
+    }
+}
+
+/// Point scans of renamed relations at their new names.
+fn rename_scans(plan: &mut IRNode, renames: &HashMap<String, String>) {
+    match plan {
+        IRNode::Scan { relation, .. } => {
+            if let Some(renamed) = renames.get(relation) {
+                relation.clone_from(renamed);
+            }
+        }
+        IRNode::HnswScan { .. } => {}
+        IRNode::Map { input, .. }
+        | IRNode::Filter { input, .. }
+        | IRNode::Distinct { input }
+        | IRNode::Sort { input, .. }
+        | IRNode::Aggregate { input, .. }
+        | IRNode::Compute { input, .. }
+        | IRNode::FlatMap { input, .. } => rename_scans(input, renames),
+        IRNode::Join { left, right, .. }
+        | IRNode::Antijoin { left, right, .. }
+        | IRNode::Semijoin { left, right, .. }
+        | IRNode::JoinFlatMap { left, right, .. } => {
+            rename_scans(left, renames);
+            rename_scans(right, renames);
+        }
+        IRNode::Union { inputs } => {
+            for input in inputs {
+                rename_scans(input, renames);
+            }
+        }
+    }
+}
+
+/// Reject plans whose operators cannot be maintained under updates.
+fn check_view_plan(plan: &IRNode) -> Result<(), String> {
+    match plan {
+        IRNode::Scan { .. } => Ok(()),
+        IRNode::HnswScan { index_name, .. } => Err(format!(
+            "Vector search on '{index_name}' is not supported in incremental views"
+        )),
+        IRNode::Map { input, .. }
+        | IRNode::Filter { input, .. }
+        | IRNode::Distinct { input }
+        | IRNode::Sort { input, .. }
+        | IRNode::Aggregate { input, .. }
+        | IRNode::Compute { input, .. }
+        | IRNode::FlatMap { input, .. } => check_view_plan(input),
+        IRNode::Join { left, right, .. }
+        | IRNode::Semijoin { left, right, .. }
+        | IRNode::Antijoin { left, right, .. }
+        | IRNode::JoinFlatMap { left, right, .. } => {
+            check_view_plan(left)?;
+            check_view_plan(right)
+        }
+        IRNode::Union { inputs } => inputs.iter().try_for_each(check_view_plan),
+    }
+}
+
 impl Drop for IncrementalEngine {
     fn drop(&mut self) {
         let (tx, rx) = channel::bounded(1);
@@ -1522,4 +2034,257 @@ mod tests {
 
         engine.shutdown().unwrap();
     }
+
+    fn edge_scan(schema: [&str; 2]) -> IRNode {
+        IRNode::Scan {
+            relation: "edge".to_string(),
+            schema: schema.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn test_view_updates_incrementally() {
+        let engine = IncrementalEngine::new(vec!["edge".to_string()]).unwrap();
+        let two_hop = IRNode::Map {
+            input: Box::new(IRNode::Join {
+                left: Box::new(edge_scan(["x", "y"])),
+                right: Box::new(edge_scan(["y", "z"])),
+                left_keys: vec![1],
+                right_keys: vec![0],
+                output_schema: vec!["x".to_string(), "y".to_string(), "z".to_string()],
+            }),
+            projection: vec![0, 2],
+            output_schema: vec!["x".to_string(), "z".to_string()],
+        };
+        engine.install_view("two_hop", two_hop).unwrap();
+        assert!(engine.has_view("two_hop"));
+
+        engine
+            .insert(
+                "edge",
+                vec![Tuple::from_pair(1, 2), Tuple::from_pair(2, 3)],
+                1,
+            )
+            .unwrap();
+        let view = engine.read_view_consistent("two_hop").unwrap().unwrap();
+        assert_eq!(view, vec![Tuple::from_pair(1, 3)]);
+
+        // An insert and a retraction at the same time are applied together
+        engine
+            .insert("edge", vec![Tuple::from_pair(3, 4)], 2)
+            .unwrap();
+        engine
+            .delete("edge", vec![Tuple::from_pair(1, 2)], 2)
+            .unwrap();
+        let view = engine.read_view_consistent("two_hop").unwrap().unwrap();
+        assert_eq!(view, vec![Tuple::from_pair(2, 4)]);
+
+        engine.shutdown().unwrap();
+    }
+
+    #[test]
+    fn test_view_aggregate_retracts_old_value() {
+        let engine = IncrementalEngine::new(vec!["edge".to_string()]).unwrap();
+        let out_degree = IRNode::Aggregate {
+            input: Box::new(edge_scan(["x", "y"])),
+            group_by: vec![0],
+            aggregations: vec![(crate::ir::AggregateFunction::Count, 1)],
+            output_schema: vec!["x".to_string(), "n".to_string()],
+        };
+        engine.install_view("out_degree", out_degree).unwrap();
+
+        engine
+            .insert(
+                "edge",
+                vec![Tuple::from_pair(1, 2), Tuple::from_pair(1, 3)],
+                1,
+            )
+            .unwrap();
+        let view = engine.read_view_consistent("out_degree").unwrap().unwrap();
+        assert_eq!(view.len(), 1);
+        let before = view[0].clone();
+
+        engine
+            .insert("edge", vec![Tuple::from_pair(1, 4)], 2)
+            .unwrap();
+        let view = engine.read_view_consistent("out_degree").unwrap().unwrap();
+        // The old count is retracted rather than kept alongside the new one
+        assert_eq!(view.len(), 1);
+        assert_ne!(view[0], before);
+
+        engine.shutdown().unwrap();
+    }
+
+    #[test]
+    fn test_view_maintains_negation() {
+        let engine = IncrementalEngine::new(vec!["edge".to_string()]).unwrap();
+        // no_back(X, Y) <- edge(X, Y), !edge(Y, X)
+        let plan = IRNode::Antijoin {
+            left: Box::new(edge_scan(["x", "y"])),
+            right: Box::new(edge_scan(["y", "x"])),
+            left_keys: vec![0, 1],
+            right_keys: vec![1, 0],
+            output_schema: vec!["x".to_string(), "y".to_string()],
+        };
+        engine.install_view("no_back", plan).unwrap();
+
+        engine
+            .insert(
+                "edge",
+                vec![
+                    Tuple::from_pair(1, 2),
+                    Tuple::from_pair(2, 1),
+                    Tuple::from_pair(2, 3),
+                ],
+                1,
+            )
+            .unwrap();
+        let view = engine.read_view_consistent("no_back").unwrap().unwrap();
+        assert_eq!(view, vec![Tuple::from_pair(2, 3)]);
+
+        // Retracting the reverse edge brings its partner back
+        engine
+            .delete("edge", vec![Tuple::from_pair(2, 1)], 2)
+            .unwrap();
+        let mut view = engine.read_view_consistent("no_back").unwrap().unwrap();
+        view.sort();
+        assert_eq!(view, vec![Tuple::from_pair(1, 2), Tuple::from_pair(2, 3)]);
+
+        engine.shutdown().unwrap();
+    }
+
+    #[test]
+    fn test_install_compiled_views() {
+        let mut compiler = crate::IQLEngine::new();
+        compiler.add_fact("edge", vec![]);
+        let views = compiler
+            .compile_views(
+                "hop(X, Z) <- edge(X, Y), edge(Y, Z)\nresult(X, Z) <- hop(X, Z), edge(X, Z)",
+            )
+            .unwrap();
+
+        let engine = IncrementalEngine::new(vec!["edge".to_string()]).unwrap();
+        engine.install_views(views).unwrap();
+        assert!(engine.has_view("hop"));
+        assert!(engine.has_view("result"));
+
+        engine
+            .insert(
+                "edge",
+                vec![
+                    Tuple::from_pair(1, 2),
+                    Tuple::from_pair(2, 3),
+                    Tuple::from_pair(1, 3),
+                ],
+                1,
+            )
+            .unwrap();
+        let result = engine.read_view_consistent("result").unwrap().unwrap();
+        assert_eq!(result, vec![Tuple::from_pair(1, 3)]);
+
+        assert!(engine.drop_view("result").unwrap());
+        assert!(!engine.has_view("result"));
+        assert_eq!(engine.read_view("result").unwrap(), None);
+
+        engine.shutdown().unwrap();
+    }
+
+    /// Collect deltas until `count` have arrived (or a timeout passes).
+    fn next_deltas(subscription: &mut ViewSubscription, count: usize) -> Vec<Delta<Tuple>> {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        let mut deltas = Vec::new();
+        while deltas.len() < count && std::time::Instant::now() < deadline {
+            match subscription.try_next() {
+                Some(delta) => deltas.push(delta),
+                None => std::thread::sleep(std::time::Duration::from_millis(5)),
+            }
+        }
+        deltas.sort_by(|a, b| (a.time, &a.data, a.diff).cmp(&(b.time, &b.data, b.diff)));
+        deltas
+    }
+
+    #[test]
+    fn test_subscribe_streams_snapshot_then_changes() {
+        let engine = IncrementalEngine::new(vec!["edge".to_string()]).unwrap();
+        engine
+            .install_view("reversed", {
+                IRNode::Map {
+                    input: Box::new(edge_scan(["x", "y"])),
+                    projection: vec![1, 0],
+                    output_schema: vec!["y".to_string(), "x".to_string()],
+                }
+            })
+            .unwrap();
+        engine
+            .insert("edge", vec![Tuple::from_pair(1, 2)], 1)
+            .unwrap();
+        engine.read_view_consistent("reversed").unwrap();
+
+        let mut subscription = engine.subscribe("reversed").unwrap();
+        assert_eq!(subscription.view(), "reversed");
+        let snapshot = next_deltas(&mut subscription, 1);
+        assert_eq!(snapshot.len(), 1);
+        assert_eq!(snapshot[0].data, Tuple::from_pair(2, 1));
+        assert_eq!(snapshot[0].diff, 1);
+
+        // Writes are pushed without a consistent read
+        engine
+            .insert("edge", vec![Tuple::from_pair(3, 4)], 2)
+            .unwrap();
+        engine
+            .delete("edge", vec![Tuple::from_pair(1, 2)], 3)
+            .unwrap();
+        let changes = next_deltas(&mut subscription, 2);
+        assert_eq!(
+            changes,
+            vec![
+                Delta {
+                    data: Tuple::from_pair(4, 3),
+                    time: 2,
+                    diff: 1
+                },
+                Delta {
+                    data: Tuple::from_pair(2, 1),
+                    time: 3,
+                    diff: -1
+                },
+            ]
+        );
+        assert!(subscription.try_next().is_none());
+
+        engine.shutdown().unwrap();
+    }
+
+    #[test]
+    fn test_subscribe_query_cleans_up_private_views() {
+        let mut compiler = crate::IQLEngine::new();
+        compiler.add_fact("edge", vec![]);
+        let views = compiler
+            .compile_views("result(X) <- edge(X, Y), edge(Y, X)")
+            .unwrap();
+
+        let engine = IncrementalEngine::new(vec!["edge".to_string()]).unwrap();
+        let mut first = engine.subscribe_query(views.clone()).unwrap();
+        let second = engine.subscribe_query(views).unwrap();
+        assert_ne!(first.view(), second.view());
+        assert!(engine.has_view(first.view()));
+
+        engine
+            .insert(
+                "edge",
+                vec![Tuple::from_pair(1, 2), Tuple::from_pair(2, 1)],
+                1,
+            )
+            .unwrap();
+        let deltas = next_deltas(&mut first, 2);
+        assert_eq!(deltas.len(), 2);
+        assert!(deltas.iter().all(|d| d.diff == 1));
+
+        let view = second.view().to_string();
+        drop(second);
+        assert!(!engine.has_view(&view));
+        assert!(engine.subscribe("missing").is_err());
+
+        engine.shutdown().unwrap();
+    }
 }
@@ -28,6 +28,7 @@
 //! - Dependency Tracking: Maps base relations -> dependent indexes
 //! - Cascade Invalidation: Base updates invalidate dependent indexes
 
+use crate::value::{Tuple, Value};
 use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
@@ -35,9 +36,23 @@ use std::sync::Arc;
 /// Counter for index versions
 static INDEX_VERSION: AtomicU64 = AtomicU64::new(0);
 
-/// Tuple ID type - position in the relation's `Vec<Tuple>`
+/// File under `indexes/` recording the LSN the saved indexes reflect
+const SAVED_LSN_FILE: &str = "lsn.json";
+
+/// Tuple ID type - the integer in the first column of an indexed tuple
 pub type TupleId = usize;
 
+/// ID under which a tuple's vector is indexed: its first column, which
+/// must be an integer. `hnsw_nearest` returns these IDs, so they join back
+/// to the relation on that column.
+pub fn tuple_id(tuple: &Tuple) -> Option<TupleId> {
+    match tuple.get(0)? {
+        Value::Int64(id) => Some(*id as TupleId),
+        Value::Int32(id) => Some(*id as TupleId),
+        _ => None,
+    }
+}
+
 /// Distance metric for similarity search
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
 pub enum DistanceMetric {
@@ -103,11 +118,31 @@ impl Default for HnswConfig {
     }
 }
 
+/// LSH-specific configuration
+#[derive(Clone, Debug, PartialEq)]
+pub struct LshConfig {
+    /// Number of hash tables; table indexes are `0..tables` (default: 4)
+    pub tables: usize,
+    /// Hyperplanes (bucket bits) per table (default: 8)
+    pub hyperplanes: usize,
+}
+
+impl Default for LshConfig {
+    fn default() -> Self {
+        Self {
+            tables: 4,
+            hyperplanes: 8,
+        }
+    }
+}
+
 /// Index type enumeration
 #[derive(Clone, Debug, PartialEq)]
 pub enum IndexType {
     /// HNSW index for approximate nearest neighbor search
     Hnsw(HnswConfig),
+    /// Materialized LSH bucket mappings, published as a relation
+    Lsh(LshConfig),
     // Future index types:
     // BTree(BTreeConfig),
     // Hash(HashConfig),
@@ -118,6 +153,7 @@ impl IndexType {
     pub fn type_name(&self) -> &'static str {
         match self {
             Self::Hnsw(_) => "hnsw",
+            Self::Lsh(_) => "lsh",
         }
     }
 }
@@ -198,11 +234,8 @@ pub struct RegisteredIndex {
 
 /// Materialized index with validity tracking
 pub struct MaterializedIndex {
-    /// The actual index structure
-    pub index: Box<dyn Index + Send + Sync>,
-
-    /// Arc-wrapped index for sharing with snapshots
-    index_arc: Arc<dyn Index + Send + Sync>,
+    /// The actual index structure, shared with snapshots
+    pub index: Arc<dyn Index + Send + Sync>,
 
     /// Version when this was last built
     pub version: u64,
@@ -231,14 +264,8 @@ impl MaterializedIndex {
             .duration_since(std::time::UNIX_EPOCH)
             .map_or(0, |d| d.as_micros() as u64);
 
-        // Create Arc wrapper for the index
-        // Box<dyn Index> and Arc<dyn Index> require different ownership,
-        // so we use an IndexWrapper to bridge between them.
-        let index_arc = Arc::from(index);
-
         Self {
-            index: Box::new(IndexWrapper(Arc::clone(&index_arc))),
-            index_arc,
+            index: Arc::from(index),
             version,
             base_versions,
             valid: true,
@@ -254,57 +281,50 @@ impl MaterializedIndex {
 
     /// Get an Arc reference to the index for snapshot sharing
     pub fn arc(&self) -> Arc<dyn Index + Send + Sync> {
-        Arc::clone(&self.index_arc)
+        Arc::clone(&self.index)
     }
-}
-
-/// Wrapper to allow Arc<dyn Index> to be used as Box<dyn Index>
-struct IndexWrapper(Arc<dyn Index + Send + Sync>);
 
-impl Index for IndexWrapper {
-    fn search(&self, query: &[f32], k: usize, ef: Option<usize>) -> Vec<(TupleId, f64)> {
-        self.0.search(query, k, ef)
+    /// Mutable access to the index for incremental updates. `None` while
+    /// a snapshot still shares it.
+    pub fn index_mut(&mut self) -> Option<&mut (dyn Index + Send + Sync + 'static)> {
+        Arc::get_mut(&mut self.index)
     }
 
-    fn insert(&mut self, _id: TupleId, _vector: &[f32]) -> Result<(), String> {
-        Err("Cannot insert into Arc-wrapped index".to_string())
-    }
-
-    fn delete(&mut self, _id: TupleId) {
-        // No-op for Arc-wrapped index
-    }
-
-    fn tombstone_ratio(&self) -> f64 {
-        self.0.tombstone_ratio()
-    }
-
-    fn rebuild(&mut self, _vectors: &[(TupleId, Vec<f32>)]) -> Result<(), String> {
-        Err("Cannot rebuild Arc-wrapped index".to_string())
-    }
-
-    fn len(&self) -> usize {
-        self.0.len()
-    }
-
-    fn index_type(&self) -> &str {
-        self.0.index_type()
-    }
-
-    fn metric(&self) -> DistanceMetric {
-        self.0.metric()
-    }
-
-    fn tombstone_count(&self) -> usize {
-        self.0.tombstone_count()
-    }
-
-    fn dimension(&self) -> usize {
-        self.0.dimension()
+    /// Apply deletes, then inserts, and refresh the vector count
+    pub fn apply(
+        &mut self,
+        inserts: &[(TupleId, Vec<f32>)],
+        deletes: &[TupleId],
+    ) -> Result<(), String> {
+        let index = self
+            .index_mut()
+            .ok_or("Index is shared with a snapshot and cannot be updated")?;
+        for &id in deletes {
+            index.delete(id);
+        }
+        for (id, vector) in inserts {
+            index.insert(*id, vector)?;
+        }
+        self.tuple_count = self
+            .index
+            .len()
+            .saturating_sub(self.index.tombstone_count());
+        Ok(())
     }
+}
 
-    fn as_any(&self) -> &dyn std::any::Any {
-        self
-    }
+/// A vector column covered by a valid HNSW index, which the query planner
+/// can answer nearest-neighbor `top_k` queries from
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexedColumn {
+    /// Index name
+    pub index: String,
+    /// Indexed relation
+    pub relation: String,
+    /// Position of the vector column
+    pub column: usize,
+    /// Distance metric of the index
+    pub metric: DistanceMetric,
 }
 
 /// Statistics about an index for reporting
@@ -332,6 +352,21 @@ pub struct IndexStats {
     pub dimension: usize,
 }
 
+/// The (ID, vector) entries to index for `tuples`, reading vectors from
+/// `column`. Tuples without a vector in that column are skipped.
+fn index_entries(tuples: &[Tuple], column: usize) -> Result<Vec<(TupleId, Vec<f32>)>, String> {
+    let mut entries = Vec::with_capacity(tuples.len());
+    for tuple in tuples {
+        let Some(vector) = tuple.get(column).and_then(Value::as_vector) else {
+            continue;
+        };
+        let id =
+            tuple_id(tuple).ok_or("Indexed tuples need an integer ID in their first column")?;
+        entries.push((id, vector.to_vec()));
+    }
+    Ok(entries)
+}
+
 /// Manages indexes for a single KnowledgeGraph
 ///
 /// Follows the same pattern as `DerivedRelationsManager`:
@@ -456,6 +491,105 @@ impl IndexManager {
         invalidated
     }
 
+    /// Build a registered index from the current tuples of its relation
+    /// and store it, replacing any previous build. Every tuple needs an
+    /// integer ID in its first column (see [`tuple_id`]); tuples whose
+    /// indexed column is not a vector are skipped. Returns the number of
+    /// vectors indexed.
+    pub fn build_index(&mut self, name: &str, tuples: &[Tuple]) -> Result<usize, String> {
+        use crate::hnsw_index::HnswIndex;
+        use crate::lsh_index::LshIndex;
+
+        let registered = self
+            .indexes
+            .get(name)
+            .ok_or_else(|| format!("Index '{name}' not found"))?;
+        let entries = index_entries(tuples, registered.column_idx)?;
+
+        let mut index: Box<dyn Index + Send + Sync> = match &registered.index_type {
+            IndexType::Hnsw(config) => Box::new(HnswIndex::new(config.clone())),
+            IndexType::Lsh(config) => Box::new(LshIndex::new(config.clone())),
+        };
+        index.insert_batch(&entries)?;
+        self.set_materialized(name, index, entries.len());
+        Ok(entries.len())
+    }
+
+    /// Apply inserted and deleted tuples of a base relation to the
+    /// materialized indexes on it, without rebuilding them.
+    ///
+    /// An index the changes cannot be applied to is invalidated instead.
+    /// Returns the names of indexes that were invalidated.
+    pub fn apply_base_changes(
+        &mut self,
+        base_relation: &str,
+        inserted: &[Tuple],
+        deleted: &[Tuple],
+    ) -> Vec<String> {
+        *self
+            .base_versions
+            .entry(base_relation.to_string())
+            .or_insert(0) += 1;
+
+        let mut invalidated = Vec::new();
+        let Some(index_names) = self.base_to_indexes.get(base_relation) else {
+            return invalidated;
+        };
+        for name in index_names {
+            let (Some(registered), Some(mat)) =
+                (self.indexes.get(name), self.materialized.get_mut(name))
+            else {
+                continue;
+            };
+            if !mat.valid {
+                continue;
+            }
+            let applied = index_entries(deleted, registered.column_idx).and_then(|deletes| {
+                let deletes: Vec<TupleId> = deletes.into_iter().map(|(id, _)| id).collect();
+                let inserts = index_entries(inserted, registered.column_idx)?;
+                mat.apply(&inserts, &deletes)
+            });
+            if let Err(e) = applied {
+                tracing::warn!(index = %name, error = %e, "index_update_failed");
+                mat.invalidate();
+                invalidated.push(name.clone());
+            }
+        }
+        invalidated
+    }
+
+    /// Vector columns covered by valid HNSW indexes
+    pub fn indexed_columns(&self) -> Vec<IndexedColumn> {
+        self.indexes
+            .values()
+            .filter(|reg| self.get_materialized(&reg.name).is_some())
+            .filter_map(|reg| match &reg.index_type {
+                IndexType::Hnsw(config) => Some(IndexedColumn {
+                    index: reg.name.clone(),
+                    relation: reg.relation.clone(),
+                    column: reg.column_idx,
+                    metric: config.metric,
+                }),
+                IndexType::Lsh(_) => None,
+            })
+            .collect()
+    }
+
+    /// Bucket mappings of every valid LSH index, as `(Table, Bucket, Id)`
+    /// rows keyed by index name
+    pub fn lsh_relations(&self) -> Vec<(String, Vec<Tuple>)> {
+        use crate::lsh_index::LshIndex;
+
+        self.materialized
+            .iter()
+            .filter(|(_, m)| m.valid)
+            .filter_map(|(name, m)| {
+                let lsh = m.index.as_any().downcast_ref::<LshIndex>()?;
+                Some((name.clone(), lsh.rows()))
+            })
+            .collect()
+    }
+
     /// Get all valid indexes for snapshot publication
     pub fn get_all_valid_indexes(&self) -> HashMap<String, Arc<dyn Index + Send + Sync>> {
         self.materialized
@@ -510,6 +644,7 @@ impl IndexManager {
             index_type: registered.index_type.type_name().to_string(),
             metric: match &registered.index_type {
                 IndexType::Hnsw(config) => config.metric,
+                IndexType::Lsh(_) => DistanceMetric::Cosine,
             },
             tuple_count,
             tombstone_count,
@@ -557,6 +692,11 @@ impl IndexManager {
                     "ef_search": config.ef_search,
                     "metric": format!("{:?}", config.metric).to_lowercase()
                 }),
+                IndexType::Lsh(config) => serde_json::json!({
+                    "type": "lsh",
+                    "tables": config.tables,
+                    "hyperplanes": config.hyperplanes
+                }),
             };
             registrations.push(serde_json::json!({
                 "name": name,
@@ -590,7 +730,7 @@ impl IndexManager {
                 continue; // Skip invalid indexes
             }
             // Downcast to HnswIndex for save
-            if let Some(hnsw) = mat.index_arc.as_any().downcast_ref::<HnswIndex>() {
+            if let Some(hnsw) = mat.index.as_any().downcast_ref::<HnswIndex>() {
                 let index_dir = indexes_dir.join(name);
                 hnsw.save(&index_dir)?;
             }
@@ -599,6 +739,32 @@ impl IndexManager {
         Ok(())
     }
 
+    /// Save all indexes (see [`Self::save_indexes`]) and record that they
+    /// reflect the knowledge graph as of `lsn`
+    pub fn save_indexes_at(&self, base_dir: &std::path::Path, lsn: u64) -> Result<(), String> {
+        self.save_indexes(base_dir)?;
+        let indexes_dir = base_dir.join("indexes");
+        if indexes_dir.exists() {
+            let content = serde_json::json!({ "lsn": lsn }).to_string();
+            std::fs::write(indexes_dir.join(SAVED_LSN_FILE), content)
+                .map_err(|e| format!("Failed to write index LSN: {e}"))?;
+        }
+        Ok(())
+    }
+
+    /// LSN recorded by the last [`Self::save_indexes_at`], if any
+    pub fn saved_lsn(base_dir: &std::path::Path) -> Option<u64> {
+        let content =
+            std::fs::read_to_string(base_dir.join("indexes").join(SAVED_LSN_FILE)).ok()?;
+        let value: serde_json::Value = serde_json::from_str(&content).ok()?;
+        value["lsn"].as_u64()
+    }
+
+    /// Whether any index registrations are saved under `base_dir`
+    pub fn has_saved_indexes(base_dir: &std::path::Path) -> bool {
+        base_dir.join("indexes").join("registrations.json").exists()
+    }
+
     /// Load registered indexes from disk and rebuild materialized HNSW structures.
     /// Returns the number of indexes loaded.
     pub fn load_indexes(&mut self, base_dir: &std::path::Path) -> Result<usize, String> {
@@ -633,21 +799,32 @@ impl IndexManager {
                 .to_string();
 
             let it = &reg["index_type"];
-            let metric = match it["metric"].as_str().unwrap_or("euclidean") {
-                "cosine" => DistanceMetric::Cosine,
-                "dotproduct" | "dot_product" => DistanceMetric::DotProduct,
-                "manhattan" => DistanceMetric::Manhattan,
-                _ => DistanceMetric::Euclidean,
-            };
-            let config = HnswConfig {
-                m: it["m"].as_u64().unwrap_or(16) as usize,
-                ef_construction: it["ef_construction"].as_u64().unwrap_or(100) as usize,
-                ef_search: it["ef_search"].as_u64().unwrap_or(32) as usize,
-                metric,
+            let index_type = if it["type"].as_str() == Some("lsh") {
+                let defaults = LshConfig::default();
+                IndexType::Lsh(LshConfig {
+                    tables: it["tables"]
+                        .as_u64()
+                        .map_or(defaults.tables, |n| n as usize),
+                    hyperplanes: it["hyperplanes"]
+                        .as_u64()
+                        .map_or(defaults.hyperplanes, |n| n as usize),
+                })
+            } else {
+                let metric = match it["metric"].as_str().unwrap_or("euclidean") {
+                    "cosine" => DistanceMetric::Cosine,
+                    "dotproduct" | "dot_product" => DistanceMetric::DotProduct,
+                    "manhattan" => DistanceMetric::Manhattan,
+                    _ => DistanceMetric::Euclidean,
+                };
+                let config = HnswConfig {
+                    m: it["m"].as_u64().unwrap_or(16) as usize,
+                    ef_construction: it["ef_construction"].as_u64().unwrap_or(100) as usize,
+                    ef_search: it["ef_search"].as_u64().unwrap_or(32) as usize,
+                    metric,
+                };
+                IndexType::Hnsw(config)
             };
 
-            let index_type = IndexType::Hnsw(config);
-
             // Register the index
             let registered = RegisteredIndex {
                 name: name.clone(),
@@ -1215,4 +1392,30 @@ mod tests {
         assert_eq!(index.len(), 1);
         assert_eq!(index.tombstone_count(), 0);
     }
+
+    #[test]
+    fn test_build_index_and_apply_base_changes() {
+        let mut manager = IndexManager::new();
+        manager
+            .register_index(make_registered_index("emb_hnsw", "emb", 1))
+            .unwrap();
+
+        let tuples: Vec<Tuple> = (0..5_i64)
+            .map(|i| Tuple::new(vec![Value::Int64(i), Value::vector(vec![i as f32, 1.0])]))
+            .collect();
+        assert_eq!(manager.build_index("emb_hnsw", &tuples).unwrap(), 5);
+        assert_eq!(manager.valid_count(), 1);
+
+        let added = Tuple::new(vec![Value::Int64(10), Value::vector(vec![10.0, 1.0])]);
+        let failed = manager.apply_base_changes("emb", &[added], &tuples[..1]);
+        assert!(failed.is_empty());
+        assert_eq!(manager.valid_count(), 1);
+        assert_eq!(manager.get_stats("emb_hnsw").unwrap().tuple_count, 5);
+
+        // A non-integer id cannot be indexed: the index is invalidated
+        let bad = Tuple::new(vec![Value::string("x"), Value::vector(vec![1.0, 1.0])]);
+        let failed = manager.apply_base_changes("emb", &[bad], &[]);
+        assert_eq!(failed, vec!["emb_hnsw".to_string()]);
+        assert_eq!(manager.get_invalid_indexes(), vec!["emb_hnsw".to_string()]);
+    }
 }
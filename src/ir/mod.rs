@@ -1,11 +1,15 @@
 //! IR types for IQL query plans, shared across all optimization passes.
 
 use crate::ast::{ArithExpr, ComparisonOp};
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 
+mod serialize;
+pub use serialize::{SerializedPlan, PLAN_FORMAT_VERSION};
+
 // IR Node Types
 /// Aggregate function types
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum AggregateFunction {
     /// Count rows
     Count,
@@ -60,7 +64,7 @@ impl AggregateFunction {
 }
 
 /// Built-in function types for vector operations
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum BuiltinFunction {
     /// Euclidean (L2) distance: euclidean(v1, v2)
     Euclidean,
@@ -212,10 +216,68 @@ pub enum BuiltinFunction {
     MinVal,
     /// Scalar maximum: `max_val(a, b)` -> same type
     MaxVal,
+
+    // Date and duration functions
+    /// Date literal or conversion: `date("2024-01-15")`, `date(ts)` -> Date
+    Date,
+    /// Duration literal or conversion: `duration("1d12h")`, `duration(ms)` -> Duration
+    Duration,
+
+    // UUID functions
+    /// UUID literal: `uuid("67e55044-10b1-426f-9247-bb680e5fe0c8")` -> Uuid
+    Uuid,
+    /// Generate a time-ordered UUID: `uuid_v7()` -> Uuid
+    UuidV7,
+
+    // Null handling functions
+    /// Test for NULL: `is_null(x)` -> Bool
+    IsNull,
+    /// First non-NULL argument: `coalesce(x, default)`
+    Coalesce,
+
+    // List and map functions
+    /// Build a list: `list(a, b, ...)` -> List
+    List,
+    /// Build a map from key-value pairs: `map("k1", v1, "k2", v2, ...)` -> Map
+    Map,
+    /// Element at a 0-based index: `list_get(l, i)` -> element, or Null if out of range
+    ListGet,
+    /// Number of elements: `list_len(l)` -> Int64
+    ListLen,
+    /// Value at a key: `map_get(m, "k")` -> value, or Null if absent
+    MapGet,
+    /// Generator: `X = list_elem(l)` binds X to each element, one row per element
+    ListElem,
+    /// Generator: `K = map_key(m)` binds K to each key, one row per key
+    MapKey,
+
+    // JSON functions
+    /// Parse JSON text: `json_parse(s)` -> Json, or Null if malformed
+    JsonParse,
+    /// Value at a path: `json_extract(j, "$.a.b[0]")` -> scalar or Json, Null if absent
+    JsonExtract,
+    /// JSON type name: `json_type(j)` -> "object", "array", "string", "number", "boolean", "null"
+    JsonType,
+    /// Generator: `E = json_array_elements(j)` binds E to each array element
+    JsonArrayElements,
+
+    // Bytes functions
+    /// Decode hex: `hex("deadbeef")` -> Bytes, or Null if malformed
+    Hex,
+    /// Decode base64: `base64("3q2+7w==")` -> Bytes, or Null if malformed
+    Base64,
+    /// Encode as lowercase hex: `to_hex(b)` -> String
+    ToHex,
+    /// Encode as base64: `to_base64(b)` -> String
+    ToBase64,
+    /// Number of bytes: `bytes_len(b)` -> Int64
+    BytesLen,
+    /// Sub-range: `bytes_slice(b, start, len)` -> Bytes, clamped to the end
+    BytesSlice,
 }
 
 /// Expression for computed columns (function calls, arithmetic)
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum IRExpression {
     /// Reference to input column by index
     Column(usize),
@@ -240,7 +302,7 @@ pub enum IRExpression {
 }
 
 /// Arithmetic operators
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ArithOp {
     Add,
     Sub,
@@ -249,6 +311,76 @@ pub enum ArithOp {
     Mod,
 }
 
+impl IRExpression {
+    /// Evaluate arithmetic over numeric constants at plan time
+    ///
+    /// Mirrors runtime evaluation: integer operands stay integral for
+    /// `+ - * %`, while division or any float operand yields a float.
+    /// Division by zero and function calls are left for the runtime.
+    pub fn fold_constants(self) -> Self {
+        match self {
+            IRExpression::Arithmetic { op, left, right } => {
+                let left = left.fold_constants();
+                let right = right.fold_constants();
+                match Self::eval_const_arithmetic(op, &left, &right) {
+                    Some(folded) => folded,
+                    None => IRExpression::Arithmetic {
+                        op,
+                        left: Box::new(left),
+                        right: Box::new(right),
+                    },
+                }
+            }
+            IRExpression::FunctionCall(func, args) => IRExpression::FunctionCall(
+                func,
+                args.into_iter().map(IRExpression::fold_constants).collect(),
+            ),
+            other => other,
+        }
+    }
+
+    fn eval_const_arithmetic(
+        op: ArithOp,
+        left: &IRExpression,
+        right: &IRExpression,
+    ) -> Option<IRExpression> {
+        let as_f64 = |expr: &IRExpression| match expr {
+            IRExpression::IntConstant(v) => Some(*v as f64),
+            IRExpression::FloatConstant(v) => Some(*v),
+            _ => None,
+        };
+        let l = as_f64(left)?;
+        let r = as_f64(right)?;
+
+        let result = match op {
+            ArithOp::Add => l + r,
+            ArithOp::Sub => l - r,
+            ArithOp::Mul => l * r,
+            ArithOp::Div | ArithOp::Mod if r == 0.0 => return None,
+            ArithOp::Div => l / r,
+            ArithOp::Mod => l % r,
+        };
+
+        let both_int = matches!(left, IRExpression::IntConstant(_))
+            && matches!(right, IRExpression::IntConstant(_));
+        if both_int && op != ArithOp::Div {
+            if !result.is_finite() {
+                return None;
+            }
+            Some(IRExpression::IntConstant(result as i64))
+        } else {
+            Some(IRExpression::FloatConstant(result))
+        }
+    }
+}
+
+/// Sort direction of an ordering column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
 /// IR Node - represents an operator in the query plan
 ///
 /// This is the canonical IR definition used across all modules.
@@ -256,7 +388,7 @@ pub enum ArithOp {
 ///
 /// Note: `IRNode` does not implement Hash or Eq because `AggregateFunction`
 /// contains f64 fields (threshold, `max_distance`) which don't implement Hash.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum IRNode {
     /// Scan a relation (read from EDB or IDB)
     Scan {
@@ -338,6 +470,23 @@ pub enum IRNode {
         output_schema: Vec<String>,
     },
 
+    /// Semijoin: keep left tuples that have at least one match in right.
+    ///
+    /// Inserted by the semijoin reducer in front of large joins so the big side
+    /// is filtered against the small side's key set before it is arranged.
+    Semijoin {
+        /// The relation to keep tuples from
+        left: Box<IRNode>,
+        /// The relation whose keys act as the filter
+        right: Box<IRNode>,
+        /// Columns from left to use as join key
+        left_keys: Vec<usize>,
+        /// Columns from right to use as join key
+        right_keys: Vec<usize>,
+        /// Output schema (same as left's schema)
+        output_schema: Vec<String>,
+    },
+
     /// Append computed columns (expressions evaluated per tuple).
     Compute {
         /// Input node
@@ -415,6 +564,18 @@ pub enum IRNode {
         /// Output column names
         output_schema: Vec<String>,
     },
+
+    /// Sort (order the final results)
+    ///
+    /// Collections are unordered, so inside a dataflow this passes its input
+    /// through unchanged; the rows a plan rooted at `Sort` returns come back
+    /// in this order.
+    Sort {
+        /// Input node to order
+        input: Box<IRNode>,
+        /// Ordering columns, most significant first: (column index, direction)
+        order_by: Vec<(usize, SortDirection)>,
+    },
 }
 
 impl IRNode {
@@ -429,6 +590,7 @@ impl IRNode {
             IRNode::Filter { input, .. } => input.output_schema(), // Pass through!
             IRNode::Join { output_schema, .. } => output_schema.clone(),
             IRNode::Distinct { input } => input.output_schema(),
+            IRNode::Sort { input, .. } => input.output_schema(),
             IRNode::Union { inputs } => {
                 // All inputs must have same schema
                 if inputs.is_empty() {
@@ -439,6 +601,7 @@ impl IRNode {
             }
             IRNode::Aggregate { output_schema, .. } => output_schema.clone(),
             IRNode::Antijoin { output_schema, .. } => output_schema.clone(),
+            IRNode::Semijoin { output_schema, .. } => output_schema.clone(),
             IRNode::Compute { input, expressions } => {
                 // Output schema is input schema + computed column names
                 let mut schema = input.output_schema();
@@ -459,9 +622,11 @@ impl IRNode {
     /// complexity. Higher costs indicate more expensive operations:
     /// - Scan: 10 (base cost of reading a relation)
     /// - Filter/Map/Distinct: pass-through (child cost + small overhead)
+    /// - Sort: child cost + sorting overhead
     /// - Join: product of child costs (cartesian product risk)
     /// - Aggregate: 2× child cost (hash grouping)
     /// - Antijoin: sum of child costs + overhead
+    /// - Semijoin: sum of child costs + small overhead (key-set filter)
     /// - HnswScan: fixed cost based on k
     ///
     /// Returns 0 for trivially cheap operations.
@@ -471,6 +636,7 @@ impl IRNode {
             IRNode::Map { input, .. } | IRNode::FlatMap { input, .. } => input.estimate_cost() + 1,
             IRNode::Filter { input, .. } => input.estimate_cost() + 1,
             IRNode::Distinct { input } => input.estimate_cost() + 5,
+            IRNode::Sort { input, .. } => input.estimate_cost() + 5,
             IRNode::Compute { input, .. } => input.estimate_cost() + 1,
             IRNode::Join { left, right, .. } | IRNode::JoinFlatMap { left, right, .. } => {
                 let lc = left.estimate_cost();
@@ -481,6 +647,9 @@ impl IRNode {
             IRNode::Antijoin { left, right, .. } => {
                 left.estimate_cost() + right.estimate_cost() + 10
             }
+            IRNode::Semijoin { left, right, .. } => {
+                left.estimate_cost() + right.estimate_cost() + 5
+            }
             IRNode::Union { inputs } => inputs.iter().map(IRNode::estimate_cost).sum::<u64>() + 1,
             IRNode::Aggregate { input, .. } => input.estimate_cost().saturating_mul(2),
             IRNode::HnswScan { k, .. } => (*k as u64) * 10 + 50,
@@ -536,6 +705,14 @@ impl IRNode {
             IRNode::Distinct { input } => {
                 format!("{}Distinct\n{}", prefix, input.pretty_print(indent + 1))
             }
+            IRNode::Sort { input, order_by } => {
+                format!(
+                    "{}Sort(order_by={:?})\n{}",
+                    prefix,
+                    order_by,
+                    input.pretty_print(indent + 1)
+                )
+            }
             IRNode::Union { inputs } => {
                 let mut result = format!("{prefix}Union\n");
                 for input in inputs {
@@ -580,6 +757,23 @@ impl IRNode {
                     right.pretty_print(indent + 1)
                 )
             }
+            IRNode::Semijoin {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                output_schema,
+            } => {
+                format!(
+                    "{}Semijoin(left_keys={:?}, right_keys={:?}, output={:?})\n{}\n{}",
+                    prefix,
+                    left_keys,
+                    right_keys,
+                    output_schema,
+                    left.pretty_print(indent + 1),
+                    right.pretty_print(indent + 1)
+                )
+            }
             IRNode::Compute { input, expressions } => {
                 let expr_strs: Vec<String> = expressions
                     .iter()
@@ -651,11 +845,90 @@ impl IRNode {
     pub fn is_join(&self) -> bool {
         matches!(self, IRNode::Join { .. })
     }
+
+    /// Direct inputs of this node
+    pub fn children(&self) -> Vec<&IRNode> {
+        match self {
+            IRNode::Scan { .. } | IRNode::HnswScan { .. } => Vec::new(),
+            IRNode::Map { input, .. }
+            | IRNode::Filter { input, .. }
+            | IRNode::Distinct { input }
+            | IRNode::Sort { input, .. }
+            | IRNode::Aggregate { input, .. }
+            | IRNode::Compute { input, .. }
+            | IRNode::FlatMap { input, .. } => vec![input.as_ref()],
+            IRNode::Join { left, right, .. }
+            | IRNode::Antijoin { left, right, .. }
+            | IRNode::Semijoin { left, right, .. }
+            | IRNode::JoinFlatMap { left, right, .. } => vec![left.as_ref(), right.as_ref()],
+            IRNode::Union { inputs } => inputs.iter().collect(),
+        }
+    }
+
+    /// The scanned relation and column that output column `col` is passed
+    /// through from unchanged, if any (computed and aggregated columns have
+    /// no source)
+    pub fn column_source(&self, col: usize) -> Option<(&str, usize)> {
+        match self {
+            IRNode::Scan { relation, schema } => {
+                (col < schema.len()).then_some((relation.as_str(), col))
+            }
+            IRNode::Filter { input, .. }
+            | IRNode::Distinct { input }
+            | IRNode::Sort { input, .. } => input.column_source(col),
+            IRNode::Compute { input, .. } if col < input.output_schema().len() => {
+                input.column_source(col)
+            }
+            IRNode::Map {
+                input, projection, ..
+            }
+            | IRNode::FlatMap {
+                input, projection, ..
+            } => input.column_source(*projection.get(col)?),
+            IRNode::Antijoin { left, .. } | IRNode::Semijoin { left, .. } => {
+                left.column_source(col)
+            }
+            IRNode::Join {
+                left,
+                right,
+                right_keys,
+                ..
+            } => {
+                let left_arity = left.output_schema().len();
+                if col < left_arity {
+                    return left.column_source(col);
+                }
+                // Join output is all of left followed by right's non-key columns
+                let right_col = (0..right.output_schema().len())
+                    .filter(|c| !right_keys.contains(c))
+                    .nth(col - left_arity)?;
+                right.column_source(right_col)
+            }
+            IRNode::JoinFlatMap {
+                left,
+                right,
+                projection,
+                ..
+            } => {
+                let joined = *projection.get(col)?;
+                let left_arity = left.output_schema().len();
+                if joined < left_arity {
+                    left.column_source(joined)
+                } else {
+                    right.column_source(joined - left_arity)
+                }
+            }
+            IRNode::Compute { .. }
+            | IRNode::Union { .. }
+            | IRNode::Aggregate { .. }
+            | IRNode::HnswScan { .. } => None,
+        }
+    }
 }
 
 // Predicate Types
 /// Predicate for Filter nodes
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Predicate {
     /// Column equals constant (integer)
     ColumnEqConst(usize, i64),
@@ -831,6 +1104,192 @@ impl Predicate {
         }
     }
 
+    /// Fold constant arithmetic and trivially decided comparisons
+    ///
+    /// - Constant arithmetic inside runtime comparisons is evaluated, and an
+    ///   `ArithCompareConst` without variables becomes `True`/`False`
+    /// - `X != X`, `X < X` and `X > X` are false. `X = X` is kept: it is
+    ///   unknown, and so drops the row, when `X` is null
+    /// - A conjunction that pins one column to conflicting values or an
+    ///   empty range is false
+    ///
+    /// The result is simplified so `True`/`False` propagate through `And`/`Or`.
+    pub fn fold_constants(self) -> Self {
+        let folded = match self {
+            Predicate::ColumnsNe(l, r)
+            | Predicate::ColumnsLt(l, r)
+            | Predicate::ColumnsGt(l, r)
+                if l == r =>
+            {
+                Predicate::False
+            }
+            Predicate::ColumnCompareArith(col, op, expr, var_map) => {
+                Predicate::ColumnCompareArith(col, op, expr.fold_constants(), var_map)
+            }
+            Predicate::ArithCompareConst(expr, op, val, var_map) => {
+                let expr = expr.fold_constants();
+                match expr {
+                    ArithExpr::Constant(lhs) => {
+                        if Self::compare_i64(lhs, &op, val) {
+                            Predicate::True
+                        } else {
+                            Predicate::False
+                        }
+                    }
+                    expr => Predicate::ArithCompareConst(expr, op, val, var_map),
+                }
+            }
+            Predicate::And(p1, p2) => {
+                let and =
+                    Predicate::And(Box::new(p1.fold_constants()), Box::new(p2.fold_constants()));
+                let mut conjuncts = Vec::new();
+                and.collect_conjuncts(&mut conjuncts);
+                let contradictory = conjuncts.iter().enumerate().any(|(i, a)| {
+                    conjuncts[i + 1..]
+                        .iter()
+                        .any(|b| Self::conflicts(a, b) || Self::conflicts(b, a))
+                });
+                if contradictory {
+                    Predicate::False
+                } else {
+                    and
+                }
+            }
+            Predicate::Or(p1, p2) => {
+                Predicate::Or(Box::new(p1.fold_constants()), Box::new(p2.fold_constants()))
+            }
+            other => other,
+        };
+        folded.simplify()
+    }
+
+    fn compare_i64(lhs: i64, op: &ComparisonOp, rhs: i64) -> bool {
+        match op {
+            ComparisonOp::Equal => lhs == rhs,
+            ComparisonOp::NotEqual => lhs != rhs,
+            ComparisonOp::LessThan => lhs < rhs,
+            ComparisonOp::LessOrEqual => lhs <= rhs,
+            ComparisonOp::GreaterThan => lhs > rhs,
+            ComparisonOp::GreaterOrEqual => lhs >= rhs,
+        }
+    }
+
+    fn collect_conjuncts<'a>(&'a self, out: &mut Vec<&'a Predicate>) {
+        match self {
+            Predicate::And(p1, p2) => {
+                p1.collect_conjuncts(out);
+                p2.collect_conjuncts(out);
+            }
+            other => out.push(other),
+        }
+    }
+
+    /// Whether `a AND b` can never hold for any tuple
+    fn conflicts(a: &Predicate, b: &Predicate) -> bool {
+        match (a, b) {
+            (Predicate::ColumnEqConst(c1, v1), Predicate::ColumnEqConst(c2, v2)) => {
+                c1 == c2 && v1 != v2
+            }
+            (Predicate::ColumnEqStr(c1, v1), Predicate::ColumnEqStr(c2, v2)) => {
+                c1 == c2 && v1 != v2
+            }
+            (Predicate::ColumnEqBool(c1, v1), Predicate::ColumnEqBool(c2, v2)) => {
+                c1 == c2 && v1 != v2
+            }
+            (Predicate::ColumnEqConst(c1, v1), Predicate::ColumnNeConst(c2, v2)) => {
+                c1 == c2 && v1 == v2
+            }
+            (Predicate::ColumnEqStr(c1, v1), Predicate::ColumnNeStr(c2, v2)) => {
+                c1 == c2 && v1 == v2
+            }
+            _ => match (Self::lower_bound(a), Self::upper_bound(b)) {
+                (Some((c1, lo, lo_strict)), Some((c2, hi, hi_strict))) => {
+                    c1 == c2 && (hi < lo || (hi == lo && (lo_strict || hi_strict)))
+                }
+                _ => false,
+            },
+        }
+    }
+
+    /// `(column, bound, strict)` for `col > v` / `col >= v`
+    fn lower_bound(p: &Predicate) -> Option<(usize, i64, bool)> {
+        match p {
+            Predicate::ColumnGtConst(col, v) => Some((*col, *v, true)),
+            Predicate::ColumnGeConst(col, v) => Some((*col, *v, false)),
+            _ => None,
+        }
+    }
+
+    /// `(column, bound, strict)` for `col < v` / `col <= v`
+    fn upper_bound(p: &Predicate) -> Option<(usize, i64, bool)> {
+        match p {
+            Predicate::ColumnLtConst(col, v) => Some((*col, *v, true)),
+            Predicate::ColumnLeConst(col, v) => Some((*col, *v, false)),
+            _ => None,
+        }
+    }
+
+    /// Rewrite every column index through `map`
+    ///
+    /// Unlike `adjust_for_projection`, no conjunct is dropped: returns `None`
+    /// if any referenced column has no mapping.
+    pub fn remap_columns(&self, map: &impl Fn(usize) -> Option<usize>) -> Option<Self> {
+        let remap_vars = |var_map: &HashMap<String, usize>| -> Option<HashMap<String, usize>> {
+            var_map
+                .iter()
+                .map(|(name, idx)| map(*idx).map(|new_idx| (name.clone(), new_idx)))
+                .collect()
+        };
+
+        Some(match self {
+            Predicate::ColumnEqConst(c, v) => Predicate::ColumnEqConst(map(*c)?, *v),
+            Predicate::ColumnNeConst(c, v) => Predicate::ColumnNeConst(map(*c)?, *v),
+            Predicate::ColumnGtConst(c, v) => Predicate::ColumnGtConst(map(*c)?, *v),
+            Predicate::ColumnLtConst(c, v) => Predicate::ColumnLtConst(map(*c)?, *v),
+            Predicate::ColumnGeConst(c, v) => Predicate::ColumnGeConst(map(*c)?, *v),
+            Predicate::ColumnLeConst(c, v) => Predicate::ColumnLeConst(map(*c)?, *v),
+            Predicate::ColumnEqStr(c, v) => Predicate::ColumnEqStr(map(*c)?, v.clone()),
+            Predicate::ColumnNeStr(c, v) => Predicate::ColumnNeStr(map(*c)?, v.clone()),
+            Predicate::ColumnLtStr(c, v) => Predicate::ColumnLtStr(map(*c)?, v.clone()),
+            Predicate::ColumnGtStr(c, v) => Predicate::ColumnGtStr(map(*c)?, v.clone()),
+            Predicate::ColumnLeStr(c, v) => Predicate::ColumnLeStr(map(*c)?, v.clone()),
+            Predicate::ColumnGeStr(c, v) => Predicate::ColumnGeStr(map(*c)?, v.clone()),
+            Predicate::ColumnEqBool(c, v) => Predicate::ColumnEqBool(map(*c)?, *v),
+            Predicate::ColumnNeBool(c, v) => Predicate::ColumnNeBool(map(*c)?, *v),
+            Predicate::ColumnEqFloat(c, v) => Predicate::ColumnEqFloat(map(*c)?, *v),
+            Predicate::ColumnNeFloat(c, v) => Predicate::ColumnNeFloat(map(*c)?, *v),
+            Predicate::ColumnGtFloat(c, v) => Predicate::ColumnGtFloat(map(*c)?, *v),
+            Predicate::ColumnLtFloat(c, v) => Predicate::ColumnLtFloat(map(*c)?, *v),
+            Predicate::ColumnGeFloat(c, v) => Predicate::ColumnGeFloat(map(*c)?, *v),
+            Predicate::ColumnLeFloat(c, v) => Predicate::ColumnLeFloat(map(*c)?, *v),
+            Predicate::ColumnsEq(l, r) => Predicate::ColumnsEq(map(*l)?, map(*r)?),
+            Predicate::ColumnsNe(l, r) => Predicate::ColumnsNe(map(*l)?, map(*r)?),
+            Predicate::ColumnsLt(l, r) => Predicate::ColumnsLt(map(*l)?, map(*r)?),
+            Predicate::ColumnsGt(l, r) => Predicate::ColumnsGt(map(*l)?, map(*r)?),
+            Predicate::ColumnsLe(l, r) => Predicate::ColumnsLe(map(*l)?, map(*r)?),
+            Predicate::ColumnsGe(l, r) => Predicate::ColumnsGe(map(*l)?, map(*r)?),
+            Predicate::ColumnCompareArith(c, op, expr, var_map) => Predicate::ColumnCompareArith(
+                map(*c)?,
+                op.clone(),
+                expr.clone(),
+                remap_vars(var_map)?,
+            ),
+            Predicate::ArithCompareConst(expr, op, val, var_map) => {
+                Predicate::ArithCompareConst(expr.clone(), op.clone(), *val, remap_vars(var_map)?)
+            }
+            Predicate::And(p1, p2) => Predicate::And(
+                Box::new(p1.remap_columns(map)?),
+                Box::new(p2.remap_columns(map)?),
+            ),
+            Predicate::Or(p1, p2) => Predicate::Or(
+                Box::new(p1.remap_columns(map)?),
+                Box::new(p2.remap_columns(map)?),
+            ),
+            Predicate::True => Predicate::True,
+            Predicate::False => Predicate::False,
+        })
+    }
+
     /// Adjust column indices after projection
     /// Returns None if predicate references columns not in projection
     ///
@@ -1016,6 +1475,34 @@ impl Predicate {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_column_source_through_join() {
+        let scan = |relation: &str, cols: &[&str]| IRNode::Scan {
+            relation: relation.to_string(),
+            schema: cols.iter().map(ToString::to_string).collect(),
+        };
+        // edge(X, Y), node(Y, L): output [X, Y, L]
+        let join = IRNode::Join {
+            left: Box::new(scan("edge", &["X", "Y"])),
+            right: Box::new(scan("node", &["Y", "L"])),
+            left_keys: vec![1],
+            right_keys: vec![0],
+            output_schema: vec!["X".into(), "Y".into(), "L".into()],
+        };
+        assert_eq!(join.column_source(1), Some(("edge", 1)));
+        assert_eq!(join.column_source(2), Some(("node", 1)));
+        assert_eq!(join.column_source(3), None);
+        assert_eq!(join.children().len(), 2);
+
+        let projected = IRNode::Map {
+            input: Box::new(join),
+            projection: vec![2, 0],
+            output_schema: vec!["L".into(), "X".into()],
+        };
+        assert_eq!(projected.column_source(0), Some(("node", 1)));
+        assert_eq!(projected.column_source(1), Some(("edge", 0)));
+    }
+
     // AggregateFunction Tests
     #[test]
     fn test_aggregate_function_clone_eq() {
@@ -0,0 +1,217 @@
+//! Stable serialized form of query plans.
+//!
+//! Optimized plans are wrapped in a versioned envelope so they can be
+//! persisted (JSON, e.g. next to view definitions) or shipped to remote
+//! workers (bincode) without re-deriving IR from source. The format version
+//! is checked before the plan body is decoded, so plans written by an
+//! incompatible build are rejected with a clear error instead of being
+//! misread.
+
+use super::IRNode;
+use serde::{Deserialize, Serialize};
+
+/// Current plan format version. Bump when a change to `IRNode`,
+/// `Predicate`, `IRExpression` or the enums they hold alters the serialized
+/// shape of existing variants. Binary plans encode a variant by its index,
+/// so new variants go at the end of their enum; inserting or reordering
+/// variants also requires a bump.
+pub const PLAN_FORMAT_VERSION: u32 = 1;
+
+/// A serialized query plan with its format version
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SerializedPlan {
+    /// Format version the plan was written with
+    pub format_version: u32,
+    /// Root of the plan
+    pub root: IRNode,
+}
+
+impl SerializedPlan {
+    /// Wrap a plan using the current format version
+    pub fn new(root: IRNode) -> Self {
+        SerializedPlan {
+            format_version: PLAN_FORMAT_VERSION,
+            root,
+        }
+    }
+
+    /// Unwrap the plan
+    pub fn into_ir(self) -> IRNode {
+        self.root
+    }
+
+    /// Encode as JSON (for on-disk storage)
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|e| format!("Failed to serialize plan: {e}"))
+    }
+
+    /// Decode from JSON, rejecting unsupported format versions
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let value: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| format!("Invalid plan JSON: {e}"))?;
+        let version = value
+            .get("format_version")
+            .and_then(serde_json::Value::as_u64)
+            .ok_or_else(|| "Plan is missing format_version".to_string())?;
+        Self::check_version(version)?;
+        serde_json::from_value(value).map_err(|e| format!("Failed to deserialize plan: {e}"))
+    }
+
+    /// Encode as compact binary (for the wire)
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        bincode::serialize(self).map_err(|e| format!("Failed to serialize plan: {e}"))
+    }
+
+    /// Decode from binary, rejecting unsupported format versions
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        // The version is the first field, so it can be read on its own
+        let version: u32 =
+            bincode::deserialize(bytes).map_err(|e| format!("Invalid plan bytes: {e}"))?;
+        Self::check_version(u64::from(version))?;
+        bincode::deserialize(bytes).map_err(|e| format!("Failed to deserialize plan: {e}"))
+    }
+
+    fn check_version(version: u64) -> Result<(), String> {
+        if version == u64::from(PLAN_FORMAT_VERSION) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Unsupported plan format version {version} (expected {PLAN_FORMAT_VERSION})"
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::ast::{ArithExpr, ArithOp, ComparisonOp};
+    use crate::ir::{
+        AggregateFunction, ArithOp as IRArithOp, BuiltinFunction, IRExpression, Predicate,
+    };
+    use std::collections::HashMap;
+
+    fn sample_plan() -> IRNode {
+        let mut var_map = HashMap::new();
+        var_map.insert("Y".to_string(), 1);
+
+        IRNode::Aggregate {
+            input: Box::new(IRNode::Compute {
+                input: Box::new(IRNode::Filter {
+                    input: Box::new(IRNode::Join {
+                        left: Box::new(IRNode::Scan {
+                            relation: "edge".to_string(),
+                            schema: vec!["X".to_string(), "Y".to_string()],
+                        }),
+                        right: Box::new(IRNode::Scan {
+                            relation: "weight".to_string(),
+                            schema: vec!["Y".to_string(), "W".to_string()],
+                        }),
+                        left_keys: vec![1],
+                        right_keys: vec![0],
+                        output_schema: vec!["X".to_string(), "Y".to_string(), "W".to_string()],
+                    }),
+                    predicate: Predicate::And(
+                        Box::new(Predicate::ColumnGtFloat(2, 0.5)),
+                        Box::new(Predicate::ArithCompareConst(
+                            ArithExpr::Binary {
+                                op: ArithOp::Add,
+                                left: Box::new(ArithExpr::Variable("Y".to_string())),
+                                right: Box::new(ArithExpr::Constant(1)),
+                            },
+                            ComparisonOp::LessThan,
+                            10,
+                            var_map,
+                        )),
+                    ),
+                }),
+                expressions: vec![(
+                    "D".to_string(),
+                    IRExpression::Arithmetic {
+                        op: IRArithOp::Mul,
+                        left: Box::new(IRExpression::Column(2)),
+                        right: Box::new(IRExpression::FloatConstant(2.0)),
+                    },
+                )],
+            }),
+            group_by: vec![0],
+            aggregations: vec![(AggregateFunction::Sum, 3)],
+            output_schema: vec!["X".to_string(), "Total".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let plan = SerializedPlan::new(sample_plan());
+        let json = plan.to_json().unwrap();
+        let restored = SerializedPlan::from_json(&json).unwrap();
+        assert_eq!(restored, plan);
+        assert_eq!(restored.into_ir(), sample_plan());
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let plan = SerializedPlan::new(sample_plan());
+        let bytes = plan.to_bytes().unwrap();
+        let restored = SerializedPlan::from_bytes(&bytes).unwrap();
+        assert_eq!(restored, plan);
+    }
+
+    #[test]
+    fn test_bytes_pin_variant_indices() {
+        let plan = SerializedPlan::new(IRNode::JoinFlatMap {
+            left: Box::new(IRNode::Scan {
+                relation: "edge".to_string(),
+                schema: vec!["X".to_string(), "Y".to_string()],
+            }),
+            right: Box::new(IRNode::Scan {
+                relation: "node".to_string(),
+                schema: vec!["Y".to_string()],
+            }),
+            left_keys: vec![1],
+            right_keys: vec![0],
+            projection: vec![0],
+            filter_predicate: Some(Predicate::False),
+            output_schema: vec!["X".to_string()],
+        });
+        let bytes = plan.to_bytes().unwrap();
+        // The format version, then the root's variant index
+        assert_eq!(bytes[..4], PLAN_FORMAT_VERSION.to_le_bytes());
+        assert_eq!(bytes[4..8], 12u32.to_le_bytes());
+        assert_eq!(SerializedPlan::from_bytes(&bytes).unwrap(), plan);
+
+        // Last variants as of format version 1
+        let index = |bytes: Vec<u8>| u32::from_le_bytes(bytes[..4].try_into().unwrap());
+        assert_eq!(index(bincode::serialize(&Predicate::False).unwrap()), 31);
+        assert_eq!(
+            index(bincode::serialize(&BuiltinFunction::MaxVal).unwrap()),
+            64
+        );
+        let arithmetic = IRExpression::Arithmetic {
+            op: IRArithOp::Add,
+            left: Box::new(IRExpression::Column(0)),
+            right: Box::new(IRExpression::IntConstant(1)),
+        };
+        assert_eq!(index(bincode::serialize(&arithmetic).unwrap()), 7);
+    }
+
+    #[test]
+    fn test_rejects_unknown_version() {
+        let mut plan = SerializedPlan::new(sample_plan());
+        plan.format_version = PLAN_FORMAT_VERSION + 1;
+
+        let err = SerializedPlan::from_json(&plan.to_json().unwrap()).unwrap_err();
+        assert!(err.contains("Unsupported plan format version"));
+
+        let err = SerializedPlan::from_bytes(&plan.to_bytes().unwrap()).unwrap_err();
+        assert!(err.contains("Unsupported plan format version"));
+    }
+
+    #[test]
+    fn test_rejects_garbage() {
+        assert!(SerializedPlan::from_json("not json").is_err());
+        assert!(SerializedPlan::from_json("{}").is_err());
+        assert!(SerializedPlan::from_bytes(&[1, 0]).is_err());
+    }
+}
@@ -22,6 +22,7 @@ use crate::ast::{Atom, BodyPredicate, BuiltinFunc, ComparisonOp, Rule, Term};
 use crate::execution::timing::IrBuilderTiming;
 use crate::ir::{BuiltinFunction, IRExpression, IRNode, Predicate};
 use std::collections::HashSet;
+use tracing::trace;
 
 use crate::catalog::Catalog;
 
@@ -50,9 +51,7 @@ impl IRBuilder {
         //    between scans that would otherwise produce a Cartesian product.
         //    E.g., `data(Id, 1), PrevId = Id - 1, data(PrevId, 0)` should join on PrevId.
         let mut current = scans.remove(0);
-        if std::env::var("IL_DEBUG").is_ok() {
-            eprintln!("DEBUG IR build: first scan = {:?}", current.output_schema());
-        }
+        trace!(schema = ?current.output_schema(), "ir_build_first_scan");
         for scan in scans {
             // Check if an arithmetic comparison bridges the current and next scan.
             // If so, add a Compute node to the left side to create the join key.
@@ -65,11 +64,7 @@ impl IRBuilder {
                 if let Some((compute_name, ir_expr)) =
                     self.find_arithmetic_join_bridge(rule, &left_schema, &right_schema)
                 {
-                    if std::env::var("IL_DEBUG").is_ok() {
-                        eprintln!(
-                            "DEBUG IR build: adding computed join key '{compute_name}' to left side"
-                        );
-                    }
+                    trace!(key = %compute_name, "ir_build_computed_join_key");
                     // Add computed column to left side to create join key
                     current = IRNode::Compute {
                         input: Box::new(current),
@@ -78,16 +73,8 @@ impl IRBuilder {
                 }
             }
 
-            if std::env::var("IL_DEBUG").is_ok() {
-                eprintln!(
-                    "DEBUG IR build: joining with scan = {:?}",
-                    scan.output_schema()
-                );
-            }
+            trace!(scan_schema = ?scan.output_schema(), "ir_build_join");
             current = self.build_join(current, scan)?;
-            if std::env::var("IL_DEBUG").is_ok() {
-                eprintln!("DEBUG IR build: after join = {:?}", current.output_schema());
-            }
         }
 
         // 3. Apply computed columns (function calls in body)
@@ -123,9 +110,7 @@ impl IRBuilder {
         // Build join tree (same logic as build_ir)
         let start = std::time::Instant::now();
         let mut current = scans.remove(0);
-        if std::env::var("IL_DEBUG").is_ok() {
-            eprintln!("DEBUG IR build: first scan = {:?}", current.output_schema());
-        }
+        trace!(schema = ?current.output_schema(), "ir_build_first_scan");
         for scan in scans {
             let left_schema = current.output_schema();
             let right_schema = scan.output_schema();
@@ -135,11 +120,7 @@ impl IRBuilder {
                 if let Some((compute_name, ir_expr)) =
                     self.find_arithmetic_join_bridge(rule, &left_schema, &right_schema)
                 {
-                    if std::env::var("IL_DEBUG").is_ok() {
-                        eprintln!(
-                            "DEBUG IR build: adding computed join key '{compute_name}' to left side"
-                        );
-                    }
+                    trace!(key = %compute_name, "ir_build_computed_join_key");
                     current = IRNode::Compute {
                         input: Box::new(current),
                         expressions: vec![(compute_name, ir_expr)],
@@ -147,16 +128,8 @@ impl IRBuilder {
                 }
             }
 
-            if std::env::var("IL_DEBUG").is_ok() {
-                eprintln!(
-                    "DEBUG IR build: joining with scan = {:?}",
-                    scan.output_schema()
-                );
-            }
+            trace!(scan_schema = ?scan.output_schema(), "ir_build_join");
             current = self.build_join(current, scan)?;
-            if std::env::var("IL_DEBUG").is_ok() {
-                eprintln!("DEBUG IR build: after join = {:?}", current.output_schema());
-            }
         }
         timing.joins_us = start.elapsed().as_micros() as u64;
 
@@ -503,15 +476,7 @@ impl IRBuilder {
                     _ => None,
                 } {
                     // Validate argument count
-                    let expected_arity = func.arity();
-                    if args.len() != expected_arity {
-                        return Err(format!(
-                            "Function '{}' requires {} argument(s), but {} provided",
-                            func.as_str(),
-                            expected_arity,
-                            args.len()
-                        ));
-                    }
+                    func.check_arity(args.len())?;
 
                     // Convert AST function to IR function
                     let ir_func = Self::ast_func_to_ir_func(func)?;
@@ -694,6 +659,33 @@ impl IRBuilder {
             BuiltinFunc::IntervalContains => Ok(BuiltinFunction::IntervalContains),
             BuiltinFunc::IntervalDuration => Ok(BuiltinFunction::IntervalDuration),
             BuiltinFunc::PointInInterval => Ok(BuiltinFunction::PointInInterval),
+            BuiltinFunc::Date => Ok(BuiltinFunction::Date),
+            BuiltinFunc::Duration => Ok(BuiltinFunction::Duration),
+            BuiltinFunc::Uuid => Ok(BuiltinFunction::Uuid),
+            BuiltinFunc::UuidV7 => Ok(BuiltinFunction::UuidV7),
+            // Null handling functions
+            BuiltinFunc::IsNull => Ok(BuiltinFunction::IsNull),
+            BuiltinFunc::Coalesce => Ok(BuiltinFunction::Coalesce),
+            // List and map functions
+            BuiltinFunc::List => Ok(BuiltinFunction::List),
+            BuiltinFunc::Map => Ok(BuiltinFunction::Map),
+            BuiltinFunc::ListGet => Ok(BuiltinFunction::ListGet),
+            BuiltinFunc::ListLen => Ok(BuiltinFunction::ListLen),
+            BuiltinFunc::MapGet => Ok(BuiltinFunction::MapGet),
+            BuiltinFunc::ListElem => Ok(BuiltinFunction::ListElem),
+            BuiltinFunc::MapKey => Ok(BuiltinFunction::MapKey),
+            // JSON functions
+            BuiltinFunc::JsonParse => Ok(BuiltinFunction::JsonParse),
+            BuiltinFunc::JsonExtract => Ok(BuiltinFunction::JsonExtract),
+            BuiltinFunc::JsonType => Ok(BuiltinFunction::JsonType),
+            BuiltinFunc::JsonArrayElements => Ok(BuiltinFunction::JsonArrayElements),
+            // Bytes functions
+            BuiltinFunc::Hex => Ok(BuiltinFunction::Hex),
+            BuiltinFunc::Base64 => Ok(BuiltinFunction::Base64),
+            BuiltinFunc::ToHex => Ok(BuiltinFunction::ToHex),
+            BuiltinFunc::ToBase64 => Ok(BuiltinFunction::ToBase64),
+            BuiltinFunc::BytesLen => Ok(BuiltinFunction::BytesLen),
+            BuiltinFunc::BytesSlice => Ok(BuiltinFunction::BytesSlice),
             // Quantization functions
             BuiltinFunc::QuantizeLinear => Ok(BuiltinFunction::QuantizeLinear),
             BuiltinFunc::QuantizeSymmetric => Ok(BuiltinFunction::QuantizeSymmetric),
@@ -755,13 +747,11 @@ impl IRBuilder {
             }
             Term::FunctionCall(func, args) => {
                 // Validate argument count
-                let expected_arity = func.arity();
-                if args.len() != expected_arity {
+                func.check_arity(args.len())?;
+                if func.is_generator() {
                     return Err(format!(
-                        "Function '{}' requires {} argument(s), but {} provided",
-                        func.as_str(),
-                        expected_arity,
-                        args.len()
+                        "Generator '{}' must be assigned directly to a variable",
+                        func.as_str()
                     ));
                 }
                 let ir_func = Self::ast_func_to_ir_func(func)?;
@@ -1388,12 +1378,6 @@ impl IRBuilder {
         let input_schema = input.output_schema();
         let head = &rule.head;
 
-        if std::env::var("IL_DEBUG").is_ok() {
-            eprintln!("DEBUG build_projection_with_computed:");
-            eprintln!("  input_schema = {input_schema:?}");
-            eprintln!("  head = {:?}", head.args);
-        }
-
         // Collect: which head terms are variables (project) vs computed (arithmetic/constant)
         let mut compute_expressions: Vec<(String, IRExpression)> = Vec::new();
         let mut final_projection: Vec<usize> = Vec::new();
@@ -1411,9 +1395,6 @@ impl IRBuilder {
                     })?;
                     final_projection.push(pos);
                     final_output_schema.push(v.clone());
-                    if std::env::var("IL_DEBUG").is_ok() {
-                        eprintln!("  head[{head_idx}] Variable({v}) -> project col {pos}");
-                    }
                 }
                 Term::Arithmetic(expr) => {
                     // Convert AST expression to IR expression
@@ -1428,11 +1409,6 @@ impl IRBuilder {
                     extended_schema.push(col_name.clone());
                     final_projection.push(computed_col_idx);
                     final_output_schema.push(col_name.clone());
-                    if std::env::var("IL_DEBUG").is_ok() {
-                        eprintln!(
-                            "  head[{head_idx}] Arithmetic -> compute col {computed_col_idx} ({col_name})"
-                        );
-                    }
                 }
                 Term::Constant(val) => {
                     // Constants in head are computed as constant columns
@@ -1447,11 +1423,6 @@ impl IRBuilder {
                     extended_schema.push(col_name.clone());
                     final_projection.push(computed_col_idx);
                     final_output_schema.push(col_name.clone());
-                    if std::env::var("IL_DEBUG").is_ok() {
-                        eprintln!(
-                            "  head[{head_idx}] Constant({val}) -> compute col {computed_col_idx} ({col_name})"
-                        );
-                    }
                 }
                 Term::FloatConstant(val) => {
                     // Float constants in head are computed as constant columns
@@ -1466,11 +1437,6 @@ impl IRBuilder {
                     extended_schema.push(col_name.clone());
                     final_projection.push(computed_col_idx);
                     final_output_schema.push(col_name.clone());
-                    if std::env::var("IL_DEBUG").is_ok() {
-                        eprintln!(
-                            "  head[{head_idx}] FloatConstant({val}) -> compute col {computed_col_idx} ({col_name})"
-                        );
-                    }
                 }
                 Term::StringConstant(s) => {
                     // String constants in head are computed as constant columns
@@ -1485,11 +1451,6 @@ impl IRBuilder {
                     extended_schema.push(col_name.clone());
                     final_projection.push(computed_col_idx);
                     final_output_schema.push(col_name.clone());
-                    if std::env::var("IL_DEBUG").is_ok() {
-                        eprintln!(
-                            "  head[{head_idx}] StringConstant({s}) -> compute col {computed_col_idx} ({col_name})"
-                        );
-                    }
                 }
                 Term::BoolConstant(b) => {
                     // Bool constants in head are computed as constant columns
@@ -1504,18 +1465,10 @@ impl IRBuilder {
                     extended_schema.push(col_name.clone());
                     final_projection.push(computed_col_idx);
                     final_output_schema.push(col_name.clone());
-                    if std::env::var("IL_DEBUG").is_ok() {
-                        eprintln!(
-                            "  head[{head_idx}] BoolConstant({b}) -> compute col {computed_col_idx} ({col_name})"
-                        );
-                    }
                 }
                 Term::Placeholder => {
                     // Placeholders in head are semantically invalid (head defines output
                     // columns, not "don't care" positions). Skip them gracefully.
-                    if std::env::var("IL_DEBUG").is_ok() {
-                        eprintln!("  head[{head_idx}] Placeholder -> SKIPPED (invalid in head)");
-                    }
                     // Don't add anything to projection - placeholders in head are ignored
                     continue;
                 }
@@ -1527,11 +1480,14 @@ impl IRBuilder {
             }
         }
 
-        if std::env::var("IL_DEBUG").is_ok() {
-            eprintln!("  extended_schema = {extended_schema:?}");
-            eprintln!("  final_projection = {final_projection:?}");
-            eprintln!("  final_output_schema = {final_output_schema:?}");
-        }
+        trace!(
+            ?input_schema,
+            head = ?head.args,
+            ?extended_schema,
+            ?final_projection,
+            ?final_output_schema,
+            "ir_build_computed_projection"
+        );
 
         // Build the Compute node if we have expressions
         let computed = if compute_expressions.is_empty() {
@@ -3261,6 +3217,10 @@ mod tests {
             IRBuilder::ast_func_to_ir_func(&BuiltinFunc::TimeAdd),
             Ok(BuiltinFunction::TimeAdd)
         ));
+        assert!(matches!(
+            IRBuilder::ast_func_to_ir_func(&BuiltinFunc::Date),
+            Ok(BuiltinFunction::Date)
+        ));
     }
 
     #[test]
@@ -3,7 +3,9 @@
 //! Reorders multi-way joins via MST to minimize intermediate result sizes.
 //!
 //! 1. Build join graph: nodes = relations, edges = shared variables
-//! 2. Compute Maximum Spanning Tree (weight = # shared vars)
+//! 2. Compute Maximum Spanning Tree (weight = # shared vars; joins that bind a
+//!    declared primary key or unique column of a side are taken first, since
+//!    they cannot grow the intermediate result)
 //! 3. Try each node as root, pick the one minimizing structural cost
 //!    (max live variables at any intermediate step)
 //! 4. Rebuild the IR tree in optimal join order
@@ -11,10 +13,23 @@
 //! ```text
 //! IRNode with Joins -> [Join Planning] -> Reordered IRNode -> Later optimizations
 //! ```
+//!
+//! ## Physical Join Selection
+//!
+//! `select_join_algorithm` picks how each join executes once its inputs are
+//! known to the code generator:
+//!
+//! - **Merge**: both sides are arranged (sorted) by key and the arrangements
+//!   are merged. Chosen when a side is already arranged by the join key by a
+//!   previous operator, and whenever no side is small enough to hash.
+//! - **Hash**: a small side whose rows are known while the dataflow is built
+//!   is loaded into a hash table, and the other side streams through it
+//!   without being arranged.
 
 use crate::ir::IRNode;
 use std::cmp::Ordering;
 use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::sync::Arc;
 
 /// Node in the join graph representing a relation/scan
 #[derive(Debug, Clone)]
@@ -23,6 +38,8 @@ pub struct JoinGraphNode {
     pub variables: HashSet<String>,
     /// The original IR node (Scan)
     pub ir_node: IRNode,
+    /// Variable sets bound to a primary key or unique column of the relation
+    pub unique_keys: Vec<HashSet<String>>,
 }
 
 /// Edge in the join graph representing shared variables between relations
@@ -34,20 +51,25 @@ pub struct JoinGraphEdge {
     pub to: usize,
     /// Weight = number of shared variables
     pub weight: usize,
+    /// The shared variables cover a unique key of one side, so each tuple of
+    /// the other side matches at most one tuple and the join cannot grow
+    pub key_join: bool,
 }
 
 impl Eq for JoinGraphEdge {}
 
 impl PartialEq for JoinGraphEdge {
     fn eq(&self, other: &Self) -> bool {
-        self.weight == other.weight
+        self.key_join == other.key_join && self.weight == other.weight
     }
 }
 
 impl Ord for JoinGraphEdge {
     fn cmp(&self, other: &Self) -> Ordering {
-        // Higher weight = higher priority (for max spanning tree)
-        self.weight.cmp(&other.weight)
+        // Key joins first, then higher weight (for max spanning tree)
+        self.key_join
+            .cmp(&other.key_join)
+            .then(self.weight.cmp(&other.weight))
     }
 }
 
@@ -80,15 +102,34 @@ impl JoinGraph {
 
     /// Build join graph from IR nodes (extracts scans and analyzes joins)
     pub fn from_ir(ir: &IRNode) -> Self {
+        Self::from_ir_with_keys(ir, &HashMap::new())
+    }
+
+    /// Build join graph from IR nodes, marking joins on the unique keys
+    /// (column indices per relation) of a side as key joins
+    pub fn from_ir_with_keys(ir: &IRNode, unique_keys: &HashMap<String, Vec<Vec<usize>>>) -> Self {
         let mut graph = JoinGraph::new();
         let scans = Self::extract_scans(ir);
 
         // Add nodes
-        for (_relation, schema, ir_node) in &scans {
+        for (relation, schema, ir_node) in &scans {
             let variables: HashSet<String> = schema.iter().cloned().collect();
+            let keys = unique_keys
+                .get(relation)
+                .map(|keys| {
+                    keys.iter()
+                        .filter_map(|key| {
+                            key.iter()
+                                .map(|&i| schema.get(i).cloned())
+                                .collect::<Option<HashSet<String>>>()
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
             graph.nodes.push(JoinGraphNode {
                 variables,
                 ir_node: ir_node.clone(),
+                unique_keys: keys,
             });
         }
 
@@ -102,10 +143,17 @@ impl JoinGraph {
                     .collect();
 
                 if !shared.is_empty() {
+                    let key_join = [i, j].iter().any(|&n| {
+                        graph.nodes[n]
+                            .unique_keys
+                            .iter()
+                            .any(|key| key.is_subset(&shared))
+                    });
                     let edge = JoinGraphEdge {
                         from: i,
                         to: j,
                         weight: shared.len(),
+                        key_join,
                     };
                     graph.add_edge(edge);
                 }
@@ -157,11 +205,13 @@ impl JoinGraph {
                 Self::extract_scans_recursive(left, scans);
                 Self::extract_scans_recursive(right, scans);
             }
-            IRNode::Antijoin { left, right, .. } => {
+            IRNode::Antijoin { left, right, .. } | IRNode::Semijoin { left, right, .. } => {
                 Self::extract_scans_recursive(left, scans);
                 Self::extract_scans_recursive(right, scans);
             }
-            IRNode::Distinct { input } => Self::extract_scans_recursive(input, scans),
+            IRNode::Distinct { input } | IRNode::Sort { input, .. } => {
+                Self::extract_scans_recursive(input, scans);
+            }
             IRNode::Union { inputs } => {
                 for input in inputs {
                     Self::extract_scans_recursive(input, scans);
@@ -466,6 +516,8 @@ pub struct JoinPlanningStats {
 pub struct JoinPlanner {
     /// Whether to enable join reordering
     enable_reordering: bool,
+    /// Primary key and unique column indices per relation
+    unique_keys: Arc<HashMap<String, Vec<Vec<usize>>>>,
 }
 
 impl JoinPlanner {
@@ -473,9 +525,16 @@ impl JoinPlanner {
     pub fn new() -> Self {
         JoinPlanner {
             enable_reordering: true,
+            unique_keys: Arc::default(),
         }
     }
 
+    /// Prefer joining relations on their declared unique keys (builder pattern)
+    pub fn with_unique_keys(mut self, unique_keys: Arc<HashMap<String, Vec<Vec<usize>>>>) -> Self {
+        self.unique_keys = unique_keys;
+        self
+    }
+
     /// Enable or disable join reordering
     pub fn set_reordering(&mut self, enable: bool) {
         self.enable_reordering = enable;
@@ -512,7 +571,7 @@ impl JoinPlanner {
         }
 
         // Build join graph
-        let graph = JoinGraph::from_ir(&ir);
+        let graph = JoinGraph::from_ir_with_keys(&ir, &self.unique_keys);
 
         // If graph has only one node or is not connected, return unchanged
         if graph.nodes.len() <= 1 || !graph.is_connected() {
@@ -566,11 +625,12 @@ impl JoinPlanner {
         match ir {
             IRNode::Join { .. } => true,
             IRNode::Antijoin { left, right, .. } => Self::has_joins(left) || Self::has_joins(right),
+            IRNode::Semijoin { left, right, .. } => Self::has_joins(left) || Self::has_joins(right),
             IRNode::Scan { .. } => false,
             IRNode::HnswScan { .. } => false,
             IRNode::Map { input, .. } => Self::has_joins(input),
             IRNode::Filter { input, .. } => Self::has_joins(input),
-            IRNode::Distinct { input } => Self::has_joins(input),
+            IRNode::Distinct { input } | IRNode::Sort { input, .. } => Self::has_joins(input),
             IRNode::Union { inputs } => inputs.iter().any(Self::has_joins),
             IRNode::Aggregate { input, .. } => Self::has_joins(input),
             IRNode::Compute { input, .. } => Self::has_joins(input),
@@ -586,6 +646,9 @@ impl JoinPlanner {
     fn has_antijoin(ir: &IRNode) -> bool {
         match ir {
             IRNode::Antijoin { .. } => true,
+            // Semijoins are placed by the reducer for a fixed join shape;
+            // reordering underneath them would invalidate their keys.
+            IRNode::Semijoin { .. } => true,
             IRNode::Join { left, right, .. } => {
                 Self::has_antijoin(left) || Self::has_antijoin(right)
             }
@@ -593,7 +656,7 @@ impl JoinPlanner {
             IRNode::HnswScan { .. } => false,
             IRNode::Map { input, .. } => Self::has_antijoin(input),
             IRNode::Filter { input, .. } => Self::has_antijoin(input),
-            IRNode::Distinct { input } => Self::has_antijoin(input),
+            IRNode::Distinct { input } | IRNode::Sort { input, .. } => Self::has_antijoin(input),
             IRNode::Union { inputs } => inputs.iter().any(Self::has_antijoin),
             IRNode::Aggregate { input, .. } => Self::has_antijoin(input),
             IRNode::Compute { input, .. } => Self::has_antijoin(input),
@@ -749,6 +812,13 @@ impl JoinPlanner {
                     input: Box::new(inner),
                 }
             }
+            IRNode::Sort { input, order_by } => {
+                let inner = self.preserve_top_operations(input, new_joins);
+                IRNode::Sort {
+                    input: Box::new(inner),
+                    order_by: order_by.clone(),
+                }
+            }
             IRNode::Aggregate {
                 input,
                 group_by,
@@ -786,7 +856,7 @@ impl JoinPlanner {
             }
             // If we hit a join or scan, return the new joins  -  but if the
             // output schema order changed, add a Map to restore the original order.
-            IRNode::Join { .. } | IRNode::Antijoin { .. } => {
+            IRNode::Join { .. } | IRNode::Antijoin { .. } | IRNode::Semijoin { .. } => {
                 let old_schema = original.output_schema();
                 let new_schema = new_joins.output_schema();
                 if old_schema == new_schema {
@@ -997,12 +1067,12 @@ impl JoinPlanner {
             IRNode::Join { left, right, .. } => {
                 1 + Self::count_joins(left) + Self::count_joins(right)
             }
-            IRNode::Antijoin { left, right, .. } => {
+            IRNode::Antijoin { left, right, .. } | IRNode::Semijoin { left, right, .. } => {
                 1 + Self::count_joins(left) + Self::count_joins(right)
             }
             IRNode::Map { input, .. } => Self::count_joins(input),
             IRNode::Filter { input, .. } => Self::count_joins(input),
-            IRNode::Distinct { input } => Self::count_joins(input),
+            IRNode::Distinct { input } | IRNode::Sort { input, .. } => Self::count_joins(input),
             IRNode::Union { inputs } => inputs.iter().map(Self::count_joins).sum(),
             IRNode::Aggregate { input, .. } => Self::count_joins(input),
             IRNode::Scan { .. } => 0,
@@ -1022,6 +1092,80 @@ impl Default for JoinPlanner {
     }
 }
 
+/// Physical algorithm of one join
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinAlgorithm {
+    /// Arrange both sides by key and merge the sorted arrangements
+    Merge,
+    /// Hash the `build` side's tuples by key and probe with the other side
+    Hash {
+        /// Side loaded into the hash table
+        build: JoinSide,
+    },
+}
+
+/// One side of a binary join
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinSide {
+    /// The left input
+    Left,
+    /// The right input
+    Right,
+}
+
+/// What the planner knows about one join input when choosing its algorithm
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JoinInputInfo {
+    /// Upper bound on the input's rows when they are known while the
+    /// dataflow is built (`None` for derived or recursive inputs)
+    pub static_rows: Option<usize>,
+    /// Whether a previous operator already arranged the input by the join key
+    pub arranged_by_key: bool,
+}
+
+/// Thresholds of physical join selection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JoinAlgorithmConfig {
+    /// Largest build side of a hash join, in rows (0 = always merge)
+    pub max_hash_build_rows: usize,
+}
+
+impl Default for JoinAlgorithmConfig {
+    fn default() -> Self {
+        JoinAlgorithmConfig {
+            max_hash_build_rows: 10_000,
+        }
+    }
+}
+
+/// Choose the physical algorithm of a join from what is known about its
+/// inputs.
+///
+/// An existing arrangement is reused by a merge join rather than rebuilt as
+/// a hash table. Otherwise the smaller of the hashable sides (known rows
+/// within `max_hash_build_rows`) is hashed; with none, the join merges.
+pub fn select_join_algorithm(
+    left: &JoinInputInfo,
+    right: &JoinInputInfo,
+    config: &JoinAlgorithmConfig,
+) -> JoinAlgorithm {
+    if config.max_hash_build_rows == 0 || left.arranged_by_key || right.arranged_by_key {
+        return JoinAlgorithm::Merge;
+    }
+    let hashable = |input: &JoinInputInfo| {
+        input
+            .static_rows
+            .filter(|&rows| rows <= config.max_hash_build_rows)
+    };
+    let build = match (hashable(left), hashable(right)) {
+        (Some(left_rows), Some(right_rows)) if left_rows < right_rows => JoinSide::Left,
+        (_, Some(_)) => JoinSide::Right,
+        (Some(_), None) => JoinSide::Left,
+        (None, None) => return JoinAlgorithm::Merge,
+    };
+    JoinAlgorithm::Hash { build }
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
@@ -1270,16 +1414,19 @@ mod tests {
             from: 0,
             to: 1,
             weight: 2,
+            key_join: false,
         };
         let edge2 = JoinGraphEdge {
             from: 0,
             to: 2,
             weight: 3,
+            key_join: false,
         };
         let edge3 = JoinGraphEdge {
             from: 1,
             to: 2,
             weight: 2,
+            key_join: false,
         };
 
         // Higher weight = higher priority (for max spanning tree)
@@ -1288,6 +1435,33 @@ mod tests {
         assert!(edge1.partial_cmp(&edge2).is_some());
     }
 
+    #[test]
+    fn test_key_joins_preferred_in_mst() {
+        let r = make_scan("R", &["a", "b"]);
+        let s = make_scan("S", &["b", "c"]);
+        let t = make_scan("T", &["a", "c"]);
+        let ir = make_join(make_join(r, s, "b"), t, "a");
+
+        let plain = JoinGraph::from_ir(&ir);
+        assert!(plain.edges.iter().all(|e| !e.key_join));
+
+        // T.a is T's primary key: the R-T join on `a` is a key join
+        let keys = HashMap::from([("T".to_string(), vec![vec![0]])]);
+        let graph = JoinGraph::from_ir_with_keys(&ir, &keys);
+        let key_edges: Vec<(usize, usize)> = graph
+            .edges
+            .iter()
+            .filter(|e| e.key_join)
+            .map(|e| (e.from, e.to))
+            .collect();
+        assert_eq!(key_edges, vec![(0, 2)]);
+        assert!(graph.compute_mst().contains(&(0, 2)));
+
+        let planner = JoinPlanner::new().with_unique_keys(Arc::new(keys));
+        let planned = planner.plan_joins(ir);
+        assert_eq!(planned.output_schema().len(), 3);
+    }
+
     #[test]
     fn test_join_planner_default() {
         let planner = JoinPlanner::default();
@@ -1697,4 +1871,51 @@ mod tests {
         // Root has one child (node 1), join_order should contain both nodes
         assert_eq!(jst.join_order.len(), 2);
     }
+
+    #[test]
+    fn test_select_join_algorithm() {
+        let config = JoinAlgorithmConfig {
+            max_hash_build_rows: 100,
+        };
+        let rows = |rows| JoinInputInfo {
+            static_rows: Some(rows),
+            arranged_by_key: false,
+        };
+        let derived = JoinInputInfo::default();
+
+        assert_eq!(
+            select_join_algorithm(&rows(10), &derived, &config),
+            JoinAlgorithm::Hash {
+                build: JoinSide::Left
+            }
+        );
+        assert_eq!(
+            select_join_algorithm(&rows(50), &rows(20), &config),
+            JoinAlgorithm::Hash {
+                build: JoinSide::Right
+            }
+        );
+        assert_eq!(
+            select_join_algorithm(&rows(500), &derived, &config),
+            JoinAlgorithm::Merge
+        );
+
+        // An input a previous operator arranged by key is merged as is
+        let arranged = JoinInputInfo {
+            static_rows: Some(1000),
+            arranged_by_key: true,
+        };
+        assert_eq!(
+            select_join_algorithm(&rows(10), &arranged, &config),
+            JoinAlgorithm::Merge
+        );
+
+        let merge_only = JoinAlgorithmConfig {
+            max_hash_build_rows: 0,
+        };
+        assert_eq!(
+            select_join_algorithm(&rows(0), &rows(0), &merge_only),
+            JoinAlgorithm::Merge
+        );
+    }
 }
@@ -0,0 +1,178 @@
+//! JSON operations behind the `json_*` builtins.
+//!
+//! `Value::Json` keeps documents as text; these helpers parse on demand,
+//! walk paths and convert results back into `Value`s.
+
+use crate::value::Value;
+use serde_json::Value as JsonValue;
+
+/// One step of a JSON path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    /// Object member: `.name` or `["name"]`
+    Key(String),
+    /// Array element: `[0]`
+    Index(usize),
+}
+
+/// Parse a JSON path such as `$.user.tags[0]`.
+///
+/// The leading `$` is optional, so `user.tags[0]` is the same path.
+/// Keys containing dots or brackets can be quoted: `$["a.b"]`.
+pub fn parse_path(path: &str) -> Result<Vec<PathSegment>, String> {
+    let path = path.trim();
+    let rest = path.strip_prefix('$').unwrap_or(path);
+    let mut segments = Vec::new();
+    let mut chars = rest.chars().peekable();
+
+    // A path without `$` may start directly with a key
+    let mut expect_key = !rest.is_empty() && !rest.starts_with(['.', '[']);
+    while expect_key || chars.peek().is_some() {
+        if !expect_key {
+            match chars.next() {
+                Some('.') => {}
+                Some('[') => {
+                    let mut inner = String::new();
+                    for c in chars.by_ref() {
+                        if c == ']' {
+                            break;
+                        }
+                        inner.push(c);
+                    }
+                    let inner = inner.trim();
+                    if let Some(key) = inner.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+                        segments.push(PathSegment::Key(key.to_string()));
+                    } else {
+                        let index = inner.parse().map_err(|_| {
+                            format!("Invalid JSON path index '[{inner}]' in '{path}'")
+                        })?;
+                        segments.push(PathSegment::Index(index));
+                    }
+                    continue;
+                }
+                Some(c) => return Err(format!("Unexpected '{c}' in JSON path '{path}'")),
+                None => break,
+            }
+        }
+        expect_key = false;
+        let mut key = String::new();
+        while let Some(&c) = chars.peek() {
+            if c == '.' || c == '[' {
+                break;
+            }
+            key.push(c);
+            chars.next();
+        }
+        if key.is_empty() {
+            return Err(format!("Empty key in JSON path '{path}'"));
+        }
+        segments.push(PathSegment::Key(key));
+    }
+    Ok(segments)
+}
+
+/// Follow `path` into `doc`; `None` if any step is missing
+pub fn extract<'a>(doc: &'a JsonValue, path: &[PathSegment]) -> Option<&'a JsonValue> {
+    path.iter().try_fold(doc, |node, segment| match segment {
+        PathSegment::Key(key) => node.get(key),
+        PathSegment::Index(index) => node.get(index),
+    })
+}
+
+/// Parse the document held by a `Json` or `String` value
+pub fn parse(value: &Value) -> Option<JsonValue> {
+    let text = match value {
+        Value::Json(text) | Value::String(text) => text,
+        _ => return None,
+    };
+    serde_json::from_str(text).ok()
+}
+
+/// Convert a JSON node into a `Value`: scalars become native values,
+/// objects and arrays stay JSON
+pub fn to_value(node: &JsonValue) -> Value {
+    match node {
+        JsonValue::Null => Value::Null,
+        JsonValue::Bool(b) => Value::Bool(*b),
+        JsonValue::Number(n) => n.as_i64().map_or_else(
+            || Value::Float64(n.as_f64().unwrap_or(f64::NAN)),
+            Value::Int64,
+        ),
+        JsonValue::String(s) => Value::string(s),
+        JsonValue::Array(_) | JsonValue::Object(_) => Value::from_json_doc(node),
+    }
+}
+
+/// JSON type name of a node, as returned by `json_type`
+pub fn type_name(node: &JsonValue) -> &'static str {
+    match node {
+        JsonValue::Null => "null",
+        JsonValue::Bool(_) => "boolean",
+        JsonValue::Number(_) => "number",
+        JsonValue::String(_) => "string",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "object",
+    }
+}
+
+/// Elements of a JSON array as values; empty for anything else
+pub fn array_elements(node: &JsonValue) -> Vec<Value> {
+    match node {
+        JsonValue::Array(items) => items.iter().map(to_value).collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_path() {
+        let expected = vec![
+            PathSegment::Key("user".to_string()),
+            PathSegment::Key("tags".to_string()),
+            PathSegment::Index(1),
+        ];
+        assert_eq!(parse_path("$.user.tags[1]").unwrap(), expected);
+        assert_eq!(parse_path("user.tags[1]").unwrap(), expected);
+        assert_eq!(parse_path("$").unwrap(), vec![]);
+        assert_eq!(
+            parse_path(r#"$["a.b"]"#).unwrap(),
+            vec![PathSegment::Key("a.b".to_string())]
+        );
+        assert!(parse_path("$.a[x]").is_err());
+        assert!(parse_path("$..a").is_err());
+    }
+
+    #[test]
+    fn test_extract_and_convert() {
+        let doc: JsonValue =
+            serde_json::from_str(r#"{"user": {"id": 7, "tags": ["a", "b"], "score": 1.5}}"#)
+                .unwrap();
+        let get = |p: &str| extract(&doc, &parse_path(p).unwrap()).map(to_value);
+        assert_eq!(get("$.user.id"), Some(Value::Int64(7)));
+        assert_eq!(get("$.user.score"), Some(Value::Float64(1.5)));
+        assert_eq!(get("$.user.tags[1]"), Some(Value::string("b")));
+        assert_eq!(
+            get("$.user.tags"),
+            Some(Value::json(r#"["a","b"]"#).unwrap())
+        );
+        assert_eq!(get("$.user.missing"), None);
+        assert_eq!(get("$.user.tags[5]"), None);
+    }
+
+    #[test]
+    fn test_type_name_and_elements() {
+        let doc: JsonValue = serde_json::from_str(r#"[1, "x", null, {"k": true}]"#).unwrap();
+        assert_eq!(type_name(&doc), "array");
+        let elems = array_elements(&doc);
+        assert_eq!(elems.len(), 4);
+        assert_eq!(elems[0], Value::Int64(1));
+        assert_eq!(elems[2], Value::Null);
+        assert_eq!(elems[3].data_type(), crate::value::DataType::Json);
+        assert!(array_elements(&JsonValue::Null).is_empty());
+        assert_eq!(type_name(&JsonValue::Null), "null");
+    }
+}
@@ -0,0 +1,114 @@
+//! Rotated JSON Lines Files
+//!
+//! Append-only logs written as JSON Lines, one record per line, shared by
+//! the statement audit log ([`crate::audit`]) and the change data capture
+//! file sink ([`crate::storage_engine::ChangeFeed`]).
+//!
+//! Records go to `<stem>.jsonl`. Once the file reaches `max_file_bytes` it
+//! is renamed to `<stem>.1.jsonl`, older files shift up (`<stem>.2.jsonl`,
+//! ...), and files past `max_files` are deleted.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+struct ActiveFile {
+    file: File,
+    len: u64,
+}
+
+/// Append-only JSON Lines files with size-based rotation
+pub struct JsonlLog {
+    dir: PathBuf,
+    stem: &'static str,
+    max_file_bytes: u64,
+    max_files: usize,
+    active: Mutex<ActiveFile>,
+}
+
+impl JsonlLog {
+    /// Open (or create) `<stem>.jsonl` in `dir`
+    pub fn open(
+        dir: PathBuf,
+        stem: &'static str,
+        max_file_bytes: u64,
+        max_files: usize,
+    ) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        let active = open_active(&dir, stem)?;
+        Ok(Self {
+            dir,
+            stem,
+            max_file_bytes,
+            max_files,
+            active: Mutex::new(active),
+        })
+    }
+
+    /// Directory holding the files
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Append `record` as one JSON line
+    pub fn append<T: Serialize>(&self, record: &T) -> io::Result<()> {
+        self.append_all(std::slice::from_ref(record))
+    }
+
+    /// Append `records`, one JSON line each, in a single write. The lines
+    /// stay together in one file: rotation happens before them, if they
+    /// would take the active file past `max_file_bytes`.
+    pub fn append_all<T: Serialize>(&self, records: &[T]) -> io::Result<()> {
+        let mut lines = Vec::new();
+        for record in records {
+            serde_json::to_writer(&mut lines, record)?;
+            lines.push(b'\n');
+        }
+        if lines.is_empty() {
+            return Ok(());
+        }
+        let mut active = self.active.lock();
+        if self.max_file_bytes > 0
+            && active.len > 0
+            && active.len + lines.len() as u64 > self.max_file_bytes
+        {
+            self.rotate()?;
+            *active = open_active(&self.dir, self.stem)?;
+        }
+        active.file.write_all(&lines)?;
+        active.len += lines.len() as u64;
+        Ok(())
+    }
+
+    /// Shift `<stem>.N.jsonl` to `<stem>.N+1.jsonl`, dropping the oldest,
+    /// then move the active file to `<stem>.1.jsonl`
+    fn rotate(&self) -> io::Result<()> {
+        let rotated = |n: usize| self.dir.join(format!("{}.{n}.jsonl", self.stem));
+        let active = self.dir.join(format!("{}.jsonl", self.stem));
+        if self.max_files == 0 {
+            return fs::remove_file(active);
+        }
+        match fs::remove_file(rotated(self.max_files)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        for n in (1..self.max_files).rev() {
+            if rotated(n).exists() {
+                fs::rename(rotated(n), rotated(n + 1))?;
+            }
+        }
+        fs::rename(active, rotated(1))
+    }
+}
+
+fn open_active(dir: &Path, stem: &str) -> io::Result<ActiveFile> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(format!("{stem}.jsonl")))?;
+    let len = file.metadata()?.len();
+    Ok(ActiveFile { file, len })
+}
@@ -24,6 +24,8 @@
 //!     |
 //! [Basic Optimizer (M06)]       -> Optimized IRNode
 //!     |
+//! [Semijoin Reduction]          -> Skewed joins pre-filtered (optional)
+//!     |
 //! [Code Generator (M11)]        -> DD Code + Execution
 //!     |
 //! Results
@@ -91,6 +93,7 @@
 //! | `optimizer` | Basic IR optimizations |
 //! | `join_planning` | Join order optimization |
 //! | `sip_rewriting` | SIP semijoin reduction |
+//! | `semijoin_reduction` | Size-based semijoin reducer for skewed joins |
 //! | `subplan_sharing` | Common subexpression elimination |
 //! | `boolean_specialization` | Semiring selection |
 //! | `code_generator` | IR -> Differential Dataflow |
@@ -107,6 +110,7 @@ pub mod hnsw_index; // HNSW vector index implementation
 pub mod incremental;
 pub mod index_manager; // Index manager for vector similarity search
 pub mod ir;
+pub mod lsh_index; // Materialized LSH bucket index
 pub mod session; // Session manager for ephemeral triggers persistent
 
 // Re-export types from internal modules
@@ -114,7 +118,7 @@ pub use crate::ast::builders::{fact, simple_rule, AtomBuilder, RuleBuilder};
 pub use crate::ast::{
     AggregateFunc, ArithExpr, ArithOp, Atom, BodyPredicate, BuiltinFunc, Program, Rule, Term,
 };
-pub use crate::ir::{IRNode, Predicate};
+pub use crate::ir::{IRNode, Predicate, SerializedPlan};
 
 // Internal modules
 mod boolean_specialization; // Semiring selection
@@ -125,6 +129,7 @@ mod magic_sets; // Magic Sets demand-driven rewriting for recursive queries
 mod optimizer; // Basic IR optimizations
 pub mod parser; // IQL parsing & AST construction
 pub mod rule_catalog; // Rule catalog for persistent rules
+mod semijoin_reduction; // IR-level semijoin reducer for skewed joins
 pub mod semiring_types; // Diff type abstraction: BooleanDiff, MinDiff, MaxDiff
 mod sip_rewriting; // AST-level semijoin reduction
 pub mod statement; // IQL-native statement parser
@@ -145,10 +150,20 @@ pub mod protocol; // InputLayer RPC protocol (server/client)
 // Execution hardening
 pub mod execution; // Query timeout, resource limits, caching
 
+// Observability
+pub mod audit; // Statement audit log: rotated JSON Lines files and the _audit KG
+pub mod jsonl_log; // Rotated JSON Lines files shared by the audit log and CDC
+pub mod logging; // Per-module log filter, adjustable at runtime with .log
+pub mod metrics; // Process-wide counters and histograms for /metrics
+pub mod slow_query_log; // Slow queries and their plans in _audit.slow_queries
+#[cfg(feature = "otel")]
+pub mod telemetry; // OTLP span export
+
 // Value type system (production-grade arbitrary arity tuples)
 pub mod value;
 
 // Re-export value types for convenience
+pub use value::coercion::CoercionMode;
 pub use value::{DataType, SchemaValidationError, Tuple, TupleSchema, Value};
 
 // Schema validation module
@@ -156,8 +171,9 @@ pub mod schema;
 
 // Re-export schema types for convenience
 pub use schema::{
-    catalog::SchemaError, ColumnSchema, RelationSchema, SchemaCatalog, SchemaType,
-    ValidationEngine, ValidationError, Violation,
+    catalog::SchemaError, CheckConstraint, ColumnDefault, ColumnSchema, ConflictAction,
+    DefaultFunction, FailureAction, RelationSchema, SchemaCatalog, SchemaMigration, SchemaType,
+    ValidationEngine, ValidationError, ValidationPolicy, ValidationTiming, Violation,
 };
 
 // Vector operations (distance functions, LSH, top-k)
@@ -202,16 +218,23 @@ pub use vector_ops::{
 // Temporal operations (time decay, temporal predicates, interval operations)
 pub mod temporal_ops;
 
+// JSON operations (path extraction for `json_*` builtins)
+pub mod json_ops;
+
+// Binary blob operations (hex/base64 codecs, slicing)
+pub mod bytes_ops;
+
 // Optimization infrastructure (reserved for future cost-based planning)
 pub mod bloom_filter; // Bloom filters for predicate transfer optimization
 pub mod hash_index; // Hash indexes for future cost-based join planning
-pub mod statistics; // Statistics collection for future selectivity estimation
+pub mod statistics; // Column statistics collected by `analyze`
 
 // Explainability
 pub mod provenance; // Why-provenance proof trees and negative explanations
 
 // Utilities
 mod catalog;
+mod equality_saturation; // E-graph based alternative to the fixed-order passes
 mod pipeline_trace;
 mod recursion;
 #[cfg(test)]
@@ -219,25 +242,34 @@ mod test_arithmetic;
 
 // Re-export public types
 pub use catalog::Catalog;
-pub use code_generator::CodeGenerator;
+pub use code_generator::{CodeGenerator, JoinPrefilter, PrefilterMetrics};
 pub use config::{Config, DurabilityMode};
+pub use equality_saturation::EqualitySaturation;
 pub use ir_builder::IRBuilder;
 pub use optimizer::Optimizer;
 pub use pipeline_trace::{OptimizationStats, PipelineTrace};
+pub use semijoin_reduction::SemijoinReducer;
 pub use storage_engine::StorageEngine;
 
-// Re-export storage utilities (Parquet and CSV)
+// Re-export storage utilities (Parquet, CSV, JSON Lines, Avro and SQLite)
+#[cfg(feature = "postgres")]
+pub use storage::{copy_from_postgres, PostgresOptions, PostgresSource};
 pub use storage::{
-    load_from_csv, load_from_csv_with_options, load_from_parquet, save_to_csv,
-    save_to_csv_with_options, save_to_parquet, CsvOptions, StorageError, StorageResult,
+    infer_csv_schema, infer_jsonl_schema, load_csv_parallel, load_from_avro, load_from_csv,
+    load_from_csv_inferred, load_from_csv_with_options, load_from_jsonl, load_from_parquet,
+    load_from_sqlite, save_to_avro, save_to_csv, save_to_csv_with_options, save_to_jsonl,
+    save_to_parquet, BulkLoadOptions, BulkLoadProgress, BulkLoadReport, CsvInference, CsvOptions,
+    CsvRowError, JsonlOptions, RowErrorPolicy, SqliteOptions, StorageError, StorageResult,
 };
 
 // Re-export execution utilities (timeout)
-pub use execution::{CancelHandle, ExecutionError, ExecutionResult, QueryTimeout, TimeoutError};
+pub use execution::{
+    CancelHandle, ExecutionError, ExecutionResult, QueryProgress, QueryTimeout, TimeoutError,
+};
 
 // Re-export optimization modules for extensibility
 pub use boolean_specialization::{BooleanSpecializer, SemiringAnnotation, SemiringType};
-pub use join_planning::JoinPlanner;
+pub use join_planning::{JoinAlgorithm, JoinAlgorithmConfig, JoinPlanner, JoinSide};
 pub use sip_rewriting::SipRewriter;
 pub use subplan_sharing::SubplanSharer;
 
@@ -254,7 +286,9 @@ pub use statement::{
 pub use parser::{parse_program, parse_rule};
 
 // Re-export rule catalog
-pub use rule_catalog::{validate_rule, validate_rules_stratification, RuleCatalog, RuleDefinition};
+pub use rule_catalog::{
+    validate_rule, validate_rules_stratification, RefreshPolicy, RuleCatalog, RuleDefinition,
+};
 
 // Re-export session types
 pub use session::{
@@ -265,9 +299,10 @@ pub use session::{
 // Re-export index types
 pub use hnsw_index::HnswIndex;
 pub use index_manager::{
-    DistanceMetric, HnswConfig, Index, IndexManager, IndexStats, IndexType, MaterializedIndex,
-    RegisteredIndex, TupleId,
+    DistanceMetric, HnswConfig, Index, IndexManager, IndexStats, IndexType, IndexedColumn,
+    LshConfig, MaterializedIndex, RegisteredIndex, TupleId,
 };
+pub use lsh_index::LshIndex;
 
 // Re-export recursion utilities
 pub use recursion::{
@@ -284,10 +319,11 @@ pub use recursion::{
     StratificationResult,
 };
 
+use rayon::prelude::*;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Instant;
-use tracing::info;
+use tracing::{debug, info, trace, Level};
 
 /// Configuration for advanced optimizations
 #[derive(Debug, Clone)]
@@ -308,6 +344,20 @@ pub struct OptimizationConfig {
     /// Restricts fixpoint computation to only demanded tuples when query has constants.
     /// Example: `?reach(1, Y)` only computes reachability from node 1, not the full TC.
     pub enable_magic_sets: bool,
+
+    /// Enable the semijoin reducer: when one join input is much larger than
+    /// the other, filter it by the small side's join keys before the join.
+    pub enable_semijoin_reduction: bool,
+
+    /// Enable the equality-saturation optimizer: explore join orders, filter
+    /// placements and union rewrites together in an e-graph and keep the
+    /// cheapest plan. Off by default; exploration cost grows with join count.
+    pub enable_equality_saturation: bool,
+
+    /// Backend that executes optimized plans. `None` selects the installed
+    /// timely cluster (`execution::install_cluster`), else the Differential
+    /// Dataflow backend (`execution::DifferentialBackend`).
+    pub execution_backend: Option<Arc<dyn execution::ExecutionBackend>>,
 }
 
 impl Default for OptimizationConfig {
@@ -326,6 +376,36 @@ impl Default for OptimizationConfig {
             enable_subplan_sharing: true,
             enable_boolean_specialization: true,
             enable_magic_sets: true,
+            // Semijoin reduction only fires on joins whose inputs have known,
+            // heavily skewed cardinalities, so it is a no-op for small data.
+            enable_semijoin_reduction: true,
+            enable_equality_saturation: false,
+            execution_backend: None,
+        }
+    }
+}
+
+/// A recursive component of a stratum, ready to run to fixpoint
+struct ComponentJob {
+    /// Each relation of the component with the plan that derives it
+    relations: Vec<(String, IRNode)>,
+    /// Head of a self-recursive rule; `None` for a mutually recursive group
+    recursive_relation: Option<String>,
+    options: execution::BackendOptions,
+}
+
+impl ComponentJob {
+    fn execute(
+        &self,
+        backend: &dyn execution::ExecutionBackend,
+        inputs: execution::BackendInputs,
+    ) -> Result<HashMap<String, Vec<Tuple>>, String> {
+        match (&self.recursive_relation, self.relations.as_slice()) {
+            (Some(relation), [(head, ir)]) => {
+                let tuples = backend.execute_recursive(ir, relation, inputs, &self.options)?;
+                Ok(HashMap::from([(head.clone(), tuples)]))
+            }
+            _ => backend.execute_mutual_recursive(&self.relations, inputs, &self.options),
         }
     }
 }
@@ -366,13 +446,43 @@ pub struct IQLEngine {
     /// Number of worker threads for parallel execution (1 = single-worker)
     num_workers: usize,
 
+    /// Whether independent recursive components of a stratum are
+    /// evaluated concurrently
+    parallel_components: bool,
+
     /// Maximum result rows returned per query (0 = unlimited)
     max_result_rows: usize,
 
+    /// Order of the query's result rows (empty = dataflow arrival order)
+    order_by: Vec<(usize, ir::SortDirection)>,
+
     /// Maximum query cost score (0 = unlimited). Queries exceeding this
     /// are rejected before DD execution.
     max_query_cost: u64,
 
+    /// Maximum bytes a query may hold in dataflow arrangements and derived
+    /// relations (0 = unlimited)
+    max_query_memory_bytes: usize,
+
+    /// Memory accounting of the running execution (`None` before the first)
+    query_memory: Option<execution::MemoryTracker>,
+
+    /// Cancellation token for executions started by this engine
+    cancel_handle: Option<execution::CancelHandle>,
+
+    /// Cancellation token of the running execution: `cancel_handle`, else
+    /// the calling thread's cancel flag
+    query_cancel: Option<execution::CancelHandle>,
+
+    /// Wall-clock limit per query in milliseconds (0 = unlimited)
+    query_timeout_ms: u64,
+
+    /// Wall-clock budget of the running execution (`None` = unlimited)
+    query_timeout: Option<execution::QueryTimeout>,
+
+    /// Pre-filtering of joins by the keys of their other side (`None` = disabled)
+    join_prefilter: Option<code_generator::JoinPrefilter>,
+
     /// Arc-wrapped shared input data (set by snapshot for zero-copy query execution)
     shared_input: Option<Arc<HashMap<String, Vec<Tuple>>>>,
 
@@ -386,8 +496,48 @@ pub struct IQLEngine {
         >,
     >,
 
+    /// Vector columns with an HNSW index: nearest-neighbor `top_k`
+    /// aggregates over them read only the index's candidates
+    indexed_columns: Arc<Vec<IndexedColumn>>,
+
     /// Timing mode for query profiling (default: Summary)
     timing_mode: execution::TimingMode,
+
+    /// Cross-query cache of materialized subplans (disabled when `None`).
+    /// Only subplans that read nothing but base relations are cached.
+    subplan_cache: Option<Arc<execution::SubplanCache>>,
+
+    /// Version of each base relation's current contents. When set, cache
+    /// keys include the versions of the relations a subplan reads, so a
+    /// cache shared by engines over different snapshots never serves a
+    /// result computed from other data.
+    relation_versions: Option<Arc<HashMap<String, u64>>>,
+
+    /// Whether executions also annotate derived tuples with why-provenance
+    provenance_mode: bool,
+
+    /// Why-provenance of derived relations from the last execution
+    /// (populated only in provenance mode)
+    why_provenance: HashMap<String, provenance::why::AnnotatedRelation>,
+
+    /// Rules as written, before SIP and magic-set rewriting, so that
+    /// derivation trees are explained in terms of the user's program
+    source_rules: Vec<ast::Rule>,
+
+    /// Primary key and unique column indices per relation, used by join
+    /// planning to prefer key joins
+    unique_keys: Arc<HashMap<String, Vec<Vec<usize>>>>,
+
+    /// Implicit type coercion mode; strict rejects mixed-type joins
+    coercion: value::coercion::CoercionMode,
+
+    /// Declared vector dimensions of base relation columns, checked
+    /// against vector function arguments when a program is parsed
+    vector_dims: Arc<schema::VectorDims>,
+
+    /// Distinct value counts per column of analyzed base relations, used
+    /// by equality saturation to estimate join sizes
+    distinct_counts: Arc<HashMap<String, Vec<usize>>>,
 }
 
 impl IQLEngine {
@@ -404,11 +554,30 @@ impl IQLEngine {
             shared_views: HashMap::new(),
             semiring_annotations: Vec::new(),
             num_workers: 1,
+            parallel_components: true,
             max_result_rows: 0,
+            order_by: Vec::new(),
             max_query_cost: 0,
+            max_query_memory_bytes: 0,
+            query_memory: None,
+            cancel_handle: None,
+            query_cancel: None,
+            query_timeout_ms: 0,
+            query_timeout: None,
+            join_prefilter: Some(code_generator::JoinPrefilter::default()),
             shared_input: None,
             hnsw_search_fn: None,
+            indexed_columns: Arc::default(),
             timing_mode: execution::TimingMode::default(),
+            subplan_cache: None,
+            relation_versions: None,
+            provenance_mode: false,
+            why_provenance: HashMap::new(),
+            source_rules: Vec::new(),
+            unique_keys: Arc::default(),
+            coercion: value::coercion::CoercionMode::default(),
+            vector_dims: Arc::default(),
+            distinct_counts: Arc::default(),
         }
     }
 
@@ -425,12 +594,105 @@ impl IQLEngine {
             shared_views: HashMap::new(),
             semiring_annotations: Vec::new(),
             num_workers: 1,
+            parallel_components: true,
             max_result_rows: 0,
+            order_by: Vec::new(),
             max_query_cost: 0,
+            max_query_memory_bytes: 0,
+            query_memory: None,
+            cancel_handle: None,
+            query_cancel: None,
+            query_timeout_ms: 0,
+            query_timeout: None,
+            join_prefilter: Some(code_generator::JoinPrefilter::default()),
             shared_input: None,
             hnsw_search_fn: None,
+            indexed_columns: Arc::default(),
             timing_mode: execution::TimingMode::default(),
+            subplan_cache: None,
+            relation_versions: None,
+            provenance_mode: false,
+            why_provenance: HashMap::new(),
+            source_rules: Vec::new(),
+            unique_keys: Arc::default(),
+            coercion: value::coercion::CoercionMode::default(),
+            vector_dims: Arc::default(),
+            distinct_counts: Arc::default(),
+        }
+    }
+
+    /// Track why-provenance in subsequent executions.
+    ///
+    /// After each execution, every derived tuple is annotated with the sets
+    /// of base facts that derive it; see `explain_tuple`. Annotation is a
+    /// second pass over the program, so it roughly doubles execution cost.
+    pub fn set_provenance_mode(&mut self, enabled: bool) {
+        self.provenance_mode = enabled;
+        if !enabled {
+            self.why_provenance.clear();
+        }
+    }
+
+    /// Why-provenance of `tuple` in `relation` from the last execution:
+    /// the minimal sets of base facts that derive it. A base fact is its
+    /// own explanation.
+    ///
+    /// Requires provenance mode (`set_provenance_mode`) to have been on
+    /// during that execution.
+    pub fn explain_tuple(
+        &self,
+        relation: &str,
+        tuple: &Tuple,
+    ) -> Result<provenance::why::WhyProvenance, String> {
+        if !self.provenance_mode {
+            return Err(
+                "Provenance mode is off; enable it before executing the program".to_string(),
+            );
+        }
+        if let Some(annotated) = self.why_provenance.get(relation) {
+            return annotated
+                .get(tuple)
+                .cloned()
+                .ok_or_else(|| format!("{relation}{tuple} was not derived"));
+        }
+        if self
+            .input_tuples
+            .get(relation)
+            .is_some_and(|tuples| tuples.contains(tuple))
+        {
+            return Ok(provenance::why::WhyProvenance::fact(relation, tuple));
         }
+        Err(format!(
+            "{relation}{tuple} is neither derived nor a base fact"
+        ))
+    }
+
+    /// Reconstruct one derivation tree for `tuple` in `relation`: the rule
+    /// that fired, its variable bindings, and the derivations of its body
+    /// facts, recursively down to base facts.
+    ///
+    /// Explains the most recently parsed program against the current base
+    /// facts, so provenance mode is not required. Render the tree with
+    /// `ProofTree::format_tree` for the REPL or `ProofTree::to_json` for
+    /// the GUI.
+    pub fn why(
+        &self,
+        relation: &str,
+        tuple: &Tuple,
+    ) -> Result<provenance::proof_tree::ProofTree, String> {
+        if self.program.is_none() {
+            return Err("No program has been parsed".to_string());
+        }
+        let config = provenance::ProofConfig {
+            max_proofs_per_tuple: 1,
+            ..provenance::ProofConfig::default()
+        };
+        let ctx = provenance::backward_chaining::ProofContext::new(
+            &self.source_rules,
+            &self.input_tuples,
+            config,
+        );
+        provenance::backward_chaining::build_proof_tree(relation, tuple, &ctx)
     }
 
     /// Set the number of worker threads for parallel execution
@@ -443,6 +705,12 @@ impl IQLEngine {
         self.num_workers = num_workers.max(1);
     }
 
+    /// Evaluate recursive components of a stratum that do not read each
+    /// other concurrently (default: enabled)
+    pub fn set_parallel_components(&mut self, enabled: bool) {
+        self.parallel_components = enabled;
+    }
+
     /// Set the timing/profiling mode for query execution
     pub fn set_timing_mode(&mut self, mode: execution::TimingMode) {
         self.timing_mode = mode;
@@ -473,14 +741,175 @@ impl IQLEngine {
         self.max_result_rows = max;
     }
 
+    /// Order the query's result rows by the given output columns, most
+    /// significant first (empty = no ordering)
+    pub fn set_order_by(&mut self, order_by: Vec<(usize, ir::SortDirection)>) {
+        self.order_by = order_by;
+    }
+
+    /// Declare the primary key and unique columns of base relations so that
+    /// join planning can prefer joins that bind them
+    pub fn set_unique_keys(&mut self, keys: Arc<HashMap<String, Vec<Vec<usize>>>>) {
+        self.unique_keys = keys;
+    }
+
+    /// Declare the vector dimensions of base relation columns so that
+    /// mismatched vector function arguments fail at compile time
+    pub fn set_vector_dims(&mut self, dims: Arc<schema::VectorDims>) {
+        self.vector_dims = dims;
+    }
+
+    /// Provide per-column distinct counts of base relations (collected by
+    /// `analyze`) for cost-based planning
+    pub fn set_distinct_counts(&mut self, counts: Arc<HashMap<String, Vec<usize>>>) {
+        self.distinct_counts = counts;
+    }
+
+    /// Set the implicit type coercion mode. Under `Strict`, queries joining
+    /// stored columns that hold values of different types are rejected.
+    pub fn set_coercion_mode(&mut self, mode: value::coercion::CoercionMode) {
+        self.coercion = mode;
+    }
+
     /// Set maximum query cost score (0 = unlimited)
     pub fn set_max_query_cost(&mut self, max: u64) {
         self.max_query_cost = max;
     }
 
+    /// Set the per-query memory budget in bytes (0 = unlimited). Queries
+    /// that materialize more fail with a resource error.
+    pub fn set_max_query_memory(&mut self, bytes: usize) {
+        self.max_query_memory_bytes = bytes;
+    }
+
+    /// Charge a derived relation held until the end of the running execution
+    /// to its memory budget
+    fn hold_result(&self, tuples: &[Tuple]) {
+        if let Some(memory) = &self.query_memory {
+            memory.charge(tuples.iter().map(Tuple::estimated_bytes).sum());
+        }
+    }
+
+    /// Cancel executions through `handle`. Cancelling stops the running
+    /// dataflow, including components evaluated on other threads, and the
+    /// execution fails with a cancellation error.
+    pub fn set_cancel_handle(&mut self, handle: Option<execution::CancelHandle>) {
+        self.cancel_handle = handle;
+    }
+
+    /// Set the wall-clock limit per query in milliseconds (0 = unlimited).
+    /// Queries that run longer fail with a timeout error reporting the
+    /// progress they made.
+    pub fn set_query_timeout_ms(&mut self, ms: u64) {
+        self.query_timeout_ms = ms;
+    }
+
+    /// Configure pre-filtering of joins by the keys of their other side:
+    /// runtime semijoin filters on scans and bloom filters on sides that
+    /// differ greatly in size (`None` disables it). Results are unaffected.
+    pub fn set_join_prefilter(&mut self, prefilter: Option<code_generator::JoinPrefilter>) {
+        self.join_prefilter = prefilter;
+    }
+
+    /// Pre-filtering metrics accumulated across this engine's executions
+    pub fn join_prefilter_metrics(&self) -> Option<code_generator::PrefilterMetrics> {
+        self.join_prefilter
+            .as_ref()
+            .map(code_generator::JoinPrefilter::metrics)
+    }
+
+    /// Clock for one execution under the configured wall-clock limit
+    fn new_query_timeout(&self) -> Option<execution::QueryTimeout> {
+        (self.query_timeout_ms > 0).then(|| {
+            execution::QueryTimeout::new(Some(std::time::Duration::from_millis(
+                self.query_timeout_ms,
+            )))
+        })
+    }
+
+    /// Per-execution resources: a fresh memory tracker under the configured
+    /// budget, the cancellation token every backend call observes, and the
+    /// clock of the wall-clock limit
+    fn start_query_resources(&mut self) {
+        self.query_timeout = self.new_query_timeout();
+        self.query_memory = Some(
+            execution::ResourceLimits::with_memory_budget(self.max_query_memory_bytes)
+                .memory_tracker(),
+        );
+        self.query_cancel = self
+            .cancel_handle
+            .clone()
+            .or_else(code_generator::current_cancel_handle);
+    }
+
+    /// `Err` once the running execution has been cancelled, run out of time
+    /// or exceeded its memory budget
+    fn check_interrupted(&self) -> Result<(), String> {
+        if let Some(timeout) = &self.query_timeout {
+            timeout.check().map_err(|e| e.to_string())?;
+        }
+        if let Some(memory) = &self.query_memory {
+            memory.check().map_err(|e| e.to_string())?;
+        }
+        if self
+            .query_cancel
+            .as_ref()
+            .is_some_and(execution::CancelHandle::is_cancelled)
+        {
+            return Err("Query cancelled".to_string());
+        }
+        Ok(())
+    }
+
     /// Set shared input data from Arc (avoids deep clone from snapshot)
     pub fn set_shared_input(&mut self, data: Arc<HashMap<String, Vec<Tuple>>>) {
         self.shared_input = Some(data);
+        // Versioned entries stay valid whatever the input
+        if self.relation_versions.is_none() {
+            self.clear_subplan_cache();
+        }
+    }
+
+    /// Enable the cross-query subplan cache with default capacity.
+    ///
+    /// Materialized results of shared views and non-recursive rules are
+    /// reused by later queries until a relation they read changes.
+    pub fn enable_subplan_cache(&mut self) {
+        self.subplan_cache = Some(Arc::new(execution::SubplanCache::new()));
+    }
+
+    /// Use an existing subplan cache (e.g., one shared by a session's engines)
+    pub fn set_subplan_cache(&mut self, cache: Arc<execution::SubplanCache>) {
+        self.subplan_cache = Some(cache);
+    }
+
+    /// Key cached subplans by the versions of the relations they read.
+    ///
+    /// Used when the cache is shared by engines over different snapshots of
+    /// the same data: a relation without a version is not cached.
+    pub fn set_relation_versions(&mut self, versions: Arc<HashMap<String, u64>>) {
+        self.relation_versions = Some(versions);
+    }
+
+    /// Get the subplan cache, if enabled
+    pub fn subplan_cache(&self) -> Option<&Arc<execution::SubplanCache>> {
+        self.subplan_cache.as_ref()
+    }
+
+    /// Drop cached subplans that read `relation`.
+    ///
+    /// Called whenever the relation's tuples change.
+    pub fn invalidate_cached_relation(&self, relation: &str) {
+        if let Some(cache) = &self.subplan_cache {
+            cache.invalidate_relation(relation);
+        }
+    }
+
+    /// Drop all cached subplans
+    fn clear_subplan_cache(&self) {
+        if let Some(cache) = &self.subplan_cache {
+            cache.clear();
+        }
     }
 
     /// Set the HNSW search callback for resolving nearest-neighbor queries.
@@ -498,6 +927,13 @@ impl IQLEngine {
         self.hnsw_search_fn = Some(f);
     }
 
+    /// Set the vector columns covered by HNSW indexes. Nearest-neighbor
+    /// `top_k` aggregates over them are answered through the search
+    /// callback (see [`Self::set_hnsw_search_fn`]).
+    pub fn set_indexed_columns(&mut self, columns: Arc<Vec<IndexedColumn>>) {
+        self.indexed_columns = columns;
+    }
+
     /// Get the catalog
     pub fn catalog(&self) -> &Catalog {
         &self.catalog
@@ -509,7 +945,10 @@ impl IQLEngine {
     }
 
     /// Get mutable reference to input tuples
+    ///
+    /// Clears the subplan cache, since any relation may be modified.
     pub fn input_tuples_mut(&mut self) -> &mut HashMap<String, Vec<Tuple>> {
+        self.clear_subplan_cache();
         &mut self.input_tuples
     }
 
@@ -531,6 +970,7 @@ impl IQLEngine {
         // Convert to Tuple format
         let tuples: Vec<Tuple> = data.iter().map(|&(a, b)| Tuple::from_pair(a, b)).collect();
         self.input_tuples.insert(relation.to_string(), tuples);
+        self.invalidate_cached_relation(relation);
 
         // Register schema in catalog if not already registered
         if !self.catalog.has_relation(relation) {
@@ -565,6 +1005,7 @@ impl IQLEngine {
             .entry(relation.to_string())
             .or_default()
             .push(tuple);
+        self.invalidate_cached_relation(relation);
     }
 
     /// Get the current optimization configuration.
@@ -581,6 +1022,7 @@ impl IQLEngine {
         }
 
         self.input_tuples.insert(relation.to_string(), tuples);
+        self.invalidate_cached_relation(relation);
     }
 
     /// Parse an IQL program string into AST
@@ -592,7 +1034,8 @@ impl IQLEngine {
     /// 1. Parse source into AST
     /// 2. Validate rule safety
     /// 3. Detect recursive rules
-    /// 4. Compute stratification (evaluation order)
+    /// 4. Compute stratification (evaluation order), rejecting programs with
+    ///    negation through recursion
     pub fn parse(&mut self, source: &str) -> Result<&Program, String> {
         // Parse source into AST
         let program = parser::parse_program(source)?;
@@ -610,14 +1053,19 @@ impl IQLEngine {
                     rule.head, unsafe_vars
                 ));
             }
+            schema::vector_dims::check_rule(rule, &self.vector_dims)?;
         }
 
         // Recursion detection
         self.has_recursion = recursion::has_recursion(&program);
 
-        // Stratification - compute evaluation order using SCCs
-        self.strata = recursion::stratify(&program);
+        // Stratification - compute evaluation order using SCCs. Programs with
+        // negation through recursion have no well-defined result.
+        self.strata = recursion::stratify_with_negation(&program)
+            .try_into_strata()
+            .map_err(|(_, reason)| format!("Program is not stratifiable: {reason}"))?;
 
+        self.source_rules.clone_from(&program.rules);
         self.program = Some(program);
         Ok(self
             .program
@@ -651,21 +1099,22 @@ impl IQLEngine {
                 })
                 .flat_map(|scc| scc.iter().cloned())
                 .collect();
-            if std::env::var("IL_DEBUG").is_ok() && !recursive_rels.is_empty() {
-                eprintln!("DEBUG SIP: skipping recursive relations: {recursive_rels:?}");
+            if !recursive_rels.is_empty() {
+                debug!(relations = ?recursive_rels, "sip_skip_recursive");
             }
             sip_rewriter.set_recursive_relations(recursive_rels);
 
             let rewritten = sip_rewriter.rewrite_program(program);
             let stats = sip_rewriter.get_stats();
 
-            if std::env::var("IL_DEBUG").is_ok() {
-                if stats.rules_rewritten > 0 {
-                    eprintln!(
-                        "DEBUG SIP: rewrote {} rules, generated {} SIP rules",
-                        stats.rules_rewritten, stats.rules_generated
-                    );
-                }
+            if stats.rules_rewritten > 0 {
+                debug!(
+                    rewritten = stats.rules_rewritten,
+                    generated = stats.rules_generated,
+                    "sip_rewrite"
+                );
+            }
+            if tracing::enabled!(Level::TRACE) {
                 for (i, rule) in rewritten.rules.iter().enumerate() {
                     let head_args = rule
                         .head
@@ -680,9 +1129,10 @@ impl IQLEngine {
                         .map(|p| format!("{p:?}"))
                         .collect::<Vec<_>>()
                         .join(", ");
-                    eprintln!(
-                        "DEBUG SIP rule[{}]: {}({}) <- {}",
-                        i, rule.head.relation, head_args, body_str
+                    trace!(
+                        rule_idx = i,
+                        rule = %format!("{}({head_args}) <- {body_str}", rule.head.relation),
+                        "sip_rule"
                     );
                 }
             }
@@ -718,15 +1168,14 @@ impl IQLEngine {
             let (rewritten, magic_seeds) =
                 magic_sets::MagicSetRewriter::rewrite_program(program, &bindings);
 
-            let debug = std::env::var("IL_DEBUG").is_ok();
-            if debug {
-                eprintln!(
-                    "DEBUG Magic Sets: rewrote {} relations, {} magic seeds",
-                    bindings.len(),
-                    magic_seeds.len()
-                );
+            debug!(
+                relations = bindings.len(),
+                seeds = magic_seeds.len(),
+                "magic_sets_rewrite"
+            );
+            if tracing::enabled!(Level::TRACE) {
                 for (name, tuples) in &magic_seeds {
-                    eprintln!("  seed {name}: {} tuples", tuples.len());
+                    trace!(relation = %name, tuples = tuples.len(), "magic_sets_seed");
                 }
                 for (i, rule) in rewritten.rules.iter().enumerate() {
                     let head_args = rule
@@ -742,9 +1191,10 @@ impl IQLEngine {
                         .map(|p| format!("{p:?}"))
                         .collect::<Vec<_>>()
                         .join(", ");
-                    eprintln!(
-                        "DEBUG Magic Sets rule[{i}]: {}({head_args}) <- {body_str}",
-                        rule.head.relation,
+                    trace!(
+                        rule_idx = i,
+                        rule = %format!("{}({head_args}) <- {body_str}", rule.head.relation),
+                        "magic_sets_rule"
                     );
                 }
             }
@@ -912,7 +1362,9 @@ impl IQLEngine {
     /// 2. SIP Rewriting: Apply Sideways Information Passing for recursion
     /// 3. Subplan Sharing: Detect and share common subexpressions
     /// 4. Boolean Specialization: Select appropriate semiring
-    /// 5. Basic Optimizations: Identity elimination, filter simplification
+    /// 5. Equality Saturation (optional): Cost-based search over equivalent plans
+    /// 6. Basic Optimizations: Constant folding, identity elimination, filter simplification
+    /// 7. Semijoin Reduction: Pre-filter the large side of skewed joins
     ///
     /// Each optimization can be enabled/disabled via `OptimizationConfig`.
     ///
@@ -924,7 +1376,8 @@ impl IQLEngine {
     ) -> Result<Option<execution::timing::OptimizerTiming>, String> {
         // Join Planning
         if self.optimization_config.enable_join_planning {
-            let join_planner = join_planning::JoinPlanner::new();
+            let join_planner =
+                join_planning::JoinPlanner::new().with_unique_keys(Arc::clone(&self.unique_keys));
             self.ir_nodes = self
                 .ir_nodes
                 .iter()
@@ -948,14 +1401,11 @@ impl IQLEngine {
             self.ir_nodes = optimized_irs;
             // Store shared views - they will be executed BEFORE main rules
             self.shared_views = shared_views;
-            if std::env::var("IL_DEBUG").is_ok() && !self.shared_views.is_empty() {
-                eprintln!(
-                    "DEBUG optimize_ir: created {} shared views",
-                    self.shared_views.len()
+            if !self.shared_views.is_empty() {
+                debug!(
+                    views = ?self.shared_views.keys().collect::<Vec<_>>(),
+                    "shared_views_created"
                 );
-                for name in self.shared_views.keys() {
-                    eprintln!("  - {name}");
-                }
             }
         }
 
@@ -975,9 +1425,14 @@ impl IQLEngine {
             self.semiring_annotations = annotations;
         }
 
+        // Equality Saturation (joint exploration of rewrites, cost-based extraction)
+        if self.optimization_config.enable_equality_saturation {
+            self.apply_equality_saturation();
+        }
+
         // Basic Optimizations (always applied)
         let optimizer = Optimizer::new();
-        if collect_timing {
+        let timing = if collect_timing {
             let mut agg_timing = execution::timing::OptimizerTiming::default();
             self.ir_nodes = self
                 .ir_nodes
@@ -990,15 +1445,154 @@ impl IQLEngine {
                     optimized
                 })
                 .collect();
-            Ok(Some(agg_timing))
+            Some(agg_timing)
         } else {
             self.ir_nodes = self
                 .ir_nodes
                 .iter()
                 .map(|ir| optimizer.optimize(ir.clone()))
                 .collect();
-            Ok(None)
+            None
+        };
+
+        // Semijoin Reduction (runs last so it sees the final join shapes)
+        if self.optimization_config.enable_semijoin_reduction {
+            self.apply_semijoin_reduction();
         }
+
+        Ok(timing)
+    }
+
+    /// Pre-filter the large side of skewed joins with a semijoin.
+    ///
+    /// Cardinalities come from the loaded base relations. Rules that scan a
+    /// derived relation are left untouched: their sizes are unknown here and
+    /// the recursive fast paths in the code generator match on plain joins.
+    fn apply_semijoin_reduction(&mut self) {
+        let cardinalities = self.base_cardinalities();
+        let derived_relations = self.get_rule_heads();
+        let reducer = semijoin_reduction::SemijoinReducer::new();
+
+        self.ir_nodes = std::mem::take(&mut self.ir_nodes)
+            .into_iter()
+            .map(|ir| {
+                let reads_derived = derived_relations
+                    .iter()
+                    .any(|rel| CodeGenerator::references_relation(&ir, rel));
+                if reads_derived {
+                    ir
+                } else {
+                    reducer.reduce(ir, &cardinalities)
+                }
+            })
+            .collect();
+    }
+
+    /// Replace each rule's plan with the cheapest equivalent found by
+    /// equality saturation.
+    ///
+    /// As with semijoin reduction, rules that scan a derived relation keep
+    /// their plan so the code generator's recursive fast paths still match.
+    fn apply_equality_saturation(&mut self) {
+        let cardinalities = self.base_cardinalities();
+        let derived_relations = self.get_rule_heads();
+        let saturation = EqualitySaturation::new();
+
+        self.ir_nodes = std::mem::take(&mut self.ir_nodes)
+            .into_iter()
+            .map(|ir| {
+                let reads_derived = derived_relations
+                    .iter()
+                    .any(|rel| CodeGenerator::references_relation(&ir, rel));
+                if reads_derived {
+                    ir
+                } else {
+                    saturation.optimize_with_stats(ir, &cardinalities, &self.distinct_counts)
+                }
+            })
+            .collect();
+    }
+
+    /// Row counts of the loaded base relations
+    fn base_cardinalities(&self) -> HashMap<String, usize> {
+        let data = self.shared_input.as_deref().unwrap_or(&self.input_tuples);
+        data.iter()
+            .map(|(name, tuples)| (name.clone(), tuples.len()))
+            .collect()
+    }
+
+    /// Reject joins whose key columns trace back to stored columns holding
+    /// values of different types (strict coercion mode).
+    ///
+    /// Each column's type is taken from its first non-null stored value;
+    /// keys over derived relations or computed columns are not checked.
+    fn check_join_types(&self) -> Result<(), String> {
+        let data = self.shared_input.as_deref().unwrap_or(&self.input_tuples);
+        let sample = |node: &IRNode, col: usize| -> Option<(String, usize, Value)> {
+            let (relation, idx) = node.column_source(col)?;
+            let value = data
+                .get(relation)?
+                .iter()
+                .filter_map(|t| t.get(idx))
+                .find(|v| !v.is_null())?;
+            Some((relation.to_string(), idx, value.clone()))
+        };
+
+        let mut stack: Vec<&IRNode> = self
+            .ir_nodes
+            .iter()
+            .chain(self.shared_views.values())
+            .collect();
+        while let Some(node) = stack.pop() {
+            stack.extend(node.children());
+            let (left, right, left_keys, right_keys) = match node {
+                IRNode::Join {
+                    left,
+                    right,
+                    left_keys,
+                    right_keys,
+                    ..
+                }
+                | IRNode::JoinFlatMap {
+                    left,
+                    right,
+                    left_keys,
+                    right_keys,
+                    ..
+                }
+                | IRNode::Antijoin {
+                    left,
+                    right,
+                    left_keys,
+                    right_keys,
+                    ..
+                }
+                | IRNode::Semijoin {
+                    left,
+                    right,
+                    left_keys,
+                    right_keys,
+                    ..
+                } => (&**left, &**right, left_keys, right_keys),
+                _ => continue,
+            };
+            for (&lk, &rk) in left_keys.iter().zip(right_keys) {
+                let (Some((lrel, lcol, lval)), Some((rrel, rcol, rval))) =
+                    (sample(left, lk), sample(right, rk))
+                else {
+                    continue;
+                };
+                if value::coercion::is_mixed_type(&lval, &rval) {
+                    return Err(format!(
+                        "Mixed-type join: {lrel} column {lcol} ({}) vs {rrel} column {rcol} ({}); \
+                         coercion mode is strict",
+                        value::coercion::type_name(&lval),
+                        value::coercion::type_name(&rval)
+                    ));
+                }
+            }
+        }
+        Ok(())
     }
 
     /// Generate and execute Differential Dataflow code
@@ -1016,30 +1610,43 @@ impl IQLEngine {
     /// Takes an IR node and executes it using Differential Dataflow,
     /// returning the computed results as Tuples of any arity.
     pub fn execute_ir_tuples(&self, ir: &IRNode) -> Result<Vec<Tuple>, String> {
-        // Create code generator
-        let mut codegen = CodeGenerator::new();
-        codegen.set_max_result_rows(self.max_result_rows);
-
         // Set semiring type from boolean specialization analysis
         let semiring = boolean_specialization::compute_global_semiring(&self.semiring_annotations);
-        codegen.set_semiring_type(semiring);
-
-        // Pass semiring annotations for debug tracing
-        if !self.semiring_annotations.is_empty() {
-            codegen.set_semiring_annotations(self.semiring_annotations.clone());
-        }
+        let options = execution::BackendOptions {
+            semiring,
+            max_result_rows: self.max_result_rows,
+            memory_tracker: Some(
+                execution::ResourceLimits::with_memory_budget(self.max_query_memory_bytes)
+                    .memory_tracker(),
+            ),
+            cancel: self.cancel_handle.clone(),
+            timeout: self.new_query_timeout(),
+            join_prefilter: self.join_prefilter.clone(),
+            // Pass semiring annotations for debug tracing
+            semiring_annotations: self.semiring_annotations.clone(),
+            ..execution::BackendOptions::default()
+        };
 
         // Load input tuples - use shared Arc if available (avoids deep clone)
-        if let Some(ref shared) = self.shared_input {
-            codegen.set_shared_input(Arc::clone(shared));
-        } else {
-            for (relation, data) in &self.input_tuples {
-                codegen.add_input(relation.clone(), data.clone());
-            }
-        }
+        let inputs = match self.shared_input {
+            Some(ref shared) => Arc::clone(shared),
+            None => Arc::new(self.input_tuples.clone()),
+        };
 
         // Execute and return Tuples
-        codegen.execute(ir)
+        self.backend().execute(ir, inputs, &options)
+    }
+
+    /// The configured execution backend: the timely cluster when one is
+    /// installed, Differential Dataflow in this process otherwise
+    fn backend(&self) -> &dyn execution::ExecutionBackend {
+        match &self.optimization_config.execution_backend {
+            Some(backend) => backend.as_ref(),
+            None => match execution::installed_cluster() {
+                Some(cluster) => cluster,
+                None => &execution::DifferentialBackend,
+            },
+        }
     }
 
     /// Full pipeline: parse -> IR -> optimize -> execute. Returns binary (i32, i32) tuples
@@ -1077,24 +1684,19 @@ impl IQLEngine {
     /// Returns a vector where each element is `Some(head_name)` if the IR node
     /// at that index is recursive, or None if non-recursive.
     fn detect_recursion_info(&self, rule_heads: &[String]) -> Vec<Option<String>> {
-        let debug = std::env::var("IL_DEBUG").is_ok();
-
         self.ir_nodes
             .iter()
             .enumerate()
             .map(|(i, ir)| {
                 let head_name = rule_heads.get(i).cloned().unwrap_or_default();
                 let is_recursive = CodeGenerator::references_relation(ir, &head_name);
-                if debug {
-                    let node_type = if matches!(ir, IRNode::Union { .. }) {
-                        "Union"
-                    } else {
-                        "non-Union"
-                    };
-                    eprintln!(
-                        "DEBUG: IR[{i}] head='{head_name}' is {node_type}, recursive={is_recursive}"
-                    );
-                }
+                trace!(
+                    ir_idx = i,
+                    head = %head_name,
+                    union = matches!(ir, IRNode::Union { .. }),
+                    recursive = is_recursive,
+                    "detect_recursion"
+                );
                 if is_recursive {
                     Some(head_name)
                 } else {
@@ -1104,40 +1706,27 @@ impl IQLEngine {
             .collect()
     }
 
-    /// Load all input data into a `CodeGenerator`
-    fn load_inputs_into_codegen(
+    /// Collect all input data for a backend: base relations plus results of
+    /// previously executed rules
+    fn collect_backend_inputs(
         &self,
-        codegen: &mut CodeGenerator,
         accumulated: &HashMap<String, Vec<Tuple>>,
-    ) {
-        let debug = std::env::var("IL_DEBUG").is_ok();
+    ) -> execution::BackendInputs {
+        let mut inputs = HashMap::with_capacity(self.input_tuples.len() + accumulated.len());
 
         // Load input tuples
         for (relation, data) in &self.input_tuples {
-            if debug {
-                eprintln!(
-                    "DEBUG: loading input_tuples['{}'] = {} tuples",
-                    relation,
-                    data.len()
-                );
-                for t in data.iter().take(3) {
-                    eprintln!("  - {t:?}");
-                }
-            }
-            codegen.add_input(relation.clone(), data.clone());
+            trace!(relation = %relation, tuples = data.len(), "backend_input");
+            inputs.insert(relation.clone(), data.clone());
         }
 
         // Load accumulated results from previously executed rules
         for (rel_name, rel_data) in accumulated {
-            if debug {
-                eprintln!(
-                    "DEBUG: loading accumulated['{}'] = {} tuples",
-                    rel_name,
-                    rel_data.len()
-                );
-            }
-            codegen.add_input(rel_name.clone(), rel_data.clone());
+            trace!(relation = %rel_name, tuples = rel_data.len(), "backend_input_accumulated");
+            inputs.insert(rel_name.clone(), rel_data.clone());
         }
+
+        Arc::new(inputs)
     }
 
     /// Execute shared views and return their results
@@ -1146,7 +1735,6 @@ impl IQLEngine {
     /// them in dependency order using topological sort: views that reference no
     /// other views first, then views that depend on already-computed views.
     fn execute_shared_views(&self) -> Result<HashMap<String, Vec<Tuple>>, String> {
-        let debug = std::env::var("IL_DEBUG").is_ok();
         let mut results: HashMap<String, Vec<Tuple>> = HashMap::new();
 
         if self.shared_views.is_empty() {
@@ -1221,23 +1809,33 @@ impl IQLEngine {
 
         for view_name in execution_order {
             let view_ir = &self.shared_views[view_name];
-            if debug {
-                eprintln!("DEBUG: executing shared view '{view_name}'");
-            }
 
-            let mut codegen = CodeGenerator::new();
-            // Load base inputs AND results from previously computed shared views
-            self.load_inputs_into_codegen(&mut codegen, &results);
+            let cache_key =
+                self.subplan_cache_key(view_ir, boolean_specialization::SemiringType::Counting);
+            let cached = cache_key
+                .as_ref()
+                .and_then(|(key, _)| self.subplan_cache.as_ref()?.get(*key));
 
-            let view_results = codegen.execute(view_ir)?;
+            let view_results = if let Some(tuples) = cached {
+                debug!(view = %view_name, "shared_view_cache_hit");
+                tuples
+            } else {
+                // Load base inputs AND results from previously computed shared views
+                let inputs = self.collect_backend_inputs(&results);
+                let options = execution::BackendOptions {
+                    memory_tracker: self.query_memory.clone(),
+                    cancel: self.query_cancel.clone(),
+                    timeout: self.query_timeout.clone(),
+                    join_prefilter: self.join_prefilter.clone(),
+                    ..execution::BackendOptions::default()
+                };
+                let start = Instant::now();
+                let tuples = self.backend().execute(view_ir, inputs, &options)?;
+                self.store_in_subplan_cache(cache_key, &tuples, start.elapsed());
+                tuples
+            };
 
-            if debug {
-                eprintln!(
-                    "DEBUG: shared view '{}' produced {} tuples",
-                    view_name,
-                    view_results.len()
-                );
-            }
+            debug!(view = %view_name, rows = view_results.len(), "shared_view_executed");
 
             results.insert(view_name.clone(), view_results);
         }
@@ -1245,6 +1843,63 @@ impl IQLEngine {
         Ok(results)
     }
 
+    /// Compute the subplan cache key and base-relation dependencies for `ir`.
+    ///
+    /// Returns `None` when the cache is disabled or `ir` reads anything other
+    /// than base relations (derived relations, shared views, HNSW results),
+    /// since those can change without a base relation changing, or reads a
+    /// relation that has no version when versions are set.
+    fn subplan_cache_key(
+        &self,
+        ir: &IRNode,
+        semiring: boolean_specialization::SemiringType,
+    ) -> Option<(u64, Vec<String>)> {
+        use std::hash::{Hash, Hasher};
+
+        self.subplan_cache.as_ref()?;
+        if Self::contains_hnsw_scan(ir) {
+            return None;
+        }
+
+        let mut scans = Vec::new();
+        Self::collect_scan_relations(ir, &mut scans);
+        let rule_heads = self.get_rule_heads();
+        let base_only = scans.iter().all(|rel| {
+            !rel.starts_with("__")
+                && self.input_tuples.contains_key(rel)
+                && !rule_heads.contains(rel)
+        });
+        if !base_only {
+            return None;
+        }
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        subplan_sharing::SubplanSharer::new()
+            .hash_ir(ir)
+            .hash(&mut hasher);
+        semiring.hash(&mut hasher);
+        self.max_result_rows.hash(&mut hasher);
+        if let Some(versions) = &self.relation_versions {
+            for relation in &scans {
+                versions.get(relation)?.hash(&mut hasher);
+            }
+        }
+        Some((hasher.finish(), scans))
+    }
+
+    /// Store a materialized subplan result that took `cost` to compute
+    /// under a key from `subplan_cache_key`
+    fn store_in_subplan_cache(
+        &self,
+        cache_key: Option<(u64, Vec<String>)>,
+        tuples: &[Tuple],
+        cost: std::time::Duration,
+    ) {
+        if let (Some(cache), Some((key, dependencies))) = (&self.subplan_cache, cache_key) {
+            cache.insert(key, tuples.to_vec(), dependencies, cost.as_micros() as u64);
+        }
+    }
+
     /// Collect all relation names referenced by Scan nodes in an IR tree
     /// Topologically sort IR nodes by their scan dependencies.
     ///
@@ -1329,13 +1984,289 @@ impl IQLEngine {
             }
         }
 
-        if std::env::var("IL_DEBUG").is_ok() {
-            eprintln!("DEBUG topological_sort_ir_nodes: execution order = {order:?}");
-        }
+        trace!(?order, "ir_execution_order");
 
         order
     }
 
+    /// Group IR nodes into the program's strata, in execution order.
+    ///
+    /// Each stratum keeps the topological order of its nodes, so positive
+    /// dependencies inside a stratum run first. Negated relations are checked
+    /// to be produced by a strictly earlier stratum: a rule must never read a
+    /// relation it negates before that relation is complete.
+    fn stratum_execution_order(&self, rule_heads: &[String]) -> Result<Vec<Vec<usize>>, String> {
+        let order = self.topological_sort_ir_nodes(rule_heads);
+        let program = match &self.program {
+            Some(p) if !self.strata.is_empty() => p,
+            _ => return Ok(vec![order]),
+        };
+
+        let mut relation_stratum: HashMap<&str, usize> = HashMap::new();
+        for (stratum_idx, rule_indices) in self.strata.iter().enumerate() {
+            for &r in rule_indices {
+                if let Some(rule) = program.rules.get(r) {
+                    let entry = relation_stratum
+                        .entry(rule.head.relation.as_str())
+                        .or_insert(0);
+                    *entry = (*entry).max(stratum_idx);
+                }
+            }
+        }
+
+        // Negation validation
+        for (stratum_idx, rule_indices) in self.strata.iter().enumerate() {
+            for rule in rule_indices.iter().filter_map(|&r| program.rules.get(r)) {
+                for atom in rule.negated_body_atoms() {
+                    if let Some(&negated_stratum) = relation_stratum.get(atom.relation.as_str()) {
+                        if negated_stratum >= stratum_idx {
+                            return Err(format!(
+                                "Rule for '{}' negates '{}', which is not complete before stratum {} \
+                                 (it is computed in stratum {})",
+                                rule.head.relation, atom.relation, stratum_idx, negated_stratum
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        // Nodes without a stratum (no matching rule head) run with the last one
+        let last_stratum = self.strata.len() - 1;
+        let mut strata: Vec<Vec<usize>> = vec![Vec::new(); self.strata.len()];
+        for i in order {
+            let stratum_idx = rule_heads
+                .get(i)
+                .and_then(|head| relation_stratum.get(head.as_str()))
+                .copied()
+                .unwrap_or(last_stratum);
+            strata[stratum_idx].push(i);
+        }
+        strata.retain(|s| !s.is_empty());
+
+        debug!(?strata, "stratum_execution_order");
+
+        Ok(strata)
+    }
+
+    /// Semiring chosen by boolean specialization for rule `i`
+    fn rule_semiring(&self, i: usize) -> boolean_specialization::SemiringType {
+        self.semiring_annotations
+            .get(i)
+            .map_or(boolean_specialization::SemiringType::Counting, |a| {
+                a.semiring
+            })
+    }
+
+    /// Leading components that can run together as one stratum batch:
+    /// single non-recursive rules with distinct, non-empty head names
+    fn stratum_batch(
+        &self,
+        components: &[Vec<usize>],
+        rule_heads: &[String],
+        recursive_info: &[Option<String>],
+    ) -> Vec<usize> {
+        let mut batch: Vec<usize> = Vec::new();
+        for component in components {
+            let &[i] = component.as_slice() else {
+                break;
+            };
+            let Some(head) = rule_heads.get(i).filter(|head| !head.is_empty()) else {
+                break;
+            };
+            if recursive_info.get(i).is_some_and(Option::is_some)
+                || batch.iter().any(|&j| rule_heads[j] == *head)
+            {
+                break;
+            }
+            batch.push(i);
+        }
+        batch
+    }
+
+    /// Number of leading components that can be evaluated concurrently:
+    /// recursive components with distinct, non-empty heads, none of which
+    /// reads the heads of another
+    fn stratum_parallel_group(
+        &self,
+        components: &[Vec<usize>],
+        rule_heads: &[String],
+        recursive_info: &[Option<String>],
+        ir_nodes: &[IRNode],
+    ) -> usize {
+        let mut group_heads: Vec<&str> = Vec::new();
+        let mut count = 0;
+        for component in components {
+            let recursive = match component.as_slice() {
+                [i] => recursive_info.get(*i).is_some_and(Option::is_some),
+                _ => true,
+            };
+            if !recursive {
+                break;
+            }
+            let heads: Vec<&str> = component
+                .iter()
+                .filter_map(|&i| rule_heads.get(i).map(String::as_str))
+                .collect();
+            if heads.len() != component.len()
+                || heads
+                    .iter()
+                    .any(|head| head.is_empty() || group_heads.contains(head))
+            {
+                break;
+            }
+            let mut scans = Vec::new();
+            for &i in component {
+                if let Some(ir) = ir_nodes.get(i) {
+                    Self::collect_scan_relations(ir, &mut scans);
+                }
+            }
+            if scans.iter().any(|rel| group_heads.contains(&rel.as_str())) {
+                break;
+            }
+            group_heads.extend(heads);
+            count += 1;
+        }
+        count
+    }
+
+    /// Plan and backend options for evaluating one recursive component
+    fn component_job(
+        &self,
+        component: &[usize],
+        rule_heads: &[String],
+        recursive_info: &[Option<String>],
+        ir_nodes: &[IRNode],
+    ) -> ComponentJob {
+        let relations: Vec<(String, IRNode)> = component
+            .iter()
+            .map(|&i| (rule_heads[i].clone(), ir_nodes[i].clone()))
+            .collect();
+        let (recursive_relation, semiring) = match component {
+            [i] => (recursive_info[*i].clone(), self.rule_semiring(*i)),
+            _ => {
+                let annotations: Vec<SemiringAnnotation> = component
+                    .iter()
+                    .filter_map(|&i| self.semiring_annotations.get(i).cloned())
+                    .collect();
+                (
+                    None,
+                    boolean_specialization::compute_global_semiring(&annotations),
+                )
+            }
+        };
+        ComponentJob {
+            relations,
+            recursive_relation,
+            options: execution::BackendOptions {
+                semiring,
+                max_result_rows: self.max_result_rows,
+                memory_tracker: self.query_memory.clone(),
+                cancel: self.query_cancel.clone(),
+                timeout: self.query_timeout.clone(),
+                join_prefilter: self.join_prefilter.clone(),
+                num_workers: self.num_workers,
+                ..execution::BackendOptions::default()
+            },
+        }
+    }
+
+    /// Split a stratum into strongly connected components of rule heads,
+    /// in dependency order.
+    ///
+    /// A component with several nodes is a group of mutually recursive
+    /// relations that must be evaluated together; self-recursion stays a
+    /// single-node component handled by recursive execution.
+    fn stratum_components(&self, stratum: &[usize], rule_heads: &[String]) -> Vec<Vec<usize>> {
+        let head_to_idx: HashMap<&str, usize> = stratum
+            .iter()
+            .filter_map(|&i| rule_heads.get(i).map(|head| (head.as_str(), i)))
+            .collect();
+
+        // Edges: head -> heads of the same stratum it reads
+        let mut graph: HashMap<String, std::collections::HashSet<String>> = HashMap::new();
+        for &i in stratum {
+            let Some(head) = rule_heads.get(i) else {
+                continue;
+            };
+            let mut scans = Vec::new();
+            Self::collect_scan_relations(&self.ir_nodes[i], &mut scans);
+            let deps = scans
+                .into_iter()
+                .filter(|rel| head_to_idx.contains_key(rel.as_str()))
+                .collect();
+            graph.insert(head.clone(), deps);
+        }
+
+        // Tarjan emits a component only after every component it reads
+        let mut components: Vec<Vec<usize>> = recursion::find_sccs(&graph)
+            .into_iter()
+            .map(|scc| {
+                let mut nodes: Vec<usize> = scc
+                    .iter()
+                    .filter_map(|head| head_to_idx.get(head.as_str()).copied())
+                    .collect();
+                nodes.sort_unstable();
+                nodes
+            })
+            .filter(|nodes| !nodes.is_empty())
+            .collect();
+
+        // Nodes without a head name run on their own, in stratum order
+        for &i in stratum {
+            if rule_heads.get(i).is_none() {
+                components.push(vec![i]);
+            }
+        }
+
+        trace!(?components, "stratum_components");
+
+        components
+    }
+
+    /// Rows a rule reads: the sizes of the relations it scans, as the
+    /// backend will see them
+    fn scanned_rows(&self, ir: &IRNode, accumulated: &HashMap<String, Vec<Tuple>>) -> usize {
+        let mut scans = Vec::new();
+        Self::collect_scan_relations(ir, &mut scans);
+        scans
+            .iter()
+            .filter_map(|relation| {
+                accumulated
+                    .get(relation)
+                    .or_else(|| self.input_tuples.get(relation))
+            })
+            .map(Vec::len)
+            .sum()
+    }
+
+    /// Each rule's optimized IR and the rows of the stored relations the
+    /// program reads, for an installed plan capture
+    fn program_plan(&self, rule_heads: &[String]) -> execution::ProgramPlan {
+        let rules = rule_heads
+            .iter()
+            .zip(&self.ir_nodes)
+            .map(|(head, ir)| (head.clone(), ir.pretty_print(0)))
+            .collect();
+        let mut scans = Vec::new();
+        for ir in self.ir_nodes.iter().chain(self.shared_views.values()) {
+            Self::collect_scan_relations(ir, &mut scans);
+        }
+        let data = self.shared_input.as_deref().unwrap_or(&self.input_tuples);
+        let inputs = scans
+            .into_iter()
+            .filter_map(|relation| {
+                let rows = data.get(&relation)?.len();
+                Some((relation, rows))
+            })
+            .collect();
+        execution::ProgramPlan {
+            rules,
+            inputs,
+            steps: Vec::new(),
+        }
+    }
+
     fn collect_scan_relations(ir: &IRNode, scans: &mut Vec<String>) {
         match ir {
             IRNode::Scan { relation, .. } => {
@@ -1346,6 +2277,7 @@ impl IQLEngine {
             IRNode::Map { input, .. }
             | IRNode::Filter { input, .. }
             | IRNode::Distinct { input }
+            | IRNode::Sort { input, .. }
             | IRNode::Aggregate { input, .. }
             | IRNode::Compute { input, .. }
             | IRNode::FlatMap { input, .. } => {
@@ -1353,6 +2285,7 @@ impl IQLEngine {
             }
             IRNode::Join { left, right, .. }
             | IRNode::Antijoin { left, right, .. }
+            | IRNode::Semijoin { left, right, .. }
             | IRNode::JoinFlatMap { left, right, .. } => {
                 Self::collect_scan_relations(left, scans);
                 Self::collect_scan_relations(right, scans);
@@ -1402,6 +2335,213 @@ impl IQLEngine {
         Ok(())
     }
 
+    /// Answer nearest-neighbor `top_k` aggregates from HNSW indexes.
+    ///
+    /// An ascending, ungrouped `top_k` ordered by the `euclidean` or
+    /// `cosine` distance between a vector column and a literal vector only
+    /// needs the `k` tuples nearest that vector. When a valid index with
+    /// the same metric covers the column, the relation scan under the
+    /// aggregate is replaced by a scan of the index's candidates; distances
+    /// are still computed exactly over them. If the index cannot be
+    /// searched the plan is left as it is.
+    fn route_top_k_through_indexes(&mut self) {
+        let Some(search_fn) = &self.hnsw_search_fn else {
+            return;
+        };
+        if self.indexed_columns.is_empty() {
+            return;
+        }
+
+        let mut counter = 0usize;
+        for ir in &mut self.ir_nodes {
+            Self::route_top_k_in_node(
+                ir,
+                &self.indexed_columns,
+                search_fn,
+                &mut self.input_tuples,
+                &mut counter,
+            );
+        }
+
+        if counter > 0 {
+            if let Some(ref mut shared) = self.shared_input {
+                *shared = Arc::new(self.input_tuples.clone());
+            }
+        }
+    }
+
+    /// Recursively rewrite nearest-neighbor `top_k` aggregates within an
+    /// IR tree to scan index candidates
+    fn route_top_k_in_node(
+        ir: &mut IRNode,
+        indexed: &[IndexedColumn],
+        search_fn: &dyn Fn(&str, &[f32], usize, Option<usize>) -> Result<Vec<(i64, f64)>, String>,
+        input_tuples: &mut HashMap<String, Vec<Tuple>>,
+        counter: &mut usize,
+    ) {
+        if let Some((k, relation, column, metric, query)) = Self::nearest_neighbor_top_k(ir) {
+            let index = indexed
+                .iter()
+                .find(|c| c.relation == relation && c.column == column && c.metric == metric);
+            if let Some(Ok(results)) = index.map(|index| search_fn(&index.index, &query, k, None)) {
+                let ids: std::collections::HashSet<i64> =
+                    results.into_iter().map(|(id, _)| id).collect();
+                let candidates: Vec<Tuple> = input_tuples
+                    .get(&relation)
+                    .map(|tuples| {
+                        tuples
+                            .iter()
+                            .filter(|t| {
+                                index_manager::tuple_id(t)
+                                    .is_some_and(|id| ids.contains(&(id as i64)))
+                            })
+                            .cloned()
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                let synthetic_name = format!("__hnsw_candidates_{counter}__");
+                *counter += 1;
+                input_tuples.insert(synthetic_name.clone(), candidates);
+                if let IRNode::Aggregate { input, .. } = ir {
+                    Self::retarget_scan(input, synthetic_name);
+                }
+                return;
+            }
+        }
+
+        match ir {
+            IRNode::Map { input, .. }
+            | IRNode::Filter { input, .. }
+            | IRNode::Distinct { input }
+            | IRNode::Sort { input, .. }
+            | IRNode::Aggregate { input, .. }
+            | IRNode::Compute { input, .. }
+            | IRNode::FlatMap { input, .. } => {
+                Self::route_top_k_in_node(input, indexed, search_fn, input_tuples, counter);
+            }
+            IRNode::Join { left, right, .. }
+            | IRNode::Antijoin { left, right, .. }
+            | IRNode::Semijoin { left, right, .. }
+            | IRNode::JoinFlatMap { left, right, .. } => {
+                Self::route_top_k_in_node(left, indexed, search_fn, input_tuples, counter);
+                Self::route_top_k_in_node(right, indexed, search_fn, input_tuples, counter);
+            }
+            IRNode::Union { inputs } => {
+                for input in inputs {
+                    Self::route_top_k_in_node(input, indexed, search_fn, input_tuples, counter);
+                }
+            }
+            IRNode::Scan { .. } | IRNode::HnswScan { .. } => {}
+        }
+    }
+
+    /// If `ir` is an ascending, ungrouped `top_k` ordered by the distance
+    /// to a literal vector, return `k` and the distance's target (see
+    /// [`Self::nearest_neighbor_target`])
+    fn nearest_neighbor_top_k(
+        ir: &IRNode,
+    ) -> Option<(usize, String, usize, DistanceMetric, Vec<f32>)> {
+        let IRNode::Aggregate {
+            input,
+            group_by,
+            aggregations,
+            ..
+        } = ir
+        else {
+            return None;
+        };
+        let [(
+            ir::AggregateFunction::TopK {
+                k,
+                order_col,
+                descending: false,
+                ..
+            },
+            _,
+        )] = aggregations.as_slice()
+        else {
+            return None;
+        };
+        if !group_by.is_empty() {
+            return None;
+        }
+        let (relation, column, metric, query) = Self::nearest_neighbor_target(input, *order_col)?;
+        Some((*k, relation, column, metric, query))
+    }
+
+    /// If column `col` of `ir` is the distance between a vector column of
+    /// a single scanned relation and a literal vector, computed through
+    /// projections and computed columns only (no filters or joins that
+    /// could drop candidates), return the relation, the vector column's
+    /// position in it, the metric and the literal
+    fn nearest_neighbor_target(
+        ir: &IRNode,
+        col: usize,
+    ) -> Option<(String, usize, DistanceMetric, Vec<f32>)> {
+        match ir {
+            IRNode::Map {
+                input, projection, ..
+            } => Self::nearest_neighbor_target(input, *projection.get(col)?),
+            IRNode::Compute { input, expressions } => {
+                let arity = input.output_schema().len();
+                if col < arity {
+                    return Self::nearest_neighbor_target(input, col);
+                }
+                let (_, expr) = expressions.get(col - arity)?;
+                let ir::IRExpression::FunctionCall(func, args) = expr else {
+                    return None;
+                };
+                let metric = match func {
+                    ir::BuiltinFunction::Euclidean => DistanceMetric::Euclidean,
+                    ir::BuiltinFunction::Cosine => DistanceMetric::Cosine,
+                    _ => return None,
+                };
+                let (vector_col, query) = match args.as_slice() {
+                    [ir::IRExpression::Column(c), ir::IRExpression::VectorLiteral(q)]
+                    | [ir::IRExpression::VectorLiteral(q), ir::IRExpression::Column(c)] => {
+                        (*c, q.clone())
+                    }
+                    _ => return None,
+                };
+                let (relation, column) = Self::scanned_column(input, vector_col)?;
+                Some((relation, column, metric, query))
+            }
+            _ => None,
+        }
+    }
+
+    /// The relation and column that column `col` of `ir` passes through
+    /// unchanged, following projections and computed columns down to a scan
+    fn scanned_column(ir: &IRNode, col: usize) -> Option<(String, usize)> {
+        match ir {
+            IRNode::Scan { relation, schema } => {
+                (col < schema.len()).then(|| (relation.clone(), col))
+            }
+            IRNode::Map {
+                input, projection, ..
+            } => Self::scanned_column(input, *projection.get(col)?),
+            IRNode::Compute { input, .. } if col < input.output_schema().len() => {
+                Self::scanned_column(input, col)
+            }
+            _ => None,
+        }
+    }
+
+    /// Point the scan at the bottom of a projection/compute chain at another
+    /// relation
+    fn retarget_scan(ir: &mut IRNode, relation: String) {
+        match ir {
+            IRNode::Scan {
+                relation: scanned, ..
+            } => *scanned = relation,
+            IRNode::Map { input, .. } | IRNode::Compute { input, .. } => {
+                Self::retarget_scan(input, relation);
+            }
+            _ => {}
+        }
+    }
+
     /// Check if an IR tree contains any HnswScan nodes
     fn contains_hnsw_scan(ir: &IRNode) -> bool {
         match ir {
@@ -1409,11 +2549,13 @@ impl IQLEngine {
             IRNode::Map { input, .. }
             | IRNode::Filter { input, .. }
             | IRNode::Distinct { input }
+            | IRNode::Sort { input, .. }
             | IRNode::Aggregate { input, .. }
             | IRNode::Compute { input, .. }
             | IRNode::FlatMap { input, .. } => Self::contains_hnsw_scan(input),
             IRNode::Join { left, right, .. }
             | IRNode::Antijoin { left, right, .. }
+            | IRNode::Semijoin { left, right, .. }
             | IRNode::JoinFlatMap { left, right, .. } => {
                 Self::contains_hnsw_scan(left) || Self::contains_hnsw_scan(right)
             }
@@ -1475,6 +2617,7 @@ impl IQLEngine {
             IRNode::Map { input, .. }
             | IRNode::Filter { input, .. }
             | IRNode::Distinct { input }
+            | IRNode::Sort { input, .. }
             | IRNode::Aggregate { input, .. }
             | IRNode::Compute { input, .. }
             | IRNode::FlatMap { input, .. } => {
@@ -1482,6 +2625,7 @@ impl IQLEngine {
             }
             IRNode::Join { left, right, .. }
             | IRNode::Antijoin { left, right, .. }
+            | IRNode::Semijoin { left, right, .. }
             | IRNode::JoinFlatMap { left, right, .. } => {
                 Self::resolve_hnsw_in_node(left, search_fn, input_tuples, counter)?;
                 Self::resolve_hnsw_in_node(right, search_fn, input_tuples, counter)
@@ -1536,22 +2680,24 @@ impl IQLEngine {
         ),
         String,
     > {
-        let debug = std::env::var("IL_DEBUG").is_ok();
-        if debug {
-            eprintln!("DEBUG execute_tuples: starting");
-        }
         let mut collector = execution::TimingCollector::new(self.timing_mode);
         let exec_start = Instant::now();
+        self.start_query_resources();
         let source_len = source.len();
         info!(source_len, "engine_execute_start");
 
         // Parse, apply SIP rewriting, and build IR
-        let (parse_result, parse_us) = collector.time(|| self.parse(source));
+        let (parse_result, parse_us) = collector.time(|| {
+            tracing::info_span!("engine_parse", source_len).in_scope(|| self.parse(source))
+        });
         parse_result?;
         let parse_ms = parse_us / 1000;
         info!(source_len, parse_ms, "engine_parse_complete");
         collector.breakdown.parse_us = parse_us;
 
+        // SIP, magic sets, IR building and optimization
+        let optimize_span =
+            tracing::info_span!("engine_optimize", ir_nodes = tracing::field::Empty).entered();
         let ((), sip_us) = collector.time(|| self.apply_sip_rewriting());
         let sip_ms = sip_us / 1000;
         info!(source_len, sip_ms, "engine_sip_complete");
@@ -1573,17 +2719,10 @@ impl IQLEngine {
         );
         collector.breakdown.ir_build_us = build_us;
 
-        if debug {
-            eprintln!(
-                "DEBUG execute_tuples: built {} IR nodes",
-                self.ir_nodes.len()
-            );
-        }
-
         // Detect recursion BEFORE optimization (optimization destroys Union structure)
         let rule_heads = self.get_rule_heads();
         let recursive_info = self.detect_recursion_info(&rule_heads);
-        let unoptimized_ir_nodes = self.ir_nodes.clone();
+        let mut unoptimized_ir_nodes = self.ir_nodes.clone();
 
         // Optimize (for non-recursive nodes)
         let (opt_result, opt_us) = collector.time(|| self.optimize_ir(collector.is_detailed()));
@@ -1591,15 +2730,38 @@ impl IQLEngine {
         let opt_ms = opt_us / 1000;
         info!(source_len, opt_ms, "engine_optimize_complete");
         collector.breakdown.optimize_us = opt_us;
+        optimize_span.record("ir_nodes", self.ir_nodes.len());
+        drop(optimize_span);
 
         if self.ir_nodes.is_empty() {
             return Err("No IR nodes to execute".to_string());
         }
 
+        // Answer nearest-neighbor top_k aggregates from HNSW indexes
+        self.route_top_k_through_indexes();
+
         // Resolve HNSW nearest-neighbor scans before DD execution (#20).
         // HnswScan nodes are replaced with Scan nodes over injected result relations.
         self.resolve_hnsw_scans()?;
 
+        // The query's rows come back in the requested order
+        if !self.order_by.is_empty() {
+            let query_idx = self.ir_nodes.len() - 1;
+            for nodes in [&mut self.ir_nodes, &mut unoptimized_ir_nodes] {
+                if let Some(node) = nodes.get_mut(query_idx) {
+                    let input = std::mem::replace(node, IRNode::Union { inputs: Vec::new() });
+                    *node = IRNode::Sort {
+                        input: Box::new(input),
+                        order_by: self.order_by.clone(),
+                    };
+                }
+            }
+        }
+
+        if self.coercion == value::coercion::CoercionMode::Strict {
+            self.check_join_types()?;
+        }
+
         // Query cost check (#47): reject queries exceeding configured cost threshold
         if self.max_query_cost > 0 {
             let total_cost: u64 = self.ir_nodes.iter().map(IRNode::estimate_cost).sum();
@@ -1624,84 +2786,522 @@ impl IQLEngine {
             );
         }
 
-        // Execute shared views first (from subplan sharing optimization)
-        let (shared_result, shared_us) = collector.time(|| self.execute_shared_views());
-        let mut accumulated_results = shared_result?;
-        let shared_ms = shared_us / 1000;
-        info!(
-            source_len,
-            shared_ms,
-            shared_views = self.shared_views.len(),
-            "engine_shared_views_complete"
-        );
-        collector.breakdown.shared_views_us = shared_us;
+        execution::plan_capture::begin_program(|| self.program_plan(&rule_heads));
+
+        let _execute_span = tracing::info_span!(
+            "engine_execute",
+            rules = self.ir_nodes.len(),
+            shared_views = self.shared_views.len()
+        )
+        .entered();
+
+        // Execute shared views first (from subplan sharing optimization)
+        let (shared_result, shared_us) = collector.time(|| self.execute_shared_views());
+        let mut accumulated_results = shared_result?;
+        let shared_ms = shared_us / 1000;
+        info!(
+            source_len,
+            shared_ms,
+            shared_views = self.shared_views.len(),
+            "engine_shared_views_complete"
+        );
+        collector.breakdown.shared_views_us = shared_us;
+
+        // Execute main rules stratum by stratum. Each stratum only reads
+        // relations completed by earlier strata (or its own members), so
+        // results feed forward. Within a stratum, mutually recursive
+        // relations are evaluated together as one component.
+        let strata = self.stratum_execution_order(&rule_heads)?;
+        let query_idx = self.ir_nodes.len() - 1;
+        let mut last_result: Vec<Tuple> = Vec::new();
+
+        for (stratum_idx, stratum) in strata.iter().enumerate() {
+            // Between strata a lower-priority query may give its execution
+            // slot to a waiting higher-priority one
+            if stratum_idx > 0 {
+                code_generator::yield_between_strata(&|| self.check_interrupted().is_err());
+            }
+            let components = self.stratum_components(stratum, &rule_heads);
+            let mut next = 0;
+            while next < components.len() {
+                self.check_interrupted()?;
+                // Consecutive non-recursive rules run as one computation,
+                // sharing input streams and join arrangements
+                let batch = self.stratum_batch(&components[next..], &rule_heads, &recursive_info);
+                if batch.len() > 1 {
+                    next += batch.len();
+
+                    // Rules answered by the subplan cache only feed the others
+                    let mut plans: Vec<(String, IRNode)> = Vec::with_capacity(batch.len());
+                    let mut pending = Vec::with_capacity(batch.len());
+                    for &i in &batch {
+                        let head_name = rule_heads[i].clone();
+                        let semiring = self.rule_semiring(i);
+                        let cache_key = self.subplan_cache_key(&self.ir_nodes[i], semiring);
+                        let cached = cache_key
+                            .as_ref()
+                            .and_then(|(key, _)| self.subplan_cache.as_ref()?.get(*key));
+                        if let Some(result) = cached {
+                            if i == query_idx {
+                                last_result.clone_from(&result);
+                            }
+                            self.hold_result(&result);
+                            accumulated_results.insert(head_name, result);
+                        } else {
+                            plans.push((head_name, self.ir_nodes[i].clone()));
+                            pending.push((i, semiring, cache_key));
+                        }
+                    }
+                    let options = execution::BackendOptions {
+                        semiring: pending.iter().fold(
+                            boolean_specialization::SemiringType::Boolean,
+                            |acc, (_, semiring, _)| acc.meet(semiring),
+                        ),
+                        max_result_rows: self.max_result_rows,
+                        memory_tracker: self.query_memory.clone(),
+                        cancel: self.query_cancel.clone(),
+                        timeout: self.query_timeout.clone(),
+                        join_prefilter: self.join_prefilter.clone(),
+                        num_workers: self.num_workers,
+                        ..execution::BackendOptions::default()
+                    };
+                    let backend = self.backend();
+
+                    let rule_span = tracing::info_span!(
+                        "engine_rule",
+                        rule_head = tracing::field::Empty,
+                        rules = batch.len(),
+                        cached = batch.len() - plans.len(),
+                        stratum = stratum_idx,
+                        recursive = false,
+                        rows = tracing::field::Empty,
+                    )
+                    .entered();
+                    let batch_start = Instant::now();
+                    let (exec_result, batch_us) = collector.time(|| {
+                        if plans.is_empty() {
+                            return Ok(HashMap::new());
+                        }
+                        let inputs = self.collect_backend_inputs(&accumulated_results);
+                        backend.execute_stratum(&plans, inputs, &options)
+                    });
+                    let mut batch_results = exec_result?;
+                    // Plans evaluated together share the cost evenly
+                    let plan_cost = batch_start.elapsed() / plans.len().max(1) as u32;
+
+                    for ((i, _, cache_key), (head_name, _)) in pending.into_iter().zip(&plans) {
+                        let result = batch_results.remove(head_name).unwrap_or_default();
+                        self.store_in_subplan_cache(cache_key, &result, plan_cost);
+                        if i == query_idx {
+                            last_result.clone_from(&result);
+                        }
+                        self.hold_result(&result);
+                        accumulated_results.insert(head_name.clone(), result);
+                    }
+
+                    let heads: Vec<&str> = batch.iter().map(|&i| rule_heads[i].as_str()).collect();
+                    let group_name = heads.join(", ");
+                    let rows: usize = heads
+                        .iter()
+                        .filter_map(|&head| accumulated_results.get(head))
+                        .map(Vec::len)
+                        .sum();
+                    rule_span.record("rule_head", group_name.as_str());
+                    rule_span.record("rows", rows);
+                    drop(rule_span);
+                    collector.record_rule(group_name.clone(), batch_us, false, self.num_workers);
+
+                    let rule_ms = batch_us / 1000;
+                    info!(
+                        source_len,
+                        rule_head = %group_name,
+                        rule_ms,
+                        recursive = false,
+                        workers = self.num_workers,
+                        stratum = stratum_idx,
+                        "engine_rule_complete"
+                    );
+                    continue;
+                }
+
+                // Recursive components that do not read each other run
+                // concurrently, each to its own fixpoint
+                let group = if self.parallel_components {
+                    self.stratum_parallel_group(
+                        &components[next..],
+                        &rule_heads,
+                        &recursive_info,
+                        &unoptimized_ir_nodes,
+                    )
+                } else {
+                    0
+                };
+                if group > 1 {
+                    let group_components = &components[next..next + group];
+                    next += group;
+
+                    let jobs: Vec<ComponentJob> = group_components
+                        .iter()
+                        .map(|component| {
+                            self.component_job(
+                                component,
+                                &rule_heads,
+                                &recursive_info,
+                                &unoptimized_ir_nodes,
+                            )
+                        })
+                        .collect();
+                    let backend = self.backend();
+                    let inputs = self.collect_backend_inputs(&accumulated_results);
+
+                    let group_span = tracing::info_span!(
+                        "engine_parallel_group",
+                        components = group,
+                        stratum = stratum_idx
+                    )
+                    .entered();
+                    let (exec_result, group_us) = collector.time(|| {
+                        jobs.par_iter()
+                            .map(|job| {
+                                let start = Instant::now();
+                                job.execute(backend, Arc::clone(&inputs))
+                                    .map(|results| (results, start.elapsed().as_micros() as u64))
+                            })
+                            .collect::<Result<Vec<_>, String>>()
+                    });
+                    let group_results = exec_result?;
+                    drop(group_span);
+
+                    for (component, (mut results, component_us)) in
+                        group_components.iter().zip(group_results)
+                    {
+                        for &i in component {
+                            let result = results.remove(&rule_heads[i]).unwrap_or_default();
+                            if i == query_idx {
+                                last_result.clone_from(&result);
+                            }
+                            self.hold_result(&result);
+                            accumulated_results.insert(rule_heads[i].clone(), result);
+                        }
+
+                        let heads: Vec<&str> =
+                            component.iter().map(|&i| rule_heads[i].as_str()).collect();
+                        let group_name = heads.join(", ");
+                        collector.record_rule(
+                            group_name.clone(),
+                            component_us,
+                            true,
+                            self.num_workers,
+                        );
+
+                        let rule_ms = component_us / 1000;
+                        info!(
+                            source_len,
+                            rule_head = %group_name,
+                            rule_ms,
+                            recursive = true,
+                            workers = self.num_workers,
+                            stratum = stratum_idx,
+                            "engine_rule_complete"
+                        );
+                    }
+
+                    let group_ms = group_us / 1000;
+                    info!(
+                        source_len,
+                        components = group,
+                        group_ms,
+                        stratum = stratum_idx,
+                        "engine_parallel_group_complete"
+                    );
+                    continue;
+                }
+
+                let component = &components[next];
+                next += 1;
+                if component.len() > 1 {
+                    let relations: Vec<(String, IRNode)> = component
+                        .iter()
+                        .map(|&i| {
+                            let head = rule_heads.get(i).cloned().unwrap_or_default();
+                            (head, unoptimized_ir_nodes[i].clone())
+                        })
+                        .collect();
+                    let annotations: Vec<SemiringAnnotation> = component
+                        .iter()
+                        .filter_map(|&i| self.semiring_annotations.get(i).cloned())
+                        .collect();
+                    let options = execution::BackendOptions {
+                        semiring: boolean_specialization::compute_global_semiring(&annotations),
+                        max_result_rows: self.max_result_rows,
+                        memory_tracker: self.query_memory.clone(),
+                        cancel: self.query_cancel.clone(),
+                        timeout: self.query_timeout.clone(),
+                        join_prefilter: self.join_prefilter.clone(),
+                        num_workers: self.num_workers,
+                        ..execution::BackendOptions::default()
+                    };
+                    let backend = self.backend();
+
+                    let heads: Vec<&str> =
+                        relations.iter().map(|(head, _)| head.as_str()).collect();
+                    let group_name = heads.join(", ");
+
+                    let rule_span = tracing::info_span!(
+                        "engine_rule",
+                        rule_head = %group_name,
+                        rules = relations.len(),
+                        stratum = stratum_idx,
+                        recursive = true,
+                        rows = tracing::field::Empty,
+                    )
+                    .entered();
+                    let (exec_result, group_us) = collector.time(|| {
+                        let inputs = self.collect_backend_inputs(&accumulated_results);
+                        backend.execute_mutual_recursive(&relations, inputs, &options)
+                    });
+                    let mut group_results = exec_result?;
+
+                    let mut rows = 0;
+                    for (&i, (head_name, _)) in component.iter().zip(&relations) {
+                        let result = group_results.remove(head_name).unwrap_or_default();
+                        rows += result.len();
+                        if i == query_idx {
+                            last_result.clone_from(&result);
+                        }
+                        self.hold_result(&result);
+                        accumulated_results.insert(head_name.clone(), result);
+                    }
+                    rule_span.record("rows", rows);
+                    drop(rule_span);
+                    collector.record_rule(group_name.clone(), group_us, true, self.num_workers);
+
+                    let rule_ms = group_us / 1000;
+                    info!(
+                        source_len,
+                        rule_head = %group_name,
+                        rule_ms,
+                        recursive = true,
+                        workers = self.num_workers,
+                        stratum = stratum_idx,
+                        "engine_rule_complete"
+                    );
+                    continue;
+                }
 
-        // Execute main rules in dependency order (topological sort)
-        let execution_order = self.topological_sort_ir_nodes(&rule_heads);
-        let mut last_result: Vec<Tuple> = Vec::new();
+                let i = component[0];
+                let head_name = rule_heads.get(i).cloned().unwrap_or_default();
 
-        for &i in &execution_order {
-            let head_name = rule_heads.get(i).cloned().unwrap_or_default();
+                // Set per-rule semiring type from boolean specialization
+                let semiring = self.rule_semiring(i);
+                let options = execution::BackendOptions {
+                    semiring,
+                    max_result_rows: self.max_result_rows,
+                    memory_tracker: self.query_memory.clone(),
+                    cancel: self.query_cancel.clone(),
+                    timeout: self.query_timeout.clone(),
+                    join_prefilter: self.join_prefilter.clone(),
+                    num_workers: self.num_workers,
+                    ..execution::BackendOptions::default()
+                };
+                let backend = self.backend();
 
-            // Create fresh CodeGenerator for each rule (avoids timely state issues)
-            let mut codegen = CodeGenerator::new();
-            codegen.set_max_result_rows(self.max_result_rows);
-            // Set per-rule semiring type from boolean specialization
-            let semiring = self
-                .semiring_annotations
-                .get(i)
-                .map_or(boolean_specialization::SemiringType::Counting, |a| {
-                    a.semiring
-                });
-            codegen.set_semiring_type(semiring);
-            self.load_inputs_into_codegen(&mut codegen, &accumulated_results);
-
-            let is_recursive = recursive_info.get(i).is_some_and(Option::is_some);
-
-            // Use unoptimized IR for recursive nodes, optimized for others
-            let (exec_result, rule_us) = collector.time(|| {
-                if let Some(Some(recursive_rel)) = recursive_info.get(i) {
-                    codegen.execute_recursive(&unoptimized_ir_nodes[i], recursive_rel)
-                } else if self.num_workers > 1 {
-                    // Use parallel execution when configured for multi-worker
-                    let config = code_generator::ExecutionConfig::with_workers(self.num_workers);
-                    codegen.execute_with_config(&self.ir_nodes[i], config)
+                let is_recursive = recursive_info.get(i).is_some_and(Option::is_some);
+
+                // Non-recursive rules over base relations can reuse a result
+                // materialized by an earlier query
+                let cache_key = if is_recursive {
+                    None
                 } else {
-                    codegen.execute(&self.ir_nodes[i])
+                    self.subplan_cache_key(&self.ir_nodes[i], semiring)
+                };
+                let cached = cache_key
+                    .as_ref()
+                    .and_then(|(key, _)| self.subplan_cache.as_ref()?.get(*key));
+                let cache_hit = cached.is_some();
+
+                let rule_span = tracing::info_span!(
+                    "engine_rule",
+                    rule_idx = i,
+                    rule_head = %head_name,
+                    stratum = stratum_idx,
+                    recursive = is_recursive,
+                    cache_hit,
+                    estimated_cost = self.ir_nodes[i].estimate_cost(),
+                    input_rows = tracing::field::Empty,
+                    rows = tracing::field::Empty,
+                )
+                .entered();
+                if !rule_span.is_disabled() {
+                    rule_span.record(
+                        "input_rows",
+                        self.scanned_rows(&self.ir_nodes[i], &accumulated_results),
+                    );
                 }
-            });
-            let result = exec_result?;
 
-            last_result.clone_from(&result);
+                // Use unoptimized IR for recursive nodes, optimized for others
+                let rule_start = Instant::now();
+                let (exec_result, rule_us) = collector.time(|| {
+                    if let Some(tuples) = cached {
+                        Ok(tuples)
+                    } else if let Some(Some(recursive_rel)) = recursive_info.get(i) {
+                        let inputs = self.collect_backend_inputs(&accumulated_results);
+                        backend.execute_recursive(
+                            &unoptimized_ir_nodes[i],
+                            recursive_rel,
+                            inputs,
+                            &options,
+                        )
+                    } else {
+                        // The backend runs multi-worker execution when num_workers > 1
+                        let inputs = self.collect_backend_inputs(&accumulated_results);
+                        backend.execute(&self.ir_nodes[i], inputs, &options)
+                    }
+                });
+                let result = exec_result?;
+                rule_span.record("rows", result.len());
+                drop(rule_span);
+                if !cache_hit {
+                    self.store_in_subplan_cache(cache_key, &result, rule_start.elapsed());
+                }
 
-            // Store results for subsequent rules
-            if !head_name.is_empty() {
-                accumulated_results.insert(head_name.clone(), result);
-            }
+                if i == query_idx {
+                    last_result.clone_from(&result);
+                }
+
+                // Store results for subsequent rules
+                if !head_name.is_empty() {
+                    self.hold_result(&result);
+                    accumulated_results.insert(head_name.clone(), result);
+                }
 
-            collector.record_rule(head_name.clone(), rule_us, is_recursive, self.num_workers);
+                collector.record_rule(head_name.clone(), rule_us, is_recursive, self.num_workers);
+
+                let rule_ms = rule_us / 1000;
+                info!(
+                    source_len,
+                    rule_idx = i,
+                    rule_head = %head_name,
+                    rule_ms,
+                    recursive = is_recursive,
+                    workers = self.num_workers,
+                    stratum = stratum_idx,
+                    "engine_rule_complete"
+                );
+            }
+        }
 
-            let rule_ms = rule_us / 1000;
-            info!(
-                source_len,
-                rule_idx = i,
-                rule_head = %head_name,
-                rule_ms,
-                recursive = is_recursive,
-                workers = self.num_workers,
-                "engine_rule_complete"
-            );
+        if self.provenance_mode {
+            self.why_provenance = self.annotate_why_provenance(
+                &strata,
+                &rule_heads,
+                &recursive_info,
+                &unoptimized_ir_nodes,
+            )?;
         }
 
+        execution::plan_capture::finish_program(|head| {
+            accumulated_results.get(head).map_or(0, Vec::len)
+        });
+
         info!(
             source_len,
             total_ms = exec_start.elapsed().as_millis() as u64,
             "engine_execute_complete"
         );
+        collector.breakdown.peak_memory_bytes =
+            self.query_memory
+                .as_ref()
+                .map_or(0, execution::MemoryTracker::peak) as u64;
         let timing = collector.finish();
         Ok((last_result, accumulated_results, timing))
     }
 
+    /// Annotate every derived relation of the current program with
+    /// why-provenance, in the same stratum order as execution
+    fn annotate_why_provenance(
+        &self,
+        strata: &[Vec<usize>],
+        rule_heads: &[String],
+        recursive_info: &[Option<String>],
+        unoptimized_ir_nodes: &[IRNode],
+    ) -> Result<HashMap<String, provenance::why::AnnotatedRelation>, String> {
+        let mut evaluator =
+            provenance::why::WhyEvaluator::new(self.collect_backend_inputs(&HashMap::new()));
+
+        // Shared views only read base relations and each other; iterating
+        // them together settles their dependency order
+        let mut views: Vec<(String, IRNode)> = self
+            .shared_views
+            .iter()
+            .map(|(name, ir)| (name.clone(), ir.clone()))
+            .collect();
+        views.sort_by(|a, b| a.0.cmp(&b.0));
+        evaluator.annotate_recursive(&views)?;
+
+        for stratum in strata {
+            for component in self.stratum_components(stratum, rule_heads) {
+                let is_recursive = component.len() > 1
+                    || recursive_info
+                        .get(component[0])
+                        .is_some_and(Option::is_some);
+                if is_recursive {
+                    let relations: Vec<(String, IRNode)> = component
+                        .iter()
+                        .filter_map(|&i| {
+                            let head = rule_heads.get(i).filter(|head| !head.is_empty())?;
+                            Some((head.clone(), unoptimized_ir_nodes[i].clone()))
+                        })
+                        .collect();
+                    evaluator.annotate_recursive(&relations)?;
+                } else if let Some(head) = rule_heads.get(component[0]).filter(|h| !h.is_empty()) {
+                    evaluator.annotate(head, &self.ir_nodes[component[0]])?;
+                }
+            }
+        }
+
+        Ok(evaluator.into_relations())
+    }
+
+    /// Compile a program into plans for incrementally maintained views.
+    ///
+    /// Runs the same parse/optimize pipeline as `execute_tuples` but returns
+    /// one `(relation, plan)` pair per rule head (plus any shared views)
+    /// instead of executing them, for installation into an
+    /// `IncrementalEngine`. Plans may read base relations and each other.
+    ///
+    /// Recursive rules and vector searches are rejected: their plans cannot
+    /// yet be maintained under updates.
+    pub fn compile_views(&mut self, source: &str) -> Result<Vec<(String, IRNode)>, String> {
+        self.parse(source)?;
+        self.apply_sip_rewriting();
+        self.apply_magic_sets();
+        self.build_ir(false)?;
+
+        let rule_heads = self.get_rule_heads();
+        if let Some(head) = self
+            .detect_recursion_info(&rule_heads)
+            .into_iter()
+            .flatten()
+            .next()
+        {
+            return Err(format!(
+                "Recursive rule '{head}' cannot be maintained incrementally"
+            ));
+        }
+
+        self.optimize_ir(false)?;
+
+        let mut views: Vec<(String, IRNode)> = self
+            .shared_views
+            .iter()
+            .map(|(name, ir)| (name.clone(), ir.clone()))
+            .collect();
+        views.sort_by(|a, b| a.0.cmp(&b.0));
+        views.extend(rule_heads.into_iter().zip(self.ir_nodes.iter().cloned()));
+        Ok(views)
+    }
+
     /// Execute all rules in the program
     ///
     /// Returns a map from rule index to results.
@@ -1714,6 +3314,7 @@ impl IQLEngine {
         self.apply_sip_rewriting();
         self.build_ir(false)?;
         self.optimize_ir(false)?;
+        self.start_query_resources();
 
         // Execute rules in dependency order, chaining intermediate results so SIP
         // intermediate rules feed into subsequent rules.
@@ -1726,8 +3327,6 @@ impl IQLEngine {
             let ir = &self.ir_nodes[i];
             let head_name = rule_heads.get(i).cloned().unwrap_or_default();
 
-            let mut codegen = CodeGenerator::new();
-            codegen.set_max_result_rows(self.max_result_rows);
             // Set per-rule semiring type from boolean specialization
             let semiring = self
                 .semiring_annotations
@@ -1735,17 +3334,19 @@ impl IQLEngine {
                 .map_or(boolean_specialization::SemiringType::Counting, |a| {
                     a.semiring
                 });
-            codegen.set_semiring_type(semiring);
-            // Load base facts
-            for (relation, data) in &self.input_tuples {
-                codegen.add_input(relation.clone(), data.clone());
-            }
-            // Load accumulated intermediate results
-            for (rel, data) in &accumulated {
-                codegen.add_input(rel.clone(), data.clone());
-            }
+            let options = execution::BackendOptions {
+                semiring,
+                max_result_rows: self.max_result_rows,
+                memory_tracker: self.query_memory.clone(),
+                cancel: self.query_cancel.clone(),
+                timeout: self.query_timeout.clone(),
+                join_prefilter: self.join_prefilter.clone(),
+                ..execution::BackendOptions::default()
+            };
+            // Load base facts and accumulated intermediate results
+            let inputs = self.collect_backend_inputs(&accumulated);
 
-            let rule_tuples = codegen.execute(ir)?;
+            let rule_tuples = self.backend().execute(ir, inputs, &options)?;
             let rule_results: Vec<(i32, i32)> =
                 rule_tuples.iter().filter_map(Tuple::to_pair).collect();
             results.insert(i, rule_results);
@@ -1898,12 +3499,16 @@ impl IQLEngine {
         let optimized_ir = optimizer.optimize(ir);
 
         // Execute
-        let mut codegen = CodeGenerator::new();
+        let mut inputs = HashMap::new();
         if let Some(data) = self.input_tuples.get(relation) {
-            codegen.add_input(relation.to_string(), data.clone());
+            inputs.insert(relation.to_string(), data.clone());
         }
 
-        let result_tuples = codegen.execute(&optimized_ir)?;
+        let result_tuples = self.backend().execute(
+            &optimized_ir,
+            Arc::new(inputs),
+            &execution::BackendOptions::default(),
+        )?;
         // Convert to binary format for legacy return type
         let results: Vec<(i32, i32)> = result_tuples.iter().filter_map(Tuple::to_pair).collect();
         Ok(results)
@@ -2105,6 +3710,9 @@ mod tests {
             enable_subplan_sharing: false,
             enable_boolean_specialization: false,
             enable_magic_sets: false,
+            enable_semijoin_reduction: false,
+            enable_equality_saturation: false,
+            execution_backend: None,
         };
         let engine = IQLEngine::with_config(config.clone());
         assert!(!engine.config().enable_join_planning);
@@ -2122,11 +3730,364 @@ mod tests {
             enable_subplan_sharing: true,
             enable_boolean_specialization: true,
             enable_magic_sets: true,
+            enable_semijoin_reduction: true,
+            enable_equality_saturation: false,
+            execution_backend: None,
         };
         engine.set_config(config);
         assert!(!engine.config().enable_join_planning);
     }
 
+    #[test]
+    fn test_custom_execution_backend() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        /// Delegates to the default backend and counts executions
+        #[derive(Debug, Default)]
+        struct CountingBackend {
+            calls: AtomicUsize,
+        }
+
+        impl execution::ExecutionBackend for CountingBackend {
+            fn name(&self) -> &'static str {
+                "counting"
+            }
+
+            fn execute(
+                &self,
+                ir: &IRNode,
+                inputs: execution::BackendInputs,
+                options: &execution::BackendOptions,
+            ) -> Result<Vec<Tuple>, String> {
+                self.calls.fetch_add(1, Ordering::SeqCst);
+                execution::DifferentialBackend.execute(ir, inputs, options)
+            }
+
+            fn execute_recursive(
+                &self,
+                ir: &IRNode,
+                recursive_relation: &str,
+                inputs: execution::BackendInputs,
+                options: &execution::BackendOptions,
+            ) -> Result<Vec<Tuple>, String> {
+                self.calls.fetch_add(1, Ordering::SeqCst);
+                execution::DifferentialBackend.execute_recursive(
+                    ir,
+                    recursive_relation,
+                    inputs,
+                    options,
+                )
+            }
+        }
+
+        let backend = Arc::new(CountingBackend::default());
+        let mut engine = IQLEngine::with_config(OptimizationConfig {
+            execution_backend: Some(backend.clone()),
+            ..OptimizationConfig::default()
+        });
+        engine.add_fact("edge", vec![(1, 2), (2, 3)]);
+
+        let results = engine.execute("result(X, Y) <- edge(X, Y)").unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(backend.calls.load(Ordering::SeqCst) > 0);
+    }
+
+    #[test]
+    fn test_equality_saturation_preserves_results() {
+        let program = "result(A, D) <- r(A, B), s(B, C), t(C, D), A > 1";
+        let run = |enable_equality_saturation: bool| {
+            let mut engine = IQLEngine::with_config(OptimizationConfig {
+                enable_equality_saturation,
+                ..OptimizationConfig::default()
+            });
+            engine.add_fact("r", (0..50).map(|i| (i, i % 5)).collect());
+            engine.add_fact("s", vec![(1, 10), (2, 20)]);
+            engine.add_fact("t", vec![(10, 100), (20, 200), (30, 300)]);
+            let mut results = engine.execute(program).unwrap();
+            results.sort_unstable();
+            results
+        };
+
+        let expected = run(false);
+        assert!(!expected.is_empty());
+        assert_eq!(run(true), expected);
+    }
+
+    #[test]
+    fn test_mutually_recursive_stratum_reaches_fixpoint() {
+        let mut engine = IQLEngine::new();
+        engine.add_fact("edge", vec![(1, 2), (2, 3), (3, 4), (4, 5)]);
+
+        // odd_path/even_path only reach each other, so a single pass
+        // over the stratum would stop after one edge
+        let program = "odd_path(X, Y) <- edge(X, Y)\n\
+                       even_path(X, Z) <- odd_path(X, Y), edge(Y, Z)\n\
+                       odd_path(X, Z) <- even_path(X, Y), edge(Y, Z)\n\
+                       result(X, Y) <- odd_path(X, Y)";
+        let mut results = engine.execute(program).unwrap();
+        results.sort_unstable();
+        assert_eq!(
+            results,
+            vec![(1, 2), (1, 4), (2, 3), (2, 5), (3, 4), (4, 5)]
+        );
+    }
+
+    #[test]
+    fn test_non_stratifiable_program_rejected() {
+        let mut engine = IQLEngine::new();
+        engine.add_fact("edge", vec![(1, 2)]);
+
+        let program = "p(X, Y) <- edge(X, Y), !q(X, Y)\n\
+                       q(X, Y) <- p(X, Y)\n\
+                       result(X, Y) <- p(X, Y)";
+        let err = engine.execute(program).unwrap_err();
+        assert!(err.contains("not stratifiable"), "{err}");
+        assert!(err.contains("p -> !q -> p"), "{err}");
+    }
+
+    #[test]
+    fn test_explain_tuple_lists_witnesses() {
+        let mut engine = IQLEngine::new();
+        engine.add_fact("edge", vec![(1, 2), (2, 3), (1, 3)]);
+
+        let program = "path(X, Y) <- edge(X, Y)\n\
+                       path(X, Z) <- path(X, Y), edge(Y, Z)\n\
+                       result(X, Y) <- path(X, Y)";
+        assert!(engine
+            .explain_tuple("result", &Tuple::from_pair(1, 3))
+            .unwrap_err()
+            .contains("Provenance mode is off"));
+
+        engine.set_provenance_mode(true);
+        engine.execute(program).unwrap();
+
+        let why = engine
+            .explain_tuple("result", &Tuple::from_pair(1, 3))
+            .unwrap();
+        let witnesses = why.witnesses();
+        assert_eq!(witnesses.len(), 2, "{why}");
+        // Direct edge first, then the two-hop path
+        assert_eq!(witnesses[0].len(), 1);
+        assert_eq!(witnesses[1].len(), 2);
+
+        let base = engine
+            .explain_tuple("edge", &Tuple::from_pair(1, 2))
+            .unwrap();
+        assert_eq!(base.witnesses().len(), 1);
+        assert!(engine
+            .explain_tuple("result", &Tuple::from_pair(3, 1))
+            .is_err());
+    }
+
+    #[test]
+    fn test_stratum_rules_feed_each_other() {
+        let mut engine = IQLEngine::new();
+        engine.add_fact("edge", vec![(1, 2), (2, 3), (3, 4), (4, 3)]);
+
+        let (mut result, derived) = engine
+            .execute_tuples_with_derived(
+                "hop(X, Z) <- edge(X, Y), edge(Y, Z)\n\
+                 hop3(X, W) <- hop(X, Z), edge(Z, W)\n\
+                 result(X, W) <- hop3(X, W), edge(W, X)",
+            )
+            .unwrap();
+
+        let mut hop3 = derived["hop3"].clone();
+        hop3.sort();
+        assert_eq!(
+            hop3,
+            vec![
+                Tuple::from_pair(1, 4),
+                Tuple::from_pair(2, 3),
+                Tuple::from_pair(3, 4),
+                Tuple::from_pair(4, 3),
+            ]
+        );
+        result.sort();
+        assert_eq!(result, vec![Tuple::from_pair(3, 4), Tuple::from_pair(4, 3)]);
+    }
+
+    #[test]
+    fn test_independent_recursive_components_run_in_parallel() {
+        let program = "path(X, Y) <- edge(X, Y)\n\
+             path(X, Z) <- path(X, Y), edge(Y, Z)\n\
+             reach(X, Y) <- link(X, Y)\n\
+             reach(X, Z) <- reach(X, Y), link(Y, Z)\n\
+             result(X, Y) <- path(X, Y), reach(X, Y)";
+        let run = |parallel: bool| {
+            let mut engine = IQLEngine::new();
+            engine.add_fact("edge", vec![(1, 2), (2, 3), (3, 4)]);
+            engine.add_fact("link", vec![(1, 2), (2, 3), (5, 6)]);
+            engine.set_parallel_components(parallel);
+            let (mut result, derived) = engine.execute_tuples_with_derived(program).unwrap();
+            result.sort();
+            let mut reach = derived["reach"].clone();
+            reach.sort();
+            (result, reach)
+        };
+
+        let (result, reach) = run(true);
+        assert_eq!(
+            result,
+            vec![
+                Tuple::from_pair(1, 2),
+                Tuple::from_pair(1, 3),
+                Tuple::from_pair(2, 3),
+            ]
+        );
+        assert_eq!(
+            reach,
+            vec![
+                Tuple::from_pair(1, 2),
+                Tuple::from_pair(1, 3),
+                Tuple::from_pair(2, 3),
+                Tuple::from_pair(5, 6),
+            ]
+        );
+        assert_eq!(run(false), (result, reach));
+    }
+
+    #[test]
+    fn test_memory_budget_aborts_query() {
+        let program = "path(X, Y) <- edge(X, Y)\n\
+             path(X, Z) <- path(X, Y), edge(Y, Z)\n\
+             result(X, Y) <- path(X, Y)";
+        let chain: Vec<(i32, i32)> = (0..50).map(|i| (i, i + 1)).collect();
+
+        let mut engine = IQLEngine::new();
+        engine.add_fact("edge", chain.clone());
+        engine.set_max_query_memory(4096);
+        let err = engine.execute_tuples(program).unwrap_err();
+        assert!(err.contains("memory budget"), "{err}");
+
+        let mut engine = IQLEngine::new();
+        engine.add_fact("edge", chain);
+        engine.set_max_query_memory(64 * 1024 * 1024);
+        assert_eq!(engine.execute_tuples(program).unwrap().len(), 50 * 51 / 2);
+    }
+
+    #[test]
+    fn test_peak_memory_reported_and_enforced() {
+        let program = "path(X, Y) <- edge(X, Y)\n\
+             path(X, Z) <- path(X, Y), edge(Y, Z)\n\
+             result(X, Y) <- path(X, Y)";
+        let chain: Vec<(i32, i32)> = (0..50).map(|i| (i, i + 1)).collect();
+
+        let mut engine = IQLEngine::new();
+        engine.add_fact("edge", chain.clone());
+        let (_, _, timing) = engine.execute_tuples_profiled(program).unwrap();
+        let peak = timing.unwrap().peak_memory_bytes as usize;
+        assert!(peak > 0);
+
+        // The budget applies to the same measure
+        let mut engine = IQLEngine::new();
+        engine.add_fact("edge", chain.clone());
+        engine.set_max_query_memory(peak);
+        assert_eq!(engine.execute_tuples(program).unwrap().len(), 50 * 51 / 2);
+
+        let mut engine = IQLEngine::new();
+        engine.add_fact("edge", chain);
+        engine.set_max_query_memory(peak - 1);
+        let err = engine.execute_tuples(program).unwrap_err();
+        assert!(err.contains("memory budget"), "{err}");
+    }
+
+    #[test]
+    fn test_cancelled_handle_stops_execution() {
+        let program = "path(X, Y) <- edge(X, Y)\n\
+             path(X, Z) <- path(X, Y), edge(Y, Z)\n\
+             result(X, Y) <- path(X, Y)";
+
+        let handle = CancelHandle::new();
+        let mut engine = IQLEngine::new();
+        engine.add_fact("edge", vec![(1, 2), (2, 3)]);
+        engine.set_cancel_handle(Some(handle.clone()));
+        assert_eq!(engine.execute_tuples(program).unwrap().len(), 3);
+
+        handle.cancel();
+        let err = engine.execute_tuples(program).unwrap_err();
+        assert!(err.contains("cancelled"), "{err}");
+    }
+
+    #[test]
+    fn test_query_timeout_stops_runaway_recursion() {
+        let program = "path(X, Y) <- edge(X, Y)\n\
+             path(X, Z) <- path(X, Y), edge(Y, Z)\n\
+             result(X, Y) <- path(X, Y)";
+        let chain: Vec<(i32, i32)> = (0..3000).map(|i| (i, i + 1)).collect();
+
+        let mut engine = IQLEngine::new();
+        engine.add_fact("edge", chain);
+        engine.set_query_timeout_ms(50);
+        let err = engine.execute_tuples(program).unwrap_err();
+        assert!(err.contains("timeout"), "{err}");
+    }
+
+    #[test]
+    fn test_order_by_sorts_query_results() {
+        let mut engine = IQLEngine::new();
+        engine.add_fact("edge", vec![(2, 1), (1, 3), (3, 2), (1, 2)]);
+        engine.set_order_by(vec![
+            (0, ir::SortDirection::Asc),
+            (1, ir::SortDirection::Desc),
+        ]);
+
+        let (result, _) = engine
+            .execute_tuples_with_derived("result(X, Y) <- edge(X, Y)")
+            .unwrap();
+        assert_eq!(
+            result,
+            vec![
+                Tuple::from_pair(1, 3),
+                Tuple::from_pair(1, 2),
+                Tuple::from_pair(2, 1),
+                Tuple::from_pair(3, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_why_reconstructs_derivation_tree() {
+        let mut engine = IQLEngine::new();
+        engine.add_fact("edge", vec![(1, 2), (2, 3)]);
+        assert!(engine.why("path", &Tuple::from_pair(1, 3)).is_err());
+
+        engine
+            .execute(
+                "path(X, Y) <- edge(X, Y)\n\
+                 path(X, Z) <- path(X, Y), edge(Y, Z)",
+            )
+            .unwrap();
+
+        let tree = engine.why("path", &Tuple::from_pair(1, 3)).unwrap();
+        assert_eq!(tree.roots.len(), 1);
+        assert!(tree.rule_count() >= 2);
+        assert!(tree.fact_count() >= 2);
+        let rendered = tree.format_tree();
+        assert!(rendered.contains("[rule]"), "{rendered}");
+        assert!(rendered.contains("[base]"), "{rendered}");
+
+        assert!(engine.why("path", &Tuple::from_pair(3, 1)).is_err());
+    }
+
+    #[test]
+    fn test_compile_views_returns_plan_per_head() {
+        let mut engine = IQLEngine::new();
+        engine.add_fact("edge", vec![(1, 2)]);
+
+        let views = engine
+            .compile_views("hop(X, Z) <- edge(X, Y), edge(Y, Z)\nresult(X) <- hop(X, _)")
+            .unwrap();
+        let names: Vec<&str> = views.iter().map(|(name, _)| name.as_str()).collect();
+        assert!(names.contains(&"hop"));
+        assert!(names.contains(&"result"));
+
+        let err = engine
+            .compile_views("tc(X, Y) <- edge(X, Y)\ntc(X, Z) <- tc(X, Y), edge(Y, Z)")
+            .unwrap_err();
+        assert!(err.contains("Recursive rule 'tc'"), "{err}");
+    }
+
     #[test]
     fn test_set_num_workers() {
         let mut engine = IQLEngine::new();
@@ -2549,6 +4510,9 @@ mod tests {
             enable_subplan_sharing: false,
             enable_boolean_specialization: false,
             enable_magic_sets: false,
+            enable_semijoin_reduction: false,
+            enable_equality_saturation: false,
+            execution_backend: None,
         };
         let mut engine = IQLEngine::with_config(config);
         engine.add_tuples(
@@ -2932,6 +4896,51 @@ mod tests {
         assert_eq!(results.len(), 2);
     }
 
+    #[test]
+    fn test_top_k_nearest_routed_through_index() {
+        let mut engine = IQLEngine::new();
+        for (id, x) in [(1, 0.0), (2, 1.0), (3, 2.0), (4, 3.0)] {
+            engine.add_tuple(
+                "emb",
+                Tuple::new(vec![Value::Int64(id), Value::vector(vec![x, 0.0])]),
+            );
+        }
+        engine.set_indexed_columns(Arc::new(vec![IndexedColumn {
+            index: "emb_idx".to_string(),
+            relation: "emb".to_string(),
+            column: 1,
+            metric: DistanceMetric::Euclidean,
+        }]));
+        // The mock index answers with ids 3 and 4, so the result proves the
+        // scan was limited to its candidates
+        engine.set_hnsw_search_fn(Box::new(
+            |idx: &str, _q: &[f32], k: usize, _ef: Option<usize>| {
+                assert_eq!(idx, "emb_idx");
+                assert_eq!(k, 2);
+                Ok(vec![(3, 2.0), (4, 3.0)])
+            },
+        ));
+
+        let mut ids: Vec<Value> = engine
+            .execute_tuples("near(top_k<2, Id, D:asc>) <- emb(Id, V), D = euclidean(V, [0.0, 0.0])")
+            .unwrap()
+            .iter()
+            .filter_map(|t| t.get(0).cloned())
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec![Value::Int64(3), Value::Int64(4)]);
+
+        // Farthest-first rankings and other metrics still scan the relation
+        let results = engine
+            .execute_tuples("far(top_k<3, Id, D:desc>) <- emb(Id, V), D = euclidean(V, [0.0, 0.0])")
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        let results = engine
+            .execute_tuples("near(top_k<1, Id, D:asc>) <- emb(Id, V), D = cosine(V, [1.0, 1.0])")
+            .unwrap();
+        assert_eq!(results.len(), 1);
+    }
+
     // ====== Magic Sets Integration Tests ======
 
     #[test]
@@ -3159,4 +5168,56 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_subplan_cache_reused_across_queries() {
+        let mut engine = IQLEngine::new();
+        engine.enable_subplan_cache();
+        engine.add_fact("edge", vec![(1, 2), (2, 3), (3, 4)]);
+
+        let program = "path2(X, Z) <- edge(X, Y), edge(Y, Z)";
+        let mut first = engine.execute_tuples(program).unwrap();
+        let after_first = engine.subplan_cache().unwrap().stats();
+        assert!(after_first.entries > 0);
+        assert_eq!(after_first.hits, 0);
+
+        // Same subplans under different variable names hit the cache
+        let mut second = engine
+            .execute_tuples("path2(A, C) <- edge(A, B), edge(B, C)")
+            .unwrap();
+        assert!(engine.subplan_cache().unwrap().stats().hits > 0);
+
+        first.sort();
+        second.sort();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_subplan_cache_invalidated_on_insert() {
+        let mut engine = IQLEngine::new();
+        engine.enable_subplan_cache();
+        engine.add_fact("edge", vec![(1, 2), (2, 3)]);
+
+        let program = "path2(X, Z) <- edge(X, Y), edge(Y, Z)";
+        assert_eq!(engine.execute(program).unwrap(), vec![(1, 3)]);
+
+        let invalidated = crate::metrics::metrics().subplan_cache_invalidations.get();
+        engine.add_fact("edge", vec![(1, 2), (2, 3), (3, 4)]);
+        assert!(engine.subplan_cache().unwrap().stats().invalidations > 0);
+        assert!(crate::metrics::metrics().subplan_cache_invalidations.get() > invalidated);
+
+        let mut results = engine.execute(program).unwrap();
+        results.sort_unstable();
+        assert_eq!(results, vec![(1, 3), (2, 4)]);
+    }
+
+    #[test]
+    fn test_subplan_cache_disabled_by_default() {
+        let mut engine = IQLEngine::new();
+        engine.add_fact("edge", vec![(1, 2), (2, 3)]);
+        engine
+            .execute("path2(X, Z) <- edge(X, Y), edge(Y, Z)")
+            .unwrap();
+        assert!(engine.subplan_cache().is_none());
+    }
 }
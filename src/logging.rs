@@ -0,0 +1,132 @@
+//! Log Verbosity
+//!
+//! Engine and storage diagnostics go through `tracing` with the module
+//! path as target, so verbosity is chosen per module with `EnvFilter`
+//! directives such as `info,inputlayer::code_generator=debug`.
+//!
+//! The configured directives come from `[logging]` (`level` plus
+//! `targets`), overridden by `IL_TRACE_LEVEL`. The server installs its
+//! subscriber with a reloadable filter and registers it here, so an admin
+//! can change verbosity at runtime with `.log level <directives>` and go
+//! back to the configured filter with `.log reset`.
+
+use std::sync::OnceLock;
+
+use parking_lot::Mutex;
+use tracing_subscriber::EnvFilter;
+
+use crate::config::LoggingConfig;
+
+/// `EnvFilter` directives for `config`: the default level, then one
+/// directive per configured target
+pub fn filter_directives(config: &LoggingConfig) -> String {
+    let mut directives = config.level.clone();
+    for (target, level) in &config.targets {
+        directives.push_str(&format!(",{target}={level}"));
+    }
+    directives
+}
+
+/// Applies a new filter to the installed subscriber
+pub type Reload = Box<dyn Fn(EnvFilter) -> Result<(), String> + Send + Sync>;
+
+/// The active filter of a subscriber installed with filter reloading
+pub struct LogFilter {
+    configured: String,
+    current: Mutex<String>,
+    reload: Reload,
+}
+
+impl LogFilter {
+    pub fn new(configured: String, reload: Reload) -> Self {
+        Self {
+            current: Mutex::new(configured.clone()),
+            configured,
+            reload,
+        }
+    }
+
+    /// The directives in effect
+    pub fn current(&self) -> String {
+        self.current.lock().clone()
+    }
+
+    /// Replace the filter. Invalid directives leave it unchanged.
+    pub fn set(&self, directives: &str) -> Result<(), String> {
+        let directives = directives.trim();
+        let filter = EnvFilter::try_new(directives)
+            .map_err(|e| format!("Invalid log filter '{directives}': {e}"))?;
+        let mut current = self.current.lock();
+        (self.reload)(filter)?;
+        *current = directives.to_string();
+        Ok(())
+    }
+
+    /// Go back to the filter the server started with, returning it
+    pub fn reset(&self) -> Result<String, String> {
+        self.set(&self.configured)?;
+        Ok(self.configured.clone())
+    }
+}
+
+static FILTER: OnceLock<LogFilter> = OnceLock::new();
+
+/// Make the installed subscriber's filter adjustable with `.log`. Only
+/// the first registration takes effect.
+pub fn register(filter: LogFilter) {
+    let _ = FILTER.set(filter);
+}
+
+/// The process's adjustable filter, if the server registered one
+pub fn filter() -> Option<&'static LogFilter> {
+    FILTER.get()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_filter_directives_append_targets() {
+        let mut config = LoggingConfig::default();
+        assert_eq!(filter_directives(&config), "info");
+        config.level = "warn".to_string();
+        config
+            .targets
+            .insert("inputlayer::storage".to_string(), "debug".to_string());
+        config.targets.insert(
+            "inputlayer::code_generator".to_string(),
+            "trace".to_string(),
+        );
+        assert_eq!(
+            filter_directives(&config),
+            "warn,inputlayer::code_generator=trace,inputlayer::storage=debug"
+        );
+    }
+
+    #[test]
+    fn test_log_filter_set_and_reset() {
+        let reloads = Arc::new(Mutex::new(0));
+        let counter = Arc::clone(&reloads);
+        let filter = LogFilter::new(
+            "info".to_string(),
+            Box::new(move |_| {
+                *counter.lock() += 1;
+                Ok(())
+            }),
+        );
+
+        filter.set("warn,inputlayer::storage=debug").unwrap();
+        assert_eq!(filter.current(), "warn,inputlayer::storage=debug");
+
+        let err = filter.set("info,inputlayer=loud").unwrap_err();
+        assert!(err.starts_with("Invalid log filter"), "{err}");
+        assert_eq!(filter.current(), "warn,inputlayer::storage=debug");
+
+        assert_eq!(filter.reset().unwrap(), "info");
+        assert_eq!(filter.current(), "info");
+        assert_eq!(*reloads.lock(), 2);
+    }
+}
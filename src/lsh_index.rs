@@ -0,0 +1,271 @@
+//! LSH Index Implementation
+//!
+//! Keeps the `lsh_bucket` of every indexed vector materialized, so rules
+//! join on stored bucket → ID mappings instead of hashing the whole
+//! relation on each query.
+//!
+//! ## Layout
+//!
+//! An index with `tables` hash tables of `hyperplanes` bits each stores,
+//! for every table index `T` in `0..tables`, the bucket
+//! `lsh_bucket(V, T, hyperplanes)` of each vector. The mappings are
+//! published as a relation named after the index with rows
+//! `(Table, Bucket, Id)`:
+//!
+//! ```text
+//! candidate(Id) <- query_vec(Q), doc_lsh(T, B, Id), QB = lsh_bucket(Q, T, 8), QB = B
+//! ```
+//!
+//! Inserts and deletes update the mappings in place; there are no
+//! tombstones.
+
+use crate::index_manager::{DistanceMetric, Index, LshConfig, TupleId};
+use crate::value::{Tuple, Value};
+use crate::vector_ops::lsh_bucket;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Materialized LSH bucket mappings of one (tables, hyperplanes)
+/// configuration
+pub struct LshIndex {
+    /// Configuration
+    config: LshConfig,
+    /// Bucket of each ID, one per table
+    buckets_by_id: HashMap<TupleId, Vec<i64>>,
+    /// IDs in each (table, bucket)
+    ids_by_bucket: BTreeMap<(usize, i64), BTreeSet<TupleId>>,
+    /// Vector dimension (0 if not yet determined)
+    dimension: usize,
+}
+
+impl LshIndex {
+    /// Create an empty LSH index with the given configuration
+    pub fn new(config: LshConfig) -> Self {
+        Self {
+            config,
+            buckets_by_id: HashMap::new(),
+            ids_by_bucket: BTreeMap::new(),
+            dimension: 0,
+        }
+    }
+
+    /// Get the configuration
+    pub fn config(&self) -> &LshConfig {
+        &self.config
+    }
+
+    /// Bucket of `vector` in each table
+    fn buckets_of(&self, vector: &[f32]) -> Vec<i64> {
+        (0..self.config.tables)
+            .map(|table| lsh_bucket(vector, table as i64, self.config.hyperplanes))
+            .collect()
+    }
+
+    /// IDs stored in a bucket of one table
+    pub fn bucket(&self, table: usize, bucket: i64) -> impl Iterator<Item = TupleId> + '_ {
+        self.ids_by_bucket
+            .get(&(table, bucket))
+            .into_iter()
+            .flatten()
+            .copied()
+    }
+
+    /// The mappings as `(Table, Bucket, Id)` rows, ordered by table and
+    /// bucket
+    pub fn rows(&self) -> Vec<Tuple> {
+        self.ids_by_bucket
+            .iter()
+            .flat_map(|(&(table, bucket), ids)| {
+                ids.iter().map(move |&id| {
+                    Tuple::new(vec![
+                        Value::Int64(table as i64),
+                        Value::Int64(bucket),
+                        Value::Int64(id as i64),
+                    ])
+                })
+            })
+            .collect()
+    }
+}
+
+impl Index for LshIndex {
+    /// Candidates sharing a bucket with `query` in at least one table.
+    /// The distance is the fraction of tables in which they do not collide,
+    /// so IDs found in more tables rank first.
+    fn search(&self, query: &[f32], k: usize, _ef: Option<usize>) -> Vec<(TupleId, f64)> {
+        if query.len() != self.dimension || k == 0 {
+            return Vec::new();
+        }
+        let mut collisions: HashMap<TupleId, usize> = HashMap::new();
+        for (table, bucket) in self.buckets_of(query).into_iter().enumerate() {
+            for id in self.bucket(table, bucket) {
+                *collisions.entry(id).or_insert(0) += 1;
+            }
+        }
+        let tables = self.config.tables as f64;
+        let mut results: Vec<(TupleId, f64)> = collisions
+            .into_iter()
+            .map(|(id, hits)| (id, 1.0 - hits as f64 / tables))
+            .collect();
+        results.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        results.truncate(k);
+        results
+    }
+
+    fn insert(&mut self, id: TupleId, vector: &[f32]) -> Result<(), String> {
+        if vector.is_empty() {
+            return Err("Cannot index an empty vector".to_string());
+        }
+        if self.dimension == 0 {
+            self.dimension = vector.len();
+        } else if vector.len() != self.dimension {
+            return Err(format!(
+                "Dimension mismatch: expected {}, got {}",
+                self.dimension,
+                vector.len()
+            ));
+        }
+
+        self.delete(id);
+        let buckets = self.buckets_of(vector);
+        for (table, &bucket) in buckets.iter().enumerate() {
+            self.ids_by_bucket
+                .entry((table, bucket))
+                .or_default()
+                .insert(id);
+        }
+        self.buckets_by_id.insert(id, buckets);
+        Ok(())
+    }
+
+    fn delete(&mut self, id: TupleId) {
+        let Some(buckets) = self.buckets_by_id.remove(&id) else {
+            return;
+        };
+        for (table, bucket) in buckets.into_iter().enumerate() {
+            if let Some(ids) = self.ids_by_bucket.get_mut(&(table, bucket)) {
+                ids.remove(&id);
+                if ids.is_empty() {
+                    self.ids_by_bucket.remove(&(table, bucket));
+                }
+            }
+        }
+    }
+
+    fn tombstone_ratio(&self) -> f64 {
+        0.0
+    }
+
+    fn rebuild(&mut self, vectors: &[(TupleId, Vec<f32>)]) -> Result<(), String> {
+        self.buckets_by_id.clear();
+        self.ids_by_bucket.clear();
+        self.dimension = 0;
+        self.insert_batch(vectors)
+    }
+
+    fn len(&self) -> usize {
+        self.buckets_by_id.len()
+    }
+
+    fn index_type(&self) -> &'static str {
+        "lsh"
+    }
+
+    /// Random-hyperplane LSH approximates angular (cosine) distance
+    fn metric(&self) -> DistanceMetric {
+        DistanceMetric::Cosine
+    }
+
+    fn tombstone_count(&self) -> usize {
+        0
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn make_index(tables: usize, hyperplanes: usize) -> LshIndex {
+        LshIndex::new(LshConfig {
+            tables,
+            hyperplanes,
+        })
+    }
+
+    #[test]
+    fn test_lsh_buckets_match_lsh_bucket() {
+        let mut index = make_index(3, 8);
+        let v = vec![0.3, -0.7, 0.2];
+        index.insert(7, &v).unwrap();
+
+        let rows = index.rows();
+        assert_eq!(rows.len(), 3);
+        for table in 0..3 {
+            let bucket = lsh_bucket(&v, table as i64, 8);
+            assert_eq!(index.bucket(table, bucket).collect::<Vec<_>>(), vec![7]);
+        }
+    }
+
+    #[test]
+    fn test_lsh_insert_replace_and_delete() {
+        let mut index = make_index(2, 4);
+        index.insert(1, &[1.0, 0.0]).unwrap();
+        index.insert(2, &[0.0, 1.0]).unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.rows().len(), 4);
+
+        // Re-inserting an ID moves it to the buckets of its new vector
+        index.insert(1, &[0.0, 1.0]).unwrap();
+        assert_eq!(index.len(), 2);
+        let bucket = lsh_bucket(&[0.0, 1.0], 0, 4);
+        assert_eq!(index.bucket(0, bucket).collect::<Vec<_>>(), vec![1, 2]);
+
+        index.delete(2);
+        index.delete(99);
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.rows().len(), 2);
+        assert_eq!(index.tombstone_count(), 0);
+    }
+
+    #[test]
+    fn test_lsh_search_ranks_by_collisions() {
+        let mut index = make_index(4, 6);
+        index.insert(1, &[1.0, 0.0, 0.0]).unwrap();
+        index.insert(2, &[0.0, 0.0, 1.0]).unwrap();
+
+        let results = index.search(&[1.0, 0.0, 0.0], 5, None);
+        assert_eq!(results[0].0, 1);
+        assert!(index.search(&[1.0, 0.0], 5, None).is_empty());
+    }
+
+    #[test]
+    fn test_lsh_dimension_mismatch() {
+        let mut index = make_index(1, 4);
+        index.insert(1, &[1.0, 2.0]).unwrap();
+        assert!(index.insert(2, &[1.0]).is_err());
+        assert!(index.insert(3, &[]).is_err());
+    }
+
+    #[test]
+    fn test_lsh_rebuild() {
+        let mut index = make_index(2, 4);
+        index.insert(1, &[1.0, 0.0]).unwrap();
+        index
+            .rebuild(&[(5, vec![0.5, 0.5, 0.5]), (6, vec![0.1, 0.2, 0.3])])
+            .unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.dimension(), 3);
+        assert!(index
+            .rows()
+            .iter()
+            .all(|row| row.get(2) != Some(&Value::Int64(1))));
+    }
+}
@@ -0,0 +1,365 @@
+//! Internal Metrics Registry
+//!
+//! Process-wide counters and latency histograms recorded by the engine,
+//! the storage layer and the server, exported in the Prometheus text
+//! format at `/metrics`. Recording is a relaxed atomic add, cheap enough
+//! for every WAL append.
+//!
+//! Values that are cheap to read when scraped (row counts, WAL size,
+//! sessions, process memory) are collected by the endpoint instead of
+//! being tracked here.
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Bucket boundaries in seconds for latency histograms
+pub const LATENCY_BUCKETS: [f64; 8] = [0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
+
+/// A monotonically increasing count
+#[derive(Debug, Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, n: u64) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A Prometheus-style histogram with fixed buckets.
+///
+/// Each bucket counter tracks cumulative observations <= its boundary; the
+/// last one is +Inf (all observations).
+#[derive(Debug)]
+pub struct Histogram {
+    bounds: &'static [f64],
+    /// Cumulative bucket counters, one per bound plus +Inf
+    buckets: Box<[AtomicU64]>,
+    /// Sum of all observed values, in microseconds
+    sum_us: AtomicU64,
+    count: AtomicU64,
+}
+
+impl Histogram {
+    pub fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            buckets: (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect(),
+            sum_us: AtomicU64::new(0),
+            count: AtomicU64::new(0),
+        }
+    }
+
+    /// Record a duration in microseconds
+    pub fn record_us(&self, us: u64) {
+        let secs = us as f64 / 1_000_000.0;
+        for (bucket, &bound) in self.buckets.iter().zip(self.bounds) {
+            if secs <= bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.buckets[self.bounds.len()].fetch_add(1, Ordering::Relaxed);
+        self.sum_us.fetch_add(us, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn observe(&self, elapsed: Duration) {
+        self.record_us(elapsed.as_micros() as u64);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// Append this histogram as Prometheus text exposition lines.
+    /// `name` is the full metric name (e.g. `inputlayer_query_parse_seconds`).
+    pub fn format_prometheus(&self, name: &str, help: &str, out: &mut String) {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} histogram");
+        for (bucket, &bound) in self.buckets.iter().zip(self.bounds) {
+            let cumulative = bucket.load(Ordering::Relaxed);
+            let _ = writeln!(out, "{name}_bucket{{le=\"{bound}\"}} {cumulative}");
+        }
+        let inf_count = self.buckets[self.bounds.len()].load(Ordering::Relaxed);
+        let _ = writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {inf_count}");
+        let sum_secs = self.sum_us.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let _ = writeln!(out, "{name}_sum {sum_secs}");
+        let _ = writeln!(out, "{name}_count {}", self.count());
+    }
+}
+
+/// Append a single-sample metric as Prometheus text exposition lines.
+/// `kind` is `counter` or `gauge`.
+pub fn write_metric(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    kind: &str,
+    value: impl std::fmt::Display,
+) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+    let _ = writeln!(out, "{name} {value}");
+}
+
+/// Append a gauge with one sample per label value, e.g. per knowledge graph
+pub fn write_labeled_gauge<V: std::fmt::Display>(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    label: &str,
+    samples: impl IntoIterator<Item = (String, V)>,
+) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} gauge");
+    for (value_of_label, value) in samples {
+        let escaped = value_of_label
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n");
+        let _ = writeln!(out, "{name}{{{label}=\"{escaped}\"}} {value}");
+    }
+}
+
+/// Everything recorded by the engine and storage layer
+#[derive(Debug)]
+pub struct Metrics {
+    /// Programs that returned an error
+    pub query_errors: Counter,
+    /// Programs cancelled by the query timeout
+    pub query_timeouts: Counter,
+    /// Time programs waited for a free execution slot
+    pub query_queue_wait: Histogram,
+    /// Programs refused because the queue was full or they waited too long
+    pub queries_rejected: Counter,
+    /// Times a running program lent its slot to a higher-priority one
+    pub queries_preempted: Counter,
+
+    /// Subplan cache lookups answered from the cache
+    pub subplan_cache_hits: Counter,
+    /// Subplan cache lookups that found no entry
+    pub subplan_cache_misses: Counter,
+    /// Subplan cache entries dropped because a relation they read changed,
+    /// each a stale result that will not be served
+    pub subplan_cache_invalidations: Counter,
+
+    /// Evicted relations read back from the persist layer
+    pub relation_loads: Counter,
+    /// Relations evicted from memory to stay under the residency cap
+    pub relation_evictions: Counter,
+
+    /// Entries appended to the WAL
+    pub wal_appends: Counter,
+    /// Bytes appended to the WAL
+    pub wal_bytes_written: Counter,
+    /// Time spent flushing and fsyncing the WAL
+    pub wal_sync: Histogram,
+    /// Shard buffers written to batch files
+    pub persist_flushes: Counter,
+    /// Updates written to batch files
+    pub persist_flushed_updates: Counter,
+    /// Time spent writing one shard buffer to a batch file
+    pub persist_flush: Histogram,
+
+    /// Kafka messages inserted into their relation
+    pub kafka_messages_ingested: Counter,
+    /// Kafka messages skipped because they could not be decoded
+    pub kafka_messages_skipped: Counter,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        Self {
+            query_errors: Counter::default(),
+            query_timeouts: Counter::default(),
+            query_queue_wait: Histogram::new(&LATENCY_BUCKETS),
+            queries_rejected: Counter::default(),
+            queries_preempted: Counter::default(),
+            subplan_cache_hits: Counter::default(),
+            subplan_cache_misses: Counter::default(),
+            subplan_cache_invalidations: Counter::default(),
+            relation_loads: Counter::default(),
+            relation_evictions: Counter::default(),
+            wal_appends: Counter::default(),
+            wal_bytes_written: Counter::default(),
+            wal_sync: Histogram::new(&LATENCY_BUCKETS),
+            persist_flushes: Counter::default(),
+            persist_flushed_updates: Counter::default(),
+            persist_flush: Histogram::new(&LATENCY_BUCKETS),
+            kafka_messages_ingested: Counter::default(),
+            kafka_messages_skipped: Counter::default(),
+        }
+    }
+
+    /// Append every metric as Prometheus text exposition
+    pub fn format_prometheus(&self, out: &mut String) {
+        let counters = [
+            (
+                "inputlayer_query_errors_total",
+                "Programs that returned an error.",
+                &self.query_errors,
+            ),
+            (
+                "inputlayer_query_timeouts_total",
+                "Programs cancelled by the query timeout.",
+                &self.query_timeouts,
+            ),
+            (
+                "inputlayer_queries_rejected_total",
+                "Programs refused by admission control (queue full or wait too long).",
+                &self.queries_rejected,
+            ),
+            (
+                "inputlayer_queries_preempted_total",
+                "Times a running program yielded its slot to a higher-priority one between strata.",
+                &self.queries_preempted,
+            ),
+            (
+                "inputlayer_subplan_cache_hits_total",
+                "Subplan cache lookups answered from the cache.",
+                &self.subplan_cache_hits,
+            ),
+            (
+                "inputlayer_subplan_cache_misses_total",
+                "Subplan cache lookups that found no entry.",
+                &self.subplan_cache_misses,
+            ),
+            (
+                "inputlayer_subplan_cache_invalidations_total",
+                "Subplan cache entries dropped because a relation they read changed.",
+                &self.subplan_cache_invalidations,
+            ),
+            (
+                "inputlayer_relation_loads_total",
+                "Evicted relations read back from disk.",
+                &self.relation_loads,
+            ),
+            (
+                "inputlayer_relation_evictions_total",
+                "Relations evicted from memory by the residency cap.",
+                &self.relation_evictions,
+            ),
+            (
+                "inputlayer_wal_appends_total",
+                "Entries appended to the write-ahead log.",
+                &self.wal_appends,
+            ),
+            (
+                "inputlayer_wal_written_bytes_total",
+                "Bytes appended to the write-ahead log.",
+                &self.wal_bytes_written,
+            ),
+            (
+                "inputlayer_persist_flushes_total",
+                "Shard buffers written to batch files.",
+                &self.persist_flushes,
+            ),
+            (
+                "inputlayer_persist_flushed_updates_total",
+                "Updates written to batch files.",
+                &self.persist_flushed_updates,
+            ),
+            (
+                "inputlayer_kafka_messages_ingested_total",
+                "Kafka messages inserted into their relation.",
+                &self.kafka_messages_ingested,
+            ),
+            (
+                "inputlayer_kafka_messages_skipped_total",
+                "Kafka messages skipped because they could not be decoded.",
+                &self.kafka_messages_skipped,
+            ),
+        ];
+        for (name, help, counter) in counters {
+            write_metric(out, name, help, "counter", counter.get());
+        }
+
+        self.query_queue_wait.format_prometheus(
+            "inputlayer_query_queue_wait_seconds",
+            "Time programs waited for a free execution slot.",
+            out,
+        );
+        self.wal_sync.format_prometheus(
+            "inputlayer_wal_sync_seconds",
+            "Time spent flushing and fsyncing the write-ahead log.",
+            out,
+        );
+        self.persist_flush.format_prometheus(
+            "inputlayer_persist_flush_seconds",
+            "Time spent writing a shard buffer to a batch file.",
+            out,
+        );
+    }
+}
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+/// The process-wide metrics
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::new)
+}
+
+/// Resident set size of this process in bytes, where the OS reports it
+pub fn process_resident_bytes() -> Option<u64> {
+    // statm: total program size, then resident pages
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    Some(pages * 4096)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let histogram = Histogram::new(&LATENCY_BUCKETS);
+        histogram.observe(Duration::from_micros(500));
+        histogram.observe(Duration::from_millis(20));
+        histogram.observe(Duration::from_secs(10));
+
+        let mut out = String::new();
+        histogram.format_prometheus("t_seconds", "Test.", &mut out);
+        assert!(out.contains("# TYPE t_seconds histogram"));
+        assert!(out.contains("t_seconds_bucket{le=\"0.001\"} 1\n"));
+        assert!(out.contains("t_seconds_bucket{le=\"0.05\"} 2\n"));
+        assert!(out.contains("t_seconds_bucket{le=\"5\"} 2\n"));
+        assert!(out.contains("t_seconds_bucket{le=\"+Inf\"} 3\n"));
+        assert!(out.contains("t_seconds_sum 10.0205\n"));
+        assert!(out.contains("t_seconds_count 3\n"));
+    }
+
+    #[test]
+    fn test_labeled_gauge_escapes_label_values() {
+        let mut out = String::new();
+        write_labeled_gauge(
+            &mut out,
+            "t_rows",
+            "Rows.",
+            "knowledge_graph",
+            [("a".to_string(), 3), ("q\"x".to_string(), 0)],
+        );
+        assert!(out.contains("t_rows{knowledge_graph=\"a\"} 3\n"));
+        assert!(out.contains("t_rows{knowledge_graph=\"q\\\"x\"} 0\n"));
+    }
+
+    #[test]
+    fn test_metrics_exported() {
+        metrics().wal_appends.add(2);
+        let mut out = String::new();
+        metrics().format_prometheus(&mut out);
+        assert!(out.contains("# TYPE inputlayer_wal_appends_total counter"));
+        assert!(out.contains("inputlayer_persist_flush_seconds_count"));
+        assert!(metrics().wal_appends.get() >= 2);
+    }
+}
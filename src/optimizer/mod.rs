@@ -5,7 +5,10 @@
 //! - Map fusion: `Map(Map(x, p1), p2)` -> `Map(x, p1 compose p2)`
 //! - Filter fusion: `Filter(Filter(x, p1), p2)` -> `Filter(x, p1 && p2)`
 //! - Filter pushdown: `Filter(Join(A, B), pred)` -> `Join(Filter(A, pred), B)`
+//! - Constant folding: `Filter(x, 2 + 3 > 4)` -> `Filter(x, True)`,
+//!   `Filter(x, A = 1 AND A = 2)` -> `Filter(x, False)`
 //! - Identity elimination: `Map(x, id)` -> `x`, `Filter(x, True)` -> `x`
+//! - Dead branch elimination: `Union(x, Filter(y, False))` -> `x`
 //! - Logic fusion: `Filter(Map(x, proj), pred)` -> `FlatMap(x, proj, pred)`
 //!
 //! ```text
@@ -87,6 +90,9 @@ impl Optimizer {
 
     /// Apply all optimization rules once
     fn apply_all_rules(&self, ir: IRNode) -> IRNode {
+        // Constant folding (decides predicates for the eliminations below)
+        let ir = self.fold_constants(ir);
+
         // Identity elimination
         let ir = self.eliminate_identity_maps(ir);
         let ir = self.eliminate_always_true_filters(ir);
@@ -182,6 +188,20 @@ impl Optimizer {
                 output_schema,
             },
 
+            IRNode::Semijoin {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                output_schema,
+            } => IRNode::Semijoin {
+                left: Box::new(self.fuse_consecutive_maps(*left)),
+                right: Box::new(self.fuse_consecutive_maps(*right)),
+                left_keys,
+                right_keys,
+                output_schema,
+            },
+
             IRNode::Distinct { input } => IRNode::Distinct {
                 input: Box::new(self.fuse_consecutive_maps(*input)),
             },
@@ -291,6 +311,20 @@ impl Optimizer {
                 output_schema,
             },
 
+            IRNode::Semijoin {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                output_schema,
+            } => IRNode::Semijoin {
+                left: Box::new(self.fuse_consecutive_filters(*left)),
+                right: Box::new(self.fuse_consecutive_filters(*right)),
+                left_keys,
+                right_keys,
+                output_schema,
+            },
+
             IRNode::Distinct { input } => IRNode::Distinct {
                 input: Box::new(self.fuse_consecutive_filters(*input)),
             },
@@ -441,6 +475,20 @@ impl Optimizer {
                 output_schema,
             },
 
+            IRNode::Semijoin {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                output_schema,
+            } => IRNode::Semijoin {
+                left: Box::new(self.pushdown_filters(*left)),
+                right: Box::new(self.pushdown_filters(*right)),
+                left_keys,
+                right_keys,
+                output_schema,
+            },
+
             IRNode::Distinct { input } => IRNode::Distinct {
                 input: Box::new(self.pushdown_filters(*input)),
             },
@@ -691,6 +739,32 @@ impl Optimizer {
                 }
             }
 
+            IRNode::Semijoin {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                output_schema,
+            } => {
+                let left = self.eliminate_empty_unions(*left);
+                let right = self.eliminate_empty_unions(*right);
+
+                // Semijoin is empty if either side is empty
+                if matches!(&left, IRNode::Union { inputs } if inputs.is_empty())
+                    || matches!(&right, IRNode::Union { inputs } if inputs.is_empty())
+                {
+                    IRNode::Union { inputs: vec![] }
+                } else {
+                    IRNode::Semijoin {
+                        left: Box::new(left),
+                        right: Box::new(right),
+                        left_keys,
+                        right_keys,
+                        output_schema,
+                    }
+                }
+            }
+
             IRNode::Distinct { input } => {
                 let input = self.eliminate_empty_unions(*input);
                 if matches!(&input, IRNode::Union { inputs } if inputs.is_empty()) {
@@ -799,6 +873,20 @@ impl Optimizer {
                 output_schema,
             },
 
+            IRNode::Semijoin {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                output_schema,
+            } => IRNode::Semijoin {
+                left: Box::new(self.eliminate_identity_maps(*left)),
+                right: Box::new(self.eliminate_identity_maps(*right)),
+                left_keys,
+                right_keys,
+                output_schema,
+            },
+
             IRNode::Distinct { input } => IRNode::Distinct {
                 input: Box::new(self.eliminate_identity_maps(*input)),
             },
@@ -877,6 +965,20 @@ impl Optimizer {
                 output_schema,
             },
 
+            IRNode::Semijoin {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                output_schema,
+            } => IRNode::Semijoin {
+                left: Box::new(self.eliminate_always_true_filters(*left)),
+                right: Box::new(self.eliminate_always_true_filters(*right)),
+                left_keys,
+                right_keys,
+                output_schema,
+            },
+
             IRNode::Distinct { input } => IRNode::Distinct {
                 input: Box::new(self.eliminate_always_true_filters(*input)),
             },
@@ -957,6 +1059,20 @@ impl Optimizer {
                 output_schema,
             },
 
+            IRNode::Semijoin {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                output_schema,
+            } => IRNode::Semijoin {
+                left: Box::new(self.eliminate_always_false_filters(*left)),
+                right: Box::new(self.eliminate_always_false_filters(*right)),
+                left_keys,
+                right_keys,
+                output_schema,
+            },
+
             IRNode::Distinct { input } => IRNode::Distinct {
                 input: Box::new(self.eliminate_always_false_filters(*input)),
             },
@@ -977,6 +1093,112 @@ impl Optimizer {
         }
     }
 
+    /// Rule: Fold constants and simplify predicates
+    ///
+    /// - `Filter(x, 2 + 3 > 4)` -> `Filter(x, True)`
+    /// - `Filter(x, A = 1 AND A = 2)` -> `Filter(x, False)`
+    /// - `Compute(x, [Y = 2 * 3])` -> `Compute(x, [Y = 6])`
+    ///
+    /// The identity and dead-branch rules then drop the decided filters.
+    #[allow(
+        unknown_lints,
+        clippy::only_used_in_recursion,
+        clippy::self_only_used_in_recursion
+    )]
+    fn fold_constants(&self, ir: IRNode) -> IRNode {
+        match ir {
+            IRNode::Filter { input, predicate } => IRNode::Filter {
+                input: Box::new(self.fold_constants(*input)),
+                predicate: predicate.fold_constants(),
+            },
+
+            IRNode::Compute { input, expressions } => IRNode::Compute {
+                input: Box::new(self.fold_constants(*input)),
+                expressions: expressions
+                    .into_iter()
+                    .map(|(name, expr)| (name, expr.fold_constants()))
+                    .collect(),
+            },
+
+            IRNode::Map {
+                input,
+                projection,
+                output_schema,
+            } => IRNode::Map {
+                input: Box::new(self.fold_constants(*input)),
+                projection,
+                output_schema,
+            },
+
+            IRNode::Join {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                output_schema,
+            } => IRNode::Join {
+                left: Box::new(self.fold_constants(*left)),
+                right: Box::new(self.fold_constants(*right)),
+                left_keys,
+                right_keys,
+                output_schema,
+            },
+
+            IRNode::Antijoin {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                output_schema,
+            } => IRNode::Antijoin {
+                left: Box::new(self.fold_constants(*left)),
+                right: Box::new(self.fold_constants(*right)),
+                left_keys,
+                right_keys,
+                output_schema,
+            },
+
+            IRNode::Semijoin {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                output_schema,
+            } => IRNode::Semijoin {
+                left: Box::new(self.fold_constants(*left)),
+                right: Box::new(self.fold_constants(*right)),
+                left_keys,
+                right_keys,
+                output_schema,
+            },
+
+            IRNode::Distinct { input } => IRNode::Distinct {
+                input: Box::new(self.fold_constants(*input)),
+            },
+
+            IRNode::Union { inputs } => IRNode::Union {
+                inputs: inputs
+                    .into_iter()
+                    .map(|ir| self.fold_constants(ir))
+                    .collect(),
+            },
+
+            IRNode::Aggregate {
+                input,
+                group_by,
+                aggregations,
+                output_schema,
+            } => IRNode::Aggregate {
+                input: Box::new(self.fold_constants(*input)),
+                group_by,
+                aggregations,
+                output_schema,
+            },
+
+            other => other,
+        }
+    }
+
     /// Logic Fusion: Fuse Map+Filter into FlatMap
     ///
     /// Patterns recognized:
@@ -1052,6 +1274,20 @@ impl Optimizer {
                 output_schema,
             },
 
+            IRNode::Semijoin {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                output_schema,
+            } => IRNode::Semijoin {
+                left: Box::new(self.fuse_to_flatmap(*left)),
+                right: Box::new(self.fuse_to_flatmap(*right)),
+                left_keys,
+                right_keys,
+                output_schema,
+            },
+
             IRNode::Distinct { input } => IRNode::Distinct {
                 input: Box::new(self.fuse_to_flatmap(*input)),
             },
@@ -1283,6 +1519,20 @@ impl Optimizer {
                 output_schema,
             },
 
+            IRNode::Semijoin {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                output_schema,
+            } => IRNode::Semijoin {
+                left: Box::new(self.fuse_to_join_flatmap(*left)),
+                right: Box::new(self.fuse_to_join_flatmap(*right)),
+                left_keys,
+                right_keys,
+                output_schema,
+            },
+
             IRNode::Distinct { input } => IRNode::Distinct {
                 input: Box::new(self.fuse_to_join_flatmap(*input)),
             },
@@ -1431,6 +1681,29 @@ impl Optimizer {
                     && Self::ir_equals(r1, r2)
             }
 
+            (
+                IRNode::Semijoin {
+                    left: l1,
+                    right: r1,
+                    left_keys: lk1,
+                    right_keys: rk1,
+                    output_schema: s1,
+                },
+                IRNode::Semijoin {
+                    left: l2,
+                    right: r2,
+                    left_keys: lk2,
+                    right_keys: rk2,
+                    output_schema: s2,
+                },
+            ) => {
+                lk1 == lk2
+                    && rk1 == rk2
+                    && s1 == s2
+                    && Self::ir_equals(l1, l2)
+                    && Self::ir_equals(r1, r2)
+            }
+
             (
                 IRNode::FlatMap {
                     input: i1,
@@ -2426,4 +2699,184 @@ mod tests {
         let p2 = Predicate::ColumnGtConst(0, 42);
         assert!(!Optimizer::predicate_equals(&p1, &p2));
     }
+
+    // === Constant Folding ===
+
+    fn const_arith(op: crate::ast::ArithOp, l: i64, r: i64) -> crate::ast::ArithExpr {
+        crate::ast::ArithExpr::Binary {
+            op,
+            left: Box::new(crate::ast::ArithExpr::Constant(l)),
+            right: Box::new(crate::ast::ArithExpr::Constant(r)),
+        }
+    }
+
+    #[test]
+    fn test_fold_constant_arith_comparison_to_true() {
+        let optimizer = Optimizer::new();
+        // 2 + 3 > 4 always holds, so the filter disappears
+        let ir = IRNode::Filter {
+            input: Box::new(IRNode::Scan {
+                relation: "a".to_string(),
+                schema: vec!["x".to_string()],
+            }),
+            predicate: Predicate::ArithCompareConst(
+                const_arith(crate::ast::ArithOp::Add, 2, 3),
+                crate::ast::ComparisonOp::GreaterThan,
+                4,
+                std::collections::HashMap::new(),
+            ),
+        };
+        assert!(optimizer.optimize(ir).is_scan());
+    }
+
+    #[test]
+    fn test_fold_contradictory_branch_eliminated() {
+        let optimizer = Optimizer::new();
+        // x = 1 AND x = 2 can never hold
+        let ir = IRNode::Union {
+            inputs: vec![
+                IRNode::Filter {
+                    input: Box::new(IRNode::Scan {
+                        relation: "a".to_string(),
+                        schema: vec!["x".to_string()],
+                    }),
+                    predicate: Predicate::And(
+                        Box::new(Predicate::ColumnEqConst(0, 1)),
+                        Box::new(Predicate::ColumnEqConst(0, 2)),
+                    ),
+                },
+                IRNode::Scan {
+                    relation: "b".to_string(),
+                    schema: vec!["x".to_string()],
+                },
+            ],
+        };
+        match optimizer.optimize(ir) {
+            IRNode::Scan { relation, .. } => assert_eq!(relation, "b"),
+            other => panic!("Expected scan of b, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_fold_empty_range_is_false() {
+        let pred = Predicate::And(
+            Box::new(Predicate::ColumnGtConst(0, 5)),
+            Box::new(Predicate::And(
+                Box::new(Predicate::ColumnEqStr(1, "a".to_string())),
+                Box::new(Predicate::ColumnLeConst(0, 5)),
+            )),
+        );
+        assert!(pred.fold_constants().is_always_false());
+
+        // Non-empty range is kept
+        let pred = Predicate::And(
+            Box::new(Predicate::ColumnGeConst(0, 5)),
+            Box::new(Predicate::ColumnLeConst(0, 5)),
+        );
+        assert!(matches!(pred.fold_constants(), Predicate::And(_, _)));
+    }
+
+    #[test]
+    fn test_fold_self_comparisons() {
+        // X = X is unknown for a null X, so it is kept
+        assert_eq!(
+            Predicate::ColumnsEq(1, 1).fold_constants(),
+            Predicate::ColumnsEq(1, 1)
+        );
+        assert!(Predicate::ColumnsNe(1, 1)
+            .fold_constants()
+            .is_always_false());
+        assert!(Predicate::ColumnsLt(1, 1)
+            .fold_constants()
+            .is_always_false());
+        // X <= X is false for non-numeric values at runtime, so it is kept
+        assert_eq!(
+            Predicate::ColumnsLe(1, 1).fold_constants(),
+            Predicate::ColumnsLe(1, 1)
+        );
+        // Contradiction inside AND decides the whole predicate
+        let pred = Predicate::And(
+            Box::new(Predicate::ColumnGtConst(0, 3)),
+            Box::new(Predicate::ColumnsNe(2, 2)),
+        );
+        assert!(pred.fold_constants().is_always_false());
+    }
+
+    #[test]
+    fn test_fold_column_compare_arith_expression() {
+        let pred = Predicate::ColumnCompareArith(
+            0,
+            crate::ast::ComparisonOp::LessThan,
+            const_arith(crate::ast::ArithOp::Mul, 4, 5),
+            std::collections::HashMap::new(),
+        );
+        match pred.fold_constants() {
+            Predicate::ColumnCompareArith(0, _, crate::ast::ArithExpr::Constant(20), _) => {}
+            other => panic!("Expected folded expression, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_fold_compute_expressions() {
+        use crate::ir::{ArithOp, IRExpression};
+
+        let optimizer = Optimizer::new();
+        let arith = |op, l: IRExpression, r: IRExpression| IRExpression::Arithmetic {
+            op,
+            left: Box::new(l),
+            right: Box::new(r),
+        };
+        let ir = IRNode::Compute {
+            input: Box::new(IRNode::Scan {
+                relation: "a".to_string(),
+                schema: vec!["x".to_string()],
+            }),
+            expressions: vec![
+                (
+                    "y".to_string(),
+                    arith(
+                        ArithOp::Add,
+                        IRExpression::Column(0),
+                        arith(
+                            ArithOp::Mul,
+                            IRExpression::IntConstant(2),
+                            IRExpression::IntConstant(3),
+                        ),
+                    ),
+                ),
+                (
+                    "z".to_string(),
+                    arith(
+                        ArithOp::Div,
+                        IRExpression::IntConstant(7),
+                        IRExpression::IntConstant(2),
+                    ),
+                ),
+                (
+                    "w".to_string(),
+                    arith(
+                        ArithOp::Div,
+                        IRExpression::IntConstant(1),
+                        IRExpression::IntConstant(0),
+                    ),
+                ),
+            ],
+        };
+
+        let IRNode::Compute { expressions, .. } = optimizer.fold_constants(ir) else {
+            panic!("Expected compute");
+        };
+        assert_eq!(
+            expressions[0].1,
+            arith(
+                ArithOp::Add,
+                IRExpression::Column(0),
+                IRExpression::IntConstant(6)
+            )
+        );
+        // Division always produces a float at runtime
+        assert_eq!(expressions[1].1, IRExpression::FloatConstant(3.5));
+        // Division by zero is left for the runtime (yields null)
+        assert!(matches!(expressions[2].1, IRExpression::Arithmetic { .. }));
+    }
 }
@@ -388,6 +388,9 @@ fn parse_atom(s: &str) -> Result<Atom, String> {
     let paren_pos = s.find('(').ok_or_else(|| format!("Invalid atom: {s}"))?;
 
     let relation = s[..paren_pos].trim().to_string();
+    if !s.ends_with(')') {
+        return Err(format!("Missing closing parenthesis in atom: {s}"));
+    }
 
     // Extract arguments - find matching closing parenthesis
     let args_str = s[paren_pos + 1..].trim_end_matches(')').trim();
@@ -891,6 +894,9 @@ mod tests {
         assert_eq!(atom.relation, "edge");
         assert_eq!(atom.args.len(), 2);
         assert!(matches!(atom.args[0], Term::Variable(_)));
+
+        assert!(parse_atom("edge(X, Y").is_err());
+        assert!(parse_atom("edge(").is_err());
     }
 
     #[test]
@@ -99,10 +99,11 @@ impl PipelineTrace {
             IRNode::Join { left, right, .. } => {
                 1 + Self::count_ir_nodes(left) + Self::count_ir_nodes(right)
             }
-            IRNode::Antijoin { left, right, .. } => {
+            IRNode::Antijoin { left, right, .. } | IRNode::Semijoin { left, right, .. } => {
                 1 + Self::count_ir_nodes(left) + Self::count_ir_nodes(right)
             }
             IRNode::Distinct { input } => 1 + Self::count_ir_nodes(input),
+            IRNode::Sort { input, .. } => 1 + Self::count_ir_nodes(input),
             IRNode::Union { inputs } => 1 + inputs.iter().map(Self::count_ir_nodes).sum::<usize>(),
             IRNode::Aggregate { input, .. } => 1 + Self::count_ir_nodes(input),
             IRNode::Compute { input, .. } => 1 + Self::count_ir_nodes(input),
@@ -352,6 +353,10 @@ impl PipelineTrace {
                 output.push_str(&format!("{prefix}Distinct\n"));
                 output.push_str(&Self::format_ir_tree(input, indent + 2));
             }
+            IRNode::Sort { input, order_by } => {
+                output.push_str(&format!("{prefix}Sort{order_by:?}\n"));
+                output.push_str(&Self::format_ir_tree(input, indent + 2));
+            }
             IRNode::Union { inputs } => {
                 output.push_str(&format!("{}Union ({} inputs)\n", prefix, inputs.len()));
                 for (i, input) in inputs.iter().enumerate() {
@@ -397,6 +402,25 @@ impl PipelineTrace {
                 output.push_str(&format!("{prefix}`- Right:\n"));
                 output.push_str(&Self::format_ir_tree(right, indent + 4));
             }
+            IRNode::Semijoin {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                output_schema,
+            } => {
+                output.push_str(&format!(
+                    "{}Semijoin[L:{:?}, R:{:?}] -> [{}]\n",
+                    prefix,
+                    left_keys,
+                    right_keys,
+                    output_schema.join(", ")
+                ));
+                output.push_str(&format!("{prefix}|- Left:\n"));
+                output.push_str(&Self::format_ir_tree(left, indent + 4));
+                output.push_str(&format!("{prefix}`- Right:\n"));
+                output.push_str(&Self::format_ir_tree(right, indent + 4));
+            }
             IRNode::Compute { input, expressions } => {
                 let expr_strs: Vec<String> =
                     expressions.iter().map(|(name, _)| name.clone()).collect();
@@ -0,0 +1,871 @@
+//! Admission Control
+//!
+//! Every query passes through [`Admission`] before it reaches the engine.
+//! At most `[admission] max_concurrent_queries` run at once; the rest wait
+//! in a bounded queue (`max_queued_queries`, `max_queued_per_knowledge_graph`)
+//! and are refused once it is full or after waiting `queue_timeout_ms`.
+//!
+//! Every query has a [`Priority`]: `interactive`, `normal` or `batch`. A
+//! freed slot goes to the highest priority with queries waiting. Within a
+//! priority, waiting queries are grouped by knowledge graph; the slot goes to
+//! the group at the front, which then moves to the back, so a knowledge graph
+//! flooded with queries gets one slot in turn with every other graph that
+//! has queries waiting rather than all of them.
+//!
+//! A running query does not wait for its slot to come free on its own: at
+//! each stratum boundary the engine asks its [`Preemption`] handle, and if a
+//! query of higher priority is waiting the slot is lent to it. The preempted
+//! query goes back to the front of its priority's queue and resumes from the
+//! next stratum once it gets a slot again.
+//!
+//! Each query is `queued`, `running` or `finished`; `show queries` lists the
+//! queued and running ones and the last `finished_history` finished ones.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+use tracing::debug;
+
+use crate::config::AdmissionConfig;
+use crate::schema::{ColumnSchema, RelationSchema, SchemaType};
+use crate::storage_engine::IntrospectionTable;
+use crate::value::{Tuple, Value};
+
+/// Characters of a program kept for `show queries`
+const PROGRAM_PREVIEW_CHARS: usize = 200;
+
+/// How often a preempted query checks whether it was interrupted
+const PREEMPTED_POLL: Duration = Duration::from_millis(50);
+
+/// Scheduling class of a query; higher classes are admitted first and take
+/// slots from lower ones between strata
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    /// Long-running recomputations and bulk jobs
+    Batch,
+    #[default]
+    Normal,
+    /// Queries someone is waiting on, such as the REPL's
+    Interactive,
+}
+
+impl Priority {
+    /// Highest first: the order waiting queries are served in
+    pub const ALL: [Priority; 3] = [Priority::Interactive, Priority::Normal, Priority::Batch];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Priority::Batch => "batch",
+            Priority::Normal => "normal",
+            Priority::Interactive => "interactive",
+        }
+    }
+
+    /// Position in [`Self::ALL`]
+    fn level(self) -> usize {
+        match self {
+            Priority::Interactive => 0,
+            Priority::Normal => 1,
+            Priority::Batch => 2,
+        }
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Priority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Priority::ALL
+            .into_iter()
+            .find(|priority| priority.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("Unknown priority '{s}'. Use: interactive, normal, batch"))
+    }
+}
+
+/// Where a query is in its life
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryState {
+    Queued,
+    Running,
+    Finished,
+}
+
+impl QueryState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QueryState::Queued => "queued",
+            QueryState::Running => "running",
+            QueryState::Finished => "finished",
+        }
+    }
+}
+
+/// A query known to the admission controller
+#[derive(Debug, Clone)]
+pub struct QueryInfo {
+    pub id: u64,
+    pub knowledge_graph: String,
+    /// The start of the program text
+    pub program: String,
+    pub priority: Priority,
+    pub state: QueryState,
+    /// Times it lent its slot to a higher-priority query
+    pub preempted: u32,
+    /// Wall-clock submission time, in milliseconds since the Unix epoch
+    pub submitted_at_ms: i64,
+    submitted: Instant,
+    started: Option<Instant>,
+    finished: Option<Instant>,
+}
+
+impl QueryInfo {
+    /// Time spent waiting for a slot
+    pub fn queued_for(&self) -> Duration {
+        let until = self.started.or(self.finished).unwrap_or_else(Instant::now);
+        until.duration_since(self.submitted)
+    }
+
+    /// Time since it first got a slot, including any time it spent
+    /// preempted; zero while first queued
+    pub fn running_for(&self) -> Duration {
+        self.started.map_or(Duration::ZERO, |started| {
+            self.finished
+                .unwrap_or_else(Instant::now)
+                .duration_since(started)
+        })
+    }
+}
+
+/// Query counts by state, for metrics
+#[derive(Debug, Clone, Default)]
+pub struct AdmissionStats {
+    pub running: usize,
+    pub queued: usize,
+    /// Waiting queries per knowledge graph that has any
+    pub queued_by_knowledge_graph: Vec<(String, usize)>,
+    /// Waiting queries per priority, highest first
+    pub queued_by_priority: Vec<(Priority, usize)>,
+}
+
+/// How a waiting query is told it has a slot
+enum Grant {
+    /// A query waiting to start, on an async task
+    Task(oneshot::Sender<()>),
+    /// A preempted query, blocked on its engine thread
+    Thread(mpsc::SyncSender<()>),
+}
+
+impl Grant {
+    fn send(self) {
+        // The waiter removes itself under the lock before its receiver goes
+        // away, so the grant is always received
+        match self {
+            Grant::Task(grant) => {
+                let _ = grant.send(());
+            }
+            Grant::Thread(grant) => {
+                let _ = grant.send(());
+            }
+        }
+    }
+}
+
+struct Waiter {
+    id: u64,
+    grant: Grant,
+}
+
+/// Waiting queries of one priority per knowledge graph, in the order the
+/// graphs get their next slot
+type Level = VecDeque<(String, VecDeque<Waiter>)>;
+
+#[derive(Default)]
+struct Queues {
+    running: usize,
+    queued: usize,
+    /// Waiting queries by priority, in the order of [`Priority::ALL`]
+    waiting: [Level; Priority::ALL.len()],
+    /// Queued and running queries
+    active: BTreeMap<u64, QueryInfo>,
+    /// Most recently finished last
+    finished: VecDeque<QueryInfo>,
+}
+
+impl Queues {
+    /// Whether a query is waiting in any of the first `levels` priorities
+    fn waiting_in(&self, levels: usize) -> bool {
+        self.waiting[..levels].iter().any(|level| !level.is_empty())
+    }
+
+    /// Queries waiting for `kg`, at any priority
+    fn waiting_for(&self, kg: &str) -> usize {
+        self.waiting
+            .iter()
+            .flatten()
+            .filter(|(name, _)| name == kg)
+            .map(|(_, waiters)| waiters.len())
+            .sum()
+    }
+
+    /// Queue `waiter`. A preempted query goes `first`, ahead of everything
+    /// else waiting at its priority.
+    fn enqueue(&mut self, priority: Priority, kg: &str, waiter: Waiter, first: bool) {
+        let level = &mut self.waiting[priority.level()];
+        let group = level.iter().position(|(name, _)| name == kg);
+        let mut waiters = group
+            .and_then(|group| level.remove(group))
+            .map_or_else(VecDeque::new, |(_, waiters)| waiters);
+        if first {
+            waiters.push_front(waiter);
+            level.push_front((kg.to_string(), waiters));
+        } else {
+            waiters.push_back(waiter);
+            match group {
+                Some(group) => level.insert(group, (kg.to_string(), waiters)),
+                None => level.push_back((kg.to_string(), waiters)),
+            }
+        }
+        self.queued += 1;
+    }
+
+    /// Take query `id` out of the queue; false if it is not waiting (it was
+    /// granted a slot in the meantime)
+    fn dequeue(&mut self, priority: Priority, kg: &str, id: u64) -> bool {
+        let level = &mut self.waiting[priority.level()];
+        let Some(group) = level.iter().position(|(name, _)| name == kg) else {
+            return false;
+        };
+        let waiters = &mut level[group].1;
+        let Some(index) = waiters.iter().position(|waiter| waiter.id == id) else {
+            return false;
+        };
+        waiters.remove(index);
+        if waiters.is_empty() {
+            level.remove(group);
+        }
+        self.queued -= 1;
+        true
+    }
+}
+
+/// Bounded, per-knowledge-graph fair queue in front of the engine
+pub struct Admission {
+    max_running: usize,
+    max_queued: usize,
+    max_queued_per_kg: usize,
+    queue_timeout: Option<Duration>,
+    history: usize,
+    next_id: AtomicU64,
+    queues: Mutex<Queues>,
+}
+
+impl Admission {
+    pub fn new(config: &AdmissionConfig) -> Self {
+        Self {
+            max_running: config.concurrency().max(1),
+            max_queued: config.max_queued_queries,
+            max_queued_per_kg: config.max_queued_per_knowledge_graph,
+            queue_timeout: (config.queue_timeout_ms > 0)
+                .then(|| Duration::from_millis(config.queue_timeout_ms)),
+            history: config.finished_history,
+            next_id: AtomicU64::new(1),
+            queues: Mutex::new(Queues::default()),
+        }
+    }
+
+    /// Queries allowed to run at once
+    pub fn max_running(&self) -> usize {
+        self.max_running
+    }
+
+    /// Wait for a slot to run `program` against `kg` at `priority`. The
+    /// query counts as running until the returned permit is dropped.
+    pub async fn admit(
+        self: &Arc<Self>,
+        kg: &str,
+        program: &str,
+        priority: Priority,
+    ) -> Result<AdmissionPermit, String> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let info = QueryInfo {
+            id,
+            knowledge_graph: kg.to_string(),
+            program: program.trim().chars().take(PROGRAM_PREVIEW_CHARS).collect(),
+            priority,
+            state: QueryState::Queued,
+            preempted: 0,
+            submitted_at_ms: now_ms(),
+            submitted: Instant::now(),
+            started: None,
+            finished: None,
+        };
+        let granted = {
+            let mut queues = self.queues.lock();
+            // Nobody of this priority or higher is waiting, so taking a free
+            // slot skips no one
+            if queues.running < self.max_running && !queues.waiting_in(priority.level() + 1) {
+                queues.running += 1;
+                let mut info = info;
+                info.state = QueryState::Running;
+                info.started = Some(info.submitted);
+                queues.active.insert(id, info);
+                crate::metrics::metrics()
+                    .query_queue_wait
+                    .observe(Duration::ZERO);
+                return Ok(AdmissionPermit {
+                    admission: Arc::clone(self),
+                    id,
+                });
+            }
+            if self.max_queued > 0 && queues.queued >= self.max_queued {
+                crate::metrics::metrics().queries_rejected.inc();
+                return Err(format!(
+                    "Server overloaded: query queue full ({} queries waiting)",
+                    queues.queued
+                ));
+            }
+            let waiting = queues.waiting_for(kg);
+            if self.max_queued_per_kg > 0 && waiting >= self.max_queued_per_kg {
+                crate::metrics::metrics().queries_rejected.inc();
+                return Err(format!(
+                    "Server overloaded: {waiting} queries already waiting for knowledge graph '{kg}'"
+                ));
+            }
+            let (grant, granted) = oneshot::channel();
+            let waiter = Waiter {
+                id,
+                grant: Grant::Task(grant),
+            };
+            queues.enqueue(priority, kg, waiter, false);
+            queues.active.insert(id, info);
+            granted
+        };
+        let wait_start = Instant::now();
+
+        let mut pending = Pending {
+            admission: self,
+            id,
+            kg,
+            priority,
+            admitted: false,
+        };
+        let outcome = match self.queue_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, granted).await,
+            None => Ok(granted.await),
+        };
+        match outcome {
+            Ok(Ok(())) => {
+                pending.admitted = true;
+                crate::metrics::metrics()
+                    .query_queue_wait
+                    .observe(wait_start.elapsed());
+                Ok(AdmissionPermit {
+                    admission: Arc::clone(self),
+                    id,
+                })
+            }
+            Ok(Err(_)) => Err("Query queue closed (server shutting down)".to_string()),
+            Err(_) => {
+                crate::metrics::metrics().queries_rejected.inc();
+                Err(format!(
+                    "Server overloaded: query waited {}ms for a free slot",
+                    self.queue_timeout.unwrap_or_default().as_millis()
+                ))
+            }
+        }
+    }
+
+    /// Hand free slots to waiting queries: highest priority first, one
+    /// knowledge graph at a time within a priority
+    fn grant_waiting(&self, queues: &mut Queues) {
+        while queues.running < self.max_running {
+            let Some(level) = queues.waiting.iter_mut().find(|level| !level.is_empty()) else {
+                return;
+            };
+            let Some((kg, mut waiters)) = level.pop_front() else {
+                return;
+            };
+            let Some(waiter) = waiters.pop_front() else {
+                continue;
+            };
+            if !waiters.is_empty() {
+                level.push_back((kg, waiters));
+            }
+            queues.queued -= 1;
+            queues.running += 1;
+            if let Some(info) = queues.active.get_mut(&waiter.id) {
+                info.state = QueryState::Running;
+                info.started.get_or_insert_with(Instant::now);
+            }
+            waiter.grant.send();
+        }
+    }
+
+    /// A running query finished: free its slot
+    fn finish(&self, id: u64) {
+        let mut queues = self.queues.lock();
+        queues.running -= 1;
+        if let Some(mut info) = queues.active.remove(&id) {
+            info.state = QueryState::Finished;
+            info.finished = Some(Instant::now());
+            if self.history > 0 {
+                if queues.finished.len() >= self.history {
+                    queues.finished.pop_front();
+                }
+                queues.finished.push_back(info);
+            }
+        }
+        self.grant_waiting(&mut queues);
+    }
+
+    /// Queued and running queries, oldest first, then the finished ones,
+    /// most recent first
+    pub fn queries(&self) -> Vec<QueryInfo> {
+        let queues = self.queues.lock();
+        queues
+            .active
+            .values()
+            .chain(queues.finished.iter().rev())
+            .cloned()
+            .collect()
+    }
+
+    pub fn stats(&self) -> AdmissionStats {
+        let queues = self.queues.lock();
+        let mut queued_by_knowledge_graph: Vec<(String, usize)> = Vec::new();
+        for (kg, waiters) in queues.waiting.iter().flatten() {
+            match queued_by_knowledge_graph
+                .iter_mut()
+                .find(|(name, _)| name == kg)
+            {
+                Some((_, queued)) => *queued += waiters.len(),
+                None => queued_by_knowledge_graph.push((kg.clone(), waiters.len())),
+            }
+        }
+        AdmissionStats {
+            running: queues.running,
+            queued: queues.queued,
+            queued_by_knowledge_graph,
+            queued_by_priority: Priority::ALL
+                .into_iter()
+                .zip(&queues.waiting)
+                .map(|(priority, level)| {
+                    (priority, level.iter().map(|(_, w)| w.len()).sum::<usize>())
+                })
+                .collect(),
+        }
+    }
+
+    /// The `show queries` table: one row per query of [`Self::queries`]
+    pub fn show_queries(&self) -> IntrospectionTable {
+        let schema = [
+            ("id", SchemaType::Int),
+            ("state", SchemaType::String),
+            ("knowledge_graph", SchemaType::String),
+            ("submitted_at", SchemaType::Timestamp),
+            ("queued_ms", SchemaType::Int),
+            ("running_ms", SchemaType::Int),
+            ("priority", SchemaType::String),
+            ("preempted", SchemaType::Int),
+            ("program", SchemaType::String),
+        ]
+        .into_iter()
+        .fold(RelationSchema::new("queries"), |schema, (name, ty)| {
+            schema.with_column(ColumnSchema::new(name, ty))
+        });
+        let millis = |d: Duration| Value::Int64(i64::try_from(d.as_millis()).unwrap_or(i64::MAX));
+        let rows = self
+            .queries()
+            .into_iter()
+            .map(|query| {
+                Tuple::new(vec![
+                    Value::Int64(i64::try_from(query.id).unwrap_or(i64::MAX)),
+                    Value::string(query.state.as_str()),
+                    Value::string(&query.knowledge_graph),
+                    Value::Timestamp(query.submitted_at_ms),
+                    millis(query.queued_for()),
+                    millis(query.running_for()),
+                    Value::string(query.priority.as_str()),
+                    Value::Int64(i64::from(query.preempted)),
+                    Value::string(&query.program),
+                ])
+            })
+            .collect();
+        (schema, rows)
+    }
+}
+
+/// A query holding an execution slot; dropping it frees the slot
+pub struct AdmissionPermit {
+    admission: Arc<Admission>,
+    id: u64,
+}
+
+impl AdmissionPermit {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Handle the engine uses to lend this query's slot between strata
+    pub fn preemption(&self) -> Preemption {
+        Preemption {
+            admission: Arc::clone(&self.admission),
+            id: self.id,
+        }
+    }
+}
+
+/// Lets a running query give its slot to waiting higher-priority queries
+#[derive(Clone)]
+pub struct Preemption {
+    admission: Arc<Admission>,
+    id: u64,
+}
+
+impl Preemption {
+    /// If a query of higher priority is waiting, lend it this query's slot
+    /// and block until a slot comes back, or until `interrupted` reports the
+    /// query was cancelled or timed out. Called on the engine thread between
+    /// strata, which must hold no storage lock.
+    pub fn yield_to_higher(&self, interrupted: &dyn Fn() -> bool) {
+        let admission = &self.admission;
+        let (grant, granted) = mpsc::sync_channel(1);
+        let (priority, kg) = {
+            let mut queues = admission.queues.lock();
+            let Some(info) = queues.active.get(&self.id) else {
+                return;
+            };
+            let (priority, kg) = (info.priority, info.knowledge_graph.clone());
+            if !queues.waiting_in(priority.level()) {
+                return;
+            }
+            if let Some(info) = queues.active.get_mut(&self.id) {
+                info.state = QueryState::Queued;
+                info.preempted += 1;
+            }
+            queues.running -= 1;
+            let waiter = Waiter {
+                id: self.id,
+                grant: Grant::Thread(grant),
+            };
+            queues.enqueue(priority, &kg, waiter, true);
+            admission.grant_waiting(&mut queues);
+            (priority, kg)
+        };
+        crate::metrics::metrics().queries_preempted.inc();
+        debug!(query_id = self.id, %priority, kg = %kg, "query_preempted");
+        loop {
+            match granted.recv_timeout(PREEMPTED_POLL) {
+                Ok(()) | Err(mpsc::RecvTimeoutError::Disconnected) => return,
+                Err(mpsc::RecvTimeoutError::Timeout) if interrupted() => {
+                    let mut queues = admission.queues.lock();
+                    // Take the slot back without waiting, so the permit's
+                    // release stays balanced; the engine stops right after
+                    if queues.dequeue(priority, &kg, self.id) {
+                        queues.running += 1;
+                        if let Some(info) = queues.active.get_mut(&self.id) {
+                            info.state = QueryState::Running;
+                        }
+                    }
+                    return;
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+            }
+        }
+    }
+
+    /// Have the engine on this thread yield through this handle between
+    /// strata, until the returned guard is dropped
+    pub fn install(&self) -> PreemptionScope {
+        let preemption = self.clone();
+        crate::code_generator::set_stratum_yield(Some(Arc::new(
+            move |interrupted: &dyn Fn() -> bool| preemption.yield_to_higher(interrupted),
+        )));
+        PreemptionScope(())
+    }
+}
+
+impl Drop for AdmissionPermit {
+    fn drop(&mut self) {
+        self.admission.finish(self.id);
+    }
+}
+
+/// Removes the engine's stratum yield hook when dropped
+pub struct PreemptionScope(());
+
+impl Drop for PreemptionScope {
+    fn drop(&mut self) {
+        crate::code_generator::set_stratum_yield(None);
+    }
+}
+
+/// A query waiting in the queue. If it gives up (timed out, or the caller
+/// went away), dropping this takes it out of the queue, or frees the slot
+/// it was granted in the meantime.
+struct Pending<'a> {
+    admission: &'a Admission,
+    id: u64,
+    kg: &'a str,
+    priority: Priority,
+    admitted: bool,
+}
+
+impl Drop for Pending<'_> {
+    fn drop(&mut self) {
+        if self.admitted {
+            return;
+        }
+        let mut queues = self.admission.queues.lock();
+        if queues.dequeue(self.priority, self.kg, self.id) {
+            queues.active.remove(&self.id);
+        } else {
+            drop(queues);
+            self.admission.finish(self.id);
+        }
+    }
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| i64::try_from(d.as_millis()).unwrap_or(i64::MAX))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn admission(running: usize, queued: usize) -> Arc<Admission> {
+        Arc::new(Admission::new(&AdmissionConfig {
+            max_concurrent_queries: running,
+            max_queued_queries: queued,
+            queue_timeout_ms: 0,
+            ..AdmissionConfig::default()
+        }))
+    }
+
+    async fn wait_for_queued(admission: &Admission, queued: usize) {
+        while admission.stats().queued < queued {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slots_rotate_between_knowledge_graphs() {
+        let admission = admission(1, 0);
+        let first = admission
+            .admit("a", "?first(X)", Priority::Normal)
+            .await
+            .unwrap();
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut tasks = Vec::new();
+        for (n, (kg, program)) in [("a", "a1"), ("a", "a2"), ("b", "b1")]
+            .into_iter()
+            .enumerate()
+        {
+            let queued = Arc::clone(&admission);
+            let order = Arc::clone(&order);
+            tasks.push(tokio::spawn(async move {
+                let _permit = queued.admit(kg, program, Priority::Normal).await.unwrap();
+                order.lock().push(program);
+            }));
+            wait_for_queued(&admission, n + 1).await;
+        }
+        let stats = admission.stats();
+        assert_eq!((stats.running, stats.queued), (1, 3));
+        assert_eq!(
+            stats.queued_by_knowledge_graph,
+            vec![("a".to_string(), 2), ("b".to_string(), 1)]
+        );
+
+        drop(first);
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(*order.lock(), vec!["a1", "b1", "a2"]);
+
+        let queries = admission.queries();
+        assert_eq!(queries.len(), 4);
+        assert!(queries.iter().all(|q| q.state == QueryState::Finished));
+        let (schema, rows) = admission.show_queries();
+        assert_eq!(schema.columns.len(), 9);
+        assert_eq!(rows.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_higher_priority_is_admitted_first() {
+        let admission = admission(1, 0);
+        let first = admission
+            .admit("a", "?first(X)", Priority::Batch)
+            .await
+            .unwrap();
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut tasks = Vec::new();
+        for (n, priority) in [Priority::Batch, Priority::Normal, Priority::Interactive]
+            .into_iter()
+            .enumerate()
+        {
+            let queued = Arc::clone(&admission);
+            let order = Arc::clone(&order);
+            tasks.push(tokio::spawn(async move {
+                let _permit = queued.admit("a", "?q(X)", priority).await.unwrap();
+                order.lock().push(priority);
+            }));
+            wait_for_queued(&admission, n + 1).await;
+        }
+        assert_eq!(
+            admission.stats().queued_by_priority,
+            vec![
+                (Priority::Interactive, 1),
+                (Priority::Normal, 1),
+                (Priority::Batch, 1)
+            ]
+        );
+
+        drop(first);
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(
+            *order.lock(),
+            vec![Priority::Interactive, Priority::Normal, Priority::Batch]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_running_query_yields_to_higher_priority() {
+        let admission = admission(1, 0);
+        let batch = admission
+            .admit("a", "?batch(X)", Priority::Batch)
+            .await
+            .unwrap();
+        let preemption = batch.preemption();
+
+        // Nothing of higher priority waiting: the slot is kept
+        preemption.yield_to_higher(&|| false);
+        assert_eq!(admission.stats().running, 1);
+
+        let interactive = {
+            let admission = Arc::clone(&admission);
+            tokio::spawn(async move {
+                admission
+                    .admit("b", "?now(X)", Priority::Interactive)
+                    .await
+                    .map(|p| p.id())
+            })
+        };
+        wait_for_queued(&admission, 1).await;
+        let yielder = preemption.clone();
+        tokio::task::spawn_blocking(move || yielder.yield_to_higher(&|| false))
+            .await
+            .unwrap();
+        interactive.await.unwrap().unwrap();
+        let info = admission
+            .queries()
+            .into_iter()
+            .find(|q| q.id == batch.id())
+            .unwrap();
+        assert_eq!((info.state, info.preempted), (QueryState::Running, 1));
+        assert_eq!(admission.stats().running, 1);
+
+        // Interrupted while preempted: it takes its slot back at once
+        let (release, released) = oneshot::channel::<()>();
+        let holder = {
+            let admission = Arc::clone(&admission);
+            tokio::spawn(async move {
+                let _permit = admission
+                    .admit("b", "?hold(X)", Priority::Interactive)
+                    .await
+                    .unwrap();
+                let _ = released.await;
+            })
+        };
+        wait_for_queued(&admission, 1).await;
+        tokio::task::spawn_blocking(move || preemption.yield_to_higher(&|| true))
+            .await
+            .unwrap();
+        let stats = admission.stats();
+        assert_eq!((stats.running, stats.queued), (2, 0));
+
+        release.send(()).unwrap();
+        holder.await.unwrap();
+        drop(batch);
+        assert_eq!(admission.stats().running, 0);
+    }
+
+    #[test]
+    fn test_priority_parse() {
+        assert_eq!("Interactive".parse::<Priority>(), Ok(Priority::Interactive));
+        assert_eq!("batch".parse::<Priority>(), Ok(Priority::Batch));
+        assert!("urgent".parse::<Priority>().is_err());
+        assert!(Priority::Interactive > Priority::Normal);
+        assert_eq!(Priority::default(), Priority::Normal);
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_and_timeout_refuse_queries() {
+        let admission = admission(1, 1);
+        let running = admission
+            .admit("a", "?r(X)", Priority::Normal)
+            .await
+            .unwrap();
+        let waiting = {
+            let admission = Arc::clone(&admission);
+            tokio::spawn(async move {
+                admission
+                    .admit("a", "?w(X)", Priority::Normal)
+                    .await
+                    .map(|p| p.id())
+            })
+        };
+        wait_for_queued(&admission, 1).await;
+        let err = admission
+            .admit("b", "?x(X)", Priority::Normal)
+            .await
+            .err()
+            .unwrap();
+        assert!(err.contains("queue full"), "{err}");
+
+        // A waiter that gives up leaves the queue
+        waiting.abort();
+        let _ = waiting.await;
+        assert_eq!(admission.stats().queued, 0);
+
+        let timed = Arc::new(Admission::new(&AdmissionConfig {
+            max_concurrent_queries: 1,
+            queue_timeout_ms: 20,
+            ..AdmissionConfig::default()
+        }));
+        let _held = timed.admit("a", "?r(X)", Priority::Normal).await.unwrap();
+        let err = timed
+            .admit("a", "?w(X)", Priority::Normal)
+            .await
+            .err()
+            .unwrap();
+        assert!(err.contains("waited"), "{err}");
+        assert_eq!(timed.stats().queued, 0);
+
+        drop(running);
+        assert_eq!(admission.stats().running, 0);
+    }
+}
@@ -0,0 +1,1151 @@
+//! Async client
+//!
+//! `AsyncClient` runs programs on a server over the WebSocket protocol
+//! (`/ws`) from async code, for embedding in services:
+//!
+//! - a pool of authenticated connections, opened on demand up to
+//!   `pool_size` and reused between requests
+//! - `pipeline` sends independent programs on one connection without
+//!   waiting for each result; the server answers them in order
+//! - `batch` runs writes, view definitions and a final query atomically in
+//!   one round trip
+//! - rows come back as `Tuple`s
+//! - large messages are zstd or LZ4 compressed in both directions when the
+//!   server agrees to a codec
+//! - connecting, waiting for a free connection and waiting for each
+//!   response are bounded by timeouts
+//! - failed requests are retried with exponential backoff (see
+//!   [`RetryPolicy`]) on a new connection, trying the `failover_urls` of
+//!   a replicated deployment when a server is down or read-only
+//!
+//! A request is only retried when running it twice cannot do harm: when it
+//! never reached the server, when the server refused it without running it
+//! (throttled, or a read-only replica asked to write), or when it only
+//! reads. A write whose connection fails after it was sent is not retried,
+//! since it may have been applied; the error is returned.
+//!
+//! Every pooled connection is its own server session, so session rules and
+//! facts do not carry over from one request to the next. Statements that
+//! depend on each other belong in one program.
+//!
+//! ```rust,no_run
+//! # async fn example() -> Result<(), inputlayer::protocol::client::ClientError> {
+//! use inputlayer::protocol::client::{AsyncClient, ClientConfig, Credentials};
+//!
+//! let config = ClientConfig::new(
+//!     "http://127.0.0.1:8080",
+//!     Credentials::ApiKey("il_...".to_string()),
+//! );
+//! let client = AsyncClient::connect(config).await?;
+//! let edges = client.query("?edge(X, Y)").await?;
+//! let results = client.pipeline(&["?edge(1, Y)", "?edge(2, Y)"]).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures_util::{FutureExt, SinkExt, StreamExt};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpStream;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::protocol::compression::{self, Codec, FrameCompressor};
+use crate::protocol::handler::is_read_only_program;
+use crate::protocol::throttle::Throttled;
+use crate::protocol::MAX_MESSAGE_SIZE;
+use crate::value::{Tuple, Value};
+
+/// Programs sent ahead of their results on one connection. Bounded so
+/// neither side blocks writing while the other is not reading.
+const PIPELINE_WINDOW: usize = 32;
+
+/// Messages the client sends are compressed from this size on
+const COMPRESS_MIN_BYTES: usize = 1024;
+
+/// zstd level for messages the client sends
+const ZSTD_LEVEL: i32 = 3;
+
+/// How the client authenticates
+#[derive(Debug, Clone)]
+pub enum Credentials {
+    ApiKey(String),
+    Login { username: String, password: String },
+}
+
+/// When and how often a failed request is tried again
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Tries per request, the first included; 1 = no retries
+    pub max_attempts: u32,
+    /// Wait before the first retry; doubled for each one after it
+    pub initial_backoff: Duration,
+    /// Longest wait between tries
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Wait before try `attempt + 1`: the doubled backoff, capped, with
+    /// the upper half randomized so clients do not retry in lockstep
+    fn backoff(&self, attempt: u32) -> Duration {
+        let doubled = self
+            .initial_backoff
+            .saturating_mul(1 << attempt.saturating_sub(1).min(16));
+        let capped = doubled.min(self.max_backoff);
+        capped / 2 + capped.mul_f64(rand::thread_rng().gen_range(0.0..0.5))
+    }
+}
+
+/// Client settings. `new` fills in defaults for everything but the server
+/// and credentials.
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    /// Server address: `http(s)://host:port` or `ws(s)://host:port`
+    pub url: String,
+    /// Further servers of the same deployment (replicas, cluster members),
+    /// tried in order after `url` when it is down or does not take writes
+    pub failover_urls: Vec<String>,
+    pub credentials: Credentials,
+    /// Knowledge graph for new connections (default: the server's default)
+    pub knowledge_graph: Option<String>,
+    /// Maximum open connections
+    pub pool_size: usize,
+    /// Limit for opening and authenticating a connection
+    pub connect_timeout: Duration,
+    /// Limit for each response, and for waiting on a free connection
+    pub request_timeout: Duration,
+    /// Idle connections older than this are closed rather than reused.
+    /// Keep it below the server's `http.ws_idle_timeout_ms`.
+    pub idle_timeout: Duration,
+    /// TLS settings for `https` / `wss` (default: web PKI roots)
+    pub tls: Option<Arc<rustls::ClientConfig>>,
+    /// Compression codecs to offer, most preferred first (default: zstd,
+    /// LZ4). Empty = uncompressed.
+    pub compression: Vec<Codec>,
+    pub retry: RetryPolicy,
+}
+
+impl ClientConfig {
+    pub fn new(url: impl Into<String>, credentials: Credentials) -> Self {
+        Self {
+            url: url.into(),
+            failover_urls: Vec::new(),
+            credentials,
+            knowledge_graph: None,
+            pool_size: 8,
+            connect_timeout: Duration::from_secs(10),
+            request_timeout: Duration::from_secs(120),
+            idle_timeout: Duration::from_secs(60),
+            tls: None,
+            compression: vec![Codec::Zstd, Codec::Lz4],
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Address of server `endpoint`: 0 is `url`, then the `failover_urls`
+    fn endpoint(&self, endpoint: usize) -> &str {
+        match endpoint.checked_sub(1) {
+            Some(i) => &self.failover_urls[i],
+            None => &self.url,
+        }
+    }
+
+    fn endpoints(&self) -> usize {
+        1 + self.failover_urls.len()
+    }
+
+    /// WebSocket URL of the `/ws` endpoint of server `endpoint`
+    fn ws_url(&self, endpoint: usize) -> Result<String, ClientError> {
+        let url = self.endpoint(endpoint);
+        let base = url.trim_end_matches('/');
+        let base = if let Some(rest) = base.strip_prefix("https://") {
+            format!("wss://{rest}")
+        } else if let Some(rest) = base.strip_prefix("http://") {
+            format!("ws://{rest}")
+        } else if base.starts_with("ws://") || base.starts_with("wss://") {
+            base.to_string()
+        } else {
+            return Err(ClientError::Connect(format!(
+                "Unsupported server URL '{url}' (expected http, https, ws or wss)"
+            )));
+        };
+        Ok(match &self.knowledge_graph {
+            Some(kg) => format!("{base}/ws?kg={kg}"),
+            None => format!("{base}/ws"),
+        })
+    }
+}
+
+/// Client error
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("Connection failed: {0}")]
+    Connect(String),
+    #[error("Authentication failed: {0}")]
+    Auth(String),
+    #[error("Timed out after {0:?}")]
+    Timeout(Duration),
+    /// The server rejected the program (parse error, access denied, ...)
+    #[error("{0}")]
+    Server(String),
+    /// The server refused the program under a per-client limit
+    #[error("{0}")]
+    Throttled(Throttled),
+    #[error("Protocol error: {0}")]
+    Protocol(String),
+    #[error("Connection closed")]
+    Closed,
+}
+
+impl ClientError {
+    /// Whether the connection is still in step with the server after this
+    /// error. Errors about the program itself leave it usable.
+    fn keeps_connection(&self) -> bool {
+        matches!(self, ClientError::Server(_) | ClientError::Throttled(_))
+    }
+
+    /// Whether the server was a read-only replica asked to write
+    fn is_read_only_replica(&self) -> bool {
+        matches!(self, ClientError::Server(message) if message.contains("Read-only replica"))
+    }
+}
+
+/// A failed try at a request
+struct Failed {
+    error: ClientError,
+    /// Server the request went to
+    endpoint: usize,
+    /// Whether the request was sent, so it may have run
+    sent: bool,
+}
+
+impl Failed {
+    /// A failure before the request reached a connection
+    fn unsent(error: ClientError, endpoint: usize) -> Self {
+        Self {
+            error,
+            endpoint,
+            sent: false,
+        }
+    }
+
+    /// Whether trying again cannot apply the request twice and may succeed
+    fn is_retryable(&self, read_only: bool) -> bool {
+        match &self.error {
+            ClientError::Throttled(throttle) => throttle.retry_after_ms.is_some(),
+            error @ ClientError::Server(_) => error.is_read_only_replica(),
+            ClientError::Auth(_) => false,
+            // A slow program would only be slow again
+            ClientError::Timeout(_) => !self.sent,
+            ClientError::Connect(_) | ClientError::Protocol(_) | ClientError::Closed => {
+                !self.sent || read_only
+            }
+        }
+    }
+}
+
+/// Result of a program
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryResponse {
+    pub columns: Vec<String>,
+    /// Values arrive as JSON: timestamps and durations as integers, dates
+    /// and UUIDs as strings, numeric arrays as vectors
+    pub rows: Vec<Tuple>,
+    /// Rows before pagination
+    pub total_count: usize,
+    pub truncated: bool,
+    pub execution_time_ms: u64,
+    /// Set when the program switched knowledge graph (`.kg use`)
+    pub switched_kg: Option<String>,
+}
+
+/// Message to the server
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage<'a> {
+    Authenticate {
+        api_key: &'a str,
+        compression: &'a [Codec],
+    },
+    Login {
+        username: &'a str,
+        password: &'a str,
+        compression: &'a [Codec],
+    },
+    Execute {
+        program: &'a str,
+    },
+    Batch {
+        statements: Vec<&'a str>,
+    },
+}
+
+/// One result of a `batch_result`
+#[derive(Deserialize)]
+struct ResultJson {
+    columns: Vec<String>,
+    rows: Vec<Vec<serde_json::Value>>,
+    total_count: usize,
+    truncated: bool,
+    execution_time_ms: u64,
+}
+
+/// Message from the server; notifications and other pushes are `Other`
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage {
+    Authenticated {
+        #[serde(default)]
+        compression: Option<Codec>,
+    },
+    AuthError {
+        message: String,
+    },
+    Result {
+        columns: Vec<String>,
+        rows: Vec<Vec<serde_json::Value>>,
+        total_count: usize,
+        truncated: bool,
+        execution_time_ms: u64,
+        #[serde(default)]
+        switched_kg: Option<String>,
+    },
+    ResultStart {
+        columns: Vec<String>,
+        total_count: usize,
+        truncated: bool,
+        execution_time_ms: u64,
+        #[serde(default)]
+        switched_kg: Option<String>,
+    },
+    ResultChunk {
+        rows: Vec<Vec<serde_json::Value>>,
+    },
+    ResultEnd {},
+    BatchResult {
+        results: Vec<ResultJson>,
+    },
+    Error {
+        message: String,
+    },
+    Throttled {
+        #[serde(flatten)]
+        throttle: Throttled,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// Convert a result value from its JSON form
+fn value_from_json(json: serde_json::Value) -> Value {
+    match json {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Bool(b),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Int64(i),
+            None => Value::Float64(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        serde_json::Value::String(s) => Value::string(&s),
+        serde_json::Value::Array(items)
+            if !items.is_empty() && items.iter().all(serde_json::Value::is_number) =>
+        {
+            Value::vector(
+                items
+                    .iter()
+                    .filter_map(serde_json::Value::as_f64)
+                    .map(|f| f as f32)
+                    .collect(),
+            )
+        }
+        serde_json::Value::Array(items) => {
+            Value::list(items.into_iter().map(value_from_json).collect())
+        }
+        serde_json::Value::Object(entries) => Value::map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key, value_from_json(value))),
+        ),
+    }
+}
+
+fn rows_from_json(rows: Vec<Vec<serde_json::Value>>) -> impl Iterator<Item = Tuple> {
+    rows.into_iter()
+        .map(|row| Tuple::new(row.into_iter().map(value_from_json).collect()))
+}
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// One authenticated WebSocket connection
+struct Connection {
+    ws: WsStream,
+    /// Server the connection is to
+    endpoint: usize,
+    idle_since: Instant,
+    /// Codec agreed with the server, if any
+    compressor: Option<FrameCompressor>,
+}
+
+impl Connection {
+    async fn open(config: &ClientConfig, endpoint: usize) -> Result<Self, ClientError> {
+        let url = config.ws_url(endpoint)?;
+        let connector = config.tls.clone().map(tokio_tungstenite::Connector::Rustls);
+        let (ws, _) = tokio::time::timeout(
+            config.connect_timeout,
+            tokio_tungstenite::connect_async_tls_with_config(url, None, false, connector),
+        )
+        .await
+        .map_err(|_| ClientError::Timeout(config.connect_timeout))?
+        .map_err(|e| ClientError::Connect(e.to_string()))?;
+
+        let mut connection = Self {
+            ws,
+            endpoint,
+            idle_since: Instant::now(),
+            compressor: None,
+        };
+        let compression = &config.compression;
+        let auth = match &config.credentials {
+            Credentials::ApiKey(api_key) => ClientMessage::Authenticate {
+                api_key,
+                compression,
+            },
+            Credentials::Login { username, password } => ClientMessage::Login {
+                username,
+                password,
+                compression,
+            },
+        };
+        connection.send(&auth).await?;
+        loop {
+            match connection.recv(config.connect_timeout).await? {
+                ServerMessage::Authenticated { compression } => {
+                    connection.compressor = compression
+                        .map(|codec| FrameCompressor::new(codec, COMPRESS_MIN_BYTES, ZSTD_LEVEL));
+                    return Ok(connection);
+                }
+                ServerMessage::AuthError { message } => return Err(ClientError::Auth(message)),
+                _ => continue,
+            }
+        }
+    }
+
+    async fn send(&mut self, message: &ClientMessage<'_>) -> Result<(), ClientError> {
+        let text =
+            serde_json::to_string(message).map_err(|e| ClientError::Protocol(e.to_string()))?;
+        let frame = match self.compressor.and_then(|c| c.compress(text.as_bytes())) {
+            Some(compressed) => Message::Binary(compressed),
+            None => Message::Text(text),
+        };
+        self.ws.send(frame).await.map_err(|_| ClientError::Closed)
+    }
+
+    /// Next message from the server, skipping control frames
+    async fn recv(&mut self, timeout: Duration) -> Result<ServerMessage, ClientError> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let frame = tokio::time::timeout_at(deadline, self.ws.next())
+                .await
+                .map_err(|_| ClientError::Timeout(timeout))?;
+            match frame {
+                Some(Ok(Message::Close(_))) | None => return Err(ClientError::Closed),
+                Some(Ok(message)) => match self.parse(message) {
+                    Some(parsed) => return parsed,
+                    None => continue,
+                },
+                Some(Err(e)) => return Err(ClientError::Protocol(e.to_string())),
+            }
+        }
+    }
+
+    /// Parse a text or compressed binary frame; `None` for other frames
+    fn parse(&self, message: Message) -> Option<Result<ServerMessage, ClientError>> {
+        let parsed = match message {
+            Message::Text(text) => serde_json::from_str(&text),
+            Message::Binary(frame) if self.compressor.is_some() => {
+                match compression::decompress(&frame, MAX_MESSAGE_SIZE) {
+                    Ok(json) => serde_json::from_slice(&json),
+                    Err(e) => return Some(Err(ClientError::Protocol(e.to_string()))),
+                }
+            }
+            _ => return None,
+        };
+        Some(parsed.map_err(|e| ClientError::Protocol(e.to_string())))
+    }
+
+    /// Read the response to the oldest outstanding program, joining a
+    /// streamed result into one
+    async fn read_result(&mut self, timeout: Duration) -> Result<QueryResponse, ClientError> {
+        loop {
+            match self.recv(timeout).await? {
+                ServerMessage::Result {
+                    columns,
+                    rows,
+                    total_count,
+                    truncated,
+                    execution_time_ms,
+                    switched_kg,
+                } => {
+                    return Ok(QueryResponse {
+                        columns,
+                        rows: rows_from_json(rows).collect(),
+                        total_count,
+                        truncated,
+                        execution_time_ms,
+                        switched_kg,
+                    });
+                }
+                ServerMessage::ResultStart {
+                    columns,
+                    total_count,
+                    truncated,
+                    execution_time_ms,
+                    switched_kg,
+                } => {
+                    let mut response = QueryResponse {
+                        columns,
+                        rows: Vec::new(),
+                        total_count,
+                        truncated,
+                        execution_time_ms,
+                        switched_kg,
+                    };
+                    loop {
+                        match self.recv(timeout).await? {
+                            ServerMessage::ResultChunk { rows } => {
+                                response.rows.extend(rows_from_json(rows));
+                            }
+                            ServerMessage::ResultEnd {} => return Ok(response),
+                            ServerMessage::Other => continue,
+                            _ => {
+                                return Err(ClientError::Protocol(
+                                    "Unexpected message in a streamed result".to_string(),
+                                ))
+                            }
+                        }
+                    }
+                }
+                ServerMessage::Error { message } => return Err(ClientError::Server(message)),
+                ServerMessage::Throttled { throttle } => {
+                    return Err(ClientError::Throttled(throttle))
+                }
+                ServerMessage::Other => continue,
+                _ => {
+                    return Err(ClientError::Protocol(
+                        "Unexpected message while waiting for a result".to_string(),
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Read the response to a batch
+    async fn read_batch(&mut self, timeout: Duration) -> Result<Vec<QueryResponse>, ClientError> {
+        loop {
+            match self.recv(timeout).await? {
+                ServerMessage::BatchResult { results } => {
+                    return Ok(results
+                        .into_iter()
+                        .map(|result| QueryResponse {
+                            columns: result.columns,
+                            rows: rows_from_json(result.rows).collect(),
+                            total_count: result.total_count,
+                            truncated: result.truncated,
+                            execution_time_ms: result.execution_time_ms,
+                            switched_kg: None,
+                        })
+                        .collect());
+                }
+                ServerMessage::Error { message } => return Err(ClientError::Server(message)),
+                ServerMessage::Throttled { throttle } => {
+                    return Err(ClientError::Throttled(throttle))
+                }
+                ServerMessage::Other => continue,
+                _ => {
+                    return Err(ClientError::Protocol(
+                        "Unexpected message while waiting for a batch result".to_string(),
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Whether an idle connection can take another request: not expired,
+    /// not closed by the server, and nothing but pushes waiting to be read
+    fn is_reusable(&mut self, idle_timeout: Duration) -> bool {
+        if self.idle_since.elapsed() >= idle_timeout {
+            return false;
+        }
+        while let Some(frame) = self.ws.next().now_or_never() {
+            match frame {
+                Some(Ok(Message::Close(_)) | Err(_)) | None => return false,
+                Some(Ok(message)) => {
+                    // Anything but a notification means the server gave
+                    // up on the connection (idle timeout, lifetime, ...)
+                    match self.parse(message) {
+                        Some(Ok(ServerMessage::Other)) | None => continue,
+                        Some(_) => return false,
+                    }
+                }
+            }
+        }
+        true
+    }
+}
+
+struct Pool {
+    config: ClientConfig,
+    idle: parking_lot::Mutex<Vec<Connection>>,
+    slots: Arc<Semaphore>,
+    /// Server new connections go to, until it fails
+    endpoint: AtomicUsize,
+}
+
+impl Pool {
+    /// Open a connection to the current server, failing over to the next
+    /// ones in turn while they cannot be reached
+    async fn open(&self) -> Result<Connection, Failed> {
+        let first = self.endpoint.load(Ordering::Relaxed);
+        let mut last_error = None;
+        for offset in 0..self.config.endpoints() {
+            let endpoint = (first + offset) % self.config.endpoints();
+            match Connection::open(&self.config, endpoint).await {
+                Ok(connection) => {
+                    if endpoint != first {
+                        self.fail_over(first, endpoint);
+                    }
+                    return Ok(connection);
+                }
+                // Credentials and URLs are the same for every server
+                Err(e @ ClientError::Auth(_)) => return Err(Failed::unsent(e, endpoint)),
+                Err(e) => last_error = Some(Failed::unsent(e, endpoint)),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| unreachable!("a client has at least one server")))
+    }
+
+    /// Send new connections to server `to` instead of `from`, unless
+    /// another request already moved them on
+    fn fail_over(&self, from: usize, to: usize) {
+        if self
+            .endpoint
+            .compare_exchange(from, to, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+        {
+            tracing::warn!(
+                from = self.config.endpoint(from),
+                to = self.config.endpoint(to),
+                "client_failover"
+            );
+        }
+    }
+}
+
+/// A connection taken from the pool. Returned when dropped, unless it was
+/// marked broken.
+struct PooledConnection {
+    connection: Option<Connection>,
+    pool: Arc<Pool>,
+    _slot: OwnedSemaphorePermit,
+}
+
+impl PooledConnection {
+    fn get(&mut self) -> &mut Connection {
+        // Only `None` after `discard`, which consumes self
+        self.connection
+            .as_mut()
+            .unwrap_or_else(|| unreachable!("pooled connection used after discard"))
+    }
+
+    fn discard(mut self) {
+        self.connection = None;
+    }
+
+    /// Keep the connection only if `result` left it in step with the server
+    fn settle<T>(self, result: &Result<T, ClientError>) {
+        if let Err(e) = result {
+            if !e.keeps_connection() {
+                self.discard();
+            }
+        }
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(mut connection) = self.connection.take() {
+            connection.idle_since = Instant::now();
+            self.pool.idle.lock().push(connection);
+        }
+    }
+}
+
+/// Pooled async client; cheap to clone, clones share the pool
+#[derive(Clone)]
+pub struct AsyncClient {
+    pool: Arc<Pool>,
+}
+
+impl AsyncClient {
+    /// Create a client and open its first connection, so bad addresses and
+    /// credentials fail here
+    pub async fn connect(config: ClientConfig) -> Result<Self, ClientError> {
+        let slots = Arc::new(Semaphore::new(config.pool_size.max(1)));
+        let pool = Pool {
+            config,
+            idle: parking_lot::Mutex::new(Vec::new()),
+            slots,
+            endpoint: AtomicUsize::new(0),
+        };
+        let connection = pool.open().await.map_err(|failed| failed.error)?;
+        pool.idle.lock().push(connection);
+        Ok(Self {
+            pool: Arc::new(pool),
+        })
+    }
+
+    /// Run a program and return its result
+    pub async fn execute(&self, program: &str) -> Result<QueryResponse, ClientError> {
+        let read_only = is_read_only_program(program);
+        self.retrying(read_only, || self.execute_once(program))
+            .await
+    }
+
+    async fn execute_once(&self, program: &str) -> Result<QueryResponse, Failed> {
+        let timeout = self.pool.config.request_timeout;
+        let mut pooled = self.checkout().await?;
+        let connection = pooled.get();
+        let endpoint = connection.endpoint;
+        let result = match connection.send(&ClientMessage::Execute { program }).await {
+            Ok(()) => connection.read_result(timeout).await,
+            Err(e) => Err(e),
+        };
+        pooled.settle(&result);
+        result.map_err(|error| Failed {
+            error,
+            endpoint,
+            sent: true,
+        })
+    }
+
+    /// Run a query and return its rows
+    pub async fn query(&self, program: &str) -> Result<Vec<Tuple>, ClientError> {
+        Ok(self.execute(program).await?.rows)
+    }
+
+    /// Run independent programs on one connection, sending each without
+    /// waiting for the previous result. Results are in program order; one
+    /// program failing does not stop the rest. The outer error is for the
+    /// connection failing, after which the remaining results are unknown.
+    /// The whole pipeline is retried only if every program is read-only.
+    pub async fn pipeline<S: AsRef<str>>(
+        &self,
+        programs: &[S],
+    ) -> Result<Vec<Result<QueryResponse, ClientError>>, ClientError> {
+        let read_only = programs
+            .iter()
+            .all(|program| is_read_only_program(program.as_ref()));
+        self.retrying(read_only, || self.pipeline_once(programs))
+            .await
+    }
+
+    async fn pipeline_once<S: AsRef<str>>(
+        &self,
+        programs: &[S],
+    ) -> Result<Vec<Result<QueryResponse, ClientError>>, Failed> {
+        let timeout = self.pool.config.request_timeout;
+        let mut pooled = self.checkout().await?;
+        let connection = pooled.get();
+        let endpoint = connection.endpoint;
+        let mut results = Vec::with_capacity(programs.len());
+        let mut sent = 0;
+        while results.len() < programs.len() {
+            while sent < programs.len() && sent - results.len() < PIPELINE_WINDOW {
+                let program = programs[sent].as_ref();
+                if let Err(error) = connection.send(&ClientMessage::Execute { program }).await {
+                    pooled.discard();
+                    return Err(Failed {
+                        error,
+                        endpoint,
+                        sent: true,
+                    });
+                }
+                sent += 1;
+            }
+            match connection.read_result(timeout).await {
+                Err(error) if !error.keeps_connection() => {
+                    pooled.discard();
+                    return Err(Failed {
+                        error,
+                        endpoint,
+                        sent: true,
+                    });
+                }
+                result => results.push(result),
+            }
+        }
+        Ok(results)
+    }
+
+    /// Run inserts, deletes, updates and view definitions atomically in one
+    /// round trip, optionally followed by a query that sees them. Returns
+    /// one result per statement; on error nothing was applied.
+    pub async fn batch<S: AsRef<str>>(
+        &self,
+        statements: &[S],
+    ) -> Result<Vec<QueryResponse>, ClientError> {
+        let read_only = statements
+            .iter()
+            .all(|statement| is_read_only_program(statement.as_ref()));
+        self.retrying(read_only, || self.batch_once(statements))
+            .await
+    }
+
+    async fn batch_once<S: AsRef<str>>(
+        &self,
+        statements: &[S],
+    ) -> Result<Vec<QueryResponse>, Failed> {
+        let timeout = self.pool.config.request_timeout;
+        let statements = statements.iter().map(AsRef::as_ref).collect();
+        let mut pooled = self.checkout().await?;
+        let connection = pooled.get();
+        let endpoint = connection.endpoint;
+        let result = match connection.send(&ClientMessage::Batch { statements }).await {
+            Ok(()) => connection.read_batch(timeout).await,
+            Err(e) => Err(e),
+        };
+        pooled.settle(&result);
+        result.map_err(|error| Failed {
+            error,
+            endpoint,
+            sent: true,
+        })
+    }
+
+    /// Connections open and waiting for a request
+    pub fn idle_connections(&self) -> usize {
+        self.pool.idle.lock().len()
+    }
+
+    /// Address of the server new connections go to
+    pub fn current_url(&self) -> &str {
+        self.pool
+            .config
+            .endpoint(self.pool.endpoint.load(Ordering::Relaxed))
+    }
+
+    /// Try `attempt` until it succeeds, fails in a way a retry cannot fix,
+    /// or runs out of tries under the retry policy
+    async fn retrying<T, F>(
+        &self,
+        read_only: bool,
+        mut attempt: impl FnMut() -> F,
+    ) -> Result<T, ClientError>
+    where
+        F: Future<Output = Result<T, Failed>>,
+    {
+        let policy = &self.pool.config.retry;
+        let mut tries = 1;
+        loop {
+            let failed = match attempt().await {
+                Ok(value) => return Ok(value),
+                Err(failed) => failed,
+            };
+            if tries >= policy.max_attempts || !failed.is_retryable(read_only) {
+                return Err(failed.error);
+            }
+            // A replica will not take the write; move on to the next server
+            if failed.error.is_read_only_replica() {
+                let next = (failed.endpoint + 1) % self.pool.config.endpoints();
+                self.pool.fail_over(failed.endpoint, next);
+            }
+            let mut wait = policy.backoff(tries);
+            if let ClientError::Throttled(throttle) = &failed.error {
+                wait = wait.max(Duration::from_millis(
+                    throttle.retry_after_ms.unwrap_or_default(),
+                ));
+            }
+            tracing::debug!(
+                attempt = tries,
+                wait_ms = wait.as_millis() as u64,
+                error = %failed.error,
+                "client_retry"
+            );
+            tokio::time::sleep(wait).await;
+            tries += 1;
+        }
+    }
+
+    /// Take an idle connection to the current server, or open one if the
+    /// pool has room
+    async fn checkout(&self) -> Result<PooledConnection, Failed> {
+        let config = &self.pool.config;
+        let endpoint = self.pool.endpoint.load(Ordering::Relaxed);
+        let slot = tokio::time::timeout(
+            config.request_timeout,
+            Arc::clone(&self.pool.slots).acquire_owned(),
+        )
+        .await
+        .map_err(|_| Failed::unsent(ClientError::Timeout(config.request_timeout), endpoint))?
+        .map_err(|_| Failed::unsent(ClientError::Closed, endpoint))?;
+
+        let reused = loop {
+            let Some(mut connection) = self.pool.idle.lock().pop() else {
+                break None;
+            };
+            // Connections to a server failed over from are closed
+            if connection.endpoint == endpoint && connection.is_reusable(config.idle_timeout) {
+                break Some(connection);
+            }
+        };
+        let connection = match reused {
+            Some(connection) => connection,
+            None => self.pool.open().await?,
+        };
+        Ok(PooledConnection {
+            connection: Some(connection),
+            pool: Arc::clone(&self.pool),
+            _slot: slot,
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::protocol::rest::create_router;
+    use crate::protocol::Handler;
+
+    /// Serve a handler on a local port; returns the base URL and an API key
+    async fn start_server() -> (String, String, tempfile::TempDir) {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = crate::Config::default();
+        config.storage.auto_create_knowledge_graphs = true;
+        config.storage.data_dir = tmp.path().to_path_buf();
+        config.http.gui.enabled = false;
+        let handler = Arc::new(Handler::from_config(config.clone()).unwrap());
+        handler.bootstrap_auth();
+        let created = handler
+            .handle_apikey_create("client-test", "admin")
+            .unwrap();
+        let api_key = created.rows[0].values[1].as_str().unwrap().to_string();
+
+        let app = create_router(handler, &config.http);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+        (format!("http://{addr}"), api_key, tmp)
+    }
+
+    #[tokio::test]
+    async fn test_execute_query_and_pool_reuse() {
+        let (url, api_key, _tmp) = start_server().await;
+        let mut config = ClientConfig::new(url, Credentials::ApiKey(api_key));
+        config.knowledge_graph = Some("client_kg".to_string());
+        let client = AsyncClient::connect(config).await.unwrap();
+        assert_eq!(client.idle_connections(), 1);
+
+        client.execute("+edge[(1, 2), (2, 3)]").await.unwrap();
+        let mut rows = client.query("?edge(X, Y)").await.unwrap();
+        rows.sort();
+        let pair = |a, b| Tuple::new(vec![Value::Int64(a), Value::Int64(b)]);
+        assert_eq!(rows, vec![pair(1, 2), pair(2, 3)]);
+        // Sequential requests share one connection
+        assert_eq!(client.idle_connections(), 1);
+
+        // A bad program fails on its own and keeps the connection
+        let err = client.execute("?edge(X").await.unwrap_err();
+        assert!(matches!(err, ClientError::Server(_)));
+        assert_eq!(client.idle_connections(), 1);
+
+        // Concurrent requests open more connections, up to the pool size
+        let (a, b) = tokio::join!(client.query("?edge(1, Y)"), client.query("?edge(2, Y)"));
+        assert_eq!(a.unwrap().len(), 1);
+        assert_eq!(b.unwrap().len(), 1);
+        assert_eq!(client.idle_connections(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_keeps_order() {
+        let (url, api_key, _tmp) = start_server().await;
+        let client = AsyncClient::connect(ClientConfig::new(url, Credentials::ApiKey(api_key)))
+            .await
+            .unwrap();
+        client.execute("+n[(1,), (2,), (3,)]").await.unwrap();
+
+        let programs: Vec<String> = (1..=3)
+            .map(|i| format!("?n(X), X >= {i}"))
+            .chain(std::iter::once("?n(".to_string()))
+            .collect();
+        let results = client.pipeline(&programs).await.unwrap();
+        let counts: Vec<usize> = results[..3]
+            .iter()
+            .map(|r| r.as_ref().unwrap().rows.len())
+            .collect();
+        assert_eq!(counts, vec![3, 2, 1]);
+        assert!(results[3].is_err());
+        assert_eq!(client.idle_connections(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_batch_is_one_round_trip() {
+        let (url, api_key, _tmp) = start_server().await;
+        let client = AsyncClient::connect(ClientConfig::new(url, Credentials::ApiKey(api_key)))
+            .await
+            .unwrap();
+        let results = client
+            .batch(&["+b[(1,), (2,)]", "+b2(X) <- b(X)", "?b2(X)"])
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[2].rows.len(), 2);
+
+        // A failing batch applies nothing and keeps the connection
+        let err = client.batch(&["+b[(3,)]", "?b("]).await.unwrap_err();
+        assert!(matches!(err, ClientError::Server(_)));
+        assert_eq!(client.query("?b(X)").await.unwrap().len(), 2);
+        assert_eq!(client.idle_connections(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_compressed_results() {
+        let (url, api_key, _tmp) = start_server().await;
+        let config = ClientConfig::new(url, Credentials::ApiKey(api_key));
+        let vector = vec!["0.123456"; 64].join(", ");
+        let facts: Vec<String> = (0..200).map(|i| format!("({i}, [{vector}])")).collect();
+        let insert = format!("+emb[{}]", facts.join(", "));
+
+        for codecs in [vec![Codec::Zstd], vec![Codec::Lz4], Vec::new()] {
+            let mut config = config.clone();
+            config.compression = codecs.clone();
+            let connection = Connection::open(&config, 0).await.unwrap();
+            assert_eq!(
+                connection.compressor.map(|c| c.codec()),
+                codecs.first().copied()
+            );
+
+            // The insert goes out compressed, the vectors come back compressed
+            let client = AsyncClient::connect(config).await.unwrap();
+            client.execute(&insert).await.unwrap();
+            let rows = client.query("?emb(Id, V)").await.unwrap();
+            assert_eq!(rows.len(), 200);
+            assert_eq!(client.idle_connections(), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connect_failures_and_timeouts() {
+        let (url, _api_key, _tmp) = start_server().await;
+        let bad_key = ClientConfig::new(url, Credentials::ApiKey("wrong".to_string()));
+        assert!(matches!(
+            AsyncClient::connect(bad_key).await,
+            Err(ClientError::Auth(_))
+        ));
+
+        let unsupported = ClientConfig::new("ftp://x", Credentials::ApiKey(String::new()));
+        assert!(matches!(
+            AsyncClient::connect(unsupported).await,
+            Err(ClientError::Connect(_))
+        ));
+
+        // A server that accepts but never answers
+        let silent = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = silent.local_addr().unwrap();
+        let mut config =
+            ClientConfig::new(format!("http://{addr}"), Credentials::ApiKey(String::new()));
+        config.connect_timeout = Duration::from_millis(100);
+        assert!(matches!(
+            AsyncClient::connect(config).await,
+            Err(ClientError::Timeout(_))
+        ));
+        drop(silent);
+    }
+
+    #[tokio::test]
+    async fn test_failover_past_unreachable_servers() {
+        let (url, api_key, _tmp) = start_server().await;
+        // Nothing listens on a port that was just released
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let down = format!("http://{}", closed.local_addr().unwrap());
+        drop(closed);
+
+        let mut config = ClientConfig::new(down.clone(), Credentials::ApiKey(api_key));
+        config.failover_urls = vec![down, url.clone()];
+        let client = AsyncClient::connect(config).await.unwrap();
+        assert_eq!(client.current_url(), url);
+        client.execute("+f[(1,)]").await.unwrap();
+        assert_eq!(client.query("?f(X)").await.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_only_harmless_retries() {
+        let failed = |error, sent| Failed {
+            error,
+            endpoint: 0,
+            sent,
+        };
+        // Never sent: anything may be retried
+        assert!(failed(ClientError::Closed, false).is_retryable(false));
+        assert!(failed(ClientError::Timeout(Duration::ZERO), false).is_retryable(false));
+        // Lost after sending: only reads, and never slow ones
+        assert!(failed(ClientError::Closed, true).is_retryable(true));
+        assert!(!failed(ClientError::Closed, true).is_retryable(false));
+        assert!(!failed(ClientError::Timeout(Duration::ZERO), true).is_retryable(true));
+        // Refused without running
+        let replica = "Read-only replica: send writes to the leader at http://a".to_string();
+        assert!(failed(ClientError::Server(replica), true).is_retryable(false));
+        assert!(!failed(ClientError::Server("Parse error".to_string()), true).is_retryable(true));
+        assert!(!failed(ClientError::Auth(String::new()), false).is_retryable(true));
+
+        let policy = RetryPolicy::default();
+        for attempt in 1..10 {
+            let capped = (policy.initial_backoff * (1 << (attempt - 1))).min(policy.max_backoff);
+            let wait = policy.backoff(attempt);
+            assert!(wait >= capped / 2 && wait <= capped, "{attempt}: {wait:?}");
+        }
+    }
+
+    #[test]
+    fn test_value_from_json() {
+        assert_eq!(value_from_json(serde_json::json!(3)), Value::Int64(3));
+        assert_eq!(value_from_json(serde_json::json!("a")), Value::string("a"));
+        assert_eq!(
+            value_from_json(serde_json::json!([1.0, 2.5])),
+            Value::vector(vec![1.0, 2.5])
+        );
+        assert_eq!(
+            value_from_json(serde_json::json!(["a", 1])),
+            Value::list(vec![Value::string("a"), Value::Int64(1)])
+        );
+        assert_eq!(
+            value_from_json(serde_json::json!({"k": null})),
+            Value::map([("k", Value::Null)])
+        );
+    }
+}
@@ -0,0 +1,1019 @@
+//! Cluster Metadata and Failover
+//!
+//! With `[cluster] members` configured, the servers form a Raft group over
+//! a small metadata log. The log records who the writer is, the membership
+//! and every schema change (schemas, rules and views, indexes, knowledge
+//! graphs), so all members agree on one writer and one order of DDL.
+//!
+//! Members start read-only. A member that hears nothing from a leader for
+//! the election timeout stands for election. Members vote for a candidate
+//! whose metadata log is at least as long as theirs and whose data is no
+//! further behind in the writer's replication log than their own, so the
+//! elected member has every write its voters had applied. The leader
+//! commits a `writer` entry and then takes writes as the replication
+//! leader; the other members follow its replication log (see
+//! [`super::replication`]). A leader that has not heard from a majority for
+//! the election timeout steps down and turns read-only, so a leader cut off
+//! from the others stops taking writes.
+//!
+//! A schema change on the leader is appended to the metadata log and runs
+//! once a majority has stored it; without a majority it is rejected after
+//! `ddl_timeout_ms`. Its effect reaches the followers through replication
+//! like any other change.
+//!
+//! Members call each other at `POST /v1/cluster/vote` and
+//! `POST /v1/cluster/append`, authenticated with the shared `secret`. The
+//! term, vote and metadata log are kept in `cluster.json` in the data
+//! directory.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures_util::future::join_all;
+use parking_lot::{Condvar, Mutex};
+use rand::Rng;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{watch, Notify};
+use tracing::{debug, info, warn};
+
+use crate::auth::constant_time_eq;
+use crate::config::{ClusterConfig, ClusterMember};
+use crate::statement::{MetaCommand, Statement};
+use crate::Config;
+
+use super::replication::{replicated_statement, Replication};
+
+/// Path of vote requests, relative to a member's URL
+pub const VOTE_PATH: &str = "/v1/cluster/vote";
+
+/// Path of append requests (and heartbeats), relative to a member's URL
+pub const APPEND_PATH: &str = "/v1/cluster/append";
+
+/// User a member is identified as when it follows the leader's
+/// replication log with the shared secret
+pub const CLUSTER_USER: &str = "_cluster";
+
+/// Entries sent in one append request at most
+const MAX_ENTRIES_PER_APPEND: usize = 64;
+
+/// Schema changes listed by `.cluster`
+const RECENT_DDL: usize = 5;
+
+/// Term, vote and metadata log, in the data directory
+const STATE_FILE: &str = "cluster.json";
+
+/// A change to the cluster's metadata
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MetadataCommand {
+    /// `node` won the election of the entry's term and takes writes
+    Writer { node: u64 },
+    /// Members the cluster runs with, recorded by its first leader
+    Members { members: Vec<ClusterMember> },
+    /// A statement changing schemas, rules, indexes or knowledge graphs
+    Ddl { kg: String, statement: String },
+}
+
+/// One entry of the metadata log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub term: u64,
+    pub command: MetadataCommand,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoteRequest {
+    pub term: u64,
+    pub candidate: u64,
+    pub last_log_index: u64,
+    pub last_log_term: u64,
+    /// Candidate's position in the writer's replication log
+    #[serde(default)]
+    pub data: Option<(String, u64)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoteResponse {
+    pub term: u64,
+    pub granted: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppendRequest {
+    pub term: u64,
+    pub leader: u64,
+    pub prev_log_index: u64,
+    pub prev_log_term: u64,
+    pub entries: Vec<Entry>,
+    pub leader_commit: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppendResponse {
+    pub term: u64,
+    pub success: bool,
+    /// Last entry known to match the leader's log
+    pub match_index: u64,
+}
+
+/// Statements ordered through the metadata log before they run
+pub fn metadata_statement(stmt: &Statement) -> bool {
+    match stmt {
+        Statement::SchemaDecl(decl) => decl.persistent,
+        Statement::Meta(
+            MetaCommand::KgCreate(_)
+            | MetaCommand::KgDrop(_)
+            | MetaCommand::KgDropCascade(_)
+            | MetaCommand::KgCopy { .. }
+            | MetaCommand::KgRename { .. },
+        ) => true,
+        Statement::Meta(MetaCommand::ClearPrefix(_)) => false,
+        stmt => replicated_statement(stmt),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NodeRole {
+    Follower,
+    Candidate,
+    Leader,
+}
+
+impl NodeRole {
+    fn as_str(self) -> &'static str {
+        match self {
+            NodeRole::Follower => "follower",
+            NodeRole::Candidate => "candidate",
+            NodeRole::Leader => "leader",
+        }
+    }
+}
+
+/// State that must survive restarts
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Durable {
+    term: u64,
+    voted_for: Option<u64>,
+    log: Vec<Entry>,
+}
+
+/// Raft state of this member. Log indexes start at 1.
+struct Raft {
+    id: u64,
+    members: Vec<ClusterMember>,
+    durable: Durable,
+    /// `durable` changed and must be saved before answering
+    dirty: bool,
+    role: NodeRole,
+    leader: Option<u64>,
+    commit_index: u64,
+    applied: u64,
+    votes: BTreeSet<u64>,
+    next_index: BTreeMap<u64, u64>,
+    match_index: BTreeMap<u64, u64>,
+    /// When each peer last answered this leader
+    acked: BTreeMap<u64, Instant>,
+    /// When to stand for election unless a leader is heard from
+    deadline: Instant,
+}
+
+impl Raft {
+    fn new(id: u64, members: Vec<ClusterMember>, durable: Durable, deadline: Instant) -> Self {
+        Self {
+            id,
+            members,
+            durable,
+            dirty: false,
+            role: NodeRole::Follower,
+            leader: None,
+            commit_index: 0,
+            applied: 0,
+            votes: BTreeSet::new(),
+            next_index: BTreeMap::new(),
+            match_index: BTreeMap::new(),
+            acked: BTreeMap::new(),
+            deadline,
+        }
+    }
+
+    fn quorum(&self) -> usize {
+        self.members.len() / 2 + 1
+    }
+
+    fn peers(&self) -> Vec<u64> {
+        self.members
+            .iter()
+            .map(|m| m.id)
+            .filter(|id| *id != self.id)
+            .collect()
+    }
+
+    fn url_of(&self, id: u64) -> Option<&str> {
+        self.members
+            .iter()
+            .find(|m| m.id == id)
+            .map(|m| m.url.as_str())
+    }
+
+    /// Index and term of the last entry
+    fn last_log(&self) -> (u64, u64) {
+        let term = self.durable.log.last().map_or(0, |entry| entry.term);
+        (self.durable.log.len() as u64, term)
+    }
+
+    fn term_at(&self, index: u64) -> Option<u64> {
+        match index {
+            0 => Some(0),
+            _ => self
+                .durable
+                .log
+                .get(index as usize - 1)
+                .map(|entry| entry.term),
+        }
+    }
+
+    /// Move to a newer term seen in a request or response
+    fn observe_term(&mut self, term: u64) {
+        if term > self.durable.term {
+            self.durable.term = term;
+            self.durable.voted_for = None;
+            self.dirty = true;
+            self.role = NodeRole::Follower;
+            self.leader = None;
+        }
+    }
+
+    fn append(&mut self, command: MetadataCommand) -> u64 {
+        self.durable.log.push(Entry {
+            term: self.durable.term,
+            command,
+        });
+        self.dirty = true;
+        self.durable.log.len() as u64
+    }
+
+    fn start_election(&mut self, deadline: Instant, data: Option<(String, u64)>) -> VoteRequest {
+        self.durable.term += 1;
+        self.durable.voted_for = Some(self.id);
+        self.dirty = true;
+        self.role = NodeRole::Candidate;
+        self.leader = None;
+        self.votes = BTreeSet::from([self.id]);
+        self.deadline = deadline;
+        let (last_log_index, last_log_term) = self.last_log();
+        VoteRequest {
+            term: self.durable.term,
+            candidate: self.id,
+            last_log_index,
+            last_log_term,
+            data,
+        }
+    }
+
+    fn handle_vote(
+        &mut self,
+        req: &VoteRequest,
+        data: Option<(String, u64)>,
+        deadline: Instant,
+    ) -> VoteResponse {
+        self.observe_term(req.term);
+        let (last_index, last_term) = self.last_log();
+        let log_ok = (req.last_log_term, req.last_log_index) >= (last_term, last_index);
+        let data_ok = match (&req.data, &data) {
+            (Some((log, seq)), Some((own_log, own_seq))) if log == own_log => seq >= own_seq,
+            _ => true,
+        };
+        let granted = req.term == self.durable.term
+            && self
+                .durable
+                .voted_for
+                .is_none_or(|voted| voted == req.candidate)
+            && log_ok
+            && data_ok;
+        if granted {
+            self.durable.voted_for = Some(req.candidate);
+            self.dirty = true;
+            self.deadline = deadline;
+        }
+        VoteResponse {
+            term: self.durable.term,
+            granted,
+        }
+    }
+
+    /// Count a vote; returns whether this candidate now has a majority
+    fn handle_vote_response(&mut self, from: u64, resp: &VoteResponse) -> bool {
+        self.observe_term(resp.term);
+        if self.role != NodeRole::Candidate || resp.term != self.durable.term || !resp.granted {
+            return false;
+        }
+        self.votes.insert(from);
+        self.votes.len() >= self.quorum()
+    }
+
+    fn become_leader(&mut self, now: Instant) {
+        self.role = NodeRole::Leader;
+        self.leader = Some(self.id);
+        let next = self.last_log().0 + 1;
+        let peers = self.peers();
+        self.next_index = peers.iter().map(|peer| (*peer, next)).collect();
+        self.match_index = peers.iter().map(|peer| (*peer, 0)).collect();
+        self.acked = peers.iter().map(|peer| (*peer, now)).collect();
+        let has_members = self
+            .durable
+            .log
+            .iter()
+            .any(|entry| matches!(entry.command, MetadataCommand::Members { .. }));
+        if !has_members {
+            self.append(MetadataCommand::Members {
+                members: self.members.clone(),
+            });
+        }
+        self.append(MetadataCommand::Writer { node: self.id });
+        self.advance_commit();
+    }
+
+    fn step_down(&mut self, deadline: Instant) {
+        self.role = NodeRole::Follower;
+        self.leader = None;
+        self.deadline = deadline;
+    }
+
+    fn append_request(&self, peer: u64) -> AppendRequest {
+        let next = self.next_index.get(&peer).copied().unwrap_or(1).max(1);
+        let prev_log_index = next - 1;
+        AppendRequest {
+            term: self.durable.term,
+            leader: self.id,
+            prev_log_index,
+            prev_log_term: self.term_at(prev_log_index).unwrap_or(0),
+            entries: self
+                .durable
+                .log
+                .iter()
+                .skip(prev_log_index as usize)
+                .take(MAX_ENTRIES_PER_APPEND)
+                .cloned()
+                .collect(),
+            leader_commit: self.commit_index,
+        }
+    }
+
+    fn handle_append(&mut self, req: &AppendRequest, deadline: Instant) -> AppendResponse {
+        self.observe_term(req.term);
+        let term = self.durable.term;
+        if req.term < term {
+            return AppendResponse {
+                term,
+                success: false,
+                match_index: 0,
+            };
+        }
+        self.role = NodeRole::Follower;
+        self.leader = Some(req.leader);
+        self.deadline = deadline;
+        if self.term_at(req.prev_log_index) != Some(req.prev_log_term) {
+            let hint = self.last_log().0.min(req.prev_log_index.saturating_sub(1));
+            return AppendResponse {
+                term,
+                success: false,
+                match_index: hint,
+            };
+        }
+        for (offset, entry) in req.entries.iter().enumerate() {
+            let index = req.prev_log_index + 1 + offset as u64;
+            match self.term_at(index) {
+                Some(existing) if existing == entry.term => continue,
+                // Conflicts with the leader: drop it and everything after
+                Some(_) => self.durable.log.truncate(index as usize - 1),
+                None => {}
+            }
+            self.durable.log.push(entry.clone());
+            self.dirty = true;
+        }
+        let match_index = req.prev_log_index + req.entries.len() as u64;
+        if req.leader_commit > self.commit_index {
+            self.commit_index = req.leader_commit.min(match_index);
+        }
+        AppendResponse {
+            term,
+            success: true,
+            match_index,
+        }
+    }
+
+    fn handle_append_response(&mut self, peer: u64, resp: &AppendResponse, now: Instant) {
+        self.observe_term(resp.term);
+        if self.role != NodeRole::Leader || resp.term != self.durable.term {
+            return;
+        }
+        self.acked.insert(peer, now);
+        if resp.success {
+            let matched = self.match_index.entry(peer).or_insert(0);
+            *matched = (*matched).max(resp.match_index);
+            let matched = *matched;
+            self.next_index.insert(peer, matched + 1);
+            self.advance_commit();
+        } else {
+            let next = self.next_index.entry(peer).or_insert(1);
+            *next = (resp.match_index + 1).clamp(1, next.saturating_sub(1).max(1));
+        }
+    }
+
+    /// Commit up to the highest entry of this term stored by a majority
+    fn advance_commit(&mut self) {
+        let mut stored: Vec<u64> = self
+            .match_index
+            .values()
+            .copied()
+            .chain([self.last_log().0])
+            .collect();
+        stored.sort_unstable_by(|a, b| b.cmp(a));
+        let Some(&majority) = stored.get(self.quorum() - 1) else {
+            return;
+        };
+        if majority > self.commit_index && self.term_at(majority) == Some(self.durable.term) {
+            self.commit_index = majority;
+        }
+    }
+
+    /// Whether this leader has not heard from a majority within `timeout`
+    fn lease_expired(&self, now: Instant, timeout: Duration) -> bool {
+        let recent = self
+            .acked
+            .values()
+            .filter(|acked| now.duration_since(**acked) < timeout)
+            .count();
+        self.role == NodeRole::Leader && recent + 1 < self.quorum()
+    }
+
+    /// Committed entries not yet applied
+    fn take_committed(&mut self) -> Vec<(u64, Entry)> {
+        let start = self.applied;
+        self.applied = self.commit_index;
+        (start + 1..=self.commit_index)
+            .filter_map(|index| {
+                self.durable
+                    .log
+                    .get(index as usize - 1)
+                    .map(|entry| (index, entry.clone()))
+            })
+            .collect()
+    }
+}
+
+/// Metadata as of the last applied entry
+#[derive(Debug, Default)]
+struct Metadata {
+    /// Writer and the term it was elected in
+    writer: Option<(u64, u64)>,
+    members: Vec<ClusterMember>,
+    ddl_count: u64,
+    /// Latest schema changes: index, knowledge graph, statement
+    recent_ddl: VecDeque<(u64, String, String)>,
+}
+
+/// This server's membership in the cluster
+pub struct Cluster {
+    config: ClusterConfig,
+    replication: Arc<Replication>,
+    path: PathBuf,
+    raft: Mutex<Raft>,
+    metadata: Mutex<Metadata>,
+    /// Signalled whenever entries are committed
+    committed: Condvar,
+    /// Wakes the driver to send appends at once
+    wake: Notify,
+    client: reqwest::Client,
+}
+
+impl Cluster {
+    /// Join the cluster configured in `[cluster]`, if any
+    pub fn open(config: &Config, replication: Arc<Replication>) -> Option<Self> {
+        let cluster = &config.cluster;
+        if !cluster.is_enabled() {
+            return None;
+        }
+        let path = config.storage.data_dir.join(STATE_FILE);
+        let durable = load_state(&path);
+        let timeout = Duration::from_millis(cluster.election_timeout_ms);
+        let client = reqwest::Client::builder()
+            .timeout(timeout / 2)
+            .build()
+            .unwrap_or_default();
+        info!(
+            node_id = cluster.node_id,
+            members = cluster.members.len(),
+            term = durable.term,
+            "cluster_joined"
+        );
+        Some(Self {
+            config: cluster.clone(),
+            replication,
+            path,
+            raft: Mutex::new(Raft::new(
+                cluster.node_id,
+                cluster.members.clone(),
+                durable,
+                Instant::now() + timeout,
+            )),
+            metadata: Mutex::new(Metadata::default()),
+            committed: Condvar::new(),
+            wake: Notify::new(),
+            client,
+        })
+    }
+
+    /// Whether `token` is the cluster's shared secret
+    pub fn accepts(&self, token: &str) -> bool {
+        self.config
+            .secret
+            .as_deref()
+            .is_some_and(|secret| constant_time_eq(secret.as_bytes(), token.as_bytes()))
+    }
+
+    /// The election timeout, randomized up to twice the configured one so
+    /// members rarely stand at the same time
+    fn election_deadline(&self, now: Instant) -> Instant {
+        let base = self.config.election_timeout_ms;
+        now + Duration::from_millis(rand::thread_rng().gen_range(base..base * 2))
+    }
+
+    fn save(&self, raft: &mut Raft) {
+        if !raft.dirty {
+            return;
+        }
+        let saved = serde_json::to_vec(&raft.durable)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| {
+                let tmp = self.path.with_extension("json.tmp");
+                fs::write(&tmp, bytes)
+                    .and_then(|()| fs::rename(&tmp, &self.path))
+                    .map_err(|e| e.to_string())
+            });
+        match saved {
+            Ok(()) => raft.dirty = false,
+            Err(e) => warn!(error = %e, "cluster_state_save_failed"),
+        }
+    }
+
+    /// Apply newly committed entries to the metadata
+    fn apply_committed(&self, raft: &mut Raft) {
+        let committed = raft.take_committed();
+        if committed.is_empty() {
+            return;
+        }
+        let mut metadata = self.metadata.lock();
+        for (index, entry) in committed {
+            match entry.command {
+                MetadataCommand::Writer { node } => {
+                    metadata.writer = Some((node, entry.term));
+                    info!(node, term = entry.term, "cluster_writer_elected");
+                }
+                MetadataCommand::Members { members } => {
+                    if members != self.config.members {
+                        warn!(
+                            recorded = ?members.iter().map(|m| m.id).collect::<Vec<_>>(),
+                            "cluster_members_differ_from_config"
+                        );
+                    }
+                    metadata.members = members;
+                }
+                MetadataCommand::Ddl { kg, statement } => {
+                    metadata.ddl_count += 1;
+                    metadata.recent_ddl.push_back((index, kg, statement));
+                    if metadata.recent_ddl.len() > RECENT_DDL {
+                        metadata.recent_ddl.pop_front();
+                    }
+                }
+            }
+        }
+        self.committed.notify_all();
+    }
+
+    /// Take writes when elected and committed as writer; otherwise follow
+    /// the current leader, if one is known
+    fn sync_replication(&self) {
+        let (writer, leader_url) = {
+            let raft = self.raft.lock();
+            let metadata = self.metadata.lock();
+            let writer = raft.role == NodeRole::Leader
+                && metadata.writer == Some((raft.id, raft.durable.term));
+            let leader_url = raft
+                .leader
+                .filter(|leader| *leader != raft.id)
+                .and_then(|leader| raft.url_of(leader))
+                .map(str::to_string);
+            (writer, leader_url)
+        };
+        if writer {
+            self.replication.lead();
+        } else {
+            self.replication.follow(leader_url);
+        }
+    }
+
+    /// Answer a candidate's vote request
+    pub fn handle_vote(&self, req: &VoteRequest) -> VoteResponse {
+        let data = self.replication.data_position();
+        let deadline = self.election_deadline(Instant::now());
+        let resp = {
+            let mut raft = self.raft.lock();
+            let resp = raft.handle_vote(req, data, deadline);
+            self.save(&mut raft);
+            resp
+        };
+        debug!(
+            candidate = req.candidate,
+            term = req.term,
+            granted = resp.granted,
+            "cluster_vote"
+        );
+        self.sync_replication();
+        resp
+    }
+
+    /// Store the leader's entries and learn its commit index
+    pub fn handle_append(&self, req: &AppendRequest) -> AppendResponse {
+        let deadline = self.election_deadline(Instant::now());
+        let resp = {
+            let mut raft = self.raft.lock();
+            let resp = raft.handle_append(req, deadline);
+            self.save(&mut raft);
+            self.apply_committed(&mut raft);
+            resp
+        };
+        self.sync_replication();
+        resp
+    }
+
+    /// Order a schema change: append it to the metadata log and wait until
+    /// a majority has stored it. Called before the statement runs, from a
+    /// blocking thread.
+    pub fn order_ddl(&self, kg: &str, statement: &str) -> Result<(), String> {
+        let timeout = Duration::from_millis(self.config.ddl_timeout_ms);
+        let give_up = Instant::now() + timeout;
+        let mut raft = self.raft.lock();
+        if raft.role != NodeRole::Leader {
+            return Err("Schema changes must be sent to the cluster leader".to_string());
+        }
+        let term = raft.durable.term;
+        let index = raft.append(MetadataCommand::Ddl {
+            kg: kg.to_string(),
+            statement: statement.to_string(),
+        });
+        self.save(&mut raft);
+        raft.advance_commit();
+        self.apply_committed(&mut raft);
+        self.wake.notify_one();
+        loop {
+            if raft.durable.term != term || raft.term_at(index) != Some(term) {
+                return Err(
+                    "Cluster leadership changed before the schema change was agreed; \
+                     retry it on the new leader"
+                        .to_string(),
+                );
+            }
+            if raft.commit_index >= index {
+                return Ok(());
+            }
+            if self.committed.wait_until(&mut raft, give_up).timed_out() {
+                return Err(format!(
+                    "Schema change not confirmed by a majority of the cluster within {} ms",
+                    self.config.ddl_timeout_ms
+                ));
+            }
+        }
+    }
+
+    /// Output of `.cluster`
+    pub fn status_lines(&self) -> Vec<String> {
+        let now = Instant::now();
+        let raft = self.raft.lock();
+        let metadata = self.metadata.lock();
+        let leader = match raft.leader {
+            Some(id) => format!("{id} ({})", raft.url_of(id).unwrap_or("unknown")),
+            None => "none".to_string(),
+        };
+        let writer = match metadata.writer {
+            Some((id, term)) => format!("{id} (elected in term {term})"),
+            None => "none".to_string(),
+        };
+        let mut lines = vec![
+            "Cluster".to_string(),
+            format!(
+                "  Node: {} ({}), term {}",
+                raft.id,
+                raft.role.as_str(),
+                raft.durable.term
+            ),
+            format!("  Leader: {leader}"),
+            format!("  Writer: {writer}"),
+            format!(
+                "  Metadata log: {} entries, {} committed",
+                raft.durable.log.len(),
+                raft.commit_index
+            ),
+            "  Members:".to_string(),
+        ];
+        for member in &raft.members {
+            let state = if member.id == raft.id {
+                "this server".to_string()
+            } else if raft.role == NodeRole::Leader {
+                let matched = raft.match_index.get(&member.id).copied().unwrap_or(0);
+                let seen = raft
+                    .acked
+                    .get(&member.id)
+                    .map_or(0, |acked| now.duration_since(*acked).as_millis());
+                format!("matched {matched}, answered {seen} ms ago")
+            } else {
+                String::new()
+            };
+            let state = if state.is_empty() {
+                String::new()
+            } else {
+                format!(" ({state})")
+            };
+            lines.push(format!("    {} {}{state}", member.id, member.url));
+        }
+        lines.push(format!("  Schema changes: {} ordered", metadata.ddl_count));
+        for (index, kg, statement) in &metadata.recent_ddl {
+            lines.push(format!("    #{index} {kg}: {statement}"));
+        }
+        lines
+    }
+
+    async fn call<Req: Serialize, Resp: DeserializeOwned>(
+        &self,
+        url: &str,
+        path: &str,
+        body: &Req,
+    ) -> Result<Resp, String> {
+        let url = format!("{}{path}", url.trim_end_matches('/'));
+        let mut request = self.client.post(&url).json(body);
+        if let Some(secret) = &self.config.secret {
+            request = request.bearer_auth(secret);
+        }
+        request
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| e.to_string())?
+            .json()
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Stand for election, or heartbeat and replicate as leader
+    async fn tick(&self) {
+        let now = Instant::now();
+        let timeout = Duration::from_millis(self.config.election_timeout_ms);
+        let mut votes = None;
+        let mut appends = Vec::new();
+        {
+            let mut raft = self.raft.lock();
+            match raft.role {
+                NodeRole::Leader if raft.lease_expired(now, timeout) => {
+                    warn!(term = raft.durable.term, "cluster_leader_lost_majority");
+                    raft.step_down(self.election_deadline(now));
+                }
+                NodeRole::Leader => {
+                    for peer in raft.peers() {
+                        if let Some(url) = raft.url_of(peer) {
+                            appends.push((peer, url.to_string(), raft.append_request(peer)));
+                        }
+                    }
+                }
+                _ if now >= raft.deadline => {
+                    let data = self.replication.data_position();
+                    let request = raft.start_election(self.election_deadline(now), data);
+                    info!(term = request.term, "cluster_election_started");
+                    if raft.votes.len() >= raft.quorum() {
+                        raft.become_leader(now);
+                        info!(term = request.term, "cluster_leader_elected");
+                    }
+                    let peers: Vec<(u64, String)> = raft
+                        .peers()
+                        .into_iter()
+                        .filter_map(|peer| raft.url_of(peer).map(|url| (peer, url.to_string())))
+                        .collect();
+                    votes = Some((request, peers));
+                }
+                _ => {}
+            }
+            self.save(&mut raft);
+            self.apply_committed(&mut raft);
+        }
+        self.sync_replication();
+
+        if let Some((request, peers)) = votes {
+            let calls = peers.iter().map(|(peer, url)| async {
+                (
+                    *peer,
+                    self.call::<_, VoteResponse>(url, VOTE_PATH, &request).await,
+                )
+            });
+            for (peer, result) in join_all(calls).await {
+                match result {
+                    Ok(resp) => {
+                        let mut raft = self.raft.lock();
+                        if raft.handle_vote_response(peer, &resp) {
+                            raft.become_leader(Instant::now());
+                            info!(term = raft.durable.term, "cluster_leader_elected");
+                            self.wake.notify_one();
+                        }
+                        self.save(&mut raft);
+                        self.apply_committed(&mut raft);
+                    }
+                    Err(e) => debug!(peer, error = %e, "cluster_peer_unreachable"),
+                }
+            }
+        }
+
+        if !appends.is_empty() {
+            let calls = appends.iter().map(|(peer, url, request)| async {
+                (
+                    *peer,
+                    self.call::<_, AppendResponse>(url, APPEND_PATH, request)
+                        .await,
+                )
+            });
+            for (peer, result) in join_all(calls).await {
+                match result {
+                    Ok(resp) => {
+                        let mut raft = self.raft.lock();
+                        raft.handle_append_response(peer, &resp, Instant::now());
+                        self.save(&mut raft);
+                        self.apply_committed(&mut raft);
+                    }
+                    Err(e) => debug!(peer, error = %e, "cluster_peer_unreachable"),
+                }
+            }
+        }
+        self.sync_replication();
+    }
+}
+
+fn load_state(path: &Path) -> Durable {
+    let Ok(bytes) = fs::read(path) else {
+        return Durable::default();
+    };
+    match serde_json::from_slice(&bytes) {
+        Ok(durable) => durable,
+        Err(e) => {
+            warn!(path = %path.display(), error = %e, "cluster_state_invalid");
+            Durable::default()
+        }
+    }
+}
+
+/// Take part in elections and metadata replication until shutdown
+pub async fn run_cluster(cluster: Arc<Cluster>, mut shutdown: watch::Receiver<bool>) {
+    let heartbeat = Duration::from_millis(cluster.config.heartbeat_ms);
+    loop {
+        cluster.tick().await;
+        tokio::select! {
+            () = tokio::time::sleep(heartbeat) => {}
+            () = cluster.wake.notified() => {}
+            _ = shutdown.changed() => break,
+        }
+    }
+    info!("cluster_stopped");
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::statement::parse_statement;
+
+    fn members(n: u64) -> Vec<ClusterMember> {
+        (1..=n)
+            .map(|id| ClusterMember {
+                id,
+                url: format!("http://node{id}:8080"),
+            })
+            .collect()
+    }
+
+    fn node(id: u64, n: u64) -> Raft {
+        Raft::new(id, members(n), Durable::default(), Instant::now())
+    }
+
+    /// Send `leader`'s appends to every other node and feed back replies
+    fn replicate(leader: &mut Raft, others: &mut [&mut Raft]) {
+        let now = Instant::now();
+        for other in others.iter_mut() {
+            let request = leader.append_request(other.id);
+            let resp = other.handle_append(&request, now);
+            leader.handle_append_response(other.id, &resp, now);
+        }
+    }
+
+    #[test]
+    fn test_election_orders_ddl_and_recovers_followers() {
+        let now = Instant::now();
+        let (mut a, mut b, mut c) = (node(1, 3), node(2, 3), node(3, 3));
+
+        let request = a.start_election(now, None);
+        let vote = b.handle_vote(&request, None, now);
+        assert!(vote.granted);
+        assert!(a.handle_vote_response(2, &vote));
+        a.become_leader(now);
+        assert_eq!(a.commit_index, 0);
+
+        replicate(&mut a, &mut [&mut b]);
+        assert_eq!(a.commit_index, 2);
+        let committed = a.take_committed();
+        assert!(matches!(
+            committed.last().unwrap().1.command,
+            MetadataCommand::Writer { node: 1 }
+        ));
+
+        let ddl = a.append(MetadataCommand::Ddl {
+            kg: "shop".to_string(),
+            statement: "+order(id: int)".to_string(),
+        });
+        replicate(&mut a, &mut [&mut b]);
+        assert_eq!(a.commit_index, ddl);
+
+        // C was down; the leader first assumes it is up to date, is
+        // refused, and then sends it the whole log
+        a.next_index.insert(3, ddl + 1);
+        replicate(&mut a, &mut [&mut c]);
+        assert!(c.durable.log.is_empty());
+        replicate(&mut a, &mut [&mut c]);
+        assert_eq!(c.durable.log, a.durable.log);
+        assert_eq!(c.leader, Some(1));
+
+        // A leader hearing from no one loses its lease
+        let later = now + Duration::from_secs(5);
+        assert!(a.lease_expired(later, Duration::from_secs(1)));
+        assert!(!a.lease_expired(now, Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_votes_refused_to_stale_candidates() {
+        let now = Instant::now();
+        let mut a = node(1, 3);
+        let mut b = node(2, 3);
+        b.durable.term = 1;
+        b.durable.log.push(Entry {
+            term: 1,
+            command: MetadataCommand::Writer { node: 2 },
+        });
+
+        // Shorter metadata log
+        let request = a.start_election(now, None);
+        assert!(!b.handle_vote(&request, None, now).granted);
+
+        // Same log, but behind in the writer's replication log
+        let mut c = node(3, 3);
+        c.durable.log = b.durable.log.clone();
+        c.durable.term = 1;
+        let request = c.start_election(now, Some(("log-a".to_string(), 10)));
+        let own = Some(("log-a".to_string(), 12));
+        assert!(!b.handle_vote(&request, own, now).granted);
+        let request = c.start_election(now, Some(("log-a".to_string(), 12)));
+        let own = Some(("log-a".to_string(), 12));
+        assert!(b.handle_vote(&request, own.clone(), now).granted);
+
+        // One vote per term
+        let mut d = node(1, 3);
+        d.durable.log = b.durable.log.clone();
+        d.durable.term = request.term - 1;
+        let request = d.start_election(now, Some(("log-a".to_string(), 12)));
+        assert!(!b.handle_vote(&request, own, now).granted);
+    }
+
+    #[test]
+    fn test_metadata_statements() {
+        for text in [
+            "+edge(a: int, b: int)",
+            "+path(X, Y) <- edge(X, Y)",
+            ".kg create other",
+            ".rule drop path",
+            ".index drop idx",
+        ] {
+            assert!(
+                metadata_statement(&parse_statement(text).unwrap()),
+                "{text}"
+            );
+        }
+        for text in [
+            "+edge(1, 2)",
+            "?edge(X, Y)",
+            ".clear prefix tmp_",
+            ".kg list",
+        ] {
+            assert!(
+                !metadata_statement(&parse_statement(text).unwrap()),
+                "{text}"
+            );
+        }
+    }
+}
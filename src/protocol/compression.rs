@@ -0,0 +1,272 @@
+//! WebSocket frame compression
+//!
+//! A client lists the codecs it accepts when it authenticates; the server
+//! picks the first of its configured codecs (`http.compression.codecs`)
+//! that the client offered and names it in `authenticated`. From then on
+//! either side may send a message as a binary frame: a one-byte codec tag
+//! followed by the compressed JSON. Messages under `min_bytes`, and
+//! messages that would not shrink, stay text frames.
+//!
+//! Results with float vectors benefit most: their JSON is long runs of
+//! digits that both codecs shrink severalfold.
+
+use std::io::Read;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::CompressionConfig;
+
+/// Compression codec
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Codec {
+    /// zstd: higher ratio
+    Zstd,
+    /// LZ4: faster, lower ratio
+    Lz4,
+}
+
+impl Codec {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Codec::Zstd => "zstd",
+            Codec::Lz4 => "lz4",
+        }
+    }
+
+    /// Codec by name (case-insensitive); `None` for unknown names
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "zstd" => Some(Codec::Zstd),
+            "lz4" => Some(Codec::Lz4),
+            _ => None,
+        }
+    }
+
+    fn tag(self) -> u8 {
+        match self {
+            Codec::Zstd => 1,
+            Codec::Lz4 => 2,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            1 => Some(Codec::Zstd),
+            2 => Some(Codec::Lz4),
+            _ => None,
+        }
+    }
+}
+
+/// Compression error
+#[derive(Debug, thiserror::Error)]
+pub enum CompressionError {
+    #[error("Empty compressed frame")]
+    Empty,
+    #[error("Unknown compression codec tag {0}")]
+    UnknownCodec(u8),
+    #[error("Decompressed frame exceeds {0} bytes")]
+    TooLarge(usize),
+    #[error("Invalid {codec} frame: {message}")]
+    Invalid {
+        codec: &'static str,
+        message: String,
+    },
+}
+
+/// Compress `payload` into a tagged frame
+pub fn compress(
+    codec: Codec,
+    zstd_level: i32,
+    payload: &[u8],
+) -> Result<Vec<u8>, CompressionError> {
+    let body = match codec {
+        Codec::Zstd => {
+            zstd::bulk::compress(payload, zstd_level).map_err(|e| CompressionError::Invalid {
+                codec: codec.as_str(),
+                message: e.to_string(),
+            })?
+        }
+        Codec::Lz4 => lz4_flex::compress_prepend_size(payload),
+    };
+    let mut frame = Vec::with_capacity(body.len() + 1);
+    frame.push(codec.tag());
+    frame.extend_from_slice(&body);
+    Ok(frame)
+}
+
+/// Decompress a tagged frame. Frames that would expand past `max_size`
+/// are rejected without being expanded.
+pub fn decompress(frame: &[u8], max_size: usize) -> Result<Vec<u8>, CompressionError> {
+    let (&tag, body) = frame.split_first().ok_or(CompressionError::Empty)?;
+    let codec = Codec::from_tag(tag).ok_or(CompressionError::UnknownCodec(tag))?;
+    let invalid = |message: String| CompressionError::Invalid {
+        codec: codec.as_str(),
+        message,
+    };
+    match codec {
+        Codec::Zstd => {
+            let decoder =
+                zstd::stream::read::Decoder::new(body).map_err(|e| invalid(e.to_string()))?;
+            let mut out = Vec::new();
+            decoder
+                .take(max_size as u64 + 1)
+                .read_to_end(&mut out)
+                .map_err(|e| invalid(e.to_string()))?;
+            if out.len() > max_size {
+                return Err(CompressionError::TooLarge(max_size));
+            }
+            Ok(out)
+        }
+        Codec::Lz4 => {
+            let size: [u8; 4] = body
+                .get(..4)
+                .and_then(|prefix| prefix.try_into().ok())
+                .ok_or_else(|| invalid("missing size prefix".to_string()))?;
+            if u32::from_le_bytes(size) as usize > max_size {
+                return Err(CompressionError::TooLarge(max_size));
+            }
+            lz4_flex::decompress_size_prepended(body).map_err(|e| invalid(e.to_string()))
+        }
+    }
+}
+
+/// Compression negotiated for one connection
+#[derive(Debug, Clone, Copy)]
+pub struct FrameCompressor {
+    codec: Codec,
+    min_bytes: usize,
+    zstd_level: i32,
+}
+
+impl FrameCompressor {
+    pub fn new(codec: Codec, min_bytes: usize, zstd_level: i32) -> Self {
+        Self {
+            codec,
+            min_bytes,
+            zstd_level,
+        }
+    }
+
+    /// The first configured codec among those the client offered. Unknown
+    /// names in the offer are ignored.
+    pub fn negotiate<S: AsRef<str>>(config: &CompressionConfig, offered: &[S]) -> Option<Self> {
+        let offered: Vec<Codec> = offered
+            .iter()
+            .filter_map(|name| Codec::parse(name.as_ref()))
+            .collect();
+        config
+            .codecs
+            .iter()
+            .find(|codec| offered.contains(codec))
+            .map(|&codec| Self::new(codec, config.min_bytes, config.zstd_level))
+    }
+
+    pub fn codec(&self) -> Codec {
+        self.codec
+    }
+
+    /// The compressed frame for `message`, or `None` when it should be
+    /// sent as is: below the size threshold, or not smaller compressed
+    pub fn compress(&self, message: &[u8]) -> Option<Vec<u8>> {
+        if message.len() < self.min_bytes {
+            return None;
+        }
+        let frame = compress(self.codec, self.zstd_level, message).ok()?;
+        (frame.len() < message.len()).then_some(frame)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    /// A result message with 64-dimensional float vectors
+    fn vector_result() -> Vec<u8> {
+        let rows: Vec<serde_json::Value> = (0..200)
+            .map(|i| {
+                let vector: Vec<f32> = (0..64).map(|j| ((i * 64 + j) % 97) as f32 / 97.0).collect();
+                serde_json::json!([i, vector])
+            })
+            .collect();
+        serde_json::to_vec(&serde_json::json!({"type": "result", "rows": rows})).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip_shrinks_vector_results() {
+        let message = vector_result();
+        for codec in [Codec::Zstd, Codec::Lz4] {
+            let frame = FrameCompressor::new(codec, 1024, 3)
+                .compress(&message)
+                .unwrap();
+            assert_eq!(frame[0], codec.tag());
+            assert!(
+                frame.len() * 2 < message.len(),
+                "{codec:?}: {}",
+                frame.len()
+            );
+            assert_eq!(decompress(&frame, message.len()).unwrap(), message);
+        }
+    }
+
+    #[test]
+    fn test_small_and_incompressible_messages_skip_compression() {
+        let compressor = FrameCompressor::new(Codec::Zstd, 1024, 3);
+        assert!(compressor.compress(br#"{"type":"pong"}"#).is_none());
+
+        // Pseudo-random bytes do not shrink
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let noise: Vec<u8> = (0..4096)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect();
+        assert!(compressor.compress(&noise).is_none());
+    }
+
+    #[test]
+    fn test_negotiate_prefers_server_order() {
+        let config = CompressionConfig::default();
+        let pick =
+            |offered: &[&str]| FrameCompressor::negotiate(&config, offered).map(|c| c.codec());
+        assert_eq!(pick(&["lz4", "zstd"]), Some(Codec::Zstd));
+        assert_eq!(pick(&["LZ4", "brotli"]), Some(Codec::Lz4));
+        assert_eq!(pick(&["brotli"]), None);
+        assert_eq!(pick(&[]), None);
+
+        let disabled = CompressionConfig {
+            codecs: Vec::new(),
+            ..CompressionConfig::default()
+        };
+        assert!(FrameCompressor::negotiate(&disabled, &["zstd"]).is_none());
+    }
+
+    #[test]
+    fn test_decompress_rejects_bad_frames() {
+        let message = vector_result();
+        for codec in [Codec::Zstd, Codec::Lz4] {
+            let frame = compress(codec, 3, &message).unwrap();
+            assert!(matches!(
+                decompress(&frame, 1024),
+                Err(CompressionError::TooLarge(1024))
+            ));
+            assert!(matches!(
+                decompress(&frame[..3], message.len()),
+                Err(CompressionError::Invalid { .. })
+            ));
+        }
+        assert!(matches!(
+            decompress(&[], 1024),
+            Err(CompressionError::Empty)
+        ));
+        assert!(matches!(
+            decompress(&[9, 0, 0], 1024),
+            Err(CompressionError::UnknownCodec(9))
+        ));
+    }
+}
@@ -0,0 +1,332 @@
+//! Server-side result cursors
+//!
+//! A query opened as a cursor runs once; its result is kept on the server
+//! and read back a page at a time, so a large result never has to fit in a
+//! single response. Cursors belong to the client that opened them (see
+//! [`client_key`](super::throttle::client_key)), so one opened over the
+//! WebSocket can be read over HTTP with the same API key.
+//!
+//! - the first page comes back with the cursor; a result that fits in it
+//!   is never stored
+//! - the cursor is dropped once its last page is read, when it is closed,
+//!   or after `http.cursor_idle_secs` without a fetch
+//! - a client may hold `http.max_cursors_per_client` open cursors
+//!   (0 = unlimited) and read at most `http.cursor_max_page_rows` rows per
+//!   page
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use super::wire::{ColumnDef, QueryResult, WireTuple, WireValue};
+use crate::config::HttpConfig;
+
+/// Rows per page when the client does not ask for a size
+pub const DEFAULT_PAGE_ROWS: usize = 1000;
+
+/// Why a cursor request failed
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CursorError {
+    /// Never existed, expired, already exhausted or closed, or belongs to
+    /// another client
+    #[error("Unknown cursor '{0}' (it may have expired or been read to the end)")]
+    NotFound(String),
+    #[error("Too many open cursors: at most {max} per client; close or finish one first")]
+    TooMany { max: usize },
+    #[error("Cursors are disabled on this server (http.cursor_idle_secs = 0)")]
+    Disabled,
+}
+
+/// One page of a cursor's result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CursorPage {
+    /// Cursor to fetch the next page from; `None` once the result is
+    /// exhausted and the cursor is gone
+    pub cursor_id: Option<String>,
+    pub schema: Vec<ColumnDef>,
+    pub rows: Vec<WireTuple>,
+    /// Position of the page's first row in the result
+    pub offset: usize,
+    /// Rows in the whole result
+    pub total_count: usize,
+    /// Whether the query's own limit truncated the result
+    pub truncated: bool,
+    pub execution_time_ms: u64,
+}
+
+impl CursorPage {
+    /// Whether this is the last page
+    pub fn is_last(&self) -> bool {
+        self.cursor_id.is_none()
+    }
+
+    /// Approximate size of the rows, for the per-request result limit
+    pub fn estimated_bytes(&self) -> usize {
+        self.rows
+            .iter()
+            .flat_map(|row| &row.values)
+            .map(WireValue::estimated_bytes)
+            .sum()
+    }
+}
+
+#[derive(Debug)]
+struct Cursor {
+    owner: String,
+    schema: Vec<ColumnDef>,
+    rows: std::vec::IntoIter<WireTuple>,
+    position: usize,
+    total_count: usize,
+    truncated: bool,
+    execution_time_ms: u64,
+    last_access: Instant,
+}
+
+impl Cursor {
+    /// Take the next page, returning whether rows remain after it
+    fn page(&mut self, id: &str, rows: usize) -> (CursorPage, bool) {
+        let offset = self.position;
+        let page: Vec<WireTuple> = self.rows.by_ref().take(rows).collect();
+        self.position += page.len();
+        self.last_access = Instant::now();
+        let more = !self.rows.as_slice().is_empty();
+        let page = CursorPage {
+            cursor_id: more.then(|| id.to_string()),
+            schema: self.schema.clone(),
+            rows: page,
+            offset,
+            total_count: self.total_count,
+            truncated: self.truncated,
+            execution_time_ms: self.execution_time_ms,
+        };
+        (page, more)
+    }
+}
+
+/// Open cursors of every client
+#[derive(Debug)]
+pub struct CursorStore {
+    cursors: parking_lot::Mutex<HashMap<String, Cursor>>,
+    idle: Duration,
+    max_per_client: usize,
+    max_page_rows: usize,
+}
+
+impl CursorStore {
+    pub fn new(config: &HttpConfig) -> Self {
+        Self {
+            cursors: parking_lot::Mutex::new(HashMap::new()),
+            idle: Duration::from_secs(config.cursor_idle_secs),
+            max_per_client: config.max_cursors_per_client,
+            max_page_rows: config.cursor_max_page_rows.max(1),
+        }
+    }
+
+    /// Page size for a requested size: 0 is the default, anything else is
+    /// capped at the configured maximum
+    pub fn page_rows(&self, requested: usize) -> usize {
+        match requested {
+            0 => DEFAULT_PAGE_ROWS.min(self.max_page_rows),
+            n => n.min(self.max_page_rows),
+        }
+    }
+
+    /// Keep `result` for `owner` and return its first page of `page_rows`
+    /// rows. The cursor is only stored when more pages follow.
+    pub fn open(
+        &self,
+        owner: &str,
+        result: QueryResult,
+        page_rows: usize,
+    ) -> Result<CursorPage, CursorError> {
+        let page_rows = self.page_rows(page_rows);
+        let id = uuid::Uuid::new_v4().to_string();
+        let mut cursor = Cursor {
+            owner: owner.to_string(),
+            schema: result.schema,
+            total_count: result.total_count,
+            rows: result.rows.into_iter(),
+            position: 0,
+            truncated: result.truncated,
+            execution_time_ms: result.execution_time_ms,
+            last_access: Instant::now(),
+        };
+        if cursor.rows.len() <= page_rows {
+            return Ok(cursor.page(&id, page_rows).0);
+        }
+        if self.idle.is_zero() {
+            return Err(CursorError::Disabled);
+        }
+
+        let mut cursors = self.cursors.lock();
+        let idle = self.idle;
+        cursors.retain(|_, cursor| cursor.last_access.elapsed() < idle);
+        if self.max_per_client > 0 {
+            let open = cursors.values().filter(|c| c.owner == owner).count();
+            if open >= self.max_per_client {
+                return Err(CursorError::TooMany {
+                    max: self.max_per_client,
+                });
+            }
+        }
+        let (page, _) = cursor.page(&id, page_rows);
+        cursors.insert(id, cursor);
+        Ok(page)
+    }
+
+    /// Read the next page of `owner`'s cursor `id`. The cursor is dropped
+    /// after its last page.
+    pub fn fetch(
+        &self,
+        owner: &str,
+        id: &str,
+        page_rows: usize,
+    ) -> Result<CursorPage, CursorError> {
+        let page_rows = self.page_rows(page_rows);
+        let mut cursors = self.cursors.lock();
+        let cursor = cursors
+            .get_mut(id)
+            .filter(|cursor| cursor.owner == owner && cursor.last_access.elapsed() < self.idle)
+            .ok_or_else(|| CursorError::NotFound(id.to_string()))?;
+        let (page, more) = cursor.page(id, page_rows);
+        if !more {
+            cursors.remove(id);
+        }
+        Ok(page)
+    }
+
+    /// Close `owner`'s cursor `id` before reading it to the end
+    pub fn close(&self, owner: &str, id: &str) -> Result<(), CursorError> {
+        let mut cursors = self.cursors.lock();
+        match cursors.get(id) {
+            Some(cursor) if cursor.owner == owner => {
+                cursors.remove(id);
+                Ok(())
+            }
+            _ => Err(CursorError::NotFound(id.to_string())),
+        }
+    }
+
+    /// Drop cursors idle for longer than `http.cursor_idle_secs`.
+    /// Returns how many were dropped.
+    pub fn reap_expired(&self) -> usize {
+        let mut cursors = self.cursors.lock();
+        let before = cursors.len();
+        cursors.retain(|_, cursor| cursor.last_access.elapsed() < self.idle);
+        before - cursors.len()
+    }
+
+    /// Cursors currently open
+    pub fn len(&self) -> usize {
+        self.cursors.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::protocol::wire::WireDataType;
+
+    fn cursor_store(idle_secs: u64, per_client: usize, max_page_rows: usize) -> CursorStore {
+        CursorStore::new(&HttpConfig {
+            cursor_idle_secs: idle_secs,
+            max_cursors_per_client: per_client,
+            cursor_max_page_rows: max_page_rows,
+            ..HttpConfig::default()
+        })
+    }
+
+    fn result(rows: i64) -> QueryResult {
+        QueryResult::new(
+            (0..rows)
+                .map(|n| WireTuple::new(vec![WireValue::Int64(n)]))
+                .collect(),
+            vec![ColumnDef {
+                name: "n".to_string(),
+                data_type: WireDataType::Int64,
+            }],
+            3,
+        )
+    }
+
+    #[test]
+    fn test_pages_through_result_and_drops_exhausted_cursor() {
+        let store = cursor_store(60, 0, 100);
+        let first = store.open("a", result(5), 2).unwrap();
+        assert_eq!(first.rows.len(), 2);
+        assert_eq!((first.offset, first.total_count), (0, 5));
+        let id = first.cursor_id.unwrap();
+        assert_eq!(store.len(), 1);
+
+        let second = store.fetch("a", &id, 2).unwrap();
+        assert_eq!(second.offset, 2);
+        assert_eq!(second.rows[0].values, vec![WireValue::Int64(2)]);
+        assert_eq!(second.cursor_id.as_deref(), Some(id.as_str()));
+
+        let last = store.fetch("a", &id, 2).unwrap();
+        assert_eq!((last.offset, last.rows.len()), (4, 1));
+        assert!(last.is_last());
+        assert!(store.is_empty());
+        assert_eq!(
+            store.fetch("a", &id, 2).unwrap_err(),
+            CursorError::NotFound(id)
+        );
+
+        // A result that fits in one page is not kept
+        let whole = store.open("a", result(3), 0).unwrap();
+        assert!(whole.is_last());
+        assert_eq!(whole.rows.len(), 3);
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_cursors_belong_to_their_client() {
+        let store = cursor_store(60, 2, 100);
+        let id = store.open("a", result(5), 1).unwrap().cursor_id.unwrap();
+        assert!(store.fetch("b", &id, 1).is_err());
+        assert!(store.close("b", &id).is_err());
+
+        store.open("a", result(5), 1).unwrap();
+        assert_eq!(
+            store.open("a", result(5), 1).unwrap_err(),
+            CursorError::TooMany { max: 2 }
+        );
+        assert!(store.open("b", result(5), 1).is_ok());
+
+        store.close("a", &id).unwrap();
+        assert!(store.fetch("a", &id, 1).is_err());
+        assert!(store.open("a", result(5), 1).is_ok());
+    }
+
+    #[test]
+    fn test_page_size_expiry_and_disabled() {
+        let store = cursor_store(60, 0, 10);
+        assert_eq!(store.page_rows(0), 10);
+        assert_eq!(store.page_rows(500), 10);
+        assert_eq!(store.page_rows(4), 4);
+
+        let expiring = cursor_store(0, 0, 10);
+        assert_eq!(
+            expiring.open("a", result(20), 5).unwrap_err(),
+            CursorError::Disabled
+        );
+        // Results that fit in a page are still returned
+        assert!(expiring.open("a", result(3), 5).unwrap().is_last());
+
+        let short = CursorStore {
+            idle: Duration::from_millis(1),
+            ..cursor_store(60, 0, 10)
+        };
+        let id = short.open("a", result(20), 5).unwrap().cursor_id.unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(short.fetch("a", &id, 5).is_err());
+        assert_eq!(short.reap_expired(), 1);
+        assert!(short.is_empty());
+    }
+}
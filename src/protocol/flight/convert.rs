@@ -0,0 +1,423 @@
+//! Conversion of query results to Arrow record batches.
+//!
+//! Column types come from the values, not the result schema (which is taken
+//! from the first row). A column whose values disagree is widened: integers
+//! to `Int64` or `Float64`, vectors of different lengths from
+//! `FixedSizeList` to `LargeList`, and anything else to text.
+
+use std::sync::Arc;
+
+use arrow::array::{
+    ArrayRef, ArrowPrimitiveType, BinaryArray, BooleanArray, Date32Array, DurationMillisecondArray,
+    FixedSizeBinaryArray, FixedSizeListArray, Float64Array, Int32Array, Int64Array, LargeListArray,
+    NullArray, PrimitiveArray, StringArray, TimestampMillisecondArray,
+};
+use arrow::buffer::{NullBuffer, OffsetBuffer};
+use arrow::datatypes::{
+    DataType, Field, FieldRef, Float32Type, Int8Type, Schema, SchemaRef, TimeUnit,
+};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+
+use crate::protocol::rest::handlers::wire_value_to_json;
+use crate::protocol::wire::{QueryResult, WireTuple, WireValue};
+
+/// Element field of vector columns
+fn item_field(data_type: DataType) -> FieldRef {
+    Arc::new(Field::new("item", data_type, false))
+}
+
+/// Arrow type of one value; `None` for nulls, which fit any column
+fn value_type(value: &WireValue) -> Option<DataType> {
+    let data_type = match value {
+        WireValue::Null => return None,
+        WireValue::Int32(_) => DataType::Int32,
+        WireValue::Int64(_) => DataType::Int64,
+        WireValue::Float64(_) => DataType::Float64,
+        WireValue::Bool(_) => DataType::Boolean,
+        WireValue::Timestamp(_) => DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+        WireValue::Date(_) => DataType::Date32,
+        WireValue::Duration(_) => DataType::Duration(TimeUnit::Millisecond),
+        WireValue::Uuid(_) => DataType::FixedSizeBinary(16),
+        WireValue::Bytes(_) => DataType::Binary,
+        WireValue::Vector(v) => {
+            DataType::FixedSizeList(item_field(DataType::Float32), v.len() as i32)
+        }
+        WireValue::VectorInt8(v) => {
+            DataType::FixedSizeList(item_field(DataType::Int8), v.len() as i32)
+        }
+        // Strings, and lists, maps and JSON in their JSON text form
+        WireValue::String(_) | WireValue::List(_) | WireValue::Map(_) | WireValue::Json(_) => {
+            DataType::Utf8
+        }
+    };
+    Some(data_type)
+}
+
+/// Narrowest type holding values of both types
+fn widen(a: DataType, b: DataType) -> DataType {
+    use DataType::{FixedSizeList, Float64, Int32, Int64, LargeList, Utf8};
+    if a == b {
+        return a;
+    }
+    match (a, b) {
+        (Int32 | Int64, Int32 | Int64) => Int64,
+        (Int32 | Int64 | Float64, Int32 | Int64 | Float64) => Float64,
+        (FixedSizeList(x, _) | LargeList(x), FixedSizeList(y, _) | LargeList(y)) if x == y => {
+            LargeList(x)
+        }
+        _ => Utf8,
+    }
+}
+
+/// Arrow schema of a result. Columns are nullable; a column with only
+/// nulls has the `Null` type.
+pub fn result_schema(result: &QueryResult) -> Schema {
+    let arity = result
+        .rows
+        .first()
+        .map_or(result.schema.len(), |row| row.values.len());
+    let fields: Vec<Field> = (0..arity)
+        .map(|col| {
+            let data_type = result
+                .rows
+                .iter()
+                .filter_map(|row| row.values.get(col).and_then(value_type))
+                .reduce(widen)
+                .unwrap_or(DataType::Null);
+            let name = result
+                .schema
+                .get(col)
+                .map_or_else(|| format!("col{col}"), |c| c.name.clone());
+            Field::new(name, data_type, true)
+        })
+        .collect();
+    Schema::new(fields)
+}
+
+/// Text form of a value in a `Utf8` column, as in JSON results
+fn text(value: &WireValue) -> Option<String> {
+    match value {
+        WireValue::Null => None,
+        WireValue::String(s) => Some(s.clone()),
+        other => Some(match wire_value_to_json(other.clone()) {
+            serde_json::Value::String(s) => s,
+            json => json.to_string(),
+        }),
+    }
+}
+
+/// Vector column with one fixed length; nulls are zero-filled slots
+fn fixed_size_list<T: ArrowPrimitiveType>(
+    values: &[Option<&WireValue>],
+    item: &FieldRef,
+    dim: i32,
+    elements: impl Fn(&WireValue) -> Option<&[T::Native]>,
+) -> Result<ArrayRef, ArrowError> {
+    let width = usize::try_from(dim).unwrap_or(0);
+    let mut flat = Vec::with_capacity(values.len() * width);
+    let mut valid = Vec::with_capacity(values.len());
+    for value in values {
+        match value.and_then(&elements) {
+            Some(v) => {
+                flat.extend_from_slice(v);
+                valid.push(true);
+            }
+            None => {
+                flat.extend(std::iter::repeat_n(T::Native::default(), width));
+                valid.push(false);
+            }
+        }
+    }
+    let flat = PrimitiveArray::<T>::from_iter_values(flat);
+    let list = FixedSizeListArray::try_new(
+        Arc::clone(item),
+        dim,
+        Arc::new(flat),
+        Some(NullBuffer::from(valid)),
+    )?;
+    Ok(Arc::new(list))
+}
+
+/// Vector column with varying lengths
+fn large_list<T: ArrowPrimitiveType>(
+    values: &[Option<&WireValue>],
+    item: &FieldRef,
+    elements: impl Fn(&WireValue) -> Option<&[T::Native]>,
+) -> Result<ArrayRef, ArrowError> {
+    let mut flat = Vec::new();
+    let mut offsets = Vec::with_capacity(values.len() + 1);
+    offsets.push(0i64);
+    let mut valid = Vec::with_capacity(values.len());
+    for value in values {
+        let v = value.and_then(&elements);
+        if let Some(v) = v {
+            flat.extend_from_slice(v);
+        }
+        valid.push(v.is_some());
+        offsets.push(flat.len() as i64);
+    }
+    let flat = PrimitiveArray::<T>::from_iter_values(flat);
+    let list = LargeListArray::try_new(
+        Arc::clone(item),
+        OffsetBuffer::new(offsets.into()),
+        Arc::new(flat),
+        Some(NullBuffer::from(valid)),
+    )?;
+    Ok(Arc::new(list))
+}
+
+fn floats(value: &WireValue) -> Option<&[f32]> {
+    match value {
+        WireValue::Vector(v) => Some(v),
+        _ => None,
+    }
+}
+
+fn int8s(value: &WireValue) -> Option<&[i8]> {
+    match value {
+        WireValue::VectorInt8(v) => Some(v),
+        _ => None,
+    }
+}
+
+/// Build one column of `data_type` from the values of the rows
+fn column(values: &[Option<&WireValue>], data_type: &DataType) -> Result<ArrayRef, ArrowError> {
+    let array: ArrayRef = match data_type {
+        DataType::Null => Arc::new(NullArray::new(values.len())),
+        DataType::Int32 => Arc::new(
+            values
+                .iter()
+                .map(|v| v.and_then(WireValue::as_i32))
+                .collect::<Int32Array>(),
+        ),
+        DataType::Int64 => Arc::new(
+            values
+                .iter()
+                .map(|v| v.and_then(WireValue::as_i64))
+                .collect::<Int64Array>(),
+        ),
+        DataType::Float64 => Arc::new(
+            values
+                .iter()
+                .map(|v| v.and_then(WireValue::as_f64))
+                .collect::<Float64Array>(),
+        ),
+        DataType::Boolean => Arc::new(
+            values
+                .iter()
+                .map(|v| v.and_then(WireValue::as_bool))
+                .collect::<BooleanArray>(),
+        ),
+        DataType::Timestamp(..) => Arc::new(
+            values
+                .iter()
+                .map(|v| match v {
+                    Some(WireValue::Timestamp(ms)) => Some(*ms),
+                    _ => None,
+                })
+                .collect::<TimestampMillisecondArray>()
+                .with_timezone("UTC"),
+        ),
+        DataType::Date32 => Arc::new(
+            values
+                .iter()
+                .map(|v| match v {
+                    Some(WireValue::Date(days)) => Some(*days),
+                    _ => None,
+                })
+                .collect::<Date32Array>(),
+        ),
+        DataType::Duration(_) => Arc::new(
+            values
+                .iter()
+                .map(|v| match v {
+                    Some(WireValue::Duration(ms)) => Some(*ms),
+                    _ => None,
+                })
+                .collect::<DurationMillisecondArray>(),
+        ),
+        DataType::FixedSizeBinary(_) => {
+            Arc::new(FixedSizeBinaryArray::try_from_sparse_iter_with_size(
+                values.iter().map(|v| match v {
+                    Some(WireValue::Uuid(u)) => Some(*u.as_bytes()),
+                    _ => None,
+                }),
+                16,
+            )?)
+        }
+        DataType::Binary => Arc::new(
+            values
+                .iter()
+                .map(|v| match v {
+                    Some(WireValue::Bytes(b)) => Some(b.as_slice()),
+                    _ => None,
+                })
+                .collect::<BinaryArray>(),
+        ),
+        DataType::FixedSizeList(item, dim) => match item.data_type() {
+            DataType::Int8 => fixed_size_list::<Int8Type>(values, item, *dim, int8s)?,
+            _ => fixed_size_list::<Float32Type>(values, item, *dim, floats)?,
+        },
+        DataType::LargeList(item) => match item.data_type() {
+            DataType::Int8 => large_list::<Int8Type>(values, item, int8s)?,
+            _ => large_list::<Float32Type>(values, item, floats)?,
+        },
+        _ => Arc::new(
+            values
+                .iter()
+                .map(|v| v.and_then(text))
+                .collect::<StringArray>(),
+        ),
+    };
+    Ok(array)
+}
+
+/// Convert rows to a record batch of `schema`
+pub fn record_batch(schema: &SchemaRef, rows: &[WireTuple]) -> Result<RecordBatch, ArrowError> {
+    let columns = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(col, field)| {
+            let values: Vec<Option<&WireValue>> =
+                rows.iter().map(|row| row.values.get(col)).collect();
+            column(&values, field.data_type())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    RecordBatch::try_new(Arc::clone(schema), columns)
+}
+
+/// Split a result into record batches of up to `batch_rows` rows. Each
+/// batch is converted when the iterator reaches it.
+pub fn record_batches(
+    result: QueryResult,
+    batch_rows: usize,
+) -> (
+    SchemaRef,
+    impl Iterator<Item = Result<RecordBatch, ArrowError>> + Send,
+) {
+    let schema = Arc::new(result_schema(&result));
+    let rows = result.rows;
+    let batch_rows = batch_rows.max(1);
+    let batch_schema = Arc::clone(&schema);
+    let batches = (0..rows.len()).step_by(batch_rows).map(move |start| {
+        let end = (start + batch_rows).min(rows.len());
+        record_batch(&batch_schema, &rows[start..end])
+    });
+    (schema, batches)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::protocol::wire::{ColumnDef, WireDataType};
+    use arrow::array::{Array, AsArray};
+
+    fn result(rows: Vec<Vec<WireValue>>) -> QueryResult {
+        let arity = rows.first().map_or(0, Vec::len);
+        QueryResult::new(
+            rows.into_iter().map(WireTuple::new).collect(),
+            (0..arity)
+                .map(|i| ColumnDef::new(format!("c{i}"), WireDataType::String))
+                .collect(),
+            0,
+        )
+    }
+
+    #[test]
+    fn test_vectors_become_fixed_size_lists() {
+        let result = result(vec![
+            vec![WireValue::Int64(1), WireValue::Vector(vec![0.5, 1.0, 2.0])],
+            vec![WireValue::Int64(2), WireValue::Null],
+            vec![WireValue::Int64(3), WireValue::Vector(vec![3.0, 4.0, 5.0])],
+        ]);
+        let (schema, batches) = record_batches(result, 2);
+        assert_eq!(
+            schema.field(1).data_type(),
+            &DataType::FixedSizeList(item_field(DataType::Float32), 3)
+        );
+        let batches: Vec<RecordBatch> = batches.collect::<Result<_, _>>().unwrap();
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].num_rows(), 2);
+
+        let vectors = batches[0].column(1).as_fixed_size_list();
+        assert!(vectors.is_null(1));
+        let first = vectors.value(0);
+        let first = first.as_primitive::<Float32Type>();
+        assert_eq!(first.values().to_vec(), vec![0.5, 1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_mixed_columns_are_widened() {
+        let result = result(vec![
+            vec![
+                WireValue::Int32(1),
+                WireValue::Vector(vec![1.0]),
+                WireValue::Int64(1),
+            ],
+            vec![
+                WireValue::Int64(2),
+                WireValue::Vector(vec![1.0, 2.0]),
+                WireValue::String("x".to_string()),
+            ],
+        ]);
+        let schema = result_schema(&result);
+        assert_eq!(schema.field(0).data_type(), &DataType::Int64);
+        assert_eq!(
+            schema.field(1).data_type(),
+            &DataType::LargeList(item_field(DataType::Float32))
+        );
+        assert_eq!(schema.field(2).data_type(), &DataType::Utf8);
+
+        let batch = record_batch(&Arc::new(schema), &result.rows).unwrap();
+        assert_eq!(batch.column(1).as_list::<i64>().value_length(1), 2);
+        let text = batch.column(2).as_string::<i32>();
+        assert_eq!((text.value(0), text.value(1)), ("1", "x"));
+    }
+
+    #[test]
+    fn test_typed_and_null_columns() {
+        let id = uuid::Uuid::new_v4();
+        let result = result(vec![
+            vec![
+                WireValue::Timestamp(1_700_000_000_000),
+                WireValue::Uuid(id),
+                WireValue::Null,
+                WireValue::VectorInt8(vec![1, -2]),
+            ],
+            vec![
+                WireValue::Null,
+                WireValue::Null,
+                WireValue::Null,
+                WireValue::VectorInt8(vec![3, 4]),
+            ],
+        ]);
+        let (schema, batches) = record_batches(result, 100);
+        assert!(matches!(
+            schema.field(0).data_type(),
+            DataType::Timestamp(TimeUnit::Millisecond, Some(_))
+        ));
+        assert_eq!(schema.field(1).data_type(), &DataType::FixedSizeBinary(16));
+        assert_eq!(schema.field(2).data_type(), &DataType::Null);
+        let batches: Vec<RecordBatch> = batches.collect::<Result<_, _>>().unwrap();
+        let batch = &batches[0];
+        assert_eq!(batch.column(0).null_count(), 1);
+        assert_eq!(
+            batch.column(1).as_fixed_size_binary().value(0),
+            id.as_bytes()
+        );
+        assert_eq!(batch.column(2).len(), 2);
+        let int8 = batch.column(3).as_fixed_size_list().value(1);
+        assert_eq!(
+            int8.as_primitive::<Int8Type>().values().to_vec(),
+            vec![3, 4]
+        );
+
+        // An empty result still has a schema and no batches
+        let (schema, mut batches) = record_batches(QueryResult::empty(), 10);
+        assert!(schema.fields().is_empty());
+        assert!(batches.next().is_none());
+    }
+}
@@ -0,0 +1,374 @@
+//! Arrow Flight result transport (`flight` feature)
+//!
+//! A Flight server whose `DoGet` runs the query in the ticket and streams
+//! its result as Arrow record batches, for analytical clients (pyarrow,
+//! polars, DuckDB, Spark) that would otherwise decode rows one by one.
+//! Rows are converted a batch at a time as the stream is read; vectors
+//! become `FixedSizeList<Float32>` (or `Int8`) columns.
+//!
+//! The ticket is JSON, `{"program": "...", "knowledge_graph": "...",
+//! "batch_size": 8192}`, or just the program text. Calls authenticate with
+//! `authorization: Bearer <api key>` like the gRPC API and go through the
+//! same role checks, per-KG ACLs and per-client limits.
+//!
+//! Only `DoGet`, `ListActions` and `DoAction` (none offered) are served;
+//! the rest of the Flight API answers `UNIMPLEMENTED`.
+
+pub mod convert;
+
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use arrow_flight::encode::FlightDataEncoderBuilder;
+use arrow_flight::error::FlightError;
+use arrow_flight::flight_service_server::{FlightService, FlightServiceServer};
+use arrow_flight::{
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightInfo,
+    HandshakeRequest, HandshakeResponse, PollInfo, PutResult, SchemaResult, Ticket,
+};
+use futures_util::{Stream, StreamExt};
+use serde::Deserialize;
+use tonic::metadata::MetadataValue;
+use tonic::{Request, Response, Status, Streaming};
+use tracing::info;
+
+use crate::auth::AuthIdentity;
+use crate::config::FlightConfig;
+use crate::protocol::throttle::{client_key, ThrottleLimit, Throttled};
+use crate::protocol::{Handler, QueryResult, MAX_MESSAGE_SIZE};
+
+/// Rows per record batch when the ticket does not set a batch size
+const DEFAULT_BATCH_ROWS: usize = 8192;
+
+/// Largest batch a ticket may ask for
+const MAX_BATCH_ROWS: usize = 1 << 20;
+
+type BoxStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+/// Query named by a `DoGet` ticket
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct FlightTicket {
+    pub program: String,
+    /// Default: the server's default knowledge graph
+    #[serde(default)]
+    pub knowledge_graph: Option<String>,
+    /// Rows per record batch (default 8192)
+    #[serde(default)]
+    pub batch_size: usize,
+}
+
+impl FlightTicket {
+    /// Parse a ticket: a JSON object, or the program text itself
+    pub fn parse(ticket: &[u8]) -> Result<Self, String> {
+        let text = std::str::from_utf8(ticket).map_err(|_| "Ticket is not UTF-8".to_string())?;
+        if text.trim_start().starts_with('{') {
+            return serde_json::from_str(text).map_err(|e| format!("Invalid ticket: {e}"));
+        }
+        Ok(Self {
+            program: text.to_string(),
+            knowledge_graph: None,
+            batch_size: 0,
+        })
+    }
+
+    fn batch_rows(&self) -> usize {
+        match self.batch_size {
+            0 => DEFAULT_BATCH_ROWS,
+            n => n.min(MAX_BATCH_ROWS),
+        }
+    }
+}
+
+/// Map a handler error to a status, as the gRPC API does
+fn handler_status(message: String) -> Status {
+    if message.starts_with("Access denied") || message.starts_with("Permission denied") {
+        Status::permission_denied(message)
+    } else {
+        Status::invalid_argument(message)
+    }
+}
+
+/// Map a per-client limit to `RESOURCE_EXHAUSTED` (`UNAVAILABLE` while the
+/// server drains), as the gRPC API does
+fn throttled_status(throttled: Throttled) -> Status {
+    let mut status = match throttled.limit {
+        ThrottleLimit::ShuttingDown => Status::unavailable(throttled.to_string()),
+        _ => Status::resource_exhausted(throttled.to_string()),
+    };
+    let metadata = status.metadata_mut();
+    metadata.insert(
+        "x-throttle-limit",
+        MetadataValue::from_static(throttled.limit.as_str()),
+    );
+    if let Some(ms) = throttled.retry_after_ms {
+        metadata.insert("retry-after-ms", MetadataValue::from(ms));
+    }
+    status
+}
+
+/// Check a knowledge graph name before it is put into a command
+fn validate_kg_name(name: &str) -> Result<(), Status> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(Status::invalid_argument(format!(
+            "Invalid knowledge graph name '{name}'"
+        )))
+    }
+}
+
+/// Stream a result as Flight data: the schema, then one message per batch
+fn result_stream(result: QueryResult, batch_rows: usize) -> BoxStream<FlightData> {
+    let (schema, batches) = convert::record_batches(result, batch_rows);
+    let batches = futures_util::stream::iter(batches.map(|batch| batch.map_err(FlightError::from)));
+    let stream = FlightDataEncoderBuilder::new()
+        .with_schema(schema)
+        .with_max_flight_data_size(MAX_MESSAGE_SIZE / 2)
+        .build(batches)
+        .map(|data| data.map_err(Status::from));
+    Box::pin(stream)
+}
+
+/// Flight service over a shared [`Handler`]
+pub struct FlightResultService {
+    handler: Arc<Handler>,
+}
+
+impl FlightResultService {
+    pub fn new(handler: Arc<Handler>) -> Self {
+        Self { handler }
+    }
+
+    /// Identity of the API key in the request metadata
+    fn authenticate<T>(&self, request: &Request<T>) -> Result<AuthIdentity, Status> {
+        request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .and_then(|token| self.handler.authenticate_api_key(token).ok())
+            .ok_or_else(|| Status::unauthenticated("Invalid or missing API key"))
+    }
+
+    /// Run a ticket's program within the caller's per-client limits
+    async fn run(
+        &self,
+        ticket: FlightTicket,
+        identity: &AuthIdentity,
+    ) -> Result<QueryResult, Status> {
+        let kg = ticket.knowledge_graph.unwrap_or_else(|| {
+            self.handler
+                .config()
+                .storage
+                .default_knowledge_graph
+                .clone()
+        });
+        validate_kg_name(&kg)?;
+        let _permit = self
+            .handler
+            .admit_program(&client_key(identity, None), &ticket.program)
+            .map_err(throttled_status)?;
+        let result = self
+            .handler
+            .execute_program(None, Some(kg), ticket.program, Some(identity))
+            .await
+            .map_err(handler_status)?;
+        self.handler
+            .check_result_size(&result)
+            .map_err(throttled_status)?;
+        Ok(result)
+    }
+}
+
+fn unimplemented<T>(call: &str) -> Result<T, Status> {
+    Err(Status::unimplemented(format!(
+        "{call} is not supported; send the query as a DoGet ticket"
+    )))
+}
+
+#[tonic::async_trait]
+impl FlightService for FlightResultService {
+    type HandshakeStream = BoxStream<HandshakeResponse>;
+    type ListFlightsStream = BoxStream<FlightInfo>;
+    type DoGetStream = BoxStream<FlightData>;
+    type DoPutStream = BoxStream<PutResult>;
+    type DoActionStream = BoxStream<arrow_flight::Result>;
+    type ListActionsStream = BoxStream<ActionType>;
+    type DoExchangeStream = BoxStream<FlightData>;
+
+    async fn do_get(
+        &self,
+        request: Request<Ticket>,
+    ) -> Result<Response<Self::DoGetStream>, Status> {
+        let identity = self.authenticate(&request)?;
+        let ticket =
+            FlightTicket::parse(&request.get_ref().ticket).map_err(Status::invalid_argument)?;
+        let batch_rows = ticket.batch_rows();
+        let result = self.run(ticket, &identity).await?;
+        info!(rows = result.rows.len(), batch_rows, "flight_do_get");
+        Ok(Response::new(result_stream(result, batch_rows)))
+    }
+
+    async fn list_actions(
+        &self,
+        request: Request<Empty>,
+    ) -> Result<Response<Self::ListActionsStream>, Status> {
+        self.authenticate(&request)?;
+        Ok(Response::new(Box::pin(futures_util::stream::empty())))
+    }
+
+    async fn do_action(
+        &self,
+        request: Request<Action>,
+    ) -> Result<Response<Self::DoActionStream>, Status> {
+        self.authenticate(&request)?;
+        Err(Status::unimplemented(format!(
+            "Unknown action '{}'",
+            request.get_ref().r#type
+        )))
+    }
+
+    async fn handshake(
+        &self,
+        _request: Request<Streaming<HandshakeRequest>>,
+    ) -> Result<Response<Self::HandshakeStream>, Status> {
+        unimplemented("Handshake (send the API key as a bearer token)")
+    }
+
+    async fn list_flights(
+        &self,
+        _request: Request<Criteria>,
+    ) -> Result<Response<Self::ListFlightsStream>, Status> {
+        unimplemented("ListFlights")
+    }
+
+    async fn get_flight_info(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        unimplemented("GetFlightInfo")
+    }
+
+    async fn poll_flight_info(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<PollInfo>, Status> {
+        unimplemented("PollFlightInfo")
+    }
+
+    async fn get_schema(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<SchemaResult>, Status> {
+        unimplemented("GetSchema")
+    }
+
+    async fn do_put(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoPutStream>, Status> {
+        unimplemented("DoPut")
+    }
+
+    async fn do_exchange(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoExchangeStream>, Status> {
+        unimplemented("DoExchange")
+    }
+}
+
+/// Serve Arrow Flight until the process exits
+pub async fn start_flight_server(
+    handler: Arc<Handler>,
+    config: &FlightConfig,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let addr: SocketAddr = format!("{}:{}", config.host, config.port).parse()?;
+    let tls = if handler.config().http.tls.enabled {
+        Some(crate::protocol::tls::server_config(
+            &handler.config().http.tls,
+        )?)
+    } else {
+        None
+    };
+    let service = FlightServiceServer::new(FlightResultService::new(handler))
+        .max_decoding_message_size(MAX_MESSAGE_SIZE)
+        .max_encoding_message_size(MAX_MESSAGE_SIZE);
+
+    info!(%addr, tls = tls.is_some(), "flight_server_listening");
+    println!("Arrow Flight server listening on: {addr}");
+    let server = tonic::transport::Server::builder().add_service(service);
+    match tls {
+        // Same certificates, SNI selection and client verification as HTTP
+        Some(tls) => {
+            let listener = tokio::net::TcpListener::bind(addr).await?;
+            let incoming = crate::protocol::tls::accept_tls(listener, Arc::new(tls));
+            server
+                .serve_with_incoming(tokio_stream::wrappers::ReceiverStream::new(incoming))
+                .await?;
+        }
+        None => server.serve(addr).await?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::protocol::wire::{ColumnDef, WireDataType, WireTuple, WireValue};
+    use arrow_flight::decode::FlightRecordBatchStream;
+
+    #[test]
+    fn test_ticket_forms() {
+        let ticket = FlightTicket::parse(b"?edge(X, Y)").unwrap();
+        assert_eq!(ticket.program, "?edge(X, Y)");
+        assert_eq!(ticket.batch_rows(), DEFAULT_BATCH_ROWS);
+
+        let ticket = FlightTicket::parse(
+            br#"{"program": "?doc(D, V)", "knowledge_graph": "docs", "batch_size": 100}"#,
+        )
+        .unwrap();
+        assert_eq!(ticket.knowledge_graph.as_deref(), Some("docs"));
+        assert_eq!(ticket.batch_rows(), 100);
+
+        assert!(FlightTicket::parse(br#"{"knowledge_graph": "docs"}"#).is_err());
+        assert!(FlightTicket::parse(&[0xff, 0xfe]).is_err());
+        assert!(validate_kg_name("docs; .kg drop x").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_result_stream_round_trips() {
+        let rows = (0..5)
+            .map(|i| {
+                WireTuple::new(vec![
+                    WireValue::Int64(i),
+                    WireValue::Vector(vec![i as f32, 0.5]),
+                ])
+            })
+            .collect();
+        let result = QueryResult::new(
+            rows,
+            vec![
+                ColumnDef::new("id", WireDataType::Int64),
+                ColumnDef::new("v", WireDataType::Vector { dim: None }),
+            ],
+            1,
+        );
+        let data = result_stream(result, 2).map(|d| d.map_err(FlightError::from));
+        let batches: Vec<_> = FlightRecordBatchStream::new_from_flight_data(data)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 5);
+        assert_eq!(batches[0].schema().field(0).name(), "id");
+    }
+}
@@ -0,0 +1,227 @@
+//! Conversions between protobuf messages and wire/storage values.
+
+use super::pb;
+use super::pb::value::Kind;
+use crate::protocol::handler::PersistentNotification;
+use crate::protocol::rest::handlers::wire_value_to_json;
+use crate::protocol::wire::{ColumnDef, WireValue};
+use crate::value::{Tuple, Value};
+
+/// Convert a result value to its protobuf form
+pub fn wire_to_pb(value: WireValue) -> pb::Value {
+    let kind = match value {
+        WireValue::Null => Kind::Null(true),
+        WireValue::Int32(i) => Kind::Int(i.into()),
+        WireValue::Int64(i) => Kind::Int(i),
+        WireValue::Float64(f) => Kind::Float(f),
+        WireValue::String(s) => Kind::String(s),
+        WireValue::Bool(b) => Kind::Bool(b),
+        WireValue::Timestamp(t) => Kind::Timestamp(t),
+        WireValue::Vector(values) => Kind::Vector(pb::Vector { values }),
+        WireValue::VectorInt8(values) => Kind::Vector(pb::Vector {
+            values: values.into_iter().map(f32::from).collect(),
+        }),
+        WireValue::Bytes(b) => Kind::Bytes(b),
+        other => match wire_value_to_json(other) {
+            serde_json::Value::String(s) => Kind::Text(s),
+            json => Kind::Text(json.to_string()),
+        },
+    };
+    pb::Value { kind: Some(kind) }
+}
+
+/// Convert a protobuf value to a storage value, with the same size limits
+/// as JSON input. Text is taken as a string and coerced by the schema.
+pub fn pb_to_value(
+    value: pb::Value,
+    max_string_bytes: usize,
+    max_vector_dims: usize,
+) -> Result<Value, String> {
+    let Some(kind) = value.kind else {
+        return Ok(Value::Null);
+    };
+    match kind {
+        Kind::Null(_) => Ok(Value::Null),
+        Kind::Int(i) => Ok(Value::Int64(i)),
+        Kind::Float(f) => {
+            if !f.is_finite() {
+                return Err(format!("Non-finite float not supported: {f}"));
+            }
+            Ok(Value::Float64(f))
+        }
+        Kind::String(s) | Kind::Text(s) => {
+            if s.len() > max_string_bytes {
+                return Err(format!(
+                    "String value too large: {} bytes (max {max_string_bytes})",
+                    s.len()
+                ));
+            }
+            Ok(Value::string(&s))
+        }
+        Kind::Bool(b) => Ok(Value::Bool(b)),
+        Kind::Timestamp(t) => Ok(Value::Timestamp(t)),
+        Kind::Vector(vector) => {
+            if vector.values.len() > max_vector_dims {
+                return Err(format!(
+                    "Vector too large: {} dimensions (max {max_vector_dims})",
+                    vector.values.len()
+                ));
+            }
+            if vector.values.iter().any(|f| !f.is_finite()) {
+                return Err("Vector elements must be finite".to_string());
+            }
+            Ok(Value::vector(vector.values))
+        }
+        Kind::Bytes(b) => {
+            if b.len() > max_string_bytes {
+                return Err(format!(
+                    "Bytes value too large: {} bytes (max {max_string_bytes})",
+                    b.len()
+                ));
+            }
+            Ok(Value::bytes(b))
+        }
+    }
+}
+
+/// Convert protobuf rows to tuples
+pub fn rows_to_tuples(
+    rows: Vec<pb::Row>,
+    max_string_bytes: usize,
+    max_vector_dims: usize,
+) -> Result<Vec<Tuple>, String> {
+    rows.into_iter()
+        .map(|row| {
+            row.values
+                .into_iter()
+                .map(|v| pb_to_value(v, max_string_bytes, max_vector_dims))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Tuple::new)
+        })
+        .collect()
+}
+
+/// Convert a result column
+pub fn column_to_pb(column: &ColumnDef) -> pb::Column {
+    pb::Column {
+        name: column.name.clone(),
+        data_type: column.data_type.to_string(),
+    }
+}
+
+/// Convert a change notification, with its kind and the name of the
+/// changed entity
+pub fn notification_to_pb(notification: PersistentNotification) -> pb::Notification {
+    match notification {
+        PersistentNotification::PersistentUpdate {
+            knowledge_graph,
+            relation,
+            operation,
+            count,
+            timestamp_ms,
+            seq,
+            ..
+        } => pb::Notification {
+            seq,
+            knowledge_graph,
+            kind: "relation".to_string(),
+            name: relation,
+            operation,
+            count: count as u64,
+            timestamp_ms,
+        },
+        PersistentNotification::RuleChange {
+            knowledge_graph,
+            rule_name,
+            operation,
+            timestamp_ms,
+            seq,
+        } => pb::Notification {
+            seq,
+            knowledge_graph,
+            kind: "rule".to_string(),
+            name: rule_name,
+            operation,
+            count: 0,
+            timestamp_ms,
+        },
+        PersistentNotification::KgChange {
+            knowledge_graph,
+            operation,
+            timestamp_ms,
+            seq,
+        } => pb::Notification {
+            seq,
+            name: knowledge_graph.clone(),
+            knowledge_graph,
+            kind: "knowledge_graph".to_string(),
+            operation,
+            count: 0,
+            timestamp_ms,
+        },
+        PersistentNotification::SchemaChange {
+            knowledge_graph,
+            entity,
+            operation,
+            timestamp_ms,
+            seq,
+        } => pb::Notification {
+            seq,
+            knowledge_graph,
+            kind: "schema".to_string(),
+            name: entity,
+            operation,
+            count: 0,
+            timestamp_ms,
+        },
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_round_trip() {
+        for wire in [
+            WireValue::Int64(7),
+            WireValue::Float64(1.5),
+            WireValue::String("a".to_string()),
+            WireValue::Bool(true),
+            WireValue::Null,
+        ] {
+            let value = pb_to_value(wire_to_pb(wire.clone()), 1024, 16).unwrap();
+            assert_eq!(WireValue::from_value(&value), wire);
+        }
+
+        let vector = pb_to_value(wire_to_pb(WireValue::Vector(vec![1.0, 2.0])), 1024, 16);
+        assert_eq!(vector.unwrap(), Value::vector(vec![1.0, 2.0]));
+
+        // Types without a protobuf counterpart travel as text
+        let date = wire_to_pb(WireValue::Date(0));
+        assert_eq!(date.kind, Some(Kind::Text("1970-01-01".to_string())));
+    }
+
+    #[test]
+    fn test_limits() {
+        let long = pb::Value {
+            kind: Some(Kind::String("x".repeat(10))),
+        };
+        assert!(pb_to_value(long, 4, 16).is_err());
+        let wide = pb::Value {
+            kind: Some(Kind::Vector(pb::Vector {
+                values: vec![0.0; 8],
+            })),
+        };
+        assert!(pb_to_value(wide, 1024, 4).is_err());
+        let nan = pb::Value {
+            kind: Some(Kind::Float(f64::NAN)),
+        };
+        assert!(pb_to_value(nan, 1024, 4).is_err());
+        assert_eq!(
+            pb_to_value(pb::Value { kind: None }, 1024, 4).unwrap(),
+            Value::Null
+        );
+    }
+}
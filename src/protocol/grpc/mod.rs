@@ -0,0 +1,636 @@
+//! gRPC API (`grpc` feature)
+//!
+//! A tonic server for the `inputlayer.v1.InputLayer` service defined in
+//! `proto/inputlayer.proto`, as a typed alternative to the WebSocket
+//! protocol. Other languages generate their clients from the same file;
+//! the generated Rust client is [`pb::input_layer_client::InputLayerClient`].
+//!
+//! Calls authenticate with `authorization: Bearer <api key>` metadata and
+//! go through the same handler methods, role checks and per-KG ACLs as the
+//! HTTP API:
+//!
+//! - `Execute` runs statement text and streams the result as a header,
+//!   row batches and a summary
+//! - `Insert` / `Delete` write facts given as typed rows
+//! - `Subscribe` streams change notifications for a knowledge graph
+//! - `ManageDatabase` creates, drops and lists knowledge graphs
+//! - `OpenCursor` / `FetchCursor` / `CloseCursor` page through a result
+//!   kept on the server (shared with the HTTP and WebSocket cursors of the
+//!   same API key)
+//!
+//! Calls count against the same per-client limits as HTTP requests; a
+//! throttled call fails with `RESOURCE_EXHAUSTED`, the limit in the
+//! `x-throttle-limit` trailer and, when worth retrying, `retry-after-ms`.
+//!
+//! With `http.tls` enabled the server only accepts TLS, using the same
+//! certificates and client verification as the HTTP listener.
+
+pub mod convert;
+
+/// Generated protobuf messages, server and client
+#[allow(clippy::all, clippy::pedantic)]
+pub mod pb {
+    tonic::include_proto!("inputlayer.v1");
+}
+
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::metadata::MetadataValue;
+use tonic::{Request, Response, Status};
+use tracing::info;
+
+use self::convert::{column_to_pb, notification_to_pb, rows_to_tuples, wire_to_pb};
+use self::pb::input_layer_server::{InputLayer, InputLayerServer};
+use self::pb::manage_database_request::Action;
+use crate::auth::{AuthIdentity, Role, INTERNAL_KG};
+use crate::config::GrpcConfig;
+use crate::protocol::cursor::{CursorError, CursorPage};
+use crate::protocol::handler::PersistentNotification;
+use crate::protocol::throttle::{client_key, ThrottleLimit, Throttled};
+use crate::protocol::{Handler, QueryResult, MAX_MESSAGE_SIZE};
+
+/// Rows per `RowBatch` when the request does not set a batch size
+const DEFAULT_BATCH_SIZE: usize = 1000;
+
+/// Maximum vector dimensions accepted in facts, as for JSON input
+const MAX_VECTOR_DIMENSIONS: usize = 65_536;
+
+type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+/// Map a handler error to a status: access errors are `PERMISSION_DENIED`,
+/// the rest are problems with the request
+fn handler_status(message: String) -> Status {
+    if message.starts_with("Access denied") || message.starts_with("Permission denied") {
+        Status::permission_denied(message)
+    } else {
+        Status::invalid_argument(message)
+    }
+}
+
+/// Map a cursor error: unknown cursors are `NOT_FOUND`, too many open ones
+/// `RESOURCE_EXHAUSTED`
+fn cursor_status(err: CursorError) -> Status {
+    match err {
+        CursorError::NotFound(_) => Status::not_found(err.to_string()),
+        CursorError::TooMany { .. } => Status::resource_exhausted(err.to_string()),
+        CursorError::Disabled => Status::failed_precondition(err.to_string()),
+    }
+}
+
+/// Map a per-client limit to `RESOURCE_EXHAUSTED` (`UNAVAILABLE` while the
+/// server drains), naming the limit and when to retry in the trailers
+fn throttled_status(throttled: Throttled) -> Status {
+    let mut status = match throttled.limit {
+        ThrottleLimit::ShuttingDown => Status::unavailable(throttled.to_string()),
+        _ => Status::resource_exhausted(throttled.to_string()),
+    };
+    let metadata = status.metadata_mut();
+    metadata.insert(
+        "x-throttle-limit",
+        MetadataValue::from_static(throttled.limit.as_str()),
+    );
+    if let Some(ms) = throttled.retry_after_ms {
+        metadata.insert("retry-after-ms", MetadataValue::from(ms));
+    }
+    status
+}
+
+/// Check a knowledge graph name before it is put into a command
+fn validate_kg_name(name: &str) -> Result<(), Status> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(Status::invalid_argument(format!(
+            "Invalid knowledge graph name '{name}'"
+        )))
+    }
+}
+
+/// Split a result into the messages of an `Execute` stream
+fn result_messages(result: QueryResult, batch_size: usize) -> Vec<pb::ExecuteResponse> {
+    use pb::execute_response::Kind;
+
+    let message = |kind| pb::ExecuteResponse { kind: Some(kind) };
+    let mut messages = vec![message(Kind::Header(pb::ResultHeader {
+        columns: result.schema.iter().map(column_to_pb).collect(),
+    }))];
+    let row_count = result.rows.len() as u64;
+    let mut rows = result.rows.into_iter().peekable();
+    while rows.peek().is_some() {
+        let batch = rows
+            .by_ref()
+            .take(batch_size)
+            .map(|row| pb::Row {
+                values: row.values.into_iter().map(wire_to_pb).collect(),
+            })
+            .collect();
+        messages.push(message(Kind::Rows(pb::RowBatch { rows: batch })));
+    }
+    messages.push(message(Kind::Summary(pb::ResultSummary {
+        row_count,
+        total_count: result.total_count as u64,
+        truncated: result.truncated,
+        execution_time_ms: result.execution_time_ms,
+    })));
+    messages
+}
+
+fn cursor_page_to_pb(page: CursorPage) -> pb::CursorPage {
+    pb::CursorPage {
+        cursor_id: page.cursor_id.unwrap_or_default(),
+        columns: page.schema.iter().map(column_to_pb).collect(),
+        rows: page
+            .rows
+            .into_iter()
+            .map(|row| pb::Row {
+                values: row.values.into_iter().map(wire_to_pb).collect(),
+            })
+            .collect(),
+        offset: page.offset as u64,
+        total_count: page.total_count as u64,
+        truncated: page.truncated,
+        execution_time_ms: page.execution_time_ms,
+    }
+}
+
+/// Whether a notification is about `kg` (and `name`, if given)
+fn notification_matches(
+    notification: &PersistentNotification,
+    kg: &str,
+    name: Option<&str>,
+) -> bool {
+    let (notification_kg, entity) = match notification {
+        PersistentNotification::PersistentUpdate {
+            knowledge_graph,
+            relation,
+            ..
+        } => (knowledge_graph, Some(relation)),
+        PersistentNotification::RuleChange {
+            knowledge_graph,
+            rule_name,
+            ..
+        } => (knowledge_graph, Some(rule_name)),
+        PersistentNotification::SchemaChange {
+            knowledge_graph,
+            entity,
+            ..
+        } => (knowledge_graph, Some(entity)),
+        PersistentNotification::KgChange {
+            knowledge_graph, ..
+        } => (knowledge_graph, None),
+    };
+    notification_kg == kg
+        && match (name, entity) {
+            (Some(name), Some(entity)) => name == entity,
+            _ => true,
+        }
+}
+
+/// Service implementation over a shared [`Handler`]
+pub struct GrpcService {
+    handler: Arc<Handler>,
+}
+
+impl GrpcService {
+    pub fn new(handler: Arc<Handler>) -> Self {
+        Self { handler }
+    }
+
+    /// Identity of the API key in the request metadata
+    fn authenticate<T>(&self, request: &Request<T>) -> Result<AuthIdentity, Status> {
+        request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .and_then(|token| self.handler.authenticate_api_key(token).ok())
+            .ok_or_else(|| Status::unauthenticated("Invalid or missing API key"))
+    }
+
+    fn default_kg(&self) -> String {
+        self.handler
+            .config()
+            .storage
+            .default_knowledge_graph
+            .clone()
+    }
+
+    /// Run `program` in `kg` as `identity`, within the caller's per-client
+    /// limits
+    async fn run(
+        &self,
+        kg: String,
+        program: String,
+        identity: &AuthIdentity,
+    ) -> Result<QueryResult, Status> {
+        let result = self.run_unsized(kg, program, identity).await?;
+        self.handler
+            .check_result_size(&result)
+            .map_err(throttled_status)?;
+        Ok(result)
+    }
+
+    /// `run` without the result size limit, for results read through a
+    /// cursor whose pages are checked instead
+    async fn run_unsized(
+        &self,
+        kg: String,
+        program: String,
+        identity: &AuthIdentity,
+    ) -> Result<QueryResult, Status> {
+        let _permit = self
+            .handler
+            .admit_program(&client_key(identity, None), &program)
+            .map_err(throttled_status)?;
+        self.handler
+            .execute_program(None, Some(kg), program, Some(identity))
+            .await
+            .map_err(handler_status)
+    }
+
+    /// A cursor page, checked against the result size limit
+    fn page(&self, page: CursorPage) -> Result<Response<pb::CursorPage>, Status> {
+        self.handler
+            .check_page_size(&page)
+            .map_err(throttled_status)?;
+        Ok(Response::new(cursor_page_to_pb(page)))
+    }
+
+    /// Message of a result from a meta command
+    fn message_of(result: QueryResult) -> String {
+        result
+            .rows
+            .into_iter()
+            .next()
+            .and_then(|row| row.values.into_iter().next())
+            .map(|value| match value {
+                crate::protocol::WireValue::String(s) => s,
+                other => format!("{other:?}"),
+            })
+            .unwrap_or_default()
+    }
+
+    fn facts(&self, rows: Vec<pb::Row>) -> Result<Vec<crate::value::Tuple>, Status> {
+        let max_str = self
+            .handler
+            .config()
+            .storage
+            .performance
+            .max_string_value_bytes;
+        rows_to_tuples(rows, max_str, MAX_VECTOR_DIMENSIONS).map_err(Status::invalid_argument)
+    }
+}
+
+#[tonic::async_trait]
+impl InputLayer for GrpcService {
+    type ExecuteStream = ResponseStream<pb::ExecuteResponse>;
+    type SubscribeStream = ResponseStream<pb::Notification>;
+
+    async fn execute(
+        &self,
+        request: Request<pb::ExecuteRequest>,
+    ) -> Result<Response<Self::ExecuteStream>, Status> {
+        let identity = self.authenticate(&request)?;
+        let request = request.into_inner();
+        let kg = request.knowledge_graph.unwrap_or_else(|| self.default_kg());
+        validate_kg_name(&kg)?;
+        let batch_size = match request.batch_size {
+            0 => DEFAULT_BATCH_SIZE,
+            n => n as usize,
+        };
+        let result = self.run(kg, request.program, &identity).await?;
+        let messages = result_messages(result, batch_size);
+        Ok(Response::new(Box::pin(tokio_stream::iter(
+            messages.into_iter().map(Ok),
+        ))))
+    }
+
+    async fn insert(
+        &self,
+        request: Request<pb::InsertRequest>,
+    ) -> Result<Response<pb::InsertResponse>, Status> {
+        let identity = self.authenticate(&request)?;
+        let request = request.into_inner();
+        let tuples = self.facts(request.facts)?;
+        let _permit = self
+            .handler
+            .admit(&client_key(&identity, None), 1)
+            .map_err(throttled_status)?;
+        let handler = Arc::clone(&self.handler);
+        let report = tokio::task::spawn_blocking(move || {
+            handler.insert_facts(
+                &request.knowledge_graph,
+                &request.relation,
+                tuples,
+                Some(&identity),
+            )
+        })
+        .await
+        .map_err(|e| Status::internal(format!("Task join error: {e}")))?
+        .map_err(handler_status)?;
+        Ok(Response::new(pb::InsertResponse {
+            inserted: report.inserted as u64,
+            duplicates: report.duplicates as u64,
+            quarantined: report.quarantined as u64,
+            replaced: report.replaced as u64,
+        }))
+    }
+
+    async fn delete(
+        &self,
+        request: Request<pb::DeleteRequest>,
+    ) -> Result<Response<pb::DeleteResponse>, Status> {
+        let identity = self.authenticate(&request)?;
+        let request = request.into_inner();
+        let tuples = self.facts(request.facts)?;
+        let _permit = self
+            .handler
+            .admit(&client_key(&identity, None), 1)
+            .map_err(throttled_status)?;
+        let handler = Arc::clone(&self.handler);
+        let deleted = tokio::task::spawn_blocking(move || {
+            handler.delete_facts(
+                &request.knowledge_graph,
+                &request.relation,
+                tuples,
+                Some(&identity),
+            )
+        })
+        .await
+        .map_err(|e| Status::internal(format!("Task join error: {e}")))?
+        .map_err(handler_status)?;
+        Ok(Response::new(pb::DeleteResponse {
+            deleted: deleted as u64,
+        }))
+    }
+
+    async fn subscribe(
+        &self,
+        request: Request<pb::SubscribeRequest>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let identity = self.authenticate(&request)?;
+        let request = request.into_inner();
+        validate_kg_name(&request.knowledge_graph)?;
+        // Subscribing is a read of the knowledge graph
+        self.run(
+            request.knowledge_graph.clone(),
+            ".rel".to_string(),
+            &identity,
+        )
+        .await?;
+
+        let kg = request.knowledge_graph;
+        let name = request.relation;
+        let stream = BroadcastStream::new(self.handler.subscribe_notifications()).filter_map(
+            move |received| match received {
+                Ok(notification) => notification_matches(&notification, &kg, name.as_deref())
+                    .then(|| Ok(notification_to_pb(notification))),
+                Err(BroadcastStreamRecvError::Lagged(missed)) => Some(Err(Status::data_loss(
+                    format!("Subscriber lagged; {missed} notification(s) missed"),
+                ))),
+            },
+        );
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn manage_database(
+        &self,
+        request: Request<pb::ManageDatabaseRequest>,
+    ) -> Result<Response<pb::ManageDatabaseResponse>, Status> {
+        let identity = self.authenticate(&request)?;
+        let request = request.into_inner();
+        let action = Action::try_from(request.action)
+            .map_err(|_| Status::invalid_argument("Unknown action"))?;
+
+        let command = match action {
+            Action::List => {
+                let handler = Arc::clone(&self.handler);
+                let knowledge_graphs = tokio::task::spawn_blocking(move || {
+                    let names = handler.get_storage().list_knowledge_graphs();
+                    names
+                        .into_iter()
+                        .filter(|kg| {
+                            identity.role == Role::Admin
+                                || (kg != INTERNAL_KG
+                                    && handler
+                                        .get_kg_role_for_user(
+                                            kg,
+                                            &identity.username,
+                                            &identity.role,
+                                        )
+                                        .is_some())
+                        })
+                        .collect()
+                })
+                .await
+                .map_err(|e| Status::internal(format!("Task join error: {e}")))?;
+                return Ok(Response::new(pb::ManageDatabaseResponse {
+                    message: String::new(),
+                    knowledge_graphs,
+                }));
+            }
+            Action::Create => "create",
+            Action::Drop => "drop",
+        };
+        validate_kg_name(&request.name)?;
+        let result = self
+            .run(
+                self.default_kg(),
+                format!(".kg {command} {}", request.name),
+                &identity,
+            )
+            .await?;
+        Ok(Response::new(pb::ManageDatabaseResponse {
+            message: Self::message_of(result),
+            knowledge_graphs: Vec::new(),
+        }))
+    }
+
+    async fn open_cursor(
+        &self,
+        request: Request<pb::OpenCursorRequest>,
+    ) -> Result<Response<pb::CursorPage>, Status> {
+        let identity = self.authenticate(&request)?;
+        let request = request.into_inner();
+        let kg = request.knowledge_graph.unwrap_or_else(|| self.default_kg());
+        validate_kg_name(&kg)?;
+        let result = self.run_unsized(kg, request.program, &identity).await?;
+        let page = self
+            .handler
+            .cursors()
+            .open(
+                &client_key(&identity, None),
+                result,
+                request.page_size as usize,
+            )
+            .map_err(cursor_status)?;
+        self.page(page)
+    }
+
+    async fn fetch_cursor(
+        &self,
+        request: Request<pb::FetchCursorRequest>,
+    ) -> Result<Response<pb::CursorPage>, Status> {
+        let identity = self.authenticate(&request)?;
+        let request = request.into_inner();
+        let client = client_key(&identity, None);
+        let _permit = self.handler.admit(&client, 1).map_err(throttled_status)?;
+        let page = self
+            .handler
+            .cursors()
+            .fetch(&client, &request.cursor_id, request.page_size as usize)
+            .map_err(cursor_status)?;
+        self.page(page)
+    }
+
+    async fn close_cursor(
+        &self,
+        request: Request<pb::CloseCursorRequest>,
+    ) -> Result<Response<pb::CloseCursorResponse>, Status> {
+        let identity = self.authenticate(&request)?;
+        self.handler
+            .cursors()
+            .close(&client_key(&identity, None), &request.get_ref().cursor_id)
+            .map_err(cursor_status)?;
+        Ok(Response::new(pb::CloseCursorResponse {}))
+    }
+}
+
+/// Serve the gRPC API until the process exits
+pub async fn start_grpc_server(
+    handler: Arc<Handler>,
+    config: &GrpcConfig,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let addr: SocketAddr = format!("{}:{}", config.host, config.port).parse()?;
+    let tls = if handler.config().http.tls.enabled {
+        Some(crate::protocol::tls::server_config(
+            &handler.config().http.tls,
+        )?)
+    } else {
+        None
+    };
+    let service = InputLayerServer::new(GrpcService::new(handler))
+        .max_decoding_message_size(MAX_MESSAGE_SIZE)
+        .max_encoding_message_size(MAX_MESSAGE_SIZE);
+
+    info!(%addr, tls = tls.is_some(), "grpc_server_listening");
+    println!("gRPC server listening on: {addr}");
+    let server = tonic::transport::Server::builder().add_service(service);
+    match tls {
+        // Same certificates, SNI selection and client verification as HTTP
+        Some(tls) => {
+            let listener = tokio::net::TcpListener::bind(addr).await?;
+            let incoming = crate::protocol::tls::accept_tls(listener, Arc::new(tls));
+            server
+                .serve_with_incoming(tokio_stream::wrappers::ReceiverStream::new(incoming))
+                .await?;
+        }
+        None => server.serve(addr).await?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::protocol::wire::{ColumnDef, WireDataType, WireTuple, WireValue};
+
+    #[test]
+    fn test_result_messages_batches_rows() {
+        let result = QueryResult {
+            rows: (0..5)
+                .map(|i| WireTuple {
+                    values: vec![WireValue::Int64(i)],
+                    provenance: None,
+                })
+                .collect(),
+            schema: vec![ColumnDef {
+                name: "x".to_string(),
+                data_type: WireDataType::Int64,
+            }],
+            total_count: 5,
+            truncated: false,
+            execution_time_ms: 3,
+            metadata: None,
+            switched_kg: None,
+            proof_trees: None,
+            timing_breakdown: None,
+        };
+        let messages = result_messages(result, 2);
+        // Header, three batches (2 + 2 + 1), summary
+        assert_eq!(messages.len(), 5);
+        let Some(pb::execute_response::Kind::Rows(last)) = &messages[3].kind else {
+            panic!("expected a row batch");
+        };
+        assert_eq!(last.rows.len(), 1);
+        let Some(pb::execute_response::Kind::Summary(summary)) = &messages[4].kind else {
+            panic!("expected a summary");
+        };
+        assert_eq!(summary.row_count, 5);
+    }
+
+    #[test]
+    fn test_notification_filter() {
+        let update = PersistentNotification::PersistentUpdate {
+            knowledge_graph: "kg".to_string(),
+            relation: "edge".to_string(),
+            operation: "insert".to_string(),
+            count: 1,
+            timestamp_ms: 0,
+            session_id: None,
+            seq: 1,
+        };
+        assert!(notification_matches(&update, "kg", None));
+        assert!(notification_matches(&update, "kg", Some("edge")));
+        assert!(!notification_matches(&update, "kg", Some("node")));
+        assert!(!notification_matches(&update, "other", None));
+    }
+
+    #[test]
+    fn test_access_errors_are_permission_denied() {
+        assert_eq!(
+            handler_status("Access denied".to_string()).code(),
+            tonic::Code::PermissionDenied
+        );
+        assert_eq!(
+            handler_status("Parse error".to_string()).code(),
+            tonic::Code::InvalidArgument
+        );
+        assert!(validate_kg_name("a b").is_err());
+    }
+
+    #[test]
+    fn test_cursor_pages_and_errors() {
+        let page = cursor_page_to_pb(CursorPage {
+            cursor_id: None,
+            schema: Vec::new(),
+            rows: vec![WireTuple::new(vec![WireValue::Int64(1)])],
+            offset: 10,
+            total_count: 11,
+            truncated: false,
+            execution_time_ms: 1,
+        });
+        // The last page has no cursor to fetch from
+        assert!(page.cursor_id.is_empty());
+        assert_eq!((page.offset, page.rows.len()), (10, 1));
+
+        assert_eq!(
+            cursor_status(CursorError::NotFound("c".to_string())).code(),
+            tonic::Code::NotFound
+        );
+        assert_eq!(
+            cursor_status(CursorError::TooMany { max: 1 }).code(),
+            tonic::Code::ResourceExhausted
+        );
+    }
+}
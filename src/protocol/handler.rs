@@ -9,24 +9,37 @@
 //! use `?`, `map_err()`, `unwrap_or()`, or `unwrap_or_default()` for proper error propagation.
 //! Test code uses `expect()` with descriptive messages for better failure diagnostics.
 
-use crate::ast::Term;
-use crate::index_manager::{DistanceMetric, HnswConfig, IndexStats, IndexType, RegisteredIndex};
+use crate::ast::{BuiltinFunc, Term};
+use crate::incremental::ViewSubscription;
+use crate::index_manager::{
+    DistanceMetric, HnswConfig, IndexStats, IndexType, LshConfig, RegisteredIndex,
+};
 use crate::rule_catalog::validate_rule;
-use crate::schema::{ColumnSchema, RelationSchema};
-use crate::session::{SessionConfig, SessionId, SessionManager};
+use crate::schema::{ColumnSchema, PartitionSpec, RelationSchema, SchemaMigration, SchemaType};
+use crate::session::{
+    PreparedSlot, SessionConfig, SessionId, SessionManager, SessionSettings, TransactionSlot,
+};
 use crate::statement;
-use crate::statement::meta::{IndexCreateOptions, MetaCommand};
+use crate::statement::meta::{IndexCreateOptions, MetaCommand, PartitionDecl, RelAlterAction};
 use crate::statement::parser::SortDirection;
-use crate::storage_engine::StorageEngine;
+use crate::storage_engine::{RuleChange, StorageEngine, Transaction, WriteOp};
 use crate::value::{Tuple, Value};
 use crate::Config;
 use parking_lot::RwLock;
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
-use tracing::{debug, info, warn};
+use tracing::{debug, info, warn, Instrument};
 
-use super::wire::{ColumnDef, QueryResult, WireDataType, WireTuple, WireValue};
+use super::admission::{Admission, Preemption, Priority};
+use super::cluster::Cluster;
+use super::cursor::{CursorPage, CursorStore};
+use super::live::LiveSubscription;
+use super::replication::Replication;
+use super::shard::{Routed, ShardRouter};
+use super::throttle::{ClientLimits, ClientPermit, Throttled};
+use super::wire::{BatchResult, ColumnDef, QueryResult, WireDataType, WireTuple, WireValue};
 
 /// Result of transforming a `?shorthand` query, including sort and pagination annotations.
 pub(crate) struct QueryTransform {
@@ -41,11 +54,11 @@ pub(crate) struct QueryTransform {
 }
 
 /// Term -> Value (constants only, rejects variables/placeholders).
-fn term_to_value(term: &Term) -> Result<Value, String> {
+pub(crate) fn term_to_value(term: &Term) -> Result<Value, String> {
     match term {
         Term::Constant(n) => Ok(Value::Int64(*n)),
         Term::FloatConstant(f) => Ok(Value::Float64(*f)),
-        Term::StringConstant(s) => Ok(Value::string(s)),
+        Term::StringConstant(s) => Ok(Value::interned(s)),
         Term::VectorLiteral(v) => {
             let f32_vals: Vec<f32> = v
                 .iter()
@@ -65,6 +78,66 @@ fn term_to_value(term: &Term) -> Result<Value, String> {
             Err("Cannot insert arithmetic expression - use constants only".to_string())
         }
         Term::Aggregate(_, _) => Err("Cannot insert aggregate - use constants only".to_string()),
+        // `date("...")` and `duration("...")` are literals, not computations
+        Term::FunctionCall(func @ (BuiltinFunc::Date | BuiltinFunc::Duration), args) => {
+            let [Term::StringConstant(text)] = args.as_slice() else {
+                return Err(format!(
+                    "{}() in a fact takes a single string literal",
+                    func.as_str()
+                ));
+            };
+            let value = if *func == BuiltinFunc::Date {
+                crate::temporal_ops::parse_date(text).map(Value::Date)
+            } else {
+                crate::temporal_ops::parse_duration(text).map(Value::Duration)
+            };
+            value.ok_or_else(|| format!("Invalid {} literal: \"{text}\"", func.as_str()))
+        }
+        Term::FunctionCall(BuiltinFunc::Uuid, args) => match args.as_slice() {
+            [Term::StringConstant(text)] => {
+                Value::uuid(text).ok_or_else(|| format!("Invalid uuid literal: \"{text}\""))
+            }
+            _ => Err("uuid() in a fact takes a single string literal".to_string()),
+        },
+        // Generated once at insert time, so the stored fact has a fixed key
+        Term::FunctionCall(BuiltinFunc::UuidV7, args) if args.is_empty() => {
+            Ok(Value::Uuid(uuid::Uuid::now_v7()))
+        }
+        // `list(...)` and `map(...)` literals nest constants
+        Term::FunctionCall(func @ (BuiltinFunc::Hex | BuiltinFunc::Base64), args) => {
+            let [Term::StringConstant(text)] = args.as_slice() else {
+                return Err(format!(
+                    "{}() in a fact takes a single string literal",
+                    func.as_str()
+                ));
+            };
+            let value = if *func == BuiltinFunc::Hex {
+                Value::from_hex(text)
+            } else {
+                Value::from_base64(text)
+            };
+            value.ok_or_else(|| format!("Invalid {} literal: \"{text}\"", func.as_str()))
+        }
+        Term::FunctionCall(BuiltinFunc::JsonParse, args) => match args.as_slice() {
+            [Term::StringConstant(text)] => {
+                Value::json(text).ok_or_else(|| format!("Invalid JSON literal: '{text}'"))
+            }
+            _ => Err("json_parse() in a fact takes a single string literal".to_string()),
+        },
+        Term::FunctionCall(BuiltinFunc::List, args) => Ok(Value::list(
+            args.iter().map(term_to_value).collect::<Result<_, _>>()?,
+        )),
+        Term::FunctionCall(BuiltinFunc::Map, args) => {
+            BuiltinFunc::Map.check_arity(args.len())?;
+            let entries = args
+                .chunks_exact(2)
+                .map(|pair| match &pair[0] {
+                    Term::StringConstant(key) => Ok((key.clone(), term_to_value(&pair[1])?)),
+                    _ => Err("map() keys in a fact must be string literals".to_string()),
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            Ok(Value::map(entries))
+        }
         Term::FunctionCall(_, _) => {
             Err("Cannot insert function call - use constants only".to_string())
         }
@@ -82,6 +155,16 @@ fn term_to_value(term: &Term) -> Result<Value, String> {
 /// WebSocket handlers can detect this prefix to extract per-line error info.
 pub const VALIDATION_ERROR_PREFIX: &str = "VALIDATION_ERRORS:";
 
+/// Most statements in one batch (see [`Handler::execute_batch`])
+pub const MAX_BATCH_STATEMENTS: usize = 1000;
+
+/// Reply to `.log` when the log subscriber cannot be reconfigured
+const LOG_FILTER_FIXED: &str =
+    "Log filter is fixed: this process was started without a reloadable log subscriber.";
+
+/// Source of the `query_id` on each program's tracing span
+static NEXT_QUERY_ID: AtomicU64 = AtomicU64::new(1);
+
 /// A parse/validation error for a specific statement in a program.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ValidationError {
@@ -130,11 +213,11 @@ pub enum PersistentNotification {
         /// Monotonic sequence number for dedup on reconnect (#39)
         seq: u64,
     },
-    /// A schema change occurred (index created/dropped, relation dropped) (#16)
+    /// A schema change occurred (index created/dropped, relation altered/dropped) (#16)
     SchemaChange {
         knowledge_graph: String,
         entity: String,
-        /// "created" or "dropped"
+        /// "created", "altered" or "dropped"
         operation: String,
         timestamp_ms: u64,
         /// Monotonic sequence number for dedup on reconnect (#39)
@@ -171,10 +254,10 @@ pub struct Handler {
     /// Broadcast channel for persistent data change notifications.
     /// WebSocket connections subscribe to receive push updates.
     notify_tx: tokio::sync::broadcast::Sender<PersistentNotification>,
-    /// Semaphore limiting concurrent DD computations.
+    /// Queue limiting concurrent DD computations.
     /// Prevents blocking-thread-pool explosion by capping CPU-bound parallelism
-    /// at the hardware thread count. Tokio workers queue via async `acquire()`.
-    query_semaphore: Arc<tokio::sync::Semaphore>,
+    /// (see [`Admission`]). Tokio workers wait via async `admit()`.
+    admission: Arc<Admission>,
     /// Monotonic sequence counter for notification dedup (#39).
     notification_seq: Arc<AtomicU64>,
     /// Bounded ring buffer of recent notifications for replay on reconnect (#39).
@@ -184,6 +267,21 @@ pub struct Handler {
     timing_histograms: Arc<crate::execution::timing::TimingHistograms>,
     /// Teaching agent for guided onboarding.
     agent: Arc<crate::agent::AgentManager>,
+    /// View subscriptions whose connection went away, by ID, waiting to be
+    /// resumed until they expire
+    detached_live: parking_lot::Mutex<HashMap<String, (Instant, LiveSubscription)>>,
+    /// Per-client concurrency, statement rate and result size limits
+    client_limits: Arc<ClientLimits>,
+    /// Results kept for clients to read a page at a time
+    cursors: CursorStore,
+    /// Statement audit files, when `[audit]` is enabled
+    audit: Option<crate::audit::AuditFile>,
+    /// Role in leader-follower replication, and the follower's position
+    replication: Arc<Replication>,
+    /// Membership of a Raft cluster, when `[cluster]` is configured
+    cluster: Option<Arc<Cluster>>,
+    /// Router of sharded relations, when `[sharding]` is configured
+    shards: Option<Arc<ShardRouter>>,
 }
 
 /// Current epoch milliseconds.
@@ -194,6 +292,95 @@ fn now_ms() -> u64 {
         .as_millis() as u64
 }
 
+/// Result of an introspection statement (`show ...`, `describe ...`)
+fn introspection_result(
+    (schema, tuples): crate::storage_engine::IntrospectionTable,
+    execution_time_ms: u64,
+) -> QueryResult {
+    let rows: Vec<WireTuple> = tuples
+        .iter()
+        .map(|tuple| WireTuple {
+            values: tuple.values().iter().map(WireValue::from_value).collect(),
+            provenance: None,
+        })
+        .collect();
+    QueryResult {
+        total_count: rows.len(),
+        rows,
+        schema: schema
+            .columns
+            .iter()
+            .map(|col| ColumnDef {
+                name: col.name.clone(),
+                data_type: match col.data_type {
+                    SchemaType::Int => WireDataType::Int64,
+                    _ => WireDataType::String,
+                },
+            })
+            .collect(),
+        truncated: false,
+        execution_time_ms,
+        metadata: None,
+        switched_kg: None,
+        proof_trees: None,
+        timing_breakdown: None,
+    }
+}
+
+const AUDIT_READ_ONLY: &str = "Access denied: the audit knowledge graph '_audit' is read-only";
+
+/// Reject statements that would change the `_audit` knowledge graph,
+/// whoever sends them
+fn check_audit_read_only(kg: &str, stmt: &statement::Statement) -> Result<(), String> {
+    if kg == crate::audit::AUDIT_KG
+        && crate::auth::authorize_kg_operation(&crate::auth::KgRole::Viewer, stmt).is_err()
+    {
+        return Err(AUDIT_READ_ONLY.to_string());
+    }
+    Ok(())
+}
+
+/// Create `schema` in the `_audit` knowledge graph, if missing
+fn prepare_audit_relation(storage: &StorageEngine, schema: RelationSchema) {
+    use crate::audit::AUDIT_KG;
+
+    let prepared = storage
+        .ensure_knowledge_graph(AUDIT_KG)
+        .or_else(|_| storage.create_knowledge_graph(AUDIT_KG))
+        .and_then(|()| storage.get_schema_in(AUDIT_KG, &schema.name))
+        .and_then(|existing| match existing {
+            Some(_) => Ok(()),
+            None => storage.register_or_update_schema_in(AUDIT_KG, schema),
+        });
+    if let Err(e) = prepared {
+        warn!(kg = AUDIT_KG, error = %e, "audit_kg_unavailable");
+    }
+}
+
+/// Open the audit files and prepare the `_audit` knowledge graph, if
+/// `[audit]` is enabled
+fn open_audit(storage: &StorageEngine) -> Option<crate::audit::AuditFile> {
+    use crate::audit;
+
+    let config = storage.config();
+    if !config.audit.enabled {
+        return None;
+    }
+    if config.audit.relation {
+        prepare_audit_relation(storage, audit::relation_schema());
+    }
+    match audit::AuditFile::open(&config.audit, &config.storage.data_dir) {
+        Ok(file) => {
+            info!(dir = %file.dir().display(), "audit_log_enabled");
+            Some(file)
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "audit_log_open_failed");
+            None
+        }
+    }
+}
+
 /// Set the sequence number on a notification (all variants have a `seq` field).
 fn set_notification_seq(notif: &mut PersistentNotification, seq: u64) {
     match notif {
@@ -218,6 +405,24 @@ struct QueryJob {
     notification_buffer:
         Arc<parking_lot::Mutex<std::collections::VecDeque<PersistentNotification>>>,
     timing_histograms: Arc<crate::execution::timing::TimingHistograms>,
+    /// Open transaction (`begin` ... `commit`); writes are queued in it
+    transaction: TransactionSlot,
+    /// The transaction belongs to a session and outlives this program
+    session_transaction: bool,
+    /// Statements from `prepare`: the session's, or this program's alone
+    prepared: PreparedSlot,
+    replication: Arc<Replication>,
+    cluster: Option<Arc<Cluster>>,
+    shards: Option<Arc<ShardRouter>>,
+    admission: Arc<Admission>,
+    /// Lends the query's execution slot to higher-priority queries between
+    /// strata of its snapshot computation
+    preemption: Option<Preemption>,
+    /// Records the plan of the snapshot computation for the slow query log
+    plan_capture: Option<crate::execution::PlanCapture>,
+    /// Running a statement replicated from the leader, which a read-only
+    /// replica accepts
+    applying: bool,
 }
 
 impl QueryJob {
@@ -233,6 +438,25 @@ impl QueryJob {
         self.start_time.elapsed().as_secs()
     }
 
+    /// The open transaction, if writes to `kg` should be queued in it
+    /// rather than applied. Errors if it belongs to another knowledge graph.
+    fn open_transaction(
+        &self,
+        kg: &str,
+    ) -> Result<Option<parking_lot::MappedMutexGuard<'_, Transaction>>, String> {
+        let Ok(tx) = parking_lot::MutexGuard::try_map(self.transaction.lock(), Option::as_mut)
+        else {
+            return Ok(None);
+        };
+        if tx.kg() != kg {
+            return Err(format!(
+                "A transaction is open on '{}'. Commit or roll it back before writing to '{kg}'.",
+                tx.kg()
+            ));
+        }
+        Ok(Some(tx))
+    }
+
     /// Assign a seq number, buffer, and broadcast a notification.
     fn send_notification(&self, mut notif: PersistentNotification) {
         let seq = self.notification_seq.fetch_add(1, Ordering::Relaxed) + 1;
@@ -329,69 +553,27 @@ impl QueryJob {
             }
         };
 
-        // Validate index type
-        let index_type_str = opts.index_type.as_str();
-        if index_type_str != "hnsw" {
-            return Err(format!(
-                "Unsupported index type '{index_type_str}'. Currently only 'hnsw' is supported."
-            ));
-        }
-
-        // Parse distance metric
-        let metric = opts
-            .metric
-            .as_deref()
-            .unwrap_or("cosine")
-            .parse::<DistanceMetric>()
-            .map_err(|e| format!("Invalid metric: {e}"))?;
-
-        let m = opts.m.unwrap_or(16);
-        let ef_construction = opts.ef_construction.unwrap_or(200);
-        let ef_search = opts.ef_search.unwrap_or(50);
-
-        // Validate HNSW parameters to prevent crashes
-        if m < 2 {
-            return Err(format!("HNSW parameter m must be >= 2, got {m}"));
-        }
-        if m > 256 {
-            return Err(format!("HNSW parameter m must be <= 256, got {m}"));
-        }
-        if ef_construction < 1 {
-            return Err("HNSW parameter ef_construction must be >= 1".to_string());
-        }
-        if ef_search < 1 {
-            return Err("HNSW parameter ef_search must be >= 1".to_string());
-        }
-
-        let hnsw_config = HnswConfig {
-            m,
-            ef_construction,
-            ef_search,
-            metric,
-        };
+        let index_type = index_type_from_options(opts)?;
 
         let registered = RegisteredIndex {
             name: opts.name.clone(),
             relation: opts.relation.clone(),
             column_idx,
             column_name: opts.column.clone(),
-            index_type: IndexType::Hnsw(hnsw_config),
+            index_type,
         };
 
-        // Enable incremental engine and register index
-        storage
+        // Register the index and build it from the relation's tuples
+        let count = storage
             .with_kg_mut(kg, |kg_data| {
-                kg_data.enable_incremental().map_err(|e| e.to_string())?;
-                if let Some(dd) = kg_data.incremental() {
-                    dd.register_index(registered)
-                } else {
-                    Err("Failed to enable incremental engine".to_string())
-                }
+                kg_data
+                    .create_vector_index(registered)
+                    .map_err(|e| e.to_string())
             })
             .map_err(|e| e.to_string())?;
 
         Ok(format!(
-            "Index '{}' created on {}.{}.",
+            "Index '{}' created on {}.{} ({count} vectors).",
             opts.name, opts.relation, opts.column
         ))
     }
@@ -400,11 +582,7 @@ impl QueryJob {
         let storage = self.storage.read();
         storage
             .with_kg_read(kg, |kg_data| {
-                if let Some(dd) = kg_data.incremental() {
-                    dd.remove_index(name)
-                } else {
-                    Err(format!("Index '{name}' not found (no incremental engine)"))
-                }
+                kg_data.drop_vector_index(name).map_err(|e| e.to_string())
             })
             .map_err(|e| e.to_string())?;
 
@@ -439,24 +617,14 @@ impl QueryJob {
 
     fn rebuild_index(&self, kg: &str, name: &str) -> Result<String, String> {
         let storage = self.storage.read();
-        storage
+        let count = storage
             .with_kg_read(kg, |kg_data| {
-                if let Some(dd) = kg_data.incremental() {
-                    // Verify the index exists
-                    let stats = dd.get_index_stats(Some(name))?;
-                    if stats.is_empty() {
-                        return Err(format!("Index '{name}' not found"));
-                    }
-                    // Indexes are automatically rebuilt when their base relation
-                    // receives updates. Notify the base relation to trigger rebuild.
-                    Ok(format!(
-                        "Index '{name}' will be rebuilt on next data update."
-                    ))
-                } else {
-                    Err(format!("Index '{name}' not found (no incremental engine)"))
-                }
+                kg_data
+                    .rebuild_vector_index(name)
+                    .map_err(|e| e.to_string())
             })
-            .map_err(|e| e.to_string())
+            .map_err(|e| e.to_string())?;
+        Ok(format!("Index '{name}' rebuilt ({count} vectors)."))
     }
 
     fn debug_query(
@@ -617,6 +785,7 @@ impl QueryJob {
                     ir_build_us: 0,
                     optimize_us: 0,
                     shared_views_us: 0,
+                    peak_memory_bytes: 0,
                     rules: vec![
                         crate::execution::timing::RuleTiming {
                             rule_head: "query_execution".into(),
@@ -711,6 +880,7 @@ impl QueryJob {
                     ir_build_us: 0,
                     optimize_us: 0,
                     shared_views_us: 0,
+                    peak_memory_bytes: 0,
                     rules: vec![
                         crate::execution::timing::RuleTiming {
                             rule_head: "query_execution".into(),
@@ -750,11 +920,16 @@ impl Handler {
         let notify_buf = storage.config().http.rate_limit.notification_buffer_size;
         let (notify_tx, _) = tokio::sync::broadcast::channel(notify_buf);
         let config = Arc::new(storage.config().clone());
-        let ncpu = std::thread::available_parallelism().map_or(4, std::num::NonZero::get);
-        // Reserve ~25% of cores (min 2) for Tokio async I/O, health checks, WebSocket handling.
-        // The rest are available for CPU-bound DD computations via spawn_blocking.
-        let io_reserve = (ncpu / 4).max(2).min(ncpu - 1);
-        let compute_permits = ncpu - io_reserve;
+        let client_limits = Arc::new(ClientLimits::new(&config.http.rate_limit));
+        let cursors = CursorStore::new(&config.http);
+        let audit = open_audit(&storage);
+        let replication = Arc::new(Replication::new(
+            &config,
+            storage.replication_log().cloned(),
+        ));
+        let cluster = Cluster::open(&config, Arc::clone(&replication)).map(Arc::new);
+        let shards = ShardRouter::open(&config).map(Arc::new);
+        let admission = Arc::new(Admission::new(&config.admission));
         Self {
             storage: Arc::new(RwLock::new(storage)),
             config,
@@ -763,7 +938,7 @@ impl Handler {
             insert_count: Arc::new(AtomicU64::new(0)),
             sessions: SessionManager::default(),
             notify_tx,
-            query_semaphore: Arc::new(tokio::sync::Semaphore::new(compute_permits)),
+            admission,
             notification_seq: Arc::new(AtomicU64::new(0)),
             notification_buffer: Arc::new(parking_lot::Mutex::new(
                 std::collections::VecDeque::new(),
@@ -772,12 +947,20 @@ impl Handler {
             agent: Arc::new(crate::agent::AgentManager::new(
                 crate::agent::AgentConfig::default(),
             )),
+            detached_live: parking_lot::Mutex::new(HashMap::new()),
+            client_limits,
+            cursors,
+            audit,
+            replication,
+            cluster,
+            shards,
         }
     }
 
     /// Create a new handler from configuration.
     pub fn from_config(mut config: Config) -> Result<Self, String> {
         config.validate()?;
+        crate::execution::install_cluster(&config.timely);
         let storage =
             StorageEngine::new(config).map_err(|e| format!("Failed to create storage: {e}"))?;
         Ok(Self::new(storage))
@@ -788,10 +971,16 @@ impl Handler {
         let notify_buf = storage.config().http.rate_limit.notification_buffer_size;
         let (notify_tx, _) = tokio::sync::broadcast::channel(notify_buf);
         let config = Arc::new(storage.config().clone());
-        let ncpu = std::thread::available_parallelism().map_or(4, std::num::NonZero::get);
-        // Reserve ~25% of cores (min 2) for Tokio async I/O, health checks, WebSocket handling.
-        let io_reserve = (ncpu / 4).max(2).min(ncpu - 1);
-        let compute_permits = ncpu - io_reserve;
+        let client_limits = Arc::new(ClientLimits::new(&config.http.rate_limit));
+        let cursors = CursorStore::new(&config.http);
+        let audit = open_audit(&storage);
+        let replication = Arc::new(Replication::new(
+            &config,
+            storage.replication_log().cloned(),
+        ));
+        let cluster = Cluster::open(&config, Arc::clone(&replication)).map(Arc::new);
+        let shards = ShardRouter::open(&config).map(Arc::new);
+        let admission = Arc::new(Admission::new(&config.admission));
         Self {
             storage: Arc::new(RwLock::new(storage)),
             config,
@@ -800,7 +989,7 @@ impl Handler {
             insert_count: Arc::new(AtomicU64::new(0)),
             sessions: SessionManager::new(session_config),
             notify_tx,
-            query_semaphore: Arc::new(tokio::sync::Semaphore::new(compute_permits)),
+            admission,
             notification_seq: Arc::new(AtomicU64::new(0)),
             notification_buffer: Arc::new(parking_lot::Mutex::new(
                 std::collections::VecDeque::new(),
@@ -809,6 +998,13 @@ impl Handler {
             agent: Arc::new(crate::agent::AgentManager::new(
                 crate::agent::AgentConfig::default(),
             )),
+            detached_live: parking_lot::Mutex::new(HashMap::new()),
+            client_limits,
+            cursors,
+            audit,
+            replication,
+            cluster,
+            shards,
         }
     }
 
@@ -824,6 +1020,16 @@ impl Handler {
             notification_seq: Arc::clone(&self.notification_seq),
             notification_buffer: Arc::clone(&self.notification_buffer),
             timing_histograms: Arc::clone(&self.timing_histograms),
+            transaction: TransactionSlot::default(),
+            session_transaction: false,
+            prepared: PreparedSlot::default(),
+            replication: Arc::clone(&self.replication),
+            cluster: self.cluster.clone(),
+            shards: self.shards.clone(),
+            admission: Arc::clone(&self.admission),
+            preemption: None,
+            plan_capture: None,
+            applying: false,
         }
     }
 
@@ -832,6 +1038,38 @@ impl Handler {
         &self.config
     }
 
+    /// Queue of queries waiting for and holding execution slots
+    pub fn admission(&self) -> &Admission {
+        &self.admission
+    }
+
+    /// Admission priority of a caller's queries: the session's choice, else
+    /// the API key's, else `[admission] default_priority`. A key's priority
+    /// is also the highest a session using it may choose.
+    fn query_priority(
+        &self,
+        session: Option<Priority>,
+        auth: Option<&crate::auth::AuthIdentity>,
+    ) -> Priority {
+        let key = auth
+            .and_then(|identity| identity.api_key.as_deref())
+            .and_then(|label| {
+                self.config
+                    .http
+                    .auth
+                    .api_keys
+                    .iter()
+                    .find(|key| key.label == label)
+            })
+            .and_then(|key| key.priority);
+        match (session, key) {
+            (Some(session), Some(key)) => session.min(key),
+            (session, key) => session
+                .or(key)
+                .unwrap_or(self.config.admission.default_priority),
+        }
+    }
+
     /// Subscribe to persistent data change notifications.
     /// Returns a broadcast receiver for push updates.
     pub fn subscribe_notifications(
@@ -926,6 +1164,12 @@ impl Handler {
 
     /// Create a new session bound to a knowledge graph.
     pub fn create_session(&self, knowledge_graph: &str) -> Result<SessionId, String> {
+        self.check_session_kg(knowledge_graph)?;
+        self.sessions.create_session(knowledge_graph)
+    }
+
+    /// Check that a session can be bound to `knowledge_graph`
+    fn check_session_kg(&self, knowledge_graph: &str) -> Result<(), String> {
         // Block direct access to the system KG
         if knowledge_graph == crate::auth::INTERNAL_KG {
             return Err(format!(
@@ -939,11 +1183,10 @@ impl Handler {
         storage
             .ensure_knowledge_graph(knowledge_graph)
             .map_err(|e| format!("Knowledge graph '{knowledge_graph}' not found: {e}"))?;
-        drop(storage);
-        self.sessions.create_session(knowledge_graph)
+        Ok(())
     }
 
-    /// Create a session with per-KG access check.
+    /// Create a session with per-KG access check, owned by the user.
     /// Rejects if the user has no access to the requested KG.
     pub fn create_session_with_auth(
         &self,
@@ -958,7 +1201,28 @@ impl Handler {
         {
             return Err("Access denied".to_string());
         }
-        self.create_session(knowledge_graph)
+        self.check_session_kg(knowledge_graph)?;
+        self.sessions
+            .create_owned_session(knowledge_graph, &auth.username)
+    }
+
+    /// Check that `auth` may use session `session_id` by its ID: it created
+    /// the session, or is an admin. Unknown sessions are reported as such.
+    pub fn authorize_session(
+        &self,
+        session_id: &SessionId,
+        auth: &crate::auth::AuthIdentity,
+    ) -> Result<(), String> {
+        let owner = self
+            .sessions
+            .with_session(session_id, |session| session.owner.clone())?;
+        match owner {
+            Some(owner) if owner != auth.username && auth.role != crate::auth::Role::Admin => {
+                // Indistinguishable from a session that does not exist
+                Err(format!("Session {session_id} not found"))
+            }
+            _ => Ok(()),
+        }
     }
 
     /// Close a session.
@@ -986,6 +1250,38 @@ impl Handler {
         self.storage.try_read_for(timeout)
     }
 
+    /// Stop admitting programs on every transport. Programs already
+    /// running continue; see [`Self::drain`].
+    pub fn begin_shutdown(&self) {
+        self.client_limits.start_draining();
+        info!(running = self.client_limits.running(), "shutdown_draining");
+    }
+
+    /// Whether [`Self::begin_shutdown`] has been called
+    pub fn is_draining(&self) -> bool {
+        self.client_limits.is_draining()
+    }
+
+    /// Wait up to `timeout` for running programs to finish. Returns the
+    /// number still running when it gave up.
+    pub async fn drain(&self, timeout: std::time::Duration) -> usize {
+        let started = Instant::now();
+        let running = self.client_limits.wait_idle(timeout).await;
+        if running == 0 {
+            info!(
+                elapsed_ms = started.elapsed().as_millis() as u64,
+                "shutdown_drained"
+            );
+        } else {
+            warn!(
+                running,
+                timeout_secs = timeout.as_secs(),
+                "shutdown_drain_timed_out"
+            );
+        }
+        running
+    }
+
     /// Graceful shutdown: flush WAL and save metadata for all knowledge graphs.
     pub fn shutdown(&self) {
         info!("Flushing WAL and saving metadata...");
@@ -1200,6 +1496,7 @@ impl Handler {
                             return Ok(auth::AuthIdentity {
                                 username: username.to_string(),
                                 role,
+                                api_key: None,
                             });
                         }
                         tracing::warn!(username, "audit_auth_login_failed");
@@ -1213,7 +1510,8 @@ impl Handler {
         Err("Invalid credentials".to_string())
     }
 
-    /// Authenticate an API key.
+    /// Authenticate an API key, either one created with `.apikey create` or
+    /// one listed in `http.auth.api_keys`.
     /// Returns `AuthIdentity` on success, error message on failure.
     pub fn authenticate_api_key(&self, key: &str) -> Result<crate::auth::AuthIdentity, String> {
         use crate::auth;
@@ -1227,46 +1525,66 @@ impl Handler {
             .map_err(|_| "Authentication service unavailable".to_string())?;
         drop(storage);
 
+        // Compare against every key, in constant time, so the time taken
+        // doesn't tell how close a guess came or which key matched.
+        // (label, owner)
+        let mut matched: Option<(String, String)> = None;
+        for configured in &self.config.http.auth.api_keys {
+            if auth::constant_time_eq(configured.key_sha256.as_bytes(), key_hash.as_bytes())
+                && matched.is_none()
+            {
+                matched = Some((configured.label.clone(), configured.username.clone()));
+            }
+        }
         let empty_vec = Vec::new();
         let api_keys = snapshot.input_tuples.get("api_keys").unwrap_or(&empty_vec);
-
         for tuple in api_keys {
             let vals = tuple.values();
             // api_keys: (label, key_hash, username)
             if vals.len() >= 3 {
-                if let (Some(hash), Some(uname)) = (vals[1].as_str(), vals[2].as_str()) {
-                    if hash == key_hash {
-                        // Look up user's role
-                        let empty_users = Vec::new();
-                        let users = snapshot.input_tuples.get("users").unwrap_or(&empty_users);
-                        for user_tuple in users {
-                            let uvals = user_tuple.values();
-                            if uvals.len() >= 3 {
-                                if let (Some(u), Some(r)) = (uvals[0].as_str(), uvals[2].as_str()) {
-                                    if u == uname {
-                                        let role = auth::Role::from_str(r)?;
-                                        tracing::info!(
-                                            username = uname,
-                                            role = %role,
-                                            "audit_auth_apikey_success"
-                                        );
-                                        return Ok(auth::AuthIdentity {
-                                            username: uname.to_string(),
-                                            role,
-                                        });
-                                    }
-                                }
-                            }
-                        }
-                        tracing::warn!(username = uname, "audit_auth_apikey_owner_not_found");
-                        return Err("API key owner not found".to_string());
+                if let (Some(label), Some(hash), Some(uname)) =
+                    (vals[0].as_str(), vals[1].as_str(), vals[2].as_str())
+                {
+                    if auth::constant_time_eq(hash.as_bytes(), key_hash.as_bytes())
+                        && matched.is_none()
+                    {
+                        matched = Some((label.to_string(), uname.to_string()));
                     }
                 }
             }
         }
 
-        tracing::warn!("audit_auth_apikey_invalid");
-        Err("Invalid API key".to_string())
+        let Some((label, uname)) = matched else {
+            tracing::warn!("audit_auth_apikey_invalid");
+            return Err("Invalid API key".to_string());
+        };
+
+        // Look up the owner's role
+        let empty_users = Vec::new();
+        let users = snapshot.input_tuples.get("users").unwrap_or(&empty_users);
+        for user_tuple in users {
+            let uvals = user_tuple.values();
+            if uvals.len() >= 3 {
+                if let (Some(u), Some(r)) = (uvals[0].as_str(), uvals[2].as_str()) {
+                    if u == uname {
+                        let role = auth::Role::from_str(r)?;
+                        tracing::info!(
+                            username = %uname,
+                            api_key = %label,
+                            role = %role,
+                            "audit_auth_apikey_success"
+                        );
+                        return Ok(auth::AuthIdentity {
+                            username: uname,
+                            role,
+                            api_key: Some(label),
+                        });
+                    }
+                }
+            }
+        }
+        tracing::warn!(username = %uname, api_key = %label, "audit_auth_apikey_owner_not_found");
+        Err("API key owner not found".to_string())
     }
 
     // ── User CRUD ───────────────────────────────────────────────────────────
@@ -1435,6 +1753,23 @@ impl Handler {
             }
         }
 
+        // And their grants on relations and views
+        if let Some(grants) = snapshot.input_tuples.get("object_grants") {
+            let to_delete: Vec<_> = grants
+                .iter()
+                .filter(|t| {
+                    t.values()
+                        .get(3)
+                        .and_then(|v| v.as_str())
+                        .is_some_and(|u| u == username)
+                })
+                .cloned()
+                .collect();
+            if !to_delete.is_empty() {
+                let _ = storage.delete_tuples_from(auth::INTERNAL_KG, "object_grants", to_delete);
+            }
+        }
+
         tracing::info!(username, "audit_user_dropped");
         Ok(self.message_result(&format!("User '{username}' dropped.")))
     }
@@ -1762,6 +2097,24 @@ impl Handler {
             }
         }
 
+        let grants = snapshot
+            .input_tuples
+            .get("object_grants")
+            .unwrap_or(&empty_vec);
+        for tuple in grants {
+            if let [kg, kind, name, user, privilege, ..] = tuple.values() {
+                if kg.as_str() == Some(kg_name) {
+                    entries.push(format!(
+                        "  {}: {} on {} {}",
+                        user.as_str().unwrap_or_default(),
+                        privilege.as_str().unwrap_or_default(),
+                        kind.as_str().unwrap_or_default(),
+                        name.as_str().unwrap_or_default()
+                    ));
+                }
+            }
+        }
+
         if entries.is_empty() {
             Ok(format!(
                 "No ACL entries for '{kg_name}'. Admins have implicit owner access."
@@ -1877,98 +2230,460 @@ impl Handler {
         Ok(format!("Revoked access on '{kg_name}' from '{username}'."))
     }
 
-    /// Look up the current global role for a user from storage.
-    /// Returns None if the user no longer exists (e.g., was dropped).
-    fn refresh_user_role(&self, identity: &crate::auth::AuthIdentity) -> Option<crate::auth::Role> {
-        use crate::auth;
+    // ── Object grants ─────────────────────────────────────────────────────
 
+    /// A user's grants on relations and views of a knowledge graph, from
+    /// `object_grants(kg, kind, name, username, privilege)`
+    fn object_grants_for(
+        &self,
+        kg_name: &str,
+        username: &str,
+    ) -> HashMap<String, crate::auth::Privilege> {
         let storage = self.storage.read();
-        let snapshot = storage.get_snapshot_for(auth::INTERNAL_KG).ok()?;
+        let Ok(snapshot) = storage.get_snapshot_for(crate::auth::INTERNAL_KG) else {
+            return HashMap::new();
+        };
         drop(storage);
 
-        let empty_vec = Vec::new();
-        let users = snapshot.input_tuples.get("users").unwrap_or(&empty_vec);
-
-        for tuple in users {
-            let vals = tuple.values();
-            if vals.len() >= 3 {
-                if let (Some(u), Some(r)) = (vals[0].as_str(), vals[2].as_str()) {
-                    if u == identity.username {
-                        return r.parse::<auth::Role>().ok();
+        let mut grants = HashMap::new();
+        for tuple in snapshot
+            .input_tuples
+            .get("object_grants")
+            .into_iter()
+            .flatten()
+        {
+            if let [kg, _, name, user, privilege, ..] = tuple.values() {
+                if kg.as_str() == Some(kg_name) && user.as_str() == Some(username) {
+                    if let (Some(name), Some(Ok(privilege))) =
+                        (name.as_str(), privilege.as_str().map(str::parse))
+                    {
+                        grants.insert(name.to_string(), privilege);
                     }
                 }
             }
         }
-
-        None // user was dropped
+        grants
     }
 
-    /// Remove all ACL entries for a dropped knowledge graph.
-    fn cleanup_kg_acls(&self, kg_name: &str) {
+    /// Set (or with `None`, remove) a user's privilege on a relation or view
+    fn set_object_grant(
+        &self,
+        kg_name: &str,
+        object: &statement::GrantObject,
+        username: &str,
+        privilege: Option<crate::auth::Privilege>,
+    ) -> Result<(), String> {
         use crate::auth;
 
         let storage = self.storage.read();
-        let snapshot = match storage.get_snapshot_for(auth::INTERNAL_KG) {
-            Ok(s) => s,
-            Err(_) => return,
-        };
-
-        let empty_vec = Vec::new();
-        let acls = snapshot.input_tuples.get("kg_acls").unwrap_or(&empty_vec);
-
-        let to_remove: Vec<_> = acls
-            .iter()
-            .filter(|t| {
-                t.values()
-                    .first()
-                    .and_then(|v| v.as_str())
-                    .is_some_and(|k| k == kg_name)
+        let snapshot = storage
+            .get_snapshot_for(auth::INTERNAL_KG)
+            .map_err(|e| format!("Auth storage error: {e}"))?;
+        let existing: Vec<_> = snapshot
+            .input_tuples
+            .get("object_grants")
+            .into_iter()
+            .flatten()
+            .filter(|t| match t.values() {
+                [kg, _, name, user, ..] => {
+                    kg.as_str() == Some(kg_name)
+                        && name.as_str() == Some(object.name())
+                        && user.as_str() == Some(username)
+                }
+                _ => false,
             })
             .cloned()
             .collect();
-
-        if !to_remove.is_empty() {
-            let count = to_remove.len();
-            let _ = storage.delete_tuples_from(auth::INTERNAL_KG, "kg_acls", to_remove);
-            tracing::info!(kg = kg_name, count, "audit_kg_acls_cleaned_up");
+        if !existing.is_empty() {
+            storage
+                .delete_tuples_from(auth::INTERNAL_KG, "object_grants", existing)
+                .map_err(|e| format!("Failed to update grant: {e}"))?;
+        }
+        if let Some(privilege) = privilege {
+            let tuple = Tuple::new(vec![
+                Value::string(kg_name),
+                Value::string(object.kind()),
+                Value::string(object.name()),
+                Value::string(username),
+                Value::string(&privilege.to_string()),
+            ]);
+            storage
+                .insert_tuples_into(auth::INTERNAL_KG, "object_grants", vec![tuple])
+                .map_err(|e| format!("Failed to grant: {e}"))?;
         }
+        Ok(())
     }
 
-    /// Get mutable access to the storage engine.
-    ///
-    /// **Warning**: This acquires a write lock on the entire storage engine.
-    /// Only use in tests - production code should use `get_storage()` (read lock).
-    pub fn get_storage_mut(&self) -> parking_lot::RwLockWriteGuard<'_, StorageEngine> {
-        self.storage.write()
-    }
-
-    /// Get total queries executed.
-    pub fn total_queries(&self) -> u64 {
-        self.query_count.load(Ordering::Relaxed)
-    }
+    /// Grant a privilege on a knowledge graph, or on a relation or view of
+    /// `kg_name`. Replaces the user's previous privilege on the object.
+    pub fn handle_grant(
+        &self,
+        kg_name: &str,
+        grant: &statement::GrantStatement,
+    ) -> Result<String, String> {
+        let statement::GrantStatement {
+            privilege,
+            object,
+            username,
+        } = grant;
+        if let statement::GrantObject::Database(name) = object {
+            return self.handle_kg_acl_grant(name, username, &privilege.kg_role().to_string());
+        }
 
-    /// Get reference to the accumulated timing histograms for Prometheus export.
-    pub fn timing_histograms(&self) -> &crate::execution::timing::TimingHistograms {
-        &self.timing_histograms
-    }
+        let storage = self.storage.read();
+        let exists = match object {
+            statement::GrantObject::View(name) => storage
+                .list_rules_in(kg_name)
+                .map_err(|e| e.to_string())?
+                .contains(name),
+            _ => storage
+                .list_relations_in(kg_name)
+                .map_err(|e| e.to_string())?
+                .iter()
+                .any(|r| r == object.name()),
+        };
+        drop(storage);
+        if !exists {
+            return Err(format!(
+                "{} '{}' not found in '{kg_name}'",
+                object.kind(),
+                object.name()
+            ));
+        }
 
-    /// Get total inserts executed.
-    pub fn total_inserts(&self) -> u64 {
-        self.insert_count.load(Ordering::Relaxed)
+        self.set_object_grant(kg_name, object, username, Some(*privilege))?;
+        tracing::info!(
+            kg = kg_name,
+            kind = object.kind(),
+            object = object.name(),
+            user = %username,
+            %privilege,
+            "audit_grant"
+        );
+        Ok(format!(
+            "Granted {privilege} on {} '{}' to '{username}'.",
+            object.kind(),
+            object.name()
+        ))
     }
 
-    /// Validate tuples against a schema for a given relation in a knowledge graph.
-    /// Returns Ok(()) if validation passes or no schema exists.
-    /// Returns Err with validation error message if schema validation fails.
-    ///
-    /// Schema validation is per-knowledge-graph, providing proper isolation.
-    pub fn validate_tuples_against_schema(
+    /// Revoke a privilege. The user keeps the next lower one: revoking
+    /// `write` from a writer leaves `read`, revoking `read` removes access.
+    pub fn handle_revoke(
         &self,
         kg_name: &str,
-        relation: &str,
-        tuples: &[Tuple],
-    ) -> Result<(), String> {
-        let storage = self.storage.read();
+        revoke: &statement::GrantStatement,
+    ) -> Result<String, String> {
+        let statement::GrantStatement {
+            privilege,
+            object,
+            username,
+        } = revoke;
+        let held = match object {
+            // The stored entry: the Viewer role skips the implicit admin owner
+            statement::GrantObject::Database(name) => self
+                .get_kg_role_for_user(name, username, &crate::auth::Role::Viewer)
+                .map(crate::auth::KgRole::privilege),
+            _ => self
+                .object_grants_for(kg_name, username)
+                .get(object.name())
+                .copied(),
+        };
+        if held.is_none_or(|held| held < *privilege) {
+            return Err(format!(
+                "'{username}' does not have {privilege} on {} '{}'",
+                object.kind(),
+                object.name()
+            ));
+        }
+
+        let remaining = privilege.below();
+        match (object, remaining) {
+            (statement::GrantObject::Database(name), Some(lower)) => {
+                self.handle_kg_acl_grant(name, username, &lower.kg_role().to_string())?;
+            }
+            (statement::GrantObject::Database(name), None) => {
+                self.handle_kg_acl_revoke(name, username)?;
+            }
+            _ => self.set_object_grant(kg_name, object, username, remaining)?,
+        }
+        tracing::info!(
+            kg = kg_name,
+            kind = object.kind(),
+            object = object.name(),
+            user = %username,
+            %privilege,
+            "audit_revoke"
+        );
+        let kept = remaining.map_or(String::new(), |lower| format!(" They keep {lower}."));
+        Ok(format!(
+            "Revoked {privilege} on {} '{}' from '{username}'.{kept}",
+            object.kind(),
+            object.name()
+        ))
+    }
+
+    /// Remove grants on relations and views of `kg_name` that no longer exist
+    fn cleanup_object_grants(&self, kg_name: &str) {
+        use crate::auth;
+
+        let storage = self.storage.read();
+        let mut stored: std::collections::HashSet<String> = storage
+            .list_relations_in(kg_name)
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+        stored.extend(storage.list_rules_in(kg_name).unwrap_or_default());
+        let Ok(snapshot) = storage.get_snapshot_for(auth::INTERNAL_KG) else {
+            return;
+        };
+        let stale: Vec<_> = snapshot
+            .input_tuples
+            .get("object_grants")
+            .into_iter()
+            .flatten()
+            .filter(|t| match t.values() {
+                [kg, _, name, ..] => {
+                    kg.as_str() == Some(kg_name)
+                        && name.as_str().is_some_and(|name| !stored.contains(name))
+                }
+                _ => false,
+            })
+            .cloned()
+            .collect();
+        if !stale.is_empty() {
+            let count = stale.len();
+            let _ = storage.delete_tuples_from(auth::INTERNAL_KG, "object_grants", stale);
+            tracing::info!(kg = kg_name, count, "audit_object_grants_cleaned_up");
+        }
+    }
+
+    /// Check `stmt` against the user's role on `kg` and, where that role
+    /// alone does not allow it, against their grants on the relations and
+    /// views the statement touches
+    fn authorize_kg_statement(
+        &self,
+        kg: &str,
+        identity: &crate::auth::AuthIdentity,
+        stmt: &statement::Statement,
+    ) -> Result<(), String> {
+        use crate::auth::{self, ObjectAccess};
+
+        let kg_role = self.get_kg_role_for_user(kg, &identity.username, &identity.role);
+        let role_check = match kg_role {
+            Some(kg_role) => auth::authorize_kg_operation(&kg_role, stmt),
+            None => Err("Access denied".to_string()),
+        };
+        if role_check.is_ok() {
+            return self.authorize_qualified_reads(identity, stmt);
+        }
+        let grants = self.object_grants_for(kg, &identity.username);
+        if grants.is_empty() {
+            return role_check;
+        }
+
+        let storage = self.storage.read();
+        let mut stored: std::collections::HashSet<String> = storage
+            .list_relations_in(kg)
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+        stored.extend(storage.list_rules_in(kg).unwrap_or_default());
+        drop(storage);
+
+        let from_role = kg_role.map(auth::KgRole::privilege);
+        auth::authorize_object_access(stmt, |name| {
+            if stored.contains(name) {
+                ObjectAccess::Stored(grants.get(name).copied().max(from_role))
+            } else {
+                ObjectAccess::Missing
+            }
+        })?;
+        self.authorize_qualified_reads(identity, stmt)
+    }
+
+    /// Check the user's access to every relation of another knowledge
+    /// graph `stmt` names as `kg.relation`, through a role on that graph or
+    /// a grant on the relation. Stored rules are not checked again when
+    /// queried: they read with the access of the user who registered them.
+    fn authorize_qualified_reads(
+        &self,
+        identity: &crate::auth::AuthIdentity,
+        stmt: &statement::Statement,
+    ) -> Result<(), String> {
+        use crate::auth;
+
+        for used in auth::statement_objects(stmt).unwrap_or_default() {
+            let Some((kg, relation)) = used
+                .name
+                .split_once('.')
+                .filter(|(kg, relation)| !kg.is_empty() && !relation.is_empty())
+            else {
+                continue;
+            };
+            let from_role = self
+                .get_kg_role_for_user(kg, &identity.username, &identity.role)
+                .map(auth::KgRole::privilege);
+            let held = self
+                .object_grants_for(kg, &identity.username)
+                .get(relation)
+                .copied()
+                .max(from_role);
+            if held.is_none_or(|held| held < used.privilege) {
+                return Err(format!(
+                    "Permission denied: needs {} on '{}'",
+                    used.privilege, used.name
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Look up the current global role for a user from storage.
+    /// Returns None if the user no longer exists (e.g., was dropped).
+    fn refresh_user_role(&self, identity: &crate::auth::AuthIdentity) -> Option<crate::auth::Role> {
+        use crate::auth;
+
+        let storage = self.storage.read();
+        let snapshot = storage.get_snapshot_for(auth::INTERNAL_KG).ok()?;
+        drop(storage);
+
+        let empty_vec = Vec::new();
+        let users = snapshot.input_tuples.get("users").unwrap_or(&empty_vec);
+
+        for tuple in users {
+            let vals = tuple.values();
+            if vals.len() >= 3 {
+                if let (Some(u), Some(r)) = (vals[0].as_str(), vals[2].as_str()) {
+                    if u == identity.username {
+                        return r.parse::<auth::Role>().ok();
+                    }
+                }
+            }
+        }
+
+        None // user was dropped
+    }
+
+    /// Remove all ACL entries and object grants for a dropped knowledge graph.
+    fn cleanup_kg_acls(&self, kg_name: &str) {
+        use crate::auth;
+
+        let storage = self.storage.read();
+        let snapshot = match storage.get_snapshot_for(auth::INTERNAL_KG) {
+            Ok(s) => s,
+            Err(_) => return,
+        };
+
+        let empty_vec = Vec::new();
+        // Both relations have the KG name in the first column
+        for relation in ["kg_acls", "object_grants"] {
+            let acls = snapshot.input_tuples.get(relation).unwrap_or(&empty_vec);
+
+            let to_remove: Vec<_> = acls
+                .iter()
+                .filter(|t| {
+                    t.values()
+                        .first()
+                        .and_then(|v| v.as_str())
+                        .is_some_and(|k| k == kg_name)
+                })
+                .cloned()
+                .collect();
+
+            if !to_remove.is_empty() {
+                let count = to_remove.len();
+                let _ = storage.delete_tuples_from(auth::INTERNAL_KG, relation, to_remove);
+                tracing::info!(kg = kg_name, relation, count, "audit_kg_acls_cleaned_up");
+            }
+        }
+    }
+
+    /// Move all ACL entries and object grants of a renamed knowledge graph
+    /// to its new name.
+    fn rename_kg_acls(&self, old_name: &str, new_name: &str) {
+        use crate::auth;
+        use crate::Value;
+
+        let storage = self.storage.read();
+        let snapshot = match storage.get_snapshot_for(auth::INTERNAL_KG) {
+            Ok(s) => s,
+            Err(_) => return,
+        };
+
+        let empty_vec = Vec::new();
+        for relation in ["kg_acls", "object_grants"] {
+            let acls = snapshot.input_tuples.get(relation).unwrap_or(&empty_vec);
+
+            let to_move: Vec<_> = acls
+                .iter()
+                .filter(|t| {
+                    t.values()
+                        .first()
+                        .and_then(|v| v.as_str())
+                        .is_some_and(|k| k == old_name)
+                })
+                .cloned()
+                .collect();
+
+            if !to_move.is_empty() {
+                let count = to_move.len();
+                let renamed: Vec<_> = to_move
+                    .iter()
+                    .map(|t| {
+                        let mut values = t.values().to_vec();
+                        values[0] = Value::String(new_name.to_string().into());
+                        crate::Tuple::new(values)
+                    })
+                    .collect();
+                let _ = storage.insert_tuples_into(auth::INTERNAL_KG, relation, renamed);
+                let _ = storage.delete_tuples_from(auth::INTERNAL_KG, relation, to_move);
+                tracing::info!(
+                    kg = old_name,
+                    new_name,
+                    relation,
+                    count,
+                    "audit_kg_acls_renamed"
+                );
+            }
+        }
+    }
+
+    /// Get mutable access to the storage engine.
+    ///
+    /// **Warning**: This acquires a write lock on the entire storage engine.
+    /// Only use in tests - production code should use `get_storage()` (read lock).
+    pub fn get_storage_mut(&self) -> parking_lot::RwLockWriteGuard<'_, StorageEngine> {
+        self.storage.write()
+    }
+
+    /// Get total queries executed.
+    pub fn total_queries(&self) -> u64 {
+        self.query_count.load(Ordering::Relaxed)
+    }
+
+    /// Get reference to the accumulated timing histograms for Prometheus export.
+    pub fn timing_histograms(&self) -> &crate::execution::timing::TimingHistograms {
+        &self.timing_histograms
+    }
+
+    /// Get total inserts executed.
+    pub fn total_inserts(&self) -> u64 {
+        self.insert_count.load(Ordering::Relaxed)
+    }
+
+    /// Validate tuples against a schema for a given relation in a knowledge graph.
+    /// Returns Ok(()) if validation passes or no schema exists.
+    /// Returns Err with validation error message if schema validation fails.
+    ///
+    /// Schema validation is per-knowledge-graph, providing proper isolation.
+    pub fn validate_tuples_against_schema(
+        &self,
+        kg_name: &str,
+        relation: &str,
+        tuples: &[Tuple],
+    ) -> Result<(), String> {
+        let storage = self.storage.read();
         storage
             .validate_tuples_in(kg_name, relation, tuples)
             .map_err(|e| format!("{e}"))
@@ -2001,7 +2716,7 @@ impl Handler {
 
     // === Index Management API ===
 
-    /// Create an HNSW index on a knowledge graph.
+    /// Create an HNSW or LSH index on a knowledge graph.
     ///
     /// Resolves the column name to an index via the schema catalog,
     /// enables the incremental engine if needed, and registers the index.
@@ -2031,69 +2746,27 @@ impl Handler {
             }
         };
 
-        // Validate index type
-        let index_type_str = opts.index_type.as_str();
-        if index_type_str != "hnsw" {
-            return Err(format!(
-                "Unsupported index type '{index_type_str}'. Currently only 'hnsw' is supported."
-            ));
-        }
-
-        // Parse distance metric
-        let metric = opts
-            .metric
-            .as_deref()
-            .unwrap_or("cosine")
-            .parse::<DistanceMetric>()
-            .map_err(|e| format!("Invalid metric: {e}"))?;
-
-        let m = opts.m.unwrap_or(16);
-        let ef_construction = opts.ef_construction.unwrap_or(200);
-        let ef_search = opts.ef_search.unwrap_or(50);
-
-        // Validate HNSW parameters to prevent crashes
-        if m < 2 {
-            return Err(format!("HNSW parameter m must be >= 2, got {m}"));
-        }
-        if m > 256 {
-            return Err(format!("HNSW parameter m must be <= 256, got {m}"));
-        }
-        if ef_construction < 1 {
-            return Err("HNSW parameter ef_construction must be >= 1".to_string());
-        }
-        if ef_search < 1 {
-            return Err("HNSW parameter ef_search must be >= 1".to_string());
-        }
-
-        let hnsw_config = HnswConfig {
-            m,
-            ef_construction,
-            ef_search,
-            metric,
-        };
+        let index_type = index_type_from_options(opts)?;
 
         let registered = RegisteredIndex {
             name: opts.name.clone(),
             relation: opts.relation.clone(),
             column_idx,
             column_name: opts.column.clone(),
-            index_type: IndexType::Hnsw(hnsw_config),
+            index_type,
         };
 
-        // Enable incremental engine and register index
-        storage
+        // Register the index and build it from the relation's tuples
+        let count = storage
             .with_kg_mut(kg, |kg_data| {
-                kg_data.enable_incremental().map_err(|e| e.to_string())?;
-                if let Some(dd) = kg_data.incremental() {
-                    dd.register_index(registered)
-                } else {
-                    Err("Failed to enable incremental engine".to_string())
-                }
+                kg_data
+                    .create_vector_index(registered)
+                    .map_err(|e| e.to_string())
             })
             .map_err(|e| e.to_string())?;
 
         Ok(format!(
-            "Index '{}' created on {}.{}.",
+            "Index '{}' created on {}.{} ({count} vectors).",
             opts.name, opts.relation, opts.column
         ))
     }
@@ -2103,11 +2776,7 @@ impl Handler {
         let storage = self.storage.read();
         storage
             .with_kg_read(kg, |kg_data| {
-                if let Some(dd) = kg_data.incremental() {
-                    dd.remove_index(name)
-                } else {
-                    Err(format!("Index '{name}' not found (no incremental engine)"))
-                }
+                kg_data.drop_vector_index(name).map_err(|e| e.to_string())
             })
             .map_err(|e| e.to_string())?;
 
@@ -2147,26 +2816,147 @@ impl Handler {
     /// be rebuilt on the next data change or query.
     pub fn rebuild_index(&self, kg: &str, name: &str) -> Result<String, String> {
         let storage = self.storage.read();
-        storage
+        let count = storage
             .with_kg_read(kg, |kg_data| {
-                if let Some(dd) = kg_data.incremental() {
-                    // Verify the index exists
-                    let stats = dd.get_index_stats(Some(name))?;
-                    if stats.is_empty() {
-                        return Err(format!("Index '{name}' not found"));
-                    }
-                    // Indexes are automatically rebuilt when their base relation
-                    // receives updates. Notify the base relation to trigger rebuild.
-                    Ok(format!(
-                        "Index '{name}' will be rebuilt on next data update."
-                    ))
-                } else {
-                    Err(format!("Index '{name}' not found (no incremental engine)"))
-                }
+                kg_data
+                    .rebuild_vector_index(name)
+                    .map_err(|e| e.to_string())
             })
+            .map_err(|e| e.to_string())?;
+        Ok(format!("Index '{name}' rebuilt ({count} vectors)."))
+    }
+
+    // === Continuous Queries API ===
+
+    /// Subscribe to a query's results on a knowledge graph.
+    ///
+    /// Accepts the same `?relation(X, Y)` shorthand as `query_program`. The
+    /// returned stream yields the current results as insertions, then the
+    /// insertions and retractions caused by later writes, so clients such
+    /// as dashboards can follow a view (e.g. a `top_k` ranking) live.
+    pub fn subscribe_query(&self, kg: &str, query: &str) -> Result<ViewSubscription, String> {
+        let program = transform_query_shorthand(query)?.query;
+        let storage = self.storage.read();
+        storage
+            .with_kg_mut(kg, |kg_data| kg_data.subscribe(&program))
             .map_err(|e| e.to_string())
     }
 
+    /// Subscribe a client to a query's changes, as `subscribe_query` does,
+    /// after checking that the user may run the query in `kg`.
+    pub fn subscribe_live(
+        &self,
+        kg: &str,
+        query: &str,
+        auth: Option<&crate::auth::AuthIdentity>,
+    ) -> Result<LiveSubscription, String> {
+        let stmt = statement::parse_statement(query)?;
+        if !matches!(stmt, statement::Statement::Query(_)) {
+            return Err("Only queries can be subscribed to".to_string());
+        }
+        self.authorize_in(kg, &stmt, auth)?;
+        let stream = self.subscribe_query(kg, query)?;
+        Ok(LiveSubscription::new(
+            uuid::Uuid::new_v4().to_string(),
+            kg.to_string(),
+            query.to_string(),
+            auth.map(|identity| identity.username.clone())
+                .unwrap_or_default(),
+            stream,
+        ))
+    }
+
+    /// Restart a subscription from a fresh snapshot, re-checking access
+    pub fn resubscribe_live(
+        &self,
+        subscription: &mut LiveSubscription,
+        auth: Option<&crate::auth::AuthIdentity>,
+    ) -> Result<(), String> {
+        let stmt = statement::parse_statement(subscription.query())?;
+        self.authorize_in(subscription.kg(), &stmt, auth)?;
+        let stream = self.subscribe_query(subscription.kg(), subscription.query())?;
+        subscription.reset(stream);
+        Ok(())
+    }
+
+    /// Keep a subscription whose connection closed, so the client can
+    /// resume it within `ws_subscription_resume_secs`.
+    pub fn detach_live(&self, subscription: LiveSubscription) {
+        if self.config.http.ws_subscription_resume_secs == 0 {
+            return;
+        }
+        self.detached_live.lock().insert(
+            subscription.id().to_string(),
+            (Instant::now(), subscription),
+        );
+    }
+
+    /// Take back a detached subscription. Only the user who created it can.
+    pub fn resume_live(
+        &self,
+        id: &str,
+        auth: Option<&crate::auth::AuthIdentity>,
+    ) -> Result<LiveSubscription, String> {
+        let username = auth.map_or("", |identity| identity.username.as_str());
+        let max_age = std::time::Duration::from_secs(self.config.http.ws_subscription_resume_secs);
+        let mut detached = self.detached_live.lock();
+        match detached.get(id) {
+            Some((since, subscription))
+                if subscription.owner() == username && since.elapsed() < max_age =>
+            {
+                detached
+                    .remove(id)
+                    .map(|(_, subscription)| subscription)
+                    .ok_or_else(|| format!("Unknown subscription '{id}'"))
+            }
+            _ => Err(format!("Unknown subscription '{id}'")),
+        }
+    }
+
+    /// Drop detached subscriptions that were not resumed in time.
+    /// Returns how many were dropped.
+    pub fn reap_detached_live(&self) -> usize {
+        let max_age = std::time::Duration::from_secs(self.config.http.ws_subscription_resume_secs);
+        let mut detached = self.detached_live.lock();
+        let before = detached.len();
+        detached.retain(|_, (since, _)| since.elapsed() < max_age);
+        before - detached.len()
+    }
+
+    /// Admit `statements` statements from `client` under the per-client
+    /// limits. Hold the permit while they run.
+    pub fn admit(&self, client: &str, statements: usize) -> Result<ClientPermit, Throttled> {
+        self.client_limits.admit(client, statements.max(1) as u64)
+    }
+
+    /// Admit `program` from `client`, counting each of its statements
+    pub fn admit_program(&self, client: &str, program: &str) -> Result<ClientPermit, Throttled> {
+        self.admit(client, statement_count(program))
+    }
+
+    /// Check a result against the per-request size limit
+    pub fn check_result_size(&self, result: &QueryResult) -> Result<(), Throttled> {
+        self.client_limits
+            .check_result_bytes(result.estimated_bytes())
+    }
+
+    /// Check the results of a batch against the per-request size limit
+    pub fn check_batch_size(&self, batch: &BatchResult) -> Result<(), Throttled> {
+        self.client_limits
+            .check_result_bytes(batch.estimated_bytes())
+    }
+
+    /// Check a cursor page against the per-request size limit
+    pub fn check_page_size(&self, page: &CursorPage) -> Result<(), Throttled> {
+        self.client_limits
+            .check_result_bytes(page.estimated_bytes())
+    }
+
+    /// Result cursors of every client
+    pub fn cursors(&self) -> &CursorStore {
+        &self.cursors
+    }
+
     /// Process an agent message asynchronously.
     ///
     /// Called from the WebSocket handler for `.agent` commands.
@@ -2248,6 +3038,29 @@ impl Handler {
         &self,
         knowledge_graph: Option<String>,
         program: String,
+    ) -> Result<QueryResult, String> {
+        self.query_program_in(
+            knowledge_graph,
+            program,
+            None,
+            None,
+            SessionSettings::default(),
+        )
+        .await
+    }
+
+    /// Execute an IQL program, queuing writes in a session's transaction
+    /// once `begin` has opened one. Without a session, a transaction lasts
+    /// until the end of the program and is rolled back if not committed,
+    /// and prepared statements are forgotten when the program ends. The
+    /// session's `settings` may shorten the query timeout.
+    async fn query_program_in(
+        &self,
+        knowledge_graph: Option<String>,
+        program: String,
+        transaction: Option<TransactionSlot>,
+        prepared: Option<PreparedSlot>,
+        settings: SessionSettings,
     ) -> Result<QueryResult, String> {
         // Intercept .agent commands - these need async context for Claude API calls
         let trimmed = program.trim();
@@ -2355,98 +3168,134 @@ impl Handler {
             ));
         }
 
-        // Acquire a semaphore permit to bound concurrent DD computations at ncpu.
+        // Wait for an execution slot to bound concurrent DD computations.
         // This prevents blocking-thread-pool explosion (without limiting, spawn_blocking
         // allows unlimited parallelism, causing CPU thrash and longer individual runtimes).
-        // `acquire_owned()` is an async wait - Tokio workers remain free while queued.
-        // Timeout after 30s to reject requests when server is overloaded.
+        // `admit()` is an async wait - Tokio workers remain free while queued.
+        // Refused when the queue is full or the wait exceeds `queue_timeout_ms`.
         let wait_start = Instant::now();
-        let permit = match tokio::time::timeout(
-            std::time::Duration::from_secs(30),
-            Arc::clone(&self.query_semaphore).acquire_owned(),
-        )
-        .await
-        {
-            Ok(Ok(permit)) => permit,
-            Ok(Err(_)) => return Err("Query semaphore closed (server shutting down)".to_string()),
-            Err(_) => {
-                return Err("Server overloaded: query queue full (timed out after 30s)".to_string())
-            }
-        };
+        let queue_kg = knowledge_graph
+            .as_deref()
+            .unwrap_or(&self.config.storage.default_knowledge_graph);
+        let priority = settings
+            .priority
+            .unwrap_or(self.config.admission.default_priority);
+        let permit = self.admission.admit(queue_kg, &program, priority).await?;
         let queued_ms = wait_start.elapsed().as_millis() as u64;
         if queued_ms > 0 {
-            info!(queued_ms, program_len, "query_semaphore_wait");
+            info!(queued_ms, program_len, "query_queue_wait");
         }
 
         // Offload CPU-bound DD computation to the blocking thread pool.
         // This keeps Tokio worker threads free for I/O and other async tasks.
-        let job = self.make_query_job();
-        let timeout_ms = self.config.storage.performance.query_timeout_ms;
+        let mut job = self.make_query_job();
+        if let Some(slot) = transaction {
+            job.transaction = slot;
+            job.session_transaction = true;
+        }
+        if let Some(slot) = prepared {
+            job.prepared = slot;
+        }
+        job.preemption = Some(permit.preemption());
+        // With a capture, the statement is kept for the slow query record
+        let plan_capture = self
+            .slow_query_capture()
+            .map(|capture| (capture, queue_kg.to_string(), program.clone()));
+        job.plan_capture = plan_capture.as_ref().map(|(capture, ..)| capture.clone());
+        let timeout_ms = settings.timeout_ms(self.config.storage.performance.query_timeout_ms);
 
         // Cooperative cancellation flag: set on timeout so DD spin loops exit promptly.
         let cancel_flag = Arc::new(std::sync::atomic::AtomicBool::new(false));
         let cancel_flag_clone = Arc::clone(&cancel_flag);
 
         // The permit is moved into the blocking task so it's released when DD finishes.
+        // The engine's spans nest under the caller's.
+        let span = tracing::Span::current();
         let blocking_task = tokio::task::spawn_blocking(move || {
+            let _entered = span.enter();
             crate::code_generator::set_query_cancel_flag(Some(cancel_flag_clone));
             let result = job.execute(knowledge_graph, program);
             crate::code_generator::set_query_cancel_flag(None);
-            drop(permit); // Explicit drop; semaphore slot returned here
+            drop(permit); // Explicit drop; execution slot returned here
             result
         });
 
-        if timeout_ms > 0 {
-            let result =
-                tokio::time::timeout(std::time::Duration::from_millis(timeout_ms), blocking_task)
-                    .await;
-            match result {
-                Ok(joined) => {
-                    let compute_ms = query_start.elapsed().as_millis() as u64;
-                    info!(program_len, compute_ms, "query_complete");
-                    let slow_ms = self.config.storage.performance.slow_query_log_ms;
-                    if slow_ms > 0 && compute_ms >= slow_ms {
-                        warn!(
-                            program_len,
-                            compute_ms,
-                            threshold_ms = slow_ms,
-                            "slow_query"
-                        );
-                    }
-                    joined.map_err(|e| {
-                        tracing::error!(error = %e, "Query task panicked");
-                        "Internal query execution error".to_string()
-                    })?
-                }
+        let joined = if timeout_ms > 0 {
+            match tokio::time::timeout(std::time::Duration::from_millis(timeout_ms), blocking_task)
+                .await
+            {
+                Ok(joined) => Some(joined),
                 Err(_) => {
                     // Signal the DD spin loop to stop
                     cancel_flag.store(true, std::sync::atomic::Ordering::Relaxed);
+                    crate::metrics::metrics().query_timeouts.inc();
                     warn!(
                         program_len,
                         timeout_ms,
                         elapsed_ms = query_start.elapsed().as_millis() as u64,
                         "query_timeout"
                     );
-                    Err("Query execution timed out".to_string())
+                    None
                 }
             }
         } else {
-            let joined = blocking_task.await.map_err(|e| {
-                tracing::error!(error = %e, "Query task panicked");
-                "Internal query execution error".to_string()
-            })?;
-            let compute_ms = query_start.elapsed().as_millis() as u64;
-            info!(program_len, compute_ms, "query_complete");
-            let slow_ms = self.config.storage.performance.slow_query_log_ms;
-            if slow_ms > 0 && compute_ms >= slow_ms {
-                warn!(
-                    program_len,
-                    compute_ms,
-                    threshold_ms = slow_ms,
-                    "slow_query"
-                );
+            Some(blocking_task.await)
+        };
+        let compute_ms = query_start.elapsed().as_millis() as u64;
+        let (result, outcome) = match joined {
+            Some(joined) => {
+                let result = joined.map_err(|e| {
+                    tracing::error!(error = %e, "Query task panicked");
+                    "Internal query execution error".to_string()
+                })?;
+                info!(program_len, compute_ms, "query_complete");
+                let outcome = if result.is_ok() { "ok" } else { "error" };
+                (result, outcome)
             }
-            joined
+            None => (Err("Query execution timed out".to_string()), "timeout"),
+        };
+        let slow_ms = self.config.storage.performance.slow_query_log_ms;
+        if slow_ms > 0 && compute_ms >= slow_ms {
+            warn!(
+                program_len,
+                compute_ms,
+                threshold_ms = slow_ms,
+                "slow_query"
+            );
+            if let Some((capture, knowledge_graph, statement)) = plan_capture {
+                self.record_slow_query(&crate::slow_query_log::SlowQueryRecord {
+                    time: now_ms().saturating_sub(compute_ms) as i64,
+                    knowledge_graph,
+                    statement: crate::audit::redact(&statement),
+                    duration_ms: compute_ms,
+                    rows: result.as_ref().map_or(0, |r| r.total_count),
+                    outcome,
+                    plans: capture.plans(),
+                });
+            }
+        }
+        result
+    }
+
+    /// A plan capture for the next query, if slow queries are recorded in
+    /// `_audit.slow_queries`
+    fn slow_query_capture(&self) -> Option<crate::execution::PlanCapture> {
+        let perf = &self.config.storage.performance;
+        (perf.slow_query_log_ms > 0 && perf.slow_query_relation)
+            .then(crate::execution::PlanCapture::default)
+    }
+
+    /// Append a slow query to `_audit.slow_queries`, creating the relation
+    /// for the first one
+    fn record_slow_query(&self, record: &crate::slow_query_log::SlowQueryRecord) {
+        let storage = self.storage.read();
+        prepare_audit_relation(&storage, crate::slow_query_log::relation_schema());
+        if let Err(e) = storage.insert_tuples_into(
+            crate::audit::AUDIT_KG,
+            crate::slow_query_log::SLOW_QUERY_RELATION,
+            vec![record.to_tuple()],
+        ) {
+            tracing::error!(error = %e, "slow_query_insert_failed");
         }
     }
 }
@@ -2455,10 +3304,35 @@ impl QueryJob {
     /// Execute an IQL program synchronously on the current thread.
     /// Called from `Handler::query_program` via `tokio::task::spawn_blocking`
     /// so that Tokio worker threads are never blocked by DD computation.
+    ///
+    /// An error rolls back the open transaction, if any.
     fn execute(
         self,
         knowledge_graph: Option<String>,
         program: String,
+    ) -> Result<QueryResult, String> {
+        let transaction = Arc::clone(&self.transaction);
+        let result = self.execute_statements(knowledge_graph, program);
+        if result.is_err() {
+            crate::metrics::metrics().query_errors.inc();
+        }
+        match result {
+            // Validation errors are JSON and stay untouched
+            Err(e) => match transaction.lock().take() {
+                Some(tx) if !e.starts_with(VALIDATION_ERROR_PREFIX) => Err(format!(
+                    "{e} (transaction rolled back, {} queued change(s) discarded)",
+                    tx.rollback()
+                )),
+                _ => Err(e),
+            },
+            ok => ok,
+        }
+    }
+
+    fn execute_statements(
+        self,
+        knowledge_graph: Option<String>,
+        program: String,
     ) -> Result<QueryResult, String> {
         self.inc_query_count();
         let start = Instant::now();
@@ -2530,6 +3404,8 @@ impl QueryJob {
         // Phase 2: Execute statements (all guaranteed to parse successfully)
         let mut messages = Vec::new();
         let mut query_to_execute: Option<String> = None;
+        // Result of the last show/describe statement, returned as a table
+        let mut introspection: Option<crate::storage_engine::IntrospectionTable> = None;
         let mut current_stmt = String::new();
         // Track KG switch for WS session binding update
         let mut switched_kg_result: Option<String> = None;
@@ -2552,16 +3428,85 @@ impl QueryJob {
 
             {
                 let stmt_text = current_stmt.trim();
+                // `execute` runs its prepared statement as if it were written here
+                let bound_text;
+                let (parsed, stmt_text) = match statement::parse_statement(stmt_text) {
+                    Ok(statement::Statement::Execute(exec)) => {
+                        bound_text = bind_prepared(&self.prepared, &exec)?;
+                        let stmt = statement::parse_statement(&bound_text)?;
+                        (Ok(stmt), bound_text.as_str())
+                    }
+                    parsed => (parsed, stmt_text),
+                };
                 if !stmt_text.is_empty() {
-                    if let Ok(stmt) = statement::parse_statement(stmt_text) {
+                    if let Ok(stmt) = parsed {
+                        if !self.applying {
+                            self.replication.check_writable(&stmt)?;
+                            // Runs only once the cluster agreed on its order
+                            if let Some(cluster) = self
+                                .cluster
+                                .as_ref()
+                                .filter(|_| super::cluster::metadata_statement(&stmt))
+                            {
+                                cluster.order_ddl(&kg_name, stmt_text)?;
+                            }
+                        }
+                        // Sent to followers as text once it has run; the
+                        // writes it makes are not recorded on their own
+                        let recording = storage
+                            .replication_log()
+                            .filter(|_| super::replication::replicated_statement(&stmt))
+                            .map(|log| (Arc::clone(log), kg_name.clone()));
+                        let _recording = recording.as_ref().map(|(log, _)| log.begin_statement());
                         match stmt {
                             statement::Statement::SchemaDecl(decl) => {
                                 // Build RelationSchema from SchemaDecl
                                 let mut relation_schema = RelationSchema::new(&decl.name);
+                                let mut constraint_error = None;
                                 for col in &decl.columns {
                                     let schema_type = col.col_type.to_schema_type();
-                                    relation_schema = relation_schema
-                                        .with_column(ColumnSchema::new(&col.name, schema_type));
+                                    let mut column = ColumnSchema::new(&col.name, schema_type);
+                                    column.primary_key =
+                                        col.annotations.contains(&statement::ColumnAnnotation::Key);
+                                    column.unique = col
+                                        .annotations
+                                        .contains(&statement::ColumnAnnotation::Unique);
+                                    match col.col_type.check_constraints() {
+                                        Ok(constraints) => column.constraints = constraints,
+                                        Err(e) => {
+                                            constraint_error =
+                                                Some(format!("column '{}': {e}", col.name));
+                                            break;
+                                        }
+                                    }
+                                    if col.annotations.contains(&statement::ColumnAnnotation::Auto)
+                                    {
+                                        if column.data_type != SchemaType::Int {
+                                            constraint_error = Some(format!(
+                                                "column '{}': @auto requires type int",
+                                                col.name
+                                            ));
+                                            break;
+                                        }
+                                        column.auto_increment = true;
+                                    }
+                                    if let Some(default) = &col.default {
+                                        if let Err(e) = default.check_type(&column.data_type) {
+                                            constraint_error =
+                                                Some(format!("column '{}': {e}", col.name));
+                                            break;
+                                        }
+                                        column.default = Some(default.clone());
+                                    }
+                                    relation_schema = relation_schema.with_column(column);
+                                }
+                                if let Some(e) = constraint_error {
+                                    messages.push(format!(
+                                        "Failed to register schema for '{}': {}",
+                                        decl.name, e
+                                    ));
+                                    current_stmt.clear();
+                                    continue;
                                 }
 
                                 // Register schema in the target knowledge graph (per-KG isolation)
@@ -2653,21 +3598,36 @@ impl QueryJob {
                                     continue;
                                 }
 
-                                // Validate against schema if one exists (per-KG isolation)
-                                if let Err(e) =
-                                    storage.validate_tuples_in(&kg_name, &op.relation, &tuples)
-                                {
+                                if let Some(mut tx) = self.open_transaction(&kg_name)? {
                                     messages.push(format!(
-                                        "Insert rejected for '{}': {}",
-                                        op.relation, e
+                                        "Queued {} fact(s) for '{}'.",
+                                        tuples.len(),
+                                        op.relation
                                     ));
+                                    tx.insert(op.relation, tuples);
                                     current_stmt.clear();
                                     continue;
                                 }
 
-                                let (inserted, _duplicates) = storage
-                                    .insert_tuples_into(&kg_name, &op.relation, tuples)
-                                    .map_err(|e| e.to_string())?;
+                                // Validated against the schema, if one exists, under the
+                                // configured policy (per-KG isolation)
+                                let report = match storage.insert_tuples_checked(
+                                    &kg_name,
+                                    &op.relation,
+                                    tuples,
+                                ) {
+                                    Ok(report) => report,
+                                    Err(e @ crate::storage::StorageError::Validation(_)) => {
+                                        messages.push(format!(
+                                            "Insert rejected for '{}': {}",
+                                            op.relation, e
+                                        ));
+                                        current_stmt.clear();
+                                        continue;
+                                    }
+                                    Err(e) => return Err(e.to_string()),
+                                };
+                                let inserted = report.inserted;
                                 self.insert_count
                                     .fetch_add(inserted as u64, Ordering::Relaxed);
                                 // Notify WebSocket subscribers of persistent data change
@@ -2683,6 +3643,24 @@ impl QueryJob {
                                     "Inserted {} fact(s) into '{}'.",
                                     inserted, op.relation
                                 ));
+                                if report.replaced > 0 {
+                                    messages.push(format!(
+                                        "Replaced {} fact(s) with the same key in '{}'.",
+                                        report.replaced, op.relation
+                                    ));
+                                }
+                                if report.quarantined > 0 {
+                                    messages.push(format!(
+                                        "Quarantined {} fact(s) into '{}'.",
+                                        report.quarantined,
+                                        crate::schema::ValidationPolicy::quarantine_relation(
+                                            &op.relation
+                                        )
+                                    ));
+                                }
+                                for violation in &report.violations {
+                                    messages.push(format!("Warning: {violation}"));
+                                }
                             }
                             statement::Statement::Fact(rule) => {
                                 // Session facts are NOT persisted - they are only available for
@@ -2736,6 +3714,15 @@ impl QueryJob {
                                                 }
                                             };
                                             let tuple = Tuple::new(values);
+                                            if let Some(mut tx) = self.open_transaction(&kg_name)? {
+                                                messages.push(format!(
+                                                    "Queued delete of 1 fact from '{}'.",
+                                                    op.relation
+                                                ));
+                                                tx.delete(op.relation, vec![tuple]);
+                                                current_stmt.clear();
+                                                continue;
+                                            }
                                             let deleted_count = storage
                                                 .delete_tuples_from(
                                                     &kg_name,
@@ -2758,6 +3745,27 @@ impl QueryJob {
                                         }
                                     }
                                     DeletePattern::BulkTuples(tuples) => {
+                                        if let Some(mut tx) = self.open_transaction(&kg_name)? {
+                                            let tuples: Vec<Tuple> = tuples
+                                                .iter()
+                                                .filter_map(|terms| {
+                                                    terms
+                                                        .iter()
+                                                        .map(term_to_value)
+                                                        .collect::<Result<Vec<_>, _>>()
+                                                        .ok()
+                                                })
+                                                .map(Tuple::new)
+                                                .collect();
+                                            messages.push(format!(
+                                                "Queued delete of {} fact(s) from '{}'.",
+                                                tuples.len(),
+                                                op.relation
+                                            ));
+                                            tx.delete(op.relation, tuples);
+                                            current_stmt.clear();
+                                            continue;
+                                        }
                                         let mut total_deleted = 0;
                                         for tuple_terms in tuples {
                                             // Convert terms to values
@@ -2833,7 +3841,7 @@ impl QueryJob {
                                             )
                                             .map_err(|e| e.to_string())?;
 
-                                        let mut deleted = 0;
+                                        let mut matched: Vec<crate::value::Tuple> = Vec::new();
 
                                         for result_tuple in results {
                                             // Build bindings from result
@@ -2885,19 +3893,25 @@ impl QueryJob {
                                             }
 
                                             if valid && !tuple_values.is_empty() {
-                                                let tuple_to_delete =
-                                                    crate::value::Tuple::new(tuple_values);
-                                                let count = storage
-                                                    .delete_tuples_from(
-                                                        &kg_name,
-                                                        &op.relation,
-                                                        vec![tuple_to_delete],
-                                                    )
-                                                    .map_err(|e| e.to_string())?;
-                                                deleted += count;
+                                                matched
+                                                    .push(crate::value::Tuple::new(tuple_values));
                                             }
                                         }
 
+                                        if let Some(mut tx) = self.open_transaction(&kg_name)? {
+                                            messages.push(format!(
+                                                "Conditional delete: queued {} fact(s) from '{}'.",
+                                                matched.len(),
+                                                op.relation
+                                            ));
+                                            tx.delete(op.relation, matched);
+                                            current_stmt.clear();
+                                            continue;
+                                        }
+                                        let deleted = storage
+                                            .delete_tuples_from(&kg_name, &op.relation, matched)
+                                            .map_err(|e| e.to_string())?;
+
                                         if deleted > 0 {
                                             self.notify_persistent_update(
                                                 &kg_name,
@@ -2917,6 +3931,12 @@ impl QueryJob {
                                 let rule_text = format_rule_text(&rule);
                                 let rule_def = statement::parse_rule_definition(&rule_text)
                                     .map_err(|e| format!("Failed to parse rule: {e}"))?;
+                                if let Some(mut tx) = self.open_transaction(&kg_name)? {
+                                    tx.register_rule(rule_def);
+                                    messages.push(format!("Rule '{}' queued.", rule.head.relation));
+                                    current_stmt.clear();
+                                    continue;
+                                }
                                 storage
                                     .register_rule_in(&kg_name, &rule_def)
                                     .map_err(|e| e.to_string())?;
@@ -2961,6 +3981,12 @@ impl QueryJob {
                                 query_to_execute = Some(stmt_text.to_string());
                             }
                             statement::Statement::DeleteRelationOrRule(name) => {
+                                if let Some(mut tx) = self.open_transaction(&kg_name)? {
+                                    messages.push(format!("Drop of rule '{name}' queued."));
+                                    tx.drop_rule(name);
+                                    current_stmt.clear();
+                                    continue;
+                                }
                                 match storage.drop_rule_in(&kg_name, &name) {
                                     Ok(()) => messages.push(format!("Rule '{name}' dropped.")),
                                     Err(_) => {
@@ -3007,8 +4033,10 @@ impl QueryJob {
                                     .execute_query_with_rules_tuples_on(&kg_name, &query_rule)
                                     .map_err(|e| e.to_string())?;
 
-                                let mut deleted = 0;
-                                let mut inserted = 0;
+                                // Resolve every target tuple first so inserts can be
+                                // validated before any delete is applied.
+                                // Entries are (is_insert, relation, tuple) in execution order.
+                                let mut writes: Vec<(bool, &str, Tuple)> = Vec::new();
 
                                 for result_tuple in results {
                                     // Build bindings from query result: var_name → Value
@@ -3023,9 +4051,17 @@ impl QueryJob {
                                             })
                                             .collect();
 
-                                    for target in &op.deletes {
-                                        let tuple_vals: Option<Vec<Value>> = target
-                                            .args
+                                    let targets = op
+                                        .deletes
+                                        .iter()
+                                        .map(|t| (false, t.relation.as_str(), &t.args))
+                                        .chain(
+                                            op.inserts
+                                                .iter()
+                                                .map(|t| (true, t.relation.as_str(), &t.args)),
+                                        );
+                                    for (is_insert, relation, args) in targets {
+                                        let tuple_vals: Option<Vec<Value>> = args
                                             .iter()
                                             .map(|arg| match arg {
                                                 Term::Variable(v) => bindings.get(v).cloned(),
@@ -3033,38 +4069,62 @@ impl QueryJob {
                                             })
                                             .collect();
                                         if let Some(vals) = tuple_vals {
-                                            let count = storage
-                                                .delete_tuples_from(
-                                                    &kg_name,
-                                                    &target.relation,
-                                                    vec![Tuple::new(vals)],
-                                                )
-                                                .map_err(|e| e.to_string())?;
-                                            deleted += count;
+                                            writes.push((is_insert, relation, Tuple::new(vals)));
                                         }
                                     }
+                                }
 
-                                    for target in &op.inserts {
-                                        let tuple_vals: Option<Vec<Value>> = target
-                                            .args
-                                            .iter()
-                                            .map(|arg| match arg {
-                                                Term::Variable(v) => bindings.get(v).cloned(),
-                                                other => term_to_value(other).ok(),
-                                            })
-                                            .collect();
-                                        if let Some(vals) = tuple_vals {
-                                            let (new_count, _) = storage
-                                                .insert_tuples_into(
-                                                    &kg_name,
-                                                    &target.relation,
-                                                    vec![Tuple::new(vals)],
-                                                )
-                                                .map_err(|e| e.to_string())?;
-                                            inserted += new_count;
+                                // Apply everything as one batch: inserts are validated
+                                // before any delete runs, and concurrent queries never
+                                // see the update half applied. Consecutive writes of
+                                // the same kind to the same relation share a step.
+                                let mut ops: Vec<WriteOp> = Vec::new();
+                                for (is_insert, relation, tuple) in writes {
+                                    match ops.last_mut() {
+                                        Some(WriteOp::Insert(rel, tuples))
+                                            if is_insert && rel.as_str() == relation =>
+                                        {
+                                            tuples.push(tuple);
+                                        }
+                                        Some(WriteOp::Delete(rel, tuples))
+                                            if !is_insert && rel.as_str() == relation =>
+                                        {
+                                            tuples.push(tuple);
+                                        }
+                                        _ if is_insert => {
+                                            ops.push(WriteOp::Insert(
+                                                relation.to_string(),
+                                                vec![tuple],
+                                            ));
+                                        }
+                                        _ => {
+                                            ops.push(WriteOp::Delete(
+                                                relation.to_string(),
+                                                vec![tuple],
+                                            ));
+                                        }
+                                    }
+                                }
+                                if let Some(mut tx) = self.open_transaction(&kg_name)? {
+                                    let (mut deletes, mut inserts) = (0, 0);
+                                    for step in &ops {
+                                        match step {
+                                            WriteOp::Delete(_, tuples) => deletes += tuples.len(),
+                                            WriteOp::Insert(_, tuples) => inserts += tuples.len(),
                                         }
                                     }
+                                    messages.push(format!(
+                                        "Update queued: {deletes} delete(s), {inserts} insert(s)."
+                                    ));
+                                    tx.extend(ops);
+                                    current_stmt.clear();
+                                    continue;
                                 }
+                                let report = storage
+                                    .apply_batch_in(&kg_name, ops)
+                                    .map_err(|e| format!("Update rejected: {e}"))?;
+                                let deleted = report.deleted;
+                                let inserted = report.inserted;
 
                                 // Track insert count for metrics
                                 self.insert_count
@@ -3085,6 +4145,193 @@ impl QueryJob {
                             statement::Statement::TypeDecl(decl) => {
                                 messages.push(format!("Type '{}' declared.", decl.name));
                             }
+                            statement::Statement::Show(target) => {
+                                let result = match target {
+                                    statement::ShowTarget::Relations => {
+                                        storage.show_relations_in(&kg_name)
+                                    }
+                                    statement::ShowTarget::Views => storage.show_views_in(&kg_name),
+                                    statement::ShowTarget::Databases => {
+                                        storage.show_databases().map(|(schema, mut rows)| {
+                                            rows.retain(|row| {
+                                                row.get(0).and_then(Value::as_str)
+                                                    != Some(crate::auth::INTERNAL_KG)
+                                            });
+                                            (schema, rows)
+                                        })
+                                    }
+                                    statement::ShowTarget::Queries => {
+                                        Ok(self.admission.show_queries())
+                                    }
+                                };
+                                match result {
+                                    Ok(table) => introspection = Some(table),
+                                    Err(e) => messages.push(format!("Error: {e}")),
+                                }
+                            }
+                            statement::Statement::Transaction(control) => match control {
+                                statement::TransactionControl::Begin => {
+                                    let mut slot = self.transaction.lock();
+                                    if let Some(tx) = slot.as_ref() {
+                                        messages.push(format!(
+                                            "A transaction is already open on '{}'. Commit or roll it back first.",
+                                            tx.kg()
+                                        ));
+                                    } else {
+                                        let tx = storage
+                                            .begin_transaction(&kg_name)
+                                            .map_err(|e| e.to_string())?;
+                                        *slot = Some(tx);
+                                        messages.push("Transaction started.".to_string());
+                                    }
+                                }
+                                statement::TransactionControl::Commit => {
+                                    let open = self.transaction.lock().take();
+                                    let Some(tx) = open else {
+                                        messages.push("No transaction is open.".to_string());
+                                        current_stmt.clear();
+                                        continue;
+                                    };
+                                    let tx_kg = tx.kg().to_string();
+                                    let registered: Vec<String> = tx
+                                        .rule_changes()
+                                        .iter()
+                                        .filter_map(|change| match change {
+                                            RuleChange::Register(def) => Some(def.name.clone()),
+                                            RuleChange::Drop(_) => None,
+                                        })
+                                        .collect();
+                                    let report = storage
+                                        .commit_transaction(tx)
+                                        .map_err(|e| format!("Transaction rolled back: {e}"))?;
+                                    let batch = report.batch;
+                                    self.insert_count
+                                        .fetch_add(batch.inserted as u64, Ordering::Relaxed);
+                                    if batch.inserted + batch.deleted + batch.replaced > 0 {
+                                        self.notify_persistent_update(
+                                            &tx_kg,
+                                            "multiple",
+                                            "transaction",
+                                            batch.inserted + batch.deleted + batch.replaced,
+                                        );
+                                    }
+                                    for name in &registered {
+                                        self.notify_rule_change(&tx_kg, name, "registered");
+                                    }
+                                    messages.push(format!(
+                                        "Transaction committed: {} inserted, {} deleted, {} replaced, {} rule change(s).",
+                                        batch.inserted,
+                                        batch.deleted,
+                                        batch.replaced,
+                                        report.rule_changes
+                                    ));
+                                }
+                                statement::TransactionControl::Rollback => {
+                                    let open = self.transaction.lock().take();
+                                    messages.push(match open {
+                                        Some(tx) => format!(
+                                            "Transaction rolled back: {} queued change(s) discarded.",
+                                            tx.rollback()
+                                        ),
+                                        None => "No transaction is open.".to_string(),
+                                    });
+                                }
+                            },
+                            statement::Statement::Describe(name) => {
+                                match storage.describe_relation_rows_in(&kg_name, &name) {
+                                    Ok(table) => introspection = Some(table),
+                                    Err(e) => messages.push(format!("Error: {e}")),
+                                }
+                            }
+                            statement::Statement::Analyze(relation) => {
+                                match storage.analyze_in(&kg_name, relation.as_deref()) {
+                                    Ok(all_stats) if all_stats.is_empty() => {
+                                        messages.push("No relations to analyze.".to_string());
+                                    }
+                                    Ok(all_stats) => {
+                                        for stats in &all_stats {
+                                            let schema = storage
+                                                .get_schema_in(&kg_name, &stats.name)
+                                                .ok()
+                                                .flatten();
+                                            messages.extend(format_relation_stats(
+                                                stats,
+                                                schema.as_ref(),
+                                            ));
+                                        }
+                                    }
+                                    Err(e) => messages.push(format!("Error: {e}")),
+                                }
+                            }
+                            statement::Statement::Copy(copy) => {
+                                if self.transaction.lock().is_some() {
+                                    messages.push(
+                                        "copy cannot run inside a transaction. Commit or roll it back first."
+                                            .to_string(),
+                                    );
+                                    current_stmt.clear();
+                                    continue;
+                                }
+                                let path = std::path::Path::new(&copy.path);
+                                match copy.direction {
+                                    statement::CopyDirection::From => match storage.copy_from_in(
+                                        &kg_name,
+                                        &copy.relation,
+                                        path,
+                                        &copy.options,
+                                    ) {
+                                        Ok(inserted) => {
+                                            self.insert_count
+                                                .fetch_add(inserted as u64, Ordering::Relaxed);
+                                            if inserted > 0 {
+                                                self.notify_persistent_update(
+                                                    &kg_name,
+                                                    &copy.relation,
+                                                    "insert",
+                                                    inserted,
+                                                );
+                                            }
+                                            messages.push(format!(
+                                                "Copied {inserted} row(s) from '{}' into '{}'.",
+                                                copy.path, copy.relation
+                                            ));
+                                        }
+                                        Err(e) => messages.push(format!("Error: {e}")),
+                                    },
+                                    statement::CopyDirection::To => match storage.copy_to_in(
+                                        &kg_name,
+                                        &copy.relation,
+                                        path,
+                                        &copy.options,
+                                    ) {
+                                        Ok(rows) => messages.push(format!(
+                                            "Copied {rows} row(s) from '{}' to '{}'.",
+                                            copy.relation, copy.path
+                                        )),
+                                        Err(e) => messages.push(format!("Error: {e}")),
+                                    },
+                                }
+                            }
+                            statement::Statement::Grant(_) | statement::Statement::Revoke(_) => {
+                                messages.push(
+                                    "Error: grant and revoke must be sent as a statement on their own."
+                                        .to_string(),
+                                );
+                            }
+                            statement::Statement::Prepare(prepared) => {
+                                messages.push(store_prepared(&self.prepared, prepared)?);
+                            }
+                            statement::Statement::Deallocate(name) => {
+                                if self.prepared.lock().remove(&name).is_some() {
+                                    messages
+                                        .push(format!("Prepared statement '{name}' deallocated."));
+                                } else {
+                                    messages
+                                        .push(format!("Prepared statement '{name}' not found."));
+                                }
+                            }
+                            // Bound to its prepared statement above, which cannot be another execute
+                            statement::Statement::Execute(_) => {}
                             statement::Statement::Meta(meta) => {
                                 let kg = kg_name.as_str();
                                 match meta {
@@ -3175,6 +4422,88 @@ impl QueryJob {
                                             }
                                         }
                                     }
+                                    MetaCommand::KgDropCascade(name) => {
+                                        if name == kg {
+                                            messages.push("Cannot drop current knowledge graph. Switch to another first.".to_string());
+                                        } else {
+                                            info!(kg = %name, "meta_kg_drop_cascade_start");
+                                            match storage
+                                                .prepare_drop_knowledge_graph_cascade(&name)
+                                            {
+                                                Ok(cleanup) => {
+                                                    messages.push(format!(
+                                                        "Knowledge graph '{name}' dropped."
+                                                    ));
+                                                    if !cleanup.dropped_rules.is_empty() {
+                                                        messages.push(format!(
+                                                            "Dropped dependent rules: {}",
+                                                            cleanup.dropped_rules.join(", ")
+                                                        ));
+                                                    }
+                                                    storage.finish_drop_knowledge_graph(cleanup);
+                                                    info!(kg = %name, "meta_kg_drop_cascade_ok");
+                                                    self.notify_kg_change(&name, "dropped");
+                                                }
+                                                Err(e) => {
+                                                    info!(kg = %name, error = %e, "meta_kg_drop_err");
+                                                    messages.push(format!("Drop failed: {e}"));
+                                                }
+                                            }
+                                        }
+                                    }
+                                    MetaCommand::KgCopy { source, target } => {
+                                        info!(kg = %source, target = %target, "meta_kg_copy_start");
+                                        match storage.copy_knowledge_graph(&source, &target) {
+                                            Ok(()) => {
+                                                messages.push(format!(
+                                                    "Knowledge graph '{source}' copied to '{target}'."
+                                                ));
+                                                self.notify_kg_change(&target, "created");
+                                            }
+                                            Err(e) => {
+                                                info!(kg = %source, error = %e, "meta_kg_copy_err");
+                                                messages.push(format!("Copy failed: {e}"));
+                                            }
+                                        }
+                                    }
+                                    MetaCommand::KgRename { source, target } => {
+                                        if source == kg {
+                                            messages.push("Cannot rename current knowledge graph. Switch to another first.".to_string());
+                                        } else {
+                                            info!(kg = %source, target = %target, "meta_kg_rename_start");
+                                            match storage.rename_knowledge_graph(&source, &target) {
+                                                Ok(()) => {
+                                                    messages.push(format!(
+                                                        "Knowledge graph '{source}' renamed to '{target}'."
+                                                    ));
+                                                    self.notify_kg_change(&source, "dropped");
+                                                    self.notify_kg_change(&target, "created");
+                                                }
+                                                Err(e) => {
+                                                    info!(kg = %source, error = %e, "meta_kg_rename_err");
+                                                    messages.push(format!("Rename failed: {e}"));
+                                                }
+                                            }
+                                        }
+                                    }
+                                    MetaCommand::KgRestore(target) => {
+                                        info!(kg = %kg, target = ?target, "meta_kg_restore_start");
+                                        match storage.restore_knowledge_graph_to(kg, target) {
+                                            Ok(summary) => {
+                                                messages.push(format!(
+                                                    "Knowledge graph '{kg}' restored to LSN {}: {} relation(s) changed, {} tuple(s) inserted, {} deleted.",
+                                                    summary.lsn,
+                                                    summary.relations,
+                                                    summary.inserted,
+                                                    summary.deleted
+                                                ));
+                                                self.notify_kg_change(kg, "restored");
+                                            }
+                                            Err(e) => {
+                                                messages.push(format!("Restore failed: {e}"));
+                                            }
+                                        }
+                                    }
 
                                     // === Relation commands ===
                                     MetaCommand::RelList => {
@@ -3251,6 +4580,51 @@ impl QueryJob {
                                         }
                                     }
 
+                                    MetaCommand::RelAlter { relation, action } => {
+                                        let altered = match action {
+                                            RelAlterAction::Partition(decl) => {
+                                                rel_alter_partition(decl).and_then(|spec| {
+                                                    storage
+                                                        .partition_relation_in(kg, &relation, spec)
+                                                        .map_err(|e| e.to_string())
+                                                })
+                                            }
+                                            RelAlterAction::Retain(policy) => storage
+                                                .set_retention_in(kg, &relation, policy)
+                                                .map_err(|e| e.to_string()),
+                                            // The router takes placements before they get here
+                                            RelAlterAction::Shard(_) => {
+                                                Err("Sharding is not configured on this server"
+                                                    .to_string())
+                                            }
+                                            action => {
+                                                rel_alter_migration(action).and_then(|migration| {
+                                                    storage
+                                                        .alter_schema_in(kg, &relation, migration)
+                                                        .map_err(|e| e.to_string())
+                                                })
+                                            }
+                                        };
+                                        match altered {
+                                            Ok(schema) => {
+                                                self.notify_schema_change(kg, &relation, "altered");
+                                                let mut message = format!(
+                                                    "Relation '{relation}' altered: {schema}"
+                                                );
+                                                if let Some(spec) = &schema.partition {
+                                                    message.push_str(&format!(
+                                                        " partitioned by {spec}"
+                                                    ));
+                                                }
+                                                if let Some(policy) = &schema.retention {
+                                                    message.push_str(&format!(", {policy}"));
+                                                }
+                                                messages.push(message);
+                                            }
+                                            Err(e) => messages.push(format!("Error: {e}")),
+                                        }
+                                    }
+
                                     // === Rule commands ===
                                     MetaCommand::RuleList => match storage.list_rules_in(kg) {
                                         Ok(rules) => {
@@ -3329,6 +4703,30 @@ impl QueryJob {
                                             Err(e) => messages.push(format!("Error: {e}")),
                                         }
                                     }
+                                    MetaCommand::RuleRefresh(name) => {
+                                        match storage.refresh_view_in(kg, &name) {
+                                            Ok(rows) => messages.push(format!(
+                                                "Rule '{name}' refreshed: {rows} row(s)."
+                                            )),
+                                            Err(e) => messages.push(format!("Error: {e}")),
+                                        }
+                                    }
+                                    MetaCommand::RuleSetRefresh { name, policy } => {
+                                        match storage.set_refresh_policy_in(kg, &name, policy) {
+                                            Ok(rows) => {
+                                                self.notify_rule_change(kg, &name, "updated");
+                                                messages.push(match policy {
+                                                    Some(policy) => format!(
+                                                        "Rule '{name}' refresh policy set to {policy}: {rows} row(s)."
+                                                    ),
+                                                    None => format!(
+                                                        "Rule '{name}' is evaluated on every query."
+                                                    ),
+                                                });
+                                            }
+                                            Err(e) => messages.push(format!("Error: {e}")),
+                                        }
+                                    }
                                     MetaCommand::RuleClear(name) => {
                                         match storage.clear_rule_in(kg, &name) {
                                             Ok(()) => {
@@ -3409,6 +4807,77 @@ impl QueryJob {
                                         }
                                     }
 
+                                    MetaCommand::EncryptionRotate => {
+                                        // Rewrites files like compaction: release the
+                                        // storage lock for the duration
+                                        drop(storage);
+                                        let rotate_result = {
+                                            let s = self.storage.read();
+                                            s.rotate_encryption()
+                                        };
+                                        storage = self.storage.read();
+                                        match rotate_result {
+                                            Ok(rewritten) => {
+                                                messages.push(format!(
+                                                    "Encryption key rotated: {rewritten} batch file(s) re-encrypted."
+                                                ));
+                                            }
+                                            Err(e) => {
+                                                messages.push(format!("Key rotation error: {e}"));
+                                            }
+                                        }
+                                    }
+
+                                    // === Log verbosity ===
+                                    MetaCommand::LogShow => match crate::logging::filter() {
+                                        Some(filter) => {
+                                            messages
+                                                .push(format!("Log filter: {}", filter.current()));
+                                        }
+                                        None => messages.push(LOG_FILTER_FIXED.to_string()),
+                                    },
+
+                                    MetaCommand::LogLevel(directives) => {
+                                        let applied = match (crate::logging::filter(), directives) {
+                                            (None, _) => Err(LOG_FILTER_FIXED.to_string()),
+                                            (Some(filter), Some(directives)) => {
+                                                filter.set(&directives).map(|()| directives)
+                                            }
+                                            (Some(filter), None) => filter.reset(),
+                                        };
+                                        match applied {
+                                            Ok(applied) => {
+                                                tracing::info!(filter = %applied, "audit_log_filter_changed");
+                                                messages.push(format!("Log filter: {applied}"));
+                                            }
+                                            Err(e) => messages.push(format!("Error: {e}")),
+                                        }
+                                    }
+
+                                    // === Replication ===
+                                    MetaCommand::ReplicationStatus => {
+                                        messages.extend(self.replication.status_lines());
+                                    }
+                                    MetaCommand::ReplicationPromote => {
+                                        match self.replication.promote() {
+                                            Ok(message) => messages.push(message),
+                                            Err(e) => messages.push(format!("Promote failed: {e}")),
+                                        }
+                                    }
+                                    MetaCommand::ClusterStatus => match &self.cluster {
+                                        Some(cluster) => messages.extend(cluster.status_lines()),
+                                        None => messages.push(
+                                            "This server is not part of a cluster.".to_string(),
+                                        ),
+                                    },
+                                    MetaCommand::ShardStatus => match &self.shards {
+                                        Some(router) => messages.extend(router.status_lines()),
+                                        None => messages.push(
+                                            "This server does not route sharded relations."
+                                                .to_string(),
+                                        ),
+                                    },
+
                                     // === Debug command ===
                                     MetaCommand::Debug(query) => {
                                         // Transform ?shorthand before debug
@@ -3590,9 +5059,13 @@ impl QueryJob {
                                     MetaCommand::SessionList
                                     | MetaCommand::SessionClear
                                     | MetaCommand::SessionDrop(_)
-                                    | MetaCommand::SessionDropName(_) => {
+                                    | MetaCommand::SessionDropName(_)
+                                    | MetaCommand::SessionSettings
+                                    | MetaCommand::SessionSet { .. }
+                                    | MetaCommand::SessionSetPriority(_)
+                                    | MetaCommand::SessionReset(_) => {
                                         messages.push(
-                                            "Session commands require a WebSocket connection."
+                                            "Session commands require a session (a WebSocket connection or the X-Session-Id header)."
                                                 .to_string(),
                                         );
                                     }
@@ -3631,6 +5104,9 @@ impl QueryJob {
                                 }
                             }
                         }
+                        if let Some((log, kg)) = &recording {
+                            log.append_statement(kg, stmt_text);
+                        }
                     } else {
                         query_to_execute = Some(stmt_text.to_string());
                     }
@@ -3638,6 +5114,15 @@ impl QueryJob {
                 current_stmt.clear();
             }
         }
+        // Outside a session a transaction cannot outlive its program
+        if !self.session_transaction {
+            if let Some(tx) = self.transaction.lock().take() {
+                messages.push(format!(
+                    "Transaction not committed: {} queued change(s) rolled back.",
+                    tx.rollback()
+                ));
+            }
+        }
         let stmt_exec_ms = stmt_exec_start.elapsed().as_millis() as u64;
         if stmt_exec_ms > 0 {
             info!(
@@ -3649,6 +5134,14 @@ impl QueryJob {
             );
         }
 
+        // Return the introspection table if that was the last result
+        if let (Some(table), None) = (introspection, &query_to_execute) {
+            drop(storage);
+            let mut result = introspection_result(table, start.elapsed().as_millis() as u64);
+            result.switched_kg = switched_kg_result;
+            return Ok(result);
+        }
+
         // Return messages if no query
         if !messages.is_empty() && query_to_execute.is_none() {
             drop(storage); // Release storage lock - we no longer need it
@@ -3708,23 +5201,28 @@ impl QueryJob {
             .map(|s| s.columns.iter().map(|c| c.name.clone()).collect());
 
         let snapshot = storage
-            .get_snapshot_for(&kg_name)
+            .get_snapshot_for_program(&kg_name, &query_program)
             .map_err(|e| e.to_string())?;
         drop(storage); // Release storage read lock BEFORE DD computation
 
-        let debug_session = std::env::var("IL_DEBUG_SESSION").is_ok();
-        if debug_session && !session_fact_tuples.is_empty() {
+        if !session_fact_tuples.is_empty() {
             debug!(
                 count = session_fact_tuples.len(),
                 "Executing with session facts (isolated)"
             );
             for (relation, tuple) in &session_fact_tuples {
-                debug!(relation, tuple = ?tuple, "session_fact");
+                tracing::trace!(relation, tuple = ?tuple, "session_fact");
             }
         }
 
         // Execute DD computation on the snapshot - completely lock-free.
         // Session facts are added to an ISOLATED COPY, providing request-scoped isolation.
+        // Holding no lock, the query may lend its slot between strata.
+        let preemptible = self.preemption.as_ref().map(Preemption::install);
+        let capturing = self
+            .plan_capture
+            .as_ref()
+            .map(crate::execution::PlanCapture::install);
         let query_exec_start = Instant::now();
         let has_session_facts = !session_fact_tuples.is_empty();
         let timing_mode = self.config.storage.performance.timing_mode;
@@ -3741,6 +5239,8 @@ impl QueryJob {
                 )
                 .map_err(|e| format!("Query execution failed: {e}"))?
         };
+        drop(capturing);
+        drop(preemptible);
         let query_exec_ms = query_exec_start.elapsed().as_millis() as u64;
         info!(
             program_len,
@@ -3767,6 +5267,12 @@ impl QueryJob {
                         Value::Bool(b) => WireValue::Bool(*b),
                         Value::Null => WireValue::Null,
                         Value::Timestamp(ts) => WireValue::Timestamp(*ts),
+                        Value::Date(d) => WireValue::Date(*d),
+                        Value::Duration(d) => WireValue::Duration(*d),
+                        Value::Uuid(u) => WireValue::Uuid(*u),
+                        v @ (Value::List(_) | Value::Map(_) | Value::Json(_) | Value::Bytes(_)) => {
+                            WireValue::from_value(v)
+                        }
                     })
                     .collect();
                 WireTuple {
@@ -3807,6 +5313,13 @@ impl QueryJob {
                         Value::Bool(_) => WireDataType::Bool,
                         Value::Null => WireDataType::String,
                         Value::Timestamp(_) => WireDataType::Timestamp,
+                        Value::Date(_) => WireDataType::Date,
+                        Value::Duration(_) => WireDataType::Duration,
+                        Value::Uuid(_) => WireDataType::Uuid,
+                        Value::List(_) => WireDataType::List,
+                        Value::Map(_) => WireDataType::Map,
+                        Value::Json(_) => WireDataType::Json,
+                        Value::Bytes(_) => WireDataType::Bytes,
                     },
                 })
                 .collect()
@@ -3900,6 +5413,24 @@ impl Handler {
         &self,
         session_id: &SessionId,
         program: String,
+    ) -> Result<QueryResult, String> {
+        let session = self
+            .sessions
+            .settings(session_id)
+            .ok()
+            .and_then(|settings| settings.priority);
+        let priority = self.query_priority(session, None);
+        self.query_session_program(session_id, program, priority)
+            .await
+    }
+
+    /// [`Self::query_program_with_session`], waiting for an execution slot
+    /// at `priority`
+    async fn query_session_program(
+        &self,
+        session_id: &SessionId,
+        program: String,
+        priority: Priority,
     ) -> Result<QueryResult, String> {
         // Input size validation (same as query_program)
         let perf = &self.config.storage.performance;
@@ -3915,16 +5446,26 @@ impl Handler {
         // If session was reaped (e.g., WS reconnect), fall back to non-session query.
         if self.sessions.touch_session(session_id).is_err() {
             tracing::debug!(session_id = %session_id, "session_gone_fallback_to_query_program");
-            return self.query_program(None, program).await;
+            let settings = SessionSettings {
+                priority: Some(priority),
+                ..SessionSettings::default()
+            };
+            return self
+                .query_program_in(None, program, None, None, settings)
+                .await;
         }
 
         // Check if session is clean → fast path
         let is_clean = self.sessions.is_session_clean(session_id)?;
         let kg = self.sessions.session_kg(session_id)?;
+        let mut settings = self.sessions.settings(session_id)?;
+        settings.priority = Some(priority);
 
         if is_clean {
             // Fast path: no ephemeral state, use global snapshot directly
-            return self.query_program(Some(kg), program).await;
+            return self
+                .query_program_in(Some(kg), program, None, None, settings)
+                .await;
         }
 
         // Slow path: combine ephemeral + persistent data
@@ -3964,17 +5505,19 @@ impl Handler {
             let names: Option<Vec<String>> = find_query_source_relation(&preprocessed)
                 .and_then(|rel| storage.get_schema_in(&kg, &rel).ok().flatten())
                 .map(|s| s.columns.iter().map(|c| c.name.clone()).collect());
-            let snap = storage.get_snapshot_for(&kg).map_err(|e| e.to_string())?;
+            let snap = storage
+                .get_snapshot_for_program(&kg, &combined_program)
+                .map_err(|e| e.to_string())?;
             (snap, names)
         }; // storage read lock released here
 
-        // Acquire semaphore permit to bound concurrent DD computations (same as query_program)
-        let permit = Arc::clone(&self.query_semaphore)
-            .acquire_owned()
-            .await
-            .map_err(|_| "Query semaphore closed (server shutting down)")?;
+        // Wait for an execution slot to bound concurrent DD computations (same as query_program)
+        let permit = self.admission.admit(&kg, &program, priority).await?;
+        let preemption = permit.preemption();
+        let plan_capture = self.slow_query_capture();
+        let job_capture = plan_capture.clone();
 
-        let timeout_ms = self.config.storage.performance.query_timeout_ms;
+        let timeout_ms = settings.timeout_ms(self.config.storage.performance.query_timeout_ms);
         let cancel_flag = Arc::new(std::sync::atomic::AtomicBool::new(false));
         let cancel_flag_clone = Arc::clone(&cancel_flag);
 
@@ -3983,10 +5526,17 @@ impl Handler {
         let preprocessed_clone = preprocessed.clone();
         let timing_mode = self.config.storage.performance.timing_mode;
         let timing_histograms = Arc::clone(&self.timing_histograms);
+        let span = tracing::Span::current();
         let blocking_task = tokio::task::spawn_blocking(move || {
+            let _entered = span.enter();
             crate::code_generator::set_query_cancel_flag(Some(cancel_flag_clone));
+            // Lock-free, so the query may lend its slot between strata
+            let _preemptible = preemption.install();
 
             // Run session query on snapshot (lock-free) with profiling
+            let capturing = job_capture
+                .as_ref()
+                .map(crate::execution::PlanCapture::install);
             let (results, timing_breakdown) = snapshot
                 .execute_with_session_facts_profiled(
                     &combined_program_clone,
@@ -3994,6 +5544,7 @@ impl Handler {
                     timing_mode,
                 )
                 .map_err(|e| format!("Query execution failed: {e}"))?;
+            drop(capturing);
 
             // Record timing in Prometheus histograms
             if let Some(ref tb) = timing_breakdown {
@@ -4014,7 +5565,7 @@ impl Handler {
             };
 
             crate::code_generator::set_query_cancel_flag(None);
-            drop(permit); // Release semaphore slot
+            drop(permit); // Release execution slot
 
             Ok::<_, String>((results, baseline, timing_breakdown))
         });
@@ -4059,6 +5610,12 @@ impl Handler {
                         Value::Bool(b) => WireValue::Bool(*b),
                         Value::Null => WireValue::Null,
                         Value::Timestamp(ts) => WireValue::Timestamp(*ts),
+                        Value::Date(d) => WireValue::Date(*d),
+                        Value::Duration(d) => WireValue::Duration(*d),
+                        Value::Uuid(u) => WireValue::Uuid(*u),
+                        v @ (Value::List(_) | Value::Map(_) | Value::Json(_) | Value::Bytes(_)) => {
+                            WireValue::from_value(v)
+                        }
                     })
                     .collect();
                 let prov = if baseline.contains(tuple) {
@@ -4101,6 +5658,13 @@ impl Handler {
                         Value::Bool(_) => WireDataType::Bool,
                         Value::Null => WireDataType::String,
                         Value::Timestamp(_) => WireDataType::Timestamp,
+                        Value::Date(_) => WireDataType::Date,
+                        Value::Duration(_) => WireDataType::Duration,
+                        Value::Uuid(_) => WireDataType::Uuid,
+                        Value::List(_) => WireDataType::List,
+                        Value::Map(_) => WireDataType::Map,
+                        Value::Json(_) => WireDataType::Json,
+                        Value::Bytes(_) => WireDataType::Bytes,
                     },
                 })
                 .collect()
@@ -4124,6 +5688,17 @@ impl Handler {
                 threshold_ms = slow_ms,
                 "slow_session_query"
             );
+            if let Some(capture) = plan_capture {
+                self.record_slow_query(&crate::slow_query_log::SlowQueryRecord {
+                    time: now_ms().saturating_sub(execution_time_ms) as i64,
+                    knowledge_graph: kg.clone(),
+                    statement: crate::audit::redact(&program),
+                    duration_ms: execution_time_ms,
+                    rows: results.len(),
+                    outcome: "ok",
+                    plans: capture.plans(),
+                });
+            }
         }
 
         // Record audit event for query with ephemeral data
@@ -4178,6 +5753,107 @@ impl Handler {
         Ok(())
     }
 
+    /// Check that `auth` may run `stmt` against knowledge graph `kg`, with
+    /// the role checks `execute_program` applies to statement text
+    fn authorize_in(
+        &self,
+        kg: &str,
+        stmt: &statement::Statement,
+        auth: Option<&crate::auth::AuthIdentity>,
+    ) -> Result<(), String> {
+        check_audit_read_only(kg, stmt)?;
+        self.replication.check_writable(stmt)?;
+        let Some(identity) = auth else {
+            return Ok(());
+        };
+        let role = self
+            .refresh_user_role(identity)
+            .ok_or_else(|| "Access denied: user no longer exists".to_string())?;
+        crate::auth::authorize_statement(&role, stmt)?;
+        if role == crate::auth::Role::Admin {
+            return Ok(());
+        }
+        if kg == crate::auth::INTERNAL_KG || kg == crate::audit::AUDIT_KG {
+            return Err(format!("Access denied: '{kg}' is a system knowledge graph"));
+        }
+        let identity = crate::auth::AuthIdentity {
+            role,
+            ..identity.clone()
+        };
+        self.authorize_kg_statement(kg, &identity, stmt)
+    }
+
+    /// Insert facts into a relation, as `+relation(...)` does, for clients
+    /// that send rows as data rather than statement text (HTTP API)
+    pub fn insert_facts(
+        &self,
+        kg: &str,
+        relation: &str,
+        tuples: Vec<Tuple>,
+        auth: Option<&crate::auth::AuthIdentity>,
+    ) -> Result<crate::storage_engine::InsertReport, String> {
+        Self::validate_relation_name(relation)?;
+        let max_tuples = self.config.storage.performance.max_insert_tuples;
+        if max_tuples > 0 && tuples.len() > max_tuples {
+            return Err(format!(
+                "Too many tuples: {} (max {})",
+                tuples.len(),
+                max_tuples
+            ));
+        }
+        let stmt = statement::Statement::Insert(statement::InsertOp {
+            relation: relation.to_string(),
+            tuples: Vec::new(),
+        });
+        self.authorize_in(kg, &stmt, auth)?;
+
+        let report = self
+            .storage
+            .read()
+            .insert_tuples_checked(kg, relation, tuples)
+            .map_err(|e| e.to_string())?;
+        self.insert_count
+            .fetch_add(report.inserted as u64, Ordering::Relaxed);
+        if report.inserted > 0 {
+            self.notify_persistent_update(kg, relation, "insert", report.inserted);
+        }
+        Ok(report)
+    }
+
+    /// Delete facts from a relation, as `-relation(...)` does (HTTP API)
+    pub fn delete_facts(
+        &self,
+        kg: &str,
+        relation: &str,
+        tuples: Vec<Tuple>,
+        auth: Option<&crate::auth::AuthIdentity>,
+    ) -> Result<usize, String> {
+        Self::validate_relation_name(relation)?;
+        let max_tuples = self.config.storage.performance.max_insert_tuples;
+        if max_tuples > 0 && tuples.len() > max_tuples {
+            return Err(format!(
+                "Too many tuples: {} (max {})",
+                tuples.len(),
+                max_tuples
+            ));
+        }
+        let stmt = statement::Statement::Delete(statement::DeleteOp {
+            relation: relation.to_string(),
+            pattern: statement::DeletePattern::BulkTuples(Vec::new()),
+        });
+        self.authorize_in(kg, &stmt, auth)?;
+
+        let deleted = self
+            .storage
+            .read()
+            .delete_tuples_from(kg, relation, tuples)
+            .map_err(|e| e.to_string())?;
+        if deleted > 0 {
+            self.notify_persistent_update(kg, relation, "delete", deleted);
+        }
+        Ok(deleted)
+    }
+
     /// Insert ephemeral facts into a session.
     /// Returns the number of facts actually inserted (after dedup).
     pub fn session_insert_ephemeral(
@@ -4251,6 +5927,343 @@ impl Handler {
         knowledge_graph: Option<String>,
         program: String,
         auth: Option<&crate::auth::AuthIdentity>,
+    ) -> Result<QueryResult, String> {
+        self.execute_program_in(session_id, knowledge_graph, program, auth, None)
+            .await
+    }
+
+    /// Execute statements in one round trip, atomically.
+    ///
+    /// Writes and view definitions are queued in a transaction of the
+    /// batch's own (never the session's) and committed together after the
+    /// last of them; a statement that fails rolls the whole batch back. A
+    /// query may only come last, and sees the committed writes. Every
+    /// statement is authorized as if it had been sent on its own.
+    pub async fn execute_batch(
+        &self,
+        session_id: Option<&SessionId>,
+        knowledge_graph: Option<String>,
+        statements: Vec<String>,
+        auth: Option<&crate::auth::AuthIdentity>,
+    ) -> Result<BatchResult, String> {
+        let start = Instant::now();
+        if statements.is_empty() {
+            return Err("Batch has no statements".to_string());
+        }
+        if statements.len() > MAX_BATCH_STATEMENTS {
+            return Err(format!(
+                "Batch too large: {} statements (max {MAX_BATCH_STATEMENTS})",
+                statements.len()
+            ));
+        }
+        let prepared = match session_id {
+            Some(sid) => self.sessions.prepared_slot(sid)?,
+            None => PreparedSlot::default(),
+        };
+        let last = statements.len() - 1;
+        let mut ends_with_query = false;
+        for (index, text) in statements.iter().enumerate() {
+            let is_query = batch_statement_is_query(text, &prepared)
+                .map_err(|e| format!("Statement {}: {e}", index + 1))?;
+            if is_query && index != last {
+                return Err(format!(
+                    "Statement {}: a query can only be the last statement of a batch",
+                    index + 1
+                ));
+            }
+            ends_with_query = is_query;
+        }
+
+        let mut statements = statements;
+        let query = ends_with_query.then(|| statements.remove(last));
+        let mut results = Vec::with_capacity(statements.len() + 1);
+        let mut commit = None;
+        if !statements.is_empty() {
+            let transaction = TransactionSlot::default();
+            let run = |program: String| {
+                self.execute_program_in(
+                    session_id,
+                    knowledge_graph.clone(),
+                    program,
+                    auth,
+                    Some(Arc::clone(&transaction)),
+                )
+            };
+            run("begin".to_string()).await?;
+            for (index, text) in statements.into_iter().enumerate() {
+                match run(text).await {
+                    Ok(result) => results.push(result),
+                    Err(e) => {
+                        // A failed job has already rolled the transaction back
+                        let discarded = transaction.lock().take().map(Transaction::rollback);
+                        return Err(match discarded {
+                            _ if e.starts_with(VALIDATION_ERROR_PREFIX) => e,
+                            Some(n) => format!(
+                                "Statement {}: {e} (batch rolled back, {n} queued change(s) discarded)",
+                                index + 1
+                            ),
+                            None => format!("Statement {}: {e}", index + 1),
+                        });
+                    }
+                }
+            }
+            let committed = run("commit".to_string())
+                .await
+                .map_err(|e| format!("Batch not committed: {e}"))?;
+            commit = committed
+                .rows
+                .first()
+                .and_then(|row| row.values.first())
+                .and_then(WireValue::as_str)
+                .map(str::to_string);
+        }
+        if let Some(query) = query {
+            results.push(
+                self.execute_program(session_id, knowledge_graph, query, auth)
+                    .await?,
+            );
+        }
+        Ok(BatchResult {
+            results,
+            commit,
+            execution_time_ms: start.elapsed().as_millis() as u64,
+        })
+    }
+
+    /// [`Self::execute_program`], queuing writes in `transaction` instead of
+    /// the session's transaction when given.
+    ///
+    /// Runs in a `query` span with a process-unique `query_id`, the parent
+    /// of the engine and persist spans the program produces.
+    async fn execute_program_in(
+        &self,
+        session_id: Option<&SessionId>,
+        knowledge_graph: Option<String>,
+        program: String,
+        auth: Option<&crate::auth::AuthIdentity>,
+        transaction: Option<TransactionSlot>,
+    ) -> Result<QueryResult, String> {
+        let span = tracing::info_span!(
+            "query",
+            query_id = NEXT_QUERY_ID.fetch_add(1, Ordering::Relaxed),
+            knowledge_graph = knowledge_graph.as_deref().unwrap_or(""),
+            program_len = program.len(),
+            rows = tracing::field::Empty,
+        );
+        // The knowledge graph is resolved before running, as the program
+        // may switch the session to another
+        let audited = self.audit_enabled().then(|| {
+            let kg = knowledge_graph
+                .clone()
+                .or_else(|| session_id.and_then(|sid| self.sessions.session_kg(sid).ok()))
+                .unwrap_or_default();
+            (kg, program.clone(), Instant::now())
+        });
+        let result = self
+            .run_program_in(session_id, knowledge_graph, program, auth, transaction)
+            .instrument(span.clone())
+            .await;
+        if let Ok(result) = &result {
+            span.record("rows", result.total_count);
+        }
+        if let Some((kg, program, start)) = audited {
+            self.audit_program(
+                auth.map(|identity| identity.username.as_str()),
+                session_id.map(String::as_str),
+                kg,
+                &program,
+                start,
+                &result,
+            );
+        }
+        result
+    }
+
+    /// Leader-follower replication state
+    pub fn replication(&self) -> &Arc<Replication> {
+        &self.replication
+    }
+
+    /// Raft cluster membership, when `[cluster]` is configured
+    pub fn cluster(&self) -> Option<&Arc<Cluster>> {
+        self.cluster.as_ref()
+    }
+
+    /// Router of sharded relations, when `[sharding]` is configured
+    pub fn shards(&self) -> Option<&Arc<ShardRouter>> {
+        self.shards.as_ref()
+    }
+
+    /// Apply a record of the leader's replication log. A statement runs
+    /// as a program against the record's knowledge graph; other changes
+    /// go to storage as recorded. Subscribers are notified as they are of
+    /// local changes.
+    pub async fn apply_replication_record(
+        &self,
+        record: crate::storage_engine::ReplicationRecord,
+    ) -> Result<(), String> {
+        use crate::storage_engine::ReplicationOp;
+
+        let crate::storage_engine::ReplicationRecord { kg, op, .. } = record;
+        if let ReplicationOp::Statement { text } = op {
+            let mut job = self.make_query_job();
+            job.applying = true;
+            return tokio::task::spawn_blocking(move || job.execute(Some(kg), text).map(|_| ()))
+                .await
+                .map_err(|e| format!("Replicated statement panicked: {e}"))?;
+        }
+
+        // What subscribers are told once the change is applied
+        let mut updates = Vec::new();
+        let mut rule_changes = Vec::new();
+        let mut kg_changes = Vec::new();
+        match &op {
+            ReplicationOp::Write { writes, rules } => {
+                updates = writes
+                    .iter()
+                    .map(|w| {
+                        let operation = if w.delete { "delete" } else { "insert" };
+                        (w.relation.clone(), operation, w.tuples.len())
+                    })
+                    .collect();
+                rule_changes = rules
+                    .iter()
+                    .map(|rule| match rule {
+                        RuleChange::Register(def) => (def.name.clone(), "registered"),
+                        RuleChange::Drop(name) => (name.clone(), "dropped"),
+                    })
+                    .collect();
+            }
+            ReplicationOp::CreateKnowledgeGraph | ReplicationOp::CopyKnowledgeGraph { .. } => {
+                kg_changes.push((kg.clone(), "created"));
+            }
+            ReplicationOp::DropKnowledgeGraph { .. } => kg_changes.push((kg.clone(), "dropped")),
+            ReplicationOp::RenameKnowledgeGraph { source } => {
+                kg_changes.push((source.clone(), "dropped"));
+                kg_changes.push((kg.clone(), "created"));
+            }
+            ReplicationOp::Schema { .. } | ReplicationOp::Statement { .. } => {}
+        }
+
+        let storage = Arc::clone(&self.storage);
+        let target = kg.clone();
+        tokio::task::spawn_blocking(move || storage.read().apply_replicated(&target, op))
+            .await
+            .map_err(|e| format!("Replication task panicked: {e}"))?
+            .map_err(|e| e.to_string())?;
+
+        for (relation, operation, count) in updates {
+            if operation == "insert" {
+                self.insert_count.fetch_add(count as u64, Ordering::Relaxed);
+            }
+            self.notify_persistent_update(&kg, &relation, operation, count);
+        }
+        for (rule, operation) in rule_changes {
+            self.notify_rule_change(&kg, &rule, operation);
+        }
+        for (name, operation) in kg_changes {
+            self.notify_kg_change(&name, operation);
+        }
+        Ok(())
+    }
+
+    /// Replace a knowledge graph with an image from the leader
+    pub async fn install_replication_image(
+        &self,
+        image: crate::storage_engine::KnowledgeGraphImage,
+    ) -> Result<(), String> {
+        let name = image.name.clone();
+        let storage = Arc::clone(&self.storage);
+        tokio::task::spawn_blocking(move || storage.read().install_image(image))
+            .await
+            .map_err(|e| format!("Replication task panicked: {e}"))?
+            .map_err(|e| format!("Cannot install image of '{name}': {e}"))?;
+        self.notify_kg_change(&name, "restored");
+        Ok(())
+    }
+
+    /// Drop the replicated knowledge graphs not in `keep`, the ones the
+    /// leader has, after catching up from images
+    pub async fn retain_replicated_knowledge_graphs(&self, keep: &[String]) {
+        let stale: Vec<String> = self
+            .storage
+            .read()
+            .list_knowledge_graphs()
+            .into_iter()
+            .filter(|kg| super::replication::replicated_kg(kg) && !keep.contains(kg))
+            .collect();
+        for kg in stale {
+            let storage = Arc::clone(&self.storage);
+            let name = kg.clone();
+            let dropped = tokio::task::spawn_blocking(move || {
+                let storage = storage.read();
+                let cleanup = storage.prepare_drop_knowledge_graph_cascade(&name)?;
+                storage.finish_drop_knowledge_graph(cleanup);
+                Ok::<_, crate::storage::StorageError>(())
+            })
+            .await;
+            match dropped {
+                Ok(Ok(())) => {
+                    info!(kg = %kg, "replication_kg_dropped");
+                    self.notify_kg_change(&kg, "dropped");
+                }
+                Ok(Err(e)) => warn!(kg = %kg, error = %e, "replication_kg_drop_failed"),
+                Err(e) => warn!(kg = %kg, error = %e, "replication_kg_drop_failed"),
+            }
+        }
+    }
+
+    /// Whether programs are recorded in the audit log
+    pub fn audit_enabled(&self) -> bool {
+        self.audit.is_some()
+    }
+
+    /// Record a program that began at `start` in the audit log, if enabled
+    pub(crate) fn audit_program(
+        &self,
+        user: Option<&str>,
+        session: Option<&str>,
+        knowledge_graph: String,
+        program: &str,
+        start: Instant,
+        result: &Result<QueryResult, String>,
+    ) {
+        let Some(audit) = &self.audit else {
+            return;
+        };
+        let elapsed = start.elapsed();
+        let record = crate::audit::AuditRecord {
+            time: now_ms().saturating_sub(elapsed.as_millis() as u64) as i64,
+            user: user.map(str::to_string),
+            session: session.map(str::to_string),
+            knowledge_graph,
+            statement: crate::audit::redact(program),
+            rows: result.as_ref().map_or(0, |r| r.total_count),
+            duration_ms: elapsed.as_millis() as u64,
+            outcome: if result.is_ok() { "ok" } else { "error" },
+            error: result.as_ref().err().cloned(),
+        };
+        if let Err(e) = audit.append(&record) {
+            tracing::error!(error = %e, "audit_append_failed");
+        }
+        if self.config.audit.relation {
+            if let Err(e) = self.storage.read().insert_tuples_into(
+                crate::audit::AUDIT_KG,
+                crate::audit::AUDIT_RELATION,
+                vec![record.to_tuple()],
+            ) {
+                tracing::error!(error = %e, "audit_relation_insert_failed");
+            }
+        }
+    }
+
+    async fn run_program_in(
+        &self,
+        session_id: Option<&SessionId>,
+        knowledge_graph: Option<String>,
+        program: String,
+        auth: Option<&crate::auth::AuthIdentity>,
+        transaction: Option<TransactionSlot>,
     ) -> Result<QueryResult, String> {
         // Input size validation (protects parsing and downstream handlers)
         let max_bytes = self.config.storage.performance.max_query_size_bytes;
@@ -4262,7 +6275,32 @@ impl Handler {
             ));
         }
 
+        // `execute` on its own runs as its bound statement, so that statement
+        // is authorized and routed as if the client had sent it
+        let program = match statement::parse_statement(program.trim()) {
+            Ok(statement::Statement::Execute(exec)) => {
+                let prepared = match session_id {
+                    Some(sid) => self.sessions.prepared_slot(sid)?,
+                    None => PreparedSlot::default(),
+                };
+                bind_prepared(&prepared, &exec)?
+            }
+            _ => program,
+        };
         let trimmed = program.trim();
+        // A program of several statements is checked statement by statement,
+        // split into lines as it is executed. Lines that do not parse fail
+        // validation before anything runs.
+        let statements: Vec<statement::Statement> = match statement::parse_statement(trimmed) {
+            Ok(stmt) => vec![stmt],
+            Err(_) => join_continuation_lines(&strip_comments(trimmed))
+                .lines()
+                .filter_map(|line| statement::parse_statement(line.trim()).ok())
+                .collect(),
+        };
+        for stmt in &statements {
+            self.replication.check_writable(stmt)?;
+        }
 
         // Refresh the user's global role from storage on every call.
         // The AuthIdentity passed in was captured at login time and may be stale
@@ -4272,6 +6310,7 @@ impl Handler {
                 Some(role) => Some(crate::auth::AuthIdentity {
                     username: identity.username.clone(),
                     role,
+                    api_key: identity.api_key.clone(),
                 }),
                 None => {
                     // User was dropped while session was active
@@ -4285,7 +6324,7 @@ impl Handler {
 
         // Authorization check: if auth is provided, validate the statement
         if let Some(identity) = effective_auth {
-            if let Ok(ref stmt) = statement::parse_statement(trimmed) {
+            for stmt in &statements {
                 crate::auth::authorize_statement(&identity.role, stmt)?;
             }
         }
@@ -4300,39 +6339,72 @@ impl Handler {
         let current_kg = knowledge_graph.as_deref().or(session_kg_owned.as_deref());
 
         if let Some(identity) = effective_auth {
-            if identity.role != crate::auth::Role::Admin
-                && current_kg == Some(crate::auth::INTERNAL_KG)
-            {
-                return Err(format!(
-                    "Access denied: '{}' is a system knowledge graph",
-                    crate::auth::INTERNAL_KG
-                ));
+            if identity.role != crate::auth::Role::Admin {
+                if let Some(kg) = current_kg
+                    .filter(|kg| *kg == crate::auth::INTERNAL_KG || *kg == crate::audit::AUDIT_KG)
+                {
+                    return Err(format!("Access denied: '{kg}' is a system knowledge graph"));
+                }
             }
         }
-        if let Ok(ref stmt) = statement::parse_statement(trimmed) {
+        for stmt in &statements {
             match stmt {
                 statement::Statement::Meta(
                     statement::MetaCommand::KgUse(name)
                     | statement::MetaCommand::KgDrop(name)
-                    | statement::MetaCommand::KgCreate(name),
+                    | statement::MetaCommand::KgDropCascade(name)
+                    | statement::MetaCommand::KgCreate(name)
+                    | statement::MetaCommand::KgCopy { source: name, .. }
+                    | statement::MetaCommand::KgCopy { target: name, .. }
+                    | statement::MetaCommand::KgRename { source: name, .. }
+                    | statement::MetaCommand::KgRename { target: name, .. },
                 ) if name == crate::auth::INTERNAL_KG => {
                     return Err(format!(
                         "Access denied: '{}' is a system knowledge graph",
                         crate::auth::INTERNAL_KG
                     ));
                 }
-                _ => {}
+                // Admins may query the audit trail, but not replace or remove it
+                statement::Statement::Meta(
+                    statement::MetaCommand::KgDrop(name)
+                    | statement::MetaCommand::KgDropCascade(name)
+                    | statement::MetaCommand::KgCreate(name)
+                    | statement::MetaCommand::KgCopy { target: name, .. }
+                    | statement::MetaCommand::KgRename { source: name, .. }
+                    | statement::MetaCommand::KgRename { target: name, .. }
+                    | statement::MetaCommand::KgAclGrant { kg_name: name, .. },
+                ) if name == crate::audit::AUDIT_KG => {
+                    return Err(AUDIT_READ_ONLY.to_string());
+                }
+                statement::Statement::Meta(statement::MetaCommand::KgUse(name))
+                    if name == crate::audit::AUDIT_KG
+                        && effective_auth
+                            .is_some_and(|identity| identity.role != crate::auth::Role::Admin) =>
+                {
+                    return Err(format!(
+                        "Access denied: '{}' is a system knowledge graph",
+                        crate::audit::AUDIT_KG
+                    ));
+                }
+                stmt => {
+                    if let Some(kg) = current_kg {
+                        check_audit_read_only(kg, stmt)?;
+                    }
+                }
             }
         }
 
         // Per-KG authorization: check if user has access to the target KG.
         if let Some(identity) = effective_auth {
             if identity.role != crate::auth::Role::Admin {
-                if let Ok(ref stmt) = statement::parse_statement(trimmed) {
+                for stmt in &statements {
                     // Determine which KG the operation targets
                     let target_kg = match stmt {
                         statement::Statement::Meta(
                             statement::MetaCommand::KgDrop(name)
+                            | statement::MetaCommand::KgDropCascade(name)
+                            | statement::MetaCommand::KgCopy { source: name, .. }
+                            | statement::MetaCommand::KgRename { source: name, .. }
                             | statement::MetaCommand::KgUse(name),
                         ) => Some(name.as_str()),
                         statement::Statement::Meta(
@@ -4342,6 +6414,13 @@ impl Handler {
                         statement::Statement::Meta(statement::MetaCommand::KgAclList(
                             ref kg_opt,
                         )) => kg_opt.as_deref(),
+                        statement::Statement::Grant(grant)
+                        | statement::Statement::Revoke(grant) => match &grant.object {
+                            statement::GrantObject::Database(name) => Some(name.as_str()),
+                            _ => Some(
+                                current_kg.unwrap_or(&self.config.storage.default_knowledge_graph),
+                            ),
+                        },
                         // KG create doesn't target an existing KG; list/show/help are global
                         statement::Statement::Meta(
                             statement::MetaCommand::KgCreate(_)
@@ -4349,20 +6428,20 @@ impl Handler {
                             | statement::MetaCommand::KgShow
                             | statement::MetaCommand::Help
                             | statement::MetaCommand::Quit
-                            | statement::MetaCommand::Status,
+                            | statement::MetaCommand::Status
+                            | statement::MetaCommand::LogShow
+                            | statement::MetaCommand::LogLevel(_)
+                            | statement::MetaCommand::ReplicationStatus
+                            | statement::MetaCommand::ReplicationPromote
+                            | statement::MetaCommand::ClusterStatus
+                            | statement::MetaCommand::ShardStatus,
                         ) => None,
                         // All other statements operate on the current KG
                         _ => current_kg,
                     };
 
                     if let Some(kg) = target_kg {
-                        if let Some(kg_role) =
-                            self.get_kg_role_for_user(kg, &identity.username, &identity.role)
-                        {
-                            crate::auth::authorize_kg_operation(&kg_role, stmt)?;
-                        } else {
-                            return Err("Access denied".to_string());
-                        }
+                        self.authorize_kg_statement(kg, identity, stmt)?;
                     }
                 }
             }
@@ -4409,6 +6488,44 @@ impl Handler {
                         let sid = session_id.ok_or_else(|| "No active session".to_string())?;
                         return self.handle_session_drop_name(sid, name);
                     }
+                    MetaCommand::SessionSettings => {
+                        let sid = session_id.ok_or_else(|| "No active session".to_string())?;
+                        return self.handle_session_settings(sid, effective_auth);
+                    }
+                    MetaCommand::SessionSet { name, value } => {
+                        let sid = session_id.ok_or_else(|| "No active session".to_string())?;
+                        self.sessions.with_session_mut(sid, |session| {
+                            session.settings.set(name, *value)
+                        })??;
+                        return Ok(self
+                            .message_result(&format!("Session setting '{name}' set to {value}.")));
+                    }
+                    MetaCommand::SessionSetPriority(priority) => {
+                        let sid = session_id.ok_or_else(|| "No active session".to_string())?;
+                        self.sessions.with_session_mut(sid, |session| {
+                            session.settings.priority = Some(*priority);
+                        })?;
+                        let applied = self.query_priority(Some(*priority), effective_auth);
+                        let msg = if applied == *priority {
+                            format!("Session priority set to {priority}.")
+                        } else {
+                            format!(
+                                "Session priority set to {priority} (limited to {applied} by the API key)."
+                            )
+                        };
+                        return Ok(self.message_result(&msg));
+                    }
+                    MetaCommand::SessionReset(name) => {
+                        let sid = session_id.ok_or_else(|| "No active session".to_string())?;
+                        self.sessions.with_session_mut(sid, |session| {
+                            session.settings.reset(name.as_deref())
+                        })??;
+                        let msg = match name {
+                            Some(name) => format!("Session setting '{name}' reset."),
+                            None => "Session settings reset.".to_string(),
+                        };
+                        return Ok(self.message_result(&msg));
+                    }
 
                     // User & API key management (handled directly, not via query_program)
                     MetaCommand::UserList => {
@@ -4475,6 +6592,22 @@ impl Handler {
             }
         }
 
+        // Grants are kept in the _internal KG, not in the target KG
+        let grant_kg = current_kg.unwrap_or(&self.config.storage.default_knowledge_graph);
+        match statement::parse_statement(trimmed) {
+            // Answered without a slot, so an overloaded server can still say why
+            Ok(statement::Statement::Show(statement::ShowTarget::Queries)) => {
+                return Ok(introspection_result(self.admission.show_queries(), 0));
+            }
+            Ok(statement::Statement::Grant(grant)) => {
+                return Ok(self.message_result(&self.handle_grant(grant_kg, &grant)?));
+            }
+            Ok(statement::Statement::Revoke(revoke)) => {
+                return Ok(self.message_result(&self.handle_revoke(grant_kg, &revoke)?));
+            }
+            _ => {}
+        }
+
         // Intercept session rules and facts when session_id is present.
         // In the WS protocol each statement is a separate request, so we must
         // persist them in the SessionManager (not in a request-local vector).
@@ -4544,6 +6677,20 @@ impl Handler {
             None
         };
 
+        // Statements over sharded relations run on the shards
+        if let Some(router) = &self.shards {
+            if let Ok(ref stmt) = statement::parse_statement(trimmed) {
+                let kg = effective_kg
+                    .clone()
+                    .unwrap_or_else(|| self.config.storage.default_knowledge_graph.clone());
+                match router.execute(self, &kg, stmt, trimmed).await? {
+                    Routed::Local => {}
+                    Routed::Message(message) => return Ok(self.message_result(&message)),
+                    Routed::Rows(result) => return Ok(result),
+                }
+            }
+        }
+
         // Only queries need session-aware execution (to prepend ephemeral rules).
         // All other statements (meta commands, inserts, deletes, persistent rules)
         // must go through query_program() directly because query_program_with_session()
@@ -4553,29 +6700,78 @@ impl Handler {
         // in the SessionManager, so they never reach this point.
         let is_query = trimmed.starts_with('?');
 
-        // Detect KG create/drop before program is moved into query_program.
+        // Detect KG create/drop/copy/rename before program is moved into query_program.
         // Extracting these from the parsed statement avoids fragile string matching
         // on the result messages.
-        let (kg_create_name, kg_drop_name) = match statement::parse_statement(trimmed) {
-            Ok(statement::Statement::Meta(statement::MetaCommand::KgCreate(name))) => {
-                (Some(name), None)
-            }
-            Ok(statement::Statement::Meta(statement::MetaCommand::KgDrop(name))) => {
-                (None, Some(name))
-            }
-            _ => (None, None),
-        };
+        let drop_kg = matches!(
+            statement::parse_statement(trimmed),
+            Ok(statement::Statement::DeleteRelationOrRule(_)
+                | statement::Statement::Meta(
+                    statement::MetaCommand::RelDrop(_)
+                        | statement::MetaCommand::RuleDrop(_)
+                        | statement::MetaCommand::RuleDropPrefix(_)
+                ))
+        )
+        .then(|| {
+            effective_kg
+                .clone()
+                .unwrap_or_else(|| self.config.storage.default_knowledge_graph.clone())
+        });
+        let (kg_create_name, kg_drop_name, kg_copy, kg_rename) =
+            match statement::parse_statement(trimmed) {
+                Ok(statement::Statement::Meta(statement::MetaCommand::KgCreate(name))) => {
+                    (Some(name), None, None, None)
+                }
+                Ok(statement::Statement::Meta(
+                    statement::MetaCommand::KgDrop(name)
+                    | statement::MetaCommand::KgDropCascade(name),
+                )) => (None, Some(name), None, None),
+                Ok(statement::Statement::Meta(statement::MetaCommand::KgCopy {
+                    source,
+                    target,
+                })) => (None, None, Some((source, target)), None),
+                Ok(statement::Statement::Meta(statement::MetaCommand::KgRename {
+                    source,
+                    target,
+                })) => (None, None, None, Some((source, target))),
+                _ => (None, None, None, None),
+            };
 
-        let result = if is_query {
+        let mut settings = session_id
+            .and_then(|sid| self.sessions.settings(sid).ok())
+            .unwrap_or_default();
+        let priority = self.query_priority(settings.priority, effective_auth);
+        settings.priority = Some(priority);
+        let mut result = if is_query {
             if let Some(sid) = session_id {
-                self.query_program_with_session(sid, program).await?
+                self.query_session_program(sid, program, priority).await?
             } else {
-                self.query_program(effective_kg, program).await?
+                self.query_program_in(effective_kg, program, None, None, settings)
+                    .await?
             }
         } else {
-            self.query_program(effective_kg, program).await?
+            // Writes are queued in the session's transaction once `begin` ran
+            let transaction = match transaction {
+                Some(slot) => Some(slot),
+                None => session_id
+                    .map(|sid| self.sessions.transaction_slot(sid))
+                    .transpose()?,
+            };
+            let prepared = session_id
+                .map(|sid| self.sessions.prepared_slot(sid))
+                .transpose()?;
+            self.query_program_in(effective_kg, program, transaction, prepared, settings)
+                .await?
         };
 
+        // The server's row limit applied in the engine; the session's may
+        // be tighter
+        let max_rows = settings.max_rows(0);
+        if max_rows > 0 && result.rows.len() > max_rows {
+            result.rows.truncate(max_rows);
+            result.truncated = true;
+        }
+
         // If KG was switched, update session binding
         if let (Some(ref new_kg), Some(sid)) = (&result.switched_kg, session_id) {
             self.sessions.switch_kg(sid, new_kg)?;
@@ -4608,6 +6804,39 @@ impl Handler {
             }
         }
 
+        // Grants on a dropped relation or view go with it, so they do not
+        // apply to a new object created later under the same name
+        if let Some(ref kg) = drop_kg {
+            self.cleanup_object_grants(kg);
+        }
+
+        // The copier of a KG owns the copy; ACLs of the source are not copied.
+        if let (Some(identity), Some((_, ref target))) = (effective_auth, &kg_copy) {
+            let copy_succeeded = result.rows.iter().any(|row| {
+                matches!(
+                    row.values.first(),
+                    Some(WireValue::String(s)) if s.contains("copied to")
+                )
+            });
+            if copy_succeeded && identity.role != crate::auth::Role::Admin {
+                let _ = self.handle_kg_acl_grant(target, &identity.username, "owner");
+            }
+        }
+
+        // A renamed KG keeps its ACLs; sessions bound to the old name are closed.
+        if let Some((ref source, ref target)) = kg_rename {
+            let rename_succeeded = result.rows.iter().any(|row| {
+                matches!(
+                    row.values.first(),
+                    Some(WireValue::String(s)) if s.contains("renamed to")
+                )
+            });
+            if rename_succeeded {
+                self.sessions.close_sessions_for_kg(source);
+                self.rename_kg_acls(source, target);
+            }
+        }
+
         // Convert error-like messages to Err for the WS protocol.
         // query_program() accumulates errors as Ok(message) for multi-statement compat,
         // but the WS protocol sends one statement at a time, so errors should abort.
@@ -4686,11 +6915,70 @@ impl Handler {
         let total_count = rows.len();
         Ok(QueryResult {
             rows,
-            schema: vec![ColumnDef {
-                name: "message".to_string(),
-                data_type: WireDataType::String,
-            }],
-            total_count,
+            schema: vec![ColumnDef {
+                name: "message".to_string(),
+                data_type: WireDataType::String,
+            }],
+            total_count,
+            truncated: false,
+            execution_time_ms: 0,
+            metadata: None,
+            switched_kg: None,
+            proof_trees: None,
+            timing_breakdown: None,
+        })
+    }
+
+    /// Handle `.session settings`: each limit as set by the session and as
+    /// applied
+    fn handle_session_settings(
+        &self,
+        session_id: &SessionId,
+        auth: Option<&crate::auth::AuthIdentity>,
+    ) -> Result<QueryResult, String> {
+        let settings = self.sessions.settings(session_id)?;
+        let perf = &self.config.storage.performance;
+        let limit = |value: u64| {
+            if value == 0 {
+                "none".to_string()
+            } else {
+                value.to_string()
+            }
+        };
+        let rows = [
+            (
+                "timeout_ms",
+                settings.query_timeout_ms.map(|ms| ms.to_string()),
+                limit(settings.timeout_ms(perf.query_timeout_ms)),
+            ),
+            (
+                "max_rows",
+                settings.max_result_rows.map(|rows| rows.to_string()),
+                limit(settings.max_rows(perf.max_result_rows) as u64),
+            ),
+            (
+                "priority",
+                settings.priority.map(|priority| priority.to_string()),
+                self.query_priority(settings.priority, auth).to_string(),
+            ),
+        ]
+        .into_iter()
+        .map(|(name, set, applied)| {
+            WireTuple::new(vec![
+                WireValue::String(name.to_string()),
+                set.map_or(WireValue::Null, WireValue::String),
+                WireValue::String(applied),
+            ])
+        })
+        .collect::<Vec<_>>();
+        Ok(QueryResult {
+            total_count: rows.len(),
+            rows,
+            schema: vec![
+                ColumnDef::string("setting"),
+                ColumnDef::string("session"),
+                ColumnDef::string("applied"),
+            ],
             truncated: false,
             execution_time_ms: 0,
             metadata: None,
@@ -4870,6 +7158,91 @@ pub(crate) fn transform_query_shorthand(program_text: &str) -> Result<QueryTrans
     }
 }
 
+/// Number of statements in a program, as the executor splits it
+pub(crate) fn statement_count(program: &str) -> usize {
+    join_continuation_lines(&strip_comments(program))
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .count()
+}
+
+/// Whether running `program` again cannot change anything: every statement
+/// parses and is a query, a session rule or introspection. Session rules
+/// die with the connection's session, so a retry on a fresh one is safe.
+pub(crate) fn is_read_only_program(program: &str) -> bool {
+    let joined = join_continuation_lines(&strip_comments(program));
+    let mut lines = joined.lines().filter(|line| !line.trim().is_empty());
+    lines.clone().next().is_some()
+        && lines.all(|line| {
+            matches!(
+                statement::parse_statement(line.trim()),
+                Ok(statement::Statement::Query(_)
+                    | statement::Statement::SessionRule(_)
+                    | statement::Statement::Show(_)
+                    | statement::Statement::Describe(_))
+            )
+        })
+}
+
+/// Check that a statement can be part of a batch, returning whether it is
+/// a query. Only writes and view definitions, which a transaction queues,
+/// and a final query are allowed.
+fn batch_statement_is_query(text: &str, prepared: &PreparedSlot) -> Result<bool, String> {
+    let stmt = match statement::parse_statement(text.trim())? {
+        statement::Statement::Execute(exec) => {
+            statement::parse_statement(&bind_prepared(prepared, &exec)?)?
+        }
+        stmt => stmt,
+    };
+    match stmt {
+        statement::Statement::Query(_) => Ok(true),
+        statement::Statement::Insert(_)
+        | statement::Statement::Delete(_)
+        | statement::Statement::Update(_)
+        | statement::Statement::PersistentRule(_)
+        | statement::Statement::DeleteRelationOrRule(_) => Ok(false),
+        statement::Statement::Transaction(_) => {
+            Err("a batch is its own transaction; leave out begin, commit and rollback".to_string())
+        }
+        _ => Err(
+            "only inserts, deletes, updates, view definitions and a final query can be batched"
+                .to_string(),
+        ),
+    }
+}
+
+/// Text of the prepared statement `exec` names, with its arguments bound
+fn bind_prepared(
+    prepared: &PreparedSlot,
+    exec: &statement::ExecuteStatement,
+) -> Result<String, String> {
+    let prepared = prepared.lock();
+    let stmt = prepared
+        .get(&exec.name)
+        .ok_or_else(|| format!("Prepared statement '{}' not found", exec.name))?;
+    stmt.bind(&exec.args)
+}
+
+/// Keep a statement from `prepare`, replacing one of the same name
+fn store_prepared(
+    prepared: &PreparedSlot,
+    stmt: statement::PreparedStatement,
+) -> Result<String, String> {
+    let mut prepared = prepared.lock();
+    let max = statement::prepared::MAX_PREPARED_STATEMENTS;
+    if prepared.len() >= max && !prepared.contains_key(&stmt.name) {
+        return Err(format!(
+            "Too many prepared statements (max {max}). Deallocate some first."
+        ));
+    }
+    let message = format!(
+        "Prepared statement '{}' with {} parameter(s).",
+        stmt.name, stmt.params
+    );
+    prepared.insert(stmt.name.clone(), stmt);
+    Ok(message)
+}
+
 /// Strip comment lines from program text
 fn strip_comments(program: &str) -> String {
     program
@@ -4917,6 +7290,95 @@ fn join_continuation_lines(program: &str) -> String {
     result.join("\n")
 }
 
+/// Describe the statistics collected by `analyze` for one relation
+fn format_relation_stats(
+    stats: &crate::statistics::RelationStats,
+    schema: Option<&crate::schema::RelationSchema>,
+) -> Vec<String> {
+    let mut lines = vec![format!(
+        "Analyzed '{}': {} rows",
+        stats.name, stats.cardinality
+    )];
+    for col in &stats.column_stats {
+        let name = schema
+            .and_then(|s| s.columns.get(col.index))
+            .map_or_else(|| format!("col{}", col.index), |c| c.name.clone());
+        let range = match (&col.min_value, &col.max_value) {
+            (Some(min), Some(max)) => format!(", min {min}, max {max}"),
+            _ => String::new(),
+        };
+        let buckets = col.histogram.as_ref().map_or(0, |h| h.counts.len());
+        lines.push(format!(
+            "  {name}: {} distinct, {:.1}% null{range}, {buckets} histogram buckets",
+            col.distinct_count,
+            col.null_fraction(stats.cardinality) * 100.0
+        ));
+    }
+    lines
+}
+
+/// Validate the type options of `.index create` and build the index type
+fn index_type_from_options(opts: &IndexCreateOptions) -> Result<IndexType, String> {
+    match opts.index_type.as_str() {
+        "hnsw" => {}
+        "lsh" => {
+            let defaults = LshConfig::default();
+            let tables = opts.tables.unwrap_or(defaults.tables);
+            let hyperplanes = opts.hyperplanes.unwrap_or(defaults.hyperplanes);
+            if !(1..=64).contains(&tables) {
+                return Err(format!("LSH parameter tables must be 1..=64, got {tables}"));
+            }
+            if !(1..=62).contains(&hyperplanes) {
+                return Err(format!(
+                    "LSH parameter hyperplanes must be 1..=62, got {hyperplanes}"
+                ));
+            }
+            return Ok(IndexType::Lsh(LshConfig {
+                tables,
+                hyperplanes,
+            }));
+        }
+        other => {
+            return Err(format!(
+                "Unsupported index type '{other}'. Supported types: hnsw, lsh."
+            ));
+        }
+    }
+
+    // Parse distance metric
+    let metric = opts
+        .metric
+        .as_deref()
+        .unwrap_or("cosine")
+        .parse::<DistanceMetric>()
+        .map_err(|e| format!("Invalid metric: {e}"))?;
+
+    let m = opts.m.unwrap_or(16);
+    let ef_construction = opts.ef_construction.unwrap_or(200);
+    let ef_search = opts.ef_search.unwrap_or(50);
+
+    // Validate HNSW parameters to prevent crashes
+    if m < 2 {
+        return Err(format!("HNSW parameter m must be >= 2, got {m}"));
+    }
+    if m > 256 {
+        return Err(format!("HNSW parameter m must be <= 256, got {m}"));
+    }
+    if ef_construction < 1 {
+        return Err("HNSW parameter ef_construction must be >= 1".to_string());
+    }
+    if ef_search < 1 {
+        return Err("HNSW parameter ef_search must be >= 1".to_string());
+    }
+
+    Ok(IndexType::Hnsw(HnswConfig {
+        m,
+        ef_construction,
+        ef_search,
+        metric,
+    }))
+}
+
 /// Format a rule as IQL text (uses Rule's Display impl)
 fn format_rule_text(rule: &crate::ast::Rule) -> String {
     rule.to_string()
@@ -5116,6 +7578,83 @@ mod tests {
         assert_eq!(result, Value::vector(vec![1.0, 2.0, 3.0]));
     }
 
+    #[test]
+    fn test_term_to_value_date_and_duration() {
+        let date = Term::FunctionCall(
+            BuiltinFunc::Date,
+            vec![Term::StringConstant("1970-01-02".to_string())],
+        );
+        assert_eq!(term_to_value(&date).unwrap(), Value::Date(1));
+        let dur = Term::FunctionCall(
+            BuiltinFunc::Duration,
+            vec![Term::StringConstant("2s".to_string())],
+        );
+        assert_eq!(term_to_value(&dur).unwrap(), Value::Duration(2_000));
+        let bad = Term::FunctionCall(
+            BuiltinFunc::Date,
+            vec![Term::StringConstant("1970-13-01".to_string())],
+        );
+        assert!(term_to_value(&bad).unwrap_err().contains("Invalid date"));
+    }
+
+    #[test]
+    fn test_term_to_value_uuid() {
+        let text = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+        let lit = Term::FunctionCall(BuiltinFunc::Uuid, vec![Term::StringConstant(text.into())]);
+        assert_eq!(term_to_value(&lit).unwrap(), Value::uuid(text).unwrap());
+        let generated = term_to_value(&Term::FunctionCall(BuiltinFunc::UuidV7, vec![])).unwrap();
+        assert!(matches!(generated, Value::Uuid(_)));
+    }
+
+    #[test]
+    fn test_term_to_value_list_and_map() {
+        let list = Term::FunctionCall(
+            BuiltinFunc::List,
+            vec![Term::Constant(1), Term::StringConstant("a".into())],
+        );
+        assert_eq!(
+            term_to_value(&list).unwrap(),
+            Value::list(vec![Value::Int64(1), Value::string("a")])
+        );
+        let map = Term::FunctionCall(
+            BuiltinFunc::Map,
+            vec![Term::StringConstant("tags".into()), list],
+        );
+        let value = term_to_value(&map).unwrap();
+        assert_eq!(value.as_map().unwrap()["tags"].as_list().unwrap().len(), 2);
+        let bad_key =
+            Term::FunctionCall(BuiltinFunc::Map, vec![Term::Constant(1), Term::Constant(2)]);
+        assert!(term_to_value(&bad_key).is_err());
+    }
+
+    #[test]
+    fn test_term_to_value_bytes() {
+        let hex = Term::FunctionCall(BuiltinFunc::Hex, vec![Term::StringConstant("cafe".into())]);
+        assert_eq!(term_to_value(&hex).unwrap(), Value::bytes(vec![0xca, 0xfe]));
+        let b64 = Term::FunctionCall(
+            BuiltinFunc::Base64,
+            vec![Term::StringConstant("yv4=".into())],
+        );
+        assert_eq!(term_to_value(&b64).unwrap(), Value::bytes(vec![0xca, 0xfe]));
+        let bad = Term::FunctionCall(BuiltinFunc::Hex, vec![Term::StringConstant("cafx".into())]);
+        assert!(term_to_value(&bad).unwrap_err().contains("Invalid hex"));
+    }
+
+    #[test]
+    fn test_term_to_value_json() {
+        let lit = Term::FunctionCall(
+            BuiltinFunc::JsonParse,
+            vec![Term::StringConstant(r#"{"b": 2, "a": [1]}"#.into())],
+        );
+        let value = term_to_value(&lit).unwrap();
+        assert_eq!(value.as_json(), Some(r#"{"a":[1],"b":2}"#));
+        let bad = Term::FunctionCall(
+            BuiltinFunc::JsonParse,
+            vec![Term::StringConstant("{oops".into())],
+        );
+        assert!(term_to_value(&bad).unwrap_err().contains("Invalid JSON"));
+    }
+
     #[test]
     fn test_term_to_value_variable_error() {
         let result = term_to_value(&Term::Variable("X".to_string()));
@@ -5188,6 +7727,69 @@ mod tests {
         (handler, tmp)
     }
 
+    #[test]
+    fn test_handler_subscribe_query_receives_changes() {
+        let (handler, _tmp) = handler_with_kg("sub_kg");
+        handler
+            .get_storage()
+            .insert_into("sub_kg", "edge", vec![(1, 2)])
+            .expect("insert failed");
+
+        let mut subscription = handler
+            .subscribe_query("sub_kg", "?edge(X, Y)")
+            .expect("subscribe failed");
+
+        handler
+            .get_storage()
+            .insert_into("sub_kg", "edge", vec![(2, 3)])
+            .expect("insert failed");
+
+        let deadline = Instant::now() + std::time::Duration::from_secs(5);
+        let mut rows = Vec::new();
+        while rows.len() < 2 && Instant::now() < deadline {
+            match subscription.try_next() {
+                Some(delta) => {
+                    assert_eq!(delta.diff, 1);
+                    rows.push(delta.data);
+                }
+                None => std::thread::sleep(std::time::Duration::from_millis(5)),
+            }
+        }
+        rows.sort();
+        assert_eq!(rows, vec![Tuple::from_pair(1, 2), Tuple::from_pair(2, 3)]);
+
+        assert!(handler
+            .subscribe_query("missing_kg", "?edge(X, Y)")
+            .is_err());
+    }
+
+    #[test]
+    fn test_handler_live_subscription_detach_and_resume() {
+        let (handler, _tmp) = handler_with_kg("live_kg");
+        assert!(handler
+            .subscribe_live("live_kg", "+edge(1, 2).", None)
+            .is_err());
+
+        let subscription = handler
+            .subscribe_live("live_kg", "?edge(X, Y)", None)
+            .expect("subscribe failed");
+        let id = subscription.id().to_string();
+        handler.detach_live(subscription);
+
+        let bob = crate::auth::AuthIdentity {
+            username: "bob".to_string(),
+            role: crate::auth::Role::Admin,
+            api_key: None,
+        };
+        assert!(handler.resume_live(&id, Some(&bob)).is_err());
+        let resumed = handler.resume_live(&id, None).expect("resume failed");
+        assert_eq!(resumed.id(), id);
+        assert!(handler.resume_live(&id, None).is_err());
+
+        handler.detach_live(resumed);
+        assert_eq!(handler.reap_detached_live(), 0);
+    }
+
     #[test]
     fn test_handler_create_and_close_session() {
         let (handler, _tmp) = handler_with_kg("sess_create_test");
@@ -5234,6 +7836,31 @@ mod tests {
         assert_eq!(retracted, 1);
     }
 
+    #[test]
+    fn test_handler_insert_and_delete_facts() {
+        let (handler, _tmp) = handler_with_kg("facts_api_test");
+        let tuples = vec![
+            Tuple::new(vec![Value::Int64(1), Value::Int64(2)]),
+            Tuple::new(vec![Value::Int64(2), Value::Int64(3)]),
+        ];
+        let report = handler
+            .insert_facts("facts_api_test", "edge", tuples.clone(), None)
+            .expect("insert failed");
+        assert_eq!(report.inserted, 2);
+        let again = handler
+            .insert_facts("facts_api_test", "edge", tuples[..1].to_vec(), None)
+            .expect("insert failed");
+        assert_eq!((again.inserted, again.duplicates), (0, 1));
+
+        let deleted = handler
+            .delete_facts("facts_api_test", "edge", tuples[..1].to_vec(), None)
+            .expect("delete failed");
+        assert_eq!(deleted, 1);
+        assert!(handler
+            .insert_facts("facts_api_test", "__edge", tuples, None)
+            .is_err());
+    }
+
     #[test]
     fn test_handler_session_stats() {
         let (handler, _tmp) = make_test_handler();
@@ -5282,68 +7909,443 @@ mod tests {
         let mut rx1 = handler.subscribe_notifications();
         let mut rx2 = handler.subscribe_notifications();
 
-        handler.notify_persistent_update("kg", "rel", "delete", 3);
+        handler.notify_persistent_update("kg", "rel", "delete", 3);
+
+        assert!(rx1.try_recv().is_ok());
+        assert!(rx2.try_recv().is_ok());
+    }
+
+    #[test]
+    fn test_notification_seq_increments() {
+        let (storage, _tmp) = make_test_storage();
+        let handler = Handler::new(storage);
+        let mut rx = handler.subscribe_notifications();
+
+        handler.notify_persistent_update("kg", "a", "insert", 1);
+        handler.notify_persistent_update("kg", "b", "insert", 2);
+        handler.notify_kg_change("kg2", "created");
+
+        let n1 = rx.try_recv().expect("notification receive failed");
+        let n2 = rx.try_recv().expect("notification receive failed");
+        let n3 = rx.try_recv().expect("notification receive failed");
+        assert_eq!(n1.seq(), 1);
+        assert_eq!(n2.seq(), 2);
+        assert_eq!(n3.seq(), 3);
+    }
+
+    #[test]
+    fn test_get_notifications_since() {
+        let (storage, _tmp) = make_test_storage();
+        let handler = Handler::new(storage);
+
+        handler.notify_persistent_update("kg", "a", "insert", 1);
+        handler.notify_persistent_update("kg", "b", "insert", 2);
+        handler.notify_kg_change("kg", "created");
+
+        // Get all since seq 0 (all notifications)
+        let all = handler.get_notifications_since(0);
+        assert_eq!(all.len(), 3);
+
+        // Get only since seq 2
+        let since2 = handler.get_notifications_since(2);
+        assert_eq!(since2.len(), 1);
+        assert_eq!(since2[0].seq(), 3);
+
+        // Get since last - should be empty
+        let none = handler.get_notifications_since(3);
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn test_notification_buffer_bounded() {
+        let (storage, _tmp) = make_test_storage();
+        let handler = Handler::new(storage);
+        let buf_size = handler.config().http.rate_limit.notification_buffer_size;
+
+        // Send more notifications than buffer size
+        for i in 0..(buf_size + 10) {
+            handler.notify_persistent_update("kg", &format!("r{i}"), "insert", 1);
+        }
+
+        let all = handler.get_notifications_since(0);
+        assert_eq!(all.len(), buf_size);
+        // First notification in buffer should be seq 11 (oldest 10 evicted)
+        assert_eq!(all[0].seq(), 11);
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_records_programs_and_is_read_only() {
+        let (mut config, tmp) = make_test_config();
+        config.storage.auto_create_knowledge_graphs = true;
+        config.audit.enabled = true;
+        let handler = Handler::from_config(config).expect("handler creation failed");
+        handler.bootstrap_auth();
+        handler
+            .handle_user_create("carol", "carol-password", "editor")
+            .expect("user creation failed");
+        let carol = crate::auth::AuthIdentity {
+            username: "carol".to_string(),
+            role: crate::auth::Role::Editor,
+            api_key: None,
+        };
+        let kg = Some("audited".to_string());
+        handler
+            .execute_program(None, kg.clone(), "+edge[(1, 2)]".into(), None)
+            .await
+            .expect("insert failed");
+        assert!(handler
+            .execute_program(None, kg.clone(), "?missing(".into(), None)
+            .await
+            .is_err());
+        handler
+            .execute_program(
+                None,
+                kg.clone(),
+                ".user create dave dave-secret viewer".into(),
+                None,
+            )
+            .await
+            .expect("user creation failed");
+
+        let file = std::fs::read_to_string(tmp.path().join("audit/audit.jsonl"))
+            .expect("audit file missing");
+        let records: Vec<serde_json::Value> = file
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("invalid audit line"))
+            .collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0]["knowledge_graph"], "audited");
+        assert_eq!(records[0]["statement"], "+edge[(1, 2)]");
+        assert_eq!(records[0]["outcome"], "ok");
+        assert_eq!(records[1]["outcome"], "error");
+        assert!(!file.contains("dave-secret"));
+
+        // Admins query the trail with Datalog; nobody can change it
+        let audit_kg = Some(crate::audit::AUDIT_KG.to_string());
+        let rows = handler
+            .execute_program(
+                None,
+                audit_kg.clone(),
+                "?statements(T, U, S, Kg, Stmt, R, Ms, \"error\", E)".into(),
+                None,
+            )
+            .await
+            .expect("audit query failed");
+        assert_eq!(rows.total_count, 1);
+        for program in ["+statements[(1, 2)]", ".rel drop statements"] {
+            let err = handler
+                .execute_program(None, audit_kg.clone(), program.into(), None)
+                .await
+                .unwrap_err();
+            assert!(err.contains("read-only"), "{err}");
+        }
+        let err = handler
+            .execute_program(None, kg.clone(), ".kg drop _audit".into(), None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("read-only"), "{err}");
+        let err = handler
+            .execute_program(
+                None,
+                audit_kg,
+                "?statements(T, U, S, Kg, Stmt, R, Ms, O, E)".into(),
+                Some(&carol),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.contains("system knowledge graph"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_slow_queries_recorded_in_audit_kg() {
+        let (config, _tmp) = make_test_config();
+        let handler = Handler::from_config(config).expect("handler creation failed");
+        assert!(handler.slow_query_capture().is_some());
+
+        let plan = crate::execution::ProgramPlan {
+            rules: vec![("path".to_string(), "Scan(edge)".to_string())],
+            inputs: vec![("edge".to_string(), 1200)],
+            steps: Vec::new(),
+        };
+        handler.record_slow_query(&crate::slow_query_log::SlowQueryRecord {
+            time: 1_700_000_000_000,
+            knowledge_graph: "default".to_string(),
+            statement: "?path(X, Y)".to_string(),
+            duration_ms: 6200,
+            rows: 0,
+            outcome: "timeout",
+            plans: vec![plan],
+        });
+
+        let rows = handler
+            .execute_program(
+                None,
+                Some(crate::audit::AUDIT_KG.to_string()),
+                "?slow_queries(T, Kg, Stmt, Ms, R, \"timeout\", Plan, \"edge=1200\", Steps)".into(),
+                None,
+            )
+            .await
+            .expect("slow query log query failed");
+        assert_eq!(rows.total_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_replication_follower_applies_leader_log_and_promotes() {
+        use crate::config::ReplicationRole;
+        use crate::storage_engine::ReplicationOp;
+
+        let (mut config, _leader_dir) = make_test_config();
+        config.storage.auto_create_knowledge_graphs = true;
+        config.replication.role = ReplicationRole::Leader;
+        let leader = Handler::from_config(config).expect("leader creation failed");
+        let kg = Some("shop".to_string());
+        let program = "+item(id: int, name: string)\n+item[(1, \"apple\"), (2, \"pear\")]\n+cheap(Id) <- item(Id, _), Id < 2";
+        leader
+            .execute_program(None, kg.clone(), program.into(), None)
+            .await
+            .expect("leader program failed");
+        let log = leader
+            .get_storage()
+            .replication_log()
+            .cloned()
+            .expect("leader has no replication log");
+        let records = log.read_from(&log.id(), 0, 100).expect("log start trimmed");
+        assert!(records.iter().any(
+            |r| matches!(&r.op, ReplicationOp::Statement { text } if text.starts_with("+cheap"))
+        ));
+
+        let (mut config, _follower_dir) = make_test_config();
+        config.replication.role = ReplicationRole::Follower;
+        config.replication.leader_url = Some("http://leader:8080".to_string());
+        config.replication.api_key = Some("il_key".to_string());
+        let follower = Handler::from_config(config).expect("follower creation failed");
+        for record in records.into_iter().filter(|r| r.kg == "shop") {
+            follower
+                .apply_replication_record(record)
+                .await
+                .expect("apply failed");
+        }
+        let items = follower
+            .execute_program(None, kg.clone(), "?item(Id, Name)".into(), None)
+            .await
+            .expect("follower query failed");
+        assert_eq!(items.rows.len(), 2);
+        let cheap = follower
+            .execute_program(None, kg.clone(), "?cheap(Id)".into(), None)
+            .await
+            .expect("follower rule query failed");
+        assert_eq!(cheap.rows.len(), 1);
+
+        let err = follower
+            .execute_program(None, kg.clone(), "+item[(3, \"plum\")]".into(), None)
+            .await
+            .expect_err("replica accepted a write");
+        assert!(err.contains("Read-only replica"), "{err}");
+        assert!(follower
+            .execute_program(None, Some("other".into()), "?item(Id, Name)".into(), None)
+            .await
+            .is_err());
+
+        follower
+            .execute_program(None, kg.clone(), ".replication promote".into(), None)
+            .await
+            .expect("promote failed");
+        assert!(!follower.replication().is_read_only());
+        follower
+            .execute_program(None, kg.clone(), "+item[(3, \"plum\")]".into(), None)
+            .await
+            .expect("write after promotion failed");
+    }
+
+    #[tokio::test]
+    async fn test_object_grants_allow_access_without_kg_role() {
+        let (handler, _tmp) = handler_with_kg("grants_kg");
+        handler.bootstrap_auth();
+        handler
+            .handle_user_create("carol", "carol-password", "editor")
+            .expect("user creation failed");
+        let carol = crate::auth::AuthIdentity {
+            username: "carol".to_string(),
+            role: crate::auth::Role::Editor,
+            api_key: None,
+        };
+        let kg = Some("grants_kg".to_string());
+        for program in ["+edge[(1, 2), (2, 3)]", "+reach(X, Y) <- edge(X, Y)"] {
+            handler
+                .execute_program(None, kg.clone(), program.to_string(), None)
+                .await
+                .expect("setup failed");
+        }
+        let run = |program: &str| {
+            handler.execute_program(None, kg.clone(), program.to_string(), Some(&carol))
+        };
+
+        // No role on the KG and no grants: nothing is allowed
+        assert!(run("?reach(X, Y)").await.is_err());
 
-        assert!(rx1.try_recv().is_ok());
-        assert!(rx2.try_recv().is_ok());
-    }
+        // A read grant on the view does not extend to its base relation
+        handler
+            .execute_program(
+                None,
+                kg.clone(),
+                "grant read on view reach to carol".into(),
+                None,
+            )
+            .await
+            .expect("grant failed");
+        assert_eq!(
+            run("?reach(X, Y)").await.expect("query failed").rows.len(),
+            2
+        );
+        assert!(run("?edge(X, Y)").await.is_err());
+        assert!(run("+edge[(3, 4)]").await.is_err());
+        assert!(run("grant read on view reach to dave").await.is_err());
 
-    #[test]
-    fn test_notification_seq_increments() {
-        let (storage, _tmp) = make_test_storage();
-        let handler = Handler::new(storage);
-        let mut rx = handler.subscribe_notifications();
+        // Grants on unknown objects are rejected
+        assert!(handler
+            .execute_program(
+                None,
+                kg.clone(),
+                "grant read on relation nope to carol".into(),
+                None
+            )
+            .await
+            .is_err());
 
-        handler.notify_persistent_update("kg", "a", "insert", 1);
-        handler.notify_persistent_update("kg", "b", "insert", 2);
-        handler.notify_kg_change("kg2", "created");
+        // Write on the base relation allows inserts
+        handler
+            .execute_program(
+                None,
+                kg.clone(),
+                "grant write on relation edge to carol".into(),
+                None,
+            )
+            .await
+            .expect("grant failed");
+        assert!(run("+edge[(3, 4)]").await.is_ok());
+        assert!(run(".rel drop edge").await.is_err());
 
-        let n1 = rx.try_recv().expect("notification receive failed");
-        let n2 = rx.try_recv().expect("notification receive failed");
-        let n3 = rx.try_recv().expect("notification receive failed");
-        assert_eq!(n1.seq(), 1);
-        assert_eq!(n2.seq(), 2);
-        assert_eq!(n3.seq(), 3);
-    }
+        // Revoking write leaves read
+        handler
+            .execute_program(
+                None,
+                kg.clone(),
+                "revoke write on relation edge from carol".into(),
+                None,
+            )
+            .await
+            .expect("revoke failed");
+        assert!(run("+edge[(4, 5)]").await.is_err());
+        assert_eq!(
+            run("?edge(X, Y)").await.expect("query failed").rows.len(),
+            3
+        );
 
-    #[test]
-    fn test_get_notifications_since() {
-        let (storage, _tmp) = make_test_storage();
-        let handler = Handler::new(storage);
+        handler
+            .execute_program(
+                None,
+                kg.clone(),
+                "revoke read on view reach from carol".into(),
+                None,
+            )
+            .await
+            .expect("revoke failed");
+        assert!(run("?reach(X, Y)").await.is_err());
 
-        handler.notify_persistent_update("kg", "a", "insert", 1);
-        handler.notify_persistent_update("kg", "b", "insert", 2);
-        handler.notify_kg_change("kg", "created");
+        // Dropping a relation removes its grants
+        handler
+            .execute_program(None, kg.clone(), ".rel drop edge".into(), None)
+            .await
+            .expect("drop failed");
+        assert!(handler.object_grants_for("grants_kg", "carol").is_empty());
+    }
 
-        // Get all since seq 0 (all notifications)
-        let all = handler.get_notifications_since(0);
-        assert_eq!(all.len(), 3);
+    #[tokio::test]
+    async fn test_qualified_reads_need_access_to_the_other_graph() {
+        let (handler, _tmp) = handler_with_kg("main");
+        handler
+            .get_storage()
+            .ensure_knowledge_graph("hr")
+            .expect("knowledge graph creation failed");
+        handler.bootstrap_auth();
+        handler
+            .handle_user_create("carol", "carol-password", "editor")
+            .expect("user creation failed");
+        let carol = crate::auth::AuthIdentity {
+            username: "carol".to_string(),
+            role: crate::auth::Role::Editor,
+            api_key: None,
+        };
+        for (kg, program) in [("hr", "+salary[(1, 100)]"), ("main", "+person[(1, 2)]")] {
+            handler
+                .execute_program(None, Some(kg.to_string()), program.to_string(), None)
+                .await
+                .expect("setup failed");
+        }
+        handler
+            .handle_kg_acl_grant("main", "carol", "editor")
+            .expect("acl grant failed");
+        let run = |program: &str| {
+            handler.execute_program(
+                None,
+                Some("main".to_string()),
+                program.to_string(),
+                Some(&carol),
+            )
+        };
 
-        // Get only since seq 2
-        let since2 = handler.get_notifications_since(2);
-        assert_eq!(since2.len(), 1);
-        assert_eq!(since2[0].seq(), 3);
+        // Access to main does not extend to the graphs it can name
+        assert!(run("?person(X, Y)").await.is_ok());
+        let err = run("?hr.salary(X, Y)")
+            .await
+            .expect_err("read another graph without access");
+        assert!(err.contains("hr.salary"), "{err}");
+        assert!(run("paid(X) <- hr.salary(X, _)\n?paid(X)").await.is_err());
+        assert!(run("+paid(X) <- hr.salary(X, _)").await.is_err());
 
-        // Get since last - should be empty
-        let none = handler.get_notifications_since(3);
-        assert!(none.is_empty());
+        // A read grant on the relation is enough
+        handler
+            .execute_program(
+                None,
+                Some("hr".to_string()),
+                "grant read on relation salary to carol".into(),
+                None,
+            )
+            .await
+            .expect("grant failed");
+        assert_eq!(
+            run("?hr.salary(X, Y)")
+                .await
+                .expect("query failed")
+                .rows
+                .len(),
+            1
+        );
     }
 
     #[test]
-    fn test_notification_buffer_bounded() {
-        let (storage, _tmp) = make_test_storage();
-        let handler = Handler::new(storage);
-        let buf_size = handler.config().http.rate_limit.notification_buffer_size;
-
-        // Send more notifications than buffer size
-        for i in 0..(buf_size + 10) {
-            handler.notify_persistent_update("kg", &format!("r{i}"), "insert", 1);
-        }
+    fn test_admit_program_counts_statements() {
+        let (mut config, _tmp) = make_test_config();
+        config.http.rate_limit.max_statements_per_sec = 3;
+        let handler = Handler::from_config(config).expect("handler creation failed");
+        let program = "% setup\n+edge[(1, 2)]\nreach(X, Y) <-\n  edge(X, Y)\n\n?reach(X, Y)";
+        assert_eq!(statement_count(program), 3);
+        assert!(!is_read_only_program(program));
+        assert!(is_read_only_program(
+            "% reads\nreach(X, Y) <-\n  edge(X, Y)\n?reach(X, Y)"
+        ));
+        assert!(!is_read_only_program(""));
 
-        let all = handler.get_notifications_since(0);
-        assert_eq!(all.len(), buf_size);
-        // First notification in buffer should be seq 11 (oldest 10 evicted)
-        assert_eq!(all[0].seq(), 11);
+        let permit = handler
+            .admit_program("key:a", program)
+            .expect("admit failed");
+        let err = handler.admit_program("key:a", "?reach(X, Y)").unwrap_err();
+        assert_eq!(
+            err.limit,
+            crate::protocol::throttle::ThrottleLimit::StatementsPerSec
+        );
+        drop(permit);
+        assert!(handler.admit_program("key:b", "?reach(X, Y)").is_ok());
     }
 
     // --- query_program tests ---
@@ -5582,6 +8584,317 @@ mod tests {
             .contains("Schema"));
     }
 
+    #[tokio::test]
+    async fn test_query_program_show_and_describe() {
+        let (handler, _tmp) = handler_with_kg("introspect_test");
+        let kg = Some("introspect_test".to_string());
+        handler
+            .query_program(
+                kg.clone(),
+                "+person(name: string, age: int @key)\n+person[(\"ann\", 30), (\"bob\", 40)]"
+                    .to_string(),
+            )
+            .await
+            .expect("query execution failed");
+
+        let relations = handler
+            .query_program(kg.clone(), "show relations".to_string())
+            .await
+            .expect("query execution failed");
+        let names: Vec<_> = relations.schema.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["name", "columns", "rows", "size_bytes", "indexes"]);
+        let person = relations
+            .rows
+            .iter()
+            .find(|row| row.values[0].as_str() == Some("person"))
+            .expect("person should be listed");
+        assert_eq!(person.values[2], WireValue::Int64(2));
+
+        let described = handler
+            .query_program(kg.clone(), "describe person".to_string())
+            .await
+            .expect("query execution failed");
+        assert_eq!(described.rows.len(), 2);
+        assert_eq!(described.rows[1].values[1].as_str(), Some("int"));
+        assert_eq!(described.rows[1].values[2].as_str(), Some("@key"));
+
+        let databases = handler
+            .query_program(kg, "show databases".to_string())
+            .await
+            .expect("query execution failed");
+        assert!(databases
+            .rows
+            .iter()
+            .any(|row| row.values[0].as_str() == Some("introspect_test")));
+    }
+
+    #[tokio::test]
+    async fn test_query_program_transaction() {
+        let (handler, _tmp) = handler_with_kg("tx_test");
+        let kg = Some("tx_test".to_string());
+        handler
+            .query_program(
+                kg.clone(),
+                "begin\n+tx_rel[(1,), (2,)]\n+tx_view(X) <- tx_rel(X)\ncommit".to_string(),
+            )
+            .await
+            .expect("query execution failed");
+        let view = handler
+            .query_program(kg.clone(), "?tx_view(X)".to_string())
+            .await
+            .expect("query execution failed");
+        assert_eq!(view.rows.len(), 2);
+
+        // A transaction left open at the end of a program is rolled back
+        let result = handler
+            .query_program(kg.clone(), "begin\n-tx_rel(1)\n+tx_rel(3)".to_string())
+            .await
+            .expect("query execution failed");
+        assert!(result.rows.iter().any(|row| row.values[0]
+            .as_str()
+            .is_some_and(|m| m.contains("rolled back"))));
+        let base = handler
+            .query_program(kg, "?tx_rel(X)".to_string())
+            .await
+            .expect("query execution failed");
+        assert_eq!(base.rows.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_execute_program_session_transaction() {
+        let (handler, _tmp) = handler_with_kg("sess_tx");
+        let sid = handler
+            .create_session("sess_tx")
+            .expect("session creation failed");
+        for stmt in ["begin", "+tx_edge[(1, 2)]", "+tx_edge[(2, 3)]"] {
+            handler
+                .execute_program(Some(&sid), None, stmt.to_string(), None)
+                .await
+                .expect("statement failed");
+        }
+        // Queued writes are invisible until commit
+        let before = handler
+            .execute_program(Some(&sid), None, "?tx_edge(X, Y)".to_string(), None)
+            .await
+            .expect("query execution failed");
+        assert!(before.rows.is_empty());
+
+        handler
+            .execute_program(Some(&sid), None, "commit".to_string(), None)
+            .await
+            .expect("commit failed");
+        let after = handler
+            .execute_program(Some(&sid), None, "?tx_edge(X, Y)".to_string(), None)
+            .await
+            .expect("query execution failed");
+        assert_eq!(after.rows.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_session_settings_and_owner() {
+        let (handler, _tmp) = handler_with_kg("sess_set");
+        let identity = |username: &str, role| crate::auth::AuthIdentity {
+            username: username.to_string(),
+            role,
+            api_key: None,
+        };
+        let root = identity("root", crate::auth::Role::Admin);
+        let sid = handler
+            .create_session_with_auth("sess_set", &root)
+            .expect("session creation failed");
+        assert!(handler.authorize_session(&sid, &root).is_ok());
+        let other = identity("mallory", crate::auth::Role::Editor);
+        assert!(handler.authorize_session(&sid, &other).is_err());
+
+        let run =
+            |program: &str| handler.execute_program(Some(&sid), None, program.to_string(), None);
+        run("+s_n[(1,), (2,), (3,)]").await.expect("insert failed");
+        run(".session set max_rows 2").await.expect("set failed");
+        let limited = run("?s_n(X)").await.expect("query failed");
+        assert_eq!(limited.rows.len(), 2);
+        assert!(limited.truncated);
+        assert!(run(".session set colour 1").await.is_err());
+
+        run(".session set priority batch")
+            .await
+            .expect("set failed");
+        assert!(run(".session set priority urgent").await.is_err());
+        let shown = run(".session settings").await.expect("settings failed");
+        assert_eq!(shown.rows[1].values[1], WireValue::String("2".to_string()));
+        assert_eq!(
+            shown.rows[2].values[2],
+            WireValue::String("batch".to_string())
+        );
+        run("?s_n(X)").await.expect("batch query failed");
+        assert!(handler
+            .admission()
+            .queries()
+            .iter()
+            .any(|query| query.priority == Priority::Batch));
+
+        run(".session reset").await.expect("reset failed");
+        assert_eq!(run("?s_n(X)").await.expect("query failed").rows.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_show_queries_lists_finished_queries() {
+        let (handler, _tmp) = handler_with_kg("queries_kg");
+        handler
+            .execute_program(
+                None,
+                Some("queries_kg".to_string()),
+                "+q_e[(1,)]".to_string(),
+                None,
+            )
+            .await
+            .expect("insert failed");
+        let shown = handler
+            .execute_program(None, None, "show queries".to_string(), None)
+            .await
+            .expect("show queries failed");
+        assert_eq!(shown.schema[1].name, "state");
+        assert!(shown.rows.iter().any(|row| {
+            row.values[1] == WireValue::String("finished".to_string())
+                && row.values[2] == WireValue::String("queries_kg".to_string())
+        }));
+        assert_eq!(handler.admission().stats().running, 0);
+    }
+
+    #[tokio::test]
+    async fn test_execute_batch_commits_writes_and_runs_final_query() {
+        let (handler, _tmp) = handler_with_kg("batch");
+        let statements = [
+            "+b_edge[(1, 2), (2, 3)]",
+            "+b_path(X, Y) <- b_edge(X, Y)",
+            "?b_path(X, Y)",
+        ];
+        let batch = handler
+            .execute_batch(
+                None,
+                Some("batch".to_string()),
+                statements.iter().map(ToString::to_string).collect(),
+                None,
+            )
+            .await
+            .expect("batch failed");
+        assert_eq!(batch.results.len(), 3);
+        assert_eq!(batch.results[2].rows.len(), 2);
+        assert!(batch
+            .commit
+            .as_deref()
+            .is_some_and(|c| c.starts_with("Transaction committed: 2 inserted")));
+
+        // A query alone runs without a transaction
+        let batch = handler
+            .execute_batch(
+                None,
+                Some("batch".to_string()),
+                vec!["?b_edge(X, Y)".to_string()],
+                None,
+            )
+            .await
+            .expect("batch failed");
+        assert!(batch.commit.is_none());
+        assert_eq!(batch.results[0].rows.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_execute_batch_is_atomic_and_checks_statements() {
+        let (handler, _tmp) = handler_with_kg("batch_fail");
+        let run = |statements: &[&str]| {
+            handler.execute_batch(
+                None,
+                Some("batch_fail".to_string()),
+                statements.iter().map(ToString::to_string).collect(),
+                None,
+            )
+        };
+
+        // A rule that cannot be registered rolls back the inserts before it
+        let err = run(&[
+            "+bf_edge[(1, 2)]",
+            "+bf_bad(X) <- bf_edge(X, Y), !bf_bad(X)",
+        ])
+        .await
+        .expect_err("unstratifiable rule accepted");
+        assert!(!err.is_empty());
+        let after = handler
+            .query_program(Some("batch_fail".to_string()), "?bf_edge(X, Y)".to_string())
+            .await
+            .expect("query execution failed");
+        assert!(after.rows.is_empty());
+
+        let err = run(&["?bf_edge(X, Y)", "+bf_edge(1, 2)"])
+            .await
+            .expect_err("query before a write accepted");
+        assert!(err.contains("Statement 1: a query can only be the last"));
+        let err = run(&["begin", "+bf_edge(1, 2)"])
+            .await
+            .expect_err("transaction control accepted");
+        assert!(err.starts_with("Statement 1: a batch is its own transaction"));
+        assert!(run(&[".kg list"]).await.is_err());
+        assert!(run(&[]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_execute_program_prepared_statements() {
+        let (handler, _tmp) = handler_with_kg("prep");
+        let sid = handler
+            .create_session("prep")
+            .expect("session creation failed");
+        for stmt in [
+            "+doc[(1, [0.0, 0.0]), (2, [3.0, 4.0])]",
+            "prepare near as ?doc(D, V), Dist = euclidean(V, $1), Dist < $2",
+            "prepare add as +doc($1, $2)",
+        ] {
+            handler
+                .execute_program(Some(&sid), None, stmt.to_string(), None)
+                .await
+                .expect("statement failed");
+        }
+        let run =
+            |program: &str| handler.execute_program(Some(&sid), None, program.to_string(), None);
+
+        let near = run("execute near ([0.0, 0.0], 1.0)")
+            .await
+            .expect("execute failed");
+        assert_eq!(near.rows.len(), 1);
+        run("execute add 3, [0.5, 0.0]")
+            .await
+            .expect("execute failed");
+        let near = run("execute near ([0.0, 0.0], 1.0)")
+            .await
+            .expect("execute failed");
+        assert_eq!(near.rows.len(), 2);
+
+        // Arguments are literals, never statement text
+        assert!(run("execute near (V, 1.0)").await.is_err());
+        assert!(run("execute near [0.0, 0.0]").await.is_err());
+
+        run("deallocate near").await.expect("deallocate failed");
+        let err = run("execute near ([0.0, 0.0], 1.0)").await.unwrap_err();
+        assert!(err.contains("not found"), "{err}");
+
+        // Without a session a prepared statement lasts for its program
+        let one = handler
+            .query_program(
+                Some("prep".to_string()),
+                "prepare one as ?doc($1, V)\nexecute one 2".to_string(),
+            )
+            .await
+            .expect("program failed");
+        assert_eq!(one.rows.len(), 1);
+        assert!(handler
+            .execute_program(
+                None,
+                Some("prep".to_string()),
+                "execute one 2".to_string(),
+                None
+            )
+            .await
+            .is_err());
+    }
+
     #[tokio::test]
     async fn test_query_program_bulk_delete() {
         let (handler, _tmp) = handler_with_kg("bulk_del_test");
@@ -7953,7 +11266,7 @@ fn extract_arith_vars(expr: &crate::ast::ArithExpr, vars: &mut Vec<String>) {
 /// Extract variables from a body predicate and add to `head_vars`
 /// Used for Cartesian product queries like ?- foo(X), bar(Y).
 /// Apply offset and limit pagination to result rows.
-fn apply_pagination(
+pub(crate) fn apply_pagination(
     rows: Vec<WireTuple>,
     limit: Option<usize>,
     offset: Option<usize>,
@@ -7971,7 +11284,10 @@ fn apply_pagination(
 
 /// Sort result rows by the given column indices and directions.
 /// Returns the rows unchanged if `order_by` is empty.
-fn sort_rows(mut rows: Vec<WireTuple>, order_by: &[(usize, SortDirection)]) -> Vec<WireTuple> {
+pub(crate) fn sort_rows(
+    mut rows: Vec<WireTuple>,
+    order_by: &[(usize, SortDirection)],
+) -> Vec<WireTuple> {
     if order_by.is_empty() {
         return rows;
     }
@@ -8008,6 +11324,20 @@ fn compare_wire_values(a: Option<&WireValue>, b: Option<&WireValue>) -> std::cmp
             (WireValue::String(a), WireValue::String(b)) => a.cmp(b),
             (WireValue::Bool(a), WireValue::Bool(b)) => a.cmp(b),
             (WireValue::Timestamp(a), WireValue::Timestamp(b)) => a.cmp(b),
+            (WireValue::Date(a), WireValue::Date(b)) => a.cmp(b),
+            (WireValue::Duration(a), WireValue::Duration(b)) => a.cmp(b),
+            (WireValue::Uuid(a), WireValue::Uuid(b)) => a.cmp(b),
+            (WireValue::Json(a), WireValue::Json(b)) => a.cmp(b),
+            (WireValue::Bytes(a), WireValue::Bytes(b)) => a.cmp(b),
+            (WireValue::List(a), WireValue::List(b)) => {
+                for (x, y) in a.iter().zip(b) {
+                    match compare_wire_values(Some(x), Some(y)) {
+                        std::cmp::Ordering::Equal => {}
+                        other => return other,
+                    }
+                }
+                a.len().cmp(&b.len())
+            }
             (WireValue::Null, WireValue::Null) => std::cmp::Ordering::Equal,
             (WireValue::Null, _) => std::cmp::Ordering::Less,
             (_, WireValue::Null) => std::cmp::Ordering::Greater,
@@ -8034,8 +11364,14 @@ fn wire_value_type_rank(v: &WireValue) -> u8 {
         WireValue::Float64(_) => 4,
         WireValue::String(_) => 5,
         WireValue::Timestamp(_) => 6,
-        WireValue::Vector(_) | WireValue::VectorInt8(_) => 7,
-        WireValue::Bytes(_) => 8,
+        WireValue::Date(_) => 7,
+        WireValue::Duration(_) => 8,
+        WireValue::Uuid(_) => 9,
+        WireValue::Vector(_) | WireValue::VectorInt8(_) => 10,
+        WireValue::Bytes(_) => 11,
+        WireValue::List(_) => 12,
+        WireValue::Map(_) => 13,
+        WireValue::Json(_) => 14,
     }
 }
 
@@ -8172,6 +11508,45 @@ fn split_respecting_brackets(s: &str) -> Vec<&str> {
     parts
 }
 
+/// Resolve a `.rel alter` action into a schema migration.
+/// An added column without a default is NULL in existing tuples.
+fn rel_alter_migration(action: RelAlterAction) -> Result<SchemaMigration, String> {
+    Ok(match action {
+        RelAlterAction::Add { column, default } => {
+            let default = match default {
+                Some(text) => term_to_value(&crate::parser::parse_term(&text)?)?,
+                None => Value::Null,
+            };
+            SchemaMigration::AddColumn { column, default }
+        }
+        RelAlterAction::Drop(name) => SchemaMigration::DropColumn { name },
+        RelAlterAction::Widen { column, to } => SchemaMigration::WidenType { column, to },
+        RelAlterAction::Partition(_) => {
+            return Err("Partitioning does not change the schema".to_string())
+        }
+        RelAlterAction::Retain(_) => return Err("Retention does not change the schema".to_string()),
+        RelAlterAction::Shard(_) => return Err("Sharding does not change the schema".to_string()),
+    })
+}
+
+/// Resolve a `.rel alter ... partition` declaration, evaluating range
+/// bounds. `None` removes the partition key.
+fn rel_alter_partition(decl: Option<PartitionDecl>) -> Result<Option<PartitionSpec>, String> {
+    let Some(decl) = decl else {
+        return Ok(None);
+    };
+    Ok(Some(match decl {
+        PartitionDecl::Hash { column, partitions } => PartitionSpec::hash(column, partitions),
+        PartitionDecl::Range { column, bounds } => {
+            let bounds = bounds
+                .iter()
+                .map(|text| term_to_value(&crate::parser::parse_term(text)?))
+                .collect::<Result<Vec<_>, String>>()?;
+            PartitionSpec::range(column, bounds)
+        }
+    }))
+}
+
 /// Parse a literal value string into a Value.
 fn parse_literal_value(s: &str) -> Result<crate::value::Value, String> {
     use crate::value::Value;
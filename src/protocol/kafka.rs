@@ -0,0 +1,555 @@
+//! Kafka Ingestion
+//!
+//! With `[[kafka.topics]]` listed and the `kafka` feature built in, the
+//! server consumes each topic into a relation. Every message is one tuple:
+//! a JSON object, or an Avro record in the topic's `avro_schema`, whose
+//! fields are read into the relation's columns (see [`PayloadDecoder`]).
+//!
+//! Messages are inserted in batches of up to `batch_size`, as an insert
+//! from a client would be: validated against the relation's schema,
+//! committed to the WAL, applied to the incremental engine so persistent
+//! rules and views stay up to date, and announced to subscribers. Only
+//! once a batch is committed are the offsets after it checkpointed in
+//! `metadata/kafka_offsets.json` in the data directory, so delivery is at
+//! least once: after a crash the last batch may be read again. Inserting
+//! a tuple twice leaves one copy, so replays are harmless unless the
+//! relation assigns `@auto` IDs.
+//!
+//! A message that cannot be decoded is logged and skipped. A batch the
+//! relation rejects is retried from its first message every
+//! [`RETRY_INTERVAL`] until it goes in; a validation policy of
+//! `quarantine` keeps bad rows from blocking the topic. Followers do not
+//! consume, and start when promoted.
+//!
+//! Partitions are assigned to this server directly rather than through
+//! group rebalancing: a single server writes, so it reads every partition.
+//! One without a checkpoint starts at the topic's `start`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use apache_avro::types::Value as AvroValue;
+use apache_avro::Schema as AvroSchema;
+use serde_json::Value as Json;
+
+use crate::config::{KafkaFormat, KafkaTopicConfig};
+use crate::schema::{RelationSchema, SchemaType};
+use crate::storage::avro::from_avro;
+use crate::storage::jsonl::{json_to_typed, lookup};
+use crate::value::{Tuple, Value};
+
+/// File in the metadata directory holding the next offset of each
+/// partition
+pub const OFFSETS_FILE: &str = "kafka_offsets.json";
+
+/// Wait before a rejected batch is retried
+pub const RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Reads message payloads of one topic as tuples of its relation
+pub struct PayloadDecoder {
+    format: KafkaFormat,
+    /// Field path read into each column
+    paths: Vec<String>,
+    types: Vec<SchemaType>,
+    avro_schema: Option<AvroSchema>,
+    confluent_header: bool,
+}
+
+impl PayloadDecoder {
+    /// A decoder for `topic` into a relation with the declared `schema`,
+    /// if any. Without a schema, `columns` must name the fields and values
+    /// keep the type they are sent with.
+    pub fn new(topic: &KafkaTopicConfig, schema: Option<&RelationSchema>) -> Result<Self, String> {
+        let paths = if topic.columns.is_empty() {
+            schema
+                .map(|schema| schema.columns.iter().map(|c| c.name.clone()).collect())
+                .ok_or_else(|| {
+                    format!(
+                        "kafka: topic '{}' needs columns, or a declared schema for '{}'",
+                        topic.topic, topic.relation
+                    )
+                })?
+        } else {
+            topic.columns.clone()
+        };
+        let types = match schema {
+            Some(schema) if schema.columns.len() != paths.len() => {
+                return Err(format!(
+                    "kafka: topic '{}' reads {} columns but '{}' has {}",
+                    topic.topic,
+                    paths.len(),
+                    topic.relation,
+                    schema.columns.len()
+                ));
+            }
+            Some(schema) => schema.columns.iter().map(|c| c.data_type.clone()).collect(),
+            None => vec![SchemaType::Any; paths.len()],
+        };
+        let avro_schema = match (topic.format, &topic.avro_schema) {
+            (KafkaFormat::Avro, Some(text)) => Some(
+                AvroSchema::parse_str(text)
+                    .map_err(|e| format!("kafka: topic '{}': avro_schema: {e}", topic.topic))?,
+            ),
+            (KafkaFormat::Avro, None) => {
+                return Err(format!("kafka: topic '{}' needs avro_schema", topic.topic));
+            }
+            (KafkaFormat::Json, _) => None,
+        };
+        Ok(Self {
+            format: topic.format,
+            paths,
+            types,
+            avro_schema,
+            confluent_header: topic.confluent_header,
+        })
+    }
+
+    /// The tuple a message payload holds. Missing fields read as null.
+    pub fn decode(&self, payload: &[u8]) -> Result<Tuple, String> {
+        match self.format {
+            KafkaFormat::Json => self.decode_json(payload),
+            KafkaFormat::Avro => self.decode_avro(payload),
+        }
+    }
+
+    fn decode_json(&self, payload: &[u8]) -> Result<Tuple, String> {
+        let Json::Object(object) =
+            serde_json::from_slice::<Json>(payload).map_err(|e| e.to_string())?
+        else {
+            return Err("expected a JSON object".to_string());
+        };
+        self.columns(|path, data_type| {
+            let field = lookup(&object, path).unwrap_or(&Json::Null);
+            json_to_typed(field, data_type)
+                .ok_or_else(|| format!("cannot read {field} as {data_type}"))
+        })
+    }
+
+    fn decode_avro(&self, payload: &[u8]) -> Result<Tuple, String> {
+        let Some(schema) = &self.avro_schema else {
+            return Err("no Avro schema".to_string());
+        };
+        let mut datum = payload;
+        if self.confluent_header {
+            datum = match payload {
+                [0, _, _, _, _, rest @ ..] => rest,
+                _ => return Err("missing the schema registry header".to_string()),
+            };
+        }
+        let AvroValue::Record(fields) =
+            apache_avro::from_avro_datum(schema, &mut datum, None).map_err(|e| e.to_string())?
+        else {
+            return Err("expected an Avro record".to_string());
+        };
+        self.columns(|path, data_type| match avro_field(&fields, path) {
+            Some(field) => from_avro(field.clone(), data_type)
+                .ok_or_else(|| format!("value does not fit {data_type}")),
+            None => Ok(Value::Null),
+        })
+    }
+
+    /// Read each column with `read`, naming the column in errors
+    fn columns(
+        &self,
+        mut read: impl FnMut(&str, &SchemaType) -> Result<Value, String>,
+    ) -> Result<Tuple, String> {
+        self.paths
+            .iter()
+            .zip(&self.types)
+            .map(|(path, data_type)| read(path, data_type).map_err(|e| format!("'{path}': {e}")))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Tuple::new)
+    }
+}
+
+/// Field of an Avro record at a dotted path, looking through nullable
+/// unions of nested records
+fn avro_field<'a>(fields: &'a [(String, AvroValue)], path: &str) -> Option<&'a AvroValue> {
+    if let Some((_, value)) = fields.iter().find(|(name, _)| name == path) {
+        return Some(value);
+    }
+    let (head, rest) = path.split_once('.')?;
+    let mut nested = &fields.iter().find(|(name, _)| name == head)?.1;
+    while let AvroValue::Union(_, inner) = nested {
+        nested = inner;
+    }
+    match nested {
+        AvroValue::Record(inner) => avro_field(inner, rest),
+        _ => None,
+    }
+}
+
+/// Next offset to read of each partition, by topic, checkpointed after
+/// each committed batch
+#[derive(Debug, Default)]
+pub struct OffsetStore {
+    path: PathBuf,
+    offsets: BTreeMap<String, BTreeMap<i32, i64>>,
+}
+
+impl OffsetStore {
+    /// Load the checkpoints kept in `metadata_dir`
+    pub fn open(metadata_dir: &Path) -> Result<Self, String> {
+        let path = metadata_dir.join(OFFSETS_FILE);
+        let offsets = match fs::read(&path) {
+            Ok(bytes) => {
+                serde_json::from_slice(&bytes).map_err(|e| format!("{}: {e}", path.display()))?
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(format!("{}: {e}", path.display())),
+        };
+        Ok(Self { path, offsets })
+    }
+
+    /// Next offset to read of `partition`, if it was checkpointed
+    pub fn next(&self, topic: &str, partition: i32) -> Option<i64> {
+        self.offsets.get(topic)?.get(&partition).copied()
+    }
+
+    /// Record the next offsets of a committed batch and save them
+    pub fn checkpoint(&mut self, next: &BTreeMap<(String, i32), i64>) -> Result<(), String> {
+        for ((topic, partition), offset) in next {
+            self.offsets
+                .entry(topic.clone())
+                .or_default()
+                .insert(*partition, *offset);
+        }
+        let bytes = serde_json::to_vec_pretty(&self.offsets).map_err(|e| e.to_string())?;
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, bytes)
+            .and_then(|()| fs::rename(&tmp, &self.path))
+            .map_err(|e| format!("{}: {e}", self.path.display()))
+    }
+}
+
+#[cfg(feature = "kafka")]
+pub use consumer::start_ingest;
+
+/// Warn that `[kafka]` topics are listed in a build that cannot read them
+#[cfg(not(feature = "kafka"))]
+pub fn start_ingest(
+    _handler: std::sync::Arc<crate::protocol::Handler>,
+    _shutdown: tokio::sync::watch::Receiver<bool>,
+) {
+    eprintln!(
+        "WARNING: [kafka] lists topics but this build does not include the kafka feature. \
+         Rebuild with --features kafka."
+    );
+}
+
+#[cfg(feature = "kafka")]
+mod consumer {
+    use std::collections::{BTreeMap, HashMap};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use rdkafka::config::ClientConfig;
+    use rdkafka::consumer::{BaseConsumer, Consumer};
+    use rdkafka::error::KafkaError;
+    use rdkafka::{Message, Offset, TopicPartitionList};
+    use tokio::sync::watch;
+    use tracing::{error, info, warn};
+
+    use super::{OffsetStore, PayloadDecoder, RETRY_INTERVAL};
+    use crate::config::{KafkaConfig, KafkaStart};
+    use crate::protocol::Handler;
+    use crate::value::Tuple;
+
+    /// Timeout of metadata requests to the brokers
+    const METADATA_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Where a topic's messages go
+    struct Route {
+        kg: String,
+        relation: String,
+        decoder: PayloadDecoder,
+    }
+
+    /// Consume the `[kafka]` topics on a thread of their own until
+    /// `shutdown` is signalled
+    pub fn start_ingest(handler: Arc<Handler>, shutdown: watch::Receiver<bool>) {
+        let spawned = std::thread::Builder::new()
+            .name("kafka-ingest".to_string())
+            .spawn(move || {
+                if let Err(e) = ingest(&handler, &shutdown) {
+                    error!(error = %e, "kafka_ingest_failed");
+                    eprintln!("ERROR: Kafka ingestion stopped: {e}");
+                }
+            });
+        if let Err(e) = spawned {
+            error!(error = %e, "kafka_ingest_spawn_failed");
+        }
+    }
+
+    fn ingest(handler: &Handler, shutdown: &watch::Receiver<bool>) -> Result<(), String> {
+        let config = handler.config().kafka.clone();
+        let metadata_dir = handler.config().storage.data_dir.join("metadata");
+        let mut offsets = OffsetStore::open(&metadata_dir)?;
+        let routes = routes(handler, &config)?;
+
+        let mut client = ClientConfig::new();
+        client
+            .set("bootstrap.servers", &config.brokers)
+            .set("group.id", &config.group_id)
+            .set("enable.auto.commit", "false")
+            .set("enable.auto.offset.store", "false");
+        for (key, value) in &config.properties {
+            client.set(key, value);
+        }
+        let consumer: BaseConsumer = client.create().map_err(|e| e.to_string())?;
+        let partitions = partitions(&consumer, &config)?;
+        assign(&consumer, &partitions, &offsets)?;
+        info!(
+            topics = config.topics.len(),
+            partitions = partitions.len(),
+            "kafka_ingest_started"
+        );
+
+        let batch_wait = Duration::from_millis(config.batch_wait_ms);
+        while !*shutdown.borrow() {
+            // Only the writer consumes
+            if handler.replication().is_read_only() {
+                std::thread::sleep(RETRY_INTERVAL);
+                continue;
+            }
+            let mut batch: HashMap<&str, Vec<Tuple>> = HashMap::new();
+            let mut next: BTreeMap<(String, i32), i64> = BTreeMap::new();
+            let deadline = Instant::now() + batch_wait;
+            let mut polled = 0;
+            while polled < config.batch_size {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                let Some(message) = consumer.poll(remaining) else {
+                    break;
+                };
+                let message = match message {
+                    Ok(message) => message,
+                    Err(KafkaError::PartitionEOF(_)) => continue,
+                    Err(e) => {
+                        warn!(error = %e, "kafka_poll_failed");
+                        break;
+                    }
+                };
+                polled += 1;
+                next.insert(
+                    (message.topic().to_string(), message.partition()),
+                    message.offset() + 1,
+                );
+                let Some((topic, route)) = routes.get_key_value(message.topic()) else {
+                    continue;
+                };
+                match route.decoder.decode(message.payload().unwrap_or_default()) {
+                    Ok(tuple) => batch.entry(topic.as_str()).or_default().push(tuple),
+                    Err(e) => {
+                        crate::metrics::metrics().kafka_messages_skipped.inc();
+                        warn!(
+                            topic = %message.topic(),
+                            partition = message.partition(),
+                            offset = message.offset(),
+                            error = %e,
+                            "kafka_message_skipped"
+                        );
+                    }
+                }
+            }
+            if next.is_empty() {
+                continue;
+            }
+
+            let mut committed = true;
+            for (topic, tuples) in batch {
+                let route = &routes[topic];
+                let count = tuples.len() as u64;
+                match handler.insert_facts(&route.kg, &route.relation, tuples, None) {
+                    Ok(_) => crate::metrics::metrics().kafka_messages_ingested.add(count),
+                    Err(e) => {
+                        error!(
+                            topic = %topic,
+                            kg = %route.kg,
+                            relation = %route.relation,
+                            error = %e,
+                            "kafka_batch_rejected"
+                        );
+                        committed = false;
+                        break;
+                    }
+                }
+            }
+            if committed {
+                offsets.checkpoint(&next)?;
+            } else {
+                // Read the batch again from its first message. Topics
+                // inserted before the failure are inserted again, which
+                // leaves them unchanged.
+                std::thread::sleep(RETRY_INTERVAL);
+                assign(&consumer, &partitions, &offsets)?;
+            }
+        }
+        info!("kafka_ingest_stopped");
+        Ok(())
+    }
+
+    /// The route of each topic, creating knowledge graphs as needed
+    fn routes(handler: &Handler, config: &KafkaConfig) -> Result<HashMap<String, Route>, String> {
+        let storage = handler.get_storage();
+        let default_kg = &handler.config().storage.default_knowledge_graph;
+        let mut routes = HashMap::new();
+        for topic in &config.topics {
+            let kg = topic.knowledge_graph.as_ref().unwrap_or(default_kg);
+            storage
+                .ensure_knowledge_graph(kg)
+                .or_else(|_| storage.create_knowledge_graph(kg))
+                .map_err(|e| format!("kafka: topic '{}': {e}", topic.topic))?;
+            let schema = storage
+                .get_schema_in(kg, &topic.relation)
+                .map_err(|e| e.to_string())?;
+            let route = Route {
+                kg: kg.clone(),
+                relation: topic.relation.clone(),
+                decoder: PayloadDecoder::new(topic, schema.as_ref())?,
+            };
+            routes.insert(topic.topic.clone(), route);
+        }
+        Ok(routes)
+    }
+
+    /// Every partition of the configured topics
+    fn partitions(
+        consumer: &BaseConsumer,
+        config: &KafkaConfig,
+    ) -> Result<Vec<(String, i32, KafkaStart)>, String> {
+        let mut partitions = Vec::new();
+        for topic in &config.topics {
+            let metadata = consumer
+                .fetch_metadata(Some(&topic.topic), METADATA_TIMEOUT)
+                .map_err(|e| format!("kafka: topic '{}': {e}", topic.topic))?;
+            for found in metadata.topics() {
+                if let Some(e) = found.error() {
+                    return Err(format!("kafka: topic '{}': {e:?}", topic.topic));
+                }
+                for partition in found.partitions() {
+                    partitions.push((topic.topic.clone(), partition.id(), topic.start));
+                }
+            }
+        }
+        Ok(partitions)
+    }
+
+    /// Read every partition from its checkpoint, or from the topic's
+    /// start if it has none
+    fn assign(
+        consumer: &BaseConsumer,
+        partitions: &[(String, i32, KafkaStart)],
+        offsets: &OffsetStore,
+    ) -> Result<(), String> {
+        let mut assignment = TopicPartitionList::new();
+        for (topic, partition, start) in partitions {
+            let offset = match (offsets.next(topic, *partition), start) {
+                (Some(next), _) => Offset::Offset(next),
+                (None, KafkaStart::Earliest) => Offset::Beginning,
+                (None, KafkaStart::Latest) => Offset::End,
+            };
+            assignment
+                .add_partition_offset(topic, *partition, offset)
+                .map_err(|e| e.to_string())?;
+        }
+        consumer.assign(&assignment).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::config::KafkaStart;
+    use crate::schema::ColumnSchema;
+
+    fn topic(format: KafkaFormat) -> KafkaTopicConfig {
+        KafkaTopicConfig {
+            topic: "orders".to_string(),
+            knowledge_graph: None,
+            relation: "order".to_string(),
+            format,
+            columns: Vec::new(),
+            avro_schema: None,
+            confluent_header: false,
+            start: KafkaStart::Earliest,
+        }
+    }
+
+    fn order_schema() -> RelationSchema {
+        RelationSchema::new("order")
+            .with_column(ColumnSchema::new("id", SchemaType::Int))
+            .with_column(ColumnSchema::new("customer", SchemaType::String))
+    }
+
+    #[test]
+    fn test_json_payload_maps_fields_to_columns() {
+        let mut config = topic(KafkaFormat::Json);
+        config.columns = vec!["id".to_string(), "customer.name".to_string()];
+        let decoder = PayloadDecoder::new(&config, Some(&order_schema())).unwrap();
+        let tuple = decoder
+            .decode(br#"{"id": 7, "customer": {"name": "alice"}, "extra": true}"#)
+            .unwrap();
+        assert_eq!(tuple.values(), &[Value::Int64(7), Value::string("alice")]);
+
+        // Missing fields are null; mistyped ones name the column
+        let tuple = decoder.decode(br#"{"id": 8}"#).unwrap();
+        assert_eq!(tuple.values()[1], Value::Null);
+        let err = decoder.decode(br#"{"id": "x"}"#).unwrap_err();
+        assert!(err.contains("'id'"), "{err}");
+        assert!(decoder.decode(b"[1, 2]").is_err());
+
+        // Columns default to the schema's; without one they are required
+        let decoder = PayloadDecoder::new(&topic(KafkaFormat::Json), Some(&order_schema()));
+        assert!(decoder.is_ok());
+        assert!(PayloadDecoder::new(&topic(KafkaFormat::Json), None).is_err());
+    }
+
+    #[test]
+    fn test_avro_payload_with_registry_header() {
+        let text = r#"{"type": "record", "name": "order", "fields": [
+            {"name": "id", "type": "long"},
+            {"name": "customer", "type": ["null", "string"]}
+        ]}"#;
+        let mut config = topic(KafkaFormat::Avro);
+        config.avro_schema = Some(text.to_string());
+        config.confluent_header = true;
+        let decoder = PayloadDecoder::new(&config, Some(&order_schema())).unwrap();
+
+        let schema = AvroSchema::parse_str(text).unwrap();
+        let record = AvroValue::Record(vec![
+            ("id".to_string(), AvroValue::Long(7)),
+            (
+                "customer".to_string(),
+                AvroValue::Union(1, Box::new(AvroValue::String("alice".to_string()))),
+            ),
+        ]);
+        let mut payload = vec![0, 0, 0, 0, 42];
+        payload.extend(apache_avro::to_avro_datum(&schema, record).unwrap());
+        let tuple = decoder.decode(&payload).unwrap();
+        assert_eq!(tuple.values(), &[Value::Int64(7), Value::string("alice")]);
+        assert!(decoder.decode(&payload[5..]).is_err(), "header required");
+    }
+
+    #[test]
+    fn test_offsets_checkpoint_and_reload() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut store = OffsetStore::open(tmp.path()).unwrap();
+        assert_eq!(store.next("orders", 0), None);
+
+        let mut next = BTreeMap::new();
+        next.insert(("orders".to_string(), 0), 42);
+        next.insert(("orders".to_string(), 3), 7);
+        store.checkpoint(&next).unwrap();
+        next.clear();
+        next.insert(("orders".to_string(), 0), 50);
+        store.checkpoint(&next).unwrap();
+
+        let reloaded = OffsetStore::open(tmp.path()).unwrap();
+        assert_eq!(reloaded.next("orders", 0), Some(50));
+        assert_eq!(reloaded.next("orders", 3), Some(7));
+        assert_eq!(reloaded.next("payments", 0), None);
+    }
+}
@@ -0,0 +1,346 @@
+//! Live view subscriptions
+//!
+//! A continuous query (`Handler::subscribe_query`) yields a stream of
+//! `Delta`s as base data changes. `LiveSubscription` adapts one for a
+//! network client:
+//!
+//! - ready deltas are consolidated into a batch (one entry per tuple, diffs
+//!   summed, zero diffs dropped) so a burst of changes goes out as one
+//!   message
+//! - each batch gets a cursor, increasing by one per batch
+//! - recent batches are kept, up to a row budget, so a client that
+//!   reconnects can resume after the last cursor it saw
+//! - a subscriber that lets more than the row budget of changes pile up is
+//!   cut off rather than buffering without bound
+
+use std::collections::{HashMap, VecDeque};
+use std::task::{Context, Poll};
+
+use futures_util::StreamExt;
+
+use crate::incremental::{Delta, ViewSubscription};
+use crate::value::Tuple;
+
+/// Consolidated changes to a view, sent to the client as one message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeltaBatch {
+    /// Position of this batch in the subscription (first batch is 1)
+    pub cursor: u64,
+    /// Tuples with their multiplicity change: positive for insertions,
+    /// negative for retractions
+    pub changes: Vec<(Tuple, isize)>,
+}
+
+impl DeltaBatch {
+    fn rows(&self) -> usize {
+        self.changes.len()
+    }
+}
+
+/// A continuous query owned by a client connection, or detached and
+/// waiting to be resumed
+pub struct LiveSubscription {
+    id: String,
+    kg: String,
+    query: String,
+    owner: String,
+    stream: ViewSubscription,
+    /// Delta taken while polling for readiness, not yet batched
+    stashed: Option<Delta<Tuple>>,
+    /// Stream ended (view dropped or engine shut down)
+    closed: bool,
+    cursor: u64,
+    /// Recent batches for resume, oldest first
+    history: VecDeque<DeltaBatch>,
+    history_rows: usize,
+}
+
+impl LiveSubscription {
+    pub fn new(
+        id: String,
+        kg: String,
+        query: String,
+        owner: String,
+        stream: ViewSubscription,
+    ) -> Self {
+        Self {
+            id,
+            kg,
+            query,
+            owner,
+            stream,
+            stashed: None,
+            closed: false,
+            cursor: 0,
+            history: VecDeque::new(),
+            history_rows: 0,
+        }
+    }
+
+    /// Subscription ID, chosen by the server
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Knowledge graph the query runs in
+    pub fn kg(&self) -> &str {
+        &self.kg
+    }
+
+    /// Query text as the client sent it
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// User who created the subscription
+    pub fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    /// Cursor of the last batch handed out (0 before the first)
+    pub fn cursor(&self) -> u64 {
+        self.cursor
+    }
+
+    /// Whether the underlying view stream has ended
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Replace the stream after the client missed changes that are no
+    /// longer kept. The new stream starts with a full snapshot; cursors
+    /// continue from where they were.
+    pub fn reset(&mut self, stream: ViewSubscription) {
+        self.stream = stream;
+        self.stashed = None;
+        self.closed = false;
+        self.history.clear();
+        self.history_rows = 0;
+    }
+
+    /// Ready when a change can be drained or the stream has ended
+    pub fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        if self.stashed.is_some() || self.closed {
+            return Poll::Ready(());
+        }
+        match self.stream.poll_next_unpin(cx) {
+            Poll::Ready(Some(delta)) => {
+                self.stashed = Some(delta);
+                Poll::Ready(())
+            }
+            Poll::Ready(None) => {
+                self.closed = true;
+                Poll::Ready(())
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    /// Take every change that is ready and consolidate it into a batch.
+    ///
+    /// Returns `Ok(None)` when the changes cancel out. Fails when more than
+    /// `max_rows` changes are pending: the client is not keeping up.
+    pub fn drain(&mut self, max_rows: usize) -> Result<Option<DeltaBatch>, String> {
+        let mut order: Vec<Tuple> = Vec::new();
+        let mut diffs: HashMap<Tuple, isize> = HashMap::new();
+        let mut pending = 0usize;
+        let mut next = self.stashed.take();
+        while let Some(delta) = next.take().or_else(|| self.stream.try_next()) {
+            pending += 1;
+            if pending > max_rows {
+                return Err(format!(
+                    "Subscription fell behind: more than {max_rows} pending changes"
+                ));
+            }
+            match diffs.get_mut(&delta.data) {
+                Some(diff) => *diff += delta.diff,
+                None => {
+                    diffs.insert(delta.data.clone(), delta.diff);
+                    order.push(delta.data);
+                }
+            }
+        }
+
+        let changes: Vec<(Tuple, isize)> = order
+            .into_iter()
+            .filter_map(|tuple| {
+                let diff = diffs.get(&tuple).copied().unwrap_or(0);
+                (diff != 0).then_some((tuple, diff))
+            })
+            .collect();
+        if changes.is_empty() {
+            return Ok(None);
+        }
+
+        self.cursor += 1;
+        let batch = DeltaBatch {
+            cursor: self.cursor,
+            changes,
+        };
+        self.history_rows += batch.rows();
+        self.history.push_back(batch.clone());
+        // Keep the latest batch even if it alone exceeds the budget
+        while self.history_rows > max_rows && self.history.len() > 1 {
+            if let Some(old) = self.history.pop_front() {
+                self.history_rows -= old.rows();
+            }
+        }
+        Ok(Some(batch))
+    }
+
+    /// Batches after `cursor`, for a client resuming from it. `None` if
+    /// some of them are no longer kept (or the cursor was never issued).
+    pub fn replay_after(&self, cursor: u64) -> Option<Vec<DeltaBatch>> {
+        if cursor > self.cursor {
+            return None;
+        }
+        if cursor == self.cursor {
+            return Some(Vec::new());
+        }
+        let oldest = self.history.front()?.cursor;
+        if oldest > cursor + 1 {
+            return None;
+        }
+        Some(
+            self.history
+                .iter()
+                .filter(|batch| batch.cursor > cursor)
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
+/// Wait until one of `subscriptions` is ready and return its index.
+/// Never completes when there are none.
+pub async fn next_ready(subscriptions: &mut [LiveSubscription]) -> usize {
+    if subscriptions.is_empty() {
+        return std::future::pending().await;
+    }
+    std::future::poll_fn(|cx| {
+        for (index, subscription) in subscriptions.iter_mut().enumerate() {
+            if subscription.poll_ready(cx).is_ready() {
+                return Poll::Ready(index);
+            }
+        }
+        Poll::Pending
+    })
+    .await
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::protocol::Handler;
+    use crate::Config;
+    use std::time::{Duration, Instant};
+
+    fn handler_with_edges() -> (Handler, tempfile::TempDir) {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage.data_dir = tmp.path().to_path_buf();
+        let handler = Handler::from_config(config).unwrap();
+        handler
+            .get_storage()
+            .create_knowledge_graph("live")
+            .unwrap();
+        handler
+            .get_storage()
+            .insert_into("live", "edge", vec![(1, 2)])
+            .unwrap();
+        (handler, tmp)
+    }
+
+    fn subscribe(handler: &Handler) -> LiveSubscription {
+        let stream = handler.subscribe_query("live", "?edge(X, Y)").unwrap();
+        LiveSubscription::new(
+            "s1".to_string(),
+            "live".to_string(),
+            "?edge(X, Y)".to_string(),
+            "admin".to_string(),
+            stream,
+        )
+    }
+
+    /// Drain until a batch arrives
+    fn next_batch(subscription: &mut LiveSubscription) -> DeltaBatch {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if let Some(batch) = subscription.drain(1000).unwrap() {
+                return batch;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        panic!("no changes arrived");
+    }
+
+    #[test]
+    fn test_snapshot_then_changes_with_cursors() {
+        let (handler, _tmp) = handler_with_edges();
+        let mut subscription = subscribe(&handler);
+
+        let snapshot = next_batch(&mut subscription);
+        assert_eq!(snapshot.cursor, 1);
+        assert_eq!(snapshot.changes, vec![(Tuple::from_pair(1, 2), 1)]);
+
+        handler
+            .get_storage()
+            .delete_from("live", "edge", vec![(1, 2)])
+            .unwrap();
+        let retraction = next_batch(&mut subscription);
+        assert_eq!(retraction.cursor, 2);
+        assert_eq!(retraction.changes, vec![(Tuple::from_pair(1, 2), -1)]);
+
+        assert_eq!(subscription.replay_after(0).unwrap().len(), 2);
+        assert_eq!(subscription.replay_after(1).unwrap(), vec![retraction]);
+        assert!(subscription.replay_after(2).unwrap().is_empty());
+        assert!(subscription.replay_after(3).is_none());
+    }
+
+    #[test]
+    fn test_backpressure_and_history_budget() {
+        let (handler, _tmp) = handler_with_edges();
+        let mut subscription = subscribe(&handler);
+        next_batch(&mut subscription);
+
+        // A subscriber with no room for pending changes is cut off
+        handler
+            .get_storage()
+            .insert_into("live", "edge", vec![(2, 3)])
+            .unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut lagged = false;
+        while !lagged && Instant::now() < deadline {
+            match subscription.drain(0) {
+                Ok(_) => std::thread::sleep(Duration::from_millis(5)),
+                Err(e) => {
+                    assert!(e.contains("fell behind"));
+                    lagged = true;
+                }
+            }
+        }
+        assert!(lagged);
+
+        // Old batches fall out of the history once it exceeds the budget
+        let mut small = subscribe(&handler);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while small.cursor() == 0 && Instant::now() < deadline {
+            small.drain(10).unwrap();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        handler
+            .get_storage()
+            .insert_into("live", "edge", vec![(5, 6)])
+            .unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut batch = None;
+        while batch.is_none() && Instant::now() < deadline {
+            batch = small.drain(1).unwrap();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(batch.unwrap().cursor, 2);
+        assert!(small.replay_after(0).is_none());
+        assert_eq!(small.replay_after(1).unwrap().len(), 1);
+    }
+}
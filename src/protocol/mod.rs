@@ -11,23 +11,55 @@
 //! |  HTTP Endpoints:                                            |
 //! |    - /health: health check                                  |
 //! |    - /metrics: server statistics                             |
-//! |    - /ws: WebSocket (all data operations)                   |
+//! |    - /ws: WebSocket (sessions, all data operations)         |
+//! |    - /query, /knowledge-graphs: HTTP data endpoints         |
+//! |  gRPC (`grpc` feature): inputlayer.v1.InputLayer service    |
+//! |  Arrow Flight (`flight` feature): DoGet result batches      |
 //! +-------------------------------------------------------------+
-//! |  Wire Format: JSON (WebSocket) / bincode (internal)         |
-//! |  Transport: WebSocket                                        |
+//! |  Wire Format: JSON (WebSocket, HTTP) / protobuf (gRPC)      |
+//! |               / bincode (internal)                          |
+//! |  Transport: WebSocket, HTTP, gRPC, Flight (optionally TLS)  |
 //! +-------------------------------------------------------------+
 //! ```
 //!
 //! # Module Structure
 //!
+//! - `admission` - Bounded, prioritized, per-knowledge-graph fair queue of queries waiting to run
+//! - `client` - Async WebSocket client (pooling, pipelining, timeouts)
+//! - `cluster` - Raft election of the writer and ordering of schema changes
+//! - `compression` - zstd/LZ4 WebSocket frame compression and negotiation
+//! - `cursor` - Server-side result cursors (paged fetch, expiry, close)
 //! - `wire` - Wire format types (`WireValue`, `WireTuple`, `QueryResult`, etc.)
 //! - `error` - Protocol error types
+//! - `flight` - Arrow Flight server streaming results as record batches (`flight` feature)
 //! - `handler` - Handler implementing business logic
+//! - `kafka` - Consumer inserting Kafka topics into relations (`kafka` feature)
+//! - `live` - Live view subscriptions (batched deltas, cursors, resume)
+//! - `replication` - Read-only followers of a leader's replication log (catch-up, promotion)
 //! - `rest` - HTTP handlers and routing
+//! - `shard` - Router spreading hash-sharded and broadcast relations over engine nodes
+//! - `grpc` - gRPC service and server (`grpc` feature)
+//! - `throttle` - Per-client concurrency, statement rate and result size limits
+//! - `tls` - rustls server and client configuration (SNI, mutual TLS)
 
+pub mod admission;
+pub mod client;
+pub mod cluster;
+pub mod compression;
+pub mod cursor;
 pub mod error;
+#[cfg(feature = "flight")]
+pub mod flight;
+#[cfg(feature = "grpc")]
+pub mod grpc;
 pub mod handler;
+pub mod kafka;
+pub mod live;
+pub mod replication;
 pub mod rest;
+pub mod shard;
+pub mod throttle;
+pub mod tls;
 pub mod wire;
 
 // Re-export error types
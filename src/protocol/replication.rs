@@ -0,0 +1,701 @@
+//! Leader-Follower Replication
+//!
+//! A server with `[replication] role = "leader"` serves its replication log
+//! (see [`ReplicationLog`]) at `GET /v1/replication`, a WebSocket open to
+//! admin API keys. A follower connects to `leader_url` with its `api_key`,
+//! asks for the records after its saved position and applies them as they
+//! arrive. When that position is no longer in the leader's log, the leader
+//! first sends an image of every knowledge graph, then the records
+//! committed since the first image was taken.
+//!
+//! A follower is a read-only replica: queries and session-only statements
+//! run locally, and statements that would change persistent data are
+//! rejected with the leader's address. `.replication promote` stops
+//! following and makes the server writable, for failover. In a cluster
+//! (see [`super::cluster`]) the elected member leads instead, and the
+//! others follow whichever member that is.
+//!
+//! The follower keeps its position in `replication.json` in the data
+//! directory, so after a restart it resumes where it stopped for as long
+//! as the leader still holds those records.
+//!
+//! Messages are JSON text frames with a `type` field:
+//!
+//! - `{"type": "snapshot", "log_id": "...", "image": {...}}`
+//! - `{"type": "snapshot_end", "log_id": "...", "seq": 42, "knowledge_graphs": [...]}`
+//! - `{"type": "records", "log_id": "...", "records": [...]}`
+//! - `{"type": "heartbeat", "log_id": "...", "next_seq": 57}`
+//! - `{"type": "error", "message": "..."}`
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{watch, Notify};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{info, warn};
+
+use crate::auth::{authorize_kg_operation, KgRole};
+use crate::config::{ReplicationConfig, ReplicationRole};
+use crate::statement::{CopyDirection, MetaCommand, Statement};
+use crate::storage_engine::{KnowledgeGraphImage, ReplicationLog, ReplicationRecord};
+use crate::Config;
+
+use super::Handler;
+
+/// Path followers connect to, relative to the leader's URL
+pub const REPLICATION_PATH: &str = "/v1/replication";
+
+/// Records sent in one `records` message at most
+pub const MAX_RECORDS_PER_MESSAGE: usize = 256;
+
+/// Interval of `heartbeat` messages while no records are committed
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Follower position, in the data directory
+const POSITION_FILE: &str = "replication.json";
+
+/// A message from the leader to a follower
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReplicationMessage {
+    /// A knowledge graph to install in place of the follower's copy
+    Snapshot {
+        log_id: String,
+        image: KnowledgeGraphImage,
+    },
+    /// Images are complete: records follow from `seq`, and knowledge
+    /// graphs missing from `knowledge_graphs` no longer exist
+    SnapshotEnd {
+        log_id: String,
+        seq: u64,
+        knowledge_graphs: Vec<String>,
+    },
+    Records {
+        log_id: String,
+        records: Vec<ReplicationRecord>,
+    },
+    /// Sent while idle, with the sequence number of the next record
+    Heartbeat {
+        log_id: String,
+        next_seq: u64,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// Whether changes to `kg` are replicated. Each server audits the
+/// programs it runs itself, so `_audit` is left out.
+pub fn replicated_kg(kg: &str) -> bool {
+    kg != crate::audit::AUDIT_KG
+}
+
+/// Statements recorded in the log as their text: they change rules,
+/// indexes or relations through the catalog rather than by writing tuples
+pub fn replicated_statement(stmt: &Statement) -> bool {
+    matches!(
+        stmt,
+        Statement::PersistentRule(_)
+            | Statement::DeleteRelationOrRule(_)
+            | Statement::Meta(
+                MetaCommand::RelDrop(_)
+                    | MetaCommand::RelAlter { .. }
+                    | MetaCommand::RuleDrop(_)
+                    | MetaCommand::RuleDropPrefix(_)
+                    | MetaCommand::RuleEdit { .. }
+                    | MetaCommand::RuleClear(_)
+                    | MetaCommand::RuleRemove { .. }
+                    | MetaCommand::RuleSetRefresh { .. }
+                    | MetaCommand::IndexCreate(_)
+                    | MetaCommand::IndexDrop(_)
+                    | MetaCommand::ClearPrefix(_)
+            )
+    )
+}
+
+/// Whether a read-only replica runs `stmt`: anything a viewer may run,
+/// session-only statements, and maintenance that stays on this server
+fn allowed_on_replica(stmt: &Statement) -> bool {
+    match stmt {
+        Statement::Fact(_) | Statement::TypeDecl(_) | Statement::Analyze(_) => true,
+        Statement::SchemaDecl(decl) => !decl.persistent,
+        Statement::Copy(copy) => copy.direction == CopyDirection::To,
+        Statement::Meta(
+            MetaCommand::Compact
+            | MetaCommand::EncryptionRotate
+            | MetaCommand::LogShow
+            | MetaCommand::LogLevel(_)
+            | MetaCommand::IndexRebuild(_)
+            | MetaCommand::RuleRefresh(_)
+            | MetaCommand::UserList
+            | MetaCommand::ApiKeyList
+            | MetaCommand::ReplicationStatus
+            | MetaCommand::ReplicationPromote
+            | MetaCommand::ClusterStatus
+            | MetaCommand::ShardStatus,
+        ) => true,
+        stmt => authorize_kg_operation(&KgRole::Viewer, stmt).is_ok(),
+    }
+}
+
+/// Where a follower is in its leader's log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Position {
+    log_id: String,
+    /// Next record to apply
+    seq: u64,
+    /// Per knowledge graph, the first record not already in the image it
+    /// was installed from; earlier records are skipped
+    #[serde(default)]
+    floors: BTreeMap<String, u64>,
+}
+
+/// Replication state of this server
+pub struct Replication {
+    config: ReplicationConfig,
+    /// Roles are assigned by the cluster's elections
+    clustered: bool,
+    /// Key presented to the leader: `api_key`, or the cluster secret
+    api_key: Option<String>,
+    log: Option<Arc<ReplicationLog>>,
+    /// Leader being followed; changes with cluster elections
+    leader_url: Mutex<Option<String>>,
+    position_path: PathBuf,
+    position: Mutex<Option<Position>>,
+    /// Knowledge graphs installed from images since the last `snapshot_end`
+    installing: Mutex<BTreeMap<String, u64>>,
+    connected: AtomicBool,
+    applied: AtomicU64,
+    leader_next_seq: AtomicU64,
+    followers: AtomicUsize,
+    /// Signalled when this server starts or stops following, or follows
+    /// another leader
+    changed: Notify,
+}
+
+/// Counts a follower connected to this server until dropped
+pub struct FollowerGuard<'a>(&'a AtomicUsize);
+
+impl Drop for FollowerGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Replication {
+    pub fn new(config: &Config, log: Option<Arc<ReplicationLog>>) -> Self {
+        let position_path = config.storage.data_dir.join(POSITION_FILE);
+        let position = if log.as_ref().is_some_and(|log| log.is_following()) {
+            load_position(&position_path)
+        } else {
+            None
+        };
+        Self {
+            config: config.replication.clone(),
+            clustered: config.cluster.is_enabled(),
+            api_key: config
+                .replication
+                .api_key
+                .clone()
+                .or_else(|| config.cluster.secret.clone()),
+            log,
+            leader_url: Mutex::new(config.replication.leader_url.clone()),
+            position_path,
+            position: Mutex::new(position),
+            installing: Mutex::new(BTreeMap::new()),
+            connected: AtomicBool::new(false),
+            applied: AtomicU64::new(0),
+            leader_next_seq: AtomicU64::new(0),
+            followers: AtomicUsize::new(0),
+            changed: Notify::new(),
+        }
+    }
+
+    /// Whether this server may follow a leader at some point: configured
+    /// as a follower, or a cluster member
+    pub fn may_follow(&self) -> bool {
+        self.log.is_some() && (self.config.role == ReplicationRole::Follower || self.clustered)
+    }
+
+    /// Whether this server follows a leader and rejects writes
+    pub fn is_read_only(&self) -> bool {
+        self.log.as_ref().is_some_and(|log| log.is_following())
+    }
+
+    /// Reject `stmt` if it would change persistent data on a read-only
+    /// replica
+    pub fn check_writable(&self, stmt: &Statement) -> Result<(), String> {
+        if self.is_read_only() && !allowed_on_replica(stmt) {
+            return Err(match self.leader_url.lock().as_deref() {
+                Some(url) => format!("Read-only replica: send writes to the leader at {url}"),
+                None => "Read-only replica: no leader is elected yet".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Stop following and accept writes
+    pub fn promote(&self) -> Result<String, String> {
+        if self.clustered {
+            return Err("cluster members are promoted by election".to_string());
+        }
+        let Some(log) = self.log.as_ref().filter(|log| log.is_following()) else {
+            return Err("this server is not a follower".to_string());
+        };
+        log.set_following(false);
+        self.changed.notify_one();
+        let position = self.position.lock().clone();
+        info!(
+            leader = self.leader_url.lock().as_deref().unwrap_or(""),
+            seq = position.map(|p| p.seq),
+            "replication_promoted"
+        );
+        Ok(
+            "Promoted: this server now accepts writes. Set [replication] role = \"leader\" \
+             before restarting it."
+                .to_string(),
+        )
+    }
+
+    /// Accept writes, having been elected leader of the cluster
+    pub fn lead(&self) {
+        let Some(log) = &self.log else {
+            return;
+        };
+        if log.is_following() {
+            log.set_following(false);
+            *self.leader_url.lock() = None;
+            self.changed.notify_one();
+            info!(log_id = %log.id(), "replication_leading");
+        }
+    }
+
+    /// Reject writes and follow `leader_url`, or no one until a leader is
+    /// known
+    pub fn follow(&self, leader_url: Option<String>) {
+        let Some(log) = &self.log else {
+            return;
+        };
+        let mut current = self.leader_url.lock();
+        if log.is_following() && *current == leader_url {
+            return;
+        }
+        info!(
+            leader = leader_url.as_deref().unwrap_or(""),
+            "replication_following"
+        );
+        *current = leader_url;
+        log.set_following(true);
+        self.changed.notify_one();
+    }
+
+    /// How far this server's data goes: its position in the leader's log
+    /// while following, or the end of its own log while writable
+    pub fn data_position(&self) -> Option<(String, u64)> {
+        let log = self.log.as_ref()?;
+        if log.is_following() {
+            self.position
+                .lock()
+                .as_ref()
+                .map(|position| (position.log_id.clone(), position.seq))
+        } else {
+            Some((log.id(), log.next_seq()))
+        }
+    }
+
+    /// Count a follower streaming from this server
+    pub fn attach_follower(&self) -> FollowerGuard<'_> {
+        self.followers.fetch_add(1, Ordering::Relaxed);
+        FollowerGuard(&self.followers)
+    }
+
+    /// Output of `.replication`
+    pub fn status_lines(&self) -> Vec<String> {
+        let Some(log) = &self.log else {
+            return vec!["Replication is not enabled.".to_string()];
+        };
+        let role = match (self.config.role, log.is_following()) {
+            (_, true) => "follower",
+            _ if self.clustered => "leader (elected)",
+            (ReplicationRole::Follower, false) => "leader (promoted)",
+            _ => "leader",
+        };
+        let (records, bytes) = log.retained();
+        let mut lines = vec![
+            "Replication".to_string(),
+            format!("  Role: {role}"),
+            format!(
+                "  Log: {} (next {}, {records} record(s), {bytes} bytes retained)",
+                log.id(),
+                log.next_seq()
+            ),
+            format!(
+                "  Followers connected: {}",
+                self.followers.load(Ordering::Relaxed)
+            ),
+        ];
+        if self.config.role == ReplicationRole::Follower || (self.clustered && log.is_following()) {
+            let leader = self
+                .leader_url
+                .lock()
+                .clone()
+                .unwrap_or_else(|| "(none elected)".to_string());
+            let connected = self.connected.load(Ordering::Relaxed) && log.is_following();
+            lines.push(format!(
+                "  Leader: {leader} ({})",
+                if connected {
+                    "connected"
+                } else {
+                    "disconnected"
+                }
+            ));
+            match self.position.lock().as_ref() {
+                Some(position) => {
+                    let leader_next = self.leader_next_seq.load(Ordering::Relaxed);
+                    lines.push(format!("  Position: {} {}", position.log_id, position.seq));
+                    lines.push(format!(
+                        "  Lag: {} record(s)",
+                        leader_next.saturating_sub(position.seq)
+                    ));
+                }
+                None => lines.push("  Position: none (catching up from a snapshot)".to_string()),
+            }
+            lines.push(format!(
+                "  Applied: {} record(s) since start",
+                self.applied.load(Ordering::Relaxed)
+            ));
+        }
+        lines
+    }
+
+    /// URL of the leader's replication endpoint, asking for the records
+    /// after the saved position
+    fn stream_url(&self) -> Result<String, String> {
+        let leader = self
+            .leader_url
+            .lock()
+            .clone()
+            .ok_or("replication: no leader to follow")?;
+        let leader = leader.trim_end_matches('/');
+        let base = if let Some(rest) = leader.strip_prefix("https://") {
+            format!("wss://{rest}")
+        } else if let Some(rest) = leader.strip_prefix("http://") {
+            format!("ws://{rest}")
+        } else {
+            leader.to_string()
+        };
+        Ok(match self.position.lock().as_ref() {
+            Some(position) => format!(
+                "{base}{REPLICATION_PATH}?log_id={}&seq={}",
+                position.log_id, position.seq
+            ),
+            None => format!("{base}{REPLICATION_PATH}"),
+        })
+    }
+
+    /// Forget the position before installing images: until `snapshot_end`
+    /// the local data matches no position of the leader's log
+    fn begin_snapshot(&self) {
+        let mut position = self.position.lock();
+        if position.take().is_some() {
+            if let Err(e) = fs::remove_file(&self.position_path) {
+                warn!(error = %e, "replication_position_remove_failed");
+            }
+        }
+    }
+
+    fn installed(&self, kg: &str, seq: u64) {
+        self.installing.lock().insert(kg.to_string(), seq);
+    }
+
+    fn end_snapshot(&self, log_id: String, seq: u64) {
+        let floors = std::mem::take(&mut *self.installing.lock());
+        *self.position.lock() = Some(Position {
+            log_id,
+            seq,
+            floors,
+        });
+        self.save_position();
+    }
+
+    /// Whether `record` still has to be applied here
+    fn should_apply(&self, record: &ReplicationRecord) -> bool {
+        replicated_kg(&record.kg)
+            && self.position.lock().as_ref().is_some_and(|position| {
+                record.seq >= position.seq
+                    && position
+                        .floors
+                        .get(&record.kg)
+                        .is_none_or(|floor| record.seq >= *floor)
+            })
+    }
+
+    fn advance(&self, seq: u64) {
+        if let Some(position) = self.position.lock().as_mut() {
+            position.seq = position.seq.max(seq + 1);
+        }
+    }
+
+    fn save_position(&self) {
+        let mut guard = self.position.lock();
+        let Some(position) = guard.as_mut() else {
+            return;
+        };
+        let seq = position.seq;
+        position.floors.retain(|_, floor| *floor > seq);
+        let saved = serde_json::to_vec(&*position)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| {
+                let tmp = self.position_path.with_extension("json.tmp");
+                fs::write(&tmp, bytes)
+                    .and_then(|()| fs::rename(&tmp, &self.position_path))
+                    .map_err(|e| e.to_string())
+            });
+        if let Err(e) = saved {
+            warn!(error = %e, "replication_position_save_failed");
+        }
+    }
+}
+
+fn load_position(path: &std::path::Path) -> Option<Position> {
+    let bytes = fs::read(path).ok()?;
+    match serde_json::from_slice(&bytes) {
+        Ok(position) => Some(position),
+        Err(e) => {
+            warn!(path = %path.display(), error = %e, "replication_position_invalid");
+            None
+        }
+    }
+}
+
+/// Follow the leader whenever this server is read-only, until shutdown.
+/// Reconnects after `reconnect_ms` when the connection fails, and at once
+/// when the leader changes.
+pub async fn run_follower(handler: Arc<Handler>, mut shutdown: watch::Receiver<bool>) {
+    let replication = Arc::clone(handler.replication());
+    let reconnect = Duration::from_millis(replication.config.reconnect_ms);
+    loop {
+        if !replication.is_read_only() || replication.leader_url.lock().is_none() {
+            // A promoted server stays writable; cluster members wait for
+            // the next election
+            if !replication.clustered && !replication.is_read_only() {
+                break;
+            }
+            tokio::select! {
+                () = replication.changed.notified() => continue,
+                _ = shutdown.changed() => break,
+            }
+        }
+        let followed = tokio::select! {
+            result = follow_leader(&handler, &replication) => result.map(|()| true),
+            () = replication.changed.notified() => Ok(false),
+            _ = shutdown.changed() => break,
+        };
+        replication.connected.store(false, Ordering::Relaxed);
+        match followed {
+            Ok(false) => continue,
+            Ok(true) => {}
+            Err(e) => warn!(error = %e, "replication_follow_failed"),
+        }
+        tokio::select! {
+            () = tokio::time::sleep(reconnect) => {}
+            () = replication.changed.notified() => {}
+            _ = shutdown.changed() => break,
+        }
+    }
+    replication.connected.store(false, Ordering::Relaxed);
+    info!("replication_follower_stopped");
+}
+
+/// One connection to the leader: catch up if needed, then apply records
+/// until the connection ends
+async fn follow_leader(handler: &Handler, replication: &Replication) -> Result<(), String> {
+    let url = replication.stream_url()?;
+    let api_key = replication
+        .api_key
+        .as_deref()
+        .ok_or("replication: a follower needs api_key")?;
+    let mut request = url
+        .as_str()
+        .into_client_request()
+        .map_err(|e| format!("Invalid leader URL '{url}': {e}"))?;
+    let bearer = HeaderValue::from_str(&format!("Bearer {api_key}"))
+        .map_err(|e| format!("Invalid api_key: {e}"))?;
+    request.headers_mut().insert("authorization", bearer);
+    // Images of large knowledge graphs come as single messages
+    let ws_config = WebSocketConfig {
+        max_message_size: None,
+        max_frame_size: None,
+        ..WebSocketConfig::default()
+    };
+    let (mut stream, _) =
+        tokio_tungstenite::connect_async_tls_with_config(request, Some(ws_config), false, None)
+            .await
+            .map_err(|e| format!("Cannot connect to leader: {e}"))?;
+    replication.connected.store(true, Ordering::Relaxed);
+    info!(url = %url, "replication_connected");
+
+    while let Some(frame) = stream.next().await {
+        let text = match frame.map_err(|e| format!("Connection to leader lost: {e}"))? {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let message: ReplicationMessage =
+            serde_json::from_str(&text).map_err(|e| format!("Invalid message from leader: {e}"))?;
+        match message {
+            ReplicationMessage::Snapshot { image, .. } => {
+                replication.begin_snapshot();
+                let (name, seq) = (image.name.clone(), image.seq);
+                handler.install_replication_image(image).await?;
+                replication.installed(&name, seq);
+            }
+            ReplicationMessage::SnapshotEnd {
+                log_id,
+                seq,
+                knowledge_graphs,
+            } => {
+                replication.begin_snapshot();
+                handler
+                    .retain_replicated_knowledge_graphs(&knowledge_graphs)
+                    .await;
+                info!(log_id = %log_id, seq, "replication_snapshot_installed");
+                replication.end_snapshot(log_id, seq);
+            }
+            ReplicationMessage::Records { log_id, records } => {
+                let current = replication
+                    .position
+                    .lock()
+                    .as_ref()
+                    .map(|position| position.log_id.clone());
+                if current.as_deref() != Some(log_id.as_str()) {
+                    return Err(format!("Records of unexpected log {log_id}"));
+                }
+                for record in records {
+                    let seq = record.seq;
+                    if replication.should_apply(&record) {
+                        let kg = record.kg.clone();
+                        if let Err(e) = handler.apply_replication_record(record).await {
+                            warn!(kg = %kg, seq, error = %e, "replication_apply_failed");
+                        }
+                        replication.applied.fetch_add(1, Ordering::Relaxed);
+                    }
+                    replication.advance(seq);
+                }
+                replication.save_position();
+                let next = replication
+                    .position
+                    .lock()
+                    .as_ref()
+                    .map_or(0, |position| position.seq);
+                replication
+                    .leader_next_seq
+                    .fetch_max(next, Ordering::Relaxed);
+            }
+            ReplicationMessage::Heartbeat { next_seq, .. } => {
+                replication
+                    .leader_next_seq
+                    .store(next_seq, Ordering::Relaxed);
+            }
+            ReplicationMessage::Error { message } => return Err(message),
+        }
+        if !replication.is_read_only() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::statement::parse_statement;
+
+    #[test]
+    fn test_replica_statement_classes() {
+        let allowed = [
+            "?edge(X, Y)",
+            "edge(1, 2)",
+            ".kg list",
+            ".rel",
+            ".replication",
+            ".compact",
+            "copy edge to '/tmp/edge.csv'",
+        ];
+        for text in allowed {
+            let stmt = parse_statement(text).unwrap();
+            assert!(allowed_on_replica(&stmt), "{text}");
+        }
+        let rejected = [
+            "+edge(1, 2)",
+            "-edge(1, 2)",
+            "+path(X, Y) <- edge(X, Y)",
+            "+edge(a: int, b: int)",
+            ".kg create other",
+            ".rule drop path",
+            ".user create bob secret editor",
+        ];
+        for text in rejected {
+            let stmt = parse_statement(text).unwrap();
+            assert!(!allowed_on_replica(&stmt), "{text}");
+        }
+
+        assert!(replicated_statement(
+            &parse_statement("+path(X, Y) <- edge(X, Y)").unwrap()
+        ));
+        assert!(replicated_statement(
+            &parse_statement(".rule drop path").unwrap()
+        ));
+        assert!(!replicated_statement(
+            &parse_statement("+edge(1, 2)").unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_records_skipped_below_floors() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage.data_dir = tmp.path().to_path_buf();
+        let log = Arc::new(ReplicationLog::new(1 << 20));
+        log.set_following(true);
+        let replication = Replication::new(&config, Some(log));
+        let record = |seq: u64, kg: &str| ReplicationRecord {
+            seq,
+            kg: kg.to_string(),
+            op: crate::storage_engine::ReplicationOp::CreateKnowledgeGraph,
+        };
+
+        assert!(!replication.should_apply(&record(0, "shop")));
+        replication.begin_snapshot();
+        replication.installed("shop", 7);
+        replication.end_snapshot("log-a".to_string(), 5);
+        assert!(!replication.should_apply(&record(4, "other")));
+        assert!(!replication.should_apply(&record(6, "shop")));
+        assert!(replication.should_apply(&record(6, "other")));
+        assert!(replication.should_apply(&record(7, "shop")));
+        assert!(!replication.should_apply(&record(7, crate::audit::AUDIT_KG)));
+
+        for seq in 5..8 {
+            replication.advance(seq);
+        }
+        replication.save_position();
+        let saved = load_position(&tmp.path().join(POSITION_FILE)).unwrap();
+        assert_eq!(saved.log_id, "log-a");
+        assert_eq!(saved.seq, 8);
+        assert!(saved.floors.is_empty());
+        assert!(replication
+            .stream_url()
+            .is_err_and(|e| e.contains("no leader")));
+    }
+}
@@ -1,8 +1,9 @@
 //! HTTP API Data Transfer Objects
 //!
-//! Defines request/response types for admin endpoints and WebSocket metadata.
+//! Defines request/response types for admin and data endpoints and
+//! WebSocket metadata.
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// JSON response: { success, data?, error? }
 #[derive(Debug, Serialize)]
@@ -72,6 +73,164 @@ pub struct SessionQueryMetadataDto {
     pub warnings: Vec<String>,
 }
 
+/// `POST /query` request
+#[derive(Debug, Deserialize)]
+pub struct QueryRequest {
+    /// Statement or program text
+    pub program: String,
+    /// Knowledge graph to run in (default: the server's default)
+    #[serde(default)]
+    pub knowledge_graph: Option<String>,
+}
+
+/// Result rows of a query or relation read
+#[derive(Debug, Serialize)]
+pub struct QueryResultDto {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+    pub row_count: usize,
+    pub total_count: usize,
+    pub truncated: bool,
+    pub execution_time_ms: u64,
+}
+
+/// `POST /batch` request
+#[derive(Debug, Deserialize)]
+pub struct BatchRequest {
+    /// Writes and view definitions, optionally followed by one query
+    pub statements: Vec<String>,
+    /// Knowledge graph to run in (default: the server's default)
+    #[serde(default)]
+    pub knowledge_graph: Option<String>,
+}
+
+/// Results of a batch, one per statement
+#[derive(Debug, Serialize)]
+pub struct BatchResultDto {
+    pub results: Vec<QueryResultDto>,
+    /// Summary of the commit; absent for a batch with only a query
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    pub execution_time_ms: u64,
+}
+
+/// `POST /cursors` request
+#[derive(Debug, Deserialize)]
+pub struct OpenCursorRequest {
+    /// Statement or program text
+    pub program: String,
+    /// Knowledge graph to run in (default: the server's default)
+    #[serde(default)]
+    pub knowledge_graph: Option<String>,
+    /// Rows per page (default 1000, capped by `http.cursor_max_page_rows`)
+    #[serde(default)]
+    pub page_size: usize,
+}
+
+/// `?page_size=` of `GET /cursors/{id}`
+#[derive(Debug, Default, Deserialize)]
+pub struct FetchCursorParams {
+    #[serde(default)]
+    pub page_size: usize,
+}
+
+/// One page of a cursor. `cursor_id` is absent on the last page.
+#[derive(Debug, Serialize)]
+pub struct CursorPageDto {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor_id: Option<String>,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+    pub row_count: usize,
+    /// Position of the first row in the whole result
+    pub offset: usize,
+    pub total_count: usize,
+    pub truncated: bool,
+    pub execution_time_ms: u64,
+}
+
+/// `POST /knowledge-graphs` request
+#[derive(Debug, Deserialize)]
+pub struct CreateKnowledgeGraphRequest {
+    pub name: String,
+}
+
+/// Relation in a knowledge graph listing
+#[derive(Debug, Serialize)]
+pub struct RelationDto {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub tuple_count: usize,
+}
+
+/// Body of the fact insert and delete endpoints: one array per fact
+#[derive(Debug, Deserialize)]
+pub struct FactsRequest {
+    pub facts: Vec<Vec<serde_json::Value>>,
+}
+
+/// Result of a fact insert
+#[derive(Debug, Serialize)]
+pub struct InsertFactsDto {
+    pub inserted: usize,
+    pub duplicates: usize,
+    pub quarantined: usize,
+    pub replaced: usize,
+}
+
+/// Result of a fact delete
+#[derive(Debug, Serialize)]
+pub struct DeleteFactsDto {
+    pub deleted: usize,
+}
+
+/// View (persistent rule) in a knowledge graph
+#[derive(Debug, Serialize)]
+pub struct ViewDto {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub definition: Option<String>,
+}
+
+/// `POST /knowledge-graphs/{kg}/views` request
+#[derive(Debug, Deserialize)]
+pub struct CreateViewRequest {
+    /// Rule text, e.g. `+path(X, Y) <- edge(X, Y)`
+    pub rule: String,
+}
+
+/// Message returned by management endpoints
+#[derive(Debug, Serialize)]
+pub struct MessageDto {
+    pub message: String,
+}
+
+/// `POST /sessions` request
+#[derive(Debug, Default, Deserialize)]
+pub struct CreateSessionRequest {
+    /// Knowledge graph the session starts in (default: the server's default)
+    #[serde(default)]
+    pub knowledge_graph: Option<String>,
+}
+
+/// A session, from `POST /sessions` and `GET /sessions/{id}`
+#[derive(Debug, Serialize)]
+pub struct SessionDto {
+    /// Token to send as `X-Session-Id`
+    pub session_id: String,
+    /// Current knowledge graph (`.kg use` switches it)
+    pub knowledge_graph: String,
+    /// `.session set timeout_ms`, if set
+    pub timeout_ms: Option<u64>,
+    /// `.session set max_rows`, if set
+    pub max_rows: Option<usize>,
+    pub session_facts: usize,
+    pub session_rules: usize,
+    pub prepared_statements: usize,
+    /// Whether a transaction is open (`begin` without `commit`)
+    pub in_transaction: bool,
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
@@ -3,17 +3,23 @@
 //! Provides error types and conversions for the HTTP handlers.
 
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde::Serialize;
 
+use crate::protocol::cursor::CursorError;
+use crate::protocol::throttle::{ThrottleLimit, Throttled};
+
 /// API error response
 #[derive(Debug, Serialize)]
 pub struct ApiError {
     pub code: String,
     pub message: String,
+    /// Which per-client limit refused the request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub throttle: Option<Throttled>,
 }
 
 impl ApiError {
@@ -21,6 +27,7 @@ impl ApiError {
         Self {
             code: code.into(),
             message: message.into(),
+            throttle: None,
         }
     }
 
@@ -66,21 +73,74 @@ impl RestError {
         }
     }
 
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::UNAUTHORIZED,
+            error: ApiError::new("UNAUTHORIZED", message),
+        }
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::FORBIDDEN,
+            error: ApiError::new("FORBIDDEN", message),
+        }
+    }
+
     pub fn service_unavailable(message: impl Into<String>) -> Self {
         Self {
             status: StatusCode::SERVICE_UNAVAILABLE,
             error: ApiError::new("SERVICE_UNAVAILABLE", message),
         }
     }
+
+    /// 429 for concurrency and rate limits, 413 for results over the size
+    /// limit, 503 while the server drains, with the limit in the body
+    pub fn throttled(throttled: Throttled) -> Self {
+        let status = match throttled.limit {
+            ThrottleLimit::ResultBytes => StatusCode::PAYLOAD_TOO_LARGE,
+            ThrottleLimit::ShuttingDown => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::TOO_MANY_REQUESTS,
+        };
+        let mut error = ApiError::new("THROTTLED", throttled.to_string());
+        error.throttle = Some(throttled);
+        Self { status, error }
+    }
+}
+
+impl From<CursorError> for RestError {
+    /// 404 for unknown cursors, 429 for too many open ones
+    fn from(err: CursorError) -> Self {
+        match err {
+            CursorError::NotFound(_) => Self::not_found(err.to_string()),
+            CursorError::TooMany { .. } => Self {
+                status: StatusCode::TOO_MANY_REQUESTS,
+                error: ApiError::new("TOO_MANY_CURSORS", err.to_string()),
+            },
+            CursorError::Disabled => Self::bad_request(err.to_string()),
+        }
+    }
 }
 
 impl IntoResponse for RestError {
     fn into_response(self) -> Response {
+        let retry_after = self
+            .error
+            .throttle
+            .as_ref()
+            .and_then(|t| t.retry_after_ms)
+            .map(|ms| ms.div_ceil(1000));
         let body = Json(serde_json::json!({
             "success": false,
             "error": self.error
         }));
-        (self.status, body).into_response()
+        let mut response = (self.status, body).into_response();
+        if let Some(secs) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        response
     }
 }
 
@@ -137,6 +197,20 @@ mod tests {
         assert_eq!(err.status, StatusCode::BAD_REQUEST);
     }
 
+    #[test]
+    fn test_rest_error_forbidden_status() {
+        let err = RestError::forbidden("no");
+        assert_eq!(err.status, StatusCode::FORBIDDEN);
+        assert_eq!(err.error.code, "FORBIDDEN");
+    }
+
+    #[test]
+    fn test_rest_error_unauthorized_status() {
+        let err = RestError::unauthorized("bad key");
+        assert_eq!(err.status, StatusCode::UNAUTHORIZED);
+        assert_eq!(err.error.code, "UNAUTHORIZED");
+    }
+
     #[test]
     fn test_rest_error_internal_status() {
         let err = RestError::internal("oops");
@@ -158,6 +232,43 @@ mod tests {
         assert!(json.contains("\"message\":\"test\""));
     }
 
+    #[test]
+    fn test_rest_error_throttled() {
+        let err = RestError::throttled(Throttled {
+            limit: ThrottleLimit::StatementsPerSec,
+            max: 10,
+            retry_after_ms: Some(250),
+        });
+        assert_eq!(err.status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(err.error.code, "THROTTLED");
+        let json = serde_json::to_value(&err.error).unwrap();
+        assert_eq!(json["throttle"]["limit"], "statements_per_sec");
+        assert_eq!(json["throttle"]["max"], 10);
+        let response = err.into_response();
+        assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+
+        let too_big = RestError::throttled(Throttled {
+            limit: ThrottleLimit::ResultBytes,
+            max: 100,
+            retry_after_ms: None,
+        });
+        assert_eq!(too_big.status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(too_big
+            .into_response()
+            .headers()
+            .get(header::RETRY_AFTER)
+            .is_none());
+    }
+
+    #[test]
+    fn test_rest_error_from_cursor_error() {
+        let err: RestError = CursorError::NotFound("c1".to_string()).into();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+        let err: RestError = CursorError::TooMany { max: 4 }.into();
+        assert_eq!(err.status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(err.error.code, "TOO_MANY_CURSORS");
+    }
+
     #[test]
     fn test_rest_error_into_response() {
         let err = RestError::not_found("gone");
@@ -1,10 +1,14 @@
 //! Admin Handlers
 //!
-//! Health check and statistics endpoints.
+//! Health check, statistics and Prometheus metrics endpoints.
 
 use std::sync::Arc;
 
-use axum::{http::StatusCode, Extension, Json};
+use axum::{
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
 
 use crate::protocol::rest::dto::{ApiResponse, HealthDto, SessionStatsDto, StatsDto};
 use crate::protocol::rest::error::RestError;
@@ -64,8 +68,12 @@ pub async fn liveness() -> StatusCode {
 /// Readiness probe: returns 200 if the server can handle requests.
 ///
 /// Checks that the storage engine is accessible (read lock can be acquired).
-/// Returns 503 if the server is not ready to handle requests.
+/// Returns 503 if the server is not ready to handle requests, including
+/// while it drains for shutdown, so load balancers stop routing to it.
 pub async fn readiness(Extension(handler): Extension<Arc<Handler>>) -> StatusCode {
+    if handler.is_draining() {
+        return StatusCode::SERVICE_UNAVAILABLE;
+    }
     let handler_clone = Arc::clone(&handler);
     let storage_ok = tokio::task::spawn_blocking(move || {
         handler_clone
@@ -155,10 +163,28 @@ pub async fn stats(
     Ok(Json(ApiResponse::success(stats)))
 }
 
+/// Metrics endpoint (`/metrics`).
+///
+/// Prometheus scrapers ask for `text/plain` or OpenMetrics and get the text
+/// exposition format; other clients get the JSON statistics of [`stats`].
+pub async fn metrics(Extension(handler): Extension<Arc<Handler>>, headers: HeaderMap) -> Response {
+    let wants_text = headers
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| {
+            accept.contains("text/plain") || accept.contains("application/openmetrics-text")
+        });
+    if wants_text {
+        prometheus_metrics(Extension(handler)).await.into_response()
+    } else {
+        stats(Extension(handler)).await.into_response()
+    }
+}
+
 /// Prometheus metrics endpoint (#12).
 ///
 /// Exports server metrics in Prometheus text exposition format.
-/// Scrape at `/metrics/prometheus` with a Prometheus server.
+/// Scrape at `/metrics/prometheus` (or `/metrics`) with a Prometheus server.
 pub async fn prometheus_metrics(
     Extension(handler): Extension<Arc<Handler>>,
 ) -> Result<
@@ -180,22 +206,27 @@ pub async fn prometheus_metrics(
             let mut total_relations = 0usize;
             let mut total_views = 0usize;
             let mut total_tuples: u64 = 0;
+            let mut kg_tuples = Vec::with_capacity(kgs.len());
             for kg_name in &kgs {
+                let mut tuples: u64 = 0;
                 if let Ok(relations) = storage.list_relations_in(kg_name) {
                     total_relations += relations.len();
                     for rel_name in &relations {
                         if let Ok(Some((_schema, count))) =
                             storage.get_relation_metadata_in(kg_name, rel_name)
                         {
-                            total_tuples += count as u64;
+                            tuples += count as u64;
                         }
                     }
                 }
                 if let Ok(rules) = storage.list_rules_in(kg_name) {
                     total_views += rules.len();
                 }
+                total_tuples += tuples;
+                kg_tuples.push((kg_name.clone(), tuples));
             }
             let estimated_memory = total_tuples.saturating_mul(64);
+            let wal_lag = storage.wal_lag();
             drop(storage);
 
             let session_stats = handler.session_stats();
@@ -272,7 +303,125 @@ pub async fn prometheus_metrics(
                 session_stats.total_ephemeral_rules
             ));
 
+            let intern = crate::value::intern::get_intern_stats();
+            out.push_str(
+                "# HELP inputlayer_interned_strings Distinct strings in the interning pool.\n",
+            );
+            out.push_str("# TYPE inputlayer_interned_strings gauge\n");
+            out.push_str(&format!("inputlayer_interned_strings {}\n", intern.entries));
+
+            out.push_str(
+                "# HELP inputlayer_interned_bytes Bytes of string data in the interning pool.\n",
+            );
+            out.push_str("# TYPE inputlayer_interned_bytes gauge\n");
+            out.push_str(&format!("inputlayer_interned_bytes {}\n", intern.bytes));
+
+            out.push_str(
+                "# HELP inputlayer_intern_hits_total String loads that reused a pooled string.\n",
+            );
+            out.push_str("# TYPE inputlayer_intern_hits_total counter\n");
+            out.push_str(&format!("inputlayer_intern_hits_total {}\n", intern.hits));
+
+            out.push_str(
+                "# HELP inputlayer_intern_misses_total String loads that added a pooled string.\n",
+            );
+            out.push_str("# TYPE inputlayer_intern_misses_total counter\n");
+            out.push_str(&format!(
+                "inputlayer_intern_misses_total {}\n",
+                intern.misses
+            ));
+
+            let lsh = crate::vector_ops::get_lsh_cache_stats();
+            out.push_str(
+                "# HELP inputlayer_lsh_cache_hits_total LSH hyperplane lookups served from the cache.\n",
+            );
+            out.push_str("# TYPE inputlayer_lsh_cache_hits_total counter\n");
+            out.push_str(&format!("inputlayer_lsh_cache_hits_total {}\n", lsh.hits));
+
+            out.push_str(
+                "# HELP inputlayer_lsh_cache_misses_total LSH hyperplane lookups that generated new hyperplanes.\n",
+            );
+            out.push_str("# TYPE inputlayer_lsh_cache_misses_total counter\n");
+            out.push_str(&format!(
+                "inputlayer_lsh_cache_misses_total {}\n",
+                lsh.misses
+            ));
+
+            crate::metrics::write_labeled_gauge(
+                &mut out,
+                "inputlayer_knowledge_graph_tuples",
+                "Stored tuples per knowledge graph.",
+                "knowledge_graph",
+                kg_tuples,
+            );
+
+            crate::metrics::write_metric(
+                &mut out,
+                "inputlayer_wal_pending_updates",
+                "Updates in the write-ahead log not yet written to batch files.",
+                "gauge",
+                wal_lag.pending_updates,
+            );
+            crate::metrics::write_metric(
+                &mut out,
+                "inputlayer_wal_size_bytes",
+                "Write-ahead log size on disk.",
+                "gauge",
+                wal_lag.wal_bytes,
+            );
+            crate::metrics::write_metric(
+                &mut out,
+                "inputlayer_wal_segments",
+                "Sealed write-ahead log segments.",
+                "gauge",
+                wal_lag.wal_segments,
+            );
+
+            let admission = handler.admission().stats();
+            crate::metrics::write_metric(
+                &mut out,
+                "inputlayer_queries_running",
+                "Queries holding an execution slot.",
+                "gauge",
+                admission.running,
+            );
+            crate::metrics::write_metric(
+                &mut out,
+                "inputlayer_queries_queued",
+                "Queries waiting for an execution slot.",
+                "gauge",
+                admission.queued,
+            );
+            crate::metrics::write_labeled_gauge(
+                &mut out,
+                "inputlayer_knowledge_graph_queries_queued",
+                "Queries waiting for an execution slot per knowledge graph.",
+                "knowledge_graph",
+                admission.queued_by_knowledge_graph,
+            );
+            crate::metrics::write_labeled_gauge(
+                &mut out,
+                "inputlayer_priority_queries_queued",
+                "Queries waiting for an execution slot per priority class.",
+                "priority",
+                admission
+                    .queued_by_priority
+                    .into_iter()
+                    .map(|(priority, queued)| (priority.to_string(), queued)),
+            );
+
+            if let Some(resident) = crate::metrics::process_resident_bytes() {
+                crate::metrics::write_metric(
+                    &mut out,
+                    "inputlayer_process_resident_memory_bytes",
+                    "Resident memory of the server process.",
+                    "gauge",
+                    resident,
+                );
+            }
+
             out.push_str(&handler.timing_histograms().format_prometheus());
+            crate::metrics::metrics().format_prometheus(&mut out);
 
             out
         }),
@@ -395,6 +544,47 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_prometheus_metrics_per_kg_and_wal() {
+        let (handler, _tmp) = make_handler();
+        handler
+            .query_program(None, "+prom_test[(1,), (2,)]".to_string())
+            .await
+            .unwrap();
+        let (status, _, body) = prometheus_metrics(Extension(handler)).await.unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("inputlayer_knowledge_graph_tuples{knowledge_graph=\"default\"} 2\n"));
+        assert!(body.contains("# TYPE inputlayer_wal_pending_updates gauge"));
+        assert!(body.contains("# TYPE inputlayer_wal_appends_total counter"));
+        assert!(body.contains("inputlayer_query_queue_wait_seconds_count"));
+        assert!(body.contains("inputlayer_queries_queued 0"));
+        assert!(body.contains("inputlayer_priority_queries_queued{priority=\"interactive\"} 0"));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_negotiates_format() {
+        let (handler, _tmp) = make_handler();
+        let content_type = |resp: &Response| {
+            resp.headers()[header::CONTENT_TYPE]
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+
+        let mut prometheus = HeaderMap::new();
+        prometheus.insert(
+            header::ACCEPT,
+            "application/openmetrics-text;version=1.0.0,text/plain;version=0.0.4;q=0.5"
+                .parse()
+                .unwrap(),
+        );
+        let resp = metrics(Extension(Arc::clone(&handler)), prometheus).await;
+        assert!(content_type(&resp).starts_with("text/plain"));
+
+        let resp = metrics(Extension(handler), HeaderMap::new()).await;
+        assert!(content_type(&resp).starts_with("application/json"));
+    }
+
     /// P1: Liveness probe always returns 200 (even under load).
     #[tokio::test]
     async fn test_liveness_always_200() {
@@ -0,0 +1,212 @@
+//! Change Data Capture Handler
+//!
+//! `GET /v1/changes` upgrades to a WebSocket that streams the changes
+//! committed from then on (see [`crate::storage_engine::ChangeFeed`]). Only
+//! admin API keys may subscribe.
+//!
+//! `?kg=...` limits the stream to one knowledge graph and
+//! `?relations=a,b` to some of its relations. Each commit is one message:
+//!
+//! ```text
+//! {"type":"changes","txn":42,"knowledge_graph":"shop","changes":[{"txn":42,...,"diff":1,"tuple":[1,"tea"]}]}
+//! ```
+//!
+//! A subscriber that falls more than `[cdc] subscriber_buffer` commits
+//! behind is sent an `error` message and disconnected; it reloads the
+//! relations and subscribes again.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket},
+        Query, WebSocketUpgrade,
+    },
+    response::IntoResponse,
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{info, warn};
+
+use crate::auth::{AuthIdentity, Role};
+use crate::protocol::replication::HEARTBEAT_INTERVAL;
+use crate::protocol::rest::error::RestError;
+use crate::protocol::Handler;
+use crate::storage_engine::ChangeEvent;
+
+/// Query parameters of `GET /changes`
+#[derive(Debug, Default, Deserialize)]
+pub struct SubscribeParams {
+    /// Only changes to this knowledge graph
+    pub kg: Option<String>,
+    /// Only changes to these comma-separated relations
+    pub relations: Option<String>,
+}
+
+/// Message sent to a subscriber
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ChangeMessage<'a> {
+    /// The changes of one commit
+    Changes {
+        txn: u64,
+        knowledge_graph: &'a str,
+        changes: Vec<&'a ChangeEvent>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// Stream committed changes to a subscriber
+pub async fn subscribe(
+    Extension(handler): Extension<Arc<Handler>>,
+    Extension(identity): Extension<AuthIdentity>,
+    Query(params): Query<SubscribeParams>,
+    ws: WebSocketUpgrade,
+) -> Result<impl IntoResponse, RestError> {
+    if identity.role != Role::Admin {
+        return Err(RestError::forbidden(
+            "Only admin API keys can subscribe to changes",
+        ));
+    }
+    let feed = handler
+        .get_storage()
+        .change_feed()
+        .cloned()
+        .ok_or_else(|| {
+            RestError::bad_request("Change data capture is not enabled on this server")
+        })?;
+    if let Some(kg) = &params.kg {
+        if !feed.publishes(kg) {
+            return Err(RestError::bad_request(format!(
+                "Changes to knowledge graph '{kg}' are not published"
+            )));
+        }
+    }
+    // Subscribed before the upgrade, so nothing committed after the
+    // response is missed
+    let changes = feed.subscribe();
+    info!(username = %identity.username, kg = ?params.kg, "cdc_subscriber_connected");
+    Ok(ws.on_upgrade(move |socket| async move {
+        if let Err(e) = stream_changes(&handler, changes, socket, &params).await {
+            warn!(error = %e, "cdc_stream_ended");
+        }
+        info!(username = %identity.username, "cdc_subscriber_disconnected");
+    }))
+}
+
+async fn send(socket: &mut WebSocket, message: &ChangeMessage<'_>) -> Result<(), String> {
+    let text = serde_json::to_string(message).map_err(|e| e.to_string())?;
+    socket
+        .send(Message::Text(text))
+        .await
+        .map_err(|e| format!("Subscriber connection lost: {e}"))
+}
+
+/// The events of `commit` that `params` asks for
+fn select<'a>(commit: &'a [ChangeEvent], params: &SubscribeParams) -> Vec<&'a ChangeEvent> {
+    let relations: Option<Vec<&str>> = params
+        .relations
+        .as_deref()
+        .map(|list| list.split(',').map(str::trim).collect());
+    commit
+        .iter()
+        .filter(|event| {
+            params
+                .kg
+                .as_deref()
+                .is_none_or(|kg| event.knowledge_graph == kg)
+                && relations
+                    .as_ref()
+                    .is_none_or(|relations| relations.contains(&event.relation.as_str()))
+        })
+        .collect()
+}
+
+async fn stream_changes(
+    handler: &Arc<Handler>,
+    mut changes: tokio::sync::broadcast::Receiver<Arc<Vec<ChangeEvent>>>,
+    mut socket: WebSocket,
+    params: &SubscribeParams,
+) -> Result<(), String> {
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    loop {
+        tokio::select! {
+            received = changes.recv() => match received {
+                Ok(commit) => {
+                    let selected = select(&commit, params);
+                    let Some(&first) = selected.first() else {
+                        continue;
+                    };
+                    let message = ChangeMessage::Changes {
+                        txn: first.txn,
+                        knowledge_graph: &first.knowledge_graph,
+                        changes: selected,
+                    };
+                    send(&mut socket, &message).await?;
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    let message = format!(
+                        "Fell {missed} commits behind; reload the relations and subscribe again"
+                    );
+                    send(&mut socket, &ChangeMessage::Error { message }).await?;
+                    return Err(format!("subscriber lagged by {missed} commits"));
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            },
+            _ = heartbeat.tick() => {
+                if handler.is_draining() {
+                    let _ = socket.send(Message::Close(None)).await;
+                    return Ok(());
+                }
+                if socket.send(Message::Ping(Vec::new())).await.is_err() {
+                    return Ok(());
+                }
+            }
+            incoming = socket.recv() => match incoming {
+                None | Some(Err(_) | Ok(Message::Close(_))) => return Ok(()),
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::value::{Tuple, Value};
+
+    fn event(kg: &str, relation: &str) -> ChangeEvent {
+        ChangeEvent {
+            txn: 7,
+            time: 1_700_000_000_000,
+            knowledge_graph: kg.to_string(),
+            relation: relation.to_string(),
+            diff: 1,
+            tuple: Tuple::new(vec![Value::Int64(1)]),
+        }
+    }
+
+    #[test]
+    fn test_select_filters_by_kg_and_relation() {
+        let commit = vec![event("shop", "item"), event("shop", "order")];
+        assert_eq!(select(&commit, &SubscribeParams::default()).len(), 2);
+
+        let params = SubscribeParams {
+            kg: Some("other".to_string()),
+            relations: None,
+        };
+        assert!(select(&commit, &params).is_empty());
+
+        let params = SubscribeParams {
+            kg: Some("shop".to_string()),
+            relations: Some("order, invoice".to_string()),
+        };
+        let selected = select(&commit, &params);
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].relation, "order");
+    }
+}
@@ -0,0 +1,49 @@
+//! Cluster Handlers
+//!
+//! `POST /v1/cluster/vote` and `POST /v1/cluster/append` carry the Raft
+//! requests members send each other (see [`crate::protocol::cluster`]).
+//! They are authenticated with the cluster's shared secret rather than an
+//! API key, so members can elect a leader before any user exists.
+
+use std::sync::Arc;
+
+use axum::{http::HeaderMap, Extension, Json};
+
+use crate::protocol::cluster::{AppendRequest, AppendResponse, Cluster, VoteRequest, VoteResponse};
+use crate::protocol::rest::error::RestError;
+use crate::protocol::Handler;
+
+/// The cluster, if the request carries its secret
+fn authorize<'a>(handler: &'a Handler, headers: &HeaderMap) -> Result<&'a Arc<Cluster>, RestError> {
+    let cluster = handler
+        .cluster()
+        .ok_or_else(|| RestError::not_found("This server is not part of a cluster"))?;
+    let token = headers
+        .get("authorization")
+        .and_then(|header| header.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match token {
+        Some(token) if cluster.accepts(token) => Ok(cluster),
+        _ => Err(RestError::unauthorized("Invalid cluster secret")),
+    }
+}
+
+/// A candidate asks for this member's vote
+pub async fn vote(
+    Extension(handler): Extension<Arc<Handler>>,
+    headers: HeaderMap,
+    Json(request): Json<VoteRequest>,
+) -> Result<Json<VoteResponse>, RestError> {
+    let cluster = authorize(&handler, &headers)?;
+    Ok(Json(cluster.handle_vote(&request)))
+}
+
+/// The leader sends metadata entries, or a heartbeat without any
+pub async fn append(
+    Extension(handler): Extension<Arc<Handler>>,
+    headers: HeaderMap,
+    Json(request): Json<AppendRequest>,
+) -> Result<Json<AppendResponse>, RestError> {
+    let cluster = authorize(&handler, &headers)?;
+    Ok(Json(cluster.handle_append(&request)))
+}
@@ -0,0 +1,675 @@
+//! Data Handlers
+//!
+//! HTTP endpoints for clients that do not speak the WebSocket protocol:
+//! running statements, writing facts as JSON, managing knowledge graphs and
+//! views, reading results as JSON or CSV, running atomic batches of
+//! statements, and paging through large results with cursors.
+//!
+//! `POST /query` and `POST /batch` run in a session when given its ID in
+//! the `X-Session-Id` header (see [`super::sessions`]).
+//!
+//! Every request runs with the identity of its API key (added by the auth
+//! middleware), through the same role and per-KG ACL checks as WebSocket
+//! statements. Management endpoints build meta commands (`.kg create`,
+//! `.rule drop`, ...) from validated names and run them like any statement.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::Deserialize;
+
+use super::{json_tuples_to_tuples_with_limits, sessions, wire_value_to_json};
+use crate::auth::{AuthIdentity, INTERNAL_KG};
+use crate::protocol::cursor::CursorPage;
+use crate::protocol::rest::dto::{
+    ApiResponse, BatchRequest, BatchResultDto, CreateKnowledgeGraphRequest, CreateViewRequest,
+    CursorPageDto, DeleteFactsDto, FactsRequest, FetchCursorParams, InsertFactsDto, MessageDto,
+    OpenCursorRequest, QueryRequest, QueryResultDto, RelationDto, ViewDto,
+};
+use crate::protocol::rest::error::RestError;
+use crate::protocol::throttle::client_key;
+use crate::protocol::wire::QueryResult;
+use crate::protocol::Handler;
+use crate::session::SessionId;
+use crate::storage::csv::escape_csv_field;
+use crate::storage::CsvOptions;
+
+/// `?format=` of endpoints returning rows
+#[derive(Debug, Default, Deserialize)]
+pub struct FormatParams {
+    /// `json` (default) or `csv`
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
+/// Whether rows should be sent as CSV: `?format=csv`, or an `Accept` header
+/// asking for `text/csv` without a format parameter
+fn wants_csv(params: &FormatParams, headers: &HeaderMap) -> Result<bool, RestError> {
+    match params.format.as_deref() {
+        Some(format) if format.eq_ignore_ascii_case("csv") => Ok(true),
+        Some(format) if format.eq_ignore_ascii_case("json") => Ok(false),
+        Some(format) => Err(RestError::bad_request(format!(
+            "Unsupported format '{format}' (expected json or csv)"
+        ))),
+        None => Ok(headers
+            .get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .is_some_and(|accept| accept.contains("text/csv"))),
+    }
+}
+
+/// Map a handler error to a response: access errors are 403, the rest are
+/// problems with the request
+fn handler_error(message: String) -> RestError {
+    if message.starts_with("Access denied") || message.starts_with("Permission denied") {
+        RestError::forbidden(message)
+    } else {
+        RestError::bad_request(message)
+    }
+}
+
+/// Check a knowledge graph name before it is put into a command
+fn validate_kg_name(name: &str) -> Result<(), RestError> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(RestError::bad_request(format!(
+            "Invalid knowledge graph name '{name}'"
+        )))
+    }
+}
+
+/// Knowledge graph a request runs in: the one it names, else the
+/// session's current one, else the server's default
+fn request_kg(
+    handler: &Handler,
+    kg: Option<String>,
+    session: Option<&SessionId>,
+) -> Result<Option<String>, RestError> {
+    let kg = match (kg, session) {
+        (None, Some(_)) => return Ok(None),
+        (kg, _) => kg.unwrap_or_else(|| handler.config().storage.default_knowledge_graph.clone()),
+    };
+    validate_kg_name(&kg)?;
+    Ok(Some(kg))
+}
+
+/// Check a relation or view name before it is put into a statement:
+/// a lowercase letter followed by letters, digits and underscores
+fn validate_relation_name(name: &str) -> Result<(), RestError> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_lowercase())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(RestError::bad_request(format!(
+            "Invalid relation name '{name}'"
+        )))
+    }
+}
+
+/// Run `program` in `kg` as `identity`, within the caller's per-client
+/// limits
+async fn run(
+    handler: &Handler,
+    kg: String,
+    program: String,
+    identity: &AuthIdentity,
+) -> Result<QueryResult, RestError> {
+    run_in(handler, None, Some(kg), program, identity).await
+}
+
+/// [`run`] in `session`, if given, and in its current knowledge graph
+/// unless `kg` names another
+async fn run_in(
+    handler: &Handler,
+    session: Option<&SessionId>,
+    kg: Option<String>,
+    program: String,
+    identity: &AuthIdentity,
+) -> Result<QueryResult, RestError> {
+    let result = run_unsized(handler, session, kg, program, identity).await?;
+    handler
+        .check_result_size(&result)
+        .map_err(RestError::throttled)?;
+    Ok(result)
+}
+
+/// `run_in` without the result size limit, for results read through a
+/// cursor whose pages are checked instead
+async fn run_unsized(
+    handler: &Handler,
+    session: Option<&SessionId>,
+    kg: Option<String>,
+    program: String,
+    identity: &AuthIdentity,
+) -> Result<QueryResult, RestError> {
+    let _permit = handler
+        .admit_program(&client_key(identity, None), &program)
+        .map_err(RestError::throttled)?;
+    handler
+        .execute_program(session, kg, program, Some(identity))
+        .await
+        .map_err(handler_error)
+}
+
+/// Text of a message result (`.kg create` and friends)
+fn message_of(result: QueryResult) -> String {
+    result
+        .rows
+        .into_iter()
+        .next()
+        .and_then(|row| row.values.into_iter().next())
+        .map(|value| match wire_value_to_json(value) {
+            serde_json::Value::String(s) => s,
+            other => other.to_string(),
+        })
+        .unwrap_or_default()
+}
+
+/// Render a result as CSV with a header row
+fn to_csv(result: QueryResult) -> String {
+    let options = CsvOptions::default();
+    let mut out = result
+        .schema
+        .iter()
+        .map(|column| escape_csv_field(&column.name, &options))
+        .collect::<Vec<_>>()
+        .join(",");
+    out.push('\n');
+    for row in result.rows {
+        let fields: Vec<String> = row
+            .values
+            .into_iter()
+            .map(|value| match wire_value_to_json(value) {
+                serde_json::Value::Null => String::new(),
+                serde_json::Value::String(s) => escape_csv_field(&s, &options),
+                other => escape_csv_field(&other.to_string(), &options),
+            })
+            .collect();
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// Send a result as JSON or CSV
+fn respond(result: QueryResult, csv: bool) -> Response {
+    if csv {
+        return (
+            [(header::CONTENT_TYPE, "text/csv; charset=utf-8")],
+            to_csv(result),
+        )
+            .into_response();
+    }
+    Json(ApiResponse::success(result_dto(result))).into_response()
+}
+
+/// JSON form of a result, as returned by `POST /query`
+pub(crate) fn result_dto(result: QueryResult) -> QueryResultDto {
+    let columns = result.schema.iter().map(|c| c.name.clone()).collect();
+    let rows: Vec<Vec<serde_json::Value>> = result
+        .rows
+        .into_iter()
+        .map(|row| row.values.into_iter().map(wire_value_to_json).collect())
+        .collect();
+    QueryResultDto {
+        columns,
+        row_count: rows.len(),
+        rows,
+        total_count: result.total_count,
+        truncated: result.truncated,
+        execution_time_ms: result.execution_time_ms,
+    }
+}
+
+/// Execute a statement or program: `POST /query`
+pub async fn query(
+    Extension(handler): Extension<Arc<Handler>>,
+    Extension(identity): Extension<AuthIdentity>,
+    Query(params): Query<FormatParams>,
+    headers: HeaderMap,
+    Json(request): Json<QueryRequest>,
+) -> Result<Response, RestError> {
+    let csv = wants_csv(&params, &headers)?;
+    let session = sessions::from_headers(&handler, &headers, &identity)?;
+    let kg = request_kg(&handler, request.knowledge_graph, session.as_ref())?;
+    let result = run_in(&handler, session.as_ref(), kg, request.program, &identity).await?;
+    Ok(respond(result, csv))
+}
+
+/// Run writes, view definitions and a final query atomically in one round
+/// trip: `POST /batch`
+pub async fn batch(
+    Extension(handler): Extension<Arc<Handler>>,
+    Extension(identity): Extension<AuthIdentity>,
+    headers: HeaderMap,
+    Json(request): Json<BatchRequest>,
+) -> Result<Json<ApiResponse<BatchResultDto>>, RestError> {
+    let session = sessions::from_headers(&handler, &headers, &identity)?;
+    let kg = request_kg(&handler, request.knowledge_graph, session.as_ref())?;
+    let _permit = handler
+        .admit(&client_key(&identity, None), request.statements.len())
+        .map_err(RestError::throttled)?;
+    let batch = handler
+        .execute_batch(session.as_ref(), kg, request.statements, Some(&identity))
+        .await
+        .map_err(handler_error)?;
+    handler
+        .check_batch_size(&batch)
+        .map_err(RestError::throttled)?;
+    Ok(Json(ApiResponse::success(BatchResultDto {
+        results: batch.results.into_iter().map(result_dto).collect(),
+        commit: batch.commit,
+        execution_time_ms: batch.execution_time_ms,
+    })))
+}
+
+/// Send a cursor page, checked against the result size limit
+fn respond_page(handler: &Handler, page: CursorPage) -> Result<Response, RestError> {
+    handler
+        .check_page_size(&page)
+        .map_err(RestError::throttled)?;
+    let rows: Vec<Vec<serde_json::Value>> = page
+        .rows
+        .into_iter()
+        .map(|row| row.values.into_iter().map(wire_value_to_json).collect())
+        .collect();
+    let dto = CursorPageDto {
+        cursor_id: page.cursor_id,
+        columns: page.schema.into_iter().map(|c| c.name).collect(),
+        row_count: rows.len(),
+        rows,
+        offset: page.offset,
+        total_count: page.total_count,
+        truncated: page.truncated,
+        execution_time_ms: page.execution_time_ms,
+    };
+    Ok(Json(ApiResponse::success(dto)).into_response())
+}
+
+/// Run a query and keep its result to read a page at a time:
+/// `POST /cursors`. Returns the first page.
+pub async fn open_cursor(
+    Extension(handler): Extension<Arc<Handler>>,
+    Extension(identity): Extension<AuthIdentity>,
+    Json(request): Json<OpenCursorRequest>,
+) -> Result<Response, RestError> {
+    let kg = request
+        .knowledge_graph
+        .unwrap_or_else(|| handler.config().storage.default_knowledge_graph.clone());
+    validate_kg_name(&kg)?;
+    let result = run_unsized(&handler, None, Some(kg), request.program, &identity).await?;
+    let page = handler
+        .cursors()
+        .open(&client_key(&identity, None), result, request.page_size)?;
+    respond_page(&handler, page)
+}
+
+/// Next page of a cursor: `GET /cursors/{id}`
+pub async fn fetch_cursor(
+    Extension(handler): Extension<Arc<Handler>>,
+    Extension(identity): Extension<AuthIdentity>,
+    Path(id): Path<String>,
+    Query(params): Query<FetchCursorParams>,
+) -> Result<Response, RestError> {
+    let client = client_key(&identity, None);
+    let _permit = handler.admit(&client, 1).map_err(RestError::throttled)?;
+    let page = handler.cursors().fetch(&client, &id, params.page_size)?;
+    respond_page(&handler, page)
+}
+
+/// Close a cursor before reading it to the end: `DELETE /cursors/{id}`
+pub async fn close_cursor(
+    Extension(handler): Extension<Arc<Handler>>,
+    Extension(identity): Extension<AuthIdentity>,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<MessageDto>>, RestError> {
+    handler.cursors().close(&client_key(&identity, None), &id)?;
+    Ok(Json(ApiResponse::success(MessageDto {
+        message: format!("Cursor '{id}' closed"),
+    })))
+}
+
+/// Knowledge graphs the caller can access: `GET /knowledge-graphs`
+pub async fn list_knowledge_graphs(
+    Extension(handler): Extension<Arc<Handler>>,
+    Extension(identity): Extension<AuthIdentity>,
+) -> Result<Json<ApiResponse<Vec<String>>>, RestError> {
+    let kgs = tokio::task::spawn_blocking(move || {
+        let names = handler.get_storage().list_knowledge_graphs();
+        names
+            .into_iter()
+            .filter(|kg| {
+                identity.role == crate::auth::Role::Admin
+                    || (kg != INTERNAL_KG
+                        && handler
+                            .get_kg_role_for_user(kg, &identity.username, &identity.role)
+                            .is_some())
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| RestError::internal(format!("Task join error: {e}")))?;
+    Ok(Json(ApiResponse::success(kgs)))
+}
+
+/// Create a knowledge graph: `POST /knowledge-graphs`
+pub async fn create_knowledge_graph(
+    Extension(handler): Extension<Arc<Handler>>,
+    Extension(identity): Extension<AuthIdentity>,
+    Json(request): Json<CreateKnowledgeGraphRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<MessageDto>>), RestError> {
+    validate_kg_name(&request.name)?;
+    let kg = handler.config().storage.default_knowledge_graph.clone();
+    let result = run(
+        &handler,
+        kg,
+        format!(".kg create {}", request.name),
+        &identity,
+    )
+    .await?;
+    let message = message_of(result);
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success(MessageDto { message })),
+    ))
+}
+
+/// Drop a knowledge graph: `DELETE /knowledge-graphs/{kg}`
+pub async fn drop_knowledge_graph(
+    Extension(handler): Extension<Arc<Handler>>,
+    Extension(identity): Extension<AuthIdentity>,
+    Path(kg): Path<String>,
+) -> Result<Json<ApiResponse<MessageDto>>, RestError> {
+    validate_kg_name(&kg)?;
+    let current = handler.config().storage.default_knowledge_graph.clone();
+    let result = run(&handler, current, format!(".kg drop {kg}"), &identity).await?;
+    let message = message_of(result);
+    Ok(Json(ApiResponse::success(MessageDto { message })))
+}
+
+/// Relations of a knowledge graph: `GET /knowledge-graphs/{kg}/relations`
+pub async fn list_relations(
+    Extension(handler): Extension<Arc<Handler>>,
+    Extension(identity): Extension<AuthIdentity>,
+    Path(kg): Path<String>,
+) -> Result<Json<ApiResponse<Vec<RelationDto>>>, RestError> {
+    validate_kg_name(&kg)?;
+    // Listing relations is a read of the knowledge graph
+    run(&handler, kg.clone(), ".rel".to_string(), &identity).await?;
+    let relations = tokio::task::spawn_blocking(move || {
+        handler
+            .get_storage()
+            .list_relations_with_metadata(&kg)
+            .map_err(|e| RestError::not_found(e.to_string()))
+    })
+    .await
+    .map_err(|e| RestError::internal(format!("Task join error: {e}")))??;
+    let relations = relations
+        .into_iter()
+        .map(|(name, columns, tuple_count)| RelationDto {
+            name,
+            columns,
+            tuple_count,
+        })
+        .collect();
+    Ok(Json(ApiResponse::success(relations)))
+}
+
+/// Rows of a relation or view: `GET /knowledge-graphs/{kg}/relations/{relation}`
+pub async fn relation_rows(
+    Extension(handler): Extension<Arc<Handler>>,
+    Extension(identity): Extension<AuthIdentity>,
+    Path((kg, relation)): Path<(String, String)>,
+    Query(params): Query<FormatParams>,
+    headers: HeaderMap,
+) -> Result<Response, RestError> {
+    let csv = wants_csv(&params, &headers)?;
+    validate_kg_name(&kg)?;
+    validate_relation_name(&relation)?;
+
+    let arity = {
+        let handler = Arc::clone(&handler);
+        let (kg, relation) = (kg.clone(), relation.clone());
+        tokio::task::spawn_blocking(move || {
+            let storage = handler.get_storage();
+            match storage.get_relation_metadata_in(&kg, &relation) {
+                Ok(Some((columns, _))) => Ok(Some(columns.len())),
+                Ok(None) => storage
+                    .rule_arity_in(&kg, &relation)
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            }
+        })
+        .await
+        .map_err(|e| RestError::internal(format!("Task join error: {e}")))?
+        .map_err(RestError::not_found)?
+    };
+    let Some(arity) = arity else {
+        return Err(RestError::not_found(format!(
+            "Relation '{relation}' not found in knowledge graph '{kg}'"
+        )));
+    };
+
+    let vars: Vec<String> = (0..arity).map(|i| format!("V{i}")).collect();
+    let program = format!("?{relation}({})", vars.join(", "));
+    let result = run(&handler, kg, program, &identity).await?;
+    Ok(respond(result, csv))
+}
+
+/// Parse the facts of a request body with the configured value limits
+fn parse_facts(
+    handler: &Handler,
+    request: &FactsRequest,
+) -> Result<Vec<crate::value::Tuple>, RestError> {
+    let max_str = handler.config().storage.performance.max_string_value_bytes;
+    json_tuples_to_tuples_with_limits(&request.facts, max_str, 65_536)
+        .map_err(RestError::bad_request)
+}
+
+/// Insert facts: `POST /knowledge-graphs/{kg}/relations/{relation}/facts`
+pub async fn insert_facts(
+    Extension(handler): Extension<Arc<Handler>>,
+    Extension(identity): Extension<AuthIdentity>,
+    Path((kg, relation)): Path<(String, String)>,
+    Json(request): Json<FactsRequest>,
+) -> Result<Json<ApiResponse<InsertFactsDto>>, RestError> {
+    validate_kg_name(&kg)?;
+    validate_relation_name(&relation)?;
+    let tuples = parse_facts(&handler, &request)?;
+    let _permit = handler
+        .admit(&client_key(&identity, None), 1)
+        .map_err(RestError::throttled)?;
+    let report = tokio::task::spawn_blocking(move || {
+        handler.insert_facts(&kg, &relation, tuples, Some(&identity))
+    })
+    .await
+    .map_err(|e| RestError::internal(format!("Task join error: {e}")))?
+    .map_err(handler_error)?;
+    Ok(Json(ApiResponse::success(InsertFactsDto {
+        inserted: report.inserted,
+        duplicates: report.duplicates,
+        quarantined: report.quarantined,
+        replaced: report.replaced,
+    })))
+}
+
+/// Delete facts: `DELETE /knowledge-graphs/{kg}/relations/{relation}/facts`
+pub async fn delete_facts(
+    Extension(handler): Extension<Arc<Handler>>,
+    Extension(identity): Extension<AuthIdentity>,
+    Path((kg, relation)): Path<(String, String)>,
+    Json(request): Json<FactsRequest>,
+) -> Result<Json<ApiResponse<DeleteFactsDto>>, RestError> {
+    validate_kg_name(&kg)?;
+    validate_relation_name(&relation)?;
+    let tuples = parse_facts(&handler, &request)?;
+    let _permit = handler
+        .admit(&client_key(&identity, None), 1)
+        .map_err(RestError::throttled)?;
+    let deleted = tokio::task::spawn_blocking(move || {
+        handler.delete_facts(&kg, &relation, tuples, Some(&identity))
+    })
+    .await
+    .map_err(|e| RestError::internal(format!("Task join error: {e}")))?
+    .map_err(handler_error)?;
+    Ok(Json(ApiResponse::success(DeleteFactsDto { deleted })))
+}
+
+/// Views of a knowledge graph with their rules: `GET /knowledge-graphs/{kg}/views`
+pub async fn list_views(
+    Extension(handler): Extension<Arc<Handler>>,
+    Extension(identity): Extension<AuthIdentity>,
+    Path(kg): Path<String>,
+) -> Result<Json<ApiResponse<Vec<ViewDto>>>, RestError> {
+    validate_kg_name(&kg)?;
+    run(&handler, kg.clone(), ".rule".to_string(), &identity).await?;
+    let views = tokio::task::spawn_blocking(move || {
+        let storage = handler.get_storage();
+        let names = storage
+            .list_rules_in(&kg)
+            .map_err(|e| RestError::not_found(e.to_string()))?;
+        Ok::<_, RestError>(
+            names
+                .into_iter()
+                .map(|name| {
+                    let definition = storage.describe_rule_in(&kg, &name).ok().flatten();
+                    ViewDto { name, definition }
+                })
+                .collect::<Vec<_>>(),
+        )
+    })
+    .await
+    .map_err(|e| RestError::internal(format!("Task join error: {e}")))??;
+    Ok(Json(ApiResponse::success(views)))
+}
+
+/// Define a view from a rule: `POST /knowledge-graphs/{kg}/views`
+pub async fn create_view(
+    Extension(handler): Extension<Arc<Handler>>,
+    Extension(identity): Extension<AuthIdentity>,
+    Path(kg): Path<String>,
+    Json(request): Json<CreateViewRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<MessageDto>>), RestError> {
+    validate_kg_name(&kg)?;
+    let rule = request.rule.trim();
+    if !rule.starts_with('+') || !rule.contains("<-") {
+        return Err(RestError::bad_request(
+            "A view is a persistent rule: +head(...) <- body",
+        ));
+    }
+    let result = run(&handler, kg, rule.to_string(), &identity).await?;
+    let message = message_of(result);
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success(MessageDto { message })),
+    ))
+}
+
+/// Drop a view: `DELETE /knowledge-graphs/{kg}/views/{name}`
+pub async fn drop_view(
+    Extension(handler): Extension<Arc<Handler>>,
+    Extension(identity): Extension<AuthIdentity>,
+    Path((kg, name)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<MessageDto>>, RestError> {
+    validate_kg_name(&kg)?;
+    validate_relation_name(&name)?;
+    let result = run(&handler, kg, format!(".rule drop {name}"), &identity).await?;
+    let message = message_of(result);
+    Ok(Json(ApiResponse::success(MessageDto { message })))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::protocol::wire::{ColumnDef, WireDataType, WireTuple, WireValue};
+
+    #[test]
+    fn test_names_are_validated() {
+        assert!(validate_kg_name("my_kg-2").is_ok());
+        assert!(validate_kg_name("").is_err());
+        assert!(validate_kg_name("kg; .user list").is_err());
+
+        assert!(validate_relation_name("edge_2").is_ok());
+        assert!(validate_relation_name("Edge").is_err());
+        assert!(validate_relation_name("edge(X)").is_err());
+        assert!(validate_relation_name("").is_err());
+    }
+
+    #[test]
+    fn test_csv_rendering() {
+        let result = QueryResult {
+            rows: vec![
+                WireTuple {
+                    values: vec![WireValue::Int64(1), WireValue::String("a,b".to_string())],
+                    provenance: None,
+                },
+                WireTuple {
+                    values: vec![WireValue::Null, WireValue::String("say \"hi\"".to_string())],
+                    provenance: None,
+                },
+            ],
+            schema: vec![
+                ColumnDef {
+                    name: "id".to_string(),
+                    data_type: WireDataType::Int64,
+                },
+                ColumnDef {
+                    name: "name".to_string(),
+                    data_type: WireDataType::String,
+                },
+            ],
+            total_count: 2,
+            truncated: false,
+            execution_time_ms: 0,
+            metadata: None,
+            switched_kg: None,
+            proof_trees: None,
+            timing_breakdown: None,
+        };
+        assert_eq!(to_csv(result), "id,name\n1,\"a,b\"\n,\"say \"\"hi\"\"\"\n");
+    }
+
+    #[test]
+    fn test_format_negotiation() {
+        let mut headers = HeaderMap::new();
+        let none = FormatParams::default();
+        assert!(!wants_csv(&none, &headers).unwrap());
+
+        headers.insert(header::ACCEPT, "text/csv".parse().unwrap());
+        assert!(wants_csv(&none, &headers).unwrap());
+        let json = FormatParams {
+            format: Some("json".to_string()),
+        };
+        assert!(!wants_csv(&json, &headers).unwrap());
+
+        let xml = FormatParams {
+            format: Some("xml".to_string()),
+        };
+        assert!(wants_csv(&xml, &HeaderMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_access_errors_are_forbidden() {
+        assert_eq!(
+            handler_error("Access denied".to_string()).status,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            handler_error("Parse error".to_string()).status,
+            StatusCode::BAD_REQUEST
+        );
+    }
+}
@@ -1,8 +1,18 @@
 //! HTTP API Handlers
 //!
-//! Contains endpoint handlers for health/stats and WebSocket connections.
+//! Contains endpoint handlers for health/stats, the HTTP data endpoints,
+//! WebSocket connections, the replication stream followers read, the
+//! change stream CDC subscribers read, the requests cluster members send
+//! each other, the programs a sharding router runs on its shards and the
+//! sessions HTTP clients keep state in.
 
 pub mod admin;
+pub mod changes;
+pub mod cluster;
+pub mod data;
+pub mod replication;
+pub mod sessions;
+pub mod shard;
 pub mod ws;
 
 use crate::protocol::wire::WireValue;
@@ -98,9 +108,25 @@ pub fn wire_value_to_json(value: WireValue) -> serde_json::Value {
         WireValue::String(s) => serde_json::Value::String(s),
         WireValue::Bool(b) => serde_json::Value::Bool(b),
         WireValue::Timestamp(t) => serde_json::json!(t),
+        WireValue::Date(d) => serde_json::Value::String(crate::temporal_ops::format_date(d)),
+        WireValue::Duration(d) => serde_json::json!(d),
+        WireValue::Uuid(u) => serde_json::Value::String(u.hyphenated().to_string()),
         WireValue::Vector(v) => serde_json::json!(v),
         WireValue::VectorInt8(v) => serde_json::json!(v),
         WireValue::Bytes(b) => serde_json::json!(b),
+        WireValue::List(items) => {
+            serde_json::Value::Array(items.into_iter().map(wire_value_to_json).collect())
+        }
+        WireValue::Map(entries) => serde_json::Value::Object(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k, wire_value_to_json(v)))
+                .collect(),
+        ),
+        // Embedded as a JSON document rather than a string
+        WireValue::Json(text) => {
+            serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text))
+        }
     }
 }
 
@@ -203,6 +229,14 @@ mod tests {
         assert_eq!(json, serde_json::json!(1234567890));
     }
 
+    #[test]
+    fn test_wire_value_to_json_date_and_duration() {
+        let json = wire_value_to_json(WireValue::Date(0));
+        assert_eq!(json, serde_json::json!("1970-01-01"));
+        let json = wire_value_to_json(WireValue::Duration(1500));
+        assert_eq!(json, serde_json::json!(1500));
+    }
+
     #[test]
     fn test_wire_value_to_json_negative_int() {
         let json = wire_value_to_json(WireValue::Int64(-100));
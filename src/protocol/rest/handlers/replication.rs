@@ -0,0 +1,205 @@
+//! Replication Handler
+//!
+//! `GET /v1/replication` upgrades to a WebSocket that streams this
+//! server's replication log to a follower (see
+//! [`crate::protocol::replication`]). Only admin API keys may follow.
+//!
+//! A follower passes the position it stopped at as `?log_id=...&seq=...`.
+//! If this log still holds that position, records are sent from there.
+//! Otherwise every knowledge graph is sent as an image first, followed by
+//! `snapshot_end` and the records committed since the first image.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket},
+        Query, WebSocketUpgrade,
+    },
+    response::IntoResponse,
+    Extension,
+};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::auth::{AuthIdentity, Role};
+use crate::protocol::replication::{
+    replicated_kg, ReplicationMessage, HEARTBEAT_INTERVAL, MAX_RECORDS_PER_MESSAGE,
+};
+use crate::protocol::rest::error::RestError;
+use crate::protocol::Handler;
+use crate::storage::StorageError;
+use crate::storage_engine::ReplicationLog;
+
+/// Query parameters of `GET /replication`
+#[derive(Debug, Deserialize)]
+pub struct FollowParams {
+    /// Log the follower's position refers to
+    pub log_id: Option<String>,
+    /// Next record the follower needs
+    pub seq: Option<u64>,
+}
+
+/// Stream the replication log to a follower
+pub async fn follow(
+    Extension(handler): Extension<Arc<Handler>>,
+    Extension(identity): Extension<AuthIdentity>,
+    Query(params): Query<FollowParams>,
+    ws: WebSocketUpgrade,
+) -> Result<impl IntoResponse, RestError> {
+    if identity.role != Role::Admin {
+        return Err(RestError::forbidden(
+            "Only admin API keys can follow this server",
+        ));
+    }
+    let log = handler
+        .get_storage()
+        .replication_log()
+        .cloned()
+        .ok_or_else(|| RestError::bad_request("Replication is not enabled on this server"))?;
+    if log.is_following() {
+        return Err(RestError::bad_request(
+            "This server is a follower; follow its leader instead",
+        ));
+    }
+    info!(username = %identity.username, seq = ?params.seq, "replication_follower_connecting");
+    Ok(ws.on_upgrade(move |socket| async move {
+        let replication = Arc::clone(handler.replication());
+        let _attached = replication.attach_follower();
+        if let Err(e) = stream_log(&handler, &log, socket, params).await {
+            warn!(error = %e, "replication_stream_ended");
+        }
+        info!(username = %identity.username, "replication_follower_disconnected");
+    }))
+}
+
+async fn send(socket: &mut WebSocket, message: &ReplicationMessage) -> Result<(), String> {
+    let text = serde_json::to_string(message).map_err(|e| e.to_string())?;
+    socket
+        .send(Message::Text(text))
+        .await
+        .map_err(|e| format!("Follower connection lost: {e}"))
+}
+
+async fn stream_log(
+    handler: &Arc<Handler>,
+    log: &Arc<ReplicationLog>,
+    mut socket: WebSocket,
+    params: FollowParams,
+) -> Result<(), String> {
+    let mut appended = log.subscribe();
+    let resumed = match (params.log_id, params.seq) {
+        (Some(log_id), Some(seq)) if log.read_from(&log_id, seq, 0).is_some() => {
+            Some((log_id, seq))
+        }
+        _ => None,
+    };
+    let (log_id, mut seq) = match resumed {
+        Some(position) => position,
+        None => send_snapshot(handler, log, &mut socket).await?,
+    };
+
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    loop {
+        let Some(records) = log.read_from(&log_id, seq, MAX_RECORDS_PER_MESSAGE) else {
+            let message = format!(
+                "Position {seq} is no longer in the replication log; reconnect to catch up"
+            );
+            send(&mut socket, &ReplicationMessage::Error { message }).await?;
+            return Err(format!("follower fell behind the log at {seq}"));
+        };
+        if let Some(last) = records.last() {
+            seq = last.seq + 1;
+            let message = ReplicationMessage::Records {
+                log_id: log_id.clone(),
+                records,
+            };
+            send(&mut socket, &message).await?;
+            continue;
+        }
+        tokio::select! {
+            changed = appended.changed() => {
+                if changed.is_err() {
+                    return Ok(());
+                }
+            }
+            _ = heartbeat.tick() => {
+                // Shutting down, or no longer the leader
+                if handler.is_draining() || log.is_following() {
+                    let _ = socket.send(Message::Close(None)).await;
+                    return Ok(());
+                }
+                let message = ReplicationMessage::Heartbeat {
+                    log_id: log_id.clone(),
+                    next_seq: log.next_seq(),
+                };
+                send(&mut socket, &message).await?;
+            }
+            incoming = socket.recv() => match incoming {
+                None | Some(Err(_) | Ok(Message::Close(_))) => return Ok(()),
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// Send an image of every replicated knowledge graph, including ones
+/// created while imaging, then `snapshot_end`. Returns the position the
+/// records continue from.
+async fn send_snapshot(
+    handler: &Arc<Handler>,
+    log: &Arc<ReplicationLog>,
+    socket: &mut WebSocket,
+) -> Result<(String, u64), String> {
+    let log_id = log.id();
+    let start = log.next_seq();
+    let mut sent = HashSet::new();
+    loop {
+        let pending: Vec<String> = handler
+            .get_storage()
+            .list_knowledge_graphs()
+            .into_iter()
+            .filter(|kg| replicated_kg(kg) && !sent.contains(kg))
+            .collect();
+        if pending.is_empty() {
+            break;
+        }
+        for kg in pending {
+            let imaging = Arc::clone(handler);
+            let name = kg.clone();
+            let image = tokio::task::spawn_blocking(move || {
+                imaging.get_storage().image_knowledge_graph(&name)
+            })
+            .await
+            .map_err(|e| format!("Imaging task panicked: {e}"))?;
+            match image {
+                Ok(image) => {
+                    let message = ReplicationMessage::Snapshot {
+                        log_id: log_id.clone(),
+                        image,
+                    };
+                    send(socket, &message).await?;
+                }
+                // Dropped since it was listed; the drop is in the log
+                Err(StorageError::KnowledgeGraphNotFound(_)) => {}
+                Err(e) => return Err(format!("Cannot image '{kg}': {e}")),
+            }
+            sent.insert(kg);
+        }
+    }
+    let knowledge_graphs = handler
+        .get_storage()
+        .list_knowledge_graphs()
+        .into_iter()
+        .filter(|kg| replicated_kg(kg))
+        .collect();
+    let message = ReplicationMessage::SnapshotEnd {
+        log_id: log_id.clone(),
+        seq: start,
+        knowledge_graphs,
+    };
+    send(socket, &message).await?;
+    info!(log_id = %log_id, seq = start, images = sent.len(), "replication_snapshot_sent");
+    Ok((log_id, start))
+}
@@ -0,0 +1,123 @@
+//! Session Handlers
+//!
+//! Sessions for HTTP clients. `POST /sessions` creates a session owned by
+//! the caller and returns its ID; sending that ID as the `X-Session-Id`
+//! header of `POST /query` or `POST /batch` runs the request in the session
+//! instead of statelessly. A session keeps what a WebSocket connection
+//! keeps between statements:
+//!
+//! - the current knowledge graph, switched with `.kg use`
+//! - session facts and rules, visible only to its own queries
+//! - prepared statements and an open transaction
+//! - its limits (`.session set timeout_ms 5000`, `.session set max_rows
+//!   100`), which can only tighten the server's
+//!
+//! Only the session's creator and admins can use it. Sessions idle for
+//! longer than the session timeout are reaped; `DELETE /sessions/{id}`
+//! closes one early, discarding an open transaction.
+
+use std::sync::Arc;
+
+use axum::{extract::Path, http::HeaderMap, http::StatusCode, Extension, Json};
+
+use crate::auth::AuthIdentity;
+use crate::protocol::rest::dto::{ApiResponse, CreateSessionRequest, MessageDto, SessionDto};
+use crate::protocol::rest::error::RestError;
+use crate::protocol::Handler;
+use crate::session::SessionId;
+
+/// Header naming the session a request runs in
+pub const SESSION_HEADER: &str = "x-session-id";
+
+/// The session named by the request's `X-Session-Id` header, if any,
+/// checked to be usable by `identity`
+pub fn from_headers(
+    handler: &Handler,
+    headers: &HeaderMap,
+    identity: &AuthIdentity,
+) -> Result<Option<SessionId>, RestError> {
+    let Some(value) = headers.get(SESSION_HEADER) else {
+        return Ok(None);
+    };
+    let id = value
+        .to_str()
+        .map_err(|_| RestError::bad_request("Invalid X-Session-Id header"))?
+        .trim()
+        .to_string();
+    handler
+        .authorize_session(&id, identity)
+        .map_err(RestError::not_found)?;
+    Ok(Some(id))
+}
+
+fn describe(handler: &Handler, id: SessionId) -> Result<SessionDto, RestError> {
+    let sessions = handler.session_manager();
+    let prepared = sessions.prepared_slot(&id).map_err(RestError::not_found)?;
+    let transaction = sessions
+        .transaction_slot(&id)
+        .map_err(RestError::not_found)?;
+    sessions
+        .with_session(&id, |session| SessionDto {
+            session_id: id.clone(),
+            knowledge_graph: session.knowledge_graph.clone(),
+            timeout_ms: session.settings.query_timeout_ms,
+            max_rows: session.settings.max_result_rows,
+            session_facts: session.ephemeral_fact_count(),
+            session_rules: session.ephemeral_rule_count(),
+            prepared_statements: prepared.lock().len(),
+            in_transaction: transaction.lock().is_some(),
+        })
+        .map_err(RestError::not_found)
+}
+
+/// Create a session: `POST /sessions`
+pub async fn create(
+    Extension(handler): Extension<Arc<Handler>>,
+    Extension(identity): Extension<AuthIdentity>,
+    body: Option<Json<CreateSessionRequest>>,
+) -> Result<(StatusCode, Json<ApiResponse<SessionDto>>), RestError> {
+    let request = body.map(|Json(request)| request).unwrap_or_default();
+    let kg = request
+        .knowledge_graph
+        .unwrap_or_else(|| handler.config().storage.default_knowledge_graph.clone());
+    let id = handler
+        .create_session_with_auth(&kg, &identity)
+        .map_err(|e| {
+            if e.starts_with("Access denied") {
+                RestError::forbidden(e)
+            } else {
+                RestError::bad_request(e)
+            }
+        })?;
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success(describe(&handler, id)?)),
+    ))
+}
+
+/// Describe a session: `GET /sessions/{id}`
+pub async fn get(
+    Extension(handler): Extension<Arc<Handler>>,
+    Extension(identity): Extension<AuthIdentity>,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<SessionDto>>, RestError> {
+    handler
+        .authorize_session(&id, &identity)
+        .map_err(RestError::not_found)?;
+    Ok(Json(ApiResponse::success(describe(&handler, id)?)))
+}
+
+/// Close a session: `DELETE /sessions/{id}`
+pub async fn close(
+    Extension(handler): Extension<Arc<Handler>>,
+    Extension(identity): Extension<AuthIdentity>,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<MessageDto>>, RestError> {
+    handler
+        .authorize_session(&id, &identity)
+        .map_err(RestError::not_found)?;
+    handler.close_session(&id).map_err(RestError::not_found)?;
+    Ok(Json(ApiResponse::success(MessageDto {
+        message: format!("Session {id} closed."),
+    })))
+}
@@ -0,0 +1,34 @@
+//! Shard Handler
+//!
+//! `POST /v1/shard/execute` runs a program a sharding router sends this
+//! server (see [`crate::protocol::shard`]) and returns the full query
+//! result, keeping value types that the `/query` DTOs flatten to JSON.
+//! Only admin API keys may call it.
+
+use std::sync::Arc;
+
+use axum::{Extension, Json};
+
+use crate::auth::{AuthIdentity, Role};
+use crate::protocol::rest::error::RestError;
+use crate::protocol::shard::ShardRequest;
+use crate::protocol::wire::QueryResult;
+use crate::protocol::Handler;
+
+/// Run a routed program against one knowledge graph
+pub async fn execute(
+    Extension(handler): Extension<Arc<Handler>>,
+    Extension(identity): Extension<AuthIdentity>,
+    Json(request): Json<ShardRequest>,
+) -> Result<Json<QueryResult>, RestError> {
+    if identity.role != Role::Admin {
+        return Err(RestError::forbidden(
+            "Only admin API keys can run routed programs",
+        ));
+    }
+    handler
+        .query_program(Some(request.knowledge_graph), request.program)
+        .await
+        .map(Json)
+        .map_err(RestError::bad_request)
+}
@@ -36,12 +36,18 @@ use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
 use tracing::{debug, info, warn, Instrument};
 
+use super::data::result_dto;
 use super::wire_value_to_json;
+use crate::protocol::compression::{self, Codec, FrameCompressor};
+use crate::protocol::cursor::CursorPage;
 use crate::protocol::handler::{PersistentNotification, ValidationError, VALIDATION_ERROR_PREFIX};
-use crate::protocol::rest::dto::SessionQueryMetadataDto;
+use crate::protocol::live::{self, DeltaBatch, LiveSubscription};
+use crate::protocol::rest::dto::{QueryResultDto, SessionQueryMetadataDto};
 use crate::protocol::rest::error::RestError;
 use crate::protocol::rest::WsSemaphore;
+use crate::protocol::throttle::{client_key, connection_key, Throttled};
 use crate::protocol::Handler;
+use crate::protocol::WireValue;
 use crate::protocol::MAX_MESSAGE_SIZE;
 
 /// Threshold in bytes: results whose single-message JSON exceeds this are
@@ -52,6 +58,9 @@ const STREAMING_THRESHOLD: usize = 1024 * 1024; // 1 MB
 /// Maximum number of rows per `result_chunk` message.
 const STREAMING_CHUNK_ROWS: usize = 500;
 
+/// Maximum number of view subscriptions per WebSocket connection.
+const MAX_LIVE_SUBSCRIPTIONS: usize = 64;
+
 /// Incoming WebSocket message from client
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -153,6 +162,13 @@ enum WsResponse {
 /// {"type": "error", "message": "Invalid query syntax"}
 /// ```
 ///
+/// **Cursor page** - `cursor_id` is absent on the last page:
+/// ```json
+/// {"type": "cursor_page", "cursor_id": "...", "columns": ["x", "y"], "rows": [[1, 2]],
+///  "row_count": 1, "offset": 0, "total_count": 2400, "truncated": false,
+///  "execution_time_ms": 5}
+/// ```
+///
 /// **Pong** - Response to ping:
 /// ```json
 /// {"type": "pong"}
@@ -487,10 +503,33 @@ async fn process_ws_message(handler: &Arc<Handler>, session_id: &str, text: &str
 
 async fn handle_ws_query(handler: &Arc<Handler>, session_id: &str, query: String) -> WsResponse {
     let start = std::time::Instant::now();
-    match handler
+    let _permit = match handler.admit_program(&connection_key(session_id), &query) {
+        Ok(permit) => permit,
+        Err(throttled) => {
+            return WsResponse::Error {
+                message: throttled.to_string(),
+            }
+        }
+    };
+    let audited = handler.audit_enabled().then(|| {
+        let kg = handler
+            .session_manager()
+            .session_kg(&session_id.to_string());
+        (kg.unwrap_or_default(), query.clone())
+    });
+    let result = handler
         .query_program_with_session(&session_id.to_string(), query)
-        .await
-    {
+        .await;
+    if let Some((kg, query)) = audited {
+        handler.audit_program(None, Some(session_id), kg, &query, start, &result);
+    }
+    let result = result.and_then(|response| {
+        handler
+            .check_result_size(&response)
+            .map(|()| response)
+            .map_err(|throttled| throttled.to_string())
+    });
+    match result {
         Ok(response) => {
             let row_provenance: Vec<String> = response
                 .rows
@@ -640,6 +679,10 @@ pub struct WsConnectParams {
     /// If provided, the server replays buffered notifications with seq > last_seq on connect (#39).
     #[serde(default)]
     pub last_seq: Option<u64>,
+    /// Comma-separated compression codecs the client accepts, for
+    /// connections authenticated by the upgrade request
+    #[serde(default)]
+    pub compression: Option<String>,
 }
 
 fn default_kg() -> String {
@@ -651,15 +694,60 @@ fn default_kg() -> String {
 #[serde(tag = "type", rename_all = "snake_case")]
 enum GlobalWsRequest {
     /// Authenticate with username and password
-    Login { username: String, password: String },
+    Login {
+        username: String,
+        password: String,
+        /// Compression codecs the client accepts
+        #[serde(default)]
+        compression: Vec<String>,
+    },
     /// Authenticate with an API key
-    Authenticate { api_key: String },
+    Authenticate {
+        api_key: String,
+        #[serde(default)]
+        compression: Vec<String>,
+    },
     /// Execute any IQL statement or meta command as raw text
     Execute { program: String },
+    /// Execute writes, view definitions and a final query atomically
+    Batch { statements: Vec<String> },
+    /// Subscribe to a query's changes in the session's knowledge graph
+    Subscribe { query: String },
+    /// Stop a subscription
+    Unsubscribe { subscription_id: String },
+    /// Take over a subscription from a closed connection, receiving the
+    /// changes after `cursor`
+    Resume {
+        subscription_id: String,
+        cursor: u64,
+    },
+    /// Run a program and keep its result to read a page at a time
+    OpenCursor {
+        program: String,
+        /// Rows per page (default 1000)
+        #[serde(default)]
+        page_size: usize,
+    },
+    /// Read the next page of a cursor
+    FetchCursor {
+        cursor_id: String,
+        #[serde(default)]
+        page_size: usize,
+    },
+    /// Drop a cursor before reading it to the end
+    CloseCursor { cursor_id: String },
     /// Keep-alive ping
     Ping,
 }
 
+/// One changed row of a subscribed view
+#[derive(Debug, Serialize)]
+struct ViewChange {
+    row: Vec<serde_json::Value>,
+    /// Multiplicity change: positive for insertions, negative for retractions
+    diff: isize,
+}
+
 /// Outgoing message for the global WebSocket protocol
 #[derive(Debug, Serialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -670,6 +758,9 @@ enum GlobalWsResponse {
         knowledge_graph: String,
         version: String,
         role: String,
+        /// Codec for compressed frames; absent when none was agreed
+        #[serde(skip_serializing_if = "Option::is_none")]
+        compression: Option<Codec>,
     },
     /// Authentication failed
     AuthError { message: String },
@@ -725,10 +816,115 @@ enum GlobalWsResponse {
         #[serde(skip_serializing_if = "Option::is_none")]
         validation_errors: Option<Vec<ValidationError>>,
     },
+    /// Subscription started (or resumed). Changes after `cursor` follow as
+    /// `view_delta` messages. With `reset`, the client's copy of the view is
+    /// stale and a full snapshot follows.
+    Subscribed {
+        subscription_id: String,
+        cursor: u64,
+        reset: bool,
+    },
+    /// Consolidated changes to a subscribed view
+    ViewDelta {
+        subscription_id: String,
+        cursor: u64,
+        changes: Vec<ViewChange>,
+    },
+    /// Subscription stopped at the client's request
+    Unsubscribed { subscription_id: String },
+    /// Subscription failed or was dropped (e.g. the client fell behind)
+    SubscriptionError {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        subscription_id: Option<String>,
+        message: String,
+    },
+    /// Request refused by a per-client limit: `limit` names it, `max` is
+    /// its configured value and `retry_after_ms` (when present) says when
+    /// to try again
+    Throttled {
+        message: String,
+        #[serde(flatten)]
+        throttle: Throttled,
+    },
+    /// One page of a cursor. `cursor_id` is absent on the last page.
+    CursorPage {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        cursor_id: Option<String>,
+        columns: Vec<String>,
+        rows: Vec<Vec<serde_json::Value>>,
+        row_count: usize,
+        offset: usize,
+        total_count: usize,
+        truncated: bool,
+        execution_time_ms: u64,
+    },
+    /// Cursor closed at the client's request
+    CursorClosed { cursor_id: String },
+    /// Results of a batch, one per statement
+    BatchResult {
+        results: Vec<QueryResultDto>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        commit: Option<String>,
+        execution_time_ms: u64,
+    },
     /// Pong response to keep-alive ping
     Pong,
 }
 
+impl GlobalWsResponse {
+    fn throttled(throttle: Throttled) -> Self {
+        GlobalWsResponse::Throttled {
+            message: throttle.to_string(),
+            throttle,
+        }
+    }
+
+    fn error(message: String) -> Self {
+        GlobalWsResponse::Error {
+            message,
+            validation_errors: None,
+        }
+    }
+
+    fn cursor_page(page: CursorPage) -> Self {
+        let rows: Vec<Vec<serde_json::Value>> = page
+            .rows
+            .into_iter()
+            .map(|row| row.values.into_iter().map(wire_value_to_json).collect())
+            .collect();
+        GlobalWsResponse::CursorPage {
+            cursor_id: page.cursor_id,
+            columns: page.schema.into_iter().map(|c| c.name).collect(),
+            row_count: rows.len(),
+            rows,
+            offset: page.offset,
+            total_count: page.total_count,
+            truncated: page.truncated,
+            execution_time_ms: page.execution_time_ms,
+        }
+    }
+
+    fn view_delta(subscription_id: &str, batch: DeltaBatch) -> Self {
+        let changes = batch
+            .changes
+            .into_iter()
+            .map(|(tuple, diff)| ViewChange {
+                row: tuple
+                    .values()
+                    .iter()
+                    .map(|value| wire_value_to_json(WireValue::from_value(value)))
+                    .collect(),
+                diff,
+            })
+            .collect();
+        GlobalWsResponse::ViewDelta {
+            subscription_id: subscription_id.to_string(),
+            cursor: batch.cursor,
+            changes,
+        }
+    }
+}
+
 /// Global WebSocket endpoint with auto-session lifecycle.
 ///
 /// Connect to `/ws?kg=<name>` to auto-create a session bound to the given
@@ -736,6 +932,9 @@ enum GlobalWsResponse {
 /// message with the session ID. On disconnect, the session is automatically
 /// closed.
 ///
+/// Clients authenticate with a `login` or `authenticate` message, or by
+/// sending `Authorization: Bearer <api key>` on the upgrade request.
+///
 /// ## Client → Server Messages
 ///
 /// **Execute** - Send any IQL statement or meta command as raw text:
@@ -746,11 +945,41 @@ enum GlobalWsResponse {
 /// {"type": "execute", "program": ".rule list"}
 /// ```
 ///
+/// **Subscribe** - Receive a query's changes as they happen:
+/// ```json
+/// {"type": "subscribe", "query": "?edge(X,Y)"}
+/// {"type": "unsubscribe", "subscription_id": "..."}
+/// {"type": "resume", "subscription_id": "...", "cursor": 12}
+/// ```
+///
+/// **Cursors** - Page through a large result:
+/// ```json
+/// {"type": "open_cursor", "program": "?edge(X,Y)", "page_size": 500}
+/// {"type": "fetch_cursor", "cursor_id": "...", "page_size": 500}
+/// {"type": "close_cursor", "cursor_id": "..."}
+/// ```
+///
+/// **Batch** - Run writes and view definitions atomically, then an
+/// optional final query, in one round trip:
+/// ```json
+/// {"type": "batch", "statements": ["+edge[(1, 2), (2, 3)]", "+path(X, Y) <- edge(X, Y)", "?path(X, Y)"]}
+/// ```
+///
 /// **Ping** - Keep-alive:
 /// ```json
 /// {"type": "ping"}
 /// ```
 ///
+/// **Compression** - Offer codecs when authenticating (or with
+/// `?compression=zstd,lz4` on the upgrade request). `authenticated` names
+/// the codec chosen; after that, messages of at least
+/// `http.compression.min_bytes` may arrive as binary frames: a codec tag
+/// byte (1 = zstd, 2 = LZ4 with a 4-byte size prefix) and the compressed
+/// JSON. The client may compress its own messages the same way.
+/// ```json
+/// {"type": "authenticate", "api_key": "...", "compression": ["zstd", "lz4"]}
+/// ```
+///
 /// ## Server → Client Messages
 ///
 /// **Connected** - Sent on connection:
@@ -769,6 +998,36 @@ enum GlobalWsResponse {
 /// {"type": "error", "message": "..."}
 /// ```
 ///
+/// **Throttled** - Refused by a per-client limit (`concurrent_queries`,
+/// `statements_per_sec` or `result_bytes`), or because the server is
+/// draining for shutdown (`shutting_down`):
+/// ```json
+/// {"type": "throttled", "message": "...", "limit": "statements_per_sec",
+///  "max": 50, "retry_after_ms": 420}
+/// ```
+///
+/// **View changes** - For each subscription, a snapshot then each change:
+/// ```json
+/// {"type": "subscribed", "subscription_id": "...", "cursor": 0, "reset": false}
+/// {"type": "view_delta", "subscription_id": "...", "cursor": 1,
+///  "changes": [{"row": [1, 2], "diff": 1}, {"row": [2, 3], "diff": -1}]}
+/// ```
+///
+/// **Cursor page** - `cursor_id` is absent on the last page:
+/// ```json
+/// {"type": "cursor_page", "cursor_id": "...", "columns": ["x", "y"], "rows": [[1, 2]],
+///  "row_count": 1, "offset": 0, "total_count": 2400, "truncated": false,
+///  "execution_time_ms": 5}
+/// ```
+///
+/// **Batch result** - One result per statement; `commit` summarizes the
+/// committed writes:
+/// ```json
+/// {"type": "batch_result", "results": [{"columns": ["message"], "rows": [["Queued 2 fact(s) for 'edge'."]], ...}, ...],
+///  "commit": "Transaction committed: 2 inserted, 0 deleted, 0 replaced, 1 rule change(s).",
+///  "execution_time_ms": 12}
+/// ```
+///
 /// **Pong** - Response to ping:
 /// ```json
 /// {"type": "pong"}
@@ -781,9 +1040,24 @@ enum GlobalWsResponse {
 pub async fn global_websocket(
     Extension(handler): Extension<Arc<Handler>>,
     Extension(ws_sem): Extension<WsSemaphore>,
+    headers: axum::http::HeaderMap,
     ws: WebSocketUpgrade,
     Query(params): Query<WsConnectParams>,
 ) -> Result<impl IntoResponse, RestError> {
+    // An API key on the upgrade request authenticates the connection up front
+    let identity = match headers
+        .get("authorization")
+        .and_then(|header| header.to_str().ok())
+        .and_then(|auth_str| auth_str.strip_prefix("Bearer "))
+    {
+        Some(token) => Some(
+            handler
+                .authenticate_api_key(token)
+                .map_err(RestError::unauthorized)?,
+        ),
+        None => None,
+    };
+
     // Enforce WebSocket connection limit
     let ws_permit = if let Some(ref sem) = ws_sem.0 {
         match sem.clone().try_acquire_owned() {
@@ -798,13 +1072,29 @@ pub async fn global_websocket(
         None
     };
 
+    let compression: Vec<String> = params
+        .compression
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::to_string)
+        .collect();
+
     Ok(ws
         .max_message_size(MAX_MESSAGE_SIZE)
         .max_frame_size(MAX_MESSAGE_SIZE)
         .on_upgrade(move |socket| {
             let permit = ws_permit;
             async move {
-                handle_global_ws_connection(socket, handler, params.kg, params.last_seq).await;
+                handle_global_ws_connection(
+                    socket,
+                    handler,
+                    params.kg,
+                    params.last_seq,
+                    identity,
+                    compression,
+                )
+                .await;
                 drop(permit);
             }
         }))
@@ -816,6 +1106,8 @@ async fn handle_global_ws_connection(
     handler: Arc<Handler>,
     kg: String,
     last_seq: Option<u64>,
+    mut pre_authenticated: Option<crate::auth::AuthIdentity>,
+    mut offered_compression: Vec<String>,
 ) {
     use crate::auth::AuthIdentity;
 
@@ -829,6 +1121,11 @@ async fn handle_global_ws_connection(
     let auth_identity: AuthIdentity;
 
     loop {
+        // Authenticated by the upgrade request's API key
+        if let Some(identity) = pre_authenticated.take() {
+            auth_identity = identity;
+            break;
+        }
         let msg = tokio::select! {
             () = tokio::time::sleep_until(auth_deadline) => {
                 let err = GlobalWsResponse::AuthError {
@@ -860,10 +1157,25 @@ async fn handle_global_ws_connection(
                 };
 
                 match request {
-                    GlobalWsRequest::Login { username, password } => {
+                    GlobalWsRequest::Login { .. } if !handler.config().http.auth.password_login => {
+                        let err = GlobalWsResponse::AuthError {
+                            message: "Password login is disabled. Authenticate with an API key."
+                                .to_string(),
+                        };
+                        if let Ok(json) = serde_json::to_string(&err) {
+                            let _ = sender.send(Message::Text(json)).await;
+                        }
+                        continue;
+                    }
+                    GlobalWsRequest::Login {
+                        username,
+                        password,
+                        compression,
+                    } => {
                         match handler.authenticate_user(&username, &password) {
                             Ok(identity) => {
                                 auth_identity = identity;
+                                offered_compression = compression;
                                 break;
                             }
                             Err(e) => {
@@ -876,10 +1188,14 @@ async fn handle_global_ws_connection(
                             }
                         }
                     }
-                    GlobalWsRequest::Authenticate { api_key } => {
+                    GlobalWsRequest::Authenticate {
+                        api_key,
+                        compression,
+                    } => {
                         match handler.authenticate_api_key(&api_key) {
                             Ok(identity) => {
                                 auth_identity = identity;
+                                offered_compression = compression;
                                 break;
                             }
                             Err(e) => {
@@ -892,7 +1208,15 @@ async fn handle_global_ws_connection(
                             }
                         }
                     }
-                    GlobalWsRequest::Execute { .. } | GlobalWsRequest::Ping => {
+                    GlobalWsRequest::Execute { .. }
+                    | GlobalWsRequest::Batch { .. }
+                    | GlobalWsRequest::Subscribe { .. }
+                    | GlobalWsRequest::Unsubscribe { .. }
+                    | GlobalWsRequest::Resume { .. }
+                    | GlobalWsRequest::OpenCursor { .. }
+                    | GlobalWsRequest::FetchCursor { .. }
+                    | GlobalWsRequest::CloseCursor { .. }
+                    | GlobalWsRequest::Ping => {
                         let err = GlobalWsResponse::AuthError {
                             message: "Authentication required. Send login or authenticate first."
                                 .to_string(),
@@ -920,6 +1244,7 @@ async fn handle_global_ws_connection(
     info!(
         kg = %kg,
         username = %auth_identity.username,
+        api_key = ?auth_identity.api_key,
         role = %auth_identity.role,
         "ws_authenticated"
     );
@@ -944,12 +1269,20 @@ async fn handle_global_ws_connection(
         }
     };
 
+    let compressor =
+        FrameCompressor::negotiate(&handler.config().http.compression, &offered_compression);
+    let mut sender = GlobalWsSender {
+        sink: sender,
+        compressor,
+    };
+
     // Send Authenticated message
     let authenticated = GlobalWsResponse::Authenticated {
         session_id: session_id.clone(),
         knowledge_graph: kg,
         version: env!("CARGO_PKG_VERSION").to_string(),
         role: auth_identity.role.to_string(),
+        compression: compressor.map(|c| c.codec()),
     };
     if let Ok(json) = serde_json::to_string(&authenticated) {
         if sender.send(Message::Text(json)).await.is_err() {
@@ -1035,6 +1368,9 @@ async fn handle_global_ws_connection(
     let mut heartbeat_interval = tokio::time::interval(std::time::Duration::from_secs(30));
     heartbeat_interval.tick().await; // consume the immediate first tick
 
+    // View subscriptions owned by this connection
+    let mut subscriptions: Vec<LiveSubscription> = Vec::new();
+
     loop {
         // Compute remaining idle time for this iteration
         let idle_sleep: std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send>> =
@@ -1083,6 +1419,20 @@ async fn handle_global_ws_connection(
             }
             // Client message
             msg = receiver.next() => {
+                let msg = match msg {
+                    Some(Ok(Message::Binary(frame))) if compressor.is_some() => {
+                        match decompress_text(&frame) {
+                            Ok(text) => Some(Ok(Message::Text(text))),
+                            Err(message) => {
+                                if !send_global_response(&mut sender, &GlobalWsResponse::error(message), &session_id).await {
+                                    break;
+                                }
+                                continue;
+                            }
+                        }
+                    }
+                    other => other,
+                };
                 match msg {
                     Some(Ok(Message::Text(text))) => {
                         last_activity = std::time::Instant::now();
@@ -1114,7 +1464,7 @@ async fn handle_global_ws_connection(
                             msg_bytes = text.len()
                         );
                         let send_ok = process_and_send_global_ws_message(
-                            &handler, &session_id, &text, &auth_identity, &mut sender,
+                            &handler, &session_id, &text, &auth_identity, &mut subscriptions, &mut sender,
                         )
                         .instrument(span)
                         .await;
@@ -1137,6 +1487,12 @@ async fn handle_global_ws_connection(
                     _ => {}
                 }
             }
+            // Changes to a subscribed view
+            index = live::next_ready(&mut subscriptions) => {
+                if !send_live_changes(&handler, &mut subscriptions, index, &session_id, &mut sender).await {
+                    break;
+                }
+            }
             // Server-initiated heartbeat ping
             _ = heartbeat_interval.tick() => {
                 if sender.send(Message::Ping(Vec::new())).await.is_err() {
@@ -1211,6 +1567,11 @@ async fn handle_global_ws_connection(
     // Send close frame before cleanup (prevents "connection reset without handshake" warnings)
     let _ = sender.send(Message::Close(None)).await;
 
+    // Keep subscriptions around for a reconnecting client to resume
+    for subscription in subscriptions.drain(..) {
+        handler.detach_live(subscription);
+    }
+
     // Auto-close session on disconnect
     let stats = handler.session_stats();
     info!(session_id = %session_id, active_sessions = stats.total_sessions, "ws_session_disconnecting");
@@ -1222,7 +1583,7 @@ async fn handle_global_ws_connection(
 /// Helper: serialize a `GlobalWsResponse` and send it. Returns `false` if the
 /// send fails (connection dead).
 async fn send_global_response(
-    sender: &mut futures_util::stream::SplitSink<WebSocket, Message>,
+    sender: &mut GlobalWsSender,
     response: &GlobalWsResponse,
     session_id: &str,
 ) -> bool {
@@ -1256,7 +1617,35 @@ async fn send_global_response(
     } else {
         json
     };
-    sender.send(Message::Text(json)).await.is_ok()
+    sender.send_json(json).await.is_ok()
+}
+
+/// Sending half of a global WebSocket connection, with the compression
+/// agreed at authentication
+struct GlobalWsSender {
+    sink: futures_util::stream::SplitSink<WebSocket, Message>,
+    compressor: Option<FrameCompressor>,
+}
+
+impl GlobalWsSender {
+    async fn send(&mut self, message: Message) -> Result<(), axum::Error> {
+        self.sink.send(message).await
+    }
+
+    /// Send a JSON message, as a compressed binary frame when compression
+    /// was agreed and the message is large enough to be worth it
+    async fn send_json(&mut self, json: String) -> Result<(), axum::Error> {
+        match self.compressor.and_then(|c| c.compress(json.as_bytes())) {
+            Some(frame) => self.sink.send(Message::Binary(frame)).await,
+            None => self.sink.send(Message::Text(json)).await,
+        }
+    }
+}
+
+/// The JSON text of a compressed binary frame from the client
+fn decompress_text(frame: &[u8]) -> Result<String, String> {
+    let bytes = compression::decompress(frame, MAX_MESSAGE_SIZE).map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|_| "Compressed message is not UTF-8 text".to_string())
 }
 
 /// Process a single global WebSocket message and send the response(s).
@@ -1270,7 +1659,8 @@ async fn process_and_send_global_ws_message(
     session_id: &str,
     text: &str,
     auth: &crate::auth::AuthIdentity,
-    sender: &mut futures_util::stream::SplitSink<WebSocket, Message>,
+    subscriptions: &mut Vec<LiveSubscription>,
+    sender: &mut GlobalWsSender,
 ) -> bool {
     let request: GlobalWsRequest = match serde_json::from_str(text) {
         Ok(r) => r,
@@ -1292,6 +1682,66 @@ async fn process_and_send_global_ws_message(
         GlobalWsRequest::Execute { program } => {
             send_global_execute(handler, session_id, program, auth, sender).await
         }
+        GlobalWsRequest::Batch { statements } => {
+            let response = run_global_batch(handler, session_id, statements, auth).await;
+            send_global_response(sender, &response, session_id).await
+        }
+        GlobalWsRequest::Subscribe { query } => {
+            send_global_subscribe(handler, session_id, query, auth, subscriptions, sender).await
+        }
+        GlobalWsRequest::Unsubscribe { subscription_id } => {
+            let response = match subscriptions.iter().position(|s| s.id() == subscription_id) {
+                Some(index) => {
+                    subscriptions.remove(index);
+                    GlobalWsResponse::Unsubscribed { subscription_id }
+                }
+                None => GlobalWsResponse::SubscriptionError {
+                    message: format!("Unknown subscription '{subscription_id}'"),
+                    subscription_id: Some(subscription_id),
+                },
+            };
+            send_global_response(sender, &response, session_id).await
+        }
+        GlobalWsRequest::Resume {
+            subscription_id,
+            cursor,
+        } => {
+            send_global_resume(
+                handler,
+                session_id,
+                subscription_id,
+                cursor,
+                auth,
+                subscriptions,
+                sender,
+            )
+            .await
+        }
+        GlobalWsRequest::OpenCursor { program, page_size } => {
+            let response = open_global_cursor(handler, session_id, program, page_size, auth).await;
+            send_global_response(sender, &response, session_id).await
+        }
+        GlobalWsRequest::FetchCursor {
+            cursor_id,
+            page_size,
+        } => {
+            let client = client_key(auth, None);
+            let response = match handler.admit(&client, 1) {
+                Ok(_permit) => match handler.cursors().fetch(&client, &cursor_id, page_size) {
+                    Ok(page) => checked_cursor_page(handler, page),
+                    Err(e) => GlobalWsResponse::error(e.to_string()),
+                },
+                Err(throttled) => GlobalWsResponse::throttled(throttled),
+            };
+            send_global_response(sender, &response, session_id).await
+        }
+        GlobalWsRequest::CloseCursor { cursor_id } => {
+            let response = match handler.cursors().close(&client_key(auth, None), &cursor_id) {
+                Ok(()) => GlobalWsResponse::CursorClosed { cursor_id },
+                Err(e) => GlobalWsResponse::error(e.to_string()),
+            };
+            send_global_response(sender, &response, session_id).await
+        }
         GlobalWsRequest::Ping => {
             send_global_response(sender, &GlobalWsResponse::Pong, session_id).await
         }
@@ -1310,6 +1760,255 @@ async fn process_and_send_global_ws_message(
     }
 }
 
+/// Handle an OpenCursor message: run the program in the session and keep
+/// its result as a cursor owned by the caller's API key (or user), so it
+/// can also be read over HTTP
+async fn open_global_cursor(
+    handler: &Arc<Handler>,
+    session_id: &str,
+    program: String,
+    page_size: usize,
+    auth: &crate::auth::AuthIdentity,
+) -> GlobalWsResponse {
+    let permit = match handler.admit_program(&client_key(auth, Some(session_id)), &program) {
+        Ok(permit) => permit,
+        Err(throttled) => return GlobalWsResponse::throttled(throttled),
+    };
+    let sid = session_id.to_string();
+    let result = handler
+        .execute_program(Some(&sid), None, program, Some(auth))
+        .await;
+    drop(permit);
+    let result = match result {
+        Ok(result) => result,
+        Err(e) => return GlobalWsResponse::error(e),
+    };
+    match handler
+        .cursors()
+        .open(&client_key(auth, None), result, page_size)
+    {
+        Ok(page) => {
+            if let Some(cursor_id) = &page.cursor_id {
+                info!(session_id, cursor_id = %cursor_id, total_count = page.total_count, "ws_cursor_opened");
+            }
+            checked_cursor_page(handler, page)
+        }
+        Err(e) => GlobalWsResponse::error(e.to_string()),
+    }
+}
+
+/// Handle a Batch message: run the statements atomically in the session
+async fn run_global_batch(
+    handler: &Arc<Handler>,
+    session_id: &str,
+    statements: Vec<String>,
+    auth: &crate::auth::AuthIdentity,
+) -> GlobalWsResponse {
+    let _permit = match handler.admit(&client_key(auth, Some(session_id)), statements.len()) {
+        Ok(permit) => permit,
+        Err(throttled) => return GlobalWsResponse::throttled(throttled),
+    };
+    let sid = session_id.to_string();
+    let batch = match handler
+        .execute_batch(Some(&sid), None, statements, Some(auth))
+        .await
+    {
+        Ok(batch) => batch,
+        Err(e) => return GlobalWsResponse::error(e),
+    };
+    if let Err(throttled) = handler.check_batch_size(&batch) {
+        return GlobalWsResponse::throttled(throttled);
+    }
+    info!(
+        session_id,
+        statements = batch.results.len(),
+        execution_time_ms = batch.execution_time_ms,
+        "ws_batch_complete"
+    );
+    GlobalWsResponse::BatchResult {
+        results: batch.results.into_iter().map(result_dto).collect(),
+        commit: batch.commit,
+        execution_time_ms: batch.execution_time_ms,
+    }
+}
+
+/// A cursor page, or a throttle error when it is over the result size limit
+fn checked_cursor_page(handler: &Handler, page: CursorPage) -> GlobalWsResponse {
+    match handler.check_page_size(&page) {
+        Ok(()) => GlobalWsResponse::cursor_page(page),
+        Err(throttled) => GlobalWsResponse::throttled(throttled),
+    }
+}
+
+/// Handle a Subscribe message: start a subscription in the session's
+/// knowledge graph. Its snapshot and changes are sent from the connection
+/// loop as they become ready.
+async fn send_global_subscribe(
+    handler: &Arc<Handler>,
+    session_id: &str,
+    query: String,
+    auth: &crate::auth::AuthIdentity,
+    subscriptions: &mut Vec<LiveSubscription>,
+    sender: &mut GlobalWsSender,
+) -> bool {
+    if subscriptions.len() >= MAX_LIVE_SUBSCRIPTIONS {
+        let err = GlobalWsResponse::SubscriptionError {
+            subscription_id: None,
+            message: format!("Too many subscriptions (max {MAX_LIVE_SUBSCRIPTIONS})"),
+        };
+        return send_global_response(sender, &err, session_id).await;
+    }
+    let kg = match handler
+        .session_manager()
+        .with_session(&session_id.to_string(), |s| s.knowledge_graph.clone())
+    {
+        Ok(kg) => kg,
+        Err(e) => {
+            let err = GlobalWsResponse::SubscriptionError {
+                subscription_id: None,
+                message: e,
+            };
+            return send_global_response(sender, &err, session_id).await;
+        }
+    };
+
+    let h = Arc::clone(handler);
+    let identity = auth.clone();
+    let result =
+        tokio::task::spawn_blocking(move || h.subscribe_live(&kg, &query, Some(&identity)))
+            .await
+            .unwrap_or_else(|e| Err(format!("Task join error: {e}")));
+    let response = match result {
+        Ok(subscription) => {
+            info!(
+                session_id,
+                subscription_id = subscription.id(),
+                "ws_subscribed"
+            );
+            let response = GlobalWsResponse::Subscribed {
+                subscription_id: subscription.id().to_string(),
+                cursor: subscription.cursor(),
+                reset: false,
+            };
+            subscriptions.push(subscription);
+            response
+        }
+        Err(message) => GlobalWsResponse::SubscriptionError {
+            subscription_id: None,
+            message,
+        },
+    };
+    send_global_response(sender, &response, session_id).await
+}
+
+/// Handle a Resume message: take over a detached subscription and send the
+/// changes after the client's cursor. If some of them are no longer kept,
+/// restart it from a snapshot and tell the client to reset.
+async fn send_global_resume(
+    handler: &Arc<Handler>,
+    session_id: &str,
+    subscription_id: String,
+    cursor: u64,
+    auth: &crate::auth::AuthIdentity,
+    subscriptions: &mut Vec<LiveSubscription>,
+    sender: &mut GlobalWsSender,
+) -> bool {
+    if subscriptions.len() >= MAX_LIVE_SUBSCRIPTIONS {
+        let err = GlobalWsResponse::SubscriptionError {
+            subscription_id: Some(subscription_id),
+            message: format!("Too many subscriptions (max {MAX_LIVE_SUBSCRIPTIONS})"),
+        };
+        return send_global_response(sender, &err, session_id).await;
+    }
+    let subscription = match handler.resume_live(&subscription_id, Some(auth)) {
+        Ok(subscription) => subscription,
+        Err(message) => {
+            let err = GlobalWsResponse::SubscriptionError {
+                subscription_id: Some(subscription_id),
+                message,
+            };
+            return send_global_response(sender, &err, session_id).await;
+        }
+    };
+
+    if let Some(batches) = subscription.replay_after(cursor) {
+        info!(session_id, subscription_id = %subscription_id, replayed = batches.len(), "ws_subscription_resumed");
+        subscriptions.push(subscription);
+        let resumed = GlobalWsResponse::Subscribed {
+            subscription_id: subscription_id.clone(),
+            cursor,
+            reset: false,
+        };
+        if !send_global_response(sender, &resumed, session_id).await {
+            return false;
+        }
+        for batch in batches {
+            let delta = GlobalWsResponse::view_delta(&subscription_id, batch);
+            if !send_global_response(sender, &delta, session_id).await {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    info!(session_id, subscription_id = %subscription_id, cursor, "ws_subscription_reset");
+    let h = Arc::clone(handler);
+    let identity = auth.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let mut subscription = subscription;
+        h.resubscribe_live(&mut subscription, Some(&identity))
+            .map(|()| subscription)
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task join error: {e}")));
+    let response = match result {
+        Ok(subscription) => {
+            let response = GlobalWsResponse::Subscribed {
+                subscription_id,
+                cursor: subscription.cursor(),
+                reset: true,
+            };
+            subscriptions.push(subscription);
+            response
+        }
+        Err(message) => GlobalWsResponse::SubscriptionError {
+            subscription_id: Some(subscription_id),
+            message,
+        },
+    };
+    send_global_response(sender, &response, session_id).await
+}
+
+/// Send the ready changes of `subscriptions[index]`. A subscription that fell
+/// behind, or whose view went away, is dropped and the client told why.
+/// Returns `true` if connection still alive, `false` to close.
+async fn send_live_changes(
+    handler: &Arc<Handler>,
+    subscriptions: &mut Vec<LiveSubscription>,
+    index: usize,
+    session_id: &str,
+    sender: &mut GlobalWsSender,
+) -> bool {
+    let max_rows = handler.config().http.ws_subscription_max_rows;
+    let subscription = &mut subscriptions[index];
+    let failure = match subscription.drain(max_rows) {
+        Ok(Some(batch)) => {
+            let delta = GlobalWsResponse::view_delta(subscription.id(), batch);
+            return send_global_response(sender, &delta, session_id).await;
+        }
+        Ok(None) if subscription.is_closed() => "Subscription ended".to_string(),
+        Ok(None) => return true,
+        Err(message) => message,
+    };
+    let subscription = subscriptions.remove(index);
+    warn!(session_id, subscription_id = subscription.id(), reason = %failure, "ws_subscription_dropped");
+    let err = GlobalWsResponse::SubscriptionError {
+        subscription_id: Some(subscription.id().to_string()),
+        message: failure,
+    };
+    send_global_response(sender, &err, session_id).await
+}
+
 /// Handle an Execute message on the global WebSocket.
 ///
 /// For small results (< STREAMING_THRESHOLD bytes when serialized), sends a
@@ -1324,7 +2023,7 @@ async fn send_global_execute(
     session_id: &str,
     program: String,
     auth: &crate::auth::AuthIdentity,
-    sender: &mut futures_util::stream::SplitSink<WebSocket, Message>,
+    sender: &mut GlobalWsSender,
 ) -> bool {
     let start = std::time::Instant::now();
     let program_len = program.len();
@@ -1335,10 +2034,41 @@ async fn send_global_execute(
         program_preview = %program_preview,
         "ws_execute_start"
     );
+    let permit = match handler.admit_program(&client_key(auth, Some(session_id)), &program) {
+        Ok(permit) => permit,
+        Err(throttled) => {
+            warn!(
+                session_id,
+                limit = throttled.limit.as_str(),
+                "ws_execute_throttled"
+            );
+            return send_global_response(
+                sender,
+                &GlobalWsResponse::throttled(throttled),
+                session_id,
+            )
+            .await;
+        }
+    };
     let sid = session_id.to_string();
     let result = handler
         .execute_program(Some(&sid), None, program.clone(), Some(auth))
         .await;
+    drop(permit);
+    let result = match result {
+        Ok(response) => match handler.check_result_size(&response) {
+            Ok(()) => Ok(response),
+            Err(throttled) => {
+                return send_global_response(
+                    sender,
+                    &GlobalWsResponse::throttled(throttled),
+                    session_id,
+                )
+                .await;
+            }
+        },
+        Err(e) => Err(e),
+    };
     let elapsed = start.elapsed();
     let slow_query_ms = handler.config().storage.performance.slow_query_log_ms;
     if slow_query_ms > 0 && elapsed.as_millis() as u64 >= slow_query_ms {
@@ -1538,6 +2268,53 @@ mod tests {
             if relation == "edge" && tuples.len() == 2));
     }
 
+    #[test]
+    fn test_global_ws_batch_message() {
+        let json = r#"{"type": "batch", "statements": ["+edge(1, 2)", "?edge(X, Y)"]}"#;
+        let req: GlobalWsRequest = serde_json::from_str(json).unwrap();
+        assert!(matches!(req, GlobalWsRequest::Batch { statements } if statements.len() == 2));
+
+        let response = GlobalWsResponse::BatchResult {
+            results: Vec::new(),
+            commit: None,
+            execution_time_ms: 1,
+        };
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["type"], "batch_result");
+        assert!(json.get("commit").is_none());
+    }
+
+    #[test]
+    fn test_global_ws_cursor_messages() {
+        let json = r#"{"type": "open_cursor", "program": "?edge(X,Y)"}"#;
+        let req: GlobalWsRequest = serde_json::from_str(json).unwrap();
+        assert!(matches!(
+            req,
+            GlobalWsRequest::OpenCursor { page_size: 0, .. }
+        ));
+        let json = r#"{"type": "fetch_cursor", "cursor_id": "c1", "page_size": 10}"#;
+        let req: GlobalWsRequest = serde_json::from_str(json).unwrap();
+        assert!(
+            matches!(req, GlobalWsRequest::FetchCursor { cursor_id, page_size: 10 }
+            if cursor_id == "c1")
+        );
+
+        let last = GlobalWsResponse::cursor_page(CursorPage {
+            cursor_id: None,
+            schema: Vec::new(),
+            rows: vec![crate::protocol::WireTuple::new(vec![WireValue::Int64(7)])],
+            offset: 40,
+            total_count: 41,
+            truncated: false,
+            execution_time_ms: 2,
+        });
+        let json = serde_json::to_value(&last).unwrap();
+        assert_eq!(json["type"], "cursor_page");
+        assert_eq!(json["rows"], serde_json::json!([[7]]));
+        assert_eq!(json["offset"], 40);
+        assert!(json.get("cursor_id").is_none());
+    }
+
     #[test]
     fn test_ws_request_ping_deserialize() {
         let json = r#"{"type": "ping"}"#;
@@ -1634,6 +2411,66 @@ mod tests {
         assert!(matches!(req, GlobalWsRequest::Ping));
     }
 
+    #[test]
+    fn test_global_ws_request_subscription_deserialize() {
+        let json = r#"{"type": "subscribe", "query": "?edge(X,Y)"}"#;
+        let req: GlobalWsRequest = serde_json::from_str(json).unwrap();
+        assert!(matches!(req, GlobalWsRequest::Subscribe { query } if query == "?edge(X,Y)"));
+
+        let json = r#"{"type": "resume", "subscription_id": "s1", "cursor": 7}"#;
+        let req: GlobalWsRequest = serde_json::from_str(json).unwrap();
+        assert!(matches!(
+            req,
+            GlobalWsRequest::Resume { subscription_id, cursor: 7 } if subscription_id == "s1"
+        ));
+    }
+
+    #[test]
+    fn test_global_ws_response_view_delta_serialize() {
+        let batch = DeltaBatch {
+            cursor: 3,
+            changes: vec![
+                (crate::value::Tuple::from_pair(1, 2), 1),
+                (crate::value::Tuple::from_pair(2, 3), -2),
+            ],
+        };
+        let json = serde_json::to_value(GlobalWsResponse::view_delta("s1", batch)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "type": "view_delta",
+                "subscription_id": "s1",
+                "cursor": 3,
+                "changes": [{"row": [1, 2], "diff": 1}, {"row": [2, 3], "diff": -2}]
+            })
+        );
+
+        let err = GlobalWsResponse::SubscriptionError {
+            subscription_id: None,
+            message: "nope".to_string(),
+        };
+        let json = serde_json::to_value(err).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"type": "subscription_error", "message": "nope"})
+        );
+    }
+
+    #[test]
+    fn test_global_ws_response_throttled_serialize() {
+        let throttled = GlobalWsResponse::throttled(Throttled {
+            limit: crate::protocol::throttle::ThrottleLimit::ConcurrentQueries,
+            max: 4,
+            retry_after_ms: Some(100),
+        });
+        let json = serde_json::to_value(throttled).unwrap();
+        assert_eq!(json["type"], "throttled");
+        assert_eq!(json["limit"], "concurrent_queries");
+        assert_eq!(json["max"], 4);
+        assert_eq!(json["retry_after_ms"], 100);
+        assert!(json["message"].as_str().unwrap().starts_with("Throttled"));
+    }
+
     #[test]
     fn test_global_ws_response_authenticated_serialize() {
         let resp = GlobalWsResponse::Authenticated {
@@ -1641,12 +2478,14 @@ mod tests {
             knowledge_graph: "default".to_string(),
             version: "0.1.0".to_string(),
             role: "admin".to_string(),
+            compression: None,
         };
         let json = serde_json::to_string(&resp).unwrap();
         assert!(json.contains("\"type\":\"authenticated\""));
         assert!(json.contains("\"session_id\":\"42\""));
         assert!(json.contains("\"knowledge_graph\":\"default\""));
         assert!(json.contains("\"role\":\"admin\""));
+        assert!(!json.contains("compression"));
     }
 
     #[test]
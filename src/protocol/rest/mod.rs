@@ -1,8 +1,10 @@
 //! HTTP API Module
 //!
 //! Provides the HTTP server with WebSocket endpoint, health/stats endpoints,
-//! and AsyncAPI documentation. All data operations go through the WebSocket
-//! `/ws` endpoint.
+//! and AsyncAPI documentation. Interactive sessions go through the WebSocket
+//! `/ws` endpoint; the `/query` and `/knowledge-graphs` endpoints run
+//! statements, write facts and manage knowledge graphs and views over plain
+//! HTTP with JSON (or CSV) bodies.
 
 pub mod dto;
 pub mod error;
@@ -16,7 +18,7 @@ use axum::{
     http::{Request, StatusCode},
     middleware::{self, Next},
     response::{Html, IntoResponse, Response},
-    routing::get,
+    routing::{delete, get, post},
     Extension, Router,
 };
 use tower_http::cors::{Any, CorsLayer};
@@ -25,10 +27,12 @@ use tower_http::services::{ServeDir, ServeFile};
 
 use tracing::{info, warn};
 
+use crate::auth::{AuthIdentity, Role};
 use crate::config::HttpConfig;
+use crate::protocol::cluster::CLUSTER_USER;
 use crate::protocol::Handler;
 
-use self::handlers::{admin, ws};
+use self::handlers::{admin, changes, cluster, data, replication, sessions, shard, ws};
 
 /// Middleware: Enforce maximum concurrent connections using a Semaphore.
 /// Unlike an atomic counter, Semaphore provides atomic check-and-acquire,
@@ -55,17 +59,23 @@ async fn connection_limit_middleware(
 
 /// Middleware: API key authentication via `_internal` KG.
 /// Checks for `Authorization: Bearer <key>` header and validates against stored API keys.
-/// Skips auth for /health, /live, /ready endpoints and WebSocket upgrades
+/// The key's identity is added to the request extensions for the data endpoints,
+/// and requests are rate limited per key.
+/// Skips auth for the health and readiness probes and WebSocket upgrades
 /// (WS has its own auth flow).
 async fn auth_middleware(
     Extension(handler): Extension<Arc<Handler>>,
-    req: Request<Body>,
+    Extension(key_limiter): Extension<KeyRateLimiter>,
+    mut req: Request<Body>,
     next: Next,
 ) -> Response {
     // Health/liveness probes and API docs are always public (both root and /v1/ prefixed)
     let path = req.uri().path();
     let effective_path = path.strip_prefix("/v1").unwrap_or(path);
-    if effective_path == "/health" || effective_path == "/live" || effective_path == "/ready" {
+    if matches!(
+        effective_path,
+        "/health" | "/live" | "/ready" | "/healthz" | "/readyz"
+    ) {
         return next.run(req).await;
     }
     if effective_path.starts_with("/api/") {
@@ -73,19 +83,52 @@ async fn auth_middleware(
     }
 
     // WebSocket endpoints handle their own auth flow (Login/Authenticate messages)
-    if effective_path == "/ws" || effective_path.starts_with("/sessions/") {
+    if effective_path == "/ws"
+        || (effective_path.starts_with("/sessions/") && effective_path.ends_with("/ws"))
+    {
+        return next.run(req).await;
+    }
+
+    // Cluster members check the shared secret themselves
+    if effective_path.starts_with("/cluster/") {
         return next.run(req).await;
     }
 
     // Check Authorization header
-    if let Some(auth_header) = req.headers().get("authorization") {
-        if let Ok(auth_str) = auth_header.to_str() {
-            if let Some(token) = auth_str.strip_prefix("Bearer ") {
-                if handler.authenticate_api_key(token).is_ok() {
-                    return next.run(req).await;
-                }
-            }
+    let token = req
+        .headers()
+        .get("authorization")
+        .and_then(|header| header.to_str().ok())
+        .and_then(|auth_str| auth_str.strip_prefix("Bearer "));
+
+    // Cluster members follow the elected leader with the shared secret
+    let member = effective_path == "/replication"
+        && token.is_some_and(|token| handler.cluster().is_some_and(|c| c.accepts(token)));
+    if member {
+        req.extensions_mut().insert(AuthIdentity {
+            username: CLUSTER_USER.to_string(),
+            role: Role::Admin,
+            api_key: None,
+        });
+        return next.run(req).await;
+    }
+
+    let identity = token.and_then(|token| handler.authenticate_api_key(token).ok());
+    if let Some(identity) = identity {
+        let key = identity
+            .api_key
+            .clone()
+            .unwrap_or_else(|| identity.username.clone());
+        if !key_limiter.check(key) {
+            warn!(username = %identity.username, api_key = ?identity.api_key, "api_key_rate_limited");
+            return (
+                StatusCode::TOO_MANY_REQUESTS,
+                "Rate limit exceeded for API key",
+            )
+                .into_response();
         }
+        req.extensions_mut().insert(identity);
+        return next.run(req).await;
     }
 
     (StatusCode::UNAUTHORIZED, "Invalid or missing API key").into_response()
@@ -101,15 +144,21 @@ async fn api_version_middleware(req: Request<Body>, next: Next) -> Response {
     response
 }
 
-/// Per-IP rate limiter state (#27).
+/// Request rate limiter state, per client IP (#27) or per API key.
 /// Uses a simple sliding window: (window_start, request_count).
 #[derive(Clone)]
-pub struct IpRateLimiter {
-    map: Arc<dashmap::DashMap<std::net::IpAddr, (std::time::Instant, u32)>>,
+pub struct RateLimiter<K> {
+    map: Arc<dashmap::DashMap<K, (std::time::Instant, u32)>>,
     max_rps: u32,
 }
 
-impl IpRateLimiter {
+/// Per-IP rate limiter (#27).
+pub type IpRateLimiter = RateLimiter<std::net::IpAddr>;
+
+/// Per-API-key rate limiter, keyed by key label.
+pub type KeyRateLimiter = RateLimiter<String>;
+
+impl<K: Eq + std::hash::Hash> RateLimiter<K> {
     fn new(max_rps: u32) -> Self {
         Self {
             map: Arc::new(dashmap::DashMap::new()),
@@ -118,12 +167,12 @@ impl IpRateLimiter {
     }
 
     /// Returns true if the request should be allowed.
-    fn check(&self, ip: std::net::IpAddr) -> bool {
+    fn check(&self, key: K) -> bool {
         if self.max_rps == 0 {
             return true;
         }
         let now = std::time::Instant::now();
-        let mut entry = self.map.entry(ip).or_insert((now, 0));
+        let mut entry = self.map.entry(key).or_insert((now, 0));
         let (window_start, count) = entry.value_mut();
         if now.duration_since(*window_start).as_secs() >= 1 {
             // Reset window
@@ -235,10 +284,45 @@ pub fn create_router(handler: Arc<Handler>, config: &HttpConfig) -> Router {
         .route("/health", get(admin::health))
         .route("/live", get(admin::liveness))
         .route("/ready", get(admin::readiness))
-        .route("/metrics", get(admin::stats))
+        .route("/healthz", get(admin::health))
+        .route("/readyz", get(admin::readiness))
+        .route("/metrics", get(admin::metrics))
         .route("/metrics/prometheus", get(admin::prometheus_metrics))
         .route("/ws", get(ws::global_websocket))
+        .route("/sessions", post(sessions::create))
+        .route("/sessions/:id", get(sessions::get).delete(sessions::close))
         .route("/sessions/:id/ws", get(ws::session_websocket))
+        .route("/replication", get(replication::follow))
+        .route("/changes", get(changes::subscribe))
+        .route("/cluster/vote", post(cluster::vote))
+        .route("/cluster/append", post(cluster::append))
+        .route("/shard/execute", post(shard::execute))
+        .route("/query", post(data::query))
+        .route("/batch", post(data::batch))
+        .route("/cursors", post(data::open_cursor))
+        .route(
+            "/cursors/:id",
+            get(data::fetch_cursor).delete(data::close_cursor),
+        )
+        .route(
+            "/knowledge-graphs",
+            get(data::list_knowledge_graphs).post(data::create_knowledge_graph),
+        )
+        .route("/knowledge-graphs/:kg", delete(data::drop_knowledge_graph))
+        .route("/knowledge-graphs/:kg/relations", get(data::list_relations))
+        .route(
+            "/knowledge-graphs/:kg/relations/:relation",
+            get(data::relation_rows),
+        )
+        .route(
+            "/knowledge-graphs/:kg/relations/:relation/facts",
+            post(data::insert_facts).delete(data::delete_facts),
+        )
+        .route(
+            "/knowledge-graphs/:kg/views",
+            get(data::list_views).post(data::create_view),
+        )
+        .route("/knowledge-graphs/:kg/views/:name", delete(data::drop_view))
         .route("/api/asyncapi.yaml", get(asyncapi_yaml))
         .route("/api/openapi.yaml", get(openapi_yaml))
         .route("/api/ws-docs", get(asyncapi_docs));
@@ -253,9 +337,11 @@ pub fn create_router(handler: Arc<Handler>, config: &HttpConfig) -> Router {
     // Health/live/ready endpoints and WebSocket paths bypass auth.
     // NOTE: Layer ordering matters! In Axum, .layer(A).layer(B) means B runs first.
     // Auth middleware needs Extension<Handler>, so Extension must be the OUTER layer.
+    let key_limiter = KeyRateLimiter::new(config.rate_limit.per_key_max_rps);
     api_app = api_app
         .layer(middleware::from_fn(auth_middleware))
-        .layer(Extension(handler));
+        .layer(Extension(handler))
+        .layer(Extension(key_limiter));
 
     // Apply connection limit middleware using Semaphore for atomic check-and-acquire
     let conn_semaphore: Option<Arc<tokio::sync::Semaphore>> =
@@ -317,8 +403,10 @@ pub fn create_router(handler: Arc<Handler>, config: &HttpConfig) -> Router {
 /// Starts the HTTP server with graceful shutdown support.
 ///
 /// Listens for SIGINT (ctrl-c) and SIGTERM to trigger graceful shutdown.
-/// On shutdown: stops accepting connections, cancels the session reaper,
-/// and flushes WAL + metadata via `handler.shutdown()`.
+/// On shutdown: stops accepting connections and admitting programs on
+/// every transport, waits up to `drain_timeout_secs` for running programs,
+/// cancels the background tasks, then flushes the WAL and checkpoints
+/// dirty relations via `handler.shutdown()`.
 pub async fn start_http_server(
     handler: Arc<Handler>,
     config: &HttpConfig,
@@ -339,6 +427,14 @@ pub async fn start_http_server(
                     if reaped > 0 {
                         info!(reaped, "session_reaper_cleanup");
                     }
+                    let reaped = reaper_handler.reap_detached_live();
+                    if reaped > 0 {
+                        info!(reaped, "live_subscription_reaper_cleanup");
+                    }
+                    let reaped = reaper_handler.cursors().reap_expired();
+                    if reaped > 0 {
+                        info!(reaped, "cursor_reaper_cleanup");
+                    }
                 }
                 _ = shutdown_rx.changed() => {
                     info!("session_reaper_shutdown");
@@ -391,21 +487,155 @@ pub async fn start_http_server(
         });
     }
 
+    // Spawn background retention sweep task (if enabled)
+    let sweep_interval = handler.config().storage.retention_sweep_interval_secs;
+    if sweep_interval > 0 {
+        let sweep_handler = Arc::clone(&handler);
+        let mut sweep_shutdown = shutdown_tx.subscribe();
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(std::time::Duration::from_secs(sweep_interval));
+            // Skip the first immediate tick
+            interval.tick().await;
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {
+                        let h = Arc::clone(&sweep_handler);
+                        let result = tokio::task::spawn_blocking(move || {
+                            // Replicas apply the leader's expiry deletes instead
+                            if h.replication().is_read_only() {
+                                return 0;
+                            }
+                            h.get_storage().sweep_expired()
+                        })
+                        .await;
+                        match result {
+                            Ok(count) if count > 0 => {
+                                info!(tuples_deleted = count, "retention_sweep_complete");
+                            }
+                            Err(e) => {
+                                warn!(error = %e, "retention_sweep_task_panicked");
+                            }
+                            _ => {} // count == 0, nothing expired
+                        }
+                    }
+                    _ = sweep_shutdown.changed() => {
+                        info!("retention_sweep_shutdown");
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
+    // Spawn background view refresh task (if enabled)
+    let refresh_interval = handler.config().storage.view_refresh_sweep_interval_secs;
+    if refresh_interval > 0 {
+        let refresh_handler = Arc::clone(&handler);
+        let mut refresh_shutdown = shutdown_tx.subscribe();
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(std::time::Duration::from_secs(refresh_interval));
+            // Skip the first immediate tick
+            interval.tick().await;
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {
+                        let h = Arc::clone(&refresh_handler);
+                        let result = tokio::task::spawn_blocking(move || {
+                            h.get_storage().refresh_due_views()
+                        })
+                        .await;
+                        match result {
+                            Ok(count) if count > 0 => {
+                                info!(views_refreshed = count, "view_refresh_sweep_complete");
+                            }
+                            Err(e) => {
+                                warn!(error = %e, "view_refresh_sweep_task_panicked");
+                            }
+                            _ => {} // count == 0, nothing due
+                        }
+                    }
+                    _ = refresh_shutdown.changed() => {
+                        info!("view_refresh_sweep_shutdown");
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
+    // Apply the leader's changes whenever following (replication
+    // followers until promoted, cluster members while not elected)
+    if handler.replication().may_follow() {
+        tokio::spawn(crate::protocol::replication::run_follower(
+            Arc::clone(&handler),
+            shutdown_tx.subscribe(),
+        ));
+    }
+    if let Some(cluster) = handler.cluster() {
+        tokio::spawn(crate::protocol::cluster::run_cluster(
+            Arc::clone(cluster),
+            shutdown_tx.subscribe(),
+        ));
+    }
+    if handler.config().kafka.is_enabled() {
+        crate::protocol::kafka::start_ingest(Arc::clone(&handler), shutdown_tx.subscribe());
+    }
+
     let addr: SocketAddr = format!("{}:{}", config.host, config.port).parse()?;
+    // Load certificates before binding so a bad path fails fast
+    let tls = if config.tls.enabled {
+        Some(crate::protocol::tls::server_config(&config.tls)?)
+    } else {
+        None
+    };
+    let scheme = if tls.is_some() { "https" } else { "http" };
 
-    println!("HTTP server listening on: http://{addr}");
+    println!("HTTP server listening on: {scheme}://{addr}");
     if config.gui.enabled {
-        println!("GUI dashboard available at: http://{addr}/");
+        println!("GUI dashboard available at: {scheme}://{addr}/");
     }
-    println!("WebSocket API docs at: http://{addr}/api/ws-docs");
+    println!("WebSocket API docs at: {scheme}://{addr}/api/ws-docs");
 
     let socket = tokio::net::TcpSocket::new_v4()?;
     socket.set_reuseaddr(true)?;
     socket.bind(addr)?;
     let listener = socket.listen(1024)?;
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await?;
+    match tls {
+        Some(tls) => {
+            info!(
+                sni_certificates = config.tls.sni.len(),
+                client_auth = config.tls.client_ca_path.is_some(),
+                "tls_enabled"
+            );
+            let handle = axum_server::Handle::new();
+            let shutdown_handle = handle.clone();
+            let draining = Arc::clone(&handler);
+            tokio::spawn(async move {
+                drain_signal(draining).await;
+                shutdown_handle.graceful_shutdown(None);
+            });
+            let rustls = axum_server::tls_rustls::RustlsConfig::from_config(Arc::new(tls));
+            axum_server::from_tcp_rustls(listener.into_std()?, rustls)
+                .handle(handle)
+                .serve(app.into_make_service())
+                .await?;
+        }
+        None => {
+            axum::serve(listener, app)
+                .with_graceful_shutdown(drain_signal(Arc::clone(&handler)))
+                .await?;
+        }
+    }
+
+    // Programs on connections that outlive the listener (WebSockets, gRPC,
+    // Flight) may still be running; give them time before flushing
+    handler
+        .drain(std::time::Duration::from_secs(
+            handler.config().http.drain_timeout_secs,
+        ))
+        .await;
 
     // Signal reaper to stop
     let _ = shutdown_tx.send(true);
@@ -535,6 +765,47 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::OK, "/ready must bypass auth");
     }
 
+    /// /healthz and /readyz bypass auth, at the root and under /v1
+    #[tokio::test]
+    async fn test_router_probe_aliases_bypass_auth() {
+        let (handler, _api_key, _tmp) = make_handler_with_api_key();
+        let config = make_default_config();
+        let app = create_router(handler, &config);
+
+        for uri in ["/healthz", "/readyz", "/v1/healthz", "/v1/readyz"] {
+            let req = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            let resp = app.clone().oneshot(req).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK, "{uri} must bypass auth");
+        }
+    }
+
+    /// While draining, /readyz reports 503 and new programs are refused
+    #[tokio::test]
+    async fn test_draining_fails_readiness_and_refuses_queries() {
+        let (handler, api_key, _tmp) = make_handler_with_api_key();
+        let config = make_default_config();
+        let app = create_router(Arc::clone(&handler), &config);
+        handler.begin_shutdown();
+
+        let req = Request::builder()
+            .uri("/readyz")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/v1/query")
+            .header("authorization", format!("Bearer {api_key}"))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"program": "?x(X)"}"#))
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(handler.drain(std::time::Duration::from_secs(1)).await, 0);
+    }
+
     // === API Key Auth Middleware Tests ===
 
     /// Auth: Valid Bearer API key is accepted.
@@ -624,6 +895,57 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::OK);
     }
 
+    /// Auth: Keys listed in the config authenticate as their user.
+    #[tokio::test]
+    async fn test_auth_configured_key() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = crate::Config::default();
+        config.storage.data_dir = tmp.path().to_path_buf();
+        config.http.auth.api_keys.push(crate::config::ApiKeyConfig {
+            label: "ci".to_string(),
+            key_sha256: crate::auth::hash_api_key("configured-secret"),
+            username: "admin".to_string(),
+            priority: None,
+        });
+        let handler = Arc::new(Handler::from_config(config).unwrap());
+        handler.bootstrap_auth();
+
+        let identity = handler.authenticate_api_key("configured-secret").unwrap();
+        assert_eq!(identity.username, "admin");
+        assert_eq!(identity.api_key.as_deref(), Some("ci"));
+        assert!(handler.authenticate_api_key("configured-secreT").is_err());
+
+        let app = create_router(handler, &make_default_config());
+        let req = Request::builder()
+            .uri("/metrics")
+            .header("authorization", "Bearer configured-secret")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    /// Auth: Requests are rate limited per API key.
+    #[tokio::test]
+    async fn test_auth_per_key_rate_limit() {
+        let (handler, key, _tmp) = make_handler_with_api_key();
+        let mut config = make_default_config();
+        config.rate_limit.per_key_max_rps = 1;
+        let app = create_router(handler, &config);
+
+        let request = || {
+            Request::builder()
+                .uri("/metrics")
+                .header("authorization", format!("Bearer {key}"))
+                .body(Body::empty())
+                .unwrap()
+        };
+        let resp = app.clone().oneshot(request()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = app.oneshot(request()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
     // === Connection Limit Middleware Tests ===
 
     /// Connection limit: limit=0 means unlimited.
@@ -684,6 +1006,12 @@ mod tests {
     }
 }
 
+/// Wait for a shutdown signal, then stop admitting programs
+async fn drain_signal(handler: Arc<Handler>) {
+    shutdown_signal().await;
+    handler.begin_shutdown();
+}
+
 /// Wait for a shutdown signal (SIGINT or SIGTERM).
 async fn shutdown_signal() {
     let ctrl_c = tokio::signal::ctrl_c();
@@ -0,0 +1,1133 @@
+//! Horizontal Sharding
+//!
+//! A router spreads relations too large for one server over the engine
+//! nodes listed in `[sharding]`. Each relation is placed on its own:
+//!
+//! ```text
+//! .rel alter events shard hash user      // each tuple on the shard its key hashes to
+//! .rel alter countries shard broadcast   // a full copy on every shard
+//! .rel alter events shard none           // back on the router, once emptied
+//! ```
+//!
+//! Placements are kept in `sharding.json` in the router's data directory.
+//! Shards are ordinary servers holding their slices as plain relations;
+//! the router runs programs on them through `POST /v1/shard/execute`.
+//!
+//! Inserts and deletes of literal tuples go to the shard each tuple's key
+//! hashes to, or to every shard for a broadcast relation. A conditional
+//! delete runs on every shard when its body is local to the shard.
+//!
+//! A query over sharded relations is anchored at its first positive atom
+//! over a hash-sharded relation. It runs on every shard, or on the one
+//! shard its key is bound to, after the other atoms are made local to the
+//! anchor's slice:
+//!
+//! - atoms over broadcast relations, and over hash-sharded relations keyed
+//!   on the anchor's key, are already there
+//! - a hash-sharded atom holding the anchor's key variable in another
+//!   column is shuffled: its relation is fetched from the shards and each
+//!   tuple sent to the shard that column hashes to
+//! - any other atom, router-local relations included, is broadcast: its
+//!   relation is fetched and sent whole to every shard
+//!
+//! Sent tuples travel with the program as session facts under a reserved
+//! name. The router unions and deduplicates the shards' rows, then sorts
+//! and paginates them.
+//!
+//! Rules, updates and `COPY` cannot use sharded relations, and a sharded
+//! relation's schema cannot change until it is unsharded. Writes to
+//! several shards are not atomic, and session rules and facts are not seen
+//! by queries over sharded relations.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use futures_util::future::join_all;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::ast::{Atom, BodyPredicate, Term};
+use crate::config::ShardingConfig;
+use crate::schema::SchemaType;
+use crate::statement::{
+    DeletePattern, InsertOp, MetaCommand, QueryGoal, RelAlterAction, ShardDecl, Statement,
+};
+use crate::Config;
+
+use super::handler::{
+    apply_pagination, sort_rows, term_to_value, transform_query_shorthand, Handler,
+};
+use super::wire::{QueryResult, WireTuple, WireValue};
+
+/// Path shards run routed programs at
+pub const EXECUTE_PATH: &str = "/v1/shard/execute";
+
+/// File in the router's data directory holding the placements
+const STATE_FILE: &str = "sharding.json";
+
+/// Prefix of the relations tuples are sent to a shard under. The `__`
+/// prefix is reserved, so it never names a user relation.
+const SHIPPED_PREFIX: &str = "__shard";
+
+/// Where a relation's tuples live
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Placement {
+    /// Each tuple on the shard its key column hashes to
+    Hash { column: String, index: usize },
+    /// A full copy on every shard
+    Broadcast,
+}
+
+impl fmt::Display for Placement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Placement::Hash { column, .. } => write!(f, "hash of '{column}'"),
+            Placement::Broadcast => write!(f, "broadcast"),
+        }
+    }
+}
+
+/// Placements per knowledge graph and relation
+type Placements = BTreeMap<String, BTreeMap<String, Placement>>;
+
+/// Body of `POST /v1/shard/execute`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShardRequest {
+    pub knowledge_graph: String,
+    pub program: String,
+}
+
+/// What the router did with a statement
+pub enum Routed {
+    /// It does not involve sharded relations and runs on this server
+    Local,
+    /// It ran on the shards, which reported this
+    Message(String),
+    /// Rows merged from the shards
+    Rows(QueryResult),
+}
+
+/// Routes statements over sharded relations to the shards
+pub struct ShardRouter {
+    config: ShardingConfig,
+    path: PathBuf,
+    placements: RwLock<Placements>,
+    client: reqwest::Client,
+}
+
+impl ShardRouter {
+    /// Route to the shards configured in `[sharding]`, if any
+    pub fn open(config: &Config) -> Option<Self> {
+        let sharding = &config.sharding;
+        if !sharding.is_enabled() {
+            return None;
+        }
+        let path = config.storage.data_dir.join(STATE_FILE);
+        let placements = load_placements(&path);
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(sharding.timeout_ms))
+            .build()
+            .unwrap_or_default();
+        info!(
+            shards = sharding.shards.len(),
+            relations = placements.values().map(BTreeMap::len).sum::<usize>(),
+            "shard_router_opened"
+        );
+        Some(Self {
+            config: sharding.clone(),
+            path,
+            placements: RwLock::new(placements),
+            client,
+        })
+    }
+
+    fn placement(&self, kg: &str, relation: &str) -> Option<Placement> {
+        self.placements.read().get(kg)?.get(relation).cloned()
+    }
+
+    fn is_placed(&self, kg: &str, relation: &str) -> bool {
+        self.placement(kg, relation).is_some()
+    }
+
+    fn save(&self, placements: &Placements) -> Result<(), String> {
+        let bytes = serde_json::to_vec_pretty(placements).map_err(|e| e.to_string())?;
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, bytes)
+            .and_then(|()| fs::rename(&tmp, &self.path))
+            .map_err(|e| format!("Cannot save shard placements: {e}"))
+    }
+
+    /// Lines of `.shard`
+    pub fn status_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("Shards: {}", self.config.shards.len())];
+        for (index, shard) in self.config.shards.iter().enumerate() {
+            lines.push(format!("  #{index} {} {}", shard.id, shard.url));
+        }
+        let placements = self.placements.read();
+        let count: usize = placements.values().map(BTreeMap::len).sum();
+        lines.push(format!("Sharded relations: {count}"));
+        for (kg, relations) in placements.iter() {
+            for (relation, placement) in relations {
+                lines.push(format!("  {kg}.{relation}: {placement}"));
+            }
+        }
+        lines
+    }
+
+    /// Run `stmt` on the shards if it involves sharded relations of `kg`
+    pub async fn execute(
+        &self,
+        handler: &Handler,
+        kg: &str,
+        stmt: &Statement,
+        text: &str,
+    ) -> Result<Routed, String> {
+        match stmt {
+            Statement::Meta(MetaCommand::RelAlter {
+                relation,
+                action: RelAlterAction::Shard(decl),
+            }) => self
+                .place(handler, kg, relation, decl.clone())
+                .await
+                .map(Routed::Message),
+            Statement::Meta(MetaCommand::RelAlter { relation, .. })
+                if self.is_placed(kg, relation) =>
+            {
+                Err(sharded_schema_error(relation))
+            }
+            Statement::SchemaDecl(decl) if self.is_placed(kg, &decl.name) => {
+                Err(sharded_schema_error(&decl.name))
+            }
+            Statement::Meta(MetaCommand::RelDrop(relation))
+            | Statement::DeleteRelationOrRule(relation)
+                if self.is_placed(kg, relation) =>
+            {
+                self.drop_relation(kg, relation, text).await?;
+                // The router still drops its copy of the schema
+                Ok(Routed::Local)
+            }
+            Statement::Insert(op) => match self.placement(kg, &op.relation) {
+                Some(placement) => self.insert(kg, op, &placement).await.map(Routed::Message),
+                None => Ok(Routed::Local),
+            },
+            Statement::Delete(op) => match self.placement(kg, &op.relation) {
+                Some(placement) => self
+                    .delete(kg, &op.relation, &op.pattern, &placement, text)
+                    .await
+                    .map(Routed::Message),
+                None => Ok(Routed::Local),
+            },
+            Statement::Update(op)
+                if op.deletes.iter().any(|d| self.is_placed(kg, &d.relation))
+                    || op.inserts.iter().any(|i| self.is_placed(kg, &i.relation))
+                    || self.reads_placed(kg, &op.body) =>
+            {
+                Err("Updates cannot use sharded relations; delete and insert instead".to_string())
+            }
+            Statement::PersistentRule(rule) | Statement::SessionRule(rule)
+                if self.is_placed(kg, &rule.head.relation) || self.reads_placed(kg, &rule.body) =>
+            {
+                Err("Rules cannot use sharded relations; query them with ? instead".to_string())
+            }
+            Statement::Copy(copy) if self.is_placed(kg, &copy.relation) => Err(format!(
+                "COPY cannot use sharded relation '{}'; insert its tuples instead",
+                copy.relation
+            )),
+            Statement::Query(goal) => {
+                let atoms = query_atoms(goal);
+                if atoms
+                    .iter()
+                    .any(|(atom, _)| self.is_placed(kg, &atom.relation))
+                {
+                    self.query(handler, kg, goal, &atoms, text)
+                        .await
+                        .map(Routed::Rows)
+                } else {
+                    Ok(Routed::Local)
+                }
+            }
+            _ => Ok(Routed::Local),
+        }
+    }
+
+    /// Whether a body reads a sharded relation
+    fn reads_placed(&self, kg: &str, body: &[BodyPredicate]) -> bool {
+        body.iter()
+            .filter_map(BodyPredicate::atom)
+            .any(|atom| self.is_placed(kg, &atom.relation))
+    }
+
+    /// `.rel alter <relation> shard ...`
+    async fn place(
+        &self,
+        handler: &Handler,
+        kg: &str,
+        relation: &str,
+        decl: Option<ShardDecl>,
+    ) -> Result<String, String> {
+        let current = self.placement(kg, relation);
+        let schema = handler
+            .get_storage()
+            .get_schema_in(kg, relation)
+            .map_err(|e| e.to_string())?;
+        let Some(decl) = decl else {
+            if current.is_none() {
+                return Err(format!("Relation '{relation}' is not sharded"));
+            }
+            let arity = schema.map_or(0, |schema| schema.columns.len());
+            let remaining = self.fetch(handler, kg, relation, arity, true).await?;
+            if !remaining.is_empty() {
+                return Err(format!(
+                    "Relation '{relation}' still holds {} tuple(s) on the shards; delete them before unsharding it",
+                    remaining.len()
+                ));
+            }
+            self.set_placement(kg, relation, None)?;
+            info!(kg, relation, "relation_unsharded");
+            return Ok(format!("Relation '{relation}' is no longer sharded."));
+        };
+
+        if let Some(current) = current {
+            return Err(format!(
+                "Relation '{relation}' is already sharded by {current}; unshard it first"
+            ));
+        }
+        let schema = schema
+            .ok_or_else(|| format!("Declare the schema of '{relation}' before sharding it"))?;
+        let stored = handler
+            .get_storage()
+            .scan_relation_in(kg, relation, None)
+            .map_err(|e| e.to_string())?;
+        if !stored.is_empty() {
+            return Err(format!(
+                "Relation '{relation}' holds {} tuple(s) on this server; shard it while empty",
+                stored.len()
+            ));
+        }
+        if let Some(column) = schema.columns.iter().find(|c| c.auto_increment) {
+            return Err(format!(
+                "Auto-increment column '{}' would repeat across shards",
+                column.name
+            ));
+        }
+        let placement = match decl {
+            ShardDecl::Broadcast => Placement::Broadcast,
+            ShardDecl::Hash { column } => {
+                let index = schema.column_index(&column).ok_or_else(|| {
+                    format!("Relation '{relation}' has no column '{column}' to shard by")
+                })?;
+                let data_type = &schema.columns[index].data_type;
+                if !matches!(
+                    data_type,
+                    SchemaType::Int
+                        | SchemaType::String
+                        | SchemaType::Symbol
+                        | SchemaType::Bool
+                        | SchemaType::Uuid
+                        | SchemaType::Any
+                ) {
+                    return Err(format!(
+                        "Shard key '{column}' has type '{data_type}'; use an int, string, symbol, bool or uuid column"
+                    ));
+                }
+                Placement::Hash { column, index }
+            }
+        };
+
+        // Declare the relation on every shard before any tuple is routed
+        let everywhere: Vec<usize> = (0..self.config.shards.len()).collect();
+        self.run_each(kg, &everywhere, &format!("+{schema}"))
+            .await?;
+        let message = format!(
+            "Relation '{relation}' sharded by {placement} across {} shard(s).",
+            everywhere.len()
+        );
+        self.set_placement(kg, relation, Some(placement))?;
+        info!(kg, relation, "relation_sharded");
+        Ok(message)
+    }
+
+    fn set_placement(
+        &self,
+        kg: &str,
+        relation: &str,
+        placement: Option<Placement>,
+    ) -> Result<(), String> {
+        let mut placements = self.placements.write();
+        let mut updated = placements.clone();
+        match placement {
+            Some(placement) => {
+                updated
+                    .entry(kg.to_string())
+                    .or_default()
+                    .insert(relation.to_string(), placement);
+            }
+            None => {
+                if let Some(relations) = updated.get_mut(kg) {
+                    relations.remove(relation);
+                    if relations.is_empty() {
+                        updated.remove(kg);
+                    }
+                }
+            }
+        }
+        self.save(&updated)?;
+        *placements = updated;
+        Ok(())
+    }
+
+    /// Drop a sharded relation on every shard and forget its placement
+    async fn drop_relation(&self, kg: &str, relation: &str, text: &str) -> Result<(), String> {
+        let everywhere: Vec<usize> = (0..self.config.shards.len()).collect();
+        self.run_each(kg, &everywhere, text).await?;
+        self.set_placement(kg, relation, None)?;
+        info!(kg, relation, "sharded_relation_dropped");
+        Ok(())
+    }
+
+    /// Send each tuple to the shard its key hashes to
+    async fn insert(
+        &self,
+        kg: &str,
+        op: &InsertOp,
+        placement: &Placement,
+    ) -> Result<String, String> {
+        let shards = self.config.shards.len();
+        let mut batches: Vec<Vec<String>> = vec![Vec::new(); shards];
+        for tuple in &op.tuples {
+            let rendered = render_tuple(tuple.iter().map(render_insert_term).collect());
+            match placement {
+                Placement::Broadcast => {
+                    for batch in &mut batches {
+                        batch.push(rendered.clone());
+                    }
+                }
+                Placement::Hash { column, index } => {
+                    let key = tuple.get(*index).ok_or_else(|| {
+                        format!(
+                            "Tuples of '{}' need a value for shard key '{column}'",
+                            op.relation
+                        )
+                    })?;
+                    let shard = term_shard(key, shards).ok_or_else(|| {
+                        format!(
+                            "Shard key '{column}' of '{}' must be a constant",
+                            op.relation
+                        )
+                    })?;
+                    batches[shard].push(rendered);
+                }
+            }
+        }
+        let programs: Vec<(usize, String)> = batches
+            .into_iter()
+            .enumerate()
+            .filter(|(_, batch)| !batch.is_empty())
+            .map(|(shard, batch)| (shard, format!("+{}[{}]", op.relation, batch.join(", "))))
+            .collect();
+        let touched = programs.len();
+        self.run_programs(kg, programs).await?;
+        Ok(format!(
+            "Inserted {} fact(s) into '{}' on {touched} shard(s).",
+            op.tuples.len(),
+            op.relation
+        ))
+    }
+
+    /// Delete literal tuples where they live, or run a conditional delete
+    /// on every shard when its body is local to the shard
+    async fn delete(
+        &self,
+        kg: &str,
+        relation: &str,
+        pattern: &DeletePattern,
+        placement: &Placement,
+        text: &str,
+    ) -> Result<String, String> {
+        let shards = self.config.shards.len();
+        let key_index = match placement {
+            Placement::Hash { index, .. } => Some(*index),
+            Placement::Broadcast => None,
+        };
+        let tuples: Vec<&Vec<Term>> = match pattern {
+            DeletePattern::SingleTuple(tuple) => vec![tuple],
+            DeletePattern::BulkTuples(tuples) => tuples.iter().collect(),
+            DeletePattern::Conditional { head_args, body } => {
+                let key = key_index.and_then(|index| head_args.get(index));
+                self.check_shard_local(kg, key, body)?;
+                let targets = match key.and_then(|key| term_shard(key, shards)) {
+                    Some(shard) => vec![shard],
+                    None => (0..shards).collect(),
+                };
+                self.run_each(kg, &targets, text).await?;
+                return Ok(format!(
+                    "Deleted matching tuples of '{relation}' on {} shard(s).",
+                    targets.len()
+                ));
+            }
+        };
+
+        let mut batches: Vec<Vec<String>> = vec![Vec::new(); shards];
+        for tuple in tuples {
+            let rendered = render_tuple(tuple.iter().map(render_term).collect());
+            let shard = key_index
+                .and_then(|index| tuple.get(index))
+                .and_then(|key| term_shard(key, shards));
+            match shard {
+                Some(shard) => batches[shard].push(rendered),
+                None => {
+                    for batch in &mut batches {
+                        batch.push(rendered.clone());
+                    }
+                }
+            }
+        }
+        let programs: Vec<(usize, String)> = batches
+            .into_iter()
+            .enumerate()
+            .filter(|(_, batch)| !batch.is_empty())
+            .map(|(shard, batch)| (shard, format!("-{relation}[{}]", batch.join(", "))))
+            .collect();
+        let touched = programs.len();
+        self.run_programs(kg, programs).await?;
+        Ok(format!("Deleted from '{relation}' on {touched} shard(s)."))
+    }
+
+    /// Check that a conditional delete only reads what each shard holds:
+    /// broadcast relations, and hash-sharded ones keyed on `key`
+    fn check_shard_local(
+        &self,
+        kg: &str,
+        key: Option<&Term>,
+        body: &[BodyPredicate],
+    ) -> Result<(), String> {
+        for atom in body.iter().filter_map(BodyPredicate::atom) {
+            let local = match self.placement(kg, &atom.relation) {
+                Some(Placement::Broadcast) => true,
+                Some(Placement::Hash { index, .. }) => {
+                    key.is_some_and(|key| same_key(atom.args.get(index), key))
+                }
+                None => false,
+            };
+            if !local {
+                return Err(format!(
+                    "A conditional delete of a sharded relation can only read broadcast relations and tuples on the same shard, not '{}'",
+                    atom.relation
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Plan a query over sharded relations, run it and merge the rows
+    async fn query(
+        &self,
+        handler: &Handler,
+        kg: &str,
+        goal: &QueryGoal,
+        atoms: &[(&Atom, bool)],
+        text: &str,
+    ) -> Result<QueryResult, String> {
+        if goal
+            .body
+            .iter()
+            .any(|p| matches!(p, BodyPredicate::HnswNearest { .. }))
+        {
+            return Err(
+                "Vector index searches cannot be combined with sharded relations".to_string(),
+            );
+        }
+        let started = Instant::now();
+        let shards = self.config.shards.len();
+        let transform = transform_query_shorthand(text)?;
+        let plan = plan_query(atoms, |relation| self.placement(kg, relation), shards)?;
+        let targets: Vec<usize> = match plan.target {
+            Some(shard) => vec![shard],
+            None => (0..shards).collect(),
+        };
+
+        // Session facts sent with the query, per shard
+        let mut facts: Vec<String> = vec![String::new(); shards];
+        let mut fetched: HashMap<&str, Vec<WireTuple>> = HashMap::new();
+        let mut names = Vec::with_capacity(atoms.len());
+        for (i, ((atom, _), route)) in atoms.iter().zip(&plan.routes).enumerate() {
+            if *route == Route::InPlace {
+                names.push(atom.relation.clone());
+                continue;
+            }
+            if !fetched.contains_key(atom.relation.as_str()) {
+                let placed = self.is_placed(kg, &atom.relation);
+                let rows = self
+                    .fetch(handler, kg, &atom.relation, atom.args.len(), placed)
+                    .await?;
+                fetched.insert(&atom.relation, rows);
+            }
+            let name = format!("{SHIPPED_PREFIX}{i}_{}", atom.relation);
+            for row in &fetched[atom.relation.as_str()] {
+                let line = fact_line(&name, row)?;
+                match route {
+                    Route::Shuffle(column) => {
+                        let key = row.values.get(*column).ok_or_else(|| {
+                            format!(
+                                "'{}' returned a tuple without column {column}",
+                                atom.relation
+                            )
+                        })?;
+                        facts[value_shard(key, shards)].push_str(&line);
+                    }
+                    _ => {
+                        for &shard in &targets {
+                            facts[shard].push_str(&line);
+                        }
+                    }
+                }
+            }
+            names.push(name);
+        }
+
+        let query = render_query(goal, &names);
+        let programs = targets
+            .iter()
+            .map(|&shard| (shard, format!("{}{query}", facts[shard])))
+            .collect();
+        let results = self.run_programs(kg, programs).await?;
+        info!(
+            kg,
+            shards = targets.len(),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "sharded_query_complete"
+        );
+        let mut merged = merge_results(
+            results,
+            &transform.order_by,
+            transform.limit,
+            transform.offset,
+        );
+        merged.execution_time_ms = started.elapsed().as_millis() as u64;
+        Ok(merged)
+    }
+
+    /// Every tuple of a relation: from the shards if it is sharded,
+    /// otherwise from this server
+    async fn fetch(
+        &self,
+        handler: &Handler,
+        kg: &str,
+        relation: &str,
+        arity: usize,
+        placed: bool,
+    ) -> Result<Vec<WireTuple>, String> {
+        let vars: Vec<String> = (0..arity).map(|i| format!("X{i}")).collect();
+        let program = format!("?{relation}({})", vars.join(", "));
+        if !placed {
+            let result = handler.query_program(Some(kg.to_string()), program).await?;
+            return Ok(result.rows);
+        }
+        let everywhere: Vec<usize> = (0..self.config.shards.len()).collect();
+        let results = self.run_each(kg, &everywhere, &program).await?;
+        let mut seen = HashSet::new();
+        Ok(results
+            .into_iter()
+            .flat_map(|result| result.rows)
+            .filter(|row| seen.insert(row_key(row)))
+            .collect())
+    }
+
+    /// Run the same program on each of `shards`
+    async fn run_each(
+        &self,
+        kg: &str,
+        shards: &[usize],
+        program: &str,
+    ) -> Result<Vec<QueryResult>, String> {
+        let programs = shards
+            .iter()
+            .map(|&shard| (shard, program.to_string()))
+            .collect();
+        self.run_programs(kg, programs).await
+    }
+
+    /// Run a program per shard concurrently
+    async fn run_programs(
+        &self,
+        kg: &str,
+        programs: Vec<(usize, String)>,
+    ) -> Result<Vec<QueryResult>, String> {
+        join_all(
+            programs
+                .into_iter()
+                .map(|(shard, program)| self.run(shard, kg, program)),
+        )
+        .await
+        .into_iter()
+        .collect()
+    }
+
+    async fn run(&self, shard: usize, kg: &str, program: String) -> Result<QueryResult, String> {
+        let node = &self.config.shards[shard];
+        let url = format!("{}{EXECUTE_PATH}", node.url.trim_end_matches('/'));
+        let body = ShardRequest {
+            knowledge_graph: kg.to_string(),
+            program,
+        };
+        let mut request = self.client.post(&url).json(&body);
+        if let Some(key) = &self.config.api_key {
+            request = request.bearer_auth(key);
+        }
+        let response = request.send().await.map_err(|e| {
+            warn!(shard = %node.id, error = %e, "shard_unreachable");
+            format!("Shard '{}' is unreachable: {e}", node.id)
+        })?;
+        let status = response.status();
+        if !status.is_success() {
+            let body: serde_json::Value = response.json().await.unwrap_or_default();
+            let message = body["error"]["message"]
+                .as_str()
+                .map_or_else(|| status.to_string(), str::to_string);
+            return Err(format!("Shard '{}': {message}", node.id));
+        }
+        let result: QueryResult = response
+            .json()
+            .await
+            .map_err(|e| format!("Shard '{}' sent an invalid result: {e}", node.id))?;
+        if let Some(error) = reported_error(&result) {
+            return Err(format!("Shard '{}': {error}", node.id));
+        }
+        Ok(result)
+    }
+}
+
+fn sharded_schema_error(relation: &str) -> String {
+    format!(
+        "Relation '{relation}' is sharded; unshard it with '.rel alter {relation} shard none' before changing its schema"
+    )
+}
+
+fn load_placements(path: &Path) -> Placements {
+    let Ok(bytes) = fs::read(path) else {
+        return Placements::new();
+    };
+    match serde_json::from_slice(&bytes) {
+        Ok(placements) => placements,
+        Err(e) => {
+            warn!(path = %path.display(), error = %e, "shard_placements_invalid");
+            Placements::new()
+        }
+    }
+}
+
+/// The error a shard reported as a message row, if any
+fn reported_error(result: &QueryResult) -> Option<&str> {
+    if !matches!(result.schema.as_slice(), [column] if column.name == "message") {
+        return None;
+    }
+    result
+        .rows
+        .iter()
+        .filter_map(|row| row.values.first().and_then(WireValue::as_str))
+        .find(|message| message.starts_with("Error"))
+}
+
+// ── Placement ───────────────────────────────────────────────────────────────
+
+/// Shard a key value hashes to. Values are hashed by their display form,
+/// so an integer key lands on the same shard however wide it is stored.
+fn value_shard(value: &WireValue, shards: usize) -> usize {
+    crc32fast::hash(value.to_string().as_bytes()) as usize % shards.max(1)
+}
+
+/// Shard a constant key term hashes to; `None` for variables and other
+/// non-constant terms
+fn term_shard(term: &Term, shards: usize) -> Option<usize> {
+    let value = term_to_value(term).ok()?;
+    Some(value_shard(&WireValue::from_value(&value), shards))
+}
+
+/// Whether `term` names the same key as `key`: the same variable, or an
+/// equal constant
+fn same_key(term: Option<&Term>, key: &Term) -> bool {
+    match (term, key) {
+        (_, Term::Placeholder) | (Some(Term::Placeholder) | None, _) => false,
+        (Some(term), key) => term == key,
+    }
+}
+
+// ── Query planning ──────────────────────────────────────────────────────────
+
+/// How an atom of a routed query reaches the shards it runs on
+#[derive(Debug, Clone, PartialEq)]
+enum Route {
+    /// Its tuples are already there
+    InPlace,
+    /// Fetched, and each tuple sent to the shard its column hashes to
+    Shuffle(usize),
+    /// Fetched and sent whole to every shard the query runs on
+    Broadcast,
+}
+
+#[derive(Debug)]
+struct QueryPlan {
+    /// Route of each atom, in `query_atoms` order
+    routes: Vec<Route>,
+    /// The only shard to run on, when the anchor's key is a constant
+    target: Option<usize>,
+}
+
+/// The goal and the atoms of the body, in order, with whether negated
+fn query_atoms(goal: &QueryGoal) -> Vec<(&Atom, bool)> {
+    std::iter::once((&goal.goal, false))
+        .chain(goal.body.iter().filter_map(|pred| match pred {
+            BodyPredicate::Positive(atom) => Some((atom, false)),
+            BodyPredicate::Negated(atom) => Some((atom, true)),
+            _ => None,
+        }))
+        .collect()
+}
+
+fn plan_query(
+    atoms: &[(&Atom, bool)],
+    placement: impl Fn(&str) -> Option<Placement>,
+    shards: usize,
+) -> Result<QueryPlan, String> {
+    let placements: Vec<Option<Placement>> = atoms
+        .iter()
+        .map(|(atom, _)| placement(&atom.relation))
+        .collect();
+    let anchor =
+        atoms
+            .iter()
+            .zip(&placements)
+            .enumerate()
+            .find_map(|(i, ((atom, negated), placement))| match placement {
+                Some(Placement::Hash { column, index }) if !negated => {
+                    Some((i, *atom, column, *index))
+                }
+                _ => None,
+            });
+
+    // Nothing to anchor at: every shard holds the broadcast relations, so
+    // the first one runs the query with the rest sent to it
+    let Some((anchor, anchor_atom, column, index)) = anchor else {
+        let routes = placements
+            .iter()
+            .map(|placement| match placement {
+                Some(Placement::Broadcast) => Route::InPlace,
+                _ => Route::Broadcast,
+            })
+            .collect();
+        return Ok(QueryPlan {
+            routes,
+            target: Some(0),
+        });
+    };
+
+    let key = anchor_atom.args.get(index).ok_or_else(|| {
+        format!(
+            "'{}' is queried with {} argument(s), short of its shard key '{column}'",
+            anchor_atom.relation,
+            anchor_atom.args.len()
+        )
+    })?;
+    let target = term_shard(key, shards);
+
+    let routes = atoms
+        .iter()
+        .zip(&placements)
+        .enumerate()
+        .map(|(i, ((atom, negated), placement))| match placement {
+            _ if i == anchor => Route::InPlace,
+            Some(Placement::Broadcast) => Route::InPlace,
+            Some(Placement::Hash { index, .. }) if same_key(atom.args.get(*index), key) => {
+                Route::InPlace
+            }
+            Some(Placement::Hash { .. }) if !negated && key.is_variable() => atom
+                .args
+                .iter()
+                .position(|arg| arg == key)
+                .map_or(Route::Broadcast, Route::Shuffle),
+            _ => Route::Broadcast,
+        })
+        .collect();
+    Ok(QueryPlan { routes, target })
+}
+
+// ── Rendering ───────────────────────────────────────────────────────────────
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// IQL text of a term. Strings are escaped and floats keep their decimal
+/// point, so constants parse back to the same value.
+fn render_term(term: &Term) -> String {
+    match term {
+        Term::StringConstant(text) => quote(text),
+        Term::FloatConstant(value) => format!("{value:?}"),
+        term => term.to_string(),
+    }
+}
+
+/// IQL literal of a value, for the types a shard can be sent
+fn render_value(value: &WireValue) -> Result<String, String> {
+    match value {
+        WireValue::Int32(n) => Ok(n.to_string()),
+        WireValue::Int64(n) => Ok(n.to_string()),
+        WireValue::Float64(f) if f.is_finite() => Ok(format!("{f:?}")),
+        WireValue::String(text) => Ok(quote(text)),
+        WireValue::Bool(b) => Ok(b.to_string()),
+        WireValue::Uuid(uuid) => Ok(format!("uuid(\"{}\")", uuid.hyphenated())),
+        WireValue::Date(days) => Ok(format!(
+            "date(\"{}\")",
+            crate::temporal_ops::format_date(*days)
+        )),
+        WireValue::Vector(values) => {
+            let values: Vec<String> = values.iter().map(|v| format!("{v:?}")).collect();
+            Ok(format!("[{}]", values.join(", ")))
+        }
+        other => Err(format!(
+            "{} values cannot be sent between shards",
+            other.data_type()
+        )),
+    }
+}
+
+/// An inserted term as the value it stands for, so generated values such
+/// as `uuid_v7()` are the same on the shard as the one the key hashed
+fn render_insert_term(term: &Term) -> String {
+    term_to_value(term)
+        .ok()
+        .and_then(|value| render_value(&WireValue::from_value(&value)).ok())
+        .unwrap_or_else(|| render_term(term))
+}
+
+fn render_tuple(values: Vec<String>) -> String {
+    if values.len() == 1 {
+        format!("({},)", values[0])
+    } else {
+        format!("({})", values.join(", "))
+    }
+}
+
+fn render_atom(relation: &str, atom: &Atom) -> String {
+    let args: Vec<String> = atom.args.iter().map(render_term).collect();
+    format!("{relation}({})", args.join(", "))
+}
+
+/// A session fact line carrying `row` as a tuple of `relation`
+fn fact_line(relation: &str, row: &WireTuple) -> Result<String, String> {
+    let values = row
+        .values
+        .iter()
+        .map(render_value)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(format!("{relation}({})\n", values.join(", ")))
+}
+
+/// The query with its atoms reading `names`, in `query_atoms` order.
+/// Sorting and pagination are left to the router.
+fn render_query(goal: &QueryGoal, names: &[String]) -> String {
+    let mut names = names.iter();
+    let mut next = |atom: &Atom| {
+        let name = names.next().map_or(atom.relation.as_str(), String::as_str);
+        render_atom(name, atom)
+    };
+    let mut parts = vec![next(&goal.goal)];
+    for pred in &goal.body {
+        parts.push(match pred {
+            BodyPredicate::Positive(atom) => next(atom),
+            BodyPredicate::Negated(atom) => format!("!{}", next(atom)),
+            BodyPredicate::Comparison(left, op, right) => {
+                format!("{} {op} {}", render_term(left), render_term(right))
+            }
+            pred => pred.to_string(),
+        });
+    }
+    format!("?{}", parts.join(", "))
+}
+
+// ── Merging ─────────────────────────────────────────────────────────────────
+
+/// Identity of a row for deduplication
+fn row_key(row: &WireTuple) -> String {
+    serde_json::to_string(&row.values).unwrap_or_default()
+}
+
+/// Union the shards' rows without duplicates, then sort and paginate
+fn merge_results(
+    results: Vec<QueryResult>,
+    order_by: &[(usize, crate::statement::SortDirection)],
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> QueryResult {
+    let schema = results
+        .iter()
+        .map(|result| &result.schema)
+        .find(|schema| !schema.is_empty())
+        .cloned()
+        .unwrap_or_default();
+    let mut seen = HashSet::new();
+    let rows: Vec<WireTuple> = results
+        .into_iter()
+        .flat_map(|result| result.rows)
+        .filter(|row| seen.insert(row_key(row)))
+        .collect();
+    let rows = sort_rows(rows, order_by);
+    let total_count = rows.len();
+    let rows = apply_pagination(rows, limit, offset);
+    let truncated = rows.len() < total_count;
+    QueryResult {
+        rows,
+        schema,
+        total_count,
+        truncated,
+        execution_time_ms: 0,
+        metadata: None,
+        switched_kg: None,
+        proof_trees: None,
+        timing_breakdown: None,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::protocol::wire::ColumnDef;
+    use crate::statement::{parse_query, SortDirection};
+
+    fn placements(relation: &str) -> Option<Placement> {
+        match relation {
+            "orders" => Some(Placement::Hash {
+                column: "customer".to_string(),
+                index: 0,
+            }),
+            "customers" => Some(Placement::Hash {
+                column: "id".to_string(),
+                index: 0,
+            }),
+            "items" => Some(Placement::Hash {
+                column: "sku".to_string(),
+                index: 1,
+            }),
+            "regions" => Some(Placement::Broadcast),
+            _ => None,
+        }
+    }
+
+    fn plan_for(query: &str) -> QueryPlan {
+        let goal = parse_query(query).unwrap();
+        plan_query(&query_atoms(&goal), placements, 4).unwrap()
+    }
+
+    #[test]
+    fn test_co_located_and_broadcast_atoms_stay_in_place() {
+        let plan = plan_for("orders(C, O), customers(C, R), regions(R, N)");
+        assert_eq!(plan.routes, vec![Route::InPlace; 3]);
+        assert_eq!(plan.target, None);
+
+        // A constant key runs on the one shard holding it
+        let plan = plan_for("orders(7, O), customers(7, R)");
+        assert_eq!(plan.routes, vec![Route::InPlace; 2]);
+        assert_eq!(plan.target, term_shard(&Term::Constant(7), 4));
+    }
+
+    #[test]
+    fn test_other_keys_are_shuffled_or_broadcast() {
+        // items holds the anchor's key outside its own key column
+        let plan = plan_for("orders(C, O), items(O, S, C)");
+        assert_eq!(plan.routes, vec![Route::InPlace, Route::Shuffle(2)]);
+
+        // Router-local relations, unrelated keys and negations are sent whole
+        let plan = plan_for("orders(C, O), vip(C), items(O, S, Q), !customers(O, C)");
+        assert_eq!(
+            plan.routes,
+            vec![
+                Route::InPlace,
+                Route::Broadcast,
+                Route::Broadcast,
+                Route::Broadcast
+            ]
+        );
+
+        // Without a positive hash-sharded atom the first shard runs it all
+        let plan = plan_for("regions(R, N), !orders(R, N)");
+        assert_eq!(plan.routes, vec![Route::InPlace, Route::Broadcast]);
+        assert_eq!(plan.target, Some(0));
+    }
+
+    #[test]
+    fn test_render_query_renames_sent_atoms() {
+        let goal = parse_query("orders(C, \"a\"), items(O, S, C), X = 1.5, !vip(C)").unwrap();
+        let names = vec![
+            "orders".to_string(),
+            "__shard1_items".to_string(),
+            "vip".to_string(),
+        ];
+        assert_eq!(
+            render_query(&goal, &names),
+            "?orders(C, \"a\"), __shard1_items(O, S, C), X = 1.5, !vip(C)"
+        );
+        assert_eq!(
+            render_term(&Term::StringConstant("say \"hi\"".into())),
+            "\"say \\\"hi\\\"\""
+        );
+        assert_eq!(render_term(&Term::FloatConstant(2.0)), "2.0");
+        let row = WireTuple::new(vec![WireValue::Int32(1), WireValue::String("x".into())]);
+        assert_eq!(
+            fact_line("__shard1_t", &row).unwrap(),
+            "__shard1_t(1, \"x\")\n"
+        );
+        assert!(fact_line("t", &WireTuple::new(vec![WireValue::Timestamp(1)])).is_err());
+    }
+
+    #[test]
+    fn test_keys_hash_the_same_however_stored() {
+        let from_term = term_shard(&Term::Constant(42), 8).unwrap();
+        assert_eq!(from_term, value_shard(&WireValue::Int32(42), 8));
+        let from_term = term_shard(&Term::StringConstant("alice".into()), 8).unwrap();
+        assert_eq!(
+            from_term,
+            value_shard(&WireValue::String("alice".into()), 8)
+        );
+        assert_eq!(term_shard(&Term::Variable("X".into()), 8), None);
+        assert!((0..100).all(|n| value_shard(&WireValue::Int64(n), 3) < 3));
+    }
+
+    #[test]
+    fn test_merge_dedupes_sorts_and_paginates() {
+        let result = |rows: &[i64]| QueryResult {
+            rows: rows
+                .iter()
+                .map(|&n| WireTuple::new(vec![WireValue::Int64(n)]))
+                .collect(),
+            schema: vec![ColumnDef::int64("X")],
+            total_count: rows.len(),
+            truncated: false,
+            execution_time_ms: 0,
+            metadata: None,
+            switched_kg: None,
+            proof_trees: None,
+            timing_breakdown: None,
+        };
+        let merged = merge_results(
+            vec![result(&[5, 1]), result(&[]), result(&[3, 1])],
+            &[(0, SortDirection::Desc)],
+            Some(2),
+            Some(1),
+        );
+        let values: Vec<i64> = merged
+            .rows
+            .iter()
+            .map(|row| row.values[0].as_i64().unwrap())
+            .collect();
+        assert_eq!(values, vec![3, 1]);
+        assert_eq!(merged.total_count, 3);
+        assert!(merged.truncated);
+        assert_eq!(merged.schema[0].name, "X");
+    }
+}
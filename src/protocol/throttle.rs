@@ -0,0 +1,366 @@
+//! Per-client limits on statement execution
+//!
+//! Every transport admits a program through `ClientLimits` before running
+//! it. A client is an API key when the caller used one, otherwise the
+//! connection (WebSocket) or user. Three limits apply, each off at 0:
+//!
+//! - concurrent programs per client (`max_concurrent_queries_per_client`)
+//! - statements per second per client (`max_statements_per_sec`)
+//! - result size per request (`max_result_bytes`)
+//!
+//! A client over a limit gets a `Throttled` error naming the limit, so it
+//! can back off instead of retrying blindly.
+//!
+//! On shutdown the limits start draining: every new program is refused
+//! with `shutting_down`, and the server waits for the programs already
+//! admitted to finish before flushing storage.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+
+use crate::auth::AuthIdentity;
+use crate::config::RateLimitConfig;
+
+/// Key of the client making a request: its API key when it used one,
+/// otherwise `connection` (a WebSocket session) or the user
+pub fn client_key(identity: &AuthIdentity, connection: Option<&str>) -> String {
+    match (&identity.api_key, connection) {
+        (Some(key), _) => format!("key:{key}"),
+        (None, Some(connection)) => connection_key(connection),
+        (None, None) => format!("user:{}", identity.username),
+    }
+}
+
+/// Key of an unauthenticated connection (a session-scoped WebSocket)
+pub fn connection_key(connection: &str) -> String {
+    format!("conn:{connection}")
+}
+
+/// Clients tracked before idle ones are swept
+const SWEEP_THRESHOLD: usize = 4096;
+
+/// Which limit a client hit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThrottleLimit {
+    ConcurrentQueries,
+    StatementsPerSec,
+    ResultBytes,
+    /// The server is draining and admits no new programs
+    ShuttingDown,
+}
+
+impl ThrottleLimit {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ThrottleLimit::ConcurrentQueries => "concurrent_queries",
+            ThrottleLimit::StatementsPerSec => "statements_per_sec",
+            ThrottleLimit::ResultBytes => "result_bytes",
+            ThrottleLimit::ShuttingDown => "shutting_down",
+        }
+    }
+}
+
+/// A request refused by a per-client limit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Throttled {
+    pub limit: ThrottleLimit,
+    /// The configured maximum
+    pub max: u64,
+    /// When a retry can succeed; `None` for results that are too large,
+    /// which fail the same way every time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
+}
+
+impl fmt::Display for Throttled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.limit {
+            ThrottleLimit::ConcurrentQueries => write!(
+                f,
+                "Throttled: more than {} concurrent queries for this client",
+                self.max
+            ),
+            ThrottleLimit::StatementsPerSec => write!(
+                f,
+                "Throttled: more than {} statements per second for this client",
+                self.max
+            ),
+            ThrottleLimit::ResultBytes => write!(
+                f,
+                "Throttled: result larger than {} bytes; narrow the query or page through it",
+                self.max
+            ),
+            ThrottleLimit::ShuttingDown => {
+                write!(f, "Server is shutting down; retry on another server")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Throttled {}
+
+#[derive(Debug)]
+struct ClientUsage {
+    in_flight: usize,
+    window_start: Instant,
+    statements: u64,
+}
+
+/// Usage of every client, checked against the configured limits
+#[derive(Debug)]
+pub struct ClientLimits {
+    clients: DashMap<String, ClientUsage>,
+    max_concurrent: usize,
+    max_statements_per_sec: u32,
+    max_result_bytes: usize,
+    /// Set once shutdown begins; no program is admitted after
+    draining: AtomicBool,
+    /// Programs admitted and not yet finished, across all clients
+    running: AtomicUsize,
+    /// Notified when `running` drops to zero
+    idle: Notify,
+}
+
+impl ClientLimits {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        Self {
+            clients: DashMap::new(),
+            max_concurrent: config.max_concurrent_queries_per_client,
+            max_statements_per_sec: config.max_statements_per_sec,
+            max_result_bytes: config.max_result_bytes,
+            draining: AtomicBool::new(false),
+            running: AtomicUsize::new(0),
+            idle: Notify::new(),
+        }
+    }
+
+    /// Admit `statements` statements from `client`. The returned permit
+    /// counts as one running program until it is dropped.
+    pub fn admit(
+        self: &Arc<Self>,
+        client: &str,
+        statements: u64,
+    ) -> Result<ClientPermit, Throttled> {
+        if self.is_draining() {
+            return Err(Throttled {
+                limit: ThrottleLimit::ShuttingDown,
+                max: 0,
+                retry_after_ms: None,
+            });
+        }
+        if self.clients.len() > SWEEP_THRESHOLD {
+            self.sweep();
+        }
+        let now = Instant::now();
+        let mut usage = self
+            .clients
+            .entry(client.to_string())
+            .or_insert_with(|| ClientUsage {
+                in_flight: 0,
+                window_start: now,
+                statements: 0,
+            });
+
+        if self.max_concurrent > 0 && usage.in_flight >= self.max_concurrent {
+            return Err(Throttled {
+                limit: ThrottleLimit::ConcurrentQueries,
+                max: self.max_concurrent as u64,
+                // Queries finish at their own pace; suggest a short wait
+                retry_after_ms: Some(100),
+            });
+        }
+
+        if self.max_statements_per_sec > 0 {
+            let elapsed = now.duration_since(usage.window_start);
+            if elapsed >= Duration::from_secs(1) {
+                usage.window_start = now;
+                usage.statements = 0;
+            }
+            let max = u64::from(self.max_statements_per_sec);
+            // A program larger than the whole budget still runs in an
+            // empty window, so it is not refused forever
+            if usage.statements > 0 && usage.statements + statements > max {
+                let remaining = Duration::from_secs(1).saturating_sub(elapsed);
+                return Err(Throttled {
+                    limit: ThrottleLimit::StatementsPerSec,
+                    max,
+                    retry_after_ms: Some(remaining.as_millis().max(1) as u64),
+                });
+            }
+            usage.statements += statements;
+        }
+
+        usage.in_flight += 1;
+        self.running.fetch_add(1, Ordering::SeqCst);
+        Ok(ClientPermit {
+            limits: Arc::clone(self),
+            client: client.to_string(),
+        })
+    }
+
+    /// Check the size of a result about to be sent
+    pub fn check_result_bytes(&self, bytes: usize) -> Result<(), Throttled> {
+        if self.max_result_bytes > 0 && bytes > self.max_result_bytes {
+            return Err(Throttled {
+                limit: ThrottleLimit::ResultBytes,
+                max: self.max_result_bytes as u64,
+                retry_after_ms: None,
+            });
+        }
+        Ok(())
+    }
+
+    /// Programs currently running for `client`
+    pub fn in_flight(&self, client: &str) -> usize {
+        self.clients.get(client).map_or(0, |usage| usage.in_flight)
+    }
+
+    /// Refuse every program from now on
+    pub fn start_draining(&self) {
+        self.draining.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    /// Programs currently running, across all clients
+    pub fn running(&self) -> usize {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Wait until no admitted program is running, or `timeout` passes.
+    /// Returns the number still running.
+    pub async fn wait_idle(&self, timeout: Duration) -> usize {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            // Register before checking, so a permit dropped in between
+            // still wakes us
+            let notified = self.idle.notified();
+            let running = self.running();
+            if running == 0 {
+                return 0;
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return self.running();
+            }
+        }
+    }
+
+    /// Forget clients with nothing running and no statements this second
+    fn sweep(&self) {
+        let now = Instant::now();
+        self.clients.retain(|_, usage| {
+            usage.in_flight > 0 || now.duration_since(usage.window_start) < Duration::from_secs(1)
+        });
+    }
+}
+
+/// A running program, counted against its client until dropped
+#[derive(Debug)]
+pub struct ClientPermit {
+    limits: Arc<ClientLimits>,
+    client: String,
+}
+
+impl Drop for ClientPermit {
+    fn drop(&mut self) {
+        if let Some(mut usage) = self.limits.clients.get_mut(&self.client) {
+            usage.in_flight = usage.in_flight.saturating_sub(1);
+        }
+        if self.limits.running.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.limits.idle.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn limits(concurrent: usize, per_sec: u32, result_bytes: usize) -> Arc<ClientLimits> {
+        let config = RateLimitConfig {
+            max_concurrent_queries_per_client: concurrent,
+            max_statements_per_sec: per_sec,
+            max_result_bytes: result_bytes,
+            ..RateLimitConfig::default()
+        };
+        Arc::new(ClientLimits::new(&config))
+    }
+
+    #[test]
+    fn test_concurrency_cap_per_client() {
+        let limits = limits(2, 0, 0);
+        let first = limits.admit("a", 1).unwrap();
+        let _second = limits.admit("a", 1).unwrap();
+        let err = limits.admit("a", 1).unwrap_err();
+        assert_eq!(err.limit, ThrottleLimit::ConcurrentQueries);
+        assert_eq!(err.max, 2);
+        // Other clients are unaffected
+        assert!(limits.admit("b", 1).is_ok());
+
+        drop(first);
+        assert_eq!(limits.in_flight("a"), 1);
+        assert!(limits.admit("a", 1).is_ok());
+    }
+
+    #[test]
+    fn test_statement_rate() {
+        let limits = limits(0, 5, 0);
+        assert!(limits.admit("a", 3).is_ok());
+        assert!(limits.admit("a", 2).is_ok());
+        let err = limits.admit("a", 1).unwrap_err();
+        assert_eq!(err.limit, ThrottleLimit::StatementsPerSec);
+        assert!(err.retry_after_ms.unwrap() <= 1000);
+        assert!(err.to_string().starts_with("Throttled"));
+
+        // A program over the whole budget runs in a fresh window
+        assert!(limits.admit("b", 50).is_ok());
+        assert!(limits.admit("b", 1).is_err());
+    }
+
+    #[test]
+    fn test_result_bytes_and_unlimited() {
+        let capped = limits(0, 0, 100);
+        assert!(capped.check_result_bytes(100).is_ok());
+        let err = capped.check_result_bytes(101).unwrap_err();
+        assert_eq!(err.limit, ThrottleLimit::ResultBytes);
+        assert_eq!(err.retry_after_ms, None);
+
+        let unlimited = limits(0, 0, 0);
+        let permits: Vec<_> = (0..100)
+            .map(|_| unlimited.admit("a", 10).unwrap())
+            .collect();
+        assert_eq!(unlimited.in_flight("a"), 100);
+        assert!(unlimited.check_result_bytes(usize::MAX).is_ok());
+        drop(permits);
+        assert_eq!(unlimited.in_flight("a"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_draining_refuses_new_programs_and_waits_for_running() {
+        let limits = limits(0, 0, 0);
+        let running = limits.admit("a", 1).unwrap();
+        limits.start_draining();
+        let err = limits.admit("b", 1).unwrap_err();
+        assert_eq!(err.limit, ThrottleLimit::ShuttingDown);
+        assert_eq!(err.retry_after_ms, None);
+
+        assert_eq!(limits.wait_idle(Duration::from_millis(20)).await, 1);
+        let finish = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            drop(running);
+        });
+        assert_eq!(limits.wait_idle(Duration::from_secs(5)).await, 0);
+        finish.await.unwrap();
+        assert_eq!(limits.running(), 0);
+    }
+}
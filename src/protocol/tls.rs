@@ -0,0 +1,345 @@
+//! TLS for the HTTP, WebSocket and gRPC listeners, and for clients
+//!
+//! Built on rustls with the ring provider:
+//!
+//! - certificates and keys are read from PEM files
+//! - the server picks its certificate by the SNI name the client sends,
+//!   falling back to the default certificate
+//! - with a client CA configured, client certificates are verified
+//!   (mutual TLS), required or optional
+//! - clients trust the web PKI roots plus an optional private CA, and can
+//!   present a certificate of their own
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::{ClientHello, ResolvesServerCert, WebPkiClientVerifier};
+use rustls::sign::CertifiedKey;
+use rustls::{ClientConfig, RootCertStore, ServerConfig};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+
+use crate::config::TlsConfig;
+
+/// Time allowed for a client to complete the handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn provider() -> Arc<CryptoProvider> {
+    Arc::new(rustls::crypto::ring::default_provider())
+}
+
+fn open(path: &Path) -> Result<BufReader<File>, String> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|e| format!("Cannot read {}: {e}", path.display()))
+}
+
+/// Read a PEM certificate chain
+pub fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, String> {
+    let certs = rustls_pemfile::certs(&mut open(path)?)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Invalid certificate in {}: {e}", path.display()))?;
+    if certs.is_empty() {
+        return Err(format!("No certificates in {}", path.display()));
+    }
+    Ok(certs)
+}
+
+/// Read the first private key from a PEM file
+pub fn load_key(path: &Path) -> Result<PrivateKeyDer<'static>, String> {
+    rustls_pemfile::private_key(&mut open(path)?)
+        .map_err(|e| format!("Invalid private key in {}: {e}", path.display()))?
+        .ok_or_else(|| format!("No private key in {}", path.display()))
+}
+
+fn load_roots(path: &Path) -> Result<RootCertStore, String> {
+    let mut roots = RootCertStore::empty();
+    for cert in load_certs(path)? {
+        roots
+            .add(cert)
+            .map_err(|e| format!("Invalid CA certificate in {}: {e}", path.display()))?;
+    }
+    Ok(roots)
+}
+
+fn certified_key(
+    provider: &CryptoProvider,
+    cert_path: &Path,
+    key_path: &Path,
+) -> Result<Arc<CertifiedKey>, String> {
+    let certs = load_certs(cert_path)?;
+    let key = provider
+        .key_provider
+        .load_private_key(load_key(key_path)?)
+        .map_err(|e| format!("Unsupported private key in {}: {e}", key_path.display()))?;
+    Ok(Arc::new(CertifiedKey::new(certs, key)))
+}
+
+/// Picks the certificate for the SNI name in the client hello
+#[derive(Debug)]
+struct SniResolver {
+    by_name: HashMap<String, Arc<CertifiedKey>>,
+    default: Arc<CertifiedKey>,
+}
+
+impl ResolvesServerCert for SniResolver {
+    fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        let key = client_hello
+            .server_name()
+            .and_then(|name| self.by_name.get(&name.to_ascii_lowercase()))
+            .unwrap_or(&self.default);
+        Some(Arc::clone(key))
+    }
+}
+
+/// Build the server configuration from `http.tls`
+pub fn server_config(config: &TlsConfig) -> Result<ServerConfig, String> {
+    let (Some(cert_path), Some(key_path)) = (&config.cert_path, &config.key_path) else {
+        return Err("http.tls: cert_path and key_path are required".to_string());
+    };
+    let provider = provider();
+
+    let mut by_name = HashMap::new();
+    for entry in &config.sni {
+        let key = certified_key(&provider, &entry.cert_path, &entry.key_path)?;
+        by_name.insert(entry.server_name.to_ascii_lowercase(), key);
+    }
+    let resolver = SniResolver {
+        by_name,
+        default: certified_key(&provider, cert_path, key_path)?,
+    };
+
+    let builder = ServerConfig::builder_with_provider(Arc::clone(&provider))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("TLS setup failed: {e}"))?;
+    let builder = match &config.client_ca_path {
+        Some(ca_path) => {
+            let verifier = WebPkiClientVerifier::builder_with_provider(
+                Arc::new(load_roots(ca_path)?),
+                Arc::clone(&provider),
+            );
+            let verifier = if config.require_client_cert {
+                verifier
+            } else {
+                verifier.allow_unauthenticated()
+            };
+            builder.with_client_cert_verifier(
+                verifier
+                    .build()
+                    .map_err(|e| format!("Invalid client CA {}: {e}", ca_path.display()))?,
+            )
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut server = builder.with_cert_resolver(Arc::new(resolver));
+    server.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(server)
+}
+
+/// Build a client configuration trusting the web PKI roots and `ca_path`,
+/// presenting `client_cert` (certificate and key paths) if given
+pub fn client_config(
+    ca_path: Option<&Path>,
+    client_cert: Option<(&Path, &Path)>,
+) -> Result<ClientConfig, String> {
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    if let Some(ca_path) = ca_path {
+        for cert in load_certs(ca_path)? {
+            roots
+                .add(cert)
+                .map_err(|e| format!("Invalid CA certificate in {}: {e}", ca_path.display()))?;
+        }
+    }
+
+    let builder = ClientConfig::builder_with_provider(provider())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("TLS setup failed: {e}"))?
+        .with_root_certificates(roots);
+    match client_cert {
+        Some((cert_path, key_path)) => builder
+            .with_client_auth_cert(load_certs(cert_path)?, load_key(key_path)?)
+            .map_err(|e| format!("Invalid client certificate: {e}")),
+        None => Ok(builder.with_no_client_auth()),
+    }
+}
+
+/// Accept TLS connections on `listener` until the receiver is dropped.
+/// Handshakes run concurrently; failed ones are logged and dropped.
+pub fn accept_tls(
+    listener: TcpListener,
+    config: Arc<ServerConfig>,
+) -> mpsc::Receiver<io::Result<TlsStream<TcpStream>>> {
+    let (tx, rx) = mpsc::channel(64);
+    let acceptor = TlsAcceptor::from(config);
+    tokio::spawn(async move {
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    tracing::warn!(error = %e, "tls_accept_failed");
+                    continue;
+                }
+            };
+            if tx.is_closed() {
+                break;
+            }
+            let acceptor = acceptor.clone();
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(tls)) => {
+                        let _ = tx.send(Ok(tls)).await;
+                    }
+                    Ok(Err(e)) => tracing::debug!(%peer, error = %e, "tls_handshake_failed"),
+                    Err(_) => tracing::debug!(%peer, "tls_handshake_timeout"),
+                }
+            });
+        }
+    });
+    rx
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::config::SniCertConfig;
+    use rustls::pki_types::ServerName;
+    use std::path::PathBuf;
+    use tokio_rustls::TlsConnector;
+
+    struct Pem {
+        cert: PathBuf,
+        key: PathBuf,
+    }
+
+    /// Write a certificate for `name` signed by `ca`, or self-signed
+    fn write_cert(
+        dir: &Path,
+        file: &str,
+        name: &str,
+        ca: Option<&(rcgen::Certificate, rcgen::KeyPair)>,
+    ) -> (Pem, rcgen::Certificate, rcgen::KeyPair) {
+        let key_pair = rcgen::KeyPair::generate().unwrap();
+        let mut params = rcgen::CertificateParams::new(vec![name.to_string()]).unwrap();
+        if ca.is_none() {
+            params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        }
+        let cert = match ca {
+            Some((ca_cert, ca_key)) => params.signed_by(&key_pair, ca_cert, ca_key).unwrap(),
+            None => params.self_signed(&key_pair).unwrap(),
+        };
+        let pem = Pem {
+            cert: dir.join(format!("{file}.pem")),
+            key: dir.join(format!("{file}.key")),
+        };
+        std::fs::write(&pem.cert, cert.pem()).unwrap();
+        std::fs::write(&pem.key, key_pair.serialize_pem()).unwrap();
+        (pem, cert, key_pair)
+    }
+
+    /// Handshake over an in-memory pipe; returns the server's leaf
+    /// certificate as seen by the client
+    async fn handshake(
+        server: ServerConfig,
+        client: ClientConfig,
+        name: &str,
+    ) -> Result<CertificateDer<'static>, String> {
+        let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+        let acceptor = TlsAcceptor::from(Arc::new(server));
+        let server_task = tokio::spawn(async move { acceptor.accept(server_io).await });
+        let connector = TlsConnector::from(Arc::new(client));
+        let name = ServerName::try_from(name.to_string()).unwrap();
+        let connected = connector.connect(name, client_io).await;
+        let accepted = server_task.await.unwrap();
+        let stream = connected.map_err(|e| e.to_string())?;
+        accepted.map_err(|e| e.to_string())?;
+        Ok(stream.get_ref().1.peer_certificates().unwrap()[0].clone())
+    }
+
+    #[tokio::test]
+    async fn test_sni_selects_certificate() {
+        let dir = tempfile::tempdir().unwrap();
+        let ca = write_cert(dir.path(), "ca", "ca", None);
+        let ca_pair = (ca.1, ca.2);
+        let default = write_cert(dir.path(), "default", "db.example.com", Some(&ca_pair));
+        let eu = write_cert(dir.path(), "eu", "eu.example.com", Some(&ca_pair));
+
+        let config = TlsConfig {
+            enabled: true,
+            cert_path: Some(default.0.cert.clone()),
+            key_path: Some(default.0.key.clone()),
+            sni: vec![SniCertConfig {
+                server_name: "eu.example.com".to_string(),
+                cert_path: eu.0.cert.clone(),
+                key_path: eu.0.key.clone(),
+            }],
+            ..TlsConfig::default()
+        };
+        let server = server_config(&config).unwrap();
+        assert_eq!(server.alpn_protocols[0], b"h2");
+        let client = client_config(Some(&ca.0.cert), None).unwrap();
+
+        let seen = handshake(server.clone(), client.clone(), "eu.example.com")
+            .await
+            .unwrap();
+        assert_eq!(seen, *eu.1.der());
+        let seen = handshake(server, client, "db.example.com").await.unwrap();
+        assert_eq!(seen, *default.1.der());
+    }
+
+    #[tokio::test]
+    async fn test_client_certificate_verification() {
+        let dir = tempfile::tempdir().unwrap();
+        let ca = write_cert(dir.path(), "ca", "ca", None);
+        let ca_pair = (ca.1, ca.2);
+        let server_pem = write_cert(dir.path(), "server", "localhost", Some(&ca_pair)).0;
+        let client_pem = write_cert(dir.path(), "client", "client", Some(&ca_pair)).0;
+
+        let mut config = TlsConfig {
+            enabled: true,
+            cert_path: Some(server_pem.cert),
+            key_path: Some(server_pem.key),
+            client_ca_path: Some(ca.0.cert.clone()),
+            ..TlsConfig::default()
+        };
+        let anonymous = client_config(Some(&ca.0.cert), None).unwrap();
+        let with_cert =
+            client_config(Some(&ca.0.cert), Some((&client_pem.cert, &client_pem.key))).unwrap();
+
+        let required = server_config(&config).unwrap();
+        assert!(handshake(required.clone(), with_cert.clone(), "localhost")
+            .await
+            .is_ok());
+        assert!(handshake(required, anonymous.clone(), "localhost")
+            .await
+            .is_err());
+
+        config.require_client_cert = false;
+        let optional = server_config(&config).unwrap();
+        assert!(handshake(optional, anonymous, "localhost").await.is_ok());
+    }
+
+    #[test]
+    fn test_missing_files() {
+        let config = TlsConfig {
+            enabled: true,
+            cert_path: Some(PathBuf::from("/nonexistent/server.pem")),
+            key_path: Some(PathBuf::from("/nonexistent/server.key")),
+            ..TlsConfig::default()
+        };
+        let err = server_config(&config).unwrap_err();
+        assert!(err.contains("/nonexistent/server.pem"));
+        assert!(server_config(&TlsConfig::default()).is_err());
+    }
+}
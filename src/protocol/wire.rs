@@ -16,6 +16,12 @@ pub enum WireDataType {
     String,
     Bool,
     Timestamp,
+    Date,
+    Duration,
+    Uuid,
+    List,
+    Map,
+    Json,
     Vector { dim: Option<usize> },
     VectorInt8 { dim: Option<usize> },
     Bytes,
@@ -30,6 +36,12 @@ impl std::fmt::Display for WireDataType {
             WireDataType::String => write!(f, "String"),
             WireDataType::Bool => write!(f, "Bool"),
             WireDataType::Timestamp => write!(f, "Timestamp"),
+            WireDataType::Date => write!(f, "Date"),
+            WireDataType::Duration => write!(f, "Duration"),
+            WireDataType::Uuid => write!(f, "Uuid"),
+            WireDataType::List => write!(f, "List"),
+            WireDataType::Map => write!(f, "Map"),
+            WireDataType::Json => write!(f, "Json"),
             WireDataType::Vector { dim: Some(d) } => write!(f, "Vector[{d}]"),
             WireDataType::Vector { dim: None } => write!(f, "Vector"),
             WireDataType::VectorInt8 { dim: Some(d) } => write!(f, "VectorInt8[{d}]"),
@@ -54,6 +66,18 @@ pub enum WireValue {
     Bool(bool),
     /// Timestamp as Unix milliseconds
     Timestamp(i64),
+    /// Date as days since 1970-01-01
+    Date(i32),
+    /// Duration in milliseconds
+    Duration(i64),
+    /// UUID (hyphenated string in JSON)
+    Uuid(uuid::Uuid),
+    /// List of values
+    List(Vec<WireValue>),
+    /// String-keyed map of values
+    Map(std::collections::BTreeMap<String, WireValue>),
+    /// JSON document text
+    Json(String),
     /// Full-precision f32 vector
     Vector(Vec<f32>),
     /// Quantized int8 vector
@@ -76,6 +100,18 @@ impl WireValue {
             Value::Bool(b) => WireValue::Bool(*b),
             Value::Null => WireValue::Null,
             Value::Timestamp(ts) => WireValue::Timestamp(*ts),
+            Value::Date(d) => WireValue::Date(*d),
+            Value::Duration(d) => WireValue::Duration(*d),
+            Value::Uuid(u) => WireValue::Uuid(*u),
+            Value::List(items) => WireValue::List(items.iter().map(Self::from_value).collect()),
+            Value::Map(entries) => WireValue::Map(
+                entries
+                    .iter()
+                    .map(|(k, v)| (k.clone(), Self::from_value(v)))
+                    .collect(),
+            ),
+            Value::Json(text) => WireValue::Json(text.to_string()),
+            Value::Bytes(b) => WireValue::Bytes(b.as_ref().clone()),
         }
     }
 
@@ -88,6 +124,12 @@ impl WireValue {
             WireValue::String(_) => WireDataType::String,
             WireValue::Bool(_) => WireDataType::Bool,
             WireValue::Timestamp(_) => WireDataType::Timestamp,
+            WireValue::Date(_) => WireDataType::Date,
+            WireValue::Duration(_) => WireDataType::Duration,
+            WireValue::Uuid(_) => WireDataType::Uuid,
+            WireValue::List(_) => WireDataType::List,
+            WireValue::Map(_) => WireDataType::Map,
+            WireValue::Json(_) => WireDataType::Json,
             WireValue::Vector(v) => WireDataType::Vector { dim: Some(v.len()) },
             WireValue::VectorInt8(v) => WireDataType::VectorInt8 { dim: Some(v.len()) },
             WireValue::Bytes(_) => WireDataType::Bytes,
@@ -142,6 +184,24 @@ impl WireValue {
             _ => None,
         }
     }
+
+    /// Approximate encoded size in bytes: payload lengths for strings,
+    /// vectors and containers, 8 bytes for scalars
+    pub fn estimated_bytes(&self) -> usize {
+        match self {
+            WireValue::String(s) | WireValue::Json(s) => s.len(),
+            WireValue::Vector(v) => v.len() * std::mem::size_of::<f32>(),
+            WireValue::VectorInt8(v) => v.len(),
+            WireValue::Bytes(b) => b.len(),
+            WireValue::Uuid(_) => 16,
+            WireValue::List(values) => values.iter().map(WireValue::estimated_bytes).sum(),
+            WireValue::Map(entries) => entries
+                .iter()
+                .map(|(key, value)| key.len() + value.estimated_bytes())
+                .sum(),
+            _ => 8,
+        }
+    }
 }
 
 impl std::fmt::Display for WireValue {
@@ -158,6 +218,12 @@ impl std::fmt::Display for WireValue {
             WireValue::String(s) => write!(f, "\"{s}\""),
             WireValue::Bool(b) => write!(f, "{b}"),
             WireValue::Timestamp(t) => write!(f, "ts:{t}"),
+            WireValue::Date(d) => write!(f, "{}", crate::temporal_ops::format_date(*d)),
+            WireValue::Duration(d) => write!(f, "{}", crate::temporal_ops::format_duration(*d)),
+            WireValue::Uuid(u) => write!(f, "{}", u.hyphenated()),
+            WireValue::List(items) => write!(f, "list[{}]", items.len()),
+            WireValue::Map(entries) => write!(f, "map[{}]", entries.len()),
+            WireValue::Json(text) => write!(f, "{text}"),
             WireValue::Vector(v) => write!(f, "vec[{}]", v.len()),
             WireValue::VectorInt8(v) => write!(f, "vec8[{}]", v.len()),
             WireValue::Bytes(b) => write!(f, "bytes[{}]", b.len()),
@@ -378,6 +444,15 @@ impl QueryResult {
         }
     }
 
+    /// Approximate size of the rows, for the per-request result limit
+    pub fn estimated_bytes(&self) -> usize {
+        self.rows
+            .iter()
+            .flat_map(|row| &row.values)
+            .map(WireValue::estimated_bytes)
+            .sum()
+    }
+
     pub fn new(rows: Vec<WireTuple>, schema: Vec<ColumnDef>, execution_time_ms: u64) -> Self {
         let total_count = rows.len();
         Self {
@@ -415,6 +490,25 @@ impl QueryResult {
     }
 }
 
+/// Result of an atomic batch of statements
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchResult {
+    /// One result per statement, in order
+    pub results: Vec<QueryResult>,
+    /// Summary of the commit of the batch's writes; `None` when it had only
+    /// a query
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    pub execution_time_ms: u64,
+}
+
+impl BatchResult {
+    /// Approximate size of all the rows, for the per-request result limit
+    pub fn estimated_bytes(&self) -> usize {
+        self.results.iter().map(QueryResult::estimated_bytes).sum()
+    }
+}
+
 // Tests
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
@@ -605,6 +699,25 @@ mod tests {
         assert_eq!(ts.data_type(), WireDataType::Timestamp);
     }
 
+    #[test]
+    fn test_wire_value_date_and_duration() {
+        let date = WireValue::from_value(&crate::value::Value::Date(19_782));
+        assert_eq!(date.data_type(), WireDataType::Date);
+        assert_eq!(date.to_string(), "2024-02-29");
+        let dur = WireValue::from_value(&crate::value::Value::Duration(3_600_000));
+        assert_eq!(dur.data_type(), WireDataType::Duration);
+        assert_eq!(dur.to_string(), "1h");
+    }
+
+    #[test]
+    fn test_wire_value_uuid_json_is_string() {
+        let text = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+        let wire = WireValue::from_value(&crate::value::Value::uuid(text).unwrap());
+        assert_eq!(wire.data_type(), WireDataType::Uuid);
+        let json = serde_json::to_value(&wire).unwrap();
+        assert_eq!(json, serde_json::json!({ "Uuid": text }));
+    }
+
     #[test]
     fn test_wire_tuple_empty() {
         let empty = WireTuple::empty();
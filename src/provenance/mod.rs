@@ -2,12 +2,14 @@
 //!
 //! Explains why derived facts exist (proof trees) and why expected facts
 //! are absent (negative explanations). Core data model for explainable
-//! derivations in the IQL engine.
+//! derivations in the IQL engine. `why` tracks, per derived tuple, the
+//! sets of base facts that derive it (why-provenance).
 
 pub mod backward_chaining;
 pub mod proof_tree;
 pub mod prove_body;
 pub mod unification;
+pub mod why;
 pub mod why_not;
 
 use crate::value::Value;
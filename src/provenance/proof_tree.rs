@@ -22,6 +22,19 @@ fn value_to_json(v: &Value) -> serde_json::Value {
         Value::Bool(b) => serde_json::Value::Bool(*b),
         Value::Null => serde_json::Value::Null,
         Value::Timestamp(ts) => serde_json::Value::Number((*ts).into()),
+        Value::Date(d) => serde_json::Value::String(crate::temporal_ops::format_date(*d)),
+        Value::Duration(ms) => serde_json::Value::Number((*ms).into()),
+        Value::Uuid(u) => serde_json::Value::String(u.hyphenated().to_string()),
+        Value::List(items) => serde_json::Value::Array(items.iter().map(value_to_json).collect()),
+        Value::Map(entries) => serde_json::Value::Object(
+            entries
+                .iter()
+                .map(|(k, v)| (k.clone(), value_to_json(v)))
+                .collect(),
+        ),
+        Value::Bytes(_) => serde_json::Value::String(v.to_string()),
+        Value::Json(text) => serde_json::from_str(text)
+            .unwrap_or_else(|_| serde_json::Value::String(text.to_string())),
         Value::Vector(v) => {
             let arr: Vec<serde_json::Value> = v
                 .iter()
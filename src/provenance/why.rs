@@ -0,0 +1,604 @@
+//! Why-provenance - which base facts each derived tuple depends on.
+//!
+//! Every tuple is annotated with a set of *witnesses*: sets of base facts
+//! that together suffice to derive it. Annotations form a semiring: joins
+//! combine witnesses pairwise (`times`), unions and alternative rules
+//! collect them (`plus`). Witnesses that contain another witness are
+//! dropped (absorption), so each annotation is a set of minimal witnesses.
+//!
+//! Evaluation runs over the same IR as Differential Dataflow but on plain
+//! maps, as an opt-in second pass. Recursive relations are iterated until
+//! annotations stop changing.
+
+use crate::code_generator::CodeGenerator;
+use crate::ir::{AggregateFunction, IRNode};
+use crate::value::Tuple;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+/// Maximum witnesses kept per tuple; the smallest are kept
+pub const MAX_WITNESSES_PER_TUPLE: usize = 64;
+
+/// Upper bound on rounds when annotating recursive relations
+const MAX_FIXPOINT_ITERATIONS: usize = 1000;
+
+/// Scratch relation name used to evaluate aggregates over annotated input
+const AGGREGATE_INPUT: &str = "__why_aggregate_input";
+
+/// A base (EDB) fact
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct BaseFact {
+    pub relation: String,
+    pub tuple: Tuple,
+}
+
+impl fmt::Display for BaseFact {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.relation, self.tuple)
+    }
+}
+
+/// Set of base facts that together derive a tuple
+pub type Witness = BTreeSet<BaseFact>;
+
+/// Why-provenance annotation: the minimal witnesses of a tuple.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WhyProvenance {
+    witnesses: BTreeSet<Witness>,
+}
+
+impl WhyProvenance {
+    /// No derivation (additive identity)
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    /// Derivation from no facts (multiplicative identity)
+    pub fn one() -> Self {
+        Self {
+            witnesses: BTreeSet::from([Witness::new()]),
+        }
+    }
+
+    /// A single base fact
+    pub fn fact(relation: &str, tuple: &Tuple) -> Self {
+        Self {
+            witnesses: BTreeSet::from([Witness::from([BaseFact {
+                relation: relation.to_string(),
+                tuple: tuple.clone(),
+            }])]),
+        }
+    }
+
+    /// Whether the tuple has no derivation
+    pub fn is_zero(&self) -> bool {
+        self.witnesses.is_empty()
+    }
+
+    /// Alternative derivations: either annotation derives the tuple
+    #[must_use]
+    pub fn plus(&self, other: &Self) -> Self {
+        Self::minimal(self.witnesses.iter().chain(&other.witnesses).cloned())
+    }
+
+    /// Joint derivation: both annotations are needed
+    #[must_use]
+    pub fn times(&self, other: &Self) -> Self {
+        Self::minimal(self.witnesses.iter().flat_map(|left| {
+            other
+                .witnesses
+                .iter()
+                .map(move |right| left.union(right).cloned().collect())
+        }))
+    }
+
+    /// The minimal witnesses, smallest first
+    pub fn witnesses(&self) -> Vec<&Witness> {
+        let mut witnesses: Vec<&Witness> = self.witnesses.iter().collect();
+        witnesses.sort_by_key(|w| w.len());
+        witnesses
+    }
+
+    /// Every base fact used by some witness
+    pub fn facts(&self) -> BTreeSet<&BaseFact> {
+        self.witnesses.iter().flatten().collect()
+    }
+
+    /// Drop witnesses that contain another witness, keeping at most
+    /// `MAX_WITNESSES_PER_TUPLE` of the smallest
+    fn minimal(witnesses: impl Iterator<Item = Witness>) -> Self {
+        let mut candidates: Vec<Witness> = witnesses.collect();
+        candidates.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+        candidates.dedup();
+
+        let mut kept: Vec<Witness> = Vec::new();
+        for witness in candidates {
+            if kept.len() == MAX_WITNESSES_PER_TUPLE {
+                break;
+            }
+            if !kept.iter().any(|smaller| smaller.is_subset(&witness)) {
+                kept.push(witness);
+            }
+        }
+        Self {
+            witnesses: kept.into_iter().collect(),
+        }
+    }
+}
+
+impl fmt::Display for WhyProvenance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let alternatives: Vec<String> = self
+            .witnesses()
+            .into_iter()
+            .map(|witness| {
+                let facts: Vec<String> = witness.iter().map(ToString::to_string).collect();
+                format!("{{{}}}", facts.join(", "))
+            })
+            .collect();
+        write!(f, "{}", alternatives.join(" | "))
+    }
+}
+
+/// Tuples of a relation with their annotations
+pub type AnnotatedRelation = HashMap<Tuple, WhyProvenance>;
+
+fn add_annotation(relation: &mut AnnotatedRelation, tuple: Tuple, annotation: &WhyProvenance) {
+    if annotation.is_zero() {
+        return;
+    }
+    match relation.get_mut(&tuple) {
+        Some(existing) => *existing = existing.plus(annotation),
+        None => {
+            relation.insert(tuple, annotation.clone());
+        }
+    }
+}
+
+/// Evaluates IR plans over annotated relations.
+///
+/// Base relations are read from the engine's inputs, each tuple being its
+/// own witness; derived relations are added as they are annotated.
+pub struct WhyEvaluator {
+    base: Arc<HashMap<String, Vec<Tuple>>>,
+    derived: HashMap<String, AnnotatedRelation>,
+}
+
+impl WhyEvaluator {
+    /// Create an evaluator over base relations
+    pub fn new(base: Arc<HashMap<String, Vec<Tuple>>>) -> Self {
+        Self {
+            base,
+            derived: HashMap::new(),
+        }
+    }
+
+    /// Annotate a non-recursive relation and record it
+    pub fn annotate(&mut self, relation: &str, ir: &IRNode) -> Result<(), String> {
+        let annotated = self.evaluate(ir)?;
+        self.derived.insert(relation.to_string(), annotated);
+        Ok(())
+    }
+
+    /// Annotate a group of (mutually) recursive relations by iterating
+    /// their plans until no annotation changes
+    pub fn annotate_recursive(&mut self, relations: &[(String, IRNode)]) -> Result<(), String> {
+        for (name, _) in relations {
+            self.derived.insert(name.clone(), AnnotatedRelation::new());
+        }
+
+        for _ in 0..MAX_FIXPOINT_ITERATIONS {
+            let mut changed = false;
+            for (name, ir) in relations {
+                let annotated = self.evaluate(ir)?;
+                if self.derived.get(name) != Some(&annotated) {
+                    changed = true;
+                    self.derived.insert(name.clone(), annotated);
+                }
+            }
+            if !changed {
+                return Ok(());
+            }
+        }
+
+        let names: Vec<&str> = relations.iter().map(|(name, _)| name.as_str()).collect();
+        Err(format!(
+            "Provenance of ({}) did not reach a fixpoint after {MAX_FIXPOINT_ITERATIONS} iterations",
+            names.join(", ")
+        ))
+    }
+
+    /// Annotated derived relations
+    pub fn into_relations(self) -> HashMap<String, AnnotatedRelation> {
+        self.derived
+    }
+
+    /// Evaluate a plan, annotating every output tuple
+    pub fn evaluate(&self, ir: &IRNode) -> Result<AnnotatedRelation, String> {
+        match ir {
+            IRNode::Scan { relation, .. } => Ok(self.scan(relation)),
+
+            IRNode::Map {
+                input, projection, ..
+            } => {
+                let mut output = AnnotatedRelation::new();
+                for (tuple, annotation) in self.evaluate(input)? {
+                    add_annotation(&mut output, tuple.project(projection), &annotation);
+                }
+                Ok(output)
+            }
+
+            IRNode::Filter { input, predicate } => {
+                let keep = CodeGenerator::predicate_to_tuple_fn(predicate);
+                let mut output = self.evaluate(input)?;
+                output.retain(|tuple, _| keep(tuple));
+                Ok(output)
+            }
+
+            IRNode::FlatMap {
+                input,
+                projection,
+                filter_predicate,
+                ..
+            } => {
+                let keep = filter_predicate
+                    .as_ref()
+                    .map(CodeGenerator::predicate_to_tuple_fn);
+                let mut output = AnnotatedRelation::new();
+                for (tuple, annotation) in self.evaluate(input)? {
+                    let projected = tuple.project(projection);
+                    if keep.as_ref().is_none_or(|keep| keep(&projected)) {
+                        add_annotation(&mut output, projected, &annotation);
+                    }
+                }
+                Ok(output)
+            }
+
+            IRNode::Compute { input, expressions } => {
+                let mut output = AnnotatedRelation::new();
+                for (tuple, annotation) in self.evaluate(input)? {
+                    for row in CodeGenerator::compute_rows(tuple, expressions) {
+                        add_annotation(&mut output, row, &annotation);
+                    }
+                }
+                Ok(output)
+            }
+
+            IRNode::Join {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                ..
+            } => self.join(left, right, left_keys, right_keys, |left, right| {
+                if right_keys.is_empty() {
+                    Some(left.concat(right))
+                } else {
+                    Some(left.concat(&right.excluding_indices(right_keys)))
+                }
+            }),
+
+            IRNode::JoinFlatMap {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                projection,
+                filter_predicate,
+                ..
+            } => {
+                let keep = filter_predicate
+                    .as_ref()
+                    .map(CodeGenerator::predicate_to_tuple_fn);
+                self.join(left, right, left_keys, right_keys, |left, right| {
+                    let projected = left.concat(right).project(projection);
+                    keep.as_ref()
+                        .is_none_or(|keep| keep(&projected))
+                        .then_some(projected)
+                })
+            }
+
+            IRNode::Distinct { input } | IRNode::Sort { input, .. } => self.evaluate(input),
+
+            IRNode::Union { inputs } => {
+                let mut output = AnnotatedRelation::new();
+                for input in inputs {
+                    for (tuple, annotation) in self.evaluate(input)? {
+                        add_annotation(&mut output, tuple, &annotation);
+                    }
+                }
+                Ok(output)
+            }
+
+            IRNode::Semijoin {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                ..
+            } => {
+                let right_by_key = Self::annotations_by_key(&self.evaluate(right)?, right_keys);
+                let mut output = AnnotatedRelation::new();
+                for (tuple, annotation) in self.evaluate(left)? {
+                    if let Some(matches) = right_by_key.get(&tuple.from_indices(left_keys)) {
+                        add_annotation(&mut output, tuple, &annotation.times(matches));
+                    }
+                }
+                Ok(output)
+            }
+
+            // A fact's absence is not a witness, so negation keeps the left
+            // annotation as is
+            IRNode::Antijoin {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                ..
+            } => {
+                let right_by_key = Self::annotations_by_key(&self.evaluate(right)?, right_keys);
+                let mut output = self.evaluate(left)?;
+                output
+                    .retain(|tuple, _| !right_by_key.contains_key(&tuple.from_indices(left_keys)));
+                Ok(output)
+            }
+
+            IRNode::Aggregate {
+                input,
+                group_by,
+                aggregations,
+                output_schema,
+            } => self.aggregate(input, group_by, aggregations, output_schema),
+
+            IRNode::HnswScan { index_name, .. } => Err(format!(
+                "Provenance is not tracked through vector search on '{index_name}'"
+            )),
+        }
+    }
+
+    /// Annotations of a relation read by a plan
+    fn scan(&self, relation: &str) -> AnnotatedRelation {
+        if let Some(derived) = self.derived.get(relation) {
+            return derived.clone();
+        }
+        let mut output = AnnotatedRelation::new();
+        for tuple in self.base.get(relation).into_iter().flatten() {
+            add_annotation(
+                &mut output,
+                tuple.clone(),
+                &WhyProvenance::fact(relation, tuple),
+            );
+        }
+        output
+    }
+
+    /// Join two annotated plans; a joined tuple needs both sides' witnesses
+    fn join(
+        &self,
+        left: &IRNode,
+        right: &IRNode,
+        left_keys: &[usize],
+        right_keys: &[usize],
+        combine: impl Fn(&Tuple, &Tuple) -> Option<Tuple>,
+    ) -> Result<AnnotatedRelation, String> {
+        let left = self.evaluate(left)?;
+        let right = self.evaluate(right)?;
+
+        let mut right_by_key: HashMap<Tuple, Vec<(&Tuple, &WhyProvenance)>> = HashMap::new();
+        for (tuple, annotation) in &right {
+            right_by_key
+                .entry(tuple.from_indices(right_keys))
+                .or_default()
+                .push((tuple, annotation));
+        }
+
+        let mut output = AnnotatedRelation::new();
+        for (left_tuple, left_annotation) in &left {
+            let Some(matches) = right_by_key.get(&left_tuple.from_indices(left_keys)) else {
+                continue;
+            };
+            for (right_tuple, right_annotation) in matches {
+                if let Some(joined) = combine(left_tuple, right_tuple) {
+                    add_annotation(
+                        &mut output,
+                        joined,
+                        &left_annotation.times(right_annotation),
+                    );
+                }
+            }
+        }
+        Ok(output)
+    }
+
+    /// Any matching tuple justifies a key
+    fn annotations_by_key(
+        relation: &AnnotatedRelation,
+        keys: &[usize],
+    ) -> HashMap<Tuple, WhyProvenance> {
+        let mut by_key = AnnotatedRelation::new();
+        for (tuple, annotation) in relation {
+            add_annotation(&mut by_key, tuple.from_indices(keys), annotation);
+        }
+        by_key
+    }
+
+    /// Aggregate values are computed by the code generator over the
+    /// annotated input. A group's row is annotated with the rows that
+    /// produced it: for a lone `min`/`max` the rows holding the extreme
+    /// value, otherwise every row of the group.
+    fn aggregate(
+        &self,
+        input: &IRNode,
+        group_by: &[usize],
+        aggregations: &[(AggregateFunction, usize)],
+        output_schema: &[String],
+    ) -> Result<AnnotatedRelation, String> {
+        let annotated_input = self.evaluate(input)?;
+
+        let mut codegen = CodeGenerator::new();
+        codegen.add_input(
+            AGGREGATE_INPUT.to_string(),
+            annotated_input.keys().cloned().collect(),
+        );
+        let rows = codegen.execute(&IRNode::Aggregate {
+            input: Box::new(IRNode::Scan {
+                relation: AGGREGATE_INPUT.to_string(),
+                schema: input.output_schema(),
+            }),
+            group_by: group_by.to_vec(),
+            aggregations: aggregations.to_vec(),
+            output_schema: output_schema.to_vec(),
+        })?;
+
+        let extreme_col = match aggregations {
+            [(AggregateFunction::Min | AggregateFunction::Max, col)] => Some(*col),
+            _ => None,
+        };
+
+        let mut output = AnnotatedRelation::new();
+        for row in rows {
+            let group = Tuple::new(row.values()[..group_by.len().min(row.arity())].to_vec());
+            let extreme = extreme_col.and_then(|_| row.get(group_by.len()));
+            let mut annotation = WhyProvenance::zero();
+            for (tuple, contributor) in &annotated_input {
+                let in_group = tuple.from_indices(group_by) == group;
+                let contributes = match (extreme_col, extreme) {
+                    (Some(col), Some(value)) => tuple.get(col) == Some(value),
+                    _ => true,
+                };
+                if in_group && contributes {
+                    annotation = annotation.plus(contributor);
+                }
+            }
+            add_annotation(&mut output, row, &annotation);
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::value::Value;
+
+    fn scan(relation: &str) -> IRNode {
+        IRNode::Scan {
+            relation: relation.to_string(),
+            schema: vec!["x".to_string(), "y".to_string()],
+        }
+    }
+
+    fn edge_fact(x: i64, y: i64) -> BaseFact {
+        BaseFact {
+            relation: "edge".to_string(),
+            tuple: Tuple::pair(x, y),
+        }
+    }
+
+    fn evaluator(edges: &[(i64, i64)]) -> WhyEvaluator {
+        let mut base = HashMap::new();
+        base.insert(
+            "edge".to_string(),
+            edges.iter().map(|&(x, y)| Tuple::pair(x, y)).collect(),
+        );
+        WhyEvaluator::new(Arc::new(base))
+    }
+
+    #[test]
+    fn test_plus_keeps_minimal_witnesses() {
+        let a = WhyProvenance::fact("edge", &Tuple::pair(1, 2));
+        let ab = a.times(&WhyProvenance::fact("edge", &Tuple::pair(2, 3)));
+        // {a} absorbs {a, b}
+        assert_eq!(a.plus(&ab), a);
+        assert_eq!(WhyProvenance::zero().plus(&a), a);
+        assert_eq!(WhyProvenance::one().times(&a), a);
+        assert!(WhyProvenance::zero().times(&a).is_zero());
+    }
+
+    #[test]
+    fn test_join_combines_witnesses() {
+        let evaluator = evaluator(&[(1, 2), (2, 3)]);
+        let ir = IRNode::Map {
+            input: Box::new(IRNode::Join {
+                left: Box::new(scan("edge")),
+                right: Box::new(scan("edge")),
+                left_keys: vec![1],
+                right_keys: vec![0],
+                output_schema: vec!["x".to_string(), "y".to_string(), "z".to_string()],
+            }),
+            projection: vec![0, 2],
+            output_schema: vec!["x".to_string(), "z".to_string()],
+        };
+
+        let annotated = evaluator.evaluate(&ir).unwrap();
+        let why = &annotated[&Tuple::pair(1, 3)];
+        assert_eq!(
+            why.witnesses(),
+            vec![&Witness::from([edge_fact(1, 2), edge_fact(2, 3)])]
+        );
+    }
+
+    #[test]
+    fn test_recursive_relation_collects_alternative_paths() {
+        // path(X, Y) <- edge(X, Y)
+        // path(X, Z) <- path(X, Y), edge(Y, Z)
+        let mut evaluator = evaluator(&[(1, 2), (2, 3), (1, 3)]);
+        let ir = IRNode::Union {
+            inputs: vec![
+                scan("edge"),
+                IRNode::Map {
+                    input: Box::new(IRNode::Join {
+                        left: Box::new(scan("path")),
+                        right: Box::new(scan("edge")),
+                        left_keys: vec![1],
+                        right_keys: vec![0],
+                        output_schema: vec!["x".to_string(), "y".to_string(), "z".to_string()],
+                    }),
+                    projection: vec![0, 2],
+                    output_schema: vec!["x".to_string(), "z".to_string()],
+                },
+            ],
+        };
+
+        evaluator
+            .annotate_recursive(&[("path".to_string(), ir)])
+            .unwrap();
+        let relations = evaluator.into_relations();
+        let why = &relations["path"][&Tuple::pair(1, 3)];
+        assert_eq!(
+            why.witnesses(),
+            vec![
+                &Witness::from([edge_fact(1, 3)]),
+                &Witness::from([edge_fact(1, 2), edge_fact(2, 3)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_min_aggregate_annotated_with_extreme_rows() {
+        let mut base = HashMap::new();
+        base.insert(
+            "cost".to_string(),
+            vec![
+                Tuple::new(vec![Value::Int64(1), Value::Int64(5)]),
+                Tuple::new(vec![Value::Int64(1), Value::Int64(3)]),
+            ],
+        );
+        let evaluator = WhyEvaluator::new(Arc::new(base));
+        let ir = IRNode::Aggregate {
+            input: Box::new(scan("cost")),
+            group_by: vec![0],
+            aggregations: vec![(AggregateFunction::Min, 1)],
+            output_schema: vec!["x".to_string(), "min_y".to_string()],
+        };
+
+        let annotated = evaluator.evaluate(&ir).unwrap();
+        let cheapest = Tuple::new(vec![Value::Int64(1), Value::Int64(3)]);
+        let facts: Vec<&BaseFact> = annotated[&cheapest].facts().into_iter().collect();
+        assert_eq!(facts.len(), 1);
+        assert_eq!(facts[0].tuple, cheapest);
+    }
+}
@@ -126,6 +126,62 @@ impl DependencyGraph {
         }
         None
     }
+
+    /// Describe a dependency cycle that starts with the negative edge
+    /// `from -> !to` and returns to `from` through members of `scc`.
+    ///
+    /// Negated hops are marked with `!`, e.g. `a -> !b -> c -> a`.
+    pub fn describe_negation_cycle(&self, from: &str, to: &str, scc: &[String]) -> String {
+        let scc_set: HashSet<&str> = scc.iter().map(String::as_str).collect();
+
+        // Shortest path to -> ... -> from (BFS, sorted for deterministic output)
+        let mut previous: HashMap<&str, &str> = HashMap::new();
+        let mut queue = std::collections::VecDeque::from([to]);
+        let mut visited: HashSet<&str> = HashSet::from([to]);
+        while let Some(current) = queue.pop_front() {
+            if current == from {
+                break;
+            }
+            let mut next: Vec<&str> = self
+                .edges
+                .get(current)
+                .map(|deps| deps.iter().map(|(r, _)| r.as_str()).collect())
+                .unwrap_or_default();
+            next.sort_unstable();
+            for rel in next {
+                if scc_set.contains(rel) && visited.insert(rel) {
+                    previous.insert(rel, current);
+                    queue.push_back(rel);
+                }
+            }
+        }
+
+        let mut path = vec![from];
+        let mut tail = Vec::new();
+        let mut current = from;
+        while current != to {
+            match previous.get(current) {
+                Some(&prev) => {
+                    tail.push(current);
+                    current = prev;
+                }
+                None => break,
+            }
+        }
+        path.push(to);
+        path.extend(tail.into_iter().rev());
+
+        let mut description = from.to_string();
+        for hop in path.windows(2) {
+            let negated = self.edges.get(hop[0]).is_some_and(|deps| {
+                deps.iter()
+                    .any(|(r, t)| r == hop[1] && *t == DependencyType::Negative)
+            });
+            description.push_str(if negated { " -> !" } else { " -> " });
+            description.push_str(hop[1]);
+        }
+        description
+    }
 }
 
 impl Default for DependencyGraph {
@@ -439,11 +495,9 @@ pub fn stratify_with_negation(program: &Program) -> StratificationResult {
                 format!("Self-negation: '{from}' negates itself (!{from} in body)")
             } else {
                 format!(
-                    "Unstratified negation: '{}' negates '{}' within same recursive cycle. \
-                     Cycle members: [{}]",
-                    from,
-                    to,
-                    scc.join(", ")
+                    "Unstratified negation: '{from}' negates '{to}' within same recursive cycle. \
+                     Cycle: {}",
+                    extended_graph.describe_negation_cycle(&from, &to, scc)
                 )
             };
             return StratificationResult::NotStratifiable {
@@ -981,6 +1035,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_stratify_with_negation_reports_cycle() {
+        // a(x) <- base(x), !b(x).
+        // b(x) <- c(x).
+        // c(x) <- a(x).
+        let var = || vec![Term::Variable("x".to_string())];
+        let mut program = Program::new();
+        program.add_rule(Rule::new(
+            Atom::new("a".to_string(), var()),
+            vec![
+                BodyPredicate::Positive(Atom::new("base".to_string(), var())),
+                BodyPredicate::Negated(Atom::new("b".to_string(), var())),
+            ],
+        ));
+        program.add_rule(Rule::new_simple(
+            Atom::new("b".to_string(), var()),
+            vec![Atom::new("c".to_string(), var())],
+        ));
+        program.add_rule(Rule::new_simple(
+            Atom::new("c".to_string(), var()),
+            vec![Atom::new("a".to_string(), var())],
+        ));
+
+        match stratify_with_negation(&program) {
+            StratificationResult::NotStratifiable { relation, reason } => {
+                assert_eq!(relation, "a");
+                assert!(reason.contains("Cycle: a -> !b -> c -> a"), "{reason}");
+            }
+            StratificationResult::Success(_) => panic!("Expected NotStratifiable"),
+        }
+    }
+
     #[test]
     fn test_stratify_with_negation_chain() {
         // a(x) <- base(x).
@@ -27,6 +27,7 @@ use crate::ast::{AggregateFunc, BodyPredicate, Program, Rule};
 use crate::recursion::{build_extended_dependency_graph, find_sccs};
 use crate::statement::serialize::SerializableTerm;
 use crate::statement::{RuleDef, SerializableRule};
+use crate::storage::persist::{Catalog, DdlLog};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
@@ -308,6 +309,35 @@ pub enum RuleRegisterResult {
     RuleAdded(usize),
 }
 
+/// When the cached result of a view is recomputed. Views without a
+/// policy are not cached: they are evaluated by every query reading them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RefreshPolicy {
+    /// After every write to a relation the view reads
+    Eager,
+    /// When a query reads the view after such a write
+    Lazy,
+    /// At most once every `interval_ms`, by a background sweep; queries
+    /// in between may read a stale result
+    Periodic { interval_ms: i64 },
+}
+
+impl std::fmt::Display for RefreshPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RefreshPolicy::Eager => write!(f, "eager"),
+            RefreshPolicy::Lazy => write!(f, "lazy"),
+            RefreshPolicy::Periodic { interval_ms } => {
+                write!(
+                    f,
+                    "every {}",
+                    crate::temporal_ops::format_duration(*interval_ms)
+                )
+            }
+        }
+    }
+}
+
 /// Rule definition stored in the catalog
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RuleDefinition {
@@ -320,6 +350,9 @@ pub struct RuleDefinition {
     /// Optional description
     #[serde(default)]
     pub description: Option<String>,
+    /// Refresh policy of the view's cached result (`None`: not cached)
+    #[serde(default)]
+    pub refresh: Option<RefreshPolicy>,
 }
 
 impl RuleDefinition {
@@ -330,6 +363,7 @@ impl RuleDefinition {
             rules: vec![rule],
             created_at: chrono::Utc::now().to_rfc3339(),
             description: None,
+            refresh: None,
         }
     }
 
@@ -361,6 +395,9 @@ impl RuleDefinition {
         if let Some(d) = &self.description {
             desc.push_str(&format!("Description: {d}\n"));
         }
+        if let Some(policy) = &self.refresh {
+            desc.push_str(&format!("Refresh: {policy}\n"));
+        }
         desc.push_str("Clauses:\n");
         for (i, rule) in self.rules.iter().enumerate() {
             let r = rule.to_rule();
@@ -396,6 +433,10 @@ pub struct RuleCatalog {
     catalog_path: PathBuf,
     /// Whether the catalog has been modified since last save
     dirty: bool,
+    /// In-memory copy that is never saved (see `scratch_copy`)
+    scratch: bool,
+    /// WAL each save is logged to before the file is rewritten
+    ddl_log: Option<DdlLog>,
 }
 
 impl RuleCatalog {
@@ -405,6 +446,8 @@ impl RuleCatalog {
             rules: HashMap::new(),
             catalog_path: PathBuf::new(),
             dirty: false,
+            scratch: false,
+            ddl_log: None,
         }
     }
 
@@ -417,6 +460,8 @@ impl RuleCatalog {
             rules: HashMap::new(),
             catalog_path,
             dirty: false,
+            scratch: false,
+            ddl_log: None,
         };
 
         // Load existing catalog if present
@@ -427,6 +472,18 @@ impl RuleCatalog {
         Ok(catalog)
     }
 
+    /// Copy of this catalog that lives only in memory, for checking rule
+    /// changes before they are applied to the real catalog
+    pub fn scratch_copy(&self) -> Self {
+        RuleCatalog {
+            rules: self.rules.clone(),
+            catalog_path: PathBuf::new(),
+            dirty: false,
+            scratch: true,
+            ddl_log: None,
+        }
+    }
+
     /// Register a rule from a `RuleDef`
     /// Returns information about whether rule was created or updated
     ///
@@ -616,6 +673,17 @@ impl RuleCatalog {
         }
     }
 
+    /// Set or remove (`None`) the refresh policy of a rule
+    pub fn set_refresh(&mut self, name: &str, policy: Option<RefreshPolicy>) -> Result<(), String> {
+        let def = self
+            .rules
+            .get_mut(name)
+            .ok_or_else(|| format!("Rule '{name}' does not exist"))?;
+        def.refresh = policy;
+        self.dirty = true;
+        self.save()
+    }
+
     /// Get the number of clauses in a rule
     pub fn rule_count(&self, name: &str) -> Option<usize> {
         self.rules.get(name).map(|r| r.rules.len())
@@ -777,9 +845,27 @@ impl RuleCatalog {
         Ok(())
     }
 
+    /// Log every save to `ddl_log` before the file is rewritten, so a
+    /// crash cannot leave data replayed without the rules it was written
+    /// under
+    pub fn set_ddl_log(&mut self, ddl_log: DdlLog) {
+        self.ddl_log = Some(ddl_log);
+    }
+
+    /// The catalog file's contents
+    pub fn to_json(&self) -> Result<String, String> {
+        let catalog_file = CatalogFile {
+            version: 1,
+            rules: self.rules.clone(),
+        };
+
+        serde_json::to_string_pretty(&catalog_file)
+            .map_err(|e| format!("Failed to serialize catalog: {e}"))
+    }
+
     /// Save the catalog to disk
     pub fn save(&mut self) -> Result<(), String> {
-        if !self.dirty {
+        if !self.dirty || self.scratch {
             return Ok(());
         }
 
@@ -789,13 +875,12 @@ impl RuleCatalog {
                 .map_err(|e| format!("Failed to create rules directory: {e}"))?;
         }
 
-        let catalog_file = CatalogFile {
-            version: 1,
-            rules: self.rules.clone(),
-        };
-
-        let content = serde_json::to_string_pretty(&catalog_file)
-            .map_err(|e| format!("Failed to serialize catalog: {e}"))?;
+        let content = self.to_json()?;
+        if let Some(ddl_log) = &self.ddl_log {
+            ddl_log
+                .record(Catalog::Rules, content.clone())
+                .map_err(|e| format!("Failed to log catalog: {e}"))?;
+        }
 
         fs::write(&self.catalog_path, content)
             .map_err(|e| format!("Failed to write catalog: {e}"))?;
@@ -804,6 +889,14 @@ impl RuleCatalog {
         Ok(())
     }
 
+    /// Sync the catalog file to disk, if there is one
+    pub fn sync(&self) -> std::io::Result<()> {
+        if self.scratch || !self.catalog_path.exists() {
+            return Ok(());
+        }
+        fs::File::open(&self.catalog_path)?.sync_all()
+    }
+
     /// Force a reload from disk
     pub fn reload(&mut self) -> Result<(), String> {
         if self.catalog_path.exists() {
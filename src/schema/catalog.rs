@@ -3,7 +3,9 @@
 //! Storage and lookup for relation schemas with type definitions.
 //! Supports both session (temporary) and persistent schemas.
 
-use super::{ColumnSchema, RelationSchema, SchemaType};
+use super::migration::{SchemaHistory, SchemaMigration};
+use super::{ColumnSchema, PartitionSpec, RelationSchema, RetentionPolicy, SchemaType};
+use crate::value::Tuple;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
@@ -41,6 +43,9 @@ pub struct SchemaCatalog {
     /// Session schemas (memory only, cleared on disconnect)
     #[serde(skip)]
     session: HashMap<String, RelationSchema>,
+    /// Migration history of persistent schemas that have been altered
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    history: HashMap<String, SchemaHistory>,
 }
 
 impl SchemaCatalog {
@@ -49,6 +54,7 @@ impl SchemaCatalog {
         SchemaCatalog {
             persistent: HashMap::new(),
             session: HashMap::new(),
+            history: HashMap::new(),
         }
     }
 
@@ -87,9 +93,30 @@ impl SchemaCatalog {
         Ok(())
     }
 
-    /// Register or update a persistent schema
-    pub fn register_or_update(&mut self, schema: RelationSchema) -> Result<(), SchemaError> {
+    /// Register or update a persistent schema.
+    /// Replacing a schema with a different one discards its migration
+    /// history; use `alter` to keep older data readable.
+    /// Redeclaring a partitioned relation keeps its partition key while the
+    /// key column still exists; likewise for a retention policy.
+    pub fn register_or_update(&mut self, mut schema: RelationSchema) -> Result<(), SchemaError> {
+        if schema.partition.is_none() {
+            schema.partition = self
+                .persistent
+                .get(&schema.name)
+                .and_then(|existing| existing.partition.clone())
+                .filter(|spec| spec.validate(&schema).is_ok());
+        }
+        if schema.retention.is_none() {
+            schema.retention = self
+                .persistent
+                .get(&schema.name)
+                .and_then(|existing| existing.retention.clone())
+                .filter(|policy| policy.validate(&schema).is_ok());
+        }
         self.validate_schema(&schema)?;
+        if self.persistent.get(&schema.name) != Some(&schema) {
+            self.history.remove(&schema.name);
+        }
         self.persistent.insert(schema.name.clone(), schema);
         Ok(())
     }
@@ -140,11 +167,12 @@ impl SchemaCatalog {
     pub fn remove(&mut self, relation: &str) -> Option<RelationSchema> {
         self.session
             .remove(relation)
-            .or_else(|| self.persistent.remove(relation))
+            .or_else(|| self.remove_persistent(relation))
     }
 
-    /// Remove a persistent schema
+    /// Remove a persistent schema and its migration history
     pub fn remove_persistent(&mut self, relation: &str) -> Option<RelationSchema> {
+        self.history.remove(relation);
         self.persistent.remove(relation)
     }
 
@@ -228,6 +256,7 @@ impl SchemaCatalog {
     pub fn clear(&mut self) {
         self.persistent.clear();
         self.session.clear();
+        self.history.clear();
     }
 
     /// Clear only session schemas (called on disconnect)
@@ -238,6 +267,97 @@ impl SchemaCatalog {
     /// Clear only persistent schemas
     pub fn clear_persistent(&mut self) {
         self.persistent.clear();
+        self.history.clear();
+    }
+
+    // Schema evolution
+    /// Current schema version of a persistent relation (1 until altered)
+    pub fn schema_version(&self, relation: &str) -> u32 {
+        self.history.get(relation).map_or(1, SchemaHistory::version)
+    }
+
+    /// Migration history of a persistent relation, if it has been altered
+    pub fn history(&self, relation: &str) -> Option<&SchemaHistory> {
+        self.history.get(relation)
+    }
+
+    /// Compute the schema a migration would produce, without applying it
+    pub fn plan_alter(
+        &self,
+        relation: &str,
+        migration: &SchemaMigration,
+    ) -> Result<RelationSchema, SchemaError> {
+        let current = self
+            .persistent
+            .get(relation)
+            .ok_or_else(|| SchemaError::NotFound(relation.to_string()))?;
+        let next = migration.apply(current)?;
+        self.validate_schema(&next)?;
+        Ok(next)
+    }
+
+    /// Apply a migration to a persistent schema and record it in the
+    /// relation's history. Returns the new schema version.
+    pub fn alter(
+        &mut self,
+        relation: &str,
+        migration: SchemaMigration,
+    ) -> Result<u32, SchemaError> {
+        let next = self.plan_alter(relation, &migration)?;
+        let Some(before) = self.persistent.insert(relation.to_string(), next) else {
+            return Err(SchemaError::NotFound(relation.to_string()));
+        };
+        let history = self.history.entry(relation.to_string()).or_default();
+        history.record(before, migration);
+        Ok(history.version())
+    }
+
+    /// Set or clear the partition key of a persistent schema. Returns the
+    /// updated schema. The schema version is unchanged: partitioning moves
+    /// tuples between shards but does not change their shape.
+    pub fn set_partition(
+        &mut self,
+        relation: &str,
+        spec: Option<PartitionSpec>,
+    ) -> Result<RelationSchema, SchemaError> {
+        let schema = self
+            .persistent
+            .get_mut(relation)
+            .ok_or_else(|| SchemaError::NotFound(relation.to_string()))?;
+        if let Some(spec) = &spec {
+            spec.validate(schema).map_err(SchemaError::InvalidSchema)?;
+        }
+        schema.partition = spec;
+        Ok(schema.clone())
+    }
+
+    /// Set or clear the retention policy of a persistent schema. Returns
+    /// the updated schema; the schema version is unchanged.
+    pub fn set_retention(
+        &mut self,
+        relation: &str,
+        policy: Option<RetentionPolicy>,
+    ) -> Result<RelationSchema, SchemaError> {
+        let schema = self
+            .persistent
+            .get_mut(relation)
+            .ok_or_else(|| SchemaError::NotFound(relation.to_string()))?;
+        if let Some(policy) = &policy {
+            policy
+                .validate(schema)
+                .map_err(SchemaError::InvalidSchema)?;
+        }
+        schema.retention = policy;
+        Ok(schema.clone())
+    }
+
+    /// Adapt a tuple written under schema `version` of `relation` to the
+    /// current schema. Tuples of relations without history pass through.
+    pub fn adapt_tuple(&self, relation: &str, version: u32, tuple: Tuple) -> Tuple {
+        match self.history.get(relation) {
+            Some(history) => history.adapt(version, tuple),
+            None => tuple,
+        }
     }
 
     /// Validate a schema definition
@@ -269,6 +389,15 @@ impl SchemaCatalog {
             }
         }
 
+        if let Some(spec) = &schema.partition {
+            spec.validate(schema).map_err(SchemaError::InvalidSchema)?;
+        }
+        if let Some(policy) = &schema.retention {
+            policy
+                .validate(schema)
+                .map_err(SchemaError::InvalidSchema)?;
+        }
+
         Ok(())
     }
 
@@ -294,6 +423,12 @@ impl SchemaCatalog {
         Ok(catalog)
     }
 
+    /// The persistent schemas as the JSON `save` writes
+    pub fn to_json(&self) -> Result<String, SchemaError> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| SchemaError::IoError(format!("Failed to serialize schemas: {e}")))
+    }
+
     /// Save the persistent schemas to a JSON file
     /// Session schemas are not saved.
     pub fn save(&self, path: &Path) -> Result<(), SchemaError> {
@@ -304,10 +439,7 @@ impl SchemaCatalog {
             })?;
         }
 
-        let content = serde_json::to_string_pretty(self)
-            .map_err(|e| SchemaError::IoError(format!("Failed to serialize schemas: {e}")))?;
-
-        fs::write(path, content)
+        fs::write(path, self.to_json()?)
             .map_err(|e| SchemaError::IoError(format!("Failed to write schema catalog: {e}")))?;
 
         Ok(())
@@ -316,8 +448,10 @@ impl SchemaCatalog {
     /// Merge another catalog's persistent schemas into this one
     pub fn merge(&mut self, other: SchemaCatalog) {
         for (name, schema) in other.persistent {
+            self.history.remove(&name);
             self.persistent.insert(name, schema);
         }
+        self.history.extend(other.history);
     }
 }
 
@@ -820,6 +954,53 @@ mod tests {
         assert_eq!(loaded.get("User").unwrap().arity(), 2);
     }
 
+    #[test]
+    fn test_alter_records_history() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("catalog.json");
+
+        let mut catalog = SchemaCatalog::new();
+        catalog
+            .register_persistent(
+                RelationSchema::new("User").with_column(ColumnSchema::new("id", SchemaType::Int)),
+            )
+            .unwrap();
+        assert_eq!(catalog.schema_version("User"), 1);
+
+        let version = catalog
+            .alter(
+                "User",
+                SchemaMigration::AddColumn {
+                    column: ColumnSchema::new("active", SchemaType::Bool),
+                    default: crate::value::Value::Bool(true),
+                },
+            )
+            .unwrap();
+        assert_eq!(version, 2);
+        assert_eq!(catalog.get("User").unwrap().arity(), 2);
+        assert!(catalog
+            .alter(
+                "Missing",
+                SchemaMigration::DropColumn {
+                    name: "id".to_string()
+                }
+            )
+            .is_err());
+
+        // History survives a save/load round trip
+        catalog.save(&path).unwrap();
+        let loaded = SchemaCatalog::load(&path).unwrap();
+        assert_eq!(loaded.schema_version("User"), 2);
+        let old = Tuple::new(vec![crate::value::Value::Int64(1)]);
+        assert_eq!(loaded.adapt_tuple("User", 1, old).arity(), 2);
+
+        // Removing the schema forgets its history
+        let mut loaded = loaded;
+        loaded.remove("User");
+        assert_eq!(loaded.schema_version("User"), 1);
+        assert!(loaded.history("User").is_none());
+    }
+
     #[test]
     fn test_load_nonexistent_returns_empty() {
         let path = std::path::Path::new("/nonexistent/path/catalog.json");
@@ -0,0 +1,289 @@
+//! # Check Constraints
+//!
+//! Per-column value checks derived from type refinements in schema
+//! declarations, e.g. `+user(age: int(range(0, 150)), name: string(not_empty))`.
+//!
+//! Supported refinements:
+//! - `range(min, max)` - numeric value within `[min, max]`
+//! - `min(n)` / `max(n)` - numeric lower / upper bound (inclusive)
+//! - `not_empty` - string, list, map, vector or bytes value is non-empty
+//! - `pattern("regex")` - string value matches the regular expression
+//! - `one_of(a, b, ...)` - value equals one of the listed literals
+//!
+//! Null values satisfy every check constraint.
+
+use crate::statement::types::{Refinement, RefinementArg};
+use crate::value::Value;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A value-level constraint attached to a column
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CheckConstraint {
+    /// Numeric value within an inclusive range; either bound may be open
+    Range { min: Option<f64>, max: Option<f64> },
+    /// Collection or string value must not be empty
+    NotEmpty,
+    /// String value must match the regular expression
+    Pattern(String),
+    /// Value must equal one of the listed values
+    OneOf(Vec<Value>),
+}
+
+impl CheckConstraint {
+    /// Build a constraint from a parsed type refinement
+    pub fn from_refinement(refinement: &Refinement) -> Result<Self, String> {
+        let name = refinement.name.as_str();
+        let args = &refinement.args;
+        let expect_args = |n: usize| -> Result<(), String> {
+            if args.len() == n {
+                Ok(())
+            } else {
+                Err(format!(
+                    "Refinement '{name}' expects {n} argument(s), got {}",
+                    args.len()
+                ))
+            }
+        };
+
+        match name {
+            "range" => {
+                expect_args(2)?;
+                let min = numeric_arg(name, &args[0])?;
+                let max = numeric_arg(name, &args[1])?;
+                if min > max {
+                    return Err(format!("Invalid range({min}, {max}): min exceeds max"));
+                }
+                Ok(CheckConstraint::Range {
+                    min: Some(min),
+                    max: Some(max),
+                })
+            }
+            "min" => {
+                expect_args(1)?;
+                Ok(CheckConstraint::Range {
+                    min: Some(numeric_arg(name, &args[0])?),
+                    max: None,
+                })
+            }
+            "max" => {
+                expect_args(1)?;
+                Ok(CheckConstraint::Range {
+                    min: None,
+                    max: Some(numeric_arg(name, &args[0])?),
+                })
+            }
+            "not_empty" => {
+                expect_args(0)?;
+                Ok(CheckConstraint::NotEmpty)
+            }
+            "pattern" => {
+                expect_args(1)?;
+                let RefinementArg::String(pattern) = &args[0] else {
+                    return Err("Refinement 'pattern' expects a string argument".to_string());
+                };
+                Regex::new(pattern).map_err(|e| format!("Invalid pattern \"{pattern}\": {e}"))?;
+                Ok(CheckConstraint::Pattern(pattern.clone()))
+            }
+            "one_of" => {
+                if args.is_empty() {
+                    return Err("Refinement 'one_of' expects at least one argument".to_string());
+                }
+                Ok(CheckConstraint::OneOf(
+                    args.iter()
+                        .map(|arg| match arg {
+                            RefinementArg::Int(i) => Value::Int64(*i),
+                            RefinementArg::Float(f) => Value::Float64(*f),
+                            RefinementArg::String(s) => Value::string(s),
+                            RefinementArg::Bool(b) => Value::Bool(*b),
+                        })
+                        .collect(),
+                ))
+            }
+            other => Err(format!(
+                "Unknown refinement '{other}'. Supported: range, min, max, not_empty, pattern, one_of"
+            )),
+        }
+    }
+
+    /// Check a value against this constraint.
+    ///
+    /// `regex` must be the compiled form of a `Pattern` constraint's
+    /// expression; it is ignored for other constraints.
+    pub fn check(&self, value: &Value, regex: Option<&Regex>) -> Result<(), String> {
+        if value.is_null() {
+            return Ok(());
+        }
+        match self {
+            CheckConstraint::Range { min, max } => {
+                let Some(n) = value.as_f64() else {
+                    return Err(format!("{self} requires a numeric value, got {value}"));
+                };
+                if min.is_some_and(|min| n < min) || max.is_some_and(|max| n > max) {
+                    return Err(format!("{value} is outside {self}"));
+                }
+                Ok(())
+            }
+            CheckConstraint::NotEmpty => {
+                let empty = match value {
+                    Value::String(s) | Value::Json(s) => s.is_empty(),
+                    Value::List(items) => items.is_empty(),
+                    Value::Map(entries) => entries.is_empty(),
+                    Value::Vector(v) => v.is_empty(),
+                    Value::VectorInt8(v) => v.is_empty(),
+                    Value::Bytes(b) => b.is_empty(),
+                    _ => false,
+                };
+                if empty {
+                    Err("value must not be empty".to_string())
+                } else {
+                    Ok(())
+                }
+            }
+            CheckConstraint::Pattern(pattern) => {
+                let Some(s) = value.as_str() else {
+                    return Err(format!("{self} requires a string value, got {value}"));
+                };
+                let matched = match regex {
+                    Some(re) => re.is_match(s),
+                    None => Regex::new(pattern)
+                        .map_err(|e| format!("Invalid pattern \"{pattern}\": {e}"))?
+                        .is_match(s),
+                };
+                if matched {
+                    Ok(())
+                } else {
+                    Err(format!("\"{s}\" does not match {self}"))
+                }
+            }
+            CheckConstraint::OneOf(allowed) => {
+                let found = allowed.iter().any(|a| match (a.as_f64(), value.as_f64()) {
+                    (Some(x), Some(y)) => (x - y).abs() < f64::EPSILON,
+                    _ => a == value,
+                });
+                if found {
+                    Ok(())
+                } else {
+                    Err(format!("{value} is not in {self}"))
+                }
+            }
+        }
+    }
+}
+
+fn numeric_arg(refinement: &str, arg: &RefinementArg) -> Result<f64, String> {
+    match arg {
+        RefinementArg::Int(i) => Ok(*i as f64),
+        RefinementArg::Float(f) => Ok(*f),
+        _ => Err(format!(
+            "Refinement '{refinement}' expects numeric arguments"
+        )),
+    }
+}
+
+impl fmt::Display for CheckConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckConstraint::Range {
+                min: Some(min),
+                max: Some(max),
+            } => write!(f, "range({min}, {max})"),
+            CheckConstraint::Range { min: Some(min), .. } => write!(f, "min({min})"),
+            CheckConstraint::Range { max: Some(max), .. } => write!(f, "max({max})"),
+            CheckConstraint::Range { .. } => write!(f, "range"),
+            CheckConstraint::NotEmpty => write!(f, "not_empty"),
+            CheckConstraint::Pattern(p) => write!(f, "pattern(\"{p}\")"),
+            CheckConstraint::OneOf(values) => {
+                write!(f, "one_of(")?;
+                for (i, v) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{v}")?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::statement::types::parse_type_expr;
+    use crate::statement::types::TypeExpr;
+
+    fn constraints_of(type_str: &str) -> Vec<CheckConstraint> {
+        match parse_type_expr(type_str).unwrap() {
+            TypeExpr::Refined { refinements, .. } => refinements
+                .iter()
+                .map(|r| CheckConstraint::from_refinement(r).unwrap())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn test_from_refinement() {
+        assert_eq!(
+            constraints_of("int(range(1, 100))"),
+            vec![CheckConstraint::Range {
+                min: Some(1.0),
+                max: Some(100.0)
+            }]
+        );
+        assert_eq!(
+            constraints_of("string(not_empty)"),
+            vec![CheckConstraint::NotEmpty]
+        );
+        assert_eq!(
+            constraints_of("string(pattern(\"^a\"))"),
+            vec![CheckConstraint::Pattern("^a".to_string())]
+        );
+
+        let bad = Refinement {
+            name: "sparkly".to_string(),
+            args: vec![],
+        };
+        assert!(CheckConstraint::from_refinement(&bad).is_err());
+        let bad_range = Refinement {
+            name: "range".to_string(),
+            args: vec![RefinementArg::Int(5), RefinementArg::Int(1)],
+        };
+        assert!(CheckConstraint::from_refinement(&bad_range).is_err());
+        let bad_pattern = Refinement {
+            name: "pattern".to_string(),
+            args: vec![RefinementArg::String("(".to_string())],
+        };
+        assert!(CheckConstraint::from_refinement(&bad_pattern).is_err());
+    }
+
+    #[test]
+    fn test_check_values() {
+        let range = CheckConstraint::Range {
+            min: Some(0.0),
+            max: Some(150.0),
+        };
+        assert!(range.check(&Value::Int64(30), None).is_ok());
+        assert!(range.check(&Value::Int64(-1), None).is_err());
+        assert!(range.check(&Value::Float64(150.5), None).is_err());
+        assert!(range.check(&Value::Null, None).is_ok());
+
+        assert!(CheckConstraint::NotEmpty
+            .check(&Value::string(""), None)
+            .is_err());
+        assert!(CheckConstraint::NotEmpty
+            .check(&Value::string("x"), None)
+            .is_ok());
+
+        let email = CheckConstraint::Pattern("^[^@]+@[^@]+$".to_string());
+        assert!(email.check(&Value::string("a@b.com"), None).is_ok());
+        assert!(email.check(&Value::string("nope"), None).is_err());
+
+        let status = CheckConstraint::OneOf(vec![Value::string("open"), Value::string("done")]);
+        assert!(status.check(&Value::string("open"), None).is_ok());
+        assert!(status.check(&Value::string("lost"), None).is_err());
+    }
+}
@@ -0,0 +1,287 @@
+//! # Schema Migrations
+//!
+//! Versioned evolution of persistent relation schemas.
+//!
+//! A relation's schema starts at version 1 when it is registered. Each
+//! migration applied through `SchemaCatalog::alter` produces the next
+//! version and is recorded in the relation's `SchemaHistory`. Batch files
+//! remember the schema version they were written under, so tuples from
+//! older files can be adapted to the current schema on load instead of
+//! being rewritten.
+
+use super::catalog::SchemaError;
+use super::{ColumnSchema, RelationSchema, SchemaType};
+use crate::value::{Tuple, Value};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A single change to a relation's schema
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SchemaMigration {
+    /// Append a column; tuples written before the migration get `default`
+    AddColumn {
+        column: ColumnSchema,
+        default: Value,
+    },
+    /// Remove a column by name
+    DropColumn { name: String },
+    /// Change a column to a type that accepts all of its existing values
+    WidenType { column: String, to: SchemaType },
+}
+
+impl SchemaMigration {
+    /// Compute the schema that results from applying this migration
+    pub fn apply(&self, schema: &RelationSchema) -> Result<RelationSchema, SchemaError> {
+        let mut next = schema.clone();
+        match self {
+            SchemaMigration::AddColumn { column, default } => {
+                if schema.column_by_name(&column.name).is_some() {
+                    return Err(SchemaError::DuplicateColumn(column.name.clone()));
+                }
+                if !default.is_null() && !column.data_type.matches(default) {
+                    return Err(SchemaError::InvalidSchema(format!(
+                        "Default {default} does not match type '{}' of column '{}'",
+                        column.data_type, column.name
+                    )));
+                }
+                next.columns.push(column.clone());
+            }
+            SchemaMigration::DropColumn { name } => {
+                let index = Self::column_index(schema, name)?;
+                Self::check_not_partition_key(schema, name)?;
+                if schema.retention.as_ref().and_then(|p| p.column()) == Some(name.as_str()) {
+                    return Err(SchemaError::InvalidSchema(format!(
+                        "Column '{name}' is used by the retention policy of '{}'",
+                        schema.name
+                    )));
+                }
+                if schema.arity() == 1 {
+                    return Err(SchemaError::InvalidSchema(format!(
+                        "Cannot drop '{name}': it is the only column of '{}'",
+                        schema.name
+                    )));
+                }
+                next.columns.remove(index);
+            }
+            SchemaMigration::WidenType { column, to } => {
+                let index = Self::column_index(schema, column)?;
+                // Widened values may be assigned to other partitions
+                Self::check_not_partition_key(schema, column)?;
+                let from = &schema.columns[index].data_type;
+                if !from.can_widen_to(to) {
+                    return Err(SchemaError::InvalidSchema(format!(
+                        "Cannot widen column '{column}' from '{from}' to '{to}'"
+                    )));
+                }
+                next.columns[index].data_type = to.clone();
+            }
+        }
+        Ok(next)
+    }
+
+    /// Convert a tuple written under `schema` (the schema this migration
+    /// was applied to) into the shape of the migrated schema
+    pub fn adapt(&self, schema: &RelationSchema, tuple: Tuple) -> Tuple {
+        match self {
+            SchemaMigration::AddColumn { default, .. } => {
+                let mut tuple = tuple;
+                tuple.push(default.clone());
+                tuple
+            }
+            SchemaMigration::DropColumn { name } => match schema.column_index(name) {
+                Some(index) => tuple.excluding_indices(&[index]),
+                None => tuple,
+            },
+            SchemaMigration::WidenType { column, to } => match schema.column_index(column) {
+                Some(index) => tuple
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, v)| if i == index { to.widen_value(v) } else { v })
+                    .collect(),
+                None => tuple,
+            },
+        }
+    }
+
+    fn check_not_partition_key(schema: &RelationSchema, name: &str) -> Result<(), SchemaError> {
+        match &schema.partition {
+            Some(spec) if spec.column == name => Err(SchemaError::InvalidSchema(format!(
+                "Column '{name}' is the partition key of '{}'",
+                schema.name
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn column_index(schema: &RelationSchema, name: &str) -> Result<usize, SchemaError> {
+        schema.column_index(name).ok_or_else(|| {
+            SchemaError::InvalidSchema(format!("Relation '{}' has no column '{name}'", schema.name))
+        })
+    }
+}
+
+impl fmt::Display for SchemaMigration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaMigration::AddColumn { column, default } => {
+                write!(f, "add {column} default {default}")
+            }
+            SchemaMigration::DropColumn { name } => write!(f, "drop {name}"),
+            SchemaMigration::WidenType { column, to } => write!(f, "widen {column} to {to}"),
+        }
+    }
+}
+
+/// Migration history of one relation.
+///
+/// `previous[i]` is the schema at version `i + 1` and `migrations[i]` turns
+/// it into version `i + 2`; the current schema lives in the catalog.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SchemaHistory {
+    /// Schemas of all superseded versions, oldest first
+    pub previous: Vec<RelationSchema>,
+    /// Migrations applied, oldest first
+    pub migrations: Vec<SchemaMigration>,
+}
+
+impl SchemaHistory {
+    /// Current schema version (1 before any migration)
+    pub fn version(&self) -> u32 {
+        self.migrations.len() as u32 + 1
+    }
+
+    /// Record a migration from `before` to the next version
+    pub fn record(&mut self, before: RelationSchema, migration: SchemaMigration) {
+        self.previous.push(before);
+        self.migrations.push(migration);
+    }
+
+    /// Adapt a tuple written under schema `version` to the current schema.
+    ///
+    /// Version 0 marks data written before schemas were versioned and is
+    /// treated as version 1. Tuples whose arity does not match the schema
+    /// of their version were not written under it and are left unchanged.
+    pub fn adapt(&self, version: u32, tuple: Tuple) -> Tuple {
+        let start = version.max(1) as usize - 1;
+        match self.previous.get(start) {
+            Some(schema) if schema.arity() == tuple.arity() => self.previous[start..]
+                .iter()
+                .zip(&self.migrations[start..])
+                .fold(tuple, |tuple, (schema, migration)| {
+                    migration.adapt(schema, tuple)
+                }),
+            _ => tuple,
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn user_schema() -> RelationSchema {
+        RelationSchema::new("user")
+            .with_column(ColumnSchema::new("id", SchemaType::Int))
+            .with_column(ColumnSchema::new("name", SchemaType::String))
+    }
+
+    #[test]
+    fn test_apply_migrations() {
+        let schema = user_schema();
+
+        let added = SchemaMigration::AddColumn {
+            column: ColumnSchema::new("score", SchemaType::Int),
+            default: Value::Int64(0),
+        }
+        .apply(&schema)
+        .unwrap();
+        assert_eq!(added.column_names(), vec!["id", "name", "score"]);
+
+        let dropped = SchemaMigration::DropColumn {
+            name: "name".to_string(),
+        }
+        .apply(&schema)
+        .unwrap();
+        assert_eq!(dropped.column_names(), vec!["id"]);
+
+        let widened = SchemaMigration::WidenType {
+            column: "id".to_string(),
+            to: SchemaType::Float,
+        }
+        .apply(&schema)
+        .unwrap();
+        assert_eq!(widened.columns[0].data_type, SchemaType::Float);
+    }
+
+    #[test]
+    fn test_invalid_migrations_rejected() {
+        let schema = user_schema();
+        let duplicate = SchemaMigration::AddColumn {
+            column: ColumnSchema::new("id", SchemaType::Int),
+            default: Value::Int64(0),
+        };
+        assert!(duplicate.apply(&schema).is_err());
+
+        let bad_default = SchemaMigration::AddColumn {
+            column: ColumnSchema::new("age", SchemaType::Int),
+            default: Value::string("zero"),
+        };
+        assert!(bad_default.apply(&schema).is_err());
+
+        let missing = SchemaMigration::DropColumn {
+            name: "email".to_string(),
+        };
+        assert!(missing.apply(&schema).is_err());
+
+        let narrowing = SchemaMigration::WidenType {
+            column: "name".to_string(),
+            to: SchemaType::Int,
+        };
+        assert!(narrowing.apply(&schema).is_err());
+    }
+
+    #[test]
+    fn test_history_adapts_old_tuples() {
+        let v1 = user_schema();
+        let add = SchemaMigration::AddColumn {
+            column: ColumnSchema::new("score", SchemaType::Int),
+            default: Value::Null,
+        };
+        let v2 = add.apply(&v1).unwrap();
+        let drop = SchemaMigration::DropColumn {
+            name: "name".to_string(),
+        };
+        let v3 = drop.apply(&v2).unwrap();
+        let widen = SchemaMigration::WidenType {
+            column: "score".to_string(),
+            to: SchemaType::Float,
+        };
+
+        let mut history = SchemaHistory::default();
+        history.record(v1, add);
+        history.record(v2, drop);
+        history.record(v3, widen);
+        assert_eq!(history.version(), 4);
+
+        let old = Tuple::new(vec![Value::Int64(1), Value::string("alice")]);
+        assert_eq!(
+            history.adapt(1, old.clone()),
+            Tuple::new(vec![Value::Int64(1), Value::Null])
+        );
+        // Unversioned data is read as version 1
+        assert_eq!(history.adapt(0, old.clone()), history.adapt(1, old));
+
+        let v3_tuple = Tuple::new(vec![Value::Int64(2), Value::Int64(7)]);
+        assert_eq!(
+            history.adapt(3, v3_tuple),
+            Tuple::new(vec![Value::Int64(2), Value::Float64(7.0)])
+        );
+
+        // Current-version tuples and mismatched arities pass through
+        let current = Tuple::new(vec![Value::Int64(3), Value::Float64(1.5)]);
+        assert_eq!(history.adapt(4, current.clone()), current);
+        let stray = Tuple::new(vec![Value::Int64(3)]);
+        assert_eq!(history.adapt(1, stray.clone()), stray);
+    }
+}
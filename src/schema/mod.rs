@@ -8,15 +8,28 @@
 //! ```
 
 pub mod catalog;
+pub mod constraints;
+pub mod migration;
+pub mod partition;
+pub mod retention;
 pub mod validator;
+pub mod vector_dims;
 
-use crate::value::{DataType, Value};
+use crate::value::{DataType, Tuple, Value};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
 // Re-export public types
 pub use catalog::SchemaCatalog;
-pub use validator::{ValidationEngine, ValidationError, Violation};
+pub use constraints::CheckConstraint;
+pub use migration::{SchemaHistory, SchemaMigration};
+pub use partition::{KeyFilter, PartitionScheme, PartitionSpec};
+pub use retention::RetentionPolicy;
+pub use validator::{
+    ConflictAction, FailureAction, ValidationEngine, ValidationError, ValidationPolicy,
+    ValidationTiming, Violation,
+};
+pub use vector_dims::VectorDims;
 
 /// Schema type in IQL syntax
 /// Maps to internal `DataType` enum
@@ -34,6 +47,20 @@ pub enum SchemaType {
     Bool,
     /// Unix timestamp in milliseconds
     Timestamp,
+    /// Calendar date
+    Date,
+    /// Signed length of time
+    Duration,
+    /// 128-bit UUID
+    Uuid,
+    /// List of values of any type
+    List,
+    /// String-keyed map of values of any type
+    Map,
+    /// JSON document
+    Json,
+    /// Binary blob
+    Bytes,
     /// Vector of f32 values (embeddings).
     /// `dim: Some(n)` enforces exact dimension; `dim: None` accepts any dimension.
     Vector { dim: Option<usize> },
@@ -53,6 +80,13 @@ impl SchemaType {
             SchemaType::String => DataType::String,
             SchemaType::Bool => DataType::Bool,
             SchemaType::Timestamp => DataType::Timestamp,
+            SchemaType::Date => DataType::Date,
+            SchemaType::Duration => DataType::Duration,
+            SchemaType::Uuid => DataType::Uuid,
+            SchemaType::List => DataType::List,
+            SchemaType::Map => DataType::Map,
+            SchemaType::Json => DataType::Json,
+            SchemaType::Bytes => DataType::Bytes,
             SchemaType::Vector { dim: Some(n) } => DataType::vector_with_dim(*n),
             SchemaType::Vector { dim: None } => DataType::vector_any(),
             SchemaType::Any => DataType::Null, // Null used as "any" marker
@@ -73,6 +107,14 @@ impl SchemaType {
             (SchemaType::Bool, Value::Bool(_)) => true,
             (SchemaType::Timestamp, Value::Timestamp(_)) => true,
             (SchemaType::Timestamp, Value::Int64(_)) => true, // Allow int as timestamp
+            // Dates and durations are strict: a bare integer has no unit
+            (SchemaType::Date, Value::Date(_)) => true,
+            (SchemaType::Duration, Value::Duration(_)) => true,
+            (SchemaType::Uuid, Value::Uuid(_)) => true,
+            (SchemaType::List, Value::List(_)) => true,
+            (SchemaType::Map, Value::Map(_)) => true,
+            (SchemaType::Json, Value::Json(_)) => true,
+            (SchemaType::Bytes, Value::Bytes(_)) => true,
             (SchemaType::Vector { dim: Some(n) }, Value::Vector(v)) => v.len() == *n,
             (SchemaType::Vector { dim: Some(n) }, Value::VectorInt8(v)) => v.len() == *n,
             (SchemaType::Vector { dim: None }, Value::Vector(_)) => true,
@@ -92,6 +134,13 @@ impl SchemaType {
             "string" | "str" | "text" => Some(SchemaType::String),
             "bool" | "boolean" => Some(SchemaType::Bool),
             "timestamp" | "time" | "datetime" => Some(SchemaType::Timestamp),
+            "date" => Some(SchemaType::Date),
+            "duration" | "interval" => Some(SchemaType::Duration),
+            "uuid" => Some(SchemaType::Uuid),
+            "list" => Some(SchemaType::List),
+            "map" => Some(SchemaType::Map),
+            "json" | "jsonb" => Some(SchemaType::Json),
+            "bytes" | "blob" | "binary" | "bytea" => Some(SchemaType::Bytes),
             "vector" | "embedding" | "vec" => Some(SchemaType::Vector { dim: None }),
             "any" => Some(SchemaType::Any),
             _ => {
@@ -111,6 +160,37 @@ impl SchemaType {
     pub fn is_base_type(&self) -> bool {
         !matches!(self, SchemaType::Named(_))
     }
+
+    /// Whether a column of this type can be migrated to `to` without
+    /// invalidating existing values
+    pub fn can_widen_to(&self, to: &SchemaType) -> bool {
+        match (self, to) {
+            (from, to) if from == to => false,
+            (_, SchemaType::Any) => true,
+            (SchemaType::Int, SchemaType::Float) => true,
+            (SchemaType::Symbol, SchemaType::String) => true,
+            (SchemaType::Vector { dim: Some(_) }, SchemaType::Vector { dim: None }) => true,
+            _ => false,
+        }
+    }
+
+    /// Convert a value stored under a narrower type into this type
+    pub fn widen_value(&self, value: Value) -> Value {
+        match (self, value) {
+            (SchemaType::Float, Value::Int32(n)) => Value::Float64(f64::from(n)),
+            (SchemaType::Float, Value::Int64(n)) => Value::Float64(n as f64),
+            (_, value) => value,
+        }
+    }
+
+    /// Normalize a value accepted by this type to the type's canonical
+    /// representation (`Int64` for `int`, `Float64` for `float`)
+    pub fn coerce_value(&self, value: Value) -> Value {
+        match (self, value) {
+            (SchemaType::Int, Value::Int32(n)) => Value::Int64(i64::from(n)),
+            (_, value) => self.widen_value(value),
+        }
+    }
 }
 
 impl fmt::Display for SchemaType {
@@ -122,8 +202,15 @@ impl fmt::Display for SchemaType {
             SchemaType::String => write!(f, "string"),
             SchemaType::Bool => write!(f, "bool"),
             SchemaType::Timestamp => write!(f, "timestamp"),
+            SchemaType::Date => write!(f, "date"),
+            SchemaType::Duration => write!(f, "duration"),
+            SchemaType::Uuid => write!(f, "uuid"),
+            SchemaType::List => write!(f, "list"),
+            SchemaType::Map => write!(f, "map"),
+            SchemaType::Json => write!(f, "json"),
+            SchemaType::Bytes => write!(f, "bytes"),
             SchemaType::Vector { dim: None } => write!(f, "vector"),
-            SchemaType::Vector { dim: Some(n) } => write!(f, "vector({n})"),
+            SchemaType::Vector { dim: Some(n) } => write!(f, "vector<{n}>"),
             SchemaType::Any => write!(f, "any"),
             SchemaType::Named(name) => write!(f, "{name}"),
         }
@@ -137,6 +224,21 @@ pub struct ColumnSchema {
     pub name: String,
     /// Column type
     pub data_type: SchemaType,
+    /// Check constraints every value in this column must satisfy
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub constraints: Vec<CheckConstraint>,
+    /// Part of the relation's primary key (`@key`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub primary_key: bool,
+    /// Values are unique across the relation (`@unique`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub unique: bool,
+    /// Value used when an insert omits this column
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<ColumnDefault>,
+    /// Values are assigned from the relation's ID sequence when omitted (`@auto`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub auto_increment: bool,
 }
 
 impl ColumnSchema {
@@ -145,13 +247,161 @@ impl ColumnSchema {
         ColumnSchema {
             name: name.into(),
             data_type,
+            constraints: Vec::new(),
+            primary_key: false,
+            unique: false,
+            default: None,
+            auto_increment: false,
         }
     }
+
+    /// Assign omitted values from the relation's ID sequence (builder pattern)
+    pub fn auto_increment(mut self) -> Self {
+        self.auto_increment = true;
+        self
+    }
+
+    /// Set the value used when an insert omits this column (builder pattern)
+    pub fn with_default(mut self, default: ColumnDefault) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    /// Mark the column as part of the primary key (builder pattern)
+    pub fn primary_key(mut self) -> Self {
+        self.primary_key = true;
+        self
+    }
+
+    /// Mark the column as unique (builder pattern)
+    pub fn unique(mut self) -> Self {
+        self.unique = true;
+        self
+    }
+
+    /// Attach a check constraint (builder pattern)
+    pub fn with_constraint(mut self, constraint: CheckConstraint) -> Self {
+        self.constraints.push(constraint);
+        self
+    }
 }
 
 impl fmt::Display for ColumnSchema {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}: {}", self.name, self.data_type)
+        write!(f, "{}: {}", self.name, self.data_type)?;
+        if !self.constraints.is_empty() {
+            write!(f, "(")?;
+            for (i, c) in self.constraints.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{c}")?;
+            }
+            write!(f, ")")?;
+        }
+        if let Some(default) = &self.default {
+            write!(f, " = {default}")?;
+        }
+        if self.primary_key {
+            write!(f, " @key")?;
+        }
+        if self.unique {
+            write!(f, " @unique")?;
+        }
+        if self.auto_increment {
+            write!(f, " @auto")?;
+        }
+        Ok(())
+    }
+}
+
+/// Default value of a column, applied when an insert provides fewer
+/// values than the schema has columns
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ColumnDefault {
+    /// A fixed value
+    Constant(Value),
+    /// A zero-argument builtin evaluated for every insert
+    Function(DefaultFunction),
+}
+
+/// Builtins usable as column defaults
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DefaultFunction {
+    /// `time_now()`: current Unix timestamp in milliseconds
+    TimeNow,
+    /// `uuid_v7()`: a fresh time-ordered UUID
+    UuidV7,
+}
+
+impl DefaultFunction {
+    /// Look up a default builtin by name
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "time_now" => Ok(DefaultFunction::TimeNow),
+            "uuid_v7" => Ok(DefaultFunction::UuidV7),
+            other => Err(format!(
+                "Function '{other}()' cannot be used as a column default. Supported: time_now(), uuid_v7()"
+            )),
+        }
+    }
+
+    /// IQL name of the builtin
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DefaultFunction::TimeNow => "time_now",
+            DefaultFunction::UuidV7 => "uuid_v7",
+        }
+    }
+
+    /// Schema type of the values this builtin produces
+    pub fn result_type(self) -> SchemaType {
+        match self {
+            DefaultFunction::TimeNow => SchemaType::Timestamp,
+            DefaultFunction::UuidV7 => SchemaType::Uuid,
+        }
+    }
+}
+
+impl ColumnDefault {
+    /// Produce the value to store
+    pub fn evaluate(&self) -> Value {
+        match self {
+            ColumnDefault::Constant(value) => value.clone(),
+            ColumnDefault::Function(DefaultFunction::TimeNow) => {
+                Value::Timestamp(crate::temporal_ops::time_now())
+            }
+            ColumnDefault::Function(DefaultFunction::UuidV7) => Value::Uuid(uuid::Uuid::now_v7()),
+        }
+    }
+
+    /// Check that the default produces values of the column's type
+    pub fn check_type(&self, data_type: &SchemaType) -> Result<(), String> {
+        let ok = match self {
+            ColumnDefault::Constant(value) => value.is_null() || data_type.matches(value),
+            ColumnDefault::Function(func) => {
+                let produced = func.result_type();
+                *data_type == produced
+                    || matches!(data_type, SchemaType::Any | SchemaType::Named(_))
+            }
+        };
+        if ok {
+            Ok(())
+        } else {
+            Err(format!(
+                "Default {self} does not match column type '{data_type}'"
+            ))
+        }
+    }
+}
+
+impl fmt::Display for ColumnDefault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColumnDefault::Constant(value) => write!(f, "{value}"),
+            ColumnDefault::Function(func) => write!(f, "{}()", func.as_str()),
+        }
     }
 }
 
@@ -162,6 +412,12 @@ pub struct RelationSchema {
     pub name: String,
     /// Column definitions
     pub columns: Vec<ColumnSchema>,
+    /// Partition key, if the relation's storage is partitioned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partition: Option<PartitionSpec>,
+    /// Retention policy, if expired tuples are swept
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention: Option<RetentionPolicy>,
 }
 
 impl RelationSchema {
@@ -170,6 +426,8 @@ impl RelationSchema {
         RelationSchema {
             name: name.into(),
             columns: Vec::new(),
+            partition: None,
+            retention: None,
         }
     }
 
@@ -199,6 +457,139 @@ impl RelationSchema {
         self.columns.iter().position(|c| c.name == name)
     }
 
+    /// Partition spec and the index of its key column, if partitioned
+    pub fn partition_key(&self) -> Option<(usize, &PartitionSpec)> {
+        let spec = self.partition.as_ref()?;
+        Some((self.column_index(&spec.column)?, spec))
+    }
+
+    /// Column indices of the primary key (empty if none is declared)
+    pub fn primary_key(&self) -> Vec<usize> {
+        self.columns
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.primary_key)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// All keys whose values must be unique: the (possibly composite)
+    /// primary key followed by each `@unique` column
+    pub fn unique_keys(&self) -> Vec<Vec<usize>> {
+        let mut keys = Vec::new();
+        let primary = self.primary_key();
+        if !primary.is_empty() {
+            keys.push(primary);
+        }
+        for (i, col) in self.columns.iter().enumerate() {
+            let key = vec![i];
+            if col.unique && !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+        keys
+    }
+
+    /// Column indices whose values come from the relation's ID sequence
+    pub fn auto_increment_columns(&self) -> Vec<usize> {
+        self.columns
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.auto_increment)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Whether any column has a default or an auto-increment value
+    pub fn has_generated_columns(&self) -> bool {
+        self.columns
+            .iter()
+            .any(|c| c.default.is_some() || c.auto_increment)
+    }
+
+    /// Complete a tuple that omits columns.
+    ///
+    /// When the tuple is short by at least the number of `@auto` columns,
+    /// those columns are skipped by the given values and assigned from
+    /// `next_id`. Any remaining trailing columns take their defaults (or a
+    /// sequence value for `@auto` columns). Tuples that are already full,
+    /// or that omit a column without a default, are returned unchanged so
+    /// that arity validation reports them.
+    pub fn fill_defaults(&self, tuple: Tuple, next_id: &mut dyn FnMut() -> i64) -> Tuple {
+        let given = tuple.arity();
+        if given >= self.arity() {
+            return tuple;
+        }
+        let auto_count = self.columns.iter().filter(|c| c.auto_increment).count();
+        let skip_auto = auto_count > 0 && given + auto_count <= self.arity();
+
+        // Columns that receive no given value must be fillable
+        let mut remaining = given;
+        for column in &self.columns {
+            if skip_auto && column.auto_increment {
+                continue;
+            }
+            if remaining > 0 {
+                remaining -= 1;
+            } else if column.default.is_none() && !column.auto_increment {
+                return tuple;
+            }
+        }
+
+        let mut values = tuple.into_iter();
+        let filled: Vec<Value> = self
+            .columns
+            .iter()
+            .map(|column| {
+                let given = if skip_auto && column.auto_increment {
+                    None
+                } else {
+                    values.next()
+                };
+                match (given, &column.default) {
+                    (Some(value), _) => value,
+                    (None, _) if column.auto_increment => Value::Int64(next_id()),
+                    (None, Some(default)) => default.evaluate(),
+                    (None, None) => Value::Null,
+                }
+            })
+            .collect();
+        Tuple::new(filled)
+    }
+
+    /// Check that stored vectors have the dimensions declared by this schema
+    pub fn check_vector_dims(&self, tuples: &[Tuple]) -> Result<(), String> {
+        for (col, column) in self.columns.iter().enumerate() {
+            let SchemaType::Vector { dim: Some(n) } = column.data_type else {
+                continue;
+            };
+            let found = tuples.iter().find_map(|t| match t.get(col) {
+                Some(Value::Vector(v)) if v.len() != n => Some(v.len()),
+                Some(Value::VectorInt8(v)) if v.len() != n => Some(v.len()),
+                _ => None,
+            });
+            if let Some(len) = found {
+                return Err(format!(
+                    "Relation '{}' column '{}' is declared vector<{n}> but holds a vector of dimension {len}",
+                    self.name, column.name
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Normalize each value to its column's canonical representation
+    pub fn coerce_tuple(&self, tuple: Tuple) -> Tuple {
+        tuple
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| match self.columns.get(i) {
+                Some(column) => column.data_type.coerce_value(value),
+                None => value,
+            })
+            .collect()
+    }
+
     /// Get all column names
     pub fn column_names(&self) -> Vec<&str> {
         self.columns.iter().map(|c| c.name.as_str()).collect()
@@ -249,6 +640,54 @@ mod tests {
         assert!(SchemaType::Any.matches(&Value::string("anything")));
     }
 
+    #[test]
+    fn test_schema_type_widening() {
+        assert!(SchemaType::Int.can_widen_to(&SchemaType::Float));
+        assert!(SchemaType::Symbol.can_widen_to(&SchemaType::String));
+        assert!(SchemaType::Vector { dim: Some(3) }.can_widen_to(&SchemaType::Vector { dim: None }));
+        assert!(SchemaType::Bool.can_widen_to(&SchemaType::Any));
+        assert!(!SchemaType::Float.can_widen_to(&SchemaType::Int));
+        assert!(!SchemaType::Int.can_widen_to(&SchemaType::Int));
+
+        assert_eq!(
+            SchemaType::Float.widen_value(Value::Int32(2)),
+            Value::Float64(2.0)
+        );
+        assert_eq!(
+            SchemaType::String.widen_value(Value::string("a")),
+            Value::string("a")
+        );
+    }
+
+    #[test]
+    fn test_check_vector_dims() {
+        let schema = RelationSchema::new("doc")
+            .with_column(ColumnSchema::new("v", SchemaType::Vector { dim: Some(2) }));
+        assert!(schema
+            .check_vector_dims(&[Tuple::new(vec![Value::vector(vec![1.0, 2.0])])])
+            .is_ok());
+        let err = schema
+            .check_vector_dims(&[Tuple::new(vec![Value::vector(vec![1.0])])])
+            .unwrap_err();
+        assert!(err.contains("vector<2>"), "{err}");
+    }
+
+    #[test]
+    fn test_coerce_tuple() {
+        let schema = RelationSchema::new("reading")
+            .with_column(ColumnSchema::new("id", SchemaType::Int))
+            .with_column(ColumnSchema::new("value", SchemaType::Float))
+            .with_column(ColumnSchema::new("label", SchemaType::String));
+        let coerced = schema.coerce_tuple(Tuple::new(vec![
+            Value::Int32(1),
+            Value::Int64(20),
+            Value::string("x"),
+        ]));
+        assert!(matches!(coerced.get(0), Some(Value::Int64(1))));
+        assert_eq!(coerced.get(1), Some(&Value::Float64(20.0)));
+        assert_eq!(coerced.get(2), Some(&Value::string("x")));
+    }
+
     #[test]
     fn test_schema_type_from_str() {
         assert_eq!(SchemaType::from_str("int"), Some(SchemaType::Int));
@@ -352,6 +791,42 @@ mod tests {
         assert!(!SchemaType::Timestamp.matches(&Value::string("now")));
     }
 
+    #[test]
+    fn test_schema_type_matches_date_and_duration() {
+        assert!(SchemaType::Date.matches(&Value::Date(19_782)));
+        assert!(!SchemaType::Date.matches(&Value::Int64(19_782)));
+        assert!(!SchemaType::Date.matches(&Value::Timestamp(0)));
+        assert!(SchemaType::Duration.matches(&Value::Duration(1_000)));
+        assert!(!SchemaType::Duration.matches(&Value::Int64(1_000)));
+        assert_eq!(SchemaType::from_str("interval"), Some(SchemaType::Duration));
+        assert_eq!(format!("{}", SchemaType::Date), "date");
+    }
+
+    #[test]
+    fn test_schema_type_matches_json() {
+        let doc = Value::json(r#"{"a": 1}"#).unwrap();
+        assert!(SchemaType::Json.matches(&doc));
+        assert!(!SchemaType::Json.matches(&Value::string(r#"{"a": 1}"#)));
+        assert_eq!(SchemaType::from_str("JSONB"), Some(SchemaType::Json));
+        assert_eq!(SchemaType::Json.to_string(), "json");
+    }
+
+    #[test]
+    fn test_schema_type_matches_bytes() {
+        assert!(SchemaType::Bytes.matches(&Value::bytes(vec![1, 2])));
+        assert!(!SchemaType::Bytes.matches(&Value::string("0102")));
+        assert!(!SchemaType::Bytes.matches(&Value::vector_int8(vec![1, 2])));
+        assert_eq!(SchemaType::from_str("blob"), Some(SchemaType::Bytes));
+    }
+
+    #[test]
+    fn test_schema_type_matches_uuid() {
+        let id = Value::uuid("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+        assert!(SchemaType::Uuid.matches(&id));
+        assert!(!SchemaType::Uuid.matches(&Value::string("67e55044-10b1-426f-9247-bb680e5fe0c8")));
+        assert_eq!(SchemaType::from_str("UUID"), Some(SchemaType::Uuid));
+    }
+
     #[test]
     fn test_schema_type_matches_named_accepts_all() {
         let named = SchemaType::Named("Email".to_string());
@@ -418,7 +893,7 @@ mod tests {
         assert_eq!(format!("{}", SchemaType::Vector { dim: None }), "vector");
         assert_eq!(
             format!("{}", SchemaType::Vector { dim: Some(3) }),
-            "vector(3)"
+            "vector<3>"
         );
         assert_eq!(format!("{}", SchemaType::Any), "any");
         assert_eq!(
@@ -481,6 +956,13 @@ mod tests {
             SchemaType::Bool,
             SchemaType::Symbol,
             SchemaType::Timestamp,
+            SchemaType::Date,
+            SchemaType::Duration,
+            SchemaType::Uuid,
+            SchemaType::List,
+            SchemaType::Map,
+            SchemaType::Json,
+            SchemaType::Bytes,
             SchemaType::Vector { dim: None },
             SchemaType::Vector { dim: Some(128) },
             SchemaType::Any,
@@ -503,4 +985,117 @@ mod tests {
         assert_eq!(back.name, "User");
         assert_eq!(back.arity(), 2);
     }
+
+    #[test]
+    fn test_unique_keys() {
+        let schema = RelationSchema::new("order_line")
+            .with_column(ColumnSchema::new("order", SchemaType::Int).primary_key())
+            .with_column(ColumnSchema::new("line", SchemaType::Int).primary_key())
+            .with_column(ColumnSchema::new("sku", SchemaType::String).unique())
+            .with_column(ColumnSchema::new("qty", SchemaType::Int));
+        assert_eq!(schema.primary_key(), vec![0, 1]);
+        assert_eq!(schema.unique_keys(), vec![vec![0, 1], vec![2]]);
+        assert!(schema.to_string().contains("order: int @key"));
+
+        let json = serde_json::to_string(&schema).unwrap();
+        let back: RelationSchema = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, schema);
+        // Plain schemas keep their serialized form
+        let plain = RelationSchema::new("p").with_column(ColumnSchema::new("x", SchemaType::Int));
+        assert!(!serde_json::to_string(&plain).unwrap().contains("unique"));
+    }
+
+    #[test]
+    fn test_fill_defaults() {
+        let schema = RelationSchema::new("ticket")
+            .with_column(ColumnSchema::new("id", SchemaType::Int))
+            .with_column(
+                ColumnSchema::new("status", SchemaType::String)
+                    .with_default(ColumnDefault::Constant(Value::string("open"))),
+            )
+            .with_column(
+                ColumnSchema::new("created", SchemaType::Timestamp)
+                    .with_default(ColumnDefault::Function(DefaultFunction::TimeNow)),
+            );
+
+        let mut no_ids = || -> i64 { unreachable!("no @auto columns") };
+        let filled = schema.fill_defaults(Tuple::new(vec![Value::Int64(1)]), &mut no_ids);
+        assert_eq!(filled.arity(), 3);
+        assert_eq!(filled.get(1), Some(&Value::string("open")));
+        assert!(matches!(filled.get(2), Some(Value::Timestamp(_))));
+
+        let partial = schema.fill_defaults(
+            Tuple::new(vec![Value::Int64(2), Value::string("closed")]),
+            &mut no_ids,
+        );
+        assert_eq!(partial.get(1), Some(&Value::string("closed")));
+        assert_eq!(partial.arity(), 3);
+
+        // `id` has no default, so an empty tuple is left for arity validation
+        assert_eq!(
+            schema
+                .fill_defaults(Tuple::new(vec![]), &mut no_ids)
+                .arity(),
+            0
+        );
+
+        assert!(ColumnDefault::Function(DefaultFunction::TimeNow)
+            .check_type(&SchemaType::Timestamp)
+            .is_ok());
+        assert!(ColumnDefault::Constant(Value::string("x"))
+            .check_type(&SchemaType::Int)
+            .is_err());
+        assert!(DefaultFunction::from_name("upper").is_err());
+        assert_eq!(
+            schema.columns[2].to_string(),
+            "created: timestamp = time_now()"
+        );
+    }
+
+    #[test]
+    fn test_fill_auto_increment() {
+        let schema = RelationSchema::new("event")
+            .with_column(ColumnSchema::new("id", SchemaType::Int).auto_increment())
+            .with_column(ColumnSchema::new("name", SchemaType::String))
+            .with_column(
+                ColumnSchema::new("level", SchemaType::Int)
+                    .with_default(ColumnDefault::Constant(Value::Int64(0))),
+            );
+        assert_eq!(schema.auto_increment_columns(), vec![0]);
+
+        let mut next = 10;
+        let mut next_id = || {
+            next += 1;
+            next
+        };
+        let filled = schema.fill_defaults(Tuple::new(vec![Value::string("boot")]), &mut next_id);
+        assert_eq!(
+            filled,
+            Tuple::new(vec![
+                Value::Int64(11),
+                Value::string("boot"),
+                Value::Int64(0)
+            ])
+        );
+        let filled = schema.fill_defaults(
+            Tuple::new(vec![Value::string("stop"), Value::Int64(2)]),
+            &mut next_id,
+        );
+        assert_eq!(
+            filled,
+            Tuple::new(vec![
+                Value::Int64(12),
+                Value::string("stop"),
+                Value::Int64(2)
+            ])
+        );
+
+        // Explicit ids are kept and consume no sequence values
+        let explicit = Tuple::new(vec![Value::Int64(5), Value::string("x"), Value::Int64(1)]);
+        assert_eq!(
+            schema.fill_defaults(explicit.clone(), &mut next_id),
+            explicit
+        );
+        assert_eq!(next, 12);
+    }
 }
@@ -0,0 +1,339 @@
+//! # Relation Partitioning
+//!
+//! A relation with a partition key stores its tuples in several persist
+//! shards, one per partition, instead of a single shard. The key is one
+//! column; tuples are assigned to partitions either by hashing the key or
+//! by comparing it against sorted range bounds.
+//!
+//! ```text
+//! .rel alter events partition hash user 8
+//! .rel alter events partition range day 20240101, 20250101
+//! ```
+//!
+//! Scans that constrain the key read only the partitions that can hold
+//! matching tuples ([`PartitionSpec::prune`]), and a knowledge graph loads
+//! its partitions in parallel.
+//!
+//! Partition shards are named `<kg>:<relation>#<layout>.<n>`, where
+//! `<layout>` identifies the spec. Changing the spec therefore never reuses
+//! a shard name, and any shard of a relation outside the current layout
+//! holds data still to be moved into it.
+
+use super::RelationSchema;
+use crate::value::{Tuple, Value};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::Bound;
+
+/// Separates a relation name from the partition part of a shard name
+const PARTITION_SEPARATOR: char = '#';
+
+/// How tuples are assigned to partitions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PartitionScheme {
+    /// By a stable hash of the key, modulo the number of partitions
+    Hash { partitions: u32 },
+    /// By sorted bounds: partition `i` holds keys in `[bounds[i-1], bounds[i])`,
+    /// the first partition keys below `bounds[0]` and the last keys at or
+    /// above the last bound
+    Range { bounds: Vec<Value> },
+}
+
+/// Partition key of a relation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PartitionSpec {
+    /// Name of the key column
+    pub column: String,
+    /// Assignment of key values to partitions
+    pub scheme: PartitionScheme,
+}
+
+/// Constraint on a partition key used to prune partitions from a scan
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyFilter {
+    /// Key equals the value
+    Eq(Value),
+    /// Key is one of the values
+    In(Vec<Value>),
+    /// Key lies between the bounds
+    Range(Bound<Value>, Bound<Value>),
+}
+
+impl KeyFilter {
+    /// Whether a key value satisfies the filter
+    pub fn matches(&self, key: &Value) -> bool {
+        match self {
+            KeyFilter::Eq(value) => key == value,
+            KeyFilter::In(values) => values.contains(key),
+            KeyFilter::Range(lower, upper) => {
+                let above = match lower {
+                    Bound::Included(v) => key >= v,
+                    Bound::Excluded(v) => key > v,
+                    Bound::Unbounded => true,
+                };
+                let below = match upper {
+                    Bound::Included(v) => key <= v,
+                    Bound::Excluded(v) => key < v,
+                    Bound::Unbounded => true,
+                };
+                above && below
+            }
+        }
+    }
+}
+
+impl PartitionSpec {
+    /// Hash partitioning into `partitions` partitions
+    pub fn hash(column: impl Into<String>, partitions: u32) -> Self {
+        PartitionSpec {
+            column: column.into(),
+            scheme: PartitionScheme::Hash { partitions },
+        }
+    }
+
+    /// Range partitioning at the given bounds
+    pub fn range(column: impl Into<String>, bounds: Vec<Value>) -> Self {
+        PartitionSpec {
+            column: column.into(),
+            scheme: PartitionScheme::Range { bounds },
+        }
+    }
+
+    /// Check the spec against the schema of the relation it partitions
+    pub fn validate(&self, schema: &RelationSchema) -> Result<(), String> {
+        let Some(column) = schema.column_by_name(&self.column) else {
+            return Err(format!(
+                "Relation '{}' has no column '{}' to partition by",
+                schema.name, self.column
+            ));
+        };
+        match &self.scheme {
+            PartitionScheme::Hash { partitions } => {
+                if *partitions < 2 {
+                    return Err("Hash partitioning needs at least 2 partitions".to_string());
+                }
+            }
+            PartitionScheme::Range { bounds } => {
+                if bounds.is_empty() {
+                    return Err("Range partitioning needs at least one bound".to_string());
+                }
+                if bounds.windows(2).any(|w| w[0] >= w[1]) {
+                    return Err("Range bounds must be strictly increasing".to_string());
+                }
+                if let Some(bound) = bounds.iter().find(|b| !column.data_type.matches(b)) {
+                    return Err(format!(
+                        "Range bound {bound} does not match type '{}' of column '{}'",
+                        column.data_type, column.name
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of partitions
+    pub fn count(&self) -> u32 {
+        match &self.scheme {
+            PartitionScheme::Hash { partitions } => *partitions,
+            PartitionScheme::Range { bounds } => {
+                u32::try_from(bounds.len() + 1).unwrap_or(u32::MAX)
+            }
+        }
+    }
+
+    /// Partition holding tuples whose key is `key`
+    pub fn partition_for(&self, key: &Value) -> u32 {
+        match &self.scheme {
+            // Hash the display form: it is stable across releases and
+            // platforms, and equal for equal integers of either width
+            PartitionScheme::Hash { partitions } => {
+                crc32fast::hash(key.to_string().as_bytes()) % (*partitions).max(1)
+            }
+            PartitionScheme::Range { bounds } => {
+                u32::try_from(bounds.partition_point(|b| b <= key)).unwrap_or(u32::MAX)
+            }
+        }
+    }
+
+    /// Partition of a tuple, given the index of the key column. Tuples
+    /// missing the column go to the first partition.
+    pub fn partition_of(&self, tuple: &Tuple, column: usize) -> u32 {
+        tuple.get(column).map_or(0, |key| self.partition_for(key))
+    }
+
+    /// Partitions that can hold tuples whose key satisfies `filter`, in
+    /// ascending order
+    pub fn prune(&self, filter: &KeyFilter) -> Vec<u32> {
+        let mut partitions: Vec<u32> = match (filter, &self.scheme) {
+            (KeyFilter::Eq(key), _) => vec![self.partition_for(key)],
+            (KeyFilter::In(keys), _) => keys.iter().map(|k| self.partition_for(k)).collect(),
+            (KeyFilter::Range(lower, upper), PartitionScheme::Range { .. }) => {
+                let first = match lower {
+                    Bound::Included(v) | Bound::Excluded(v) => self.partition_for(v),
+                    Bound::Unbounded => 0,
+                };
+                let last = match upper {
+                    Bound::Included(v) | Bound::Excluded(v) => self.partition_for(v),
+                    Bound::Unbounded => self.count() - 1,
+                };
+                (first..=last).collect()
+            }
+            // Hashing does not preserve order
+            (KeyFilter::Range(..), PartitionScheme::Hash { .. }) => (0..self.count()).collect(),
+        };
+        partitions.sort_unstable();
+        partitions.dedup();
+        partitions
+    }
+
+    /// Short identifier of the spec, part of its partitions' shard names
+    pub fn layout_id(&self) -> String {
+        let encoded = serde_json::to_vec(self).unwrap_or_default();
+        format!("{:08x}", crc32fast::hash(&encoded))
+    }
+
+    /// Name of the shard storing partition `n`, given the shard name the
+    /// relation would have unpartitioned
+    pub fn shard_name(&self, base: &str, n: u32) -> String {
+        format!("{base}{PARTITION_SEPARATOR}{}.{n}", self.layout_id())
+    }
+
+    /// Names of the shards of all partitions, in partition order
+    pub fn shard_names(&self, base: &str) -> Vec<String> {
+        let layout = self.layout_id();
+        (0..self.count())
+            .map(|n| format!("{base}{PARTITION_SEPARATOR}{layout}.{n}"))
+            .collect()
+    }
+}
+
+impl fmt::Display for PartitionSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.scheme {
+            PartitionScheme::Hash { partitions } => {
+                write!(f, "hash {} {partitions}", self.column)
+            }
+            PartitionScheme::Range { bounds } => {
+                write!(f, "range {} ", self.column)?;
+                for (i, bound) in bounds.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{bound}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Relation a shard-level relation name belongs to: `events#1a2b3c4d.3`
+/// is a partition of `events`
+pub fn base_relation(name: &str) -> &str {
+    name.split_once(PARTITION_SEPARATOR)
+        .map_or(name, |(relation, _)| relation)
+}
+
+/// Whether `shard` stores data of the relation whose unpartitioned shard
+/// name is `base`
+pub fn is_shard_of(shard: &str, base: &str) -> bool {
+    shard
+        .strip_prefix(base)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(PARTITION_SEPARATOR))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::schema::{ColumnSchema, SchemaType};
+
+    fn events() -> RelationSchema {
+        RelationSchema::new("events")
+            .with_column(ColumnSchema::new("user", SchemaType::Int))
+            .with_column(ColumnSchema::new("day", SchemaType::Int))
+    }
+
+    #[test]
+    fn test_hash_partitioning_is_stable() {
+        let spec = PartitionSpec::hash("user", 4);
+        spec.validate(&events()).unwrap();
+        assert_eq!(spec.count(), 4);
+        for i in 0..100 {
+            let p = spec.partition_for(&Value::Int32(i));
+            assert!(p < 4);
+            // Integer width does not change the partition
+            assert_eq!(p, spec.partition_for(&Value::Int64(i64::from(i))));
+        }
+        let key = Value::Int64(42);
+        assert_eq!(
+            spec.prune(&KeyFilter::Eq(key.clone())),
+            vec![spec.partition_for(&key)]
+        );
+        assert_eq!(
+            spec.prune(&KeyFilter::Range(Bound::Unbounded, Bound::Excluded(key))),
+            vec![0, 1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_range_partitioning_and_pruning() {
+        let spec = PartitionSpec::range("day", vec![Value::Int64(10), Value::Int64(20)]);
+        spec.validate(&events()).unwrap();
+        assert_eq!(spec.count(), 3);
+        assert_eq!(spec.partition_for(&Value::Int64(3)), 0);
+        assert_eq!(spec.partition_for(&Value::Int64(10)), 1);
+        assert_eq!(spec.partition_for(&Value::Int64(19)), 1);
+        assert_eq!(spec.partition_for(&Value::Int64(25)), 2);
+
+        let filter = KeyFilter::Range(
+            Bound::Included(Value::Int64(12)),
+            Bound::Excluded(Value::Int64(18)),
+        );
+        assert_eq!(spec.prune(&filter), vec![1]);
+        assert!(filter.matches(&Value::Int64(12)));
+        assert!(!filter.matches(&Value::Int64(18)));
+        let filter = KeyFilter::Range(Bound::Included(Value::Int64(15)), Bound::Unbounded);
+        assert_eq!(spec.prune(&filter), vec![1, 2]);
+        let filter = KeyFilter::In(vec![Value::Int64(1), Value::Int64(2), Value::Int64(30)]);
+        assert_eq!(spec.prune(&filter), vec![0, 2]);
+    }
+
+    #[test]
+    fn test_invalid_specs_rejected() {
+        let schema = events();
+        assert!(PartitionSpec::hash("missing", 4).validate(&schema).is_err());
+        assert!(PartitionSpec::hash("user", 1).validate(&schema).is_err());
+        assert!(PartitionSpec::range("day", vec![])
+            .validate(&schema)
+            .is_err());
+        let unsorted = vec![Value::Int64(20), Value::Int64(10)];
+        assert!(PartitionSpec::range("day", unsorted)
+            .validate(&schema)
+            .is_err());
+        let mistyped = vec![Value::string("monday")];
+        assert!(PartitionSpec::range("day", mistyped)
+            .validate(&schema)
+            .is_err());
+    }
+
+    #[test]
+    fn test_partition_shard_names() {
+        let spec = PartitionSpec::hash("user", 2);
+        let names = spec.shard_names("kg:events");
+        assert_eq!(names.len(), 2);
+        assert_eq!(names[1], spec.shard_name("kg:events", 1));
+        assert!(names.iter().all(|n| is_shard_of(n, "kg:events")));
+        assert!(is_shard_of("kg:events", "kg:events"));
+        assert!(!is_shard_of("kg:events_log", "kg:events"));
+        assert_eq!(
+            base_relation(names[0].strip_prefix("kg:").unwrap()),
+            "events"
+        );
+        // A different spec never reuses a shard name
+        assert_ne!(
+            PartitionSpec::hash("user", 4).shard_name("kg:events", 0),
+            names[0]
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! # Retention Policies
+//!
+//! A relation with a retention policy has its expired tuples deleted by a
+//! periodic sweep of the storage engine. Deletes go through the normal
+//! write path, so derived relations see the retractions.
+//!
+//! ```text
+//! .rel alter events retain 30d by ts        -- keep 30 days by timestamp column
+//! .rel alter events retain 1000 rows        -- keep the last 1000 inserted
+//! .rel alter events retain 1000 rows by ts  -- keep the 1000 latest by ts
+//! .rel alter events retain none
+//! ```
+//!
+//! Age is measured on a `timestamp` column, a `date` column (from the
+//! start of the day) or an `int` column holding Unix milliseconds. Tuples
+//! with a null in that column never expire.
+
+use super::{RelationSchema, SchemaType};
+use crate::temporal_ops::{date_to_timestamp, format_duration};
+use crate::value::{Tuple, Value};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Which tuples of a relation to keep
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RetentionPolicy {
+    /// Keep tuples whose `column` is at most `max_age_ms` in the past
+    MaxAge { column: String, max_age_ms: i64 },
+    /// Keep the `rows` latest tuples: the most recently inserted, or those
+    /// with the largest values of `column`
+    MaxRows { rows: usize, column: Option<String> },
+}
+
+impl RetentionPolicy {
+    /// Check the policy against the schema of the relation it applies to
+    pub fn validate(&self, schema: &RelationSchema) -> Result<(), String> {
+        match self {
+            RetentionPolicy::MaxAge { column, max_age_ms } => {
+                if *max_age_ms <= 0 {
+                    return Err("Retention age must be positive".to_string());
+                }
+                let Some(col) = schema.column_by_name(column) else {
+                    return Err(format!(
+                        "Relation '{}' has no column '{column}' to measure age by",
+                        schema.name
+                    ));
+                };
+                match col.data_type {
+                    SchemaType::Timestamp | SchemaType::Date | SchemaType::Int => Ok(()),
+                    ref other => Err(format!(
+                        "Column '{column}' is {other}; retention by age needs a timestamp, date or int column"
+                    )),
+                }
+            }
+            RetentionPolicy::MaxRows { rows, column } => {
+                if *rows == 0 {
+                    return Err("Retention must keep at least one row".to_string());
+                }
+                match column {
+                    Some(column) if schema.column_by_name(column).is_none() => Err(format!(
+                        "Relation '{}' has no column '{column}' to order by",
+                        schema.name
+                    )),
+                    _ => Ok(()),
+                }
+            }
+        }
+    }
+
+    /// Column the policy reads, if any
+    pub fn column(&self) -> Option<&str> {
+        match self {
+            RetentionPolicy::MaxAge { column, .. } => Some(column),
+            RetentionPolicy::MaxRows { column, .. } => column.as_deref(),
+        }
+    }
+
+    /// Tuples of `tuples` (in insertion order) that have expired at
+    /// `now_ms`
+    pub fn expired(&self, schema: &RelationSchema, tuples: &[Tuple], now_ms: i64) -> Vec<Tuple> {
+        let index = self.column().and_then(|c| schema.column_index(c));
+        match self {
+            RetentionPolicy::MaxAge { max_age_ms, .. } => {
+                let Some(index) = index else {
+                    return Vec::new();
+                };
+                let cutoff = now_ms.saturating_sub(*max_age_ms);
+                tuples
+                    .iter()
+                    .filter(|t| {
+                        t.get(index)
+                            .and_then(age_millis)
+                            .is_some_and(|ts| ts < cutoff)
+                    })
+                    .cloned()
+                    .collect()
+            }
+            RetentionPolicy::MaxRows { rows, .. } => {
+                let excess = tuples.len().saturating_sub(*rows);
+                if excess == 0 {
+                    return Vec::new();
+                }
+                let mut order: Vec<usize> = (0..tuples.len()).collect();
+                if let Some(index) = index {
+                    // Stable: equal values expire in insertion order
+                    order.sort_by(|&a, &b| tuples[a].get(index).cmp(&tuples[b].get(index)));
+                }
+                order[..excess].iter().map(|&i| tuples[i].clone()).collect()
+            }
+        }
+    }
+}
+
+/// Unix milliseconds of a value used for age (`None` for nulls and other
+/// types)
+fn age_millis(value: &Value) -> Option<i64> {
+    match value {
+        Value::Timestamp(ms) | Value::Int64(ms) => Some(*ms),
+        Value::Int32(ms) => Some(i64::from(*ms)),
+        Value::Date(days) => Some(date_to_timestamp(*days)),
+        _ => None,
+    }
+}
+
+impl fmt::Display for RetentionPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RetentionPolicy::MaxAge { column, max_age_ms } => {
+                write!(f, "retain {} by {column}", format_duration(*max_age_ms))
+            }
+            RetentionPolicy::MaxRows { rows, column: None } => write!(f, "retain {rows} rows"),
+            RetentionPolicy::MaxRows {
+                rows,
+                column: Some(column),
+            } => write!(f, "retain {rows} rows by {column}"),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::schema::ColumnSchema;
+
+    fn events_schema() -> RelationSchema {
+        RelationSchema::new("events")
+            .with_column(ColumnSchema::new("id", SchemaType::Int))
+            .with_column(ColumnSchema::new("ts", SchemaType::Timestamp))
+    }
+
+    fn event(id: i64, ts: Value) -> Tuple {
+        Tuple::new(vec![Value::Int64(id), ts])
+    }
+
+    #[test]
+    fn test_max_age_expiry() {
+        let policy = RetentionPolicy::MaxAge {
+            column: "ts".to_string(),
+            max_age_ms: 1_000,
+        };
+        let tuples = vec![
+            event(1, Value::Timestamp(8_000)),
+            event(2, Value::Timestamp(9_500)),
+            event(3, Value::Null),
+        ];
+        let expired = policy.expired(&events_schema(), &tuples, 10_000);
+        assert_eq!(expired, vec![tuples[0].clone()]);
+        assert_eq!(policy.to_string(), "retain 1s by ts");
+    }
+
+    #[test]
+    fn test_max_rows_expiry() {
+        let tuples = vec![
+            event(1, Value::Timestamp(300)),
+            event(2, Value::Timestamp(100)),
+            event(3, Value::Timestamp(200)),
+        ];
+        let by_insertion = RetentionPolicy::MaxRows {
+            rows: 2,
+            column: None,
+        };
+        assert_eq!(
+            by_insertion.expired(&events_schema(), &tuples, 0),
+            vec![tuples[0].clone()]
+        );
+
+        let by_ts = RetentionPolicy::MaxRows {
+            rows: 2,
+            column: Some("ts".to_string()),
+        };
+        assert_eq!(
+            by_ts.expired(&events_schema(), &tuples, 0),
+            vec![tuples[1].clone()]
+        );
+        assert!(RetentionPolicy::MaxRows {
+            rows: 5,
+            column: None
+        }
+        .expired(&events_schema(), &tuples, 0)
+        .is_empty());
+    }
+
+    #[test]
+    fn test_retention_validate() {
+        let schema = events_schema();
+        let by_id = RetentionPolicy::MaxAge {
+            column: "id".to_string(),
+            max_age_ms: 10,
+        };
+        assert!(by_id.validate(&schema).is_ok());
+        let missing = RetentionPolicy::MaxRows {
+            rows: 1,
+            column: Some("day".to_string()),
+        };
+        assert!(missing.validate(&schema).is_err());
+        let zero = RetentionPolicy::MaxRows {
+            rows: 0,
+            column: None,
+        };
+        assert!(zero.validate(&schema).is_err());
+    }
+}
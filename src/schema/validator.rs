@@ -3,11 +3,19 @@
 //! Validates tuples against schema definitions with support for:
 //! - Type checking
 //! - Arity checking (correct number of columns)
+//! - Check constraints (`range`, `not_empty`, `pattern`, ...)
 //! - All-or-nothing batch semantics
 //! - Violation reporting
+//!
+//! Arity and type violations always reject the batch. What happens to
+//! tuples that fail a check constraint is governed by the configured
+//! `ValidationPolicy`.
 
-use super::RelationSchema;
-use crate::value::Tuple;
+use super::{CheckConstraint, RelationSchema, SchemaType};
+use crate::value::{Tuple, Value};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// Represents a validation violation
 #[derive(Debug, Clone)]
@@ -67,6 +75,21 @@ pub enum ViolationType {
     ArityMismatch,
     /// Column value has wrong type
     TypeMismatch,
+    /// Column value fails a check constraint
+    CheckFailed,
+    /// Tuple repeats the primary key or a unique column of another tuple
+    UniqueViolation,
+}
+
+impl ViolationType {
+    /// Whether the violation concerns the shape of the tuple rather than
+    /// its values. Structural violations are never accepted.
+    pub fn is_structural(&self) -> bool {
+        matches!(
+            self,
+            ViolationType::ArityMismatch | ViolationType::TypeMismatch
+        )
+    }
 }
 
 impl std::fmt::Display for ViolationType {
@@ -74,10 +97,98 @@ impl std::fmt::Display for ViolationType {
         match self {
             ViolationType::ArityMismatch => write!(f, "ARITY_MISMATCH"),
             ViolationType::TypeMismatch => write!(f, "TYPE_MISMATCH"),
+            ViolationType::CheckFailed => write!(f, "CHECK_FAILED"),
+            ViolationType::UniqueViolation => write!(f, "UNIQUE_VIOLATION"),
         }
     }
 }
 
+/// When violations stop validation of a batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValidationTiming {
+    /// Stop at the first violating tuple (cheapest for large batches)
+    Immediate,
+    /// Validate the whole batch and report every violation
+    #[default]
+    Batch,
+}
+
+/// What to do with tuples that fail a check constraint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailureAction {
+    /// Reject the whole batch (all-or-nothing)
+    #[default]
+    Reject,
+    /// Insert every tuple and report the violations
+    Warn,
+    /// Insert passing tuples; divert failing ones to `<relation>_quarantine`
+    Quarantine,
+}
+
+/// What to do when an insert repeats an existing primary or unique key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictAction {
+    /// Reject the whole batch
+    #[default]
+    Reject,
+    /// Replace the existing tuples that share a key with the new ones
+    Upsert,
+}
+
+/// Validation settings applied to writes into relations with a schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ValidationPolicy {
+    /// Stop at the first violation or collect them all
+    #[serde(default)]
+    pub timing: ValidationTiming,
+    /// Handling of check constraint failures
+    #[serde(default)]
+    pub on_failure: FailureAction,
+    /// Handling of primary key / unique column conflicts
+    #[serde(default)]
+    pub on_conflict: ConflictAction,
+}
+
+impl ValidationPolicy {
+    /// Name of the relation that receives quarantined tuples of `relation`
+    pub fn quarantine_relation(relation: &str) -> String {
+        format!("{relation}_quarantine")
+    }
+}
+
+/// A batch split according to a `ValidationPolicy`
+#[derive(Debug, Clone, Default)]
+pub struct ScreenedBatch {
+    /// Tuples to write into the target relation
+    pub accepted: Vec<Tuple>,
+    /// Tuples to divert to the quarantine relation
+    pub quarantined: Vec<Tuple>,
+    /// Check violations that were tolerated (warned or quarantined)
+    pub violations: Vec<Violation>,
+}
+
+/// Key conflicts between a batch and the data already in a relation
+#[derive(Debug, Clone, Default)]
+pub struct KeyConflicts {
+    /// One violation per conflicting batch tuple and key
+    pub violations: Vec<Violation>,
+    /// Existing tuples that share a key with a batch tuple
+    pub existing: Vec<Tuple>,
+    /// Batch indices superseded by a later batch tuple with the same key
+    pub superseded: Vec<usize>,
+}
+
+impl KeyConflicts {
+    /// Whether no conflicts were found
+    pub fn is_empty(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
 /// Validation error for batch operations
 #[derive(Debug, Clone, thiserror::Error)]
 pub enum ValidationError {
@@ -86,7 +197,9 @@ pub enum ValidationError {
     NoSchema(String),
     /// All-or-nothing: batch rejected due to violations
     #[error(
-        "Insert rejected for '{relation}': batch of {total_tuples} tuples had type/arity errors"
+        "Insert rejected for '{relation}': batch of {total_tuples} tuples had {} violation(s): {}",
+        .violations.len(),
+        .violations.first().map(ToString::to_string).unwrap_or_default()
     )]
     BatchRejected {
         relation: String,
@@ -126,29 +239,102 @@ impl ValidationResult {
 
 /// Validation engine for checking tuples against schemas
 #[derive(Default)]
-pub struct ValidationEngine;
+pub struct ValidationEngine {
+    timing: ValidationTiming,
+    /// Compiled `pattern` constraints, keyed by expression
+    patterns: HashMap<String, Regex>,
+}
 
 impl ValidationEngine {
     /// Create a new validation engine
     pub fn new() -> Self {
-        ValidationEngine
+        ValidationEngine::default()
     }
 
-    /// Validate a batch of tuples against a schema
-    /// Returns Ok if all tuples pass, Err with all violations if any fail
-    pub fn validate_batch(
+    /// Set when validation stops (builder pattern)
+    pub fn with_timing(mut self, timing: ValidationTiming) -> Self {
+        self.timing = timing;
+        self
+    }
+
+    /// Validate a batch and split it according to `policy`.
+    ///
+    /// Structural violations, and check violations under
+    /// `FailureAction::Reject`, reject the whole batch. Otherwise the
+    /// violations are returned alongside the tuples to write.
+    pub fn screen_batch(
+        &mut self,
+        schema: &RelationSchema,
+        tuples: Vec<Tuple>,
+        policy: &ValidationPolicy,
+    ) -> Result<ScreenedBatch, ValidationError> {
+        // Warn and quarantine must see every tuple to route it correctly
+        let timing = match policy.on_failure {
+            FailureAction::Reject => self.timing,
+            FailureAction::Warn | FailureAction::Quarantine => ValidationTiming::Batch,
+        };
+        let violations = self.collect_violations(schema, &tuples, timing);
+        if violations.is_empty() {
+            return Ok(ScreenedBatch {
+                accepted: tuples,
+                ..ScreenedBatch::default()
+            });
+        }
+
+        let rejected = policy.on_failure == FailureAction::Reject
+            || violations.iter().any(|v| v.violation_type.is_structural());
+        if rejected {
+            return Err(ValidationError::BatchRejected {
+                relation: schema.name.clone(),
+                total_tuples: tuples.len(),
+                violations,
+            });
+        }
+
+        let mut screened = ScreenedBatch::default();
+        if policy.on_failure == FailureAction::Quarantine {
+            let failing: std::collections::HashSet<usize> =
+                violations.iter().map(|v| v.tuple_index).collect();
+            for (idx, tuple) in tuples.into_iter().enumerate() {
+                if failing.contains(&idx) {
+                    screened.quarantined.push(tuple);
+                } else {
+                    screened.accepted.push(tuple);
+                }
+            }
+        } else {
+            screened.accepted = tuples;
+        }
+        screened.violations = violations;
+        Ok(screened)
+    }
+
+    fn collect_violations(
         &mut self,
         schema: &RelationSchema,
         tuples: &[Tuple],
-    ) -> Result<ValidationResult, ValidationError> {
+        timing: ValidationTiming,
+    ) -> Vec<Violation> {
         let mut violations = Vec::new();
-
         for (idx, tuple) in tuples.iter().enumerate() {
-            // Collect all violations for this tuple
             if let Err(mut tuple_violations) = self.validate_tuple(schema, tuple, idx) {
                 violations.append(&mut tuple_violations);
+                if timing == ValidationTiming::Immediate {
+                    break;
+                }
             }
         }
+        violations
+    }
+
+    /// Validate a batch of tuples against a schema
+    /// Returns Ok if all tuples pass, Err with all violations if any fail
+    pub fn validate_batch(
+        &mut self,
+        schema: &RelationSchema,
+        tuples: &[Tuple],
+    ) -> Result<ValidationResult, ValidationError> {
+        let violations = self.collect_violations(schema, tuples, self.timing);
 
         if violations.is_empty() {
             Ok(ValidationResult::success(tuples.len()))
@@ -189,17 +375,45 @@ impl ValidationEngine {
             if let Some(value) = tuple.get(col_idx) {
                 // Type check
                 if !col_schema.data_type.matches(value) {
+                    let len = match value {
+                        Value::Vector(v) => Some(v.len()),
+                        Value::VectorInt8(v) => Some(v.len()),
+                        _ => None,
+                    };
+                    let message = match (&col_schema.data_type, len) {
+                        (SchemaType::Vector { dim: Some(n) }, Some(len)) => {
+                            format!("Expected vector<{n}>, got a vector of dimension {len}")
+                        }
+                        _ => format!(
+                            "Expected type '{}', got '{:?}'",
+                            col_schema.data_type,
+                            value.data_type()
+                        ),
+                    };
                     violations.push(Violation::new(
                         tuple_index,
                         tuple.clone(),
                         Some(col_schema.name.clone()),
                         ViolationType::TypeMismatch,
-                        format!(
-                            "Expected type '{}', got '{:?}'",
-                            col_schema.data_type,
-                            value.data_type()
-                        ),
+                        message,
                     ));
+                    continue;
+                }
+
+                for constraint in &col_schema.constraints {
+                    let regex = match constraint {
+                        CheckConstraint::Pattern(pattern) => self.compiled(pattern),
+                        _ => None,
+                    };
+                    if let Err(message) = constraint.check(value, regex) {
+                        violations.push(Violation::new(
+                            tuple_index,
+                            tuple.clone(),
+                            Some(col_schema.name.clone()),
+                            ViolationType::CheckFailed,
+                            message,
+                        ));
+                    }
                 }
             }
         }
@@ -211,6 +425,84 @@ impl ValidationEngine {
         }
     }
 
+    /// Find tuples in `batch` that share a primary or unique key with a
+    /// different tuple, either earlier in the batch or in `existing`.
+    ///
+    /// Keys containing null never conflict. Re-inserting an identical tuple
+    /// is not a conflict (relations have set semantics).
+    pub fn check_unique_keys(
+        &mut self,
+        schema: &RelationSchema,
+        batch: &[Tuple],
+        existing: &[Tuple],
+    ) -> KeyConflicts {
+        let mut conflicts = KeyConflicts::default();
+        for key in schema.unique_keys() {
+            let key_names: Vec<&str> = key
+                .iter()
+                .filter_map(|&i| schema.column(i).map(|c| c.name.as_str()))
+                .collect();
+            let column = Some(key_names.join(", "));
+            let project = |tuple: &Tuple| -> Option<Vec<Value>> {
+                key.iter()
+                    .map(|&i| tuple.get(i).filter(|v| !v.is_null()).cloned())
+                    .collect()
+            };
+
+            // Last batch index per key value
+            let mut latest: HashMap<Vec<Value>, usize> = HashMap::new();
+            for (idx, tuple) in batch.iter().enumerate() {
+                let Some(values) = project(tuple) else {
+                    continue;
+                };
+                if let Some(prev) = latest.insert(values, idx) {
+                    if batch[prev] != *tuple {
+                        conflicts.superseded.push(prev);
+                        conflicts.violations.push(Violation::new(
+                            idx,
+                            tuple.clone(),
+                            column.clone(),
+                            ViolationType::UniqueViolation,
+                            format!("Duplicate key in batch (also tuple #{prev})"),
+                        ));
+                    }
+                }
+            }
+
+            for tuple in existing {
+                let Some(values) = project(tuple) else {
+                    continue;
+                };
+                if let Some(&idx) = latest.get(&values) {
+                    if batch[idx] != *tuple {
+                        conflicts.violations.push(Violation::new(
+                            idx,
+                            batch[idx].clone(),
+                            column.clone(),
+                            ViolationType::UniqueViolation,
+                            format!("Key already exists in {tuple}"),
+                        ));
+                        if !conflicts.existing.contains(tuple) {
+                            conflicts.existing.push(tuple.clone());
+                        }
+                    }
+                }
+            }
+        }
+        conflicts.superseded.sort_unstable();
+        conflicts.superseded.dedup();
+        conflicts
+    }
+
+    /// Compiled regex for a pattern, cached across tuples
+    fn compiled(&mut self, pattern: &str) -> Option<&Regex> {
+        if !self.patterns.contains_key(pattern) {
+            let regex = Regex::new(pattern).ok()?;
+            self.patterns.insert(pattern.to_string(), regex);
+        }
+        self.patterns.get(pattern)
+    }
+
     /// Validate with existing data (for data-first schema registration)
     /// This validates existing tuples when a schema is registered after data exists
     #[allow(unused_variables)]
@@ -227,7 +519,7 @@ impl ValidationEngine {
 #[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
-    use crate::schema::{ColumnSchema, SchemaType};
+    use crate::schema::{CheckConstraint, ColumnSchema, SchemaType};
     use crate::value::Value;
 
     /// Simple schema for testing type/arity validation only
@@ -387,6 +679,7 @@ mod tests {
     fn test_violation_type_display() {
         assert_eq!(ViolationType::ArityMismatch.to_string(), "ARITY_MISMATCH");
         assert_eq!(ViolationType::TypeMismatch.to_string(), "TYPE_MISMATCH");
+        assert_eq!(ViolationType::CheckFailed.to_string(), "CHECK_FAILED");
     }
 
     #[test]
@@ -500,4 +793,140 @@ mod tests {
     fn test_validation_engine_default() {
         let _engine = ValidationEngine::default();
     }
+
+    fn make_checked_schema() -> RelationSchema {
+        RelationSchema::new("person")
+            .with_column(
+                ColumnSchema::new("name", SchemaType::String)
+                    .with_constraint(CheckConstraint::NotEmpty),
+            )
+            .with_column(ColumnSchema::new("age", SchemaType::Int).with_constraint(
+                CheckConstraint::Range {
+                    min: Some(0.0),
+                    max: Some(150.0),
+                },
+            ))
+    }
+
+    fn person(name: &str, age: i64) -> Tuple {
+        Tuple::new(vec![Value::string(name), Value::Int64(age)])
+    }
+
+    #[test]
+    fn test_check_constraint_violation() {
+        let schema = make_checked_schema();
+        let mut engine = ValidationEngine::new();
+
+        assert!(engine.validate_batch(&schema, &[person("Ann", 40)]).is_ok());
+
+        let result = engine.validate_batch(&schema, &[person("", 200)]);
+        let Err(ValidationError::BatchRejected { violations, .. }) = result else {
+            panic!("expected rejection");
+        };
+        assert_eq!(violations.len(), 2);
+        assert!(violations
+            .iter()
+            .all(|v| v.violation_type == ViolationType::CheckFailed));
+        assert_eq!(violations[1].column.as_deref(), Some("age"));
+    }
+
+    #[test]
+    fn test_immediate_timing_stops_at_first_violation() {
+        let schema = make_checked_schema();
+        let tuples = vec![person("Ann", -1), person("Bob", 30), person("", 20)];
+
+        let mut batch = ValidationEngine::new();
+        let Err(ValidationError::BatchRejected { violations, .. }) =
+            batch.validate_batch(&schema, &tuples)
+        else {
+            panic!("expected rejection");
+        };
+        assert_eq!(violations.len(), 2);
+
+        let mut immediate = ValidationEngine::new().with_timing(ValidationTiming::Immediate);
+        let Err(ValidationError::BatchRejected { violations, .. }) =
+            immediate.validate_batch(&schema, &tuples)
+        else {
+            panic!("expected rejection");
+        };
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].tuple_index, 0);
+    }
+
+    #[test]
+    fn test_screen_batch_failure_actions() {
+        let schema = make_checked_schema();
+        let tuples = vec![person("Ann", 40), person("Bob", 999)];
+        let mut engine = ValidationEngine::new();
+
+        let reject = ValidationPolicy::default();
+        assert!(engine
+            .screen_batch(&schema, tuples.clone(), &reject)
+            .is_err());
+
+        let warn = ValidationPolicy {
+            on_failure: FailureAction::Warn,
+            ..ValidationPolicy::default()
+        };
+        let screened = engine.screen_batch(&schema, tuples.clone(), &warn).unwrap();
+        assert_eq!(screened.accepted.len(), 2);
+        assert!(screened.quarantined.is_empty());
+        assert_eq!(screened.violations.len(), 1);
+
+        let quarantine = ValidationPolicy {
+            on_failure: FailureAction::Quarantine,
+            timing: ValidationTiming::Immediate,
+            ..ValidationPolicy::default()
+        };
+        let screened = engine
+            .screen_batch(&schema, tuples.clone(), &quarantine)
+            .unwrap();
+        assert_eq!(screened.accepted, vec![person("Ann", 40)]);
+        assert_eq!(screened.quarantined, vec![person("Bob", 999)]);
+        assert_eq!(screened.violations[0].tuple_index, 1);
+
+        // Structural violations are rejected regardless of the action
+        let wrong_type = vec![Tuple::new(vec![Value::string("Cy"), Value::string("x")])];
+        assert!(engine
+            .screen_batch(&schema, wrong_type, &quarantine)
+            .is_err());
+    }
+
+    #[test]
+    fn test_check_unique_keys() {
+        let schema = RelationSchema::new("account")
+            .with_column(ColumnSchema::new("id", SchemaType::Int).primary_key())
+            .with_column(ColumnSchema::new("email", SchemaType::String).unique());
+        let account =
+            |id: i64, email: &str| Tuple::new(vec![Value::Int64(id), Value::string(email)]);
+        let existing = vec![account(1, "a@x"), account(2, "b@x")];
+        let mut engine = ValidationEngine::new();
+
+        // Identical re-insert and fresh keys do not conflict
+        let ok =
+            engine.check_unique_keys(&schema, &[account(1, "a@x"), account(3, "c@x")], &existing);
+        assert!(ok.is_empty());
+
+        // Existing id with a new email, and an existing email with a new id
+        let clash =
+            engine.check_unique_keys(&schema, &[account(1, "z@x"), account(4, "b@x")], &existing);
+        assert_eq!(clash.violations.len(), 2);
+        assert!(clash
+            .violations
+            .iter()
+            .all(|v| v.violation_type == ViolationType::UniqueViolation));
+        assert_eq!(clash.existing, existing);
+
+        // Duplicate key within the batch: the earlier tuple is superseded
+        let batch = engine.check_unique_keys(&schema, &[account(5, "e@x"), account(5, "f@x")], &[]);
+        assert_eq!(batch.superseded, vec![0]);
+        assert_eq!(batch.violations[0].tuple_index, 1);
+
+        // Null keys never conflict
+        let nulls = vec![
+            Tuple::new(vec![Value::Int64(6), Value::Null]),
+            Tuple::new(vec![Value::Int64(7), Value::Null]),
+        ];
+        assert!(engine.check_unique_keys(&schema, &nulls, &[]).is_empty());
+    }
 }
@@ -0,0 +1,181 @@
+//! # Vector Dimension Checking
+//!
+//! Columns declared as `vector<N>` (or `vector(N)`) fix the dimension of
+//! every vector they hold. Rules and queries that pass such columns, or
+//! vector literals, to a distance or vector arithmetic function are checked
+//! before execution, so `euclidean(V, Q)` over vectors of different
+//! dimensions is a compile error rather than a null result at runtime.
+
+use super::RelationSchema;
+use crate::ast::{BodyPredicate, BuiltinFunc, ComparisonOp, Rule, Term};
+use std::collections::HashMap;
+
+/// Declared vector dimensions: relation name to column index to dimension
+pub type VectorDims = HashMap<String, HashMap<usize, usize>>;
+
+/// Collect the declared vector dimensions of the given schemas
+pub fn collect<'a>(schemas: impl IntoIterator<Item = &'a RelationSchema>) -> VectorDims {
+    schemas
+        .into_iter()
+        .filter_map(|schema| {
+            let cols: HashMap<usize, usize> = schema
+                .columns
+                .iter()
+                .enumerate()
+                .filter_map(|(i, col)| match col.data_type {
+                    super::SchemaType::Vector { dim: Some(n) } => Some((i, n)),
+                    _ => None,
+                })
+                .collect();
+            (!cols.is_empty()).then(|| (schema.name.clone(), cols))
+        })
+        .collect()
+}
+
+/// Check that every vector function in `rule` receives vectors of the
+/// same dimension, where both dimensions are known
+pub fn check_rule(rule: &Rule, dims: &VectorDims) -> Result<(), String> {
+    let mut var_dims: HashMap<&str, usize> = HashMap::new();
+    for pred in &rule.body {
+        let BodyPredicate::Positive(atom) = pred else {
+            continue;
+        };
+        let Some(cols) = dims.get(&atom.relation) else {
+            continue;
+        };
+        for (i, arg) in atom.args.iter().enumerate() {
+            if let (Term::Variable(var), Some(&n)) = (arg, cols.get(&i)) {
+                var_dims.insert(var, n);
+            }
+        }
+    }
+    // Variables bound to vector expressions: `N = vec_normalize(V)`
+    for pred in &rule.body {
+        if let BodyPredicate::Comparison(Term::Variable(var), ComparisonOp::Equal, expr) = pred {
+            if let Some(n) = term_dim(expr, &var_dims) {
+                var_dims.entry(var).or_insert(n);
+            }
+        }
+    }
+
+    for term in &rule.head.args {
+        check_term(term, &var_dims)?;
+    }
+    for pred in &rule.body {
+        match pred {
+            BodyPredicate::Comparison(left, _, right) => {
+                check_term(left, &var_dims)?;
+                check_term(right, &var_dims)?;
+            }
+            BodyPredicate::HnswNearest { query, .. } => check_term(query, &var_dims)?,
+            BodyPredicate::Positive(_) | BodyPredicate::Negated(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Functions whose two vector arguments must have the same dimension
+fn requires_equal_dims(func: &BuiltinFunc) -> bool {
+    matches!(
+        func,
+        BuiltinFunc::Euclidean
+            | BuiltinFunc::Cosine
+            | BuiltinFunc::DotProduct
+            | BuiltinFunc::Manhattan
+            | BuiltinFunc::VecAdd
+            | BuiltinFunc::EuclideanInt8
+            | BuiltinFunc::CosineInt8
+            | BuiltinFunc::DotProductInt8
+            | BuiltinFunc::ManhattanInt8
+    )
+}
+
+fn check_term(term: &Term, var_dims: &HashMap<&str, usize>) -> Result<(), String> {
+    let Term::FunctionCall(func, args) = term else {
+        return Ok(());
+    };
+    for arg in args {
+        check_term(arg, var_dims)?;
+    }
+    if let [left, right] = args.as_slice() {
+        if requires_equal_dims(func) {
+            if let (Some(l), Some(r)) = (term_dim(left, var_dims), term_dim(right, var_dims)) {
+                if l != r {
+                    return Err(format!(
+                        "Vector dimension mismatch in {}({}, {}): vector<{l}> vs vector<{r}>",
+                        func.as_str(),
+                        describe(left),
+                        describe(right)
+                    ));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Statically known dimension of a vector-valued term
+fn term_dim(term: &Term, var_dims: &HashMap<&str, usize>) -> Option<usize> {
+    match term {
+        Term::Variable(var) => var_dims.get(var.as_str()).copied(),
+        Term::VectorLiteral(values) => Some(values.len()),
+        Term::FunctionCall(
+            BuiltinFunc::VecNormalize | BuiltinFunc::VecScale | BuiltinFunc::VecAdd,
+            args,
+        ) => args.first().and_then(|arg| term_dim(arg, var_dims)),
+        _ => None,
+    }
+}
+
+fn describe(term: &Term) -> String {
+    match term {
+        Term::Variable(var) => var.clone(),
+        Term::VectorLiteral(_) => "[...]".to_string(),
+        Term::FunctionCall(func, _) => format!("{}(...)", func.as_str()),
+        _ => "_".to_string(),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_rule;
+    use crate::schema::{ColumnSchema, SchemaType};
+
+    fn dims() -> VectorDims {
+        let doc = RelationSchema::new("doc")
+            .with_column(ColumnSchema::new("id", SchemaType::Int))
+            .with_column(ColumnSchema::new("v", SchemaType::Vector { dim: Some(3) }));
+        let query = RelationSchema::new("query")
+            .with_column(ColumnSchema::new("v", SchemaType::Vector { dim: Some(2) }));
+        let any = RelationSchema::new("any_vec")
+            .with_column(ColumnSchema::new("v", SchemaType::Vector { dim: None }));
+        collect([&doc, &query, &any])
+    }
+
+    #[test]
+    fn test_collect_declared_dims() {
+        let dims = dims();
+        assert_eq!(dims["doc"][&1], 3);
+        assert!(!dims.contains_key("any_vec"));
+    }
+
+    #[test]
+    fn test_check_rule_dims() {
+        let dims = dims();
+        let ok = parse_rule("r(I, D) <- doc(I, V), D = euclidean(V, [1.0, 2.0, 3.0])").unwrap();
+        assert!(check_rule(&ok, &dims).is_ok());
+
+        let bad = parse_rule("r(I, D) <- doc(I, V), query(Q), D = euclidean(V, Q)").unwrap();
+        let err = check_rule(&bad, &dims).unwrap_err();
+        assert!(err.contains("vector<3> vs vector<2>"), "{err}");
+
+        let bad_literal = parse_rule("r(I, D) <- doc(I, V), D = cosine(V, [1.0])").unwrap();
+        assert!(check_rule(&bad_literal, &dims).is_err());
+
+        // Undeclared dimensions are checked at runtime only
+        let unknown = parse_rule("r(D) <- any_vec(V), query(Q), D = euclidean(V, Q)").unwrap();
+        assert!(check_rule(&unknown, &dims).is_ok());
+    }
+}
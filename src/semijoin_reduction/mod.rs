@@ -0,0 +1,504 @@
+//! # Semijoin Reduction
+//!
+//! Pre-filters the large input of a skewed join against the key set of the
+//! small input, so the large side is shrunk before it is arranged.
+//!
+//! Unlike SIP rewriting (which works on the AST and introduces helper rules),
+//! this pass rewrites a single IR tree in place:
+//!
+//! ```text
+//! Join(big, small)  ->  Join(Semijoin(big, small), small)
+//! ```
+//!
+//! The rewrite only fires when both input sizes can be estimated from base
+//! relation cardinalities, the large side has at least `min_rows` tuples, and
+//! it is at least `size_ratio` times larger than the small side. Cartesian
+//! products (no join keys) are never reduced.
+
+use crate::ir::IRNode;
+use std::collections::HashMap;
+
+/// Default minimum estimated size of the large side before reduction applies
+const DEFAULT_MIN_ROWS: usize = 10_000;
+
+/// Default size ratio between the large and the small side
+const DEFAULT_SIZE_RATIO: usize = 10;
+
+/// Inserts semijoin filters in front of joins with heavily skewed inputs.
+pub struct SemijoinReducer {
+    /// Minimum estimated rows on the large side
+    min_rows: usize,
+    /// Minimum ratio between the large and small side estimates
+    size_ratio: usize,
+}
+
+impl SemijoinReducer {
+    /// Create a reducer with default thresholds
+    pub fn new() -> Self {
+        SemijoinReducer {
+            min_rows: DEFAULT_MIN_ROWS,
+            size_ratio: DEFAULT_SIZE_RATIO,
+        }
+    }
+
+    /// Create a reducer with custom thresholds
+    pub fn with_thresholds(min_rows: usize, size_ratio: usize) -> Self {
+        SemijoinReducer {
+            min_rows,
+            size_ratio: size_ratio.max(1),
+        }
+    }
+
+    /// Rewrite skewed joins in `ir`, bottom-up.
+    ///
+    /// `cardinalities` maps base relation names to their tuple counts.
+    /// Joins whose input sizes cannot be estimated are left unchanged.
+    pub fn reduce(&self, ir: IRNode, cardinalities: &HashMap<String, usize>) -> IRNode {
+        match ir {
+            IRNode::Join {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                output_schema,
+            } => {
+                let left = self.reduce(*left, cardinalities);
+                let right = self.reduce(*right, cardinalities);
+                let (left, right) =
+                    self.reduce_join_inputs(left, right, &left_keys, &right_keys, cardinalities);
+                IRNode::Join {
+                    left: Box::new(left),
+                    right: Box::new(right),
+                    left_keys,
+                    right_keys,
+                    output_schema,
+                }
+            }
+
+            IRNode::JoinFlatMap {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                projection,
+                filter_predicate,
+                output_schema,
+            } => {
+                let left = self.reduce(*left, cardinalities);
+                let right = self.reduce(*right, cardinalities);
+                let (left, right) =
+                    self.reduce_join_inputs(left, right, &left_keys, &right_keys, cardinalities);
+                IRNode::JoinFlatMap {
+                    left: Box::new(left),
+                    right: Box::new(right),
+                    left_keys,
+                    right_keys,
+                    projection,
+                    filter_predicate,
+                    output_schema,
+                }
+            }
+
+            IRNode::Antijoin {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                output_schema,
+            } => IRNode::Antijoin {
+                left: Box::new(self.reduce(*left, cardinalities)),
+                right: Box::new(self.reduce(*right, cardinalities)),
+                left_keys,
+                right_keys,
+                output_schema,
+            },
+
+            IRNode::Semijoin {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                output_schema,
+            } => IRNode::Semijoin {
+                left: Box::new(self.reduce(*left, cardinalities)),
+                right: Box::new(self.reduce(*right, cardinalities)),
+                left_keys,
+                right_keys,
+                output_schema,
+            },
+
+            IRNode::Map {
+                input,
+                projection,
+                output_schema,
+            } => IRNode::Map {
+                input: Box::new(self.reduce(*input, cardinalities)),
+                projection,
+                output_schema,
+            },
+
+            IRNode::Filter { input, predicate } => IRNode::Filter {
+                input: Box::new(self.reduce(*input, cardinalities)),
+                predicate,
+            },
+
+            IRNode::Distinct { input } => IRNode::Distinct {
+                input: Box::new(self.reduce(*input, cardinalities)),
+            },
+
+            IRNode::Sort { input, order_by } => IRNode::Sort {
+                input: Box::new(self.reduce(*input, cardinalities)),
+                order_by,
+            },
+
+            IRNode::Union { inputs } => IRNode::Union {
+                inputs: inputs
+                    .into_iter()
+                    .map(|input| self.reduce(input, cardinalities))
+                    .collect(),
+            },
+
+            IRNode::Aggregate {
+                input,
+                group_by,
+                aggregations,
+                output_schema,
+            } => IRNode::Aggregate {
+                input: Box::new(self.reduce(*input, cardinalities)),
+                group_by,
+                aggregations,
+                output_schema,
+            },
+
+            IRNode::Compute { input, expressions } => IRNode::Compute {
+                input: Box::new(self.reduce(*input, cardinalities)),
+                expressions,
+            },
+
+            IRNode::FlatMap {
+                input,
+                projection,
+                filter_predicate,
+                output_schema,
+            } => IRNode::FlatMap {
+                input: Box::new(self.reduce(*input, cardinalities)),
+                projection,
+                filter_predicate,
+                output_schema,
+            },
+
+            other @ (IRNode::Scan { .. } | IRNode::HnswScan { .. }) => other,
+        }
+    }
+
+    /// Wrap the larger join input in a semijoin against the smaller one
+    /// when the size skew crosses the configured thresholds.
+    fn reduce_join_inputs(
+        &self,
+        left: IRNode,
+        right: IRNode,
+        left_keys: &[usize],
+        right_keys: &[usize],
+        cardinalities: &HashMap<String, usize>,
+    ) -> (IRNode, IRNode) {
+        if left_keys.is_empty() || left_keys.len() != right_keys.len() {
+            return (left, right);
+        }
+        // Already reduced on either side - don't stack filters
+        if matches!(left, IRNode::Semijoin { .. }) || matches!(right, IRNode::Semijoin { .. }) {
+            return (left, right);
+        }
+
+        let (Some(left_rows), Some(right_rows)) = (
+            Self::estimate_rows(&left, cardinalities),
+            Self::estimate_rows(&right, cardinalities),
+        ) else {
+            return (left, right);
+        };
+
+        if self.is_skewed(left_rows, right_rows) {
+            let reduced = Self::semijoin(left, &right, left_keys, right_keys);
+            (reduced, right)
+        } else if self.is_skewed(right_rows, left_rows) {
+            let reduced = Self::semijoin(right, &left, right_keys, left_keys);
+            (left, reduced)
+        } else {
+            (left, right)
+        }
+    }
+
+    /// Whether `big` is large enough, relative to `small`, to be worth filtering
+    fn is_skewed(&self, big: usize, small: usize) -> bool {
+        big >= self.min_rows && big >= small.saturating_mul(self.size_ratio)
+    }
+
+    /// Build `Semijoin(big, small)` keeping `big`'s schema
+    fn semijoin(big: IRNode, small: &IRNode, big_keys: &[usize], small_keys: &[usize]) -> IRNode {
+        let output_schema = big.output_schema();
+        IRNode::Semijoin {
+            left: Box::new(big),
+            right: Box::new(small.clone()),
+            left_keys: big_keys.to_vec(),
+            right_keys: small_keys.to_vec(),
+            output_schema,
+        }
+    }
+
+    /// Estimate the number of tuples produced by `ir`.
+    ///
+    /// Returns `None` when the subtree scans a relation with unknown size.
+    /// Estimates are deliberately coarse: filters halve their input, joins
+    /// are bounded by their larger input.
+    fn estimate_rows(ir: &IRNode, cardinalities: &HashMap<String, usize>) -> Option<usize> {
+        match ir {
+            IRNode::Scan { relation, .. } => cardinalities.get(relation).copied(),
+            IRNode::HnswScan { k, .. } => Some(*k),
+            IRNode::Filter { input, .. } => {
+                Self::estimate_rows(input, cardinalities).map(|n| n / 2)
+            }
+            IRNode::FlatMap {
+                input,
+                filter_predicate,
+                ..
+            } => {
+                let n = Self::estimate_rows(input, cardinalities)?;
+                Some(if filter_predicate.is_some() { n / 2 } else { n })
+            }
+            IRNode::Map { input, .. }
+            | IRNode::Distinct { input }
+            | IRNode::Sort { input, .. }
+            | IRNode::Compute { input, .. }
+            | IRNode::Aggregate { input, .. } => Self::estimate_rows(input, cardinalities),
+            IRNode::Join { left, right, .. } | IRNode::JoinFlatMap { left, right, .. } => {
+                let l = Self::estimate_rows(left, cardinalities)?;
+                let r = Self::estimate_rows(right, cardinalities)?;
+                Some(l.max(r))
+            }
+            IRNode::Antijoin { left, .. } | IRNode::Semijoin { left, .. } => {
+                Self::estimate_rows(left, cardinalities)
+            }
+            IRNode::Union { inputs } => inputs.iter().try_fold(0usize, |acc, input| {
+                Some(acc.saturating_add(Self::estimate_rows(input, cardinalities)?))
+            }),
+        }
+    }
+}
+
+impl Default for SemijoinReducer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn make_scan(name: &str, cols: &[&str]) -> IRNode {
+        IRNode::Scan {
+            relation: name.to_string(),
+            schema: cols.iter().map(|s| (*s).to_string()).collect(),
+        }
+    }
+
+    fn make_join(left: IRNode, right: IRNode, left_key: usize, right_key: usize) -> IRNode {
+        let right_keys = vec![right_key];
+        let mut output_schema = left.output_schema();
+        output_schema.extend(
+            right
+                .output_schema()
+                .into_iter()
+                .enumerate()
+                .filter(|(i, _)| !right_keys.contains(i))
+                .map(|(_, c)| c),
+        );
+        IRNode::Join {
+            left: Box::new(left),
+            right: Box::new(right),
+            left_keys: vec![left_key],
+            right_keys,
+            output_schema,
+        }
+    }
+
+    fn cards(entries: &[(&str, usize)]) -> HashMap<String, usize> {
+        entries
+            .iter()
+            .map(|(name, n)| ((*name).to_string(), *n))
+            .collect()
+    }
+
+    #[test]
+    fn test_reduces_large_left_side() {
+        let reducer = SemijoinReducer::new();
+        let ir = make_join(
+            make_scan("big", &["x", "y"]),
+            make_scan("small", &["y", "z"]),
+            1,
+            0,
+        );
+        let result = reducer.reduce(ir, &cards(&[("big", 100_000), ("small", 10)]));
+
+        match result {
+            IRNode::Join { left, right, .. } => {
+                match *left {
+                    IRNode::Semijoin {
+                        left_keys,
+                        right_keys,
+                        output_schema,
+                        ..
+                    } => {
+                        assert_eq!(left_keys, vec![1]);
+                        assert_eq!(right_keys, vec![0]);
+                        assert_eq!(output_schema, vec!["x", "y"]);
+                    }
+                    other => panic!("Expected Semijoin on left, got {other:?}"),
+                }
+                assert!(matches!(*right, IRNode::Scan { .. }));
+            }
+            other => panic!("Expected Join, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_reduces_large_right_side() {
+        let reducer = SemijoinReducer::new();
+        let ir = make_join(
+            make_scan("small", &["x", "y"]),
+            make_scan("big", &["y", "z"]),
+            1,
+            0,
+        );
+        let result = reducer.reduce(ir, &cards(&[("big", 100_000), ("small", 10)]));
+
+        match result {
+            IRNode::Join { left, right, .. } => {
+                assert!(matches!(*left, IRNode::Scan { .. }));
+                match *right {
+                    IRNode::Semijoin {
+                        left_keys,
+                        right_keys,
+                        ..
+                    } => {
+                        assert_eq!(left_keys, vec![0]);
+                        assert_eq!(right_keys, vec![1]);
+                    }
+                    other => panic!("Expected Semijoin on right, got {other:?}"),
+                }
+            }
+            other => panic!("Expected Join, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_balanced_join_unchanged() {
+        let reducer = SemijoinReducer::new();
+        let ir = make_join(
+            make_scan("a", &["x", "y"]),
+            make_scan("b", &["y", "z"]),
+            1,
+            0,
+        );
+        let result = reducer.reduce(ir.clone(), &cards(&[("a", 50_000), ("b", 40_000)]));
+        assert_eq!(result, ir);
+    }
+
+    #[test]
+    fn test_below_min_rows_unchanged() {
+        let reducer = SemijoinReducer::new();
+        let ir = make_join(
+            make_scan("big", &["x", "y"]),
+            make_scan("small", &["y", "z"]),
+            1,
+            0,
+        );
+        let result = reducer.reduce(ir.clone(), &cards(&[("big", 500), ("small", 1)]));
+        assert_eq!(result, ir);
+    }
+
+    #[test]
+    fn test_unknown_cardinality_unchanged() {
+        let reducer = SemijoinReducer::new();
+        let ir = make_join(
+            make_scan("big", &["x", "y"]),
+            make_scan("derived", &["y", "z"]),
+            1,
+            0,
+        );
+        let result = reducer.reduce(ir.clone(), &cards(&[("big", 100_000)]));
+        assert_eq!(result, ir);
+    }
+
+    #[test]
+    fn test_cartesian_join_unchanged() {
+        let reducer = SemijoinReducer::new();
+        let ir = IRNode::Join {
+            left: Box::new(make_scan("big", &["x"])),
+            right: Box::new(make_scan("small", &["y"])),
+            left_keys: vec![],
+            right_keys: vec![],
+            output_schema: vec!["x".to_string(), "y".to_string()],
+        };
+        let result = reducer.reduce(ir.clone(), &cards(&[("big", 100_000), ("small", 10)]));
+        assert_eq!(result, ir);
+    }
+
+    #[test]
+    fn test_custom_thresholds() {
+        let reducer = SemijoinReducer::with_thresholds(10, 2);
+        let ir = make_join(
+            make_scan("a", &["x", "y"]),
+            make_scan("b", &["y", "z"]),
+            1,
+            0,
+        );
+        let result = reducer.reduce(ir, &cards(&[("a", 40), ("b", 20)]));
+        match result {
+            IRNode::Join { left, .. } => assert!(matches!(*left, IRNode::Semijoin { .. })),
+            other => panic!("Expected Join, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_reduces_nested_join_under_map() {
+        let reducer = SemijoinReducer::new();
+        let join = make_join(
+            make_scan("big", &["x", "y"]),
+            make_scan("small", &["y", "z"]),
+            1,
+            0,
+        );
+        let ir = IRNode::Map {
+            input: Box::new(join),
+            projection: vec![0, 2],
+            output_schema: vec!["x".to_string(), "z".to_string()],
+        };
+        let result = reducer.reduce(ir, &cards(&[("big", 100_000), ("small", 10)]));
+        match result {
+            IRNode::Map { input, .. } => match *input {
+                IRNode::Join { left, .. } => assert!(matches!(*left, IRNode::Semijoin { .. })),
+                other => panic!("Expected Join, got {other:?}"),
+            },
+            other => panic!("Expected Map, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_estimate_rows() {
+        let c = cards(&[("r", 1000), ("s", 10)]);
+        let filter = IRNode::Filter {
+            input: Box::new(make_scan("r", &["x"])),
+            predicate: crate::ir::Predicate::ColumnGtConst(0, 5),
+        };
+        assert_eq!(SemijoinReducer::estimate_rows(&filter, &c), Some(500));
+
+        let union = IRNode::Union {
+            inputs: vec![make_scan("r", &["x"]), make_scan("s", &["x"])],
+        };
+        assert_eq!(SemijoinReducer::estimate_rows(&union, &c), Some(1010));
+
+        let unknown = make_scan("t", &["x"]);
+        assert_eq!(SemijoinReducer::estimate_rows(&unknown, &c), None);
+    }
+}
@@ -3,7 +3,8 @@
 //! Defines the `DiffType` supertrait and concrete diff type implementations:
 //! - `isize` (Counting semiring)  -  full bag semantics, 8 bytes per tuple
 //! - `BooleanDiff(i8)` (Boolean semiring)  -  set semantics, 1 byte per tuple
-//! - `MinDiff` / `MaxDiff`  -  infrastructure for recursive min/max aggregation
+//! - `MinDiff` (min-plus semiring)  -  shortest-path recursion, distance carried in the diff
+//! - `MaxDiff`  -  infrastructure for recursive max aggregation
 //!
 //! The `DiffType` trait combines all DD trait requirements (`Semigroup`, `Monoid`, `Abelian`)
 //! with helpers needed by the code generator (`one()`, `to_count()`).
@@ -13,7 +13,10 @@
 //! │   └── Session
 //! │       ├── Ephemeral facts: HashMap<relation, Vec<Tuple>>
 //! │       ├── Ephemeral rules: Vec<Rule>
+//! │       ├── Prepared statements: HashMap<name, PreparedStatement>
 //! │       ├── Knowledge graph binding
+//! │       ├── Settings: query timeout, result row limit, priority
+//! │       ├── Owner (sessions created over HTTP)
 //! │       └── Created/accessed timestamps
 //! └── Config (max sessions, idle timeout)
 //! ```
@@ -38,15 +41,27 @@
 //! - Per-tuple provenance tags (persistent / ephemeral / mixed)
 
 use crate::ast::Rule;
+use crate::protocol::admission::Priority;
+use crate::statement::PreparedStatement;
+use crate::storage_engine::Transaction;
 use crate::value::Tuple;
 use parking_lot::RwLock;
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::Instant;
 
 /// Unique session identifier (cryptographic UUID to prevent enumeration)
 pub type SessionId = String;
 
+/// A session's open transaction (`begin` ... `commit`), shared with the
+/// query job executing its statements
+pub type TransactionSlot = Arc<parking_lot::Mutex<Option<Transaction>>>;
+
+/// A session's prepared statements by name, shared with the query job
+/// executing its statements
+pub type PreparedSlot = Arc<parking_lot::Mutex<HashMap<String, PreparedStatement>>>;
+
 /// Session manager configuration
 #[derive(Debug, Clone)]
 pub struct SessionConfig {
@@ -71,6 +86,84 @@ impl Default for SessionConfig {
     }
 }
 
+/// Limits a session sets on its own programs (`.session set`). They can
+/// only tighten the server's: the smaller of the two applies. The priority
+/// is capped by the session's API key instead (see `Handler`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SessionSettings {
+    /// Query timeout in milliseconds
+    pub query_timeout_ms: Option<u64>,
+    /// Most rows a query returns
+    pub max_result_rows: Option<usize>,
+    /// Admission priority of the session's queries
+    pub priority: Option<Priority>,
+}
+
+impl SessionSettings {
+    /// Names accepted by `.session set` and `.session reset`
+    pub const NAMES: [&'static str; 3] = ["timeout_ms", "max_rows", "priority"];
+
+    /// Set the limit called `name` to `value`
+    pub fn set(&mut self, name: &str, value: u64) -> Result<(), String> {
+        if value == 0 {
+            return Err(format!("Session setting '{name}' must be positive"));
+        }
+        match name {
+            "timeout_ms" => self.query_timeout_ms = Some(value),
+            "max_rows" => self.max_result_rows = Some(value as usize),
+            "priority" => {
+                return Err(
+                    "Session setting 'priority' takes one of: interactive, normal, batch"
+                        .to_string(),
+                )
+            }
+            _ => return Err(Self::unknown(name)),
+        }
+        Ok(())
+    }
+
+    /// Go back to the server's limit called `name`, or all of them
+    pub fn reset(&mut self, name: Option<&str>) -> Result<(), String> {
+        match name {
+            None => *self = Self::default(),
+            Some("timeout_ms") => self.query_timeout_ms = None,
+            Some("max_rows") => self.max_result_rows = None,
+            Some("priority") => self.priority = None,
+            Some(name) => return Err(Self::unknown(name)),
+        }
+        Ok(())
+    }
+
+    /// Timeout for the session's queries under the server's (0 = none)
+    pub fn timeout_ms(&self, server_ms: u64) -> u64 {
+        tighter(self.query_timeout_ms, server_ms)
+    }
+
+    /// Row limit for the session's queries under the server's (0 = none)
+    pub fn max_rows(&self, server_rows: usize) -> usize {
+        tighter(
+            self.max_result_rows.map(|rows| rows as u64),
+            server_rows as u64,
+        ) as usize
+    }
+
+    fn unknown(name: &str) -> String {
+        format!(
+            "Unknown session setting '{name}'. Use: {}",
+            Self::NAMES.join(", ")
+        )
+    }
+}
+
+/// The smaller of a session and a server limit, where 0 means none
+fn tighter(session: Option<u64>, server: u64) -> u64 {
+    match session {
+        Some(limit) if server == 0 => limit,
+        Some(limit) => limit.min(server),
+        None => server,
+    }
+}
+
 /// Provenance tag for query result tuples.
 ///
 /// Assigned by comparing session query results against a persistent-only baseline:
@@ -132,6 +225,15 @@ pub struct Session {
     /// Whether a WebSocket connection is currently attached (#14).
     /// Prevents two WS connections from interleaving on the same session.
     pub ws_attached: bool,
+    /// Writes queued since `begin`, discarded when the session closes
+    transaction: TransactionSlot,
+    /// Statements from `prepare`; kept across knowledge graph switches
+    prepared: PreparedSlot,
+    /// Limits set with `.session set`; kept across knowledge graph switches
+    pub settings: SessionSettings,
+    /// User that created the session over HTTP, the only one (besides
+    /// admins) that may use it by its ID
+    pub owner: Option<String>,
 }
 
 impl Session {
@@ -147,6 +249,10 @@ impl Session {
             last_accessed: now,
             in_use_count: 0,
             ws_attached: false,
+            transaction: TransactionSlot::default(),
+            prepared: PreparedSlot::default(),
+            settings: SessionSettings::default(),
+            owner: None,
         }
     }
 
@@ -386,6 +492,23 @@ impl SessionManager {
     ///
     /// Returns the session ID (UUID), or an error if max sessions exceeded.
     pub fn create_session(&self, knowledge_graph: &str) -> Result<SessionId, String> {
+        self.insert_session(knowledge_graph, None)
+    }
+
+    /// Create a session that only `owner` and admins may use by its ID
+    pub fn create_owned_session(
+        &self,
+        knowledge_graph: &str,
+        owner: &str,
+    ) -> Result<SessionId, String> {
+        self.insert_session(knowledge_graph, Some(owner))
+    }
+
+    fn insert_session(
+        &self,
+        knowledge_graph: &str,
+        owner: Option<&str>,
+    ) -> Result<SessionId, String> {
         let mut sessions = self.sessions.write();
 
         // Check max sessions limit
@@ -397,7 +520,8 @@ impl SessionManager {
         }
 
         let id = uuid::Uuid::new_v4().to_string();
-        let session = Session::new(id.clone(), knowledge_graph.to_string());
+        let mut session = Session::new(id.clone(), knowledge_graph.to_string());
+        session.owner = owner.map(str::to_string);
         sessions.insert(id.clone(), session);
 
         self.audit.record(AuditEvent::SessionCreated {
@@ -616,6 +740,21 @@ impl SessionManager {
         self.with_session(session_id, |session| session.knowledge_graph.clone())
     }
 
+    /// Get the slot holding a session's open transaction
+    pub fn transaction_slot(&self, session_id: &SessionId) -> Result<TransactionSlot, String> {
+        self.with_session(session_id, |session| Arc::clone(&session.transaction))
+    }
+
+    /// Get the slot holding a session's prepared statements
+    pub fn prepared_slot(&self, session_id: &SessionId) -> Result<PreparedSlot, String> {
+        self.with_session(session_id, |session| Arc::clone(&session.prepared))
+    }
+
+    /// Get a session's settings
+    pub fn settings(&self, session_id: &SessionId) -> Result<SessionSettings, String> {
+        self.with_session(session_id, |session| session.settings)
+    }
+
     /// Get query metadata for a session
     pub fn get_query_metadata(&self, session_id: &SessionId) -> Result<QueryMetadata, String> {
         self.with_session(session_id, Session::build_query_metadata)
@@ -942,6 +1081,45 @@ mod tests {
 
     // === Session Lifecycle ===
 
+    #[test]
+    fn test_session_settings_only_tighten() {
+        let mgr = SessionManager::default();
+        let id = mgr.create_owned_session("default", "alice").unwrap();
+        assert_eq!(
+            mgr.with_session(&id, |s| s.owner.clone())
+                .unwrap()
+                .as_deref(),
+            Some("alice")
+        );
+
+        let mut settings = mgr.settings(&id).unwrap();
+        assert_eq!(settings, SessionSettings::default());
+        assert_eq!(settings.timeout_ms(30_000), 30_000);
+        settings.set("timeout_ms", 5_000).unwrap();
+        settings.set("max_rows", 10).unwrap();
+        assert!(settings.set("timeout_ms", 0).is_err());
+        assert!(settings.set("colour", 1).is_err());
+        assert_eq!(settings.timeout_ms(30_000), 5_000);
+        assert_eq!(settings.timeout_ms(1_000), 1_000);
+        assert_eq!(settings.timeout_ms(0), 5_000);
+        assert_eq!(settings.max_rows(0), 10);
+
+        mgr.with_session_mut(&id, |s| s.settings = settings)
+            .unwrap();
+        // Settings survive a knowledge graph switch
+        mgr.switch_kg(&id, "other").unwrap();
+        assert_eq!(mgr.settings(&id).unwrap(), settings);
+
+        settings.reset(Some("timeout_ms")).unwrap();
+        assert_eq!(settings.query_timeout_ms, None);
+        assert_eq!(settings.max_result_rows, Some(10));
+        settings.priority = Some(Priority::Batch);
+        settings.reset(Some("priority")).unwrap();
+        assert_eq!(settings.priority, None);
+        settings.reset(None).unwrap();
+        assert_eq!(settings, SessionSettings::default());
+    }
+
     #[test]
     fn test_create_session() {
         let mgr = SessionManager::default();
@@ -0,0 +1,169 @@
+//! Slow Query Log
+//!
+//! Queries running for at least `slow_query_log_ms` are logged at WARN
+//! level. With `slow_query_relation` (the default), each one is also
+//! appended to the `slow_queries` relation of the `_audit` knowledge graph,
+//! together with the plan it ran: the optimized IR of each rule, the rows of
+//! each stored relation it read, and the time and output rows of each rule.
+//! Regressions can then be diagnosed after the fact, without re-running the
+//! query with debug logging:
+//!
+//! ```text
+//! .kg use _audit
+//! ?slow_queries(Time, Kg, Statement, Ms, Rows, Outcome, Plan, Inputs, Steps), Ms > 10000
+//! ```
+//!
+//! The relation is created with the first slow query. Like `statements`, it
+//! is read-only and admin-only.
+
+use std::fmt::Write as _;
+
+use crate::execution::ProgramPlan;
+use crate::schema::{ColumnSchema, RelationSchema, SchemaType};
+use crate::value::{Tuple, Value};
+
+/// Relation of [`crate::audit::AUDIT_KG`] slow queries are appended to
+pub const SLOW_QUERY_RELATION: &str = "slow_queries";
+
+/// One query that ran for at least `slow_query_log_ms`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlowQueryRecord {
+    /// Start of execution, in milliseconds since the epoch
+    pub time: i64,
+    pub knowledge_graph: String,
+    /// Program text, with passwords redacted
+    pub statement: String,
+    pub duration_ms: u64,
+    /// Rows in the result
+    pub rows: usize,
+    /// `ok`, `error` or `timeout`
+    pub outcome: &'static str,
+    /// Every program the engine ran for the query, in order
+    pub plans: Vec<ProgramPlan>,
+}
+
+impl SlowQueryRecord {
+    /// The record as a tuple of [`relation_schema`]
+    pub fn to_tuple(&self) -> Tuple {
+        Tuple::new(vec![
+            Value::Timestamp(self.time),
+            Value::string(&self.knowledge_graph),
+            Value::string(&self.statement),
+            Value::Int64(self.duration_ms as i64),
+            Value::Int64(self.rows as i64),
+            Value::string(self.outcome),
+            Value::string(&format_rules(&self.plans)),
+            Value::string(&format_inputs(&self.plans)),
+            Value::string(&format_steps(&self.plans)),
+        ])
+    }
+}
+
+/// Schema of the `slow_queries` relation
+pub fn relation_schema() -> RelationSchema {
+    [
+        ("time", SchemaType::Timestamp),
+        ("knowledge_graph", SchemaType::String),
+        ("statement", SchemaType::String),
+        ("duration_ms", SchemaType::Int),
+        ("rows", SchemaType::Int),
+        ("outcome", SchemaType::String),
+        ("plan", SchemaType::String),
+        ("inputs", SchemaType::String),
+        ("steps", SchemaType::String),
+    ]
+    .into_iter()
+    .fold(
+        RelationSchema::new(SLOW_QUERY_RELATION),
+        |schema, (name, ty)| schema.with_column(ColumnSchema::new(name, ty)),
+    )
+}
+
+/// The optimized IR of each rule, under its head
+fn format_rules(plans: &[ProgramPlan]) -> String {
+    let mut out = String::new();
+    for (head, ir) in plans.iter().flat_map(|plan| &plan.rules) {
+        let _ = writeln!(out, "{head}:\n{}", ir.trim_end());
+    }
+    out.trim_end().to_string()
+}
+
+/// Input cardinalities, e.g. `edge=1200, node=40`. A relation read by
+/// several programs is listed once, with the rows it had first.
+fn format_inputs(plans: &[ProgramPlan]) -> String {
+    let mut seen = Vec::new();
+    for (relation, rows) in plans.iter().flat_map(|plan| &plan.inputs) {
+        if !seen.iter().any(|(r, _)| r == &relation) {
+            seen.push((relation, rows));
+        }
+    }
+    seen.iter()
+        .map(|(relation, rows)| format!("{relation}={rows}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// One line per executed rule, in execution order
+fn format_steps(plans: &[ProgramPlan]) -> String {
+    plans
+        .iter()
+        .flat_map(|plan| &plan.steps)
+        .map(|step| {
+            format!(
+                "{}{}: {:.3} ms, {} rows",
+                step.rules,
+                if step.recursive { " (recursive)" } else { "" },
+                step.execution_us as f64 / 1000.0,
+                step.rows
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::execution::PlanStep;
+
+    fn plan(inputs: &[(&str, usize)], steps: Vec<PlanStep>) -> ProgramPlan {
+        ProgramPlan {
+            rules: vec![("path".to_string(), "Scan(edge)\n".to_string())],
+            inputs: inputs.iter().map(|(r, n)| (r.to_string(), *n)).collect(),
+            steps,
+        }
+    }
+
+    #[test]
+    fn test_record_renders_plan_inputs_and_steps() {
+        let step = PlanStep {
+            rules: "path".to_string(),
+            recursive: true,
+            execution_us: 12_500,
+            rows: 6,
+        };
+        let record = SlowQueryRecord {
+            time: 1_700_000_000_000,
+            knowledge_graph: "default".to_string(),
+            statement: "?path(X, Y)".to_string(),
+            duration_ms: 6000,
+            rows: 6,
+            outcome: "ok",
+            plans: vec![
+                plan(&[("edge", 3), ("node", 4)], vec![step]),
+                plan(&[("edge", 5)], Vec::new()),
+            ],
+        };
+        let tuple = record.to_tuple();
+        assert_eq!(tuple.arity(), relation_schema().arity());
+
+        let text = |i: usize| match &tuple.values()[i] {
+            Value::String(s) => s.to_string(),
+            other => panic!("expected a string, got {other:?}"),
+        };
+        assert_eq!(text(6), "path:\nScan(edge)\npath:\nScan(edge)");
+        assert_eq!(text(7), "edge=3, node=4");
+        assert_eq!(text(8), "path (recursive): 12.500 ms, 6 rows");
+    }
+}
@@ -2,6 +2,10 @@
 //!
 //! Meta commands are dot-prefixed: .kg, .rel, .rule, .session, etc.
 
+use super::types::parse_type_expr;
+use crate::protocol::admission::Priority;
+use crate::storage::persist::RecoveryTarget;
+
 /// Meta commands for knowledge graph/relation/rule management
 #[derive(Clone, PartialEq)]
 pub enum MetaCommand {
@@ -11,11 +15,25 @@ pub enum MetaCommand {
     KgCreate(String),
     KgUse(String),
     KgDrop(String),
+    KgDropCascade(String), // .kg drop <name> cascade - also drop rules of other KGs reading it
+    KgCopy {
+        source: String,
+        target: String,
+    }, // .kg copy <source> <target>
+    KgRename {
+        source: String,
+        target: String,
+    }, // .kg rename <old> <new>
+    KgRestore(RecoveryTarget), // .kg restore lsn <n> | time <timestamp> - point-in-time recovery
 
     // Relation commands
     RelList,
     RelDescribe(String),
     RelDrop(String),
+    RelAlter {
+        relation: String,
+        action: RelAlterAction,
+    }, // .rel alter <name> add|drop|widen ... - migrate a persistent schema
 
     // Rule commands (persistent derived relations)
     RuleList,
@@ -35,12 +53,25 @@ pub enum MetaCommand {
         name: String,
         index: usize,
     },
+    RuleRefresh(String), // .rule refresh <name> - recompute a cached view now
+    RuleSetRefresh {
+        // .rule refresh <name> eager|lazy|every <duration>|none - set the refresh policy
+        name: String,
+        policy: Option<crate::rule_catalog::RefreshPolicy>,
+    },
 
     // Session commands (transient rules)
     SessionList,             // .session - list session rules
     SessionClear,            // .session clear - clear all session rules
     SessionDrop(usize),      // .session drop <n> - remove rule #n (0-based internally)
     SessionDropName(String), // .session drop <name> - remove all rules for a relation
+    SessionSettings,         // .session settings - show the session's limits
+    SessionSet {
+        name: String,
+        value: u64,
+    }, // .session set <name> <value>
+    SessionSetPriority(Priority), // .session set priority <interactive|normal|batch>
+    SessionReset(Option<String>), // .session reset [name] - back to the server's limits
 
     // Index commands (HNSW and other indexes)
     IndexList,                       // .index list - list all indexes
@@ -54,6 +85,13 @@ pub enum MetaCommand {
 
     // System commands
     Compact,
+    EncryptionRotate, // .encryption rotate - re-encrypt persist files with the current key
+    LogShow,          // .log - show the active log filter
+    LogLevel(Option<String>), // .log level <directives> | .log reset - change log verbosity
+    ReplicationStatus, // .replication - show role, position and lag
+    ReplicationPromote, // .replication promote - stop following and accept writes
+    ClusterStatus,    // .cluster - show term, leader, members and ordered schema changes
+    ShardStatus,      // .shard - show the shards and where sharded relations live
     Status,
     Debug(String),   // .debug <query> - show query plan without executing
     Why(String),     // .why <query> - show proof trees for query results
@@ -138,9 +176,20 @@ fn format_meta_debug(cmd: &MetaCommand) -> String {
         MetaCommand::KgCreate(s) => format!("KgCreate({s:?})"),
         MetaCommand::KgUse(s) => format!("KgUse({s:?})"),
         MetaCommand::KgDrop(s) => format!("KgDrop({s:?})"),
+        MetaCommand::KgDropCascade(s) => format!("KgDropCascade({s:?})"),
+        MetaCommand::KgCopy { source, target } => {
+            format!("KgCopy {{ source: {source:?}, target: {target:?} }}")
+        }
+        MetaCommand::KgRename { source, target } => {
+            format!("KgRename {{ source: {source:?}, target: {target:?} }}")
+        }
+        MetaCommand::KgRestore(target) => format!("KgRestore({target:?})"),
         MetaCommand::RelList => "RelList".to_string(),
         MetaCommand::RelDescribe(s) => format!("RelDescribe({s:?})"),
         MetaCommand::RelDrop(s) => format!("RelDrop({s:?})"),
+        MetaCommand::RelAlter { relation, action } => {
+            format!("RelAlter {{ relation: {relation:?}, action: {action:?} }}")
+        }
         MetaCommand::RuleList => "RuleList".to_string(),
         MetaCommand::RuleQuery(s) => format!("RuleQuery({s:?})"),
         MetaCommand::RuleShowDef(s) => format!("RuleShowDef({s:?})"),
@@ -155,10 +204,20 @@ fn format_meta_debug(cmd: &MetaCommand) -> String {
         MetaCommand::RuleRemove { name, index } => {
             format!("RuleRemove {{ name: {name:?}, index: {index} }}")
         }
+        MetaCommand::RuleRefresh(s) => format!("RuleRefresh({s:?})"),
+        MetaCommand::RuleSetRefresh { name, policy } => {
+            format!("RuleSetRefresh {{ name: {name:?}, policy: {policy:?} }}")
+        }
         MetaCommand::SessionList => "SessionList".to_string(),
         MetaCommand::SessionClear => "SessionClear".to_string(),
         MetaCommand::SessionDrop(n) => format!("SessionDrop({n})"),
         MetaCommand::SessionDropName(s) => format!("SessionDropName({s:?})"),
+        MetaCommand::SessionSettings => "SessionSettings".to_string(),
+        MetaCommand::SessionSet { name, value } => {
+            format!("SessionSet {{ name: {name:?}, value: {value} }}")
+        }
+        MetaCommand::SessionSetPriority(p) => format!("SessionSetPriority({p:?})"),
+        MetaCommand::SessionReset(s) => format!("SessionReset({s:?})"),
         MetaCommand::IndexList => "IndexList".to_string(),
         MetaCommand::IndexCreate(opts) => format!("IndexCreate({opts:?})"),
         MetaCommand::IndexDrop(s) => format!("IndexDrop({s:?})"),
@@ -166,6 +225,13 @@ fn format_meta_debug(cmd: &MetaCommand) -> String {
         MetaCommand::IndexRebuild(s) => format!("IndexRebuild({s:?})"),
         MetaCommand::ClearPrefix(s) => format!("ClearPrefix({s:?})"),
         MetaCommand::Compact => "Compact".to_string(),
+        MetaCommand::EncryptionRotate => "EncryptionRotate".to_string(),
+        MetaCommand::LogShow => "LogShow".to_string(),
+        MetaCommand::LogLevel(s) => format!("LogLevel({s:?})"),
+        MetaCommand::ReplicationStatus => "ReplicationStatus".to_string(),
+        MetaCommand::ReplicationPromote => "ReplicationPromote".to_string(),
+        MetaCommand::ClusterStatus => "ClusterStatus".to_string(),
+        MetaCommand::ShardStatus => "ShardStatus".to_string(),
         MetaCommand::Status => "Status".to_string(),
         MetaCommand::Debug(s) => format!("Debug({s:?})"),
         MetaCommand::Why(s) => format!("Why({s:?})"),
@@ -202,6 +268,51 @@ fn format_meta_debug(cmd: &MetaCommand) -> String {
     }
 }
 
+/// Schema change requested by `.rel alter`
+#[derive(Debug, Clone, PartialEq)]
+pub enum RelAlterAction {
+    /// `add <col>: <type> [default <value>]`; the default is kept as IQL
+    /// source text and evaluated when the command runs
+    Add {
+        column: crate::schema::ColumnSchema,
+        default: Option<String>,
+    },
+    /// `drop <col>`
+    Drop(String),
+    /// `widen <col> to <type>`
+    Widen {
+        column: String,
+        to: crate::schema::SchemaType,
+    },
+    /// `partition hash|range ...`, or `partition none` to remove the key
+    Partition(Option<PartitionDecl>),
+    /// `retain <duration> by <col>` or `retain <n> rows [by <col>]`, or
+    /// `retain none` to remove the policy
+    Retain(Option<crate::schema::RetentionPolicy>),
+    /// `shard hash <col>` or `shard broadcast`, or `shard none` to keep the
+    /// relation on the router again
+    Shard(Option<ShardDecl>),
+}
+
+/// Placement declared by `.rel alter <name> shard`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShardDecl {
+    /// `hash <col>`: each tuple lives on the shard its key hashes to
+    Hash { column: String },
+    /// `broadcast`: every shard holds a full copy
+    Broadcast,
+}
+
+/// Partition key declared by `.rel alter <name> partition`
+#[derive(Debug, Clone, PartialEq)]
+pub enum PartitionDecl {
+    /// `hash <col> <partitions>`
+    Hash { column: String, partitions: u32 },
+    /// `range <col> <bound>, <bound>, ...`; the bounds are kept as IQL
+    /// source text and evaluated when the command runs
+    Range { column: String, bounds: Vec<String> },
+}
+
 /// Options for creating an index
 #[derive(Debug, Clone, PartialEq)]
 pub struct IndexCreateOptions {
@@ -221,6 +332,10 @@ pub struct IndexCreateOptions {
     pub ef_construction: Option<usize>,
     /// HNSW ef_search parameter (default search quality)
     pub ef_search: Option<usize>,
+    /// LSH number of hash tables
+    pub tables: Option<usize>,
+    /// LSH hyperplanes (bucket bits) per table
+    pub hyperplanes: Option<usize>,
 }
 
 /// Mode for loading files
@@ -246,12 +361,26 @@ pub fn parse_meta_command(input: &str) -> Result<MetaCommand, String> {
 
     match parts[0].to_lowercase().as_str() {
         "kg" => parse_kg_command(&parts),
-        "rel" | "relation" => parse_rel_command(&parts),
+        "rel" | "relation" => parse_rel_command(&parts, input),
         "rule" => parse_rule_command(&parts, input),
         "session" | "rules" => parse_session_command(&parts),
         "index" | "idx" => parse_index_command(&parts, input),
         "clear" => parse_clear_command(&parts),
         "compact" => Ok(MetaCommand::Compact),
+        "encryption" => match parts.get(1).map(|p| p.to_lowercase()).as_deref() {
+            Some("rotate") if parts.len() == 2 => Ok(MetaCommand::EncryptionRotate),
+            _ => Err("Usage: .encryption rotate".to_string()),
+        },
+        "log" => parse_log_command(&parts),
+        "replication" => match parts.get(1).map(|p| p.to_lowercase()).as_deref() {
+            None => Ok(MetaCommand::ReplicationStatus),
+            Some("promote") if parts.len() == 2 => Ok(MetaCommand::ReplicationPromote),
+            _ => Err("Usage: .replication | .replication promote".to_string()),
+        },
+        "cluster" if parts.len() == 1 => Ok(MetaCommand::ClusterStatus),
+        "cluster" => Err("Usage: .cluster".to_string()),
+        "shard" | "shards" if parts.len() == 1 => Ok(MetaCommand::ShardStatus),
+        "shard" | "shards" => Err("Usage: .shard".to_string()),
         "status" => Ok(MetaCommand::Status),
         "debug" => {
             if parts.len() < 2 {
@@ -334,19 +463,67 @@ fn parse_kg_command(parts: &[&str]) -> Result<MetaCommand, String> {
                     Ok(MetaCommand::KgUse(parts[2].to_string()))
                 }
             }
-            "drop" => {
-                if parts.len() < 3 {
-                    Err("Usage: .kg drop <name>".to_string())
+            "drop" => match parts.get(3).map(|p| p.to_lowercase()) {
+                _ if parts.len() < 3 || parts.len() > 4 => {
+                    Err("Usage: .kg drop <name> [cascade]".to_string())
+                }
+                None => Ok(MetaCommand::KgDrop(parts[2].to_string())),
+                Some(flag) if flag == "cascade" => {
+                    Ok(MetaCommand::KgDropCascade(parts[2].to_string()))
+                }
+                Some(_) => Err("Usage: .kg drop <name> [cascade]".to_string()),
+            },
+            "copy" => {
+                if parts.len() != 4 {
+                    Err("Usage: .kg copy <source> <target>".to_string())
+                } else {
+                    Ok(MetaCommand::KgCopy {
+                        source: parts[2].to_string(),
+                        target: parts[3].to_string(),
+                    })
+                }
+            }
+            "rename" => {
+                if parts.len() != 4 {
+                    Err("Usage: .kg rename <old> <new>".to_string())
                 } else {
-                    Ok(MetaCommand::KgDrop(parts[2].to_string()))
+                    Ok(MetaCommand::KgRename {
+                        source: parts[2].to_string(),
+                        target: parts[3].to_string(),
+                    })
                 }
             }
+            "restore" => parse_kg_restore_command(parts),
             "acl" => parse_kg_acl_command(parts),
             _ => Err(format!("Unknown kg subcommand: {}", parts[1])),
         }
     }
 }
 
+/// Parse `.kg restore lsn <n>` or `.kg restore time <timestamp>`. The
+/// timestamp is ISO 8601 or Unix milliseconds.
+fn parse_kg_restore_command(parts: &[&str]) -> Result<MetaCommand, String> {
+    const USAGE: &str = "Usage: .kg restore lsn <n> | .kg restore time <timestamp>";
+    if parts.len() < 4 {
+        return Err(USAGE.to_string());
+    }
+    let value = parts[3..].join(" ");
+    let target = match parts[2].to_lowercase().as_str() {
+        "lsn" => value
+            .parse()
+            .map(RecoveryTarget::Lsn)
+            .map_err(|_| format!("Invalid LSN '{value}'"))?,
+        "time" => value
+            .parse()
+            .ok()
+            .or_else(|| crate::temporal_ops::parse_timestamp(&value))
+            .map(RecoveryTarget::Timestamp)
+            .ok_or_else(|| format!("Invalid timestamp '{value}'"))?,
+        _ => return Err(USAGE.to_string()),
+    };
+    Ok(MetaCommand::KgRestore(target))
+}
+
 fn parse_kg_acl_command(parts: &[&str]) -> Result<MetaCommand, String> {
     // .kg acl → error (need subcommand)
     // .kg acl list → list ACLs for current KG
@@ -393,7 +570,7 @@ fn parse_kg_acl_command(parts: &[&str]) -> Result<MetaCommand, String> {
     }
 }
 
-fn parse_rel_command(parts: &[&str]) -> Result<MetaCommand, String> {
+fn parse_rel_command(parts: &[&str], input: &str) -> Result<MetaCommand, String> {
     if parts.len() == 1 {
         Ok(MetaCommand::RelList)
     } else if parts[1].to_lowercase() == "drop" {
@@ -402,11 +579,161 @@ fn parse_rel_command(parts: &[&str]) -> Result<MetaCommand, String> {
         } else {
             Ok(MetaCommand::RelDrop(parts[2].to_string()))
         }
+    } else if parts[1].to_lowercase() == "alter" {
+        parse_rel_alter(input)
     } else {
         Ok(MetaCommand::RelDescribe(parts[1].to_string()))
     }
 }
 
+const REL_ALTER_USAGE: &str = "Usage: .rel alter <name> add <col>: <type> [default <value>] \
+     | .rel alter <name> drop <col> | .rel alter <name> widen <col> to <type> \
+     | .rel alter <name> partition hash <col> <n> \
+     | .rel alter <name> partition range <col> <bound>, ... | .rel alter <name> partition none \
+     | .rel alter <name> retain <duration> by <col> | .rel alter <name> retain <n> rows [by <col>] \
+     | .rel alter <name> retain none \
+     | .rel alter <name> shard hash <col> | .rel alter <name> shard broadcast \
+     | .rel alter <name> shard none";
+
+/// Split off the first whitespace-delimited word
+fn next_word(s: &str) -> (&str, &str) {
+    let s = s.trim_start();
+    let end = s.find(char::is_whitespace).unwrap_or(s.len());
+    (&s[..end], s[end..].trim_start())
+}
+
+fn parse_rel_alter(input: &str) -> Result<MetaCommand, String> {
+    // Skip "rel alter"
+    let (_, rest) = next_word(input);
+    let (_, rest) = next_word(rest);
+    let (relation, rest) = next_word(rest);
+    let (verb, rest) = next_word(rest);
+    if relation.is_empty() || verb.is_empty() || rest.is_empty() {
+        return Err(REL_ALTER_USAGE.to_string());
+    }
+
+    let action = match verb.to_lowercase().as_str() {
+        "add" => {
+            let (decl, default) = match rest.split_once(" default ") {
+                Some((decl, default)) => (decl, Some(default.trim().to_string())),
+                None => (rest, None),
+            };
+            let (name, type_text) = decl
+                .split_once(':')
+                .ok_or_else(|| REL_ALTER_USAGE.to_string())?;
+            let name = name.trim();
+            if name.is_empty() || default.as_deref() == Some("") {
+                return Err(REL_ALTER_USAGE.to_string());
+            }
+            let data_type = parse_type_expr(type_text.trim())?.to_schema_type();
+            RelAlterAction::Add {
+                column: crate::schema::ColumnSchema::new(name, data_type),
+                default,
+            }
+        }
+        "drop" => match next_word(rest) {
+            (column, "") => RelAlterAction::Drop(column.to_string()),
+            _ => return Err(REL_ALTER_USAGE.to_string()),
+        },
+        "widen" => {
+            let (column, rest) = next_word(rest);
+            let (to, type_text) = next_word(rest);
+            if !to.eq_ignore_ascii_case("to") || type_text.is_empty() {
+                return Err(REL_ALTER_USAGE.to_string());
+            }
+            RelAlterAction::Widen {
+                column: column.to_string(),
+                to: parse_type_expr(type_text)?.to_schema_type(),
+            }
+        }
+        "partition" => RelAlterAction::Partition(parse_partition_decl(rest)?),
+        "retain" => RelAlterAction::Retain(parse_retention(rest)?),
+        "shard" => RelAlterAction::Shard(parse_shard_decl(rest)?),
+        _ => return Err(REL_ALTER_USAGE.to_string()),
+    };
+
+    Ok(MetaCommand::RelAlter {
+        relation: relation.to_string(),
+        action,
+    })
+}
+
+/// Parse the part of `.rel alter <name> retain ...` after `retain`
+fn parse_retention(rest: &str) -> Result<Option<crate::schema::RetentionPolicy>, String> {
+    use crate::schema::RetentionPolicy;
+    let (amount, rest) = next_word(rest);
+    if amount.eq_ignore_ascii_case("none") && rest.is_empty() {
+        return Ok(None);
+    }
+    let (rows, rest) = match next_word(rest) {
+        (unit, rest) if unit.eq_ignore_ascii_case("rows") => (true, rest),
+        _ => (false, rest),
+    };
+    let column = match next_word(rest) {
+        ("", _) => None,
+        (by, rest) if by.eq_ignore_ascii_case("by") => match next_word(rest) {
+            (column, "") if !column.is_empty() => Some(column.to_string()),
+            _ => return Err(REL_ALTER_USAGE.to_string()),
+        },
+        _ => return Err(REL_ALTER_USAGE.to_string()),
+    };
+    let policy = if rows {
+        RetentionPolicy::MaxRows {
+            rows: amount.parse().map_err(|_| REL_ALTER_USAGE.to_string())?,
+            column,
+        }
+    } else {
+        RetentionPolicy::MaxAge {
+            max_age_ms: crate::temporal_ops::parse_duration(amount)
+                .ok_or_else(|| format!("Invalid retention duration '{amount}' (e.g. 30d, 12h)"))?,
+            column: column.ok_or_else(|| REL_ALTER_USAGE.to_string())?,
+        }
+    };
+    Ok(Some(policy))
+}
+
+/// Parse the part of `.rel alter <name> partition ...` after `partition`
+fn parse_partition_decl(rest: &str) -> Result<Option<PartitionDecl>, String> {
+    let (scheme, rest) = next_word(rest);
+    let (column, rest) = next_word(rest);
+    let decl = match (scheme.to_lowercase().as_str(), column) {
+        ("none", "") => return Ok(None),
+        ("hash", column) if !column.is_empty() => match next_word(rest) {
+            (count, "") => PartitionDecl::Hash {
+                column: column.to_string(),
+                partitions: count.parse().map_err(|_| REL_ALTER_USAGE.to_string())?,
+            },
+            _ => return Err(REL_ALTER_USAGE.to_string()),
+        },
+        ("range", column) if !column.is_empty() && !rest.is_empty() => {
+            let bounds: Vec<String> = rest.split(',').map(|b| b.trim().to_string()).collect();
+            if bounds.iter().any(String::is_empty) {
+                return Err(REL_ALTER_USAGE.to_string());
+            }
+            PartitionDecl::Range {
+                column: column.to_string(),
+                bounds,
+            }
+        }
+        _ => return Err(REL_ALTER_USAGE.to_string()),
+    };
+    Ok(Some(decl))
+}
+
+/// Parse the part of `.rel alter <name> shard ...` after `shard`
+fn parse_shard_decl(rest: &str) -> Result<Option<ShardDecl>, String> {
+    let (scheme, rest) = next_word(rest);
+    let (column, rest) = next_word(rest);
+    match (scheme.to_lowercase().as_str(), column, rest) {
+        ("none", "", "") => Ok(None),
+        ("broadcast", "", "") => Ok(Some(ShardDecl::Broadcast)),
+        ("hash", column, "") if !column.is_empty() => Ok(Some(ShardDecl::Hash {
+            column: column.to_string(),
+        })),
+        _ => Err(REL_ALTER_USAGE.to_string()),
+    }
+}
+
 fn parse_rule_command(parts: &[&str], input: &str) -> Result<MetaCommand, String> {
     if parts.len() == 1 {
         Ok(MetaCommand::RuleList)
@@ -483,12 +810,41 @@ fn parse_rule_command(parts: &[&str], input: &str) -> Result<MetaCommand, String
                 index: index - 1,
             }) // Convert to 0-based
         }
+    } else if parts[1].to_lowercase() == "refresh" {
+        parse_rule_refresh(parts)
     } else {
         // .rule <name> - query the rule and show computed results
         Ok(MetaCommand::RuleQuery(parts[1].to_string()))
     }
 }
 
+/// Parse `.rule refresh <name> [eager | lazy | every <duration> | none]`
+fn parse_rule_refresh(parts: &[&str]) -> Result<MetaCommand, String> {
+    use crate::rule_catalog::RefreshPolicy;
+    const USAGE: &str = "Usage: .rule refresh <name> [eager | lazy | every <duration> | none]";
+    let Some(name) = parts.get(2) else {
+        return Err(USAGE.to_string());
+    };
+    let name = (*name).to_string();
+    let policy = match parts[3..] {
+        [] => return Ok(MetaCommand::RuleRefresh(name)),
+        [mode] => match mode.to_lowercase().as_str() {
+            "eager" => Some(RefreshPolicy::Eager),
+            "lazy" => Some(RefreshPolicy::Lazy),
+            "none" => None,
+            _ => return Err(USAGE.to_string()),
+        },
+        [every, duration] if every.eq_ignore_ascii_case("every") => {
+            let interval_ms = crate::temporal_ops::parse_duration(duration)
+                .filter(|ms| *ms > 0)
+                .ok_or_else(|| format!("Invalid refresh interval '{duration}' (e.g. 30s, 5m)"))?;
+            Some(RefreshPolicy::Periodic { interval_ms })
+        }
+        _ => return Err(USAGE.to_string()),
+    };
+    Ok(MetaCommand::RuleSetRefresh { name, policy })
+}
+
 fn parse_session_command(parts: &[&str]) -> Result<MetaCommand, String> {
     if parts.len() == 1 {
         Ok(MetaCommand::SessionList)
@@ -507,8 +863,27 @@ fn parse_session_command(parts: &[&str]) -> Result<MetaCommand, String> {
                     Ok(MetaCommand::SessionDropName(parts[2].to_string()))
                 }
             }
+            "settings" => Ok(MetaCommand::SessionSettings),
+            "set" => match parts {
+                [_, _, name, value] if name.eq_ignore_ascii_case("priority") => {
+                    Ok(MetaCommand::SessionSetPriority(value.parse()?))
+                }
+                [_, _, name, value] => {
+                    let value = value
+                        .parse::<u64>()
+                        .map_err(|_| format!("Invalid value '{value}': expected an integer"))?;
+                    Ok(MetaCommand::SessionSet {
+                        name: name.to_lowercase(),
+                        value,
+                    })
+                }
+                _ => Err("Usage: .session set <name> <value>".to_string()),
+            },
+            "reset" => Ok(MetaCommand::SessionReset(
+                parts.get(2).map(|name| name.to_lowercase()),
+            )),
             _ => Err(format!(
-                "Unknown session subcommand: {}. Use: clear, drop <n|name>",
+                "Unknown session subcommand: {}. Use: clear, drop <n|name>, settings, set <name> <value>, reset [name]",
                 parts[1]
             )),
         }
@@ -539,6 +914,17 @@ fn parse_load_command(parts: &[&str]) -> Result<MetaCommand, String> {
     }
 }
 
+fn parse_log_command(parts: &[&str]) -> Result<MetaCommand, String> {
+    const USAGE: &str = "Usage: .log | .log level <directives> | .log reset";
+    match parts.get(1).map(|p| p.to_lowercase()).as_deref() {
+        None => Ok(MetaCommand::LogShow),
+        Some("reset") if parts.len() == 2 => Ok(MetaCommand::LogLevel(None)),
+        // Directives are comma-separated; spaces after commas are tolerated
+        Some("level") if parts.len() > 2 => Ok(MetaCommand::LogLevel(Some(parts[2..].concat()))),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
 fn parse_clear_command(parts: &[&str]) -> Result<MetaCommand, String> {
     if parts.len() < 3 {
         return Err("Usage: .clear prefix <prefix>".to_string());
@@ -595,7 +981,7 @@ fn parse_index_command(parts: &[&str], input: &str) -> Result<MetaCommand, Strin
     }
 }
 
-/// Parse `.index create <name> on <relation>(<column>) [type hnsw] [metric cosine] [m 16] [ef_construction 200] [ef_search 50]`
+/// Parse `.index create <name> on <relation>(<column>) [type hnsw|lsh] [metric cosine] [m 16] [ef_construction 200] [ef_search 50] [tables 4] [hyperplanes 8]`
 fn parse_index_create_command(input: &str) -> Result<MetaCommand, String> {
     // Extract the part after "index create"
     let input = input.trim_start_matches('.').trim();
@@ -644,6 +1030,8 @@ fn parse_index_create_command(input: &str) -> Result<MetaCommand, String> {
     let mut m = None;
     let mut ef_construction = None;
     let mut ef_search = None;
+    let mut tables = None;
+    let mut hyperplanes = None;
 
     let mut i = on_pos + 2;
     while i < tokens.len() {
@@ -699,9 +1087,33 @@ fn parse_index_create_command(input: &str) -> Result<MetaCommand, String> {
                 })?);
                 i += 2;
             }
+            "tables" => {
+                if i + 1 >= tokens.len() {
+                    return Err("Missing value for 'tables'".to_string());
+                }
+                tables = Some(tokens[i + 1].parse().map_err(|_| {
+                    format!(
+                        "Invalid value for 'tables': expected integer, got '{}'",
+                        tokens[i + 1]
+                    )
+                })?);
+                i += 2;
+            }
+            "hyperplanes" | "hp" => {
+                if i + 1 >= tokens.len() {
+                    return Err("Missing value for 'hyperplanes'".to_string());
+                }
+                hyperplanes = Some(tokens[i + 1].parse().map_err(|_| {
+                    format!(
+                        "Invalid value for 'hyperplanes': expected integer, got '{}'",
+                        tokens[i + 1]
+                    )
+                })?);
+                i += 2;
+            }
             _ => {
                 return Err(format!(
-                    "Unknown option: '{key}'. Valid options: type, metric, m, ef_construction, ef_search"
+                    "Unknown option: '{key}'. Valid options: type, metric, m, ef_construction, ef_search, tables, hyperplanes"
                 ));
             }
         }
@@ -716,6 +1128,8 @@ fn parse_index_create_command(input: &str) -> Result<MetaCommand, String> {
         m,
         ef_construction,
         ef_search,
+        tables,
+        hyperplanes,
     }))
 }
 
@@ -852,6 +1266,57 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_rule_refresh() {
+        use crate::rule_catalog::RefreshPolicy;
+        assert_eq!(
+            parse_meta_command(".rule refresh top").unwrap(),
+            MetaCommand::RuleRefresh("top".to_string())
+        );
+        assert_eq!(
+            parse_meta_command(".rule refresh top every 5m").unwrap(),
+            MetaCommand::RuleSetRefresh {
+                name: "top".to_string(),
+                policy: Some(RefreshPolicy::Periodic {
+                    interval_ms: 300_000
+                }),
+            }
+        );
+        assert_eq!(
+            parse_meta_command(".rule refresh top none").unwrap(),
+            MetaCommand::RuleSetRefresh {
+                name: "top".to_string(),
+                policy: None,
+            }
+        );
+        assert!(parse_meta_command(".rule refresh top sometimes").is_err());
+        assert!(parse_meta_command(".rule refresh top every 0s").is_err());
+    }
+
+    #[test]
+    fn test_parse_kg_copy_rename_cascade() {
+        assert_eq!(
+            parse_meta_command(".kg copy main backup").unwrap(),
+            MetaCommand::KgCopy {
+                source: "main".to_string(),
+                target: "backup".to_string(),
+            }
+        );
+        assert_eq!(
+            parse_meta_command(".kg rename old new").unwrap(),
+            MetaCommand::KgRename {
+                source: "old".to_string(),
+                target: "new".to_string(),
+            }
+        );
+        assert_eq!(
+            parse_meta_command(".kg drop shared CASCADE").unwrap(),
+            MetaCommand::KgDropCascade("shared".to_string())
+        );
+        assert!(parse_meta_command(".kg drop shared now").is_err());
+        assert!(parse_meta_command(".kg copy main").is_err());
+    }
+
     #[test]
     fn test_parse_kg_use() {
         let cmd = parse_meta_command(".kg use mykg").unwrap();
@@ -862,6 +1327,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_kg_restore() {
+        assert_eq!(
+            parse_meta_command(".kg restore lsn 42").unwrap(),
+            MetaCommand::KgRestore(RecoveryTarget::Lsn(42))
+        );
+        assert_eq!(
+            parse_meta_command(".kg restore time 2024-05-01T12:00:00Z").unwrap(),
+            MetaCommand::KgRestore(RecoveryTarget::Timestamp(1_714_564_800_000))
+        );
+        assert_eq!(
+            parse_meta_command(".kg restore time 1714564800000").unwrap(),
+            MetaCommand::KgRestore(RecoveryTarget::Timestamp(1_714_564_800_000))
+        );
+        assert!(parse_meta_command(".kg restore lsn x").is_err());
+        assert!(parse_meta_command(".kg restore").is_err());
+    }
     #[test]
     fn test_parse_kg_acl_list() {
         let cmd = parse_meta_command(".kg acl list").unwrap();
@@ -929,6 +1411,142 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_rel_alter() {
+        use crate::schema::{ColumnSchema, SchemaType};
+
+        let cmd = parse_meta_command(".rel alter users add score: int default 0").unwrap();
+        assert_eq!(
+            cmd,
+            MetaCommand::RelAlter {
+                relation: "users".to_string(),
+                action: RelAlterAction::Add {
+                    column: ColumnSchema::new("score", SchemaType::Int),
+                    default: Some("0".to_string()),
+                },
+            }
+        );
+
+        let cmd = parse_meta_command(".rel alter users add bio: string").unwrap();
+        assert!(matches!(
+            cmd,
+            MetaCommand::RelAlter {
+                action: RelAlterAction::Add { default: None, .. },
+                ..
+            }
+        ));
+
+        let cmd = parse_meta_command(".rel alter users drop bio").unwrap();
+        assert!(matches!(
+            cmd,
+            MetaCommand::RelAlter { action: RelAlterAction::Drop(ref c), .. } if c == "bio"
+        ));
+
+        let cmd = parse_meta_command(".rel alter users widen score to float").unwrap();
+        assert!(matches!(
+            cmd,
+            MetaCommand::RelAlter {
+                action: RelAlterAction::Widen {
+                    to: SchemaType::Float,
+                    ..
+                },
+                ..
+            }
+        ));
+
+        assert!(parse_meta_command(".rel alter users").is_err());
+        assert!(parse_meta_command(".rel alter users add score int").is_err());
+        assert!(parse_meta_command(".rel alter users widen score float").is_err());
+        assert!(parse_meta_command(".rel alter users rename a b").is_err());
+
+        let cmd = parse_meta_command(".rel alter events partition hash user 8").unwrap();
+        assert!(matches!(
+            cmd,
+            MetaCommand::RelAlter {
+                action: RelAlterAction::Partition(Some(PartitionDecl::Hash { partitions: 8, .. })),
+                ..
+            }
+        ));
+        let cmd = parse_meta_command(".rel alter events partition range day 10, 20").unwrap();
+        assert!(matches!(
+            cmd,
+            MetaCommand::RelAlter {
+                action: RelAlterAction::Partition(Some(PartitionDecl::Range { ref bounds, .. })),
+                ..
+            } if bounds == &["10", "20"]
+        ));
+        let cmd = parse_meta_command(".rel alter events partition none").unwrap();
+        assert!(matches!(
+            cmd,
+            MetaCommand::RelAlter {
+                action: RelAlterAction::Partition(None),
+                ..
+            }
+        ));
+        assert!(parse_meta_command(".rel alter events partition hash user").is_err());
+        assert!(parse_meta_command(".rel alter events partition range day 10,").is_err());
+
+        let cmd = parse_meta_command(".rel alter events retain 30d by ts").unwrap();
+        assert!(matches!(
+            cmd,
+            MetaCommand::RelAlter {
+                action: RelAlterAction::Retain(Some(crate::schema::RetentionPolicy::MaxAge {
+                    max_age_ms: 2_592_000_000,
+                    ..
+                })),
+                ..
+            }
+        ));
+        let cmd = parse_meta_command(".rel alter events retain 100 rows").unwrap();
+        assert!(matches!(
+            cmd,
+            MetaCommand::RelAlter {
+                action: RelAlterAction::Retain(Some(crate::schema::RetentionPolicy::MaxRows {
+                    rows: 100,
+                    column: None
+                })),
+                ..
+            }
+        ));
+        let cmd = parse_meta_command(".rel alter events retain none").unwrap();
+        assert!(matches!(
+            cmd,
+            MetaCommand::RelAlter {
+                action: RelAlterAction::Retain(None),
+                ..
+            }
+        ));
+        assert!(parse_meta_command(".rel alter events retain 30d").is_err());
+        assert!(parse_meta_command(".rel alter events retain 10 rows by").is_err());
+
+        let cmd = parse_meta_command(".rel alter events shard hash user").unwrap();
+        assert!(matches!(
+            cmd,
+            MetaCommand::RelAlter {
+                action: RelAlterAction::Shard(Some(ShardDecl::Hash { ref column })),
+                ..
+            } if column == "user"
+        ));
+        let cmd = parse_meta_command(".rel alter countries shard broadcast").unwrap();
+        assert!(matches!(
+            cmd,
+            MetaCommand::RelAlter {
+                action: RelAlterAction::Shard(Some(ShardDecl::Broadcast)),
+                ..
+            }
+        ));
+        let cmd = parse_meta_command(".rel alter events shard none").unwrap();
+        assert!(matches!(
+            cmd,
+            MetaCommand::RelAlter {
+                action: RelAlterAction::Shard(None),
+                ..
+            }
+        ));
+        assert!(parse_meta_command(".rel alter events shard hash").is_err());
+        assert!(parse_meta_command(".rel alter events shard hash user 4").is_err());
+    }
+
     #[test]
     fn test_parse_rule_list() {
         let cmd = parse_meta_command(".rule").unwrap();
@@ -979,6 +1597,58 @@ mod tests {
         assert!(matches!(cmd, MetaCommand::Compact));
     }
 
+    #[test]
+    fn test_parse_encryption_rotate() {
+        let cmd = parse_meta_command(".encryption rotate").unwrap();
+        assert!(matches!(cmd, MetaCommand::EncryptionRotate));
+        assert!(parse_meta_command(".encryption").is_err());
+        assert!(parse_meta_command(".encryption rotate now").is_err());
+    }
+
+    #[test]
+    fn test_parse_log() {
+        assert!(matches!(
+            parse_meta_command(".log").unwrap(),
+            MetaCommand::LogShow
+        ));
+        assert!(matches!(
+            parse_meta_command(".log reset").unwrap(),
+            MetaCommand::LogLevel(None)
+        ));
+        match parse_meta_command(".log level warn, inputlayer::storage=debug").unwrap() {
+            MetaCommand::LogLevel(Some(directives)) => {
+                assert_eq!(directives, "warn,inputlayer::storage=debug");
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+        assert!(parse_meta_command(".log level").is_err());
+        assert!(parse_meta_command(".log loud").is_err());
+    }
+
+    #[test]
+    fn test_parse_replication() {
+        assert!(matches!(
+            parse_meta_command(".replication").unwrap(),
+            MetaCommand::ReplicationStatus
+        ));
+        assert!(matches!(
+            parse_meta_command(".replication PROMOTE").unwrap(),
+            MetaCommand::ReplicationPromote
+        ));
+        assert!(parse_meta_command(".replication promote now").is_err());
+        assert!(parse_meta_command(".replication demote").is_err());
+        assert_eq!(
+            parse_meta_command(".cluster").unwrap(),
+            MetaCommand::ClusterStatus
+        );
+        assert!(parse_meta_command(".cluster join").is_err());
+        assert_eq!(
+            parse_meta_command(".shard").unwrap(),
+            MetaCommand::ShardStatus
+        );
+        assert!(parse_meta_command(".shard add").is_err());
+    }
+
     #[test]
     fn test_parse_status() {
         let cmd = parse_meta_command(".status").unwrap();
@@ -1130,6 +1800,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_index_create_lsh() {
+        let cmd =
+            parse_meta_command(".index create doc_lsh on docs(embedding) type lsh tables 2 hp 6")
+                .unwrap();
+        if let MetaCommand::IndexCreate(opts) = cmd {
+            assert_eq!(opts.index_type, "lsh");
+            assert_eq!(opts.tables, Some(2));
+            assert_eq!(opts.hyperplanes, Some(6));
+        } else {
+            panic!("Expected IndexCreate");
+        }
+    }
+
     #[test]
     fn test_parse_index_create_missing_on() {
         let result = parse_meta_command(".index create my_idx embeddings(vector)");
@@ -1215,6 +1899,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_session_settings() {
+        let cmd = parse_meta_command(".session set timeout_ms 5000").unwrap();
+        assert!(
+            matches!(cmd, MetaCommand::SessionSet { ref name, value: 5000 } if name == "timeout_ms")
+        );
+        assert!(parse_meta_command(".session set timeout_ms soon").is_err());
+        assert!(parse_meta_command(".session set timeout_ms").is_err());
+        assert!(matches!(
+            parse_meta_command(".session set priority Interactive").unwrap(),
+            MetaCommand::SessionSetPriority(Priority::Interactive)
+        ));
+        assert!(parse_meta_command(".session set priority urgent").is_err());
+        assert!(matches!(
+            parse_meta_command(".session reset").unwrap(),
+            MetaCommand::SessionReset(None)
+        ));
+        assert!(matches!(
+            parse_meta_command(".session settings").unwrap(),
+            MetaCommand::SessionSettings
+        ));
+    }
+
     #[test]
     fn test_parse_session_drop_missing_arg() {
         let result = parse_meta_command(".session drop");
@@ -1,21 +1,26 @@
 //! Statement Parser for IQL-Native Syntax
 //!
 //! Parses meta commands (`.kg`, `.rel`, `.rule`, `.help`, etc.), data ops (`+`/`-`),
-//! type/schema declarations, rules (persistent `+` and session), and queries (`?-`).
+//! type/schema declarations, rules (persistent `+` and session), queries (`?-`),
+//! and prepared statements (`prepare`, `execute`, `deallocate`).
 
 // Submodules
 pub mod data;
 pub mod meta;
 pub mod parser;
+pub mod prepared;
 pub mod schema;
 pub mod serialize;
 pub mod types;
 
 // Re-exports
 pub use data::{DeleteOp, DeletePattern, DeleteTarget, InsertOp, InsertTarget, UpdateOp};
-pub use meta::{IndexCreateOptions, LoadMode, MetaCommand};
+pub use meta::{
+    IndexCreateOptions, LoadMode, MetaCommand, PartitionDecl, RelAlterAction, ShardDecl,
+};
 pub use parser::{parse_query, parse_transient_rule, QueryGoal, SortDirection};
-pub use schema::{ColumnDef, SchemaDecl};
+pub use prepared::{ExecuteStatement, PreparedStatement};
+pub use schema::{ColumnAnnotation, ColumnDef, SchemaDecl};
 pub use serialize::{
     RuleDef, SerializableArithExpr, SerializableArithOp, SerializableBodyPred, SerializableRule,
     SerializableTerm,
@@ -23,6 +28,8 @@ pub use serialize::{
 pub use types::{BaseType, RecordField, Refinement, RefinementArg, TypeDecl, TypeExpr};
 
 use crate::ast::Rule;
+use crate::auth::Privilege;
+use crate::storage_engine::{CopyFormat, CopyOptions};
 
 // Statement Types
 /// Top-level statement parsed from user input
@@ -50,6 +57,106 @@ pub enum Statement {
     PersistentRule(Rule),
     /// Delete relation or rule: -name.
     DeleteRelationOrRule(String),
+    /// Collect planner statistics: analyze [relation].
+    Analyze(Option<String>),
+    /// Introspection: show relations | show views | show databases | show queries.
+    Show(ShowTarget),
+    /// Introspection: describe relation.
+    Describe(String),
+    /// Transaction control: begin | commit | rollback.
+    Transaction(TransactionControl),
+    /// Copy between a relation and a file: copy relation from|to 'path' (options).
+    Copy(CopyStatement),
+    /// Grant a privilege: grant read on relation edge to alice.
+    Grant(GrantStatement),
+    /// Revoke a privilege: revoke write on view path from alice.
+    Revoke(GrantStatement),
+    /// Prepare a statement with parameters: prepare q1 as ?edge($1, Y).
+    Prepare(PreparedStatement),
+    /// Run a prepared statement: execute q1 42.
+    Execute(ExecuteStatement),
+    /// Forget a prepared statement: deallocate q1.
+    Deallocate(String),
+}
+
+/// What a `show` statement lists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShowTarget {
+    Relations,
+    Views,
+    Databases,
+    /// Queued, running and recently finished queries
+    Queries,
+}
+
+/// Transaction control statement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionControl {
+    /// Start queuing writes and rule changes
+    Begin,
+    /// Apply everything queued atomically
+    Commit,
+    /// Discard everything queued
+    Rollback,
+}
+
+/// Direction of a `copy` statement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyDirection {
+    /// Insert the rows of the file into the relation
+    From,
+    /// Write the relation to the file
+    To,
+}
+
+/// Copy statement: `copy edge from 'edges.parquet'` or
+/// `copy path to 'out.csv' (format csv, header true)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CopyStatement {
+    pub relation: String,
+    pub direction: CopyDirection,
+    pub path: String,
+    pub options: CopyOptions,
+}
+
+/// Grant or revoke statement: `grant read on relation edge to alice`,
+/// `revoke admin on database sales from bob`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrantStatement {
+    pub privilege: Privilege,
+    pub object: GrantObject,
+    pub username: String,
+}
+
+/// What a grant applies to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GrantObject {
+    /// A knowledge graph, as a `.kg acl` entry
+    Database(String),
+    /// A base relation in the current knowledge graph
+    Relation(String),
+    /// A rule (derived view) in the current knowledge graph
+    View(String),
+}
+
+impl GrantObject {
+    /// Name of the knowledge graph, relation or view
+    pub fn name(&self) -> &str {
+        match self {
+            GrantObject::Database(name) | GrantObject::Relation(name) | GrantObject::View(name) => {
+                name
+            }
+        }
+    }
+
+    /// Kind keyword as written in the statement
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GrantObject::Database(_) => "database",
+            GrantObject::Relation(_) => "relation",
+            GrantObject::View(_) => "view",
+        }
+    }
 }
 
 // Statement Parser
@@ -84,6 +191,61 @@ pub fn parse_statement(input: &str) -> Result<Statement, String> {
         return types::parse_type_decl(input).map(Statement::TypeDecl);
     }
 
+    // Statistics collection: analyze [relation]
+    if input == "analyze" || input.starts_with("analyze ") || input.starts_with("analyze.") {
+        return parse_analyze(input);
+    }
+
+    // Transaction control: begin | commit | rollback
+    match input.strip_suffix('.').unwrap_or(input).trim_end() {
+        "begin" => return Ok(Statement::Transaction(TransactionControl::Begin)),
+        "commit" => return Ok(Statement::Transaction(TransactionControl::Commit)),
+        "rollback" => return Ok(Statement::Transaction(TransactionControl::Rollback)),
+        _ => {}
+    }
+
+    // Introspection: show relations | views | databases | queries, describe relation
+    if let Some(rest) = keyword_argument(input, "show") {
+        return match rest {
+            "relations" => Ok(Statement::Show(ShowTarget::Relations)),
+            "views" | "rules" => Ok(Statement::Show(ShowTarget::Views)),
+            "databases" | "knowledge graphs" => Ok(Statement::Show(ShowTarget::Databases)),
+            "queries" => Ok(Statement::Show(ShowTarget::Queries)),
+            _ => Err(format!(
+                "Unknown show target '{rest}'. Expected relations, views, databases or queries"
+            )),
+        };
+    }
+    if let Some(rest) = keyword_argument(input, "describe") {
+        validate_relation_name(rest)?;
+        return Ok(Statement::Describe(rest.to_string()));
+    }
+
+    // Copy between a relation and a file: copy relation from|to 'path' (options)
+    if let Some(rest) = keyword_argument(input, "copy") {
+        return parse_copy(rest).map(Statement::Copy);
+    }
+
+    // Access control: grant <privilege> on <kind> <name> to <user>, revoke ... from <user>
+    if let Some(rest) = keyword_argument(input, "grant") {
+        return parse_grant(rest, "to").map(Statement::Grant);
+    }
+    if let Some(rest) = keyword_argument(input, "revoke") {
+        return parse_grant(rest, "from").map(Statement::Revoke);
+    }
+
+    // Prepared statements: prepare <name> as <statement>, execute <name> args, deallocate <name>
+    if let Some(rest) = keyword_argument(input, "prepare") {
+        return prepared::parse_prepare(rest).map(Statement::Prepare);
+    }
+    if let Some(rest) = keyword_argument(input, "execute") {
+        return prepared::parse_execute(rest).map(Statement::Execute);
+    }
+    if let Some(rest) = keyword_argument(input, "deallocate") {
+        prepared::validate_statement_name(rest)?;
+        return Ok(Statement::Deallocate(rest.to_string()));
+    }
+
     // Check for update pattern: -rel(...), +rel(...) <- body.
     // This must be checked before simple +/- to handle atomic updates
     if input.starts_with('-') || input.starts_with('+') {
@@ -152,6 +314,161 @@ pub fn parse_statement(input: &str) -> Result<Statement, String> {
     Err(format!("Unrecognized statement: {input}"))
 }
 
+/// Parse `analyze` or `analyze relation` (a trailing '.' is allowed)
+fn parse_analyze(input: &str) -> Result<Statement, String> {
+    let rest = input["analyze".len()..].trim();
+    let rest = rest.strip_suffix('.').unwrap_or(rest).trim();
+    if rest.is_empty() {
+        return Ok(Statement::Analyze(None));
+    }
+    validate_relation_name(rest)?;
+    Ok(Statement::Analyze(Some(rest.to_string())))
+}
+
+/// Parse the part of a `copy` statement after the keyword:
+/// `relation from|to 'path' [(option value, ...)]`
+fn parse_copy(input: &str) -> Result<CopyStatement, String> {
+    const USAGE: &str = "Usage: copy <relation> from|to '<path>' [(format csv|parquet|jsonl|avro, header true|false, delimiter ',')]";
+    let (relation, rest) = input.split_once(char::is_whitespace).ok_or(USAGE)?;
+    validate_relation_name(relation)?;
+    let rest = rest.trim_start();
+    let (direction, rest) = if let Some(rest) = rest.strip_prefix("from ") {
+        (CopyDirection::From, rest)
+    } else if let Some(rest) = rest.strip_prefix("to ") {
+        (CopyDirection::To, rest)
+    } else {
+        return Err(USAGE.to_string());
+    };
+    let (path, rest) = quoted(rest.trim_start()).ok_or(USAGE)?;
+    if path.is_empty() {
+        return Err("Copy path cannot be empty".to_string());
+    }
+
+    let mut options = CopyOptions::default();
+    let rest = rest.trim();
+    if !rest.is_empty() {
+        let list = rest
+            .strip_prefix('(')
+            .and_then(|r| r.strip_suffix(')'))
+            .ok_or(USAGE)?;
+        let mut remaining = list.trim();
+        while !remaining.is_empty() {
+            let (key, value) = remaining
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| format!("Copy option '{remaining}' needs a value"))?;
+            let value = value.trim_start();
+            let (value, after) = quoted(value).unwrap_or_else(|| {
+                let (v, a) = value.split_once(',').unwrap_or((value, ""));
+                (v.to_string(), a)
+            });
+            let value = value.trim();
+            match key.to_ascii_lowercase().as_str() {
+                "format" => {
+                    options.format = Some(CopyFormat::from_name(value).ok_or_else(|| {
+                        format!(
+                            "Unknown copy format '{value}'. Expected csv, parquet, jsonl or avro"
+                        )
+                    })?);
+                }
+                "header" => {
+                    options.header = match value {
+                        "true" => true,
+                        "false" => false,
+                        _ => {
+                            return Err(format!(
+                                "Copy option header must be true or false, got '{value}'"
+                            ))
+                        }
+                    };
+                }
+                "delimiter" => {
+                    let value = if value == "\\t" { "\t" } else { value };
+                    let mut chars = value.chars();
+                    options.delimiter = match (chars.next(), chars.next()) {
+                        (Some(c), None) => c,
+                        _ => {
+                            return Err(format!(
+                                "Copy delimiter must be one character, got '{value}'"
+                            ))
+                        }
+                    };
+                }
+                _ => {
+                    return Err(format!(
+                        "Unknown copy option '{key}'. Expected format, header or delimiter"
+                    ))
+                }
+            }
+            let after = after.trim_start();
+            remaining = after.strip_prefix(',').unwrap_or(after).trim_start();
+        }
+    }
+
+    Ok(CopyStatement {
+        relation: relation.to_string(),
+        direction,
+        path,
+        options,
+    })
+}
+
+/// Parse the part of a `grant` or `revoke` statement after the keyword:
+/// `privilege on kind name to|from user`
+fn parse_grant(input: &str, preposition: &str) -> Result<GrantStatement, String> {
+    let keyword = if preposition == "to" {
+        "grant"
+    } else {
+        "revoke"
+    };
+    let usage = format!(
+        "Usage: {keyword} read|write|admin on database|relation|view <name> {preposition} <user>"
+    );
+    let parts: Vec<&str> = input.split_whitespace().collect();
+    let [privilege, on, kind, name, prep, username] = parts.as_slice() else {
+        return Err(usage);
+    };
+    if !on.eq_ignore_ascii_case("on") || !prep.eq_ignore_ascii_case(preposition) {
+        return Err(usage);
+    }
+    let privilege: Privilege = privilege.parse()?;
+    let object = match kind.to_ascii_lowercase().as_str() {
+        "database" | "kg" => GrantObject::Database((*name).to_string()),
+        "relation" => {
+            validate_relation_name(name)?;
+            GrantObject::Relation((*name).to_string())
+        }
+        "view" | "rule" => {
+            validate_relation_name(name)?;
+            GrantObject::View((*name).to_string())
+        }
+        _ => {
+            return Err(format!(
+                "Unknown grant target '{kind}'. Expected database, relation or view"
+            ))
+        }
+    };
+    Ok(GrantStatement {
+        privilege,
+        object,
+        username: (*username).to_string(),
+    })
+}
+
+/// A string in single or double quotes at the start of `input`, and the
+/// text after it
+fn quoted(input: &str) -> Option<(String, &str)> {
+    let quote = input.chars().next().filter(|c| *c == '\'' || *c == '"')?;
+    let end = input[1..].find(quote)? + 1;
+    Some((input[1..end].to_string(), &input[end + 1..]))
+}
+
+/// Argument of a `keyword argument` statement, without a trailing '.'.
+/// Returns None if `input` does not start with the keyword and a space.
+fn keyword_argument<'a>(input: &'a str, keyword: &str) -> Option<&'a str> {
+    let rest = input.strip_prefix(keyword)?.strip_prefix(' ')?.trim();
+    Some(rest.strip_suffix('.').unwrap_or(rest).trim())
+}
+
 // Re-export parse_rule_definition for convenience
 pub use parser::parse_rule_definition;
 
@@ -162,6 +479,182 @@ mod tests {
     use super::*;
     use crate::ast::{BodyPredicate, Term};
 
+    #[test]
+    fn test_parse_analyze() {
+        assert!(matches!(
+            parse_statement("analyze").unwrap(),
+            Statement::Analyze(None)
+        ));
+        assert!(matches!(
+            parse_statement("analyze edge.").unwrap(),
+            Statement::Analyze(Some(name)) if name == "edge"
+        ));
+        assert!(parse_statement("analyze edge(X)").is_err());
+        // A relation named analyze is still a fact
+        assert!(matches!(
+            parse_statement("analyze(1)").unwrap(),
+            Statement::Fact(_)
+        ));
+    }
+
+    #[test]
+    fn test_parse_show_and_describe() {
+        assert!(matches!(
+            parse_statement("show relations").unwrap(),
+            Statement::Show(ShowTarget::Relations)
+        ));
+        assert!(matches!(
+            parse_statement("show views.").unwrap(),
+            Statement::Show(ShowTarget::Views)
+        ));
+        assert!(matches!(
+            parse_statement("show databases").unwrap(),
+            Statement::Show(ShowTarget::Databases)
+        ));
+        assert!(matches!(
+            parse_statement("show queries").unwrap(),
+            Statement::Show(ShowTarget::Queries)
+        ));
+        assert!(parse_statement("show tables").is_err());
+        assert!(matches!(
+            parse_statement("describe employee").unwrap(),
+            Statement::Describe(name) if name == "employee"
+        ));
+        assert!(parse_statement("describe").is_err());
+        assert!(matches!(
+            parse_statement("show(1)").unwrap(),
+            Statement::Fact(_)
+        ));
+    }
+
+    #[test]
+    fn test_parse_grant_and_revoke() {
+        let Statement::Grant(grant) =
+            parse_statement("grant read on relation edge to alice.").unwrap()
+        else {
+            panic!("expected a grant");
+        };
+        assert_eq!(grant.privilege, Privilege::Read);
+        assert_eq!(grant.object, GrantObject::Relation("edge".to_string()));
+        assert_eq!(grant.username, "alice");
+
+        assert!(matches!(
+            parse_statement("revoke admin on database sales from bob").unwrap(),
+            Statement::Revoke(GrantStatement {
+                privilege: Privilege::Admin,
+                object: GrantObject::Database(ref name),
+                ..
+            }) if name == "sales"
+        ));
+        assert!(matches!(
+            parse_statement("grant write on view path to carol").unwrap(),
+            Statement::Grant(GrantStatement {
+                object: GrantObject::View(_),
+                ..
+            })
+        ));
+        assert!(parse_statement("grant read on relation edge from alice").is_err());
+        assert!(parse_statement("grant drop on relation edge to alice").is_err());
+        assert!(parse_statement("grant read on table edge to alice").is_err());
+        assert!(parse_statement("revoke read on relation Edge from alice").is_err());
+        // A relation named grant is still a fact
+        assert!(matches!(
+            parse_statement("grant(1)").unwrap(),
+            Statement::Fact(_)
+        ));
+    }
+
+    #[test]
+    fn test_parse_prepared_statements() {
+        let Statement::Prepare(prepared) =
+            parse_statement("prepare near as ?doc(D, V), Dist = euclidean(V, $1)").unwrap()
+        else {
+            panic!("expected prepare");
+        };
+        assert_eq!(prepared.name, "near");
+        assert_eq!(prepared.params, 1);
+
+        let Statement::Execute(exec) = parse_statement("execute near [0.1, 0.2].").unwrap() else {
+            panic!("expected execute");
+        };
+        assert_eq!(exec.name, "near");
+        assert!(matches!(&exec.args[..], [Term::VectorLiteral(v)] if v.len() == 2));
+
+        assert!(matches!(
+            parse_statement("deallocate near").unwrap(),
+            Statement::Deallocate(name) if name == "near"
+        ));
+        assert!(parse_statement("prepare near ?edge(X, Y)").is_err());
+        // Relations with these names are still facts
+        assert!(matches!(
+            parse_statement("execute(1)").unwrap(),
+            Statement::Fact(_)
+        ));
+    }
+
+    #[test]
+    fn test_parse_transaction_control() {
+        assert!(matches!(
+            parse_statement("begin").unwrap(),
+            Statement::Transaction(TransactionControl::Begin)
+        ));
+        assert!(matches!(
+            parse_statement("commit.").unwrap(),
+            Statement::Transaction(TransactionControl::Commit)
+        ));
+        assert!(matches!(
+            parse_statement("rollback").unwrap(),
+            Statement::Transaction(TransactionControl::Rollback)
+        ));
+        // Relations with these names are still facts
+        assert!(matches!(
+            parse_statement("commit(1)").unwrap(),
+            Statement::Fact(_)
+        ));
+    }
+
+    #[test]
+    fn test_parse_copy() {
+        let Statement::Copy(copy) = parse_statement("copy edge from 'edges.parquet'").unwrap()
+        else {
+            panic!("Expected Copy");
+        };
+        assert_eq!(copy.relation, "edge");
+        assert_eq!(copy.direction, CopyDirection::From);
+        assert_eq!(copy.path, "edges.parquet");
+        assert_eq!(copy.options, CopyOptions::default());
+
+        let Statement::Copy(copy) = parse_statement(
+            "copy path to \"out data.csv\" (format csv, header false, delimiter ';').",
+        )
+        .unwrap() else {
+            panic!("Expected Copy");
+        };
+        assert_eq!(copy.direction, CopyDirection::To);
+        assert_eq!(copy.path, "out data.csv");
+        assert_eq!(copy.options.format, Some(CopyFormat::Csv));
+        assert!(!copy.options.header);
+        assert_eq!(copy.options.delimiter, ';');
+
+        let Statement::Copy(copy) =
+            parse_statement("copy edge to 'edges.txt' (delimiter ',', format csv)").unwrap()
+        else {
+            panic!("Expected Copy");
+        };
+        assert_eq!(copy.options.delimiter, ',');
+        assert_eq!(copy.options.format, Some(CopyFormat::Csv));
+
+        assert!(parse_statement("copy edge into 'edges.csv'").is_err());
+        assert!(parse_statement("copy edge from edges.csv").is_err());
+        assert!(parse_statement("copy edge from 'edges.csv' (format xml)").is_err());
+        assert!(parse_statement("copy edge from 'edges.csv' (compression zstd)").is_err());
+        // A relation named copy is still a fact
+        assert!(matches!(
+            parse_statement("copy(1)").unwrap(),
+            Statement::Fact(_)
+        ));
+    }
+
     // Insert tests
     #[test]
     fn test_parse_single_insert() {
@@ -3,12 +3,7 @@
 use crate::ast::{AggregateFunc, Atom, BodyPredicate, Rule, Term};
 use crate::parser::{parse_rule, parse_term};
 
-/// Sort direction for query result ordering.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum SortDirection {
-    Asc,
-    Desc,
-}
+pub use crate::ir::SortDirection;
 
 /// Query goal: ?- atom.
 #[derive(Debug, Clone)]
@@ -112,14 +107,17 @@ pub fn has_typed_arguments(args_content: &str) -> bool {
                     continue;
                 }
 
-                // After should start with a type name (int, string, bool, float, or TypeRef)
+                // After should start with a type name (int, string, ..., or TypeRef)
                 if after.is_empty() {
                     continue;
                 }
 
                 // Check if it starts with a known type or looks like a type identifier
                 let type_part = after.split_whitespace().next().unwrap_or("");
-                let base_types = ["int", "string", "bool", "float", "list"];
+                let base_types = [
+                    "int", "string", "bool", "float", "date", "duration", "uuid", "list", "map",
+                    "json", "byte", "blob", "binary",
+                ];
                 if base_types.iter().any(|t| type_part.starts_with(t))
                     || type_part.chars().next().is_some_and(char::is_uppercase)
                 {
@@ -440,8 +438,9 @@ pub fn term_to_string(term: &Term) -> String {
 /// Parse a query: ?- goal.
 ///
 /// Supports `:asc`/`:desc` annotations on variables in the goal atom, e.g.
-/// `?relation(X, Score:desc)`. Annotations are stripped before parsing and
-/// recorded as `order_by` on the returned `QueryGoal`.
+/// `?relation(X, Score:desc)`, and `order_by(X, asc|desc)` clauses in the
+/// body, e.g. `?relation(X, Score), order_by(Score, desc)`. Both are recorded,
+/// annotations first, as `order_by` on the returned `QueryGoal`.
 pub fn parse_query(input: &str) -> Result<QueryGoal, String> {
     let input = input.trim();
 
@@ -457,7 +456,9 @@ pub fn parse_query(input: &str) -> Result<QueryGoal, String> {
 
     // Extract :asc/:desc annotations from the goal atom arguments
     // before passing to the parser (which doesn't understand them).
-    let (cleaned_input, order_by) = strip_sort_annotations(input);
+    let (cleaned_input, mut order_by) = strip_sort_annotations(input);
+    let (cleaned_input, order_clauses) = strip_order_by_clauses(&cleaned_input)?;
+    order_by.extend(order_clauses);
 
     // Try to parse as a simple rule body
     let dummy_rule_str = format!("__query__(X) <- {cleaned_input}");
@@ -526,6 +527,53 @@ pub fn parse_query(input: &str) -> Result<QueryGoal, String> {
     })
 }
 
+/// Remove top-level `order_by(X)` / `order_by(X, asc|desc)` clauses.
+///
+/// Directions are bare words, which the rule parser rejects as unquoted
+/// atoms, so the clauses are extracted before parsing.
+fn strip_order_by_clauses(input: &str) -> Result<(String, Vec<(String, SortDirection)>), String> {
+    if !input.contains("order_by(") {
+        return Ok((input.to_string(), vec![]));
+    }
+
+    let mut order_by = Vec::new();
+    let mut kept = Vec::new();
+    for part in split_top_level(input, ',') {
+        let clause = part
+            .trim()
+            .strip_prefix("order_by(")
+            .and_then(|rest| rest.strip_suffix(')'));
+        match clause {
+            Some(args) => order_by.push(parse_order_by(args)?),
+            None => kept.push(part),
+        }
+    }
+    Ok((kept.join(","), order_by))
+}
+
+/// Parse the arguments of an `order_by(...)` clause.
+fn parse_order_by(args: &str) -> Result<(String, SortDirection), String> {
+    let usage = || {
+        format!(
+            "Invalid order_by({args}): expected order_by(X), order_by(X, asc) or order_by(X, desc)"
+        )
+    };
+    let args: Vec<&str> = args.split(',').map(str::trim).collect();
+    let (var, direction) = match args.as_slice() {
+        [var] => (*var, SortDirection::Asc),
+        [var, dir] => match dir.to_ascii_lowercase().as_str() {
+            "asc" => (*var, SortDirection::Asc),
+            "desc" => (*var, SortDirection::Desc),
+            _ => return Err(usage()),
+        },
+        _ => return Err(usage()),
+    };
+    if !var.starts_with(|c: char| c.is_uppercase() || c == '_') {
+        return Err(usage());
+    }
+    Ok((var.to_string(), direction))
+}
+
 /// Strip `:asc`/`:desc` annotations from the first atom's arguments.
 ///
 /// Given `rel(X, Score:desc, Name:asc), cond(X)`, returns
@@ -606,13 +654,27 @@ fn split_top_level(s: &str, delim: char) -> Vec<&str> {
 
 /// Parse a transient rule: head <- body.
 pub fn parse_transient_rule(input: &str) -> Result<Rule, String> {
-    parse_rule(input.trim())
+    parse_local_rule(input.trim())
 }
 
 /// Parse a persistent rule: +name(...) <- body.
 pub fn parse_persistent_rule(input: &str) -> Result<Rule, String> {
     let input = input.trim();
-    parse_rule(input)
+    parse_local_rule(input)
+}
+
+/// Parse a rule whose head is a relation of the current knowledge graph.
+/// Qualified names (`analytics.edge`) may only appear in the body: other
+/// knowledge graphs are read-only.
+fn parse_local_rule(input: &str) -> Result<Rule, String> {
+    let rule = parse_rule(input)?;
+    if rule.head.relation.contains('.') {
+        return Err(format!(
+            "Cannot define '{}': relations of other knowledge graphs are read-only",
+            rule.head.relation
+        ));
+    }
+    Ok(rule)
 }
 
 /// Parse a rule definition: head <- body.
@@ -621,7 +683,7 @@ pub fn parse_rule_definition(input: &str) -> Result<super::serialize::RuleDef, S
 
     let input = input.trim();
 
-    let rule = parse_rule(input)?;
+    let rule = parse_local_rule(input)?;
 
     Ok(RuleDef {
         name: rule.head.relation.clone(),
@@ -991,6 +1053,13 @@ mod tests {
         assert_eq!(result.head.relation, "path");
     }
 
+    #[test]
+    fn test_parse_rule_with_qualified_relation() {
+        let result = parse_persistent_rule("both(X) <- edge(X, _), analytics.edge(X, _)").unwrap();
+        assert_eq!(result.body[1].atom().unwrap().relation, "analytics.edge");
+        assert!(parse_transient_rule("analytics.path(X) <- edge(X, _)").is_err());
+    }
+
     // === parse_rule_definition ===
 
     #[test]
@@ -1051,6 +1120,30 @@ mod tests {
         assert!(parse_aggregate("count<x>").is_none());
     }
 
+    // === parse_query order_by ===
+
+    #[test]
+    fn test_parse_query_with_order_by_clauses() {
+        let result = parse_query("data(X, Score:desc), order_by(X), order_by(Y, DESC)").unwrap();
+        assert_eq!(
+            result.order_by,
+            vec![
+                ("Score".to_string(), SortDirection::Desc),
+                ("X".to_string(), SortDirection::Asc),
+                ("Y".to_string(), SortDirection::Desc),
+            ]
+        );
+        // order_by clauses should NOT appear in body
+        assert!(result.body.is_empty());
+    }
+
+    #[test]
+    fn test_parse_query_order_by_invalid_direction_rejected() {
+        let err = parse_query("data(X), order_by(X, sideways)").unwrap_err();
+        assert!(err.contains("order_by"), "{err}");
+        assert!(parse_query("data(X), order_by(x)").is_err());
+    }
+
     // === parse_query limit/offset ===
 
     #[test]
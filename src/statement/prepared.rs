@@ -0,0 +1,302 @@
+//! Prepared statements.
+//!
+//! - `prepare name as statement` - parse and keep a statement with `$1`, `$2`, ... parameters
+//! - `execute name arg1, arg2, ...` - run it with the parameters bound to literals
+//! - `deallocate name` - forget it
+//!
+//! The statement is checked when it is prepared. Arguments are parsed as single
+//! literal terms and written back in canonical form, so an argument can never
+//! change the shape of the statement it is bound into.
+
+use crate::ast::Term;
+
+use super::parser::{parse_single_term, split_by_comma};
+use super::Statement;
+
+/// Prepared statements kept per session (or per program without one)
+pub const MAX_PREPARED_STATEMENTS: usize = 256;
+
+/// A statement with numbered parameters, ready to be bound
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreparedStatement {
+    /// Name given in `prepare`
+    pub name: String,
+    /// Statement text as written, with `$n` parameters
+    pub text: String,
+    /// Number of parameters (`$1` through `$n`)
+    pub params: usize,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Text(String),
+    /// Zero-based parameter index
+    Param(usize),
+}
+
+/// `execute name args`: run a prepared statement
+#[derive(Debug, Clone)]
+pub struct ExecuteStatement {
+    pub name: String,
+    pub args: Vec<Term>,
+}
+
+impl PreparedStatement {
+    /// Parse the statement text of `prepare`, checking that it is a valid
+    /// statement once its parameters are bound
+    pub fn parse(name: &str, text: &str) -> Result<Self, String> {
+        validate_statement_name(name)?;
+        let text = text.trim();
+        let text = text.strip_suffix('.').unwrap_or(text).trim_end();
+        if text.is_empty() {
+            return Err(format!("Prepared statement '{name}' has no statement"));
+        }
+
+        // Checked before parsing, which would otherwise recurse through
+        // `prepare a as prepare b as ...`
+        if matches!(
+            text.split_whitespace().next(),
+            Some("prepare" | "execute" | "deallocate")
+        ) {
+            return Err("Prepared statements cannot prepare or execute others".to_string());
+        }
+
+        let segments = split_parameters(text)?;
+        let params = segments
+            .iter()
+            .filter_map(|segment| match segment {
+                Segment::Param(index) => Some(index + 1),
+                Segment::Text(_) => None,
+            })
+            .max()
+            .unwrap_or(0);
+        for index in 0..params {
+            if !segments.contains(&Segment::Param(index)) {
+                return Err(format!(
+                    "Parameter ${} of '{name}' is never used (parameters are numbered from $1)",
+                    index + 1
+                ));
+            }
+        }
+
+        let prepared = Self {
+            name: name.to_string(),
+            text: text.to_string(),
+            params,
+            segments,
+        };
+        // Any literal will do to check the statement's shape
+        let probe = prepared.bind_text(&vec!["0".to_string(); params]);
+        match super::parse_statement(&probe) {
+            Ok(Statement::Meta(_)) => Err("Meta commands cannot be prepared".to_string()),
+            Ok(_) => Ok(prepared),
+            Err(e) => Err(format!("Cannot prepare '{name}': {e}")),
+        }
+    }
+
+    /// Statement text with every parameter replaced by its argument
+    pub fn bind(&self, args: &[Term]) -> Result<String, String> {
+        if args.len() != self.params {
+            return Err(format!(
+                "Prepared statement '{}' takes {} parameter(s), got {}",
+                self.name,
+                self.params,
+                args.len()
+            ));
+        }
+        let literals = args
+            .iter()
+            .enumerate()
+            .map(|(index, term)| {
+                literal_text(term).map_err(|e| format!("Parameter ${}: {e}", index + 1))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(self.bind_text(&literals))
+    }
+
+    fn bind_text(&self, literals: &[String]) -> String {
+        let mut bound = String::with_capacity(self.text.len());
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(text) => bound.push_str(text),
+                Segment::Param(index) => bound.push_str(&literals[*index]),
+            }
+        }
+        bound
+    }
+}
+
+/// Parse the part of a `prepare` statement after the keyword: `name as statement`
+pub fn parse_prepare(input: &str) -> Result<PreparedStatement, String> {
+    const USAGE: &str = "Usage: prepare <name> as <statement>";
+    let (name, rest) = input.split_once(char::is_whitespace).ok_or(USAGE)?;
+    let rest = rest.trim_start();
+    let text = rest
+        .strip_prefix("as ")
+        .or_else(|| rest.strip_prefix("AS "))
+        .ok_or(USAGE)?;
+    PreparedStatement::parse(name, text)
+}
+
+/// Parse the part of an `execute` statement after the keyword:
+/// `name`, `name arg, ...` or `name (arg, ...)`
+pub fn parse_execute(input: &str) -> Result<ExecuteStatement, String> {
+    let (name, rest) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+    validate_statement_name(name)?;
+    let rest = rest.trim();
+    let rest = match rest.strip_prefix('(').and_then(|r| r.strip_suffix(')')) {
+        // `(a), (b)` is two arguments, not one parenthesized list
+        Some(inner) if split_by_comma(rest).len() == 1 => inner,
+        _ => rest,
+    };
+    let args = if rest.trim().is_empty() {
+        Vec::new()
+    } else {
+        split_by_comma(rest)
+            .iter()
+            .map(String::as_str)
+            .map(parse_single_term)
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    Ok(ExecuteStatement {
+        name: name.to_string(),
+        args,
+    })
+}
+
+/// Check the name of a prepared statement: a lowercase identifier
+pub fn validate_statement_name(name: &str) -> Result<(), String> {
+    let valid = name.chars().next().is_some_and(|c| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid prepared statement name '{name}': use lowercase letters, digits and '_'"
+        ))
+    }
+}
+
+/// Split statement text into text and `$n` parameters (outside string literals)
+fn split_parameters(text: &str) -> Result<Vec<Segment>, String> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let mut in_string = false;
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch == '"' {
+            in_string = !in_string;
+        } else if ch == '$' && !in_string {
+            let mut digits = String::new();
+            while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                digits.push(digit);
+            }
+            let index: usize = digits
+                .parse()
+                .ok()
+                .filter(|n| *n > 0)
+                .ok_or("Parameters are written $1, $2, ...")?;
+            if !current.is_empty() {
+                segments.push(Segment::Text(std::mem::take(&mut current)));
+            }
+            segments.push(Segment::Param(index - 1));
+            continue;
+        }
+        current.push(ch);
+    }
+    if !current.is_empty() {
+        segments.push(Segment::Text(current));
+    }
+    Ok(segments)
+}
+
+/// Canonical IQL text of a literal argument
+fn literal_text(term: &Term) -> Result<String, String> {
+    let finite = |f: f64| {
+        if f.is_finite() {
+            Ok(format!("{f:?}"))
+        } else {
+            Err(format!("{f} is not a finite number"))
+        }
+    };
+    match term {
+        Term::Constant(n) => Ok(n.to_string()),
+        Term::FloatConstant(f) => finite(*f),
+        Term::BoolConstant(b) => Ok(b.to_string()),
+        // The statement layer and the engine disagree on escapes, so a
+        // string that needs one cannot be bound safely
+        Term::StringConstant(s) => {
+            if s.contains(['"', '\\', '\n', '\r']) {
+                Err("strings cannot contain quotes, backslashes or line breaks".to_string())
+            } else {
+                Ok(format!("\"{s}\""))
+            }
+        }
+        Term::VectorLiteral(values) => {
+            let values = values
+                .iter()
+                .map(|v| finite(*v))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(format!("[{}]", values.join(", ")))
+        }
+        _ => Err("expected a number, string, boolean or vector literal".to_string()),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepare_counts_parameters_outside_strings() {
+        let prepared =
+            parse_prepare("nearest as ?doc(D, V), Dist = euclidean(V, $1), Dist < $2, D != \"$3\"")
+                .unwrap();
+        assert_eq!(prepared.name, "nearest");
+        assert_eq!(prepared.params, 2);
+
+        assert!(parse_prepare("q as ?edge($2, Y)")
+            .unwrap_err()
+            .contains("$1 of 'q' is never used"));
+        assert!(parse_prepare("q as ?edge($0, Y)").is_err());
+        assert!(parse_prepare("q as .kg drop $1").is_err());
+        assert!(parse_prepare("q as execute r 1").is_err());
+        assert!(parse_prepare("q as ?edge(").is_err());
+        assert!(parse_prepare("Q as ?edge(X, Y)").is_err());
+        assert!(parse_prepare("q ?edge(X, Y)").is_err());
+    }
+
+    #[test]
+    fn test_bind_renders_canonical_literals() {
+        let prepared = parse_prepare("q as ?doc(D, V), Dist = euclidean(V, $1), D = $2").unwrap();
+        let exec = parse_execute("q [0.1, 0.25], \"a b\"").unwrap();
+        assert_eq!(
+            prepared.bind(&exec.args).unwrap(),
+            "?doc(D, V), Dist = euclidean(V, [0.1, 0.25]), D = \"a b\""
+        );
+
+        let insert = parse_prepare("ins as +edge($1, $2).").unwrap();
+        let exec = parse_execute("ins (1, -2.5)").unwrap();
+        assert_eq!(insert.bind(&exec.args).unwrap(), "+edge(1, -2.5)");
+
+        // Wrong arity, variables and strings that would need escapes are refused
+        assert!(insert.bind(&exec.args[..1]).is_err());
+        let exec = parse_execute("ins X, 1").unwrap();
+        assert!(insert.bind(&exec.args).is_err());
+        let exec = parse_execute(r#"ins "a\\b", 1"#).unwrap();
+        assert!(insert.bind(&exec.args).unwrap_err().contains("backslashes"));
+    }
+
+    #[test]
+    fn test_parse_execute_arguments() {
+        assert!(parse_execute("q").unwrap().args.is_empty());
+        assert_eq!(parse_execute("q [1.0, 2.0]").unwrap().args.len(), 1);
+        assert_eq!(parse_execute("q ([1.0, 2.0], 3)").unwrap().args.len(), 2);
+        assert!(parse_execute("q foo").is_err());
+        assert!(parse_execute("Q 1").is_err());
+    }
+}
@@ -8,6 +8,10 @@ use serde::{Deserialize, Serialize};
 
 use super::parser::validate_relation_name;
 use super::types::{parse_type_expr, split_respecting_braces, TypeExpr};
+use crate::ast::Term;
+use crate::parser::parse_term;
+use crate::schema::{ColumnDefault, DefaultFunction};
+use crate::value::Value;
 
 /// Schema declaration via unified prefix syntax: +name(col: type, ...). or name(col: type, ...).
 /// Use `+` prefix for persistent schema, no prefix for session schema.
@@ -28,6 +32,36 @@ pub struct ColumnDef {
     pub name: String,
     /// Column type
     pub col_type: TypeExpr,
+    /// Column annotations (`@key`, `@unique`, `@auto`)
+    #[serde(default)]
+    pub annotations: Vec<ColumnAnnotation>,
+    /// Default value (`= "open"`, `= time_now()`) used when an insert omits the column
+    #[serde(default)]
+    pub default: Option<ColumnDefault>,
+}
+
+/// Column annotation in a schema declaration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColumnAnnotation {
+    /// `@key`: part of the primary key
+    Key,
+    /// `@unique`: values are unique across the relation
+    Unique,
+    /// `@auto`: assigned from the relation's ID sequence when omitted
+    Auto,
+}
+
+impl ColumnAnnotation {
+    fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "key" => Ok(ColumnAnnotation::Key),
+            "unique" => Ok(ColumnAnnotation::Unique),
+            "auto" => Ok(ColumnAnnotation::Auto),
+            other => Err(format!(
+                "Unknown column annotation '@{other}'. Supported: @key, @unique, @auto"
+            )),
+        }
+    }
 }
 
 // Schema Parsing
@@ -133,16 +167,32 @@ fn parse_rel_columns(content: &str) -> Result<Vec<ColumnDef>, String> {
             .ok_or_else(|| format!("Column definition '{part}' must have type: 'name: type'"))?;
 
         let col_name = part[..colon_pos].trim().to_string();
-        let type_str = part[colon_pos + 1..].trim();
+        let (type_str, annotation_str) = split_annotations(part[colon_pos + 1..].trim());
 
         // Validate column name (may include aggregation syntax)
         validate_column_name(&col_name)?;
 
+        let (type_str, default_str) = split_top_level(type_str, '=');
         let col_type = parse_type_expr(type_str)?;
+        let default = default_str
+            .map(parse_column_default)
+            .transpose()
+            .map_err(|e| format!("Invalid default for column '{col_name}': {e}"))?;
+        let annotations = match annotation_str {
+            Some(rest) => rest
+                .split('@')
+                .map(str::trim)
+                .filter(|a| !a.is_empty())
+                .map(ColumnAnnotation::parse)
+                .collect::<Result<Vec<_>, _>>()?,
+            None => Vec::new(),
+        };
 
         columns.push(ColumnDef {
             name: col_name,
             col_type,
+            annotations,
+            default,
         });
     }
 
@@ -153,6 +203,50 @@ fn parse_rel_columns(content: &str) -> Result<Vec<ColumnDef>, String> {
     Ok(columns)
 }
 
+/// Parse the default of a column: a literal or a zero-argument builtin
+fn parse_column_default(input: &str) -> Result<ColumnDefault, String> {
+    let value = match parse_term(input.trim())? {
+        Term::Constant(n) => Value::Int64(n),
+        Term::FloatConstant(f) => Value::Float64(f),
+        Term::StringConstant(s) => Value::string(&s),
+        Term::BoolConstant(b) => Value::Bool(b),
+        Term::FunctionCall(func, args) if args.is_empty() => {
+            return DefaultFunction::from_name(func.as_str()).map(ColumnDefault::Function);
+        }
+        other => {
+            return Err(format!(
+                "'{other}' is not a literal or a zero-argument builtin such as time_now()"
+            ))
+        }
+    };
+    Ok(ColumnDefault::Constant(value))
+}
+
+/// Split `int(range(1, 9)) @key @unique` into the type and the annotation
+/// list (without its leading `@`). `@` inside strings or parentheses is
+/// part of the type.
+fn split_annotations(input: &str) -> (&str, Option<&str>) {
+    split_top_level(input, '@')
+}
+
+/// Split `input` at the first `delim` outside strings and brackets
+fn split_top_level(input: &str, delim: char) -> (&str, Option<&str>) {
+    let mut depth = 0i32;
+    let mut in_string = false;
+    for (i, c) in input.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '(' | '[' | '{' if !in_string => depth += 1,
+            ')' | ']' | '}' if !in_string => depth -= 1,
+            c if c == delim && !in_string && depth == 0 => {
+                return (input[..i].trim(), Some(&input[i + c.len_utf8()..]));
+            }
+            _ => {}
+        }
+    }
+    (input, None)
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
@@ -269,6 +363,8 @@ mod tests {
                 col_type: crate::statement::types::TypeExpr::Base(
                     crate::statement::types::BaseType::Int,
                 ),
+                annotations: vec![ColumnAnnotation::Key],
+                default: None,
             }],
             persistent: true,
         };
@@ -276,5 +372,58 @@ mod tests {
         let back: SchemaDecl = serde_json::from_str(&json).unwrap();
         assert_eq!(back.name, "test");
         assert!(back.persistent);
+        assert_eq!(back.columns[0].annotations, vec![ColumnAnnotation::Key]);
+    }
+
+    #[test]
+    fn test_parse_key_annotations() {
+        let Statement::SchemaDecl(decl) = parse_schema_decl(
+            "user(id: int @key, email: string(pattern(\"^[^@]+@\")) @unique, name: string)",
+            true,
+        )
+        .unwrap() else {
+            panic!("expected schema declaration");
+        };
+        assert_eq!(decl.columns[0].annotations, vec![ColumnAnnotation::Key]);
+        assert_eq!(decl.columns[1].annotations, vec![ColumnAnnotation::Unique]);
+        assert!(matches!(decl.columns[1].col_type, TypeExpr::Refined { .. }));
+        assert!(decl.columns[2].annotations.is_empty());
+
+        let Statement::SchemaDecl(decl) =
+            parse_schema_decl("event(id: int @key @auto, name: string)", true).unwrap()
+        else {
+            panic!("expected schema declaration");
+        };
+        assert_eq!(
+            decl.columns[0].annotations,
+            vec![ColumnAnnotation::Key, ColumnAnnotation::Auto]
+        );
+
+        assert!(parse_schema_decl("user(id: int @primary)", true).is_err());
+    }
+
+    #[test]
+    fn test_parse_column_defaults() {
+        let result = parse_schema_decl(
+            "ticket(id: int @key, status: string = \"open\", created: timestamp = time_now())",
+            true,
+        )
+        .unwrap();
+        let Statement::SchemaDecl(decl) = result else {
+            panic!("Expected SchemaDecl");
+        };
+        assert_eq!(decl.columns[0].default, None);
+        assert_eq!(decl.columns[0].annotations, vec![ColumnAnnotation::Key]);
+        assert_eq!(
+            decl.columns[1].default,
+            Some(ColumnDefault::Constant(Value::string("open")))
+        );
+        assert_eq!(
+            decl.columns[2].default,
+            Some(ColumnDefault::Function(DefaultFunction::TimeNow))
+        );
+
+        assert!(parse_schema_decl("t(x: int = X)", true).is_err());
+        assert!(parse_schema_decl("t(x: string = upper(\"a\"))", true).is_err());
     }
 }
@@ -44,8 +44,17 @@ impl TypeExpr {
             TypeExpr::Base(BaseType::String) => SchemaType::String,
             TypeExpr::Base(BaseType::Bool) => SchemaType::Bool,
             TypeExpr::Base(BaseType::Vector) => SchemaType::Vector { dim: None },
+            TypeExpr::Base(BaseType::Date) => SchemaType::Date,
+            TypeExpr::Base(BaseType::Duration) => SchemaType::Duration,
+            TypeExpr::Base(BaseType::Uuid) => SchemaType::Uuid,
             TypeExpr::TypeRef(name) => SchemaType::Named(name.clone()),
-            TypeExpr::List(_) => SchemaType::Any, // Lists not directly supported yet
+            TypeExpr::Base(BaseType::List) => SchemaType::List,
+            TypeExpr::Base(BaseType::Map) => SchemaType::Map,
+            TypeExpr::Base(BaseType::Json) => SchemaType::Json,
+            TypeExpr::Base(BaseType::Bytes) => SchemaType::Bytes,
+            TypeExpr::Base(BaseType::Timestamp) => SchemaType::Timestamp,
+            // Element types are not checked: any list matches
+            TypeExpr::List(_) => SchemaType::List,
             TypeExpr::Record(_) => SchemaType::Any, // Records not directly supported yet
             TypeExpr::Refined { base, refinements } => {
                 // Special case: vector(N) carries a dimension constraint.
@@ -56,10 +65,29 @@ impl TypeExpr {
                         .and_then(|r| r.name.parse::<usize>().ok());
                     return SchemaType::Vector { dim };
                 }
-                base.to_schema_type() // Ignore other refinements
+                base.to_schema_type() // Other refinements become check constraints
             }
         }
     }
+
+    /// Check constraints implied by this type's refinements.
+    ///
+    /// Vector dimensions are part of the schema type and yield no constraint.
+    pub fn check_constraints(&self) -> Result<Vec<crate::schema::CheckConstraint>, String> {
+        match self {
+            TypeExpr::Refined { base, refinements } => {
+                if let TypeExpr::Base(BaseType::Vector) = base.as_ref() {
+                    return Ok(Vec::new());
+                }
+                let mut constraints = base.check_constraints()?;
+                for refinement in refinements {
+                    constraints.push(crate::schema::CheckConstraint::from_refinement(refinement)?);
+                }
+                Ok(constraints)
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
 }
 
 /// Base types
@@ -71,6 +99,22 @@ pub enum BaseType {
     Float,
     /// Vector of f32 values (embeddings). Dimension is an optional refinement.
     Vector,
+    /// Calendar date
+    Date,
+    /// Signed length of time
+    Duration,
+    /// 128-bit UUID
+    Uuid,
+    /// List of values of any type
+    List,
+    /// String-keyed map of values of any type
+    Map,
+    /// JSON document
+    Json,
+    /// Binary blob
+    Bytes,
+    /// Unix timestamp in milliseconds
+    Timestamp,
 }
 
 impl fmt::Display for BaseType {
@@ -81,6 +125,14 @@ impl fmt::Display for BaseType {
             BaseType::Bool => write!(f, "bool"),
             BaseType::Float => write!(f, "float"),
             BaseType::Vector => write!(f, "vector"),
+            BaseType::Date => write!(f, "date"),
+            BaseType::Duration => write!(f, "duration"),
+            BaseType::Uuid => write!(f, "uuid"),
+            BaseType::List => write!(f, "list"),
+            BaseType::Map => write!(f, "map"),
+            BaseType::Json => write!(f, "json"),
+            BaseType::Bytes => write!(f, "bytes"),
+            BaseType::Timestamp => write!(f, "timestamp"),
         }
     }
 }
@@ -205,6 +257,27 @@ pub fn parse_type_expr(input: &str) -> Result<TypeExpr, String> {
         return Ok(TypeExpr::List(Box::new(inner_type)));
     }
 
+    // Vector dimension: vector<N> (same as vector(N))
+    if let Some(dim) = input
+        .strip_suffix('>')
+        .and_then(|s| s.split_once('<'))
+        .filter(|(base, _)| matches!(base.trim(), "vector" | "vec" | "embedding"))
+        .map(|(_, dim)| dim.trim())
+    {
+        if dim.parse::<usize>().map_or(true, |n| n == 0) {
+            return Err(format!(
+                "Invalid vector dimension '{dim}': expected a positive integer"
+            ));
+        }
+        return Ok(TypeExpr::Refined {
+            base: Box::new(TypeExpr::Base(BaseType::Vector)),
+            refinements: vec![Refinement {
+                name: dim.to_string(),
+                args: Vec::new(),
+            }],
+        });
+    }
+
     // Check for refinements: base_type(constraint1, constraint2, ...)
     if let Some(paren_pos) = input.find('(') {
         if input.ends_with(')') {
@@ -228,6 +301,14 @@ pub fn parse_type_expr(input: &str) -> Result<TypeExpr, String> {
         "bool" => Ok(TypeExpr::Base(BaseType::Bool)),
         "float" => Ok(TypeExpr::Base(BaseType::Float)),
         "vector" | "vec" | "embedding" => Ok(TypeExpr::Base(BaseType::Vector)),
+        "date" => Ok(TypeExpr::Base(BaseType::Date)),
+        "duration" | "interval" => Ok(TypeExpr::Base(BaseType::Duration)),
+        "uuid" => Ok(TypeExpr::Base(BaseType::Uuid)),
+        "list" => Ok(TypeExpr::Base(BaseType::List)),
+        "map" => Ok(TypeExpr::Base(BaseType::Map)),
+        "json" | "jsonb" => Ok(TypeExpr::Base(BaseType::Json)),
+        "bytes" | "blob" | "binary" | "bytea" => Ok(TypeExpr::Base(BaseType::Bytes)),
+        "timestamp" | "time" | "datetime" => Ok(TypeExpr::Base(BaseType::Timestamp)),
         _ => {
             // Must be a type reference (uppercase name)
             // Note: input is non-empty (checked at start of function)
@@ -236,7 +317,7 @@ pub fn parse_type_expr(input: &str) -> Result<TypeExpr, String> {
                     Ok(TypeExpr::TypeRef(input.to_string()))
                 } else {
                     Err(format!(
-                        "Unknown base type: '{input}'. Use int, string, bool, float, or a type name."
+                        "Unknown base type: '{input}'. Use int, string, bool, float, date, timestamp, duration, uuid, list, map, json, bytes, or a type name."
                     ))
                 }
             } else {
@@ -611,6 +692,18 @@ mod tests {
             TypeExpr::Base(BaseType::Bool).to_schema_type(),
             SchemaType::Bool
         ));
+        assert!(matches!(
+            parse_type_expr("date").unwrap().to_schema_type(),
+            SchemaType::Date
+        ));
+        assert!(matches!(
+            parse_type_expr("interval").unwrap().to_schema_type(),
+            SchemaType::Duration
+        ));
+        assert!(matches!(
+            parse_type_expr("uuid").unwrap().to_schema_type(),
+            SchemaType::Uuid
+        ));
     }
 
     #[test]
@@ -621,10 +714,22 @@ mod tests {
     }
 
     #[test]
-    fn test_to_schema_type_list_is_any() {
+    fn test_to_schema_type_list() {
         use crate::schema::SchemaType;
         let list = TypeExpr::List(Box::new(TypeExpr::Base(BaseType::Int)));
-        assert!(matches!(list.to_schema_type(), SchemaType::Any));
+        assert!(matches!(list.to_schema_type(), SchemaType::List));
+        assert!(matches!(
+            parse_type_expr("map").unwrap().to_schema_type(),
+            SchemaType::Map
+        ));
+        assert!(matches!(
+            parse_type_expr("json").unwrap().to_schema_type(),
+            SchemaType::Json
+        ));
+        assert!(matches!(
+            parse_type_expr("blob").unwrap().to_schema_type(),
+            SchemaType::Bytes
+        ));
     }
 
     #[test]
@@ -644,6 +749,34 @@ mod tests {
         assert!(matches!(refined.to_schema_type(), SchemaType::Int));
     }
 
+    #[test]
+    fn test_check_constraints_from_refinements() {
+        use crate::schema::CheckConstraint;
+        let age = parse_type_expr("int(range(0, 150))").unwrap();
+        assert_eq!(
+            age.check_constraints().unwrap(),
+            vec![CheckConstraint::Range {
+                min: Some(0.0),
+                max: Some(150.0)
+            }]
+        );
+        assert!(parse_type_expr("vector(3)")
+            .unwrap()
+            .check_constraints()
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            parse_type_expr("vector<768>").unwrap().to_schema_type(),
+            crate::schema::SchemaType::Vector { dim: Some(768) }
+        );
+        assert!(parse_type_expr("vector<0>").is_err());
+        assert!(parse_type_expr("vector<big>").is_err());
+        assert!(parse_type_expr("string(shiny)")
+            .unwrap()
+            .check_constraints()
+            .is_err());
+    }
+
     // === parse_type_decl ===
 
     #[test]
@@ -694,6 +827,10 @@ mod tests {
             parse_type_expr("float").unwrap(),
             TypeExpr::Base(BaseType::Float)
         ));
+        assert!(matches!(
+            parse_type_expr("timestamp").unwrap(),
+            TypeExpr::Base(BaseType::Timestamp)
+        ));
     }
 
     #[test]
@@ -3,6 +3,11 @@
 //! Tracks per-relation cardinality, per-column distinct counts, min/max values,
 //! MCV lists, histograms, and join selectivity estimates.
 //!
+//! Statistics are collected by the `analyze [relation]` statement and
+//! stored per knowledge graph in `statistics.json`, so they survive
+//! restarts. They describe the data as of the last analyze; the planner
+//! only uses them to rank plans, never for correctness.
+//!
 //! # Example
 //!
 //! ```
@@ -29,10 +34,13 @@
 //! ```
 
 use crate::value::{Tuple, Value};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
 
 /// Statistics for a single relation.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RelationStats {
     /// Relation name
     pub name: String,
@@ -45,7 +53,7 @@ pub struct RelationStats {
 }
 
 /// Statistics for a single column.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ColumnStats {
     /// Column index (0-based)
     pub index: usize,
@@ -63,10 +71,21 @@ pub struct ColumnStats {
     pub histogram: Option<Histogram>,
 }
 
+impl ColumnStats {
+    /// Fraction of the relation's tuples that are null in this column
+    pub fn null_fraction(&self, cardinality: usize) -> f64 {
+        if cardinality == 0 {
+            0.0
+        } else {
+            self.null_count as f64 / cardinality as f64
+        }
+    }
+}
+
 /// Equi-depth histogram for selectivity estimation.
 ///
 /// Each bucket contains approximately the same number of values.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Histogram {
     /// Bucket boundaries (n+1 values for n buckets)
     pub boundaries: Vec<Value>,
@@ -103,6 +122,8 @@ pub struct StatisticsManager {
     config: StatsConfig,
     /// Track changes for auto-update
     change_counts: HashMap<String, usize>,
+    /// Declared unique key column sets per relation
+    unique_keys: HashMap<String, Vec<Vec<usize>>>,
 }
 
 impl StatisticsManager {
@@ -111,9 +132,31 @@ impl StatisticsManager {
             stats: HashMap::new(),
             config,
             change_counts: HashMap::new(),
+            unique_keys: HashMap::new(),
+        }
+    }
+
+    /// Record the unique key column sets declared for a relation.
+    ///
+    /// A join on a superset of a unique key matches at most one tuple of
+    /// that relation per tuple of the other side, which bounds the
+    /// join cardinality estimate.
+    pub fn set_unique_keys(&mut self, name: &str, keys: Vec<Vec<usize>>) {
+        if keys.is_empty() {
+            self.unique_keys.remove(name);
+        } else {
+            self.unique_keys.insert(name.to_string(), keys);
         }
     }
 
+    /// Whether joining `relation` on `columns` covers one of its unique keys
+    pub fn is_unique_join(&self, relation: &str, columns: &[usize]) -> bool {
+        self.unique_keys.get(relation).is_some_and(|keys| {
+            keys.iter()
+                .any(|key| key.iter().all(|col| columns.contains(col)))
+        })
+    }
+
     /// Analyze a relation and compute its statistics.
     ///
     /// # Arguments
@@ -220,6 +263,7 @@ impl StatisticsManager {
         let numeric_values: Vec<f64> = values
             .iter()
             .filter_map(|v| match v {
+                Value::Int32(i) => Some(f64::from(*i)),
                 Value::Int64(i) => Some(*i as f64),
                 Value::Float64(f) => Some(*f),
                 _ => None,
@@ -322,6 +366,10 @@ impl StatisticsManager {
     /// # Formula
     ///
     /// |A JOIN B| ~= |A| × |B| × selectivity
+    ///
+    /// When the join keys cover a unique key of one side, each tuple of the
+    /// other side matches at most once, so the estimate is capped at the
+    /// other side's cardinality.
     pub fn estimate_join_cardinality(
         &self,
         left_rel: &str,
@@ -335,7 +383,14 @@ impl StatisticsManager {
         let selectivity =
             self.estimate_join_selectivity(left_rel, left_keys, right_rel, right_keys);
 
-        ((left_card as f64) * (right_card as f64) * selectivity).ceil() as usize
+        let mut estimate = ((left_card as f64) * (right_card as f64) * selectivity).ceil() as usize;
+        if self.is_unique_join(left_rel, left_keys) {
+            estimate = estimate.min(right_card);
+        }
+        if self.is_unique_join(right_rel, right_keys) {
+            estimate = estimate.min(left_card);
+        }
+        estimate
     }
 
     /// Estimate selectivity for a filter predicate.
@@ -451,6 +506,55 @@ impl StatisticsManager {
         self.change_counts.remove(name);
         self.stats.remove(name).is_some()
     }
+
+    /// Statistics of every analyzed relation, ordered by name
+    pub fn all(&self) -> Vec<&RelationStats> {
+        let mut all: Vec<_> = self.stats.values().collect();
+        all.sort_by(|a, b| a.name.cmp(&b.name));
+        all
+    }
+
+    /// Distinct value count of every column of every analyzed relation
+    pub fn distinct_counts(&self) -> HashMap<String, Vec<usize>> {
+        self.stats
+            .iter()
+            .map(|(name, stats)| {
+                let counts = stats
+                    .column_stats
+                    .iter()
+                    .map(|c| c.distinct_count)
+                    .collect();
+                (name.clone(), counts)
+            })
+            .collect()
+    }
+
+    /// Load the statistics stored at `path`, or start empty if it does not exist
+    pub fn load(path: &Path, config: StatsConfig) -> Result<Self, String> {
+        let mut manager = Self::new(config);
+        if !path.exists() {
+            return Ok(manager);
+        }
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read statistics: {e}"))?;
+        manager.stats = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse statistics: {e}"))?;
+        Ok(manager)
+    }
+
+    /// Write the collected statistics to `path`
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create statistics directory: {e}"))?;
+        }
+        let content = serde_json::to_string(&self.stats)
+            .map_err(|e| format!("Failed to serialize statistics: {e}"))?;
+        // Write-then-rename so a crash never leaves a truncated file
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, content).map_err(|e| format!("Failed to write statistics: {e}"))?;
+        fs::rename(&tmp, path).map_err(|e| format!("Failed to write statistics: {e}"))
+    }
 }
 
 impl Default for StatisticsManager {
@@ -649,6 +753,30 @@ mod tests {
         assert!(cardinality < 500, "Cardinality {} too high", cardinality);
     }
 
+    #[test]
+    fn test_stats_join_cardinality_unique_key() {
+        let mut manager = StatisticsManager::new(StatsConfig::default());
+
+        // Without stats: 1000 x 1000 x 0.1
+        assert_eq!(
+            manager.estimate_join_cardinality("orders", &[1], "customer", &[0]),
+            100_000
+        );
+
+        manager.set_unique_keys("customer", vec![vec![0]]);
+        assert!(manager.is_unique_join("customer", &[0, 1]));
+        assert!(!manager.is_unique_join("customer", &[1]));
+        assert_eq!(
+            manager.estimate_join_cardinality("orders", &[1], "customer", &[0]),
+            1000
+        );
+        // Joining on a non-key column is not bounded
+        assert_eq!(
+            manager.estimate_join_cardinality("orders", &[1], "customer", &[1]),
+            100_000
+        );
+    }
+
     #[test]
     fn test_stats_join_selectivity_missing_stats() {
         let manager = StatisticsManager::new(StatsConfig::default());
@@ -758,4 +886,28 @@ mod tests {
         let manager = StatisticsManager::default();
         assert_eq!(manager.relation_count(), 0);
     }
+
+    #[test]
+    fn test_stats_save_and_load() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("statistics.json");
+
+        let mut manager = StatisticsManager::default();
+        let mut tuples = vec![make_tuple(vec![1, 10]), make_tuple(vec![2, 20])];
+        tuples.push(Tuple::new(vec![Value::Int64(1), Value::Null]));
+        manager.analyze("r", &tuples, 2);
+        manager.save(&path).unwrap();
+
+        let loaded = StatisticsManager::load(&path, StatsConfig::default()).unwrap();
+        let stats = loaded.get("r").unwrap();
+        assert_eq!(stats.cardinality, 3);
+        assert_eq!(stats.column_stats[0].distinct_count, 2);
+        assert_eq!(stats.column_stats[1].max_value, Some(Value::Int64(20)));
+        assert!((stats.column_stats[1].null_fraction(3) - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(loaded.distinct_counts()["r"], vec![2, 2]);
+
+        let missing = temp.path().join("none.json");
+        let empty = StatisticsManager::load(&missing, StatsConfig::default()).unwrap();
+        assert_eq!(empty.relation_count(), 0);
+    }
 }
@@ -0,0 +1,358 @@
+//! Avro Storage Module
+//!
+//! Reads and writes Avro object container files, the format Kafka
+//! connectors archive topics in, so archived records load without a
+//! conversion step.
+//!
+//! ## Schema Mapping
+//!
+//! A file's writer schema must be a record; each field becomes a column:
+//!
+//! | Avro | Column type |
+//! |------|-------------|
+//! | `boolean` | `bool` |
+//! | `int`, `long` | `int` |
+//! | `float`, `double` | `float` |
+//! | `string`, `enum` | `string` |
+//! | `bytes`, `fixed` | `bytes` |
+//! | `int` (`date`) | `date` |
+//! | `long` (`timestamp-millis`, `timestamp-micros`) | `timestamp` |
+//! | `string` (`uuid`) | `uuid` |
+//! | `array` of `float` or `double` | `vector` |
+//! | other `array` | `list` |
+//! | `map`, `record` | `map` |
+//!
+//! A union of `null` and one type maps like that type; other unions map
+//! to `any`. Files are written with every field a union of `null` and the
+//! column's type, so null values round-trip.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use apache_avro::types::Value as AvroValue;
+use apache_avro::{Reader, Schema, Writer};
+use serde_json::{json, Value as Json};
+
+use crate::schema::{ColumnSchema, RelationSchema, SchemaType};
+use crate::storage::error::{StorageError, StorageResult};
+use crate::value::{Tuple, Value};
+
+/// Load an Avro container file as a relation whose schema is mapped from
+/// the file's writer schema
+pub fn load_from_avro<P: AsRef<Path>>(
+    path: P,
+    relation: &str,
+) -> StorageResult<(RelationSchema, Vec<Tuple>)> {
+    let reader = Reader::new(BufReader::new(File::open(path)?))?;
+    let schema = avro_to_relation_schema(reader.writer_schema(), relation)?;
+
+    let mut tuples = Vec::new();
+    for (i, record) in reader.enumerate() {
+        let AvroValue::Record(fields) = record? else {
+            return Err(StorageError::ParseError(format!(
+                "Record {}: expected an Avro record",
+                i + 1
+            )));
+        };
+        let values = fields
+            .into_iter()
+            .zip(&schema.columns)
+            .map(|((_, field), column)| {
+                from_avro(field, &column.data_type).ok_or_else(|| {
+                    StorageError::ParseError(format!(
+                        "Record {}, column '{}': value does not fit {}",
+                        i + 1,
+                        column.name,
+                        column.data_type
+                    ))
+                })
+            })
+            .collect::<StorageResult<Vec<_>>>()?;
+        tuples.push(Tuple::new(values));
+    }
+    Ok((schema, tuples))
+}
+
+/// Save tuples to an Avro container file with a record schema mapped from
+/// `schema`. Relation and column names must be valid Avro names.
+pub fn save_to_avro<P: AsRef<Path>>(
+    path: P,
+    schema: &RelationSchema,
+    tuples: &[Tuple],
+) -> StorageResult<()> {
+    let path = path.as_ref();
+    let avro_schema = relation_to_avro_schema(schema)?;
+
+    // Create parent directories if needed
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let file = BufWriter::new(File::create(path)?);
+    let mut writer = Writer::new(&avro_schema, file);
+    for tuple in tuples {
+        let fields = schema
+            .columns
+            .iter()
+            .zip(tuple.values())
+            .map(|(column, value)| {
+                let field = match value {
+                    Value::Null => AvroValue::Union(0, Box::new(AvroValue::Null)),
+                    value => AvroValue::Union(1, Box::new(to_avro(value, &column.data_type))),
+                };
+                (column.name.clone(), field)
+            })
+            .collect();
+        writer.append(AvroValue::Record(fields))?;
+    }
+    let mut file = writer.into_inner()?;
+    file.flush()?;
+    Ok(())
+}
+
+/// Map an Avro record schema to a relation schema
+pub fn avro_to_relation_schema(schema: &Schema, relation: &str) -> StorageResult<RelationSchema> {
+    let doc = serde_json::to_value(schema)?;
+    if doc["type"] != "record" {
+        return Err(StorageError::ParseError(
+            "Avro schema must be a record".to_string(),
+        ));
+    }
+    let fields = doc["fields"].as_array().cloned().unwrap_or_default();
+    let mut relation_schema = RelationSchema::new(relation);
+    for field in &fields {
+        let name = field["name"]
+            .as_str()
+            .ok_or_else(|| StorageError::ParseError("Avro field without a name".to_string()))?;
+        relation_schema =
+            relation_schema.with_column(ColumnSchema::new(name, column_type(&field["type"])));
+    }
+    Ok(relation_schema)
+}
+
+/// Column type for an Avro type, given as its JSON form
+fn column_type(avro_type: &Json) -> SchemaType {
+    match avro_type {
+        Json::String(name) => match name.as_str() {
+            "boolean" => SchemaType::Bool,
+            "int" | "long" => SchemaType::Int,
+            "float" | "double" => SchemaType::Float,
+            "bytes" => SchemaType::Bytes,
+            "string" => SchemaType::String,
+            _ => SchemaType::Any,
+        },
+        Json::Array(branches) => {
+            let mut non_null = branches.iter().filter(|b| b.as_str() != Some("null"));
+            match (non_null.next(), non_null.next()) {
+                (Some(only), None) => column_type(only),
+                _ => SchemaType::Any,
+            }
+        }
+        Json::Object(_) => match (
+            avro_type["type"].as_str(),
+            avro_type["logicalType"].as_str(),
+        ) {
+            (Some("int"), Some("date")) => SchemaType::Date,
+            (Some("long"), Some("timestamp-millis" | "timestamp-micros")) => SchemaType::Timestamp,
+            (Some("string"), Some("uuid")) => SchemaType::Uuid,
+            (Some("array"), _) => match avro_type["items"].as_str() {
+                Some("float" | "double") => SchemaType::Vector { dim: None },
+                _ => SchemaType::List,
+            },
+            (Some("map" | "record"), _) => SchemaType::Map,
+            (Some("enum"), _) => SchemaType::String,
+            (Some("fixed"), _) => SchemaType::Bytes,
+            (Some(primitive), _) => column_type(&Json::from(primitive)),
+            (None, _) => SchemaType::Any,
+        },
+        _ => SchemaType::Any,
+    }
+}
+
+/// Map a relation schema to an Avro record schema with nullable fields
+fn relation_to_avro_schema(schema: &RelationSchema) -> StorageResult<Schema> {
+    let fields: Vec<Json> = schema
+        .columns
+        .iter()
+        .map(|column| {
+            json!({
+                "name": column.name,
+                "type": ["null", avro_type(&column.data_type)],
+                "default": null,
+            })
+        })
+        .collect();
+    let doc = json!({ "type": "record", "name": schema.name, "fields": fields });
+    Ok(Schema::parse(&doc)?)
+}
+
+/// Avro type a column is written as. Types Avro has no equivalent for
+/// are written as strings.
+fn avro_type(data_type: &SchemaType) -> Json {
+    match data_type {
+        SchemaType::Int | SchemaType::Duration => json!("long"),
+        SchemaType::Float => json!("double"),
+        SchemaType::Bool => json!("boolean"),
+        SchemaType::Bytes => json!("bytes"),
+        SchemaType::Timestamp => json!({ "type": "long", "logicalType": "timestamp-millis" }),
+        SchemaType::Date => json!({ "type": "int", "logicalType": "date" }),
+        SchemaType::Uuid => json!({ "type": "string", "logicalType": "uuid" }),
+        SchemaType::Vector { .. } => json!({ "type": "array", "items": "float" }),
+        _ => json!("string"),
+    }
+}
+
+/// Convert a non-null value to the Avro value of its column's type
+fn to_avro(value: &Value, data_type: &SchemaType) -> AvroValue {
+    match (data_type, value) {
+        (SchemaType::Timestamp, v) => AvroValue::TimestampMillis(v.to_i64()),
+        (SchemaType::Date, Value::Date(d)) => AvroValue::Date(*d),
+        (SchemaType::Uuid, Value::Uuid(u)) => AvroValue::Uuid(*u),
+        (SchemaType::Int | SchemaType::Duration, v) => AvroValue::Long(v.to_i64()),
+        (SchemaType::Float, v) => AvroValue::Double(v.to_f64()),
+        (SchemaType::Bool, Value::Bool(b)) => AvroValue::Boolean(*b),
+        (SchemaType::Bytes, Value::Bytes(b)) => AvroValue::Bytes(b.to_vec()),
+        (SchemaType::Vector { .. }, Value::Vector(v)) => {
+            AvroValue::Array(v.iter().map(|x| AvroValue::Float(*x)).collect())
+        }
+        (SchemaType::Vector { .. }, Value::VectorInt8(v)) => {
+            AvroValue::Array(v.iter().map(|x| AvroValue::Float(f32::from(*x))).collect())
+        }
+        (_, Value::String(s)) => AvroValue::String(s.to_string()),
+        (_, Value::Json(text)) => AvroValue::String(text.to_string()),
+        (_, v) => AvroValue::String(v.to_string()),
+    }
+}
+
+/// Convert an Avro value to a value of `data_type` (`None` if it does not
+/// fit)
+pub(crate) fn from_avro(value: AvroValue, data_type: &SchemaType) -> Option<Value> {
+    let converted = match value {
+        AvroValue::Null => return Some(Value::Null),
+        AvroValue::Union(_, inner) => return from_avro(*inner, data_type),
+        AvroValue::Array(items) if matches!(data_type, SchemaType::Vector { .. }) => {
+            let v: Option<Vec<f32>> = items
+                .into_iter()
+                .map(|item| match item {
+                    AvroValue::Float(f) => Some(f),
+                    AvroValue::Double(d) => Some(d as f32),
+                    _ => None,
+                })
+                .collect();
+            Value::Vector(Arc::new(v?))
+        }
+        other => untyped(other)?,
+    };
+    data_type.matches(&converted).then_some(converted)
+}
+
+/// Convert an Avro value on its own: arrays become lists and maps and
+/// records maps
+fn untyped(value: AvroValue) -> Option<Value> {
+    Some(match value {
+        AvroValue::Null => Value::Null,
+        AvroValue::Boolean(b) => Value::Bool(b),
+        AvroValue::Int(i) => Value::Int64(i64::from(i)),
+        AvroValue::Long(l) => Value::Int64(l),
+        AvroValue::Float(f) => Value::Float64(f64::from(f)),
+        AvroValue::Double(d) => Value::Float64(d),
+        AvroValue::String(s) | AvroValue::Enum(_, s) => Value::interned(&s),
+        AvroValue::Bytes(b) | AvroValue::Fixed(_, b) => Value::bytes(b),
+        AvroValue::Date(d) => Value::Date(d),
+        AvroValue::TimestampMillis(ms) => Value::Timestamp(ms),
+        AvroValue::TimestampMicros(us) => Value::Timestamp(us / 1000),
+        AvroValue::Uuid(u) => Value::Uuid(u),
+        AvroValue::Union(_, inner) => untyped(*inner)?,
+        AvroValue::Array(items) => {
+            Value::list(items.into_iter().map(untyped).collect::<Option<Vec<_>>>()?)
+        }
+        AvroValue::Map(entries) => Value::Map(Arc::new(
+            entries
+                .into_iter()
+                .map(|(k, v)| Some((k, untyped(v)?)))
+                .collect::<Option<BTreeMap<_, _>>>()?,
+        )),
+        AvroValue::Record(fields) => Value::Map(Arc::new(
+            fields
+                .into_iter()
+                .map(|(k, v)| Some((k, untyped(v)?)))
+                .collect::<Option<BTreeMap<_, _>>>()?,
+        )),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_avro_roundtrip() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("events.avro");
+        let schema = RelationSchema::new("event")
+            .with_column(ColumnSchema::new("id", SchemaType::Int))
+            .with_column(ColumnSchema::new("name", SchemaType::String))
+            .with_column(ColumnSchema::new("at", SchemaType::Timestamp))
+            .with_column(ColumnSchema::new("emb", SchemaType::Vector { dim: None }));
+        let tuples = vec![
+            Tuple::new(vec![
+                Value::Int64(1),
+                Value::string("alice"),
+                Value::Timestamp(1_700_000_000_000),
+                Value::vector(vec![0.5, 1.5]),
+            ]),
+            Tuple::new(vec![
+                Value::Int64(2),
+                Value::Null,
+                Value::Timestamp(1_700_000_000_001),
+                Value::vector(vec![2.0, 3.0]),
+            ]),
+        ];
+
+        save_to_avro(&path, &schema, &tuples).unwrap();
+        let (loaded_schema, loaded) = load_from_avro(&path, "event").unwrap();
+        assert_eq!(loaded_schema, schema);
+        assert_eq!(loaded, tuples);
+    }
+
+    #[test]
+    fn test_avro_schema_mapping() {
+        let schema = Schema::parse_str(
+            r#"{
+                "type": "record",
+                "name": "click",
+                "fields": [
+                    {"name": "user", "type": ["null", "string"]},
+                    {"name": "count", "type": "int"},
+                    {"name": "day", "type": {"type": "int", "logicalType": "date"}},
+                    {"name": "tags", "type": {"type": "array", "items": "string"}},
+                    {"name": "props", "type": {"type": "map", "values": "long"}},
+                    {"name": "either", "type": ["int", "string"]}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let mapped = avro_to_relation_schema(&schema, "click").unwrap();
+        let types: Vec<&SchemaType> = mapped.columns.iter().map(|c| &c.data_type).collect();
+        assert_eq!(
+            types,
+            vec![
+                &SchemaType::String,
+                &SchemaType::Int,
+                &SchemaType::Date,
+                &SchemaType::List,
+                &SchemaType::Map,
+                &SchemaType::Any,
+            ]
+        );
+
+        let not_record = Schema::parse_str(r#""long""#).unwrap();
+        assert!(avro_to_relation_schema(&not_record, "r").is_err());
+    }
+}
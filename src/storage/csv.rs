@@ -14,6 +14,22 @@
 //!   - Floats: parsed as f64
 //!   - Strings: quoted or unquoted text
 //!   - Booleans: "true"/"false" (case-insensitive)
+//!   - Vectors: bracketed numbers, `[0.1,0.2]` (or `[1,-2]i8`); the commas
+//!     inside the brackets do not split fields
+//!
+//! ## Schema Inference
+//!
+//! [`load_from_csv_inferred`] samples the first rows of a file to infer a
+//! `RelationSchema` (`int`, `float`, `bool`, `timestamp`, `vector` or
+//! `string` per column, with per-column overrides) and parses every value
+//! as its column's type.
+//!
+//! ## Parallel Loading
+//!
+//! [`load_csv_parallel`] loads large files in batches: the rows of a batch
+//! are parsed on the Rayon thread pool while the next one is read, and
+//! each batch is handed on as soon as it is parsed. Rows that cannot be
+//! parsed are skipped, collected or fail the load ([`RowErrorPolicy`]).
 //!
 //! ## Example
 //!
@@ -24,14 +40,17 @@
 //! 3,4,0.5
 //! ```
 
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader, BufWriter, Write};
 use std::path::Path;
-
 use std::sync::Arc;
 
+use crate::schema::{ColumnSchema, RelationSchema, SchemaType};
 use crate::storage::error::{StorageError, StorageResult};
+use crate::temporal_ops::{parse_date, parse_timestamp};
 use crate::value::{Tuple, Value};
+use rayon::prelude::*;
 
 /// Options for CSV parsing
 #[derive(Debug, Clone)]
@@ -57,6 +76,32 @@ impl Default for CsvOptions {
     }
 }
 
+/// Options for inferring a relation schema from CSV content
+#[derive(Debug, Clone)]
+pub struct CsvInference {
+    /// Number of data rows sampled to infer column types (default: 1000)
+    pub sample_rows: usize,
+    /// Column types to use instead of inferred ones, by column name
+    pub overrides: HashMap<String, SchemaType>,
+}
+
+impl Default for CsvInference {
+    fn default() -> Self {
+        CsvInference {
+            sample_rows: 1000,
+            overrides: HashMap::new(),
+        }
+    }
+}
+
+impl CsvInference {
+    /// Use `data_type` for `column` instead of inferring it
+    pub fn with_override(mut self, column: impl Into<String>, data_type: SchemaType) -> Self {
+        self.overrides.insert(column.into(), data_type);
+        self
+    }
+}
+
 /// Load tuples from a CSV file
 ///
 /// # Arguments
@@ -74,19 +119,299 @@ pub fn load_from_csv_with_options<P: AsRef<Path>>(
     path: P,
     options: CsvOptions,
 ) -> StorageResult<(Vec<String>, Vec<Tuple>)> {
+    let mut tuples = Vec::new();
+    let schema = read_rows(path, &options, |_, fields| {
+        tuples.push(Tuple::new(fields.into_iter().map(parse_value).collect()));
+        Ok(true)
+    })?;
+    Ok((schema, tuples))
+}
+
+/// Infer a relation schema for a CSV file from its first
+/// `inference.sample_rows` data rows.
+///
+/// Columns whose sampled values are all null are inferred as `string`.
+pub fn infer_csv_schema<P: AsRef<Path>>(
+    path: P,
+    relation: &str,
+    options: &CsvOptions,
+    inference: &CsvInference,
+) -> StorageResult<RelationSchema> {
+    let mut inferred: Vec<Option<SchemaType>> = Vec::new();
+    let mut sampled = 0;
+    let columns = read_rows(path, options, |_, fields| {
+        if inferred.is_empty() {
+            inferred = vec![None; fields.len()];
+        }
+        for (slot, field) in inferred.iter_mut().zip(fields) {
+            if let Some(ty) = infer_field_type(field) {
+                *slot = Some(match slot.take() {
+                    Some(prev) => merge_types(prev, ty),
+                    None => ty,
+                });
+            }
+        }
+        sampled += 1;
+        Ok(sampled < inference.sample_rows)
+    })?;
+
+    if let Some(unknown) = inference.overrides.keys().find(|c| !columns.contains(c)) {
+        return Err(StorageError::ParseError(format!(
+            "Type override for unknown column '{unknown}'"
+        )));
+    }
+
+    let mut schema = RelationSchema::new(relation);
+    for (i, name) in columns.iter().enumerate() {
+        let data_type = match inference.overrides.get(name) {
+            Some(ty) => ty.clone(),
+            None => inferred
+                .get(i)
+                .cloned()
+                .flatten()
+                .unwrap_or(SchemaType::String),
+        };
+        schema = schema.with_column(ColumnSchema::new(name.clone(), data_type));
+    }
+    Ok(schema)
+}
+
+/// Load a CSV file as a relation with an inferred schema.
+///
+/// Every value is parsed as its column's type, so a value beyond the
+/// sampled rows that does not fit its column fails the load.
+pub fn load_from_csv_inferred<P: AsRef<Path>>(
+    path: P,
+    relation: &str,
+    options: &CsvOptions,
+    inference: &CsvInference,
+) -> StorageResult<(RelationSchema, Vec<Tuple>)> {
+    let path = path.as_ref();
+    let schema = infer_csv_schema(path, relation, options, inference)?;
+    let mut tuples = Vec::new();
+    read_rows(path, options, |row, fields| {
+        let tuple = parse_row(&fields, &schema)
+            .map_err(|e| StorageError::ParseError(format!("Row {row}, {e}")))?;
+        tuples.push(tuple);
+        Ok(true)
+    })?;
+    Ok((schema, tuples))
+}
+
+/// Parse the fields of a row as the columns of `schema`
+fn parse_row(fields: &[&str], schema: &RelationSchema) -> Result<Tuple, String> {
+    if fields.len() != schema.columns.len() {
+        return Err(format!(
+            "{} fields, expected {}",
+            fields.len(),
+            schema.columns.len()
+        ));
+    }
+    let values = fields
+        .iter()
+        .zip(&schema.columns)
+        .map(|(field, column)| {
+            parse_typed(field, &column.data_type).ok_or_else(|| {
+                format!(
+                    "column '{}': cannot parse \"{field}\" as {}",
+                    column.name, column.data_type
+                )
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Tuple::new(values))
+}
+
+/// What a bulk load does with a row it cannot parse
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RowErrorPolicy {
+    /// Leave the row out
+    Skip,
+    /// Leave the row out and report it in [`BulkLoadReport::errors`]
+    Collect,
+    /// Fail the load
+    #[default]
+    Abort,
+}
+
+/// Options for [`load_csv_parallel`]
+#[derive(Debug, Clone)]
+pub struct BulkLoadOptions {
+    /// Rows parsed in parallel and handed on as one batch (default: 100000)
+    pub batch_rows: usize,
+    /// What to do with rows that cannot be parsed (default: abort)
+    pub on_error: RowErrorPolicy,
+    /// Row errors kept under `RowErrorPolicy::Collect` (default: 1000);
+    /// later ones are only counted
+    pub max_errors: usize,
+}
+
+impl Default for BulkLoadOptions {
+    fn default() -> Self {
+        BulkLoadOptions {
+            batch_rows: 100_000,
+            on_error: RowErrorPolicy::Abort,
+            max_errors: 1000,
+        }
+    }
+}
+
+/// A row a bulk load could not parse
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvRowError {
+    /// 1-based line number
+    pub row: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for CsvRowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Row {}, {}", self.row, self.message)
+    }
+}
+
+/// Progress of a bulk load, reported after each batch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BulkLoadProgress {
+    pub bytes_read: u64,
+    pub total_bytes: u64,
+    pub rows_loaded: usize,
+    pub rows_skipped: usize,
+}
+
+/// Outcome of a bulk load
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BulkLoadReport {
+    pub rows_loaded: usize,
+    /// Rows left out under `RowErrorPolicy::Skip` or `Collect`
+    pub rows_skipped: usize,
+    /// The first `max_errors` rows left out under `RowErrorPolicy::Collect`
+    pub errors: Vec<CsvRowError>,
+    pub batches: usize,
+}
+
+/// Load a CSV file in batches of `bulk.batch_rows` rows, parsing the rows
+/// of each batch in parallel as the columns of `schema` while the next
+/// batch is read.
+///
+/// Each batch is passed to `on_batch` in file order, and `on_progress` is
+/// called after it, so a file larger than memory can be loaded. A row that
+/// cannot be parsed, or has the wrong number of fields, is handled as
+/// `bulk.on_error` says; when the load fails the earlier batches have
+/// already been passed on.
+pub fn load_csv_parallel<P: AsRef<Path>>(
+    path: P,
+    schema: &RelationSchema,
+    options: &CsvOptions,
+    bulk: &BulkLoadOptions,
+    mut on_batch: impl FnMut(Vec<Tuple>) -> StorageResult<()>,
+    mut on_progress: impl FnMut(&BulkLoadProgress),
+) -> StorageResult<BulkLoadReport> {
+    let file = File::open(path)?;
+    let total_bytes = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+    let batch_rows = bulk.batch_rows.max(1);
+    let mut next_row = 1;
+    let mut bytes_read = 0;
+    if options.has_header {
+        bytes_read += read_chunk(&mut reader, 1, &mut next_row)?.1;
+    }
+
+    let mut report = BulkLoadReport::default();
+    let (mut chunk, mut chunk_bytes) = read_chunk(&mut reader, batch_rows, &mut next_row)?;
+    while !chunk.is_empty() {
+        let (parsed, next) = rayon::join(
+            || {
+                chunk
+                    .par_iter()
+                    .map(|(row, line)| {
+                        parse_row(&parse_csv_line(line, options), schema)
+                            .map_err(|message| CsvRowError { row: *row, message })
+                    })
+                    .collect::<Vec<_>>()
+            },
+            || read_chunk(&mut reader, batch_rows, &mut next_row),
+        );
+
+        let mut tuples = Vec::with_capacity(parsed.len());
+        for result in parsed {
+            match (result, bulk.on_error) {
+                (Ok(tuple), _) => tuples.push(tuple),
+                (Err(error), RowErrorPolicy::Abort) => {
+                    return Err(StorageError::ParseError(error.to_string()));
+                }
+                (Err(_), RowErrorPolicy::Skip) => report.rows_skipped += 1,
+                (Err(error), RowErrorPolicy::Collect) => {
+                    report.rows_skipped += 1;
+                    if report.errors.len() < bulk.max_errors {
+                        report.errors.push(error);
+                    }
+                }
+            }
+        }
+        report.rows_loaded += tuples.len();
+        report.batches += 1;
+        bytes_read += chunk_bytes;
+        if !tuples.is_empty() {
+            on_batch(tuples)?;
+        }
+        on_progress(&BulkLoadProgress {
+            bytes_read,
+            total_bytes,
+            rows_loaded: report.rows_loaded,
+            rows_skipped: report.rows_skipped,
+        });
+        (chunk, chunk_bytes) = next?;
+    }
+    Ok(report)
+}
+
+/// Read up to `rows` non-empty lines, with their 1-based line numbers,
+/// and the number of bytes read
+fn read_chunk(
+    reader: &mut BufReader<File>,
+    rows: usize,
+    next_row: &mut usize,
+) -> StorageResult<(Vec<(usize, String)>, u64)> {
+    let mut chunk = Vec::with_capacity(rows);
+    let mut bytes_read = 0;
+    while chunk.len() < rows {
+        let mut line = String::new();
+        let read = reader.read_line(&mut line)?;
+        if read == 0 {
+            break;
+        }
+        bytes_read += read as u64;
+        let row = *next_row;
+        *next_row += 1;
+        let len = line.trim_end_matches(['\n', '\r']).len();
+        line.truncate(len);
+        if !line.trim().is_empty() {
+            chunk.push((row, line));
+        }
+    }
+    Ok((chunk, bytes_read))
+}
+
+/// Read the column names of a CSV file and pass each non-empty data row
+/// (with its 1-based line number) to `on_row` until it returns `false`
+fn read_rows<P: AsRef<Path>>(
+    path: P,
+    options: &CsvOptions,
+    mut on_row: impl FnMut(usize, Vec<&str>) -> StorageResult<bool>,
+) -> StorageResult<Vec<String>> {
     let path = path.as_ref();
     let file = File::open(path)?;
     let reader = BufReader::new(file);
 
     let mut lines = reader.lines();
     let mut schema = Vec::new();
-    let mut tuples = Vec::new();
 
     // Read header if present
     if options.has_header {
         if let Some(header_line) = lines.next() {
             let header = header_line?;
-            schema = parse_csv_line(&header, &options)
+            schema = parse_csv_line(&header, options)
                 .into_iter()
                 .map(std::string::ToString::to_string)
                 .collect();
@@ -104,30 +429,29 @@ pub fn load_from_csv_with_options<P: AsRef<Path>>(
             continue;
         }
 
-        let fields = parse_csv_line(&line, &options);
+        let fields = parse_csv_line(&line, options);
 
         // If no header, create schema from first data row
         if schema.is_empty() {
             schema = (0..fields.len()).map(|i| format!("col{i}")).collect();
         }
 
-        // Parse fields into values
-        let values: Vec<Value> = fields.into_iter().map(parse_value).collect();
-
-        if values.len() != schema.len() {
+        if fields.len() != schema.len() {
             return Err(StorageError::ParseError(format!(
                 "Row {} has {} fields, expected {}",
                 row_num,
-                values.len(),
+                fields.len(),
                 schema.len()
             )));
         }
 
-        tuples.push(Tuple::new(values));
+        if !on_row(row_num, fields)? {
+            break;
+        }
         row_num += 1;
     }
 
-    Ok((schema, tuples))
+    Ok(schema)
 }
 
 /// Save tuples to a CSV file
@@ -191,13 +515,23 @@ fn parse_csv_line<'a>(line: &'a str, options: &CsvOptions) -> Vec<&'a str> {
     let mut fields = Vec::new();
     let mut current_start = 0;
     let mut in_quotes = false;
+    // Depth of an unquoted bracketed vector (`[1.0,2.0]`), whose commas
+    // do not split fields
+    let mut bracket_depth = 0usize;
     let chars: Vec<char> = line.chars().collect();
     let mut i = 0;
 
     while i < chars.len() {
         let c = chars[i];
 
-        if c == options.quote_char && !in_quotes {
+        if c == '['
+            && !in_quotes
+            && (bracket_depth > 0 || chars[current_start..i].iter().all(|c| c.is_whitespace()))
+        {
+            bracket_depth += 1;
+        } else if c == ']' && !in_quotes && bracket_depth > 0 {
+            bracket_depth -= 1;
+        } else if c == options.quote_char && !in_quotes {
             in_quotes = true;
             current_start = i + 1;
         } else if c == options.quote_char && in_quotes {
@@ -207,7 +541,7 @@ fn parse_csv_line<'a>(line: &'a str, options: &CsvOptions) -> Vec<&'a str> {
             } else {
                 in_quotes = false;
             }
-        } else if c == options.delimiter && !in_quotes {
+        } else if c == options.delimiter && !in_quotes && bracket_depth == 0 {
             let field = &line[current_start..i];
             let field = if options.trim_whitespace {
                 field.trim()
@@ -273,7 +607,76 @@ fn parse_value(s: &str) -> Value {
     }
 
     // Default to string
-    Value::String(Arc::from(s))
+    Value::interned(s)
+}
+
+/// Parse a bracketed vector (`[0.1, 0.2]`, or `[1, -2]i8` for int8)
+fn parse_vector(s: &str) -> Option<Value> {
+    let s = s.trim();
+    let (body, int8) = match s.strip_suffix("i8") {
+        Some(body) => (body, true),
+        None => (s, false),
+    };
+    let body = body.strip_prefix('[')?.strip_suffix(']')?.trim();
+    let items: Vec<&str> = if body.is_empty() {
+        Vec::new()
+    } else {
+        body.split(',').map(str::trim).collect()
+    };
+    if int8 {
+        let v: Result<Vec<i8>, _> = items.iter().map(|x| x.parse::<i8>()).collect();
+        v.ok().map(|v| Value::VectorInt8(Arc::new(v)))
+    } else {
+        let v: Result<Vec<f32>, _> = items.iter().map(|x| x.parse::<f32>()).collect();
+        v.ok().map(|v| Value::Vector(Arc::new(v)))
+    }
+}
+
+/// Infer the column type of a single field (`None` for nulls)
+fn infer_field_type(s: &str) -> Option<SchemaType> {
+    match parse_value(s) {
+        Value::Null => None,
+        Value::Int32(_) | Value::Int64(_) => Some(SchemaType::Int),
+        Value::Float64(_) => Some(SchemaType::Float),
+        Value::Bool(_) => Some(SchemaType::Bool),
+        _ => Some(match parse_vector(s) {
+            Some(Value::Vector(v)) => SchemaType::Vector { dim: Some(v.len()) },
+            Some(Value::VectorInt8(v)) => SchemaType::Vector { dim: Some(v.len()) },
+            _ if parse_timestamp(s).is_some() => SchemaType::Timestamp,
+            _ => SchemaType::String,
+        }),
+    }
+}
+
+/// Narrowest type covering values of both types
+pub(super) fn merge_types(a: SchemaType, b: SchemaType) -> SchemaType {
+    match (a, b) {
+        (a, b) if a == b => a,
+        (SchemaType::Int, SchemaType::Float) | (SchemaType::Float, SchemaType::Int) => {
+            SchemaType::Float
+        }
+        (SchemaType::Vector { .. }, SchemaType::Vector { .. }) => SchemaType::Vector { dim: None },
+        _ => SchemaType::String,
+    }
+}
+
+/// Parse a field as a value of `data_type` (`None` if it does not fit)
+pub(super) fn parse_typed(s: &str, data_type: &SchemaType) -> Option<Value> {
+    let s = s.trim();
+    if parse_value(s).is_null() {
+        return Some(Value::Null);
+    }
+    let value = match data_type {
+        SchemaType::Int => Value::Int64(s.parse().ok()?),
+        SchemaType::Float => Value::Float64(s.parse().ok()?),
+        SchemaType::String | SchemaType::Any => Value::interned(s),
+        SchemaType::Timestamp => Value::Timestamp(parse_timestamp(s).or_else(|| s.parse().ok())?),
+        SchemaType::Date => Value::Date(parse_date(s)?),
+        SchemaType::Uuid => Value::Uuid(uuid::Uuid::parse_str(s).ok()?),
+        SchemaType::Vector { .. } => parse_vector(s)?,
+        _ => parse_value(s),
+    };
+    data_type.matches(&value).then_some(value)
 }
 
 /// Convert a Value to a CSV field string
@@ -311,11 +714,20 @@ fn value_to_csv(value: &Value, options: &CsvOptions) -> String {
             // Output timestamps as Unix milliseconds
             ts.to_string()
         }
+        Value::Date(d) => crate::temporal_ops::format_date(*d),
+        // Durations as milliseconds, matching timestamps
+        Value::Duration(ms) => ms.to_string(),
+        Value::Uuid(u) => u.hyphenated().to_string(),
+        // Lists and maps in their display form: ["a", 1], {"k": 2}
+        // Bytes as 0x-prefixed hex
+        Value::List(_) | Value::Map(_) | Value::Json(_) | Value::Bytes(_) => {
+            escape_csv_field(&value.to_string(), options)
+        }
     }
 }
 
 /// Escape a CSV field if it contains special characters
-fn escape_csv_field(s: &str, options: &CsvOptions) -> String {
+pub(crate) fn escape_csv_field(s: &str, options: &CsvOptions) -> String {
     let needs_quoting = s.contains(options.delimiter)
         || s.contains(options.quote_char)
         || s.contains('\n')
@@ -336,6 +748,7 @@ fn escape_csv_field(s: &str, options: &CsvOptions) -> String {
 #[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
+    use std::sync::Arc;
     use tempfile::TempDir;
 
     #[test]
@@ -354,8 +767,6 @@ mod tests {
 
     #[test]
     fn test_parse_value_types() {
-        use std::sync::Arc;
-
         assert_eq!(parse_value("42"), Value::Int32(42));
         assert_eq!(parse_value("-123"), Value::Int32(-123));
         assert_eq!(parse_value("3.14"), Value::Float64(3.14));
@@ -501,6 +912,8 @@ mod tests {
             "hello"
         );
         assert_eq!(value_to_csv(&Value::Timestamp(12345), &opts), "12345");
+        assert_eq!(value_to_csv(&Value::Date(0), &opts), "1970-01-01");
+        assert_eq!(value_to_csv(&Value::Duration(1500), &opts), "1500");
     }
 
     #[test]
@@ -617,6 +1030,160 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_parse_csv_line_bracketed_vector() {
+        let options = CsvOptions::default();
+        let fields = parse_csv_line("1,[0.5, 1.5],\"a,b\"", &options);
+        assert_eq!(fields, vec!["1", "[0.5, 1.5]", "a,b"]);
+    }
+
+    #[test]
+    fn test_infer_csv_schema() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("events.csv");
+        std::fs::write(
+            &path,
+            "id,score,active,at,embedding,label,note\n\
+             1,2,true,2024-05-01T00:00:00Z,[0.1,0.2],a,\n\
+             2,2.5,false,2024-05-02 08:30:00,[0.3,0.4],7,\n",
+        )
+        .unwrap();
+
+        let schema = infer_csv_schema(
+            &path,
+            "event",
+            &CsvOptions::default(),
+            &CsvInference::default(),
+        )
+        .unwrap();
+        let types: Vec<String> = schema
+            .columns
+            .iter()
+            .map(|c| c.data_type.to_string())
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                "int",
+                "float",
+                "bool",
+                "timestamp",
+                "vector<2>",
+                "string",
+                "string"
+            ]
+        );
+
+        let inference = CsvInference::default().with_override("id", SchemaType::String);
+        let schema = infer_csv_schema(&path, "event", &CsvOptions::default(), &inference).unwrap();
+        assert_eq!(schema.columns[0].data_type, SchemaType::String);
+
+        let bad = CsvInference::default().with_override("missing", SchemaType::Int);
+        assert!(infer_csv_schema(&path, "event", &CsvOptions::default(), &bad).is_err());
+    }
+
+    #[test]
+    fn test_load_from_csv_inferred() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("points.csv");
+        std::fs::write(&path, "id,v\n1,[1.0,2.0]\n2,[3.0,4.0]\n").unwrap();
+
+        let (schema, tuples) = load_from_csv_inferred(
+            &path,
+            "point",
+            &CsvOptions::default(),
+            &CsvInference::default(),
+        )
+        .unwrap();
+        assert_eq!(schema.name, "point");
+        assert!(matches!(tuples[0].get(0), Some(Value::Int64(1))));
+        assert_eq!(
+            tuples[1].get(1),
+            Some(&Value::Vector(Arc::new(vec![3.0, 4.0])))
+        );
+
+        // Only the first row is sampled; the second does not fit `int`
+        std::fs::write(&path, "id\n1\nx\n").unwrap();
+        let inference = CsvInference {
+            sample_rows: 1,
+            ..Default::default()
+        };
+        let err = load_from_csv_inferred(&path, "point", &CsvOptions::default(), &inference)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("Row 3, column 'id'"),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn test_load_csv_parallel() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("edges.csv");
+        let content = "src,dst\n1,2\n2,x\n\n3,4\n4\n5,6\n";
+        std::fs::write(&path, content).unwrap();
+        let schema = RelationSchema::new("edge")
+            .with_column(ColumnSchema::new("src", SchemaType::Int))
+            .with_column(ColumnSchema::new("dst", SchemaType::Int));
+        let load = |on_error| {
+            let bulk = BulkLoadOptions {
+                batch_rows: 2,
+                on_error,
+                max_errors: 1,
+            };
+            let mut batches = Vec::new();
+            let mut progress = Vec::new();
+            let report = load_csv_parallel(
+                &path,
+                &schema,
+                &CsvOptions::default(),
+                &bulk,
+                |batch| {
+                    batches.push(batch);
+                    Ok(())
+                },
+                |p| progress.push(*p),
+            );
+            (report, batches, progress)
+        };
+
+        // Bad rows are left out, in file order, and the last one is only counted
+        let (report, batches, progress) = load(RowErrorPolicy::Collect);
+        let report = report.unwrap();
+        assert_eq!(report.rows_loaded, 3);
+        assert_eq!(report.rows_skipped, 2);
+        assert_eq!(report.batches, 3);
+        assert_eq!(
+            report.errors,
+            vec![CsvRowError {
+                row: 3,
+                message: "column 'dst': cannot parse \"x\" as int".to_string(),
+            }]
+        );
+        let loaded: Vec<Tuple> = batches.into_iter().flatten().collect();
+        assert_eq!(loaded[0].get(0), Some(&Value::Int64(1)));
+        assert_eq!(loaded[2].get(1), Some(&Value::Int64(6)));
+        let last = progress.last().unwrap();
+        assert_eq!(last.bytes_read, content.len() as u64);
+        assert_eq!(last.total_bytes, content.len() as u64);
+        assert_eq!(last.rows_loaded, 3);
+
+        let (report, _, _) = load(RowErrorPolicy::Skip);
+        let report = report.unwrap();
+        assert_eq!(report.rows_skipped, 2);
+        assert!(report.errors.is_empty());
+
+        // A bad row fails the load before its batch is handed on
+        let (report, batches, _) = load(RowErrorPolicy::Abort);
+        let err = report.unwrap_err().to_string();
+        assert!(
+            err.contains("Row 3, column 'dst'"),
+            "unexpected error: {err}"
+        );
+        assert!(batches.is_empty());
+    }
+
     #[test]
     fn test_parse_csv_line_with_whitespace() {
         let options = CsvOptions::default();
@@ -18,10 +18,32 @@ pub enum StorageError {
     #[error("Arrow error: {0}")]
     Arrow(#[from] arrow::error::ArrowError),
 
+    /// Avro error
+    #[error("Avro error: {0}")]
+    Avro(#[from] apache_avro::Error),
+
+    /// SQLite error
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    /// PostgreSQL error
+    #[cfg(feature = "postgres")]
+    #[error("PostgreSQL error: {0}")]
+    Postgres(#[from] postgres::Error),
+
+    /// Object store error
+    #[error("Object store error: {0}")]
+    ObjectStore(#[from] object_store::Error),
+
     /// JSON serialization error
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
 
+    /// Encryption at rest error: no key for an encrypted file, or a file
+    /// that fails authentication
+    #[error("Encryption error: {0}")]
+    Encryption(String),
+
     /// Knowledge graph not found
     #[error("Knowledge graph not found: {0}")]
     KnowledgeGraphNotFound(String),
@@ -85,6 +107,10 @@ pub enum StorageError {
     /// The DD background worker thread terminated unexpectedly.
     #[error("DD worker disconnected")]
     DDWorkerDisconnected,
+
+    /// Write rejected by schema validation; carries the violations
+    #[error("{0}")]
+    Validation(#[from] crate::schema::ValidationError),
 }
 
 /// Result type for storage operations
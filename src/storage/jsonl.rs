@@ -0,0 +1,504 @@
+//! JSON Lines Storage Module
+//!
+//! Loads and saves relations as JSON Lines: one JSON object per line, the
+//! usual shape of event logs and LLM traces.
+//!
+//! ## Field Mapping
+//!
+//! Each column reads a field path; dots name nested fields (`user.id`).
+//! Without an explicit mapping every field becomes a column, with nested
+//! objects flattened into `parent.child` columns, ordered by first
+//! appearance (the fields of one line alphabetically). Saving reverses this:
+//! a column named `user.id` is written as `{"user": {"id": ...}}`.
+//!
+//! ## Types
+//!
+//! Column types are inferred from a sample of the lines: JSON integers
+//! are `int`, other numbers `float`, arrays of numbers `vector`, other
+//! arrays `list`, objects `json`, and strings `timestamp` when every
+//! sampled value parses as one, `string` otherwise.
+//!
+//! ## Example
+//!
+//! ```text
+//! {"id": 1, "user": {"name": "alice"}, "embedding": [0.1, 0.2]}
+//! {"id": 2, "user": {"name": "bob"}, "embedding": [0.3, 0.4]}
+//! ```
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use serde_json::{Map, Value as Json};
+
+use crate::schema::{ColumnSchema, RelationSchema, SchemaType};
+use crate::storage::csv::merge_types;
+use crate::storage::error::{StorageError, StorageResult};
+use crate::temporal_ops::{format_date, parse_date, parse_timestamp};
+use crate::value::{Tuple, Value};
+
+/// Options for reading JSON Lines files
+#[derive(Debug, Clone)]
+pub struct JsonlOptions {
+    /// Columns to read as (column name, field path) pairs. When empty,
+    /// every field of the sampled lines is read, nested objects flattened.
+    pub columns: Vec<(String, String)>,
+    /// Number of lines sampled to discover fields and infer column types
+    /// (default: 1000)
+    pub sample_rows: usize,
+    /// Column types to use instead of inferred ones, by column name
+    pub overrides: HashMap<String, SchemaType>,
+}
+
+impl Default for JsonlOptions {
+    fn default() -> Self {
+        JsonlOptions {
+            columns: Vec::new(),
+            sample_rows: 1000,
+            overrides: HashMap::new(),
+        }
+    }
+}
+
+impl JsonlOptions {
+    /// Read the field at `path` into `column`
+    pub fn with_column(mut self, column: impl Into<String>, path: impl Into<String>) -> Self {
+        self.columns.push((column.into(), path.into()));
+        self
+    }
+
+    /// Use `data_type` for `column` instead of inferring it
+    pub fn with_override(mut self, column: impl Into<String>, data_type: SchemaType) -> Self {
+        self.overrides.insert(column.into(), data_type);
+        self
+    }
+}
+
+/// Load a JSON Lines file as a relation with an inferred schema.
+///
+/// Missing fields load as null. Without an explicit column mapping,
+/// fields that first appear after the sampled lines are not read. A value
+/// that does not fit its column's type fails the load.
+pub fn load_from_jsonl<P: AsRef<Path>>(
+    path: P,
+    relation: &str,
+    options: &JsonlOptions,
+) -> StorageResult<(RelationSchema, Vec<Tuple>)> {
+    let path = path.as_ref();
+    let schema = infer_jsonl_schema(path, relation, options)?;
+    let paths = column_paths(&schema, options);
+    let mut tuples = Vec::new();
+    read_objects(path, |line, object| {
+        let values = paths
+            .iter()
+            .zip(&schema.columns)
+            .map(|(path, column)| {
+                let field = lookup(object, path).unwrap_or(&Json::Null);
+                json_to_typed(field, &column.data_type).ok_or_else(|| {
+                    StorageError::ParseError(format!(
+                        "Line {line}, column '{}': cannot read {field} as {}",
+                        column.name, column.data_type
+                    ))
+                })
+            })
+            .collect::<StorageResult<Vec<_>>>()?;
+        tuples.push(Tuple::new(values));
+        Ok(true)
+    })?;
+    Ok((schema, tuples))
+}
+
+/// Infer a relation schema for a JSON Lines file from its first
+/// `options.sample_rows` lines.
+///
+/// Columns whose sampled values are all null or missing are inferred as
+/// `string`.
+pub fn infer_jsonl_schema<P: AsRef<Path>>(
+    path: P,
+    relation: &str,
+    options: &JsonlOptions,
+) -> StorageResult<RelationSchema> {
+    let mut columns: Vec<String> = options.columns.iter().map(|(c, _)| c.clone()).collect();
+    let mut inferred: HashMap<String, SchemaType> = HashMap::new();
+    let mut sampled = 0;
+    read_objects(path, |_, object| {
+        let mut fields = Vec::new();
+        if options.columns.is_empty() {
+            flatten("", object, &mut fields);
+        } else {
+            for (column, path) in &options.columns {
+                if let Some(field) = lookup(object, path) {
+                    fields.push((column.clone(), field));
+                }
+            }
+        }
+        for (column, field) in fields {
+            if !columns.contains(&column) {
+                columns.push(column.clone());
+            }
+            if let Some(ty) = infer_json_type(field) {
+                let merged = match inferred.remove(&column) {
+                    Some(prev) => merge_types(prev, ty),
+                    None => ty,
+                };
+                inferred.insert(column, merged);
+            }
+        }
+        sampled += 1;
+        Ok(sampled < options.sample_rows)
+    })?;
+
+    if let Some(unknown) = options.overrides.keys().find(|c| !columns.contains(c)) {
+        return Err(StorageError::ParseError(format!(
+            "Type override for unknown column '{unknown}'"
+        )));
+    }
+
+    let mut schema = RelationSchema::new(relation);
+    for name in columns {
+        let data_type = match options.overrides.get(&name) {
+            Some(ty) => ty.clone(),
+            None => inferred.remove(&name).unwrap_or(SchemaType::String),
+        };
+        schema = schema.with_column(ColumnSchema::new(name, data_type));
+    }
+    Ok(schema)
+}
+
+/// Save tuples to a JSON Lines file, one object per tuple. Dotted column
+/// names are written as nested objects; null values are written as `null`.
+pub fn save_to_jsonl<P: AsRef<Path>>(
+    path: P,
+    columns: &[String],
+    tuples: &[Tuple],
+) -> StorageResult<()> {
+    let path = path.as_ref();
+
+    // Create parent directories if needed
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+    for tuple in tuples {
+        let mut object = Map::new();
+        for (column, value) in columns.iter().zip(tuple.values()) {
+            insert_path(&mut object, column, value_to_json(value));
+        }
+        serde_json::to_writer(&mut writer, &object)?;
+        writeln!(writer)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Field path each column of `schema` reads: its mapped path, or its
+/// own (flattened) name
+fn column_paths(schema: &RelationSchema, options: &JsonlOptions) -> Vec<String> {
+    schema
+        .columns
+        .iter()
+        .map(|column| {
+            options
+                .columns
+                .iter()
+                .find(|(name, _)| *name == column.name)
+                .map_or_else(|| column.name.clone(), |(_, path)| path.clone())
+        })
+        .collect()
+}
+
+/// Pass each non-empty line of a JSON Lines file, parsed as an object,
+/// to `on_object` (with its 1-based line number) until it returns `false`
+fn read_objects<P: AsRef<Path>>(
+    path: P,
+    mut on_object: impl FnMut(usize, &Map<String, Json>) -> StorageResult<bool>,
+) -> StorageResult<()> {
+    let reader = BufReader::new(File::open(path)?);
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let line_num = i + 1;
+        let doc: Json = serde_json::from_str(&line)
+            .map_err(|e| StorageError::ParseError(format!("Line {line_num}: {e}")))?;
+        let Json::Object(object) = doc else {
+            return Err(StorageError::ParseError(format!(
+                "Line {line_num}: expected a JSON object"
+            )));
+        };
+        if !on_object(line_num, &object)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Collect the leaf fields of `object` as (dotted path, value) pairs.
+/// Empty objects are leaves.
+fn flatten<'a>(prefix: &str, object: &'a Map<String, Json>, out: &mut Vec<(String, &'a Json)>) {
+    for (key, value) in object {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match value {
+            Json::Object(inner) if !inner.is_empty() => flatten(&path, inner, out),
+            _ => out.push((path, value)),
+        }
+    }
+}
+
+/// Field at a dotted path, preferring a key that contains the dots
+/// literally
+pub(crate) fn lookup<'a>(object: &'a Map<String, Json>, path: &str) -> Option<&'a Json> {
+    if let Some(value) = object.get(path) {
+        return Some(value);
+    }
+    let (head, rest) = path.split_once('.')?;
+    match object.get(head)? {
+        Json::Object(inner) => lookup(inner, rest),
+        _ => None,
+    }
+}
+
+/// Put `value` at a dotted path, creating nested objects. A path that
+/// runs into a non-object field is written under its full dotted name.
+fn insert_path(object: &mut Map<String, Json>, path: &str, value: Json) {
+    let mut parts: Vec<&str> = path.split('.').collect();
+    let Some(last) = parts.pop() else {
+        return;
+    };
+    let mut target = &mut *object;
+    for part in &parts {
+        let nested = target
+            .entry((*part).to_string())
+            .or_insert_with(|| Json::Object(Map::new()));
+        match nested {
+            Json::Object(inner) => target = inner,
+            _ => {
+                object.insert(path.to_string(), value);
+                return;
+            }
+        }
+    }
+    target.insert(last.to_string(), value);
+}
+
+/// Infer the column type of a single JSON value (`None` for null)
+fn infer_json_type(value: &Json) -> Option<SchemaType> {
+    Some(match value {
+        Json::Null => return None,
+        Json::Bool(_) => SchemaType::Bool,
+        Json::Number(n) if n.is_i64() => SchemaType::Int,
+        Json::Number(_) => SchemaType::Float,
+        Json::String(s) if parse_timestamp(s).is_some() => SchemaType::Timestamp,
+        Json::String(_) => SchemaType::String,
+        Json::Array(items) if !items.is_empty() && items.iter().all(Json::is_number) => {
+            SchemaType::Vector {
+                dim: Some(items.len()),
+            }
+        }
+        Json::Array(_) => SchemaType::List,
+        Json::Object(_) => SchemaType::Json,
+    })
+}
+
+/// Convert a JSON value to a value of `data_type` (`None` if it does not
+/// fit)
+pub(crate) fn json_to_typed(value: &Json, data_type: &SchemaType) -> Option<Value> {
+    if value.is_null() {
+        return Some(Value::Null);
+    }
+    let converted = match (data_type, value) {
+        (SchemaType::Int, Json::Number(n)) => Value::Int64(n.as_i64()?),
+        (SchemaType::Float, Json::Number(n)) => Value::Float64(n.as_f64()?),
+        (SchemaType::String, Json::String(s)) => Value::interned(s),
+        // Columns mixing strings with other values keep their JSON text
+        (SchemaType::String, other) => Value::string(&other.to_string()),
+        (SchemaType::Timestamp, Json::String(s)) => Value::Timestamp(parse_timestamp(s)?),
+        (SchemaType::Timestamp, Json::Number(n)) => Value::Timestamp(n.as_i64()?),
+        (SchemaType::Date, Json::String(s)) => Value::Date(parse_date(s)?),
+        (SchemaType::Uuid, Json::String(s)) => Value::Uuid(uuid::Uuid::parse_str(s).ok()?),
+        (SchemaType::Vector { .. }, Json::Array(items)) => {
+            let v: Option<Vec<f32>> = items.iter().map(|x| x.as_f64().map(|f| f as f32)).collect();
+            Value::Vector(Arc::new(v?))
+        }
+        (SchemaType::Json, doc) => Value::from_json_doc(doc),
+        (_, other) => json_to_value(other),
+    };
+    data_type.matches(&converted).then_some(converted)
+}
+
+/// Convert a JSON value without a target type: arrays become lists and
+/// objects maps
+fn json_to_value(value: &Json) -> Value {
+    match value {
+        Json::Null => Value::Null,
+        Json::Bool(b) => Value::Bool(*b),
+        Json::Number(n) => n.as_i64().map_or_else(
+            || Value::Float64(n.as_f64().unwrap_or(f64::NAN)),
+            Value::Int64,
+        ),
+        Json::String(s) => Value::interned(s),
+        Json::Array(items) => Value::list(items.iter().map(json_to_value).collect()),
+        Json::Object(entries) => Value::Map(Arc::new(
+            entries
+                .iter()
+                .map(|(k, v)| (k.clone(), json_to_value(v)))
+                .collect(),
+        )),
+    }
+}
+
+/// Convert a value to JSON. Timestamps and durations are written as
+/// milliseconds, dates as `YYYY-MM-DD` and bytes as `0x` hex, as in CSV.
+pub(crate) fn value_to_json(value: &Value) -> Json {
+    match value {
+        Value::Int32(i) => Json::from(*i),
+        Value::Int64(i) | Value::Timestamp(i) | Value::Duration(i) => Json::from(*i),
+        // NaN and infinities have no JSON form and are written as null
+        Value::Float64(f) => serde_json::Number::from_f64(*f).map_or(Json::Null, Json::Number),
+        Value::String(s) => Json::from(&**s),
+        Value::Bool(b) => Json::Bool(*b),
+        Value::Null => Json::Null,
+        Value::Vector(v) => Json::Array(
+            v.iter()
+                .map(|x| {
+                    serde_json::Number::from_f64(f64::from(*x)).map_or(Json::Null, Json::Number)
+                })
+                .collect(),
+        ),
+        Value::VectorInt8(v) => Json::Array(v.iter().map(|x| Json::from(*x)).collect()),
+        Value::Date(d) => Json::from(format_date(*d)),
+        Value::Uuid(u) => Json::from(u.hyphenated().to_string()),
+        Value::List(items) => Json::Array(items.iter().map(value_to_json).collect()),
+        Value::Map(entries) => Json::Object(
+            entries
+                .iter()
+                .map(|(k, v)| (k.clone(), value_to_json(v)))
+                .collect(),
+        ),
+        Value::Json(text) => serde_json::from_str(text).unwrap_or_else(|_| Json::from(&**text)),
+        Value::Bytes(_) => Json::from(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_lines(dir: &TempDir, lines: &[&str]) -> std::path::PathBuf {
+        let path = dir.path().join("events.jsonl");
+        std::fs::write(&path, lines.join("\n")).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_load_flattens_nested_fields() {
+        let temp = TempDir::new().unwrap();
+        let path = write_lines(
+            &temp,
+            &[
+                r#"{"id": 1, "user": {"name": "alice", "age": 30}, "emb": [0.5, 1]}"#,
+                "",
+                r#"{"id": 2, "user": {"name": "bob"}, "emb": [1.5, 2], "score": 0.5}"#,
+            ],
+        );
+
+        let (schema, tuples) = load_from_jsonl(&path, "event", &JsonlOptions::default()).unwrap();
+        assert_eq!(
+            schema.column_names(),
+            vec!["emb", "id", "user.age", "user.name", "score"]
+        );
+        assert_eq!(schema.columns[1].data_type, SchemaType::Int);
+        assert_eq!(
+            schema.columns[0].data_type,
+            SchemaType::Vector { dim: Some(2) }
+        );
+        assert_eq!(tuples.len(), 2);
+        assert_eq!(tuples[0].get(3), Some(&Value::string("alice")));
+        assert_eq!(tuples[0].get(0), Some(&Value::vector(vec![0.5, 1.0])));
+        assert_eq!(tuples[1].get(2), Some(&Value::Null));
+        assert_eq!(tuples[0].get(4), Some(&Value::Null));
+    }
+
+    #[test]
+    fn test_load_with_column_mapping() {
+        let temp = TempDir::new().unwrap();
+        let path = write_lines(
+            &temp,
+            &[
+                r#"{"trace": {"id": "t1", "ts": "2024-01-01T00:00:00Z"}, "tokens": 12}"#,
+                r#"{"trace": {"id": "t2", "ts": "2024-01-02T00:00:00Z"}, "tokens": 7}"#,
+            ],
+        );
+        let options = JsonlOptions::default()
+            .with_column("trace_id", "trace.id")
+            .with_column("at", "trace.ts")
+            .with_column("tokens", "tokens")
+            .with_override("tokens", SchemaType::Float);
+
+        let (schema, tuples) = load_from_jsonl(&path, "trace", &options).unwrap();
+        assert_eq!(schema.column_names(), vec!["trace_id", "at", "tokens"]);
+        assert_eq!(schema.columns[1].data_type, SchemaType::Timestamp);
+        assert_eq!(tuples[1].get(0), Some(&Value::string("t2")));
+        assert_eq!(tuples[1].get(2), Some(&Value::Float64(7.0)));
+    }
+
+    #[test]
+    fn test_load_rejects_bad_lines() {
+        let temp = TempDir::new().unwrap();
+        let path = write_lines(&temp, &[r#"{"id": 1}"#, "[1, 2]"]);
+        let err = load_from_jsonl(&path, "r", &JsonlOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("Line 2"));
+
+        // A value past the sampled lines that does not fit its column
+        let path = write_lines(&temp, &[r#"{"id": 1}"#, r#"{"id": "x"}"#]);
+        let options = JsonlOptions {
+            sample_rows: 1,
+            ..JsonlOptions::default()
+        };
+        assert!(load_from_jsonl(&path, "r", &options).is_err());
+    }
+
+    #[test]
+    fn test_jsonl_roundtrip_nests_dotted_columns() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("out").join("rows.jsonl");
+        let columns = vec!["id".to_string(), "user.name".to_string(), "emb".to_string()];
+        let tuples = vec![
+            Tuple::new(vec![
+                Value::Int64(1),
+                Value::string("alice"),
+                Value::vector(vec![0.25, 0.5]),
+            ]),
+            Tuple::new(vec![
+                Value::Int64(2),
+                Value::Null,
+                Value::vector(vec![1.0, 2.0]),
+            ]),
+        ];
+        save_to_jsonl(&path, &columns, &tuples).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let first: Json = serde_json::from_str(content.lines().next().unwrap()).unwrap();
+        assert_eq!(first["user"]["name"], "alice");
+
+        let options = columns
+            .iter()
+            .fold(JsonlOptions::default(), |options, column| {
+                options.with_column(column.clone(), column.clone())
+            });
+        let (schema, loaded) = load_from_jsonl(&path, "r", &options).unwrap();
+        assert_eq!(schema.column_names(), columns);
+        assert_eq!(loaded, tuples);
+    }
+}
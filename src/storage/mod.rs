@@ -4,6 +4,12 @@
 //! - DD-native persistence with (data, time, diff) triples
 //! - Parquet serialization (columnar, compressed, efficient for analytics)
 //! - CSV serialization (human-readable, interoperable)
+//! - JSON Lines import/export (event logs, traces)
+//! - Avro container file import/export (Kafka archives)
+//! - SQLite table import (seed data)
+//! - PostgreSQL bulk ingestion over `COPY` (`postgres` feature)
+//! - Object store (S3, GCS) mirroring of persist files
+//! - Encryption at rest (AES-256-GCM) of persist files
 //! - Metadata management
 //! - Error handling
 //!
@@ -18,27 +24,46 @@
 //!
 //! - Parquet: Best for large datasets, analytics workloads, and production use
 //! - CSV: Best for data exchange, debugging, and human inspection
+//! - JSON Lines: Best for nested records such as event logs and LLM traces
+//! - Avro: Best for records archived from Kafka topics
+//! - SQLite: Import only, for seed data kept in small database files
 
+pub mod avro;
 pub mod csv;
 pub mod error;
+pub mod jsonl;
 pub mod metadata;
+pub mod object_store;
 pub mod parquet;
 pub mod persist;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod sqlite;
 pub mod wal;
 
 // Re-export commonly used types
+pub use self::object_store::{
+    open_object_store, LocalObjectStore, ObjectStore, RemoteObjectStore, StoreMirror,
+};
+#[cfg(feature = "postgres")]
+pub use self::postgres::{copy_from_postgres, PostgresOptions, PostgresSource};
+pub use avro::{avro_to_relation_schema, load_from_avro, save_to_avro};
 pub use csv::{
-    load_from_csv, load_from_csv_with_options, save_to_csv, save_to_csv_with_options, CsvOptions,
+    infer_csv_schema, load_csv_parallel, load_from_csv, load_from_csv_inferred,
+    load_from_csv_with_options, save_to_csv, save_to_csv_with_options, BulkLoadOptions,
+    BulkLoadProgress, BulkLoadReport, CsvInference, CsvOptions, CsvRowError, RowErrorPolicy,
 };
 pub use error::{StorageError, StorageResult};
+pub use jsonl::{infer_jsonl_schema, load_from_jsonl, save_to_jsonl, JsonlOptions};
 pub use metadata::{
     KnowledgeGraphInfo, KnowledgeGraphMetadata, KnowledgeGraphsMetadata, RelationMetadata,
 };
 pub use parquet::{load_from_parquet, save_to_parquet};
+pub use sqlite::{load_from_sqlite, read_sqlite_batches, sqlite_table_schema, SqliteOptions};
 pub use wal::{replay_wal, Wal, WalEntry, WalOp};
 
 // Re-export persist types
 pub use persist::{
-    consolidate, consolidate_to_current, to_tuples, Batch, BatchRef, FilePersist, PersistBackend,
-    PersistConfig, PersistWal, ShardInfo, ShardMeta, Update,
+    consolidate, consolidate_to_current, to_tuples, Batch, BatchRef, Encryption, FilePersist,
+    PersistBackend, PersistConfig, PersistWal, ShardInfo, ShardMeta, Update,
 };
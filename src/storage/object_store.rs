@@ -0,0 +1,441 @@
+//! Object Store Backend
+//!
+//! Lets the persist layer keep its files in S3, GCS or any S3-compatible
+//! store, so a server can start on an empty disk and pick up where
+//! another left off.
+//!
+//! ## Model
+//!
+//! The local persist directory stays the working copy and acts as a
+//! cache. A [`StoreMirror`] uploads new and changed files under it after
+//! each flush and WAL sync (deleting remote copies of files removed
+//! locally), and fills an empty directory from the store on startup.
+//! The store therefore trails the local disk by the writes since the
+//! last sync.
+//!
+//! Keys are file paths relative to the persist directory, with `/`
+//! separators (`batches/42.parquet`, `shards/default_edge.json`,
+//! `wal/current.wal`).
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use ::object_store::aws::AmazonS3Builder;
+use ::object_store::gcp::GoogleCloudStorageBuilder;
+use ::object_store::path::Path as StorePath;
+use ::object_store::PutPayload;
+use futures_util::TryStreamExt;
+use parking_lot::Mutex;
+
+use crate::config::ObjectStoreConfig;
+use crate::storage::error::{StorageError, StorageResult};
+
+/// Flat key-value store of files
+pub trait ObjectStore: Send + Sync + fmt::Debug {
+    /// Write an object, replacing any existing one
+    fn put(&self, key: &str, data: &[u8]) -> StorageResult<()>;
+
+    /// Read an object (`None` if it does not exist)
+    fn get(&self, key: &str) -> StorageResult<Option<Vec<u8>>>;
+
+    /// Delete an object; deleting a missing object is not an error
+    fn delete(&self, key: &str) -> StorageResult<()>;
+
+    /// Keys of all objects starting with `prefix`
+    fn list(&self, prefix: &str) -> StorageResult<Vec<String>>;
+}
+
+/// Open the store described by `config`
+pub fn open_object_store(config: &ObjectStoreConfig) -> StorageResult<Arc<dyn ObjectStore>> {
+    let (scheme, rest) = config.url.split_once("://").ok_or_else(|| {
+        StorageError::Other(format!(
+            "Invalid object store URL '{}': expected s3://, gs:// or file://",
+            config.url
+        ))
+    })?;
+    let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+    let inner: Arc<dyn ::object_store::ObjectStore> = match scheme {
+        "file" => return Ok(Arc::new(LocalObjectStore::new(PathBuf::from(rest)))),
+        "s3" | "s3a" => {
+            let mut builder = AmazonS3Builder::from_env()
+                .with_bucket_name(bucket)
+                .with_allow_http(config.allow_http);
+            if let Some(region) = &config.region {
+                builder = builder.with_region(region);
+            }
+            if let Some(endpoint) = &config.endpoint {
+                builder = builder.with_endpoint(endpoint);
+            }
+            if let Some(key_id) = &config.access_key_id {
+                builder = builder.with_access_key_id(key_id);
+            }
+            if let Some(secret) = &config.secret_access_key {
+                builder = builder.with_secret_access_key(secret);
+            }
+            Arc::new(builder.build()?)
+        }
+        "gs" => {
+            let mut builder = GoogleCloudStorageBuilder::from_env().with_bucket_name(bucket);
+            if let Some(path) = &config.service_account_path {
+                builder = builder.with_service_account_path(path.to_string_lossy());
+            }
+            Arc::new(builder.build()?)
+        }
+        other => {
+            return Err(StorageError::Other(format!(
+                "Unsupported object store scheme '{other}': expected s3, gs or file"
+            )))
+        }
+    };
+    Ok(Arc::new(RemoteObjectStore::new(
+        inner,
+        &config.url,
+        prefix,
+    )?))
+}
+
+/// Object store backed by a local directory (or a mounted network share)
+#[derive(Debug)]
+pub struct LocalObjectStore {
+    root: PathBuf,
+}
+
+impl LocalObjectStore {
+    /// Store objects as files under `root`
+    pub fn new(root: PathBuf) -> Self {
+        LocalObjectStore { root }
+    }
+}
+
+impl ObjectStore for LocalObjectStore {
+    fn put(&self, key: &str, data: &[u8]) -> StorageResult<()> {
+        let path = self.root.join(key);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("upload");
+        fs::write(&tmp, data)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> StorageResult<Option<Vec<u8>>> {
+        match fs::read(self.root.join(key)) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn delete(&self, key: &str) -> StorageResult<()> {
+        match fs::remove_file(self.root.join(key)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn list(&self, prefix: &str) -> StorageResult<Vec<String>> {
+        let mut keys: Vec<String> = list_files(&self.root)?
+            .into_iter()
+            .filter(|key| key.starts_with(prefix))
+            .collect();
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+/// Object store reached over the network (S3, GCS)
+pub struct RemoteObjectStore {
+    inner: Arc<dyn ::object_store::ObjectStore>,
+    url: String,
+    prefix: String,
+    runtime: tokio::runtime::Runtime,
+}
+
+impl fmt::Debug for RemoteObjectStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteObjectStore")
+            .field("url", &self.url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RemoteObjectStore {
+    /// Wrap a store, keeping objects under `prefix`
+    pub fn new(
+        inner: Arc<dyn ::object_store::ObjectStore>,
+        url: &str,
+        prefix: &str,
+    ) -> StorageResult<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        Ok(RemoteObjectStore {
+            inner,
+            url: url.to_string(),
+            prefix: prefix.trim_matches('/').to_string(),
+            runtime,
+        })
+    }
+
+    fn path(&self, key: &str) -> StorePath {
+        if self.prefix.is_empty() {
+            StorePath::from(key)
+        } else {
+            StorePath::from(format!("{}/{key}", self.prefix))
+        }
+    }
+
+    /// Run a request to completion. The runtime is driven from a scoped
+    /// thread, since callers may already be on a runtime thread.
+    fn block_on<F>(&self, request: F) -> StorageResult<F::Output>
+    where
+        F: Future + Send,
+        F::Output: Send,
+    {
+        std::thread::scope(|s| s.spawn(|| self.runtime.block_on(request)).join())
+            .map_err(|_| StorageError::Other(format!("Request to {} panicked", self.url)))
+    }
+}
+
+impl ObjectStore for RemoteObjectStore {
+    fn put(&self, key: &str, data: &[u8]) -> StorageResult<()> {
+        let path = self.path(key);
+        let payload = PutPayload::from(data.to_vec());
+        self.block_on(self.inner.put(&path, payload))??;
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> StorageResult<Option<Vec<u8>>> {
+        let path = self.path(key);
+        self.block_on(async {
+            match self.inner.get(&path).await {
+                Ok(result) => Ok(Some(result.bytes().await?.to_vec())),
+                Err(::object_store::Error::NotFound { .. }) => Ok(None),
+                Err(e) => Err(e.into()),
+            }
+        })?
+    }
+
+    fn delete(&self, key: &str) -> StorageResult<()> {
+        let path = self.path(key);
+        match self.block_on(self.inner.delete(&path))? {
+            Ok(()) | Err(::object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn list(&self, prefix: &str) -> StorageResult<Vec<String>> {
+        let root = self.path("");
+        let objects =
+            self.block_on(async { self.inner.list(Some(&root)).try_collect::<Vec<_>>().await })??;
+        let strip = if self.prefix.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", self.prefix)
+        };
+        let mut keys: Vec<String> = objects
+            .into_iter()
+            .filter_map(|meta| {
+                let key = meta.location.as_ref().strip_prefix(&strip)?.to_string();
+                key.starts_with(prefix).then_some(key)
+            })
+            .collect();
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+/// Keeps a local directory and an object store in step
+#[derive(Debug)]
+pub struct StoreMirror {
+    store: Arc<dyn ObjectStore>,
+    root: PathBuf,
+    /// Size and modification time of each file when it was last uploaded
+    uploaded: Mutex<HashMap<String, (u64, SystemTime)>>,
+}
+
+impl StoreMirror {
+    /// Mirror the files under `root` to `store`
+    pub fn new(store: Arc<dyn ObjectStore>, root: PathBuf) -> Self {
+        StoreMirror {
+            store,
+            root,
+            uploaded: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Download every object missing locally. Returns the number of files
+    /// written.
+    pub fn restore(&self) -> StorageResult<usize> {
+        let mut restored = 0;
+        let mut uploaded = self.uploaded.lock();
+        for key in self.store.list("")? {
+            let path = self.root.join(&key);
+            if path.exists() {
+                continue;
+            }
+            let Some(data) = self.store.get(&key)? else {
+                continue;
+            };
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, data)?;
+            if let Some(stamp) = file_stamp(&path) {
+                uploaded.insert(key, stamp);
+            }
+            restored += 1;
+        }
+        Ok(restored)
+    }
+
+    /// Upload files under the `dirs` subdirectories that are new or changed
+    /// since their last upload, and delete objects whose files are gone.
+    /// Returns the number of files uploaded.
+    pub fn push(&self, dirs: &[&str]) -> StorageResult<usize> {
+        let mut pushed = 0;
+        let mut uploaded = self.uploaded.lock();
+        for dir in dirs {
+            let prefix = format!("{dir}/");
+            let local: Vec<String> = list_files(&self.root.join(dir))?
+                .into_iter()
+                .map(|key| format!("{prefix}{key}"))
+                .filter(|key| Path::new(key).extension().is_none_or(|ext| ext != "tmp"))
+                .collect();
+            for key in &local {
+                let path = self.root.join(key);
+                let Some(stamp) = file_stamp(&path) else {
+                    continue;
+                };
+                if uploaded.get(key) == Some(&stamp) {
+                    continue;
+                }
+                self.store.put(key, &fs::read(&path)?)?;
+                uploaded.insert(key.clone(), stamp);
+                pushed += 1;
+            }
+            for key in self.store.list(&prefix)? {
+                if !local.contains(&key) {
+                    self.store.delete(&key)?;
+                    uploaded.remove(&key);
+                }
+            }
+        }
+        Ok(pushed)
+    }
+}
+
+/// Size and modification time of a file
+fn file_stamp(path: &Path) -> Option<(u64, SystemTime)> {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.len(), meta.modified().ok()?))
+}
+
+/// Paths of all files under `dir`, relative to it with `/` separators
+fn list_files(dir: &Path) -> StorageResult<Vec<String>> {
+    let mut files = Vec::new();
+    if !dir.exists() {
+        return Ok(files);
+    }
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if let Ok(relative) = path.strip_prefix(dir) {
+                let parts: Vec<_> = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect();
+                files.push(parts.join("/"));
+            }
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_local_object_store() {
+        let temp = TempDir::new().unwrap();
+        let store = LocalObjectStore::new(temp.path().to_path_buf());
+        store.put("batches/1.parquet", b"one").unwrap();
+        store.put("shards/a.json", b"{}").unwrap();
+
+        assert_eq!(
+            store.get("batches/1.parquet").unwrap(),
+            Some(b"one".to_vec())
+        );
+        assert_eq!(store.get("batches/2.parquet").unwrap(), None);
+        assert_eq!(store.list("batches/").unwrap(), vec!["batches/1.parquet"]);
+
+        store.delete("batches/1.parquet").unwrap();
+        store.delete("batches/1.parquet").unwrap();
+        assert!(store.list("batches/").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_mirror_push_and_restore() {
+        let local = TempDir::new().unwrap();
+        let remote = TempDir::new().unwrap();
+        let store: Arc<dyn ObjectStore> =
+            Arc::new(LocalObjectStore::new(remote.path().to_path_buf()));
+
+        fs::create_dir_all(local.path().join("batches")).unwrap();
+        fs::write(local.path().join("batches/1.parquet"), b"one").unwrap();
+        fs::write(local.path().join("batches/2.parquet"), b"two").unwrap();
+        fs::write(local.path().join("batches/3.parquet.tmp"), b"partial").unwrap();
+
+        let mirror = StoreMirror::new(Arc::clone(&store), local.path().to_path_buf());
+        assert_eq!(mirror.push(&["batches"]).unwrap(), 2);
+        // Unchanged files are not uploaded again
+        assert_eq!(mirror.push(&["batches"]).unwrap(), 0);
+
+        fs::remove_file(local.path().join("batches/1.parquet")).unwrap();
+        mirror.push(&["batches"]).unwrap();
+        assert_eq!(store.list("").unwrap(), vec!["batches/2.parquet"]);
+
+        let fresh = TempDir::new().unwrap();
+        let mirror = StoreMirror::new(store, fresh.path().to_path_buf());
+        assert_eq!(mirror.restore().unwrap(), 1);
+        assert_eq!(
+            fs::read(fresh.path().join("batches/2.parquet")).unwrap(),
+            b"two"
+        );
+    }
+
+    #[test]
+    fn test_open_object_store_urls() {
+        let temp = TempDir::new().unwrap();
+        let config = ObjectStoreConfig {
+            url: format!("file://{}", temp.path().display()),
+            region: None,
+            endpoint: None,
+            access_key_id: None,
+            secret_access_key: None,
+            service_account_path: None,
+            allow_http: false,
+        };
+        let store = open_object_store(&config).unwrap();
+        store.put("k", b"v").unwrap();
+        assert!(temp.path().join("k").exists());
+
+        let bad = ObjectStoreConfig {
+            url: "ftp://host/path".to_string(),
+            ..config
+        };
+        assert!(open_object_store(&bad).is_err());
+    }
+}
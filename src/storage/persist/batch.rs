@@ -111,6 +111,14 @@ pub struct BatchRef {
     pub upper: u64,
     /// Number of updates in this batch
     pub len: usize,
+    /// Schema version of the relation when the batch was written
+    /// (0 for batches written before schemas were versioned)
+    #[serde(default)]
+    pub schema_version: u32,
+    /// CRC32 of the Parquet file, checked on recovery
+    /// (`None` for batches written before checksums were recorded)
+    #[serde(default)]
+    pub checksum: Option<u32>,
 }
 
 /// Current shard metadata format version.
@@ -138,6 +146,9 @@ pub struct ShardMeta {
     pub upper: u64,
     /// Total number of updates across all batches
     pub total_updates: usize,
+    /// Schema version stamped on batches written from now on
+    #[serde(default)]
+    pub schema_version: u32,
 }
 
 fn default_version() -> u32 {
@@ -154,6 +165,7 @@ impl ShardMeta {
             since: 0,
             upper: 0,
             total_updates: 0,
+            schema_version: 0,
         }
     }
 
@@ -183,6 +195,8 @@ pub struct ShardInfo {
     pub upper: u64,
     pub batch_count: usize,
     pub total_updates: usize,
+    /// Bytes of batch files on disk (missing files count as 0)
+    pub size_bytes: u64,
 }
 
 impl From<&ShardMeta> for ShardInfo {
@@ -193,6 +207,11 @@ impl From<&ShardMeta> for ShardInfo {
             upper: meta.upper,
             batch_count: meta.batches.len(),
             total_updates: meta.total_updates,
+            size_bytes: meta
+                .batches
+                .iter()
+                .map(|b| std::fs::metadata(&b.path).map_or(0, |m| m.len()))
+                .sum(),
         }
     }
 }
@@ -259,6 +278,8 @@ mod tests {
             lower: 0,
             upper: 100,
             len: 50,
+            schema_version: 0,
+            checksum: None,
         });
 
         assert_eq!(shard.upper, 100);
@@ -298,6 +319,8 @@ mod tests {
             lower: 0,
             upper: 50,
             len: 10,
+            schema_version: 0,
+            checksum: None,
         });
         shard.add_batch(BatchRef {
             id: "b2".to_string(),
@@ -305,6 +328,8 @@ mod tests {
             lower: 50,
             upper: 100,
             len: 20,
+            schema_version: 0,
+            checksum: None,
         });
 
         assert_eq!(shard.batches.len(), 2);
@@ -331,6 +356,8 @@ mod tests {
             lower: 0,
             upper: 10,
             len: 5,
+            schema_version: 0,
+            checksum: None,
         });
         shard.advance_since(3);
 
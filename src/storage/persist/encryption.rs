@@ -0,0 +1,502 @@
+//! Encryption at rest
+//!
+//! With `[storage.persist.encryption]` enabled, batch files, shard
+//! metadata and WAL records are sealed with AES-256-GCM before they reach
+//! the disk (or the object store). A sealed file is an envelope:
+//!
+//! ```text
+//! "ILE1" | key id (8 bytes) | nonce (12 bytes) | ciphertext and tag
+//! ```
+//!
+//! The key id is the start of the SHA-256 of the key, so a file names the
+//! key it needs without revealing it. The magic and key id are
+//! authenticated with the ciphertext, and so are the kind of file and its
+//! path below the persist directory (e.g. `batches/12.parquet`): a sealed
+//! file copied over another one fails authentication instead of being read
+//! as the other file. A WAL record is the base64 of an envelope holding
+//! its JSON, bound to `wal` rather than to a segment, as segments are
+//! renamed when they are sealed and archived.
+//!
+//! Files written before encryption was enabled stay readable until a key
+//! rotation has rewritten them all; from then on plain files are rejected
+//! (strict mode, also available as `reject_plaintext`), so a plain file
+//! slipped into the directory is not taken as data. A file sealed with a
+//! key that is not configured, or that fails authentication, is an error
+//! rather than being read as garbage.
+//!
+//! ## Key rotation
+//!
+//! 1. Configure the new key as `key` and move the old one to
+//!    `previous_keys`, then restart: new files use the new key, old files
+//!    are still read with the previous one.
+//! 2. Run `.encryption rotate` to rewrite every file not sealed with the
+//!    new key (plain files included). This turns on strict mode.
+//! 3. Drop the old key from `previous_keys`.
+
+use crate::bytes_ops::encode_hex;
+use crate::config::EncryptionConfig;
+use crate::storage::{StorageError, StorageResult};
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Leading bytes of a sealed file
+const MAGIC: &[u8; 4] = b"ILE1";
+const KEY_ID_LEN: usize = 8;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = MAGIC.len() + KEY_ID_LEN + NONCE_LEN;
+
+/// Environment variable holding the key when the config has none
+pub const KEY_ENV: &str = "IL_ENCRYPTION_KEY";
+/// Environment variable holding previous keys (comma separated) when the
+/// config has none
+pub const PREVIOUS_KEYS_ENV: &str = "IL_ENCRYPTION_PREVIOUS_KEYS";
+
+struct DataKey {
+    id: [u8; KEY_ID_LEN],
+    cipher: Aes256Gcm,
+}
+
+impl DataKey {
+    fn new(key: &[u8; 32]) -> Self {
+        let digest = Sha256::digest(key);
+        let mut id = [0u8; KEY_ID_LEN];
+        id.copy_from_slice(&digest[..KEY_ID_LEN]);
+        DataKey {
+            id,
+            cipher: Aes256Gcm::new(key.into()),
+        }
+    }
+}
+
+/// What a sealed file holds, authenticated with its contents
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    /// Parquet batch file
+    Batch,
+    /// Shard metadata
+    ShardMeta,
+    /// WAL record
+    WalRecord,
+    /// Saved subplan cache of a knowledge graph
+    SubplanCache,
+}
+
+impl FileKind {
+    fn tag(self) -> &'static str {
+        match self {
+            FileKind::Batch => "batch",
+            FileKind::ShardMeta => "shard",
+            FileKind::WalRecord => "wal",
+            FileKind::SubplanCache => "subplan_cache",
+        }
+    }
+}
+
+impl fmt::Display for FileKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.tag())
+    }
+}
+
+/// Path of a file below the persist (or knowledge graph) directory: its
+/// parent directory and name, e.g. `batches/12.parquet`. It does not
+/// depend on where the directory lives, so files stay readable after the
+/// directory is moved or restored from an object store.
+pub fn relative_name(path: &Path) -> String {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    match path.parent().and_then(Path::file_name) {
+        Some(dir) => format!("{}/{name}", dir.to_string_lossy()),
+        None => name.into_owned(),
+    }
+}
+
+/// Keys sealing and opening persist files. The first key seals; all of
+/// them open.
+pub struct Encryption {
+    keys: Vec<DataKey>,
+    /// Reject plain files
+    strict: AtomicBool,
+}
+
+impl fmt::Debug for Encryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ids: Vec<String> = self.keys.iter().map(|k| encode_hex(&k.id)).collect();
+        f.debug_struct("Encryption")
+            .field("key_ids", &ids)
+            .field("strict", &self.is_strict())
+            .finish()
+    }
+}
+
+impl Encryption {
+    /// Seal with `key`, and also open files sealed with `previous` keys
+    pub fn new(key: &[u8; 32], previous: &[[u8; 32]]) -> Self {
+        let keys = std::iter::once(key)
+            .chain(previous)
+            .map(DataKey::new)
+            .collect();
+        Encryption {
+            keys,
+            strict: AtomicBool::new(false),
+        }
+    }
+
+    /// Whether plain files are rejected
+    pub fn is_strict(&self) -> bool {
+        self.strict.load(Ordering::Relaxed)
+    }
+
+    /// Reject plain files from now on, or accept them again
+    pub fn set_strict(&self, strict: bool) {
+        self.strict.store(strict, Ordering::Relaxed);
+    }
+
+    /// Keys of an encryption config, falling back to the environment.
+    /// `None` when encryption is disabled.
+    pub fn from_config(config: &EncryptionConfig) -> StorageResult<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        let key = match &config.key {
+            Some(key) => key.clone(),
+            None => std::env::var(KEY_ENV).map_err(|_| {
+                StorageError::Encryption(format!(
+                    "Encryption is enabled but no key is configured (set key or {KEY_ENV})"
+                ))
+            })?,
+        };
+        let previous = if config.previous_keys.is_empty() {
+            std::env::var(PREVIOUS_KEYS_ENV)
+                .map(|keys| {
+                    keys.split(',')
+                        .map(str::trim)
+                        .filter(|key| !key.is_empty())
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default()
+        } else {
+            config.previous_keys.clone()
+        };
+        let previous = previous
+            .iter()
+            .map(|key| parse_key(key))
+            .collect::<StorageResult<Vec<_>>>()?;
+        let encryption = Encryption::new(&parse_key(&key)?, &previous);
+        encryption.set_strict(config.reject_plaintext);
+        Ok(Some(encryption))
+    }
+
+    /// Hex id of the key new files are sealed with
+    pub fn key_id(&self) -> String {
+        encode_hex(&self.keys[0].id)
+    }
+
+    /// Seal `plaintext` with the current key, as the file of `kind` at
+    /// `name` (see [`relative_name`])
+    pub fn encrypt(&self, plaintext: &[u8], kind: FileKind, name: &str) -> StorageResult<Vec<u8>> {
+        let key = &self.keys[0];
+        let mut envelope = Vec::with_capacity(HEADER_LEN + plaintext.len() + 16);
+        envelope.extend_from_slice(MAGIC);
+        envelope.extend_from_slice(&key.id);
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::rngs::OsRng.fill_bytes(&mut nonce);
+        let ciphertext = key
+            .cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: &associated_data(&envelope, kind, name),
+                },
+            )
+            .map_err(|_| StorageError::Encryption("Encryption failed".to_string()))?;
+        envelope.extend_from_slice(&nonce);
+        envelope.extend_from_slice(&ciphertext);
+        Ok(envelope)
+    }
+
+    /// Open a sealed envelope of the file of `kind` at `name`
+    pub fn decrypt(&self, data: &[u8], kind: FileKind, name: &str) -> StorageResult<Vec<u8>> {
+        let id = envelope_key_id(data).ok_or_else(|| {
+            StorageError::Encryption("Data is not an encrypted envelope".to_string())
+        })?;
+        let key = self.keys.iter().find(|k| k.id == id).ok_or_else(|| {
+            StorageError::Encryption(format!(
+                "Data is encrypted with key {} which is not configured",
+                encode_hex(&id)
+            ))
+        })?;
+        let header = &data[..MAGIC.len() + KEY_ID_LEN];
+        let nonce = &data[header.len()..HEADER_LEN];
+        key.cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: &data[HEADER_LEN..],
+                    aad: &associated_data(header, kind, name),
+                },
+            )
+            .map_err(|_| {
+                StorageError::Encryption(format!(
+                    "Encrypted {kind} data failed authentication (corrupt, tampered or moved from another file)"
+                ))
+            })
+    }
+
+    /// Whether `data` is an envelope one of the keys opens
+    pub fn has_key_for(&self, data: &[u8]) -> bool {
+        envelope_key_id(data).is_some_and(|id| self.keys.iter().any(|k| k.id == id))
+    }
+
+    /// Whether `data` is an envelope sealed with the current key
+    pub fn is_current(&self, data: &[u8]) -> bool {
+        envelope_key_id(data) == Some(self.keys[0].id)
+    }
+
+    /// Whether the file at `path` is sealed with the current key
+    pub fn is_current_file(&self, path: &Path) -> StorageResult<bool> {
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        fs::File::open(path)?
+            .take(HEADER_LEN as u64)
+            .read_to_end(&mut header)?;
+        Ok(self.is_current(&header))
+    }
+}
+
+/// Data authenticated with a sealed file: its header, kind and name
+fn associated_data(header: &[u8], kind: FileKind, name: &str) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(header.len() + kind.tag().len() + name.len() + 1);
+    aad.extend_from_slice(header);
+    aad.extend_from_slice(kind.tag().as_bytes());
+    aad.push(0);
+    aad.extend_from_slice(name.as_bytes());
+    aad
+}
+
+/// Decode a base64 key of 32 bytes
+fn parse_key(key: &str) -> StorageResult<[u8; 32]> {
+    let bytes = STANDARD
+        .decode(key.trim())
+        .map_err(|e| StorageError::Encryption(format!("Encryption key is not base64: {e}")))?;
+    bytes.try_into().map_err(|bytes: Vec<u8>| {
+        StorageError::Encryption(format!(
+            "Encryption key must be 32 bytes, got {}",
+            bytes.len()
+        ))
+    })
+}
+
+fn envelope_key_id(data: &[u8]) -> Option<[u8; KEY_ID_LEN]> {
+    if !is_encrypted(data) || data.len() < HEADER_LEN {
+        return None;
+    }
+    data[MAGIC.len()..MAGIC.len() + KEY_ID_LEN].try_into().ok()
+}
+
+/// Whether `data` is a sealed envelope
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Whether the file at `path` is sealed
+pub fn is_encrypted_file(path: &Path) -> StorageResult<bool> {
+    let mut magic = [0u8; MAGIC.len()];
+    let mut file = fs::File::open(path)?;
+    match file.read_exact(&mut magic) {
+        Ok(()) => Ok(is_encrypted(&magic)),
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Error unless plain data of `kind` is accepted, i.e. encryption is
+/// disabled or not strict
+pub fn check_plain(encryption: Option<&Encryption>, kind: FileKind) -> StorageResult<()> {
+    if encryption.is_some_and(Encryption::is_strict) {
+        return Err(StorageError::Encryption(format!(
+            "Plain {kind} data is rejected: every file should be encrypted"
+        )));
+    }
+    Ok(())
+}
+
+/// Seal `data`, the file of `kind` at `path`, if encryption is enabled
+pub fn seal(
+    data: Vec<u8>,
+    encryption: Option<&Encryption>,
+    kind: FileKind,
+    path: &Path,
+) -> StorageResult<Vec<u8>> {
+    match encryption {
+        Some(encryption) => encryption.encrypt(&data, kind, &relative_name(path)),
+        None => Ok(data),
+    }
+}
+
+/// Open `data`, the file of `kind` at `path`, which may be plain or sealed
+pub fn open(
+    data: Vec<u8>,
+    encryption: Option<&Encryption>,
+    kind: FileKind,
+    path: &Path,
+) -> StorageResult<Vec<u8>> {
+    if !is_encrypted(&data) {
+        check_plain(encryption, kind)?;
+        return Ok(data);
+    }
+    match encryption {
+        Some(encryption) => encryption.decrypt(&data, kind, &relative_name(path)),
+        None => Err(StorageError::Encryption(
+            "Data is encrypted but encryption is not enabled".to_string(),
+        )),
+    }
+}
+
+/// Read a file of `kind` that may be plain or sealed
+pub fn read_file(
+    path: &Path,
+    encryption: Option<&Encryption>,
+    kind: FileKind,
+) -> StorageResult<Vec<u8>> {
+    open(fs::read(path)?, encryption, kind, path).map_err(|e| match e {
+        StorageError::Encryption(msg) => {
+            StorageError::Encryption(format!("{}: {msg}", path.display()))
+        }
+        e => e,
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    const BATCH: FileKind = FileKind::Batch;
+
+    #[test]
+    fn test_seal_and_open() {
+        let encryption = Encryption::new(&[7; 32], &[]);
+        let sealed = encryption
+            .encrypt(b"secret tuples", BATCH, "batches/1.parquet")
+            .unwrap();
+        assert!(is_encrypted(&sealed));
+        assert!(!sealed.windows(6).any(|w| w == b"secret"));
+        assert_eq!(
+            encryption
+                .decrypt(&sealed, BATCH, "batches/1.parquet")
+                .unwrap(),
+            b"secret tuples"
+        );
+
+        // Plain data passes through; sealed data needs a key
+        let path = Path::new("/data/batches/1.parquet");
+        assert_eq!(
+            open(b"plain".to_vec(), None, BATCH, path).unwrap(),
+            b"plain"
+        );
+        assert!(open(sealed.clone(), None, BATCH, path).is_err());
+
+        // Tampering is detected
+        let mut tampered = sealed;
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(encryption
+            .decrypt(&tampered, BATCH, "batches/1.parquet")
+            .is_err());
+    }
+
+    #[test]
+    fn test_sealed_file_is_bound_to_its_kind_and_path() {
+        let encryption = Encryption::new(&[7; 32], &[]);
+        let path = Path::new("/data/batches/1.parquet");
+        assert_eq!(relative_name(path), "batches/1.parquet");
+        let sealed = seal(b"tuples".to_vec(), Some(&encryption), BATCH, path).unwrap();
+
+        // The same directory elsewhere still opens
+        let moved = Path::new("/restored/batches/1.parquet");
+        assert_eq!(
+            open(sealed.clone(), Some(&encryption), BATCH, moved).unwrap(),
+            b"tuples"
+        );
+
+        // Another file, or another kind of file, does not
+        let other = Path::new("/data/batches/2.parquet");
+        assert!(open(sealed.clone(), Some(&encryption), BATCH, other).is_err());
+        assert!(open(sealed, Some(&encryption), FileKind::ShardMeta, path).is_err());
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_plain_files() {
+        let encryption = Encryption::new(&[7; 32], &[]);
+        let path = Path::new("/data/shards/db_a.json");
+        let kind = FileKind::ShardMeta;
+        assert!(open(b"{}".to_vec(), Some(&encryption), kind, path).is_ok());
+
+        encryption.set_strict(true);
+        assert!(open(b"{}".to_vec(), Some(&encryption), kind, path).is_err());
+        let sealed = seal(b"{}".to_vec(), Some(&encryption), kind, path).unwrap();
+        assert_eq!(open(sealed, Some(&encryption), kind, path).unwrap(), b"{}");
+    }
+
+    #[test]
+    fn test_rotation_keeps_previous_keys() {
+        let name = "batches/1.parquet";
+        let old = Encryption::new(&[1; 32], &[]);
+        let sealed = old.encrypt(b"data", BATCH, name).unwrap();
+
+        let rotated = Encryption::new(&[2; 32], &[[1; 32]]);
+        assert!(rotated.has_key_for(&sealed));
+        assert!(!rotated.is_current(&sealed));
+        assert_eq!(rotated.decrypt(&sealed, BATCH, name).unwrap(), b"data");
+        assert!(rotated.is_current(&rotated.encrypt(b"data", BATCH, name).unwrap()));
+
+        let dropped = Encryption::new(&[2; 32], &[]);
+        assert!(!dropped.has_key_for(&sealed));
+        assert!(dropped.decrypt(&sealed, BATCH, name).is_err());
+    }
+
+    #[test]
+    fn test_from_config() {
+        let key = STANDARD.encode([3u8; 32]);
+        let config = EncryptionConfig {
+            enabled: true,
+            key: Some(key.clone()),
+            previous_keys: vec![STANDARD.encode([4u8; 32])],
+            reject_plaintext: false,
+        };
+        let encryption = Encryption::from_config(&config).unwrap().unwrap();
+        assert_eq!(encryption.key_id(), Encryption::new(&[3; 32], &[]).key_id());
+        assert!(!encryption.is_strict());
+
+        let strict = EncryptionConfig {
+            reject_plaintext: true,
+            ..config.clone()
+        };
+        assert!(Encryption::from_config(&strict)
+            .unwrap()
+            .unwrap()
+            .is_strict());
+
+        let disabled = EncryptionConfig {
+            enabled: false,
+            ..config.clone()
+        };
+        assert!(Encryption::from_config(&disabled).unwrap().is_none());
+
+        let short = EncryptionConfig {
+            key: Some(STANDARD.encode([3u8; 16])),
+            ..config
+        };
+        assert!(Encryption::from_config(&short).is_err());
+    }
+}
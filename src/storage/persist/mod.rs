@@ -20,28 +20,39 @@
 //! ## Recovery
 //!
 //! On startup:
-//! 1. Load shard metadata
-//! 2. Read batch files
-//! 3. Replay WAL (uncommitted updates)
-//! 4. Consolidate to get current state
+//! 1. Fill an empty persist directory from the object store, if configured
+//! 2. Load shard metadata (verifying batch files after an unclean shutdown)
+//! 3. Read batch files
+//! 4. Replay WAL (uncommitted updates)
+//! 5. Consolidate to get current state
 
 pub mod batch;
 pub mod consolidate;
+pub mod encryption;
+pub mod recovery;
+pub mod scan;
 pub mod wal;
 
 pub use batch::{Batch, BatchRef, ShardInfo, ShardMeta, Update};
 pub use consolidate::{
     consolidate, consolidate_to_current, filter_since, to_tuples, to_tuples_with_multiplicity,
 };
-pub use wal::PersistWal;
+pub use encryption::{Encryption, FileKind};
+pub use recovery::{CorruptBatch, RecoveryReport, RecoveryTarget};
+pub use scan::{ScanFilter, ScanStats};
+pub use wal::{Catalog, DdlChange, DdlRecord, PersistWal};
 
-use crate::storage::{StorageError, StorageResult};
+use crate::metrics::metrics;
+use crate::storage::{ObjectStore, StorageError, StorageResult, StoreMirror};
 use crate::value::{record_batch_to_tuples, tuples_to_record_batch, DataType, Tuple, TupleSchema};
+use bytes::Bytes;
 use parking_lot::{Mutex, RwLock};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
+use std::io::Write;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 
 // Parquet I/O for batches
 use arrow::array::{ArrayRef, Int64Array, UInt64Array};
@@ -51,9 +62,14 @@ use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
 use parquet::arrow::ArrowWriter;
 use parquet::basic::Compression;
 use parquet::file::properties::WriterProperties;
+use parquet::file::reader::ChunkReader;
 use std::sync::Arc;
 
-use crate::config::DurabilityMode;
+use crate::config::{CorruptionPolicy, DurabilityMode};
+
+/// File in the persist directory written by the first key rotation. While
+/// it exists, plain files are rejected.
+pub const STRICT_ENCRYPTION_MARKER: &str = "encryption.strict";
 
 /// Configuration for the persist layer
 #[derive(Debug, Clone)]
@@ -66,6 +82,20 @@ pub struct PersistConfig {
     pub durability_mode: DurabilityMode,
     /// Maximum WAL file size in bytes before forcing a flush (0 = unlimited)
     pub max_wal_size_bytes: u64,
+    /// WAL segment size in bytes before rotating to a new segment (0 = never)
+    pub wal_segment_size_bytes: u64,
+    /// Interval between WAL syncs in batched durability mode
+    pub wal_sync_interval_ms: u64,
+    /// Handling of batch files that fail verification on startup
+    pub on_corruption: CorruptionPolicy,
+    /// Keep sealed WAL segments for point-in-time recovery
+    pub wal_archive: bool,
+    /// Age after which archived WAL segments are deleted (0 = keep forever)
+    pub wal_archive_retention_secs: u64,
+    /// Object store mirroring batch files, shard metadata and WAL segments
+    pub object_store: Option<Arc<dyn ObjectStore>>,
+    /// Keys sealing batch files, shard metadata and WAL records at rest
+    pub encryption: Option<Arc<Encryption>>,
 }
 
 impl Default for PersistConfig {
@@ -74,7 +104,14 @@ impl Default for PersistConfig {
             path: PathBuf::from("./data/persist"),
             buffer_size: 10000,
             durability_mode: DurabilityMode::Immediate,
-            max_wal_size_bytes: 67_108_864, // 64 MB
+            max_wal_size_bytes: 67_108_864,     // 64 MB
+            wal_segment_size_bytes: 16_777_216, // 16 MB
+            wal_sync_interval_ms: 1000,
+            on_corruption: CorruptionPolicy::Quarantine,
+            wal_archive: false,
+            wal_archive_retention_secs: 604_800, // 7 days
+            object_store: None,
+            encryption: None,
         }
     }
 }
@@ -87,6 +124,18 @@ pub trait PersistBackend: Send + Sync {
     /// Read all updates for a shard since a frontier
     fn read(&self, shard: &str, since: u64) -> StorageResult<Vec<Update>>;
 
+    /// Read all updates for a shard since a frontier, grouped by the
+    /// schema version they were written under (ascending)
+    fn read_by_schema_version(
+        &self,
+        shard: &str,
+        since: u64,
+    ) -> StorageResult<Vec<(u32, Vec<Update>)>>;
+
+    /// Set the schema version stamped on batches written from now on.
+    /// Buffered updates are flushed first so they keep their old version.
+    fn set_schema_version(&self, shard: &str, version: u32) -> StorageResult<()>;
+
     /// Compact a shard to a frontier (discard history before `since`)
     fn compact(&self, shard: &str, new_since: u64) -> StorageResult<()>;
 
@@ -109,6 +158,17 @@ pub trait PersistBackend: Send + Sync {
     fn delete_shard(&self, shard: &str) -> StorageResult<()>;
 }
 
+/// Updates not yet written to batch files (see [`FilePersist::wal_lag`])
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WalLag {
+    /// Buffered updates whose only durable copy is the WAL
+    pub pending_updates: usize,
+    /// WAL size on disk, across all segments
+    pub wal_bytes: u64,
+    /// Sealed WAL segments
+    pub wal_segments: usize,
+}
+
 /// In-memory state for a shard
 struct ShardState {
     meta: ShardMeta,
@@ -121,6 +181,12 @@ pub struct FilePersist {
     shards: RwLock<HashMap<String, ShardState>>,
     wal: Mutex<PersistWal>,
     next_batch_id: AtomicU64,
+    recovery: RecoveryReport,
+    mirror: Option<StoreMirror>,
+    /// DDL records found in the WAL at startup, in write order
+    replayed_ddl: Vec<DdlRecord>,
+    /// Sequence number of the next DDL record
+    next_ddl_seq: AtomicU64,
 }
 
 impl FilePersist {
@@ -131,19 +197,51 @@ impl FilePersist {
         fs::create_dir_all(config.path.join("shards"))?;
         fs::create_dir_all(config.path.join("batches"))?;
 
-        let wal = PersistWal::new(config.path.join("wal"))?;
+        // An empty local directory is a cold cache: fill it from the store
+        let mirror = config
+            .object_store
+            .as_ref()
+            .map(|store| StoreMirror::new(Arc::clone(store), config.path.clone()));
+        if let Some(mirror) = &mirror {
+            if fs::read_dir(config.path.join("shards"))?.next().is_none() {
+                let restored = mirror.restore()?;
+                if restored > 0 {
+                    tracing::info!(restored, "persist_restored_from_object_store");
+                }
+            }
+        }
+
+        if let Some(encryption) = &config.encryption {
+            if config.path.join(STRICT_ENCRYPTION_MARKER).exists() {
+                encryption.set_strict(true);
+            }
+        }
+
+        let wal = PersistWal::open(config.path.join("wal"), config.encryption.clone())?
+            .with_segment_size(config.wal_segment_size_bytes)
+            .with_archive(config.wal_archive)?;
+
+        let unclean_shutdown = recovery::mark_running(&config.path)?;
 
         let mut persist = FilePersist {
             config,
             shards: RwLock::new(HashMap::new()),
             wal: Mutex::new(wal),
             next_batch_id: AtomicU64::new(1),
+            recovery: RecoveryReport {
+                unclean_shutdown,
+                ..Default::default()
+            },
+            mirror,
+            replayed_ddl: Vec::new(),
+            next_ddl_seq: AtomicU64::new(0),
         };
 
         // Load existing shards, clean up orphans, and replay WAL
         persist.load_shards()?;
         persist.cleanup_orphaned_batches();
         let replayed = persist.replay_wal()?;
+        persist.recovery.wal_entries_replayed = replayed;
 
         // Crash-safe WAL drain: if we replayed any entries, flush them to batch
         // files and clear the WAL immediately. This makes replay idempotent -
@@ -167,11 +265,35 @@ impl FilePersist {
             let wal = persist.wal.lock();
             wal.cleanup_archives()?;
         }
+        persist.prune_wal_archive()?;
+        persist.push_to_store(&["shards", "batches", "wal"]);
 
         Ok(persist)
     }
 
-    /// Load shard metadata from disk
+    /// Upload changed files under `dirs` to the object store, if any.
+    ///
+    /// The local files are already durable, so a failed upload is logged
+    /// rather than failing the write; the next push retries it.
+    fn push_to_store(&self, dirs: &[&str]) {
+        let Some(mirror) = &self.mirror else {
+            return;
+        };
+        if let Err(e) = mirror.push(dirs) {
+            tracing::warn!(error = %e, "persist_object_store_push_failed");
+        }
+    }
+
+    /// Outcome of startup recovery
+    pub fn recovery_report(&self) -> &RecoveryReport {
+        &self.recovery
+    }
+
+    /// Load shard metadata from disk.
+    ///
+    /// Batch files that are missing are always dropped from the metadata.
+    /// After an unclean shutdown every batch is also verified against its
+    /// checksum and row count, and failures are handled per `on_corruption`.
     fn load_shards(&mut self) -> StorageResult<()> {
         let shards_dir = self.config.path.join("shards");
         if !shards_dir.exists() {
@@ -185,8 +307,8 @@ impl FilePersist {
             let path = entry.path();
 
             if path.extension().and_then(|s| s.to_str()) == Some("json") {
-                let content = fs::read_to_string(&path)?;
-                let meta: ShardMeta = serde_json::from_str(&content).map_err(|e| {
+                let content = encryption::read_file(&path, self.encryption(), FileKind::ShardMeta)?;
+                let mut meta: ShardMeta = serde_json::from_slice(&content).map_err(|e| {
                     StorageError::Other(format!("Failed to parse shard metadata: {e}"))
                 })?;
 
@@ -209,26 +331,68 @@ impl FilePersist {
                     }
                 }
 
+                // Batch paths are absolute; files restored into a different
+                // directory are found by file name
+                let mut relocated = false;
+                for batch_ref in &mut meta.batches {
+                    let moved = batch_ref
+                        .path
+                        .file_name()
+                        .map(|name| self.config.path.join("batches").join(name))
+                        .filter(|path| !batch_ref.path.exists() && path.exists());
+                    if let Some(path) = moved {
+                        batch_ref.path = path;
+                        relocated = true;
+                    }
+                }
+                if relocated {
+                    self.save_shard_meta(&meta)?;
+                }
+
                 // Validate batch files exist and are readable (#6)
                 let mut valid_batches = Vec::new();
                 let mut removed_count = 0usize;
                 for batch_ref in &meta.batches {
-                    if batch_ref.path.exists() {
-                        valid_batches.push(batch_ref.clone());
+                    let problem = if !batch_ref.path.exists() {
+                        Some("batch file missing".to_string())
+                    } else if self.recovery.unclean_shutdown {
+                        self.recovery.batches_verified += 1;
+                        recovery::verify_batch(batch_ref, self.encryption()).err()
                     } else {
-                        tracing::warn!(
-                            shard = %meta.name,
-                            batch_id = %batch_ref.id,
-                            path = %batch_ref.path.display(),
-                            "Batch file missing - removing stale reference"
-                        );
-                        removed_count += 1;
-                    }
+                        None
+                    };
+                    let Some(reason) = problem else {
+                        valid_batches.push(batch_ref.clone());
+                        continue;
+                    };
+                    let quarantined_to = match self.config.on_corruption {
+                        CorruptionPolicy::Quarantine => {
+                            recovery::quarantine(&self.config.path, &meta.name, batch_ref)
+                        }
+                        CorruptionPolicy::Fail => None,
+                    };
+                    tracing::warn!(
+                        shard = %meta.name,
+                        batch_id = %batch_ref.id,
+                        path = %batch_ref.path.display(),
+                        reason = %reason,
+                        "Batch file failed verification - removing reference"
+                    );
+                    self.recovery.corrupt_batches.push(CorruptBatch {
+                        shard: meta.name.clone(),
+                        batch_id: batch_ref.id.clone(),
+                        reason,
+                        quarantined_to,
+                    });
+                    removed_count += 1;
                 }
-                let mut meta = meta;
                 if removed_count > 0 {
+                    if self.config.on_corruption == CorruptionPolicy::Fail {
+                        continue;
+                    }
                     meta.batches = valid_batches;
                     meta.total_updates = meta.batches.iter().map(|b| b.len).sum();
+                    self.save_shard_meta(&meta)?;
                 }
 
                 shards.insert(
@@ -241,6 +405,22 @@ impl FilePersist {
             }
         }
 
+        if self.config.on_corruption == CorruptionPolicy::Fail
+            && !self.recovery.corrupt_batches.is_empty()
+        {
+            let details: Vec<String> = self
+                .recovery
+                .corrupt_batches
+                .iter()
+                .map(|c| format!("{} batch {}: {}", c.shard, c.batch_id, c.reason))
+                .collect();
+            return Err(StorageError::Other(format!(
+                "Persist consistency check failed ({} corrupt batch file(s)): {}",
+                details.len(),
+                details.join("; ")
+            )));
+        }
+
         Ok(())
     }
 
@@ -286,20 +466,32 @@ impl FilePersist {
         }
 
         if removed > 0 {
-            eprintln!("[persist] Cleaned up {removed} orphaned batch file(s)");
+            tracing::info!(removed, "persist_orphaned_batches_removed");
             sync_directory(&batches_dir);
         }
     }
 
-    /// Replay WAL entries into shard buffers. Returns the number of entries replayed.
-    fn replay_wal(&self) -> StorageResult<usize> {
+    /// Replay WAL entries into shard buffers, setting DDL records aside for
+    /// the engine to apply before it loads any data. Returns the number of
+    /// data entries replayed.
+    fn replay_wal(&mut self) -> StorageResult<usize> {
         let wal = self.wal.lock();
-        let entries = wal.read_all()?;
-        let count = entries.len();
+        let (records, skipped) = wal.read_records_counted()?;
+        self.recovery.wal_entries_skipped = skipped;
 
         let mut shards = self.shards.write();
 
-        for entry in entries {
+        let mut count = 0;
+        for record in records {
+            let entry = match record {
+                wal::WalRecord::Data(entry) => entry,
+                wal::WalRecord::Ddl(ddl) => {
+                    self.next_ddl_seq.fetch_max(ddl.seq + 1, Ordering::Relaxed);
+                    self.replayed_ddl.push(ddl);
+                    continue;
+                }
+            };
+            count += 1;
             let state = shards
                 .entry(entry.shard.clone())
                 .or_insert_with(|| ShardState {
@@ -321,16 +513,18 @@ impl FilePersist {
         let dir = self.config.path.join("shards");
         let final_path = dir.join(format!("{}.json", sanitize_name(&meta.name)));
         let tmp_path = dir.join(format!("{}.json.tmp", sanitize_name(&meta.name)));
-        let content = serde_json::to_string_pretty(meta)
+        let content = serde_json::to_vec_pretty(meta)
             .map_err(|e| StorageError::Other(format!("Failed to serialize shard metadata: {e}")))?;
+        let content =
+            encryption::seal(content, self.encryption(), FileKind::ShardMeta, &final_path)?;
 
         // Write to temp file
         if let Err(e) = fs::write(&tmp_path, &content) {
-            eprintln!(
-                "[persist] ERROR save_shard_meta: path={}, parent_exists={}, error={}",
-                tmp_path.display(),
-                tmp_path.parent().is_some_and(std::path::Path::exists),
-                e
+            tracing::error!(
+                path = %tmp_path.display(),
+                parent_exists = tmp_path.parent().is_some_and(std::path::Path::exists),
+                error = %e,
+                "persist_save_shard_meta_failed"
             );
             return Err(e.into());
         }
@@ -357,8 +551,8 @@ impl FilePersist {
             .to_string()
     }
 
-    /// Write a batch to a Parquet file
-    fn write_batch(&self, updates: &[Update]) -> StorageResult<(String, PathBuf)> {
+    /// Write a batch to a Parquet file. Returns its id, path and checksum.
+    fn write_batch(&self, updates: &[Update]) -> StorageResult<(String, PathBuf, Option<u32>)> {
         let batch_id = self.generate_batch_id();
         let path = self
             .config
@@ -366,34 +560,311 @@ impl FilePersist {
             .join("batches")
             .join(format!("{batch_id}.parquet"));
 
-        write_updates_parquet(&path, updates)?;
+        write_updates_parquet(&path, updates, self.encryption())?;
+        let checksum = recovery::file_checksum(&path);
 
-        Ok((batch_id, path))
+        Ok((batch_id, path, checksum))
     }
 
     /// Read updates from a batch file
     fn read_batch(&self, batch_ref: &BatchRef) -> StorageResult<Vec<Update>> {
-        read_updates_parquet(&batch_ref.path)
+        read_updates_parquet(&batch_ref.path, self.encryption())
+    }
+
+    /// Keys files are sealed with, if encryption is enabled
+    pub fn encryption(&self) -> Option<&Encryption> {
+        self.config.encryption.as_deref()
+    }
+
+    /// In `batched` mode, sync WAL entries written more than the sync
+    /// interval ago. `append` only syncs when it is called, so this is run
+    /// periodically to bound what a power failure loses once writes stop.
+    /// Returns whether a sync happened.
+    pub fn sync_wal_if_due(&self) -> StorageResult<bool> {
+        if self.config.durability_mode != DurabilityMode::Batched {
+            return Ok(false);
+        }
+        self.wal
+            .lock()
+            .sync_if_due(Duration::from_millis(self.config.wal_sync_interval_ms))
+    }
+
+    /// Checkpoint: flush every dirty shard to Parquet batch files and sync.
+    /// Each flush truncates the shard's WAL entries, so once this returns
+    /// the WAL only holds updates appended concurrently.
+    pub fn checkpoint(&self) -> StorageResult<()> {
+        self.flush_all()?;
+        self.prune_wal_archive()?;
+        {
+            let mut wal = self.wal.lock();
+            wal.sync()?;
+            tracing::debug!(
+                wal_size_bytes = wal.file_size(),
+                wal_segments = wal.segment_count(),
+                "persist_checkpoint"
+            );
+        }
+        self.push_to_store(&["shards", "batches", "wal"]);
+        Ok(())
+    }
+
+    /// Log a catalog change of `knowledge_graph` to the WAL, synced, before
+    /// the catalog file is rewritten. Skipped in `async` mode, which has no
+    /// WAL.
+    pub fn log_ddl(&self, knowledge_graph: &str, change: DdlChange) -> StorageResult<()> {
+        if self.config.durability_mode == DurabilityMode::Async {
+            return Ok(());
+        }
+        let mut wal = self.wal.lock();
+        let record = DdlRecord {
+            seq: self.next_ddl_seq.fetch_add(1, Ordering::Relaxed),
+            knowledge_graph: knowledge_graph.to_string(),
+            change,
+        };
+        wal.append_ddl(&record)
+    }
+
+    /// DDL records of `knowledge_graph` found in the WAL at startup, in
+    /// write order
+    pub fn replayed_ddl(&self, knowledge_graph: &str) -> Vec<DdlRecord> {
+        self.replayed_ddl
+            .iter()
+            .filter(|record| record.knowledge_graph == knowledge_graph)
+            .cloned()
+            .collect()
+    }
+
+    /// Sequence number the next DDL record gets. Records before it are
+    /// covered once every catalog file has been synced.
+    pub fn ddl_seq(&self) -> u64 {
+        self.next_ddl_seq.load(Ordering::Relaxed)
+    }
+
+    /// Drop the DDL records before `seq` from the WAL, once the catalog
+    /// files they cover are synced
+    pub fn truncate_ddl(&self, seq: u64) -> StorageResult<()> {
+        self.wal.lock().remove_ddl_before(seq)
+    }
+
+    /// Delete archived WAL segments older than the retention period
+    fn prune_wal_archive(&self) -> StorageResult<()> {
+        if self.config.wal_archive_retention_secs == 0 {
+            return Ok(());
+        }
+        let retention = Duration::from_secs(self.config.wal_archive_retention_secs);
+        let removed = self.wal.lock().prune_archive(retention)?;
+        if removed > 0 {
+            tracing::info!(removed, "wal_archive_pruned");
+        }
+        Ok(())
+    }
+
+    /// Logical time (LSN) of the last update written at or before
+    /// `timestamp_ms`, according to the WAL archive.
+    ///
+    /// Fails if archiving is disabled or the timestamp predates the oldest
+    /// archived entry.
+    pub fn lsn_at(&self, timestamp_ms: i64) -> StorageResult<u64> {
+        if !self.config.wal_archive {
+            return Err(StorageError::Other(
+                "Restoring to a timestamp requires storage.persist.wal_archive = true".to_string(),
+            ));
+        }
+        let entries = self.wal.lock().read_archive()?;
+        let mut oldest: Option<i64> = None;
+        let mut lsn: Option<u64> = None;
+        for entry in &entries {
+            let Some(ts) = entry.timestamp_ms else {
+                continue;
+            };
+            oldest = Some(oldest.map_or(ts, |o| o.min(ts)));
+            if ts <= timestamp_ms {
+                lsn = Some(lsn.map_or(entry.update.time, |l| l.max(entry.update.time)));
+            }
+        }
+        match (lsn, oldest) {
+            (Some(lsn), _) => Ok(lsn),
+            (None, Some(oldest)) => Err(StorageError::Other(format!(
+                "Timestamp {timestamp_ms} predates the oldest archived WAL entry ({oldest})"
+            ))),
+            (None, None) => Err(StorageError::Other(
+                "The WAL archive is empty; nothing to restore from".to_string(),
+            )),
+        }
+    }
+
+    /// Rewrite every batch file, shard metadata file and WAL file not
+    /// sealed with the current encryption key, so previous keys can be
+    /// dropped. Batch files get new ids; the old files are deleted once
+    /// the metadata points to their replacements. Returns the number of
+    /// batch files rewritten.
+    ///
+    /// No plain file is left afterwards, so plain files are rejected from
+    /// then on, across restarts (see [`STRICT_ENCRYPTION_MARKER`]).
+    pub fn rotate_encryption(&self) -> StorageResult<usize> {
+        let Some(encryption) = self.encryption() else {
+            return Err(StorageError::Encryption(
+                "Encryption at rest is not enabled".to_string(),
+            ));
+        };
+
+        let mut shards = self.shards.write();
+        let mut old_files = Vec::new();
+        for state in shards.values_mut() {
+            for batch_ref in &mut state.meta.batches {
+                if encryption.is_current_file(&batch_ref.path)? {
+                    continue;
+                }
+                let updates = self.read_batch(batch_ref)?;
+                if updates.is_empty() {
+                    continue;
+                }
+                let (batch_id, path, checksum) = self.write_batch(&updates)?;
+                old_files.push(std::mem::replace(&mut batch_ref.path, path));
+                batch_ref.id = batch_id;
+                batch_ref.checksum = checksum;
+            }
+            self.save_shard_meta(&state.meta)?;
+        }
+        for path in &old_files {
+            let _ = fs::remove_file(path);
+        }
+        if !old_files.is_empty() {
+            sync_directory(&self.config.path.join("batches"));
+        }
+        let wal_files = self.wal.lock().reencrypt()?;
+        drop(shards);
+        self.push_to_store(&["shards", "batches", "wal"]);
+        fs::write(
+            self.config.path.join(STRICT_ENCRYPTION_MARKER),
+            encryption.key_id(),
+        )?;
+        encryption.set_strict(true);
+
+        tracing::info!(
+            key_id = %encryption.key_id(),
+            batches = old_files.len(),
+            wal_files,
+            "encryption_key_rotated"
+        );
+        Ok(old_files.len())
     }
 
     /// Flush all dirty shards (shards with non-empty buffers).
     /// Used when WAL size exceeds the configured limit.
     fn flush_all(&self) -> StorageResult<()> {
-        let dirty_shards: Vec<String> = {
-            let shards = self.shards.read();
-            shards
-                .iter()
-                .filter(|(_, state)| !state.buffer.is_empty())
-                .map(|(name, _)| name.clone())
-                .collect()
-        };
+        self.flush_shards(&self.dirty_shards()).map(|_| ())
+    }
+
+    /// Shards with buffered updates that are not yet in a batch file
+    pub fn dirty_shards(&self) -> Vec<String> {
+        self.shards
+            .read()
+            .iter()
+            .filter(|(_, state)| !state.buffer.is_empty())
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
 
-        for shard_name in &dirty_shards {
-            self.flush(shard_name)?;
+    /// Write the buffer of each dirty shard in `shards` to a new batch file
+    /// holding only that delta, then drop their WAL entries in one pass.
+    /// Clean and unknown shards are skipped, so checkpointing after a write
+    /// to one relation costs one batch file, not one per relation.
+    /// Returns the number of shards written.
+    pub fn flush_shards(&self, shards: &[String]) -> StorageResult<usize> {
+        let mut flushed = HashSet::new();
+        let mut states = self.shards.write();
+        let written = shards.iter().try_for_each(|name| {
+            let Some(state) = states.get_mut(name) else {
+                return Ok(());
+            };
+            if state.buffer.is_empty() {
+                return Ok(());
+            }
+            self.write_buffer(state)?;
+            flushed.insert(name.clone());
+            Ok(())
+        });
+
+        // Remove WAL entries LAST (safe - metadata already points to the
+        // batches), including those of shards flushed before a failure
+        if !flushed.is_empty() {
+            self.wal.lock().remove_entries_of(&flushed)?;
+        }
+        drop(states);
+        if !flushed.is_empty() {
+            self.push_to_store(&["shards", "batches", "wal"]);
         }
+        written.map(|()| flushed.len())
+    }
+
+    /// Write a shard's buffer to a batch file and record it in the shard
+    /// metadata, clearing the buffer
+    fn write_buffer(&self, state: &mut ShardState) -> StorageResult<()> {
+        let _span = tracing::info_span!(
+            "persist_flush",
+            shard = %state.meta.name,
+            updates = state.buffer.len()
+        )
+        .entered();
+        let start = std::time::Instant::now();
+        // Step 1: Write buffer to batch file (atomic via temp+rename in write_batch)
+        let batch = Batch::new(state.buffer.clone());
+        let (batch_id, path, checksum) = self.write_batch(&state.buffer)?;
+
+        let batch_ref = BatchRef {
+            id: batch_id,
+            path: path.clone(),
+            lower: batch.lower,
+            upper: batch.upper,
+            len: batch.len(),
+            schema_version: state.meta.schema_version,
+            checksum,
+        };
+
+        // Step 2: Update metadata and save atomically
+        state.meta.add_batch(batch_ref);
+        let flushed = state.buffer.len() as u64;
+        state.buffer.clear();
 
+        if let Err(e) = self.save_shard_meta(&state.meta) {
+            // Metadata save failed - clean up the orphaned batch file
+            let _ = fs::remove_file(&path);
+            return Err(e);
+        }
+        metrics().persist_flushes.inc();
+        metrics().persist_flushed_updates.add(flushed);
+        metrics().persist_flush.observe(start.elapsed());
         Ok(())
     }
+
+    /// How far batch files trail the WAL: updates only in the WAL, and
+    /// the WAL's size on disk
+    pub fn wal_lag(&self) -> WalLag {
+        let pending_updates = self
+            .shards
+            .read()
+            .values()
+            .map(|state| state.buffer.len())
+            .sum();
+        let wal = self.wal.lock();
+        WalLag {
+            pending_updates,
+            wal_bytes: wal.file_size(),
+            wal_segments: wal.segment_count(),
+        }
+    }
+}
+
+impl Drop for FilePersist {
+    /// Clean shutdown: sync the WAL and remove the running marker so the
+    /// next startup skips full batch verification
+    fn drop(&mut self) {
+        if self.wal.lock().sync().is_ok() {
+            recovery::mark_clean(&self.config.path);
+        }
+        self.push_to_store(&["wal"]);
+    }
 }
 
 impl PersistBackend for FilePersist {
@@ -401,6 +872,7 @@ impl PersistBackend for FilePersist {
         if updates.is_empty() {
             return Ok(());
         }
+        let _span = tracing::info_span!("persist_append", shard, updates = updates.len()).entered();
 
         // Handle WAL based on durability mode
         match self.config.durability_mode {
@@ -410,9 +882,11 @@ impl PersistBackend for FilePersist {
                 wal.append_batch(shard, updates)?;
             }
             DurabilityMode::Batched => {
-                // Write to WAL without sync (faster, batched durability)
+                // Write to WAL without sync (faster, batched durability),
+                // syncing at most once per interval
                 let mut wal = self.wal.lock();
                 wal.append_batch_buffered(shard, updates)?;
+                wal.sync_if_due(Duration::from_millis(self.config.wal_sync_interval_ms))?;
             }
             DurabilityMode::Async => {
                 // Skip WAL entirely for maximum speed (in-memory only until flush).
@@ -484,6 +958,55 @@ impl PersistBackend for FilePersist {
         Ok(updates)
     }
 
+    fn read_by_schema_version(
+        &self,
+        shard: &str,
+        since: u64,
+    ) -> StorageResult<Vec<(u32, Vec<Update>)>> {
+        let shards = self.shards.read();
+
+        let state = shards
+            .get(shard)
+            .ok_or_else(|| StorageError::Other(format!("Shard not found: {shard}")))?;
+
+        let mut groups: BTreeMap<u32, Vec<Update>> = BTreeMap::new();
+        for batch_ref in &state.meta.batches {
+            if batch_ref.upper > since {
+                let batch_updates = self.read_batch(batch_ref)?;
+                groups
+                    .entry(batch_ref.schema_version)
+                    .or_default()
+                    .extend(batch_updates.into_iter().filter(|u| u.time >= since));
+            }
+        }
+
+        // Buffered updates were written under the shard's current version
+        groups
+            .entry(state.meta.schema_version)
+            .or_default()
+            .extend(state.buffer.iter().filter(|u| u.time >= since).cloned());
+
+        Ok(groups
+            .into_iter()
+            .filter(|(_, updates)| !updates.is_empty())
+            .collect())
+    }
+
+    fn set_schema_version(&self, shard: &str, version: u32) -> StorageResult<()> {
+        self.ensure_shard(shard)?;
+        self.flush(shard)?;
+
+        let mut shards = self.shards.write();
+        let state = shards
+            .get_mut(shard)
+            .ok_or_else(|| StorageError::Other(format!("Shard not found: {shard}")))?;
+        if state.meta.schema_version != version {
+            state.meta.schema_version = version;
+            self.save_shard_meta(&state.meta)?;
+        }
+        Ok(())
+    }
+
     fn compact(&self, shard: &str, new_since: u64) -> StorageResult<()> {
         // Flush first to ensure all data is in batches
         self.flush(shard)?;
@@ -493,36 +1016,44 @@ impl PersistBackend for FilePersist {
             .get_mut(shard)
             .ok_or_else(|| StorageError::Other(format!("Shard not found: {shard}")))?;
 
-        // Read all updates
-        let mut all_updates = Vec::new();
+        // Read all updates, keeping batches written under different schema
+        // versions apart: their tuples have different shapes
+        let mut by_version: BTreeMap<u32, Vec<Update>> = BTreeMap::new();
         for batch_ref in &state.meta.batches {
             let batch_updates = self.read_batch(batch_ref)?;
-            all_updates.extend(batch_updates);
+            by_version
+                .entry(batch_ref.schema_version)
+                .or_default()
+                .extend(batch_updates);
         }
 
-        // Filter and consolidate
-        let mut filtered: Vec<Update> = all_updates
-            .into_iter()
-            .filter(|u| u.time >= new_since)
-            .collect();
-        consolidate(&mut filtered);
-
-        // Remember old batch refs for cleanup after the new batch is durable
+        // Remember old batch refs for cleanup after the new batches are durable
         let old_batches: Vec<BatchRef> = state.meta.batches.drain(..).collect();
 
-        // Step 1: Write new compacted batch FIRST (crash-safe ordering)
-        // If we crash here, old batches still exist and metadata still points to them.
-        if !filtered.is_empty() {
-            let batch = Batch::new(filtered.clone());
-            let (batch_id, path) = self.write_batch(&filtered)?;
-
-            state.meta.add_batch(BatchRef {
-                id: batch_id,
-                path,
-                lower: batch.lower,
-                upper: batch.upper,
-                len: batch.len(),
-            });
+        for (schema_version, all_updates) in by_version {
+            // Filter and consolidate
+            let mut filtered: Vec<Update> = all_updates
+                .into_iter()
+                .filter(|u| u.time >= new_since)
+                .collect();
+            consolidate(&mut filtered);
+
+            // Step 1: Write new compacted batch FIRST (crash-safe ordering)
+            // If we crash here, old batches still exist and metadata still points to them.
+            if !filtered.is_empty() {
+                let batch = Batch::new(filtered.clone());
+                let (batch_id, path, checksum) = self.write_batch(&filtered)?;
+
+                state.meta.add_batch(BatchRef {
+                    id: batch_id,
+                    path,
+                    lower: batch.lower,
+                    upper: batch.upper,
+                    len: batch.len(),
+                    schema_version,
+                    checksum,
+                });
+            }
         }
 
         // Step 2: Update metadata atomically (write-to-temp+rename in save_shard_meta)
@@ -540,6 +1071,8 @@ impl PersistBackend for FilePersist {
         if !old_batches.is_empty() {
             sync_directory(&self.config.path.join("batches"));
         }
+        drop(shards);
+        self.push_to_store(&["shards", "batches"]);
 
         Ok(())
     }
@@ -569,54 +1102,23 @@ impl PersistBackend for FilePersist {
                     buffer: Vec::new(),
                 },
             );
+            drop(shards);
+            self.push_to_store(&["shards"]);
         }
         Ok(())
     }
 
     fn sync(&self) -> StorageResult<()> {
-        let mut wal = self.wal.lock();
-        wal.sync()
+        self.wal.lock().sync()?;
+        self.push_to_store(&["wal"]);
+        Ok(())
     }
 
     fn flush(&self, shard: &str) -> StorageResult<()> {
-        let mut shards = self.shards.write();
-        let state = shards
-            .get_mut(shard)
-            .ok_or_else(|| StorageError::Other(format!("Shard not found: {shard}")))?;
-
-        if state.buffer.is_empty() {
-            return Ok(());
-        }
-
-        // Step 1: Write buffer to batch file (atomic via temp+rename in write_batch)
-        let batch = Batch::new(state.buffer.clone());
-        let (batch_id, path) = self.write_batch(&state.buffer)?;
-
-        let batch_ref = BatchRef {
-            id: batch_id,
-            path: path.clone(),
-            lower: batch.lower,
-            upper: batch.upper,
-            len: batch.len(),
-        };
-
-        // Step 2: Update metadata and save atomically
-        state.meta.add_batch(batch_ref);
-        state.buffer.clear();
-
-        if let Err(e) = self.save_shard_meta(&state.meta) {
-            // Metadata save failed - clean up the orphaned batch file
-            let _ = fs::remove_file(&path);
-            return Err(e);
+        if !self.shards.read().contains_key(shard) {
+            return Err(StorageError::Other(format!("Shard not found: {shard}")));
         }
-
-        // Step 3: Remove WAL entries LAST (safe - metadata already points to batch)
-        {
-            let mut wal = self.wal.lock();
-            wal.remove_shard_entries(shard)?;
-        }
-
-        Ok(())
+        self.flush_shards(&[shard.to_string()]).map(|_| ())
     }
 
     fn delete_shard(&self, shard: &str) -> StorageResult<()> {
@@ -660,11 +1162,46 @@ impl PersistBackend for FilePersist {
             let _ = fs::remove_file(&meta_path);
             sync_directory(&self.config.path.join("shards"));
         }
+        self.push_to_store(&["shards", "batches", "wal"]);
 
         Ok(())
     }
 }
 
+/// Handle a knowledge graph's catalogs log their changes through (see
+/// [`FilePersist::log_ddl`])
+#[derive(Clone)]
+pub struct DdlLog {
+    persist: Arc<FilePersist>,
+    knowledge_graph: String,
+}
+
+impl DdlLog {
+    /// Log for the catalogs of `knowledge_graph`
+    pub fn new(persist: Arc<FilePersist>, knowledge_graph: String) -> Self {
+        DdlLog {
+            persist,
+            knowledge_graph,
+        }
+    }
+
+    /// Record the contents a catalog file is about to be rewritten with
+    pub fn record(&self, catalog: Catalog, contents: String) -> StorageResult<()> {
+        self.persist.log_ddl(
+            &self.knowledge_graph,
+            DdlChange::Catalog { catalog, contents },
+        )
+    }
+}
+
+impl std::fmt::Debug for DdlLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DdlLog")
+            .field("knowledge_graph", &self.knowledge_graph)
+            .finish_non_exhaustive()
+    }
+}
+
 // Parquet I/O for Update batches
 /// Infer schema from updates - needed because we don't have stored schema yet
 fn infer_schema_from_updates(updates: &[Update]) -> TupleSchema {
@@ -693,7 +1230,11 @@ fn infer_schema_from_updates(updates: &[Update]) -> TupleSchema {
 /// - N data columns (from the Tuple)
 /// - time column (`UInt64`)
 /// - diff column (Int64)
-fn write_updates_parquet(path: &PathBuf, updates: &[Update]) -> StorageResult<()> {
+fn write_updates_parquet(
+    path: &PathBuf,
+    updates: &[Update],
+    encryption: Option<&Encryption>,
+) -> StorageResult<()> {
     if updates.is_empty() {
         // No data to write - skip creating the file entirely.
         // The caller handles absence of batch files gracefully.
@@ -732,12 +1273,21 @@ fn write_updates_parquet(path: &PathBuf, updates: &[Update]) -> StorageResult<()
 
     let batch = RecordBatch::try_new(full_schema.clone(), columns).map_err(StorageError::Arrow)?;
 
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer = ArrowWriter::try_new(Vec::new(), full_schema, Some(props))
+        .map_err(StorageError::Parquet)?;
+    writer.write(&batch).map_err(StorageError::Parquet)?;
+    let content = writer.into_inner().map_err(StorageError::Parquet)?;
+    let content = encryption::seal(content, encryption, FileKind::Batch, path)?;
+
     // Write to temp file then rename atomically (crash-safe).
     // If we crash mid-write, the temp file is orphaned but the original path
     // is never left in a corrupt half-written state.
     let tmp_path = path.with_extension("parquet.tmp");
 
-    let file = match fs::File::create(&tmp_path) {
+    let mut file = match fs::File::create(&tmp_path) {
         Ok(f) => f,
         Err(e) => {
             // ENOSPC or permission error - no temp file to clean up
@@ -747,17 +1297,11 @@ fn write_updates_parquet(path: &PathBuf, updates: &[Update]) -> StorageResult<()
             )));
         }
     };
-    let props = WriterProperties::builder()
-        .set_compression(Compression::SNAPPY)
-        .build();
 
     // Helper: clean up temp file on any write error (ENOSPC, etc.)
     let write_result = (|| -> StorageResult<()> {
-        let mut writer =
-            ArrowWriter::try_new(file, full_schema, Some(props)).map_err(StorageError::Parquet)?;
-        writer.write(&batch).map_err(StorageError::Parquet)?;
-        writer.close().map_err(StorageError::Parquet)?;
-        fs::File::open(&tmp_path)?.sync_all()?;
+        file.write_all(&content)?;
+        file.sync_all()?;
         Ok(())
     })();
 
@@ -773,10 +1317,24 @@ fn write_updates_parquet(path: &PathBuf, updates: &[Update]) -> StorageResult<()
     Ok(())
 }
 
-/// Read updates from a Parquet file
-fn read_updates_parquet(path: &PathBuf) -> StorageResult<Vec<Update>> {
-    let file = fs::File::open(path)?;
-    let builder = ParquetRecordBatchReaderBuilder::try_new(file).map_err(StorageError::Parquet)?;
+/// Read updates from a Parquet file, plain or sealed
+fn read_updates_parquet(
+    path: &PathBuf,
+    encryption: Option<&Encryption>,
+) -> StorageResult<Vec<Update>> {
+    if encryption::is_encrypted_file(path)? {
+        let content = encryption::read_file(path, encryption, FileKind::Batch)?;
+        read_updates_from(Bytes::from(content))
+    } else {
+        encryption::check_plain(encryption, FileKind::Batch)?;
+        read_updates_from(fs::File::open(path)?)
+    }
+}
+
+/// Read updates from Parquet data
+fn read_updates_from<T: ChunkReader + 'static>(source: T) -> StorageResult<Vec<Update>> {
+    let builder =
+        ParquetRecordBatchReaderBuilder::try_new(source).map_err(StorageError::Parquet)?;
 
     let reader = builder.build().map_err(StorageError::Parquet)?;
 
@@ -911,6 +1469,7 @@ mod tests {
         // After flush, data should be in batch file
         let info = persist.shard_info("db:edge").unwrap();
         assert_eq!(info.batch_count, 1);
+        assert!(info.size_bytes > 0);
 
         let read = persist.read("db:edge", 0).unwrap();
         assert_eq!(read.len(), 2);
@@ -992,6 +1551,45 @@ mod tests {
         assert!(updates.iter().all(|u| u.time >= 15));
     }
 
+    #[test]
+    fn test_schema_version_stamped_on_batches() {
+        let temp = TempDir::new().unwrap();
+        let config = PersistConfig {
+            path: temp.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        {
+            let persist = FilePersist::new(config.clone()).unwrap();
+            persist
+                .append("db:user", &[Update::insert(Tuple::from_pair(1, 2), 1)])
+                .unwrap();
+            // Buffered v0 data is flushed before the version changes
+            persist.set_schema_version("db:user", 2).unwrap();
+            let wide = Tuple::new(vec![Value::Int64(3), Value::Int64(4), Value::Int64(5)]);
+            persist
+                .append("db:user", &[Update::insert(wide, 2)])
+                .unwrap();
+
+            let groups = persist.read_by_schema_version("db:user", 0).unwrap();
+            assert_eq!(groups.len(), 2);
+            assert_eq!(groups[0].0, 0);
+            assert_eq!(groups[0].1[0].data.arity(), 2);
+            assert_eq!(groups[1].0, 2);
+            assert_eq!(groups[1].1[0].data.arity(), 3);
+
+            // Compaction keeps versions in separate batches
+            persist.compact("db:user", 0).unwrap();
+            assert_eq!(persist.shard_info("db:user").unwrap().batch_count, 2);
+        }
+
+        // Versions survive a restart
+        let persist = FilePersist::new(config).unwrap();
+        let groups = persist.read_by_schema_version("db:user", 0).unwrap();
+        let versions: Vec<u32> = groups.iter().map(|(v, _)| *v).collect();
+        assert_eq!(versions, vec![0, 2]);
+    }
+
     #[test]
     fn test_list_shards() {
         let (_temp, persist) = create_test_persist();
@@ -1187,6 +1785,7 @@ mod tests {
         persist.ensure_shard("db:info_test").unwrap();
         let info = persist.shard_info("db:info_test").unwrap();
         assert_eq!(info.batch_count, 0);
+        assert_eq!(info.size_bytes, 0);
         assert_eq!(info.since, 0);
     }
 
@@ -1699,6 +2298,7 @@ mod tests {
             buffer_size: 1000, // High buffer so normal buffer-based flush won't trigger
             durability_mode: DurabilityMode::Immediate,
             max_wal_size_bytes: 100, // Very low limit to trigger flush quickly
+            ..Default::default()
         };
         let persist = FilePersist::new(config).unwrap();
 
@@ -1747,6 +2347,7 @@ mod tests {
             buffer_size: 1000,
             durability_mode: DurabilityMode::Immediate,
             max_wal_size_bytes: 0, // Unlimited
+            ..Default::default()
         };
         let persist = FilePersist::new(config).unwrap();
 
@@ -1781,4 +2382,244 @@ mod tests {
         let read = persist.read("db:unlimited", 0).unwrap();
         assert_eq!(read.len(), 20);
     }
+
+    /// Write one flushed batch to `db:edge` and return its file path
+    fn write_flushed_batch(config: &PersistConfig) -> PathBuf {
+        let persist = FilePersist::new(config.clone()).unwrap();
+        persist
+            .append(
+                "db:edge",
+                &[
+                    Update::insert(Tuple::from_pair(1, 2), 1),
+                    Update::insert(Tuple::from_pair(3, 4), 2),
+                ],
+            )
+            .unwrap();
+        persist.flush("db:edge").unwrap();
+        let path = persist.shards.read()["db:edge"].meta.batches[0]
+            .path
+            .clone();
+        path
+    }
+
+    #[test]
+    fn test_unclean_shutdown_quarantines_corrupt_batch() {
+        let temp = TempDir::new().unwrap();
+        let config = PersistConfig {
+            path: temp.path().to_path_buf(),
+            ..Default::default()
+        };
+        let batch_path = write_flushed_batch(&config);
+
+        // Clean restart: no verification needed
+        {
+            let persist = FilePersist::new(config.clone()).unwrap();
+            let report = persist.recovery_report();
+            assert!(!report.unclean_shutdown);
+            assert_eq!(report.batches_verified, 0);
+        }
+
+        // Simulate a crash (marker left behind) plus bit-rot in the batch
+        fs::write(temp.path().join("running"), "").unwrap();
+        let mut bytes = fs::read(&batch_path).unwrap();
+        let mid = bytes.len() / 2;
+        bytes[mid] ^= 0xff;
+        fs::write(&batch_path, bytes).unwrap();
+
+        let persist = FilePersist::new(config).unwrap();
+        let report = persist.recovery_report().clone();
+        assert!(report.unclean_shutdown);
+        assert_eq!(report.batches_verified, 1);
+        assert_eq!(report.corrupt_batches.len(), 1);
+        assert!(report.corrupt_batches[0].reason.contains("checksum"));
+        let quarantined = report.corrupt_batches[0].quarantined_to.clone().unwrap();
+        assert!(quarantined.exists());
+        assert!(!batch_path.exists());
+        assert!(persist.read("db:edge", 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unclean_shutdown_fail_policy() {
+        let temp = TempDir::new().unwrap();
+        let config = PersistConfig {
+            path: temp.path().to_path_buf(),
+            on_corruption: CorruptionPolicy::Fail,
+            ..Default::default()
+        };
+        let batch_path = write_flushed_batch(&config);
+
+        fs::write(temp.path().join("running"), "").unwrap();
+        fs::write(&batch_path, b"not parquet").unwrap();
+
+        let err = FilePersist::new(config).err().unwrap().to_string();
+        assert!(err.contains("consistency check failed"), "{err}");
+        assert!(batch_path.exists(), "fail policy leaves files in place");
+    }
+
+    #[test]
+    fn test_checkpoint_truncates_wal_and_survives_restart() {
+        let temp = TempDir::new().unwrap();
+        let config = PersistConfig {
+            path: temp.path().to_path_buf(),
+            buffer_size: 1000,
+            wal_segment_size_bytes: 256, // Rotate every few entries
+            ..Default::default()
+        };
+        {
+            let persist = FilePersist::new(config.clone()).unwrap();
+            for i in 0..20i32 {
+                let shard = if i % 2 == 0 { "db:a" } else { "db:b" };
+                persist
+                    .append(shard, &[Update::insert(Tuple::from_pair(i, i), i as u64)])
+                    .unwrap();
+            }
+            assert!(persist.wal.lock().segment_count() > 0);
+
+            persist.checkpoint().unwrap();
+            let wal = persist.wal.lock();
+            assert_eq!(wal.file_size(), 0, "checkpoint should truncate the WAL");
+            assert_eq!(wal.segment_count(), 0);
+        }
+
+        let persist = FilePersist::new(config).unwrap();
+        assert_eq!(persist.read("db:a", 0).unwrap().len(), 10);
+        assert_eq!(persist.read("db:b", 0).unwrap().len(), 10);
+    }
+
+    #[test]
+    fn test_checkpoint_writes_only_dirty_shards() {
+        let temp = TempDir::new().unwrap();
+        let persist = FilePersist::new(PersistConfig {
+            path: temp.path().to_path_buf(),
+            buffer_size: 1000,
+            ..Default::default()
+        })
+        .unwrap();
+        for shard in ["db:a", "db:b", "db:c"] {
+            persist
+                .append(shard, &[Update::insert(Tuple::from_pair(1, 1), 1)])
+                .unwrap();
+        }
+        persist.checkpoint().unwrap();
+        assert!(persist.dirty_shards().is_empty());
+
+        persist
+            .append("db:b", &[Update::insert(Tuple::from_pair(2, 2), 2)])
+            .unwrap();
+        assert_eq!(persist.dirty_shards(), vec!["db:b".to_string()]);
+        persist.checkpoint().unwrap();
+
+        assert_eq!(persist.shard_info("db:a").unwrap().batch_count, 1);
+        assert_eq!(persist.shard_info("db:b").unwrap().batch_count, 2);
+        assert_eq!(persist.shard_info("db:c").unwrap().batch_count, 1);
+        assert_eq!(persist.wal.lock().file_size(), 0);
+        assert_eq!(persist.read("db:b", 0).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_object_store_restores_empty_directory() {
+        let remote = TempDir::new().unwrap();
+        let store: Arc<dyn ObjectStore> = Arc::new(crate::storage::LocalObjectStore::new(
+            remote.path().to_path_buf(),
+        ));
+        let first = TempDir::new().unwrap();
+        {
+            let persist = FilePersist::new(PersistConfig {
+                path: first.path().to_path_buf(),
+                buffer_size: 1000,
+                object_store: Some(Arc::clone(&store)),
+                ..Default::default()
+            })
+            .unwrap();
+            for i in 0..5i32 {
+                persist
+                    .append("db:a", &[Update::insert(Tuple::from_pair(i, i), 1)])
+                    .unwrap();
+            }
+            persist.flush("db:a").unwrap();
+            // Still only in the WAL: reaches the store on sync
+            persist
+                .append("db:a", &[Update::insert(Tuple::from_pair(9, 9), 2)])
+                .unwrap();
+            persist.sync().unwrap();
+        }
+        assert!(!store.list("batches/").unwrap().is_empty());
+        drop(first);
+
+        // A server on a fresh disk picks up the data from the store
+        let second = TempDir::new().unwrap();
+        let persist = FilePersist::new(PersistConfig {
+            path: second.path().to_path_buf(),
+            object_store: Some(store),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(persist.read("db:a", 0).unwrap().len(), 6);
+    }
+
+    #[test]
+    fn test_encryption_at_rest_and_rotation() {
+        let temp = TempDir::new().unwrap();
+        let open = |encryption: Option<Encryption>| {
+            FilePersist::new(PersistConfig {
+                path: temp.path().to_path_buf(),
+                buffer_size: 1000,
+                encryption: encryption.map(Arc::new),
+                ..Default::default()
+            })
+        };
+        let batch_file =
+            |persist: &FilePersist| persist.shards.read()["db:a"].meta.batches[0].path.clone();
+
+        // Plain batch, then a sealed batch and sealed WAL entries
+        {
+            let persist = open(None).unwrap();
+            persist
+                .append("db:a", &[Update::insert(Tuple::from_pair(1, 1), 1)])
+                .unwrap();
+            persist.flush("db:a").unwrap();
+        }
+        {
+            let persist = open(Some(Encryption::new(&[1; 32], &[]))).unwrap();
+            persist
+                .append("db:a", &[Update::insert(Tuple::from_pair(2, 2), 2)])
+                .unwrap();
+            persist.flush("db:a").unwrap();
+            persist
+                .append("db:a", &[Update::insert(Tuple::from_pair(3, 3), 3)])
+                .unwrap();
+            let meta = fs::read(temp.path().join("shards").join("db_a.json")).unwrap();
+            assert!(encryption::is_encrypted(&meta));
+            let wal = fs::read_to_string(temp.path().join("wal").join("current.wal")).unwrap();
+            assert!(!wal.contains("db:a"));
+        }
+
+        // Sealed files cannot be read without their key
+        assert!(open(None).is_err());
+        assert!(open(Some(Encryption::new(&[2; 32], &[]))).is_err());
+
+        // Rotate to a new key, keeping the old one for reading
+        {
+            let persist = open(Some(Encryption::new(&[2; 32], &[[1; 32]]))).unwrap();
+            assert_eq!(persist.read("db:a", 0).unwrap().len(), 3);
+            assert!(!encryption::is_encrypted_file(&batch_file(&persist)).unwrap());
+            assert_eq!(persist.rotate_encryption().unwrap(), 2);
+            assert_eq!(persist.rotate_encryption().unwrap(), 0);
+        }
+
+        // The old key is no longer needed
+        {
+            let persist = open(Some(Encryption::new(&[2; 32], &[]))).unwrap();
+            assert!(encryption::is_encrypted_file(&batch_file(&persist)).unwrap());
+            assert_eq!(persist.read("db:a", 0).unwrap().len(), 3);
+            assert!(persist.encryption().unwrap().is_strict());
+        }
+
+        // After the rotation a plain file is rejected
+        let wal = temp.path().join("wal").join("current.wal");
+        let mut content = fs::read_to_string(&wal).unwrap_or_default();
+        content.push_str("{}\n");
+        fs::write(&wal, content).unwrap();
+        assert!(open(Some(Encryption::new(&[2; 32], &[]))).is_err());
+    }
 }
@@ -0,0 +1,488 @@
+//! Filtered Scans
+//!
+//! A scan reads a shard like [`PersistBackend::read_by_schema_version`],
+//! but pushes a [`ScanFilter`] into the Parquet reader of each batch file:
+//!
+//! - row groups whose column statistics (min/max) rule out every
+//!   alternative of the filter are skipped without being decoded;
+//! - only the data columns the filter selects or compares (plus `time` and
+//!   `diff`) are decoded; the others come back as `Null`, so tuples keep
+//!   their arity;
+//! - rows that cannot match are dropped before they are collected.
+//!
+//! The filter only describes the batches written under one schema
+//! version. Batches of other versions are read whole, for the caller to
+//! adapt and then filter with [`ScanFilter::apply`].
+//!
+//! [`PersistBackend::read_by_schema_version`]: super::PersistBackend::read_by_schema_version
+
+use super::encryption::{self, Encryption, FileKind};
+use super::{FilePersist, Update};
+use crate::storage::{StorageError, StorageResult};
+use crate::value::{record_batch_to_tuples, Tuple, Value};
+use arrow::array::{Int64Array, UInt64Array};
+use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
+use bytes::Bytes;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ProjectionMask;
+use parquet::file::metadata::RowGroupMetaData;
+use parquet::file::reader::ChunkReader;
+use parquet::file::statistics::Statistics;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Largest difference at which a number still equals a float constant, as
+/// in rule evaluation
+const FLOAT_EQ_TOLERANCE: f64 = 1e-10;
+
+/// Rows and columns a scan needs
+#[derive(Debug, Clone, Default)]
+pub struct ScanFilter {
+    /// Alternatives of column equalities: a row is kept if it satisfies
+    /// every equality of at least one. No alternatives keeps every row.
+    pub any_of: Vec<Vec<(usize, Value)>>,
+    /// Data columns to read; the others are returned as `Null`. `None`
+    /// reads every column.
+    pub columns: Option<Vec<usize>>,
+}
+
+impl ScanFilter {
+    /// Whether `tuple` satisfies the filter
+    pub fn matches(&self, tuple: &Tuple) -> bool {
+        self.any_of.is_empty()
+            || self.any_of.iter().any(|all| {
+                all.iter().all(|(col, constant)| {
+                    tuple
+                        .get(*col)
+                        .is_some_and(|value| value_equals(value, constant))
+                })
+            })
+    }
+
+    /// Keep the updates that satisfy the filter, with the columns it
+    /// neither selects nor compares set to `Null`, as a scan returns them
+    pub fn apply(&self, updates: Vec<Update>) -> Vec<Update> {
+        updates
+            .into_iter()
+            .filter(|update| self.matches(&update.data))
+            .map(|update| Update {
+                data: self.project(update.data),
+                ..update
+            })
+            .collect()
+    }
+
+    fn project(&self, tuple: Tuple) -> Tuple {
+        if self.columns.is_none() {
+            return tuple;
+        }
+        let columns = self.read_columns(tuple.arity());
+        tuple
+            .into_values()
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| {
+                if columns.contains(&i) {
+                    value
+                } else {
+                    Value::Null
+                }
+            })
+            .collect()
+    }
+
+    /// Data columns to decode out of `arity`: the selected ones and those
+    /// the filter compares
+    fn read_columns(&self, arity: usize) -> Vec<usize> {
+        let mut columns: Vec<usize> = match &self.columns {
+            Some(columns) => columns
+                .iter()
+                .copied()
+                .chain(self.any_of.iter().flatten().map(|(col, _)| *col))
+                .filter(|&col| col < arity)
+                .collect(),
+            None => (0..arity).collect(),
+        };
+        columns.sort_unstable();
+        columns.dedup();
+        columns
+    }
+
+    /// Whether a row group may hold a matching row, judging by its column
+    /// statistics. `leaves` maps a data column to its Parquet leaf column.
+    fn row_group_may_match(
+        &self,
+        group: &RowGroupMetaData,
+        leaves: &HashMap<usize, usize>,
+    ) -> bool {
+        self.any_of.is_empty()
+            || self.any_of.iter().any(|all| {
+                all.iter().all(|(col, constant)| {
+                    leaves
+                        .get(col)
+                        .and_then(|&leaf| group.column(leaf).statistics())
+                        .is_none_or(|stats| may_contain(stats, constant))
+                })
+            })
+    }
+}
+
+/// Row groups and rows seen by a scan
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanStats {
+    /// Row groups in the batch files read
+    pub row_groups: usize,
+    /// Row groups skipped on their statistics
+    pub row_groups_skipped: usize,
+    /// Rows decoded from the row groups read
+    pub rows_read: usize,
+    /// Rows kept
+    pub rows_kept: usize,
+}
+
+impl std::ops::AddAssign for ScanStats {
+    fn add_assign(&mut self, other: Self) {
+        self.row_groups += other.row_groups;
+        self.row_groups_skipped += other.row_groups_skipped;
+        self.rows_read += other.rows_read;
+        self.rows_kept += other.rows_kept;
+    }
+}
+
+/// Whether `value` equals the constant of a filter, the way rule
+/// evaluation compares them: integers match floats within a tolerance
+fn value_equals(value: &Value, constant: &Value) -> bool {
+    match constant {
+        Value::Int64(c) => match value.as_i64() {
+            Some(i) => i == *c,
+            None => value
+                .as_f64()
+                .is_some_and(|f| (f - *c as f64).abs() < FLOAT_EQ_TOLERANCE),
+        },
+        Value::Float64(c) => value
+            .as_f64()
+            .is_some_and(|f| (f - c).abs() < FLOAT_EQ_TOLERANCE),
+        Value::String(c) => value.as_str() == Some(c.as_ref()),
+        Value::Bool(c) => value.as_bool() == Some(*c),
+        other => value == other,
+    }
+}
+
+/// Whether a column chunk with these statistics may hold a value equal to
+/// `constant`. Without usable bounds it may.
+fn may_contain(stats: &Statistics, constant: &Value) -> bool {
+    let in_range =
+        |min: f64, max: f64, c: f64| min - FLOAT_EQ_TOLERANCE <= c && c <= max + FLOAT_EQ_TOLERANCE;
+    match (stats, constant) {
+        (Statistics::Int32(s), Value::Int64(c)) => match (s.min_opt(), s.max_opt()) {
+            (Some(&min), Some(&max)) => i64::from(min) <= *c && *c <= i64::from(max),
+            _ => true,
+        },
+        (Statistics::Int64(s), Value::Int64(c)) => match (s.min_opt(), s.max_opt()) {
+            (Some(&min), Some(&max)) => min <= *c && *c <= max,
+            _ => true,
+        },
+        (Statistics::Int32(s), Value::Float64(c)) => match (s.min_opt(), s.max_opt()) {
+            (Some(&min), Some(&max)) => in_range(f64::from(min), f64::from(max), *c),
+            _ => true,
+        },
+        (Statistics::Int64(s), Value::Float64(c)) => match (s.min_opt(), s.max_opt()) {
+            (Some(&min), Some(&max)) => in_range(min as f64, max as f64, *c),
+            _ => true,
+        },
+        (Statistics::Double(s), Value::Int64(c)) => match (s.min_opt(), s.max_opt()) {
+            (Some(&min), Some(&max)) => in_range(min, max, *c as f64),
+            _ => true,
+        },
+        (Statistics::Double(s), Value::Float64(c)) => match (s.min_opt(), s.max_opt()) {
+            (Some(&min), Some(&max)) => in_range(min, max, *c),
+            _ => true,
+        },
+        (Statistics::ByteArray(s), Value::String(c)) => match (s.min_opt(), s.max_opt()) {
+            (Some(min), Some(max)) => min.data() <= c.as_bytes() && c.as_bytes() <= max.data(),
+            _ => true,
+        },
+        (Statistics::Boolean(s), Value::Bool(c)) => match (s.min_opt(), s.max_opt()) {
+            (Some(min), Some(max)) => min <= c && c <= max,
+            _ => true,
+        },
+        _ => true,
+    }
+}
+
+/// Read the updates of a batch file that may satisfy `filter`. A sealed
+/// file is opened in memory first.
+fn read_updates_filtered(
+    path: &Path,
+    encryption: Option<&Encryption>,
+    filter: &ScanFilter,
+    stats: &mut ScanStats,
+) -> StorageResult<Vec<Update>> {
+    if encryption::is_encrypted_file(path)? {
+        let content = encryption::read_file(path, encryption, FileKind::Batch)?;
+        scan_updates(Bytes::from(content), filter, stats)
+    } else {
+        encryption::check_plain(encryption, FileKind::Batch)?;
+        scan_updates(fs::File::open(path)?, filter, stats)
+    }
+}
+
+/// Read the updates of Parquet data that may satisfy `filter`
+fn scan_updates<T: ChunkReader + 'static>(
+    source: T,
+    filter: &ScanFilter,
+    stats: &mut ScanStats,
+) -> StorageResult<Vec<Update>> {
+    let builder =
+        ParquetRecordBatchReaderBuilder::try_new(source).map_err(StorageError::Parquet)?;
+
+    // Last two columns are always time and diff
+    let fields = builder.schema().fields().len();
+    if fields < 2 {
+        return Err(StorageError::Other(
+            "Invalid parquet file: not enough columns".to_string(),
+        ));
+    }
+    let arity = fields - 2;
+
+    // Statistics are kept per leaf column; nested columns have several
+    // leaves and are not used for pruning
+    let descr = builder.parquet_schema();
+    let mut leaf_counts: HashMap<usize, (usize, usize)> = HashMap::new();
+    for leaf in 0..descr.num_columns() {
+        leaf_counts
+            .entry(descr.get_column_root_idx(leaf))
+            .and_modify(|(count, _)| *count += 1)
+            .or_insert((1, leaf));
+    }
+    let leaves: HashMap<usize, usize> = leaf_counts
+        .into_iter()
+        .filter(|&(_, (count, _))| count == 1)
+        .map(|(root, (_, leaf))| (root, leaf))
+        .collect();
+
+    let groups = builder.metadata().row_groups();
+    let keep: Vec<usize> = (0..groups.len())
+        .filter(|&i| filter.row_group_may_match(&groups[i], &leaves))
+        .collect();
+    stats.row_groups += groups.len();
+    stats.row_groups_skipped += groups.len() - keep.len();
+
+    let columns = filter.read_columns(arity);
+    let mask = ProjectionMask::roots(
+        builder.parquet_schema(),
+        columns.iter().copied().chain([arity, arity + 1]),
+    );
+    let reader = builder
+        .with_row_groups(keep)
+        .with_projection(mask)
+        .build()
+        .map_err(StorageError::Parquet)?;
+
+    let mut updates = Vec::new();
+    for batch_result in reader {
+        let batch = batch_result.map_err(StorageError::Arrow)?;
+        let time_col_idx = columns.len();
+        let times = batch
+            .column(time_col_idx)
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .ok_or_else(|| StorageError::Other("Invalid time column type".to_string()))?;
+        let diffs = batch
+            .column(time_col_idx + 1)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .ok_or_else(|| StorageError::Other("Invalid diff column type".to_string()))?;
+
+        let rows = if columns.is_empty() {
+            vec![Vec::new(); batch.num_rows()]
+        } else {
+            let data_schema = Arc::new(Schema::new(
+                batch.schema().fields()[..time_col_idx]
+                    .iter()
+                    .map(|f| f.as_ref().clone())
+                    .collect::<Vec<_>>(),
+            ));
+            let data_batch =
+                RecordBatch::try_new(data_schema, batch.columns()[..time_col_idx].to_vec())
+                    .map_err(StorageError::Arrow)?;
+            let (tuples, _) = record_batch_to_tuples(&data_batch)
+                .map_err(|e| StorageError::Other(format!("Arrow conversion error: {e}")))?;
+            tuples.into_iter().map(Tuple::into_values).collect()
+        };
+
+        stats.rows_read += rows.len();
+        for (i, read) in rows.into_iter().enumerate() {
+            let mut values = vec![Value::Null; arity];
+            for (&col, value) in columns.iter().zip(read) {
+                values[col] = value;
+            }
+            let data = Tuple::new(values);
+            if filter.matches(&data) {
+                updates.push(Update {
+                    data,
+                    time: times.value(i),
+                    diff: diffs.value(i),
+                });
+            }
+        }
+    }
+    stats.rows_kept += updates.len();
+    Ok(updates)
+}
+
+impl FilePersist {
+    /// Read the updates of a shard at or after `since`, grouped by the
+    /// schema version they were written under. Those of `version` are
+    /// read through `filter`; the others are returned whole.
+    pub fn scan_by_schema_version(
+        &self,
+        shard: &str,
+        since: u64,
+        version: u32,
+        filter: &ScanFilter,
+    ) -> StorageResult<(Vec<(u32, Vec<Update>)>, ScanStats)> {
+        let shards = self.shards.read();
+
+        let state = shards
+            .get(shard)
+            .ok_or_else(|| StorageError::Other(format!("Shard not found: {shard}")))?;
+
+        let mut stats = ScanStats::default();
+        let mut groups: BTreeMap<u32, Vec<Update>> = BTreeMap::new();
+        for batch_ref in &state.meta.batches {
+            if batch_ref.upper <= since {
+                continue;
+            }
+            let batch_updates = if batch_ref.schema_version == version {
+                read_updates_filtered(&batch_ref.path, self.encryption(), filter, &mut stats)?
+            } else {
+                self.read_batch(batch_ref)?
+            };
+            groups
+                .entry(batch_ref.schema_version)
+                .or_default()
+                .extend(batch_updates.into_iter().filter(|u| u.time >= since));
+        }
+
+        // Buffered updates were written under the shard's current version
+        let buffered: Vec<Update> = state
+            .buffer
+            .iter()
+            .filter(|u| u.time >= since)
+            .cloned()
+            .collect();
+        let buffered = if state.meta.schema_version == version {
+            filter.apply(buffered)
+        } else {
+            buffered
+        };
+        groups
+            .entry(state.meta.schema_version)
+            .or_default()
+            .extend(buffered);
+
+        Ok((
+            groups
+                .into_iter()
+                .filter(|(_, updates)| !updates.is_empty())
+                .collect(),
+            stats,
+        ))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::super::{PersistBackend, PersistConfig};
+    use super::*;
+    use tempfile::TempDir;
+
+    fn row(id: i64, name: &str, score: f64) -> Tuple {
+        Tuple::new(vec![
+            Value::Int64(id),
+            Value::String(name.into()),
+            Value::Float64(score),
+        ])
+    }
+
+    #[test]
+    fn test_scan_prunes_row_groups_and_columns() {
+        let dir = TempDir::new().unwrap();
+        let persist = FilePersist::new(PersistConfig {
+            path: dir.path().to_path_buf(),
+            buffer_size: 1000,
+            ..PersistConfig::default()
+        })
+        .unwrap();
+
+        // Three batch files with disjoint id and name ranges, one row group
+        // each
+        for (batch, time) in [(0, 1), (1, 2), (2, 3)] {
+            let updates: Vec<Update> = (batch * 10..batch * 10 + 10)
+                .map(|id| Update::insert(row(id, &format!("n{id:02}"), id as f64 / 2.0), time))
+                .collect();
+            persist.append("kg:r", &updates).unwrap();
+            persist.flush("kg:r").unwrap();
+        }
+        // A buffered delete of a matching row
+        persist
+            .append("kg:r", &[Update::delete(row(12, "n12", 6.0), 4)])
+            .unwrap();
+        // The shard was never re-versioned
+        let version = 0;
+
+        let filter = ScanFilter {
+            any_of: vec![vec![(0, Value::Int64(12))], vec![(1, "n25".into())]],
+            columns: Some(vec![0]),
+        };
+        let (groups, stats) = persist
+            .scan_by_schema_version("kg:r", 0, version, &filter)
+            .unwrap();
+        assert_eq!(stats.row_groups, 3);
+        assert_eq!(stats.row_groups_skipped, 1);
+        assert_eq!(stats.rows_read, 20);
+
+        let mut updates: Vec<Update> = groups.into_iter().flat_map(|(_, u)| u).collect();
+        updates.sort_by_key(|u| u.time);
+        let rows: Vec<(Tuple, i64)> = updates.into_iter().map(|u| (u.data, u.diff)).collect();
+        // Unselected columns come back as Null, filter columns are read
+        assert_eq!(
+            rows,
+            vec![
+                (
+                    Tuple::new(vec![Value::Int64(12), "n12".into(), Value::Null]),
+                    1
+                ),
+                (
+                    Tuple::new(vec![Value::Int64(25), "n25".into(), Value::Null]),
+                    1
+                ),
+                (
+                    Tuple::new(vec![Value::Int64(12), "n12".into(), Value::Null]),
+                    -1
+                ),
+            ]
+        );
+
+        // An integer constant matches a float column within tolerance
+        let filter = ScanFilter {
+            any_of: vec![vec![(2, Value::Int64(7))]],
+            columns: None,
+        };
+        let (groups, stats) = persist
+            .scan_by_schema_version("kg:r", 0, version, &filter)
+            .unwrap();
+        assert_eq!(stats.row_groups_skipped, 2);
+        let rows: Vec<Tuple> = groups
+            .into_iter()
+            .flat_map(|(_, u)| u)
+            .map(|u| u.data)
+            .collect();
+        assert_eq!(rows, vec![row(14, "n14", 7.0)]);
+    }
+}
@@ -2,13 +2,53 @@
 //!
 //! The WAL provides durability for updates that haven't been flushed to batch files yet.
 //! Each entry contains the shard name and the update data.
+//!
+//! Entries are appended to `current.wal`. Once it reaches the configured
+//! segment size it is sealed as `segment-<seq>.wal` and a fresh `current.wal`
+//! is started, so flushing one shard only rewrites the segments that hold
+//! its entries. Recovery reads sealed segments in order, then `current.wal`.
+//!
+//! With archiving enabled, every sealed segment is also hard-linked into
+//! `archive/` before it can be rewritten, and entries carry the wall-clock
+//! time they were written. Truncation seals the current file first, so the
+//! archive holds every entry exactly once, in write order. Point-in-time
+//! recovery uses it to map a timestamp to a logical time.
+//!
+//! With encryption at rest, each line's payload is the base64 of a sealed
+//! envelope (see [`super::encryption`]) instead of JSON; the checksum
+//! covers the payload either way. Plain and sealed lines can be mixed
+//! until a key rotation turns on strict mode. Records are bound to the WAL
+//! as a whole rather than to a file, as they move between files.
+//!
+//! Besides data entries the log holds DDL records: the contents of a
+//! knowledge graph's schema or rule catalog after a change, appended and
+//! synced before the catalog file is rewritten. Recovery restores the
+//! catalog files from them before any data is read, so data written under
+//! a schema is never replayed without it. They are tracked under
+//! [`DDL_SHARD`] and dropped once the catalog files are synced.
 
 use super::batch::Update;
+use super::encryption::{self, Encryption, FileKind};
+use crate::metrics::metrics;
 use crate::storage::{StorageError, StorageResult};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs::{self, File, OpenOptions};
 use std::io::{BufRead, BufReader, BufWriter, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Name sealed records are bound to (see [`FileKind::WalRecord`])
+const WAL_RECORD_NAME: &str = "wal";
+
+/// Pseudo-shard DDL records are tracked under in segment bookkeeping
+pub const DDL_SHARD: &str = "@ddl";
+
+/// Line prefix of a serialized [`DdlLine`]; data entries start with `{"shard"`
+const DDL_LINE_PREFIX: &str = "{\"ddl\":";
 
 /// A WAL entry containing shard and update information
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,6 +57,94 @@ pub struct WalEntry {
     pub shard: String,
     /// The update
     pub update: Update,
+    /// Unix milliseconds when the entry was written (archiving only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp_ms: Option<i64>,
+}
+
+/// Catalog file a DDL record restores
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Catalog {
+    /// Relation schemas (`schema.json`)
+    Schema,
+    /// Persistent rules (`rules/catalog.json`)
+    Rules,
+}
+
+/// A catalog change of one knowledge graph
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DdlChange {
+    /// The catalog file's contents after the change
+    Catalog {
+        /// Which catalog changed
+        catalog: Catalog,
+        /// Its file contents
+        contents: String,
+    },
+    /// The knowledge graph was dropped, catalogs included
+    DropKnowledgeGraph,
+}
+
+/// A DDL record
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DdlRecord {
+    /// Position among DDL records, increasing in write order
+    pub seq: u64,
+    /// Knowledge graph the change applies to
+    pub knowledge_graph: String,
+    /// The change
+    pub change: DdlChange,
+}
+
+/// Serialized form of a DDL record, distinguishable from a [`WalEntry`]
+#[derive(Serialize, Deserialize)]
+struct DdlLine {
+    ddl: DdlRecord,
+}
+
+/// A record of the log: a data entry or a DDL record
+#[derive(Debug, Clone)]
+pub enum WalRecord {
+    /// An update to a shard
+    Data(WalEntry),
+    /// A catalog change
+    Ddl(DdlRecord),
+}
+
+impl WalRecord {
+    /// Shard the record is tracked under ([`DDL_SHARD`] for DDL records)
+    pub fn shard(&self) -> &str {
+        match self {
+            WalRecord::Data(entry) => &entry.shard,
+            WalRecord::Ddl(_) => DDL_SHARD,
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Result<String> {
+        match self {
+            WalRecord::Data(entry) => serde_json::to_string(entry),
+            WalRecord::Ddl(record) => serde_json::to_string(&DdlLine {
+                ddl: record.clone(),
+            }),
+        }
+    }
+
+    fn from_json(json: &str) -> serde_json::Result<Self> {
+        if json.starts_with(DDL_LINE_PREFIX) {
+            serde_json::from_str::<DdlLine>(json).map(|line| WalRecord::Ddl(line.ddl))
+        } else {
+            serde_json::from_str(json).map(WalRecord::Data)
+        }
+    }
+}
+
+/// A sealed WAL segment
+struct Segment {
+    path: PathBuf,
+    /// Shards with entries in this segment
+    shards: HashSet<String>,
 }
 
 /// Write-Ahead Log writer
@@ -27,25 +155,104 @@ pub struct PersistWal {
     writer: Option<BufWriter<File>>,
     /// Current WAL file path
     current_file: PathBuf,
+    /// Bytes in the current WAL file
+    current_size: u64,
+    /// Shards with entries in the current WAL file
+    current_shards: HashSet<String>,
+    /// Sealed segments, oldest first
+    segments: Vec<Segment>,
+    /// Sequence number of the next sealed segment
+    next_segment: u64,
+    /// Size at which the current file is sealed (0 = never)
+    segment_size: u64,
+    /// Last time buffered entries were synced to disk
+    last_sync: Instant,
+    /// Whether entries were written since the last sync
+    unsynced: bool,
+    /// Directory sealed segments are archived to, if archiving is enabled
+    archive_dir: Option<PathBuf>,
     /// Number of entries written
     entries_written: usize,
+    /// Keys entries are sealed with, if encryption is enabled
+    encryption: Option<Arc<Encryption>>,
 }
 
 impl PersistWal {
     /// Create a new WAL
     pub fn new(wal_dir: PathBuf) -> StorageResult<Self> {
+        Self::open(wal_dir, None)
+    }
+
+    /// Create a new WAL sealing its entries with `encryption`
+    pub fn open(wal_dir: PathBuf, encryption: Option<Arc<Encryption>>) -> StorageResult<Self> {
         fs::create_dir_all(&wal_dir)?;
+        let keys = encryption.as_deref();
 
         let current_file = wal_dir.join("current.wal");
 
+        let mut sealed = list_segments(&wal_dir)?;
+        sealed.sort();
+        // Archived segments outlive the live ones; never reuse their numbers
+        let archived_max = list_segments(&wal_dir.join("archive"))?
+            .into_iter()
+            .map(|(seq, _)| seq)
+            .max();
+        let next_segment = sealed
+            .last()
+            .map(|(seq, _)| *seq)
+            .max(archived_max)
+            .map_or(1, |seq| seq + 1);
+        let segments = sealed
+            .into_iter()
+            .map(|(_, path)| {
+                let shards = Self::read_file(&path, keys)?
+                    .iter()
+                    .map(|r| r.shard().to_string())
+                    .collect();
+                Ok(Segment { path, shards })
+            })
+            .collect::<StorageResult<Vec<_>>>()?;
+        let current_shards = Self::read_file(&current_file, keys)?
+            .iter()
+            .map(|r| r.shard().to_string())
+            .collect();
+        let current_size = fs::metadata(&current_file).map_or(0, |m| m.len());
+
         Ok(PersistWal {
             wal_dir,
             writer: None,
             current_file,
+            current_size,
+            current_shards,
+            segments,
+            next_segment,
+            segment_size: 0,
+            last_sync: Instant::now(),
+            unsynced: false,
+            archive_dir: None,
             entries_written: 0,
+            encryption,
         })
     }
 
+    /// Keep sealed segments in `archive/` for point-in-time recovery
+    pub fn with_archive(mut self, enabled: bool) -> StorageResult<Self> {
+        self.archive_dir = if enabled {
+            let dir = self.wal_dir.join("archive");
+            fs::create_dir_all(&dir)?;
+            Some(dir)
+        } else {
+            None
+        };
+        Ok(self)
+    }
+
+    /// Seal the current file once it reaches `bytes` (0 = never rotate)
+    pub fn with_segment_size(mut self, bytes: u64) -> Self {
+        self.segment_size = bytes;
+        self
+    }
+
     /// Ensure writer is open
     fn ensure_writer(&mut self) -> StorageResult<&mut BufWriter<File>> {
         if self.writer.is_none() {
@@ -54,13 +261,14 @@ impl PersistWal {
                 .append(true)
                 .open(&self.current_file)
                 .map_err(|e| {
-                    eprintln!(
-                        "[wal] ERROR ensure_writer: path={}, parent_exists={}, error={}",
-                        self.current_file.display(),
-                        self.current_file
+                    tracing::error!(
+                        path = %self.current_file.display(),
+                        parent_exists = self
+                            .current_file
                             .parent()
                             .is_some_and(std::path::Path::exists),
-                        e
+                        error = %e,
+                        "wal_open_failed"
                     );
                     e
                 })?;
@@ -74,12 +282,14 @@ impl PersistWal {
 
     /// Append an entry to the WAL with immediate flush (durable)
     pub fn append(&mut self, shard: &str, update: &Update) -> StorageResult<()> {
-        self.append_inner(shard, update, true)
+        self.append_inner(shard, update, true)?;
+        self.rotate_if_full()
     }
 
     /// Append an entry to the WAL without immediate flush (buffered)
     pub fn append_buffered(&mut self, shard: &str, update: &Update) -> StorageResult<()> {
-        self.append_inner(shard, update, false)
+        self.append_inner(shard, update, false)?;
+        self.rotate_if_full()
     }
 
     /// Compute CRC32 checksum of a byte slice and return as 8-char hex string.
@@ -87,26 +297,55 @@ impl PersistWal {
         format!("{:08x}", crc32fast::hash(data))
     }
 
+    /// Encode a record as a WAL line: "<crc32hex>:<payload>", where the
+    /// payload is the record's JSON, or the base64 of it sealed
+    fn encode_line(record: &WalRecord, encryption: Option<&Encryption>) -> StorageResult<String> {
+        let json = record
+            .to_json()
+            .map_err(|e| StorageError::Other(format!("WAL serialization failed: {e}")))?;
+        let payload = match encryption {
+            Some(encryption) => STANDARD.encode(encryption.encrypt(
+                json.as_bytes(),
+                FileKind::WalRecord,
+                WAL_RECORD_NAME,
+            )?),
+            None => json,
+        };
+        let checksum = Self::crc32_hex(payload.as_bytes());
+        Ok(format!("{checksum}:{payload}"))
+    }
+
     /// Internal append implementation
     fn append_inner(&mut self, shard: &str, update: &Update, flush: bool) -> StorageResult<()> {
-        let entry = WalEntry {
+        let entry = WalRecord::Data(WalEntry {
             shard: shard.to_string(),
             update: update.clone(),
-        };
+            timestamp_ms: self
+                .archive_dir
+                .as_ref()
+                .map(|_| chrono::Utc::now().timestamp_millis()),
+        });
 
+        let line = Self::encode_line(&entry, self.encryption.as_deref())?;
         let writer = self.ensure_writer()?;
-        let json = serde_json::to_string(&entry)
-            .map_err(|e| StorageError::Other(format!("WAL serialization failed: {e}")))?;
-
-        // Write format: "<crc32hex>:<json>"
-        let checksum = Self::crc32_hex(json.as_bytes());
-        writeln!(writer, "{checksum}:{json}")?;
+        writeln!(writer, "{line}")?;
         if flush {
+            let sync_start = Instant::now();
             writer.flush()?;
             // sync_all() forces data to disk (not just OS page cache).
             // Without this, a power failure after flush() could still lose data
             // because the OS may not have written the page cache to the physical disk yet.
             writer.get_ref().sync_all()?;
+            self.last_sync = Instant::now();
+            metrics().wal_sync.observe(sync_start.elapsed());
+        } else {
+            self.unsynced = true;
+        }
+        metrics().wal_appends.inc();
+        metrics().wal_bytes_written.add(line.len() as u64 + 1);
+        self.current_size += line.len() as u64 + 1;
+        if !self.current_shards.contains(shard) {
+            self.current_shards.insert(shard.to_string());
         }
         self.entries_written += 1;
 
@@ -135,26 +374,156 @@ impl PersistWal {
         }
         if flush {
             // Flush once at the end for the whole batch and sync to disk
-            if let Some(ref mut writer) = self.writer {
-                writer.flush()?;
-                writer.get_ref().sync_all()?;
+            self.sync()?;
+        }
+        // Rotate only between batches so a batch never spans two segments
+        self.rotate_if_full()
+    }
+
+    /// Append a DDL record and sync it, whatever the durability mode: the
+    /// catalog file it covers is written right after
+    pub fn append_ddl(&mut self, record: &DdlRecord) -> StorageResult<()> {
+        let line = Self::encode_line(&WalRecord::Ddl(record.clone()), self.encryption.as_deref())?;
+        let writer = self.ensure_writer()?;
+        writeln!(writer, "{line}")?;
+        self.sync()?;
+        metrics().wal_appends.inc();
+        metrics().wal_bytes_written.add(line.len() as u64 + 1);
+        self.current_size += line.len() as u64 + 1;
+        self.current_shards.insert(DDL_SHARD.to_string());
+        self.entries_written += 1;
+        self.rotate_if_full()
+    }
+
+    /// Seal the current file once it reaches the segment size
+    fn rotate_if_full(&mut self) -> StorageResult<()> {
+        if self.segment_size == 0 || self.current_size < self.segment_size {
+            return Ok(());
+        }
+        self.rotate()
+    }
+
+    /// Seal the current file as a numbered segment, archiving it if enabled.
+    /// The sealed segment is synced before it is renamed.
+    fn rotate(&mut self) -> StorageResult<()> {
+        self.sync()?;
+        self.writer = None;
+        if !self.current_file.exists() {
+            return Ok(());
+        }
+
+        let name = format!("segment-{:010}.wal", self.next_segment);
+        let path = self.wal_dir.join(&name);
+        fs::rename(&self.current_file, &path)?;
+        if let Some(archive_dir) = &self.archive_dir {
+            // A hard link survives later rewrites of the live segment, which
+            // replace it by rename. Fall back to a copy across filesystems.
+            let archived = archive_dir.join(&name);
+            if fs::hard_link(&path, &archived).is_err() {
+                fs::copy(&path, &archived)?;
             }
         }
+        self.next_segment += 1;
+        self.segments.push(Segment {
+            path,
+            shards: std::mem::take(&mut self.current_shards),
+        });
+        self.current_size = 0;
         Ok(())
     }
 
-    /// Read all entries from the WAL.
+    /// Read every archived entry plus the not yet archived tail of the
+    /// log, in write order. Empty when archiving is disabled.
+    pub fn read_archive(&self) -> StorageResult<Vec<WalEntry>> {
+        let Some(archive_dir) = &self.archive_dir else {
+            return Ok(Vec::new());
+        };
+        let mut archived = list_segments(archive_dir)?;
+        archived.sort();
+        let mut records = Vec::new();
+        for (_, path) in archived {
+            records.extend(Self::read_file(&path, self.encryption.as_deref())?);
+        }
+        records.extend(Self::read_file(
+            &self.current_file,
+            self.encryption.as_deref(),
+        )?);
+        Ok(data_entries(records))
+    }
+
+    /// Delete archived segments last modified more than `retention` ago
+    pub fn prune_archive(&self, retention: Duration) -> StorageResult<usize> {
+        let Some(archive_dir) = &self.archive_dir else {
+            return Ok(0);
+        };
+        let mut removed = 0;
+        for (_, path) in list_segments(archive_dir)? {
+            let expired = fs::metadata(&path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|age| age > retention);
+            if expired {
+                fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Read all entries from the WAL, oldest segment first.
     ///
     /// Tolerates corrupt or truncated lines by logging a warning and skipping
     /// them. This makes WAL recovery resilient to partial writes (crash mid-write)
     /// AND bit-rot or other corruption - the system recovers as many valid
     /// entries as possible rather than refusing to start.
     pub fn read_all(&self) -> StorageResult<Vec<WalEntry>> {
-        if !self.current_file.exists() {
-            return Ok(Vec::new());
+        Ok(self.read_all_counted()?.0)
+    }
+
+    /// Read all data entries, also returning how many corrupt entries were
+    /// skipped
+    pub fn read_all_counted(&self) -> StorageResult<(Vec<WalEntry>, usize)> {
+        let (records, skipped) = self.read_records_counted()?;
+        Ok((data_entries(records), skipped))
+    }
+
+    /// Read all records, data and DDL, in write order, also returning how
+    /// many corrupt entries were skipped
+    pub fn read_records_counted(&self) -> StorageResult<(Vec<WalRecord>, usize)> {
+        let mut entries = Vec::new();
+        let mut skipped = 0;
+        for path in self
+            .segments
+            .iter()
+            .map(|s| &s.path)
+            .chain(std::iter::once(&self.current_file))
+        {
+            let (file_entries, file_skipped) =
+                Self::read_file_counted(path, self.encryption.as_deref())?;
+            entries.extend(file_entries);
+            skipped += file_skipped;
         }
+        Ok((entries, skipped))
+    }
+
+    /// Read the records of one WAL file, skipping corrupt lines
+    fn read_file(path: &Path, encryption: Option<&Encryption>) -> StorageResult<Vec<WalRecord>> {
+        Ok(Self::read_file_counted(path, encryption)?.0)
+    }
 
-        let file = File::open(&self.current_file)?;
+    /// Read the entries of one WAL file, also returning how many corrupt
+    /// lines were skipped. A sealed entry without a configured key is an
+    /// error; one that fails authentication is skipped as corrupt.
+    fn read_file_counted(
+        path: &Path,
+        encryption: Option<&Encryption>,
+    ) -> StorageResult<(Vec<WalRecord>, usize)> {
+        if !path.exists() {
+            return Ok((Vec::new(), 0));
+        }
+
+        let file = File::open(path)?;
         let reader = BufReader::new(file);
         let mut entries = Vec::new();
         let mut lines: Vec<String> = Vec::new();
@@ -190,7 +559,7 @@ impl PersistWal {
                 if actual != expected {
                     tracing::warn!(
                         line = i + 1,
-                        file = %self.current_file.display(),
+                        file = %path.display(),
                         expected_crc = expected,
                         actual_crc = %actual,
                         "Skipping WAL entry with CRC32 mismatch (bit-rot or corruption)"
@@ -200,12 +569,59 @@ impl PersistWal {
                 }
             }
 
-            match serde_json::from_str::<WalEntry>(json_str) {
+            // JSON starts with '{'; anything else is a sealed entry
+            let opened;
+            let json_str = if json_str.starts_with('{') {
+                encryption::check_plain(encryption, FileKind::WalRecord).map_err(|e| {
+                    StorageError::Encryption(format!("{}: line {}: {e}", path.display(), i + 1))
+                })?;
+                json_str
+            } else {
+                let sealed = STANDARD.decode(json_str).ok();
+                let Some(sealed) = sealed.filter(|s| encryption::is_encrypted(s)) else {
+                    tracing::warn!(
+                        line = i + 1,
+                        file = %path.display(),
+                        "Skipping corrupt WAL entry"
+                    );
+                    skipped += 1;
+                    continue;
+                };
+                let encryption = encryption
+                    .filter(|encryption| encryption.has_key_for(&sealed))
+                    .ok_or_else(|| {
+                        StorageError::Encryption(format!(
+                            "{}: WAL entry is encrypted with a key that is not configured",
+                            path.display()
+                        ))
+                    })?;
+                match encryption
+                    .decrypt(&sealed, FileKind::WalRecord, WAL_RECORD_NAME)
+                    .ok()
+                    .and_then(|json| String::from_utf8(json).ok())
+                {
+                    Some(json) => {
+                        opened = json;
+                        opened.as_str()
+                    }
+                    None => {
+                        tracing::warn!(
+                            line = i + 1,
+                            file = %path.display(),
+                            "Skipping WAL entry that failed authentication"
+                        );
+                        skipped += 1;
+                        continue;
+                    }
+                }
+            };
+
+            match WalRecord::from_json(json_str) {
                 Ok(entry) => entries.push(entry),
                 Err(e) => {
                     tracing::warn!(
                         line = i + 1,
-                        file = %self.current_file.display(),
+                        file = %path.display(),
                         error = %e,
                         "Skipping corrupt WAL entry"
                     );
@@ -218,12 +634,12 @@ impl PersistWal {
             tracing::warn!(
                 skipped,
                 recovered = entries.len(),
-                file = %self.current_file.display(),
+                file = %path.display(),
                 "WAL recovery: skipped corrupt entries - possible data loss"
             );
         }
 
-        Ok(entries)
+        Ok((entries, skipped))
     }
 
     /// Read entries for a specific shard
@@ -238,15 +654,25 @@ impl PersistWal {
 
     /// Clear the WAL (after successful flush to batch files)
     pub fn clear(&mut self) -> StorageResult<()> {
+        if self.archive_dir.is_some() {
+            self.rotate()?;
+        }
         // Close writer
         self.writer = None;
 
-        // Simply remove the old WAL file. The caller has already flushed
+        // Simply remove the old WAL files. The caller has already flushed
         // all data to batch files, so the WAL entries are redundant.
+        for segment in self.segments.drain(..) {
+            if segment.path.exists() {
+                fs::remove_file(&segment.path)?;
+            }
+        }
         if self.current_file.exists() {
             fs::remove_file(&self.current_file)?;
         }
 
+        self.current_size = 0;
+        self.current_shards.clear();
         self.entries_written = 0;
         Ok(())
     }
@@ -254,43 +680,167 @@ impl PersistWal {
     /// Sync WAL to disk (flushes buffer and calls fsync)
     pub fn sync(&mut self) -> StorageResult<()> {
         if let Some(ref mut writer) = self.writer {
+            let sync_start = Instant::now();
             writer.flush()?;
             writer.get_ref().sync_all()?;
+            metrics().wal_sync.observe(sync_start.elapsed());
         }
+        self.last_sync = Instant::now();
+        self.unsynced = false;
         Ok(())
     }
 
+    /// Sync if entries were written since the last sync and more than
+    /// `interval` has passed since it. Returns whether a sync happened.
+    pub fn sync_if_due(&mut self, interval: Duration) -> StorageResult<bool> {
+        if !self.unsynced || self.writer.is_none() || self.last_sync.elapsed() < interval {
+            return Ok(false);
+        }
+        self.sync()?;
+        Ok(true)
+    }
+
     /// Get number of entries written since last clear
     pub fn entries_written(&self) -> usize {
         self.entries_written
     }
 
+    /// Number of sealed segments
+    pub fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+
     /// Remove all WAL entries for a specific shard.
     /// Rewrites the WAL excluding those entries. Other shards' data is preserved.
     ///
-    /// Uses atomic write-to-new+rename: surviving entries are written to
-    /// `current.wal.new`, synced to disk, then atomically renamed to `current.wal`.
-    /// This guarantees that either the old or new WAL exists at all times -
-    /// a crash at any point cannot lose other shards' data.
+    /// Only sealed segments that hold entries for the shard are touched.
+    /// Each file is rewritten with atomic write-to-new+rename: surviving
+    /// entries are written to `<file>.new`, synced to disk, then atomically
+    /// renamed over the original. This guarantees that either the old or new
+    /// file exists at all times - a crash at any point cannot lose other
+    /// shards' data.
     pub fn remove_shard_entries(&mut self, shard_name: &str) -> StorageResult<()> {
-        let entries = self.read_all()?;
+        self.remove_entries_of(&HashSet::from([shard_name.to_string()]))
+    }
+
+    /// Remove the WAL entries of several shards, rewriting each affected
+    /// file once (see [`Self::remove_shard_entries`])
+    pub fn remove_entries_of(&mut self, shard_names: &HashSet<String>) -> StorageResult<()> {
+        if shard_names.is_empty() {
+            return Ok(());
+        }
+        // Entries must reach the archive before they are dropped here
+        if self.archive_dir.is_some() && !self.current_shards.is_disjoint(shard_names) {
+            self.rotate()?;
+        }
+
+        let mut i = 0;
+        while i < self.segments.len() {
+            if self.segments[i].shards.is_disjoint(shard_names) {
+                i += 1;
+                continue;
+            }
+            let path = self.segments[i].path.clone();
+            let keep = |record: &WalRecord| !shard_names.contains(record.shard());
+            if Self::rewrite_retaining(&path, keep, self.encryption.as_deref())? == 0 {
+                self.segments.remove(i);
+            } else {
+                self.segments[i].shards.retain(|s| !shard_names.contains(s));
+                i += 1;
+            }
+        }
 
         // Close writer before manipulating the file
         self.writer = None;
 
-        let surviving: Vec<&WalEntry> = entries.iter().filter(|e| e.shard != shard_name).collect();
+        let surviving = Self::rewrite_retaining(
+            &self.current_file,
+            |record| !shard_names.contains(record.shard()),
+            self.encryption.as_deref(),
+        )?;
+        self.current_shards.retain(|s| !shard_names.contains(s));
+        self.current_size = fs::metadata(&self.current_file).map_or(0, |m| m.len());
+        self.entries_written = surviving;
+        Ok(())
+    }
+
+    /// Remove the DDL records written before `seq`, once the catalog files
+    /// they cover are synced. Later records are kept.
+    pub fn remove_ddl_before(&mut self, seq: u64) -> StorageResult<()> {
+        let keep = |record: &WalRecord| !matches!(record, WalRecord::Ddl(r) if r.seq < seq);
+        let mut i = 0;
+        while i < self.segments.len() {
+            if !self.segments[i].shards.contains(DDL_SHARD) {
+                i += 1;
+                continue;
+            }
+            let path = self.segments[i].path.clone();
+            if Self::rewrite_retaining(&path, keep, self.encryption.as_deref())? == 0 {
+                self.segments.remove(i);
+                continue;
+            }
+            if !Self::read_file(&path, self.encryption.as_deref())?
+                .iter()
+                .any(|r| matches!(r, WalRecord::Ddl(_)))
+            {
+                self.segments[i].shards.remove(DDL_SHARD);
+            }
+            i += 1;
+        }
+        if !self.current_shards.contains(DDL_SHARD) {
+            return Ok(());
+        }
+
+        self.sync()?;
+        self.writer = None;
+        let surviving =
+            Self::rewrite_retaining(&self.current_file, keep, self.encryption.as_deref())?;
+        if !Self::read_file(&self.current_file, self.encryption.as_deref())?
+            .iter()
+            .any(|r| matches!(r, WalRecord::Ddl(_)))
+        {
+            self.current_shards.remove(DDL_SHARD);
+        }
+        self.current_size = fs::metadata(&self.current_file).map_or(0, |m| m.len());
+        self.entries_written = surviving;
+        Ok(())
+    }
 
+    /// Rewrite a WAL file with only the records `keep` accepts, removing it
+    /// if nothing survives. Returns the number of surviving records.
+    fn rewrite_retaining(
+        path: &Path,
+        keep: impl Fn(&WalRecord) -> bool,
+        encryption: Option<&Encryption>,
+    ) -> StorageResult<usize> {
+        let entries = Self::read_file(path, encryption)?;
+        let surviving: Vec<&WalRecord> = entries.iter().filter(|r| keep(r)).collect();
         if surviving.is_empty() {
             // No surviving entries: just remove the WAL file
-            if self.current_file.exists() {
-                fs::remove_file(&self.current_file)?;
+            if path.exists() {
+                fs::remove_file(path)?;
             }
-            self.entries_written = 0;
-            return Ok(());
+            return Ok(0);
+        }
+        if surviving.len() == entries.len() {
+            return Ok(surviving.len());
         }
 
-        // Write surviving entries to a temp file
-        let new_file = self.wal_dir.join("current.wal.new");
+        Self::write_entries(path, &surviving, encryption)?;
+        Ok(surviving.len())
+    }
+
+    /// Replace a WAL file with `entries`: they are written to
+    /// `<file>.new`, synced, then renamed over the original. On POSIX,
+    /// rename is atomic - either the old or new file is visible.
+    fn write_entries(
+        path: &Path,
+        entries: &[&WalRecord],
+        encryption: Option<&Encryption>,
+    ) -> StorageResult<()> {
+        let mut new_name = path.as_os_str().to_owned();
+        new_name.push(".new");
+        let new_file = PathBuf::from(new_name);
         {
             let file = OpenOptions::new()
                 .create(true)
@@ -298,22 +848,46 @@ impl PersistWal {
                 .truncate(true)
                 .open(&new_file)?;
             let mut writer = BufWriter::new(file);
-            for entry in &surviving {
-                let json = serde_json::to_string(entry)
-                    .map_err(|e| StorageError::Other(format!("WAL serialization failed: {e}")))?;
-                let checksum = Self::crc32_hex(json.as_bytes());
-                writeln!(writer, "{checksum}:{json}")?;
+            for entry in entries {
+                writeln!(writer, "{}", Self::encode_line(entry, encryption)?)?;
             }
             writer.flush()?;
             writer.get_ref().sync_all()?;
         }
+        fs::rename(&new_file, path)?;
+        Ok(())
+    }
 
-        // Atomic rename: replaces old WAL with the new one.
-        // On POSIX, rename is atomic - either the old or new file is visible.
-        fs::rename(&new_file, &self.current_file)?;
+    /// Rewrite every WAL file, archived segments included, with entries
+    /// sealed under the current key (after a key rotation). Returns the
+    /// number of files rewritten.
+    pub fn reencrypt(&mut self) -> StorageResult<usize> {
+        self.sync()?;
+        self.writer = None;
 
-        self.entries_written = surviving.len();
-        Ok(())
+        let mut paths: Vec<PathBuf> = self.segments.iter().map(|s| s.path.clone()).collect();
+        paths.push(self.current_file.clone());
+        if let Some(archive_dir) = &self.archive_dir {
+            paths.extend(
+                list_segments(archive_dir)?
+                    .into_iter()
+                    .map(|(_, path)| path),
+            );
+        }
+
+        let encryption = self.encryption.as_deref();
+        let mut rewritten = 0;
+        for path in paths {
+            if !path.exists() {
+                continue;
+            }
+            let entries = Self::read_file(&path, encryption)?;
+            let entries: Vec<&WalRecord> = entries.iter().collect();
+            Self::write_entries(&path, &entries, encryption)?;
+            rewritten += 1;
+        }
+        self.current_size = fs::metadata(&self.current_file).map_or(0, |m| m.len());
+        Ok(rewritten)
     }
 
     /// Remove stale .archived WAL files left over from previous runs.
@@ -334,17 +908,62 @@ impl PersistWal {
                 let _ = fs::remove_file(&path);
             }
             // Also clean up incomplete .new files from interrupted rewrites
-            if path.file_name().and_then(|n| n.to_str()) == Some("current.wal.new") {
+            if path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.ends_with(".wal.new"))
+            {
                 let _ = fs::remove_file(&path);
             }
         }
         Ok(())
     }
 
-    /// Get WAL file size
+    /// Get total WAL size in bytes, across all segments
     pub fn file_size(&self) -> u64 {
-        fs::metadata(&self.current_file).map_or(0, |m| m.len())
+        let sealed: u64 = self
+            .segments
+            .iter()
+            .map(|s| fs::metadata(&s.path).map_or(0, |m| m.len()))
+            .sum();
+        sealed + fs::metadata(&self.current_file).map_or(0, |m| m.len())
+    }
+}
+
+/// The data entries among `records`, in order
+fn data_entries(records: Vec<WalRecord>) -> Vec<WalEntry> {
+    records
+        .into_iter()
+        .filter_map(|record| match record {
+            WalRecord::Data(entry) => Some(entry),
+            WalRecord::Ddl(_) => None,
+        })
+        .collect()
+}
+
+/// Sealed segment files in a directory, unsorted
+fn list_segments(dir: &Path) -> StorageResult<Vec<(u64, PathBuf)>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
     }
+    let mut segments = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if let Some(seq) = segment_seq(&path) {
+            segments.push((seq, path));
+        }
+    }
+    Ok(segments)
+}
+
+/// Sequence number of a sealed segment file (`segment-<seq>.wal`)
+fn segment_seq(path: &Path) -> Option<u64> {
+    path.file_name()?
+        .to_str()?
+        .strip_prefix("segment-")?
+        .strip_suffix(".wal")?
+        .parse()
+        .ok()
 }
 
 #[cfg(test)]
@@ -482,6 +1101,7 @@ mod tests {
         let entry = WalEntry {
             shard: "db:edge".to_string(),
             update: Update::insert(Tuple::from_pair(1, 2), 10),
+            timestamp_ms: None,
         };
         let json = serde_json::to_string(&entry).unwrap();
         let back: WalEntry = serde_json::from_str(&json).unwrap();
@@ -515,6 +1135,50 @@ mod tests {
         assert_eq!(wal.entries_written(), 2);
     }
 
+    #[test]
+    fn test_wal_ddl_records() {
+        let temp = TempDir::new().unwrap();
+        let mut wal = PersistWal::new(temp.path().to_path_buf()).unwrap();
+        let schema = |seq: u64, contents: &str| DdlRecord {
+            seq,
+            knowledge_graph: "db".to_string(),
+            change: DdlChange::Catalog {
+                catalog: Catalog::Schema,
+                contents: contents.to_string(),
+            },
+        };
+
+        wal.append_ddl(&schema(0, "{}")).unwrap();
+        wal.append("db:edge", &Update::insert(Tuple::from_pair(1, 2), 10))
+            .unwrap();
+        wal.append_ddl(&schema(1, "{\"persistent\":{}}")).unwrap();
+
+        // Data readers skip DDL records, and removing a shard keeps them
+        wal.remove_shard_entries("db:edge").unwrap();
+        assert!(wal.read_all().unwrap().is_empty());
+
+        let mut wal = PersistWal::new(temp.path().to_path_buf()).unwrap();
+        let (records, skipped) = wal.read_records_counted().unwrap();
+        assert_eq!(skipped, 0);
+        let ddl: Vec<&DdlRecord> = records
+            .iter()
+            .map(|r| match r {
+                WalRecord::Ddl(record) => record,
+                WalRecord::Data(_) => panic!("data entry survived"),
+            })
+            .collect();
+        assert_eq!(
+            ddl,
+            vec![&schema(0, "{}"), &schema(1, "{\"persistent\":{}}")]
+        );
+
+        wal.remove_ddl_before(1).unwrap();
+        let (records, _) = wal.read_records_counted().unwrap();
+        assert!(matches!(&records[..], [WalRecord::Ddl(r)] if r.seq == 1));
+        wal.remove_ddl_before(2).unwrap();
+        assert!(wal.read_records_counted().unwrap().0.is_empty());
+    }
+
     #[test]
     fn test_wal_remove_shard_entries_all() {
         let temp = TempDir::new().unwrap();
@@ -727,6 +1391,7 @@ mod tests {
             let valid_entry = WalEntry {
                 shard: "db:edge".to_string(),
                 update: Update::insert(Tuple::from_pair(1, 2), 10),
+                timestamp_ms: None,
             };
             let json = serde_json::to_string(&valid_entry).unwrap();
             writeln!(file, "{json}").unwrap();
@@ -844,6 +1509,7 @@ mod tests {
             let entry = WalEntry {
                 shard: "db:legacy".to_string(),
                 update: Update::insert(Tuple::from_pair(1, 2), 10),
+                timestamp_ms: None,
             };
             let json = serde_json::to_string(&entry).unwrap();
             let mut file = OpenOptions::new()
@@ -864,6 +1530,90 @@ mod tests {
         assert_eq!(entries[0].shard, "db:legacy");
     }
 
+    #[test]
+    fn test_wal_segment_rotation() {
+        let temp = TempDir::new().unwrap();
+        let wal_dir = temp.path().to_path_buf();
+
+        {
+            let mut wal = PersistWal::new(wal_dir.clone())
+                .unwrap()
+                .with_segment_size(200);
+            for i in 0..10 {
+                let shard = if i % 2 == 0 { "db:edge" } else { "db:node" };
+                wal.append(shard, &Update::insert(Tuple::from_pair(i, i), i as u64))
+                    .unwrap();
+            }
+            assert!(wal.segment_count() > 1, "WAL should have rotated");
+            assert!(wal_dir.join("segment-0000000001.wal").exists());
+        }
+
+        // Reopening picks up sealed segments, in order
+        let mut wal = PersistWal::new(wal_dir.clone()).unwrap();
+        let entries = wal.read_all().unwrap();
+        assert_eq!(entries.len(), 10);
+        let times: Vec<u64> = entries.iter().map(|e| e.update.time).collect();
+        assert_eq!(times, (0..10).collect::<Vec<_>>());
+
+        // Removing a shard rewrites only what it touches; emptied segments go away
+        wal.remove_shard_entries("db:edge").unwrap();
+        assert_eq!(wal.read_shard("db:edge").unwrap().len(), 0);
+        assert_eq!(wal.read_shard("db:node").unwrap().len(), 5);
+        wal.remove_shard_entries("db:node").unwrap();
+        assert_eq!(wal.segment_count(), 0);
+        assert_eq!(wal.file_size(), 0);
+    }
+
+    #[test]
+    fn test_wal_archive_keeps_truncated_entries() {
+        let temp = TempDir::new().unwrap();
+        let wal_dir = temp.path().to_path_buf();
+        let mut wal = PersistWal::new(wal_dir.clone())
+            .unwrap()
+            .with_archive(true)
+            .unwrap();
+
+        wal.append("db:edge", &Update::insert(Tuple::from_pair(1, 2), 10))
+            .unwrap();
+        wal.append("db:node", &Update::insert(Tuple::from_pair(3, 4), 11))
+            .unwrap();
+        wal.remove_shard_entries("db:edge").unwrap();
+        wal.append("db:edge", &Update::insert(Tuple::from_pair(5, 6), 12))
+            .unwrap();
+        wal.clear().unwrap();
+
+        assert!(wal.read_all().unwrap().is_empty());
+        let archived = wal.read_archive().unwrap();
+        let times: Vec<u64> = archived.iter().map(|e| e.update.time).collect();
+        assert_eq!(times, vec![10, 11, 12]);
+        assert!(archived.iter().all(|e| e.timestamp_ms.is_some()));
+
+        // Reopening continues numbering after the archived segments
+        let wal = PersistWal::new(wal_dir).unwrap();
+        assert_eq!(wal.next_segment, 3);
+    }
+
+    #[test]
+    fn test_wal_sync_if_due() {
+        let temp = TempDir::new().unwrap();
+        let mut wal = PersistWal::new(temp.path().to_path_buf()).unwrap();
+        assert!(!wal.sync_if_due(Duration::ZERO).unwrap(), "nothing to sync");
+
+        wal.append_buffered("db:edge", &Update::insert(Tuple::from_pair(1, 2), 10))
+            .unwrap();
+        assert!(!wal.sync_if_due(Duration::from_secs(3600)).unwrap());
+        assert!(wal.sync_if_due(Duration::ZERO).unwrap());
+        assert!(!wal.sync_if_due(Duration::ZERO).unwrap(), "already synced");
+        assert_eq!(
+            PersistWal::new(temp.path().to_path_buf())
+                .unwrap()
+                .read_all()
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
     /// P0-1: Verify sync() calls fsync (data readable from new instance).
     #[test]
     fn test_wal_sync_actually_syncs() {
@@ -0,0 +1,286 @@
+//! PostgreSQL Connector
+//!
+//! Streams a table or query result out of PostgreSQL with
+//! `COPY ... TO STDOUT`, so nightly syncs load straight into a relation
+//! without a CSV intermediary. Only built with the `postgres` feature.
+//!
+//! ## Type Mapping
+//!
+//! Column types come from the result's column types:
+//!
+//! | PostgreSQL | Column type |
+//! |------------|-------------|
+//! | `boolean` | `bool` |
+//! | `smallint`, `integer`, `bigint` | `int` |
+//! | `real`, `double precision`, `numeric` | `float` |
+//! | `text`, `varchar`, `char`, `name` | `string` |
+//! | `date` | `date` |
+//! | `timestamp`, `timestamptz` | `timestamp` |
+//! | `uuid` | `uuid` |
+//! | `bytea` | `bytes` |
+//! | `json`, `jsonb` | `json` |
+//! | `real[]`, `double precision[]` | `vector` |
+//! | anything else | `string` (its text form) |
+//!
+//! The session time zone is set to UTC before copying, so `timestamptz`
+//! values keep their instant.
+
+use std::io::{BufRead, BufReader};
+
+use chrono::DateTime;
+use postgres::{Client, NoTls};
+
+use crate::schema::{ColumnSchema, RelationSchema, SchemaType};
+use crate::storage::csv::parse_typed;
+use crate::storage::error::{StorageError, StorageResult};
+use crate::temporal_ops::parse_timestamp;
+use crate::value::{Tuple, Value};
+
+/// What to copy out of PostgreSQL
+#[derive(Debug, Clone)]
+pub enum PostgresSource {
+    /// An entire table, optionally schema-qualified (`public.users`)
+    Table(String),
+    /// The result of a `SELECT` query
+    Query(String),
+}
+
+impl PostgresSource {
+    /// The query producing the rows to copy
+    fn query(&self) -> String {
+        match self {
+            PostgresSource::Table(table) => format!(
+                "SELECT * FROM {}",
+                table
+                    .split('.')
+                    .map(quote_ident)
+                    .collect::<Vec<_>>()
+                    .join(".")
+            ),
+            PostgresSource::Query(query) => query.trim().trim_end_matches(';').to_string(),
+        }
+    }
+}
+
+/// Options for copying from PostgreSQL
+#[derive(Debug, Clone)]
+pub struct PostgresOptions {
+    /// Number of rows handed over per chunk (default: 10000)
+    pub chunk_size: usize,
+}
+
+impl Default for PostgresOptions {
+    fn default() -> Self {
+        PostgresOptions { chunk_size: 10_000 }
+    }
+}
+
+/// Copy the rows of `source` out of the database at `url` (a libpq
+/// connection string or `postgres://` URL), passing them to `on_chunk` in
+/// chunks of `options.chunk_size` along with the relation schema mapped
+/// from the result's columns. Returns that schema.
+pub fn copy_from_postgres<F>(
+    url: &str,
+    source: &PostgresSource,
+    relation: &str,
+    options: &PostgresOptions,
+    mut on_chunk: F,
+) -> StorageResult<RelationSchema>
+where
+    F: FnMut(&RelationSchema, Vec<Tuple>) -> StorageResult<()>,
+{
+    let mut client = Client::connect(url, NoTls)?;
+    client.batch_execute("SET TIME ZONE 'UTC'")?;
+
+    let query = source.query();
+    let statement = client.prepare(&query)?;
+    let mut schema = RelationSchema::new(relation);
+    for column in statement.columns() {
+        schema = schema.with_column(ColumnSchema::new(
+            column.name(),
+            column_type(column.type_().name()),
+        ));
+    }
+
+    let chunk_size = options.chunk_size.max(1);
+    let reader = BufReader::new(client.copy_out(&format!("COPY ({query}) TO STDOUT"))?);
+    let mut chunk = Vec::with_capacity(chunk_size);
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let fields = split_copy_line(&line);
+        if fields.len() != schema.columns.len() {
+            return Err(StorageError::ParseError(format!(
+                "Row {}: expected {} fields, got {}",
+                i + 1,
+                schema.columns.len(),
+                fields.len()
+            )));
+        }
+        let values = fields
+            .iter()
+            .zip(&schema.columns)
+            .map(|(field, column)| {
+                from_copy_text(field.as_deref(), &column.data_type).ok_or_else(|| {
+                    StorageError::ParseError(format!(
+                        "Row {}, column '{}': value does not fit {}",
+                        i + 1,
+                        column.name,
+                        column.data_type
+                    ))
+                })
+            })
+            .collect::<StorageResult<Vec<_>>>()?;
+        chunk.push(Tuple::new(values));
+        if chunk.len() == chunk_size {
+            on_chunk(
+                &schema,
+                std::mem::replace(&mut chunk, Vec::with_capacity(chunk_size)),
+            )?;
+        }
+    }
+    if !chunk.is_empty() {
+        on_chunk(&schema, chunk)?;
+    }
+    Ok(schema)
+}
+
+/// Quote a name as a PostgreSQL identifier
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Column type for a PostgreSQL type name
+fn column_type(type_name: &str) -> SchemaType {
+    match type_name {
+        "bool" => SchemaType::Bool,
+        "int2" | "int4" | "int8" | "oid" => SchemaType::Int,
+        "float4" | "float8" | "numeric" => SchemaType::Float,
+        "date" => SchemaType::Date,
+        "timestamp" | "timestamptz" => SchemaType::Timestamp,
+        "uuid" => SchemaType::Uuid,
+        "bytea" => SchemaType::Bytes,
+        "json" | "jsonb" => SchemaType::Json,
+        "_float4" | "_float8" => SchemaType::Vector { dim: None },
+        _ => SchemaType::String,
+    }
+}
+
+/// Split a line of `COPY` text format into fields, undoing backslash
+/// escapes (`None` for `\N`)
+fn split_copy_line(line: &str) -> Vec<Option<String>> {
+    line.split('\t')
+        .map(|field| {
+            if field == "\\N" {
+                return None;
+            }
+            let mut out = String::with_capacity(field.len());
+            let mut chars = field.chars();
+            while let Some(c) = chars.next() {
+                if c != '\\' {
+                    out.push(c);
+                    continue;
+                }
+                match chars.next() {
+                    Some('b') => out.push('\u{8}'),
+                    Some('f') => out.push('\u{c}'),
+                    Some('n') => out.push('\n'),
+                    Some('r') => out.push('\r'),
+                    Some('t') => out.push('\t'),
+                    Some('v') => out.push('\u{b}'),
+                    Some(other) => out.push(other),
+                    None => out.push('\\'),
+                }
+            }
+            Some(out)
+        })
+        .collect()
+}
+
+/// Convert a field in PostgreSQL's text output format to a value of
+/// `data_type` (`None` if it does not fit)
+fn from_copy_text(field: Option<&str>, data_type: &SchemaType) -> Option<Value> {
+    let Some(text) = field else {
+        return Some(Value::Null);
+    };
+    match data_type {
+        SchemaType::Bool => match text {
+            "t" => Some(Value::Bool(true)),
+            "f" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        SchemaType::Float => match text {
+            "NaN" => Some(Value::Float64(f64::NAN)),
+            "Infinity" => Some(Value::Float64(f64::INFINITY)),
+            "-Infinity" => Some(Value::Float64(f64::NEG_INFINITY)),
+            _ => text.parse().ok().map(Value::Float64),
+        },
+        SchemaType::String => Some(Value::interned(text)),
+        SchemaType::Timestamp => parse_timestamp(text)
+            .or_else(|| {
+                DateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f%#z")
+                    .ok()
+                    .map(|dt| dt.timestamp_millis())
+            })
+            .map(Value::Timestamp),
+        SchemaType::Bytes => Value::from_hex(text.strip_prefix("\\x")?),
+        SchemaType::Json => Value::json(text),
+        SchemaType::Vector { .. } => {
+            let items = text.strip_prefix('{')?.strip_suffix('}')?;
+            parse_typed(&format!("[{items}]"), data_type)
+        }
+        _ => parse_typed(text, data_type),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_copy_line() {
+        assert_eq!(
+            split_copy_line("1\tline\\none\t\\N\ta\\\\b"),
+            vec![
+                Some("1".to_string()),
+                Some("line\none".to_string()),
+                None,
+                Some("a\\b".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_postgres_type_mapping() {
+        assert_eq!(column_type("int8"), SchemaType::Int);
+        assert_eq!(column_type("timestamptz"), SchemaType::Timestamp);
+        assert_eq!(column_type("_float4"), SchemaType::Vector { dim: None });
+        assert_eq!(column_type("inet"), SchemaType::String);
+    }
+
+    #[test]
+    fn test_from_copy_text() {
+        assert_eq!(
+            from_copy_text(Some("t"), &SchemaType::Bool),
+            Some(Value::Bool(true))
+        );
+        assert_eq!(
+            from_copy_text(Some("42"), &SchemaType::Int),
+            Some(Value::Int64(42))
+        );
+        assert_eq!(
+            from_copy_text(Some("\\x0aff"), &SchemaType::Bytes),
+            Some(Value::bytes(vec![0x0a, 0xff]))
+        );
+        assert_eq!(
+            from_copy_text(Some("2024-01-15 10:30:00+00"), &SchemaType::Timestamp),
+            Some(Value::Timestamp(1_705_314_600_000))
+        );
+        assert!(matches!(
+            from_copy_text(Some("{0.5,1}"), &SchemaType::Vector { dim: None }),
+            Some(Value::Vector(v)) if v.len() == 2
+        ));
+        assert_eq!(from_copy_text(None, &SchemaType::Int), Some(Value::Null));
+        assert_eq!(from_copy_text(Some("x"), &SchemaType::Int), None);
+    }
+}
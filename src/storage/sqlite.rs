@@ -0,0 +1,301 @@
+//! SQLite Storage Module
+//!
+//! Reads tables out of SQLite database files, where seed data often
+//! lives, so they load without exporting to CSV first. Import only.
+//!
+//! ## Type Mapping
+//!
+//! Column types follow the declared type of each column, checked in this
+//! order (case-insensitive substring match, like SQLite's own affinity
+//! rules):
+//!
+//! | Declared type contains | Column type |
+//! |------------------------|-------------|
+//! | `BOOL` | `bool` |
+//! | `TIMESTAMP`, `DATETIME` | `timestamp` |
+//! | `DATE` | `date` |
+//! | `UUID` | `uuid` |
+//! | `INT` | `int` |
+//! | `CHAR`, `CLOB`, `TEXT` | `string` |
+//! | `BLOB` | `bytes` |
+//! | `REAL`, `FLOA`, `DOUB` | `float` |
+//! | (no declared type) | `any` |
+//! | anything else (`NUMERIC`, `DECIMAL`) | `float` |
+//!
+//! Booleans are stored by SQLite as integers, timestamps as integer
+//! milliseconds or text, dates and UUIDs as text; text values of typed
+//! columns are parsed like CSV fields.
+
+use std::path::Path;
+
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags};
+
+use crate::schema::{ColumnSchema, RelationSchema, SchemaType};
+use crate::storage::csv::parse_typed;
+use crate::storage::error::{StorageError, StorageResult};
+use crate::value::{Tuple, Value};
+
+/// Options for reading SQLite tables
+#[derive(Debug, Clone)]
+pub struct SqliteOptions {
+    /// Number of rows handed over per batch (default: 10000)
+    pub batch_size: usize,
+}
+
+impl Default for SqliteOptions {
+    fn default() -> Self {
+        SqliteOptions { batch_size: 10_000 }
+    }
+}
+
+/// Map the columns of a SQLite table to a relation schema
+pub fn sqlite_table_schema<P: AsRef<Path>>(
+    path: P,
+    table: &str,
+    relation: &str,
+) -> StorageResult<RelationSchema> {
+    table_schema(&open(path)?, table, relation)
+}
+
+/// Load a whole SQLite table as a relation
+pub fn load_from_sqlite<P: AsRef<Path>>(
+    path: P,
+    table: &str,
+    relation: &str,
+) -> StorageResult<(RelationSchema, Vec<Tuple>)> {
+    let mut tuples = Vec::new();
+    let schema = read_sqlite_batches(path, table, relation, &SqliteOptions::default(), |batch| {
+        tuples.extend(batch);
+        Ok(())
+    })?;
+    Ok((schema, tuples))
+}
+
+/// Read a SQLite table in batches of `options.batch_size` rows, passing
+/// each batch to `on_batch`. Returns the table's relation schema.
+pub fn read_sqlite_batches<P, F>(
+    path: P,
+    table: &str,
+    relation: &str,
+    options: &SqliteOptions,
+    mut on_batch: F,
+) -> StorageResult<RelationSchema>
+where
+    P: AsRef<Path>,
+    F: FnMut(Vec<Tuple>) -> StorageResult<()>,
+{
+    let conn = open(path)?;
+    let schema = table_schema(&conn, table, relation)?;
+    let batch_size = options.batch_size.max(1);
+
+    let mut stmt = conn.prepare(&format!("SELECT * FROM {}", quote_ident(table)))?;
+    let mut rows = stmt.query([])?;
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut row_number = 0;
+    while let Some(row) = rows.next()? {
+        row_number += 1;
+        let mut values = Vec::with_capacity(schema.columns.len());
+        for (i, column) in schema.columns.iter().enumerate() {
+            let value = from_sqlite(row.get_ref(i)?, &column.data_type).ok_or_else(|| {
+                StorageError::ParseError(format!(
+                    "Table '{table}', row {row_number}, column '{}': value does not fit {}",
+                    column.name, column.data_type
+                ))
+            })?;
+            values.push(value);
+        }
+        batch.push(Tuple::new(values));
+        if batch.len() == batch_size {
+            on_batch(std::mem::replace(
+                &mut batch,
+                Vec::with_capacity(batch_size),
+            ))?;
+        }
+    }
+    if !batch.is_empty() {
+        on_batch(batch)?;
+    }
+    Ok(schema)
+}
+
+/// Open a database file read-only
+fn open<P: AsRef<Path>>(path: P) -> StorageResult<Connection> {
+    Ok(Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )?)
+}
+
+/// Quote a table name as a SQLite identifier
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn table_schema(conn: &Connection, table: &str, relation: &str) -> StorageResult<RelationSchema> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", quote_ident(table)))?;
+    let columns = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    if columns.is_empty() {
+        return Err(StorageError::Other(format!(
+            "SQLite table '{table}' not found"
+        )));
+    }
+
+    let mut schema = RelationSchema::new(relation);
+    for (name, declared) in columns {
+        schema = schema.with_column(ColumnSchema::new(name, column_type(&declared)));
+    }
+    Ok(schema)
+}
+
+/// Column type for a declared SQLite column type
+fn column_type(declared: &str) -> SchemaType {
+    let declared = declared.to_ascii_uppercase();
+    let has = |pattern: &str| declared.contains(pattern);
+    if declared.is_empty() {
+        SchemaType::Any
+    } else if has("BOOL") {
+        SchemaType::Bool
+    } else if has("TIMESTAMP") || has("DATETIME") {
+        SchemaType::Timestamp
+    } else if has("DATE") {
+        SchemaType::Date
+    } else if has("UUID") {
+        SchemaType::Uuid
+    } else if has("INT") {
+        SchemaType::Int
+    } else if has("CHAR") || has("CLOB") || has("TEXT") {
+        SchemaType::String
+    } else if has("BLOB") {
+        SchemaType::Bytes
+    } else {
+        SchemaType::Float
+    }
+}
+
+/// Convert a SQLite value to a value of `data_type` (`None` if it does not
+/// fit). `NULL` fits every column type.
+fn from_sqlite(value: ValueRef<'_>, data_type: &SchemaType) -> Option<Value> {
+    let converted = match (data_type, value) {
+        (_, ValueRef::Null) => return Some(Value::Null),
+        (SchemaType::Bool, ValueRef::Integer(i)) => Value::Bool(i != 0),
+        (SchemaType::Float, ValueRef::Integer(i)) => Value::Float64(i as f64),
+        (SchemaType::Timestamp, ValueRef::Integer(ms)) => Value::Timestamp(ms),
+        (SchemaType::String | SchemaType::Symbol, ValueRef::Integer(i)) => {
+            Value::interned(&i.to_string())
+        }
+        (SchemaType::String | SchemaType::Symbol, ValueRef::Real(f)) => {
+            Value::interned(&f.to_string())
+        }
+        (SchemaType::String | SchemaType::Symbol | SchemaType::Any, ValueRef::Text(text)) => {
+            Value::interned(std::str::from_utf8(text).ok()?)
+        }
+        (SchemaType::Uuid, ValueRef::Blob(bytes)) => {
+            Value::Uuid(uuid::Uuid::from_slice(bytes).ok()?)
+        }
+        (SchemaType::Bytes, ValueRef::Text(bytes)) | (_, ValueRef::Blob(bytes)) => {
+            Value::bytes(bytes.to_vec())
+        }
+        (_, ValueRef::Text(text)) => {
+            return parse_typed(std::str::from_utf8(text).ok()?, data_type)
+        }
+        (_, ValueRef::Integer(i)) => Value::Int64(i),
+        (_, ValueRef::Real(f)) => Value::Float64(f),
+    };
+    data_type.matches(&converted).then_some(converted)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn create_db(dir: &TempDir) -> std::path::PathBuf {
+        let path = dir.path().join("seed.db");
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE users (
+                id INTEGER PRIMARY KEY,
+                name VARCHAR(40),
+                score REAL,
+                active BOOLEAN,
+                joined DATE,
+                avatar BLOB,
+                extra
+            );
+            INSERT INTO users VALUES (1, 'alice', 9.5, 1, '2024-01-15', x'0102', 'x');
+            INSERT INTO users VALUES (2, 'bob', 7, 0, NULL, NULL, 42);
+            INSERT INTO users VALUES (3, NULL, NULL, NULL, NULL, NULL, NULL);",
+        )
+        .unwrap();
+        path
+    }
+
+    #[test]
+    fn test_sqlite_type_mapping() {
+        let dir = TempDir::new().unwrap();
+        let path = create_db(&dir);
+
+        let schema = sqlite_table_schema(&path, "users", "user").unwrap();
+        let types: Vec<_> = schema.columns.iter().map(|c| c.data_type.clone()).collect();
+        assert_eq!(
+            types,
+            vec![
+                SchemaType::Int,
+                SchemaType::String,
+                SchemaType::Float,
+                SchemaType::Bool,
+                SchemaType::Date,
+                SchemaType::Bytes,
+                SchemaType::Any,
+            ]
+        );
+        assert!(sqlite_table_schema(&path, "missing", "m").is_err());
+    }
+
+    #[test]
+    fn test_load_from_sqlite() {
+        let dir = TempDir::new().unwrap();
+        let path = create_db(&dir);
+
+        let (_, tuples) = load_from_sqlite(&path, "users", "user").unwrap();
+        assert_eq!(tuples.len(), 3);
+        let alice = &tuples[0];
+        assert_eq!(alice.get(1), Some(&Value::string("alice")));
+        assert_eq!(alice.get(3), Some(&Value::Bool(true)));
+        assert!(matches!(alice.get(4), Some(Value::Date(_))));
+        assert_eq!(alice.get(5), Some(&Value::bytes(vec![1, 2])));
+        // Integers in a REAL column are read as floats
+        assert_eq!(tuples[1].get(2), Some(&Value::Float64(7.0)));
+        assert_eq!(tuples[1].get(6), Some(&Value::Int64(42)));
+        assert!(tuples[2].values().iter().skip(1).all(Value::is_null));
+    }
+
+    #[test]
+    fn test_read_sqlite_batches() {
+        let dir = TempDir::new().unwrap();
+        let path = create_db(&dir);
+
+        let mut sizes = Vec::new();
+        read_sqlite_batches(
+            &path,
+            "users",
+            "user",
+            &SqliteOptions { batch_size: 2 },
+            |b| {
+                sizes.push(b.len());
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(sizes, vec![2, 1]);
+    }
+}
@@ -0,0 +1,571 @@
+//! Atomic Write Batches
+//!
+//! Every write goes through a batch: all of its steps are validated first,
+//! persisted under a single logical time (LSN) and applied to memory under
+//! one write lock, after which exactly one snapshot is published. Queries
+//! read whole snapshots, so a statement that touches several tuples or
+//! relations (`update`, upserts, restore) is seen either completely or not
+//! at all, and a long query keeps reading the snapshot it started with
+//! while writers go on publishing newer ones.
+
+use super::cdc::Change;
+use super::partition::{route_updates, PartitionKeys};
+use super::replication::ReplicatedWrite;
+use super::{KnowledgeGraph, StorageEngine};
+use crate::schema::{
+    ConflictAction, FailureAction, ValidationEngine, ValidationError, ValidationPolicy,
+};
+use crate::storage::persist::{PersistBackend, Update};
+use crate::storage::{StorageError, StorageResult};
+use crate::value::Tuple;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+use tracing::info;
+
+/// One step of a write batch
+#[derive(Debug, Clone, PartialEq)]
+pub enum WriteOp {
+    /// Insert tuples into a relation, validated as by `insert_tuples_checked`
+    Insert(String, Vec<Tuple>),
+    /// Delete tuples from a relation
+    Delete(String, Vec<Tuple>),
+}
+
+/// Outcome of a write batch
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchReport {
+    /// Logical time the batch was committed at (0 if nothing was written)
+    pub lsn: u64,
+    /// Tuples newly added
+    pub inserted: usize,
+    /// Tuples removed by delete steps
+    pub deleted: usize,
+    /// Existing tuples replaced because they shared a key (upsert)
+    pub replaced: usize,
+    /// Tuples diverted to quarantine relations
+    pub quarantined: usize,
+}
+
+impl BatchReport {
+    /// Tally the `(changed, duplicates)` counts of committed writes
+    pub(super) fn new(
+        lsn: u64,
+        kinds: Vec<(WriteKind, usize)>,
+        counts: Vec<(usize, usize)>,
+    ) -> Self {
+        let mut report = BatchReport {
+            lsn,
+            ..BatchReport::default()
+        };
+        for ((kind, len), (changed, _)) in kinds.into_iter().zip(counts) {
+            match kind {
+                WriteKind::Insert => report.inserted += changed,
+                WriteKind::Delete => report.deleted += changed,
+                WriteKind::Replace => report.replaced += changed,
+                WriteKind::Quarantine => report.quarantined += len,
+            }
+        }
+        report
+    }
+}
+
+/// Why a validated write is part of a batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum WriteKind {
+    Insert,
+    Delete,
+    /// Removal of a tuple superseded by an upsert
+    Replace,
+    /// Insert into `<relation>_quarantine`
+    Quarantine,
+}
+
+impl WriteKind {
+    fn is_insert(self) -> bool {
+        matches!(self, Self::Insert | Self::Quarantine)
+    }
+}
+
+/// A validated write, ready to be committed
+pub(super) struct PendingWrite {
+    relation: String,
+    tuples: Vec<Tuple>,
+    kind: WriteKind,
+}
+
+impl PendingWrite {
+    pub(super) fn new(relation: impl Into<String>, tuples: Vec<Tuple>, kind: WriteKind) -> Self {
+        Self {
+            relation: relation.into(),
+            tuples,
+            kind,
+        }
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.tuples.is_empty()
+    }
+
+    /// The write that cancels this one out in the persist layer
+    pub(super) fn inverse(&self) -> Self {
+        let kind = if self.kind.is_insert() {
+            WriteKind::Delete
+        } else {
+            WriteKind::Insert
+        };
+        Self::new(self.relation.clone(), self.tuples.clone(), kind)
+    }
+
+    /// The write as recorded for followers
+    pub(super) fn replicated(&self) -> ReplicatedWrite {
+        ReplicatedWrite {
+            relation: self.relation.clone(),
+            delete: !self.kind.is_insert(),
+            tuples: self.tuples.clone(),
+        }
+    }
+}
+
+impl From<ReplicatedWrite> for PendingWrite {
+    fn from(write: ReplicatedWrite) -> Self {
+        let kind = if write.delete {
+            WriteKind::Delete
+        } else {
+            WriteKind::Insert
+        };
+        PendingWrite::new(write.relation, write.tuples, kind)
+    }
+}
+
+impl StorageEngine {
+    /// Apply inserts and deletes to a knowledge graph as one atomic batch.
+    ///
+    /// Steps take effect in order, so deleting a tuple and inserting its
+    /// replacement keeps a unique key intact. Every step is validated
+    /// before anything is written: a rejected insert leaves the knowledge
+    /// graph untouched. Concurrent queries see the state before the batch
+    /// or after it, never in between.
+    pub fn apply_batch_in(&self, kg: &str, ops: Vec<WriteOp>) -> StorageResult<BatchReport> {
+        self.ensure_resident_in(kg, &op_relations(&ops))?;
+        let writes = {
+            let db = self
+                .knowledge_graphs
+                .get(kg)
+                .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
+            let db = db.read();
+            self.prepare_writes(&db, kg, ops)?
+        };
+
+        let kinds = write_kinds(&writes);
+        let (lsn, counts) = self.commit_writes(kg, writes)?;
+        Ok(BatchReport::new(lsn, kinds, counts))
+    }
+
+    /// Validate the steps of a batch against the knowledge graph's current
+    /// state, filling defaults and resolving key conflicts
+    pub(super) fn prepare_writes(
+        &self,
+        db: &KnowledgeGraph,
+        kg: &str,
+        ops: Vec<WriteOp>,
+    ) -> StorageResult<Vec<PendingWrite>> {
+        let policy = self.config.storage.validation;
+        let structural_only = ValidationPolicy {
+            on_failure: FailureAction::Warn,
+            ..policy
+        };
+
+        let mut writes = Vec::new();
+        // Keyed relations as the earlier steps leave them, so key
+        // conflicts account for tuples deleted or inserted by the batch
+        let mut staged: HashMap<String, Vec<Tuple>> = HashMap::new();
+        for op in ops {
+            match op {
+                WriteOp::Delete(relation, tuples) => {
+                    if tuples.is_empty() {
+                        continue;
+                    }
+                    let tuples = db
+                        .screen_tuples(&relation, tuples, &structural_only)?
+                        .accepted;
+                    if let Some(existing) = staged_tuples(db, &mut staged, &relation) {
+                        let removed: HashSet<&Tuple> = tuples.iter().collect();
+                        existing.retain(|t| !removed.contains(t));
+                    }
+                    writes.push(PendingWrite::new(relation, tuples, WriteKind::Delete));
+                }
+                WriteOp::Insert(relation, tuples) => {
+                    if tuples.is_empty() {
+                        continue;
+                    }
+                    let tuples = db.fill_defaults(&relation, tuples, true)?;
+                    let tuples = db.coerce_tuples(&relation, tuples);
+                    let mut screened = db.screen_tuples(&relation, tuples, &policy)?;
+
+                    let schema = db.schema_catalog.get(&relation);
+                    if let (Some(schema), Some(existing)) =
+                        (schema, staged_tuples(db, &mut staged, &relation))
+                    {
+                        let conflicts = ValidationEngine::new().check_unique_keys(
+                            schema,
+                            &screened.accepted,
+                            existing,
+                        );
+                        if !conflicts.is_empty() {
+                            if policy.on_conflict == ConflictAction::Reject {
+                                return Err(ValidationError::BatchRejected {
+                                    relation,
+                                    total_tuples: screened.accepted.len(),
+                                    violations: conflicts.violations,
+                                }
+                                .into());
+                            }
+                            for &idx in conflicts.superseded.iter().rev() {
+                                screened.accepted.remove(idx);
+                            }
+                            {
+                                let replaced: HashSet<&Tuple> = conflicts.existing.iter().collect();
+                                existing.retain(|t| !replaced.contains(t));
+                            }
+                            writes.push(PendingWrite::new(
+                                relation.clone(),
+                                conflicts.existing,
+                                WriteKind::Replace,
+                            ));
+                        }
+                        for tuple in &screened.accepted {
+                            if !existing.contains(tuple) {
+                                existing.push(tuple.clone());
+                            }
+                        }
+                    }
+
+                    if !screened.violations.is_empty() {
+                        tracing::warn!(
+                            kg = %kg,
+                            relation = %relation,
+                            violations = screened.violations.len(),
+                            action = ?policy.on_failure,
+                            "check_constraint_violations"
+                        );
+                    }
+                    if !screened.quarantined.is_empty() {
+                        writes.push(PendingWrite::new(
+                            ValidationPolicy::quarantine_relation(&relation),
+                            screened.quarantined,
+                            WriteKind::Quarantine,
+                        ));
+                    }
+                    writes.push(PendingWrite::new(
+                        relation,
+                        screened.accepted,
+                        WriteKind::Insert,
+                    ));
+                }
+            }
+        }
+        Ok(writes)
+    }
+
+    /// Persist validated writes under one logical time, then apply them to
+    /// memory under a single write lock and publish one snapshot.
+    ///
+    /// Returns the logical time used (0 if every write was empty) and
+    /// `(changed, duplicates)` per write, in order.
+    pub(super) fn commit_writes(
+        &self,
+        kg: &str,
+        writes: Vec<PendingWrite>,
+    ) -> StorageResult<(u64, Vec<(usize, usize)>)> {
+        if writes.iter().all(|w| w.tuples.is_empty()) {
+            return Ok((0, vec![(0, 0); writes.len()]));
+        }
+        let relations: Vec<String> = writes.iter().map(|w| w.relation.clone()).collect();
+        self.ensure_resident_in(kg, &relations)?;
+
+        let keys = {
+            let db = self
+                .knowledge_graphs
+                .get(kg)
+                .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
+            let db = db.read();
+            db.check_writes(&writes)?;
+            db.partition_keys(&writes)
+        };
+
+        let time = self.persist_writes(kg, &writes, &keys)?;
+
+        // Update in-memory state (the dropping_kgs guard taken while
+        // persisting is released before the KG write lock is acquired)
+        let db = self
+            .knowledge_graphs
+            .get(kg)
+            .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
+
+        let replicated = self.replicated_writes(&writes);
+        let mut changes = self.change_capture(kg);
+        let mut db = db.write();
+        // Reload anything evicted since validation, without this batch
+        db.make_resident(&relations, Some(time))?;
+        let (counts, failure) = db.stage_writes(writes, time, changes.as_mut());
+        self.record_writes(kg, replicated, counts.len(), &[]);
+        self.publish_changes(kg, time, changes);
+
+        // Publish even after a failed step, so readers never lag behind
+        // what is already in memory
+        if counts.iter().any(|&(changed, _)| changed > 0) {
+            db.publish_snapshot();
+        }
+        if let Some(e) = failure {
+            return Err(e);
+        }
+        if db.evict_over_cap(&relations) {
+            db.publish_snapshot();
+        }
+        Ok((time, counts))
+    }
+
+    /// Append validated writes to the persist layer under one new logical
+    /// time, which is returned. Writes to partitioned relations are split
+    /// across their partitions' shards.
+    pub(super) fn persist_writes(
+        &self,
+        kg: &str,
+        writes: &[PendingWrite],
+        keys: &PartitionKeys,
+    ) -> StorageResult<u64> {
+        // Hold dropping_kgs read guard across the entire persist operation
+        // to prevent a TOCTOU race where a KG drop starts between the check
+        // and the persist call. The read lock allows concurrent inserts but
+        // blocks KG drops from marking the KG as dropping until we finish.
+        let dropping_guard = self.dropping_kgs.read();
+        if dropping_guard.contains(kg) {
+            return Err(StorageError::KnowledgeGraphNotFound(kg.to_string()));
+        }
+
+        // One logical time for the whole batch
+        let time = self.logical_time.fetch_add(1, Ordering::SeqCst);
+
+        // Persist first (durability guarantee via WAL + batches)
+        for write in writes.iter().filter(|w| !w.tuples.is_empty()) {
+            let base = format!("{kg}:{}", write.relation);
+            let key = keys
+                .get(&write.relation)
+                .map(|(column, spec)| (*column, spec));
+            let updates: Vec<Update> = write
+                .tuples
+                .iter()
+                .map(|data| {
+                    if write.kind.is_insert() {
+                        Update::insert(data.clone(), time)
+                    } else {
+                        Update::delete(data.clone(), time)
+                    }
+                })
+                .collect();
+
+            let persist_start = Instant::now();
+            let tuples = updates.len();
+            for (shard, updates) in route_updates(&base, key, updates) {
+                self.persist.ensure_shard(&shard)?;
+                self.persist.append(&shard, &updates)?;
+            }
+            let persist_ms = persist_start.elapsed().as_millis() as u64;
+            info!(
+                kg = %kg,
+                relation = %write.relation,
+                tuples,
+                time,
+                persist_ms,
+                "persist_append_complete"
+            );
+        }
+
+        Ok(time)
+    }
+}
+
+impl KnowledgeGraph {
+    /// Partition keys of the partitioned relations a batch writes to
+    pub(super) fn partition_keys(&self, writes: &[PendingWrite]) -> PartitionKeys {
+        writes
+            .iter()
+            .filter_map(|w| Some((w.relation.clone(), self.partition_key(&w.relation)?)))
+            .collect()
+    }
+
+    /// Check every insert of a batch with [`Self::check_insertable`]
+    pub(super) fn check_writes(&self, writes: &[PendingWrite]) -> StorageResult<()> {
+        let mut arities: HashMap<&str, usize> = HashMap::new();
+        for write in writes.iter().filter(|w| w.kind.is_insert()) {
+            self.check_insertable(&write.relation, &write.tuples, &mut arities)?;
+        }
+        Ok(())
+    }
+
+    /// Reject inserts into views and tuples whose arity differs from the
+    /// relation's (or from earlier inserts of the same batch)
+    fn check_insertable<'a>(
+        &self,
+        relation: &'a str,
+        tuples: &[Tuple],
+        arities: &mut HashMap<&'a str, usize>,
+    ) -> StorageResult<()> {
+        if tuples.is_empty() {
+            return Ok(());
+        }
+
+        // Check if relation is a view (derived relation) - cannot insert into views
+        if self.rule_exists(relation) {
+            return Err(StorageError::Other(format!(
+                "Cannot insert into '{relation}': it is a derived relation (view). \
+                 Use a base relation or drop the rule first with '.rule drop {relation}'."
+            )));
+        }
+
+        // Verify all tuples in this batch have the same arity
+        let new_arity = tuples.first().map_or(0, Tuple::arity);
+        for tuple in tuples {
+            if tuple.arity() != new_arity {
+                return Err(StorageError::Other(format!(
+                    "Arity mismatch in insert batch: expected {}, got {}",
+                    new_arity,
+                    tuple.arity()
+                )));
+            }
+        }
+
+        // Check if relation already exists with a different arity
+        let existing_arity = match arities.get(relation) {
+            Some(&arity) => Some(arity),
+            None => self.metadata.relations.get(relation).map(|meta| {
+                self.schema_catalog
+                    .get(relation)
+                    .map_or(meta.schema.len(), |schema| schema.columns.len())
+            }),
+        };
+        if let Some(existing_arity) = existing_arity {
+            if existing_arity != new_arity {
+                return Err(StorageError::Other(format!(
+                    "Arity mismatch for relation '{relation}': existing arity is {existing_arity}, but trying to insert tuples with arity {new_arity}"
+                )));
+            }
+        }
+        arities.insert(relation, new_arity);
+        Ok(())
+    }
+
+    /// Apply committed writes in order without publishing. Stops at the
+    /// first failing write and returns the counts of those applied before
+    /// it, along with the error.
+    pub(super) fn stage_writes(
+        &mut self,
+        writes: Vec<PendingWrite>,
+        time: u64,
+        mut changes: Option<&mut Vec<Change>>,
+    ) -> (Vec<(usize, usize)>, Option<StorageError>) {
+        let mut counts = Vec::with_capacity(writes.len());
+        let mut failure = None;
+        for write in writes {
+            let change = changes.as_ref().map(|_| self.change_of(&write));
+            let applied = if write.tuples.is_empty() {
+                Ok((0, 0))
+            } else if write.kind.is_insert() {
+                self.insert_in_memory(&write.relation, write.tuples, time)
+            } else {
+                self.delete_in_memory(&write.relation, &write.tuples, time)
+                    .map(|deleted| (deleted, 0))
+            };
+            match applied {
+                Ok(count) => {
+                    counts.push(count);
+                    if let (Some(changes), Some(change)) = (changes.as_deref_mut(), change) {
+                        if !change.tuples.is_empty() {
+                            changes.push(change);
+                        }
+                    }
+                }
+                Err(e) => {
+                    // The relation's tuples may have changed before the
+                    // step failed (e.g. in the incremental engine)
+                    if let (Some(changes), Some(change)) = (changes.as_deref_mut(), change) {
+                        if !change.tuples.is_empty() {
+                            changes.push(change);
+                        }
+                    }
+                    failure = Some(e);
+                    break;
+                }
+            }
+        }
+        if counts.iter().any(|&(changed, _)| changed > 0) {
+            self.lsn = time;
+        }
+        (counts, failure)
+    }
+
+    /// The tuples `write` changes if it is the next write applied: the
+    /// new tuples of an insert, the present tuples of a removal
+    fn change_of(&self, write: &PendingWrite) -> Change {
+        let insert = write.kind.is_insert();
+        let present: HashSet<&Tuple> = self
+            .engine
+            .input_tuples
+            .get(&write.relation)
+            .map(|tuples| tuples.iter().collect())
+            .unwrap_or_default();
+        let mut seen = HashSet::new();
+        let tuples = write
+            .tuples
+            .iter()
+            .filter(|t| present.contains(t) != insert && seen.insert(*t))
+            .cloned()
+            .collect();
+        Change {
+            relation: write.relation.clone(),
+            diff: if insert { 1 } else { -1 },
+            tuples,
+        }
+    }
+}
+
+/// Relations the steps of a batch write to
+pub(super) fn op_relations(ops: &[WriteOp]) -> Vec<String> {
+    let mut relations: Vec<String> = ops
+        .iter()
+        .map(|op| match op {
+            WriteOp::Insert(relation, _) | WriteOp::Delete(relation, _) => relation.clone(),
+        })
+        .collect();
+    relations.dedup();
+    relations
+}
+
+/// The tuples a keyed relation holds as of the current batch step, or
+/// `None` if the relation declares no unique key
+fn staged_tuples<'a>(
+    db: &KnowledgeGraph,
+    staged: &'a mut HashMap<String, Vec<Tuple>>,
+    relation: &str,
+) -> Option<&'a mut Vec<Tuple>> {
+    let keyed = db
+        .schema_catalog
+        .get(relation)
+        .is_some_and(|schema| !schema.unique_keys().is_empty());
+    if !keyed {
+        return None;
+    }
+    Some(staged.entry(relation.to_string()).or_insert_with(|| {
+        db.engine
+            .input_tuples
+            .get(relation)
+            .cloned()
+            .unwrap_or_default()
+    }))
+}
+
+/// Kind and size of each write, recorded before the writes are consumed
+pub(super) fn write_kinds(writes: &[PendingWrite]) -> Vec<(WriteKind, usize)> {
+    writes.iter().map(|w| (w.kind, w.tuples.len())).collect()
+}
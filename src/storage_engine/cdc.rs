@@ -0,0 +1,351 @@
+//! Change Data Capture
+//!
+//! With `[cdc] enabled`, every committed write to a base relation is
+//! published as a stream of [`ChangeEvent`]s, so downstream systems can
+//! mirror the knowledge graphs. An event is one tuple that was inserted
+//! (`diff` 1) or removed (`diff` -1), with the transaction it was committed
+//! in and the commit time. The transaction is the logical time (LSN) the
+//! batch was persisted under: every change of a batch or transaction
+//! shares it, and it increases from one commit to the next.
+//!
+//! Only actual changes are published. Inserting a tuple that is already
+//! present, or deleting one that is not, changes nothing and produces no
+//! event, so applying the events in order reproduces the relations. An
+//! upsert is a removal of the replaced tuple followed by an insert.
+//! Derived relations are not published; they follow from the base
+//! relations and the rules. A schema migration (`.rel alter`) rewrites the
+//! tuples of a relation without publishing them, so consumers reload the
+//! relation after one.
+//!
+//! A commit's events are published together, while the knowledge graph is
+//! still locked, so the events of one knowledge graph arrive in commit
+//! order. They go to every sink that is configured:
+//!
+//! - `changes.jsonl` in the CDC directory, one event per line, rotated
+//!   like the audit log
+//! - WebSocket subscribers of `GET /v1/changes`, one message per commit
+//! - a Kafka topic, one message per event keyed by knowledge graph
+//!   (`kafka` feature)
+//!
+//! ```text
+//! {"txn":42,"time":1700000000000,"knowledge_graph":"shop","relation":"item","diff":1,"tuple":[1,"tea"]}
+//! ```
+//!
+//! Sinks never fail a commit: the changes are already durable in the WAL.
+//! A file or Kafka error is logged and the events are lost to that sink.
+
+use super::StorageEngine;
+use crate::config::CdcConfig;
+use crate::jsonl_log::JsonlLog;
+use crate::storage::jsonl::value_to_json;
+use crate::storage::StorageResult;
+use crate::value::Tuple;
+use serde::{Serialize, Serializer};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+/// Tuples one write of a batch changed
+pub(super) struct Change {
+    pub(super) relation: String,
+    /// 1 for inserted tuples, -1 for removed ones
+    pub(super) diff: i8,
+    pub(super) tuples: Vec<Tuple>,
+}
+
+/// One tuple inserted into or removed from a base relation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChangeEvent {
+    /// Logical time of the commit, shared by its changes
+    pub txn: u64,
+    /// Commit time, in milliseconds since the epoch
+    pub time: i64,
+    pub knowledge_graph: String,
+    pub relation: String,
+    /// 1 if the tuple was inserted, -1 if it was removed
+    pub diff: i8,
+    #[serde(serialize_with = "tuple_to_json")]
+    pub tuple: Tuple,
+}
+
+fn tuple_to_json<S: Serializer>(tuple: &Tuple, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(tuple.values().iter().map(value_to_json))
+}
+
+/// Committed changes, published to the configured sinks
+pub struct ChangeFeed {
+    config: CdcConfig,
+    file: Option<JsonlLog>,
+    /// The events of each commit, for `GET /v1/changes`
+    subscribers: broadcast::Sender<Arc<Vec<ChangeEvent>>>,
+    #[cfg(feature = "kafka")]
+    kafka: Option<kafka::KafkaSink>,
+}
+
+impl ChangeFeed {
+    /// Open the sinks of `config`. The change files default to `cdc`
+    /// under `data_dir`.
+    pub fn open(config: &CdcConfig, data_dir: &Path) -> StorageResult<Self> {
+        let file = if config.file {
+            let dir = config.dir.clone().unwrap_or_else(|| data_dir.join("cdc"));
+            let file = JsonlLog::open(dir, "changes", config.max_file_bytes, config.max_files)?;
+            info!(dir = %file.dir().display(), "cdc_file_enabled");
+            Some(file)
+        } else {
+            None
+        };
+        #[cfg(feature = "kafka")]
+        let kafka = config
+            .kafka
+            .as_ref()
+            .map(kafka::KafkaSink::new)
+            .transpose()
+            .map_err(|e| crate::storage::StorageError::Other(format!("cdc.kafka: {e}")))?;
+        #[cfg(not(feature = "kafka"))]
+        if config.kafka.is_some() {
+            warn!("cdc_kafka_unavailable: rebuild with --features kafka to produce to Kafka");
+        }
+        Ok(Self {
+            config: config.clone(),
+            file,
+            subscribers: broadcast::channel(config.subscriber_buffer.max(1)).0,
+            #[cfg(feature = "kafka")]
+            kafka,
+        })
+    }
+
+    /// Whether changes to `kg` are published
+    pub fn publishes(&self, kg: &str) -> bool {
+        self.config.publishes(kg)
+    }
+
+    /// Receive the events of each commit from now on. A receiver that
+    /// falls more than `subscriber_buffer` commits behind loses the oldest
+    /// and sees `RecvError::Lagged`.
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<Vec<ChangeEvent>>> {
+        self.subscribers.subscribe()
+    }
+
+    /// Publish the changes of one commit to `kg`
+    pub(super) fn publish(&self, kg: &str, txn: u64, changes: Vec<Change>) {
+        let time = chrono::Utc::now().timestamp_millis();
+        let events: Vec<ChangeEvent> = changes
+            .into_iter()
+            .flat_map(|change| {
+                let relation = change.relation;
+                change.tuples.into_iter().map(move |tuple| ChangeEvent {
+                    txn,
+                    time,
+                    knowledge_graph: kg.to_string(),
+                    relation: relation.clone(),
+                    diff: change.diff,
+                    tuple,
+                })
+            })
+            .collect();
+        if events.is_empty() {
+            return;
+        }
+        if let Some(file) = &self.file {
+            if let Err(e) = file.append_all(&events) {
+                warn!(kg = %kg, txn, error = %e, "cdc_file_append_failed");
+            }
+        }
+        #[cfg(feature = "kafka")]
+        if let Some(kafka) = &self.kafka {
+            kafka.send(&events);
+        }
+        // No subscribers is not an error
+        let _ = self.subscribers.send(Arc::new(events));
+    }
+
+    /// Wait for events handed to Kafka to be delivered
+    pub fn flush(&self) {
+        #[cfg(feature = "kafka")]
+        if let Some(kafka) = &self.kafka {
+            kafka.flush();
+        }
+    }
+}
+
+impl StorageEngine {
+    /// The change feed, when `[cdc]` is enabled
+    pub fn change_feed(&self) -> Option<&Arc<ChangeFeed>> {
+        self.cdc.as_ref()
+    }
+
+    /// A buffer for the changes of a batch on `kg`, if they are published
+    pub(super) fn change_capture(&self, kg: &str) -> Option<Vec<Change>> {
+        self.cdc
+            .as_ref()
+            .filter(|feed| feed.publishes(kg))
+            .map(|_| Vec::new())
+    }
+
+    /// Publish the changes captured while committing transaction `txn`
+    pub(super) fn publish_changes(&self, kg: &str, txn: u64, changes: Option<Vec<Change>>) {
+        if let (Some(feed), Some(changes)) = (&self.cdc, changes) {
+            feed.publish(kg, txn, changes);
+        }
+    }
+}
+
+#[cfg(feature = "kafka")]
+mod kafka {
+    use super::ChangeEvent;
+    use crate::config::CdcKafkaConfig;
+    use rdkafka::config::ClientConfig;
+    use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+    use rdkafka::producer::{BaseRecord, DefaultProducerContext, Producer, ThreadedProducer};
+    use std::time::Duration;
+    use tracing::warn;
+
+    /// Attempts at handing an event to a full producer queue before it is
+    /// dropped, `QUEUE_FULL_WAIT` apart
+    const QUEUE_FULL_RETRIES: usize = 100;
+    const QUEUE_FULL_WAIT: Duration = Duration::from_millis(10);
+
+    pub(super) struct KafkaSink {
+        producer: ThreadedProducer<DefaultProducerContext>,
+        topic: String,
+    }
+
+    impl KafkaSink {
+        pub(super) fn new(config: &CdcKafkaConfig) -> Result<Self, KafkaError> {
+            let mut client = ClientConfig::new();
+            client
+                .set("bootstrap.servers", &config.brokers)
+                .set("enable.idempotence", "true");
+            for (key, value) in &config.properties {
+                client.set(key, value);
+            }
+            Ok(Self {
+                producer: client.create()?,
+                topic: config.topic.clone(),
+            })
+        }
+
+        /// Hand `events` to the producer, which delivers them in the
+        /// background. Events of one knowledge graph share a key, so they
+        /// land in one partition in commit order.
+        pub(super) fn send(&self, events: &[ChangeEvent]) {
+            for event in events {
+                let payload = match serde_json::to_vec(event) {
+                    Ok(payload) => payload,
+                    Err(e) => {
+                        warn!(error = %e, "cdc_kafka_encode_failed");
+                        continue;
+                    }
+                };
+                let mut record = BaseRecord::to(&self.topic)
+                    .key(event.knowledge_graph.as_str())
+                    .payload(&payload);
+                let mut retries = 0;
+                loop {
+                    match self.producer.send(record) {
+                        Ok(()) => break,
+                        Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), r))
+                            if retries < QUEUE_FULL_RETRIES =>
+                        {
+                            retries += 1;
+                            record = r;
+                            std::thread::sleep(QUEUE_FULL_WAIT);
+                        }
+                        Err((e, _)) => {
+                            warn!(
+                                topic = %self.topic,
+                                txn = event.txn,
+                                error = %e,
+                                "cdc_kafka_send_failed"
+                            );
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        pub(super) fn flush(&self) {
+            if let Err(e) = self.producer.flush(Duration::from_secs(10)) {
+                warn!(topic = %self.topic, error = %e, "cdc_kafka_flush_failed");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::storage_engine::WriteOp;
+    use crate::value::Value;
+    use std::fs;
+
+    fn engine(dir: &Path) -> StorageEngine {
+        let mut config = Config::default();
+        config.storage.data_dir = dir.to_path_buf();
+        config.cdc.enabled = true;
+        StorageEngine::new(config).unwrap()
+    }
+
+    fn tuple(n: i64, name: &str) -> Tuple {
+        Tuple::new(vec![Value::Int64(n), Value::string(name)])
+    }
+
+    #[test]
+    fn test_only_actual_changes_are_published() {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = engine(tmp.path());
+        let mut changes = storage.change_feed().unwrap().subscribe();
+
+        storage
+            .insert_tuples_into("default", "item", vec![tuple(1, "tea"), tuple(2, "cake")])
+            .unwrap();
+        let first = changes.try_recv().unwrap();
+        assert_eq!(first.len(), 2);
+        assert!(first.iter().all(|e| e.diff == 1 && e.relation == "item"));
+
+        // A duplicate insert and a delete of a missing tuple change nothing
+        storage
+            .apply_batch_in(
+                "default",
+                vec![
+                    WriteOp::Insert("item".to_string(), vec![tuple(1, "tea")]),
+                    WriteOp::Delete("item".to_string(), vec![tuple(2, "cake"), tuple(3, "pie")]),
+                ],
+            )
+            .unwrap();
+        let second = changes.try_recv().unwrap();
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].diff, -1);
+        assert_eq!(second[0].tuple, tuple(2, "cake"));
+        assert!(second[0].txn > first[0].txn);
+        assert!(changes.try_recv().is_err());
+
+        let text = fs::read_to_string(tmp.path().join("cdc").join("changes.jsonl")).unwrap();
+        let lines: Vec<serde_json::Value> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["knowledge_graph"], "default");
+        assert_eq!(lines[0]["tuple"], serde_json::json!([1, "tea"]));
+        assert_eq!(lines[2]["diff"], -1);
+    }
+
+    #[test]
+    fn test_system_knowledge_graphs_are_not_published() {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = engine(tmp.path());
+        let mut changes = storage.change_feed().unwrap().subscribe();
+        storage
+            .create_knowledge_graph(crate::auth::INTERNAL_KG)
+            .unwrap();
+        storage
+            .insert_tuples_into(crate::auth::INTERNAL_KG, "users", vec![tuple(1, "admin")])
+            .unwrap();
+        assert!(changes.try_recv().is_err());
+    }
+}
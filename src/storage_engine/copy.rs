@@ -0,0 +1,220 @@
+//! COPY Statements
+//!
+//! `copy` moves a relation between a knowledge graph and a file on the
+//! server, using the readers and writers of [`crate::storage`]:
+//!
+//! ```text
+//! copy edge from 'edges.parquet'
+//! copy path to 'out.csv' (format csv, header true)
+//! ```
+//!
+//! The format is the `format` option or, without it, the file's extension:
+//! `csv`, `parquet`, `jsonl` (or `ndjson`) or `avro`. CSV files also take
+//! `header` (default true) and `delimiter` (default `,`).
+//!
+//! Rows copied from a file are inserted like any other write. For CSV,
+//! JSON Lines and Avro the schema read from the file is registered unless
+//! the relation already has one, as [`StorageEngine::import_csv_in`] does;
+//! Parquet rows are inserted as read.
+//!
+//! Copying to a file writes the relation as a query sees it, so a view is
+//! exported with its rules applied. Columns are named after the schema, or
+//! `col0`, `col1`, ... without one.
+
+use super::StorageEngine;
+use crate::storage::parquet::{load_tuples_from_parquet, save_tuples_to_parquet};
+use crate::storage::{
+    save_to_avro, save_to_csv_with_options, save_to_jsonl, CsvInference, CsvOptions, JsonlOptions,
+    StorageError, StorageResult,
+};
+use crate::value::{infer_schema_from_tuples, Tuple};
+use std::fmt;
+use std::path::Path;
+use tracing::info;
+
+/// File format of a `copy` statement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyFormat {
+    Csv,
+    Parquet,
+    Jsonl,
+    Avro,
+}
+
+impl CopyFormat {
+    /// Format named `name` in a `format` option
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "csv" => Some(CopyFormat::Csv),
+            "parquet" => Some(CopyFormat::Parquet),
+            "jsonl" | "ndjson" => Some(CopyFormat::Jsonl),
+            "avro" => Some(CopyFormat::Avro),
+            _ => None,
+        }
+    }
+
+    /// Format implied by the extension of `path`
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Self::from_name(path.extension()?.to_str()?)
+    }
+}
+
+impl fmt::Display for CopyFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CopyFormat::Csv => "csv",
+            CopyFormat::Parquet => "parquet",
+            CopyFormat::Jsonl => "jsonl",
+            CopyFormat::Avro => "avro",
+        })
+    }
+}
+
+/// Options of a `copy` statement
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CopyOptions {
+    /// File format; taken from the file's extension when not given
+    pub format: Option<CopyFormat>,
+    /// Whether a CSV file has a header row (default: true)
+    pub header: bool,
+    /// CSV field delimiter (default: ',')
+    pub delimiter: char,
+}
+
+impl Default for CopyOptions {
+    fn default() -> Self {
+        CopyOptions {
+            format: None,
+            header: true,
+            delimiter: ',',
+        }
+    }
+}
+
+impl CopyOptions {
+    fn format_for(&self, path: &Path) -> StorageResult<CopyFormat> {
+        self.format
+            .or_else(|| CopyFormat::from_path(path))
+            .ok_or_else(|| {
+                StorageError::Other(format!(
+                    "Cannot tell the format of '{}'; give one with (format csv|parquet|jsonl|avro)",
+                    path.display()
+                ))
+            })
+    }
+
+    fn csv(&self) -> CsvOptions {
+        CsvOptions {
+            delimiter: self.delimiter,
+            has_header: self.header,
+            ..CsvOptions::default()
+        }
+    }
+}
+
+impl StorageEngine {
+    /// Copy the rows of a file into a relation of a specific knowledge
+    /// graph. Returns the number of tuples inserted.
+    pub fn copy_from_in(
+        &self,
+        kg: &str,
+        relation: &str,
+        path: &Path,
+        options: &CopyOptions,
+    ) -> StorageResult<usize> {
+        let format = options.format_for(path)?;
+        let inserted = match format {
+            CopyFormat::Csv => {
+                self.import_csv_in(kg, relation, path, &options.csv(), &CsvInference::default())?
+                    .1
+            }
+            CopyFormat::Jsonl => {
+                self.import_jsonl_in(kg, relation, path, &JsonlOptions::default())?
+                    .1
+            }
+            CopyFormat::Avro => self.import_avro_in(kg, relation, path)?.1,
+            CopyFormat::Parquet => {
+                if !path.exists() {
+                    return Err(StorageError::Other(format!(
+                        "File '{}' does not exist",
+                        path.display()
+                    )));
+                }
+                let (tuples, _) = load_tuples_from_parquet(path)?;
+                self.insert_tuples_into(kg, relation, tuples)?.0
+            }
+        };
+        info!(kg, relation, path = %path.display(), %format, inserted, "copy_from_complete");
+        Ok(inserted)
+    }
+
+    /// Copy a relation or view of a specific knowledge graph to a file,
+    /// replacing it. Returns the number of tuples written.
+    pub fn copy_to_in(
+        &self,
+        kg: &str,
+        relation: &str,
+        path: &Path,
+        options: &CopyOptions,
+    ) -> StorageResult<usize> {
+        let format = options.format_for(path)?;
+        let (schema, arity, base) = self.with_resident(
+            kg,
+            |_| vec![relation.to_string()],
+            |db| {
+                let schema = db.schema_catalog.get(relation).cloned();
+                let is_view = db.rule_catalog.exists(relation);
+                let base = db.engine.input_tuples.get(relation);
+                let arity = schema
+                    .as_ref()
+                    .map(|schema| schema.columns.len())
+                    .or_else(|| db.rule_catalog.rule_arity(relation))
+                    .or_else(|| base?.first().map(Tuple::arity));
+                // Views are read through a query, base relations directly
+                let base = (!is_view).then(|| base.cloned().unwrap_or_default());
+                (schema, arity, base)
+            },
+        )?;
+        let arity = arity
+            .ok_or_else(|| StorageError::RelationNotFound(relation.to_string(), kg.to_string()))?;
+
+        let mut tuples = match base {
+            Some(tuples) => tuples,
+            None => {
+                let vars: Vec<String> = (0..arity).map(|i| format!("V{i}")).collect();
+                let vars = vars.join(", ");
+                self.execute_query_with_rules_tuples_on(
+                    kg,
+                    &format!("__copy__({vars}) <- {relation}({vars})"),
+                )?
+            }
+        };
+        tuples.sort();
+        let columns: Vec<String> = match &schema {
+            Some(schema) => schema.columns.iter().map(|c| c.name.clone()).collect(),
+            None => (0..arity).map(|i| format!("col{i}")).collect(),
+        };
+
+        match format {
+            CopyFormat::Csv => save_to_csv_with_options(path, &columns, &tuples, options.csv())?,
+            CopyFormat::Jsonl => save_to_jsonl(path, &columns, &tuples)?,
+            CopyFormat::Avro => {
+                let schema = schema.ok_or_else(|| {
+                    StorageError::Other(format!(
+                        "Relation '{relation}' has no schema; Avro export needs one"
+                    ))
+                })?;
+                save_to_avro(path, &schema, &tuples)?;
+            }
+            CopyFormat::Parquet => {
+                let tuple_schema = match &schema {
+                    Some(schema) => schema.to_tuple_schema(),
+                    None => infer_schema_from_tuples(&tuples, &columns),
+                };
+                save_tuples_to_parquet(path, &tuples, &tuple_schema)?;
+            }
+        }
+        info!(kg, relation, path = %path.display(), %format, rows = tuples.len(), "copy_to_complete");
+        Ok(tuples.len())
+    }
+}
@@ -0,0 +1,104 @@
+//! Catalog Recovery
+//!
+//! Schema and rule catalog changes are logged to the WAL before their
+//! files are rewritten (see [`crate::storage::persist::wal`]). At startup
+//! the latest logged contents of each catalog are written back before the
+//! knowledge graph's data is loaded, so a crash between a DDL statement
+//! and the writes that depend on it cannot leave data without its schema.
+//! `save_all` syncs every catalog file, then drops the records it covers.
+
+use super::{KnowledgeGraph, StorageEngine};
+use crate::storage::persist::{Catalog, DdlChange, DdlLog};
+use crate::storage::{StorageError, StorageResult};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+impl StorageEngine {
+    /// Handle the catalogs of `kg` log their changes through
+    pub(super) fn ddl_log(&self, kg: &str) -> DdlLog {
+        DdlLog::new(Arc::clone(&self.persist), kg.to_string())
+    }
+
+    /// Write back the catalog files of `kg` whose latest logged contents
+    /// they do not hold. Records logged before a drop of `kg` are ignored.
+    pub(super) fn restore_catalogs(&self, kg: &str, data_dir: &Path) -> StorageResult<()> {
+        let mut latest: HashMap<Catalog, String> = HashMap::new();
+        for record in self.persist.replayed_ddl(kg) {
+            match record.change {
+                DdlChange::Catalog { catalog, contents } => {
+                    latest.insert(catalog, contents);
+                }
+                DdlChange::DropKnowledgeGraph => latest.clear(),
+            }
+        }
+
+        for (catalog, contents) in latest {
+            let path = catalog_path(data_dir, catalog);
+            if fs::read_to_string(&path).is_ok_and(|current| current == contents) {
+                continue;
+            }
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut file = fs::File::create(&path)?;
+            file.write_all(contents.as_bytes())?;
+            file.sync_all()?;
+            tracing::warn!(kg, ?catalog, "catalog_restored_from_wal");
+        }
+        Ok(())
+    }
+
+    /// Sync the catalog files of every knowledge graph, then drop the DDL
+    /// records logged before the sync from the WAL
+    pub(super) fn checkpoint_catalogs(&self) -> StorageResult<()> {
+        let covered = self.persist.ddl_seq();
+        for entry in &self.knowledge_graphs {
+            entry.value().read().sync_catalogs()?;
+        }
+        self.persist.truncate_ddl(covered)
+    }
+}
+
+impl KnowledgeGraph {
+    /// Log the catalog changes of this knowledge graph through `ddl_log`
+    pub(super) fn set_ddl_log(&mut self, ddl_log: DdlLog) {
+        self.rule_catalog.set_ddl_log(ddl_log.clone());
+        self.ddl_log = Some(ddl_log);
+    }
+
+    /// Log the current contents of both catalogs, for catalog files that
+    /// were copied rather than saved
+    pub(super) fn log_catalogs(&self) -> StorageResult<()> {
+        let Some(ddl_log) = &self.ddl_log else {
+            return Ok(());
+        };
+        let schemas = self
+            .schema_catalog
+            .to_json()
+            .map_err(|e| StorageError::Other(e.to_string()))?;
+        ddl_log.record(Catalog::Schema, schemas)?;
+        let rules = self.rule_catalog.to_json().map_err(StorageError::Other)?;
+        ddl_log.record(Catalog::Rules, rules)
+    }
+
+    /// Sync both catalog files to disk
+    fn sync_catalogs(&self) -> StorageResult<()> {
+        let schema_path = catalog_path(&self.data_dir, Catalog::Schema);
+        if schema_path.exists() {
+            fs::File::open(&schema_path)?.sync_all()?;
+        }
+        self.rule_catalog.sync()?;
+        Ok(())
+    }
+}
+
+/// File a catalog of the knowledge graph stored in `data_dir` is saved to
+fn catalog_path(data_dir: &Path, catalog: Catalog) -> PathBuf {
+    match catalog {
+        Catalog::Schema => data_dir.join("schema.json"),
+        Catalog::Rules => data_dir.join("rules").join("catalog.json"),
+    }
+}
@@ -0,0 +1,326 @@
+//! Introspection Tables
+//!
+//! Backs the `show relations`, `show views`, `show databases` and
+//! `describe <relation>` statements. Each returns an ordinary relation
+//! (a schema plus tuples), so clients render it like any query result.
+
+use super::{KnowledgeGraph, StorageEngine};
+use crate::schema::{ColumnSchema, RelationSchema, SchemaType};
+use crate::storage::persist::PersistBackend;
+use crate::storage::{StorageError, StorageResult};
+use crate::value::{Tuple, Value};
+use std::collections::BTreeSet;
+
+/// Result of an introspection statement
+pub type IntrospectionTable = (RelationSchema, Vec<Tuple>);
+
+fn table(name: &str, columns: &[(&str, SchemaType)]) -> RelationSchema {
+    columns
+        .iter()
+        .fold(RelationSchema::new(name), |schema, (col, ty)| {
+            schema.with_column(ColumnSchema::new(*col, ty.clone()))
+        })
+}
+
+fn int(n: usize) -> Value {
+    Value::Int64(i64::try_from(n).unwrap_or(i64::MAX))
+}
+
+impl StorageEngine {
+    /// One row per relation of a knowledge graph: name, columns, live row
+    /// count, bytes on disk and the indexes built on it
+    pub fn show_relations_in(&self, kg: &str) -> StorageResult<IntrospectionTable> {
+        let schema = table(
+            "relations",
+            &[
+                ("name", SchemaType::String),
+                ("columns", SchemaType::String),
+                ("rows", SchemaType::Int),
+                ("size_bytes", SchemaType::Int),
+                ("indexes", SchemaType::String),
+            ],
+        );
+        let rows = self.with_kg_read(kg, |db| {
+            let indexes = db.index_names_by_relation()?;
+            let rows = db
+                .relation_names()
+                .into_iter()
+                .map(|name| {
+                    let columns = match db.schema_catalog.get(&name) {
+                        Some(schema) => schema
+                            .columns
+                            .iter()
+                            .map(|c| format!("{}: {}", c.name, c.data_type))
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                        None => db
+                            .metadata
+                            .relations
+                            .get(&name)
+                            .map(|meta| meta.schema.join(", "))
+                            .unwrap_or_default(),
+                    };
+                    // Evicted relations report their last known count
+                    let count = db.engine.input_tuples.get(&name).map_or_else(
+                        || {
+                            db.metadata
+                                .relations
+                                .get(&name)
+                                .map_or(0, |m| m.tuple_count)
+                        },
+                        Vec::len,
+                    );
+                    let size = self.relation_size_bytes(kg, &name);
+                    let index_list = indexes
+                        .iter()
+                        .filter(|(rel, _)| *rel == name)
+                        .map(|(_, index)| index.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    Tuple::new(vec![
+                        Value::string(&name),
+                        Value::string(&columns),
+                        int(count),
+                        Value::Int64(i64::try_from(size).unwrap_or(i64::MAX)),
+                        Value::string(&index_list),
+                    ])
+                })
+                .collect();
+            Ok(rows)
+        })?;
+        Ok((schema, rows))
+    }
+
+    /// One row per persistent rule: name, clause count, materialized row
+    /// count (null if not materialized), definition, refresh policy (null
+    /// if evaluated on every query), time of the last refresh and whether
+    /// the cached result is stale (null until first computed)
+    pub fn show_views_in(&self, kg: &str) -> StorageResult<IntrospectionTable> {
+        let schema = table(
+            "views",
+            &[
+                ("name", SchemaType::String),
+                ("clauses", SchemaType::Int),
+                ("rows", SchemaType::Int),
+                ("definition", SchemaType::String),
+                ("refresh", SchemaType::String),
+                ("refreshed_at", SchemaType::Timestamp),
+                ("stale", SchemaType::Bool),
+            ],
+        );
+        let rows = self.with_kg_read(kg, |db| {
+            let snapshot = db.snapshot();
+            let rows = db
+                .rule_catalog
+                .list()
+                .into_iter()
+                .filter_map(|name| {
+                    let def = db.rule_catalog.get(&name)?;
+                    let definition = def
+                        .to_rules()
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    let materialized = if snapshot.materialized_relations.contains(&name) {
+                        snapshot
+                            .input_tuples
+                            .get(&name)
+                            .map_or(int(0), |t| int(t.len()))
+                    } else {
+                        Value::Null
+                    };
+                    let (refresh, refreshed_at, stale) = match db.view_status(&name) {
+                        Some((policy, Some((at_ms, stale)))) => (
+                            Value::string(&policy.to_string()),
+                            Value::Timestamp(at_ms),
+                            Value::Bool(stale),
+                        ),
+                        Some((policy, None)) => {
+                            (Value::string(&policy.to_string()), Value::Null, Value::Null)
+                        }
+                        None => (Value::Null, Value::Null, Value::Null),
+                    };
+                    Some(Tuple::new(vec![
+                        Value::string(&name),
+                        int(def.rules.len()),
+                        materialized,
+                        Value::string(&definition),
+                        refresh,
+                        refreshed_at,
+                        stale,
+                    ]))
+                })
+                .collect();
+            Ok(rows)
+        })?;
+        Ok((schema, rows))
+    }
+
+    /// One row per knowledge graph: name, relation count, rule count and
+    /// total base rows
+    pub fn show_databases(&self) -> StorageResult<IntrospectionTable> {
+        let schema = table(
+            "databases",
+            &[
+                ("name", SchemaType::String),
+                ("relations", SchemaType::Int),
+                ("views", SchemaType::Int),
+                ("rows", SchemaType::Int),
+            ],
+        );
+        let mut rows = Vec::new();
+        for name in self.list_knowledge_graphs() {
+            let row = self.with_kg_read(&name, |db| {
+                let total: usize = db.engine.input_tuples.values().map(Vec::len).sum();
+                Ok(Tuple::new(vec![
+                    Value::string(&name),
+                    int(db.relation_names().len()),
+                    int(db.rule_catalog.len()),
+                    int(total),
+                ]))
+            });
+            match row {
+                Ok(row) => rows.push(row),
+                // Dropped while we were listing
+                Err(StorageError::KnowledgeGraphNotFound(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok((schema, rows))
+    }
+
+    /// One row per column of a relation: name, type, constraints, indexes
+    /// on the column and its distinct count (null until `analyze` runs)
+    pub fn describe_relation_rows_in(
+        &self,
+        kg: &str,
+        relation: &str,
+    ) -> StorageResult<IntrospectionTable> {
+        let schema = table(
+            "describe",
+            &[
+                ("column", SchemaType::String),
+                ("type", SchemaType::String),
+                ("constraints", SchemaType::String),
+                ("indexes", SchemaType::String),
+                ("distinct", SchemaType::Int),
+            ],
+        );
+        let rows = self.with_kg_read(kg, |db| {
+            let columns: Vec<(String, String, String)> = match db.schema_catalog.get(relation) {
+                Some(schema) => schema
+                    .columns
+                    .iter()
+                    .map(|c| {
+                        (
+                            c.name.clone(),
+                            c.data_type.to_string(),
+                            column_attributes(c),
+                        )
+                    })
+                    .collect(),
+                None => {
+                    let Some(meta) = db.metadata.relations.get(relation) else {
+                        return Err(format!("Relation '{relation}' not found."));
+                    };
+                    meta.schema
+                        .iter()
+                        .map(|c| (c.clone(), "any".to_string(), String::new()))
+                        .collect()
+                }
+            };
+            let indexes = db.index_stats()?;
+            let stats = db.statistics.get(relation);
+            let rows = columns
+                .into_iter()
+                .enumerate()
+                .map(|(i, (name, ty, attributes))| {
+                    let index_list = indexes
+                        .iter()
+                        .filter(|s| s.relation == relation && s.column == name)
+                        .map(|s| format!("{} ({})", s.name, s.index_type))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    let distinct = stats
+                        .and_then(|s| s.column_stats.get(i))
+                        .map_or(Value::Null, |c| int(c.distinct_count));
+                    Tuple::new(vec![
+                        Value::string(&name),
+                        Value::string(&ty),
+                        Value::string(&attributes),
+                        Value::string(&index_list),
+                        distinct,
+                    ])
+                })
+                .collect();
+            Ok(rows)
+        })?;
+        Ok((schema, rows))
+    }
+
+    /// Bytes of Parquet batches stored for a relation, over all of its
+    /// partitions (buffered updates not yet flushed are not counted)
+    fn relation_size_bytes(&self, kg: &str, relation: &str) -> u64 {
+        self.relation_shards(kg, relation)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|shard| self.persist.shard_info(shard).ok())
+            .map(|info| info.size_bytes)
+            .sum()
+    }
+}
+
+impl KnowledgeGraph {
+    /// Names of all relations with data, metadata or a schema, sorted
+    fn relation_names(&self) -> Vec<String> {
+        let names: BTreeSet<String> = self
+            .metadata
+            .relations
+            .keys()
+            .cloned()
+            .chain(self.engine.input_tuples.keys().cloned())
+            .chain(
+                self.schema_catalog
+                    .persistent_schemas()
+                    .map(|s| s.name.clone()),
+            )
+            .filter(|name| !self.rule_catalog.exists(name))
+            .collect();
+        names.into_iter().collect()
+    }
+
+    fn index_stats(&self) -> Result<Vec<crate::index_manager::IndexStats>, String> {
+        match self.incremental() {
+            Some(dd) => dd.get_index_stats(None),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// (relation, index name) of every index
+    fn index_names_by_relation(&self) -> Result<Vec<(String, String)>, String> {
+        Ok(self
+            .index_stats()?
+            .into_iter()
+            .map(|s| (s.relation, s.name))
+            .collect())
+    }
+}
+
+/// Constraints and annotations of a column, as written in a schema
+fn column_attributes(column: &ColumnSchema) -> String {
+    let mut parts: Vec<String> = column.constraints.iter().map(ToString::to_string).collect();
+    if let Some(default) = &column.default {
+        parts.push(format!("= {default}"));
+    }
+    if column.primary_key {
+        parts.push("@key".to_string());
+    }
+    if column.unique {
+        parts.push("@unique".to_string());
+    }
+    if column.auto_increment {
+        parts.push("@auto".to_string());
+    }
+    parts.join(" ")
+}
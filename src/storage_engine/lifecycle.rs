@@ -0,0 +1,279 @@
+//! Knowledge Graph Copy, Rename and Cascading Drop
+//!
+//! A copy replays the update history of every shard of the source into
+//! shards of the target, adapted to the current schemas as a partition
+//! move does, and copies the data directory: schemas, rules, sequences,
+//! statistics and vector indexes. Writes to the source are blocked while
+//! it runs. Until it finishes, the target's data directory holds a marker;
+//! a copy interrupted by a crash is discarded on the next start, so the
+//! target appears whole or not at all.
+//!
+//! A rename is a copy followed by a drop of the source, with writes to the
+//! source blocked from the start of the copy until it is gone. A crash in
+//! between leaves both knowledge graphs, never neither.
+//!
+//! Rules that read a knowledge graph from another one (`kg.relation`, see
+//! [`super::qualified`]) stop a drop or rename of it; a cascading drop
+//! drops those rules first.
+
+use super::partition::{read_adapted, MOVE_MARKER};
+use super::qualified::reads_knowledge_graph;
+use super::replication::ReplicationOp;
+use super::{KgDropCleanup, StorageEngine};
+use crate::schema::partition::base_relation;
+use crate::schema::SchemaCatalog;
+use crate::storage::persist::PersistBackend;
+use crate::storage::{StorageError, StorageResult};
+use dashmap::mapref::entry::Entry;
+use parking_lot::RwLock;
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::info;
+
+/// Present in the data directory of a knowledge graph being copied into
+pub(super) const COPY_MARKER: &str = "copy_in_progress";
+
+/// Whether `data_dir` belongs to a copy that did not finish
+pub(super) fn is_partial_copy(data_dir: &Path) -> bool {
+    data_dir.join(COPY_MARKER).exists()
+}
+
+/// Whether a data directory entry marks an unfinished operation
+pub(super) fn is_marker(name: &OsStr) -> bool {
+    name == MOVE_MARKER || name == COPY_MARKER
+}
+
+/// Copy the files of a data directory, leaving out markers of unfinished
+/// operations
+fn copy_dir(from: &Path, to: &Path) -> StorageResult<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if is_marker(&name) {
+            continue;
+        }
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            copy_dir(&path, &to.join(&name))?;
+        } else {
+            fs::copy(&path, to.join(&name))?;
+        }
+    }
+    Ok(())
+}
+
+impl StorageEngine {
+    /// Copy a knowledge graph, with its data, schemas and rules, to a new
+    /// knowledge graph `target`
+    pub fn copy_knowledge_graph(&self, source: &str, target: &str) -> StorageResult<()> {
+        let start = Instant::now();
+        let db = self
+            .knowledge_graphs
+            .get(source)
+            .map(|db| Arc::clone(db.value()))
+            .ok_or_else(|| StorageError::KnowledgeGraphNotFound(source.to_string()))?;
+        {
+            let db = db.read();
+            let dropping = self.dropping_kgs.write();
+            self.copy_blocked(source, target, &db.data_dir, &db.schema_catalog, &dropping)?;
+            self.record_change(target, || ReplicationOp::CopyKnowledgeGraph {
+                source: source.to_string(),
+            });
+        }
+        self.save_knowledge_graphs_metadata()?;
+        info!(
+            source,
+            target,
+            elapsed_ms = start.elapsed().as_millis() as u64,
+            "kg_copy_complete"
+        );
+        Ok(())
+    }
+
+    /// Rename a knowledge graph. The default and current knowledge graphs
+    /// cannot be renamed.
+    pub fn rename_knowledge_graph(&self, source: &str, target: &str) -> StorageResult<()> {
+        let start = Instant::now();
+        if source == self.config.storage.default_knowledge_graph {
+            return Err(StorageError::Other(
+                "Cannot rename the default knowledge graph".to_string(),
+            ));
+        }
+        if self.current_kg.as_deref() == Some(source) {
+            return Err(StorageError::Other(
+                "Cannot rename the current knowledge graph".to_string(),
+            ));
+        }
+        let db = self
+            .knowledge_graphs
+            .get(source)
+            .map(|db| Arc::clone(db.value()))
+            .ok_or_else(|| StorageError::KnowledgeGraphNotFound(source.to_string()))?;
+        self.drop_dependent_rules(source, false)?;
+        {
+            let db = db.read();
+            let mut dropping = self.dropping_kgs.write();
+            self.copy_blocked(source, target, &db.data_dir, &db.schema_catalog, &dropping)?;
+            // Writes to the source stay blocked until it is gone
+            dropping.insert(source.to_string());
+            self.knowledge_graphs.remove(source);
+            self.record_change(target, || ReplicationOp::RenameKnowledgeGraph {
+                source: source.to_string(),
+            });
+        }
+        self.save_knowledge_graphs_metadata()?;
+        self.finish_drop_knowledge_graph(KgDropCleanup {
+            name: source.to_string(),
+            data_dir: self.config.storage.data_dir.join(source),
+            persist: Arc::clone(&self.persist),
+            dropped_rules: Vec::new(),
+        });
+        info!(
+            source,
+            target,
+            elapsed_ms = start.elapsed().as_millis() as u64,
+            "kg_rename_complete"
+        );
+        Ok(())
+    }
+
+    /// Copy `source` to a new knowledge graph `target` and register it.
+    /// The caller holds the source's lock and the `dropping_kgs` write
+    /// lock, so nothing is written to the source meanwhile. On failure
+    /// everything written for the target is removed.
+    fn copy_blocked(
+        &self,
+        source: &str,
+        target: &str,
+        source_dir: &Path,
+        catalog: &SchemaCatalog,
+        dropping: &HashSet<String>,
+    ) -> StorageResult<()> {
+        self.check_new_kg_name(target)?;
+        if dropping.contains(source) {
+            return Err(StorageError::KnowledgeGraphNotFound(source.to_string()));
+        }
+        if dropping.contains(target) {
+            return Err(StorageError::Other(format!(
+                "Knowledge graph '{target}' is being dropped, cannot create"
+            )));
+        }
+        if self.knowledge_graphs.contains_key(target) {
+            return Err(StorageError::KnowledgeGraphExists(target.to_string()));
+        }
+
+        let target_dir = self.config.storage.data_dir.join(target);
+        let source_prefix = format!("{source}:");
+        let copied = (|| -> StorageResult<usize> {
+            fs::create_dir_all(&target_dir)?;
+            fs::write(target_dir.join(COPY_MARKER), source)?;
+            copy_dir(source_dir, &target_dir)?;
+
+            let mut updates = 0;
+            for shard in self.persist.list_shards()? {
+                let Some(suffix) = shard.strip_prefix(&source_prefix) else {
+                    continue;
+                };
+                let relation = base_relation(suffix);
+                let history = read_adapted(&self.persist, &shard, relation, catalog)?;
+                let target_shard = format!("{target}:{suffix}");
+                self.persist
+                    .set_schema_version(&target_shard, catalog.schema_version(relation))?;
+                if !history.is_empty() {
+                    self.persist.append(&target_shard, &history)?;
+                    self.persist.flush(&target_shard)?;
+                }
+                updates += history.len();
+            }
+            self.persist.sync()?;
+
+            let kg = self.load_knowledge_graph_from_persist(target, target_dir.clone())?;
+            fs::remove_file(target_dir.join(COPY_MARKER))?;
+            match self.knowledge_graphs.entry(target.to_string()) {
+                Entry::Occupied(_) => Err(StorageError::KnowledgeGraphExists(target.to_string())),
+                Entry::Vacant(vacant) => {
+                    // Logged under the entry lock, so `save_all` syncs the
+                    // copied catalog files before dropping the records
+                    kg.log_catalogs()?;
+                    vacant.insert(Arc::new(RwLock::new(kg)));
+                    Ok(updates)
+                }
+            }
+        })();
+
+        match copied {
+            Ok(updates) => {
+                info!(source, target, updates, "kg_copied");
+                Ok(())
+            }
+            Err(e) => {
+                let target_prefix = format!("{target}:");
+                if let Ok(shards) = self.persist.list_shards() {
+                    for shard in shards.iter().filter(|s| s.starts_with(&target_prefix)) {
+                        let _ = self.persist.delete_shard(shard);
+                    }
+                }
+                let _ = fs::remove_dir_all(&target_dir);
+                Err(e)
+            }
+        }
+    }
+
+    /// Rules of other knowledge graphs that read `kg`, as
+    /// `(knowledge graph, rule)` pairs
+    fn dependent_rules(&self, kg: &str) -> Vec<(String, String)> {
+        let mut found = Vec::new();
+        for entry in &self.knowledge_graphs {
+            if entry.key() == kg {
+                continue;
+            }
+            let db = entry.value().read();
+            let mut rules: Vec<String> = db
+                .rule_catalog
+                .all_rules()
+                .iter()
+                .filter(|rule| reads_knowledge_graph(rule, kg))
+                .map(|rule| rule.head.relation.clone())
+                .collect();
+            rules.sort();
+            rules.dedup();
+            found.extend(rules.into_iter().map(|rule| (entry.key().clone(), rule)));
+        }
+        found.sort();
+        found
+    }
+
+    /// Check for rules of other knowledge graphs that read `kg`. Without
+    /// `cascade` any such rule is an error; with it they are dropped.
+    /// Returns the dropped rules (`kg.rule`).
+    pub(super) fn drop_dependent_rules(
+        &self,
+        kg: &str,
+        cascade: bool,
+    ) -> StorageResult<Vec<String>> {
+        let dependents = self.dependent_rules(kg);
+        let names: Vec<String> = dependents
+            .iter()
+            .map(|(other, rule)| format!("{other}.{rule}"))
+            .collect();
+        if names.is_empty() {
+            return Ok(names);
+        }
+        if !cascade {
+            return Err(StorageError::Other(format!(
+                "Knowledge graph '{kg}' is read by rules {}; drop them first or drop with cascade",
+                names.join(", ")
+            )));
+        }
+        for (other, rule) in &dependents {
+            self.drop_rule_in(other, rule)?;
+            info!(kg = %other, rule = %rule, dropped_with = kg, "dependent_rule_dropped");
+        }
+        Ok(names)
+    }
+}
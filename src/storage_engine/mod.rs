@@ -30,21 +30,65 @@
 //! storage.save_knowledge_graph("analytics").unwrap();
 //! ```
 
+mod batch;
+mod cdc;
+mod copy;
+mod ddl;
+mod introspect;
+mod lifecycle;
+mod partition;
+mod pushdown;
+mod qualified;
+mod replication;
+mod residency;
+mod restore;
+mod retention;
+mod sequence;
 mod snapshot;
+mod transaction;
+mod views;
+mod wal_sync;
+pub use batch::{BatchReport, WriteOp};
+use batch::{PendingWrite, WriteKind};
+pub use cdc::{ChangeEvent, ChangeFeed};
+pub use copy::{CopyFormat, CopyOptions};
+pub use introspect::IntrospectionTable;
+pub use replication::{
+    ImageFile, KnowledgeGraphImage, ReplicatedWrite, ReplicationLog, ReplicationOp,
+    ReplicationRecord, StatementGuard,
+};
+use residency::Residency;
+pub use restore::RestoreSummary;
+use sequence::SequenceCatalog;
 pub use snapshot::KnowledgeGraphSnapshot;
+pub use transaction::{CommitReport, RuleChange, Transaction};
+use views::ViewCache;
+use wal_sync::WalSyncer;
 
-use crate::config::Config;
+use crate::config::{Config, DurabilityMode, ReplicationRole};
 use crate::derived_relations::CompiledRule;
-use crate::incremental::IncrementalEngine;
+use crate::execution::SubplanCache;
+use crate::incremental::{IncrementalEngine, ViewSubscription};
+use crate::index_manager::{IndexManager, IndexType, RegisteredIndex};
 use crate::rule_catalog::RuleCatalog;
-use crate::schema::{RelationSchema, SchemaCatalog, ValidationEngine};
+use crate::schema::validator::{KeyConflicts, ScreenedBatch};
+use crate::schema::{
+    vector_dims, ConflictAction, FailureAction, RelationSchema, SchemaCatalog, SchemaMigration,
+    SchemaType, ValidationEngine, ValidationError, ValidationPolicy, VectorDims, Violation,
+};
 use crate::statement::{RuleDef, SerializableBodyPred};
+use crate::statistics::{RelationStats, StatisticsManager, StatsConfig};
 use crate::storage::persist::{
-    consolidate_to_current, to_tuples, FilePersist, PersistBackend, PersistConfig, Update,
+    consolidate_to_current, encryption, to_tuples, Catalog, DdlChange, DdlLog, Encryption,
+    FileKind, FilePersist, PersistBackend, PersistConfig, Update,
 };
 use crate::storage::{
-    KnowledgeGraphMetadata, KnowledgeGraphsMetadata, StorageError, StorageResult,
+    infer_csv_schema, load_csv_parallel, load_from_avro, load_from_csv_inferred, load_from_jsonl,
+    read_sqlite_batches, save_to_avro, save_to_jsonl, sqlite_table_schema, BulkLoadOptions,
+    BulkLoadProgress, BulkLoadReport, CsvInference, CsvOptions, JsonlOptions,
+    KnowledgeGraphMetadata, KnowledgeGraphsMetadata, SqliteOptions, StorageError, StorageResult,
 };
+use crate::value::coercion::CoercionMode;
 use crate::value::Tuple;
 use crate::IQLEngine;
 use arc_swap::ArcSwap;
@@ -52,13 +96,31 @@ use chrono::Utc;
 use dashmap::DashMap;
 use parking_lot::RwLock;
 use rayon::prelude::*;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
-use tracing::info;
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+/// File in a knowledge graph's directory holding its saved subplan cache
+const SUBPLAN_CACHE_FILE: &str = "subplan_cache.bin";
+
+/// Outcome of a validated insert
+#[derive(Debug, Clone, Default)]
+pub struct InsertReport {
+    /// Tuples newly added to the relation
+    pub inserted: usize,
+    /// Tuples that were already present
+    pub duplicates: usize,
+    /// Tuples diverted to the quarantine relation
+    pub quarantined: usize,
+    /// Existing tuples replaced because they shared a key (upsert)
+    pub replaced: usize,
+    /// Check constraint violations tolerated by the validation policy
+    pub violations: Vec<Violation>,
+}
 
 /// Cleanup token returned by Phase 1 of KG drop.
 /// Carries the data needed for Phase 2 (slow file I/O cleanup).
@@ -69,6 +131,9 @@ pub struct KgDropCleanup {
     data_dir: PathBuf,
     /// Reference to the persist backend for shard cleanup
     persist: Arc<FilePersist>,
+    /// Rules of other knowledge graphs that read this one, dropped with
+    /// it by a cascading drop (`kg.rule`)
+    pub dropped_rules: Vec<String>,
 }
 
 /// Storage Engine - manages multiple knowledge graphs
@@ -79,12 +144,19 @@ pub struct StorageEngine {
     /// Knowledge graphs with lock-free concurrent access
     knowledge_graphs: DashMap<String, Arc<RwLock<KnowledgeGraph>>>,
     current_kg: Option<String>,
+    /// Syncs the WAL tail in `batched` mode; declared before `persist` so
+    /// it stops before the backend is dropped
+    _wal_syncer: Option<WalSyncer>,
     /// DD-native persist backend
     persist: Arc<FilePersist>,
     /// Logical timestamp for DD updates (monotonically increasing)
     logical_time: AtomicU64,
     /// KG names pending async cleanup - prevents same-name recreation and blocks persist writes
     dropping_kgs: parking_lot::RwLock<HashSet<String>>,
+    /// Committed changes for followers (leaders and followers only)
+    replication: Option<Arc<ReplicationLog>>,
+    /// Committed changes for downstream systems (`[cdc]` only)
+    cdc: Option<Arc<ChangeFeed>>,
 }
 
 /// Single knowledge graph instance
@@ -98,8 +170,15 @@ pub struct KnowledgeGraph {
     rule_catalog: RuleCatalog,
     /// Schema catalog for relation type definitions (per-KG isolation)
     schema_catalog: SchemaCatalog,
+    /// ID sequences for `@auto` columns (locked independently so inserts
+    /// can assign IDs under the knowledge graph's read lock)
+    sequences: parking_lot::Mutex<SequenceCatalog>,
+    /// Column statistics collected by `analyze`, consulted by the planner
+    statistics: StatisticsManager,
     /// Current snapshot for lock-free reads (updated atomically on writes)
     snapshot: ArcSwap<KnowledgeGraphSnapshot>,
+    /// Logical time of the last write applied in memory
+    lsn: u64,
     /// Persistent DD computation for incremental updates (shadow writes)
     incremental: Option<IncrementalEngine>,
     /// Number of workers for parallel query execution
@@ -108,6 +187,23 @@ pub struct KnowledgeGraph {
     max_result_rows: usize,
     /// Maximum query cost score (0 = unlimited)
     max_query_cost: u64,
+    /// Maximum bytes a query may materialize (0 = unlimited)
+    max_query_memory_bytes: usize,
+    /// Wall-clock limit per query in milliseconds (0 = unlimited)
+    query_timeout_ms: u64,
+    /// Implicit type coercion on insert and in joins
+    coercion: CoercionMode,
+    /// Which relations are in memory
+    residency: Residency,
+    /// Cached results of views with a refresh policy
+    views: parking_lot::Mutex<ViewCache>,
+    /// Materialized subplans, shared by the queries of every snapshot
+    subplan_cache: Arc<SubplanCache>,
+    /// LSN of the last write to each base relation, which with its schema
+    /// version keys the relation's entries in `subplan_cache`
+    relation_lsns: HashMap<String, u64>,
+    /// WAL catalog changes are logged to before their files are rewritten
+    ddl_log: Option<DdlLog>,
 }
 
 impl StorageEngine {
@@ -132,16 +228,60 @@ impl StorageEngine {
             buffer_size: config.storage.persist.buffer_size,
             durability_mode: config.storage.persist.durability_mode,
             max_wal_size_bytes: config.storage.persist.max_wal_size_bytes,
+            wal_segment_size_bytes: config.storage.persist.wal_segment_size_bytes,
+            wal_sync_interval_ms: config.storage.persist.wal_sync_interval_ms,
+            on_corruption: config.storage.persist.on_corruption,
+            wal_archive: config.storage.persist.wal_archive,
+            wal_archive_retention_secs: config.storage.persist.wal_archive_retention_secs,
+            object_store: config
+                .storage
+                .persist
+                .object_store
+                .as_ref()
+                .map(crate::storage::open_object_store)
+                .transpose()?,
+            encryption: Encryption::from_config(&config.storage.persist.encryption)?.map(Arc::new),
         };
         let persist = Arc::new(FilePersist::new(persist_config)?);
+        log_recovery_report(persist.recovery_report());
+        let sync_interval = config.storage.persist.wal_sync_interval_ms;
+        let wal_syncer = if config.storage.persist.durability_mode == DurabilityMode::Batched
+            && sync_interval > 0
+        {
+            Some(WalSyncer::start(
+                &persist,
+                Duration::from_millis(sync_interval),
+            )?)
+        } else {
+            None
+        };
+        // Cluster members follow until elected
+        let clustered = config.cluster.is_enabled();
+        let replication =
+            (config.replication.role != ReplicationRole::None || clustered).then(|| {
+                let log = ReplicationLog::new(config.replication.log_max_bytes);
+                log.set_following(
+                    config.replication.role == ReplicationRole::Follower || clustered,
+                );
+                Arc::new(log)
+            });
+        let cdc = if config.cdc.enabled {
+            let feed = ChangeFeed::open(&config.cdc, &config.storage.data_dir)?;
+            Some(Arc::new(feed))
+        } else {
+            None
+        };
 
         let mut engine = StorageEngine {
             config,
             knowledge_graphs: DashMap::new(),
             current_kg: None,
+            _wal_syncer: wal_syncer,
             persist,
             logical_time: AtomicU64::new(1),
             dropping_kgs: parking_lot::RwLock::new(HashSet::new()),
+            replication,
+            cdc,
         };
 
         // Load existing knowledge graphs from persist layer
@@ -165,33 +305,7 @@ impl StorageEngine {
     /// Create a new knowledge graph
     pub fn create_knowledge_graph(&self, name: &str) -> StorageResult<()> {
         let start = Instant::now();
-        // Validate knowledge graph name
-        if name.is_empty()
-            || name.contains('/')
-            || name.contains('\\')
-            || name.contains('\0')
-            || name.contains("..")
-            || name == "."
-        {
-            return Err(StorageError::InvalidRelationName(name.to_string()));
-        }
-
-        // Validate name length to prevent filesystem PATH_MAX failures
-        if name.len() > Self::MAX_KG_NAME_BYTES {
-            return Err(StorageError::InvalidRelationName(format!(
-                "Knowledge graph name too long: {} bytes (max {})",
-                name.len(),
-                Self::MAX_KG_NAME_BYTES
-            )));
-        }
-
-        // Check max knowledge graph limit
-        let max_kgs = self.config.storage.max_knowledge_graphs;
-        if max_kgs > 0 && self.knowledge_graphs.len() >= max_kgs {
-            return Err(StorageError::Other(format!(
-                "Maximum number of knowledge graphs ({max_kgs}) exceeded"
-            )));
-        }
+        self.check_new_kg_name(name)?;
 
         // Block creation if a same-name KG is being dropped (prevents RC-2)
         if self.dropping_kgs.read().contains(name) {
@@ -219,7 +333,14 @@ impl StorageEngine {
                     KnowledgeGraph::new_with_workers(name.to_string(), db_dir, num_workers);
                 kg.max_result_rows = self.config.storage.performance.max_result_rows;
                 kg.max_query_cost = self.config.storage.performance.max_query_cost;
-
+                kg.max_query_memory_bytes = self.config.storage.performance.max_query_memory_bytes;
+                kg.query_timeout_ms = self.config.storage.performance.query_timeout_ms;
+                kg.coercion = self.config.storage.coercion;
+                kg.residency = self.new_residency();
+                kg.set_ddl_log(self.ddl_log(name));
+
+                // Recorded before the knowledge graph can be written to
+                self.record_change(name, || ReplicationOp::CreateKnowledgeGraph);
                 vacant.insert(Arc::new(RwLock::new(kg)));
             }
         }
@@ -233,10 +354,56 @@ impl StorageEngine {
         Ok(())
     }
 
+    /// Check that `name` can name a new knowledge graph and that the
+    /// knowledge graph limit leaves room for it
+    fn check_new_kg_name(&self, name: &str) -> StorageResult<()> {
+        // Validate knowledge graph name
+        if name.is_empty()
+            || name.contains('/')
+            || name.contains('\\')
+            || name.contains('\0')
+            || name.contains("..")
+            || name == "."
+        {
+            return Err(StorageError::InvalidRelationName(name.to_string()));
+        }
+
+        // Validate name length to prevent filesystem PATH_MAX failures
+        if name.len() > Self::MAX_KG_NAME_BYTES {
+            return Err(StorageError::InvalidRelationName(format!(
+                "Knowledge graph name too long: {} bytes (max {})",
+                name.len(),
+                Self::MAX_KG_NAME_BYTES
+            )));
+        }
+
+        // Check max knowledge graph limit
+        let max_kgs = self.config.storage.max_knowledge_graphs;
+        if max_kgs > 0 && self.knowledge_graphs.len() >= max_kgs {
+            return Err(StorageError::Other(format!(
+                "Maximum number of knowledge graphs ({max_kgs}) exceeded"
+            )));
+        }
+        Ok(())
+    }
+
     /// Phase 1 of KG drop: Fast in-memory removal (~microseconds).
     /// Returns a `KgDropCleanup` token for Phase 2.
     /// Uses interior mutability (DashMap + RwLock) so only needs `&self`.
+    ///
+    /// Fails if rules of other knowledge graphs read this one; see
+    /// `prepare_drop_knowledge_graph_cascade`.
     pub fn prepare_drop_knowledge_graph(&self, name: &str) -> StorageResult<KgDropCleanup> {
+        self.prepare_drop(name, false)
+    }
+
+    /// Phase 1 of a cascading KG drop: rules of other knowledge graphs
+    /// that read this one are dropped first
+    pub fn prepare_drop_knowledge_graph_cascade(&self, name: &str) -> StorageResult<KgDropCleanup> {
+        self.prepare_drop(name, true)
+    }
+
+    fn prepare_drop(&self, name: &str, cascade: bool) -> StorageResult<KgDropCleanup> {
         let start = Instant::now();
         // Cannot drop default knowledge graph
         if name == self.config.storage.default_knowledge_graph {
@@ -254,12 +421,14 @@ impl StorageEngine {
         if !self.knowledge_graphs.contains_key(name) {
             return Err(StorageError::KnowledgeGraphNotFound(name.to_string()));
         }
+        let dropped_rules = self.drop_dependent_rules(name, cascade)?;
 
         // Add to tombstone BEFORE removing from DashMap (ordering matters for RC-2)
         self.dropping_kgs.write().insert(name.to_string());
 
         // Remove from in-memory DashMap (instant)
         self.knowledge_graphs.remove(name);
+        self.record_change(name, || ReplicationOp::DropKnowledgeGraph { cascade });
 
         // Save metadata JSON (small file write, fast)
         self.save_knowledge_graphs_metadata()?;
@@ -271,6 +440,7 @@ impl StorageEngine {
             name: name.to_string(),
             data_dir: self.config.storage.data_dir.join(name),
             persist: Arc::clone(&self.persist),
+            dropped_rules,
         })
     }
 
@@ -278,6 +448,14 @@ impl StorageEngine {
     /// Deletes persist shards and data directory, then removes tombstone.
     pub fn finish_drop_knowledge_graph(&self, cleanup: KgDropCleanup) {
         let start = Instant::now();
+        // Catalog records logged so far must not revive a later knowledge
+        // graph of the same name
+        if let Err(e) = cleanup
+            .persist
+            .log_ddl(&cleanup.name, DdlChange::DropKnowledgeGraph)
+        {
+            tracing::warn!(kg = %cleanup.name, error = %e, "kg_drop_log_failed");
+        }
         let prefix = format!("{}:", cleanup.name);
         if let Ok(shards) = cleanup.persist.list_shards() {
             for shard in shards.iter().filter(|s| s.starts_with(&prefix)) {
@@ -295,8 +473,14 @@ impl StorageEngine {
         }
         // Remove tombstone - name is now safe to reuse
         self.dropping_kgs.write().remove(&cleanup.name);
+        let purged = crate::value::intern::purge_unused_interned();
         let elapsed_ms = start.elapsed().as_millis() as u64;
-        info!(kg = %cleanup.name, elapsed_ms, "kg_drop_finish_complete");
+        info!(
+            kg = %cleanup.name,
+            elapsed_ms,
+            interned_purged = purged,
+            "kg_drop_finish_complete"
+        );
     }
 
     /// Drop a knowledge graph (delete all data).
@@ -307,6 +491,15 @@ impl StorageEngine {
         Ok(())
     }
 
+    /// Drop a knowledge graph along with the rules of other knowledge
+    /// graphs that read it. Returns the dropped rules (`kg.rule`).
+    pub fn drop_knowledge_graph_cascade(&self, name: &str) -> StorageResult<Vec<String>> {
+        let mut cleanup = self.prepare_drop_knowledge_graph_cascade(name)?;
+        let dropped = std::mem::take(&mut cleanup.dropped_rules);
+        self.finish_drop_knowledge_graph(cleanup);
+        Ok(dropped)
+    }
+
     /// Switch to a different knowledge graph
     pub fn use_knowledge_graph(&mut self, name: &str) -> StorageResult<()> {
         let start = Instant::now();
@@ -336,7 +529,7 @@ impl StorageEngine {
             info!(kg = %name, elapsed_ms, "kg_ensure_exists");
             return Ok(());
         }
-        if self.config.storage.auto_create_knowledge_graphs {
+        if self.config.storage.auto_create_knowledge_graphs && !self.is_replica() {
             let result = self.create_knowledge_graph(name);
             let elapsed_ms = start.elapsed().as_millis() as u64;
             info!(kg = %name, elapsed_ms, "kg_ensure_created");
@@ -415,6 +608,10 @@ impl StorageEngine {
     /// Insert arbitrary-arity tuples into a specific knowledge graph (explicit API)
     /// Returns (`new_count`, `duplicate_count`) for reporting to user
     ///
+    /// Tuples are validated against the relation's schema, if any, under the
+    /// configured `ValidationPolicy`. Use `insert_tuples_checked` to receive
+    /// tolerated violations and quarantine counts.
+    ///
     /// Uses `&self` instead of `&mut self` to enable concurrent writes to different KGs.
     pub fn insert_tuples_into(
         &self,
@@ -422,92 +619,111 @@ impl StorageEngine {
         relation: &str,
         tuples: Vec<Tuple>,
     ) -> StorageResult<(usize, usize)> {
+        let report = self.insert_tuples_checked(kg, relation, tuples)?;
+        Ok((report.inserted, report.duplicates))
+    }
+
+    /// Validate and insert tuples into a specific knowledge graph.
+    ///
+    /// Tuples that omit trailing columns are completed with the column
+    /// defaults declared in the schema before validation.
+    ///
+    /// Under `FailureAction::Reject` any violation fails the insert with
+    /// `StorageError::Validation` and nothing is written. Under `Warn` all
+    /// tuples are written; under `Quarantine` failing tuples are written to
+    /// `<relation>_quarantine` instead. Tolerated violations are returned in
+    /// the report.
+    pub fn insert_tuples_checked(
+        &self,
+        kg: &str,
+        relation: &str,
+        tuples: Vec<Tuple>,
+    ) -> StorageResult<InsertReport> {
         if tuples.is_empty() {
-            return Ok((0, 0));
+            return Ok(InsertReport::default());
         }
+        self.ensure_resident_in(kg, &[relation.to_string()])?;
 
-        // Check if relation is a view (derived relation) - cannot insert into views
-        {
+        let policy = self.config.storage.validation;
+        let (mut screened, conflicts) = {
             let db = self
                 .knowledge_graphs
                 .get(kg)
                 .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
             let db = db.read();
-            if db.rule_exists(relation) {
-                return Err(StorageError::Other(format!(
-                    "Cannot insert into '{relation}': it is a derived relation (view). \
-                     Use a base relation or drop the rule first with '.rule drop {relation}'."
-                )));
-            }
-        }
-
-        // Check arity consistency
-        let new_arity = tuples.first().map_or(0, super::value::Tuple::arity);
+            let tuples = db.fill_defaults(relation, tuples, true)?;
+            let tuples = db.coerce_tuples(relation, tuples);
+            let screened = db.screen_tuples(relation, tuples, &policy)?;
+            let conflicts = db.key_conflicts(relation, &screened.accepted);
+            (screened, conflicts)
+        };
 
-        // Verify all tuples in this batch have the same arity
-        for tuple in &tuples {
-            if tuple.arity() != new_arity {
-                return Err(StorageError::Other(format!(
-                    "Arity mismatch in insert batch: expected {}, got {}",
-                    new_arity,
-                    tuple.arity()
-                )));
+        let mut writes = Vec::new();
+        if !conflicts.is_empty() {
+            match policy.on_conflict {
+                ConflictAction::Reject => {
+                    return Err(ValidationError::BatchRejected {
+                        relation: relation.to_string(),
+                        total_tuples: screened.accepted.len(),
+                        violations: conflicts.violations,
+                    }
+                    .into());
+                }
+                ConflictAction::Upsert => {
+                    // Later tuples win over earlier ones with the same key
+                    for &idx in conflicts.superseded.iter().rev() {
+                        screened.accepted.remove(idx);
+                    }
+                    writes.push(PendingWrite::new(
+                        relation,
+                        conflicts.existing,
+                        WriteKind::Replace,
+                    ));
+                }
             }
         }
 
-        // Check if relation already exists with a different arity
-        if let Some((existing_schema, _)) = self.get_relation_metadata_in(kg, relation)? {
-            let existing_arity = existing_schema.len();
-            if existing_arity != new_arity {
-                return Err(StorageError::Other(format!(
-                    "Arity mismatch for relation '{relation}': existing arity is {existing_arity}, but trying to insert tuples with arity {new_arity}"
-                )));
-            }
+        if !screened.violations.is_empty() {
+            tracing::warn!(
+                kg = %kg,
+                relation = %relation,
+                violations = screened.violations.len(),
+                action = ?policy.on_failure,
+                "check_constraint_violations"
+            );
         }
 
-        // Hold dropping_kgs read guard across the entire persist operation
-        // to prevent a TOCTOU race where a KG drop starts between the check
-        // and the persist call. The read lock allows concurrent inserts but
-        // blocks KG drops from marking the KG as dropping until we finish.
-        let dropping_guard = self.dropping_kgs.read();
-        if dropping_guard.contains(kg) {
-            return Err(StorageError::KnowledgeGraphNotFound(kg.to_string()));
+        // Replaced tuples, quarantined tuples and the insert itself become
+        // visible together
+        let replacing = !writes.is_empty();
+        let quarantined = screened.quarantined.len();
+        if quarantined > 0 {
+            let target = ValidationPolicy::quarantine_relation(relation);
+            writes.push(PendingWrite::new(
+                target,
+                screened.quarantined,
+                WriteKind::Quarantine,
+            ));
         }
-
-        // Generate shard name and logical time
-        let shard = format!("{kg}:{relation}");
-        let time = self.logical_time.fetch_add(1, Ordering::SeqCst);
-
-        // Create DD-style updates (+1 diff for insert)
-        let updates: Vec<Update> = tuples
-            .iter()
-            .map(|data| Update::insert(data.clone(), time))
-            .collect();
-
-        // Persist first (durability guarantee via WAL + batches)
-        let persist_start = Instant::now();
-        self.persist.ensure_shard(&shard)?;
-        self.persist.append(&shard, &updates)?;
-        let persist_ms = persist_start.elapsed().as_millis() as u64;
-        info!(
-            kg = %kg,
-            relation = %relation,
-            tuples = updates.len(),
-            persist_ms,
-            "persist_append_complete"
-        );
-
-        // Release dropping_kgs guard before acquiring KG write lock
-        drop(dropping_guard);
-
-        // Update in-memory state
-        let db = self
-            .knowledge_graphs
-            .get(kg)
-            .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
-
-        let mut db = db.write();
-        db.insert_in_memory(relation, tuples, time)
+        writes.push(PendingWrite::new(
+            relation,
+            screened.accepted,
+            WriteKind::Insert,
+        ));
+        let (_, counts) = self.commit_writes(kg, writes)?;
+        let replaced = match counts.first() {
+            Some(&(replaced, _)) if replacing => replaced,
+            _ => 0,
+        };
+        let (inserted, duplicates) = counts.last().copied().unwrap_or_default();
+
+        Ok(InsertReport {
+            inserted,
+            duplicates,
+            quarantined,
+            replaced,
+            violations: screened.violations,
+        })
     }
 
     /// Delete binary tuples from a relation in the current knowledge graph
@@ -568,6 +784,10 @@ impl StorageEngine {
     /// This is the production API that supports arbitrary-arity tuples.
     /// Returns the count of actually deleted tuples.
     ///
+    /// Tuples that do not fit the relation's schema (wrong arity or type)
+    /// are rejected; check constraints are not applied since removing a
+    /// tuple cannot violate them.
+    ///
     /// Uses `&self` instead of `&mut self` to enable concurrent writes to different KGs.
     pub fn delete_tuples_from(
         &self,
@@ -579,37 +799,25 @@ impl StorageEngine {
             return Ok(0);
         }
 
-        // Hold dropping_kgs read guard across the persist operation (same as insert)
-        let dropping_guard = self.dropping_kgs.read();
-        if dropping_guard.contains(kg) {
-            return Err(StorageError::KnowledgeGraphNotFound(kg.to_string()));
-        }
-
-        // Generate shard name and logical time
-        let shard = format!("{kg}:{relation}");
-        let time = self.logical_time.fetch_add(1, Ordering::SeqCst);
-
-        // Create DD-style updates (-1 diff for delete)
-        let updates: Vec<Update> = tuples
-            .iter()
-            .map(|data| Update::delete(data.clone(), time))
-            .collect();
-
-        // Persist first (durability guarantee via WAL + batches)
-        self.persist.ensure_shard(&shard)?;
-        self.persist.append(&shard, &updates)?;
-
-        // Release dropping_kgs guard before acquiring KG write lock
-        drop(dropping_guard);
-
-        // Update in-memory state
-        let db = self
-            .knowledge_graphs
-            .get(kg)
-            .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
+        let tuples = {
+            let db = self
+                .knowledge_graphs
+                .get(kg)
+                .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
+            let db = db.read();
+            let structural_only = ValidationPolicy {
+                on_failure: FailureAction::Warn,
+                ..self.config.storage.validation
+            };
+            db.screen_tuples(relation, tuples, &structural_only)?
+                .accepted
+        };
 
-        let mut db = db.write();
-        db.delete_in_memory(relation, &tuples, time)
+        let (_, counts) = self.commit_writes(
+            kg,
+            vec![PendingWrite::new(relation, tuples, WriteKind::Delete)],
+        )?;
+        Ok(counts.first().map_or(0, |&(deleted, _)| deleted))
     }
 
     /// Execute an IQL query on the current knowledge graph
@@ -635,16 +843,8 @@ impl StorageEngine {
     /// Returns binary tuples (i32, i32) for backward compatibility.
     /// For arbitrary arity results, use `execute_query_tuples_on` instead.
     pub fn execute_query_on(&self, kg: &str, program: &str) -> StorageResult<Vec<(i32, i32)>> {
-        let db = self
-            .knowledge_graphs
-            .get(kg)
-            .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
-
         // Get snapshot atomically - O(1), no lock needed
-        let snapshot = {
-            let db_guard = db.read();
-            db_guard.snapshot()
-        };
+        let snapshot = self.get_snapshot_for_program(kg, program)?;
 
         // Execute on snapshot - completely lock-free
         snapshot
@@ -665,16 +865,8 @@ impl StorageEngine {
 
     /// Execute an IQL query on a specific knowledge graph, returning arbitrary arity tuples
     pub fn execute_query_tuples_on(&self, kg: &str, program: &str) -> StorageResult<Vec<Tuple>> {
-        let db = self
-            .knowledge_graphs
-            .get(kg)
-            .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
-
         // Get snapshot atomically - O(1), no lock needed
-        let snapshot = {
-            let db_guard = db.read();
-            db_guard.snapshot()
-        };
+        let snapshot = self.get_snapshot_for_program(kg, program)?;
 
         // Execute on snapshot - completely lock-free
         snapshot
@@ -690,15 +882,7 @@ impl StorageEngine {
         kg: &str,
         program: &str,
     ) -> StorageResult<crate::pipeline_trace::PipelineTrace> {
-        let db = self
-            .knowledge_graphs
-            .get(kg)
-            .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
-
-        let snapshot = {
-            let db_guard = db.read();
-            db_guard.snapshot()
-        };
+        let snapshot = self.get_snapshot_for_program(kg, program)?;
 
         // Prepend persistent rules (same as execute_with_rules) so the debug
         // plan matches actual execution behavior.
@@ -731,31 +915,30 @@ impl StorageEngine {
         std::collections::HashMap<String, Vec<Tuple>>, // derived relation data
         std::collections::HashMap<String, String>,     // index_name -> metric
     )> {
+        let snapshot = self.get_snapshot_for_program(kg, program)?;
         let db = self
             .knowledge_graphs
             .get(kg)
             .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
-
-        let (snapshot, index_metrics) = {
+        let index_metrics: std::collections::HashMap<String, String> = {
             let db_guard = db.read();
-            let snap = db_guard.snapshot();
             // Collect index metric info for HNSW proof enrichment
-            let metrics: std::collections::HashMap<String, String> =
-                if let Some(dd) = db_guard.incremental() {
-                    let idx_mgr = dd.index_manager();
-                    let guard = idx_mgr.lock();
-                    guard
-                        .registered_indexes()
-                        .iter()
-                        .map(|(name, idx)| {
-                            let crate::index_manager::IndexType::Hnsw(ref cfg) = idx.index_type;
-                            (name.clone(), format!("{:?}", cfg.metric).to_lowercase())
-                        })
-                        .collect()
-                } else {
-                    std::collections::HashMap::new()
-                };
-            (snap, metrics)
+            if let Some(dd) = db_guard.incremental() {
+                let idx_mgr = dd.index_manager();
+                let guard = idx_mgr.lock();
+                guard
+                    .registered_indexes()
+                    .iter()
+                    .filter_map(|(name, idx)| match idx.index_type {
+                        IndexType::Hnsw(ref cfg) => {
+                            Some((name.clone(), format!("{:?}", cfg.metric).to_lowercase()))
+                        }
+                        IndexType::Lsh(_) => None,
+                    })
+                    .collect()
+            } else {
+                std::collections::HashMap::new()
+            }
         };
 
         let (result_tuples, derived_data) = snapshot
@@ -798,13 +981,17 @@ impl StorageEngine {
             return Err(StorageError::KnowledgeGraphNotFound(name.to_string()));
         }
 
-        // Flush all shards for this knowledge graph
+        // Flush only the shards with unwritten updates; each gets one
+        // incremental batch file
         let prefix = format!("{name}:");
-        for shard_name in self.persist.list_shards()? {
-            if shard_name.starts_with(&prefix) {
-                self.persist.flush(&shard_name)?;
-            }
-        }
+        let dirty: Vec<String> = self
+            .persist
+            .dirty_shards()
+            .into_iter()
+            .filter(|shard| shard.starts_with(&prefix))
+            .collect();
+        let written = self.persist.flush_shards(&dirty)?;
+        debug!(kg = name, shards_written = written, "save_knowledge_graph");
 
         // Sync to disk
         self.persist.sync()?;
@@ -815,10 +1002,9 @@ impl StorageEngine {
             if let Some(ref dd) = kg.incremental {
                 let idx_mgr = dd.index_manager();
                 let idx_guard = idx_mgr.lock();
-                if idx_guard.index_count() > 0 {
-                    if let Err(e) = idx_guard.save_indexes(&kg.data_dir) {
-                        tracing::warn!(kg = name, error = %e, "failed_to_save_indexes");
-                    }
+                // Also saves the removal of dropped indexes
+                if let Err(e) = idx_guard.save_indexes_at(&kg.data_dir, kg.lsn) {
+                    tracing::warn!(kg = name, error = %e, "failed_to_save_indexes");
                 }
             }
         }
@@ -875,21 +1061,84 @@ impl StorageEngine {
         Ok(compacted)
     }
 
+    /// Sync buffered WAL entries that are due in `batched` durability mode
+    /// (see [`FilePersist::sync_wal_if_due`])
+    pub fn sync_wal_if_due(&self) -> StorageResult<bool> {
+        self.persist.sync_wal_if_due()
+    }
+
+    /// Re-encrypt persist files not sealed with the current key (see
+    /// [`FilePersist::rotate_encryption`]). Returns the number of batch
+    /// files rewritten.
+    pub fn rotate_encryption(&self) -> StorageResult<usize> {
+        self.persist.rotate_encryption()
+    }
+
     /// Flush all buffers to disk without full compaction (legacy compatibility)
     pub fn save_all(&self) -> StorageResult<()> {
-        // Flush all shards
-        for shard_name in self.persist.list_shards()? {
-            self.persist.flush(&shard_name)?;
+        // Flush all shards to Parquet and truncate the WAL
+        self.persist.checkpoint()?;
+        self.checkpoint_catalogs()?;
+
+        self.save_knowledge_graphs_metadata()?;
+
+        // Best effort: a lost cache only costs recomputation
+        if let Err(e) = self.save_subplan_caches() {
+            tracing::warn!(error = %e, "subplan_cache_save_failed");
         }
 
-        // Sync to disk
-        self.persist.sync()?;
+        if let Some(feed) = &self.cdc {
+            feed.flush();
+        }
 
-        self.save_knowledge_graphs_metadata()?;
+        Ok(())
+    }
 
+    /// Write the subplan cache of every knowledge graph, so results warmed
+    /// before a restart are still cached after it. Their keys hold the
+    /// versions of the relations read, so entries for data or schemas
+    /// changed in between are never served.
+    fn save_subplan_caches(&self) -> StorageResult<()> {
+        for entry in &self.knowledge_graphs {
+            let kg = entry.value().read();
+            if kg.subplan_cache.is_empty() {
+                continue;
+            }
+            let bytes = kg.subplan_cache.to_bytes().map_err(StorageError::Other)?;
+            let path = kg.data_dir.join(SUBPLAN_CACHE_FILE);
+            let bytes = encryption::seal(
+                bytes,
+                self.persist.encryption(),
+                FileKind::SubplanCache,
+                &path,
+            )?;
+            fs::write(path, bytes)?;
+        }
         Ok(())
     }
 
+    /// Load the subplan cache `save_all` wrote for a knowledge graph. The
+    /// file is removed once read, so a later unclean shutdown starts with
+    /// an empty cache rather than an old one.
+    fn load_subplan_cache(&self, name: &str, data_dir: &Path) -> SubplanCache {
+        let cache = SubplanCache::new();
+        let path = data_dir.join(SUBPLAN_CACHE_FILE);
+        if !path.exists() {
+            return cache;
+        }
+        let loaded =
+            encryption::read_file(&path, self.persist.encryption(), FileKind::SubplanCache)
+                .and_then(|bytes| cache.load_bytes(&bytes).map_err(StorageError::Other));
+        match loaded {
+            Ok(entries) => info!(kg = %name, entries, "subplan_cache_loaded"),
+            Err(e) => tracing::warn!(kg = %name, error = %e, "subplan_cache_load_failed"),
+        }
+        if let Err(e) = fs::remove_file(&path) {
+            tracing::warn!(kg = %name, error = %e, "subplan_cache_remove_failed");
+        }
+        cache
+    }
+
     // Rule Management (Persistent Derived Relations)
     /// Register a persistent rule in the current knowledge graph
     pub fn register_rule(
@@ -961,11 +1210,16 @@ impl StorageEngine {
         let mut db = db.write();
         db.drop_relation(name)
             .map_err(|e| StorageError::Other(format!("Failed to drop relation: {e}")))?;
+        drop(db);
 
-        // Clean up persist shard (fire-and-forget - WAL + batch files)
-        let shard = format!("{kg}:{name}");
-        let _ = self.persist.delete_shard(&shard);
+        // Clean up persist shards, one per partition if partitioned
+        // (fire-and-forget - WAL + batch files)
+        for shard in self.relation_shards(kg, name).unwrap_or_default() {
+            let _ = self.persist.delete_shard(&shard);
+        }
 
+        // The relation's strings are only freed once unpooled
+        crate::value::intern::purge_unused_interned();
         Ok(())
     }
 
@@ -995,9 +1249,11 @@ impl StorageEngine {
             .get(kg)
             .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
 
-        let time = self.logical_time.fetch_add(1, Ordering::SeqCst);
-
         let mut db = db.write();
+        let evicted = db.residency.evicted_with_prefix(prefix);
+        db.make_resident(&evicted, None)?;
+
+        let time = self.logical_time.fetch_add(1, Ordering::SeqCst);
         db.clear_relations_by_prefix(prefix, time, &self.persist, kg)
     }
 
@@ -1188,7 +1444,10 @@ impl StorageEngine {
             .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
 
         let mut db = db.write();
-        db.register_schema(schema).map_err(StorageError::Other)
+        db.register_schema(schema.clone())
+            .map_err(StorageError::Other)?;
+        self.record_change(kg, || ReplicationOp::Schema { schema });
+        Ok(())
     }
 
     /// Get schema for a relation in the current knowledge graph
@@ -1314,8 +1573,9 @@ impl StorageEngine {
 
     /// Validate tuples against schema in a specific knowledge graph
     ///
-    /// Returns Ok(()) if no schema exists or validation passes.
-    /// Returns Err with message if validation fails.
+    /// Returns Ok(()) if no schema exists or the configured validation
+    /// policy would accept the batch.
+    /// Returns `StorageError::Validation` with the violations otherwise.
     pub fn validate_tuples_in(
         &self,
         kg: &str,
@@ -1328,8 +1588,9 @@ impl StorageEngine {
             .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
 
         let db = db.read();
-        db.validate_tuples(relation, tuples)
-            .map_err(StorageError::Other)
+        let tuples = db.fill_defaults(relation, tuples.to_vec(), false)?;
+        db.screen_tuples(relation, tuples, &self.config.storage.validation)?;
+        Ok(())
     }
 
     /// Register or update a persistent schema in a specific knowledge graph
@@ -1344,36 +1605,401 @@ impl StorageEngine {
             .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
 
         let mut db = db.write();
-        db.register_or_update_schema(schema)
-            .map_err(StorageError::Other)
+        db.register_or_update_schema(schema.clone())
+            .map_err(StorageError::Other)?;
+        self.record_change(kg, || ReplicationOp::Schema { schema });
+        Ok(())
     }
 
-    /// Register or update a session schema in a specific knowledge graph (not persisted)
-    pub fn register_or_update_session_schema_in(
+    /// Import a CSV file into a relation of a specific knowledge graph.
+    ///
+    /// Column types are inferred from a sample of the file (see
+    /// `CsvInference`). The inferred schema is registered unless the
+    /// relation already has one, in which case the rows are validated
+    /// against the existing schema instead. Returns the relation's schema
+    /// and the number of tuples inserted.
+    pub fn import_csv_in(
         &self,
         kg: &str,
-        schema: RelationSchema,
-    ) -> StorageResult<()> {
-        let db = self
-            .knowledge_graphs
-            .get(kg)
-            .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
-
-        let mut db = db.write();
-        db.register_or_update_session_schema(schema)
-            .map_err(StorageError::Other)
+        relation: &str,
+        path: &std::path::Path,
+        options: &CsvOptions,
+        inference: &CsvInference,
+    ) -> StorageResult<(RelationSchema, usize)> {
+        let (inferred, tuples) = load_from_csv_inferred(path, relation, options, inference)?;
+        let schema = match self.get_schema_in(kg, relation)? {
+            Some(existing) => existing,
+            None => {
+                self.register_or_update_schema_in(kg, inferred.clone())?;
+                inferred
+            }
+        };
+        let (inserted, _) = self.insert_tuples_into(kg, relation, tuples)?;
+        Ok((schema, inserted))
     }
 
-    /// Execute a query with rules prepended (current knowledge graph)
+    /// Import a large CSV file into a relation of a specific knowledge
+    /// graph, parsing it in parallel batches that are inserted as they are
+    /// parsed (see `load_csv_parallel`).
     ///
-    /// Returns binary tuples (i32, i32) for backward compatibility.
-    /// For arbitrary arity results, use `execute_query_with_rules_tuples` instead.
-    pub fn execute_query_with_rules(&self, program: &str) -> StorageResult<Vec<(i32, i32)>> {
-        let db_name = self
-            .current_kg
-            .as_ref()
-            .ok_or(StorageError::NoCurrentKnowledgeGraph)?
-            .clone();
+    /// Rows are parsed as the relation's schema or, without one, as a
+    /// schema inferred like [`Self::import_csv_in`] does, which is
+    /// registered. A failure part-way through leaves the earlier batches
+    /// inserted. `on_progress` is called after each batch. Returns the
+    /// relation's schema and the load report.
+    #[allow(clippy::too_many_arguments)]
+    pub fn import_csv_parallel_in(
+        &self,
+        kg: &str,
+        relation: &str,
+        path: &std::path::Path,
+        options: &CsvOptions,
+        inference: &CsvInference,
+        bulk: &BulkLoadOptions,
+        mut on_progress: impl FnMut(&BulkLoadProgress),
+    ) -> StorageResult<(RelationSchema, BulkLoadReport)> {
+        let start = Instant::now();
+        let schema = match self.get_schema_in(kg, relation)? {
+            Some(existing) => existing,
+            None => {
+                let inferred = infer_csv_schema(path, relation, options, inference)?;
+                self.register_or_update_schema_in(kg, inferred.clone())?;
+                inferred
+            }
+        };
+        let report = load_csv_parallel(
+            path,
+            &schema,
+            options,
+            bulk,
+            |batch| self.insert_tuples_into(kg, relation, batch).map(|_| ()),
+            |progress| {
+                info!(
+                    kg,
+                    relation,
+                    bytes_read = progress.bytes_read,
+                    total_bytes = progress.total_bytes,
+                    rows_loaded = progress.rows_loaded,
+                    rows_skipped = progress.rows_skipped,
+                    "csv_bulk_load_progress"
+                );
+                on_progress(progress);
+            },
+        )?;
+        info!(
+            kg,
+            relation,
+            rows_loaded = report.rows_loaded,
+            rows_skipped = report.rows_skipped,
+            batches = report.batches,
+            elapsed_ms = start.elapsed().as_millis() as u64,
+            "csv_bulk_load_complete"
+        );
+        Ok((schema, report))
+    }
+
+    /// Import a JSON Lines file into a relation of a specific knowledge
+    /// graph, reading columns as `options` maps them (see `JsonlOptions`).
+    ///
+    /// Like [`Self::import_csv_in`], the inferred schema is registered
+    /// unless the relation already has one. Returns the relation's schema
+    /// and the number of tuples inserted.
+    pub fn import_jsonl_in(
+        &self,
+        kg: &str,
+        relation: &str,
+        path: &std::path::Path,
+        options: &JsonlOptions,
+    ) -> StorageResult<(RelationSchema, usize)> {
+        let (inferred, tuples) = load_from_jsonl(path, relation, options)?;
+        let schema = match self.get_schema_in(kg, relation)? {
+            Some(existing) => existing,
+            None => {
+                self.register_or_update_schema_in(kg, inferred.clone())?;
+                inferred
+            }
+        };
+        let (inserted, _) = self.insert_tuples_into(kg, relation, tuples)?;
+        Ok((schema, inserted))
+    }
+
+    /// Export a relation of a specific knowledge graph to a JSON Lines
+    /// file, one object per tuple keyed by the schema's column names
+    /// (`col0`, `col1`, ... without a schema). Returns the number of
+    /// tuples written.
+    pub fn export_jsonl_in(
+        &self,
+        kg: &str,
+        relation: &str,
+        path: &std::path::Path,
+    ) -> StorageResult<usize> {
+        self.with_resident(
+            kg,
+            |_| vec![relation.to_string()],
+            |db| {
+                let tuples = db.engine.input_tuples.get(relation).ok_or_else(|| {
+                    StorageError::RelationNotFound(relation.to_string(), kg.to_string())
+                })?;
+                let columns: Vec<String> = match db.schema_catalog.get(relation) {
+                    Some(schema) => schema.columns.iter().map(|c| c.name.clone()).collect(),
+                    None => {
+                        let arity = tuples.first().map_or(0, Tuple::arity);
+                        (0..arity).map(|i| format!("col{i}")).collect()
+                    }
+                };
+                save_to_jsonl(path, &columns, tuples)?;
+                Ok(tuples.len())
+            },
+        )?
+    }
+
+    /// Import an Avro container file into a relation of a specific
+    /// knowledge graph. The file's record schema is mapped to a relation
+    /// schema, which is registered unless the relation already has one.
+    /// Returns the relation's schema and the number of tuples inserted.
+    pub fn import_avro_in(
+        &self,
+        kg: &str,
+        relation: &str,
+        path: &std::path::Path,
+    ) -> StorageResult<(RelationSchema, usize)> {
+        let (mapped, tuples) = load_from_avro(path, relation)?;
+        let schema = match self.get_schema_in(kg, relation)? {
+            Some(existing) => existing,
+            None => {
+                self.register_or_update_schema_in(kg, mapped.clone())?;
+                mapped
+            }
+        };
+        let (inserted, _) = self.insert_tuples_into(kg, relation, tuples)?;
+        Ok((schema, inserted))
+    }
+
+    /// Export a relation with a persistent schema to an Avro container
+    /// file. Returns the number of tuples written.
+    pub fn export_avro_in(
+        &self,
+        kg: &str,
+        relation: &str,
+        path: &std::path::Path,
+    ) -> StorageResult<usize> {
+        self.with_resident(
+            kg,
+            |_| vec![relation.to_string()],
+            |db| {
+                let schema = db.schema_catalog.get(relation).ok_or_else(|| {
+                    StorageError::Other(format!(
+                        "Relation '{relation}' has no schema; Avro export needs one"
+                    ))
+                })?;
+                let tuples = db
+                    .engine
+                    .input_tuples
+                    .get(relation)
+                    .map_or(&[][..], Vec::as_slice);
+                save_to_avro(path, schema, tuples)?;
+                Ok(tuples.len())
+            },
+        )?
+    }
+
+    /// Import tables of a SQLite database file into relations of the
+    /// current knowledge graph, given as (table, relation) pairs.
+    pub fn import_sqlite(
+        &self,
+        path: &std::path::Path,
+        tables: &[(&str, &str)],
+    ) -> StorageResult<Vec<(RelationSchema, usize)>> {
+        let db_name = self
+            .current_kg
+            .as_ref()
+            .ok_or(StorageError::NoCurrentKnowledgeGraph)?
+            .clone();
+
+        self.import_sqlite_in(&db_name, path, tables, &SqliteOptions::default())
+    }
+
+    /// Import tables of a SQLite database file into relations of a
+    /// specific knowledge graph, given as (table, relation) pairs.
+    ///
+    /// Each table's mapped schema is registered unless its relation
+    /// already has one. Rows are inserted in batches of
+    /// `options.batch_size`, so a failure part-way through a table leaves
+    /// the earlier batches inserted. Returns each relation's schema and
+    /// the number of tuples inserted, in the order of `tables`.
+    pub fn import_sqlite_in(
+        &self,
+        kg: &str,
+        path: &std::path::Path,
+        tables: &[(&str, &str)],
+        options: &SqliteOptions,
+    ) -> StorageResult<Vec<(RelationSchema, usize)>> {
+        let mut imported = Vec::with_capacity(tables.len());
+        for &(table, relation) in tables {
+            let schema = match self.get_schema_in(kg, relation)? {
+                Some(existing) => existing,
+                None => {
+                    let mapped = sqlite_table_schema(path, table, relation)?;
+                    self.register_or_update_schema_in(kg, mapped.clone())?;
+                    mapped
+                }
+            };
+            let mut inserted = 0;
+            read_sqlite_batches(path, table, relation, options, |batch| {
+                inserted += self.insert_tuples_into(kg, relation, batch)?.0;
+                Ok(())
+            })?;
+            imported.push((schema, inserted));
+        }
+        Ok(imported)
+    }
+
+    /// Copy a PostgreSQL table or query result into a relation of a
+    /// specific knowledge graph over `COPY`.
+    ///
+    /// Like [`Self::import_sqlite_in`], the mapped schema is registered
+    /// unless the relation already has one, and rows are inserted in
+    /// chunks of `options.chunk_size` as they stream in, so a failed copy
+    /// leaves the earlier chunks inserted. Returns the relation's schema and
+    /// the number of tuples inserted.
+    #[cfg(feature = "postgres")]
+    pub fn import_postgres_in(
+        &self,
+        kg: &str,
+        relation: &str,
+        url: &str,
+        source: &crate::storage::PostgresSource,
+        options: &crate::storage::PostgresOptions,
+    ) -> StorageResult<(RelationSchema, usize)> {
+        let existing = self.get_schema_in(kg, relation)?;
+        let mut registered = existing.is_some();
+        let mut inserted = 0;
+        let mapped =
+            crate::storage::copy_from_postgres(url, source, relation, options, |mapped, chunk| {
+                if !registered {
+                    self.register_or_update_schema_in(kg, mapped.clone())?;
+                    registered = true;
+                }
+                inserted += self.insert_tuples_into(kg, relation, chunk)?.0;
+                Ok(())
+            })?;
+        if !registered {
+            self.register_or_update_schema_in(kg, mapped.clone())?;
+        }
+        Ok((existing.unwrap_or(mapped), inserted))
+    }
+
+    /// Collect statistics for one relation (or every base relation) of a
+    /// specific knowledge graph and persist them.
+    pub fn analyze_in(
+        &self,
+        kg: &str,
+        relation: Option<&str>,
+    ) -> StorageResult<Vec<RelationStats>> {
+        let db = self
+            .knowledge_graphs
+            .get(kg)
+            .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
+
+        let mut db = db.write();
+        match relation {
+            Some(name) => db.make_resident(&[name.to_string()], None)?,
+            None => db.make_all_resident()?,
+        }
+        db.analyze(relation).map_err(StorageError::Other)
+    }
+
+    /// Outcome of crash recovery and the consistency check run at startup
+    pub fn recovery_report(&self) -> &crate::storage::persist::RecoveryReport {
+        self.persist.recovery_report()
+    }
+
+    /// Updates written to the WAL but not yet to batch files
+    pub fn wal_lag(&self) -> crate::storage::persist::WalLag {
+        self.persist.wal_lag()
+    }
+
+    /// Statistics collected for a specific knowledge graph
+    pub fn statistics_in(&self, kg: &str) -> StorageResult<Vec<RelationStats>> {
+        let db = self
+            .knowledge_graphs
+            .get(kg)
+            .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
+
+        let db = db.read();
+        Ok(db.statistics())
+    }
+
+    /// Alter the persistent schema of a relation in a specific knowledge graph.
+    ///
+    /// The migration is recorded in the schema history and the relation's
+    /// shard is stamped with the new version, so batch files written before
+    /// it are adapted when the knowledge graph is next loaded. Tuples in
+    /// memory are migrated immediately. Returns the new schema.
+    pub fn alter_schema_in(
+        &self,
+        kg: &str,
+        relation: &str,
+        migration: SchemaMigration,
+    ) -> StorageResult<RelationSchema> {
+        let db = self
+            .knowledge_graphs
+            .get(kg)
+            .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
+
+        let mut db = db.write();
+        db.schema_catalog
+            .plan_alter(relation, &migration)
+            .map_err(|e| StorageError::Other(format!("{e}")))?;
+        let next_version = db.schema_catalog.schema_version(relation) + 1;
+
+        let dropping_guard = self.dropping_kgs.read();
+        if dropping_guard.contains(kg) {
+            return Err(StorageError::KnowledgeGraphNotFound(kg.to_string()));
+        }
+
+        // Stamp the shards before saving the catalog: buffered updates are
+        // flushed under the old version, and if we crash before the catalog
+        // is saved, batches of an unknown newer version are left untouched
+        let mut shards = self.relation_shards(kg, relation)?;
+        if shards.is_empty() {
+            shards.push(format!("{kg}:{relation}"));
+        }
+        for shard in &shards {
+            self.persist.set_schema_version(shard, next_version)?;
+        }
+        drop(dropping_guard);
+
+        let time = self.logical_time.fetch_add(1, Ordering::SeqCst);
+        db.alter_schema(relation, migration, time)
+    }
+
+    /// Register or update a session schema in a specific knowledge graph (not persisted)
+    pub fn register_or_update_session_schema_in(
+        &self,
+        kg: &str,
+        schema: RelationSchema,
+    ) -> StorageResult<()> {
+        let db = self
+            .knowledge_graphs
+            .get(kg)
+            .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
+
+        let mut db = db.write();
+        db.register_or_update_session_schema(schema)
+            .map_err(StorageError::Other)
+    }
+
+    /// Execute a query with rules prepended (current knowledge graph)
+    ///
+    /// Returns binary tuples (i32, i32) for backward compatibility.
+    /// For arbitrary arity results, use `execute_query_with_rules_tuples` instead.
+    pub fn execute_query_with_rules(&self, program: &str) -> StorageResult<Vec<(i32, i32)>> {
+        let db_name = self
+            .current_kg
+            .as_ref()
+            .ok_or(StorageError::NoCurrentKnowledgeGraph)?
+            .clone();
 
         self.execute_query_with_rules_on(&db_name, program)
     }
@@ -1389,16 +2015,8 @@ impl StorageEngine {
         kg: &str,
         program: &str,
     ) -> StorageResult<Vec<(i32, i32)>> {
-        let db = self
-            .knowledge_graphs
-            .get(kg)
-            .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
-
         // Get snapshot atomically - O(1), no lock needed
-        let snapshot = {
-            let db_guard = db.read();
-            db_guard.snapshot()
-        };
+        let snapshot = self.get_snapshot_for_program(kg, program)?;
 
         // Execute on snapshot - completely lock-free
         snapshot
@@ -1422,14 +2040,15 @@ impl StorageEngine {
     /// Returns an `Arc<KnowledgeGraphSnapshot>` that can be used for lock-free
     /// query execution. Callers can release the storage lock after obtaining
     /// the snapshot and run DD computations without holding any locks.
+    ///
+    /// Every relation is made resident first; to run a program, prefer
+    /// `get_snapshot_for_program`, which loads only what it reads.
     pub fn get_snapshot_for(&self, kg: &str) -> StorageResult<Arc<KnowledgeGraphSnapshot>> {
-        let db = self
-            .knowledge_graphs
-            .get(kg)
-            .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
-
-        let db_guard = db.read();
-        Ok(db_guard.snapshot())
+        self.with_resident(
+            kg,
+            KnowledgeGraph::evicted_relations,
+            KnowledgeGraph::snapshot,
+        )
     }
 
     /// Execute a query with rules prepended, returning tuples of arbitrary arity (specific knowledge graph)
@@ -1440,16 +2059,8 @@ impl StorageEngine {
         kg: &str,
         program: &str,
     ) -> StorageResult<Vec<Tuple>> {
-        let db = self
-            .knowledge_graphs
-            .get(kg)
-            .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
-
         // Get snapshot atomically - O(1), no lock needed
-        let snapshot = {
-            let db_guard = db.read();
-            db_guard.snapshot()
-        };
+        let snapshot = self.get_snapshot_for_program(kg, program)?;
 
         // Execute on snapshot - completely lock-free
         snapshot
@@ -1474,16 +2085,8 @@ impl StorageEngine {
         program: &str,
         session_facts: Vec<(String, Tuple)>,
     ) -> StorageResult<Vec<Tuple>> {
-        let db = self
-            .knowledge_graphs
-            .get(kg)
-            .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
-
         // Get snapshot atomically - O(1), no lock needed
-        let snapshot = {
-            let db_guard = db.read();
-            db_guard.snapshot()
-        };
+        let snapshot = self.get_snapshot_for_program(kg, program)?;
 
         // Execute with session facts on isolated snapshot copy - completely lock-free
         // The session facts are added to a CLONE of the data, not the shared store
@@ -1680,8 +2283,18 @@ impl StorageEngine {
         let load_start = std::time::Instant::now();
         for (i, kg_name) in kg_names.into_iter().enumerate() {
             let kg_dir = self.config.storage.data_dir.join(&kg_name);
+            // An interrupted copy is discarded; its shards are removed
+            // with the other orphans below
+            if lifecycle::is_partial_copy(&kg_dir) {
+                fs::remove_dir_all(&kg_dir)?;
+                tracing::warn!(kg = %kg_name, "kg_partial_copy_discarded");
+                continue;
+            }
             fs::create_dir_all(&kg_dir)?;
 
+            // Catalog changes logged after the files were last synced
+            // come back before the data written under them
+            self.restore_catalogs(&kg_name, &kg_dir)?;
             let kg = self.load_knowledge_graph_from_persist(&kg_name, kg_dir)?;
             self.knowledge_graphs
                 .insert(kg_name, Arc::new(RwLock::new(kg)));
@@ -1727,77 +2340,174 @@ impl StorageEngine {
         let prefix = format!("{name}:");
         let mut engine = IQLEngine::new();
         let mut metadata = KnowledgeGraphMetadata::new(name.to_string());
+        let mut lsn = 0;
+        let mut relation_lsns: HashMap<String, u64> = HashMap::new();
 
-        // Find all shards for this knowledge graph
-        for shard_name in self.persist.list_shards()? {
-            if shard_name.starts_with(&prefix) {
-                let relation = match shard_name.strip_prefix(&prefix) {
-                    Some(r) => r,
-                    None => continue, // Skip malformed shard names
-                };
+        // Load schema catalog first (will load existing schemas if present):
+        // its migration history adapts tuples written under older schemas
+        let schema_path = data_dir.join("schema.json");
+        let schema_catalog = if schema_path.exists() {
+            SchemaCatalog::load(&schema_path).unwrap_or_else(|e| {
+                tracing::warn!(kg = %name, error = %e, "schema_catalog_load_failed");
+                SchemaCatalog::new()
+            })
+        } else {
+            SchemaCatalog::new()
+        };
+
+        // Finish moving relations between partition layouts, then read
+        // every shard (one per partition of a partitioned relation) in
+        // parallel, bringing older schema versions up to date
+        self.finish_partition_moves(name, &data_dir, &schema_catalog)?;
+        let shard_names: Vec<String> = self
+            .persist
+            .list_shards()?
+            .into_iter()
+            .filter(|shard| shard.starts_with(&prefix))
+            .collect();
 
-                // Get shard info to determine since frontier
-                let info = self.persist.shard_info(&shard_name)?;
+        // Lazily loaded relations are only registered: their data is read
+        // on first use. Vector indexes need every relation in memory.
+        let mut residency = self.new_residency();
+        let lazy =
+            self.config.storage.residency.lazy_load && !IndexManager::has_saved_indexes(&data_dir);
+        if lazy {
+            let mut stored: BTreeMap<&str, usize> = BTreeMap::new();
+            for shard_name in &shard_names {
+                let relation = crate::schema::partition::base_relation(&shard_name[prefix.len()..]);
+                let info = self.persist.shard_info(shard_name)?;
+                let upper = info.upper.saturating_sub(1);
+                lsn = lsn.max(upper);
+                let relation_lsn = relation_lsns.entry(relation.to_string()).or_default();
+                *relation_lsn = (*relation_lsn).max(upper);
+                *stored.entry(relation).or_default() += info.total_updates;
+            }
+            for (relation, updates) in stored {
+                // Until loaded, the row count is the number of stored updates
+                let columns = schema_catalog
+                    .get(relation)
+                    .map_or_else(Vec::new, |schema| {
+                        (0..schema.arity()).map(|i| format!("col{i}")).collect()
+                    });
+                metadata.add_relation(relation.to_string(), columns, updates);
+                residency.mark_evicted(relation);
+            }
+        }
 
-                // Read and consolidate updates
-                let mut updates = self.persist.read(&shard_name, info.since)?;
+        let eager: &[String] = if lazy { &[] } else { &shard_names };
+        let persist: &FilePersist = &self.persist;
+        let loaded = eager
+            .par_iter()
+            .map(|shard_name| -> StorageResult<(String, u64, Vec<Tuple>)> {
+                let relation = crate::schema::partition::base_relation(&shard_name[prefix.len()..]);
+                let info = persist.shard_info(shard_name)?;
+                let mut updates =
+                    partition::read_adapted(persist, shard_name, relation, &schema_catalog)?;
                 consolidate_to_current(&mut updates);
 
-                // Extract current tuples (positive multiplicities only)
-                let tuples = to_tuples(&updates);
+                // Current tuples (positive multiplicities only)
+                Ok((
+                    relation.to_string(),
+                    info.upper.saturating_sub(1),
+                    to_tuples(&updates),
+                ))
+            })
+            .collect::<StorageResult<Vec<_>>>()?;
+
+        // Partitions hold disjoint tuples, so a relation is their union
+        let mut relations: BTreeMap<String, Vec<Tuple>> = BTreeMap::new();
+        for (relation, upper, tuples) in loaded {
+            lsn = lsn.max(upper);
+            let relation_lsn = relation_lsns.entry(relation.clone()).or_default();
+            *relation_lsn = (*relation_lsn).max(upper);
+            relations.entry(relation).or_default().extend(tuples);
+        }
+        for (relation, tuples) in relations {
+            // Stored vectors must match the dimensions the schema declares
+            if let Some(schema) = schema_catalog.get(&relation) {
+                schema
+                    .check_vector_dims(&tuples)
+                    .map_err(StorageError::Other)?;
+            }
 
-                if !tuples.is_empty() {
-                    // Infer schema from first tuple
-                    let arity = tuples.first().map_or(2, super::value::Tuple::arity);
-                    let schema: Vec<String> = (0..arity).map(|i| format!("col{i}")).collect();
-                    let tuple_count = tuples.len();
+            if !tuples.is_empty() {
+                // Infer schema from first tuple
+                let arity = tuples.first().map_or(2, super::value::Tuple::arity);
+                let schema: Vec<String> = (0..arity).map(|i| format!("col{i}")).collect();
+                let tuple_count = tuples.len();
 
-                    // Update metadata with relation info
-                    metadata.add_relation(relation.to_string(), schema, tuple_count);
+                // Update metadata with relation info
+                metadata.add_relation(relation.clone(), schema, tuple_count);
 
-                    engine.add_tuples(relation, tuples);
-                }
+                engine.add_tuples(&relation, tuples);
+            }
+        }
+
+        // Never hand out an ID that is already stored, even if the sequence
+        // file is behind the data (e.g. lost or restored from an old backup)
+        let sequence_path = data_dir.join("sequences.json");
+        let mut sequences = SequenceCatalog::load(&sequence_path).unwrap_or_else(|e| {
+            tracing::warn!(kg = %name, error = %e, "sequences_load_failed_recovering");
+            SequenceCatalog::new(&sequence_path)
+        });
+        for schema in schema_catalog.persistent_schemas() {
+            if let Some(tuples) = engine.input_tuples.get(&schema.name) {
+                residency::recover_sequences(&mut sequences, schema, tuples)?;
             }
         }
 
+        let statistics = load_statistics(&data_dir, name);
+        let subplan_cache = self.load_subplan_cache(name, &data_dir);
+
         // Load view catalog (will load existing views if present)
         let rule_catalog = RuleCatalog::new(data_dir.clone())
             .map_err(|e| StorageError::Other(format!("Failed to load view catalog: {e}")))?;
 
-        // Load schema catalog (will load existing schemas if present)
-        let schema_path = data_dir.join("schema.json");
-        let schema_catalog = if schema_path.exists() {
-            SchemaCatalog::load(&schema_path).unwrap_or_else(|e| {
-                eprintln!(
-                    "Warning: Failed to load schema catalog for '{name}': {e}. Creating empty catalog."
-                );
-                SchemaCatalog::new()
-            })
-        } else {
-            SchemaCatalog::new()
-        };
-
         // Create initial snapshot from loaded data
         let num_workers = self.config.storage.performance.num_threads;
-        let snapshot = ArcSwap::from_pointee(KnowledgeGraphSnapshot::new_with_workers(
+        let mut initial = KnowledgeGraphSnapshot::new_with_workers(
             engine.input_tuples.clone(),
             rule_catalog.all_rules(),
             num_workers,
-        ));
+        );
+        initial.lsn = lsn;
+        let snapshot = ArcSwap::from_pointee(initial);
 
-        Ok(KnowledgeGraph {
+        let mut kg = KnowledgeGraph {
             name: name.to_string(),
             engine,
             metadata,
             data_dir,
             rule_catalog,
             schema_catalog,
+            sequences: parking_lot::Mutex::new(sequences),
+            statistics,
             snapshot,
+            lsn,
             incremental: None,
             num_workers,
             max_result_rows: self.config.storage.performance.max_result_rows,
             max_query_cost: self.config.storage.performance.max_query_cost,
-        })
+            max_query_memory_bytes: self.config.storage.performance.max_query_memory_bytes,
+            query_timeout_ms: self.config.storage.performance.query_timeout_ms,
+            coercion: self.config.storage.coercion,
+            residency,
+            views: parking_lot::Mutex::default(),
+            subplan_cache: Arc::new(subplan_cache),
+            relation_lsns,
+            ddl_log: None,
+        };
+        kg.set_ddl_log(self.ddl_log(name));
+
+        // Vector indexes are maintained by the incremental engine, so it
+        // has to run for them to serve queries and follow writes
+        if IndexManager::has_saved_indexes(&kg.data_dir) {
+            kg.enable_incremental()?;
+        }
+        // Republished so queries reach the loaded subplan cache, under the
+        // relation versions its entries were keyed with
+        kg.publish_snapshot();
+        Ok(kg)
     }
 
     /// Find the maximum logical time across all shards
@@ -1819,10 +2529,10 @@ impl StorageEngine {
         let start = Instant::now();
         let metadata_dir = self.config.storage.data_dir.join("metadata");
         if let Err(e) = fs::create_dir_all(&metadata_dir) {
-            eprintln!(
-                "[storage] ERROR create metadata dir: path={}, error={}",
-                metadata_dir.display(),
-                e
+            tracing::error!(
+                path = %metadata_dir.display(),
+                error = %e,
+                "create_metadata_dir_failed"
             );
             return Err(e.into());
         }
@@ -2014,9 +2724,7 @@ impl KnowledgeGraph {
         // Create rule catalog (will load existing rules if present)
         let rule_catalog = RuleCatalog::new(data_dir.clone()).unwrap_or_else(|e| {
             // Log warning but create empty catalog to avoid panic
-            eprintln!(
-                "Warning: Failed to load rule catalog for '{name}': {e}. Creating empty catalog."
-            );
+            tracing::warn!(kg = %name, error = %e, "rule_catalog_load_failed");
             RuleCatalog::empty()
         });
 
@@ -2024,17 +2732,23 @@ impl KnowledgeGraph {
         let schema_path = data_dir.join("schema.json");
         let schema_catalog = if schema_path.exists() {
             SchemaCatalog::load(&schema_path).unwrap_or_else(|e| {
-                eprintln!(
-                    "Warning: Failed to load schema catalog for '{name}': {e}. Creating empty catalog."
-                );
+                tracing::warn!(kg = %name, error = %e, "schema_catalog_load_failed");
                 SchemaCatalog::new()
             })
         } else {
             SchemaCatalog::new()
         };
 
+        let sequence_path = data_dir.join("sequences.json");
+        let sequences = SequenceCatalog::load(&sequence_path).unwrap_or_else(|e| {
+            tracing::warn!(kg = %name, error = %e, "sequences_load_failed");
+            SequenceCatalog::new(&sequence_path)
+        });
+        let statistics = load_statistics(&data_dir, &name);
+
         // Create initial empty snapshot
         let snapshot = ArcSwap::from_pointee(KnowledgeGraphSnapshot::empty());
+        let lsn = 0;
 
         KnowledgeGraph {
             name: name.clone(),
@@ -2043,11 +2757,22 @@ impl KnowledgeGraph {
             data_dir,
             rule_catalog,
             schema_catalog,
+            sequences: parking_lot::Mutex::new(sequences),
+            statistics,
             snapshot,
+            lsn,
             incremental: None,
             num_workers,
             max_result_rows: 0,
             max_query_cost: 0,
+            max_query_memory_bytes: 0,
+            query_timeout_ms: 0,
+            coercion: CoercionMode::default(),
+            residency: Residency::default(),
+            views: parking_lot::Mutex::default(),
+            subplan_cache: Arc::new(SubplanCache::new()),
+            relation_lsns: HashMap::new(),
+            ddl_log: None,
         }
     }
 
@@ -2061,6 +2786,19 @@ impl KnowledgeGraph {
     /// Returns error if worker thread fails to spawn or replaying existing data fails.
     pub fn enable_incremental(&mut self) -> StorageResult<()> {
         if self.incremental.is_none() {
+            if let Some(relation) = self
+                .rule_catalog
+                .all_rules()
+                .iter()
+                .find_map(|rule| qualified::qualified_body_relation(rule).map(str::to_string))
+            {
+                return Err(StorageError::Other(format!(
+                    "A rule reads '{relation}' from another knowledge graph; \
+                     the incremental engine cannot maintain it"
+                )));
+            }
+            // The incremental engine replays (and keeps) every relation
+            self.make_all_resident()?;
             let dd =
                 IncrementalEngine::new(vec![]).map_err(StorageError::IncrementalEngineError)?;
 
@@ -2092,6 +2830,34 @@ impl KnowledgeGraph {
                 }
                 _ => {}
             }
+
+            // Saved indexes miss writes made after they were saved (e.g.
+            // before a crash): rebuild them from the relations instead.
+            // LSH mappings are not saved and are always rebuilt.
+            let saved_current = IndexManager::saved_lsn(&self.data_dir) == Some(self.lsn);
+            let stale: Vec<(String, String)> = guard
+                .registered_indexes()
+                .values()
+                .filter(|reg| !saved_current || guard.get_materialized(&reg.name).is_none())
+                .map(|reg| (reg.name.clone(), reg.relation.clone()))
+                .collect();
+            for (name, relation) in stale {
+                let tuples = self
+                    .engine
+                    .input_tuples
+                    .get(&relation)
+                    .map_or(&[][..], Vec::as_slice);
+                match guard.build_index(&name, tuples) {
+                    Ok(count) => tracing::info!(
+                        kg = %self.name, index = %name, vectors = count,
+                        "index_rebuilt_on_load"
+                    ),
+                    Err(e) => tracing::warn!(
+                        kg = %self.name, index = %name, error = %e,
+                        "index_rebuild_failed"
+                    ),
+                }
+            }
             drop(guard);
 
             self.incremental = Some(dd);
@@ -2099,20 +2865,122 @@ impl KnowledgeGraph {
         Ok(())
     }
 
-    /// Get a reference to the IncrementalEngine (if enabled).
+    /// Register an index over a vector column and build it from the
+    /// relation's current tuples. The index is then maintained on every
+    /// insert and delete. Nearest-neighbor `top_k` queries over the column
+    /// use an HNSW index; an LSH index is published as a relation of
+    /// `(Table, Bucket, Id)` rows named after the index. Returns the number
+    /// of vectors indexed.
     ///
-    /// Used for reading from DD arrangements and verifying consistency.
-    pub fn incremental(&self) -> Option<&IncrementalEngine> {
-        self.incremental.as_ref()
+    /// The relation's first column must be an integer: it is the ID the
+    /// index stores for each tuple.
+    pub fn create_vector_index(&mut self, index: RegisteredIndex) -> StorageResult<usize> {
+        let shadows_relation = self.engine.input_tuples.contains_key(&index.name)
+            || self.schema_catalog.get(&index.name).is_some()
+            || self.rule_catalog.exists(&index.name);
+        if matches!(index.index_type, IndexType::Lsh(_)) && shadows_relation {
+            return Err(StorageError::Other(format!(
+                "LSH index '{}' would shadow the relation of the same name",
+                index.name
+            )));
+        }
+        if let Some(schema) = self.schema_catalog.get(&index.relation) {
+            if schema
+                .columns
+                .first()
+                .is_none_or(|c| c.data_type != SchemaType::Int)
+            {
+                return Err(StorageError::Other(format!(
+                    "Relation '{}' needs an integer ID as its first column to be indexed",
+                    index.relation
+                )));
+            }
+        }
+        self.enable_incremental()?;
+        let name = index.name.clone();
+        let relation = index.relation.clone();
+        if let Some(dd) = &self.incremental {
+            dd.register_index(index).map_err(StorageError::Other)?;
+        }
+        let count = match self.rebuild_vector_index(&name) {
+            Ok(count) => count,
+            Err(e) => {
+                if let Some(dd) = &self.incremental {
+                    let _ = dd.remove_index(&name);
+                }
+                return Err(e);
+            }
+        };
+        info!(kg = %self.name, index = %name, relation = %relation, vectors = count, "vector_index_created");
+        Ok(count)
     }
 
-    /// Publish a new snapshot atomically
-    ///
-    /// Called after data modifications to make changes visible to readers.
-    /// This is O(1) - just an atomic pointer swap.
-    ///
-    /// If IncrementalEngine has valid materializations, includes them
-    /// in the snapshot. Materialized tuples are merged into input_tuples,
+    /// Rebuild a registered index from its relation's current tuples,
+    /// compacting away deleted entries. Returns the number of vectors
+    /// indexed.
+    pub fn rebuild_vector_index(&self, name: &str) -> StorageResult<usize> {
+        let Some(dd) = &self.incremental else {
+            return Err(StorageError::Other(format!(
+                "Index '{name}' not found (no incremental engine)"
+            )));
+        };
+        let relation = dd
+            .index_manager()
+            .lock()
+            .get_registered(name)
+            .map(|reg| reg.relation.clone())
+            .ok_or_else(|| StorageError::Other(format!("Index '{name}' not found")))?;
+        let tuples = self
+            .engine
+            .input_tuples
+            .get(&relation)
+            .map_or(&[][..], Vec::as_slice);
+        let count = dd.build_index(name, tuples).map_err(StorageError::Other)?;
+        self.save_vector_indexes();
+        self.publish_snapshot();
+        Ok(count)
+    }
+
+    /// Drop an index and stop routing queries through it
+    pub fn drop_vector_index(&self, name: &str) -> StorageResult<()> {
+        let Some(dd) = &self.incremental else {
+            return Err(StorageError::Other(format!(
+                "Index '{name}' not found (no incremental engine)"
+            )));
+        };
+        dd.remove_index(name).map_err(StorageError::Other)?;
+        self.save_vector_indexes();
+        self.publish_snapshot();
+        Ok(())
+    }
+
+    /// Save the knowledge graph's indexes, tagged with its current LSN
+    fn save_vector_indexes(&self) {
+        if let Some(dd) = &self.incremental {
+            if let Err(e) = dd
+                .index_manager()
+                .lock()
+                .save_indexes_at(&self.data_dir, self.lsn)
+            {
+                tracing::warn!(kg = %self.name, error = %e, "failed_to_save_indexes");
+            }
+        }
+    }
+
+    /// Get a reference to the IncrementalEngine (if enabled).
+    ///
+    /// Used for reading from DD arrangements and verifying consistency.
+    pub fn incremental(&self) -> Option<&IncrementalEngine> {
+        self.incremental.as_ref()
+    }
+
+    /// Publish a new snapshot atomically
+    ///
+    /// Called after data modifications to make changes visible to readers.
+    /// This is O(1) - just an atomic pointer swap.
+    ///
+    /// If IncrementalEngine has valid materializations, includes them
+    /// in the snapshot. Materialized tuples are merged into input_tuples,
     /// and their rules are skipped during query execution.
     ///
     /// IMPORTANT: This method holds the DerivedRelationsManager lock through
@@ -2124,6 +2992,13 @@ impl KnowledgeGraph {
         // Start with base relation data
         let mut input_tuples = self.engine.input_tuples.clone();
         let rules = self.rule_catalog.all_rules();
+        // Relations merged in below are not base data and get no version
+        let mut relation_versions = self.relation_versions();
+
+        // Cached views appear as base facts, like materializations
+        let cached_views = self.cached_views_for_snapshot();
+        let cached_names: HashSet<String> = cached_views.keys().cloned().collect();
+        input_tuples.extend(cached_views);
 
         // Gather valid materializations from IncrementalEngine
         // CRITICAL: Hold the lock through snapshot creation AND publication
@@ -2138,14 +3013,23 @@ impl KnowledgeGraph {
             // Merge materialized tuples into input_tuples
             // They appear as base facts so the rules don't need to recompute them
             for (rel_name, tuples) in materializations {
+                relation_versions.remove(&rel_name);
                 input_tuples
                     .entry(rel_name.clone())
                     .or_default()
                     .extend(tuples);
             }
 
+            // LSH indexes are published as relations of their bucket mappings
+            for (index_name, rows) in dd.index_manager().lock().lsh_relations() {
+                relation_versions.remove(&index_name);
+                input_tuples.insert(index_name, rows);
+            }
+
             // Get names of materialized relations
-            let materialized_names = manager_guard.get_materialized_relation_names();
+            let mut materialized_names = manager_guard.get_materialized_relation_names();
+            materialized_names.extend(cached_names);
+            relation_versions.retain(|name, _| !materialized_names.contains(name));
 
             // Create AND publish snapshot while still holding the lock
             // This ensures no concurrent invalidation can occur between
@@ -2161,20 +3045,40 @@ impl KnowledgeGraph {
             );
             new_snapshot.max_result_rows = self.max_result_rows;
             new_snapshot.max_query_cost = self.max_query_cost;
+            new_snapshot.max_query_memory_bytes = self.max_query_memory_bytes;
+            new_snapshot.query_timeout_ms = self.query_timeout_ms;
+            new_snapshot.unique_keys = Arc::new(self.unique_key_map());
+            new_snapshot.coercion = self.coercion;
+            new_snapshot.vector_dims = Arc::new(self.vector_dim_map());
+            new_snapshot.distinct_counts = Arc::new(self.statistics.distinct_counts());
+            new_snapshot.lsn = self.lsn;
             new_snapshot.hnsw_search_fn = hnsw_fn;
+            new_snapshot.indexed_columns = Arc::new(dd.index_manager().lock().indexed_columns());
+            new_snapshot.subplan_cache = Some(Arc::clone(&self.subplan_cache));
+            new_snapshot.relation_versions = Arc::new(relation_versions);
             self.snapshot.store(Arc::new(new_snapshot));
 
             // Lock drops here AFTER publication - this is the fix for TOCTOU
         } else {
             // No DD computation - publish without materializations
+            relation_versions.retain(|name, _| !cached_names.contains(name));
             let mut new_snapshot = KnowledgeGraphSnapshot::new_with_materializations(
                 input_tuples,
                 rules,
                 self.num_workers,
-                HashSet::new(),
+                cached_names,
             );
             new_snapshot.max_result_rows = self.max_result_rows;
             new_snapshot.max_query_cost = self.max_query_cost;
+            new_snapshot.max_query_memory_bytes = self.max_query_memory_bytes;
+            new_snapshot.query_timeout_ms = self.query_timeout_ms;
+            new_snapshot.unique_keys = Arc::new(self.unique_key_map());
+            new_snapshot.coercion = self.coercion;
+            new_snapshot.vector_dims = Arc::new(self.vector_dim_map());
+            new_snapshot.distinct_counts = Arc::new(self.statistics.distinct_counts());
+            new_snapshot.lsn = self.lsn;
+            new_snapshot.subplan_cache = Some(Arc::clone(&self.subplan_cache));
+            new_snapshot.relation_versions = Arc::new(relation_versions);
             self.snapshot.store(Arc::new(new_snapshot));
         }
 
@@ -2186,6 +3090,88 @@ impl KnowledgeGraph {
         );
     }
 
+    /// Record that a write at `time` changed `relation`, dropping the
+    /// cached subplans that read it
+    fn relation_changed(&mut self, relation: &str, time: u64) {
+        self.relation_lsns.insert(relation.to_string(), time);
+        self.subplan_cache.invalidate_relation(relation);
+    }
+
+    /// Subplan cache version of each base relation in memory: its last
+    /// write LSN combined with its schema version, so neither a write nor
+    /// a schema change can leave a cached result looking current
+    fn relation_versions(&self) -> HashMap<String, u64> {
+        use std::hash::{Hash, Hasher};
+
+        self.engine
+            .input_tuples
+            .keys()
+            .filter_map(|relation| {
+                let lsn = self.relation_lsns.get(relation)?;
+                let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                (lsn, self.schema_catalog.schema_version(relation)).hash(&mut hasher);
+                Some((relation.clone(), hasher.finish()))
+            })
+            .collect()
+    }
+
+    /// Unique key column sets of every relation that declares one
+    fn unique_key_map(&self) -> std::collections::HashMap<String, Vec<Vec<usize>>> {
+        self.schema_catalog
+            .all_schemas()
+            .filter_map(|schema| {
+                let keys = schema.unique_keys();
+                (!keys.is_empty()).then(|| (schema.name.clone(), keys))
+            })
+            .collect()
+    }
+
+    /// Declared vector dimensions of every relation that has any
+    fn vector_dim_map(&self) -> VectorDims {
+        vector_dims::collect(self.schema_catalog.all_schemas())
+    }
+
+    /// Recompute statistics for one base relation, or all of them, and
+    /// persist the statistics catalog. Returns the new statistics.
+    pub fn analyze(&mut self, relation: Option<&str>) -> Result<Vec<RelationStats>, String> {
+        let names: Vec<String> = match relation {
+            Some(name) => {
+                if !self.engine.input_tuples.contains_key(name) && !self.has_schema(name) {
+                    return Err(format!("Relation '{name}' not found."));
+                }
+                vec![name.to_string()]
+            }
+            None => {
+                let mut names: Vec<String> = self.engine.input_tuples.keys().cloned().collect();
+                names.sort();
+                names
+            }
+        };
+
+        let empty = Vec::new();
+        for name in &names {
+            let tuples = self.engine.input_tuples.get(name).unwrap_or(&empty);
+            let arity = match self.schema_catalog.get(name) {
+                Some(schema) => schema.arity(),
+                None => tuples.iter().map(Tuple::arity).max().unwrap_or(0),
+            };
+            self.statistics.analyze(name, tuples, arity);
+        }
+        self.statistics
+            .save(&self.data_dir.join("statistics.json"))?;
+        self.publish_snapshot();
+
+        Ok(names
+            .iter()
+            .filter_map(|name| self.statistics.get(name).cloned())
+            .collect())
+    }
+
+    /// Statistics of every analyzed relation, ordered by name
+    pub fn statistics(&self) -> Vec<RelationStats> {
+        self.statistics.all().into_iter().cloned().collect()
+    }
+
     /// Build an HNSW search closure that captures the IndexManager Arc.
     ///
     /// Returns `None` if no IncrementalEngine or no materialized indexes exist.
@@ -2254,7 +3240,8 @@ impl KnowledgeGraph {
 
     /// Insert tuples into in-memory state only
     ///
-    /// Persistence is handled by `StorageEngine` via the persist layer.
+    /// Persistence is handled by `StorageEngine` via the persist layer, and
+    /// the caller publishes the snapshot once all writes of a batch are in.
     /// Returns (`new_count`, `duplicate_count`) for caller to report.
     ///
     /// # Errors
@@ -2296,6 +3283,10 @@ impl KnowledgeGraph {
             }
         }
         let tuple_count = existing_tuples.len();
+        if new_count > 0 {
+            self.relation_changed(relation, time);
+            self.views.get_mut().mark_stale(relation);
+        }
 
         // Update metadata
         self.metadata
@@ -2306,22 +3297,16 @@ impl KnowledgeGraph {
         // Time advancement is lazy  -  only happens when a consistent read is requested.
         if !new_tuples_for_dd.is_empty() {
             if let Some(dd) = &self.incremental {
+                // Link the new vectors into indexes on this base relation
+                dd.update_indexes(relation, &new_tuples_for_dd, &[]);
                 dd.insert(relation, new_tuples_for_dd, time)
                     .map_err(StorageError::IncrementalEngineError)?;
                 // Invalidate derived relations that depend on this base
                 dd.notify_base_update(relation)
                     .map_err(StorageError::IncrementalEngineError)?;
-                // Invalidate indexes that depend on this base relation
-                dd.notify_indexes_base_update(relation)
-                    .map_err(StorageError::IncrementalEngineError)?;
             }
         }
 
-        // Publish new snapshot for lock-free reads (only if data actually changed)
-        if new_count > 0 {
-            self.publish_snapshot();
-        }
-
         info!(
             relation = %relation,
             new_count,
@@ -2335,7 +3320,8 @@ impl KnowledgeGraph {
 
     /// Delete tuples from in-memory state only
     ///
-    /// Persistence is handled by `StorageEngine` via the persist layer.
+    /// Persistence is handled by `StorageEngine` via the persist layer, and
+    /// the caller publishes the snapshot once all writes of a batch are in.
     /// Returns the count of actually deleted tuples.
     ///
     /// # Errors
@@ -2380,6 +3366,8 @@ impl KnowledgeGraph {
 
         // Update metadata and DD only if data actually changed
         if found && deleted_count > 0 {
+            self.relation_changed(relation, time);
+            self.views.get_mut().mark_stale(relation);
             self.metadata
                 .add_relation(relation.to_string(), schema, final_count);
 
@@ -2387,19 +3375,15 @@ impl KnowledgeGraph {
             // Uses the logical timestamp from StorageEngine.
             if !deleted_tuples_for_dd.is_empty() {
                 if let Some(dd) = &self.incremental {
+                    // Remove the deleted vectors from indexes on this relation
+                    dd.update_indexes(relation, &[], &deleted_tuples_for_dd);
                     dd.delete(relation, deleted_tuples_for_dd, time)
                         .map_err(StorageError::IncrementalEngineError)?;
                     // Invalidate derived relations that depend on this base
                     dd.notify_base_update(relation)
                         .map_err(StorageError::IncrementalEngineError)?;
-                    // Invalidate indexes that depend on this base relation
-                    dd.notify_indexes_base_update(relation)
-                        .map_err(StorageError::IncrementalEngineError)?;
                 }
             }
-
-            // Publish new snapshot for lock-free reads
-            self.publish_snapshot();
         }
 
         Ok(deleted_count)
@@ -2428,26 +3412,44 @@ impl KnowledgeGraph {
         &mut self,
         rule_def: &RuleDef,
     ) -> Result<crate::rule_catalog::RuleRegisterResult, String> {
+        let result = self.add_rule_definition(rule_def)?;
+        self.publish_snapshot();
+        Ok(result)
+    }
+
+    /// Register and materialize a persistent rule without publishing a
+    /// snapshot; the caller publishes
+    fn add_rule_definition(
+        &mut self,
+        rule_def: &RuleDef,
+    ) -> Result<crate::rule_catalog::RuleRegisterResult, String> {
+        let rule = rule_def.rule.to_rule();
+        vector_dims::check_rule(&rule, &self.vector_dim_map())?;
+        if self.incremental.is_some() {
+            if let Some(relation) = qualified::qualified_body_relation(&rule) {
+                return Err(format!(
+                    "Rule '{}' reads '{relation}' from another knowledge graph; \
+                     the incremental engine cannot maintain it",
+                    rule_def.name
+                ));
+            }
+        }
         let result = self.rule_catalog.register_rule(rule_def)?;
 
         // Register with IncrementalEngine for materialization
         if let Some(ref dd) = self.incremental {
             let compiled_rule = self.compile_rule_for_dd(rule_def);
             if let Err(e) = dd.register_rule(compiled_rule) {
-                eprintln!("Warning: failed to register rule with IncrementalEngine: {e}");
+                tracing::warn!(rule = %rule_def.name, error = %e, "incremental_register_rule_failed");
             }
 
             // Auto-materialize the rule
             // Execute the rule against current base data and store results
             if let Err(e) = self.auto_materialize_rule(&rule_def.name) {
-                eprintln!(
-                    "Warning: failed to auto-materialize rule '{}': {e}",
-                    rule_def.name
-                );
+                tracing::warn!(rule = %rule_def.name, error = %e, "auto_materialize_failed");
             }
         }
 
-        self.publish_snapshot();
         Ok(result)
     }
 
@@ -2547,16 +3549,22 @@ impl KnowledgeGraph {
 
     /// Drop a view
     pub fn drop_rule(&mut self, name: &str) -> Result<(), String> {
+        self.remove_rule_definition(name)?;
+        self.publish_snapshot();
+        Ok(())
+    }
+
+    /// Drop a view without publishing a snapshot; the caller publishes
+    fn remove_rule_definition(&mut self, name: &str) -> Result<(), String> {
         self.rule_catalog.drop(name)?;
 
         // Remove from IncrementalEngine
         if let Some(ref dd) = self.incremental {
             if let Err(e) = dd.remove_rule(name) {
-                eprintln!("Warning: failed to remove rule from IncrementalEngine: {e}");
+                tracing::warn!(rule = %name, error = %e, "incremental_remove_rule_failed");
             }
         }
 
-        self.publish_snapshot();
         Ok(())
     }
 
@@ -2564,7 +3572,8 @@ impl KnowledgeGraph {
     pub fn drop_relation(&mut self, name: &str) -> Result<(), String> {
         // Check the relation exists (in metadata or as data)
         let has_metadata = self.metadata.relations.contains_key(name);
-        let has_data = self.engine.input_tuples.contains_key(name);
+        let has_data =
+            self.engine.input_tuples.contains_key(name) || self.residency.is_evicted(name);
         let has_rule = self.rule_catalog.exists(name);
         let has_schema = self.schema_catalog.get(name).is_some();
 
@@ -2574,12 +3583,21 @@ impl KnowledgeGraph {
 
         // 1. Remove data from engine
         self.engine.input_tuples.remove(name);
+        self.relation_lsns.remove(name);
+        self.subplan_cache.invalidate_relation(name);
+        self.views.get_mut().mark_stale(name);
+        self.residency.forget(name);
 
         // 2. Remove from metadata
         self.metadata.relations.remove(name);
 
-        // 3. Remove schema
+        // 3. Remove schema, ID sequence and statistics
         self.schema_catalog.remove(name);
+        self.sequences.get_mut().remove(name)?;
+        if self.statistics.remove(name) {
+            self.statistics
+                .save(&self.data_dir.join("statistics.json"))?;
+        }
 
         // 4. Drop any associated rules (ignore error if no rules)
         let _ = self.rule_catalog.drop(name);
@@ -2602,9 +3620,7 @@ impl KnowledgeGraph {
         if let Some(ref dd) = self.incremental {
             for name in &dropped {
                 if let Err(e) = dd.remove_rule(name) {
-                    eprintln!(
-                        "Warning: failed to remove rule '{name}' from IncrementalEngine: {e}"
-                    );
+                    tracing::warn!(rule = %name, error = %e, "incremental_remove_rule_failed");
                 }
             }
         }
@@ -2651,21 +3667,29 @@ impl KnowledgeGraph {
 
                 // Feed deletes to IncrementalEngine
                 if let Some(ref dd) = self.incremental {
+                    dd.update_indexes(relation, &[], tuples);
                     let _ = dd.delete(relation, tuples.clone(), time);
                     let _ = dd.notify_base_update(relation);
-                    let _ = dd.notify_indexes_base_update(relation);
                 }
 
                 // Write deletes to persist
-                let shard = format!("{kg_name}:{relation}");
                 let updates: Vec<Update> = tuples
                     .iter()
                     .map(|t| Update::delete(t.clone(), time))
                     .collect();
-                let _ = persist.ensure_shard(&shard);
-                let _ = persist.append(&shard, &updates);
+                let key = self
+                    .schema_catalog
+                    .get(relation)
+                    .and_then(RelationSchema::partition_key);
+                let base = format!("{kg_name}:{relation}");
+                for (shard, updates) in partition::route_updates(&base, key, updates) {
+                    let _ = persist.ensure_shard(&shard);
+                    let _ = persist.append(&shard, &updates);
+                }
 
                 tuples.clear();
+                self.relation_changed(relation, time);
+                self.views.get_mut().mark_stale(relation);
 
                 // Update metadata
                 let schema = self
@@ -2681,6 +3705,7 @@ impl KnowledgeGraph {
         }
 
         if !results.is_empty() {
+            self.lsn = time;
             self.publish_snapshot();
         }
 
@@ -2828,19 +3853,41 @@ impl KnowledgeGraph {
         // Add the query
         combined.push_str(program);
 
-        if std::env::var("IL_DEBUG").is_ok() {
-            eprintln!(
-                "DEBUG execute_with_rules_tuples: {} view rules ({} skipped as materialized), program = {}",
-                rule_defs.len(),
-                skipped_count,
-                combined.replace('\n', " | ")
-            );
-        }
+        debug!(
+            view_rules = rule_defs.len(),
+            skipped_materialized = skipped_count,
+            program = %combined.replace('\n', " | "),
+            "execute_with_rules"
+        );
 
         // Execute combined program
         self.engine.execute_tuples(&combined)
     }
 
+    /// Subscribe to the results of a query as base facts change.
+    ///
+    /// The query is compiled together with all persistent rules into views
+    /// maintained by the IncrementalEngine (enabled if needed). The returned
+    /// stream yields the current results, then every insertion and
+    /// retraction caused by later writes.
+    pub fn subscribe(&mut self, program: &str) -> Result<ViewSubscription, String> {
+        self.enable_incremental().map_err(|e| e.to_string())?;
+
+        let mut combined = String::new();
+        for rule in &self.rule_catalog.all_rules() {
+            combined.push_str(&format_rule(rule));
+            combined.push('\n');
+        }
+        combined.push_str(program);
+
+        let views = self.engine.compile_views(&combined)?;
+        let dd = self
+            .incremental
+            .as_ref()
+            .ok_or("Failed to enable incremental engine")?;
+        dd.subscribe_query(views)
+    }
+
     /// Get reference to view catalog
     pub fn rule_catalog(&self) -> &RuleCatalog {
         &self.rule_catalog
@@ -2904,6 +3951,59 @@ impl KnowledgeGraph {
             .map_err(|e| format!("{e}"))
     }
 
+    /// Apply a schema migration and migrate the relation's tuples in memory
+    ///
+    /// Persistence of the tuples is handled on load via the schema history.
+    fn alter_schema(
+        &mut self,
+        relation: &str,
+        migration: SchemaMigration,
+        time: u64,
+    ) -> StorageResult<RelationSchema> {
+        let version = self
+            .schema_catalog
+            .alter(relation, migration)
+            .map_err(|e| StorageError::Other(format!("{e}")))?;
+        self.save_schema_catalog().map_err(StorageError::Other)?;
+
+        let schema = self
+            .schema_catalog
+            .get(relation)
+            .cloned()
+            .ok_or_else(|| StorageError::Other(format!("No schema found for '{relation}'")))?;
+
+        let old_tuples = self
+            .engine
+            .input_tuples
+            .get(relation)
+            .cloned()
+            .unwrap_or_default();
+        if old_tuples.is_empty() {
+            // Keep the recorded arity in step so new inserts are accepted
+            if let Some(meta) = self.metadata.relations.get_mut(relation) {
+                meta.schema = (0..schema.arity()).map(|i| format!("col{i}")).collect();
+            }
+        } else {
+            let migrated: Vec<Tuple> = old_tuples
+                .iter()
+                .map(|t| {
+                    self.schema_catalog
+                        .adapt_tuple(relation, version - 1, t.clone())
+                })
+                .collect();
+            self.delete_in_memory(relation, &old_tuples, time)?;
+            self.insert_in_memory(relation, migrated, time)?;
+        }
+        // Subplans cached against the old schema no longer apply
+        self.relation_changed(relation, time);
+        // Old and migrated tuples are swapped in one snapshot
+        self.lsn = time;
+        self.publish_snapshot();
+
+        info!(relation = %relation, version, "schema_altered");
+        Ok(schema)
+    }
+
     /// Clear all session schemas (called on disconnect/session end)
     pub fn clear_session_schemas(&mut self) {
         self.schema_catalog.clear_session();
@@ -2949,9 +4049,116 @@ impl KnowledgeGraph {
         Ok(())
     }
 
-    /// Save schema catalog to disk
+    /// Key conflicts between `tuples` and the relation's current contents
+    /// (empty if the relation declares no primary or unique key)
+    fn key_conflicts(&self, relation: &str, tuples: &[Tuple]) -> KeyConflicts {
+        match self.schema_catalog.get(relation) {
+            Some(schema) if !schema.unique_keys().is_empty() => {
+                let existing = self
+                    .engine
+                    .input_tuples
+                    .get(relation)
+                    .map_or(&[][..], Vec::as_slice);
+                ValidationEngine::new().check_unique_keys(schema, tuples, existing)
+            }
+            _ => KeyConflicts::default(),
+        }
+    }
+
+    /// Complete tuples that omit columns with the column defaults and
+    /// `@auto` IDs declared in the relation's schema.
+    ///
+    /// With `assign_ids` the relation's sequence is advanced past every ID
+    /// assigned or given explicitly and persisted before returning;
+    /// without it (validation only) placeholder IDs are used.
+    fn fill_defaults(
+        &self,
+        relation: &str,
+        tuples: Vec<Tuple>,
+        assign_ids: bool,
+    ) -> StorageResult<Vec<Tuple>> {
+        let schema = match self.schema_catalog.get(relation) {
+            Some(schema) if schema.has_generated_columns() => schema,
+            _ => return Ok(tuples),
+        };
+        let auto_columns = schema.auto_increment_columns();
+        if !assign_ids || auto_columns.is_empty() {
+            return Ok(tuples
+                .into_iter()
+                .map(|tuple| schema.fill_defaults(tuple, &mut || 0))
+                .collect());
+        }
+
+        let mut sequences = self.sequences.lock();
+        let mut next = sequences.peek(relation);
+        let filled: Vec<Tuple> = tuples
+            .into_iter()
+            .map(|tuple| {
+                schema.fill_defaults(tuple, &mut || {
+                    next += 1;
+                    next - 1
+                })
+            })
+            .collect();
+        // Explicit IDs move the sequence too, so later assigned IDs never collide
+        for tuple in &filled {
+            for &col in &auto_columns {
+                if let Some(id) = tuple.get(col).and_then(crate::value::Value::as_i64) {
+                    next = next.max(id.saturating_add(1));
+                }
+            }
+        }
+        sequences
+            .advance_to(relation, next)
+            .map_err(StorageError::Other)?;
+        Ok(filled)
+    }
+
+    /// Normalize values to their column types under lenient coercion
+    fn coerce_tuples(&self, relation: &str, tuples: Vec<Tuple>) -> Vec<Tuple> {
+        if self.coercion == CoercionMode::Strict {
+            return tuples;
+        }
+        match self.schema_catalog.get(relation) {
+            Some(schema) => tuples
+                .into_iter()
+                .map(|tuple| schema.coerce_tuple(tuple))
+                .collect(),
+            None => tuples,
+        }
+    }
+
+    /// Validate tuples against the relation's schema (if one exists) and
+    /// split them according to `policy`
+    fn screen_tuples(
+        &self,
+        relation: &str,
+        tuples: Vec<Tuple>,
+        policy: &ValidationPolicy,
+    ) -> Result<ScreenedBatch, ValidationError> {
+        match self.schema_catalog.get(relation) {
+            Some(schema) => ValidationEngine::new()
+                .with_timing(policy.timing)
+                .screen_batch(schema, tuples, policy),
+            None => Ok(ScreenedBatch {
+                accepted: tuples,
+                ..ScreenedBatch::default()
+            }),
+        }
+    }
+
+    /// Save schema catalog to disk, logging it to the WAL first
     fn save_schema_catalog(&self) -> Result<(), String> {
         let schema_path = self.data_dir.join("schema.json");
+        if let Some(ddl_log) = &self.ddl_log {
+            let contents = self
+                .schema_catalog
+                .to_json()
+                .map_err(|e| format!("Failed to save schema catalog: {e}"))?;
+            ddl_log
+                .record(Catalog::Schema, contents)
+                .map_err(|e| format!("Failed to log schema catalog: {e}"))?;
+        }
         self.schema_catalog
             .save(&schema_path)
             .map_err(|e| format!("Failed to save schema catalog: {e}"))
@@ -2963,6 +4170,42 @@ fn format_rule(rule: &crate::ast::Rule) -> String {
     rule.to_string()
 }
 
+/// Log the outcome of persist-layer recovery at startup
+fn log_recovery_report(report: &crate::storage::persist::RecoveryReport) {
+    if report.unclean_shutdown {
+        tracing::warn!(
+            wal_entries_replayed = report.wal_entries_replayed,
+            batches_verified = report.batches_verified,
+            "Recovered from unclean shutdown"
+        );
+    }
+    if report.wal_entries_skipped > 0 {
+        tracing::error!(
+            skipped = report.wal_entries_skipped,
+            "Corrupt WAL entries were skipped during recovery"
+        );
+    }
+    for corrupt in &report.corrupt_batches {
+        tracing::error!(
+            shard = %corrupt.shard,
+            batch_id = %corrupt.batch_id,
+            reason = %corrupt.reason,
+            quarantined_to = ?corrupt.quarantined_to,
+            "Batch file failed consistency check; its data was not loaded"
+        );
+    }
+}
+
+/// Load a knowledge graph's statistics catalog. Statistics only guide the
+/// planner, so an unreadable file is discarded rather than failing the load.
+fn load_statistics(data_dir: &std::path::Path, name: &str) -> StatisticsManager {
+    StatisticsManager::load(&data_dir.join("statistics.json"), StatsConfig::default())
+        .unwrap_or_else(|e| {
+            tracing::warn!(kg = %name, error = %e, "statistics_load_failed");
+            StatisticsManager::default()
+        })
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
@@ -5387,85 +6630,415 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    fn age_schema() -> RelationSchema {
+        use crate::schema::{CheckConstraint, ColumnSchema, SchemaType};
+        RelationSchema::new("person")
+            .with_column(ColumnSchema::new("name", SchemaType::String))
+            .with_column(ColumnSchema::new("age", SchemaType::Int).with_constraint(
+                CheckConstraint::Range {
+                    min: Some(0.0),
+                    max: Some(150.0),
+                },
+            ))
+    }
+
+    fn person(name: &str, age: i64) -> Tuple {
+        Tuple::new(vec![Value::string(name), Value::Int64(age)])
+    }
+
     #[test]
-    fn test_execute_with_rules_on() {
+    fn test_insert_enforces_check_constraints() {
         let temp = TempDir::new().unwrap();
         let config = create_test_config(temp.path().to_path_buf());
         let storage = StorageEngine::new(config).unwrap();
-
-        storage.create_knowledge_graph("ewr_kg").unwrap();
+        storage.create_knowledge_graph("chk_kg").unwrap();
         storage
-            .insert_into("ewr_kg", "edge", vec![(1, 2), (2, 3)])
+            .register_or_update_schema_in("chk_kg", age_schema())
             .unwrap();
 
-        let rule_def = make_simple_rule_def("path", "edge");
-        storage.register_rule_in("ewr_kg", &rule_def).unwrap();
+        let err = storage
+            .insert_tuples_into("chk_kg", "person", vec![person("a", 30), person("b", 200)])
+            .unwrap_err();
+        let StorageError::Validation(ValidationError::BatchRejected { violations, .. }) = err
+        else {
+            panic!("expected validation error, got {err}");
+        };
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].tuple_index, 1);
+        // All-or-nothing: the valid tuple was not written either
+        let stored = storage
+            .get_relation_metadata_in("chk_kg", "person")
+            .unwrap()
+            .map_or(0, |(_, count)| count);
+        assert_eq!(stored, 0);
 
-        let results = storage
-            .execute_query_with_rules_tuples_on("ewr_kg", "result(X, Y) <- path(X, Y)")
-            .unwrap();
-        assert!(!results.is_empty());
+        // Deletes are checked for shape only
+        assert!(storage
+            .delete_tuples_from("chk_kg", "person", vec![person("b", 200)])
+            .is_ok());
+        assert!(storage
+            .delete_tuples_from("chk_kg", "person", vec![Tuple::new(vec![Value::Int64(1)])])
+            .is_err());
     }
 
     #[test]
-    fn test_set_num_threads_already_initialized() {
-        // Rayon global pool is already initialized in test context.
-        // set_num_threads should still return Ok (silently succeeds).
-        let result = StorageEngine::set_num_threads(4);
-        // In test context, rayon pool is already built by other tests
-        // so this may succeed silently or error depending on error message format
-        // Either way, it should not panic
-        let _ = result;
+    fn test_insert_quarantines_failing_tuples() {
+        use crate::schema::{FailureAction, ValidationTiming};
+        let temp = TempDir::new().unwrap();
+        let mut config = create_test_config(temp.path().to_path_buf());
+        config.storage.validation = ValidationPolicy {
+            timing: ValidationTiming::Immediate,
+            on_failure: FailureAction::Quarantine,
+            ..ValidationPolicy::default()
+        };
+        let storage = StorageEngine::new(config).unwrap();
+        storage.create_knowledge_graph("q_kg").unwrap();
+        storage
+            .register_or_update_schema_in("q_kg", age_schema())
+            .unwrap();
+
+        let report = storage
+            .insert_tuples_checked(
+                "q_kg",
+                "person",
+                vec![person("a", 30), person("b", -5), person("c", 999)],
+            )
+            .unwrap();
+        assert_eq!(report.inserted, 1);
+        assert_eq!(report.quarantined, 2);
+        assert_eq!(report.violations.len(), 2);
+        assert!(storage
+            .get_relation_metadata_in("q_kg", "person_quarantine")
+            .unwrap()
+            .is_some());
+
+        // Type errors are never quarantined
+        let bad = Tuple::new(vec![Value::string("d"), Value::string("old")]);
+        assert!(storage
+            .insert_tuples_checked("q_kg", "person", vec![bad])
+            .is_err());
     }
 
-    // === Batch 26: Untested public method coverage ===
+    fn keyed_schema() -> RelationSchema {
+        use crate::schema::{ColumnSchema, SchemaType};
+        RelationSchema::new("person")
+            .with_column(ColumnSchema::new("name", SchemaType::String).primary_key())
+            .with_column(ColumnSchema::new("age", SchemaType::Int))
+    }
 
     #[test]
-    fn test_clear_rule_in() {
+    fn test_insert_rejects_duplicate_keys() {
         let temp = TempDir::new().unwrap();
         let config = create_test_config(temp.path().to_path_buf());
-        let mut storage = StorageEngine::new(config).unwrap();
+        let storage = StorageEngine::new(config).unwrap();
+        storage.create_knowledge_graph("pk_kg").unwrap();
+        storage
+            .register_or_update_schema_in("pk_kg", keyed_schema())
+            .unwrap();
 
-        storage.create_knowledge_graph("clear_rule_kg").unwrap();
-        let rule_def = make_simple_rule_def("reach", "edge");
         storage
-            .register_rule_in("clear_rule_kg", &rule_def)
+            .insert_tuples_into("pk_kg", "person", vec![person("a", 30)])
             .unwrap();
-        assert_eq!(
-            storage.rule_count_in("clear_rule_kg", "reach").unwrap(),
-            Some(1)
-        );
+        // Re-inserting the same fact is a plain duplicate
+        assert!(storage
+            .insert_tuples_into("pk_kg", "person", vec![person("a", 30)])
+            .is_ok());
 
-        storage.clear_rule_in("clear_rule_kg", "reach").unwrap();
-        // After clear, the rule count should be 0 (no clauses)
-        let count = storage.rule_count_in("clear_rule_kg", "reach").unwrap();
-        assert!(count.is_none() || count == Some(0));
+        let err = storage
+            .insert_tuples_into("pk_kg", "person", vec![person("a", 31)])
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            StorageError::Validation(ValidationError::BatchRejected { .. })
+        ));
+        let (_, count) = storage
+            .get_relation_metadata_in("pk_kg", "person")
+            .unwrap()
+            .unwrap();
+        assert_eq!(count, 1);
     }
 
     #[test]
-    fn test_execute_query_with_rules_binary_on() {
+    fn test_insert_upserts_on_key_conflict() {
+        use crate::schema::ConflictAction;
         let temp = TempDir::new().unwrap();
-        let config = create_test_config(temp.path().to_path_buf());
+        let mut config = create_test_config(temp.path().to_path_buf());
+        config.storage.validation.on_conflict = ConflictAction::Upsert;
         let storage = StorageEngine::new(config).unwrap();
+        storage.create_knowledge_graph("up_kg").unwrap();
+        storage
+            .register_or_update_schema_in("up_kg", keyed_schema())
+            .unwrap();
 
-        storage.create_knowledge_graph("qwrb_kg").unwrap();
         storage
-            .insert_into("qwrb_kg", "edge", vec![(1, 2), (2, 3)])
+            .insert_tuples_into("up_kg", "person", vec![person("a", 30), person("b", 40)])
+            .unwrap();
+        let report = storage
+            .insert_tuples_checked(
+                "up_kg",
+                "person",
+                vec![person("a", 31), person("c", 1), person("c", 2)],
+            )
             .unwrap();
+        assert_eq!(report.replaced, 1);
+        assert_eq!(report.inserted, 2);
 
-        let rule_def = RuleDef {
-            name: "path".to_string(),
-            rule: SerializableRule {
-                head_relation: "path".to_string(),
-                head_args: vec![
-                    SerializableTerm::Variable("X".to_string()),
-                    SerializableTerm::Variable("Y".to_string()),
-                ],
-                body: vec![SerializableBodyPred::Atom {
-                    relation: "edge".to_string(),
-                    args: vec![
-                        SerializableTerm::Variable("X".to_string()),
-                        SerializableTerm::Variable("Y".to_string()),
+        let mut rows = storage
+            .execute_query_with_rules_tuples_on("up_kg", "result(N) <- person(N)")
+            .unwrap();
+        rows.sort();
+        assert_eq!(rows, vec![person("a", 31), person("b", 40), person("c", 2)]);
+    }
+
+    #[test]
+    fn test_insert_fills_column_defaults() {
+        use crate::schema::{ColumnDefault, ColumnSchema, DefaultFunction, SchemaType};
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(temp.path().to_path_buf());
+        let storage = StorageEngine::new(config).unwrap();
+        storage.create_knowledge_graph("def_kg").unwrap();
+        let schema = RelationSchema::new("ticket")
+            .with_column(ColumnSchema::new("id", SchemaType::Int))
+            .with_column(
+                ColumnSchema::new("status", SchemaType::String)
+                    .with_default(ColumnDefault::Constant(Value::string("open"))),
+            )
+            .with_column(
+                ColumnSchema::new("created", SchemaType::Timestamp)
+                    .with_default(ColumnDefault::Function(DefaultFunction::TimeNow)),
+            );
+        storage
+            .register_or_update_schema_in("def_kg", schema)
+            .unwrap();
+
+        storage
+            .insert_tuples_into(
+                "def_kg",
+                "ticket",
+                vec![
+                    Tuple::new(vec![Value::Int64(1)]),
+                    Tuple::new(vec![Value::Int64(2), Value::string("closed")]),
+                ],
+            )
+            .unwrap();
+
+        let mut rows = storage
+            .execute_query_with_rules_tuples_on("def_kg", "result(I, S, T) <- ticket(I, S, T)")
+            .unwrap();
+        rows.sort();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].get(1), Some(&Value::string("open")));
+        assert_eq!(rows[1].get(1), Some(&Value::string("closed")));
+        assert!(matches!(rows[0].get(2), Some(Value::Timestamp(_))));
+
+        // Omitting a column without a default is still an arity error
+        assert!(storage
+            .insert_tuples_into("def_kg", "ticket", vec![Tuple::new(vec![])])
+            .is_err());
+    }
+
+    #[test]
+    fn test_catalogs_restored_from_wal_after_crash() {
+        use crate::schema::{ColumnSchema, SchemaType};
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(temp.path().to_path_buf());
+        let kg_dir = temp.path().join("ddl_kg");
+
+        {
+            let storage = StorageEngine::new(config.clone()).unwrap();
+            storage.create_knowledge_graph("ddl_kg").unwrap();
+            let schema = RelationSchema::new("person")
+                .with_column(ColumnSchema::new("name", SchemaType::String));
+            storage.register_schema_in("ddl_kg", schema).unwrap();
+            storage
+                .register_rule_in("ddl_kg", &make_simple_rule_def("adult", "person"))
+                .unwrap();
+            storage
+                .insert_tuples_into(
+                    "ddl_kg",
+                    "person",
+                    vec![Tuple::new(vec![Value::string("ada")])],
+                )
+                .unwrap();
+        }
+
+        // The catalog files never reached the disk; the WAL records did
+        fs::remove_file(kg_dir.join("schema.json")).unwrap();
+        fs::write(kg_dir.join("rules").join("catalog.json"), "").unwrap();
+
+        {
+            let storage = StorageEngine::new(config.clone()).unwrap();
+            assert!(storage.get_schema_in("ddl_kg", "person").unwrap().is_some());
+            assert_eq!(
+                storage.list_rules_in("ddl_kg").unwrap(),
+                vec!["adult".to_string()]
+            );
+            assert_eq!(
+                storage
+                    .execute_query_with_rules_tuples_on("ddl_kg", "result(N) <- person(N)")
+                    .unwrap()
+                    .len(),
+                1
+            );
+
+            // Once the catalog files are synced the records are dropped
+            storage.save_all().unwrap();
+        }
+        fs::remove_file(kg_dir.join("schema.json")).unwrap();
+        let storage = StorageEngine::new(config).unwrap();
+        assert!(storage.get_schema_in("ddl_kg", "person").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_dropped_knowledge_graph_catalogs_not_restored() {
+        use crate::schema::{ColumnSchema, SchemaType};
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(temp.path().to_path_buf());
+
+        {
+            let storage = StorageEngine::new(config.clone()).unwrap();
+            storage.create_knowledge_graph("reused").unwrap();
+            let schema = RelationSchema::new("person")
+                .with_column(ColumnSchema::new("name", SchemaType::String));
+            storage.register_schema_in("reused", schema).unwrap();
+            storage.drop_knowledge_graph("reused").unwrap();
+            storage.create_knowledge_graph("reused").unwrap();
+        }
+
+        let storage = StorageEngine::new(config).unwrap();
+        assert!(storage.get_schema_in("reused", "person").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_auto_increment_ids_survive_restart() {
+        use crate::schema::{ColumnSchema, SchemaType};
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(temp.path().to_path_buf());
+        let event = |id: i64, name: &str| Tuple::new(vec![Value::Int64(id), Value::string(name)]);
+        let named = |name: &str| Tuple::new(vec![Value::string(name)]);
+        let ids_query = "result(I, N) <- event(I, N)";
+
+        {
+            let storage = StorageEngine::new(config.clone()).unwrap();
+            storage.create_knowledge_graph("seq_kg").unwrap();
+            let schema = RelationSchema::new("event")
+                .with_column(
+                    ColumnSchema::new("id", SchemaType::Int)
+                        .primary_key()
+                        .auto_increment(),
+                )
+                .with_column(ColumnSchema::new("name", SchemaType::String));
+            storage
+                .register_or_update_schema_in("seq_kg", schema)
+                .unwrap();
+
+            storage
+                .insert_tuples_into("seq_kg", "event", vec![named("boot"), named("load")])
+                .unwrap();
+            // An explicit ID moves the sequence past it
+            storage
+                .insert_tuples_into("seq_kg", "event", vec![event(10, "manual")])
+                .unwrap();
+        }
+
+        let storage = StorageEngine::new(config).unwrap();
+        storage
+            .insert_tuples_into("seq_kg", "event", vec![named("restart")])
+            .unwrap();
+        let mut rows = storage
+            .execute_query_with_rules_tuples_on("seq_kg", ids_query)
+            .unwrap();
+        rows.sort();
+        assert_eq!(
+            rows,
+            vec![
+                event(1, "boot"),
+                event(2, "load"),
+                event(10, "manual"),
+                event(11, "restart")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_execute_with_rules_on() {
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(temp.path().to_path_buf());
+        let storage = StorageEngine::new(config).unwrap();
+
+        storage.create_knowledge_graph("ewr_kg").unwrap();
+        storage
+            .insert_into("ewr_kg", "edge", vec![(1, 2), (2, 3)])
+            .unwrap();
+
+        let rule_def = make_simple_rule_def("path", "edge");
+        storage.register_rule_in("ewr_kg", &rule_def).unwrap();
+
+        let results = storage
+            .execute_query_with_rules_tuples_on("ewr_kg", "result(X, Y) <- path(X, Y)")
+            .unwrap();
+        assert!(!results.is_empty());
+    }
+
+    #[test]
+    fn test_set_num_threads_already_initialized() {
+        // Rayon global pool is already initialized in test context.
+        // set_num_threads should still return Ok (silently succeeds).
+        let result = StorageEngine::set_num_threads(4);
+        // In test context, rayon pool is already built by other tests
+        // so this may succeed silently or error depending on error message format
+        // Either way, it should not panic
+        let _ = result;
+    }
+
+    // === Batch 26: Untested public method coverage ===
+
+    #[test]
+    fn test_clear_rule_in() {
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(temp.path().to_path_buf());
+        let mut storage = StorageEngine::new(config).unwrap();
+
+        storage.create_knowledge_graph("clear_rule_kg").unwrap();
+        let rule_def = make_simple_rule_def("reach", "edge");
+        storage
+            .register_rule_in("clear_rule_kg", &rule_def)
+            .unwrap();
+        assert_eq!(
+            storage.rule_count_in("clear_rule_kg", "reach").unwrap(),
+            Some(1)
+        );
+
+        storage.clear_rule_in("clear_rule_kg", "reach").unwrap();
+        // After clear, the rule count should be 0 (no clauses)
+        let count = storage.rule_count_in("clear_rule_kg", "reach").unwrap();
+        assert!(count.is_none() || count == Some(0));
+    }
+
+    #[test]
+    fn test_execute_query_with_rules_binary_on() {
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(temp.path().to_path_buf());
+        let storage = StorageEngine::new(config).unwrap();
+
+        storage.create_knowledge_graph("qwrb_kg").unwrap();
+        storage
+            .insert_into("qwrb_kg", "edge", vec![(1, 2), (2, 3)])
+            .unwrap();
+
+        let rule_def = RuleDef {
+            name: "path".to_string(),
+            rule: SerializableRule {
+                head_relation: "path".to_string(),
+                head_args: vec![
+                    SerializableTerm::Variable("X".to_string()),
+                    SerializableTerm::Variable("Y".to_string()),
+                ],
+                body: vec![SerializableBodyPred::Atom {
+                    relation: "edge".to_string(),
+                    args: vec![
+                        SerializableTerm::Variable("X".to_string()),
+                        SerializableTerm::Variable("Y".to_string()),
                     ],
                     negated: false,
                 }],
@@ -5517,6 +7090,99 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_alter_schema_adapts_old_batches_on_load() {
+        use crate::schema::{ColumnSchema, SchemaType};
+        use crate::value::Value;
+        let temp = TempDir::new().unwrap();
+        let query = "result(A, B, C) <- users(A, B, C)";
+
+        {
+            let config = create_test_config(temp.path().to_path_buf());
+            let storage = StorageEngine::new(config).unwrap();
+            storage.create_knowledge_graph("evolve").unwrap();
+            storage
+                .register_schema_in(
+                    "evolve",
+                    RelationSchema::new("users")
+                        .with_column(ColumnSchema::new("id", SchemaType::Int))
+                        .with_column(ColumnSchema::new("name", SchemaType::String)),
+                )
+                .unwrap();
+            storage
+                .insert_tuples_into(
+                    "evolve",
+                    "users",
+                    vec![
+                        Tuple::new(vec![Value::Int64(1), Value::string("ann")]),
+                        Tuple::new(vec![Value::Int64(2), Value::string("bob")]),
+                    ],
+                )
+                .unwrap();
+            storage.save_all().unwrap();
+
+            let schema = storage
+                .alter_schema_in(
+                    "evolve",
+                    "users",
+                    SchemaMigration::AddColumn {
+                        column: ColumnSchema::new("score", SchemaType::Int),
+                        default: Value::Int64(0),
+                    },
+                )
+                .unwrap();
+            assert_eq!(schema.arity(), 3);
+
+            // Tuples in memory are migrated immediately
+            let rows = storage.execute_query_tuples_on("evolve", query).unwrap();
+            assert_eq!(rows.len(), 2);
+
+            // New writes use the new shape
+            storage
+                .insert_tuples_into(
+                    "evolve",
+                    "users",
+                    vec![Tuple::new(vec![
+                        Value::Int64(3),
+                        Value::string("cy"),
+                        Value::Int64(9),
+                    ])],
+                )
+                .unwrap();
+            storage.save_all().unwrap();
+        }
+
+        // Old batches are adapted when the knowledge graph is reloaded
+        let config = create_test_config(temp.path().to_path_buf());
+        let storage = StorageEngine::new(config).unwrap();
+        let rows = storage.execute_query_tuples_on("evolve", query).unwrap();
+        assert_eq!(rows.len(), 3);
+        assert!(rows.contains(&Tuple::new(vec![
+            Value::Int64(1),
+            Value::string("ann"),
+            Value::Int64(0),
+        ])));
+        assert_eq!(
+            storage
+                .with_kg_read("evolve", |kg| Ok(kg
+                    .schema_catalog()
+                    .schema_version("users")))
+                .unwrap(),
+            2
+        );
+
+        // Invalid migrations leave the schema untouched
+        assert!(storage
+            .alter_schema_in(
+                "evolve",
+                "users",
+                SchemaMigration::DropColumn {
+                    name: "missing".to_string(),
+                },
+            )
+            .is_err());
+    }
+
     #[test]
     fn test_register_or_update_session_schema_in() {
         let temp = TempDir::new().unwrap();
@@ -5778,41 +7444,1645 @@ mod tests {
     }
 
     #[test]
-    fn test_max_query_cost_zero_means_unlimited() {
+    fn test_import_csv_registers_inferred_schema() {
         let temp = TempDir::new().unwrap();
-        let mut config = create_test_config(temp.path().to_path_buf());
-        config.storage.performance.max_query_cost = 0; // Unlimited
-        let storage = StorageEngine::new(config).unwrap();
+        let storage = StorageEngine::new(create_test_config(temp.path().to_path_buf())).unwrap();
+        storage.create_knowledge_graph("csv_kg").unwrap();
+        let path = temp.path().join("people.csv");
+        std::fs::write(
+            &path,
+            "id,name,joined\n1,alice,2024-01-01T00:00:00Z\n2,bob,2024-02-01 09:00:00\n",
+        )
+        .unwrap();
+
+        let (schema, inserted) = storage
+            .import_csv_in(
+                "csv_kg",
+                "person",
+                &path,
+                &CsvOptions::default(),
+                &CsvInference::default(),
+            )
+            .unwrap();
+        assert_eq!(inserted, 2);
+        assert_eq!(
+            storage.get_schema_in("csv_kg", "person").unwrap(),
+            Some(schema.clone())
+        );
+        assert_eq!(
+            schema.columns[2].data_type,
+            crate::schema::SchemaType::Timestamp
+        );
 
-        storage.create_knowledge_graph("nocost_kg").unwrap();
-        storage.insert_into("nocost_kg", "a", vec![(1, 2)]).unwrap();
-        storage.insert_into("nocost_kg", "b", vec![(2, 3)]).unwrap();
+        let rows = storage
+            .execute_query_tuples_on("csv_kg", "result(I, N) <- person(I, N, _)")
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+    }
 
-        // Join query - should succeed with unlimited cost
-        let result =
-            storage.execute_query_tuples_on("nocost_kg", "result(X, Y, Z) <- a(X, Y), b(Y, Z)");
-        assert!(
-            result.is_ok(),
-            "Query should succeed with max_query_cost=0 (unlimited)"
+    #[test]
+    fn test_import_csv_parallel_in_batches() {
+        let temp = TempDir::new().unwrap();
+        let storage = StorageEngine::new(create_test_config(temp.path().to_path_buf())).unwrap();
+        storage.create_knowledge_graph("bulk_kg").unwrap();
+        let path = temp.path().join("edges.csv");
+        let mut content = "src,dst\n".to_string();
+        for i in 0..100 {
+            content.push_str(&format!("{i},{}\n", i + 1));
+        }
+        content.push_str("bad,row\n");
+        std::fs::write(&path, &content).unwrap();
+
+        let bulk = BulkLoadOptions {
+            batch_rows: 16,
+            on_error: crate::storage::RowErrorPolicy::Collect,
+            ..BulkLoadOptions::default()
+        };
+        let mut updates = 0;
+        let (schema, report) = storage
+            .import_csv_parallel_in(
+                "bulk_kg",
+                "edge",
+                &path,
+                &CsvOptions::default(),
+                &CsvInference {
+                    sample_rows: 10,
+                    ..CsvInference::default()
+                },
+                &bulk,
+                |_| updates += 1,
+            )
+            .unwrap();
+        assert_eq!(report.rows_loaded, 100);
+        assert_eq!(report.rows_skipped, 1);
+        assert_eq!(report.errors[0].row, 102);
+        assert_eq!(updates, report.batches);
+        assert_eq!(
+            storage.get_schema_in("bulk_kg", "edge").unwrap(),
+            Some(schema)
         );
+        let rows = storage
+            .execute_query_tuples_on("bulk_kg", "result(X, Y) <- edge(X, Y)")
+            .unwrap();
+        assert_eq!(rows.len(), 100);
     }
 
     #[test]
-    fn test_max_query_cost_allows_simple_query() {
+    fn test_import_and_export_jsonl() {
         let temp = TempDir::new().unwrap();
-        let mut config = create_test_config(temp.path().to_path_buf());
-        config.storage.performance.max_query_cost = 1_000_000; // High threshold
-        let storage = StorageEngine::new(config).unwrap();
+        let storage = StorageEngine::new(create_test_config(temp.path().to_path_buf())).unwrap();
+        storage.create_knowledge_graph("jsonl_kg").unwrap();
+        let path = temp.path().join("traces.jsonl");
+        std::fs::write(
+            &path,
+            concat!(
+                r#"{"span": {"id": 1, "model": "m1"}, "emb": [0.1, 0.2]}"#,
+                "\n",
+                r#"{"span": {"id": 2, "model": "m2"}, "emb": [0.3, 0.4]}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+
+        let options = JsonlOptions::default()
+            .with_column("id", "span.id")
+            .with_column("model", "span.model")
+            .with_column("emb", "emb");
+        let (schema, inserted) = storage
+            .import_jsonl_in("jsonl_kg", "span", &path, &options)
+            .unwrap();
+        assert_eq!(inserted, 2);
+        assert_eq!(schema.column_names(), vec!["id", "model", "emb"]);
+        let rows = storage
+            .execute_query_tuples_on("jsonl_kg", "result(M) <- span(2, M, _)")
+            .unwrap();
+        assert_eq!(rows, vec![Tuple::new(vec![Value::string("m2")])]);
 
-        storage.create_knowledge_graph("simple_kg").unwrap();
+        let out = temp.path().join("export.jsonl");
+        assert_eq!(
+            storage.export_jsonl_in("jsonl_kg", "span", &out).unwrap(),
+            2
+        );
+        let (_, exported) = load_from_jsonl(&out, "span", &options).unwrap();
+        assert_eq!(exported.len(), 2);
+        assert!(storage
+            .export_jsonl_in("jsonl_kg", "missing", &out)
+            .is_err());
+    }
+
+    #[test]
+    fn test_avro_export_and_import() {
+        use crate::schema::{ColumnSchema, SchemaType};
+        let temp = TempDir::new().unwrap();
+        let storage = StorageEngine::new(create_test_config(temp.path().to_path_buf())).unwrap();
+        storage.create_knowledge_graph("avro_kg").unwrap();
         storage
-            .insert_into("simple_kg", "data", vec![(1, 10), (2, 20)])
+            .register_schema_in(
+                "avro_kg",
+                RelationSchema::new("click")
+                    .with_column(ColumnSchema::new("user", SchemaType::String))
+                    .with_column(ColumnSchema::new("count", SchemaType::Int)),
+            )
+            .unwrap();
+        storage
+            .insert_tuples_into(
+                "avro_kg",
+                "click",
+                vec![
+                    Tuple::new(vec![Value::string("alice"), Value::Int64(3)]),
+                    Tuple::new(vec![Value::string("bob"), Value::Int64(5)]),
+                ],
+            )
             .unwrap();
 
-        let result = storage.execute_query_tuples_on("simple_kg", "result(X, Y) <- data(X, Y)");
-        assert!(
-            result.is_ok(),
-            "Simple query should be under high cost threshold"
+        let path = temp.path().join("clicks.avro");
+        assert_eq!(
+            storage.export_avro_in("avro_kg", "click", &path).unwrap(),
+            2
         );
+
+        // Loading into a relation without a schema registers the mapped one
+        let (schema, inserted) = storage
+            .import_avro_in("avro_kg", "archived", &path)
+            .unwrap();
+        assert_eq!(inserted, 2);
+        assert_eq!(schema.column_names(), vec!["user", "count"]);
+        assert_eq!(schema.columns[1].data_type, SchemaType::Int);
+        let rows = storage
+            .execute_query_tuples_on("avro_kg", "result(U) <- archived(U, 5)")
+            .unwrap();
+        assert_eq!(rows, vec![Tuple::new(vec![Value::string("bob")])]);
+    }
+
+    #[test]
+    fn test_import_sqlite_tables() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("seed.db");
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE people (id INTEGER, name TEXT);
+            INSERT INTO people VALUES (1, 'alice'), (2, 'bob'), (3, 'carol');
+            CREATE TABLE follows (src INTEGER, dst INTEGER);
+            INSERT INTO follows VALUES (1, 2), (2, 3);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let mut storage =
+            StorageEngine::new(create_test_config(temp.path().to_path_buf())).unwrap();
+        storage.create_knowledge_graph("seed_kg").unwrap();
+        storage.use_knowledge_graph("seed_kg").unwrap();
+        let imported = storage
+            .import_sqlite(&path, &[("people", "person"), ("follows", "follows")])
+            .unwrap();
+        assert_eq!(imported.len(), 2);
+        assert_eq!(imported[0].0.column_names(), vec!["id", "name"]);
+        assert_eq!(imported[0].1, 3);
+        assert_eq!(imported[1].1, 2);
+
+        // Small batches insert the same rows
+        let batched = storage
+            .import_sqlite_in(
+                "seed_kg",
+                &path,
+                &[("people", "person_copy")],
+                &SqliteOptions { batch_size: 2 },
+            )
+            .unwrap();
+        assert_eq!(batched[0].1, 3);
+
+        let rows = storage
+            .execute_query_tuples_on("seed_kg", "result(N) <- follows(1, D), person(D, N)")
+            .unwrap();
+        assert_eq!(rows, vec![Tuple::new(vec![Value::string("bob")])]);
+        assert!(storage
+            .import_sqlite(&path, &[("missing", "missing")])
+            .is_err());
+    }
+
+    #[test]
+    fn test_analyze_statistics_survive_restart() {
+        let temp = TempDir::new().unwrap();
+        {
+            let storage =
+                StorageEngine::new(create_test_config(temp.path().to_path_buf())).unwrap();
+            storage.create_knowledge_graph("stats_kg").unwrap();
+            let tuples = (0..10)
+                .map(|i| Tuple::new(vec![Value::Int64(i % 3), Value::Int64(i)]))
+                .collect();
+            storage
+                .insert_tuples_into("stats_kg", "edge", tuples)
+                .unwrap();
+
+            assert!(storage.analyze_in("stats_kg", Some("missing")).is_err());
+            let stats = storage.analyze_in("stats_kg", None).unwrap();
+            assert_eq!(stats.len(), 1);
+            assert_eq!(stats[0].cardinality, 10);
+            assert_eq!(stats[0].column_stats[0].distinct_count, 3);
+
+            let snapshot = storage.get_snapshot_for("stats_kg").unwrap();
+            assert_eq!(snapshot.distinct_counts["edge"], vec![3, 10]);
+        }
+
+        let storage = StorageEngine::new(create_test_config(temp.path().to_path_buf())).unwrap();
+        let stats = storage.statistics_in("stats_kg").unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].column_stats[1].max_value, Some(Value::Int64(9)));
+
+        storage.drop_relation_in("stats_kg", "edge").unwrap();
+        assert!(storage.statistics_in("stats_kg").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_vector_dimension_mismatch_is_compile_error() {
+        use crate::schema::{ColumnSchema, SchemaType};
+        let temp = TempDir::new().unwrap();
+        let storage = StorageEngine::new(create_test_config(temp.path().to_path_buf())).unwrap();
+        storage.create_knowledge_graph("vec_kg").unwrap();
+        for (name, dim) in [("doc", 3), ("probe", 2)] {
+            let schema = RelationSchema::new(name).with_column(ColumnSchema::new(
+                "v",
+                SchemaType::Vector { dim: Some(dim) },
+            ));
+            storage
+                .register_or_update_schema_in("vec_kg", schema)
+                .unwrap();
+        }
+        storage
+            .insert_tuples_into(
+                "vec_kg",
+                "doc",
+                vec![Tuple::new(vec![Value::vector(vec![1.0, 0.0, 0.0])])],
+            )
+            .unwrap();
+        assert!(storage
+            .insert_tuples_into(
+                "vec_kg",
+                "probe",
+                vec![Tuple::new(vec![Value::vector(vec![1.0, 0.0, 0.0])])],
+            )
+            .is_err());
+        storage
+            .insert_tuples_into(
+                "vec_kg",
+                "probe",
+                vec![Tuple::new(vec![Value::vector(vec![1.0, 0.0])])],
+            )
+            .unwrap();
+
+        let err = storage
+            .execute_query_tuples_on(
+                "vec_kg",
+                "result(D) <- doc(V), probe(Q), D = euclidean(V, Q)",
+            )
+            .unwrap_err();
+        assert!(
+            format!("{err}").contains("vector<3> vs vector<2>"),
+            "unexpected error: {err}"
+        );
+        let rows = storage
+            .execute_query_tuples_on(
+                "vec_kg",
+                "result(D) <- doc(V), D = euclidean(V, [1.0, 0.0, 0.0])",
+            )
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+    }
+
+    #[test]
+    fn test_coercion_modes_for_mixed_integer_joins() {
+        use crate::schema::{ColumnSchema, SchemaType};
+        let query = "result(X) <- narrow(X), wide(X)";
+        let load = |storage: &StorageEngine| {
+            storage.create_knowledge_graph("co_kg").unwrap();
+            storage
+                .insert_tuples_into("co_kg", "narrow", vec![Tuple::new(vec![Value::Int32(7)])])
+                .unwrap();
+            storage
+                .insert_tuples_into("co_kg", "wide", vec![Tuple::new(vec![Value::Int64(7)])])
+                .unwrap();
+        };
+
+        // Lenient: integers compare by value, so the join matches
+        let temp = TempDir::new().unwrap();
+        let storage = StorageEngine::new(create_test_config(temp.path().to_path_buf())).unwrap();
+        load(&storage);
+        let rows = storage.execute_query_tuples_on("co_kg", query).unwrap();
+        assert_eq!(rows.len(), 1);
+
+        // Lenient with a schema: Int32 is normalized to Int64 on insert
+        let schema =
+            RelationSchema::new("typed").with_column(ColumnSchema::new("n", SchemaType::Int));
+        storage
+            .register_or_update_schema_in("co_kg", schema)
+            .unwrap();
+        storage
+            .insert_tuples_into("co_kg", "typed", vec![Tuple::new(vec![Value::Int32(3)])])
+            .unwrap();
+        let stored = storage
+            .execute_query_tuples_on("co_kg", "result(N) <- typed(N)")
+            .unwrap();
+        assert!(matches!(stored[0].get(0), Some(Value::Int64(3))));
+
+        // Strict: the mixed-type join is rejected
+        let temp = TempDir::new().unwrap();
+        let mut config = create_test_config(temp.path().to_path_buf());
+        config.storage.coercion = CoercionMode::Strict;
+        let storage = StorageEngine::new(config).unwrap();
+        load(&storage);
+        let err = storage.execute_query_tuples_on("co_kg", query).unwrap_err();
+        assert!(
+            format!("{err}").contains("Mixed-type join"),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn test_max_query_cost_zero_means_unlimited() {
+        let temp = TempDir::new().unwrap();
+        let mut config = create_test_config(temp.path().to_path_buf());
+        config.storage.performance.max_query_cost = 0; // Unlimited
+        let storage = StorageEngine::new(config).unwrap();
+
+        storage.create_knowledge_graph("nocost_kg").unwrap();
+        storage.insert_into("nocost_kg", "a", vec![(1, 2)]).unwrap();
+        storage.insert_into("nocost_kg", "b", vec![(2, 3)]).unwrap();
+
+        // Join query - should succeed with unlimited cost
+        let result =
+            storage.execute_query_tuples_on("nocost_kg", "result(X, Y, Z) <- a(X, Y), b(Y, Z)");
+        assert!(
+            result.is_ok(),
+            "Query should succeed with max_query_cost=0 (unlimited)"
+        );
+    }
+
+    #[test]
+    fn test_max_query_cost_allows_simple_query() {
+        let temp = TempDir::new().unwrap();
+        let mut config = create_test_config(temp.path().to_path_buf());
+        config.storage.performance.max_query_cost = 1_000_000; // High threshold
+        let storage = StorageEngine::new(config).unwrap();
+
+        storage.create_knowledge_graph("simple_kg").unwrap();
+        storage
+            .insert_into("simple_kg", "data", vec![(1, 10), (2, 20)])
+            .unwrap();
+
+        let result = storage.execute_query_tuples_on("simple_kg", "result(X, Y) <- data(X, Y)");
+        assert!(
+            result.is_ok(),
+            "Simple query should be under high cost threshold"
+        );
+    }
+
+    #[test]
+    fn test_restore_knowledge_graph_to_lsn_and_timestamp() {
+        use crate::storage::persist::RecoveryTarget;
+        let temp = TempDir::new().unwrap();
+        let mut config = create_test_config(temp.path().to_path_buf());
+        config.storage.persist.wal_archive = true;
+        let storage = StorageEngine::new(config).unwrap();
+        storage.create_knowledge_graph("pitr").unwrap();
+
+        let row = |i: i64| Tuple::new(vec![Value::Int64(i)]);
+        let contents = || {
+            let mut tuples = storage
+                .with_kg_read("pitr", |db| {
+                    Ok(db.engine.input_tuples.get("t").cloned().unwrap_or_default())
+                })
+                .unwrap();
+            tuples.sort();
+            tuples
+        };
+
+        storage
+            .insert_tuples_into("pitr", "t", vec![row(1), row(2), row(3)])
+            .unwrap();
+        let lsn = storage.logical_time.load(Ordering::SeqCst) - 1;
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let before_delete = Utc::now().timestamp_millis();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+
+        // A bad bulk delete, then more writes
+        storage
+            .delete_tuples_from("pitr", "t", vec![row(1), row(2), row(3)])
+            .unwrap();
+        storage
+            .insert_tuples_into("pitr", "t", vec![row(4)])
+            .unwrap();
+
+        let summary = storage
+            .restore_knowledge_graph_to("pitr", RecoveryTarget::Lsn(lsn))
+            .unwrap();
+        assert_eq!(summary.relations, 1);
+        assert_eq!(summary.inserted, 3);
+        assert_eq!(summary.deleted, 1);
+        assert_eq!(contents(), vec![row(1), row(2), row(3)]);
+
+        // Undo the restore by restoring past it, then restore by timestamp
+        storage
+            .restore_knowledge_graph_to("pitr", RecoveryTarget::Lsn(lsn + 2))
+            .unwrap();
+        assert_eq!(contents(), vec![row(4)]);
+        storage
+            .restore_knowledge_graph_to("pitr", RecoveryTarget::Timestamp(before_delete))
+            .unwrap();
+        assert_eq!(contents(), vec![row(1), row(2), row(3)]);
+
+        assert!(storage
+            .restore_knowledge_graph_to("pitr", RecoveryTarget::Timestamp(0))
+            .is_err());
+    }
+
+    #[test]
+    fn test_write_batch_is_published_atomically() {
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(temp.path().to_path_buf());
+        let storage = StorageEngine::new(config).unwrap();
+        storage.create_knowledge_graph("mvcc").unwrap();
+        storage
+            .register_or_update_schema_in("mvcc", keyed_schema())
+            .unwrap();
+        storage
+            .insert_tuples_into("mvcc", "person", vec![person("a", 30), person("b", 40)])
+            .unwrap();
+        let before = storage.get_snapshot_for("mvcc").unwrap();
+
+        // Moving a key to a new row is accepted when the old row is deleted
+        // earlier in the same batch
+        let report = storage
+            .apply_batch_in(
+                "mvcc",
+                vec![
+                    WriteOp::Delete("person".to_string(), vec![person("a", 30)]),
+                    WriteOp::Insert("person".to_string(), vec![person("a", 31)]),
+                    WriteOp::Insert(
+                        "edge".to_string(),
+                        vec![Tuple::new(vec![Value::Int32(1), Value::Int32(2)])],
+                    ),
+                ],
+            )
+            .unwrap();
+        assert_eq!((report.deleted, report.inserted), (1, 2));
+
+        // One snapshot holds the whole batch; the old one still holds none of it
+        let after = storage.get_snapshot_for("mvcc").unwrap();
+        assert_eq!(after.lsn, report.lsn);
+        assert!(after.lsn > before.lsn);
+        assert!(after.input_tuples["person"].contains(&person("a", 31)));
+        assert!(!after.input_tuples["person"].contains(&person("a", 30)));
+        assert_eq!(after.input_tuples["edge"].len(), 1);
+        assert!(before.input_tuples["person"].contains(&person("a", 30)));
+        assert!(!before.input_tuples.contains_key("edge"));
+
+        // A rejected step rejects the whole batch
+        let err = storage.apply_batch_in(
+            "mvcc",
+            vec![
+                WriteOp::Delete("person".to_string(), vec![person("b", 40)]),
+                WriteOp::Insert("person".to_string(), vec![person("a", 99)]),
+            ],
+        );
+        assert!(err.is_err());
+        let unchanged = storage.get_snapshot_for("mvcc").unwrap();
+        assert!(Arc::ptr_eq(&unchanged, &after));
+        assert!(unchanged.input_tuples["person"].contains(&person("b", 40)));
+    }
+
+    #[test]
+    fn test_transaction_commits_atomically() {
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(temp.path().to_path_buf());
+        let storage = StorageEngine::new(config).unwrap();
+        storage.create_knowledge_graph("tx").unwrap();
+        let one = || vec![Tuple::new(vec![Value::Int32(1)])];
+
+        let mut tx = storage.begin_transaction("tx").unwrap();
+        tx.insert("base_t", one());
+        tx.register_rule(make_simple_rule_def("view_t", "base_t"));
+        assert_eq!(tx.len(), 2);
+        let before = storage.get_snapshot_for("tx").unwrap();
+
+        // Nothing is visible until commit
+        assert!(!before.input_tuples.contains_key("base_t"));
+        assert!(storage.list_rules_in("tx").unwrap().is_empty());
+
+        let report = storage.commit_transaction(tx).unwrap();
+        assert_eq!((report.batch.inserted, report.rule_changes), (1, 1));
+        let after = storage.get_snapshot_for("tx").unwrap();
+        assert_eq!(after.lsn, report.batch.lsn);
+        assert_eq!(after.input_tuples["base_t"], one());
+        assert_eq!(after.rules.len(), 1);
+
+        // A rejected rule change rejects the data writes with it
+        let mut tx = storage.begin_transaction("tx").unwrap();
+        tx.insert("base_t", vec![Tuple::new(vec![Value::Int32(2)])]);
+        tx.drop_rule("missing");
+        assert!(storage.commit_transaction(tx).is_err());
+        let unchanged = storage.get_snapshot_for("tx").unwrap();
+        assert!(Arc::ptr_eq(&unchanged, &after));
+
+        // Rolling back discards everything queued
+        let mut tx = storage.begin_transaction("tx").unwrap();
+        tx.delete("base_t", one());
+        tx.drop_rule("view_t");
+        assert_eq!(tx.rollback(), 2);
+        assert!(Arc::ptr_eq(
+            &storage.get_snapshot_for("tx").unwrap(),
+            &after
+        ));
+        assert_eq!(
+            storage.list_rules_in("tx").unwrap(),
+            vec!["view_t".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_drop_relation_releases_interned_strings() {
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(temp.path().to_path_buf());
+        let storage = StorageEngine::new(config).unwrap();
+        storage.create_knowledge_graph("strings").unwrap();
+        let tag = "intern-release-test-tag";
+        storage
+            .insert_tuples_into(
+                "strings",
+                "tag",
+                vec![Tuple::new(vec![Value::interned(tag)])],
+            )
+            .unwrap();
+        let pooled = Arc::downgrade(&crate::value::intern::intern(tag));
+        assert!(pooled.upgrade().is_some());
+
+        // Neither the pool nor any tuple keeps the string alive afterwards
+        storage.drop_relation_in("strings", "tag").unwrap();
+        assert!(pooled.upgrade().is_none());
+    }
+
+    #[test]
+    fn test_failed_transaction_step_is_rolled_back() {
+        use std::sync::atomic::AtomicBool;
+
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(temp.path().to_path_buf());
+        let int = |i: i32| Tuple::new(vec![Value::Int32(i)]);
+        let storage = StorageEngine::new(config.clone()).unwrap();
+        storage.create_knowledge_graph("tx").unwrap();
+        storage.create_knowledge_graph("other").unwrap();
+        storage
+            .insert_tuples_into("other", "edge", vec![Tuple::from_pair(1, 2)])
+            .unwrap();
+        storage
+            .insert_tuples_into("tx", "base_t", vec![int(1), int(2)])
+            .unwrap();
+        // The incremental engine cannot maintain rules reading another
+        // knowledge graph, which only shows once the rule is applied
+        storage
+            .knowledge_graphs
+            .get("tx")
+            .unwrap()
+            .write()
+            .enable_incremental()
+            .unwrap();
+        let before = storage.get_snapshot_for("tx").unwrap();
+        let mut tuples_before = before.input_tuples["base_t"].clone();
+        tuples_before.sort();
+
+        let mut tx = storage.begin_transaction("tx").unwrap();
+        tx.insert("base_t", vec![int(3)]);
+        tx.delete("base_t", vec![int(1)]);
+        tx.register_rule(make_simple_rule_def("view_t", "base_t"));
+        tx.register_rule(
+            crate::statement::parse_rule_definition("mirror(X, Y) <- other.edge(X, Y)").unwrap(),
+        );
+
+        // A reader running alongside the commit never sees its writes
+        let done = AtomicBool::new(false);
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                while !done.load(Ordering::SeqCst) {
+                    let snapshot = storage.get_snapshot_for("tx").unwrap();
+                    let mut tuples = snapshot.input_tuples["base_t"].clone();
+                    tuples.sort();
+                    assert_eq!(tuples, tuples_before);
+                    assert!(snapshot.rules.is_empty());
+                }
+            });
+            assert!(storage.commit_transaction(tx).is_err());
+            done.store(true, Ordering::SeqCst);
+        });
+
+        // Memory, the rule catalog and the persist layer are as before
+        assert!(Arc::ptr_eq(
+            &storage.get_snapshot_for("tx").unwrap(),
+            &before
+        ));
+        assert!(storage.list_rules_in("tx").unwrap().is_empty());
+        let stored = || {
+            let mut rows = storage
+                .with_kg_read("tx", |db| Ok(db.engine.input_tuples["base_t"].clone()))
+                .unwrap();
+            rows.sort();
+            rows
+        };
+        assert_eq!(stored(), tuples_before);
+        drop(storage);
+        let storage = StorageEngine::new(config).unwrap();
+        let mut rows = storage.get_snapshot_for("tx").unwrap().input_tuples["base_t"].clone();
+        rows.sort();
+        assert_eq!(rows, tuples_before);
+        assert!(storage.list_rules_in("tx").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_partitioned_relation_storage() {
+        use crate::schema::{ColumnSchema, KeyFilter, PartitionSpec, SchemaType};
+        use std::ops::Bound;
+        let temp = TempDir::new().unwrap();
+        let event = |user: i64, day: i64| Tuple::new(vec![Value::Int64(user), Value::Int64(day)]);
+
+        {
+            let config = create_test_config(temp.path().to_path_buf());
+            let storage = StorageEngine::new(config).unwrap();
+            storage.create_knowledge_graph("parts").unwrap();
+            storage
+                .register_schema_in(
+                    "parts",
+                    RelationSchema::new("events")
+                        .with_column(ColumnSchema::new("user", SchemaType::Int))
+                        .with_column(ColumnSchema::new("day", SchemaType::Int)),
+                )
+                .unwrap();
+            // Written before partitioning: moved into the partitions
+            storage
+                .insert_tuples_into("parts", "events", vec![event(1, 5), event(2, 15)])
+                .unwrap();
+
+            let spec = PartitionSpec::range("day", vec![Value::Int64(10), Value::Int64(20)]);
+            let schema = storage
+                .partition_relation_in("parts", "events", Some(spec.clone()))
+                .unwrap();
+            assert_eq!(schema.partition, Some(spec.clone()));
+            storage
+                .insert_tuples_into("parts", "events", vec![event(3, 25), event(4, 12)])
+                .unwrap();
+            storage
+                .delete_tuples_from("parts", "events", vec![event(1, 5)])
+                .unwrap();
+
+            let mut shards = storage.relation_shards("parts", "events").unwrap();
+            shards.sort();
+            let mut layout = spec.shard_names("parts:events");
+            layout.sort();
+            assert_eq!(shards, layout);
+
+            // Only the middle partition is read
+            let filter = KeyFilter::Range(
+                Bound::Included(Value::Int64(10)),
+                Bound::Excluded(Value::Int64(20)),
+            );
+            let mut rows = storage
+                .scan_relation_in("parts", "events", Some(&filter))
+                .unwrap();
+            rows.sort();
+            assert_eq!(rows, vec![event(2, 15), event(4, 12)]);
+            assert_eq!(
+                storage
+                    .scan_relation_in("parts", "events", None)
+                    .unwrap()
+                    .len(),
+                3
+            );
+
+            // The key column cannot be dropped
+            assert!(storage
+                .alter_schema_in(
+                    "parts",
+                    "events",
+                    SchemaMigration::DropColumn {
+                        name: "day".to_string(),
+                    },
+                )
+                .is_err());
+            storage.save_all().unwrap();
+        }
+
+        // Partitions are merged back into one relation on load
+        let config = create_test_config(temp.path().to_path_buf());
+        let storage = StorageEngine::new(config).unwrap();
+        let rows = storage
+            .execute_query_tuples_on("parts", "result(U, D) <- events(U, D)")
+            .unwrap();
+        assert_eq!(rows.len(), 3);
+        assert!(!rows.contains(&event(1, 5)));
+
+        // Removing the key moves everything back into a single shard
+        let schema = storage
+            .partition_relation_in("parts", "events", None)
+            .unwrap();
+        assert_eq!(schema.partition, None);
+        assert_eq!(
+            storage.relation_shards("parts", "events").unwrap(),
+            vec!["parts:events".to_string()]
+        );
+        assert_eq!(
+            storage
+                .scan_relation_in("parts", "events", None)
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_lazy_loading_and_eviction() {
+        let temp = TempDir::new().unwrap();
+        let row = |i: i64| Tuple::new(vec![Value::Int64(i)]);
+        {
+            let config = create_test_config(temp.path().to_path_buf());
+            let storage = StorageEngine::new(config).unwrap();
+            storage.create_knowledge_graph("big").unwrap();
+            for relation in ["a", "b", "c"] {
+                storage
+                    .insert_tuples_into("big", relation, (0..100).map(row).collect())
+                    .unwrap();
+            }
+            storage.save_all().unwrap();
+        }
+
+        let mut config = create_test_config(temp.path().to_path_buf());
+        config.storage.residency.lazy_load = true;
+        config.storage.residency.max_resident_bytes = 150 * std::mem::size_of::<Tuple>();
+        let storage = StorageEngine::new(config).unwrap();
+        let resident = |storage: &StorageEngine| {
+            storage
+                .with_kg_read("big", |db| {
+                    let mut names: Vec<String> = db.engine.input_tuples.keys().cloned().collect();
+                    names.sort();
+                    Ok(names)
+                })
+                .unwrap()
+        };
+        assert!(resident(&storage).is_empty());
+        assert_eq!(storage.list_relations_in("big").unwrap().len(), 3);
+
+        let query = |relation: &str| {
+            storage
+                .execute_query_tuples_on("big", &format!("result(X) <- {relation}(X)"))
+                .unwrap()
+                .len()
+        };
+        assert_eq!(query("a"), 100);
+        assert_eq!(resident(&storage), vec!["a".to_string()]);
+
+        // Loading b goes over the cap, evicting the least recently used a
+        assert_eq!(query("b"), 100);
+        assert_eq!(resident(&storage), vec!["b".to_string()]);
+
+        // Writes load their relation before deduplicating against it
+        let (inserted, duplicates) = storage
+            .insert_tuples_into("big", "a", vec![row(5), row(100)])
+            .unwrap();
+        assert_eq!((inserted, duplicates), (1, 1));
+        assert_eq!(resident(&storage), vec!["a".to_string()]);
+        assert_eq!(query("a"), 101);
+    }
+
+    #[test]
+    fn test_retention_sweep() {
+        use crate::schema::{ColumnSchema, RetentionPolicy, SchemaType};
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(temp.path().to_path_buf());
+        let storage = StorageEngine::new(config).unwrap();
+        storage.create_knowledge_graph("ttl").unwrap();
+        storage
+            .register_schema_in(
+                "ttl",
+                RelationSchema::new("events")
+                    .with_column(ColumnSchema::new("id", SchemaType::Int))
+                    .with_column(ColumnSchema::new("ts", SchemaType::Timestamp)),
+            )
+            .unwrap();
+        let seen = RuleDef {
+            name: "seen".to_string(),
+            rule: SerializableRule {
+                head_relation: "seen".to_string(),
+                head_args: vec![SerializableTerm::Variable("I".to_string())],
+                body: vec![SerializableBodyPred::Atom {
+                    relation: "events".to_string(),
+                    args: vec![
+                        SerializableTerm::Variable("I".to_string()),
+                        SerializableTerm::Variable("T".to_string()),
+                    ],
+                    negated: false,
+                }],
+            },
+        };
+        storage.register_rule_in("ttl", &seen).unwrap();
+
+        let now = Utc::now().timestamp_millis();
+        let event = |id: i64, ts: i64| Tuple::new(vec![Value::Int64(id), Value::Timestamp(ts)]);
+        storage
+            .insert_tuples_into(
+                "ttl",
+                "events",
+                vec![
+                    event(1, now - 7_200_000),
+                    event(2, now),
+                    event(3, now - 60_000),
+                ],
+            )
+            .unwrap();
+
+        // No policy: nothing expires
+        assert_eq!(storage.sweep_expired_in("ttl").unwrap(), 0);
+
+        let policy = RetentionPolicy::MaxAge {
+            column: "ts".to_string(),
+            max_age_ms: 3_600_000,
+        };
+        let schema = storage
+            .set_retention_in("ttl", "events", Some(policy.clone()))
+            .unwrap();
+        assert_eq!(schema.retention, Some(policy));
+        assert_eq!(storage.sweep_expired(), 1);
+
+        let mut ids = storage
+            .execute_query_with_rules_tuples_on("ttl", "result(I) <- seen(I)")
+            .unwrap();
+        ids.sort();
+        assert_eq!(
+            ids,
+            vec![
+                Tuple::new(vec![Value::Int64(2)]),
+                Tuple::new(vec![Value::Int64(3)]),
+            ]
+        );
+
+        // Keep only the latest row by timestamp
+        storage
+            .set_retention_in(
+                "ttl",
+                "events",
+                Some(RetentionPolicy::MaxRows {
+                    rows: 1,
+                    column: Some("ts".to_string()),
+                }),
+            )
+            .unwrap();
+        assert_eq!(storage.sweep_expired_in("ttl").unwrap(), 1);
+        assert_eq!(
+            storage
+                .execute_query_with_rules_tuples_on("ttl", "result(I, T) <- events(I, T)")
+                .unwrap(),
+            vec![event(2, now)]
+        );
+
+        // The retention column cannot be dropped
+        assert!(storage
+            .alter_schema_in(
+                "ttl",
+                "events",
+                SchemaMigration::DropColumn {
+                    name: "ts".to_string(),
+                },
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_vector_index_maintained_and_persisted() {
+        use crate::index_manager::{DistanceMetric, HnswConfig, IndexType};
+        use crate::schema::{ColumnSchema, SchemaType};
+        let temp = TempDir::new().unwrap();
+        let emb = |id: i64, x: f32| Tuple::new(vec![Value::Int64(id), Value::vector(vec![x, 0.0])]);
+        let nearest = |storage: &StorageEngine| -> Vec<Tuple> {
+            storage
+                .execute_query_tuples_on(
+                    "vecs",
+                    "near(top_k<2, Id, D:asc>) <- emb(Id, V), D = euclidean(V, [10.0, 0.0])",
+                )
+                .unwrap()
+        };
+        let index_stats = |storage: &StorageEngine| {
+            storage
+                .with_kg_read("vecs", |kg| kg.incremental().unwrap().get_index_stats(None))
+                .unwrap()
+        };
+
+        {
+            let config = create_test_config(temp.path().to_path_buf());
+            let storage = StorageEngine::new(config).unwrap();
+            storage.create_knowledge_graph("vecs").unwrap();
+            storage
+                .register_schema_in(
+                    "vecs",
+                    RelationSchema::new("emb")
+                        .with_column(ColumnSchema::new("id", SchemaType::Int))
+                        .with_column(ColumnSchema::new("v", SchemaType::Vector { dim: None })),
+                )
+                .unwrap();
+            storage
+                .insert_tuples_into("vecs", "emb", (0..10).map(|i| emb(i, i as f32)).collect())
+                .unwrap();
+
+            let index = RegisteredIndex {
+                name: "emb_idx".to_string(),
+                relation: "emb".to_string(),
+                column_idx: 1,
+                column_name: "v".to_string(),
+                index_type: IndexType::Hnsw(HnswConfig {
+                    metric: DistanceMetric::Euclidean,
+                    ..HnswConfig::default()
+                }),
+            };
+            let count = storage
+                .with_kg_mut("vecs", |kg| {
+                    kg.create_vector_index(index).map_err(|e| e.to_string())
+                })
+                .unwrap();
+            assert_eq!(count, 10);
+
+            // Inserts and deletes are applied to the index as they happen
+            storage
+                .insert_tuples_into("vecs", "emb", vec![emb(100, 9.9)])
+                .unwrap();
+            storage
+                .delete_tuples_from("vecs", "emb", vec![emb(9, 9.0)])
+                .unwrap();
+            let stats = index_stats(&storage);
+            assert!(stats[0].valid);
+            assert_eq!(stats[0].tuple_count, 10);
+            let rows = nearest(&storage);
+            let ids: Vec<&Value> = rows.iter().filter_map(|t| t.get(0)).collect();
+            assert_eq!(rows.len(), 2);
+            assert!(ids.contains(&&Value::Int64(100)));
+            assert!(ids.contains(&&Value::Int64(8)));
+            storage.save_all().unwrap();
+        }
+
+        // The index is back after a restart and keeps serving queries
+        let config = create_test_config(temp.path().to_path_buf());
+        let storage = StorageEngine::new(config).unwrap();
+        let stats = index_stats(&storage);
+        assert_eq!(stats.len(), 1);
+        assert!(stats[0].valid);
+        assert_eq!(nearest(&storage).len(), 2);
+
+        // Dropping it is persisted too
+        storage
+            .with_kg_read("vecs", |kg| {
+                kg.drop_vector_index("emb_idx").map_err(|e| e.to_string())
+            })
+            .unwrap();
+        assert!(!IndexManager::has_saved_indexes(&temp.path().join("vecs")));
+    }
+
+    #[test]
+    fn test_lsh_index_published_as_relation() {
+        use crate::index_manager::{IndexType, LshConfig};
+        use crate::schema::{ColumnSchema, SchemaType};
+        let temp = TempDir::new().unwrap();
+        let emb = |id: i64, v: Vec<f32>| Tuple::new(vec![Value::Int64(id), Value::vector(v)]);
+        let buckets = |storage: &StorageEngine| {
+            storage
+                .execute_query_tuples_on("lsh", "b(T, B, Id) <- emb_lsh(T, B, Id)")
+                .unwrap()
+        };
+        let lsh_index = |name: &str| RegisteredIndex {
+            name: name.to_string(),
+            relation: "emb".to_string(),
+            column_idx: 1,
+            column_name: "v".to_string(),
+            index_type: IndexType::Lsh(LshConfig {
+                tables: 2,
+                hyperplanes: 4,
+            }),
+        };
+
+        {
+            let config = create_test_config(temp.path().to_path_buf());
+            let storage = StorageEngine::new(config).unwrap();
+            storage.create_knowledge_graph("lsh").unwrap();
+            storage
+                .register_schema_in(
+                    "lsh",
+                    RelationSchema::new("emb")
+                        .with_column(ColumnSchema::new("id", SchemaType::Int))
+                        .with_column(ColumnSchema::new("v", SchemaType::Vector { dim: None })),
+                )
+                .unwrap();
+            storage
+                .insert_tuples_into(
+                    "lsh",
+                    "emb",
+                    vec![emb(1, vec![1.0, 0.0]), emb(2, vec![0.0, 1.0])],
+                )
+                .unwrap();
+
+            // An LSH index may not shadow an existing relation
+            assert!(storage
+                .with_kg_mut("lsh", |kg| kg
+                    .create_vector_index(lsh_index("emb"))
+                    .map_err(|e| e.to_string()))
+                .is_err());
+            storage
+                .with_kg_mut("lsh", |kg| {
+                    kg.create_vector_index(lsh_index("emb_lsh"))
+                        .map_err(|e| e.to_string())
+                })
+                .unwrap();
+            assert_eq!(buckets(&storage).len(), 4);
+
+            // One (table, bucket, id) row per table follows every write
+            storage
+                .insert_tuples_into("lsh", "emb", vec![emb(3, vec![-1.0, -1.0])])
+                .unwrap();
+            assert_eq!(buckets(&storage).len(), 6);
+            storage
+                .delete_tuples_from("lsh", "emb", vec![emb(2, vec![0.0, 1.0])])
+                .unwrap();
+            let rows = buckets(&storage);
+            assert_eq!(rows.len(), 4);
+            assert!(rows.iter().all(|row| row.get(2) != Some(&Value::Int64(2))));
+
+            // Joining on the mappings finds the vectors in the query's buckets
+            let candidates = storage
+                .execute_query_tuples_on(
+                    "lsh",
+                    "cand(Id) <- emb_lsh(T, B, Id), QB = lsh_bucket([1.0, 0.0], T, 4), QB = B",
+                )
+                .unwrap();
+            assert!(candidates.contains(&Tuple::new(vec![Value::Int64(1)])));
+            storage.save_all().unwrap();
+        }
+
+        // The mappings are rebuilt when the knowledge graph is loaded
+        let config = create_test_config(temp.path().to_path_buf());
+        let storage = StorageEngine::new(config).unwrap();
+        assert_eq!(buckets(&storage).len(), 4);
+    }
+
+    #[test]
+    fn test_qualified_relation_from_other_kg() {
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(temp.path().to_path_buf());
+        let storage = StorageEngine::new(config).unwrap();
+        storage.create_knowledge_graph("main").unwrap();
+        storage.create_knowledge_graph("analytics").unwrap();
+        storage
+            .insert_tuples_into(
+                "main",
+                "edge",
+                vec![Tuple::from_pair(1, 2), Tuple::from_pair(2, 3)],
+            )
+            .unwrap();
+        storage
+            .insert_tuples_into(
+                "analytics",
+                "edge",
+                vec![Tuple::from_pair(2, 3), Tuple::from_pair(5, 6)],
+            )
+            .unwrap();
+
+        let shared = storage
+            .execute_query_tuples_on("main", "shared(X, Y) <- edge(X, Y), analytics.edge(X, Y)")
+            .unwrap();
+        assert_eq!(shared, vec![Tuple::from_pair(2, 3)]);
+
+        // Persistent rules can read foreign relations too
+        let rule = crate::statement::parse_rule_definition("mirror(X, Y) <- analytics.edge(X, Y)")
+            .unwrap();
+        storage.register_rule_in("main", &rule).unwrap();
+        let mut mirrored = storage
+            .execute_query_with_rules_tuples_on("main", "result(X, Y) <- mirror(X, Y)")
+            .unwrap();
+        mirrored.sort();
+        assert_eq!(
+            mirrored,
+            vec![Tuple::from_pair(2, 3), Tuple::from_pair(5, 6)]
+        );
+
+        assert!(matches!(
+            storage.execute_query_tuples_on("main", "r(X) <- analytics.missing(X)"),
+            Err(StorageError::RelationNotFound(..))
+        ));
+        assert!(matches!(
+            storage.execute_query_tuples_on("main", "r(X) <- nowhere.edge(X, _)"),
+            Err(StorageError::KnowledgeGraphNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_copy_rename_and_cascading_drop() {
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(temp.path().to_path_buf());
+        let storage = StorageEngine::new(config.clone()).unwrap();
+        storage.create_knowledge_graph("main").unwrap();
+        storage.create_knowledge_graph("analytics").unwrap();
+        storage
+            .insert_tuples_into(
+                "analytics",
+                "edge",
+                vec![Tuple::from_pair(1, 2), Tuple::from_pair(2, 3)],
+            )
+            .unwrap();
+        let rule =
+            crate::statement::parse_rule_definition("hop(X, Z) <- edge(X, Y), edge(Y, Z)").unwrap();
+        storage.register_rule_in("analytics", &rule).unwrap();
+
+        // A copy has the data and rules of the source, and survives a restart
+        storage
+            .copy_knowledge_graph("analytics", "analytics_copy")
+            .unwrap();
+        assert!(matches!(
+            storage.copy_knowledge_graph("analytics", "main"),
+            Err(StorageError::KnowledgeGraphExists(_))
+        ));
+        drop(storage);
+        let storage = StorageEngine::new(config).unwrap();
+        assert_eq!(
+            storage
+                .execute_query_with_rules_tuples_on("analytics_copy", "r(X, Z) <- hop(X, Z)")
+                .unwrap(),
+            vec![Tuple::from_pair(1, 3)]
+        );
+        assert_eq!(
+            storage.list_rules_in("analytics_copy").unwrap(),
+            vec!["hop"]
+        );
+
+        // A rule of another knowledge graph reading it blocks drop and rename
+        let mirror =
+            crate::statement::parse_rule_definition("mirror(X, Y) <- analytics.edge(X, Y)")
+                .unwrap();
+        storage.register_rule_in("main", &mirror).unwrap();
+        assert!(storage
+            .rename_knowledge_graph("analytics", "stats")
+            .is_err());
+        assert!(storage.drop_knowledge_graph("analytics").is_err());
+        storage.drop_rule_in("main", "mirror").unwrap();
+
+        storage
+            .rename_knowledge_graph("analytics", "stats")
+            .unwrap();
+        let mut renamed = storage
+            .execute_query_tuples_on("stats", "r(X, Y) <- edge(X, Y)")
+            .unwrap();
+        renamed.sort();
+        assert_eq!(
+            renamed,
+            vec![Tuple::from_pair(1, 2), Tuple::from_pair(2, 3)]
+        );
+        assert!(!storage
+            .list_knowledge_graphs()
+            .contains(&"analytics".to_string()));
+
+        // A cascading drop takes the dependent rules with it
+        let mirror =
+            crate::statement::parse_rule_definition("mirror(X, Y) <- stats.edge(X, Y)").unwrap();
+        storage.register_rule_in("main", &mirror).unwrap();
+        assert_eq!(
+            storage.drop_knowledge_graph_cascade("stats").unwrap(),
+            vec!["main.mirror".to_string()]
+        );
+        assert!(storage.list_rules_in("main").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_view_refresh_matches_evaluation() {
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(temp.path().to_path_buf());
+        let storage = StorageEngine::new(config).unwrap();
+        storage.create_knowledge_graph("reach_kg").unwrap();
+        storage
+            .insert_tuples_into(
+                "reach_kg",
+                "edge",
+                vec![
+                    Tuple::from_pair(1, 2),
+                    Tuple::from_pair(2, 3),
+                    Tuple::from_pair(3, 4),
+                ],
+            )
+            .unwrap();
+        for rule in [
+            "reach(X, Y) <- edge(X, Y)",
+            "reach(X, Z) <- reach(X, Y), edge(Y, Z)",
+        ] {
+            let rule = crate::statement::parse_rule_definition(rule).unwrap();
+            storage.register_rule_in("reach_kg", &rule).unwrap();
+        }
+        let reach = |storage: &StorageEngine| {
+            let mut reach = storage
+                .execute_query_with_rules_tuples_on("reach_kg", "r(X, Y) <- reach(X, Y)")
+                .unwrap();
+            reach.sort();
+            reach
+        };
+        let evaluated = reach(&storage);
+        assert_eq!(evaluated.len(), 6);
+
+        // The cached result is the one evaluating the rules gives
+        assert_eq!(
+            storage
+                .set_refresh_policy_in(
+                    "reach_kg",
+                    "reach",
+                    Some(crate::rule_catalog::RefreshPolicy::Eager)
+                )
+                .unwrap(),
+            6
+        );
+        assert_eq!(storage.refresh_view_in("reach_kg", "reach").unwrap(), 6);
+        assert_eq!(reach(&storage), evaluated);
+    }
+
+    #[test]
+    fn test_view_refresh_policies() {
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(temp.path().to_path_buf());
+        let storage = StorageEngine::new(config).unwrap();
+        storage.create_knowledge_graph("views").unwrap();
+        storage
+            .insert_tuples_into("views", "edge", vec![Tuple::from_pair(1, 2)])
+            .unwrap();
+        let rule = crate::statement::parse_rule_definition("hop(X, Y) <- edge(X, Y)").unwrap();
+        storage.register_rule_in("views", &rule).unwrap();
+        let stale = |storage: &StorageEngine| {
+            let (_, rows) = storage.show_views_in("views").unwrap();
+            rows.iter()
+                .find(|row| row.get(0) == Some(&Value::string("hop")))
+                .and_then(|row| row.get(6).cloned())
+                .unwrap()
+        };
+        let hops = |storage: &StorageEngine| {
+            let mut hops = storage
+                .execute_query_with_rules_tuples_on("views", "r(X, Y) <- hop(X, Y)")
+                .unwrap();
+            hops.sort();
+            hops
+        };
+
+        // Without a policy a view is not cached
+        assert_eq!(stale(&storage), Value::Null);
+        assert!(storage.refresh_view_in("views", "hop").is_err());
+
+        // A lazy view goes stale on write and is refreshed by the next read
+        assert_eq!(
+            storage
+                .set_refresh_policy_in(
+                    "views",
+                    "hop",
+                    Some(crate::rule_catalog::RefreshPolicy::Lazy)
+                )
+                .unwrap(),
+            1
+        );
+        assert_eq!(stale(&storage), Value::Bool(false));
+        storage
+            .insert_tuples_into("views", "edge", vec![Tuple::from_pair(2, 3)])
+            .unwrap();
+        assert_eq!(stale(&storage), Value::Bool(true));
+        assert_eq!(
+            hops(&storage),
+            vec![Tuple::from_pair(1, 2), Tuple::from_pair(2, 3)]
+        );
+        assert_eq!(stale(&storage), Value::Bool(false));
+
+        // An eager view is refreshed by the write itself
+        storage
+            .set_refresh_policy_in(
+                "views",
+                "hop",
+                Some(crate::rule_catalog::RefreshPolicy::Eager),
+            )
+            .unwrap();
+        storage
+            .insert_tuples_into("views", "edge", vec![Tuple::from_pair(3, 4)])
+            .unwrap();
+        assert_eq!(stale(&storage), Value::Bool(false));
+        assert_eq!(hops(&storage).len(), 3);
+
+        // A periodic view serves its stale result until it is due
+        storage
+            .set_refresh_policy_in(
+                "views",
+                "hop",
+                Some(crate::rule_catalog::RefreshPolicy::Periodic {
+                    interval_ms: 3_600_000,
+                }),
+            )
+            .unwrap();
+        storage
+            .insert_tuples_into("views", "edge", vec![Tuple::from_pair(4, 5)])
+            .unwrap();
+        assert_eq!(hops(&storage).len(), 3);
+        assert_eq!(stale(&storage), Value::Bool(true));
+        assert_eq!(storage.refresh_due_views_in("views").unwrap(), 0);
+        assert_eq!(storage.refresh_view_in("views", "hop").unwrap(), 4);
+        assert_eq!(hops(&storage).len(), 4);
+
+        // Removing the policy goes back to evaluating on every query
+        storage.set_refresh_policy_in("views", "hop", None).unwrap();
+        storage
+            .insert_tuples_into("views", "edge", vec![Tuple::from_pair(5, 6)])
+            .unwrap();
+        assert_eq!(stale(&storage), Value::Null);
+        assert_eq!(hops(&storage).len(), 5);
+        assert!(storage
+            .set_refresh_policy_in(
+                "views",
+                "hop",
+                Some(crate::rule_catalog::RefreshPolicy::Periodic { interval_ms: 0 })
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_copy_view_to_file() {
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(temp.path().to_path_buf());
+        let storage = StorageEngine::new(config).unwrap();
+        storage.create_knowledge_graph("copy_view").unwrap();
+        storage
+            .insert_tuples_into(
+                "copy_view",
+                "edge",
+                vec![Tuple::from_pair(1, 2), Tuple::from_pair(2, 3)],
+            )
+            .unwrap();
+        for rule in [
+            "reach(X, Y) <- edge(X, Y)",
+            "reach(X, Z) <- reach(X, Y), edge(Y, Z)",
+        ] {
+            let rule = crate::statement::parse_rule_definition(rule).unwrap();
+            storage.register_rule_in("copy_view", &rule).unwrap();
+        }
+
+        // The file holds what evaluating the view gives
+        let csv = temp.path().join("reach.csv");
+        assert_eq!(
+            storage
+                .copy_to_in("copy_view", "reach", &csv, &CopyOptions::default())
+                .unwrap(),
+            3
+        );
+        let text = std::fs::read_to_string(&csv).unwrap();
+        let mut lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.remove(0), "col0,col1");
+        lines.sort_unstable();
+        assert_eq!(lines, vec!["1,2", "1,3", "2,3"]);
+    }
+
+    #[test]
+    fn test_copy_from_and_to() {
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(temp.path().to_path_buf());
+        let storage = StorageEngine::new(config).unwrap();
+        storage.create_knowledge_graph("copy_kg").unwrap();
+        storage
+            .insert_tuples_into(
+                "copy_kg",
+                "edge",
+                vec![Tuple::from_pair(1, 2), Tuple::from_pair(2, 3)],
+            )
+            .unwrap();
+        let rule =
+            crate::statement::parse_rule_definition("hop(X, Z) <- edge(X, Y), edge(Y, Z)").unwrap();
+        storage.register_rule_in("copy_kg", &rule).unwrap();
+        let options = CopyOptions::default();
+
+        // A base relation round-trips through Parquet
+        let parquet = temp.path().join("edges.parquet");
+        assert_eq!(
+            storage
+                .copy_to_in("copy_kg", "edge", &parquet, &options)
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            storage
+                .copy_from_in("copy_kg", "edge_copy", &parquet, &options)
+                .unwrap(),
+            2
+        );
+        let mut copied = storage
+            .execute_query_tuples_on("copy_kg", "r(X, Y) <- edge_copy(X, Y)")
+            .unwrap();
+        copied.sort();
+        assert_eq!(copied, vec![Tuple::from_pair(1, 2), Tuple::from_pair(2, 3)]);
+
+        // A view is exported with its rules applied; the format option
+        // overrides the extension
+        let csv = temp.path().join("hops.txt");
+        let csv_options = CopyOptions {
+            format: Some(CopyFormat::Csv),
+            delimiter: ';',
+            ..CopyOptions::default()
+        };
+        assert_eq!(
+            storage
+                .copy_to_in("copy_kg", "hop", &csv, &csv_options)
+                .unwrap(),
+            1
+        );
+        assert_eq!(std::fs::read_to_string(&csv).unwrap(), "col0;col1\n1;3\n");
+        assert_eq!(
+            storage
+                .copy_from_in("copy_kg", "hop_copy", &csv, &csv_options)
+                .unwrap(),
+            1
+        );
+        assert!(storage
+            .get_schema_in("copy_kg", "hop_copy")
+            .unwrap()
+            .is_some());
+
+        let jsonl = temp.path().join("edges.jsonl");
+        assert_eq!(
+            storage
+                .copy_to_in("copy_kg", "edge", &jsonl, &options)
+                .unwrap(),
+            2
+        );
+
+        // No format, no relation, no schema for Avro
+        let unknown = temp.path().join("edges.txt");
+        assert!(storage
+            .copy_to_in("copy_kg", "edge", &unknown, &options)
+            .is_err());
+        assert!(matches!(
+            storage.copy_to_in("copy_kg", "missing", &parquet, &options),
+            Err(StorageError::RelationNotFound(_, _))
+        ));
+        assert!(storage
+            .copy_to_in("copy_kg", "edge", &temp.path().join("edges.avro"), &options)
+            .is_err());
+        assert!(storage
+            .copy_from_in(
+                "copy_kg",
+                "edge",
+                &temp.path().join("none.parquet"),
+                &options
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_pushdown_scans_evicted_relation() {
+        let temp = TempDir::new().unwrap();
+        let edge = |x: i64| Tuple::new(vec![Value::Int64(x), Value::Int64(x % 10)]);
+        {
+            let config = create_test_config(temp.path().to_path_buf());
+            let storage = StorageEngine::new(config).unwrap();
+            storage.create_knowledge_graph("big").unwrap();
+            for range in [0..50, 50..100] {
+                storage
+                    .insert_tuples_into("big", "edge", range.map(edge).collect())
+                    .unwrap();
+                storage.save_all().unwrap();
+            }
+            storage
+                .delete_tuples_from("big", "edge", vec![edge(17)])
+                .unwrap();
+        }
+
+        let mut config = create_test_config(temp.path().to_path_buf());
+        config.storage.residency.lazy_load = true;
+        let storage = StorageEngine::new(config).unwrap();
+        let resident = |storage: &StorageEngine| {
+            storage
+                .with_kg_read("big", |db| Ok(db.engine.input_tuples.contains_key("edge")))
+                .unwrap()
+        };
+        let query = |program: &str| {
+            let mut rows = storage.execute_query_tuples_on("big", program).unwrap();
+            rows.sort();
+            rows
+        };
+
+        // Constant filters are scanned without loading the relation
+        let rows = query("result(X) <- edge(X, 7)");
+        assert_eq!(rows.len(), 9);
+        assert!(!rows.contains(&Tuple::new(vec![Value::Int64(17)])));
+        assert_eq!(
+            query("result(Y) <- edge(X, Y), X = 72"),
+            vec![Tuple::new(vec![Value::Int64(2)])]
+        );
+        assert!(!resident(&storage));
+
+        // A scan of every row loads it
+        assert_eq!(query("result(X, Y) <- edge(X, Y)").len(), 99);
+        assert!(resident(&storage));
+    }
+
+    #[test]
+    fn test_subplan_cache_shared_across_queries() {
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(temp.path().to_path_buf());
+        let storage = StorageEngine::new(config).unwrap();
+        storage.create_knowledge_graph("cached").unwrap();
+        storage
+            .insert_tuples_into(
+                "cached",
+                "edge",
+                vec![Tuple::from_pair(1, 2), Tuple::from_pair(2, 3)],
+            )
+            .unwrap();
+        let stats = || {
+            storage
+                .with_kg_read("cached", |db| Ok(db.subplan_cache.stats()))
+                .unwrap()
+        };
+        let query = || {
+            let mut rows = storage
+                .execute_query_tuples_on("cached", "hop(X, Z) <- edge(X, Y), edge(Y, Z)")
+                .unwrap();
+            rows.sort();
+            rows
+        };
+
+        // Each query runs on its own snapshot engine; the second reuses
+        // what the first cached
+        assert_eq!(query(), vec![Tuple::from_pair(1, 3)]);
+        let first = stats();
+        assert!(first.entries > 0);
+        assert_eq!(query(), vec![Tuple::from_pair(1, 3)]);
+        assert!(stats().hits > first.hits);
+
+        // A write gives the relation a new version, so nothing cached
+        // before it is served
+        storage
+            .insert_tuples_into("cached", "edge", vec![Tuple::from_pair(3, 4)])
+            .unwrap();
+        let hits = stats().hits;
+        assert_eq!(
+            query(),
+            vec![Tuple::from_pair(1, 3), Tuple::from_pair(2, 4)]
+        );
+        assert_eq!(stats().hits, hits);
+        assert!(stats().invalidations > 0);
+
+        // An old snapshot still reads the data it was taken at
+        let old = storage.get_snapshot_for_program("cached", "").unwrap();
+        storage
+            .insert_tuples_into("cached", "edge", vec![Tuple::from_pair(4, 5)])
+            .unwrap();
+        let mut rows = old
+            .execute_tuples("hop(X, Z) <- edge(X, Y), edge(Y, Z)")
+            .unwrap();
+        rows.sort();
+        assert_eq!(rows, vec![Tuple::from_pair(1, 3), Tuple::from_pair(2, 4)]);
+        assert_eq!(query().len(), 3);
+    }
+
+    #[test]
+    fn test_subplan_cache_survives_restart() {
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(temp.path().to_path_buf());
+        let program = "hop(X, Z) <- edge(X, Y), edge(Y, Z)";
+        let stats = |storage: &StorageEngine| {
+            storage
+                .with_kg_read("warm", |db| Ok(db.subplan_cache.stats()))
+                .unwrap()
+        };
+
+        let storage = StorageEngine::new(config.clone()).unwrap();
+        storage.create_knowledge_graph("warm").unwrap();
+        storage
+            .insert_tuples_into(
+                "warm",
+                "edge",
+                vec![Tuple::from_pair(1, 2), Tuple::from_pair(2, 3)],
+            )
+            .unwrap();
+        storage.execute_query_tuples_on("warm", program).unwrap();
+        assert!(stats(&storage).entries > 0);
+        storage.save_all().unwrap();
+        drop(storage);
+
+        let cache_file = temp.path().join("warm").join(SUBPLAN_CACHE_FILE);
+        assert!(cache_file.exists());
+        let storage = StorageEngine::new(config).unwrap();
+        assert!(!cache_file.exists());
+        let loaded = stats(&storage).entries;
+        assert!(loaded > 0);
+        assert_eq!(
+            storage.execute_query_tuples_on("warm", program).unwrap(),
+            vec![Tuple::from_pair(1, 3)]
+        );
+        assert!(stats(&storage).hits > 0);
+
+        // A relation written after the restart is recomputed
+        storage
+            .insert_tuples_into("warm", "edge", vec![Tuple::from_pair(3, 4)])
+            .unwrap();
+        let hits = stats(&storage).hits;
+        let mut rows = storage.execute_query_tuples_on("warm", program).unwrap();
+        rows.sort();
+        assert_eq!(rows, vec![Tuple::from_pair(1, 3), Tuple::from_pair(2, 4)]);
+        assert_eq!(stats(&storage).hits, hits);
     }
 }
@@ -0,0 +1,317 @@
+//! Partitioned Relation Storage
+//!
+//! A relation with a partition key (see [`crate::schema::partition`]) keeps
+//! one persist shard per partition. Writes are routed to the partition of
+//! each tuple's key, [`StorageEngine::scan_relation_in`] reads only the
+//! partitions a key filter can match, and loading a knowledge graph reads
+//! all of its shards in parallel.
+//!
+//! Declaring, changing or removing a partition key moves the relation's
+//! update history (with its original logical times, so point-in-time
+//! restore still works) into the shards of the new layout. Shards of a
+//! relation outside its current layout are moved the same way whenever
+//! the knowledge graph is loaded, so an interrupted move completes on
+//! restart. A marker file lists shards whose history has already been
+//! copied, so they are deleted rather than copied a second time.
+
+use super::{KnowledgeGraph, StorageEngine};
+use crate::schema::partition::{base_relation, is_shard_of, KeyFilter, PartitionSpec};
+use crate::schema::{RelationSchema, SchemaCatalog};
+use crate::storage::persist::{
+    consolidate_to_current, to_tuples, FilePersist, PersistBackend, Update,
+};
+use crate::storage::{StorageError, StorageResult};
+use crate::value::Tuple;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::info;
+
+/// Lists moved shards that have not been deleted yet
+pub(super) const MOVE_MARKER: &str = "partition_move.json";
+
+/// Partition key (column index and spec) of each partitioned relation
+/// written by a batch
+pub(super) type PartitionKeys = HashMap<String, (usize, PartitionSpec)>;
+
+/// Shards of a relation in its current layout
+fn layout_shards(base: &str, schema: Option<&RelationSchema>) -> Vec<String> {
+    match schema.and_then(RelationSchema::partition_key) {
+        Some((_, spec)) => spec.shard_names(base),
+        None => vec![base.to_string()],
+    }
+}
+
+/// Group updates of one relation by the shard they belong in, given the
+/// shard name the relation has unpartitioned
+pub(super) fn route_updates(
+    base: &str,
+    key: Option<(usize, &PartitionSpec)>,
+    updates: Vec<Update>,
+) -> Vec<(String, Vec<Update>)> {
+    let Some((column, spec)) = key else {
+        return vec![(base.to_string(), updates)];
+    };
+    let mut routed: BTreeMap<u32, Vec<Update>> = BTreeMap::new();
+    for update in updates {
+        routed
+            .entry(spec.partition_of(&update.data, column))
+            .or_default()
+            .push(update);
+    }
+    routed
+        .into_iter()
+        .map(|(n, updates)| (spec.shard_name(base, n), updates))
+        .collect()
+}
+
+/// Updates of a shard since its frontier, adapted to the relation's
+/// current schema
+pub(super) fn read_adapted(
+    persist: &FilePersist,
+    shard: &str,
+    relation: &str,
+    catalog: &SchemaCatalog,
+) -> StorageResult<Vec<Update>> {
+    let info = persist.shard_info(shard)?;
+    let mut updates = Vec::new();
+    for (version, batch) in persist.read_by_schema_version(shard, info.since)? {
+        updates.extend(batch.into_iter().map(|mut update| {
+            update.data = catalog.adapt_tuple(relation, version, update.data);
+            update
+        }));
+    }
+    Ok(updates)
+}
+
+impl KnowledgeGraph {
+    /// Partition key of a relation, if it is partitioned
+    pub(super) fn partition_key(&self, relation: &str) -> Option<(usize, PartitionSpec)> {
+        let (column, spec) = self.schema_catalog.get(relation)?.partition_key()?;
+        Some((column, spec.clone()))
+    }
+}
+
+impl StorageEngine {
+    /// Set, change or remove (`None`) the partition key of a relation with
+    /// a persistent schema, moving its stored data into the new layout.
+    /// Returns the updated schema.
+    pub fn partition_relation_in(
+        &self,
+        kg: &str,
+        relation: &str,
+        spec: Option<PartitionSpec>,
+    ) -> StorageResult<RelationSchema> {
+        let db = self
+            .knowledge_graphs
+            .get(kg)
+            .map(|db| Arc::clone(db.value()))
+            .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
+        let mut db = db.write();
+        if db.schema_catalog.has_session_schema(relation) {
+            return Err(StorageError::Other(format!(
+                "Relation '{relation}' has a session schema; only persistent relations can be partitioned"
+            )));
+        }
+
+        // Exclusive: no batch may append to the old layout while it is
+        // being moved (writers hold the read side while persisting)
+        let dropping_guard = self.dropping_kgs.write();
+        if dropping_guard.contains(kg) {
+            return Err(StorageError::KnowledgeGraphNotFound(kg.to_string()));
+        }
+        let schema = db
+            .schema_catalog
+            .set_partition(relation, spec)
+            .map_err(|e| StorageError::Other(e.to_string()))?;
+
+        // Save the catalog first: if the move is interrupted, loading the
+        // knowledge graph finishes it
+        db.save_schema_catalog().map_err(StorageError::Other)?;
+        let base = format!("{kg}:{relation}");
+        let version = db.schema_catalog.schema_version(relation);
+        for shard in layout_shards(&base, Some(&schema)) {
+            self.persist.set_schema_version(&shard, version)?;
+        }
+        let shards = self.persist.list_shards()?;
+        let moved = self.move_strays(kg, relation, &db.schema_catalog, &db.data_dir, &shards)?;
+        drop(dropping_guard);
+
+        info!(
+            kg = %kg,
+            relation = %relation,
+            partitions = schema.partition.as_ref().map_or(1, PartitionSpec::count),
+            moved_updates = moved,
+            "relation_partitioned"
+        );
+        Ok(schema)
+    }
+
+    /// Read a relation's stored tuples, keeping those whose partition key
+    /// satisfies `filter`. Only the partitions that can hold matching
+    /// tuples are read. Without a filter every shard is read; a filter on
+    /// an unpartitioned relation is an error.
+    ///
+    /// Reads the persist layer (including updates still in the WAL), not
+    /// the in-memory relation.
+    pub fn scan_relation_in(
+        &self,
+        kg: &str,
+        relation: &str,
+        filter: Option<&KeyFilter>,
+    ) -> StorageResult<Vec<Tuple>> {
+        let db = self
+            .knowledge_graphs
+            .get(kg)
+            .map(|db| Arc::clone(db.value()))
+            .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
+        let db = db.read();
+        let key = db.partition_key(relation);
+        if filter.is_some() && key.is_none() {
+            return Err(StorageError::Other(format!(
+                "Relation '{relation}' is not partitioned"
+            )));
+        }
+
+        let base = format!("{kg}:{relation}");
+        let stored = self.relation_shards(kg, relation)?;
+        let layout = layout_shards(&base, db.schema_catalog.get(relation));
+        let selected: Vec<&String> = match (&key, filter) {
+            (Some((_, spec)), Some(filter)) => {
+                let wanted: HashSet<String> = spec
+                    .prune(filter)
+                    .into_iter()
+                    .map(|n| spec.shard_name(&base, n))
+                    .collect();
+                // Shards outside the layout have not been moved yet and
+                // may hold matching tuples
+                stored
+                    .iter()
+                    .filter(|s| wanted.contains(*s) || !layout.contains(*s))
+                    .collect()
+            }
+            _ => stored.iter().collect(),
+        };
+
+        let persist: &FilePersist = &self.persist;
+        let catalog = &db.schema_catalog;
+        let per_shard = selected
+            .par_iter()
+            .map(|shard| -> StorageResult<Vec<Tuple>> {
+                let mut updates = read_adapted(persist, shard, relation, catalog)?;
+                consolidate_to_current(&mut updates);
+                Ok(to_tuples(&updates))
+            })
+            .collect::<StorageResult<Vec<_>>>()?;
+
+        let mut seen = HashSet::new();
+        let tuples = per_shard
+            .into_iter()
+            .flatten()
+            .filter(|tuple| match (&key, filter) {
+                (Some((column, _)), Some(filter)) => {
+                    tuple.get(*column).is_some_and(|v| filter.matches(v))
+                }
+                _ => true,
+            })
+            .filter(|tuple| seen.insert(tuple.clone()))
+            .collect();
+        Ok(tuples)
+    }
+
+    /// Names of every shard storing data of a relation, partitions included
+    pub(super) fn relation_shards(&self, kg: &str, relation: &str) -> StorageResult<Vec<String>> {
+        let base = format!("{kg}:{relation}");
+        Ok(self
+            .persist
+            .list_shards()?
+            .into_iter()
+            .filter(|shard| is_shard_of(shard, &base))
+            .collect())
+    }
+
+    /// Bring every relation of a knowledge graph being loaded into its
+    /// current layout: delete shards a previous move already copied, then
+    /// move any other shard outside the layout
+    pub(super) fn finish_partition_moves(
+        &self,
+        kg: &str,
+        data_dir: &Path,
+        catalog: &SchemaCatalog,
+    ) -> StorageResult<()> {
+        let marker = data_dir.join(MOVE_MARKER);
+        if let Ok(content) = fs::read_to_string(&marker) {
+            let copied: Vec<String> = serde_json::from_str(&content).unwrap_or_default();
+            for shard in &copied {
+                let _ = self.persist.delete_shard(shard);
+            }
+            fs::remove_file(&marker)?;
+        }
+
+        let prefix = format!("{kg}:");
+        let shards = self.persist.list_shards()?;
+        let relations: BTreeSet<&str> = shards
+            .iter()
+            .filter_map(|shard| shard.strip_prefix(&prefix))
+            .map(base_relation)
+            .collect();
+        for relation in relations {
+            let moved = self.move_strays(kg, relation, catalog, data_dir, &shards)?;
+            if moved > 0 {
+                info!(kg = %kg, relation = %relation, moved_updates = moved, "partition_move_resumed");
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy the history of a relation's shards outside its current layout
+    /// into the layout, then delete them. Returns the number of updates
+    /// moved.
+    fn move_strays(
+        &self,
+        kg: &str,
+        relation: &str,
+        catalog: &SchemaCatalog,
+        data_dir: &Path,
+        shards: &[String],
+    ) -> StorageResult<usize> {
+        let base = format!("{kg}:{relation}");
+        let schema = catalog.get(relation);
+        let layout = layout_shards(&base, schema);
+        let strays: Vec<&String> = shards
+            .iter()
+            .filter(|shard| is_shard_of(shard, &base) && !layout.contains(*shard))
+            .collect();
+        if strays.is_empty() {
+            return Ok(0);
+        }
+
+        let version = catalog.schema_version(relation);
+        let mut updates = Vec::new();
+        for shard in &strays {
+            updates.extend(read_adapted(&self.persist, shard, relation, catalog)?);
+        }
+        let moved = updates.len();
+        let key = schema.and_then(RelationSchema::partition_key);
+        for (shard, updates) in route_updates(&base, key, updates) {
+            self.persist.set_schema_version(&shard, version)?;
+            self.persist.append(&shard, &updates)?;
+            self.persist.flush(&shard)?;
+        }
+        self.persist.sync()?;
+
+        // The history is in the layout now. Until the old shards are gone,
+        // the marker keeps a restart from copying them again.
+        let marker = data_dir.join(MOVE_MARKER);
+        let content = serde_json::to_string(&strays)
+            .map_err(|e| StorageError::Other(format!("Failed to record partition move: {e}")))?;
+        fs::write(&marker, content)?;
+        for shard in strays {
+            self.persist.delete_shard(shard)?;
+        }
+        fs::remove_file(&marker)?;
+        Ok(moved)
+    }
+}
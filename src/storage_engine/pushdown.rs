@@ -0,0 +1,285 @@
+//! Predicate and Column Pushdown
+//!
+//! With residency enabled (see [`super::residency`]), a program that reads
+//! an evicted relation only through atoms fixing some of its columns to
+//! constants does not load the relation. Its persist shards are scanned
+//! with those constants and the columns the program uses pushed into the
+//! Parquet reader ([`crate::storage::persist::scan`]), and only the rows
+//! that match go into the program's snapshot. The relation stays evicted.
+//!
+//! ```text
+//! r(Y) <- edge(1, Y)              rows with col0 = 1
+//! r(X) <- edge(X, Y, _), Y = 2    col0 and col1 of rows with col1 = 2
+//! ```
+//!
+//! A relation is only pushed down when every atom of the program naming it
+//! has a constant (in the atom, or through an `X = c` comparison in its
+//! rule), the program does not derive it, and no stored rule the program
+//! uses reads it. A column is left out, and read as `Null`, when every
+//! atom has `_` or a variable used nowhere else in its rule there.
+
+use super::snapshot::KnowledgeGraphSnapshot;
+use super::{KnowledgeGraph, StorageEngine};
+use crate::ast::{Atom, BodyPredicate, ComparisonOp, Rule, Term};
+use crate::parser::parse_program;
+use crate::schema::partition::is_shard_of;
+use crate::storage::persist::{
+    consolidate_to_current, to_tuples, PersistBackend, ScanFilter, ScanStats,
+};
+use crate::storage::StorageResult;
+use crate::value::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tracing::info;
+
+/// Identifiers in `text`, with their number of occurrences
+fn word_counts(text: &str) -> HashMap<&str, usize> {
+    let mut counts = HashMap::new();
+    for word in text
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+    {
+        *counts.entry(word).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Value of a constant term
+fn constant_value(term: &Term) -> Option<Value> {
+    match term {
+        Term::Constant(i) => Some(Value::Int64(*i)),
+        Term::FloatConstant(f) => Some(Value::Float64(*f)),
+        Term::StringConstant(s) => Some(Value::from(s.as_str())),
+        Term::BoolConstant(b) => Some(Value::Bool(*b)),
+        _ => None,
+    }
+}
+
+/// Variables the comparisons of `rule` fix to a constant (`X = c`)
+fn fixed_variables(rule: &Rule) -> HashMap<&str, Value> {
+    let mut fixed = HashMap::new();
+    for predicate in &rule.body {
+        if let BodyPredicate::Comparison(Term::Variable(var), ComparisonOp::Equal, constant)
+        | BodyPredicate::Comparison(constant, ComparisonOp::Equal, Term::Variable(var)) =
+            predicate
+        {
+            if let Some(value) = constant_value(constant) {
+                fixed.insert(var.as_str(), value);
+            }
+        }
+    }
+    fixed
+}
+
+/// Scan filters for the relations among `candidates` that every rule of
+/// `program` reads only through atoms with a constant
+fn scan_filters(program: &str, candidates: &[String]) -> HashMap<String, ScanFilter> {
+    let Ok(parsed) = parse_program(program) else {
+        return HashMap::new();
+    };
+    let mut blocked: HashSet<&str> = parsed
+        .rules
+        .iter()
+        .map(|rule| rule.head.relation.as_str())
+        .collect();
+    let mut filters: HashMap<String, ScanFilter> = HashMap::new();
+
+    for rule in &parsed.rules {
+        let text = rule.to_string();
+        let words = word_counts(&text);
+        let fixed = fixed_variables(rule);
+        for relation in candidates {
+            let atoms: Vec<&Atom> = rule
+                .body
+                .iter()
+                .filter_map(BodyPredicate::atom)
+                .filter(|atom| atom.relation == *relation)
+                .collect();
+            // Named other than by an atom (e.g. by a vector search), the
+            // relation is read whole
+            if words.get(relation.as_str()).copied().unwrap_or(0) != atoms.len() {
+                blocked.insert(relation.as_str());
+                continue;
+            }
+            for atom in atoms {
+                let mut all = Vec::new();
+                let mut columns = Vec::new();
+                for (col, arg) in atom.args.iter().enumerate() {
+                    match arg {
+                        Term::Placeholder => {}
+                        Term::Variable(var) => {
+                            if let Some(value) = fixed.get(var.as_str()) {
+                                all.push((col, value.clone()));
+                            }
+                            if words.get(var.as_str()).copied().unwrap_or(0) > 1 {
+                                columns.push(col);
+                            }
+                        }
+                        term => {
+                            if let Some(value) = constant_value(term) {
+                                all.push((col, value));
+                            }
+                            columns.push(col);
+                        }
+                    }
+                }
+                if all.is_empty() {
+                    blocked.insert(relation.as_str());
+                    continue;
+                }
+                let filter = filters
+                    .entry(relation.clone())
+                    .or_insert_with(|| ScanFilter {
+                        any_of: Vec::new(),
+                        columns: Some(Vec::new()),
+                    });
+                filter.any_of.push(all);
+                filter.columns.get_or_insert_with(Vec::new).extend(columns);
+            }
+        }
+    }
+
+    filters.retain(|relation, _| !blocked.contains(relation.as_str()));
+    for filter in filters.values_mut() {
+        if let Some(columns) = &mut filter.columns {
+            columns.sort_unstable();
+            columns.dedup();
+        }
+    }
+    filters
+}
+
+impl KnowledgeGraph {
+    /// Evicted relations `program` reads that can be scanned with a
+    /// filter instead of being loaded, with their filters
+    pub(super) fn pushdown_filters(&self, program: &str) -> HashMap<String, ScanFilter> {
+        let evicted: Vec<String> = self
+            .referenced_relations(program)
+            .into_iter()
+            .filter(|relation| self.residency.is_evicted(relation))
+            .collect();
+        if evicted.is_empty() {
+            return HashMap::new();
+        }
+
+        // Stored rules read their relations whole
+        let mut rules = String::new();
+        for word in word_counts(program).into_keys() {
+            if let Some(rule) = self.rule_catalog.get(word) {
+                for rule in rule.to_rules() {
+                    rules.push_str(&rule.to_string());
+                    rules.push('\n');
+                }
+            }
+        }
+        let through_rules: HashSet<String> =
+            self.referenced_relations(&rules).into_iter().collect();
+        let candidates: Vec<String> = evicted
+            .into_iter()
+            .filter(|relation| !through_rules.contains(relation))
+            .collect();
+        scan_filters(program, &candidates)
+    }
+}
+
+impl StorageEngine {
+    /// Add the rows of the evicted relations in `filters` that pass them
+    /// to `snapshot`, read from the persist shards of `db`
+    pub(super) fn scan_pushed_down(
+        &self,
+        db: &KnowledgeGraph,
+        snapshot: Arc<KnowledgeGraphSnapshot>,
+        filters: HashMap<String, ScanFilter>,
+    ) -> StorageResult<Arc<KnowledgeGraphSnapshot>> {
+        if filters.is_empty() {
+            return Ok(snapshot);
+        }
+
+        let mut input_tuples = snapshot.input_tuples.as_ref().clone();
+        // Filtered reads must not be cached as the whole relation
+        let mut versions = snapshot.relation_versions.as_ref().clone();
+        let shards = self.persist.list_shards()?;
+        for (relation, filter) in filters {
+            versions.remove(&relation);
+            let base = format!("{}:{relation}", db.name);
+            let version = db.schema_catalog.schema_version(&relation);
+            let mut stats = ScanStats::default();
+            let mut tuples = Vec::new();
+            for shard in shards.iter().filter(|shard| is_shard_of(shard, &base)) {
+                let since = self.persist.shard_info(shard)?.since;
+                let (groups, scanned) = self
+                    .persist
+                    .scan_by_schema_version(shard, since, version, &filter)?;
+                stats += scanned;
+                let mut updates = Vec::new();
+                for (written, batch) in groups {
+                    if written == version {
+                        updates.extend(batch);
+                        continue;
+                    }
+                    // Older batches are adapted before they can be filtered
+                    let adapted = batch
+                        .into_iter()
+                        .map(|mut update| {
+                            update.data =
+                                db.schema_catalog
+                                    .adapt_tuple(&relation, written, update.data);
+                            update
+                        })
+                        .collect();
+                    updates.extend(filter.apply(adapted));
+                }
+                consolidate_to_current(&mut updates);
+                tuples.extend(to_tuples(&updates));
+            }
+            info!(
+                kg = %db.name,
+                relation = %relation,
+                tuples = tuples.len(),
+                row_groups = stats.row_groups,
+                row_groups_skipped = stats.row_groups_skipped,
+                rows_read = stats.rows_read,
+                "relation_scanned"
+            );
+            input_tuples.insert(relation, tuples);
+        }
+
+        let mut pushed = snapshot.as_ref().clone();
+        pushed.input_tuples = Arc::new(input_tuples);
+        pushed.relation_versions = Arc::new(versions);
+        Ok(Arc::new(pushed))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_filters() {
+        let candidates = vec!["edge".to_string(), "node".to_string()];
+        let filters = scan_filters(
+            "r(Y) <- edge(1, Y, _)\n\
+             s(X) <- edge(X, Y, Z), Y = \"b\", !node(X, 2)",
+            &candidates,
+        );
+        let edge = &filters["edge"];
+        assert_eq!(
+            edge.any_of,
+            vec![vec![(0, Value::Int64(1))], vec![(1, Value::from("b"))]]
+        );
+        // Z is used nowhere else, nor is the third column in the first rule
+        assert_eq!(edge.columns, Some(vec![0, 1]));
+        assert_eq!(filters["node"].columns, Some(vec![0, 1]));
+
+        // An atom without a constant reads the whole relation
+        let filters = scan_filters("r(X) <- edge(X, 1), node(X)", &candidates);
+        assert!(filters.contains_key("edge"));
+        assert!(!filters.contains_key("node"));
+
+        // Neither are relations the program derives, nor unparseable programs
+        assert!(scan_filters("edge(X, 2) <- edge(X, 1)", &candidates).is_empty());
+        assert!(scan_filters("r(X) <- edge(X, 1) <- node(X)", &candidates).is_empty());
+    }
+}
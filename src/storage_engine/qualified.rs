@@ -0,0 +1,151 @@
+//! Cross-Knowledge-Graph References
+//!
+//! A rule or query can read a base relation of another knowledge graph in
+//! the same engine by qualifying it with that graph's name:
+//!
+//! ```text
+//! ?analytics.edge(X, Y)
+//! +shared(X, Y) <- edge(X, Y), analytics.edge(X, Y)
+//! ```
+//!
+//! Qualified names are resolved when a snapshot is taken for a program:
+//! the foreign relation's tuples, as of the other graph's current
+//! snapshot, are added to this snapshot under the qualified name, so the
+//! IR builder scans them like any local base relation. They are read-only:
+//! rule heads and writes cannot name them. Only graphs whose names are
+//! plain identifiers can be referenced this way.
+//!
+//! Nothing here checks access: the server refuses a statement naming a
+//! relation of a graph the user cannot read before it gets this far.
+
+use super::snapshot::KnowledgeGraphSnapshot;
+use super::{KnowledgeGraph, StorageEngine};
+use crate::ast::{BodyPredicate, Rule};
+use crate::storage::{StorageError, StorageResult};
+use std::sync::Arc;
+
+/// Split `kg.relation` into its knowledge graph and relation names
+pub(super) fn split_qualified(relation: &str) -> Option<(&str, &str)> {
+    relation
+        .split_once('.')
+        .filter(|(kg, rel)| !kg.is_empty() && !rel.is_empty())
+}
+
+/// First qualified relation read by the body of `rule`
+pub(super) fn qualified_body_relation(rule: &Rule) -> Option<&str> {
+    rule.body
+        .iter()
+        .filter_map(BodyPredicate::atom)
+        .map(|atom| atom.relation.as_str())
+        .find(|relation| split_qualified(relation).is_some())
+}
+
+/// Whether the body of `rule` reads a relation of knowledge graph `kg`
+pub(super) fn reads_knowledge_graph(rule: &Rule, kg: &str) -> bool {
+    rule.body
+        .iter()
+        .filter_map(BodyPredicate::atom)
+        .any(|atom| split_qualified(&atom.relation).is_some_and(|(other, _)| other == kg))
+}
+
+/// Qualified relation names (`kg.relation(`) in program text, outside
+/// string literals, without duplicates
+fn qualified_references(text: &str) -> Vec<String> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let mut found: Vec<String> = Vec::new();
+    // Odd pieces are inside string literals
+    for code in text.split('"').step_by(2) {
+        let chars: Vec<char> = code.chars().collect();
+        let ident_end = |start: usize| {
+            (start..chars.len())
+                .find(|&i| !is_ident(chars[i]))
+                .unwrap_or(chars.len())
+        };
+        let mut pos = 0;
+        while pos < chars.len() {
+            let starts_word = chars[pos].is_alphabetic()
+                && (pos == 0 || !(is_ident(chars[pos - 1]) || chars[pos - 1] == '.'));
+            if !starts_word {
+                pos += 1;
+                continue;
+            }
+            let kg_end = ident_end(pos);
+            if chars.get(kg_end) != Some(&'.')
+                || !chars.get(kg_end + 1).is_some_and(|c| c.is_lowercase())
+            {
+                pos = kg_end;
+                continue;
+            }
+            let rel_end = ident_end(kg_end + 1);
+            let next = (rel_end..chars.len()).find(|&i| !chars[i].is_whitespace());
+            if next.is_some_and(|i| chars[i] == '(') {
+                let name: String = chars[pos..rel_end].iter().collect();
+                if !found.contains(&name) {
+                    found.push(name);
+                }
+            }
+            pos = rel_end;
+        }
+    }
+    found
+}
+
+impl StorageEngine {
+    /// Add the foreign relations `program` (or a persistent rule of the
+    /// snapshot) reads to `snapshot`, under their qualified names
+    pub(super) fn resolve_qualified(
+        &self,
+        snapshot: Arc<KnowledgeGraphSnapshot>,
+        program: &str,
+    ) -> StorageResult<Arc<KnowledgeGraphSnapshot>> {
+        let mut references = qualified_references(program);
+        for name in qualified_references(snapshot.rule_prefix()) {
+            if !references.contains(&name) {
+                references.push(name);
+            }
+        }
+        if references.is_empty() {
+            return Ok(snapshot);
+        }
+
+        let mut input_tuples = snapshot.input_tuples.as_ref().clone();
+        for name in references {
+            let Some((kg, relation)) = split_qualified(&name) else {
+                continue;
+            };
+            let foreign =
+                self.with_resident(kg, |_| vec![relation.to_string()], KnowledgeGraph::snapshot)?;
+            let tuples = foreign.input_tuples.get(relation).ok_or_else(|| {
+                StorageError::RelationNotFound(relation.to_string(), kg.to_string())
+            })?;
+            input_tuples.insert(name.clone(), tuples.clone());
+        }
+
+        let mut resolved = snapshot.as_ref().clone();
+        resolved.input_tuples = Arc::new(input_tuples);
+        Ok(Arc::new(resolved))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_qualified_references() {
+        let program = "r(X) <- edge(X, Y), analytics.edge(Y, _), !main.blocked(X)\n\
+                       ?r(X), X = \"a.b(c)\", analytics.edge (X, 1)";
+        assert_eq!(
+            qualified_references(program),
+            vec!["analytics.edge".to_string(), "main.blocked".to_string()]
+        );
+        // Field access and floats are not relation references
+        assert!(qualified_references("r(X) <- p(R), X = R.name, Y = 1.5").is_empty());
+        assert_eq!(
+            split_qualified("analytics.edge"),
+            Some(("analytics", "edge"))
+        );
+        assert_eq!(split_qualified("edge"), None);
+    }
+}
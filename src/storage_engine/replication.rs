@@ -0,0 +1,725 @@
+//! Replication Log
+//!
+//! With `[replication] role` set, every committed change is recorded in an
+//! in-memory log that followers read to stay in step with this server.
+//! Records are numbered in commit order and hold what was applied, not
+//! what was asked for: a write record carries the tuples after defaults,
+//! `@auto` IDs, upserts and quarantine were resolved, so a follower
+//! applies them without validating again and ends up with the same data.
+//!
+//! Schema declarations, knowledge graph creation, drops, copies and
+//! renames are recorded as they happen. Statements that change rules,
+//! indexes or relations through the catalog (`.rule drop`, `.rel alter`,
+//! `.index create`, ...) are recorded as their text instead, and the
+//! writes they make are left out of the log so the follower does not
+//! apply them twice (see [`ReplicationLog::begin_statement`]).
+//!
+//! The log keeps the newest `log_max_bytes` of records. A follower whose
+//! position has been trimmed, or that was following a log that no longer
+//! exists (each log has a random ID, renewed on restart), catches up from
+//! a [`KnowledgeGraphImage`] of every knowledge graph instead.
+
+use super::batch::PendingWrite;
+use super::lifecycle::COPY_MARKER;
+use super::partition::route_updates;
+use super::{KnowledgeGraph, RuleChange, StorageEngine};
+use crate::schema::{RelationSchema, SchemaCatalog};
+use crate::storage::persist::{PersistBackend, Update};
+use crate::storage::{StorageError, StorageResult};
+use crate::value::Tuple;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use parking_lot::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Component, Path};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::watch;
+use tracing::info;
+
+thread_local! {
+    /// Statements being recorded as text on this thread; their writes are
+    /// not recorded again
+    static STATEMENT_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Bytes counted for a record besides its tuples and text
+const RECORD_OVERHEAD: usize = 64;
+
+/// A write of a committed batch, as applied
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplicatedWrite {
+    pub relation: String,
+    /// Removal (delete or upsert replacement) rather than insert
+    pub delete: bool,
+    pub tuples: Vec<Tuple>,
+}
+
+/// A change to apply on followers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplicationOp {
+    /// A committed batch or transaction
+    Write {
+        writes: Vec<ReplicatedWrite>,
+        #[serde(default)]
+        rules: Vec<RuleChange>,
+    },
+    /// A schema declared or updated
+    Schema {
+        schema: RelationSchema,
+    },
+    CreateKnowledgeGraph,
+    DropKnowledgeGraph {
+        cascade: bool,
+    },
+    /// The record's knowledge graph was copied from `source`
+    CopyKnowledgeGraph {
+        source: String,
+    },
+    /// `source` was renamed to the record's knowledge graph
+    RenameKnowledgeGraph {
+        source: String,
+    },
+    /// A statement to run against the record's knowledge graph
+    Statement {
+        text: String,
+    },
+}
+
+/// One entry of the replication log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicationRecord {
+    pub seq: u64,
+    pub kg: String,
+    pub op: ReplicationOp,
+}
+
+impl ReplicationRecord {
+    /// Approximate size, for trimming the log
+    fn estimated_bytes(&self) -> usize {
+        let payload = match &self.op {
+            ReplicationOp::Write { writes, .. } => writes
+                .iter()
+                .flat_map(|w| &w.tuples)
+                .map(Tuple::estimated_bytes)
+                .sum(),
+            ReplicationOp::Statement { text } => text.len(),
+            _ => 0,
+        };
+        RECORD_OVERHEAD + self.kg.len() + payload
+    }
+}
+
+struct LogState {
+    id: String,
+    records: VecDeque<(ReplicationRecord, usize)>,
+    bytes: usize,
+    next_seq: u64,
+}
+
+/// Recent committed changes, for followers to apply
+pub struct ReplicationLog {
+    max_bytes: usize,
+    state: Mutex<LogState>,
+    /// Next sequence number, for followers waiting on new records
+    appended: watch::Sender<u64>,
+    /// Shared by statements being recorded, exclusive while imaging
+    statements: RwLock<()>,
+    /// This server applies another server's log rather than taking writes
+    following: AtomicBool,
+}
+
+/// Held while a statement runs and is recorded; see
+/// [`ReplicationLog::begin_statement`]
+pub struct StatementGuard<'a> {
+    _gate: RwLockReadGuard<'a, ()>,
+}
+
+impl Drop for StatementGuard<'_> {
+    fn drop(&mut self) {
+        STATEMENT_DEPTH.with(|depth| depth.set(depth.get().saturating_sub(1)));
+    }
+}
+
+impl ReplicationLog {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            state: Mutex::new(LogState {
+                id: uuid::Uuid::new_v4().to_string(),
+                records: VecDeque::new(),
+                bytes: 0,
+                next_seq: 0,
+            }),
+            appended: watch::Sender::new(0),
+            statements: RwLock::new(()),
+            following: AtomicBool::new(false),
+        }
+    }
+
+    /// Whether this server is a follower that has not been promoted
+    pub fn is_following(&self) -> bool {
+        self.following.load(Ordering::Acquire)
+    }
+
+    /// Start or stop following; stopping makes the server writable
+    pub fn set_following(&self, following: bool) {
+        self.following.store(following, Ordering::Release);
+    }
+
+    /// Random ID of this log; positions in another log mean nothing here
+    pub fn id(&self) -> String {
+        self.state.lock().id.clone()
+    }
+
+    /// Sequence number the next record will get
+    pub fn next_seq(&self) -> u64 {
+        self.state.lock().next_seq
+    }
+
+    /// Records retained and their approximate size in bytes
+    pub fn retained(&self) -> (usize, usize) {
+        let state = self.state.lock();
+        (state.records.len(), state.bytes)
+    }
+
+    /// Append a record, trimming the oldest ones past `max_bytes`.
+    /// Returns its sequence number.
+    pub(super) fn append(&self, kg: &str, op: ReplicationOp) -> u64 {
+        let mut state = self.state.lock();
+        let seq = state.next_seq;
+        let record = ReplicationRecord {
+            seq,
+            kg: kg.to_string(),
+            op,
+        };
+        let bytes = record.estimated_bytes();
+        state.records.push_back((record, bytes));
+        state.bytes += bytes;
+        state.next_seq += 1;
+        while state.bytes > self.max_bytes && state.records.len() > 1 {
+            if let Some((_, trimmed)) = state.records.pop_front() {
+                state.bytes -= trimmed;
+            }
+        }
+        drop(state);
+        self.appended.send_replace(seq + 1);
+        seq
+    }
+
+    /// Record a statement run against `kg`. Called while the statement's
+    /// [`StatementGuard`] is held.
+    pub fn append_statement(&self, kg: &str, text: &str) -> u64 {
+        self.append(
+            kg,
+            ReplicationOp::Statement {
+                text: text.to_string(),
+            },
+        )
+    }
+
+    /// Up to `max` records starting at `seq` of the log `log_id`, or
+    /// `None` if that position is no longer (or was never) in this log
+    pub fn read_from(&self, log_id: &str, seq: u64, max: usize) -> Option<Vec<ReplicationRecord>> {
+        let state = self.state.lock();
+        if log_id != state.id || seq > state.next_seq {
+            return None;
+        }
+        let first = state
+            .records
+            .front()
+            .map_or(state.next_seq, |(record, _)| record.seq);
+        if seq < first {
+            return None;
+        }
+        let skip = (seq - first) as usize;
+        Some(
+            state
+                .records
+                .iter()
+                .skip(skip)
+                .take(max)
+                .map(|(record, _)| record.clone())
+                .collect(),
+        )
+    }
+
+    /// Receiver notified with the next sequence number after each append
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.appended.subscribe()
+    }
+
+    /// Start over under a new ID, so followers of this log take a full
+    /// snapshot. Used once the data was replaced wholesale.
+    pub fn reset(&self) {
+        let mut state = self.state.lock();
+        state.id = uuid::Uuid::new_v4().to_string();
+        state.records.clear();
+        state.bytes = 0;
+        let next = state.next_seq;
+        drop(state);
+        self.appended.send_replace(next);
+    }
+
+    /// Start running a statement that will be recorded as text with
+    /// [`Self::append_statement`]. Until the guard is dropped, writes made
+    /// on this thread are not recorded, and no knowledge graph is imaged,
+    /// so an image never includes half of a statement.
+    pub fn begin_statement(&self) -> StatementGuard<'_> {
+        let gate = self.statements.read();
+        STATEMENT_DEPTH.with(|depth| depth.set(depth.get() + 1));
+        StatementGuard { _gate: gate }
+    }
+
+    /// Wait for running statements to be recorded and hold off new ones
+    fn pause_statements(&self) -> RwLockWriteGuard<'_, ()> {
+        self.statements.write()
+    }
+}
+
+/// Whether writes on this thread belong to a statement recorded as text
+fn in_statement() -> bool {
+    STATEMENT_DEPTH.with(|depth| depth.get() > 0)
+}
+
+/// A file of a knowledge graph's data directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageFile {
+    /// Path relative to the data directory
+    pub path: String,
+    /// Base64-encoded content
+    pub content: String,
+}
+
+/// Everything needed to recreate a knowledge graph on a follower: its
+/// data directory (schemas, rules, sequences, indexes) and the current
+/// tuples of each relation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnowledgeGraphImage {
+    pub name: String,
+    /// First log record not reflected in the image
+    pub seq: u64,
+    pub files: Vec<ImageFile>,
+    pub relations: Vec<(String, Vec<Tuple>)>,
+}
+
+impl StorageEngine {
+    /// The replication log, when this server replicates
+    pub fn replication_log(&self) -> Option<&Arc<ReplicationLog>> {
+        self.replication.as_ref()
+    }
+
+    /// Whether this server is a read-only replica. A replica does not
+    /// create knowledge graphs on first use.
+    pub fn is_replica(&self) -> bool {
+        self.replication
+            .as_ref()
+            .is_some_and(|log| log.is_following())
+    }
+
+    /// Record a change to `kg` unless it is part of a statement recorded
+    /// as text
+    pub(super) fn record_change(&self, kg: &str, op: impl FnOnce() -> ReplicationOp) {
+        if let Some(log) = &self.replication {
+            if !in_statement() {
+                log.append(kg, op());
+            }
+        }
+    }
+
+    /// The writes of a batch in replicated form, taken before they are
+    /// applied, if they are to be recorded
+    pub(super) fn replicated_writes(
+        &self,
+        writes: &[PendingWrite],
+    ) -> Option<Vec<ReplicatedWrite>> {
+        if self.replication.is_none() || in_statement() {
+            return None;
+        }
+        Some(writes.iter().map(PendingWrite::replicated).collect())
+    }
+
+    /// Record the first `applied` writes of a batch and the rule changes
+    /// committed with them
+    pub(super) fn record_writes(
+        &self,
+        kg: &str,
+        writes: Option<Vec<ReplicatedWrite>>,
+        applied: usize,
+        rules: &[RuleChange],
+    ) {
+        let Some(mut writes) = writes else {
+            return;
+        };
+        writes.truncate(applied);
+        writes.retain(|w| !w.tuples.is_empty());
+        if !writes.is_empty() || !rules.is_empty() {
+            self.record_change(kg, || ReplicationOp::Write {
+                writes,
+                rules: rules.to_vec(),
+            });
+        }
+    }
+
+    /// Apply a change recorded by the leader. Writes are applied as
+    /// recorded, without validation. Statements are run by the server,
+    /// not here.
+    pub fn apply_replicated(&self, kg: &str, op: ReplicationOp) -> StorageResult<()> {
+        match op {
+            ReplicationOp::Write { writes, rules } => {
+                let relations: Vec<String> = writes.iter().map(|w| w.relation.clone()).collect();
+                let inserted: Vec<(String, Vec<Tuple>)> = writes
+                    .iter()
+                    .filter(|w| !w.delete)
+                    .map(|w| (w.relation.clone(), w.tuples.clone()))
+                    .collect();
+                let writes: Vec<PendingWrite> =
+                    writes.into_iter().map(PendingWrite::from).collect();
+                if rules.is_empty() {
+                    self.commit_writes(kg, writes)?;
+                } else {
+                    let db = self
+                        .knowledge_graphs
+                        .get(kg)
+                        .map(|db| Arc::clone(db.value()))
+                        .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
+                    let mut db = db.write();
+                    db.make_resident(&relations, None)?;
+                    self.commit_locked(kg, &mut db, writes, rules)?;
+                }
+                // IDs assigned on the leader are taken here too, so they
+                // are not handed out again after a promotion
+                let db = self
+                    .knowledge_graphs
+                    .get(kg)
+                    .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
+                let db = db.read();
+                db.advance_sequences(&inserted)
+            }
+            ReplicationOp::Schema { schema } => self.register_or_update_schema_in(kg, schema),
+            // Every node creates its default knowledge graph at startup,
+            // so the leader's record of creating it finds it here already
+            ReplicationOp::CreateKnowledgeGraph => match self.create_knowledge_graph(kg) {
+                Err(StorageError::KnowledgeGraphExists(_))
+                    if kg == self.config.storage.default_knowledge_graph =>
+                {
+                    Ok(())
+                }
+                result => result,
+            },
+            ReplicationOp::DropKnowledgeGraph { cascade } => {
+                let cleanup = self.prepare_drop(kg, cascade)?;
+                self.finish_drop_knowledge_graph(cleanup);
+                Ok(())
+            }
+            ReplicationOp::CopyKnowledgeGraph { source } => self.copy_knowledge_graph(&source, kg),
+            ReplicationOp::RenameKnowledgeGraph { source } => {
+                self.rename_knowledge_graph(&source, kg)
+            }
+            ReplicationOp::Statement { .. } => Err(StorageError::Other(
+                "Replicated statements are run by the server".to_string(),
+            )),
+        }
+    }
+
+    /// Capture a knowledge graph for a follower catching up. Statements
+    /// being recorded finish first; `seq` of the image is the first record
+    /// committed after it.
+    pub fn image_knowledge_graph(&self, kg: &str) -> StorageResult<KnowledgeGraphImage> {
+        let log = self
+            .replication
+            .as_ref()
+            .ok_or_else(|| StorageError::Other("Replication is not enabled".to_string()))?;
+        let _paused = log.pause_statements();
+        self.with_resident(kg, KnowledgeGraph::evicted_relations, |db| {
+            let mut files = Vec::new();
+            collect_files(&db.data_dir, &db.data_dir, &mut files)?;
+            let mut relations: Vec<(String, Vec<Tuple>)> = db
+                .engine
+                .input_tuples
+                .iter()
+                .map(|(name, tuples)| (name.clone(), tuples.clone()))
+                .collect();
+            relations.sort_by(|a, b| a.0.cmp(&b.0));
+            Ok(KnowledgeGraphImage {
+                name: kg.to_string(),
+                seq: log.next_seq(),
+                files,
+                relations,
+            })
+        })?
+    }
+
+    /// Replace a knowledge graph (or create it) with an image taken on
+    /// the leader. Nothing is written to other knowledge graphs
+    /// meanwhile. Interrupted by a crash, the knowledge graph is
+    /// discarded on the next start, as an unfinished copy is.
+    pub fn install_image(&self, image: KnowledgeGraphImage) -> StorageResult<()> {
+        let KnowledgeGraphImage {
+            name,
+            files,
+            relations,
+            ..
+        } = image;
+        let mut dropping = self.dropping_kgs.write();
+        if dropping.contains(&name) {
+            return Err(StorageError::Other(format!(
+                "Knowledge graph '{name}' is being dropped, cannot replace"
+            )));
+        }
+        let existed = self.knowledge_graphs.remove(&name).is_some();
+        dropping.insert(name.clone());
+
+        let data_dir = self.config.storage.data_dir.join(&name);
+        let installed = (|| -> StorageResult<usize> {
+            let prefix = format!("{name}:");
+            for shard in self.persist.list_shards()? {
+                if shard.starts_with(&prefix) {
+                    self.persist.delete_shard(&shard)?;
+                }
+            }
+            if data_dir.exists() {
+                fs::remove_dir_all(&data_dir)?;
+            }
+            fs::create_dir_all(data_dir.join("relations"))?;
+            fs::write(data_dir.join(COPY_MARKER), "replication")?;
+            for file in files {
+                let relative = Path::new(&file.path);
+                if !relative
+                    .components()
+                    .all(|c| matches!(c, Component::Normal(_)))
+                {
+                    return Err(StorageError::Other(format!(
+                        "Invalid path in knowledge graph image: {}",
+                        file.path
+                    )));
+                }
+                let content = STANDARD.decode(&file.content).map_err(|e| {
+                    StorageError::Other(format!("Invalid file in knowledge graph image: {e}"))
+                })?;
+                let path = data_dir.join(relative);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(path, content)?;
+            }
+
+            let schema_path = data_dir.join("schema.json");
+            let catalog = if schema_path.exists() {
+                SchemaCatalog::load(&schema_path).map_err(|e| StorageError::Other(e.to_string()))?
+            } else {
+                SchemaCatalog::new()
+            };
+            let time = self.logical_time.fetch_add(1, Ordering::SeqCst);
+            let mut tuples_written = 0;
+            for (relation, tuples) in relations.into_iter().filter(|(_, t)| !t.is_empty()) {
+                tuples_written += tuples.len();
+                let updates = tuples
+                    .into_iter()
+                    .map(|t| Update::insert(t, time))
+                    .collect();
+                let key = catalog
+                    .get(&relation)
+                    .and_then(RelationSchema::partition_key);
+                let base = format!("{name}:{relation}");
+                for (shard, updates) in route_updates(&base, key, updates) {
+                    self.persist
+                        .set_schema_version(&shard, catalog.schema_version(&relation))?;
+                    self.persist.append(&shard, &updates)?;
+                    self.persist.flush(&shard)?;
+                }
+            }
+            self.persist.sync()?;
+
+            let kg = self.load_knowledge_graph_from_persist(&name, data_dir.clone())?;
+            // Supersedes the catalogs logged for a replaced knowledge graph
+            kg.log_catalogs()?;
+            fs::remove_file(data_dir.join(COPY_MARKER))?;
+            self.knowledge_graphs
+                .insert(name.clone(), Arc::new(parking_lot::RwLock::new(kg)));
+            Ok(tuples_written)
+        })();
+        dropping.remove(&name);
+        drop(dropping);
+
+        let tuples = installed?;
+        self.save_knowledge_graphs_metadata()?;
+        info!(kg = %name, tuples, replaced = existed, "kg_image_installed");
+        Ok(())
+    }
+}
+
+impl KnowledgeGraph {
+    /// Move `@auto` sequences past the IDs of replicated inserts
+    fn advance_sequences(&self, inserted: &[(String, Vec<Tuple>)]) -> StorageResult<()> {
+        let mut sequences = self.sequences.lock();
+        for (relation, tuples) in inserted {
+            if let Some(schema) = self.schema_catalog.get(relation) {
+                super::residency::recover_sequences(&mut sequences, schema, tuples)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Files under `dir`, with paths relative to `root`, leaving out markers
+/// of unfinished operations
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<ImageFile>) -> StorageResult<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if super::lifecycle::is_marker(&entry.file_name()) {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            collect_files(root, &path, files)?;
+        } else {
+            let relative = path
+                .strip_prefix(root)
+                .map_err(|e| StorageError::Other(e.to_string()))?;
+            files.push(ImageFile {
+                path: relative.to_string_lossy().replace('\\', "/"),
+                content: STANDARD.encode(fs::read(&path)?),
+            });
+        }
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, ReplicationRole};
+    use crate::schema::{ColumnSchema, SchemaType};
+    use crate::value::Value;
+
+    fn engine(dir: &Path) -> StorageEngine {
+        let mut config = Config::default();
+        config.storage.data_dir = dir.to_path_buf();
+        config.replication.role = ReplicationRole::Leader;
+        StorageEngine::new(config).unwrap()
+    }
+
+    fn write(relation: &str, n: i64) -> ReplicationOp {
+        ReplicationOp::Write {
+            writes: vec![ReplicatedWrite {
+                relation: relation.to_string(),
+                delete: false,
+                tuples: vec![Tuple::new(vec![Value::Int64(n)])],
+            }],
+            rules: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_log_trims_and_reads_from_position() {
+        let log = ReplicationLog::new(RECORD_OVERHEAD * 3);
+        for n in 0..5 {
+            log.append("", write("r", n));
+        }
+        let id = log.id();
+        assert_eq!(log.next_seq(), 5);
+        assert!(log.read_from(&id, 0, 10).is_none(), "trimmed");
+        let records = log.read_from(&id, 4, 10).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].seq, 4);
+        assert!(log.read_from(&id, 5, 10).unwrap().is_empty());
+        assert!(log.read_from(&id, 6, 10).is_none());
+        assert!(log.read_from("other", 4, 10).is_none());
+
+        log.reset();
+        assert_ne!(log.id(), id);
+        assert!(log.read_from(&log.id(), 5, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_writes_recorded_except_inside_statements() {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = engine(tmp.path());
+        let log = Arc::clone(storage.replication_log().unwrap());
+        let start = log.next_seq();
+
+        storage
+            .insert_tuples_into("default", "r", vec![Tuple::new(vec![Value::Int64(1)])])
+            .unwrap();
+        let records = log.read_from(&log.id(), start, 10).unwrap();
+        assert_eq!(records.len(), 1);
+        assert!(matches!(&records[0].op, ReplicationOp::Write { writes, .. }
+            if writes[0].relation == "r" && !writes[0].delete));
+
+        {
+            let _statement = log.begin_statement();
+            storage
+                .insert_tuples_into("default", "r", vec![Tuple::new(vec![Value::Int64(2)])])
+                .unwrap();
+            log.append_statement("default", ".rule drop r");
+        }
+        let records = log.read_from(&log.id(), start + 1, 10).unwrap();
+        assert_eq!(records.len(), 1);
+        assert!(
+            matches!(&records[0].op, ReplicationOp::Statement { text } if text == ".rule drop r")
+        );
+    }
+
+    #[test]
+    fn test_replay_and_image_reproduce_knowledge_graph() {
+        let leader_dir = tempfile::tempdir().unwrap();
+        let leader = engine(leader_dir.path());
+        let log = Arc::clone(leader.replication_log().unwrap());
+        let schema = RelationSchema::new("item")
+            .with_column(ColumnSchema::new("id", SchemaType::Int))
+            .with_column(ColumnSchema::new("name", SchemaType::String));
+        leader.create_knowledge_graph("shop").unwrap();
+        leader.register_schema_in("shop", schema).unwrap();
+        let items = vec![
+            Tuple::new(vec![Value::Int64(1), Value::string("tea")]),
+            Tuple::new(vec![Value::Int64(2), Value::string("cake")]),
+        ];
+        leader
+            .insert_tuples_into("shop", "item", items.clone())
+            .unwrap();
+
+        // Replaying the log
+        let follower_dir = tempfile::tempdir().unwrap();
+        let follower = engine(follower_dir.path());
+        for record in log.read_from(&log.id(), 0, 100).unwrap() {
+            follower.apply_replicated(&record.kg, record.op).unwrap();
+        }
+        let mut replayed = follower
+            .execute_query_tuples_on("shop", "result(Id, Name) <- item(Id, Name)")
+            .unwrap();
+        replayed.sort();
+        assert_eq!(replayed, items);
+        assert!(follower.get_schema_in("shop", "item").unwrap().is_some());
+
+        // Installing an image, over an existing knowledge graph
+        let image = leader.image_knowledge_graph("shop").unwrap();
+        assert_eq!(image.seq, log.next_seq());
+        let imaged_dir = tempfile::tempdir().unwrap();
+        let imaged = engine(imaged_dir.path());
+        imaged.create_knowledge_graph("shop").unwrap();
+        imaged
+            .insert_tuples_into("shop", "stale", vec![Tuple::new(vec![Value::Int64(9)])])
+            .unwrap();
+        imaged.install_image(image).unwrap();
+        let mut installed = imaged
+            .execute_query_tuples_on("shop", "result(Id, Name) <- item(Id, Name)")
+            .unwrap();
+        installed.sort();
+        assert_eq!(installed, items);
+        assert!(!imaged
+            .list_relations_in("shop")
+            .unwrap()
+            .contains(&"stale".to_string()));
+        assert!(imaged.get_schema_in("shop", "item").unwrap().is_some());
+    }
+}
@@ -0,0 +1,449 @@
+//! Relation Residency
+//!
+//! By default every relation of a knowledge graph is read into memory when
+//! the knowledge graph is loaded and stays there. Two settings under
+//! `[storage.residency]` relax this so knowledge graphs larger than RAM
+//! stay usable:
+//!
+//! - `lazy_load`: relations are only registered at startup and read from
+//!   their persist shards the first time a query or write touches them.
+//! - `max_resident_bytes`: once the estimated size of the resident
+//!   relations of a knowledge graph exceeds the cap, the least recently
+//!   used ones are evicted from memory. Their data stays in the persist
+//!   layer and is read back on next use.
+//!
+//! Queries name the relations they read (directly or through rules), and
+//! those are made resident before the query's snapshot is taken. Writes
+//! make their target relations resident before validation, since key
+//! checks and deduplication need the current contents.
+//!
+//! A knowledge graph running the incremental engine (vector indexes,
+//! subscriptions) keeps all of its relations resident.
+
+use super::partition::read_adapted;
+use super::sequence::SequenceCatalog;
+use super::snapshot::KnowledgeGraphSnapshot;
+use super::{KnowledgeGraph, StorageEngine};
+use crate::schema::partition::is_shard_of;
+use crate::schema::RelationSchema;
+use crate::storage::persist::{consolidate_to_current, to_tuples, FilePersist, PersistBackend};
+use crate::storage::{StorageError, StorageResult};
+use crate::value::{Tuple, Value};
+use parking_lot::Mutex;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+use tracing::info;
+
+/// Tuples sampled to estimate a relation's bytes per tuple
+const SIZE_SAMPLE: usize = 1024;
+
+/// Which relations of a knowledge graph are in memory
+#[derive(Default)]
+pub(super) struct Residency {
+    /// Persist layer evicted relations are read back from (`None` when
+    /// every relation stays resident)
+    persist: Option<Arc<FilePersist>>,
+    /// Estimated bytes of resident relations to stay under (0 = unlimited)
+    max_bytes: usize,
+    /// Relations with stored data that is not in memory
+    evicted: BTreeSet<String>,
+    /// Recency and size estimates of resident relations
+    usage: Mutex<Usage>,
+}
+
+impl std::fmt::Debug for Residency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Residency")
+            .field("enabled", &self.persist.is_some())
+            .field("max_bytes", &self.max_bytes)
+            .field("evicted", &self.evicted)
+            .field("usage", &self.usage)
+            .finish()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Usage {
+    clock: u64,
+    /// Clock value at each relation's last use
+    last_used: HashMap<String, u64>,
+    /// Sampled estimate of bytes per tuple
+    tuple_bytes: HashMap<String, usize>,
+}
+
+impl Residency {
+    /// Residency reading evicted relations from `persist`, or keeping
+    /// everything resident if neither lazy loading nor a cap is configured
+    pub(super) fn new(persist: &Arc<FilePersist>, lazy: bool, max_bytes: usize) -> Self {
+        Residency {
+            persist: (lazy || max_bytes > 0).then(|| Arc::clone(persist)),
+            max_bytes,
+            ..Residency::default()
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        self.persist.is_some()
+    }
+
+    /// Record a stored relation as not loaded
+    pub(super) fn mark_evicted(&mut self, relation: &str) {
+        self.evicted.insert(relation.to_string());
+    }
+
+    pub(super) fn is_evicted(&self, relation: &str) -> bool {
+        self.evicted.contains(relation)
+    }
+
+    /// Forget a dropped relation
+    pub(super) fn forget(&mut self, relation: &str) {
+        self.evicted.remove(relation);
+        let usage = self.usage.get_mut();
+        usage.last_used.remove(relation);
+        usage.tuple_bytes.remove(relation);
+    }
+
+    /// Evicted relations whose names start with `prefix`
+    pub(super) fn evicted_with_prefix(&self, prefix: &str) -> Vec<String> {
+        self.evicted
+            .iter()
+            .filter(|name| name.starts_with(prefix))
+            .cloned()
+            .collect()
+    }
+
+    fn touch(&self, relations: &[String]) {
+        let mut usage = self.usage.lock();
+        usage.clock += 1;
+        let now = usage.clock;
+        for relation in relations {
+            usage.last_used.insert(relation.clone(), now);
+        }
+    }
+
+    /// Relations to evict, least recently used first, to bring the
+    /// estimated size of `relations` under the cap. Relations in `keep`
+    /// are never chosen.
+    fn victims(&self, relations: &HashMap<String, Vec<Tuple>>, keep: &[String]) -> Vec<String> {
+        let mut usage = self.usage.lock();
+        let Usage {
+            last_used,
+            tuple_bytes,
+            ..
+        } = &mut *usage;
+        let mut sizes: Vec<(u64, &String, usize)> = relations
+            .iter()
+            .filter(|(_, tuples)| !tuples.is_empty())
+            .map(|(name, tuples)| {
+                let per_tuple = *tuple_bytes
+                    .entry(name.clone())
+                    .or_insert_with(|| sample_tuple_bytes(tuples));
+                let last = last_used.get(name).copied().unwrap_or(0);
+                (last, name, per_tuple * tuples.len())
+            })
+            .collect();
+        let mut total: usize = sizes.iter().map(|&(_, _, bytes)| bytes).sum();
+        if total <= self.max_bytes {
+            return Vec::new();
+        }
+
+        sizes.sort();
+        let mut victims = Vec::new();
+        for (_, name, bytes) in sizes {
+            if total <= self.max_bytes {
+                break;
+            }
+            if keep.contains(name) {
+                continue;
+            }
+            total -= bytes;
+            victims.push(name.clone());
+        }
+        victims
+    }
+}
+
+/// Average estimated bytes per tuple over an evenly spaced sample
+fn sample_tuple_bytes(tuples: &[Tuple]) -> usize {
+    let step = (tuples.len() / SIZE_SAMPLE).max(1);
+    let (count, bytes) = tuples
+        .iter()
+        .step_by(step)
+        .fold((0, 0), |(count, bytes), tuple| {
+            (count + 1, bytes + tuple.estimated_bytes())
+        });
+    bytes.checked_div(count).unwrap_or(0)
+}
+
+/// Never hand out an `@auto` ID that is already stored, even if the
+/// sequence file is behind the data (e.g. lost or restored from an old
+/// backup)
+pub(super) fn recover_sequences(
+    sequences: &mut SequenceCatalog,
+    schema: &RelationSchema,
+    tuples: &[Tuple],
+) -> StorageResult<()> {
+    for col in schema.auto_increment_columns() {
+        let max = tuples
+            .iter()
+            .filter_map(|t| t.get(col).and_then(Value::as_i64))
+            .max();
+        if let Some(max) = max {
+            sequences
+                .advance_to(&schema.name, max.saturating_add(1))
+                .map_err(StorageError::Other)?;
+        }
+    }
+    Ok(())
+}
+
+impl KnowledgeGraph {
+    /// Relations a program reads, directly or through the rules it uses,
+    /// that are resident or evicted. Names are matched on identifiers, so
+    /// this may include a few relations the program does not read.
+    pub(super) fn referenced_relations(&self, program: &str) -> Vec<String> {
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut pending = vec![program.to_string()];
+        while let Some(text) = pending.pop() {
+            for word in text
+                .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .filter(|word| !word.is_empty())
+            {
+                if seen.contains(word) {
+                    continue;
+                }
+                seen.insert(word.to_string());
+                if let Some(rule) = self.rule_catalog.get(word) {
+                    pending.extend(rule.to_rules().iter().map(ToString::to_string));
+                }
+            }
+        }
+        seen.into_iter()
+            .filter(|name| {
+                self.engine.input_tuples.contains_key(name) || self.residency.is_evicted(name)
+            })
+            .collect()
+    }
+
+    /// Load the evicted relations among `relations` from the persist
+    /// layer, then evict others if the cap is exceeded, publishing a new
+    /// snapshot if anything changed. With `before`, updates at or after
+    /// that logical time are left out (they are being applied by the
+    /// caller).
+    pub(super) fn make_resident(
+        &mut self,
+        relations: &[String],
+        before: Option<u64>,
+    ) -> StorageResult<()> {
+        if !self.residency.enabled() {
+            return Ok(());
+        }
+        let mut changed = false;
+        for relation in relations {
+            if self.residency.is_evicted(relation) {
+                self.load_relation(relation, before)?;
+                changed = true;
+            }
+        }
+        self.residency.touch(relations);
+        changed |= self.evict_over_cap(relations);
+        if changed {
+            self.publish_snapshot();
+        }
+        Ok(())
+    }
+
+    /// Relations with stored data that is not in memory
+    pub(super) fn evicted_relations(&self) -> Vec<String> {
+        self.residency.evicted.iter().cloned().collect()
+    }
+
+    /// Load every evicted relation
+    pub(super) fn make_all_resident(&mut self) -> StorageResult<()> {
+        let evicted = self.evicted_relations();
+        self.make_resident(&evicted, None)
+    }
+
+    /// Read an evicted relation back from its persist shards
+    fn load_relation(&mut self, relation: &str, before: Option<u64>) -> StorageResult<()> {
+        let Some(persist) = self.residency.persist.clone() else {
+            return Ok(());
+        };
+        let base = format!("{}:{relation}", self.name);
+        let mut tuples = Vec::new();
+        for shard in persist.list_shards()? {
+            if !is_shard_of(&shard, &base) {
+                continue;
+            }
+            let mut updates = read_adapted(&persist, &shard, relation, &self.schema_catalog)?;
+            if let Some(before) = before {
+                updates.retain(|update| update.time < before);
+            }
+            consolidate_to_current(&mut updates);
+            tuples.extend(to_tuples(&updates));
+        }
+
+        if let Some(schema) = self.schema_catalog.get(relation) {
+            schema
+                .check_vector_dims(&tuples)
+                .map_err(StorageError::Other)?;
+            recover_sequences(self.sequences.get_mut(), schema, &tuples)?;
+        }
+        self.residency.evicted.remove(relation);
+        let count = tuples.len();
+        if let Some(first) = tuples.first() {
+            let schema = (0..first.arity()).map(|i| format!("col{i}")).collect();
+            self.metadata
+                .add_relation(relation.to_string(), schema, count);
+            self.engine.add_tuples(relation, tuples);
+        }
+        crate::metrics::metrics().relation_loads.inc();
+        info!(kg = %self.name, relation, tuples = count, "relation_loaded");
+        Ok(())
+    }
+
+    /// Evict least recently used relations (other than `keep`) while the
+    /// resident relations exceed the cap. Returns whether any were evicted.
+    pub(super) fn evict_over_cap(&mut self, keep: &[String]) -> bool {
+        // The incremental engine holds its own copy of every relation
+        if self.residency.max_bytes == 0 || self.incremental.is_some() {
+            return false;
+        }
+        let victims = self.residency.victims(&self.engine.input_tuples, keep);
+        for relation in &victims {
+            self.engine.input_tuples.remove(relation);
+            let usage = self.residency.usage.get_mut();
+            usage.last_used.remove(relation);
+            usage.tuple_bytes.remove(relation);
+            self.residency.evicted.insert(relation.clone());
+            info!(kg = %self.name, relation = %relation, "relation_evicted");
+        }
+        crate::metrics::metrics()
+            .relation_evictions
+            .add(victims.len() as u64);
+        !victims.is_empty()
+    }
+}
+
+impl StorageEngine {
+    /// Residency for a knowledge graph, as configured
+    pub(super) fn new_residency(&self) -> Residency {
+        let config = &self.config.storage.residency;
+        Residency::new(&self.persist, config.lazy_load, config.max_resident_bytes)
+    }
+
+    /// Make relations of a knowledge graph resident, loading any that are
+    /// evicted
+    pub fn ensure_resident_in(&self, kg: &str, relations: &[String]) -> StorageResult<()> {
+        self.with_resident(kg, |_| relations.to_vec(), |_| ())
+    }
+
+    /// Snapshot for running `program`: the relations it reads, directly or
+    /// through rules, are made resident first (or, for evicted relations
+    /// it only reads with constants, scanned with them; see
+    /// [`super::pushdown`]), cached views it reads are refreshed as their
+    /// policies require, and relations of other knowledge graphs it names
+    /// are resolved into it
+    pub fn get_snapshot_for_program(
+        &self,
+        kg: &str,
+        program: &str,
+    ) -> StorageResult<Arc<KnowledgeGraphSnapshot>> {
+        let snapshot = self.with_resident(
+            kg,
+            |db| {
+                let pushed = db.pushdown_filters(program);
+                db.referenced_relations(program)
+                    .into_iter()
+                    .filter(|relation| !pushed.contains_key(relation))
+                    .collect()
+            },
+            |db| {
+                db.refresh_views_for_read(program);
+                self.scan_pushed_down(db, db.snapshot(), db.pushdown_filters(program))
+            },
+        )??;
+        self.resolve_qualified(snapshot, program)
+    }
+
+    /// Run `read` on a knowledge graph once the relations `needed` names
+    /// are resident, under the same lock so none is evicted in between
+    pub(super) fn with_resident<T>(
+        &self,
+        kg: &str,
+        needed: impl Fn(&KnowledgeGraph) -> Vec<String>,
+        read: impl FnOnce(&KnowledgeGraph) -> T,
+    ) -> StorageResult<T> {
+        let db = self
+            .knowledge_graphs
+            .get(kg)
+            .map(|db| Arc::clone(db.value()))
+            .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
+        {
+            let guard = db.read();
+            if !guard.residency.enabled() {
+                return Ok(read(&guard));
+            }
+            let relations = needed(&guard);
+            if !relations.iter().any(|r| guard.residency.is_evicted(r)) {
+                guard.residency.touch(&relations);
+                return Ok(read(&guard));
+            }
+        }
+        let mut guard = db.write();
+        let relations = needed(&guard);
+        guard.make_resident(&relations, None)?;
+        Ok(read(&guard))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn relation(rows: i64) -> Vec<Tuple> {
+        (0..rows)
+            .map(|i| Tuple::new(vec![Value::Int64(i)]))
+            .collect()
+    }
+
+    #[test]
+    fn test_victims_least_recently_used_first() {
+        let per_tuple = std::mem::size_of::<Tuple>();
+        let residency = Residency {
+            max_bytes: 25 * per_tuple,
+            ..Residency::default()
+        };
+        let relations: HashMap<String, Vec<Tuple>> = [
+            ("a".to_string(), relation(10)),
+            ("b".to_string(), relation(10)),
+            ("c".to_string(), relation(10)),
+        ]
+        .into_iter()
+        .collect();
+
+        residency.touch(&["b".to_string()]);
+        residency.touch(&["a".to_string()]);
+        residency.touch(&["c".to_string()]);
+        assert_eq!(residency.victims(&relations, &[]), vec!["b".to_string()]);
+        // Kept relations are skipped even if least recently used
+        assert_eq!(
+            residency.victims(&relations, &["b".to_string()]),
+            vec!["a".to_string()]
+        );
+
+        let roomy = Residency {
+            max_bytes: 100 * per_tuple,
+            ..Residency::default()
+        };
+        assert!(roomy.victims(&relations, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_sample_tuple_bytes() {
+        assert_eq!(sample_tuple_bytes(&[]), 0);
+        let tuples = vec![Tuple::new(vec![Value::string("abcd")]); 5000];
+        assert_eq!(sample_tuple_bytes(&tuples), tuples[0].estimated_bytes());
+    }
+}
@@ -0,0 +1,102 @@
+//! Retention Sweeps
+//!
+//! Relations with a retention policy (see [`crate::schema::retention`])
+//! have their expired tuples deleted by [`StorageEngine::sweep_expired`],
+//! which the server runs every `storage.retention_sweep_interval_secs`.
+//! Expired tuples are deleted through the normal write path, so rules
+//! over a retained relation see the retractions and stay consistent.
+//! Each sweep ends by releasing the interned strings no tuple references
+//! any more, whichever deletes freed them since the last sweep.
+
+use super::{KnowledgeGraph, StorageEngine};
+use crate::schema::{RelationSchema, RetentionPolicy};
+use crate::storage::{StorageError, StorageResult};
+use crate::value::intern;
+use chrono::Utc;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+impl StorageEngine {
+    /// Set or remove (`None`) the retention policy of a relation with a
+    /// persistent schema. Returns the updated schema; expired tuples are
+    /// deleted by the next sweep.
+    pub fn set_retention_in(
+        &self,
+        kg: &str,
+        relation: &str,
+        policy: Option<RetentionPolicy>,
+    ) -> StorageResult<RelationSchema> {
+        let db = self
+            .knowledge_graphs
+            .get(kg)
+            .map(|db| Arc::clone(db.value()))
+            .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
+        let mut db = db.write();
+        if db.schema_catalog.has_session_schema(relation) {
+            return Err(StorageError::Other(format!(
+                "Relation '{relation}' has a session schema; only persistent relations can have a retention policy"
+            )));
+        }
+        let schema = db
+            .schema_catalog
+            .set_retention(relation, policy)
+            .map_err(|e| StorageError::Other(e.to_string()))?;
+        db.save_schema_catalog().map_err(StorageError::Other)?;
+        Ok(schema)
+    }
+
+    /// Delete the expired tuples of every relation with a retention policy
+    /// in a knowledge graph. Returns the number of tuples deleted.
+    pub fn sweep_expired_in(&self, kg: &str) -> StorageResult<usize> {
+        let now_ms = Utc::now().timestamp_millis();
+        // Policies are applied to the whole relation, so evicted relations
+        // are loaded first
+        let retained = |db: &KnowledgeGraph| -> Vec<String> {
+            db.schema_catalog
+                .persistent_schemas()
+                .filter(|schema| schema.retention.is_some())
+                .map(|schema| schema.name.clone())
+                .collect()
+        };
+        let expired: Vec<_> = self.with_resident(kg, retained, |db| {
+            db.schema_catalog
+                .persistent_schemas()
+                .filter_map(|schema| {
+                    let policy = schema.retention.as_ref()?;
+                    let tuples = db.engine.input_tuples().get(&schema.name)?;
+                    let expired = policy.expired(schema, tuples, now_ms);
+                    (!expired.is_empty()).then(|| (schema.name.clone(), expired))
+                })
+                .collect()
+        })?;
+
+        let mut deleted = 0;
+        for (relation, tuples) in expired {
+            let count = self.delete_tuples_from(kg, &relation, tuples)?;
+            info!(kg, relation, count, "Deleted expired tuples");
+            deleted += count;
+        }
+        Ok(deleted)
+    }
+
+    /// Sweep expired tuples in all knowledge graphs, then purge unused
+    /// interned strings. A knowledge graph whose sweep fails is logged and
+    /// skipped. Returns the number of tuples deleted.
+    pub fn sweep_expired(&self) -> usize {
+        let deleted = self
+            .list_knowledge_graphs()
+            .iter()
+            .map(|kg| {
+                self.sweep_expired_in(kg).unwrap_or_else(|e| {
+                    warn!(kg, error = %e, "Retention sweep failed");
+                    0
+                })
+            })
+            .sum();
+        let purged = intern::purge_unused_interned();
+        if purged > 0 {
+            info!(purged, "interned_strings_purged");
+        }
+        deleted
+    }
+}
@@ -0,0 +1,107 @@
+//! Per-Relation ID Sequences
+//!
+//! Monotonically increasing Int64 generators backing `@auto` columns.
+//! Each knowledge graph keeps one sequence per relation in
+//! `sequences.json` inside its data directory. The file is rewritten
+//! before any newly assigned ID becomes visible, so IDs are never reused
+//! after a restart (a crash may leave gaps, which is allowed).
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Next unassigned ID of every relation in a knowledge graph
+#[derive(Debug)]
+pub struct SequenceCatalog {
+    path: PathBuf,
+    next: BTreeMap<String, i64>,
+}
+
+impl SequenceCatalog {
+    /// Create an empty catalog stored at `path`
+    pub fn new(path: &Path) -> Self {
+        SequenceCatalog {
+            path: path.to_path_buf(),
+            next: BTreeMap::new(),
+        }
+    }
+
+    /// Load the sequences stored at `path`, or start empty if it does not exist
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::new(path));
+        }
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read sequences: {e}"))?;
+        let next = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse sequences: {e}"))?;
+        Ok(SequenceCatalog {
+            path: path.to_path_buf(),
+            next,
+        })
+    }
+
+    /// Next ID that would be assigned for `relation` (sequences start at 1)
+    pub fn peek(&self, relation: &str) -> i64 {
+        self.next.get(relation).copied().unwrap_or(1)
+    }
+
+    /// Move the sequence of `relation` forward to `next` and persist it.
+    ///
+    /// Sequences never move backwards; a lower `next` is ignored.
+    pub fn advance_to(&mut self, relation: &str, next: i64) -> Result<(), String> {
+        if next <= self.peek(relation) {
+            return Ok(());
+        }
+        self.next.insert(relation.to_string(), next);
+        self.save()
+    }
+
+    /// Forget the sequence of a dropped relation
+    pub fn remove(&mut self, relation: &str) -> Result<(), String> {
+        if self.next.remove(relation).is_some() {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create sequence directory: {e}"))?;
+        }
+        let content = serde_json::to_string_pretty(&self.next)
+            .map_err(|e| format!("Failed to serialize sequences: {e}"))?;
+        // Write-then-rename so a crash never leaves a truncated file
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, content).map_err(|e| format!("Failed to write sequences: {e}"))?;
+        fs::rename(&tmp, &self.path).map_err(|e| format!("Failed to write sequences: {e}"))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_sequences_persist_and_never_move_back() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("sequences.json");
+
+        let mut seqs = SequenceCatalog::load(&path).unwrap();
+        assert_eq!(seqs.peek("event"), 1);
+        seqs.advance_to("event", 4).unwrap();
+        seqs.advance_to("event", 2).unwrap();
+        assert_eq!(seqs.peek("event"), 4);
+
+        let reloaded = SequenceCatalog::load(&path).unwrap();
+        assert_eq!(reloaded.peek("event"), 4);
+        assert_eq!(reloaded.peek("other"), 1);
+
+        let mut reloaded = reloaded;
+        reloaded.remove("event").unwrap();
+        assert_eq!(SequenceCatalog::load(&path).unwrap().peek("event"), 1);
+    }
+}
@@ -9,8 +9,14 @@
 //! - Data is shared via Arc, so cloning a snapshot is O(1)
 //! - Writers publish new snapshots atomically via `ArcSwap`
 //! - Readers get consistent snapshots without holding locks
+//! - Each snapshot records the LSN of the last write batch it includes;
+//!   batches are published whole, never partially
 
 use crate::ast::Rule;
+use crate::execution::SubplanCache;
+use crate::index_manager::IndexedColumn;
+use crate::schema::VectorDims;
+use crate::value::coercion::CoercionMode;
 use crate::value::Tuple;
 use crate::IQLEngine;
 use std::collections::{HashMap, HashSet};
@@ -34,6 +40,12 @@ pub struct KnowledgeGraphSnapshot {
     /// Timestamp when snapshot was created (microseconds since epoch)
     pub timestamp: u64,
 
+    /// Logical time (LSN) of the last write this snapshot reflects.
+    ///
+    /// Writes become visible a whole batch at a time, so every snapshot
+    /// holds all of the writes up to its LSN and none after it.
+    pub lsn: u64,
+
     /// Base relation data (arbitrary arity)
     /// Wrapped in Arc for lock-free sharing
     pub input_tuples: Arc<HashMap<String, Vec<Tuple>>>,
@@ -64,6 +76,24 @@ pub struct KnowledgeGraphSnapshot {
     /// Maximum query cost score (0 = unlimited)
     pub max_query_cost: u64,
 
+    /// Maximum bytes a query may materialize (0 = unlimited)
+    pub max_query_memory_bytes: usize,
+
+    /// Wall-clock limit per query in milliseconds (0 = unlimited)
+    pub query_timeout_ms: u64,
+
+    /// Unique key column sets per relation, used by the join planner
+    pub unique_keys: Arc<HashMap<String, Vec<Vec<usize>>>>,
+
+    /// Implicit type coercion mode (strict rejects mixed-type joins)
+    pub coercion: CoercionMode,
+
+    /// Declared vector column dimensions, checked when queries compile
+    pub vector_dims: Arc<VectorDims>,
+
+    /// Per-column distinct counts from the statistics catalog
+    pub distinct_counts: Arc<HashMap<String, Vec<usize>>>,
+
     /// Optional HNSW search function for resolving nearest-neighbor queries.
     /// Wrapped in Arc for cheap cloning. Signature:
     /// `(index_name, query_vector, k, ef_search) -> Vec<(tuple_id, distance)>`
@@ -74,6 +104,19 @@ pub struct KnowledgeGraphSnapshot {
                 + Sync,
         >,
     >,
+
+    /// Vector columns with a valid HNSW index, used to answer
+    /// nearest-neighbor `top_k` queries without a full scan
+    pub indexed_columns: Arc<Vec<IndexedColumn>>,
+
+    /// Subplan cache of the knowledge graph, shared by the queries of every
+    /// snapshot (`None` = no caching)
+    pub subplan_cache: Option<Arc<SubplanCache>>,
+
+    /// Version of each base relation in `input_tuples`, keying the shared
+    /// subplan cache. Relations without one (materializations, cached
+    /// views, relations read through pushdown) are never cached.
+    pub relation_versions: Arc<HashMap<String, u64>>,
 }
 
 impl KnowledgeGraphSnapshot {
@@ -122,6 +165,7 @@ impl KnowledgeGraphSnapshot {
         Self {
             version,
             timestamp,
+            lsn: 0,
             input_tuples: Arc::new(input_tuples),
             rules: Arc::new(rules),
             num_workers,
@@ -129,7 +173,16 @@ impl KnowledgeGraphSnapshot {
             rule_prefix: Arc::new(prefix),
             max_result_rows: 0,
             max_query_cost: 0,
+            max_query_memory_bytes: 0,
+            query_timeout_ms: 0,
+            unique_keys: Arc::default(),
+            coercion: CoercionMode::default(),
+            vector_dims: Arc::default(),
+            distinct_counts: Arc::default(),
             hnsw_search_fn: None,
+            indexed_columns: Arc::default(),
+            subplan_cache: None,
+            relation_versions: Arc::default(),
         }
     }
 
@@ -165,9 +218,12 @@ impl KnowledgeGraphSnapshot {
         engine.set_num_workers(self.num_workers);
         engine.set_max_result_rows(self.max_result_rows);
         engine.set_max_query_cost(self.max_query_cost);
+        engine.set_max_query_memory(self.max_query_memory_bytes);
+        engine.set_query_timeout_ms(self.query_timeout_ms);
         engine.input_tuples.clone_from(&self.input_tuples);
         engine.set_shared_input(Arc::clone(&self.input_tuples));
-        self.configure_hnsw(&mut engine);
+        self.configure_engine(&mut engine);
+        self.share_subplan_cache(&mut engine);
         engine.execute(program)
     }
 
@@ -192,7 +248,10 @@ impl KnowledgeGraphSnapshot {
         engine.set_num_workers(self.num_workers);
         engine.set_max_result_rows(self.max_result_rows);
         engine.set_max_query_cost(self.max_query_cost);
-        self.configure_hnsw(&mut engine);
+        engine.set_max_query_memory(self.max_query_memory_bytes);
+        engine.set_query_timeout_ms(self.query_timeout_ms);
+        self.configure_engine(&mut engine);
+        self.share_subplan_cache(&mut engine);
         engine.execute_tuples(program)
     }
 
@@ -268,12 +327,15 @@ impl KnowledgeGraphSnapshot {
         engine.set_num_workers(self.num_workers);
         engine.set_max_result_rows(self.max_result_rows);
         engine.set_max_query_cost(self.max_query_cost);
+        engine.set_max_query_memory(self.max_query_memory_bytes);
+        engine.set_query_timeout_ms(self.query_timeout_ms);
         engine.set_timing_mode(timing_mode);
-        self.configure_hnsw(&mut engine);
+        self.configure_engine(&mut engine);
 
         // Use shared input for zero-copy
         engine.input_tuples.clone_from(&self.input_tuples);
         engine.set_shared_input(Arc::clone(&self.input_tuples));
+        self.share_subplan_cache(&mut engine);
 
         let result = engine.execute_tuples_profiled(&combined);
         info!(
@@ -316,7 +378,9 @@ impl KnowledgeGraphSnapshot {
         engine.set_num_workers(self.num_workers);
         engine.set_max_result_rows(self.max_result_rows);
         engine.set_max_query_cost(self.max_query_cost);
-        self.configure_hnsw(&mut engine);
+        engine.set_max_query_memory(self.max_query_memory_bytes);
+        engine.set_query_timeout_ms(self.query_timeout_ms);
+        self.configure_engine(&mut engine);
 
         // Copy-on-write: only clone relation vectors that receive session facts.
         // Relations without session facts share the same underlying data via Arc.
@@ -379,8 +443,10 @@ impl KnowledgeGraphSnapshot {
         engine.set_num_workers(self.num_workers);
         engine.set_max_result_rows(self.max_result_rows);
         engine.set_max_query_cost(self.max_query_cost);
+        engine.set_max_query_memory(self.max_query_memory_bytes);
+        engine.set_query_timeout_ms(self.query_timeout_ms);
         engine.set_timing_mode(timing_mode);
-        self.configure_hnsw(&mut engine);
+        self.configure_engine(&mut engine);
 
         // Copy-on-write: only clone relation vectors that receive session facts.
         let mut needs_mutation: HashMap<String, Vec<Tuple>> = HashMap::new();
@@ -421,11 +487,25 @@ impl KnowledgeGraphSnapshot {
         result
     }
 
-    /// Configure HNSW search on a IQLEngine if available.
-    fn configure_hnsw(&self, engine: &mut IQLEngine) {
+    /// Apply the schema-derived settings and HNSW search to an IQLEngine.
+    fn configure_engine(&self, engine: &mut IQLEngine) {
+        engine.set_unique_keys(Arc::clone(&self.unique_keys));
+        engine.set_coercion_mode(self.coercion);
+        engine.set_vector_dims(Arc::clone(&self.vector_dims));
+        engine.set_distinct_counts(Arc::clone(&self.distinct_counts));
         if let Some(ref search_fn) = self.hnsw_search_fn {
             let f = Arc::clone(search_fn);
             engine.set_hnsw_search_fn(Box::new(move |idx, query, k, ef| f(idx, query, k, ef)));
+            engine.set_indexed_columns(Arc::clone(&self.indexed_columns));
+        }
+    }
+
+    /// Let `engine` reuse and fill the knowledge graph's subplan cache.
+    /// Only for engines reading this snapshot's `input_tuples` unchanged.
+    fn share_subplan_cache(&self, engine: &mut IQLEngine) {
+        if let Some(cache) = &self.subplan_cache {
+            engine.set_relation_versions(Arc::clone(&self.relation_versions));
+            engine.set_subplan_cache(Arc::clone(cache));
         }
     }
 
@@ -460,6 +540,7 @@ impl std::fmt::Debug for KnowledgeGraphSnapshot {
         f.debug_struct("KnowledgeGraphSnapshot")
             .field("version", &self.version)
             .field("timestamp", &self.timestamp)
+            .field("lsn", &self.lsn)
             .field("relations", &self.relation_count())
             .field("tuples", &self.tuple_count())
             .field("rules", &self.rules.len())
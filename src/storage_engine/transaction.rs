@@ -0,0 +1,305 @@
+//! Explicit Transactions
+//!
+//! A [`Transaction`] collects inserts, deletes and rule (view) changes for
+//! one knowledge graph without applying any of them. Committing validates
+//! everything first, then persists all data writes under a single logical
+//! time (LSN), applies the rule changes and publishes one snapshot, all
+//! under the knowledge graph's write lock. Queries see either none of the
+//! transaction or all of it; a transaction that fails validation, or is
+//! rolled back, leaves no trace. Neither does one with a step that fails
+//! while it is applied: the steps already applied are undone, in memory
+//! and in the persist layer, before the lock is released, and no snapshot
+//! is published.
+//!
+//! Statements inside a transaction are queued, not executed, so they read
+//! the committed state (the transaction does not see its own writes).
+
+use super::batch::{op_relations, write_kinds, PendingWrite};
+use super::cdc::Change;
+use super::{BatchReport, KnowledgeGraph, StorageEngine, WriteOp};
+use crate::rule_catalog::RuleDefinition;
+use crate::schema::vector_dims;
+use crate::statement::RuleDef;
+use crate::storage::{StorageError, StorageResult};
+use crate::value::Tuple;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// A change to the persistent rules of a knowledge graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RuleChange {
+    /// Register a rule clause (`+name(...) <- ...`)
+    Register(RuleDef),
+    /// Drop all clauses of a rule
+    Drop(String),
+}
+
+impl RuleChange {
+    /// Name of the rule changed
+    fn name(&self) -> &str {
+        match self {
+            RuleChange::Register(rule_def) => &rule_def.name,
+            RuleChange::Drop(name) => name,
+        }
+    }
+}
+
+/// Writes queued for atomic commit to one knowledge graph
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    kg: String,
+    ops: Vec<WriteOp>,
+    rules: Vec<RuleChange>,
+}
+
+/// Outcome of a committed transaction
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommitReport {
+    /// Data written (`lsn` is 0 if the transaction only changed rules)
+    pub batch: BatchReport,
+    /// Rule registrations and drops applied
+    pub rule_changes: usize,
+}
+
+impl Transaction {
+    /// Knowledge graph the transaction writes to
+    pub fn kg(&self) -> &str {
+        &self.kg
+    }
+
+    /// Queue tuples to insert
+    pub fn insert(&mut self, relation: impl Into<String>, tuples: Vec<Tuple>) {
+        self.ops.push(WriteOp::Insert(relation.into(), tuples));
+    }
+
+    /// Queue tuples to delete
+    pub fn delete(&mut self, relation: impl Into<String>, tuples: Vec<Tuple>) {
+        self.ops.push(WriteOp::Delete(relation.into(), tuples));
+    }
+
+    /// Queue the steps of a write batch
+    pub fn extend(&mut self, ops: impl IntoIterator<Item = WriteOp>) {
+        self.ops.extend(ops);
+    }
+
+    /// Queue a rule registration
+    pub fn register_rule(&mut self, rule_def: RuleDef) {
+        self.rules.push(RuleChange::Register(rule_def));
+    }
+
+    /// Queue dropping a rule
+    pub fn drop_rule(&mut self, name: impl Into<String>) {
+        self.rules.push(RuleChange::Drop(name.into()));
+    }
+
+    /// Rule changes queued so far, in order
+    pub fn rule_changes(&self) -> &[RuleChange] {
+        &self.rules
+    }
+
+    /// Number of queued writes and rule changes
+    pub fn len(&self) -> usize {
+        self.ops.len() + self.rules.len()
+    }
+
+    /// Nothing has been queued
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty() && self.rules.is_empty()
+    }
+
+    /// Discard the transaction, returning how many queued changes were
+    /// dropped. Nothing was applied, so there is nothing to undo.
+    pub fn rollback(self) -> usize {
+        self.len()
+    }
+}
+
+impl StorageEngine {
+    /// Start a transaction on a knowledge graph
+    pub fn begin_transaction(&self, kg: &str) -> StorageResult<Transaction> {
+        self.with_kg_read(kg, |_| Ok(()))?;
+        Ok(Transaction {
+            kg: kg.to_string(),
+            ops: Vec::new(),
+            rules: Vec::new(),
+        })
+    }
+
+    /// Commit a transaction atomically.
+    ///
+    /// Data writes are validated as by [`StorageEngine::apply_batch_in`]
+    /// and rule changes are checked (stratification, vector dimensions,
+    /// existence of dropped rules) against a scratch copy of the rule
+    /// catalog. If anything is rejected nothing is applied. Otherwise the
+    /// data is persisted under one LSN, rules are registered after the data
+    /// so their materializations include it, and a single snapshot is
+    /// published.
+    pub fn commit_transaction(&self, tx: Transaction) -> StorageResult<CommitReport> {
+        let Transaction { kg, ops, rules, .. } = tx;
+
+        // Clone the Arc out so no map shard stays locked while the write
+        // lock is held (dropping a knowledge graph needs the shard)
+        let db = self
+            .knowledge_graphs
+            .get(&kg)
+            .map(|db| Arc::clone(db.value()))
+            .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.clone()))?;
+        let mut db = db.write();
+        db.make_resident(&op_relations(&ops), None)?;
+
+        // Validate everything before writing anything
+        let writes = self.prepare_writes(&db, &kg, ops)?;
+        db.check_writes(&writes)?;
+        if !rules.is_empty() {
+            let dims = db.vector_dim_map();
+            let mut scratch = db.rule_catalog.scratch_copy();
+            for change in &rules {
+                let checked = match change {
+                    RuleChange::Register(rule_def) => {
+                        vector_dims::check_rule(&rule_def.rule.to_rule(), &dims)
+                            .and_then(|()| scratch.register_rule(rule_def).map(|_| ()))
+                    }
+                    RuleChange::Drop(name) => scratch.drop(name),
+                };
+                checked.map_err(StorageError::Other)?;
+            }
+        }
+
+        self.commit_locked(&kg, &mut db, writes, rules)
+    }
+
+    /// Persist and apply validated writes, then the rule changes, under
+    /// the knowledge graph's write lock held by the caller, and publish
+    /// one snapshot. If a step fails, everything applied before it is
+    /// undone and nothing is published.
+    pub(super) fn commit_locked(
+        &self,
+        kg: &str,
+        db: &mut KnowledgeGraph,
+        writes: Vec<PendingWrite>,
+        rules: Vec<RuleChange>,
+    ) -> StorageResult<CommitReport> {
+        let kinds = write_kinds(&writes);
+        let replicated = self.replicated_writes(&writes);
+        let prior_lsn = db.lsn;
+        let mut lsn = 0;
+        let mut inverse = Vec::new();
+        let mut changes = Vec::new();
+        let (counts, mut failure) = if writes.iter().all(PendingWrite::is_empty) {
+            (vec![(0, 0); writes.len()], None)
+        } else {
+            lsn = self.persist_writes(kg, &writes, &db.partition_keys(&writes))?;
+            inverse = writes.iter().map(PendingWrite::inverse).collect();
+            db.stage_writes(writes, lsn, Some(&mut changes))
+        };
+
+        // The rules as they were before their first change, to put back
+        let mut previous: Vec<(String, Option<RuleDefinition>)> = Vec::new();
+        let mut rule_changes = 0;
+        if failure.is_none() {
+            for change in &rules {
+                let name = change.name();
+                let first_change = !previous.iter().any(|(changed, _)| changed == name);
+                if first_change {
+                    previous.push((name.to_string(), db.rule_catalog.get(name).cloned()));
+                }
+                let applied = match change {
+                    RuleChange::Register(rule_def) => db.add_rule_definition(rule_def).map(|_| ()),
+                    RuleChange::Drop(name) => db.remove_rule_definition(name),
+                };
+                if let Err(e) = applied {
+                    if first_change {
+                        previous.pop();
+                    }
+                    failure = Some(StorageError::Other(e));
+                    break;
+                }
+                rule_changes += 1;
+            }
+        }
+
+        if let Some(e) = failure {
+            db.restore_rules(previous);
+            db.undo_changes(changes, lsn);
+            db.lsn = prior_lsn;
+            if !inverse.is_empty() {
+                let undone = self.persist_writes(kg, &inverse, &db.partition_keys(&inverse));
+                if let Err(undo_error) = undone {
+                    tracing::error!(
+                        kg = %kg,
+                        lsn,
+                        error = %undo_error,
+                        "transaction_persist_rollback_failed"
+                    );
+                }
+            }
+            return Err(e);
+        }
+
+        self.record_writes(kg, replicated, counts.len(), &rules);
+        self.publish_changes(kg, lsn, self.change_capture(kg).map(|_| changes));
+
+        // One snapshot for the whole transaction
+        if rule_changes > 0 || counts.iter().any(|&(changed, _)| changed > 0) {
+            db.publish_snapshot();
+        }
+        Ok(CommitReport {
+            batch: BatchReport::new(lsn, kinds, counts),
+            rule_changes,
+        })
+    }
+}
+
+impl KnowledgeGraph {
+    /// Revert the tuples `changes` inserted and removed, latest first
+    fn undo_changes(&mut self, changes: Vec<Change>, time: u64) {
+        for change in changes.into_iter().rev() {
+            let undone = if change.diff > 0 {
+                self.delete_in_memory(&change.relation, &change.tuples, time)
+                    .map(|_| ())
+            } else {
+                self.insert_in_memory(&change.relation, change.tuples, time)
+                    .map(|_| ())
+            };
+            if let Err(e) = undone {
+                tracing::error!(
+                    kg = %self.name,
+                    relation = %change.relation,
+                    error = %e,
+                    "transaction_rollback_failed"
+                );
+            }
+        }
+    }
+
+    /// Put rules back as they were (`None`: did not exist), last changed
+    /// first
+    fn restore_rules(&mut self, previous: Vec<(String, Option<RuleDefinition>)>) {
+        for (name, definition) in previous.into_iter().rev() {
+            let mut restored = Ok(());
+            if self.rule_catalog.exists(&name) {
+                restored = self.remove_rule_definition(&name);
+            }
+            if let Some(definition) = definition {
+                for rule in definition.rules {
+                    let rule_def = RuleDef {
+                        name: name.clone(),
+                        rule,
+                    };
+                    restored =
+                        restored.and_then(|()| self.add_rule_definition(&rule_def).map(|_| ()));
+                }
+                restored = restored
+                    .and_then(|()| self.rule_catalog.set_refresh(&name, definition.refresh));
+            }
+            if let Err(e) = restored {
+                tracing::error!(
+                    kg = %self.name,
+                    rule = %name,
+                    error = %e,
+                    "transaction_rule_rollback_failed"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,401 @@
+//! View Refresh Policies
+//!
+//! A persistent rule (a view) is normally evaluated by every query that
+//! reads it, so its results are always current. A view with a refresh
+//! policy (see [`RefreshPolicy`]) is cached instead: queries read the
+//! cached tuples, and the policy decides when they are recomputed:
+//!
+//! ```text
+//! .rule refresh top_products eager       -- after every write it depends on
+//! .rule refresh top_products lazy        -- on the next read after such a write
+//! .rule refresh top_products every 5m    -- by the background sweep
+//! .rule refresh top_products none        -- back to evaluating on every query
+//! .rule refresh top_products             -- recompute now
+//! ```
+//!
+//! A write to any relation a cached view reads, directly or through other
+//! rules, marks it stale, as does a change to those rules. Caches live in
+//! memory only: after a restart each cached view is computed on its first
+//! read. `show views` reports each view's policy, when it was last
+//! refreshed and whether it is stale.
+//!
+//! Periodic views are refreshed every
+//! `storage.view_refresh_sweep_interval_secs` once their interval has
+//! passed, and only if they are stale.
+
+use super::qualified::split_qualified;
+use super::snapshot::KnowledgeGraphSnapshot;
+use super::{format_rule, KnowledgeGraph, StorageEngine};
+use crate::ast::BodyPredicate;
+use crate::rule_catalog::RefreshPolicy;
+use crate::storage::{StorageError, StorageResult};
+use crate::value::Tuple;
+use chrono::Utc;
+use std::collections::{HashMap, HashSet};
+use tracing::{info, warn};
+
+/// Cached result of a view with a refresh policy
+#[derive(Debug)]
+struct CachedView {
+    tuples: Vec<Tuple>,
+    /// Text of the rules the result was computed from
+    definition: String,
+    /// Relations those rules read; a write to any of them makes the
+    /// result stale
+    reads: HashSet<String>,
+    /// Unix milliseconds of the last refresh
+    refreshed_at_ms: i64,
+    stale: bool,
+}
+
+/// Cached results of the views of a knowledge graph
+#[derive(Debug, Default)]
+pub(super) struct ViewCache {
+    views: HashMap<String, CachedView>,
+}
+
+impl ViewCache {
+    /// Mark the cached views reading `relation` stale
+    pub(super) fn mark_stale(&mut self, relation: &str) {
+        for view in self.views.values_mut() {
+            if view.reads.contains(relation) {
+                view.stale = true;
+            }
+        }
+    }
+}
+
+impl KnowledgeGraph {
+    /// Views with a refresh policy
+    fn view_policies(&self) -> Vec<(String, RefreshPolicy)> {
+        self.rule_catalog
+            .list()
+            .into_iter()
+            .filter_map(|name| {
+                let policy = self.rule_catalog.get(&name)?.refresh?;
+                Some((name, policy))
+            })
+            .collect()
+    }
+
+    /// The rules a view is computed from, as text, and every relation
+    /// they read
+    fn view_closure(&self, view: &str) -> (String, HashSet<String>) {
+        let mut definition = String::new();
+        let mut reads = HashSet::new();
+        let mut visited = HashSet::new();
+        let mut pending = vec![view.to_string()];
+        while let Some(name) = pending.pop() {
+            if !visited.insert(name.clone()) {
+                continue;
+            }
+            let Some(def) = self.rule_catalog.get(&name) else {
+                continue;
+            };
+            for rule in def.to_rules() {
+                for atom in rule.body.iter().filter_map(BodyPredicate::atom) {
+                    reads.insert(atom.relation.clone());
+                    pending.push(atom.relation.clone());
+                }
+                definition.push_str(&format_rule(&rule));
+                definition.push('\n');
+            }
+        }
+        (definition, reads)
+    }
+
+    /// Refresh policy and status of a view: when it was last refreshed
+    /// and whether it is stale (`None` until first computed)
+    pub(super) fn view_status(&self, view: &str) -> Option<(RefreshPolicy, Option<(i64, bool)>)> {
+        let policy = self.rule_catalog.get(view)?.refresh?;
+        let status = self
+            .views
+            .lock()
+            .views
+            .get(view)
+            .map(|cached| (cached.refreshed_at_ms, cached.stale));
+        Some((policy, status))
+    }
+
+    /// Recompute the cached results of `views` from the current base data.
+    /// A view that fails keeps its previous result, still stale.
+    fn refresh_views(&self, views: &[String]) -> Vec<(String, Result<usize, String>)> {
+        if views.is_empty() {
+            return Vec::new();
+        }
+        let mut snapshot = KnowledgeGraphSnapshot::new_with_workers(
+            self.engine.input_tuples.clone(),
+            self.rule_catalog.all_rules(),
+            self.num_workers,
+        );
+        snapshot.hnsw_search_fn = self.build_hnsw_search_fn();
+        let now_ms = Utc::now().timestamp_millis();
+
+        views
+            .iter()
+            .map(|name| {
+                let result = self
+                    .rule_catalog
+                    .rule_arity(name)
+                    .ok_or_else(|| format!("Rule '{name}' does not exist"))
+                    .and_then(|arity| {
+                        let vars: Vec<String> = (0..arity).map(|i| format!("V{i}")).collect();
+                        let vars = vars.join(", ");
+                        snapshot.execute_with_rules_tuples(&format!(
+                            "__view__({vars}) <- {name}({vars})"
+                        ))
+                    })
+                    .map(|tuples| {
+                        let rows = tuples.len();
+                        let (definition, reads) = self.view_closure(name);
+                        self.views.lock().views.insert(
+                            name.clone(),
+                            CachedView {
+                                tuples,
+                                definition,
+                                reads,
+                                refreshed_at_ms: now_ms,
+                                stale: false,
+                            },
+                        );
+                        rows
+                    });
+                (name.clone(), result)
+            })
+            .collect()
+    }
+
+    /// Bring the view cache in line with the rule catalog before a
+    /// snapshot is published, refreshing the stale eager views, and return
+    /// the cached results to publish
+    pub(super) fn cached_views_for_snapshot(&self) -> HashMap<String, Vec<Tuple>> {
+        let policies: HashMap<String, RefreshPolicy> = self.view_policies().into_iter().collect();
+        let eager = {
+            let mut cache = self.views.lock();
+            cache.views.retain(|name, _| policies.contains_key(name));
+            for (name, cached) in &mut cache.views {
+                if cached.definition != self.view_closure(name).0 {
+                    cached.stale = true;
+                }
+            }
+            policies
+                .iter()
+                .filter(|(name, policy)| {
+                    **policy == RefreshPolicy::Eager
+                        && cache.views.get(*name).is_none_or(|cached| cached.stale)
+                })
+                .map(|(name, _)| name.clone())
+                // Left for the next read if some of its data is not in memory
+                .filter(|name| {
+                    !self
+                        .view_closure(name)
+                        .1
+                        .iter()
+                        .any(|r| self.residency.is_evicted(r))
+                })
+                .collect::<Vec<_>>()
+        };
+        self.log_refreshes(self.refresh_views(&eager), "eager");
+
+        self.views
+            .lock()
+            .views
+            .iter()
+            .map(|(name, cached)| (name.clone(), cached.tuples.clone()))
+            .collect()
+    }
+
+    /// Views read by `program` (directly or through rules) whose cached
+    /// result must be recomputed before it runs: never computed, or stale
+    /// and not refreshed periodically
+    fn views_due_on_read(&self, program: &str) -> Vec<String> {
+        let policies: HashMap<String, RefreshPolicy> = self.view_policies().into_iter().collect();
+        if policies.is_empty() {
+            return Vec::new();
+        }
+        let cache = self.views.lock();
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut due = Vec::new();
+        let mut pending = vec![program.to_string()];
+        while let Some(text) = pending.pop() {
+            for word in text
+                .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .filter(|word| !word.is_empty())
+            {
+                if !seen.insert(word.to_string()) {
+                    continue;
+                }
+                let Some(def) = self.rule_catalog.get(word) else {
+                    continue;
+                };
+                match (policies.get(word), cache.views.get(word)) {
+                    (Some(_), None) => due.push(word.to_string()),
+                    (Some(policy), Some(cached)) => {
+                        if cached.stale && !matches!(policy, RefreshPolicy::Periodic { .. }) {
+                            due.push(word.to_string());
+                        }
+                        // Read from the cache, not through its rules
+                        continue;
+                    }
+                    (None, _) => {}
+                }
+                pending.extend(def.to_rules().iter().map(ToString::to_string));
+            }
+        }
+        due.sort();
+        due
+    }
+
+    /// Refresh the views `program` needs current, publishing a new
+    /// snapshot if any was refreshed
+    pub(super) fn refresh_views_for_read(&self, program: &str) {
+        let due = self.views_due_on_read(program);
+        if due.is_empty() {
+            return;
+        }
+        self.log_refreshes(self.refresh_views(&due), "on_read");
+        self.publish_snapshot();
+    }
+
+    /// Periodic views whose interval has passed at `now_ms` and that are
+    /// stale or were never computed
+    fn periodic_views_due(&self, now_ms: i64) -> Vec<String> {
+        let cache = self.views.lock();
+        self.view_policies()
+            .into_iter()
+            .filter_map(|(name, policy)| {
+                let RefreshPolicy::Periodic { interval_ms } = policy else {
+                    return None;
+                };
+                let due = cache.views.get(&name).is_none_or(|cached| {
+                    cached.stale && now_ms - cached.refreshed_at_ms >= interval_ms
+                });
+                due.then_some(name)
+            })
+            .collect()
+    }
+
+    fn log_refreshes(&self, results: Vec<(String, Result<usize, String>)>, trigger: &str) {
+        for (view, result) in results {
+            match result {
+                Ok(rows) => info!(kg = %self.name, view = %view, rows, trigger, "view_refreshed"),
+                Err(error) => {
+                    warn!(kg = %self.name, view = %view, error = %error, trigger, "view_refresh_failed");
+                }
+            }
+        }
+    }
+}
+
+impl StorageEngine {
+    /// Set or remove (`None`) the refresh policy of a view. A view given a
+    /// policy is computed right away; returns its row count (0 when the
+    /// policy is removed).
+    pub fn set_refresh_policy_in(
+        &self,
+        kg: &str,
+        view: &str,
+        policy: Option<RefreshPolicy>,
+    ) -> StorageResult<usize> {
+        if let Some(RefreshPolicy::Periodic { interval_ms }) = policy {
+            if interval_ms <= 0 {
+                return Err(StorageError::Other(
+                    "Refresh interval must be positive".to_string(),
+                ));
+            }
+        }
+        let db = self
+            .knowledge_graphs
+            .get(kg)
+            .map(|db| std::sync::Arc::clone(db.value()))
+            .ok_or_else(|| StorageError::KnowledgeGraphNotFound(kg.to_string()))?;
+        let mut db = db.write();
+        if !db.rule_catalog.exists(view) {
+            return Err(StorageError::Other(format!("Rule '{view}' does not exist")));
+        }
+        let reads = db.view_closure(view).1;
+        if policy.is_some() {
+            if let Some(foreign) = reads.iter().find(|r| split_qualified(r).is_some()) {
+                return Err(StorageError::Other(format!(
+                    "Rule '{view}' reads '{foreign}' from another knowledge graph; \
+                     it is evaluated on every query and cannot be cached"
+                )));
+            }
+        }
+        db.rule_catalog
+            .set_refresh(view, policy)
+            .map_err(StorageError::Other)?;
+        let rows = if policy.is_some() {
+            let reads: Vec<String> = reads.into_iter().collect();
+            db.make_resident(&reads, None)?;
+            let (_, result) = db
+                .refresh_views(&[view.to_string()])
+                .pop()
+                .ok_or_else(|| StorageError::Other(format!("Rule '{view}' does not exist")))?;
+            result.map_err(StorageError::Other)?
+        } else {
+            0
+        };
+        db.publish_snapshot();
+        info!(kg, view, policy = ?policy, "view_refresh_policy_set");
+        Ok(rows)
+    }
+
+    /// Recompute the cached result of a view with a refresh policy now.
+    /// Returns its row count.
+    pub fn refresh_view_in(&self, kg: &str, view: &str) -> StorageResult<usize> {
+        let needed = |db: &KnowledgeGraph| db.view_closure(view).1.into_iter().collect();
+        let result = self.with_resident(kg, needed, |db| {
+            if db.view_status(view).is_none() {
+                return Err(format!(
+                    "Rule '{view}' has no refresh policy; it is evaluated on every query"
+                ));
+            }
+            let (_, result) = db
+                .refresh_views(&[view.to_string()])
+                .pop()
+                .ok_or_else(|| format!("Rule '{view}' does not exist"))?;
+            db.publish_snapshot();
+            result
+        })?;
+        result.map_err(StorageError::Other)
+    }
+
+    /// Refresh the periodic views of a knowledge graph that are due.
+    /// Returns the number refreshed.
+    pub fn refresh_due_views_in(&self, kg: &str) -> StorageResult<usize> {
+        let now_ms = Utc::now().timestamp_millis();
+        let needed = |db: &KnowledgeGraph| {
+            db.periodic_views_due(now_ms)
+                .iter()
+                .flat_map(|view| db.view_closure(view).1)
+                .collect()
+        };
+        self.with_resident(kg, needed, |db| {
+            let due = db.periodic_views_due(now_ms);
+            if due.is_empty() {
+                return 0;
+            }
+            let results = db.refresh_views(&due);
+            let refreshed = results.iter().filter(|(_, r)| r.is_ok()).count();
+            db.log_refreshes(results, "periodic");
+            db.publish_snapshot();
+            refreshed
+        })
+    }
+
+    /// Refresh the due periodic views of all knowledge graphs. A knowledge
+    /// graph whose refresh fails is logged and skipped. Returns the number
+    /// of views refreshed.
+    pub fn refresh_due_views(&self) -> usize {
+        self.list_knowledge_graphs()
+            .iter()
+            .map(|kg| {
+                self.refresh_due_views_in(kg).unwrap_or_else(|e| {
+                    warn!(kg, error = %e, "View refresh sweep failed");
+                    0
+                })
+            })
+            .sum()
+    }
+}
@@ -0,0 +1,84 @@
+//! Background WAL Sync
+//!
+//! In `batched` durability mode appends sync at most once per
+//! `wal_sync_interval_ms`, so the tail written before writes stop would
+//! stay unsynced. The engine runs [`WalSyncer`] for as long as it lives
+//! to sync that tail, whichever frontend (if any) it is embedded in.
+
+use crate::storage::persist::FilePersist;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Weak};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Thread syncing due WAL entries every interval; stopped when dropped
+pub(super) struct WalSyncer {
+    stop: Option<mpsc::Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl WalSyncer {
+    /// Sync the WAL of `persist` every `interval` until dropped
+    pub(super) fn start(persist: &Arc<FilePersist>, interval: Duration) -> std::io::Result<Self> {
+        let persist: Weak<FilePersist> = Arc::downgrade(persist);
+        let (stop, stopped) = mpsc::channel::<()>();
+        let handle = std::thread::Builder::new()
+            .name("wal-sync".to_string())
+            .spawn(move || {
+                while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                    let Some(persist) = persist.upgrade() else {
+                        break;
+                    };
+                    if let Err(e) = persist.sync_wal_if_due() {
+                        tracing::warn!(error = %e, "wal_sync_error");
+                    }
+                }
+                tracing::debug!("wal_sync_shutdown");
+            })?;
+        Ok(WalSyncer {
+            stop: Some(stop),
+            handle: Some(handle),
+        })
+    }
+}
+
+impl Drop for WalSyncer {
+    fn drop(&mut self) {
+        // Disconnecting the channel wakes the thread
+        self.stop.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use crate::config::{Config, DurabilityMode};
+    use crate::storage_engine::StorageEngine;
+    use crate::value::Tuple;
+    use std::time::Duration;
+
+    #[test]
+    fn test_engine_syncs_batched_wal_tail() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage.data_dir = tmp.path().to_path_buf();
+        config.storage.persist.durability_mode = DurabilityMode::Batched;
+        config.storage.persist.wal_sync_interval_ms = 100;
+        let storage = StorageEngine::new(config).unwrap();
+
+        // The second append comes within the interval, so it is not synced
+        for (a, b) in [(1, 2), (2, 3)] {
+            storage
+                .insert_tuples_into("default", "edge", vec![Tuple::from_pair(a, b)])
+                .unwrap();
+        }
+        std::thread::sleep(Duration::from_millis(400));
+
+        // Nothing is left for a caller to sync
+        assert!(!storage.sync_wal_if_due().unwrap());
+        drop(storage);
+    }
+}
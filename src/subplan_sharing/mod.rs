@@ -200,6 +200,11 @@ impl SubplanSharer {
                 input: Box::new(self.rewrite_with_shared_views(input, hash_to_view)),
             },
 
+            IRNode::Sort { input, order_by } => IRNode::Sort {
+                input: Box::new(self.rewrite_with_shared_views(input, hash_to_view)),
+                order_by: order_by.clone(),
+            },
+
             IRNode::Union { inputs } => IRNode::Union {
                 inputs: inputs
                     .iter()
@@ -233,6 +238,20 @@ impl SubplanSharer {
                 output_schema: output_schema.clone(),
             },
 
+            IRNode::Semijoin {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                output_schema,
+            } => IRNode::Semijoin {
+                left: Box::new(self.rewrite_with_shared_views(left, hash_to_view)),
+                right: Box::new(self.rewrite_with_shared_views(right, hash_to_view)),
+                left_keys: left_keys.clone(),
+                right_keys: right_keys.clone(),
+                output_schema: output_schema.clone(),
+            },
+
             IRNode::Compute { input, expressions } => IRNode::Compute {
                 input: Box::new(self.rewrite_with_shared_views(input, hash_to_view)),
                 expressions: expressions.clone(),
@@ -301,7 +320,7 @@ impl SubplanSharer {
                 self.collect_subtrees(left, ir_idx, subtree_counts);
                 self.collect_subtrees(right, ir_idx, subtree_counts);
             }
-            IRNode::Distinct { input } => {
+            IRNode::Distinct { input } | IRNode::Sort { input, .. } => {
                 self.collect_subtrees(input, ir_idx, subtree_counts);
             }
             IRNode::Union { inputs } => {
@@ -312,7 +331,7 @@ impl SubplanSharer {
             IRNode::Aggregate { input, .. } => {
                 self.collect_subtrees(input, ir_idx, subtree_counts);
             }
-            IRNode::Antijoin { left, right, .. } => {
+            IRNode::Antijoin { left, right, .. } | IRNode::Semijoin { left, right, .. } => {
                 self.collect_subtrees(left, ir_idx, subtree_counts);
                 self.collect_subtrees(right, ir_idx, subtree_counts);
             }
@@ -434,6 +453,15 @@ impl SubplanSharer {
                 }
             }
 
+            IRNode::Sort { input, order_by } => {
+                let canonical_input = self.canonicalize_recursive(input, var_counter, var_mapping);
+
+                IRNode::Sort {
+                    input: Box::new(canonical_input),
+                    order_by: order_by.clone(),
+                }
+            }
+
             IRNode::Union { inputs } => {
                 let canonical_inputs: Vec<IRNode> = inputs
                     .iter()
@@ -488,6 +516,29 @@ impl SubplanSharer {
                 }
             }
 
+            IRNode::Semijoin {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                output_schema,
+            } => {
+                let canonical_left = self.canonicalize_recursive(left, var_counter, var_mapping);
+                let canonical_right = self.canonicalize_recursive(right, var_counter, var_mapping);
+                let canonical_output: Vec<String> = output_schema
+                    .iter()
+                    .map(|var| self.get_canonical_var(var, var_counter, var_mapping))
+                    .collect();
+
+                IRNode::Semijoin {
+                    left: Box::new(canonical_left),
+                    right: Box::new(canonical_right),
+                    left_keys: left_keys.clone(),
+                    right_keys: right_keys.clone(),
+                    output_schema: canonical_output,
+                }
+            }
+
             IRNode::Compute { input, expressions } => {
                 let canonical_input = self.canonicalize_recursive(input, var_counter, var_mapping);
 
@@ -584,8 +635,11 @@ impl SubplanSharer {
         }
     }
 
-    /// Compute structural hash of an IR node
-    fn hash_ir(&self, ir: &IRNode) -> u64 {
+    /// Compute structural hash of an IR node.
+    ///
+    /// Variable names are ignored, so renamed but otherwise identical
+    /// subplans hash the same. Also used as the cross-query cache key.
+    pub(crate) fn hash_ir(&self, ir: &IRNode) -> u64 {
         let mut hasher = DefaultHasher::new();
         self.hash_ir_recursive(ir, &mut hasher);
         hasher.finish()
@@ -638,6 +692,11 @@ impl SubplanSharer {
                 self.hash_ir_recursive(input, hasher);
             }
 
+            IRNode::Sort { input, order_by } => {
+                order_by.hash(hasher);
+                self.hash_ir_recursive(input, hasher);
+            }
+
             IRNode::Union { inputs } => {
                 inputs.len().hash(hasher);
                 for input in inputs {
@@ -665,6 +724,13 @@ impl SubplanSharer {
                 left_keys,
                 right_keys,
                 ..
+            }
+            | IRNode::Semijoin {
+                left,
+                right,
+                left_keys,
+                right_keys,
+                ..
             } => {
                 left_keys.hash(hasher);
                 right_keys.hash(hasher);
@@ -739,6 +805,7 @@ impl SubplanSharer {
             IRNode::Map { input, .. }
             | IRNode::Filter { input, .. }
             | IRNode::Distinct { input }
+            | IRNode::Sort { input, .. }
             | IRNode::Aggregate { input, .. }
             | IRNode::Compute { input, .. }
             | IRNode::FlatMap { input, .. } => {
@@ -746,6 +813,7 @@ impl SubplanSharer {
             }
             IRNode::Join { left, right, .. }
             | IRNode::Antijoin { left, right, .. }
+            | IRNode::Semijoin { left, right, .. }
             | IRNode::JoinFlatMap { left, right, .. } => {
                 Self::references_derived_relation(left, derived_relations)
                     || Self::references_derived_relation(right, derived_relations)
@@ -771,7 +839,9 @@ impl SubplanSharer {
             IRNode::Join { left, right, .. } => {
                 1 + self.subtree_depth(left).max(self.subtree_depth(right))
             }
-            IRNode::Distinct { input } => 1 + self.subtree_depth(input),
+            IRNode::Distinct { input } | IRNode::Sort { input, .. } => {
+                1 + self.subtree_depth(input)
+            }
             IRNode::Union { inputs } => {
                 1 + inputs
                     .iter()
@@ -780,7 +850,7 @@ impl SubplanSharer {
                     .unwrap_or(0)
             }
             IRNode::Aggregate { input, .. } => 1 + self.subtree_depth(input),
-            IRNode::Antijoin { left, right, .. } => {
+            IRNode::Antijoin { left, right, .. } | IRNode::Semijoin { left, right, .. } => {
                 1 + self.subtree_depth(left).max(self.subtree_depth(right))
             }
             IRNode::Compute { input, .. } => 1 + self.subtree_depth(input),
@@ -848,14 +918,16 @@ impl SubplanSharer {
                 self.count_subtrees_internal(left, counts);
                 self.count_subtrees_internal(right, counts);
             }
-            IRNode::Distinct { input } => self.count_subtrees_internal(input, counts),
+            IRNode::Distinct { input } | IRNode::Sort { input, .. } => {
+                self.count_subtrees_internal(input, counts);
+            }
             IRNode::Union { inputs } => {
                 for input in inputs {
                     self.count_subtrees_internal(input, counts);
                 }
             }
             IRNode::Aggregate { input, .. } => self.count_subtrees_internal(input, counts),
-            IRNode::Antijoin { left, right, .. } => {
+            IRNode::Antijoin { left, right, .. } | IRNode::Semijoin { left, right, .. } => {
                 self.count_subtrees_internal(left, counts);
                 self.count_subtrees_internal(right, counts);
             }
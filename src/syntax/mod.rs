@@ -148,11 +148,22 @@ const SCHEMA_TYPES: &[&str] = &[
     "timestamp",
     "time",
     "datetime",
+    "date",
+    "duration",
+    "interval",
+    "uuid",
     "vector",
     "embedding",
     "vec",
     "any",
     "list",
+    "map",
+    "json",
+    "jsonb",
+    "bytes",
+    "blob",
+    "binary",
+    "bytea",
 ];
 
 /// Promote flat tokens to semantic variants based on structural context.
@@ -0,0 +1,70 @@
+//! OpenTelemetry Export
+//!
+//! Sends `tracing` spans to an OTLP/gRPC collector (`[tracing]` in the
+//! config). A query produces one trace: the `ws_request` span of the
+//! WebSocket message, the `query` span carrying its query ID, then `engine_parse`,
+//! `engine_optimize` and `engine_execute` with one `engine_rule` span per
+//! rule (index, head, stratum, output rows), and `persist_append` /
+//! `persist_flush` for writes.
+//!
+//! Spans are batched and exported from a background task, so a slow or
+//! unreachable collector never blocks queries; spans that cannot be sent
+//! are dropped.
+
+use std::sync::OnceLock;
+
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::trace::{Sampler, TracerProvider};
+use opentelemetry_sdk::{runtime, Resource};
+use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::config::TracingConfig;
+
+pub use opentelemetry_sdk::trace::Tracer;
+
+static PROVIDER: OnceLock<TracerProvider> = OnceLock::new();
+
+/// Start the OTLP exporter and return the tracer spans are recorded with.
+/// Must be called from within a Tokio runtime.
+pub fn init_tracer(config: &TracingConfig) -> Result<Tracer, String> {
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(&config.otlp_endpoint)
+        .build()
+        .map_err(|e| format!("OTLP exporter for {}: {e}", config.otlp_endpoint))?;
+
+    let sampler = Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(config.sample_ratio)));
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_sampler(sampler)
+        .with_resource(Resource::new(vec![
+            KeyValue::new("service.name", config.service_name.clone()),
+            KeyValue::new("service.version", env!("CARGO_PKG_VERSION")),
+        ]))
+        .build();
+    let tracer = provider.tracer("inputlayer");
+    opentelemetry::global::set_tracer_provider(provider.clone());
+    let _ = PROVIDER.set(provider);
+    Ok(tracer)
+}
+
+/// A subscriber layer that exports spans through `tracer`
+pub fn layer<S>(tracer: Tracer) -> OpenTelemetryLayer<S, Tracer>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    tracing_opentelemetry::layer().with_tracer(tracer)
+}
+
+/// Export spans still buffered. Called on shutdown.
+pub fn shutdown() {
+    if let Some(provider) = PROVIDER.get() {
+        if let Err(e) = provider.shutdown() {
+            eprintln!("WARNING: Failed to flush OpenTelemetry spans: {e}");
+        }
+    }
+}
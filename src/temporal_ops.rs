@@ -1,10 +1,18 @@
 //! Temporal operations for spatio-temporal memory systems.
 //!
 //! Provides timestamp arithmetic, time decay functions, and temporal predicates
-//! for implementing recency-weighted retrieval and temporal queries.
+//! for implementing recency-weighted retrieval and temporal queries, plus the
+//! calendar conversions behind `Value::Date` and `Value::Duration`.
 
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Milliseconds in one calendar day
+pub const MS_PER_DAY: i64 = 86_400_000;
+
+/// Days from 0001-01-01 (chrono's day 1) to 1970-01-01
+const UNIX_EPOCH_DAYS_FROM_CE: i32 = 719_163;
+
 // Core Time Functions
 /// Get current time as Unix milliseconds since epoch.
 ///
@@ -231,12 +239,198 @@ pub fn point_in_interval(ts: i64, start: i64, end: i64) -> bool {
     ts >= start && ts <= end
 }
 
+// Dates and Durations
+/// Parse an ISO 8601 calendar date (`YYYY-MM-DD`) into days since the Unix
+/// epoch.
+///
+/// # Returns
+/// `None` if the text is not a valid calendar date.
+pub fn parse_date(s: &str) -> Option<i32> {
+    let date = NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d").ok()?;
+    Some(date.num_days_from_ce() - UNIX_EPOCH_DAYS_FROM_CE)
+}
+
+/// Parse an ISO 8601 date-time into Unix milliseconds.
+///
+/// Accepts RFC 3339 (`2024-05-01T12:00:00Z`, with any offset) and naive
+/// `YYYY-MM-DD HH:MM:SS` / `YYYY-MM-DDTHH:MM:SS` forms, read as UTC.
+///
+/// # Returns
+/// `None` if the text is not a date-time.
+pub fn parse_timestamp(s: &str) -> Option<i64> {
+    let s = s.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.timestamp_millis());
+    }
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(s, fmt).ok())
+        .map(|dt| dt.and_utc().timestamp_millis())
+}
+
+/// Format days since the Unix epoch as an ISO 8601 date (`YYYY-MM-DD`).
+///
+/// Days outside the supported calendar range are formatted as `day:N`.
+pub fn format_date(days: i32) -> String {
+    days.checked_add(UNIX_EPOCH_DAYS_FROM_CE)
+        .and_then(NaiveDate::from_num_days_from_ce_opt)
+        .map_or_else(
+            || format!("day:{days}"),
+            |date| date.format("%Y-%m-%d").to_string(),
+        )
+}
+
+/// Date (days since epoch) containing a timestamp (milliseconds, UTC).
+#[inline]
+pub fn timestamp_to_date(ts: i64) -> i32 {
+    let days = ts.div_euclid(MS_PER_DAY);
+    days.clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32
+}
+
+/// Timestamp (milliseconds) of midnight UTC at the start of a date.
+#[inline]
+pub fn date_to_timestamp(days: i32) -> i64 {
+    i64::from(days) * MS_PER_DAY
+}
+
+/// Add a duration to a date.
+///
+/// Dates have day resolution: the duration is rounded down to whole days,
+/// so `date + 36h` is the next day and `date - 1ms` is the previous one.
+#[inline]
+pub fn date_add(days: i32, duration_ms: i64) -> i32 {
+    timestamp_to_date(date_to_timestamp(days).saturating_add(duration_ms))
+}
+
+/// Parse a duration literal into milliseconds.
+///
+/// A duration is an optionally negative sequence of `<integer><unit>` parts,
+/// with units `w`, `d`, `h`, `m`, `s` and `ms`: `90s`, `1d12h`, `-2w`,
+/// `1h 30m`.
+///
+/// # Returns
+/// `None` for malformed text, unknown units or overflow.
+pub fn parse_duration(s: &str) -> Option<i64> {
+    let s = s.trim();
+    let (negative, body) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest.trim_start()),
+        None => (false, s),
+    };
+    if body.is_empty() {
+        return None;
+    }
+
+    let mut total: i64 = 0;
+    let mut rest = body;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if digits == 0 {
+            return None;
+        }
+        let amount: i64 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+        let unit_len = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        let unit_ms = match &rest[..unit_len] {
+            "w" => 7 * MS_PER_DAY,
+            "d" => MS_PER_DAY,
+            "h" => 3_600_000,
+            "m" => 60_000,
+            "s" => 1_000,
+            "ms" => 1,
+            _ => return None,
+        };
+        total = total.checked_add(amount.checked_mul(unit_ms)?)?;
+        rest = rest[unit_len..].trim_start();
+    }
+    Some(if negative { -total } else { total })
+}
+
+/// Format milliseconds as a duration literal accepted by `parse_duration`,
+/// e.g. `1d2h30m` or `-500ms`.
+pub fn format_duration(ms: i64) -> String {
+    if ms == 0 {
+        return "0s".to_string();
+    }
+    let mut out = String::new();
+    if ms < 0 {
+        out.push('-');
+    }
+    let mut rest = ms.unsigned_abs();
+    for (unit, unit_ms) in [
+        ("d", MS_PER_DAY as u64),
+        ("h", 3_600_000),
+        ("m", 60_000),
+        ("s", 1_000),
+        ("ms", 1),
+    ] {
+        if rest >= unit_ms {
+            out.push_str(&format!("{}{unit}", rest / unit_ms));
+            rest %= unit_ms;
+        }
+    }
+    out
+}
+
 // Tests
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
 
+    // Dates and Durations
+    #[test]
+    fn test_date_roundtrip() {
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        assert_eq!(parse_date("1969-12-31"), Some(-1));
+        assert_eq!(parse_date("2024-02-29"), Some(19_782));
+        assert_eq!(format_date(19_782), "2024-02-29");
+        assert_eq!(parse_date("2023-02-29"), None);
+        assert_eq!(parse_date("yesterday"), None);
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:01Z"), Some(1000));
+        assert_eq!(parse_timestamp("1970-01-01T01:00:00+01:00"), Some(0));
+        assert_eq!(parse_timestamp("1970-01-02 00:00:00"), Some(MS_PER_DAY));
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00.250"), Some(250));
+        assert_eq!(parse_timestamp("1970-01-01"), None);
+        assert_eq!(parse_timestamp("noon"), None);
+    }
+
+    #[test]
+    fn test_date_timestamp_conversion() {
+        assert_eq!(timestamp_to_date(MS_PER_DAY - 1), 0);
+        assert_eq!(timestamp_to_date(-1), -1);
+        assert_eq!(date_to_timestamp(2), 2 * MS_PER_DAY);
+        assert_eq!(date_add(0, 36 * 3_600_000), 1);
+        assert_eq!(date_add(0, -1), -1);
+    }
+
+    #[test]
+    fn test_duration_roundtrip() {
+        assert_eq!(parse_duration("90s"), Some(90_000));
+        assert_eq!(parse_duration("1d12h"), Some(36 * 3_600_000));
+        assert_eq!(parse_duration("1h 30m"), Some(5_400_000));
+        assert_eq!(parse_duration("-2w"), Some(-14 * MS_PER_DAY));
+        assert_eq!(parse_duration("250ms"), Some(250));
+        assert_eq!(parse_duration("5y"), None);
+        assert_eq!(parse_duration("h"), None);
+        assert_eq!(parse_duration(""), None);
+
+        assert_eq!(format_duration(93_784_005), "1d2h3m4s5ms");
+        assert_eq!(format_duration(-500), "-500ms");
+        assert_eq!(format_duration(0), "0s");
+        assert_eq!(
+            parse_duration(&format_duration(93_784_005)),
+            Some(93_784_005)
+        );
+    }
+
     // Core Time Functions
     #[test]
     fn test_time_now_returns_reasonable_value() {
@@ -5,11 +5,13 @@
 
 use super::{DataType, Tuple, TupleSchema, Value};
 use arrow::array::{
-    Array, ArrayRef, BooleanArray, FixedSizeListArray, Float32Array, Float64Array, Int32Array,
-    Int64Array, Int8Array, LargeListArray, ListArray, StringArray,
+    Array, ArrayRef, BinaryArray, BooleanArray, Date32Array, DurationMillisecondArray,
+    FixedSizeBinaryArray, FixedSizeListArray, Float32Array, Float64Array, Int32Array, Int64Array,
+    Int8Array, LargeBinaryArray, LargeListArray, LargeStringArray, ListArray, MapArray,
+    StringArray, StructArray,
 };
-use arrow::buffer::OffsetBuffer;
-use arrow::datatypes::{DataType as ArrowDataType, Field};
+use arrow::buffer::{NullBuffer, OffsetBuffer};
+use arrow::datatypes::{DataType as ArrowDataType, Field, FieldRef, Fields};
 use arrow::record_batch::RecordBatch;
 use std::sync::Arc;
 
@@ -27,6 +29,93 @@ pub enum ArrowConvertError {
     ArrowError(#[from] arrow::error::ArrowError),
 }
 
+/// Item field of a `List` column: each element is the JSON encoding of a `Value`
+pub(crate) fn list_item_field() -> FieldRef {
+    Arc::new(Field::new("item", ArrowDataType::LargeUtf8, false))
+}
+
+/// Fields of a `Map` column's entries: string keys, JSON-encoded values
+fn map_entry_fields() -> Fields {
+    Fields::from(vec![
+        Field::new("keys", ArrowDataType::Utf8, false),
+        Field::new("values", ArrowDataType::LargeUtf8, false),
+    ])
+}
+
+/// Entries field of a `Map` column
+pub(crate) fn map_entries_field() -> FieldRef {
+    Arc::new(Field::new(
+        "entries",
+        ArrowDataType::Struct(map_entry_fields()),
+        false,
+    ))
+}
+
+/// JSON encoding of a nested element, as stored in list and map columns
+fn encode_element(value: &Value) -> Result<String, ArrowConvertError> {
+    serde_json::to_string(value).map_err(|e| ArrowConvertError::UnsupportedType(e.to_string()))
+}
+
+/// Decode a nested element written by [`encode_element`]
+fn decode_element(json: &str) -> Result<Value, ArrowConvertError> {
+    serde_json::from_str(json).map_err(|e| ArrowConvertError::UnsupportedType(e.to_string()))
+}
+
+/// Build a `List` column; rows without a list are null
+fn build_list_array(tuples: &[Tuple], col_idx: usize) -> Result<ArrayRef, ArrowConvertError> {
+    let mut items: Vec<String> = Vec::new();
+    let mut offsets: Vec<i64> = vec![0];
+    let mut valid = Vec::with_capacity(tuples.len());
+    for tuple in tuples {
+        let list = tuple.get(col_idx).and_then(Value::as_list);
+        for item in list.unwrap_or_default() {
+            items.push(encode_element(item)?);
+        }
+        offsets.push(items.len() as i64);
+        valid.push(list.is_some());
+    }
+    let array = LargeListArray::try_new(
+        list_item_field(),
+        OffsetBuffer::new(offsets.into()),
+        Arc::new(LargeStringArray::from(items)),
+        Some(NullBuffer::from(valid)),
+    )?;
+    Ok(Arc::new(array))
+}
+
+/// Build a `Map` column; rows without a map are null
+fn build_map_array(tuples: &[Tuple], col_idx: usize) -> Result<ArrayRef, ArrowConvertError> {
+    let mut keys: Vec<&str> = Vec::new();
+    let mut values: Vec<String> = Vec::new();
+    let mut offsets: Vec<i32> = vec![0];
+    let mut valid = Vec::with_capacity(tuples.len());
+    for tuple in tuples {
+        let map = tuple.get(col_idx).and_then(Value::as_map);
+        for (key, value) in map.into_iter().flatten() {
+            keys.push(key.as_str());
+            values.push(encode_element(value)?);
+        }
+        offsets.push(keys.len() as i32);
+        valid.push(map.is_some());
+    }
+    let entries = StructArray::try_new(
+        map_entry_fields(),
+        vec![
+            Arc::new(StringArray::from(keys)),
+            Arc::new(LargeStringArray::from(values)),
+        ],
+        None,
+    )?;
+    let array = MapArray::try_new(
+        map_entries_field(),
+        OffsetBuffer::new(offsets.into()),
+        entries,
+        Some(NullBuffer::from(valid)),
+        false,
+    )?;
+    Ok(Arc::new(array))
+}
+
 /// Convert a vector of tuples to an Arrow `RecordBatch`
 ///
 /// # Arguments
@@ -208,6 +297,45 @@ fn build_column_array(
                 .collect();
             Ok(Arc::new(Int64Array::from(values)))
         }
+        DataType::Date => {
+            let values: Vec<Option<i32>> = tuples
+                .iter()
+                .map(|t| t.get(col_idx).and_then(super::Value::as_date))
+                .collect();
+            Ok(Arc::new(Date32Array::from(values)))
+        }
+        DataType::Duration => {
+            let values: Vec<Option<i64>> = tuples
+                .iter()
+                .map(|t| t.get(col_idx).and_then(super::Value::as_duration))
+                .collect();
+            Ok(Arc::new(DurationMillisecondArray::from(values)))
+        }
+        DataType::Uuid => {
+            let values = tuples.iter().map(|t| {
+                t.get(col_idx)
+                    .and_then(super::Value::as_uuid)
+                    .map(|u| *u.as_bytes())
+            });
+            let array = FixedSizeBinaryArray::try_from_sparse_iter_with_size(values, 16)?;
+            Ok(Arc::new(array))
+        }
+        DataType::List => build_list_array(tuples, col_idx),
+        DataType::Map => build_map_array(tuples, col_idx),
+        DataType::Json => {
+            let values: Vec<Option<&str>> = tuples
+                .iter()
+                .map(|t| t.get(col_idx).and_then(super::Value::as_json))
+                .collect();
+            Ok(Arc::new(LargeStringArray::from(values)))
+        }
+        DataType::Bytes => {
+            let values: Vec<Option<&[u8]>> = tuples
+                .iter()
+                .map(|t| t.get(col_idx).and_then(super::Value::as_bytes))
+                .collect();
+            Ok(Arc::new(BinaryArray::from(values)))
+        }
         DataType::VectorInt8 { dim } => {
             // Build array from int8 vectors - use FixedSizeList when dimension is known
             let mut all_values: Vec<i8> = Vec::new();
@@ -270,11 +398,33 @@ fn extract_value_from_array(array: &dyn Array, row_idx: usize) -> Result<Value,
         return Ok(Value::Float64(arr.value(row_idx)));
     }
     if let Some(arr) = array.as_any().downcast_ref::<StringArray>() {
-        return Ok(Value::String(Arc::from(arr.value(row_idx))));
+        return Ok(Value::interned(arr.value(row_idx)));
+    }
+    // The text was normalized when written, so it is not re-parsed here
+    if let Some(arr) = array.as_any().downcast_ref::<LargeStringArray>() {
+        return Ok(Value::Json(Arc::from(arr.value(row_idx))));
     }
     if let Some(arr) = array.as_any().downcast_ref::<BooleanArray>() {
         return Ok(Value::Bool(arr.value(row_idx)));
     }
+    if let Some(arr) = array.as_any().downcast_ref::<Date32Array>() {
+        return Ok(Value::Date(arr.value(row_idx)));
+    }
+    if let Some(arr) = array.as_any().downcast_ref::<DurationMillisecondArray>() {
+        return Ok(Value::Duration(arr.value(row_idx)));
+    }
+    if let Some(arr) = array.as_any().downcast_ref::<FixedSizeBinaryArray>() {
+        if let Ok(u) = uuid::Uuid::from_slice(arr.value(row_idx)) {
+            return Ok(Value::Uuid(u));
+        }
+    }
+
+    if let Some(arr) = array.as_any().downcast_ref::<BinaryArray>() {
+        return Ok(Value::bytes(arr.value(row_idx).to_vec()));
+    }
+    if let Some(arr) = array.as_any().downcast_ref::<LargeBinaryArray>() {
+        return Ok(Value::bytes(arr.value(row_idx).to_vec()));
+    }
 
     // Handle FixedSizeListArray (vectors with known dimension)
     if let Some(arr) = array.as_any().downcast_ref::<FixedSizeListArray>() {
@@ -291,9 +441,15 @@ fn extract_value_from_array(array: &dyn Array, row_idx: usize) -> Result<Value,
         }
     }
 
-    // Handle LargeListArray (vectors with unknown dimension)
+    // Handle LargeListArray (vectors with unknown dimension, or lists)
     if let Some(arr) = array.as_any().downcast_ref::<LargeListArray>() {
         let values = arr.value(row_idx);
+        if let Some(items) = values.as_any().downcast_ref::<LargeStringArray>() {
+            let list = (0..items.len())
+                .map(|i| decode_element(items.value(i)))
+                .collect::<Result<Vec<_>, _>>()?;
+            return Ok(Value::list(list));
+        }
         // Check for Float32 vectors first
         if let Some(float_arr) = values.as_any().downcast_ref::<Float32Array>() {
             let vec: Vec<f32> = (0..float_arr.len()).map(|i| float_arr.value(i)).collect();
@@ -306,6 +462,22 @@ fn extract_value_from_array(array: &dyn Array, row_idx: usize) -> Result<Value,
         }
     }
 
+    if let Some(arr) = array.as_any().downcast_ref::<MapArray>() {
+        let entries = arr.value(row_idx);
+        let keys = entries.column(0).as_any().downcast_ref::<StringArray>();
+        let values = entries
+            .column(1)
+            .as_any()
+            .downcast_ref::<LargeStringArray>();
+        if let (Some(keys), Some(values)) = (keys, values) {
+            let mut map = Vec::with_capacity(keys.len());
+            for i in 0..keys.len() {
+                map.push((keys.value(i), decode_element(values.value(i))?));
+            }
+            return Ok(Value::map(map));
+        }
+    }
+
     // Handle ListArray (vectors)
     if let Some(arr) = array.as_any().downcast_ref::<ListArray>() {
         let values = arr.value(row_idx);
@@ -379,6 +551,12 @@ fn empty_array_for_type(dt: &DataType) -> ArrayRef {
             }
         }
         DataType::Timestamp => Arc::new(Int64Array::from(Vec::<i64>::new())),
+        DataType::Date => Arc::new(Date32Array::from(Vec::<i32>::new())),
+        DataType::Duration => Arc::new(DurationMillisecondArray::from(Vec::<i64>::new())),
+        DataType::Uuid => Arc::new(FixedSizeBinaryArray::new_null(16, 0)),
+        DataType::List | DataType::Map | DataType::Json | DataType::Bytes => {
+            arrow::array::new_empty_array(&dt.to_arrow())
+        }
     }
 }
 
@@ -732,6 +910,130 @@ mod tests {
         assert_eq!(batch.num_rows(), 2);
     }
 
+    #[test]
+    fn test_date_and_duration_roundtrip() {
+        let tuples = vec![
+            Tuple::new(vec![Value::Date(19_782), Value::Duration(90_000)]),
+            Tuple::new(vec![Value::Date(-1), Value::Duration(-250)]),
+        ];
+        let schema = TupleSchema::new(vec![
+            ("day".to_string(), DataType::Date),
+            ("took".to_string(), DataType::Duration),
+        ]);
+
+        let batch = tuples_to_record_batch(&tuples, &schema).unwrap();
+        assert_eq!(batch.schema().field(0).data_type(), &ArrowDataType::Date32);
+
+        let (restored, restored_schema) = record_batch_to_tuples(&batch).unwrap();
+        assert_eq!(restored, tuples);
+        assert_eq!(restored_schema.field_type(1), Some(&DataType::Duration));
+    }
+
+    #[test]
+    fn test_uuid_roundtrip() {
+        let id = Value::uuid("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+        let tuples = vec![
+            Tuple::new(vec![id.clone(), Value::Int64(1)]),
+            Tuple::new(vec![Value::Null, Value::Int64(2)]),
+        ];
+        let schema = TupleSchema::new(vec![
+            ("id".to_string(), DataType::Uuid),
+            ("n".to_string(), DataType::Int64),
+        ]);
+
+        let batch = tuples_to_record_batch(&tuples, &schema).unwrap();
+        assert_eq!(
+            batch.schema().field(0).data_type(),
+            &ArrowDataType::FixedSizeBinary(16)
+        );
+
+        let (restored, _) = record_batch_to_tuples(&batch).unwrap();
+        assert_eq!(restored[0].get(0), Some(&id));
+        assert_eq!(restored[1].get(0), Some(&Value::Null));
+    }
+
+    #[test]
+    fn test_list_and_map_roundtrip() {
+        let tags = Value::list(vec![
+            Value::string("a"),
+            Value::Int64(2),
+            Value::list(vec![Value::Bool(true)]),
+        ]);
+        let attrs = Value::map([
+            ("name", Value::string("alice")),
+            ("scores", Value::list(vec![Value::Float64(1.5)])),
+        ]);
+        let tuples = vec![
+            Tuple::new(vec![tags.clone(), attrs.clone()]),
+            Tuple::new(vec![Value::Null, Value::map::<String>([])]),
+        ];
+        let schema = TupleSchema::new(vec![
+            ("tags".to_string(), DataType::List),
+            ("attrs".to_string(), DataType::Map),
+        ]);
+
+        let batch = tuples_to_record_batch(&tuples, &schema).unwrap();
+        assert_eq!(
+            DataType::from_arrow(batch.schema().field(0).data_type()),
+            Some(DataType::List)
+        );
+        assert_eq!(
+            DataType::from_arrow(batch.schema().field(1).data_type()),
+            Some(DataType::Map)
+        );
+
+        let (restored, _) = record_batch_to_tuples(&batch).unwrap();
+        assert_eq!(restored[0].get(0), Some(&tags));
+        assert_eq!(restored[0].get(1), Some(&attrs));
+        assert_eq!(restored[1].get(0), Some(&Value::Null));
+        assert_eq!(restored[1].get(1), Some(&Value::map::<String>([])));
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        let doc = Value::json(r#"{"user": {"id": 7}, "tags": ["a"]}"#).unwrap();
+        let tuples = vec![
+            Tuple::new(vec![doc.clone(), Value::string("plain")]),
+            Tuple::new(vec![Value::Null, Value::string("other")]),
+        ];
+        let schema = TupleSchema::new(vec![
+            ("payload".to_string(), DataType::Json),
+            ("name".to_string(), DataType::String),
+        ]);
+
+        let batch = tuples_to_record_batch(&tuples, &schema).unwrap();
+        assert_eq!(
+            batch.schema().field(0).data_type(),
+            &ArrowDataType::LargeUtf8
+        );
+
+        let (restored, restored_schema) = record_batch_to_tuples(&batch).unwrap();
+        assert_eq!(restored[0].get(0), Some(&doc));
+        assert_eq!(restored[0].get(1), Some(&Value::string("plain")));
+        assert_eq!(restored[1].get(0), Some(&Value::Null));
+        assert_eq!(restored_schema.field_type(0), Some(&DataType::Json));
+        assert_eq!(restored_schema.field_type(1), Some(&DataType::String));
+    }
+
+    #[test]
+    fn test_bytes_roundtrip() {
+        let blob = Value::bytes(vec![0, 159, 146, 150]);
+        let tuples = vec![
+            Tuple::new(vec![blob.clone()]),
+            Tuple::new(vec![Value::bytes(Vec::new())]),
+            Tuple::new(vec![Value::Null]),
+        ];
+        let schema = TupleSchema::new(vec![("blob".to_string(), DataType::Bytes)]);
+
+        let batch = tuples_to_record_batch(&tuples, &schema).unwrap();
+        assert_eq!(batch.schema().field(0).data_type(), &ArrowDataType::Binary);
+
+        let (restored, _) = record_batch_to_tuples(&batch).unwrap();
+        assert_eq!(restored[0].get(0), Some(&blob));
+        assert_eq!(restored[1].get(0), Some(&Value::bytes(Vec::new())));
+        assert_eq!(restored[2].get(0), Some(&Value::Null));
+    }
+
     #[test]
     fn test_infer_schema_empty_tuples() {
         let tuples: Vec<Tuple> = vec![];
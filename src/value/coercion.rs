@@ -0,0 +1,97 @@
+//! # Implicit Type Coercion
+//!
+//! Integers compare by value regardless of width: `Int32(1)` equals, hashes
+//! and orders like `Int64(1)`, so relations loaded from different sources
+//! (CSV infers `Int32`, IQL literals are `Int64`) join as expected. Values of
+//! other types never compare equal to each other.
+//!
+//! Two modes control what happens beyond that:
+//! - `lenient` (default): values inserted into a relation with a schema are
+//!   normalized to the column type (`int` → `Int64`, integers in `float`
+//!   columns → `Float64`)
+//! - `strict`: no normalization on insert, and a query that joins columns
+//!   of stored relations holding values of different types is rejected
+//!   instead of silently matching nothing
+
+use super::Value;
+use serde::{Deserialize, Serialize};
+
+/// How implicit type coercion is handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CoercionMode {
+    /// Normalize inserted values to their column types
+    #[default]
+    Lenient,
+    /// Never coerce; reject joins between columns of different types
+    Strict,
+}
+
+/// Type name of a value, as shown in coercion errors
+pub fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Int32(_) => "int32",
+        Value::Int64(_) => "int64",
+        Value::Float64(_) => "float",
+        Value::String(_) => "string",
+        Value::Bool(_) => "bool",
+        Value::Null => "null",
+        Value::Vector(_) => "vector",
+        Value::VectorInt8(_) => "vector_int8",
+        Value::Timestamp(_) => "timestamp",
+        Value::Date(_) => "date",
+        Value::Duration(_) => "duration",
+        Value::Uuid(_) => "uuid",
+        Value::List(_) => "list",
+        Value::Map(_) => "map",
+        Value::Json(_) => "json",
+        Value::Bytes(_) => "bytes",
+    }
+}
+
+/// Whether joining a column holding `left` with one holding `right` mixes
+/// value types. Nulls never match, so they never make a join mixed.
+pub fn is_mixed_type(left: &Value, right: &Value) -> bool {
+    !left.is_null()
+        && !right.is_null()
+        && std::mem::discriminant(left) != std::mem::discriminant(right)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    fn hash_of(value: &Value) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_integers_compare_by_value() {
+        assert_eq!(Value::Int32(7), Value::Int64(7));
+        assert_eq!(hash_of(&Value::Int32(7)), hash_of(&Value::Int64(7)));
+        assert!(Value::Int32(7) < Value::Int64(8));
+        assert!(Value::Int64(-1) < Value::Int32(0));
+        assert_ne!(Value::Int64(1), Value::Float64(1.0));
+    }
+
+    #[test]
+    fn test_mixed_types() {
+        assert!(is_mixed_type(&Value::Int32(1), &Value::Int64(1)));
+        assert!(is_mixed_type(&Value::Int64(1), &Value::Float64(1.0)));
+        assert!(!is_mixed_type(&Value::Int64(1), &Value::Int64(2)));
+        assert!(!is_mixed_type(&Value::Null, &Value::string("a")));
+        assert_eq!(type_name(&Value::Timestamp(0)), "timestamp");
+    }
+
+    #[test]
+    fn test_mode_serde() {
+        let mode: CoercionMode = serde_json::from_str("\"strict\"").unwrap();
+        assert_eq!(mode, CoercionMode::Strict);
+        assert_eq!(CoercionMode::default(), CoercionMode::Lenient);
+    }
+}
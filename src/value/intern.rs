@@ -0,0 +1,173 @@
+//! Global string interning pool.
+//!
+//! `Value::String` holds an `Arc<str>`, so cloning a string value in a map,
+//! join or projection is a reference-count bump rather than a copy. Interning
+//! goes one step further for strings entering the engine from facts, CSV and
+//! Parquet: equal strings share a single allocation, which saves memory for
+//! relations with many repeated strings (tags, categories, foreign keys) and
+//! lets equality checks short-circuit on pointer identity.
+//!
+//! Only short strings are pooled; long text is rarely repeated and would
+//! only grow the pool. Once the pool reaches its entry limit, new strings
+//! are allocated normally until `purge_unused_interned` frees space.
+//!
+//! The pool keeps every string it holds alive, so a string is only freed
+//! once purged after its last tuple is gone. The storage engine purges
+//! after dropping a relation or knowledge graph, and at the end of every
+//! retention sweep, which also catches strings freed by other deletes.
+
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+
+/// Strings longer than this (in bytes) are never pooled
+pub const MAX_INTERNED_LEN: usize = 128;
+
+/// Maximum number of distinct pooled strings
+pub const DEFAULT_MAX_INTERNED: usize = 1 << 20;
+
+/// String interning pool statistics
+#[derive(Debug, Clone, Default)]
+pub struct InternStats {
+    /// Lookups that returned an existing pooled string
+    pub hits: usize,
+    /// Lookups that added a new string to the pool
+    pub misses: usize,
+    /// Strings allocated outside the pool (too long, or pool full)
+    pub bypassed: usize,
+    /// Distinct strings currently pooled
+    pub entries: usize,
+    /// Total bytes of pooled string data
+    pub bytes: usize,
+}
+
+impl InternStats {
+    /// Fraction of pooled lookups that reused an existing string (0.0 to 1.0)
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+struct InternPool {
+    strings: DashMap<Arc<str>, ()>,
+    bytes: AtomicUsize,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    bypassed: AtomicUsize,
+}
+
+static POOL: OnceLock<InternPool> = OnceLock::new();
+
+fn pool() -> &'static InternPool {
+    POOL.get_or_init(|| InternPool {
+        strings: DashMap::new(),
+        bytes: AtomicUsize::new(0),
+        hits: AtomicUsize::new(0),
+        misses: AtomicUsize::new(0),
+        bypassed: AtomicUsize::new(0),
+    })
+}
+
+/// Return the pooled `Arc<str>` for `s`, adding it if absent
+pub fn intern(s: &str) -> Arc<str> {
+    let pool = pool();
+    if s.len() > MAX_INTERNED_LEN {
+        pool.bypassed.fetch_add(1, Ordering::Relaxed);
+        return Arc::from(s);
+    }
+    if let Some(entry) = pool.strings.get(s) {
+        pool.hits.fetch_add(1, Ordering::Relaxed);
+        return Arc::clone(entry.key());
+    }
+    if pool.strings.len() >= DEFAULT_MAX_INTERNED {
+        pool.bypassed.fetch_add(1, Ordering::Relaxed);
+        return Arc::from(s);
+    }
+    // Another thread may insert the same string between `get` and here;
+    // `entry` keeps whichever arrived first
+    let entry = pool.strings.entry(Arc::from(s)).or_insert_with(|| {
+        pool.misses.fetch_add(1, Ordering::Relaxed);
+        pool.bytes.fetch_add(s.len(), Ordering::Relaxed);
+    });
+    Arc::clone(entry.key())
+}
+
+/// Drop pooled strings that no value references any more.
+/// Returns the number of strings removed.
+pub fn purge_unused_interned() -> usize {
+    let pool = pool();
+    let before = pool.strings.len();
+    pool.strings.retain(|s, ()| {
+        // The pool's own reference is the only one left
+        let unused = Arc::strong_count(s) == 1;
+        if unused {
+            pool.bytes.fetch_sub(s.len(), Ordering::Relaxed);
+        }
+        !unused
+    });
+    before.saturating_sub(pool.strings.len())
+}
+
+/// Get current interning pool statistics
+pub fn get_intern_stats() -> InternStats {
+    let pool = pool();
+    InternStats {
+        hits: pool.hits.load(Ordering::Relaxed),
+        misses: pool.misses.load(Ordering::Relaxed),
+        bypassed: pool.bypassed.load(Ordering::Relaxed),
+        entries: pool.strings.len(),
+        bytes: pool.bytes.load(Ordering::Relaxed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The pool is global and shared with concurrently running tests, so
+    // these assertions only rely on the strings they create themselves.
+
+    #[test]
+    fn test_intern_shares_allocation() {
+        let a = intern("intern-test-shared");
+        let b = intern(&String::from("intern-test-shared"));
+        assert!(Arc::ptr_eq(&a, &b));
+        assert_eq!(&*a, "intern-test-shared");
+        let stats = get_intern_stats();
+        assert!(stats.hits >= 1);
+        assert!(stats.entries >= 1);
+        assert!(stats.hit_rate() > 0.0);
+    }
+
+    #[test]
+    fn test_long_strings_bypass_pool() {
+        let long = "x".repeat(MAX_INTERNED_LEN + 1);
+        let before = get_intern_stats().bypassed;
+        let a = intern(&long);
+        let b = intern(&long);
+        assert!(!Arc::ptr_eq(&a, &b));
+        assert_eq!(a, b);
+        assert!(get_intern_stats().bypassed >= before + 2);
+    }
+
+    #[test]
+    fn test_purge_releases_unreferenced_strings() {
+        let kept = intern("intern-test-kept");
+        drop(intern("intern-test-dropped"));
+        purge_unused_interned();
+        // A string still referenced stays pooled
+        assert!(Arc::ptr_eq(&kept, &intern("intern-test-kept")));
+        let pool = pool();
+        assert!(pool.strings.get("intern-test-dropped").is_none());
+    }
+
+    #[test]
+    fn test_intern_stats_hit_rate_empty() {
+        assert!((InternStats::default().hit_rate() - 0.0).abs() < f64::EPSILON);
+    }
+}
@@ -1,6 +1,7 @@
 //! # Value Type System
 //!
-//! Core value types: Int32, Int64, Float64, String, Bool, Null, Vector, VectorInt8, Timestamp.
+//! Core value types: Int32, Int64, Float64, String, Bool, Null, Vector, VectorInt8, Timestamp,
+//! Date, Duration, Uuid, List, Map, Json, Bytes.
 //! Arbitrary arity tuples with Arrow-compatible types and DD trait implementations.
 //!
 //! ## Usage
@@ -24,13 +25,17 @@
 //! ```
 
 pub mod arrow_convert;
+pub mod coercion;
+pub mod intern;
 
 pub use arrow_convert::{
     infer_schema_from_tuples, record_batch_to_tuples, tuples_to_record_batch, ArrowConvertError,
 };
 
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use smallvec::SmallVec;
 use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::sync::Arc;
@@ -61,6 +66,20 @@ pub enum DataType {
     },
     /// Unix timestamp in milliseconds (for temporal operations)
     Timestamp,
+    /// Calendar date (days since 1970-01-01)
+    Date,
+    /// Signed length of time in milliseconds
+    Duration,
+    /// 128-bit UUID
+    Uuid,
+    /// List of values of any type
+    List,
+    /// String-keyed map of values of any type
+    Map,
+    /// JSON document
+    Json,
+    /// Binary blob
+    Bytes,
 }
 
 impl DataType {
@@ -108,6 +127,13 @@ impl DataType {
             (DataType::Bool, Value::Bool(_)) => true,
             (DataType::Null, Value::Null) => true,
             (DataType::Timestamp, Value::Timestamp(_)) => true,
+            (DataType::Date, Value::Date(_)) => true,
+            (DataType::Duration, Value::Duration(_)) => true,
+            (DataType::Uuid, Value::Uuid(_)) => true,
+            (DataType::List, Value::List(_)) => true,
+            (DataType::Map, Value::Map(_)) => true,
+            (DataType::Json, Value::Json(_)) => true,
+            (DataType::Bytes, Value::Bytes(_)) => true,
             _ => false,
         }
     }
@@ -151,6 +177,21 @@ impl DataType {
             )),
             // Timestamps stored as Int64 (milliseconds since Unix epoch)
             DataType::Timestamp => ArrowDataType::Int64,
+            // Dates and durations use Arrow's logical types so Parquet files
+            // carry DATE / INTERVAL annotations other readers understand
+            DataType::Date => ArrowDataType::Date32,
+            DataType::Duration => ArrowDataType::Duration(arrow::datatypes::TimeUnit::Millisecond),
+            // UUIDs are 16 raw bytes (Parquet FIXED_LEN_BYTE_ARRAY(16))
+            DataType::Uuid => ArrowDataType::FixedSizeBinary(16),
+            // Lists and maps hold values of mixed types, so each element is
+            // stored as its JSON encoding inside Arrow's nested types
+            DataType::List => ArrowDataType::LargeList(arrow_convert::list_item_field()),
+            DataType::Map => ArrowDataType::Map(arrow_convert::map_entries_field(), false),
+            // JSON is kept as text (Parquet UTF-8); LargeUtf8 tells it apart
+            // from plain strings when reading back
+            DataType::Json => ArrowDataType::LargeUtf8,
+            // Parquet BYTE_ARRAY
+            DataType::Bytes => ArrowDataType::Binary,
         }
     }
 
@@ -160,9 +201,16 @@ impl DataType {
             ArrowDataType::Int32 => Some(DataType::Int32),
             ArrowDataType::Int64 => Some(DataType::Int64),
             ArrowDataType::Float64 => Some(DataType::Float64),
-            ArrowDataType::Utf8 | ArrowDataType::LargeUtf8 => Some(DataType::String),
+            ArrowDataType::Utf8 => Some(DataType::String),
+            ArrowDataType::LargeUtf8 => Some(DataType::Json),
             ArrowDataType::Boolean => Some(DataType::Bool),
             ArrowDataType::Null => Some(DataType::Null),
+            ArrowDataType::Date32 => Some(DataType::Date),
+            ArrowDataType::Duration(arrow::datatypes::TimeUnit::Millisecond) => {
+                Some(DataType::Duration)
+            }
+            ArrowDataType::FixedSizeBinary(16) => Some(DataType::Uuid),
+            ArrowDataType::Binary | ArrowDataType::LargeBinary => Some(DataType::Bytes),
             // FixedSizeList preserves dimension information
             ArrowDataType::FixedSizeList(field, size)
                 if matches!(field.data_type(), ArrowDataType::Float32) =>
@@ -191,6 +239,12 @@ impl DataType {
             {
                 Some(DataType::VectorInt8 { dim: None })
             }
+            ArrowDataType::LargeList(field)
+                if matches!(field.data_type(), ArrowDataType::LargeUtf8) =>
+            {
+                Some(DataType::List)
+            }
+            ArrowDataType::Map(_, _) => Some(DataType::Map),
             _ => None,
         }
     }
@@ -220,6 +274,22 @@ pub enum Value {
     /// Unix timestamp in milliseconds since epoch (1970-01-01 00:00:00 UTC)
     /// For temporal operations in spatio-temporal memory systems
     Timestamp(i64),
+    /// Calendar date as days since 1970-01-01 (negative before the epoch)
+    Date(i32),
+    /// Signed duration in milliseconds
+    Duration(i64),
+    /// UUID stored inline as 16 bytes; orders by byte value, so v7 UUIDs
+    /// sort by creation time
+    Uuid(uuid::Uuid),
+    /// Ordered list of values of any type, including nested lists and maps
+    List(Arc<Vec<Value>>),
+    /// Map from string keys to values of any type, kept sorted by key
+    Map(Arc<BTreeMap<String, Value>>),
+    /// JSON document as compact text. Parsed only when a `json_*` builtin
+    /// reads it, so scanning a JSON column costs no more than a string column
+    Json(Arc<str>),
+    /// Opaque binary blob: hashes, payloads, embeddings serialized elsewhere
+    Bytes(Arc<Vec<u8>>),
 }
 
 impl Value {
@@ -235,6 +305,13 @@ impl Value {
             Value::Vector(v) => DataType::Vector { dim: Some(v.len()) },
             Value::VectorInt8(v) => DataType::VectorInt8 { dim: Some(v.len()) },
             Value::Timestamp(_) => DataType::Timestamp,
+            Value::Date(_) => DataType::Date,
+            Value::Duration(_) => DataType::Duration,
+            Value::Uuid(_) => DataType::Uuid,
+            Value::List(_) => DataType::List,
+            Value::Map(_) => DataType::Map,
+            Value::Json(_) => DataType::Json,
+            Value::Bytes(_) => DataType::Bytes,
         }
     }
 
@@ -252,7 +329,7 @@ impl Value {
         match self {
             Value::Int32(v) => Some(i64::from(*v)),
             Value::Int64(v) => Some(*v),
-            Value::Timestamp(t) => Some(*t),
+            Value::Timestamp(t) | Value::Duration(t) => Some(*t),
             _ => None,
         }
     }
@@ -327,6 +404,12 @@ impl Value {
         Value::String(Arc::from(s))
     }
 
+    /// Create a string value backed by the global interning pool, so equal
+    /// strings share one allocation. Use for strings loaded in bulk.
+    pub fn interned(s: &str) -> Self {
+        Value::String(intern::intern(s))
+    }
+
     pub fn vector(data: Vec<f32>) -> Self {
         Value::Vector(Arc::new(data))
     }
@@ -348,6 +431,117 @@ impl Value {
         Value::Timestamp(ms)
     }
 
+    /// Create a date value from days since 1970-01-01
+    pub fn date(days: i32) -> Self {
+        Value::Date(days)
+    }
+
+    /// Create a duration value from milliseconds
+    pub fn duration(ms: i64) -> Self {
+        Value::Duration(ms)
+    }
+
+    /// Try to get as date (days since Unix epoch)
+    pub fn as_date(&self) -> Option<i32> {
+        match self {
+            Value::Date(d) => Some(*d),
+            _ => None,
+        }
+    }
+
+    /// Try to get as duration (milliseconds)
+    pub fn as_duration(&self) -> Option<i64> {
+        match self {
+            Value::Duration(d) => Some(*d),
+            _ => None,
+        }
+    }
+
+    /// Parse a UUID from its textual form (hyphenated, simple, braced or URN)
+    pub fn uuid(s: &str) -> Option<Self> {
+        uuid::Uuid::parse_str(s.trim()).ok().map(Value::Uuid)
+    }
+
+    /// Try to get as UUID
+    pub fn as_uuid(&self) -> Option<&uuid::Uuid> {
+        match self {
+            Value::Uuid(u) => Some(u),
+            _ => None,
+        }
+    }
+
+    /// Create a list value
+    pub fn list(items: Vec<Value>) -> Self {
+        Value::List(Arc::new(items))
+    }
+
+    /// Create a map value from key-value pairs (a repeated key keeps its last value)
+    pub fn map<K: Into<String>>(entries: impl IntoIterator<Item = (K, Value)>) -> Self {
+        Value::Map(Arc::new(
+            entries.into_iter().map(|(k, v)| (k.into(), v)).collect(),
+        ))
+    }
+
+    /// Try to get as list slice
+    pub fn as_list(&self) -> Option<&[Value]> {
+        match self {
+            Value::List(items) => Some(items.as_slice()),
+            _ => None,
+        }
+    }
+
+    /// Try to get as map
+    pub fn as_map(&self) -> Option<&BTreeMap<String, Value>> {
+        match self {
+            Value::Map(entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    /// Parse JSON text into a JSON value, normalized to compact form
+    /// so equal documents compare equal
+    pub fn json(s: &str) -> Option<Self> {
+        serde_json::from_str::<serde_json::Value>(s)
+            .ok()
+            .map(|doc| Value::from_json_doc(&doc))
+    }
+
+    /// Wrap an already-parsed JSON document
+    pub fn from_json_doc(doc: &serde_json::Value) -> Self {
+        Value::Json(Arc::from(doc.to_string()))
+    }
+
+    /// Try to get the JSON text
+    pub fn as_json(&self) -> Option<&str> {
+        match self {
+            Value::Json(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Create a bytes value
+    pub fn bytes(bytes: Vec<u8>) -> Self {
+        Value::Bytes(Arc::new(bytes))
+    }
+
+    /// Decode a hex literal (optional `0x` prefix) into bytes
+    pub fn from_hex(s: &str) -> Option<Self> {
+        crate::bytes_ops::decode_hex(s).map(Value::bytes)
+    }
+
+    /// Decode a standard base64 literal into bytes
+    pub fn from_base64(s: &str) -> Option<Self> {
+        crate::bytes_ops::decode_base64(s).map(Value::bytes)
+    }
+
+    /// Try to get as byte slice
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(b) => Some(b.as_slice()),
+            _ => None,
+        }
+    }
+
     /// Convert to i64 (for aggregation operations)
     /// Returns 0 for non-numeric types
     pub fn to_i64(&self) -> i64 {
@@ -359,7 +553,8 @@ impl Value {
             // out-of-range f64→i64 is well-defined: clamps to i64::MIN/MAX).
             Value::Float64(v) if v.is_finite() => *v as i64,
             Value::Bool(b) => i64::from(*b),
-            Value::Timestamp(t) => *t,
+            Value::Timestamp(t) | Value::Duration(t) => *t,
+            Value::Date(d) => i64::from(*d),
             _ => 0,
         }
     }
@@ -373,7 +568,8 @@ impl Value {
             Value::Float64(v) => *v,
             // `false` falls through to the `_ => 0.0` wildcard.
             Value::Bool(b) if *b => 1.0,
-            Value::Timestamp(t) => *t as f64,
+            Value::Timestamp(t) | Value::Duration(t) => *t as f64,
+            Value::Date(d) => f64::from(*d),
             _ => 0.0,
         }
     }
@@ -429,23 +625,60 @@ impl fmt::Display for Value {
                 write!(f, "]i8")
             }
             Value::Timestamp(ts) => write!(f, "{ts}ms"),
+            Value::Date(d) => write!(f, "{}", crate::temporal_ops::format_date(*d)),
+            Value::Duration(ms) => write!(f, "{}", crate::temporal_ops::format_duration(*ms)),
+            Value::Uuid(u) => write!(f, "{}", u.hyphenated()),
+            Value::List(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                write!(f, "]")
+            }
+            Value::Map(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "\"{key}\": {value}")?;
+                }
+                write!(f, "}}")
+            }
+            Value::Json(text) => write!(f, "{text}"),
+            Value::Bytes(b) => write!(f, "0x{}", crate::bytes_ops::encode_hex(b)),
         }
     }
 }
 
-// Implement PartialEq manually to handle f64 comparison
+// Implement PartialEq manually to handle f64 comparison. Integers compare
+// by value regardless of width (see `coercion`).
 impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Value::Int32(a), Value::Int32(b)) => a == b,
             (Value::Int64(a), Value::Int64(b)) => a == b,
+            (Value::Int32(a), Value::Int64(b)) | (Value::Int64(b), Value::Int32(a)) => {
+                i64::from(*a) == *b
+            }
             (Value::Float64(a), Value::Float64(b)) => a.to_bits() == b.to_bits(),
-            (Value::String(a), Value::String(b)) => a == b,
+            // Interned strings are usually the same allocation
+            (Value::String(a), Value::String(b)) => Arc::ptr_eq(a, b) || a == b,
             (Value::Bool(a), Value::Bool(b)) => a == b,
             (Value::Null, Value::Null) => true,
             (Value::Vector(a), Value::Vector(b)) => a == b,
             (Value::VectorInt8(a), Value::VectorInt8(b)) => a == b,
             (Value::Timestamp(a), Value::Timestamp(b)) => a == b,
+            (Value::Date(a), Value::Date(b)) => a == b,
+            (Value::Duration(a), Value::Duration(b)) => a == b,
+            (Value::Uuid(a), Value::Uuid(b)) => a == b,
+            (Value::List(a), Value::List(b)) => a == b,
+            (Value::Map(a), Value::Map(b)) => a == b,
+            (Value::Json(a), Value::Json(b)) => a == b,
+            (Value::Bytes(a), Value::Bytes(b)) => a == b,
             _ => false,
         }
     }
@@ -456,9 +689,13 @@ impl Eq for Value {}
 // Implement Hash manually to handle f64 and vectors
 impl Hash for Value {
     fn hash<H: Hasher>(&self, state: &mut H) {
+        // Int32 hashes as the equal Int64
+        if let Value::Int32(v) = self {
+            return Value::Int64(i64::from(*v)).hash(state);
+        }
         std::mem::discriminant(self).hash(state);
         match self {
-            Value::Int32(v) => v.hash(state),
+            Value::Int32(_) => {}
             Value::Int64(v) => v.hash(state),
             Value::Float64(v) => v.to_bits().hash(state),
             Value::String(s) => s.hash(state),
@@ -477,6 +714,24 @@ impl Hash for Value {
                 }
             }
             Value::Timestamp(t) => t.hash(state),
+            Value::Date(d) => d.hash(state),
+            Value::Duration(d) => d.hash(state),
+            Value::Uuid(u) => u.as_bytes().hash(state),
+            Value::List(items) => {
+                items.len().hash(state);
+                for item in items.iter() {
+                    item.hash(state);
+                }
+            }
+            Value::Map(entries) => {
+                entries.len().hash(state);
+                for (key, value) in entries.iter() {
+                    key.hash(state);
+                    value.hash(state);
+                }
+            }
+            Value::Json(text) => text.hash(state),
+            Value::Bytes(b) => b.hash(state),
         }
     }
 }
@@ -493,6 +748,8 @@ impl Ord for Value {
         match (self, other) {
             (Value::Int32(a), Value::Int32(b)) => a.cmp(b),
             (Value::Int64(a), Value::Int64(b)) => a.cmp(b),
+            (Value::Int32(a), Value::Int64(b)) => i64::from(*a).cmp(b),
+            (Value::Int64(a), Value::Int32(b)) => a.cmp(&i64::from(*b)),
             (Value::Float64(a), Value::Float64(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
             (Value::String(a), Value::String(b)) => a.cmp(b),
             (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
@@ -528,7 +785,15 @@ impl Ord for Value {
                 }
             }
             (Value::Timestamp(a), Value::Timestamp(b)) => a.cmp(b),
-            // Cross-type ordering: Null < Bool < Int32 < Int64 < Float64 < Timestamp < String < Vector < VectorInt8
+            (Value::Date(a), Value::Date(b)) => a.cmp(b),
+            (Value::Duration(a), Value::Duration(b)) => a.cmp(b),
+            (Value::Uuid(a), Value::Uuid(b)) => a.cmp(b),
+            (Value::List(a), Value::List(b)) => a.cmp(b),
+            (Value::Map(a), Value::Map(b)) => a.cmp(b),
+            (Value::Json(a), Value::Json(b)) => a.cmp(b),
+            (Value::Bytes(a), Value::Bytes(b)) => a.cmp(b),
+            // Cross-type ordering: Null < Bool < integers < Float64 < Timestamp < Date
+            // < Duration < Uuid < String < Vector < VectorInt8 < List < Map < Json < Bytes
             (Value::Null, _) => Ordering::Less,
             (_, Value::Null) => Ordering::Greater,
             (Value::Bool(_), _) => Ordering::Less,
@@ -541,10 +806,24 @@ impl Ord for Value {
             (_, Value::Float64(_)) => Ordering::Greater,
             (Value::Timestamp(_), _) => Ordering::Less,
             (_, Value::Timestamp(_)) => Ordering::Greater,
+            (Value::Date(_), _) => Ordering::Less,
+            (_, Value::Date(_)) => Ordering::Greater,
+            (Value::Duration(_), _) => Ordering::Less,
+            (_, Value::Duration(_)) => Ordering::Greater,
+            (Value::Uuid(_), _) => Ordering::Less,
+            (_, Value::Uuid(_)) => Ordering::Greater,
             (Value::String(_), _) => Ordering::Less,
             (_, Value::String(_)) => Ordering::Greater,
             (Value::Vector(_), _) => Ordering::Less,
             (_, Value::Vector(_)) => Ordering::Greater,
+            (Value::VectorInt8(_), _) => Ordering::Less,
+            (_, Value::VectorInt8(_)) => Ordering::Greater,
+            (Value::List(_), _) => Ordering::Less,
+            (_, Value::List(_)) => Ordering::Greater,
+            (Value::Map(_), _) => Ordering::Less,
+            (_, Value::Map(_)) => Ordering::Greater,
+            (Value::Json(_), _) => Ordering::Less,
+            (_, Value::Json(_)) => Ordering::Greater,
         }
     }
 }
@@ -592,6 +871,12 @@ impl From<Vec<f32>> for Value {
     }
 }
 
+impl From<uuid::Uuid> for Value {
+    fn from(u: uuid::Uuid) -> Self {
+        Value::Uuid(u)
+    }
+}
+
 impl From<Vec<i8>> for Value {
     fn from(v: Vec<i8>) -> Self {
         Value::VectorInt8(Arc::new(v))
@@ -643,6 +928,34 @@ impl Serialize for Value {
                 map.serialize_entry("type", "Timestamp")?;
                 map.serialize_entry("value", t)?;
             }
+            Value::Date(d) => {
+                map.serialize_entry("type", "Date")?;
+                map.serialize_entry("value", d)?;
+            }
+            Value::Duration(d) => {
+                map.serialize_entry("type", "Duration")?;
+                map.serialize_entry("value", d)?;
+            }
+            Value::Uuid(u) => {
+                map.serialize_entry("type", "Uuid")?;
+                map.serialize_entry("value", u)?;
+            }
+            Value::List(items) => {
+                map.serialize_entry("type", "List")?;
+                map.serialize_entry("value", items.as_ref())?;
+            }
+            Value::Map(entries) => {
+                map.serialize_entry("type", "Map")?;
+                map.serialize_entry("value", entries.as_ref())?;
+            }
+            Value::Json(text) => {
+                map.serialize_entry("type", "Json")?;
+                map.serialize_entry("value", text.as_ref())?;
+            }
+            Value::Bytes(b) => {
+                map.serialize_entry("type", "Bytes")?;
+                map.serialize_entry("value", &crate::bytes_ops::encode_base64(b))?;
+            }
         }
         map.end()
     }
@@ -732,6 +1045,43 @@ impl<'de> Deserialize<'de> for Value {
                             serde_json::from_value(raw_value).map_err(serde::de::Error::custom)?;
                         Ok(Value::Timestamp(v))
                     }
+                    "Date" => {
+                        let v: i32 =
+                            serde_json::from_value(raw_value).map_err(serde::de::Error::custom)?;
+                        Ok(Value::Date(v))
+                    }
+                    "Duration" => {
+                        let v: i64 =
+                            serde_json::from_value(raw_value).map_err(serde::de::Error::custom)?;
+                        Ok(Value::Duration(v))
+                    }
+                    "Uuid" => {
+                        let v: uuid::Uuid =
+                            serde_json::from_value(raw_value).map_err(serde::de::Error::custom)?;
+                        Ok(Value::Uuid(v))
+                    }
+                    "List" => {
+                        let v: Vec<Value> =
+                            serde_json::from_value(raw_value).map_err(serde::de::Error::custom)?;
+                        Ok(Value::List(Arc::new(v)))
+                    }
+                    "Map" => {
+                        let v: BTreeMap<String, Value> =
+                            serde_json::from_value(raw_value).map_err(serde::de::Error::custom)?;
+                        Ok(Value::Map(Arc::new(v)))
+                    }
+                    "Json" => {
+                        let v: String =
+                            serde_json::from_value(raw_value).map_err(serde::de::Error::custom)?;
+                        Ok(Value::Json(Arc::from(v)))
+                    }
+                    "Bytes" => {
+                        let v: String =
+                            serde_json::from_value(raw_value).map_err(serde::de::Error::custom)?;
+                        Value::from_base64(&v).ok_or_else(|| {
+                            serde::de::Error::custom("invalid base64 in Bytes value")
+                        })
+                    }
                     _ => Err(serde::de::Error::unknown_variant(
                         &type_str,
                         &[
@@ -744,6 +1094,13 @@ impl<'de> Deserialize<'de> for Value {
                             "Vector",
                             "VectorInt8",
                             "Timestamp",
+                            "Date",
+                            "Duration",
+                            "Uuid",
+                            "List",
+                            "Map",
+                            "Json",
+                            "Bytes",
                         ],
                     )),
                 }
@@ -754,10 +1111,16 @@ impl<'de> Deserialize<'de> for Value {
     }
 }
 
+/// Tuples up to this arity keep their values inline, without a heap
+/// allocation; wider tuples spill to the heap
+pub const TUPLE_INLINE_ARITY: usize = 4;
+
+type TupleValues = SmallVec<[Value; TUPLE_INLINE_ARITY]>;
+
 /// A tuple with arbitrary arity containing Values
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Tuple {
-    values: Vec<Value>,
+    values: TupleValues,
 }
 
 // Implement Ord for Tuple (lexicographic ordering)
@@ -774,12 +1137,18 @@ impl Ord for Tuple {
 }
 
 impl Tuple {
+    /// Create a tuple from a vector. Narrow tuples move their values inline
+    /// and free the vector; prefer `collect()` to avoid the vector entirely.
     pub fn new(values: Vec<Value>) -> Self {
-        Tuple { values }
+        Tuple {
+            values: SmallVec::from_vec(values),
+        }
     }
 
     pub fn empty() -> Self {
-        Tuple { values: Vec::new() }
+        Tuple {
+            values: SmallVec::new(),
+        }
     }
 
     pub fn iter(&self) -> std::slice::Iter<'_, Value> {
@@ -789,14 +1158,14 @@ impl Tuple {
     /// Create a 2-tuple from two i64 values (convenience for tests)
     pub fn pair(a: i64, b: i64) -> Self {
         Tuple {
-            values: vec![Value::Int64(a), Value::Int64(b)],
+            values: smallvec::smallvec![Value::Int64(a), Value::Int64(b)],
         }
     }
 
     /// Create a 3-tuple from three i64 values (convenience for tests)
     pub fn triple(a: i64, b: i64, c: i64) -> Self {
         Tuple {
-            values: vec![Value::Int64(a), Value::Int64(b), Value::Int64(c)],
+            values: smallvec::smallvec![Value::Int64(a), Value::Int64(b), Value::Int64(c)],
         }
     }
 
@@ -804,6 +1173,38 @@ impl Tuple {
         self.values.len()
     }
 
+    /// Approximate memory footprint in bytes, including string and vector
+    /// payloads (shared payloads are counted in full)
+    pub fn estimated_bytes(&self) -> usize {
+        let payload: usize = self
+            .values
+            .iter()
+            .map(|value| match value {
+                Value::String(s) => s.len(),
+                Value::Vector(v) => v.len() * std::mem::size_of::<f32>(),
+                Value::VectorInt8(v) => v.len(),
+                _ => 0,
+            })
+            .sum();
+        // Inline values are already part of `size_of::<Tuple>()`
+        let spilled = if self.values.spilled() {
+            self.values.capacity() * std::mem::size_of::<Value>()
+        } else {
+            0
+        };
+        std::mem::size_of::<Tuple>() + spilled + payload
+    }
+
+    /// Whether the values are stored inline (no heap allocation)
+    pub fn is_inline(&self) -> bool {
+        !self.values.spilled()
+    }
+
+    /// Append a value
+    pub fn push(&mut self, value: Value) {
+        self.values.push(value);
+    }
+
     pub fn get(&self, index: usize) -> Option<&Value> {
         self.values.get(index)
     }
@@ -817,7 +1218,7 @@ impl Tuple {
     }
 
     pub fn into_values(self) -> Vec<Value> {
-        self.values
+        self.values.into_vec()
     }
 
     pub fn project(&self, indices: &[usize]) -> Self {
@@ -844,16 +1245,49 @@ impl Tuple {
     }
 
     pub fn concat(&self, other: &Tuple) -> Self {
-        let mut values = self.values.clone();
+        let mut values = TupleValues::with_capacity(self.arity() + other.arity());
+        values.extend(self.values.iter().cloned());
         values.extend(other.values.iter().cloned());
         Tuple { values }
     }
 
+    /// `self.concat(&other.excluding_indices(exclude))` without the
+    /// intermediate tuple (join output: left row plus right non-key columns)
+    pub fn concat_excluding(&self, other: &Tuple, exclude: &[usize]) -> Self {
+        let mut values = TupleValues::with_capacity(self.arity() + other.arity());
+        values.extend(self.values.iter().cloned());
+        values.extend(
+            other
+                .values
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !exclude.contains(i))
+                .map(|(_, v)| v.clone()),
+        );
+        Tuple { values }
+    }
+
+    /// `self.concat(other).project(indices)` without the intermediate tuple
+    pub fn concat_project(&self, other: &Tuple, indices: &[usize]) -> Self {
+        let left = self.arity();
+        indices
+            .iter()
+            .filter_map(|&i| {
+                if i < left {
+                    self.values.get(i)
+                } else {
+                    other.values.get(i - left)
+                }
+            })
+            .cloned()
+            .collect()
+    }
+
     /// Create from a 2-tuple of i32 (for backward compatibility)
     /// Uses Int64 internally for consistency with production API
     pub fn from_pair(a: i32, b: i32) -> Self {
         Tuple {
-            values: vec![Value::Int64(i64::from(a)), Value::Int64(i64::from(b))],
+            values: smallvec::smallvec![Value::Int64(i64::from(a)), Value::Int64(i64::from(b))],
         }
     }
 
@@ -898,6 +1332,14 @@ impl fmt::Display for Tuple {
     }
 }
 
+impl FromIterator<Value> for Tuple {
+    fn from_iter<I: IntoIterator<Item = Value>>(iter: I) -> Self {
+        Tuple {
+            values: iter.into_iter().collect(),
+        }
+    }
+}
+
 // Allow iterating over tuple values
 impl<'a> IntoIterator for &'a Tuple {
     type Item = &'a Value;
@@ -910,7 +1352,7 @@ impl<'a> IntoIterator for &'a Tuple {
 
 impl IntoIterator for Tuple {
     type Item = Value;
-    type IntoIter = std::vec::IntoIter<Value>;
+    type IntoIter = smallvec::IntoIter<[Value; TUPLE_INLINE_ARITY]>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.values.into_iter()
@@ -1128,7 +1570,9 @@ mod tests {
     #[test]
     fn test_value_equality() {
         assert_eq!(Value::Int32(42), Value::Int32(42));
-        assert_ne!(Value::Int32(42), Value::Int64(42));
+        // Integers compare by value regardless of width
+        assert_eq!(Value::Int32(42), Value::Int64(42));
+        assert_ne!(Value::Int32(42), Value::Int64(43));
         assert_eq!(Value::string("hello"), Value::string("hello"));
     }
 
@@ -1165,6 +1609,35 @@ mod tests {
         assert_eq!(combined.get(2), Some(&Value::Int32(3)));
     }
 
+    #[test]
+    fn test_tuple_inline_storage() {
+        let small: Tuple = (0..TUPLE_INLINE_ARITY as i64).map(Value::Int64).collect();
+        assert!(small.is_inline());
+        assert_eq!(small.estimated_bytes(), std::mem::size_of::<Tuple>());
+
+        let mut grown = small.clone();
+        grown.push(Value::Int64(99));
+        assert!(!grown.is_inline());
+        assert_eq!(grown.arity(), TUPLE_INLINE_ARITY + 1);
+        assert_eq!(grown.get(TUPLE_INLINE_ARITY), Some(&Value::Int64(99)));
+        assert_eq!(grown.clone().into_values().len(), TUPLE_INLINE_ARITY + 1);
+    }
+
+    #[test]
+    fn test_tuple_fused_concat() {
+        let left = Tuple::new(vec![Value::Int32(1), Value::string("a")]);
+        let right = Tuple::new(vec![Value::Int32(1), Value::Bool(true), Value::Null]);
+
+        assert_eq!(
+            left.concat_excluding(&right, &[0]),
+            left.concat(&right.excluding_indices(&[0]))
+        );
+        assert_eq!(
+            left.concat_project(&right, &[3, 1, 0, 9]),
+            left.concat(&right).project(&[3, 1, 0, 9])
+        );
+    }
+
     #[test]
     fn test_tuple_backward_compat() {
         let tuple = Tuple::from_pair(1, 2);
@@ -1435,6 +1908,193 @@ mod tests {
         );
     }
 
+    // Date and Duration Tests
+    #[test]
+    fn test_date_and_duration_types() {
+        let date = Value::date(19_782);
+        let dur = Value::duration(90_000);
+        assert_eq!(date.data_type(), DataType::Date);
+        assert_eq!(dur.data_type(), DataType::Duration);
+        assert!(DataType::Date.matches(&date));
+        assert!(!DataType::Date.matches(&Value::Int32(19_782)));
+        assert!(!DataType::Duration.matches(&Value::Timestamp(90_000)));
+        assert_eq!(date.to_string(), "2024-02-29");
+        assert_eq!(dur.to_string(), "1m30s");
+    }
+
+    #[test]
+    fn test_date_and_duration_ordering() {
+        assert!(Value::date(1) < Value::date(2));
+        assert!(Value::duration(-5) < Value::duration(5));
+        // Timestamp < Date < Duration < String
+        assert!(Value::Timestamp(i64::MAX) < Value::date(0));
+        assert!(Value::date(i32::MAX) < Value::duration(0));
+        assert!(Value::duration(i64::MAX) < Value::string(""));
+        assert_ne!(Value::date(0), Value::Int32(0));
+    }
+
+    #[test]
+    fn test_date_and_duration_arrow_types() {
+        assert_eq!(DataType::Date.to_arrow(), ArrowDataType::Date32);
+        assert_eq!(
+            DataType::from_arrow(&DataType::Duration.to_arrow()),
+            Some(DataType::Duration)
+        );
+        assert_eq!(
+            DataType::from_arrow(&ArrowDataType::Date32),
+            Some(DataType::Date)
+        );
+    }
+
+    #[test]
+    fn test_date_and_duration_serde_roundtrip() {
+        for value in [Value::date(-3), Value::duration(86_400_000)] {
+            let json = serde_json::to_string(&value).unwrap();
+            let back: Value = serde_json::from_str(&json).unwrap();
+            assert_eq!(back, value);
+        }
+    }
+
+    // UUID Tests
+    #[test]
+    fn test_uuid_parse_and_display() {
+        let text = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+        let v = Value::uuid(text).unwrap();
+        assert_eq!(v.data_type(), DataType::Uuid);
+        assert_eq!(v.to_string(), text);
+        assert_eq!(Value::uuid("67E5504410B1426F9247BB680E5FE0C8"), Some(v));
+        assert_eq!(Value::uuid("not-a-uuid"), None);
+    }
+
+    #[test]
+    fn test_uuid_ordering_and_hash() {
+        let a = Value::uuid("00000000-0000-0000-0000-000000000001").unwrap();
+        let b = Value::uuid("00000000-0000-0000-0000-000000000002").unwrap();
+        assert!(a < b);
+        assert!(Value::duration(i64::MAX) < a);
+        assert!(b < Value::string(""));
+        let mut set = std::collections::HashSet::new();
+        set.insert(a.clone());
+        assert!(set.contains(&a));
+        assert!(!set.contains(&b));
+        // The same UUID as text is a different value
+        assert_ne!(a, Value::string(&a.to_string()));
+    }
+
+    #[test]
+    fn test_uuid_arrow_and_serde() {
+        assert_eq!(
+            DataType::Uuid.to_arrow(),
+            ArrowDataType::FixedSizeBinary(16)
+        );
+        assert_eq!(
+            DataType::from_arrow(&ArrowDataType::FixedSizeBinary(16)),
+            Some(DataType::Uuid)
+        );
+        let v = Value::Uuid(uuid::Uuid::now_v7());
+        let back: Value = serde_json::from_str(&serde_json::to_string(&v).unwrap()).unwrap();
+        assert_eq!(back, v);
+    }
+
+    // List and Map Tests
+    #[test]
+    fn test_list_and_map_constructors() {
+        let list = Value::list(vec![Value::Int64(1), Value::string("a")]);
+        assert_eq!(list.data_type(), DataType::List);
+        assert_eq!(list.as_list().unwrap().len(), 2);
+        assert_eq!(list.to_string(), "[1, \"a\"]");
+        let map = Value::map([("b", Value::Int64(2)), ("a", list.clone())]);
+        assert_eq!(map.data_type(), DataType::Map);
+        assert_eq!(map.as_map().unwrap().keys().collect::<Vec<_>>(), ["a", "b"]);
+        assert!(list.as_map().is_none());
+        assert!(map.as_list().is_none());
+    }
+
+    #[test]
+    fn test_list_and_map_ordering_and_serde() {
+        let short = Value::list(vec![Value::Int64(1)]);
+        let long = Value::list(vec![Value::Int64(1), Value::Int64(0)]);
+        assert!(short < long);
+        assert!(Value::string("z") < short);
+        assert!(long < Value::map(Vec::<(String, Value)>::new()));
+        let mut set = std::collections::HashSet::new();
+        set.insert(long.clone());
+        assert!(set.contains(&long));
+        assert!(!set.contains(&short));
+        let nested = Value::map([("tags", long)]);
+        let back: Value = serde_json::from_str(&serde_json::to_string(&nested).unwrap()).unwrap();
+        assert_eq!(back, nested);
+    }
+
+    // JSON Tests
+    #[test]
+    fn test_json_normalizes_text() {
+        let a = Value::json(r#"{ "b": 1, "a": [true, null] }"#).unwrap();
+        let b = Value::json(r#"{"a":[true,null],"b":1}"#).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.data_type(), DataType::Json);
+        assert_eq!(a.to_string(), r#"{"a":[true,null],"b":1}"#);
+        assert_eq!(Value::json("{not json"), None);
+        // JSON text is not the same value as the plain string
+        assert_ne!(Value::json("\"x\"").unwrap(), Value::string("\"x\""));
+    }
+
+    #[test]
+    fn test_json_arrow_and_serde() {
+        assert_eq!(DataType::Json.to_arrow(), ArrowDataType::LargeUtf8);
+        assert_eq!(
+            DataType::from_arrow(&ArrowDataType::LargeUtf8),
+            Some(DataType::Json)
+        );
+        assert_eq!(
+            DataType::from_arrow(&ArrowDataType::Utf8),
+            Some(DataType::String)
+        );
+        let v = Value::json(r#"[1, {"k": "v"}]"#).unwrap();
+        let back: Value = serde_json::from_str(&serde_json::to_string(&v).unwrap()).unwrap();
+        assert_eq!(back, v);
+        assert!(Value::map(Vec::<(String, Value)>::new()) < v);
+    }
+
+    #[test]
+    fn test_interned_string_equals_plain_string() {
+        let a = Value::interned("value-test-interned");
+        let b = Value::interned("value-test-interned");
+        assert_eq!(a, Value::string("value-test-interned"));
+        match (&a, &b) {
+            (Value::String(x), Value::String(y)) => assert!(Arc::ptr_eq(x, y)),
+            _ => panic!("expected strings"),
+        }
+    }
+
+    // Bytes Tests
+    #[test]
+    fn test_bytes_literals_and_display() {
+        let v = Value::from_hex("0xDEADbeef").unwrap();
+        assert_eq!(v.as_bytes(), Some(&[0xde, 0xad, 0xbe, 0xef][..]));
+        assert_eq!(v.data_type(), DataType::Bytes);
+        assert_eq!(v.to_string(), "0xdeadbeef");
+        assert_eq!(Value::from_base64("3q2+7w=="), Some(v));
+        assert_eq!(Value::from_hex("xyz"), None);
+        assert_eq!(Value::from_base64("%%"), None);
+    }
+
+    #[test]
+    fn test_bytes_ordering_arrow_and_serde() {
+        let a = Value::bytes(vec![1, 2]);
+        let b = Value::bytes(vec![1, 3]);
+        assert!(a < b);
+        assert!(Value::json("{}").unwrap() < a);
+        assert_ne!(a, Value::vector_int8(vec![1, 2]));
+        assert_eq!(DataType::Bytes.to_arrow(), ArrowDataType::Binary);
+        assert_eq!(
+            DataType::from_arrow(&ArrowDataType::LargeBinary),
+            Some(DataType::Bytes)
+        );
+        let back: Value = serde_json::from_str(&serde_json::to_string(&b).unwrap()).unwrap();
+        assert_eq!(back, b);
+    }
+
     // Vector Dimension Validation Tests
     #[test]
     fn test_datatype_vector_with_dim() {
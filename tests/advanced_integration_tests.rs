@@ -527,6 +527,9 @@ fn test_sip_chain_join_correctness() {
         enable_subplan_sharing: true,
         enable_boolean_specialization: true,
         enable_magic_sets: true,
+        enable_semijoin_reduction: false,
+        enable_equality_saturation: false,
+        execution_backend: None,
     };
 
     let mut engine = IQLEngine::with_config(config);
@@ -557,6 +560,9 @@ fn test_sip_two_way_join_correctness() {
         enable_subplan_sharing: true,
         enable_boolean_specialization: true,
         enable_magic_sets: true,
+        enable_semijoin_reduction: false,
+        enable_equality_saturation: false,
+        execution_backend: None,
     };
 
     let mut engine = IQLEngine::with_config(config);
@@ -585,6 +591,9 @@ fn test_sip_with_dangling_tuples() {
         enable_subplan_sharing: true,
         enable_boolean_specialization: true,
         enable_magic_sets: true,
+        enable_semijoin_reduction: false,
+        enable_equality_saturation: false,
+        execution_backend: None,
     };
 
     let mut engine = IQLEngine::with_config(config);
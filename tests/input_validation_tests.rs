@@ -365,6 +365,36 @@ async fn test_vector_schema_exact_dimension_accepted() {
     );
 }
 
+#[tokio::test]
+async fn test_vector_schema_angle_bracket_dimension() {
+    let (handler, _tmp) = create_test_handler();
+    handler
+        .query_program(None, "+embed(id: int, v: vector<3>)".to_string())
+        .await
+        .unwrap();
+    let result = handler
+        .query_program(None, "+embed[(1, [1.0, 2.0])]".to_string())
+        .await;
+    assert!(
+        result.is_err(),
+        "vector<3> should reject a 2-element vector, got: {result:?}"
+    );
+    handler
+        .query_program(None, "+embed[(1, [1.0, 2.0, 3.0])]".to_string())
+        .await
+        .unwrap();
+    let result = handler
+        .query_program(
+            None,
+            "?embed(I, V), D = euclidean(V, [1.0, 2.0])".to_string(),
+        )
+        .await;
+    assert!(
+        result.is_err(),
+        "euclidean over vector<3> and a 2-element literal should not compile, got: {result:?}"
+    );
+}
+
 #[tokio::test]
 async fn test_vector_schema_no_dimension_accepts_any_size() {
     let (handler, _tmp) = create_test_handler();
@@ -297,6 +297,9 @@ fn test_optimization_config() {
         enable_subplan_sharing: false,
         enable_boolean_specialization: false,
         enable_magic_sets: false,
+        enable_semijoin_reduction: false,
+        enable_equality_saturation: false,
+        execution_backend: None,
     };
 
     let engine = IQLEngine::with_config(config.clone());
@@ -803,6 +806,60 @@ fn test_recursive_widest_path_max_aggregation() {
     assert_eq!(results, expected, "Widest path results mismatch");
 }
 
+/// Test min<> in the head of the recursive rule itself. The graph has a
+/// cycle, so without per-group pruning inside the fixpoint the distances
+/// would grow forever.
+#[test]
+fn test_recursive_min_in_recursive_head_terminates_on_cycle() {
+    use inputlayer::{Tuple, Value};
+
+    let mut engine = IQLEngine::new();
+
+    //   1 --5--> 2 --3--> 3 --2--> 4
+    //   1 ------10------> 3 --1--> 1
+    let edge = |x, y, w| Tuple::new(vec![Value::Int64(x), Value::Int64(y), Value::Int64(w)]);
+    engine.add_tuples(
+        "edge",
+        vec![
+            edge(1, 2, 5),
+            edge(2, 3, 3),
+            edge(1, 3, 10),
+            edge(3, 4, 2),
+            edge(3, 1, 1),
+        ],
+    );
+
+    let program = "\
+        dist(Y, min<D>) <- edge(1, Y, D)\n\
+        dist(Z, min<D>) <- dist(Y, D1), edge(Y, Z, W), D = D1 + W\n\
+        result(Y, D) <- dist(Y, D)";
+
+    let mut results = engine.execute_tuples(program).unwrap();
+    results.sort();
+
+    let pair = |y, d| Tuple::new(vec![Value::Int64(y), Value::Int64(d)]);
+    assert_eq!(
+        results,
+        vec![pair(1, 9), pair(2, 5), pair(3, 8), pair(4, 10)],
+        "Recursive min distances mismatch"
+    );
+}
+
+/// sum<> is not monotonic, so it is rejected inside recursion
+#[test]
+fn test_recursive_sum_in_recursive_head_rejected() {
+    let mut engine = IQLEngine::new();
+    engine.add_fact("edge", vec![(1, 2), (2, 3)]);
+
+    let program = "\
+        total(Y, sum<X>) <- edge(X, Y)\n\
+        total(Z, sum<S>) <- total(Y, S), edge(Y, Z)\n\
+        result(Y, S) <- total(Y, S)";
+
+    let err = engine.execute_tuples(program).unwrap_err();
+    assert!(err.contains("not monotonic"), "{err}");
+}
+
 /// Verify Min semiring annotation is correctly detected for recursive min aggregation
 #[test]
 fn test_min_semiring_annotation() {
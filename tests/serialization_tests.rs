@@ -423,6 +423,13 @@ fn test_datatype_serialization() {
         DataType::VectorInt8 { dim: None },
         DataType::VectorInt8 { dim: Some(64) },
         DataType::Timestamp,
+        DataType::Date,
+        DataType::Duration,
+        DataType::Uuid,
+        DataType::List,
+        DataType::Map,
+        DataType::Json,
+        DataType::Bytes,
     ];
 
     for original in types {
@@ -443,6 +450,21 @@ fn test_value_type_preserved_after_roundtrip() {
         (Value::Bool(true), DataType::Bool),
         (Value::Null, DataType::Null),
         (Value::Timestamp(1000), DataType::Timestamp),
+        (Value::Date(19_782), DataType::Date),
+        (Value::Duration(-1000), DataType::Duration),
+        (
+            Value::Uuid(uuid::Uuid::from_u128(
+                0x67e5_5044_10b1_426f_9247_bb68_0e5f_e0c8,
+            )),
+            DataType::Uuid,
+        ),
+        (
+            Value::list(vec![Value::Int64(1), Value::string("a")]),
+            DataType::List,
+        ),
+        (Value::map([("k", Value::Bool(true))]), DataType::Map),
+        (Value::json(r#"{"k": [1, 2]}"#).unwrap(), DataType::Json),
+        (Value::bytes(vec![0, 255, 7]), DataType::Bytes),
     ];
 
     for (original, expected_type) in values {